@@ -13,7 +13,7 @@
 //!
 //! - **Resource Aggregation**: Reduces the number of GPU buffers to one, simplifying management.
 //! - **Efficient Updates**: The `flash()` method batches updates to multiple buffers into a single
-//!   GPU command.
+//!   GPU command, staged through an [`UploadRing`](crate::upload::UploadRing).
 //! - **Automatic Lifetime Management**: When a `Buffer` handle is dropped, its space within the
 //!   atlas is automatically marked for reuse.
 //!
@@ -24,14 +24,35 @@
 //! 3. Write data to a `Buffer` with `Buffer::store()`.
 //! 4. At the beginning of your rendering cycle, call `BufferAtlas::flash()` to apply all
 //!    changes to the GPU.
-
-use log::{debug, trace};
+//! 5. Bind the buffer returned by `BufferAtlas::buffer()`.
+//!
+//! ## Slot Alignment
+//!
+//! Each slot starts at a multiple of the atlas alignment, which defaults to the device's
+//! `min_uniform_buffer_offset_alignment` (often 256 bytes), so every slot can be bound on its
+//! own with a dynamic offset from `BufferAtlas::offset_of()`. Use `BufferAtlas::with_alignment(1)`
+//! to pack slots tightly when they are only accessed through the whole buffer.
+//!
+//! ## Double Buffering
+//!
+//! By default `flash()` writes into the same GPU buffer the previous frame may still be reading.
+//! Backends without automatic queue synchronization can observe those writes mid-frame.
+//! `BufferAtlas::new_double_buffered()` keeps two GPU buffers instead: `flash()` always writes into
+//! the buffer that is *not* bound by the in-flight frame and then swaps them. Because the
+//! returned buffer changes every frame, bind groups must be rebuilt (or one bind group per
+//! buffer cached and selected with `BufferAtlas::buffer_index()`) after each `flash()`.
+
+use log::{debug, trace, warn};
 use std::{
     collections::VecDeque,
     sync::{Arc, Weak},
 };
 
 use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::memory::{BUFFER_ATLASES, TrackedBytes};
+use crate::upload::UploadRing;
 
 /// A handle to a single buffer within the atlas.
 ///
@@ -117,15 +138,35 @@ impl BufferAtlasId {
     }
 }
 
+/// How many GPU buffers a `BufferAtlas` cycles through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BufferingMode {
+    /// One GPU buffer, updated in place.
+    #[default]
+    Single,
+    /// Two GPU buffers swapped on every `flash()`.
+    Double,
+}
+
 /// An atlas that manages many fixed-size buffers on a single GPU buffer.
 pub struct BufferAtlas<const N: usize> {
     id: BufferAtlasId,
+    mode: BufferingMode,
 
-    /// The single GPU buffer that holds all buffer data.
+    /// Requested slot alignment in bytes. `None` uses the device's
+    /// `min_uniform_buffer_offset_alignment`.
+    alignment: Option<wgpu::BufferAddress>,
+    /// Bytes between the starts of consecutive slots. Fixed by the first `flash()`.
+    stride: Option<wgpu::BufferAddress>,
+
+    /// The GPU buffer that holds all buffer data and is bound for rendering.
     ///
     /// This is `None` until the first `flash()` call, after which it is always `Some`.
     atlas: Option<wgpu::Buffer>,
 
+    /// Double-buffer state. `None` in single-buffered mode.
+    double: Option<DoubleBufferState>,
+
     /// A vector tracking the state of slots in the atlas.
     ///
     /// The index of the vector corresponds to a slot in the atlas.
@@ -136,6 +177,26 @@ pub struct BufferAtlas<const N: usize> {
     ///
     /// Buffers created with `allocate()` are first added here.
     to_be_allocated: Vec<Weak<BufferData<N>>>,
+
+    /// Size of the GPU buffers, counted in [`BUFFER_ATLASES`].
+    memory: TrackedBytes,
+
+    /// Stages the writes of `flash()`. Created by the first `flash()` unless one was set
+    /// with `with_upload_ring()`.
+    upload: Option<Arc<UploadRing>>,
+}
+
+struct DoubleBufferState {
+    /// The buffer written by the next `flash()`; not used by the frame in flight.
+    back: Option<wgpu::Buffer>,
+    /// Index (0 or 1) of the buffer currently returned by `buffer()`.
+    front_index: usize,
+    /// CPU copy of the atlas content, used to bring the back buffer up to date.
+    shadow: Vec<u8>,
+    /// Slots updated by the previous `flash()` that are not yet written to `back`.
+    back_dirty: Vec<bool>,
+    /// Number of upcoming flashes that must rewrite every slot (after buffers were recreated).
+    full_rewrites: u8,
 }
 
 impl<const N: usize> Default for BufferAtlas<N> {
@@ -147,16 +208,130 @@ impl<const N: usize> Default for BufferAtlas<N> {
 impl<const N: usize> BufferAtlas<N> {
     /// Creates a new `BufferAtlas`.
     pub fn new() -> Self {
+        Self::with_mode(BufferingMode::Single)
+    }
+
+    /// Creates a new `BufferAtlas` that swaps between two GPU buffers on every `flash()`.
+    pub fn new_double_buffered() -> Self {
+        Self::with_mode(BufferingMode::Double)
+    }
+
+    /// Creates a new `BufferAtlas` with the given buffering mode.
+    pub fn with_mode(mode: BufferingMode) -> Self {
         let atlas = Self {
             id: BufferAtlasId::new(),
+            mode,
+            alignment: None,
+            stride: None,
             atlas: None,
+            double: match mode {
+                BufferingMode::Single => None,
+                BufferingMode::Double => Some(DoubleBufferState {
+                    back: None,
+                    front_index: 0,
+                    shadow: Vec::new(),
+                    back_dirty: Vec::new(),
+                    full_rewrites: 0,
+                }),
+            },
             allocations: Vec::new(),
             to_be_allocated: Vec::new(),
+            memory: TrackedBytes::new(&BUFFER_ATLASES),
+            upload: None,
         };
-        trace!("BufferAtlas::new: created atlas_id={:?}", atlas.id);
+        trace!(
+            "BufferAtlas::new: created atlas_id={:?} mode={:?}",
+            atlas.id, mode
+        );
         atlas
     }
 
+    /// Aligns every slot to `alignment` bytes instead of the device's
+    /// `min_uniform_buffer_offset_alignment`.
+    ///
+    /// Pass `1` to pack slots tightly when they are never bound individually.
+    /// Fails if `alignment` is not a power of two or the atlas was already flashed.
+    pub fn with_alignment(
+        mut self,
+        alignment: wgpu::BufferAddress,
+    ) -> Result<Self, BufferAtlasError> {
+        if !alignment.is_power_of_two() {
+            return Err(BufferAtlasError::InvalidAlignment(alignment));
+        }
+        if self.stride.is_some() {
+            return Err(BufferAtlasError::AlignmentFixed);
+        }
+        self.alignment = Some(alignment);
+        Ok(self)
+    }
+
+    /// Stages the writes of `flash()` through `ring`, e.g. the ring shared with the texture
+    /// atlases, so that one flush submits all of them.
+    pub fn with_upload_ring(mut self, ring: Arc<UploadRing>) -> Self {
+        self.upload = Some(ring);
+        self
+    }
+
+    pub fn mode(&self) -> BufferingMode {
+        self.mode
+    }
+
+    /// The ID shared by the buffers allocated from this atlas, see [`Buffer::atlas_id`].
+    pub fn id(&self) -> BufferAtlasId {
+        self.id
+    }
+
+    /// Bytes between the starts of consecutive slots: `N` rounded up to the alignment.
+    ///
+    /// `None` until the first `flash()`, which resolves the default alignment from the device.
+    pub fn stride(&self) -> Option<wgpu::BufferAddress> {
+        self.stride
+    }
+
+    /// The GPU buffer to bind for rendering. `None` until the first `flash()`.
+    ///
+    /// In double-buffered mode this changes on every `flash()`.
+    pub fn buffer(&self) -> Option<&wgpu::Buffer> {
+        self.atlas.as_ref()
+    }
+
+    /// Index (0 or 1) of the buffer returned by `buffer()`. Always 0 in single-buffered mode.
+    ///
+    /// Useful to cache one bind group per buffer instead of rebuilding it every frame.
+    pub fn buffer_index(&self) -> usize {
+        self.double.as_ref().map_or(0, |d| d.front_index)
+    }
+
+    /// Byte offset of the slot that `buffer` occupies, if it is already placed in the atlas.
+    ///
+    /// The offset is a multiple of the alignment, so it can be used as a dynamic offset when
+    /// binding `N` bytes of `buffer()`.
+    pub fn offset_of(&self, buffer: &Buffer<N>) -> Option<wgpu::BufferAddress> {
+        let stride = self.stride?;
+        self.allocations
+            .iter()
+            .position(|weak| std::ptr::eq(weak.as_ptr(), Arc::as_ptr(&buffer.data)))
+            .map(|index| index as wgpu::BufferAddress * stride)
+    }
+
+    /// Fixes the stride on the first call.
+    fn resolve_stride(&mut self, device: &wgpu::Device) -> usize {
+        let stride = *self.stride.get_or_insert_with(|| {
+            let device_alignment =
+                wgpu::BufferAddress::from(device.limits().min_uniform_buffer_offset_alignment);
+            let alignment = self.alignment.unwrap_or(device_alignment);
+            if !alignment.is_multiple_of(device_alignment) {
+                warn!(
+                    "BufferAtlas: alignment {alignment} is not a multiple of the device's \
+                     min_uniform_buffer_offset_alignment {device_alignment}; \
+                     slots cannot be bound with dynamic offsets"
+                );
+            }
+            (N as wgpu::BufferAddress).next_multiple_of(alignment)
+        });
+        stride as usize
+    }
+
     /// Allocates a new buffer within the atlas.
     ///
     /// The actual GPU memory allocation and data upload will occur
@@ -188,6 +363,8 @@ impl<const N: usize> BufferAtlas<N> {
             self.allocations.len(),
             self.to_be_allocated.len()
         );
+        let stride = self.resolve_stride(device);
+
         // 1. Garbage Collection: Collect slots from dropped `Buffer`s in `allocations`.
         let mut empty_slots: VecDeque<usize> = self
             .allocations
@@ -228,14 +405,29 @@ impl<const N: usize> BufferAtlas<N> {
                 "BufferAtlas::flash: resizing atlas_id={:?} from {} to {} slots",
                 self.id, current_capacity, new_capacity
             );
-            Self::resize(
-                device,
-                queue,
-                &mut self.atlas,
-                &mut self.allocations,
-                &mut empty_slots,
-                new_capacity,
-            );
+            match &mut self.double {
+                None => Self::resize(
+                    device,
+                    queue,
+                    &mut self.atlas,
+                    &mut self.allocations,
+                    &mut empty_slots,
+                    new_capacity,
+                    stride,
+                ),
+                Some(double) => Self::resize_double(
+                    device,
+                    double,
+                    &mut self.atlas,
+                    &mut self.allocations,
+                    &mut empty_slots,
+                    new_capacity,
+                    stride,
+                ),
+            }
+            let back = self.double.as_ref().and_then(|double| double.back.as_ref());
+            let bytes = self.atlas.iter().chain(back).map(wgpu::Buffer::size).sum();
+            self.memory.set(bytes);
         }
 
         // 3. Reallocation: Move buffers from `to_be_allocated` into the empty slots of `allocations`.
@@ -252,9 +444,19 @@ impl<const N: usize> BufferAtlas<N> {
             self.allocations[index] = Arc::downgrade(&new_item);
         }
 
+        let ring = self
+            .upload
+            .get_or_insert_with(|| Arc::new(UploadRing::new(device, queue)))
+            .clone();
+        if let Some(double) = &mut self.double {
+            Self::flash_double(&ring, double, &mut self.atlas, &self.allocations, stride);
+            ring.flush();
+            return;
+        }
+
         // 4. Data Transfer: Upload updated data to the GPU.
         //    To improve performance, we batch consecutive memory writes into a single chunk
-        //    to reduce the number of `write_buffer` calls.
+        //    to reduce the number of staged copies.
         let mut chunk_start: usize = 0;
         let mut chunk_data: Vec<u8> = Vec::new();
 
@@ -272,7 +474,9 @@ impl<const N: usize> BufferAtlas<N> {
                 if chunk_data.is_empty() {
                     chunk_start = i;
                 }
+                // padding up to the next slot is written as zeros
                 chunk_data.extend_from_slice(&data);
+                chunk_data.resize(chunk_data.len() + stride - N, 0);
             } else if !chunk_data.is_empty() {
                 // End of a chunk. Write the collected data to the GPU.
                 if let Some(atlas_buffer) = &self.atlas {
@@ -281,15 +485,16 @@ impl<const N: usize> BufferAtlas<N> {
                         chunk_start,
                         chunk_data.len()
                     );
-                    queue.write_buffer(
+                    ring.write_buffer(
                         atlas_buffer,
-                        (chunk_start * N) as wgpu::BufferAddress,
+                        (chunk_start * stride) as wgpu::BufferAddress,
                         &chunk_data,
                     );
                 }
                 chunk_data.clear();
             }
         }
+        ring.flush();
     }
 }
 
@@ -303,13 +508,14 @@ impl<const N: usize> BufferAtlas<N> {
         allocations: &mut Vec<Weak<BufferData<N>>>,
         empty_slots: &mut VecDeque<usize>,
         new_size: usize,
+        stride: usize,
     ) {
         let old_size = allocations.len();
         if new_size <= old_size {
             return;
         }
 
-        let new_buffer_size = (N * new_size) as wgpu::BufferAddress;
+        let new_buffer_size = (stride * new_size) as wgpu::BufferAddress;
 
         let new_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("buffer-atlas buffer"),
@@ -322,7 +528,7 @@ impl<const N: usize> BufferAtlas<N> {
 
         // If an old buffer exists, copy its contents to the new, larger buffer.
         if let Some(old_buffer) = atlas.take() {
-            let old_buffer_size = (N * old_size) as wgpu::BufferAddress;
+            let old_buffer_size = (stride * old_size) as wgpu::BufferAddress;
             let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("buffer-atlas resize encoder"),
             });
@@ -344,4 +550,271 @@ impl<const N: usize> BufferAtlas<N> {
             new_size - old_size
         );
     }
+
+    /// Double-buffered variant of `resize`.
+    ///
+    /// Both GPU buffers are recreated. Their content is restored from the shadow copy by the
+    /// next two `flash()` calls, so no copy from a buffer that may be in use is needed.
+    fn resize_double(
+        device: &wgpu::Device,
+        double: &mut DoubleBufferState,
+        atlas: &mut Option<wgpu::Buffer>,
+        allocations: &mut Vec<Weak<BufferData<N>>>,
+        empty_slots: &mut VecDeque<usize>,
+        new_size: usize,
+        stride: usize,
+    ) {
+        let old_size = allocations.len();
+        if new_size <= old_size {
+            return;
+        }
+
+        let new_buffer_size = (stride * new_size) as wgpu::BufferAddress;
+        let create = || {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("buffer-atlas buffer (double-buffered)"),
+                size: new_buffer_size,
+                usage: wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            })
+        };
+
+        *atlas = Some(create());
+        double.back = Some(create());
+        double.shadow.resize(stride * new_size, 0);
+        double.back_dirty.resize(new_size, false);
+        double.full_rewrites = 2;
+
+        allocations.resize_with(new_size, Weak::new);
+        empty_slots.extend(old_size..new_size);
+        trace!("BufferAtlas::resize_double: recreated both buffers with {new_size} slots");
+    }
+
+    /// Writes pending updates into the back buffer and swaps it to the front.
+    fn flash_double(
+        ring: &UploadRing,
+        double: &mut DoubleBufferState,
+        atlas: &mut Option<wgpu::Buffer>,
+        allocations: &[Weak<BufferData<N>>],
+        stride: usize,
+    ) {
+        let Some(back) = &double.back else {
+            // nothing was ever allocated
+            return;
+        };
+
+        let full_rewrite = double.full_rewrites > 0;
+        double.full_rewrites = double.full_rewrites.saturating_sub(1);
+
+        // collect this frame's updates into the shadow copy
+        let mut updated_now = vec![false; allocations.len()];
+        for (i, weak) in allocations.iter().enumerate() {
+            if let Some(data) = weak.upgrade().and_then(|b| b.copy_updated()) {
+                double.shadow[i * stride..i * stride + N].copy_from_slice(&data);
+                updated_now[i] = true;
+            }
+        }
+
+        // write slots updated now or by the previous frame (which only reached the other buffer)
+        let mut chunk_start: Option<usize> = None;
+        let dirty_slots = updated_now
+            .iter()
+            .zip(&double.back_dirty)
+            .map(|(now, previous)| full_rewrite || *now || *previous)
+            // a trailing clean slot flushes the last chunk
+            .chain(std::iter::once(false));
+        for (i, dirty) in dirty_slots.enumerate() {
+            match (dirty, chunk_start) {
+                (true, None) => chunk_start = Some(i),
+                (false, Some(start)) => {
+                    trace!(
+                        "BufferAtlas::flash: writing back buffer chunk start={} slots={}",
+                        start,
+                        i - start
+                    );
+                    ring.write_buffer(
+                        back,
+                        (start * stride) as wgpu::BufferAddress,
+                        &double.shadow[start * stride..i * stride],
+                    );
+                    chunk_start = None;
+                }
+                _ => (),
+            }
+        }
+
+        double.back_dirty = updated_now;
+        std::mem::swap(atlas, &mut double.back);
+        double.front_index ^= 1;
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BufferAtlasError {
+    #[error("slot alignment must be a power of two, got {0}")]
+    InvalidAlignment(wgpu::BufferAddress),
+    #[error("slot alignment cannot change after the first flash")]
+    AlignmentFixed,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn single_buffered_keeps_buffer() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let mut atlas = BufferAtlas::<16>::new();
+        assert_eq!(atlas.mode(), BufferingMode::Single);
+        assert!(atlas.buffer().is_none());
+
+        let buffer = atlas.allocate();
+        buffer.store([1; 16]);
+        atlas.flash(&device, &queue);
+        let first = atlas.buffer().cloned().unwrap();
+        assert_eq!(atlas.offset_of(&buffer), Some(0));
+
+        buffer.store([2; 16]);
+        atlas.flash(&device, &queue);
+        assert_eq!(atlas.buffer(), Some(&first));
+        assert_eq!(atlas.buffer_index(), 0);
+    }
+
+    #[tokio::test]
+    async fn double_buffered_swaps_every_flash() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let mut atlas = BufferAtlas::<16>::new_double_buffered();
+        assert_eq!(atlas.mode(), BufferingMode::Double);
+
+        let a = atlas.allocate();
+        let b = atlas.allocate();
+        a.store([1; 16]);
+        b.store([2; 16]);
+        atlas.flash(&device, &queue);
+        let first = atlas.buffer().cloned().unwrap();
+        let first_index = atlas.buffer_index();
+
+        a.store([3; 16]);
+        atlas.flash(&device, &queue);
+        let second = atlas.buffer().cloned().unwrap();
+        assert_ne!(first, second);
+        assert_ne!(first_index, atlas.buffer_index());
+
+        atlas.flash(&device, &queue);
+        assert_eq!(atlas.buffer(), Some(&first));
+        assert_eq!(atlas.buffer_index(), first_index);
+
+        // the shadow copy holds the latest data for both slots
+        let stride = atlas.stride().unwrap() as usize;
+        let double = atlas.double.as_ref().unwrap();
+        assert_eq!(&double.shadow[0..16], &[3; 16]);
+        assert_eq!(&double.shadow[stride..stride + 16], &[2; 16]);
+        assert_eq!(atlas.offset_of(&b), Some(stride as u64));
+    }
+
+    #[tokio::test]
+    async fn double_buffered_resize_recreates_both_buffers() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let mut atlas = BufferAtlas::<16>::new_double_buffered();
+
+        let a = atlas.allocate();
+        a.store([1; 16]);
+        atlas.flash(&device, &queue);
+        let before = atlas.buffer().cloned().unwrap();
+
+        let b = atlas.allocate();
+        b.store([2; 16]);
+        atlas.flash(&device, &queue);
+        let after = atlas.buffer().cloned().unwrap();
+        assert_ne!(before, after);
+        assert_eq!(after.size(), 2 * atlas.stride().unwrap());
+        assert_eq!(atlas.double.as_ref().unwrap().full_rewrites, 1);
+    }
+
+    async fn check_slots_are_aligned(device: &wgpu::Device, queue: &wgpu::Queue) {
+        let device_alignment = u64::from(device.limits().min_uniform_buffer_offset_alignment);
+        let mut atlas = BufferAtlas::<20>::new();
+        let buffers = (0..3).map(|_| atlas.allocate()).collect::<Vec<_>>();
+        for (i, buffer) in buffers.iter().enumerate() {
+            buffer.store([i as u8; 20]);
+        }
+        atlas.flash(device, queue);
+
+        let stride = atlas.stride().unwrap();
+        assert_eq!(stride, 20u64.next_multiple_of(device_alignment));
+        for buffer in &buffers {
+            assert_eq!(atlas.offset_of(buffer).unwrap() % device_alignment, 0);
+        }
+        // capacity is rounded up to a power of two
+        assert_eq!(atlas.buffer().unwrap().size(), 4 * stride);
+    }
+
+    #[tokio::test]
+    async fn slots_follow_device_alignment_on_noop_adapter() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        check_slots_are_aligned(&device, &queue).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a GPU adapter"]
+    async fn slots_follow_device_alignment_on_real_adapter() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::real_wgpu()
+            .await
+            .expect("no GPU adapter available");
+        check_slots_are_aligned(&device, &queue).await;
+    }
+
+    #[tokio::test]
+    async fn custom_alignment() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+
+        let mut packed = BufferAtlas::<20>::new().with_alignment(1).unwrap();
+        let a = packed.allocate();
+        let b = packed.allocate();
+        packed.flash(&device, &queue);
+        assert_eq!(packed.stride(), Some(20));
+        assert_eq!(packed.offset_of(&a), Some(0));
+        assert_eq!(packed.offset_of(&b), Some(20));
+
+        let mut aligned = BufferAtlas::<20>::new_double_buffered()
+            .with_alignment(64)
+            .unwrap();
+        let a = aligned.allocate();
+        let b = aligned.allocate();
+        a.store([1; 20]);
+        b.store([2; 20]);
+        aligned.flash(&device, &queue);
+        assert_eq!(aligned.stride(), Some(64));
+        assert_eq!(aligned.offset_of(&b), Some(64));
+        let double = aligned.double.as_ref().unwrap();
+        assert_eq!(&double.shadow[64..84], &[2; 20]);
+        assert!(double.shadow[20..64].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn alignment_is_validated() {
+        assert_eq!(
+            BufferAtlas::<16>::new().with_alignment(0).err(),
+            Some(BufferAtlasError::InvalidAlignment(0))
+        );
+        assert_eq!(
+            BufferAtlas::<16>::new().with_alignment(48).err(),
+            Some(BufferAtlasError::InvalidAlignment(48))
+        );
+        assert!(BufferAtlas::<16>::new().with_alignment(256).is_ok());
+    }
+
+    #[tokio::test]
+    async fn alignment_is_fixed_after_flash() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let mut atlas = BufferAtlas::<16>::new();
+        atlas.flash(&device, &queue);
+        assert_eq!(
+            atlas.with_alignment(16).err(),
+            Some(BufferAtlasError::AlignmentFixed)
+        );
+    }
 }
@@ -11,8 +11,12 @@ pub struct GpuDescriptor {
     pub backends: wgpu::Backends,
     /// Power preference for adapter selection.
     pub power_preference: wgpu::PowerPreference,
+    /// Picks a specific adapter instead of the one wgpu prefers.
+    pub adapter_selection: AdapterSelection,
     /// Features that must be available on the device.
     pub required_features: wgpu::Features,
+    /// Features enabled only when the adapter supports them, e.g. texture compression.
+    pub optional_features: wgpu::Features,
     /// Optional device limits to request. If `None`, the adapter's limits are used.
     pub required_limits: Option<wgpu::Limits>,
     /// Preferred surface format for swapchains or surfaces created using this GPU.
@@ -27,7 +31,9 @@ impl Default for GpuDescriptor {
         Self {
             backends: wgpu::Backends::PRIMARY,
             power_preference: wgpu::PowerPreference::LowPower,
+            adapter_selection: AdapterSelection::Automatic,
             required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
             required_limits: None,
             preferred_surface_format: wgpu::TextureFormat::Bgra8UnormSrgb,
             auto_recover_enabled: false,
@@ -35,6 +41,47 @@ impl Default for GpuDescriptor {
     }
 }
 
+/// How [`Gpu::new`] picks an adapter among those of the enabled backends.
+///
+/// When the selected adapter does not exist, e.g. a saved setting after the hardware changed,
+/// the automatic choice is used instead.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AdapterSelection {
+    /// Let wgpu choose by power preference.
+    #[default]
+    Automatic,
+    /// The adapter at this index of [`Gpu::enumerate_adapters`].
+    Index(usize),
+    /// The first adapter whose name contains this string, ignoring case.
+    Name(String),
+}
+
+impl AdapterSelection {
+    /// Index of the selected adapter in `adapters`, or `None` for the automatic choice.
+    fn select(&self, adapters: &[wgpu::AdapterInfo]) -> Option<usize> {
+        match self {
+            AdapterSelection::Automatic => None,
+            AdapterSelection::Index(index) => (*index < adapters.len()).then_some(*index),
+            AdapterSelection::Name(name) => {
+                let name = name.to_lowercase();
+                adapters
+                    .iter()
+                    .position(|info| info.name.to_lowercase().contains(&name))
+            }
+        }
+    }
+}
+
+/// An adapter available on this machine, see [`Gpu::enumerate_adapters`].
+#[derive(Debug, Clone)]
+pub struct AdapterDescription {
+    /// Position in the list, usable as [`AdapterSelection::Index`].
+    pub index: usize,
+    pub info: wgpu::AdapterInfo,
+    pub features: wgpu::Features,
+    pub downlevel_flags: wgpu::DownlevelFlags,
+}
+
 static CALLBACK_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CallbackId {
@@ -83,6 +130,9 @@ pub struct Gpu {
         HashMap<CallbackId, Arc<dyn Fn(&wgpu::RequestDeviceError) + Send + Sync>, FxBuildHasher>,
     >,
 
+    /// Optional features the adapter lacks; downstream systems fall back without them.
+    unavailable_features: wgpu::Features,
+
     weak_self: Weak<Gpu>,
 }
 
@@ -103,7 +153,9 @@ impl Gpu {
         let GpuDescriptor {
             backends,
             power_preference,
+            adapter_selection,
             required_features,
+            optional_features,
             required_limits,
             preferred_surface_format,
             auto_recover_enabled,
@@ -117,14 +169,27 @@ impl Gpu {
             ..Default::default()
         });
 
-        trace!("Gpu::new: requesting adapter");
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await?;
+        let mut adapters = match adapter_selection {
+            AdapterSelection::Automatic => Vec::new(),
+            _ => instance.enumerate_adapters(backends),
+        };
+        let infos: Vec<_> = adapters.iter().map(|adapter| adapter.get_info()).collect();
+        let adapter = match adapter_selection.select(&infos) {
+            Some(index) => {
+                trace!("Gpu::new: using selected adapter {index}");
+                adapters.swap_remove(index)
+            }
+            None => {
+                if adapter_selection != AdapterSelection::Automatic {
+                    warn!(
+                        "Gpu::new: no adapter matches {adapter_selection:?}, choosing automatically"
+                    );
+                }
+                request_adapter(&instance, power_preference)
+                    .await
+                    .map_err(|source| GpuError::NoAdapter { backends, source })?
+            }
+        };
         debug!("Gpu::new: adapter received: {:#?}", adapter.get_info());
 
         // Validate features requested by user are supported by the adapter.
@@ -133,12 +198,19 @@ impl Gpu {
             warn!(
                 "Gpu::new: adapter does not support required features: required={required_features:?} available={adapter_features:?}"
             );
-            return Err(GpuError::AdapterFeatureUnsupported);
+            return Err(GpuError::AdapterFeatureUnsupported {
+                adapter: adapter.get_info().name,
+                missing: required_features - adapter_features,
+            });
         }
 
         // Determine limits (use adapter limits if not provided)
         let limits = required_limits.unwrap_or_else(|| adapter.limits());
-        let features = required_features;
+        let features = required_features | (optional_features & adapter_features);
+        let unavailable_features = optional_features - adapter_features;
+        if !unavailable_features.is_empty() {
+            debug!("Gpu::new: optional features unavailable: {unavailable_features:?}");
+        }
         trace!(
             "Gpu::new: requesting device with features={features:?}, limits={limits:?}, preferred_surface_format={preferred_surface_format:?}"
         );
@@ -148,19 +220,63 @@ impl Gpu {
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("Gpu: request device"),
                 required_features: features,
-                required_limits: limits.clone(),
+                required_limits: limits,
                 memory_hints: wgpu::MemoryHints::default(),
                 trace: wgpu::Trace::Off,
             })
             .await?;
 
-        // Build Arc<Gpu> with cyclic weak reference so callbacks can upgrade to Arc<Gpu>.
-        let arc_self = Arc::new_cyclic(|weak: &Weak<Gpu>| {
-            let device_queue = RwLock::new(GpuDeviceQueue {
-                device: device.clone(),
-                queue: queue.clone(),
-            });
+        trace!("Gpu::new: device and queue successfully created");
+        Ok(Self::assemble(
+            instance,
+            adapter,
+            device,
+            queue,
+            preferred_surface_format,
+            unavailable_features,
+            auto_recover_enabled,
+        ))
+    }
 
+    /// Wraps a device created elsewhere, e.g. by an engine that shares it with the UI or by
+    /// a test on wgpu's noop backend.
+    ///
+    /// The features and limits are those the device was created with. Automatic recovery is
+    /// disabled, since a lost device is recreated from `adapter` without the original
+    /// descriptor.
+    pub fn from_device(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        preferred_surface_format: wgpu::TextureFormat,
+    ) -> Arc<Self> {
+        trace!(
+            "Gpu::from_device: wrapping device of {:?}",
+            adapter.get_info().name
+        );
+        Self::assemble(
+            instance,
+            adapter,
+            device,
+            queue,
+            preferred_surface_format,
+            wgpu::Features::empty(),
+            false,
+        )
+    }
+
+    fn assemble(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        preferred_surface_format: wgpu::TextureFormat,
+        unavailable_features: wgpu::Features,
+        auto_recover_enabled: bool,
+    ) -> Arc<Self> {
+        // Build Arc<Gpu> with cyclic weak reference so callbacks can upgrade to Arc<Gpu>.
+        Arc::new_cyclic(|weak: &Weak<Gpu>| {
             // Install callbacks on the initial device so device-lost and uncaptured errors are handled.
             Self::install_device_callbacks(&device, weak);
             Self::install_uncaptured_error_handler(&device);
@@ -168,9 +284,9 @@ impl Gpu {
             Self {
                 instance,
                 adapter,
-                device_queue,
-                features,
-                limits,
+                features: device.features(),
+                limits: device.limits(),
+                device_queue: RwLock::new(GpuDeviceQueue { device, queue }),
                 preferred_surface_format,
                 device_lost: AtomicBool::new(false),
                 device_lost_details: RwLock::new(None),
@@ -179,12 +295,10 @@ impl Gpu {
                 is_recovering: AtomicBool::new(false),
                 device_recover_callback: Default::default(),
                 device_recover_failed_callback: Default::default(),
+                unavailable_features,
                 weak_self: weak.clone(),
             }
-        });
-
-        trace!("Gpu::new: device and queue successfully created");
-        Ok(arc_self)
+        })
     }
 
     /// Add a callback to be invoked when the device is lost.
@@ -286,11 +400,26 @@ impl Gpu {
         self.device_queue.read().queue.clone()
     }
 
-    /// Get features requested at creation.
+    /// Get features enabled on the device: the required ones and the supported optional ones.
     pub fn features(&self) -> &wgpu::Features {
         &self.features
     }
 
+    /// Whether all of `features` are enabled on the device.
+    pub fn has_features(&self, features: wgpu::Features) -> bool {
+        self.features.contains(features)
+    }
+
+    /// Optional features that were requested but are not supported by the adapter.
+    pub fn unavailable_features(&self) -> wgpu::Features {
+        self.unavailable_features
+    }
+
+    /// Capabilities beyond the WebGPU baseline, e.g. compute shaders or indirect draws.
+    pub fn downlevel_flags(&self) -> wgpu::DownlevelFlags {
+        self.adapter.get_downlevel_capabilities().flags
+    }
+
     /// Get limits requested at creation.
     pub fn limits(&self) -> &wgpu::Limits {
         &self.limits
@@ -333,6 +462,30 @@ impl Gpu {
     }
 }
 
+impl Gpu {
+    /// Lists the adapters of `backends` on this machine, e.g. for a GPU picker in settings.
+    ///
+    /// Pass the chosen [`AdapterDescription::index`] as [`AdapterSelection::Index`] with the
+    /// same backends.
+    pub fn enumerate_adapters(backends: wgpu::Backends) -> Vec<AdapterDescription> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends,
+            ..Default::default()
+        });
+        instance
+            .enumerate_adapters(backends)
+            .into_iter()
+            .enumerate()
+            .map(|(index, adapter)| AdapterDescription {
+                index,
+                info: adapter.get_info(),
+                features: adapter.features(),
+                downlevel_flags: adapter.get_downlevel_capabilities().flags,
+            })
+            .collect()
+    }
+}
+
 /* ----------------------
 Private helpers and callback handlers
 ---------------------- */
@@ -465,12 +618,70 @@ impl Gpu {
     }
 }
 
+async fn request_adapter(
+    instance: &wgpu::Instance,
+    power_preference: wgpu::PowerPreference,
+) -> Result<wgpu::Adapter, wgpu::RequestAdapterError> {
+    trace!("Gpu::new: requesting adapter");
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        })
+        .await
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GpuError {
-    #[error("Failed to request adapter")]
-    AdapterRequestFailed(#[from] wgpu::RequestAdapterError),
-    #[error("Adapter does not support required features")]
-    AdapterFeatureUnsupported,
+    #[error("No GPU adapter available on backends {backends:?}: {source}")]
+    NoAdapter {
+        backends: wgpu::Backends,
+        #[source]
+        source: wgpu::RequestAdapterError,
+    },
+    #[error("Adapter {adapter} does not support required features {missing:?}")]
+    AdapterFeatureUnsupported {
+        adapter: String,
+        missing: wgpu::Features,
+    },
     #[error("Failed to request device")]
     DeviceRequestFailed(#[from] wgpu::RequestDeviceError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str) -> wgpu::AdapterInfo {
+        wgpu::AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type: wgpu::DeviceType::DiscreteGpu,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        }
+    }
+
+    #[test]
+    fn adapter_selection_falls_back_to_automatic() {
+        let adapters = [
+            adapter("Intel(R) UHD Graphics"),
+            adapter("NVIDIA GeForce RTX"),
+        ];
+
+        assert_eq!(AdapterSelection::Automatic.select(&adapters), None);
+        assert_eq!(AdapterSelection::Index(1).select(&adapters), Some(1));
+        assert_eq!(AdapterSelection::Index(2).select(&adapters), None);
+        assert_eq!(
+            AdapterSelection::Name("geforce".to_string()).select(&adapters),
+            Some(1)
+        );
+        assert_eq!(
+            AdapterSelection::Name("radeon".to_string()).select(&adapters),
+            None
+        );
+    }
+}
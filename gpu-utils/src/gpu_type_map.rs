@@ -1,6 +1,9 @@
 use std::{
     any::{Any, TypeId},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
 };
 
 use crate::device_loss_recoverable::DeviceLossRecoverable;
@@ -115,6 +118,109 @@ impl GpuTypeMap {
     }
 }
 
+// MARK: RendererRegistry
+
+/// A shared, device-bound renderer (pipelines, layouts, samplers) that widgets draw with.
+///
+/// Instances are created on first use by [`RendererRegistry::get`] and shared by every widget.
+pub trait WidgetRenderer: Send + Sync + 'static {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self;
+}
+
+struct RendererEntry {
+    generation: u64,
+    renderer: Arc<dyn Any + Send + Sync>,
+}
+
+/// Type-keyed registry of singleton [`WidgetRenderer`]s.
+///
+/// Each entry remembers the device generation it was built for. Recovering from device loss
+/// bumps the generation and drops all entries, so every renderer is rebuilt lazily against the
+/// new device the next time it is requested.
+#[derive(Default)]
+pub struct RendererRegistry {
+    generation: AtomicU64,
+    map: dashmap::DashMap<TypeId, RendererEntry, fxhash::FxBuildHasher>,
+}
+
+impl DeviceLossRecoverable for RendererRegistry {
+    fn recover(&self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        self.map.clear();
+        log::debug!("RendererRegistry::recover: evicted all renderers, generation={generation}");
+    }
+}
+
+impl RendererRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The current device generation. Incremented on every device recovery.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns the shared instance of `T`, creating it if it does not exist yet or was built
+    /// for a previous device generation.
+    pub fn get<T>(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Arc<T>
+    where
+        T: WidgetRenderer,
+    {
+        let generation = self.generation();
+        let new_entry = || {
+            log::trace!(
+                "RendererRegistry::get: creating {} (generation={generation})",
+                std::any::type_name::<T>()
+            );
+            RendererEntry {
+                generation,
+                renderer: Arc::new(T::new(device, queue)),
+            }
+        };
+
+        let mut entry = self.map.entry(TypeId::of::<T>()).or_insert_with(new_entry);
+        if entry.generation != generation {
+            *entry = new_entry();
+        }
+
+        entry.renderer.clone().downcast().expect(TYPE_LOGIC_ERROR)
+    }
+
+    /// Returns `true` if an up-to-date instance of `T` exists.
+    pub fn contains<T>(&self) -> bool
+    where
+        T: WidgetRenderer,
+    {
+        let generation = self.generation();
+        self.map
+            .get(&TypeId::of::<T>())
+            .is_some_and(|entry| entry.generation == generation)
+    }
+
+    /// Drops the shared instance of `T`. Widgets still holding an `Arc` keep it alive,
+    /// the next [`get`](Self::get) creates a new one.
+    pub fn evict<T>(&self) -> bool
+    where
+        T: WidgetRenderer,
+    {
+        self.map.remove(&TypeId::of::<T>()).is_some()
+    }
+
+    /// Drops every renderer.
+    pub fn clear(&self) {
+        self.map.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,4 +259,45 @@ mod tests {
         let c2 = resource.get_or_insert(TypeC { v: 42 });
         assert_eq!(c2.v, u32::default());
     }
+
+    struct CountingRenderer {
+        id: u32,
+    }
+
+    static CREATED: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+    impl WidgetRenderer for CountingRenderer {
+        fn new(_device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+            Self {
+                id: CREATED.fetch_add(1, Ordering::SeqCst),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_renderer_registry_generation() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let registry = RendererRegistry::new();
+        assert!(!registry.contains::<CountingRenderer>());
+
+        let a = registry.get::<CountingRenderer>(&device, &queue);
+        let b = registry.get::<CountingRenderer>(&device, &queue);
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(registry.contains::<CountingRenderer>());
+        assert_eq!(registry.generation(), 0);
+
+        // device recovery rebuilds the entry
+        registry.recover(&device, &queue);
+        assert_eq!(registry.generation(), 1);
+        assert!(registry.is_empty());
+        let c = registry.get::<CountingRenderer>(&device, &queue);
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_ne!(a.id, c.id);
+
+        // eviction
+        assert!(registry.evict::<CountingRenderer>());
+        assert!(!registry.evict::<CountingRenderer>());
+        let d = registry.get::<CountingRenderer>(&device, &queue);
+        assert_ne!(c.id, d.id);
+    }
 }
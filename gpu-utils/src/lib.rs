@@ -2,7 +2,9 @@ pub mod buffer_atlas;
 pub mod device_loss_recoverable;
 pub mod gpu;
 pub mod gpu_type_map;
+pub mod memory;
 pub mod texture_atlas;
+pub mod upload;
 
-#[cfg(debug_assertions)]
+#[cfg(any(debug_assertions, feature = "testing"))]
 pub mod wgpu_utils;
@@ -0,0 +1,295 @@
+//! Process-wide accounting of the GPU memory held by atlases and renderer buffers.
+//!
+//! Texture atlases register themselves when they are created and are measured when a report
+//! is collected. Buffers that grow and shrink on their own report their size through a
+//! [`TrackedBytes`] into one of the [`MemoryCounter`]s. [`MemoryReport::collect`] sums it all
+//! up, e.g. to show it in a debug overlay or to drop caches under memory pressure.
+
+use std::fmt::{self, Display};
+use std::sync::Weak;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+
+/// Bytes held by the GPU buffers of all
+/// [`BufferAtlas`](crate::buffer_atlas::buffer_atlas::BufferAtlas)es.
+pub static BUFFER_ATLASES: MemoryCounter = MemoryCounter::new();
+/// Bytes held by buffers that renderers keep across frames.
+pub static RENDERER_BUFFERS: MemoryCounter = MemoryCounter::new();
+/// Bytes held by the staging buffers of [`UploadRing`](crate::upload::UploadRing)s.
+pub static UPLOAD_STAGING: MemoryCounter = MemoryCounter::new();
+
+/// A texture atlas whose textures are counted by [`MemoryReport::collect`].
+pub(crate) trait AtlasMemory: Send + Sync {
+    /// Adds `(format, bytes, used bytes)` of every texture of the atlas to `out`.
+    fn texture_memory(&self, out: &mut Vec<(wgpu::TextureFormat, u64, u64)>);
+}
+
+static TEXTURE_ATLASES: Mutex<Vec<Weak<dyn AtlasMemory>>> = parking_lot::const_mutex(Vec::new());
+
+pub(crate) fn register_atlas(atlas: Weak<dyn AtlasMemory>) {
+    let mut atlases = TEXTURE_ATLASES.lock();
+    atlases.retain(|atlas| atlas.strong_count() > 0);
+    atlases.push(atlas);
+}
+
+/// Bytes of a texture of `format` and `size`, or 0 for formats without a fixed block size.
+pub fn texture_bytes(format: wgpu::TextureFormat, size: wgpu::Extent3d) -> u64 {
+    let Some(block_bytes) = format.block_copy_size(None) else {
+        return 0;
+    };
+    let (block_width, block_height) = format.block_dimensions();
+    u64::from(size.width.div_ceil(block_width))
+        * u64::from(size.height.div_ceil(block_height))
+        * u64::from(size.depth_or_array_layers)
+        * u64::from(block_bytes)
+}
+
+/// Bytes of `texels` texels of `format`, rounded down to whole blocks.
+pub(crate) fn texel_bytes(format: wgpu::TextureFormat, texels: u64) -> u64 {
+    let Some(block_bytes) = format.block_copy_size(None) else {
+        return 0;
+    };
+    let (block_width, block_height) = format.block_dimensions();
+    texels / u64::from(block_width * block_height) * u64::from(block_bytes)
+}
+
+/// A running total of bytes held by one kind of GPU resource.
+#[derive(Debug)]
+pub struct MemoryCounter(AtomicU64);
+
+impl MemoryCounter {
+    pub const fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for MemoryCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The size of one resource, counted in a [`MemoryCounter`] until it is dropped.
+#[derive(Debug)]
+pub struct TrackedBytes {
+    counter: &'static MemoryCounter,
+    bytes: u64,
+}
+
+impl TrackedBytes {
+    pub fn new(counter: &'static MemoryCounter) -> Self {
+        Self { counter, bytes: 0 }
+    }
+
+    /// Replaces the size of the resource.
+    pub fn set(&mut self, bytes: u64) {
+        if bytes >= self.bytes {
+            self.counter
+                .0
+                .fetch_add(bytes - self.bytes, Ordering::Relaxed);
+        } else {
+            self.counter
+                .0
+                .fetch_sub(self.bytes - bytes, Ordering::Relaxed);
+        }
+        self.bytes = bytes;
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Drop for TrackedBytes {
+    fn drop(&mut self) {
+        self.set(0);
+    }
+}
+
+/// Texture atlas memory of one format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatMemory {
+    pub format: wgpu::TextureFormat,
+    /// Number of atlas textures of this format.
+    pub textures: usize,
+    /// Bytes of the textures.
+    pub bytes: u64,
+    /// Bytes covered by allocated regions.
+    pub used_bytes: u64,
+}
+
+/// GPU memory held by the atlases and renderer buffers of the process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Texture atlas memory per format, largest first.
+    pub textures: Vec<FormatMemory>,
+    pub buffer_atlas_bytes: u64,
+    pub renderer_buffer_bytes: u64,
+    pub upload_staging_bytes: u64,
+}
+
+impl MemoryReport {
+    /// Measures every live texture atlas and reads the buffer counters.
+    pub fn collect() -> Self {
+        let atlases: Vec<_> = {
+            let mut atlases = TEXTURE_ATLASES.lock();
+            atlases.retain(|atlas| atlas.strong_count() > 0);
+            atlases.iter().filter_map(Weak::upgrade).collect()
+        };
+        // atlases are measured without holding the registry, they lock their own state
+        let mut textures = Vec::new();
+        for atlas in atlases {
+            atlas.texture_memory(&mut textures);
+        }
+
+        Self {
+            textures: group_by_format(textures),
+            buffer_atlas_bytes: BUFFER_ATLASES.bytes(),
+            renderer_buffer_bytes: RENDERER_BUFFERS.bytes(),
+            upload_staging_bytes: UPLOAD_STAGING.bytes(),
+        }
+    }
+
+    pub fn texture_bytes(&self) -> u64 {
+        self.textures.iter().map(|format| format.bytes).sum()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.texture_bytes()
+            + self.buffer_atlas_bytes
+            + self.renderer_buffer_bytes
+            + self.upload_staging_bytes
+    }
+
+    /// Whether more than `budget_bytes` are held in total.
+    pub fn exceeds(&self, budget_bytes: u64) -> bool {
+        self.total_bytes() > budget_bytes
+    }
+}
+
+fn group_by_format(textures: Vec<(wgpu::TextureFormat, u64, u64)>) -> Vec<FormatMemory> {
+    let mut formats: Vec<FormatMemory> = Vec::new();
+    for (format, bytes, used_bytes) in textures {
+        match formats.iter_mut().find(|memory| memory.format == format) {
+            Some(memory) => {
+                memory.textures += 1;
+                memory.bytes += bytes;
+                memory.used_bytes += used_bytes;
+            }
+            None => formats.push(FormatMemory {
+                format,
+                textures: 1,
+                bytes,
+                used_bytes,
+            }),
+        }
+    }
+    formats.sort_by_key(|format| std::cmp::Reverse(format.bytes));
+    formats
+}
+
+struct Bytes(u64);
+
+impl Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MIB: f64 = 1024.0 * 1024.0;
+        write!(f, "{:.1} MiB", self.0 as f64 / MIB)
+    }
+}
+
+/// One line per kind of memory, e.g. for a debug overlay.
+impl Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "GPU memory: {}", Bytes(self.total_bytes()))?;
+        for format in &self.textures {
+            writeln!(
+                f,
+                "  atlas {:?}: {} ({} used)",
+                format.format,
+                Bytes(format.bytes),
+                Bytes(format.used_bytes)
+            )?;
+        }
+        writeln!(f, "  buffer atlases: {}", Bytes(self.buffer_atlas_bytes))?;
+        writeln!(
+            f,
+            "  renderer buffers: {}",
+            Bytes(self.renderer_buffer_bytes)
+        )?;
+        write!(f, "  upload staging: {}", Bytes(self.upload_staging_bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_sizes_count_whole_blocks() {
+        let size = wgpu::Extent3d {
+            width: 10,
+            height: 6,
+            depth_or_array_layers: 2,
+        };
+        assert_eq!(
+            texture_bytes(wgpu::TextureFormat::Rgba8Unorm, size),
+            10 * 6 * 2 * 4
+        );
+        assert_eq!(
+            texture_bytes(wgpu::TextureFormat::R8Unorm, size),
+            10 * 6 * 2
+        );
+        // 3 x 2 blocks of 4x4 texels and 16 bytes
+        assert_eq!(
+            texture_bytes(wgpu::TextureFormat::Bc7RgbaUnorm, size),
+            3 * 2 * 2 * 16
+        );
+        assert_eq!(texel_bytes(wgpu::TextureFormat::Bc7RgbaUnorm, 32), 2 * 16);
+    }
+
+    #[test]
+    fn tracked_bytes_leave_the_counter_when_dropped() {
+        static COUNTER: MemoryCounter = MemoryCounter::new();
+        let mut first = TrackedBytes::new(&COUNTER);
+        let mut second = TrackedBytes::new(&COUNTER);
+        first.set(100);
+        second.set(50);
+        first.set(40);
+        assert_eq!(COUNTER.bytes(), 90);
+        drop(first);
+        assert_eq!(COUNTER.bytes(), 50);
+        drop(second);
+        assert_eq!(COUNTER.bytes(), 0);
+    }
+
+    #[test]
+    fn formats_are_summed_largest_first() {
+        let grouped = group_by_format(vec![
+            (wgpu::TextureFormat::R8Unorm, 10, 5),
+            (wgpu::TextureFormat::Rgba8Unorm, 40, 0),
+            (wgpu::TextureFormat::R8Unorm, 20, 10),
+        ]);
+        assert_eq!(
+            grouped,
+            [
+                FormatMemory {
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    textures: 1,
+                    bytes: 40,
+                    used_bytes: 0,
+                },
+                FormatMemory {
+                    format: wgpu::TextureFormat::R8Unorm,
+                    textures: 2,
+                    bytes: 30,
+                    used_bytes: 15,
+                },
+            ]
+        );
+    }
+}
@@ -1,11 +1,12 @@
 // simple implementation of a texture atlas.
 pub mod atlas_simple;
-// atlas with runtime witch automatically resizes the atlas(did not complete yet).
+// atlas that resizes incrementally across frames, for heavy allocation churn.
 pub mod atlas_with_runtime;
 
 pub use atlas_simple::{
-    AtlasManager, AtlasManagerError, AtlasRegion, MemoryAllocateStrategy, RegionError,
-    TextureAtlas, TextureAtlasError, TextureAtlasId,
+    AllocationStrategy, AtlasFragmentation, AtlasKey, AtlasManager, AtlasManagerError, AtlasRegion,
+    AtlasUsage, GuillotineStrategy, MarginFill, MemoryAllocateStrategy, RegionError, ShelfStrategy,
+    SkylineStrategy, TextureAtlas, TextureAtlasError, TextureAtlasId, WeakAtlasRegion,
 };
 
 // re-exports
@@ -1,4 +1,12 @@
+pub mod allocator;
+pub use allocator::{
+    AllocationId, AllocationRect, AllocationStrategy, AtlasFragmentation, FreeSpace,
+    GuillotineStrategy, PageAllocation, PageAllocator, ShelfStrategy, SkylineStrategy,
+};
 pub mod atlas;
-pub use atlas::{AtlasRegion, RegionError, TextureAtlas, TextureAtlasError, TextureAtlasId};
+pub use atlas::{
+    AtlasRegion, MarginFill, RegionError, TextureAtlas, TextureAtlasError, TextureAtlasId,
+    WeakAtlasRegion,
+};
 pub mod manager;
-pub use manager::{AtlasManager, AtlasManagerError, MemoryAllocateStrategy};
+pub use manager::{AtlasKey, AtlasManager, AtlasManagerError, AtlasUsage, MemoryAllocateStrategy};
@@ -0,0 +1,644 @@
+//! Rectangle packing for the pages of a [`TextureAtlas`](super::TextureAtlas).
+//!
+//! Each atlas packs with one [`AllocationStrategy`], chosen when it is created. Which one packs
+//! best depends on the content:
+//!
+//! - [`GuillotineStrategy`], the default, splits free space recursively. It copes with mixed
+//!   sizes and churn, e.g. decoded images.
+//! - [`ShelfStrategy`] stacks rows of similar height. Many small items of few heights, e.g.
+//!   glyphs of a handful of font sizes, waste little space and free quickly.
+//! - [`SkylineStrategy`] places each rectangle as low as possible along the top edge of the
+//!   packed area. It packs sets of varied sizes tightly, but reuses space below other
+//!   allocations only once those are freed too.
+//!
+//! Compare them on real content with
+//! [`TextureAtlas::fragmentation`](super::TextureAtlas::fragmentation). Other strategies
+//! implement [`AllocationStrategy`] and [`PageAllocator`].
+
+use std::collections::HashMap;
+
+use guillotiere::{AllocId, AllocatorOptions, Size, euclid};
+
+pub type AllocationRect = euclid::Box2D<i32, euclid::UnknownUnit>;
+
+/// Identifies an allocation within one [`PageAllocator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AllocationId(pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageAllocation {
+    pub id: AllocationId,
+    pub rectangle: AllocationRect,
+}
+
+/// Packs rectangles into one page of an atlas.
+pub trait PageAllocator: Send {
+    /// Reserves a rectangle of at least `size`, with its position and size multiples of the
+    /// snap the allocator was created with. `None` if the page has no room.
+    fn allocate(&mut self, size: [u32; 2]) -> Option<PageAllocation>;
+
+    /// Frees an allocation made by this allocator.
+    fn deallocate(&mut self, id: AllocationId);
+
+    /// The space later allocations can still use.
+    fn free_space(&self) -> FreeSpace;
+}
+
+/// Creates the [`PageAllocator`] of each page of an atlas.
+pub trait AllocationStrategy: Send + Sync + std::fmt::Debug {
+    /// An empty allocator for a page of `page_size`. Allocations must be aligned to `snap`,
+    /// e.g. the block size of a compressed format.
+    fn create_allocator(&self, page_size: [u32; 2], snap: u32) -> Box<dyn PageAllocator>;
+}
+
+/// Usable free space of a page, see [`PageAllocator::free_space`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FreeSpace {
+    /// Total area of the free rectangles.
+    pub area: u64,
+    /// Area of the largest free rectangle.
+    pub largest_area: u64,
+    /// Number of disjoint free rectangles.
+    pub rectangles: usize,
+}
+
+impl FreeSpace {
+    pub(crate) fn merge(self, other: FreeSpace) -> FreeSpace {
+        FreeSpace {
+            area: self.area + other.area,
+            largest_area: self.largest_area.max(other.largest_area),
+            rectangles: self.rectangles + other.rectangles,
+        }
+    }
+
+    fn add_rect(&mut self, width: u32, height: u32) {
+        let area = u64::from(width) * u64::from(height);
+        if area == 0 {
+            return;
+        }
+        self.area += area;
+        self.largest_area = self.largest_area.max(area);
+        self.rectangles += 1;
+    }
+}
+
+/// Packing metrics of a [`TextureAtlas`](super::TextureAtlas) across its pages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtlasFragmentation {
+    /// Texels across all pages.
+    pub capacity: u64,
+    /// Texels held by live allocations, including margins and block padding.
+    pub allocated: u64,
+    /// Space still usable by allocations, summed over pages.
+    pub free: FreeSpace,
+}
+
+impl AtlasFragmentation {
+    /// Share of the free area outside the largest free rectangle: 0 when the free space is one
+    /// rectangle, approaching 1 when it is scattered into small pieces.
+    pub fn fragmentation(&self) -> f32 {
+        if self.free.area == 0 {
+            return 0.0;
+        }
+        1.0 - self.free.largest_area as f32 / self.free.area as f32
+    }
+
+    /// Share of the capacity that is neither allocated nor usable, e.g. the gaps above short
+    /// items on a shelf or below a skyline.
+    pub fn waste(&self) -> f32 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        let wasted = self
+            .capacity
+            .saturating_sub(self.allocated)
+            .saturating_sub(self.free.area);
+        wasted as f32 / self.capacity as f32
+    }
+}
+
+fn snap_size(size: [u32; 2], snap: u32) -> Option<[u32; 2]> {
+    Some([
+        size[0].checked_next_multiple_of(snap)?,
+        size[1].checked_next_multiple_of(snap)?,
+    ])
+}
+
+fn rect(x: u32, y: u32, width: u32, height: u32) -> AllocationRect {
+    euclid::Box2D::new(
+        euclid::Point2D::new(x as i32, y as i32),
+        euclid::Point2D::new((x + width) as i32, (y + height) as i32),
+    )
+}
+
+// MARK: Guillotine
+
+/// Splits free rectangles recursively, using the `guillotiere` crate.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GuillotineStrategy;
+
+impl AllocationStrategy for GuillotineStrategy {
+    fn create_allocator(&self, page_size: [u32; 2], snap: u32) -> Box<dyn PageAllocator> {
+        Box::new(GuillotineAllocator {
+            allocator: guillotine_allocator(page_size, snap),
+        })
+    }
+}
+
+/// A `guillotiere` allocator for a page of `page_size` whose allocations are aligned to
+/// multiples of `snap`.
+pub(crate) fn guillotine_allocator(page_size: [u32; 2], snap: u32) -> guillotiere::AtlasAllocator {
+    guillotiere::AtlasAllocator::with_options(
+        Size::new(page_size[0] as i32, page_size[1] as i32),
+        &AllocatorOptions {
+            alignment: guillotiere::size2(snap as i32, snap as i32),
+            ..guillotiere::DEFAULT_OPTIONS
+        },
+    )
+}
+
+struct GuillotineAllocator {
+    allocator: guillotiere::AtlasAllocator,
+}
+
+impl PageAllocator for GuillotineAllocator {
+    fn allocate(&mut self, size: [u32; 2]) -> Option<PageAllocation> {
+        let size = Size::new(i32::try_from(size[0]).ok()?, i32::try_from(size[1]).ok()?);
+        let allocation = self.allocator.allocate(size)?;
+        Some(PageAllocation {
+            id: AllocationId(u64::from(allocation.id.serialize())),
+            rectangle: allocation.rectangle,
+        })
+    }
+
+    fn deallocate(&mut self, id: AllocationId) {
+        self.allocator.deallocate(AllocId::deserialize(id.0 as u32));
+    }
+
+    fn free_space(&self) -> FreeSpace {
+        let mut free = FreeSpace::default();
+        self.allocator.for_each_free_rectangle(|rectangle| {
+            free.add_rect(rectangle.width() as u32, rectangle.height() as u32);
+        });
+        free
+    }
+}
+
+// MARK: Shelf
+
+/// Packs rows ("shelves") of items of similar height, left to right.
+///
+/// An item goes on the lowest shelf at most half again as tall as the item, or on a new shelf
+/// of its height below the last one. Once the page is full of shelves, any shelf tall enough
+/// is used. Trailing shelves are removed when emptied, so their space can take other heights.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShelfStrategy;
+
+impl AllocationStrategy for ShelfStrategy {
+    fn create_allocator(&self, page_size: [u32; 2], snap: u32) -> Box<dyn PageAllocator> {
+        Box::new(ShelfAllocator {
+            size: page_size,
+            snap: snap.max(1),
+            shelves: Vec::new(),
+            allocations: HashMap::new(),
+            next_id: 0,
+        })
+    }
+}
+
+struct ShelfAllocator {
+    size: [u32; 2],
+    snap: u32,
+    shelves: Vec<Shelf>,
+    // shelf index, x and width of each live allocation
+    allocations: HashMap<u64, (usize, u32, u32)>,
+    next_id: u64,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    // free spans as (x, width), sorted by x and never adjacent
+    free: Vec<(u32, u32)>,
+    allocations: usize,
+}
+
+impl ShelfAllocator {
+    fn next_shelf_y(&self) -> u32 {
+        self.shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height)
+    }
+
+    /// The shelf with the least height to spare that has a span of `width`, if it spares at
+    /// most `max_spare`.
+    fn best_shelf(&self, [width, height]: [u32; 2], max_spare: u32) -> Option<usize> {
+        self.shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, shelf)| {
+                shelf.height >= height
+                    && shelf.height - height <= max_spare
+                    && shelf.free.iter().any(|&(_, span)| span >= width)
+            })
+            .min_by_key(|(_, shelf)| shelf.height)
+            .map(|(index, _)| index)
+    }
+}
+
+impl PageAllocator for ShelfAllocator {
+    fn allocate(&mut self, size: [u32; 2]) -> Option<PageAllocation> {
+        let [width, height] = snap_size(size, self.snap)?;
+        if width == 0 || height == 0 || width > self.size[0] || height > self.size[1] {
+            return None;
+        }
+
+        let index = match self.best_shelf([width, height], height / 2) {
+            Some(index) => index,
+            None if self.next_shelf_y() + height <= self.size[1] => {
+                self.shelves.push(Shelf {
+                    y: self.next_shelf_y(),
+                    height,
+                    free: vec![(0, self.size[0])],
+                    allocations: 0,
+                });
+                self.shelves.len() - 1
+            }
+            None => self.best_shelf([width, height], u32::MAX)?,
+        };
+
+        let shelf = &mut self.shelves[index];
+        let span = shelf.free.iter().position(|&(_, span)| span >= width)?;
+        let x = shelf.free[span].0;
+        shelf.free[span].0 += width;
+        shelf.free[span].1 -= width;
+        if shelf.free[span].1 == 0 {
+            shelf.free.remove(span);
+        }
+        shelf.allocations += 1;
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.allocations.insert(id, (index, x, width));
+        Some(PageAllocation {
+            id: AllocationId(id),
+            rectangle: rect(x, shelf.y, width, height),
+        })
+    }
+
+    fn deallocate(&mut self, id: AllocationId) {
+        let Some((index, x, width)) = self.allocations.remove(&id.0) else {
+            return;
+        };
+        let shelf = &mut self.shelves[index];
+        let at = shelf.free.partition_point(|&(span_x, _)| span_x < x);
+        shelf.free.insert(at, (x, width));
+        // merge with the following and the preceding span
+        if at + 1 < shelf.free.len() && x + width == shelf.free[at + 1].0 {
+            shelf.free[at].1 += shelf.free[at + 1].1;
+            shelf.free.remove(at + 1);
+        }
+        if at > 0 && shelf.free[at - 1].0 + shelf.free[at - 1].1 == x {
+            shelf.free[at - 1].1 += shelf.free[at].1;
+            shelf.free.remove(at);
+        }
+        shelf.allocations -= 1;
+
+        while self
+            .shelves
+            .last()
+            .is_some_and(|shelf| shelf.allocations == 0)
+        {
+            self.shelves.pop();
+        }
+    }
+
+    fn free_space(&self) -> FreeSpace {
+        let mut free = FreeSpace::default();
+        for shelf in &self.shelves {
+            for &(_, width) in &shelf.free {
+                free.add_rect(width, shelf.height);
+            }
+        }
+        free.add_rect(self.size[0], self.size[1] - self.next_shelf_y());
+        free
+    }
+}
+
+// MARK: Skyline
+
+/// Places each item at the lowest position along the top edge ("skyline") of the packed
+/// area, leftmost first.
+///
+/// The skyline follows the tops of the live allocations, so freed space is reused once
+/// nothing is allocated above it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SkylineStrategy;
+
+impl AllocationStrategy for SkylineStrategy {
+    fn create_allocator(&self, page_size: [u32; 2], snap: u32) -> Box<dyn PageAllocator> {
+        Box::new(SkylineAllocator {
+            size: page_size,
+            snap: snap.max(1),
+            skyline: vec![Segment {
+                x: 0,
+                y: 0,
+                width: page_size[0],
+            }],
+            allocations: HashMap::new(),
+            next_id: 0,
+        })
+    }
+}
+
+struct SkylineAllocator {
+    size: [u32; 2],
+    snap: u32,
+    // covers the page width left to right, without neighbors of the same height
+    skyline: Vec<Segment>,
+    allocations: HashMap<u64, AllocationRect>,
+    next_id: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    x: u32,
+    y: u32,
+    width: u32,
+}
+
+impl SkylineAllocator {
+    /// The height an item of `width` rests at when placed at the start of segment `index`.
+    fn rest_height(&self, index: usize, width: u32) -> Option<u32> {
+        let x = self.skyline[index].x;
+        if x + width > self.size[0] {
+            return None;
+        }
+        self.skyline[index..]
+            .iter()
+            .take_while(|segment| segment.x < x + width)
+            .map(|segment| segment.y)
+            .max()
+    }
+
+    /// Sets the skyline between `x` and `x + width` to `y`.
+    fn set_span(&mut self, x: u32, width: u32, y: u32) {
+        let end = x + width;
+        let mut skyline = Vec::with_capacity(self.skyline.len() + 2);
+        let mut inserted = false;
+        for segment in &self.skyline {
+            let segment_end = segment.x + segment.width;
+            if segment.x < x {
+                skyline.push(Segment {
+                    width: segment_end.min(x) - segment.x,
+                    ..*segment
+                });
+            }
+            if !inserted && segment_end > x {
+                skyline.push(Segment { x, y, width });
+                inserted = true;
+            }
+            if segment_end > end {
+                let start = segment.x.max(end);
+                skyline.push(Segment {
+                    x: start,
+                    y: segment.y,
+                    width: segment_end - start,
+                });
+            }
+        }
+        skyline.dedup_by(|next, previous| {
+            let same_height = next.y == previous.y;
+            if same_height {
+                previous.width += next.width;
+            }
+            same_height
+        });
+        self.skyline = skyline;
+    }
+}
+
+impl PageAllocator for SkylineAllocator {
+    fn allocate(&mut self, size: [u32; 2]) -> Option<PageAllocation> {
+        let [width, height] = snap_size(size, self.snap)?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let (x, y) = (0..self.skyline.len())
+            .filter_map(|index| {
+                let y = self.rest_height(index, width)?;
+                (y + height <= self.size[1]).then_some((self.skyline[index].x, y))
+            })
+            .min_by_key(|&(x, y)| (y, x))?;
+        self.set_span(x, width, y + height);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let rectangle = rect(x, y, width, height);
+        self.allocations.insert(id, rectangle);
+        Some(PageAllocation {
+            id: AllocationId(id),
+            rectangle,
+        })
+    }
+
+    fn deallocate(&mut self, id: AllocationId) {
+        let Some(freed) = self.allocations.remove(&id.0) else {
+            return;
+        };
+        let [min, max] = [freed.min.x as u32, freed.max.x as u32];
+
+        // lower the span to the tops of the allocations still below it
+        let below: Vec<_> = self
+            .allocations
+            .values()
+            .filter(|other| (other.min.x as u32) < max && (other.max.x as u32) > min)
+            .collect();
+        let mut edges = vec![min, max];
+        for other in &below {
+            edges.push((other.min.x as u32).clamp(min, max));
+            edges.push((other.max.x as u32).clamp(min, max));
+        }
+        edges.sort_unstable();
+        edges.dedup();
+
+        let spans: Vec<_> = edges
+            .windows(2)
+            .map(|pair| {
+                let top = below
+                    .iter()
+                    .filter(|other| other.min.x as u32 <= pair[0] && other.max.x as u32 >= pair[1])
+                    .map(|other| other.max.y as u32)
+                    .max()
+                    .unwrap_or(0);
+                (pair[0], pair[1] - pair[0], top)
+            })
+            .collect();
+        for (x, width, y) in spans {
+            self.set_span(x, width, y);
+        }
+    }
+
+    fn free_space(&self) -> FreeSpace {
+        // the area above the skyline, split at each segment
+        let mut free = FreeSpace::default();
+        for segment in &self.skyline {
+            free.add_rect(segment.width, self.size[1] - segment.y);
+        }
+
+        // the largest rectangle resting on the skyline
+        free.largest_area = self
+            .skyline
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let fits = |other: &&Segment| other.y <= segment.y;
+                let left: u32 = self.skyline[..index]
+                    .iter()
+                    .rev()
+                    .take_while(fits)
+                    .map(|other| other.width)
+                    .sum();
+                let right: u32 = self.skyline[index..]
+                    .iter()
+                    .take_while(fits)
+                    .map(|other| other.width)
+                    .sum();
+                u64::from(left + right) * u64::from(self.size[1] - segment.y)
+            })
+            .max()
+            .unwrap_or(0);
+        free
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn strategies() -> [Box<dyn AllocationStrategy>; 3] {
+        [
+            Box::new(GuillotineStrategy),
+            Box::new(ShelfStrategy),
+            Box::new(SkylineStrategy),
+        ]
+    }
+
+    fn overlap(a: &AllocationRect, b: &AllocationRect) -> bool {
+        a.min.x < b.max.x && b.min.x < a.max.x && a.min.y < b.max.y && b.min.y < a.max.y
+    }
+
+    #[test]
+    fn allocations_stay_in_the_page_and_do_not_overlap() {
+        for strategy in strategies() {
+            let mut allocator = strategy.create_allocator([128, 128], 1);
+            let mut live: Vec<PageAllocation> = Vec::new();
+            for i in 0..200u32 {
+                // free every third allocation to exercise reuse
+                if i % 3 == 2 {
+                    let freed = live.remove((i as usize * 7) % live.len());
+                    allocator.deallocate(freed.id);
+                }
+                let size = [4 + i * 13 % 21, 3 + i * 7 % 17];
+                let Some(allocation) = allocator.allocate(size) else {
+                    continue;
+                };
+                let rectangle = allocation.rectangle;
+                assert!(rectangle.width() as u32 >= size[0], "{strategy:?}");
+                assert!(rectangle.height() as u32 >= size[1], "{strategy:?}");
+                assert!(rectangle.min.x >= 0 && rectangle.min.y >= 0, "{strategy:?}");
+                assert!(
+                    rectangle.max.x <= 128 && rectangle.max.y <= 128,
+                    "{strategy:?}"
+                );
+                for other in &live {
+                    assert!(
+                        !overlap(&rectangle, &other.rectangle),
+                        "{strategy:?}: {rectangle:?} overlaps {:?}",
+                        other.rectangle
+                    );
+                }
+                live.push(allocation);
+            }
+        }
+    }
+
+    #[test]
+    fn freeing_everything_restores_the_whole_page() {
+        for strategy in strategies() {
+            let mut allocator = strategy.create_allocator([64, 64], 1);
+            let ids: Vec<_> = (0..20)
+                .filter_map(|i| allocator.allocate([5 + i % 4, 6 + i % 5]))
+                .map(|allocation| allocation.id)
+                .collect();
+            assert!(allocator.free_space().area < 64 * 64, "{strategy:?}");
+            for id in ids {
+                allocator.deallocate(id);
+            }
+            assert_eq!(allocator.free_space().largest_area, 64 * 64, "{strategy:?}");
+            assert!(allocator.allocate([64, 64]).is_some(), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn allocations_snap_to_blocks() {
+        for strategy in strategies() {
+            let mut allocator = strategy.create_allocator([64, 64], 4);
+            for size in [[5, 3], [1, 1], [13, 7], [4, 9]] {
+                let rectangle = allocator.allocate(size).unwrap().rectangle;
+                assert_eq!(rectangle.min.x % 4, 0, "{strategy:?}: {rectangle:?}");
+                assert_eq!(rectangle.min.y % 4, 0, "{strategy:?}: {rectangle:?}");
+                assert_eq!(rectangle.width() % 4, 0, "{strategy:?}: {rectangle:?}");
+                assert_eq!(rectangle.height() % 4, 0, "{strategy:?}: {rectangle:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn shelves_pack_equal_heights_in_rows() {
+        let mut allocator = ShelfStrategy.create_allocator([64, 64], 1);
+        let rectangles: Vec<_> = (0..8)
+            .map(|_| allocator.allocate([16, 10]).unwrap().rectangle)
+            .collect();
+        assert!(rectangles[..4].iter().all(|r| r.min.y == 0));
+        assert!(rectangles[4..].iter().all(|r| r.min.y == 10));
+
+        // a much shorter item gets a shelf of its own
+        assert_eq!(allocator.allocate([16, 4]).unwrap().rectangle.min.y, 20);
+    }
+
+    #[test]
+    fn skyline_lowers_when_top_allocations_are_freed() {
+        let mut allocator = SkylineStrategy.create_allocator([32, 32], 1);
+        let bottom = allocator.allocate([32, 8]).unwrap();
+        let top = allocator.allocate([16, 8]).unwrap();
+        assert_eq!(top.rectangle.min.y, 8);
+
+        // freeing below another allocation keeps the skyline up there
+        allocator.deallocate(bottom.id);
+        let wide = allocator.allocate([32, 4]).unwrap();
+        assert_eq!(wide.rectangle.min.y, 16);
+
+        allocator.deallocate(wide.id);
+        allocator.deallocate(top.id);
+        assert_eq!(allocator.allocate([32, 4]).unwrap().rectangle.min.y, 0);
+    }
+
+    #[test]
+    fn fragmentation_metrics() {
+        let metrics = AtlasFragmentation {
+            capacity: 100,
+            allocated: 40,
+            free: FreeSpace {
+                area: 40,
+                largest_area: 30,
+                rectangles: 2,
+            },
+        };
+        assert!((metrics.fragmentation() - 0.25).abs() < 1e-6);
+        assert!((metrics.waste() - 0.2).abs() < 1e-6);
+        assert_eq!(AtlasFragmentation::default().fragmentation(), 0.0);
+    }
+}
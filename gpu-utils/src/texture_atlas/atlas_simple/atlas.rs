@@ -1,18 +1,48 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 
+use guillotiere::euclid;
 use guillotiere::euclid::Box2D;
-use guillotiere::{AllocId, AtlasAllocator, Size, euclid};
 use log::{trace, warn};
 use parking_lot::{Mutex, RwLock};
 use thiserror::Error;
 use uuid::Uuid;
 
+use super::allocator::{
+    AllocationId, AllocationStrategy, AtlasFragmentation, FreeSpace, GuillotineStrategy,
+    PageAllocator,
+};
 use crate::device_loss_recoverable::DeviceLossRecoverable;
+use crate::memory::{self, AtlasMemory};
+use crate::upload::UploadRing;
 
 mod viewport_clear;
 use viewport_clear::ViewportClear;
 
+/// What the margin around a region holds, which linear filtering samples at the region's
+/// edges.
+///
+/// Block-compressed regions cannot be rendered to and keep zeroed margins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarginFill {
+    /// The allocation is cleared to this color before it is rendered to. Choose the
+    /// background the region is drawn over to avoid dark halos around content on light
+    /// themes.
+    Color([f32; 4]),
+    /// The border texels of the region are replicated into the margin, like clamp-to-edge
+    /// sampling. [`AtlasRegion::write_data`] does this on upload; after rendering, call
+    /// [`AtlasRegion::extend_margin`].
+    Extend,
+}
+
+/// Transparent black.
+impl Default for MarginFill {
+    fn default() -> Self {
+        Self::Color([0.0; 4])
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AtlasRegion {
     inner: Arc<RegionData>,
@@ -32,6 +62,37 @@ struct RegionData {
     usable_size: [u32; 2],     // size of the usable texture area excluding margins
     atlas_size: [u32; 2],      // size of the atlas when the texture was allocated
     format: wgpu::TextureFormat, // format of the texture
+    margin_fill: MarginFill,
+    // atlas generation the current location belongs to
+    generation: AtomicU64,
+}
+
+/// Two regions are equal when they refer to the same allocation.
+impl PartialEq for AtlasRegion {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for AtlasRegion {}
+
+/// A reference to an [`AtlasRegion`] that does not keep its allocation alive.
+///
+/// Lets caches track what was drawn into the atlas without delaying deallocation.
+#[derive(Debug, Clone)]
+pub struct WeakAtlasRegion {
+    inner: Weak<RegionData>,
+}
+
+impl WeakAtlasRegion {
+    /// Whether the region is still allocated.
+    pub fn is_alive(&self) -> bool {
+        self.inner.strong_count() > 0
+    }
+
+    pub fn upgrade(&self) -> Option<AtlasRegion> {
+        self.inner.upgrade().map(|inner| AtlasRegion { inner })
+    }
 }
 
 impl std::fmt::Debug for RegionData {
@@ -42,6 +103,8 @@ impl std::fmt::Debug for RegionData {
             .field("texture_size", &self.usable_size)
             .field("atlas_size", &self.atlas_size)
             .field("format", &self.format)
+            .field("margin_fill", &self.margin_fill)
+            .field("generation", &self.generation.load(Ordering::Relaxed))
             .finish()
     }
 }
@@ -49,6 +112,13 @@ impl std::fmt::Debug for RegionData {
 /// Public API to interact with a texture.
 /// User code should not need to know about its id, location, or atlas.
 impl AtlasRegion {
+    /// A handle that observes this region without keeping it allocated.
+    pub fn downgrade(&self) -> WeakAtlasRegion {
+        WeakAtlasRegion {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
     pub fn atlas_id(&self) -> TextureAtlasId {
         trace!(
             "AtlasRegion::atlas_id called for region={:?}",
@@ -57,6 +127,25 @@ impl AtlasRegion {
         self.inner.atlas_id
     }
 
+    /// The atlas generation this region's location was assigned in.
+    ///
+    /// Cached UVs or positions derived from this region are only meaningful while
+    /// this equals [`TextureAtlas::generation`].
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns `false` when the atlas was dropped, or its content was discarded or moved
+    /// (e.g. by device-loss recovery) since this region was placed.
+    ///
+    /// This is a cheap check (no locks) intended to be called on every frame by render caches.
+    pub fn is_valid(&self) -> bool {
+        self.inner
+            .atlas
+            .upgrade()
+            .is_some_and(|atlas| atlas.generation() == self.generation())
+    }
+
     pub fn position_in_atlas(&self) -> Result<(u32, Box2D<f32, euclid::UnknownUnit>), RegionError> {
         trace!(
             "AtlasRegion::position_in_atlas: querying region={:?}",
@@ -95,6 +184,10 @@ impl AtlasRegion {
         self.inner.format
     }
 
+    pub fn margin_fill(&self) -> MarginFill {
+        self.inner.margin_fill
+    }
+
     pub fn atlas_pointer(&self) -> Option<usize> {
         self.inner
             .atlas
@@ -137,6 +230,31 @@ impl AtlasRegion {
     }
 
     pub fn write_data(&self, queue: &wgpu::Queue, data: &[u8]) -> Result<(), RegionError> {
+        let tight_row_pitch = self.row_pitch()?;
+        self.write_data_with_row_pitch(queue, data, tight_row_pitch)
+    }
+
+    /// Bytes in one tightly packed row of blocks of the region.
+    ///
+    /// For block-compressed formats a row covers a whole block row (e.g. 4 texel rows for BCn).
+    pub fn row_pitch(&self) -> Result<u32, RegionError> {
+        let bytes_per_block = self
+            .inner
+            .format
+            .block_copy_size(None)
+            .ok_or(RegionError::InvalidFormatBlockCopySize)?;
+        let (block_width, _) = self.inner.format.block_dimensions();
+        Ok(self.inner.usable_size[0].div_ceil(block_width) * bytes_per_block)
+    }
+
+    /// Like [`write_data`](Self::write_data), for data whose rows of blocks are
+    /// `bytes_per_row` apart. The last row does not need to be padded.
+    pub fn write_data_with_row_pitch(
+        &self,
+        queue: &wgpu::Queue,
+        data: &[u8],
+        bytes_per_row: u32,
+    ) -> Result<(), RegionError> {
         trace!(
             "AtlasRegion::write_data: uploading {} bytes to region={:?}",
             data.len(),
@@ -144,13 +262,33 @@ impl AtlasRegion {
         );
         // Check data consistency
         // todo: `block_copy_size()` may return deferent size from the actual size.
-        let bytes_per_pixel = self
+        let bytes_per_block = self
             .inner
             .format
             .block_copy_size(None)
             .ok_or(RegionError::InvalidFormatBlockCopySize)?;
-        let expected_size = self.inner.usable_size[0] * self.inner.usable_size[1] * bytes_per_pixel;
-        if data.len() as u32 != expected_size {
+        // compressed data covers whole blocks, including the padding past the usable size
+        let (block_width, block_height) = self.inner.format.block_dimensions();
+        let blocks = [
+            self.inner.usable_size[0].div_ceil(block_width),
+            self.inner.usable_size[1].div_ceil(block_height),
+        ];
+        let tight_row_pitch = blocks[0] * bytes_per_block;
+        if bytes_per_row < tight_row_pitch || !bytes_per_row.is_multiple_of(bytes_per_block) {
+            warn!(
+                "AtlasRegion::write_data: invalid row pitch {bytes_per_row} (at least {tight_row_pitch}, multiple of {bytes_per_block})"
+            );
+            return Err(RegionError::InvalidRowPitch {
+                bytes_per_row,
+                min: tight_row_pitch,
+                bytes_per_block,
+            });
+        }
+        // the last row may omit its padding
+        let expected_size = bytes_per_row * blocks[1].saturating_sub(1) + tight_row_pitch;
+        let size_matches =
+            (expected_size..=bytes_per_row * blocks[1]).contains(&(data.len() as u32));
+        if !size_matches {
             warn!(
                 "AtlasRegion::write_data: data size mismatch (expected {} bytes, got {})",
                 expected_size,
@@ -174,15 +312,63 @@ impl AtlasRegion {
             return Err(RegionError::TextureNotFoundInAtlas);
         };
 
-        let bytes_per_row = self.inner.usable_size[0] * bytes_per_pixel;
-
-        let origin = wgpu::Origin3d {
-            x: location.usable_bounds.min.x as u32,
-            y: location.usable_bounds.min.y as u32,
-            z: location.page_index,
+        let ring = atlas.upload_ring_or_init(queue);
+        let margin = location.margin;
+        let fills_margin = margin > 0 && !self.inner.format.is_compressed();
+        let extended;
+        let (data, bytes_per_row, origin, size) = match self.inner.margin_fill {
+            MarginFill::Extend if fills_margin => {
+                // uncompressed blocks are single texels
+                extended = extend_rows(
+                    data,
+                    bytes_per_row,
+                    self.inner.usable_size,
+                    bytes_per_block,
+                    margin,
+                );
+                let [width, height] = self.inner.usable_size;
+                (
+                    extended.as_slice(),
+                    (width + margin * 2) * bytes_per_block,
+                    wgpu::Origin3d {
+                        x: location.usable_bounds.min.x as u32 - margin,
+                        y: location.usable_bounds.min.y as u32 - margin,
+                        z: location.page_index,
+                    },
+                    [width + margin * 2, height + margin * 2],
+                )
+            }
+            fill => {
+                // uploads leave default margins alone, so only other colors need a clear
+                if let MarginFill::Color(color) = fill
+                    && fills_margin
+                    && fill != MarginFill::default()
+                {
+                    let mut encoder =
+                        atlas
+                            .device()
+                            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                                label: Some("Texture Atlas Margin Clear Encoder"),
+                            });
+                    atlas.clear_allocation(&mut encoder, &location, color);
+                    // after the uploads staged so far and before this one
+                    ring.flush();
+                    queue.submit(Some(encoder.finish()));
+                }
+                (
+                    data,
+                    bytes_per_row,
+                    wgpu::Origin3d {
+                        x: location.usable_bounds.min.x as u32,
+                        y: location.usable_bounds.min.y as u32,
+                        z: location.page_index,
+                    },
+                    [blocks[0] * block_width, blocks[1] * block_height],
+                )
+            }
         };
 
-        queue.write_texture(
+        ring.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture: &texture,
                 mip_level: 0,
@@ -196,23 +382,173 @@ impl AtlasRegion {
                 rows_per_image: None,
             },
             wgpu::Extent3d {
-                width: self.inner.usable_size[0],
-                height: self.inner.usable_size[1],
+                width: size[0],
+                height: size[1],
                 depth_or_array_layers: 1,
             },
         );
 
-        trace!("AtlasRegion::write_data: upload completed");
+        trace!("AtlasRegion::write_data: upload staged");
 
         Ok(())
     }
 
-    pub fn read_data(&self) -> Result<(), RegionError> {
-        todo!()
+    /// Reads the texels of the region back from the GPU, in tightly packed rows.
+    ///
+    /// Blocks until the work submitted to `queue` so far has finished, so this suits occasional
+    /// lookups such as building a hit mask, not per-frame use. Block-compressed formats are
+    /// not supported.
+    pub fn read_data(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<u8>, RegionError> {
+        if self.inner.format.is_compressed() {
+            return Err(RegionError::InvalidFormatBlockCopySize);
+        }
+        let tight_row_pitch = self.row_pitch()?;
+        let [width, height] = self.inner.usable_size;
+
+        let Some(atlas) = self.inner.atlas.upgrade() else {
+            warn!("AtlasRegion::read_data: atlas dropped");
+            return Err(RegionError::AtlasGone);
+        };
+        let Some(location) = atlas.get_location(self.inner.region_id) else {
+            warn!("AtlasRegion::read_data: region not found in atlas");
+            return Err(RegionError::TextureNotFoundInAtlas);
+        };
+
+        // staged uploads to the region are copied first
+        atlas.flush_uploads();
+
+        // buffer rows must be aligned for the copy
+        let padded_row_pitch = tight_row_pitch.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture Atlas Readback Buffer"),
+            size: u64::from(padded_row_pitch) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Texture Atlas Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &atlas.texture(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: location.usable_bounds.min.x as u32,
+                    y: location.usable_bounds.min.y as u32,
+                    z: location.page_index,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_pitch),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::Wait)
+            .map_err(|e| RegionError::ReadFailed(e.to_string()))?;
+        receiver
+            .recv()
+            .map_err(|e| RegionError::ReadFailed(e.to_string()))?
+            .map_err(|e| RegionError::ReadFailed(e.to_string()))?;
+
+        let data = {
+            let mapped = slice.get_mapped_range();
+            mapped
+                .chunks(padded_row_pitch as usize)
+                .flat_map(|row| &row[..tight_row_pitch as usize])
+                .copied()
+                .collect()
+        };
+        buffer.unmap();
+
+        trace!("AtlasRegion::read_data: read {width}x{height} texels");
+        Ok(data)
     }
 
-    pub fn copy_from_texture(&self) -> Result<(), RegionError> {
-        todo!()
+    /// Records a copy of the top-left `texture_size()` texels of `source` into this region.
+    ///
+    /// `source` must have the region's format and the `COPY_SRC` usage.
+    pub fn copy_from_texture(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::Texture,
+    ) -> Result<(), RegionError> {
+        let [width, height] = self.inner.usable_size;
+        if source.format() != self.inner.format {
+            return Err(RegionError::DataConsistencyError(format!(
+                "Source format({:?}) does not match region format({:?})",
+                source.format(),
+                self.inner.format
+            )));
+        }
+        if source.width() < width || source.height() < height {
+            return Err(RegionError::DataConsistencyError(format!(
+                "Source size({}x{}) is smaller than region size({}x{})",
+                source.width(),
+                source.height(),
+                width,
+                height
+            )));
+        }
+
+        let Some(atlas) = self.inner.atlas.upgrade() else {
+            warn!("AtlasRegion::copy_from_texture: atlas dropped");
+            return Err(RegionError::AtlasGone);
+        };
+        let Some(location) = atlas.get_location(self.inner.region_id) else {
+            warn!("AtlasRegion::copy_from_texture: region not found in atlas");
+            return Err(RegionError::TextureNotFoundInAtlas);
+        };
+        // staged uploads must not overwrite the copy
+        atlas.flush_uploads();
+
+        encoder.copy_texture_to_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: source,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture: &atlas.texture(),
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: location.usable_bounds.min.x as u32,
+                    y: location.usable_bounds.min.y as u32,
+                    z: location.page_index,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
     }
 
     pub fn copy_to_texture(&self) -> Result<(), RegionError> {
@@ -253,6 +589,10 @@ impl AtlasRegion {
         &'a self,
         encoder: &'a mut wgpu::CommandEncoder,
     ) -> Result<wgpu::RenderPass<'a>, RegionError> {
+        if self.inner.format.is_compressed() {
+            return Err(RegionError::NotRenderable(self.inner.format));
+        }
+
         // Get the texture location in the atlas
         let Some(atlas) = self.inner.atlas.upgrade() else {
             return Err(RegionError::AtlasGone);
@@ -261,54 +601,18 @@ impl AtlasRegion {
             return Err(RegionError::TextureNotFoundInAtlas);
         };
 
-        // Clear the allocated region (including the margin) before exposing the render pass to users.
-        let view = atlas.layer_texture_view(location.page_index as usize);
-        let allocation_bounds = location.allocation_bounds();
-        let allocation_width = (allocation_bounds.max.x - allocation_bounds.min.x) as u32;
-        let allocation_height = (allocation_bounds.max.y - allocation_bounds.min.y) as u32;
-        debug_assert!(allocation_width > 0 && allocation_height > 0);
-        debug_assert!(allocation_bounds.min.x >= 0);
-        debug_assert!(allocation_bounds.min.y >= 0);
+        // staged uploads must not overwrite what is rendered
+        atlas.flush_uploads();
 
-        {
-            let mut clear_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Texture Atlas Margin Clear Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: wgpu::StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
-
-            clear_pass.set_viewport(
-                allocation_bounds.min.x as f32,
-                allocation_bounds.min.y as f32,
-                allocation_width as f32,
-                allocation_height as f32,
-                0.0,
-                1.0,
-            );
-            clear_pass.set_scissor_rect(
-                allocation_bounds.min.x as u32,
-                allocation_bounds.min.y as u32,
-                allocation_width,
-                allocation_height,
-            );
-            atlas.viewport_clear.render(
-                &atlas.device(),
-                &mut clear_pass,
-                atlas.format(),
-                [0.0, 0.0, 0.0, 0.0],
-            );
-        }
+        // Clear the allocated region (including the margin) before exposing the render pass to users.
+        let color = match self.inner.margin_fill {
+            MarginFill::Color(color) => color,
+            // the margin is extended from the content once it is rendered
+            MarginFill::Extend => [0.0; 4],
+        };
+        atlas.clear_allocation(encoder, &location, color);
 
+        let view = atlas.layer_texture_view(location.page_index as usize);
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Texture Atlas Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -338,6 +642,98 @@ impl AtlasRegion {
         Ok(render_pass)
     }
 
+    /// Records copies that replicate the border texels of the region into its margin.
+    ///
+    /// Call it once the render pass of [`begin_render_pass`](Self::begin_render_pass) ended.
+    /// Does nothing unless the region was allocated with [`MarginFill::Extend`].
+    pub fn extend_margin(&self, encoder: &mut wgpu::CommandEncoder) -> Result<(), RegionError> {
+        if self.inner.margin_fill != MarginFill::Extend || self.inner.format.is_compressed() {
+            return Ok(());
+        }
+        let Some(atlas) = self.inner.atlas.upgrade() else {
+            return Err(RegionError::AtlasGone);
+        };
+        let Some(location) = atlas.get_location(self.inner.region_id) else {
+            return Err(RegionError::TextureNotFoundInAtlas);
+        };
+        let margin = location.margin;
+        if margin == 0 {
+            return Ok(());
+        }
+        // the margin is extended from the uploaded content too
+        atlas.flush_uploads();
+
+        let texture = atlas.texture();
+        let [width, height] = self.inner.usable_size;
+        let [x, y] = [
+            location.usable_bounds.min.x as u32,
+            location.usable_bounds.min.y as u32,
+        ];
+        let page = location.page_index;
+        // a texture cannot be copied within one layer, so the edges take a detour through
+        // a scratch texture: first the left and right columns, then the top and bottom rows
+        // including the extended columns
+        let device = atlas.device();
+        let columns = scratch_texture(&device, self.inner.format, [2, height]);
+        copy_rect(
+            encoder,
+            (&texture, [x, y, page]),
+            (&columns, [0, 0, 0]),
+            [1, height],
+        );
+        copy_rect(
+            encoder,
+            (&texture, [x + width - 1, y, page]),
+            (&columns, [1, 0, 0]),
+            [1, height],
+        );
+        for i in 0..margin {
+            copy_rect(
+                encoder,
+                (&columns, [0, 0, 0]),
+                (&texture, [x - 1 - i, y, page]),
+                [1, height],
+            );
+            copy_rect(
+                encoder,
+                (&columns, [1, 0, 0]),
+                (&texture, [x + width + i, y, page]),
+                [1, height],
+            );
+        }
+
+        let row_width = width + margin * 2;
+        let rows = scratch_texture(&device, self.inner.format, [row_width, 2]);
+        copy_rect(
+            encoder,
+            (&texture, [x - margin, y, page]),
+            (&rows, [0, 0, 0]),
+            [row_width, 1],
+        );
+        copy_rect(
+            encoder,
+            (&texture, [x - margin, y + height - 1, page]),
+            (&rows, [0, 1, 0]),
+            [row_width, 1],
+        );
+        for i in 0..margin {
+            copy_rect(
+                encoder,
+                (&rows, [0, 0, 0]),
+                (&texture, [x - margin, y - 1 - i, page]),
+                [row_width, 1],
+            );
+            copy_rect(
+                encoder,
+                (&rows, [0, 1, 0]),
+                (&texture, [x - margin, y + height + i, page]),
+                [row_width, 1],
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn uv(&self) -> Result<Box2D<f32, euclid::UnknownUnit>, RegionError> {
         // Get the texture location in the atlas
         let Some(atlas) = self.inner.atlas.upgrade() else {
@@ -350,6 +746,25 @@ impl AtlasRegion {
         Ok(location.usable_uv_bounds)
     }
 
+    /// A 2D view of the atlas page holding the region, with the region's UV bounds on it.
+    ///
+    /// Lets renderers that bind a single 2D texture sample the region.
+    pub fn page_view(
+        &self,
+    ) -> Result<(wgpu::TextureView, Box2D<f32, euclid::UnknownUnit>), RegionError> {
+        let Some(atlas) = self.inner.atlas.upgrade() else {
+            return Err(RegionError::AtlasGone);
+        };
+        let Some(location) = atlas.get_location(self.inner.region_id) else {
+            return Err(RegionError::TextureNotFoundInAtlas);
+        };
+
+        Ok((
+            atlas.layer_texture_view(location.page_index as usize),
+            location.usable_uv_bounds,
+        ))
+    }
+
     // pub fn with_data<Init, F>(&self, init: Init, f: F) -> Result<(), TextureError>
     // where
     //     Init: FnOnce(&Texture) -> Result<(), TextureError>,
@@ -392,24 +807,21 @@ struct RegionLocation {
 impl RegionLocation {
     fn new(
         allocation_bounds: Box2D<i32, euclid::UnknownUnit>,
+        usable_size: [u32; 2],
         atlas_size: [u32; 2],
         page_index: usize,
         margin: u32,
     ) -> Self {
-        let bounds = if margin == 0 {
-            allocation_bounds
-        } else {
-            euclid::Box2D::new(
-                euclid::Point2D::new(
-                    allocation_bounds.min.x + margin as i32,
-                    allocation_bounds.min.y + margin as i32,
-                ),
-                euclid::Point2D::new(
-                    allocation_bounds.max.x - margin as i32,
-                    allocation_bounds.max.y - margin as i32,
-                ),
-            )
-        };
+        // the allocation may be larger than the usable area plus margins when the allocator
+        // rounds sizes up to whole compression blocks
+        let min = euclid::Point2D::new(
+            allocation_bounds.min.x + margin as i32,
+            allocation_bounds.min.y + margin as i32,
+        );
+        let bounds = euclid::Box2D::new(
+            min,
+            euclid::Point2D::new(min.x + usable_size[0] as i32, min.y + usable_size[1] as i32),
+        );
 
         debug_assert!(bounds.min.x >= allocation_bounds.min.x);
         debug_assert!(bounds.min.y >= allocation_bounds.min.y);
@@ -479,6 +891,11 @@ pub struct TextureAtlas {
     device: RwLock<wgpu::Device>,
     viewport_clear: ViewportClear,
     margin: u32,
+    strategy: Arc<dyn AllocationStrategy>,
+    /// Incremented whenever existing regions lose their location or content.
+    generation: AtomicU64,
+    /// Stages `AtlasRegion::write_data`. Created on the first write unless one was set.
+    upload: RwLock<Option<Arc<UploadRing>>>,
     weak_self: Weak<Self>,
 }
 
@@ -490,9 +907,9 @@ struct TextureAtlasResources {
 }
 
 struct TextureAtlasState {
-    allocators: Vec<AtlasAllocator>,
+    allocators: Vec<Box<dyn PageAllocator>>,
     texture_id_to_location: HashMap<RegionId, RegionLocation>,
-    texture_id_to_alloc_id: HashMap<RegionId, AllocId>,
+    texture_id_to_alloc_id: HashMap<RegionId, AllocationId>,
     usage: usize,
 }
 
@@ -506,14 +923,28 @@ impl TextureAtlas {
         format: wgpu::TextureFormat,
         margin: u32,
     ) -> Arc<Self> {
+        Self::with_strategy(device, size, format, margin, Arc::new(GuillotineStrategy))
+    }
+
+    /// Creates an atlas that packs its pages with `strategy`, see
+    /// [`allocator`](super::allocator).
+    pub fn with_strategy(
+        device: &wgpu::Device,
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+        margin: u32,
+        strategy: Arc<dyn AllocationStrategy>,
+    ) -> Arc<Self> {
+        // keep the usable area of compressed regions on block boundaries
+        let margin = margin.next_multiple_of(block_snap(format));
+
         let (texture, texture_view, layer_texture_views) =
             Self::create_texture_and_view(device, format, size);
 
         // Initialize the state with an empty allocator and allocation map.
         let state = TextureAtlasState {
             allocators: (0..size.depth_or_array_layers)
-                .map(|_| Size::new(size.width as i32, size.height as i32))
-                .map(AtlasAllocator::new)
+                .map(|_| Self::create_allocator(&*strategy, format, size))
                 .collect(),
             texture_id_to_location: HashMap::new(),
             texture_id_to_alloc_id: HashMap::new(),
@@ -527,7 +958,7 @@ impl TextureAtlas {
             size,
         };
 
-        Arc::new_cyclic(|weak_self| Self {
+        let atlas = Arc::new_cyclic(|weak_self| Self {
             id: TextureAtlasId::new(),
             format,
             state: Mutex::new(state),
@@ -535,13 +966,28 @@ impl TextureAtlas {
             device: RwLock::new(device.clone()),
             viewport_clear: ViewportClear::default(),
             margin,
+            strategy,
+            generation: AtomicU64::new(0),
+            upload: RwLock::new(None),
             weak_self: weak_self.clone(),
-        })
+        });
+        memory::register_atlas(atlas.weak_self.clone());
+        atlas
+    }
+}
+
+impl AtlasMemory for TextureAtlas {
+    fn texture_memory(&self, out: &mut Vec<(wgpu::TextureFormat, u64, u64)>) {
+        out.push((
+            self.format,
+            memory::texture_bytes(self.format, self.size()),
+            memory::texel_bytes(self.format, self.usage() as u64),
+        ));
     }
 }
 
 impl DeviceLossRecoverable for TextureAtlas {
-    fn recover(&self, device: &wgpu::Device, _: &wgpu::Queue) {
+    fn recover(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
         let format = self.format;
         let size = self.size();
         let id = self.id;
@@ -552,8 +998,7 @@ impl DeviceLossRecoverable for TextureAtlas {
         // Initialize the state with an empty allocator and allocation map.
         let state = TextureAtlasState {
             allocators: (0..size.depth_or_array_layers)
-                .map(|_| Size::new(size.width as i32, size.height as i32))
-                .map(AtlasAllocator::new)
+                .map(|_| Self::create_allocator(&*self.strategy, format, size))
                 .collect(),
             texture_id_to_location: HashMap::new(),
             texture_id_to_alloc_id: HashMap::new(),
@@ -575,9 +1020,16 @@ impl DeviceLossRecoverable for TextureAtlas {
 
         *self.device.write() = device.clone();
         self.viewport_clear.reset();
+        // staged uploads target the lost textures; recovering a shared ring again is harmless
+        if let Some(ring) = self.upload_ring() {
+            ring.recover(device, queue);
+        }
+
+        // every region allocated so far has lost its location and content
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
 
         trace!(
-            "TextureAtlas::recover: recovered atlas id={id:?} with size={size:?} and format={format:?}"
+            "TextureAtlas::recover: recovered atlas id={id:?} with size={size:?} and format={format:?} generation={generation}"
         );
     }
 }
@@ -595,6 +1047,34 @@ impl TextureAtlas {
         self.device.read().clone()
     }
 
+    /// Stages the uploads of the regions through `ring`, e.g. to share it with other atlases
+    /// and buffers. Uploads staged in the previous ring are submitted first.
+    pub fn set_upload_ring(&self, ring: Arc<UploadRing>) {
+        if let Some(previous) = self.upload.write().replace(ring) {
+            previous.flush();
+        }
+    }
+
+    /// The ring the uploads of the regions are staged in, if one was set or created yet.
+    pub fn upload_ring(&self) -> Option<Arc<UploadRing>> {
+        self.upload.read().clone()
+    }
+
+    /// Submits the staged uploads. The atlas does this before it renders to or copies its
+    /// textures; call it before submitting other work that samples them.
+    pub fn flush_uploads(&self) {
+        if let Some(ring) = self.upload_ring() {
+            ring.flush();
+        }
+    }
+
+    fn upload_ring_or_init(&self, queue: &wgpu::Queue) -> Arc<UploadRing> {
+        self.upload
+            .write()
+            .get_or_insert_with(|| Arc::new(UploadRing::new(&self.device(), queue)))
+            .clone()
+    }
+
     pub fn margin(&self) -> u32 {
         self.margin
     }
@@ -610,6 +1090,32 @@ impl TextureAtlas {
         self.state.lock().usage
     }
 
+    /// The strategy the pages are packed with.
+    pub fn strategy(&self) -> &Arc<dyn AllocationStrategy> {
+        &self.strategy
+    }
+
+    /// How well the live regions are packed, e.g. to compare allocation strategies on the
+    /// same content.
+    pub fn fragmentation(&self) -> AtlasFragmentation {
+        let capacity = self.capacity() as u64;
+        let state = self.state.lock();
+        AtlasFragmentation {
+            capacity,
+            allocated: state.usage as u64,
+            free: state
+                .allocators
+                .iter()
+                .map(|allocator| allocator.free_space())
+                .fold(FreeSpace::default(), FreeSpace::merge),
+        }
+    }
+
+    /// Monotonic counter incremented whenever previously allocated regions become stale.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
     // todo: we can optimize this performance.
     pub fn max_allocation_size(&self) -> [u32; 2] {
         let mut max_size = [0; 2];
@@ -632,6 +1138,17 @@ impl TextureAtlas {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         requested_size: [u32; 2],
+    ) -> Result<AtlasRegion, TextureAtlasError> {
+        self.allocate_with_fill(device, queue, requested_size, MarginFill::default())
+    }
+
+    /// Allocate a texture in the atlas whose margin holds `margin_fill`.
+    pub fn allocate_with_fill(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        requested_size: [u32; 2],
+        margin_fill: MarginFill,
     ) -> Result<AtlasRegion, TextureAtlasError> {
         // Check if size is smaller than the atlas size
         if requested_size[0] == 0 || requested_size[1] == 0 {
@@ -657,16 +1174,20 @@ impl TextureAtlas {
                     requested: requested_size,
                 })?;
 
-        let allocation_width = requested_size[0].checked_add(doubled_margin).ok_or(
-            TextureAtlasError::AllocationFailedInvalidSize {
+        // compressed formats are written in whole blocks
+        let (block_width, block_height) = self.format.block_dimensions();
+        let allocation_width = requested_size[0]
+            .next_multiple_of(block_width)
+            .checked_add(doubled_margin)
+            .ok_or(TextureAtlasError::AllocationFailedInvalidSize {
                 requested: requested_size,
-            },
-        )?;
-        let allocation_height = requested_size[1].checked_add(doubled_margin).ok_or(
-            TextureAtlasError::AllocationFailedInvalidSize {
+            })?;
+        let allocation_height = requested_size[1]
+            .next_multiple_of(block_height)
+            .checked_add(doubled_margin)
+            .ok_or(TextureAtlasError::AllocationFailedInvalidSize {
                 requested: requested_size,
-            },
-        )?;
+            })?;
 
         if allocation_width > i32::MAX as u32 || allocation_height > i32::MAX as u32 {
             return Err(TextureAtlasError::AllocationFailedInvalidSize {
@@ -674,19 +1195,27 @@ impl TextureAtlas {
             });
         }
 
-        let allocation_size = Size::new(allocation_width as i32, allocation_height as i32);
+        let allocation_size = [allocation_width, allocation_height];
 
-        if let Some(region) =
-            self.try_allocate(allocation_size, [atlas_size.width, atlas_size.height])
-        {
+        if let Some(region) = self.try_allocate(
+            allocation_size,
+            requested_size,
+            [atlas_size.width, atlas_size.height],
+            margin_fill,
+        ) {
             return Ok(region);
         }
 
         self.add_one_page(device, queue);
 
         let updated_size = self.size();
-        self.try_allocate(allocation_size, [updated_size.width, updated_size.height])
-            .ok_or(TextureAtlasError::AllocationFailedNotEnoughSpace)
+        self.try_allocate(
+            allocation_size,
+            requested_size,
+            [updated_size.width, updated_size.height],
+            margin_fill,
+        )
+        .ok_or(TextureAtlasError::AllocationFailedNotEnoughSpace)
     }
 
     /// Deallocate a texture from the atlas.
@@ -715,13 +1244,24 @@ impl TextureAtlas {
         Ok(())
     }
 
-    fn try_allocate(&self, allocation_size: Size, atlas_size: [u32; 2]) -> Option<AtlasRegion> {
+    fn try_allocate(
+        &self,
+        allocation_size: [u32; 2],
+        usable_size: [u32; 2],
+        atlas_size: [u32; 2],
+        margin_fill: MarginFill,
+    ) -> Option<AtlasRegion> {
         let mut state = self.state.lock();
 
         for (page_index, allocator) in state.allocators.iter_mut().enumerate() {
             if let Some(alloc) = allocator.allocate(allocation_size) {
-                let location =
-                    RegionLocation::new(alloc.rectangle, atlas_size, page_index, self.margin);
+                let location = RegionLocation::new(
+                    alloc.rectangle,
+                    usable_size,
+                    atlas_size,
+                    page_index,
+                    self.margin,
+                );
 
                 let texture_id = RegionId {
                     texture_uuid: Uuid::new_v4(),
@@ -740,6 +1280,8 @@ impl TextureAtlas {
                     ],
                     atlas_size,
                     format: self.format,
+                    margin_fill,
+                    generation: AtomicU64::new(self.generation()),
                 };
                 let texture = AtlasRegion {
                     inner: Arc::new(texture_inner),
@@ -760,6 +1302,8 @@ impl TextureAtlas {
 /// Resize the atlas to a new size.
 impl TextureAtlas {
     fn add_one_page(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        // staged uploads target the old texture
+        self.flush_uploads();
         let mut resources = self.resources.write();
         let previous_size = resources.size;
         let new_size = wgpu::Extent3d {
@@ -773,10 +1317,11 @@ impl TextureAtlas {
 
         {
             let mut state = self.state.lock();
-            state.allocators.push(AtlasAllocator::new(Size::new(
-                new_size.width as i32,
-                new_size.height as i32,
-            )));
+            state.allocators.push(Self::create_allocator(
+                &*self.strategy,
+                self.format,
+                new_size,
+            ));
         }
 
         let old_texture = resources.texture.clone();
@@ -809,8 +1354,11 @@ impl TextureAtlas {
 
         // Clear only the newly added layer to ensure it is initialized and transparent.
         // This prevents uninitialized memory in the new layer and keeps existing pages intact.
+        // Compressed pages cannot be rendered to; wgpu zero-initializes them instead.
         let new_layer_index = new_size.depth_or_array_layers - 1;
-        if let Some(view) = new_layer_texture_views.get(new_layer_index as usize) {
+        if let Some(view) = new_layer_texture_views.get(new_layer_index as usize)
+            && !self.format.is_compressed()
+        {
             let _clear_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("TextureAtlas Init New Layer Clear"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -853,10 +1401,74 @@ impl TextureAtlas {
     fn layer_texture_view(&self, index: usize) -> wgpu::TextureView {
         self.resources.read().layer_texture_views[index].clone()
     }
+
+    /// Records a clear of the allocation of `location`, including its margin, to `color`.
+    fn clear_allocation(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        location: &RegionLocation,
+        color: [f32; 4],
+    ) {
+        let view = self.layer_texture_view(location.page_index as usize);
+        let allocation_bounds = location.allocation_bounds();
+        let allocation_width = (allocation_bounds.max.x - allocation_bounds.min.x) as u32;
+        let allocation_height = (allocation_bounds.max.y - allocation_bounds.min.y) as u32;
+        debug_assert!(allocation_width > 0 && allocation_height > 0);
+        debug_assert!(allocation_bounds.min.x >= 0);
+        debug_assert!(allocation_bounds.min.y >= 0);
+
+        let mut clear_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Texture Atlas Margin Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        clear_pass.set_viewport(
+            allocation_bounds.min.x as f32,
+            allocation_bounds.min.y as f32,
+            allocation_width as f32,
+            allocation_height as f32,
+            0.0,
+            1.0,
+        );
+        clear_pass.set_scissor_rect(
+            allocation_bounds.min.x as u32,
+            allocation_bounds.min.y as u32,
+            allocation_width,
+            allocation_height,
+        );
+        self.viewport_clear
+            .render(&self.device(), &mut clear_pass, self.format, color);
+    }
 }
 
 // helper functions
 impl TextureAtlas {
+    /// Whether the atlas holds a block-compressed format. Such atlases cannot be rendered to;
+    /// regions are filled with [`AtlasRegion::write_data`].
+    pub fn is_compressed(&self) -> bool {
+        self.format.is_compressed()
+    }
+
+    fn create_allocator(
+        strategy: &dyn AllocationStrategy,
+        format: wgpu::TextureFormat,
+        page_size: wgpu::Extent3d,
+    ) -> Box<dyn PageAllocator> {
+        // snapping sizes to whole blocks keeps every allocation block-aligned
+        strategy.create_allocator([page_size.width, page_size.height], block_snap(format))
+    }
+
     fn create_texture_and_view(
         device: &wgpu::Device,
         format: wgpu::TextureFormat,
@@ -865,6 +1477,13 @@ impl TextureAtlas {
         let texture_label = format!("texture_atlas_texture_{format:?}");
         let texture_view_label = format!("texture_atlas_texture_view_{format:?}");
 
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC;
+        if !format.is_compressed() {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+
         let texture_descriptor = wgpu::TextureDescriptor {
             label: Some(&texture_label),
             size: page_size,
@@ -872,10 +1491,7 @@ impl TextureAtlas {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::COPY_DST
-                | wgpu::TextureUsages::COPY_SRC,
+            usage,
             view_formats: &[],
         };
         let texture = device.create_texture(&texture_descriptor);
@@ -908,6 +1524,99 @@ impl TextureAtlas {
     }
 }
 
+/// A texture that edges of a region are copied through.
+fn scratch_texture(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    size: [u32; 2],
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("texture_atlas_margin_scratch"),
+        size: wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+/// Records a copy of a `size` rectangle between `(texture, [x, y, layer])` locations.
+fn copy_rect(
+    encoder: &mut wgpu::CommandEncoder,
+    from: (&wgpu::Texture, [u32; 3]),
+    to: (&wgpu::Texture, [u32; 3]),
+    size: [u32; 2],
+) {
+    fn info((texture, [x, y, z]): (&wgpu::Texture, [u32; 3])) -> wgpu::TexelCopyTextureInfo<'_> {
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z },
+            aspect: wgpu::TextureAspect::All,
+        }
+    }
+    encoder.copy_texture_to_texture(
+        info(from),
+        info(to),
+        wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+/// Pads `size` texels of `texel_bytes` each, in rows `bytes_per_row` apart, with `margin`
+/// copies of the border texels on every side. The result is tightly packed.
+fn extend_rows(
+    data: &[u8],
+    bytes_per_row: u32,
+    size: [u32; 2],
+    texel_bytes: u32,
+    margin: u32,
+) -> Vec<u8> {
+    let [width, height] = size.map(|length| length as usize);
+    let [bytes_per_row, texel_bytes, margin] =
+        [bytes_per_row, texel_bytes, margin].map(|value| value as usize);
+    let row_bytes = width * texel_bytes;
+    let padded_row_bytes = row_bytes + margin * texel_bytes * 2;
+
+    let mut rows = Vec::with_capacity(height * padded_row_bytes);
+    for y in 0..height {
+        let row = &data[y * bytes_per_row..y * bytes_per_row + row_bytes];
+        let (first, last) = (&row[..texel_bytes], &row[row_bytes - texel_bytes..]);
+        (0..margin).for_each(|_| rows.extend_from_slice(first));
+        rows.extend_from_slice(row);
+        (0..margin).for_each(|_| rows.extend_from_slice(last));
+    }
+
+    let mut padded = Vec::with_capacity((height + margin * 2) * padded_row_bytes);
+    let (first, last) = (
+        &rows[..padded_row_bytes],
+        &rows[rows.len() - padded_row_bytes..],
+    );
+    (0..margin).for_each(|_| padded.extend_from_slice(first));
+    padded.extend_from_slice(&rows);
+    (0..margin).for_each(|_| padded.extend_from_slice(last));
+    padded
+}
+
+/// The smallest length that is a whole number of blocks of `format` on both axes; 1 for
+/// uncompressed formats.
+fn block_snap(format: wgpu::TextureFormat) -> u32 {
+    fn gcd(a: u32, b: u32) -> u32 {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    let (block_width, block_height) = format.block_dimensions();
+    block_width * block_height / gcd(block_width, block_height)
+}
+
 /// `DeallocationErrorTextureNotFound` only be used in this file.
 struct DeallocationErrorTextureNotFound;
 
@@ -921,6 +1630,18 @@ pub enum RegionError {
     DataConsistencyError(String),
     #[error("Invalid format block copy size.")]
     InvalidFormatBlockCopySize,
+    #[error("Regions of format {0:?} cannot be rendered to.")]
+    NotRenderable(wgpu::TextureFormat),
+    #[error("Reading the region back failed: {0}")]
+    ReadFailed(String),
+    #[error(
+        "Invalid row pitch {bytes_per_row}: must be at least {min} bytes and a multiple of the {bytes_per_block} byte block."
+    )]
+    InvalidRowPitch {
+        bytes_per_row: u32,
+        min: u32,
+        bytes_per_block: u32,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -1159,6 +1880,30 @@ mod tests {
         assert_eq!(atlas.usage(), 0);
     }
 
+    #[tokio::test]
+    async fn weak_region_does_not_keep_allocation_alive() {
+        let (device, queue, atlas) = setup_atlas(
+            wgpu::Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            1,
+        )
+        .await;
+
+        let region = atlas.allocate(&device, &queue, [4, 4]).unwrap();
+        let weak = region.downgrade();
+        assert!(weak.is_alive());
+        assert_eq!(weak.upgrade().unwrap(), region);
+
+        drop(region);
+        assert!(!weak.is_alive());
+        assert!(weak.upgrade().is_none());
+        assert_eq!(atlas.usage(), 0);
+    }
+
     #[tokio::test]
     async fn max_allocation_size_reflects_largest_live_region() {
         let (device, queue, atlas) = setup_atlas(
@@ -1327,6 +2072,69 @@ mod tests {
         assert!(matches!(err, RegionError::DataConsistencyError(_)));
     }
 
+    #[tokio::test]
+    async fn write_data_validates_row_pitch() {
+        let (device, queue, atlas) = setup_atlas(
+            wgpu::Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            0,
+        )
+        .await;
+        let region = atlas.allocate(&device, &queue, [3, 2]).unwrap();
+        assert_eq!(region.row_pitch().unwrap(), 12);
+
+        // rows padded to 16 bytes, with or without padding after the last row
+        region
+            .write_data_with_row_pitch(&queue, &[0u8; 16 + 12], 16)
+            .unwrap();
+        region
+            .write_data_with_row_pitch(&queue, &[0u8; 32], 16)
+            .unwrap();
+
+        for bytes_per_row in [8, 14] {
+            let err = region
+                .write_data_with_row_pitch(&queue, &[0u8; 32], bytes_per_row)
+                .unwrap_err();
+            assert!(matches!(err, RegionError::InvalidRowPitch { min: 12, .. }));
+        }
+        let err = region
+            .write_data_with_row_pitch(&queue, &[0u8; 24], 16)
+            .unwrap_err();
+        assert!(matches!(err, RegionError::DataConsistencyError(_)));
+    }
+
+    #[test]
+    fn compressed_allocations_are_block_aligned() {
+        let format = wgpu::TextureFormat::Bc7RgbaUnormSrgb;
+        let mut allocator = TextureAtlas::create_allocator(
+            &GuillotineStrategy,
+            format,
+            wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        for size in [[5, 3], [1, 1], [13, 7], [4, 9]] {
+            let alloc = allocator.allocate(size).unwrap();
+            let rect = alloc.rectangle;
+            assert_eq!(rect.min.x % 4, 0, "{rect:?}");
+            assert_eq!(rect.min.y % 4, 0, "{rect:?}");
+            assert_eq!(rect.width() % 4, 0, "{rect:?}");
+            assert_eq!(rect.height() % 4, 0, "{rect:?}");
+
+            // the usable area keeps the requested size at a block boundary
+            let location = RegionLocation::new(rect, size, [64, 64], 0, 0);
+            assert_eq!(location.size(), size);
+            assert_eq!(location.usable_bounds.min, rect.min);
+        }
+    }
+
     #[tokio::test]
     async fn write_data_fails_on_invalid_format_block_size() {
         let (device, queue, atlas) = setup_atlas(
@@ -1409,10 +2217,6 @@ mod tests {
             ));
         let region = atlas.allocate(&device, &queue, [2, 2]).unwrap();
 
-        let read = std::panic::catch_unwind(AssertUnwindSafe(|| region.read_data()));
-        assert!(read.is_err());
-        let copy_tex = std::panic::catch_unwind(AssertUnwindSafe(|| region.copy_from_texture()));
-        assert!(copy_tex.is_err());
         let copy_to_tex = std::panic::catch_unwind(AssertUnwindSafe(|| region.copy_to_texture()));
         assert!(copy_to_tex.is_err());
         let copy_buf = std::panic::catch_unwind(AssertUnwindSafe(|| region.copy_from_buffer()));
@@ -1421,15 +2225,12 @@ mod tests {
         assert!(copy_to_buf.is_err());
     }
 
-    // Skeleton for when read/copy APIs return Result<_, RegionError> in the future.
-    // After implementation, remove #[ignore] and assert specific Err variants.
-    #[ignore]
     #[tokio::test]
-    async fn read_and_copy_operations_return_error_when_implemented() {
+    async fn copy_from_texture_checks_format_and_size() {
         let (device, queue, atlas) = setup_atlas(
             wgpu::Extent3d {
-                width: 4,
-                height: 4,
+                width: 8,
+                height: 8,
                 depth_or_array_layers: 1,
             },
             wgpu::TextureFormat::Rgba8Unorm,
@@ -1437,10 +2238,68 @@ mod tests {
         )
         .await;
         let region = atlas.allocate(&device, &queue, [2, 2]).unwrap();
+        let source = |size: u32, format| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("copy source"),
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+        };
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        let err = region
+            .copy_from_texture(&mut encoder, &source(4, wgpu::TextureFormat::Bgra8Unorm))
+            .unwrap_err();
+        assert!(matches!(err, RegionError::DataConsistencyError(_)));
+        let err = region
+            .copy_from_texture(&mut encoder, &source(1, wgpu::TextureFormat::Rgba8Unorm))
+            .unwrap_err();
+        assert!(matches!(err, RegionError::DataConsistencyError(_)));
+
+        region
+            .copy_from_texture(&mut encoder, &source(4, wgpu::TextureFormat::Rgba8Unorm))
+            .unwrap();
+        queue.submit(Some(encoder.finish()));
 
-        // FIXME: After implementation, check concrete Err variants
-        let _ = region; // placate lint
-        // e.g., assert!(matches!(region.read_data(), Err(RegionError::...)));
+        drop(atlas);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let err = region
+            .copy_from_texture(&mut encoder, &source(4, wgpu::TextureFormat::Rgba8Unorm))
+            .unwrap_err();
+        assert!(matches!(err, RegionError::AtlasGone));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a GPU adapter; the noop backend does not execute copies"]
+    async fn read_data_returns_written_texels() {
+        let (_, _, device, queue) = crate::wgpu_utils::real_wgpu()
+            .await
+            .expect("no GPU adapter available");
+        let atlas = TextureAtlas::new(
+            &device,
+            wgpu::Extent3d {
+                width: 8,
+                height: 8,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            1,
+        );
+        // 3 texels of 4 bytes per row, padded to 256 bytes in the readback buffer
+        let region = atlas.allocate(&device, &queue, [3, 2]).unwrap();
+        let data: Vec<u8> = (0..3 * 2 * 4).collect();
+        region.write_data(&queue, &data).unwrap();
+
+        assert_eq!(region.read_data(&device, &queue).unwrap(), data);
     }
 
     #[tokio::test]
@@ -1563,6 +2422,65 @@ mod tests {
         queue.submit(Some(encoder.finish()));
     }
 
+    #[test]
+    fn extend_rows_replicates_border_texels() {
+        // 2x2 texels of one byte, rows 3 bytes apart
+        let data = [1, 2, 0, 3, 4];
+        assert_eq!(
+            extend_rows(&data, 3, [2, 2], 1, 1),
+            [
+                1, 1, 2, 2, //
+                1, 1, 2, 2, //
+                3, 3, 4, 4, //
+                3, 3, 4, 4,
+            ]
+        );
+        // texels of two bytes stay whole
+        assert_eq!(
+            extend_rows(&[1, 2], 2, [1, 1], 2, 2),
+            [1, 2].repeat(25).as_slice()
+        );
+    }
+
+    #[tokio::test]
+    async fn margin_fills_are_applied_on_upload_and_after_rendering() {
+        let (device, queue, atlas) = setup_atlas(
+            wgpu::Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            2,
+        )
+        .await;
+        let extended = atlas
+            .allocate_with_fill(&device, &queue, [3, 2], MarginFill::Extend)
+            .unwrap();
+        let colored = atlas
+            .allocate_with_fill(&device, &queue, [3, 2], MarginFill::Color([1.0; 4]))
+            .unwrap();
+        assert_eq!(extended.margin_fill(), MarginFill::Extend);
+        assert_eq!(
+            atlas
+                .allocate(&device, &queue, [1, 1])
+                .unwrap()
+                .margin_fill(),
+            MarginFill::default()
+        );
+
+        extended.write_data(&queue, &[0; 3 * 2 * 4]).unwrap();
+        if device.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+            colored.write_data(&queue, &[0; 3 * 2 * 4]).unwrap();
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        extended.extend_margin(&mut encoder).unwrap();
+        // a no-op for other fills
+        colored.extend_margin(&mut encoder).unwrap();
+        queue.submit(Some(encoder.finish()));
+    }
+
     // Verify allocation succeeds at equality boundary (requested + 2*margin == atlas_size)
     #[tokio::test]
     async fn allocate_accepts_size_equal_to_atlas_including_margins() {
@@ -1694,6 +2612,35 @@ mod tests {
         assert!(matches!(err, RegionError::TextureNotFoundInAtlas));
     }
 
+    #[tokio::test]
+    async fn recover_bumps_generation_and_invalidates_regions() {
+        let (device, queue, atlas) = setup_atlas(
+            wgpu::Extent3d {
+                width: 8,
+                height: 8,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            0,
+        )
+        .await;
+        assert_eq!(atlas.generation(), 0);
+        let old_region = atlas.allocate(&device, &queue, [2, 2]).unwrap();
+        assert_eq!(old_region.generation(), 0);
+        assert!(old_region.is_valid());
+
+        atlas.recover(&device, &queue);
+        assert_eq!(atlas.generation(), 1);
+        assert!(!old_region.is_valid());
+
+        let new_region = atlas.allocate(&device, &queue, [2, 2]).unwrap();
+        assert_eq!(new_region.generation(), 1);
+        assert!(new_region.is_valid());
+
+        drop(atlas);
+        assert!(!new_region.is_valid());
+    }
+
     #[tokio::test]
     async fn recover_recreates_gpu_resources_and_resets_caches() {
         let (device, queue, atlas) = setup_atlas(
@@ -4,7 +4,7 @@ use dashmap::DashMap;
 use log::{debug, trace, warn};
 use thiserror::Error;
 
-use super::{AtlasRegion, TextureAtlas, TextureAtlasError};
+use super::{AllocationStrategy, AtlasRegion, GuillotineStrategy, TextureAtlas, TextureAtlasError};
 
 pub struct MemoryAllocateStrategy {
     pub initial_pages: u32,
@@ -14,15 +14,68 @@ pub struct MemoryAllocateStrategy {
     pub shrink_factor: f32,
 }
 
+/// Broad category of content stored in an atlas.
+///
+/// Content with the same format but different usage lives in separate atlases so that,
+/// for example, glyph masks and stencil masks (both `R8Unorm`) do not fragment each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AtlasUsage {
+    /// Glyph coverage masks.
+    Glyph,
+    /// Color images.
+    Image,
+    /// High dynamic range color content.
+    Hdr,
+    /// Clipping / stencil masks.
+    Stencil,
+}
+
+impl AtlasUsage {
+    /// The usage class assumed when only a format is given.
+    pub fn default_for_format(format: wgpu::TextureFormat) -> Self {
+        use wgpu::TextureFormat as F;
+        match format {
+            F::R8Unorm | F::R8Snorm | F::R8Uint | F::R8Sint => AtlasUsage::Glyph,
+            F::Rgba16Float | F::Rgba32Float | F::Rg11b10Ufloat | F::Rgb10a2Unorm => AtlasUsage::Hdr,
+            _ => AtlasUsage::Image,
+        }
+    }
+}
+
+/// Identifies one atlas in an [`AtlasManager`] pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AtlasKey {
+    pub format: wgpu::TextureFormat,
+    pub margin: u32,
+    pub usage: AtlasUsage,
+}
+
+impl AtlasKey {
+    pub fn new(format: wgpu::TextureFormat, margin: u32, usage: AtlasUsage) -> Self {
+        Self {
+            format,
+            margin,
+            usage,
+        }
+    }
+}
+
+/// A pool of texture atlases keyed by [`AtlasKey`].
+///
+/// Atlases are created on demand, so content of different formats (glyphs, images, HDR)
+/// can be allocated through a single [`allocate`](Self::allocate) entry point.
 pub struct AtlasManager {
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
 
     max_size_of_3d_texture: wgpu::Extent3d,
     memory_strategy: MemoryAllocateStrategy,
+    // margin used when the caller does not specify a key
     margin: u32,
+    // packing of atlases created for each usage, guillotine when missing
+    allocation_strategies: DashMap<AtlasUsage, Arc<dyn AllocationStrategy>>,
 
-    atlases: DashMap<wgpu::TextureFormat, Arc<TextureAtlas>>,
+    atlases: DashMap<AtlasKey, Arc<TextureAtlas>>,
 }
 
 impl AtlasManager {
@@ -45,39 +98,71 @@ impl AtlasManager {
             max_size_of_3d_texture,
             memory_strategy,
             margin,
+            allocation_strategies: DashMap::new(),
             atlases: DashMap::new(),
         }
     }
 
+    /// Packs atlases created from now on for `usage` with `strategy`, e.g. a
+    /// [`ShelfStrategy`](super::ShelfStrategy) for glyphs. Existing atlases keep theirs.
+    pub fn set_allocation_strategy(
+        &self,
+        usage: AtlasUsage,
+        strategy: Arc<dyn AllocationStrategy>,
+    ) {
+        debug!("AtlasManager::set_allocation_strategy: {usage:?} uses {strategy:?}");
+        self.allocation_strategies.insert(usage, strategy);
+    }
+
+    /// The key used for `format` when no usage class or margin is specified.
+    pub fn default_key(&self, format: wgpu::TextureFormat) -> AtlasKey {
+        AtlasKey::new(format, self.margin, AtlasUsage::default_for_format(format))
+    }
+
+    /// Eagerly creates the default atlas for `format`.
     pub fn add_format(&self, format: wgpu::TextureFormat) -> Result<(), AtlasManagerError> {
-        if self.atlases.contains_key(&format) {
-            warn!(
-                "AtlasManager::add_format: format {:?} already exists",
-                format
-            );
-            return Err(AtlasManagerError::FormatSetAlreadyExists);
+        self.add_pool(self.default_key(format))
+    }
+
+    /// Eagerly creates the atlas for `key`.
+    pub fn add_pool(&self, key: AtlasKey) -> Result<(), AtlasManagerError> {
+        match self.atlases.entry(key) {
+            dashmap::Entry::Occupied(_) => {
+                warn!("AtlasManager::add_pool: pool {key:?} already exists");
+                Err(AtlasManagerError::FormatSetAlreadyExists)
+            }
+            dashmap::Entry::Vacant(entry) => {
+                entry.insert(self.create_atlas(key));
+                debug!("AtlasManager::add_pool: added pool {key:?}");
+                Ok(())
+            }
         }
+    }
 
-        let atlas = TextureAtlas::new(
-            &self.device,
-            wgpu::Extent3d {
-                width: self.max_size_of_3d_texture.width,
-                height: self.max_size_of_3d_texture.height,
-                depth_or_array_layers: self.memory_strategy.initial_pages,
-            },
-            format,
-            self.margin,
-        );
-        self.atlases.insert(format, atlas);
-        debug!("AtlasManager::add_format: added format {:?}", format);
+    /// Returns the atlas for `key` if it has been created.
+    pub fn atlas(&self, key: &AtlasKey) -> Option<Arc<TextureAtlas>> {
+        self.atlases.get(key).map(|atlas| atlas.value().clone())
+    }
 
-        Ok(())
+    /// Keys of all atlases currently in the pool.
+    pub fn keys(&self) -> Vec<AtlasKey> {
+        self.atlases.iter().map(|entry| *entry.key()).collect()
     }
 
+    /// Allocates `size` in the default atlas for `format`, creating the atlas if needed.
     pub fn allocate(
         &self,
-        size: [u32; 2],
         format: wgpu::TextureFormat,
+        size: [u32; 2],
+    ) -> Result<AtlasRegion, AtlasManagerError> {
+        self.allocate_with_key(self.default_key(format), size)
+    }
+
+    /// Allocates `size` in the atlas for `key`, creating the atlas if needed.
+    pub fn allocate_with_key(
+        &self,
+        key: AtlasKey,
+        size: [u32; 2],
     ) -> Result<AtlasRegion, AtlasManagerError> {
         if size[0] == 0 || size[1] == 0 {
             warn!("AtlasManager::allocate: zero-sized allocation requested");
@@ -95,17 +180,40 @@ impl AtlasManager {
 
         let atlas = self
             .atlases
-            .get(&format)
-            .ok_or(AtlasManagerError::FormatSetNotFound)?;
+            .entry(key)
+            .or_insert_with(|| {
+                debug!("AtlasManager::allocate: creating pool {key:?}");
+                self.create_atlas(key)
+            })
+            .value()
+            .clone();
         trace!(
-            "AtlasManager::allocate: allocating {:?} in format {:?}",
-            size, format
+            "AtlasManager::allocate: allocating {:?} in pool {:?}",
+            size, key
         );
-        // Try to allocate directly.
         atlas
             .allocate(&self.device, &self.queue, size)
             .map_err(AtlasManagerError::AtlasError)
     }
+
+    fn create_atlas(&self, key: AtlasKey) -> Arc<TextureAtlas> {
+        let strategy = self
+            .allocation_strategies
+            .get(&key.usage)
+            .map(|strategy| strategy.value().clone())
+            .unwrap_or_else(|| Arc::new(GuillotineStrategy));
+        TextureAtlas::with_strategy(
+            &self.device,
+            wgpu::Extent3d {
+                width: self.max_size_of_3d_texture.width,
+                height: self.max_size_of_3d_texture.height,
+                depth_or_array_layers: self.memory_strategy.initial_pages,
+            },
+            key.format,
+            key.margin,
+            strategy,
+        )
+    }
 }
 
 #[derive(Debug, Error)]
@@ -136,11 +244,13 @@ mod tests {
         }
 
         fn get_atlas_size(&self, format: wgpu::TextureFormat) -> Option<wgpu::Extent3d> {
-            self.atlases.get(&format).map(|atlas| atlas.value().size())
+            self.atlas(&self.default_key(format))
+                .map(|atlas| atlas.size())
         }
 
         fn get_atlas_usage(&self, format: wgpu::TextureFormat) -> Option<usize> {
-            self.atlases.get(&format).map(|atlas| atlas.value().usage())
+            self.atlas(&self.default_key(format))
+                .map(|atlas| atlas.usage())
         }
     }
 
@@ -241,7 +351,7 @@ mod tests {
         let format = wgpu::TextureFormat::Rgba8UnormSrgb;
         manager.add_format(format).unwrap();
 
-        let texture = manager.allocate([32, 32], format).unwrap();
+        let texture = manager.allocate(format, [32, 32]).unwrap();
         assert_eq!(texture.texture_size(), [32, 32]);
         let margin = TextureAtlas::DEFAULT_MARGIN_PX as usize;
         assert_eq!(
@@ -278,48 +388,126 @@ mod tests {
         manager.add_format(format).unwrap();
 
         // Zero width
-        let result = manager.allocate([0, 32], format);
+        let result = manager.allocate(format, [0, 32]);
         assert!(matches!(result, Err(AtlasManagerError::InvalidTextureSize)));
 
         // Zero height
-        let result = manager.allocate([32, 0], format);
+        let result = manager.allocate(format, [32, 0]);
         assert!(matches!(result, Err(AtlasManagerError::InvalidTextureSize)));
 
         // Exceeds max width
-        let result = manager.allocate([257, 32], format);
+        let result = manager.allocate(format, [257, 32]);
         assert!(matches!(result, Err(AtlasManagerError::InvalidTextureSize)));
 
         // Exceeds max height
-        let result = manager.allocate([32, 257], format);
+        let result = manager.allocate(format, [32, 257]);
         assert!(matches!(result, Err(AtlasManagerError::InvalidTextureSize)));
     }
 
-    /// Tests allocation with a non-existent format set.
-    #[tokio::test]
-    async fn test_allocate_format_not_found() {
-        let (_, _, device, queue) = crate::wgpu_utils::noop_wgpu().await;
-        let memory_strategy = MemoryAllocateStrategy {
-            initial_pages: 1,
-            resize_threshold: Some(0.8),
-            resize_factor: 2.0,
-            shrink_threshold: 0.2,
-            shrink_factor: 0.5,
-        };
-        let max_size = wgpu::Extent3d {
-            width: 256,
-            height: 256,
-            depth_or_array_layers: 1,
-        };
-        let manager = AtlasManager::new(
+    fn make_manager(device: wgpu::Device, queue: wgpu::Queue) -> AtlasManager {
+        AtlasManager::new(
             Arc::new(device),
             Arc::new(queue),
-            memory_strategy,
-            max_size,
+            MemoryAllocateStrategy {
+                initial_pages: 1,
+                resize_threshold: Some(0.8),
+                resize_factor: 2.0,
+                shrink_threshold: 0.2,
+                shrink_factor: 0.5,
+            },
+            wgpu::Extent3d {
+                width: 256,
+                height: 256,
+                depth_or_array_layers: 1,
+            },
             TextureAtlas::DEFAULT_MARGIN_PX,
-        );
+        )
+    }
+
+    /// Tests that allocating in a format without an atlas creates one on demand.
+    #[tokio::test]
+    async fn test_allocate_creates_pool_on_demand() {
+        let (_, _, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let manager = make_manager(device, queue);
 
         let format = wgpu::TextureFormat::Rgba8UnormSrgb;
-        let result = manager.allocate([32, 32], format);
-        assert!(matches!(result, Err(AtlasManagerError::FormatSetNotFound)));
+        let region = manager.allocate(format, [32, 32]).unwrap();
+        assert_eq!(region.format(), format);
+        assert_eq!(manager.atlas_count(), 1);
+        assert!(manager.atlas(&manager.default_key(format)).is_some());
+
+        // eager creation of an existing pool is rejected
+        assert!(matches!(
+            manager.add_format(format),
+            Err(AtlasManagerError::FormatSetAlreadyExists)
+        ));
+    }
+
+    /// Tests that glyph, image and HDR content coexist in separate atlases.
+    #[tokio::test]
+    async fn test_allocate_mixed_formats_and_usages() {
+        let (_, _, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let manager = make_manager(device, queue);
+
+        let glyph = manager
+            .allocate(wgpu::TextureFormat::R8Unorm, [8, 8])
+            .unwrap();
+        let image = manager
+            .allocate(wgpu::TextureFormat::Rgba8UnormSrgb, [16, 16])
+            .unwrap();
+        let hdr = manager
+            .allocate(wgpu::TextureFormat::Rgba16Float, [16, 16])
+            .unwrap();
+        assert_eq!(manager.atlas_count(), 3);
+        assert_ne!(glyph.atlas_id(), image.atlas_id());
+        assert_ne!(image.atlas_id(), hdr.atlas_id());
+        assert_eq!(
+            manager.default_key(wgpu::TextureFormat::Rgba16Float).usage,
+            AtlasUsage::Hdr
+        );
+
+        // same format, different usage class and margin: separate pool
+        let stencil_key = AtlasKey::new(wgpu::TextureFormat::R8Unorm, 0, AtlasUsage::Stencil);
+        let stencil = manager.allocate_with_key(stencil_key, [8, 8]).unwrap();
+        assert_eq!(manager.atlas_count(), 4);
+        assert_ne!(stencil.atlas_id(), glyph.atlas_id());
+        assert_eq!(stencil.allocation_size(), [8, 8]);
+
+        // same key reuses the pool
+        let glyph2 = manager
+            .allocate(wgpu::TextureFormat::R8Unorm, [8, 8])
+            .unwrap();
+        assert_eq!(glyph2.atlas_id(), glyph.atlas_id());
+        assert_eq!(manager.atlas_count(), 4);
+        assert_eq!(manager.keys().len(), 4);
+    }
+
+    /// Tests that atlases are created with the strategy set for their usage.
+    #[tokio::test]
+    async fn test_allocation_strategy_per_usage() {
+        let (_, _, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let manager = make_manager(device, queue);
+        manager.set_allocation_strategy(AtlasUsage::Glyph, Arc::new(super::super::ShelfStrategy));
+
+        let glyph = manager.default_key(wgpu::TextureFormat::R8Unorm);
+        let image = manager.default_key(wgpu::TextureFormat::Rgba8UnormSrgb);
+        manager.add_pool(glyph).unwrap();
+        manager.add_pool(image).unwrap();
+        let strategy = |key| format!("{:?}", manager.atlas(&key).unwrap().strategy());
+        assert_eq!(strategy(glyph), "ShelfStrategy");
+        assert_eq!(strategy(image), "GuillotineStrategy");
+
+        // shelves of the glyph height leave no usable space beside the glyphs
+        let _glyphs: Vec<_> = (0..4)
+            .map(|_| {
+                manager
+                    .allocate(wgpu::TextureFormat::R8Unorm, [62, 14])
+                    .unwrap()
+            })
+            .collect();
+        let fragmentation = manager.atlas(&glyph).unwrap().fragmentation();
+        assert_eq!(fragmentation.allocated, 4 * 64 * 16);
+        assert_eq!(fragmentation.free.area, 256 * 256 - 4 * 64 * 16);
+        assert_eq!(fragmentation.fragmentation(), 0.0);
     }
 }
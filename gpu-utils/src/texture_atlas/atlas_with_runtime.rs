@@ -1,4 +1,6 @@
 pub mod atlas;
-pub use atlas::{AtlasRegion, AtlasRegionError, TextureAtlas, TextureAtlasError};
+pub use atlas::{
+    AtlasRegion, AtlasRegionError, GROW_THRESHOLD, MigrationStatus, TextureAtlas, TextureAtlasError,
+};
 pub mod manager;
 // pub use manager::{AtlasManager, AtlasManagerError, MemoryAllocateStrategy};
@@ -1,25 +1,63 @@
+//! # Texture atlas with incremental resize
+//!
+//! A multi-format texture atlas that grows without stalling a frame.
+//!
+//! [`atlas_simple`](crate::texture_atlas::atlas_simple) grows by adding a page and copying every
+//! existing page in one submission, which is cheap while the atlas is small but produces a hitch
+//! when a large atlas fills up. This atlas instead moves through a `Solid → Resize → Solid` state
+//! machine:
+//!
+//! - **Solid**: one set of textures; allocations are placed in it. When usage passes
+//!   [`GROW_THRESHOLD`] (or an allocation does not fit), a resize begins.
+//! - **Resize**: larger textures are created and every live region gets a reserved location in
+//!   them. The old textures stay bound, so UVs do not change yet. Each call to
+//!   [`TextureAtlas::migrate`] copies a bounded number of pixels into the new textures.
+//!   Allocations made meanwhile are placed in both layouts; writes to regions that were already
+//!   copied schedule them to be copied again.
+//! - When every region is copied, the new textures become the bound ones and
+//!   [`TextureAtlas::generation`] increases. Consumers must rebind the textures and refresh UVs.
+//!
+//! If the old textures run out of space before migration completes, the remaining copies are
+//! done synchronously so the allocation can succeed.
+//!
+//! ## When to prefer it
+//!
+//! Use this atlas under heavy churn: many allocations per frame (e.g. glyphs or thumbnails
+//! streaming in) with an unknown final size. Call [`TextureAtlas::migrate`] once per frame with a
+//! pixel budget that fits the frame time.
+//!
+//! Prefer `atlas_simple` when the working set is small or known up front, when UVs must stay
+//! stable (this atlas relocates every region on resize), or when a single format is enough.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 
-use dashmap::DashMap;
 use euclid::Box2D;
-use guillotiere::{AllocId, AtlasAllocator, euclid};
-use parking_lot::{Mutex, RwLock};
+use guillotiere::{AllocId, AtlasAllocator, Size, euclid};
+use log::{debug, trace};
+use parking_lot::RwLock;
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::memory::{self, AtlasMemory};
+use crate::texture_atlas::atlas_simple::allocator::guillotine_allocator;
+
+/// Usage ratio (0.0 to 1.0) after which an allocation starts an incremental resize.
+pub const GROW_THRESHOLD: f32 = 0.75;
+
 #[derive(Clone)]
 pub struct AtlasRegion {
     inner: Arc<RegionData>,
 }
 
 // We only store the texture id and reference to the atlas,
-// to make `Texture` remain valid after `TextureAtlas` resizes or changes,
-// except for data loss when the atlas shrinks.
+// to make `Texture` remain valid after `TextureAtlas` resizes or changes.
 struct RegionData {
     // allocation info
     texture_id: RegionId,
     // interaction with the atlas
-    atlas: Weak<RwLock<TextureAtlas>>,
+    atlas: Weak<TextureAtlas>,
     // It may be useful to store some information about the texture that will not change during atlas resizing
     size: [u32; 2],                    // size of the texture in pixels
     formats: Vec<wgpu::TextureFormat>, // formats of the texture
@@ -40,6 +78,10 @@ impl AtlasRegion {
         &self.inner.formats
     }
 
+    /// Uploads one slice of pixel data per atlas format into the region.
+    ///
+    /// Each slice holds tightly packed rows of blocks; for block-compressed formats it covers
+    /// the region rounded up to whole blocks.
     pub fn write_data(&self, queue: &wgpu::Queue, data: &[&[u8]]) -> Result<(), AtlasRegionError> {
         // Check data consistency
         if data.len() != self.inner.formats.len() {
@@ -47,38 +89,32 @@ impl AtlasRegion {
                 "Data length does not match formats length".to_string(),
             ));
         }
+        let mut layouts = Vec::with_capacity(self.inner.formats.len());
         for (i, format) in self.inner.formats.iter().enumerate() {
-            let bytes_per_pixel = format
-                .block_copy_size(None)
+            let layout = helper::BlockLayout::new(*format, self.inner.size)
                 .ok_or(AtlasRegionError::InvalidFormatBlockCopySize)?;
-            let expected_size = self.inner.size[0] * self.inner.size[1] * bytes_per_pixel;
+            let expected_size = layout.bytes_per_row * layout.blocks[1];
             if data[i].len() as u32 != expected_size {
                 return Err(AtlasRegionError::DataConsistencyError(format!(
-                    "Data size for format {i} does not match expected size"
+                    "Data size for format {i} ({} bytes) does not match expected size ({expected_size} bytes, {} bytes per row)",
+                    data[i].len(),
+                    layout.bytes_per_row,
                 )));
             }
+            layouts.push(layout);
         }
 
         // Get the texture in the atlas and location
         let Some(atlas) = self.inner.atlas.upgrade() else {
             return Err(AtlasRegionError::AtlasGone);
         };
-        let atlas = atlas.read();
-
-        let textures = atlas.textures();
-        let Some(location) = atlas.get_location(self.inner.texture_id) else {
+        let state = atlas.state.read();
+        let pages = state.bound_pages();
+        let Some(location) = pages.location(self.inner.texture_id) else {
             return Err(AtlasRegionError::TextureNotFoundInAtlas);
         };
 
-        for (i, texture) in textures.iter().enumerate() {
-            let data = data[i];
-
-            let bytes_per_pixel = texture
-                .format()
-                .block_copy_size(None)
-                .ok_or(AtlasRegionError::InvalidFormatBlockCopySize)?;
-            let bytes_per_row = self.inner.size[0] * bytes_per_pixel;
-
+        for ((texture, data), layout) in pages.textures.iter().zip(data).zip(&layouts) {
             let origin = wgpu::Origin3d {
                 x: location.bounds.min.x as u32,
                 y: location.bounds.min.y as u32,
@@ -95,16 +131,15 @@ impl AtlasRegion {
                 data,
                 wgpu::TexelCopyBufferLayout {
                     offset: 0,
-                    bytes_per_row: Some(bytes_per_row),
+                    bytes_per_row: Some(layout.bytes_per_row),
                     rows_per_image: None,
                 },
-                wgpu::Extent3d {
-                    width: self.inner.size[0],
-                    height: self.inner.size[1],
-                    depth_or_array_layers: 1,
-                },
+                layout.extent(),
             );
         }
+        drop(state);
+
+        atlas.mark_written(self.inner.texture_id);
 
         Ok(())
     }
@@ -133,21 +168,14 @@ impl AtlasRegion {
         &self,
         render_pass: &mut wgpu::RenderPass<'_>,
     ) -> Result<(), AtlasRegionError> {
-        // Get the texture location in the atlas
-        let Some(atlas) = self.inner.atlas.upgrade() else {
-            return Err(AtlasRegionError::AtlasGone);
-        };
-        let atlas = atlas.read();
-        let Some(location) = atlas.get_location(self.inner.texture_id) else {
-            return Err(AtlasRegionError::TextureNotFoundInAtlas);
-        };
+        let location = self.location()?;
 
         // Set the viewport to the texture area
         render_pass.set_viewport(
             location.bounds.min.x as f32,
             location.bounds.min.y as f32,
-            location.size()[0] as f32,
-            location.size()[1] as f32,
+            location.usable_size[0] as f32,
+            location.usable_size[1] as f32,
             0.0,
             1.0,
         );
@@ -155,67 +183,74 @@ impl AtlasRegion {
         Ok(())
     }
 
+    /// Begins a render pass with one color attachment per atlas format, limited to the region
+    /// by the viewport and scissor rect. Existing content is loaded, not cleared.
     pub fn begin_render_pass<'a>(
-        &'a self,
+        &self,
         encoder: &'a mut wgpu::CommandEncoder,
     ) -> Result<wgpu::RenderPass<'a>, AtlasRegionError> {
+        if let Some(format) = self.inner.formats.iter().find(|f| f.is_compressed()) {
+            return Err(AtlasRegionError::NotRenderable(*format));
+        }
+
         // Get the texture location in the atlas
         let Some(atlas) = self.inner.atlas.upgrade() else {
             return Err(AtlasRegionError::AtlasGone);
         };
-        let atlas = atlas.read();
-        let Some(location) = atlas.get_location(self.inner.texture_id) else {
-            return Err(AtlasRegionError::TextureNotFoundInAtlas);
-        };
 
-        // Create a render pass for the texture area
-        let mut render_pass = {
-            // Keep the state read guard alive for the duration of the descriptor construction.
-            let state_guard = atlas.state.read();
-            let layer_texture_views = match &*state_guard {
-                TextureAtlasState::Solid(atlas_solid) => &atlas_solid.layer_texture_views,
-                TextureAtlasState::Resize(atlas_resize) => {
-                    if let Some(views) = &atlas_resize.new_layer_texture_views {
-                        views
-                    } else {
-                        &atlas_resize.old_layer_texture_views
-                    }
-                }
+        let (location, layer_views) = {
+            let state = atlas.state.read();
+            let pages = state.bound_pages();
+            let Some(location) = pages.location(self.inner.texture_id) else {
+                return Err(AtlasRegionError::TextureNotFoundInAtlas);
             };
+            let layer_views = pages
+                .layer_texture_views
+                .iter()
+                .map(|views| views[location.page_index as usize].clone())
+                .collect::<Vec<_>>();
+            (location, layer_views)
+        };
+        atlas.mark_written(self.inner.texture_id);
 
-            // Build color attachments slice referencing per-layer D2 views.
-            let color_attachments_vec: Vec<Option<wgpu::RenderPassColorAttachment>> =
-                layer_texture_views
-                    .iter()
-                    .map(|views| {
-                        let view = &views[location.page_index as usize];
-                        Some(wgpu::RenderPassColorAttachment {
-                            view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
-                                store: wgpu::StoreOp::Store,
-                            },
-                            depth_slice: None,
-                        })
-                    })
-                    .collect();
-
-            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        // Build color attachments referencing per-layer D2 views.
+        let color_attachments = layer_views
+            .iter()
+            .map(|view| {
+                Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut render_pass = encoder
+            .begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Texture Atlas Render Pass"),
-                color_attachments: color_attachments_vec.as_slice(),
+                color_attachments: &color_attachments,
                 depth_stencil_attachment: None,
                 timestamp_writes: None,
                 occlusion_query_set: None,
             })
-        };
-
-        // Set the viewport to the texture area
+            .forget_lifetime();
+
+        // The rest of the page belongs to other regions.
+        render_pass.set_scissor_rect(
+            location.bounds.min.x as u32,
+            location.bounds.min.y as u32,
+            location.usable_size[0],
+            location.usable_size[1],
+        );
         render_pass.set_viewport(
             location.bounds.min.x as f32,
             location.bounds.min.y as f32,
-            location.size()[0] as f32,
-            location.size()[1] as f32,
+            location.usable_size[0] as f32,
+            location.usable_size[1] as f32,
             0.0,
             1.0,
         );
@@ -223,17 +258,30 @@ impl AtlasRegion {
         Ok(render_pass)
     }
 
+    /// UVs of the region in the currently bound textures.
+    ///
+    /// Changes when a resize completes, see [`TextureAtlas::generation`].
     pub fn uv(&self) -> Result<Box2D<f32, euclid::UnknownUnit>, AtlasRegionError> {
-        // Get the texture location in the atlas
+        Ok(self.location()?.uv)
+    }
+
+    /// Page (texture array layer) and UVs of the region in the currently bound textures.
+    pub fn position_in_atlas(
+        &self,
+    ) -> Result<(u32, Box2D<f32, euclid::UnknownUnit>), AtlasRegionError> {
+        let location = self.location()?;
+        Ok((location.page_index, location.uv))
+    }
+
+    fn location(&self) -> Result<RegionLocation, AtlasRegionError> {
         let Some(atlas) = self.inner.atlas.upgrade() else {
             return Err(AtlasRegionError::AtlasGone);
         };
-        let atlas = atlas.read();
-        let Some(location) = atlas.get_location(self.inner.texture_id) else {
-            return Err(AtlasRegionError::TextureNotFoundInAtlas);
-        };
-
-        Ok(location.uv)
+        let state = atlas.state.read();
+        state
+            .bound_pages()
+            .location(self.inner.texture_id)
+            .ok_or(AtlasRegionError::TextureNotFoundInAtlas)
     }
 }
 
@@ -241,7 +289,7 @@ impl AtlasRegion {
 impl Drop for RegionData {
     fn drop(&mut self) {
         if let Some(atlas) = self.atlas.upgrade() {
-            match atlas.read().deallocate(self.texture_id) {
+            match atlas.deallocate(self.texture_id) {
                 Ok(_) => {
                     // Successfully deallocated
                 }
@@ -263,6 +311,8 @@ pub enum AtlasRegionError {
     DataConsistencyError(String),
     #[error("Invalid format block copy size.")]
     InvalidFormatBlockCopySize,
+    #[error("Regions of format {0:?} cannot be rendered to.")]
+    NotRenderable(wgpu::TextureFormat),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -270,10 +320,21 @@ struct RegionId {
     texture_uuid: Uuid,
 }
 
+impl RegionId {
+    fn new() -> Self {
+        Self {
+            texture_uuid: Uuid::new_v4(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 struct RegionLocation {
     page_index: u32,
+    // the allocated rectangle, aligned to whole blocks of every format
     bounds: euclid::Box2D<i32, euclid::UnknownUnit>,
+    // the requested size, starting at `bounds.min`
+    usable_size: [u32; 2],
     uv: euclid::Box2D<f32, euclid::UnknownUnit>,
 }
 
@@ -290,9 +351,22 @@ impl RegionLocation {
     }
 }
 
+/// Whether a resize is in progress after [`TextureAtlas::migrate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationStatus {
+    /// The atlas is solid; nothing left to copy.
+    Done,
+    /// Pixels still waiting to be copied into the new textures.
+    InProgress { remaining_pixels: u64 },
+}
+
 pub struct TextureAtlas {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
     formats: Vec<wgpu::TextureFormat>,
     state: RwLock<TextureAtlasState>,
+    // incremented whenever the bound textures change
+    generation: AtomicU64,
     weak_self: Weak<Self>,
 }
 
@@ -301,22 +375,39 @@ enum TextureAtlasState {
     Resize(TextureAtlasResize),
 }
 
+impl TextureAtlasState {
+    /// The pages that are bound for sampling and that region locations refer to.
+    fn bound_pages(&self) -> &Pages {
+        match self {
+            TextureAtlasState::Solid(solid) => &solid.pages,
+            TextureAtlasState::Resize(resize) => &resize.old,
+        }
+    }
+}
+
 // Constructor and information methods.
 impl TextureAtlas {
     pub fn new(
         device: &wgpu::Device,
+        queue: &wgpu::Queue,
         size: wgpu::Extent3d,
         formats: &[wgpu::TextureFormat],
     ) -> Arc<Self> {
-        Arc::new_cyclic(|weak_self| Self {
+        let atlas = Arc::new_cyclic(|weak_self| Self {
+            device: device.clone(),
+            queue: queue.clone(),
             formats: formats.to_vec(),
-            state: RwLock::new(TextureAtlasState::Solid(TextureAtlasSolid::new(
-                device, size, formats,
-            ))),
+            state: RwLock::new(TextureAtlasState::Solid(TextureAtlasSolid {
+                pages: Pages::new(device, formats, size),
+            })),
+            generation: AtomicU64::new(0),
             weak_self: weak_self.clone(),
-        })
+        });
+        memory::register_atlas(atlas.weak_self.clone());
+        atlas
     }
 
+    /// Size of the textures regions are placed in; the target size while resizing.
     pub fn size(&self) -> wgpu::Extent3d {
         match &*self.state.read() {
             TextureAtlasState::Solid(atlas) => atlas.size(),
@@ -342,55 +433,304 @@ impl TextureAtlas {
         }
     }
 
-    // todo: we can optimize this performance.
     pub fn max_allocation_size(&self) -> [u32; 2] {
         match &*self.state.read() {
             TextureAtlasState::Solid(atlas) => atlas.max_allocation_size(),
             TextureAtlasState::Resize(atlas) => atlas.max_allocation_size(),
         }
     }
+
+    pub fn is_resizing(&self) -> bool {
+        matches!(&*self.state.read(), TextureAtlasState::Resize(_))
+    }
+
+    /// Incremented whenever the bound textures are replaced at the end of a resize.
+    ///
+    /// Bind groups and UVs derived from the atlas are only valid for the generation they were
+    /// created in.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// The textures to sample from, one per format.
+    pub fn textures(&self) -> Vec<wgpu::Texture> {
+        self.state.read().bound_pages().textures.clone()
+    }
+
+    /// `D2Array` views of [`textures`](Self::textures).
+    pub fn texture_views(&self) -> Vec<wgpu::TextureView> {
+        self.state.read().bound_pages().texture_views.clone()
+    }
+}
+
+impl AtlasMemory for TextureAtlas {
+    fn texture_memory(&self, out: &mut Vec<(wgpu::TextureFormat, u64, u64)>) {
+        let state = self.state.read();
+        // both layouts are held while resizing; regions are counted in the one they move to
+        let (used, unused) = match &*state {
+            TextureAtlasState::Solid(solid) => (&solid.pages, None),
+            TextureAtlasState::Resize(resize) => (&resize.new, Some(&resize.old)),
+        };
+        for (pages, usage) in [(used, used.usage)]
+            .into_iter()
+            .chain(unused.map(|pages| (pages, 0)))
+        {
+            for texture in &pages.textures {
+                out.push((
+                    texture.format(),
+                    memory::texture_bytes(texture.format(), texture.size()),
+                    memory::texel_bytes(texture.format(), usage as u64),
+                ));
+            }
+        }
+    }
 }
 
 /// TextureAtlas allocation and deallocation
 impl TextureAtlas {
+    /// Allocate a texture in the atlas, growing it when needed.
     pub fn allocate(&self, size: [u32; 2]) -> Result<AtlasRegion, TextureAtlasError> {
-        todo!()
+        let limit = self.device.limits().max_texture_dimension_2d;
+        if size[0] == 0 || size[1] == 0 || size[0] > limit || size[1] > limit {
+            return Err(TextureAtlasError::AllocationFailedInvalidSize { requested: size });
+        }
+
+        let id = RegionId::new();
+        let mut state = self.state.write();
+
+        loop {
+            match &mut *state {
+                TextureAtlasState::Solid(solid) => {
+                    if solid.pages.allocate(id, size).is_some() {
+                        let usage = solid.usage() as f32 / solid.capacity() as f32;
+                        if usage > GROW_THRESHOLD
+                            && let Some(new_size) = self.grown_size(solid.size())
+                        {
+                            // best effort, the current textures still have room
+                            let _ = self.begin_resize(&mut state, new_size);
+                        }
+                        return Ok(self.region(id, size));
+                    }
+
+                    let Some(new_size) = self.grown_size(solid.size()) else {
+                        return Err(TextureAtlasError::AllocationFailedNotEnoughSpace);
+                    };
+                    self.begin_resize(&mut state, new_size)?;
+                }
+                TextureAtlasState::Resize(resize) => {
+                    if resize.old.allocate(id, size).is_some() {
+                        if resize.new.allocate(id, size).is_some() {
+                            resize.pending.push_back(id);
+                            return Ok(self.region(id, size));
+                        }
+                        resize.old.remove(id);
+                    }
+
+                    // the bound textures are full, so the new ones have to take over now
+                    debug!("TextureAtlas::allocate: finishing resize to make room");
+                    self.migrate_locked(&mut state, u64::MAX);
+                }
+            }
+        }
+    }
+
+    /// Starts an incremental resize to `new_size`. See the [module documentation](self).
+    ///
+    /// Fails if a resize is already in progress or the live regions do not fit.
+    pub fn resize(&self, new_size: wgpu::Extent3d) -> Result<(), TextureAtlasError> {
+        let mut state = self.state.write();
+        self.begin_resize(&mut state, new_size)
+    }
+
+    /// Copies up to about `budget_pixels` pixels of pending regions into the new textures,
+    /// and swaps the textures in when everything is copied.
+    ///
+    /// Call once per frame while [`is_resizing`](Self::is_resizing).
+    pub fn migrate(&self, budget_pixels: u64) -> MigrationStatus {
+        let mut state = self.state.write();
+        self.migrate_locked(&mut state, budget_pixels)
+    }
+
+    /// Finishes a resize in progress synchronously.
+    pub fn finish_resize(&self) {
+        self.migrate(u64::MAX);
     }
 
     fn deallocate(&self, id: RegionId) -> Result<(), DeallocationErrorTextureNotFound> {
-        todo!()
+        match &mut *self.state.write() {
+            TextureAtlasState::Solid(solid) => solid
+                .pages
+                .remove(id)
+                .map(|_| ())
+                .ok_or(DeallocationErrorTextureNotFound),
+            TextureAtlasState::Resize(resize) => {
+                resize.pending.retain(|pending| *pending != id);
+                let new = resize.new.remove(id);
+                let old = resize.old.remove(id);
+                new.or(old)
+                    .map(|_| ())
+                    .ok_or(DeallocationErrorTextureNotFound)
+            }
+        }
     }
 }
 
 // for internal use only
 impl TextureAtlas {
-    fn get_location(&self, id: RegionId) -> Option<RegionLocation> {
-        todo!()
+    fn region(&self, id: RegionId, size: [u32; 2]) -> AtlasRegion {
+        AtlasRegion {
+            inner: Arc::new(RegionData {
+                texture_id: id,
+                atlas: self.weak_self.clone(),
+                size,
+                formats: self.formats.clone(),
+            }),
+        }
     }
 
-    fn textures(&self) -> &[wgpu::Texture] {
-        todo!()
+    /// Next size to grow to: double the page size up to the device limit, then double the
+    /// page count. `None` when the atlas cannot grow any further.
+    fn grown_size(&self, size: wgpu::Extent3d) -> Option<wgpu::Extent3d> {
+        let limits = self.device.limits();
+        let max_dimension = limits.max_texture_dimension_2d;
+        if size.width < max_dimension || size.height < max_dimension {
+            Some(wgpu::Extent3d {
+                width: (size.width * 2).min(max_dimension),
+                height: (size.height * 2).min(max_dimension),
+                depth_or_array_layers: size.depth_or_array_layers,
+            })
+        } else if size.depth_or_array_layers < limits.max_texture_array_layers {
+            Some(wgpu::Extent3d {
+                depth_or_array_layers: (size.depth_or_array_layers * 2)
+                    .min(limits.max_texture_array_layers),
+                ..size
+            })
+        } else {
+            None
+        }
     }
 
-    fn texture_views(&self) -> &[wgpu::TextureView] {
-        todo!()
+    fn begin_resize(
+        &self,
+        state: &mut TextureAtlasState,
+        new_size: wgpu::Extent3d,
+    ) -> Result<(), TextureAtlasError> {
+        let TextureAtlasState::Solid(solid) = state else {
+            return Err(TextureAtlasError::ResizeInProgress);
+        };
+
+        // reserve a location for every live region, largest first for tighter packing
+        let mut new = Pages::new(&self.device, &self.formats, new_size);
+        let mut regions = solid
+            .pages
+            .regions
+            .iter()
+            .map(|(id, (location, _))| (*id, location.usable_size))
+            .collect::<Vec<_>>();
+        regions.sort_by_key(|(_, size)| std::cmp::Reverse(size[0] * size[1]));
+        for (id, size) in &regions {
+            if new.allocate(*id, *size).is_none() {
+                return Err(TextureAtlasError::ResizeFailedNotEnoughSpace);
+            }
+        }
+
+        debug!(
+            "TextureAtlas::begin_resize: {:?} -> {:?}, {} regions to migrate",
+            solid.size(),
+            new_size,
+            regions.len()
+        );
+
+        let old = std::mem::take(&mut solid.pages);
+        *state = TextureAtlasState::Resize(TextureAtlasResize {
+            old,
+            new,
+            pending: regions.into_iter().map(|(id, _)| id).collect(),
+        });
+        Ok(())
+    }
+
+    fn migrate_locked(&self, state: &mut TextureAtlasState, budget_pixels: u64) -> MigrationStatus {
+        let TextureAtlasState::Resize(resize) = state else {
+            return MigrationStatus::Done;
+        };
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("TextureAtlas Migration Encoder"),
+            });
+        let mut copied = 0u64;
+        while copied < budget_pixels
+            && let Some(id) = resize.pending.pop_front()
+        {
+            let (Some(old_location), Some(new_location)) =
+                (resize.old.location(id), resize.new.location(id))
+            else {
+                continue;
+            };
+            helper::copy_texture_data(
+                &mut encoder,
+                &resize.old.textures,
+                &resize.new.textures,
+                std::iter::once((old_location, new_location)),
+            );
+            copied += u64::from(old_location.area());
+        }
+        self.queue.submit(std::iter::once(encoder.finish()));
+        trace!("TextureAtlas::migrate: copied {copied} pixels");
+
+        if !resize.pending.is_empty() {
+            let remaining_pixels = resize
+                .pending
+                .iter()
+                .filter_map(|id| resize.old.location(*id))
+                .map(|location| u64::from(location.area()))
+                .sum();
+            return MigrationStatus::InProgress { remaining_pixels };
+        }
+
+        let pages = std::mem::take(&mut resize.new);
+        debug!("TextureAtlas::migrate: resize to {:?} complete", pages.size);
+        *state = TextureAtlasState::Solid(TextureAtlasSolid { pages });
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        MigrationStatus::Done
+    }
+
+    /// Schedules a region written in the old textures to be copied (again) while resizing.
+    fn mark_written(&self, id: RegionId) {
+        if let TextureAtlasState::Resize(resize) = &mut *self.state.write()
+            && !resize.pending.contains(&id)
+        {
+            resize.pending.push_back(id);
+        }
     }
 }
 
-struct TextureAtlasSolid {
+/// Textures of one layout and the regions placed in them.
+#[derive(Default)]
+struct Pages {
     textures: Vec<wgpu::Texture>,
     texture_views: Vec<wgpu::TextureView>,
     layer_texture_views: Vec<Vec<wgpu::TextureView>>,
     size: wgpu::Extent3d,
+    // allocations are rounded to multiples of this, see `helper::block_snap`
+    snap: u32,
 
-    allocators: Vec<Mutex<AtlasAllocator>>,
-    texture_id_to_location: DashMap<RegionId, RegionLocation>,
-    texture_id_to_alloc_id: DashMap<RegionId, AllocId>,
-    usage: std::sync::atomic::AtomicUsize,
+    allocators: Vec<AtlasAllocator>,
+    regions: HashMap<RegionId, (RegionLocation, AllocId)>,
+    usage: usize,
 }
 
-impl TextureAtlasSolid {
-    fn new(device: &wgpu::Device, size: wgpu::Extent3d, formats: &[wgpu::TextureFormat]) -> Self {
+impl Pages {
+    fn new(device: &wgpu::Device, formats: &[wgpu::TextureFormat], size: wgpu::Extent3d) -> Self {
+        // compressed textures must span whole blocks
+        let snap = helper::block_snap(formats);
+        let size = wgpu::Extent3d {
+            width: size.width.next_multiple_of(snap),
+            height: size.height.next_multiple_of(snap),
+            ..size
+        };
         let (textures, texture_views, layer_texture_views) =
             helper::create_texture_and_view(device, formats, size);
         Self {
@@ -398,79 +738,110 @@ impl TextureAtlasSolid {
             texture_views,
             layer_texture_views,
             size,
-            allocators: Vec::new(),
-            texture_id_to_location: DashMap::new(),
-            texture_id_to_alloc_id: DashMap::new(),
-            usage: std::sync::atomic::AtomicUsize::new(0),
+            snap,
+            allocators: (0..size.depth_or_array_layers)
+                .map(|_| guillotine_allocator([size.width, size.height], snap))
+                .collect(),
+            regions: HashMap::new(),
+            usage: 0,
         }
     }
 
+    fn location(&self, id: RegionId) -> Option<RegionLocation> {
+        self.regions.get(&id).map(|(location, _)| *location)
+    }
+
+    fn allocate(&mut self, id: RegionId, size: [u32; 2]) -> Option<RegionLocation> {
+        let request = Size::new(
+            size[0].next_multiple_of(self.snap) as i32,
+            size[1].next_multiple_of(self.snap) as i32,
+        );
+        for (page_index, allocator) in self.allocators.iter_mut().enumerate() {
+            let Some(alloc) = allocator.allocate(request) else {
+                continue;
+            };
+            // guillotiere may hand out a larger rectangle; only use the requested area
+            let min = alloc.rectangle.min;
+            let bounds = Box2D::new(
+                min,
+                euclid::point2(min.x + request.width, min.y + request.height),
+            );
+            let location = RegionLocation {
+                page_index: page_index as u32,
+                bounds,
+                usable_size: size,
+                uv: Box2D::new(
+                    euclid::point2(
+                        bounds.min.x as f32 / self.size.width as f32,
+                        bounds.min.y as f32 / self.size.height as f32,
+                    ),
+                    euclid::point2(
+                        (bounds.min.x + size[0] as i32) as f32 / self.size.width as f32,
+                        (bounds.min.y + size[1] as i32) as f32 / self.size.height as f32,
+                    ),
+                ),
+            };
+            self.regions.insert(id, (location, alloc.id));
+            self.usage += location.area() as usize;
+            return Some(location);
+        }
+        None
+    }
+
+    fn remove(&mut self, id: RegionId) -> Option<RegionLocation> {
+        let (location, alloc_id) = self.regions.remove(&id)?;
+        self.allocators[location.page_index as usize].deallocate(alloc_id);
+        self.usage -= location.area() as usize;
+        Some(location)
+    }
+}
+
+struct TextureAtlasSolid {
+    pages: Pages,
+}
+
+impl TextureAtlasSolid {
     fn size(&self) -> wgpu::Extent3d {
-        self.size
+        self.pages.size
     }
 
     fn capacity(&self) -> usize {
-        self.size.width as usize
-            * self.size.height as usize
-            * self.size.depth_or_array_layers as usize
+        helper::capacity(self.pages.size)
     }
 
     fn usage(&self) -> usize {
-        self.usage.load(std::sync::atomic::Ordering::SeqCst)
+        self.pages.usage
     }
 
     fn max_allocation_size(&self) -> [u32; 2] {
-        let mut max_size = [0; 2];
-
-        for entry in self.texture_id_to_location.iter() {
-            let size = entry.value().size();
-            max_size[0] = max_size[0].max(size[0]);
-            max_size[1] = max_size[1].max(size[1]);
-        }
-        max_size
+        helper::max_allocation_size(&self.pages)
     }
 }
 
 struct TextureAtlasResize {
-    // The new atlas state
-    new_textures: Option<Vec<wgpu::Texture>>,
-    new_texture_views: Option<Vec<wgpu::TextureView>>,
-    new_layer_texture_views: Option<Vec<Vec<wgpu::TextureView>>>,
-    new_size: wgpu::Extent3d,
-
-    new_allocators: Vec<Mutex<AtlasAllocator>>,
-    new_texture_id_to_location: DashMap<RegionId, RegionLocation>,
-    new_texture_id_to_alloc_id: DashMap<RegionId, AllocId>,
-    new_usage: usize,
-
-    // The previous atlas state
-    old_textures: Vec<wgpu::Texture>,
-    old_texture_views: Vec<wgpu::TextureView>,
-    old_layer_texture_views: Vec<Vec<wgpu::TextureView>>,
-    old_size: wgpu::Extent3d,
-    old_allocators: Vec<Mutex<AtlasAllocator>>,
-    old_texture_id_to_location: DashMap<RegionId, RegionLocation>,
-    old_texture_id_to_alloc_id: DashMap<RegionId, AllocId>,
-    old_usage: std::sync::atomic::AtomicUsize,
+    // The previous layout, still bound for sampling
+    old: Pages,
+    // The layout being migrated to; every region of `old` has a location here
+    new: Pages,
+    // Regions whose content still has to be copied from `old` to `new`
+    pending: VecDeque<RegionId>,
 }
 
 impl TextureAtlasResize {
     fn size(&self) -> wgpu::Extent3d {
-        self.new_size
+        self.new.size
     }
 
     fn capacity(&self) -> usize {
-        self.new_size.width as usize
-            * self.new_size.height as usize
-            * self.new_size.depth_or_array_layers as usize
+        helper::capacity(self.new.size)
     }
 
     fn usage(&self) -> usize {
-        self.new_usage
+        self.new.usage
     }
 
     fn max_allocation_size(&self) -> [u32; 2] {
-        todo!()
+        helper::max_allocation_size(&self.new)
     }
 }
 
@@ -478,6 +849,68 @@ impl TextureAtlasResize {
 mod helper {
     use super::*;
 
+    /// Allocation granularity that keeps regions block-aligned in every format: the least
+    /// common multiple of all block dimensions.
+    pub fn block_snap(formats: &[wgpu::TextureFormat]) -> u32 {
+        fn gcd(a: u32, b: u32) -> u32 {
+            if b == 0 { a } else { gcd(b, a % b) }
+        }
+        formats
+            .iter()
+            .flat_map(|format| {
+                let (block_width, block_height) = format.block_dimensions();
+                [block_width, block_height]
+            })
+            .fold(1, |snap, dimension| snap * dimension / gcd(snap, dimension))
+    }
+
+    /// How a region's pixel data of one format is laid out in whole blocks.
+    pub struct BlockLayout {
+        pub block_dimensions: (u32, u32),
+        pub blocks: [u32; 2],
+        pub bytes_per_row: u32,
+    }
+
+    impl BlockLayout {
+        /// `None` for formats without a fixed block size, such as depth formats.
+        pub fn new(format: wgpu::TextureFormat, size: [u32; 2]) -> Option<Self> {
+            let bytes_per_block = format.block_copy_size(None)?;
+            let block_dimensions = format.block_dimensions();
+            let blocks = [
+                size[0].div_ceil(block_dimensions.0),
+                size[1].div_ceil(block_dimensions.1),
+            ];
+            Some(Self {
+                block_dimensions,
+                blocks,
+                bytes_per_row: blocks[0] * bytes_per_block,
+            })
+        }
+
+        /// The copy extent, padded to whole blocks.
+        pub fn extent(&self) -> wgpu::Extent3d {
+            wgpu::Extent3d {
+                width: self.blocks[0] * self.block_dimensions.0,
+                height: self.blocks[1] * self.block_dimensions.1,
+                depth_or_array_layers: 1,
+            }
+        }
+    }
+
+    pub fn capacity(size: wgpu::Extent3d) -> usize {
+        size.width as usize * size.height as usize * size.depth_or_array_layers as usize
+    }
+
+    pub fn max_allocation_size(pages: &Pages) -> [u32; 2] {
+        pages
+            .regions
+            .values()
+            .map(|(location, _)| location.usable_size)
+            .fold([0; 2], |max, size| {
+                [max[0].max(size[0]), max[1].max(size[1])]
+            })
+    }
+
     pub fn create_texture_and_view(
         device: &wgpu::Device,
         formats: &[wgpu::TextureFormat],
@@ -495,6 +928,14 @@ mod helper {
             let texture_label = format!("texture_atlas_texture_{format:?}");
             let texture_view_label = format!("texture_atlas_texture_view_{format:?}");
 
+            // compressed formats can only be written by copies
+            let mut usage = wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC;
+            if !format.is_compressed() {
+                usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+            }
+
             let texture_descriptor = wgpu::TextureDescriptor {
                 label: Some(&texture_label),
                 size: page_size,
@@ -502,10 +943,7 @@ mod helper {
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
                 format,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::COPY_DST
-                    | wgpu::TextureUsages::COPY_SRC,
+                usage,
                 view_formats: &[],
             };
             let texture = device.create_texture(&texture_descriptor);
@@ -539,13 +977,10 @@ mod helper {
         (textures, texture_views, layer_texture_views)
     }
 
-    // Leave unused args to make refactoring easier.
     pub fn copy_texture_data(
         encoder: &mut wgpu::CommandEncoder,
         old_textures: &[wgpu::Texture],
-        _old_texture_views: &[wgpu::TextureView],
         new_textures: &[wgpu::Texture],
-        _new_texture_views: &[wgpu::TextureView],
         location_map: impl Iterator<Item = (RegionLocation, RegionLocation)>,
     ) {
         for (old_location, new_location) in location_map {
@@ -588,487 +1023,163 @@ mod helper {
     }
 }
 
-// old implementation of the texture atlas
-
-/*
-
-pub struct TextureAtlasOldImpl {
-    textures: Vec<wgpu::Texture>,
-    texture_views: Vec<wgpu::TextureView>,
-    size: wgpu::Extent3d,
-    formats: Vec<wgpu::TextureFormat>,
-
-    state: TextureAtlasState,
-
-    weak_self: Weak<RwLock<Self>>,
-}
+/// `DeallocationErrorTextureNotFound` only be used in this file.
+struct DeallocationErrorTextureNotFound;
 
-struct TextureAtlasState {
-    allocators: Vec<Mutex<AtlasAllocator>>,
-    texture_id_to_location: DashMap<TextureId, TextureLocation>,
-    texture_id_to_alloc_id: DashMap<TextureId, AllocId>,
-    usage: std::sync::atomic::AtomicUsize,
+#[derive(Error, Debug)]
+pub enum TextureAtlasError {
+    #[error("Allocation failed because there was not enough space in the atlas.")]
+    AllocationFailedNotEnoughSpace,
+    #[error("Allocation failed because the requested size {requested:?} is invalid.")]
+    AllocationFailedInvalidSize { requested: [u32; 2] },
+    #[error("Resizing the atlas failed because there was not enough space for all the textures.")]
+    ResizeFailedNotEnoughSpace,
+    #[error("A resize is already in progress.")]
+    ResizeInProgress,
 }
 
-/// Constructor and information methods.
-impl TextureAtlasOldImpl {
-    pub fn new(
-        device: &wgpu::Device,
-        size: wgpu::Extent3d,
-        formats: &[wgpu::TextureFormat],
-    ) -> Arc<RwLock<Self>> {
-        let (textures, texture_views) = Self::create_texture_and_view(device, formats, size);
-
-        // Initialize the state with an empty allocator and allocation map.
-        let state = TextureAtlasState {
-            allocators: (0..size.depth_or_array_layers)
-                .map(|_| Size::new(size.width as i32, size.height as i32))
-                .map(AtlasAllocator::new)
-                .map(Mutex::new)
-                .collect(),
-            texture_id_to_location: DashMap::new(),
-            texture_id_to_alloc_id: DashMap::new(),
-            usage: std::sync::atomic::AtomicUsize::new(0),
-        };
-
-        Arc::new_cyclic(|weak_self| {
-            RwLock::new(Self {
-                textures,
-                texture_views,
-                size,
-                formats: formats.to_vec(),
-                state,
-                weak_self: weak_self.clone(),
-            })
-        })
-    }
-
-    pub fn size(&self) -> wgpu::Extent3d {
-        self.size
-    }
-
-    pub fn formats(&self) -> &[wgpu::TextureFormat] {
-        &self.formats
-    }
-
-    pub fn capacity(&self) -> usize {
-        self.size.width as usize
-            * self.size.height as usize
-            * self.size.depth_or_array_layers as usize
-    }
-
-    pub fn usage(&self) -> usize {
-        self.state.usage.load(std::sync::atomic::Ordering::SeqCst)
-    }
-
-    // todo: we can optimize this performance.
-    pub fn max_allocation_size(&self) -> [u32; 2] {
-        let mut max_size = [0; 2];
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
 
-        for entry in self.state.texture_id_to_location.iter() {
-            let size = entry.value().size();
-            max_size[0] = max_size[0].max(size[0]);
-            max_size[1] = max_size[1].max(size[1]);
+    fn extent(width: u32, height: u32, layers: u32) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers,
         }
-        max_size
     }
-}
-
-/// TextureAtlas allocation and deallocation
-impl TextureAtlasOldImpl {
-    /// Allocate a texture in the atlas.
-    pub fn allocate(&self, size: [u32; 2]) -> Result<Texture, TextureAtlasError> {
-        let size = Size::new(size[0] as i32, size[1] as i32);
-
-        for (page_index, allocator) in self.state.allocators.iter().enumerate() {
-            if let Some(alloc) = allocator.lock().allocate(size) {
-                let bounds = alloc.rectangle;
-                let uvs = euclid::Box2D::new(
-                    euclid::Point2D::new(
-                        (bounds.min.x as f32) / (self.size.width as f32),
-                        (bounds.min.y as f32) / (self.size.height as f32),
-                    ),
-                    euclid::Point2D::new(
-                        (bounds.max.x as f32) / (self.size.width as f32),
-                        (bounds.max.y as f32) / (self.size.height as f32),
-                    ),
-                );
-                let location = TextureLocation {
-                    page_index: page_index as u32,
-                    bounds,
-                    uv: uvs,
-                };
-
-                // Create a new TextureId and Texture
-                let texture_id = TextureId {
-                    texture_uuid: Uuid::new_v4(),
-                };
-                let texture_inner = TextureInner {
-                    texture_id,
-                    atlas: self.weak_self.clone(),
-                    size: [size.width as u32, size.height as u32],
-                    formats: self.formats.clone(),
-                };
-                let texture = Texture {
-                    inner: Arc::new(texture_inner),
-                };
-
-                // Store the texture location and allocation id in the atlas state.
-                self.state
-                    .texture_id_to_location
-                    .insert(texture_id, location);
-                self.state
-                    .texture_id_to_alloc_id
-                    .insert(texture_id, alloc.id);
-                // Update usage
-                // use `SeqCst` for safety, just for now. we can optimize this later.
-                self.state.usage.fetch_add(
-                    location.bounds.area() as usize,
-                    std::sync::atomic::Ordering::SeqCst,
-                );
 
-                // Return the allocated texture
-                return Ok(texture);
-            }
-        }
-
-        Err(TextureAtlasError::AllocationFailedNotEnoughSpace)
-    }
-
-    /// Deallocate a texture from the atlas.
-    /// This will be called automatically when the `TextureInner` is dropped.
-    fn deallocate(&self, id: TextureId) -> Result<(), DeallocationErrorTextureNotFound> {
-        // Find the texture location and remove it from the id-to-location map.
-        let (_, location) = self
-            .state
-            .texture_id_to_location
-            .remove(&id)
-            .ok_or(DeallocationErrorTextureNotFound)?;
-
-        // Find the allocation id and remove it from the id-to-alloc-id map.
-        let (_, alloc_id) = self
-            .state
-            .texture_id_to_alloc_id
-            .remove(&id)
-            .ok_or(DeallocationErrorTextureNotFound)?;
-
-        // Deallocate the texture from the allocator.
-        self.state.allocators[location.page_index as usize]
-            .lock()
-            .deallocate(alloc_id);
-
-        // Update usage
-        // use `SeqCst` for safety, just for now. we can optimize this later.
-        self.state.usage.fetch_sub(
-            location.area() as usize,
-            std::sync::atomic::Ordering::SeqCst,
-        );
-
-        Ok(())
+    const FORMATS: [wgpu::TextureFormat; 2] = [
+        wgpu::TextureFormat::Rgba8Unorm,
+        wgpu::TextureFormat::R8Unorm,
+    ];
+
+    #[tokio::test]
+    async fn allocate_and_drop() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let atlas = TextureAtlas::new(&device, &queue, extent(256, 256, 1), &FORMATS);
+
+        let region = atlas.allocate([32, 16]).unwrap();
+        assert_eq!(region.size(), [32, 16]);
+        assert_eq!(atlas.usage(), 32 * 16);
+        assert_eq!(atlas.max_allocation_size(), [32, 16]);
+        assert_eq!(atlas.textures().len(), 2);
+
+        let (page, uv) = region.position_in_atlas().unwrap();
+        assert_eq!(page, 0);
+        assert_eq!(uv.width(), 32.0 / 256.0);
+
+        drop(region);
+        assert_eq!(atlas.usage(), 0);
+        assert!(matches!(
+            atlas.allocate([0, 4]),
+            Err(TextureAtlasError::AllocationFailedInvalidSize { .. })
+        ));
     }
-}
-
-/// Resize the atlas to a new size.
-impl TextureAtlasOldImpl {
-    pub fn resize(
-        &mut self,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        new_size: wgpu::Extent3d,
-        allow_data_loss: bool,
-        new_allocation: Option<[u32; 2]>,
-    ) -> Result<Option<Texture>, TextureAtlasError> {
-        // mutable reference ensures we can modify the atlas state without threading issues.
-
-        // new allocator and allocation map
-        let mut new_allocators = (0..new_size.depth_or_array_layers)
-            .map(|_| Size::new(new_size.width as i32, new_size.height as i32))
-            .map(AtlasAllocator::new)
-            .map(Mutex::new)
-            .collect::<Vec<_>>();
-
-        // Re-allocate existing textures
-        let (new_texture_id_to_location, new_texture_id_to_alloc_id, mut new_usage) =
-            self.reallocate_existing(&mut new_allocators, new_size, allow_data_loss)?;
-
-        // Allocate for the new texture if requested
-        let return_texture = if let Some(size) = new_allocation {
-            match self.allocate_new(&mut new_allocators, new_size, size)? {
-                Some(new_texture_data) => Some(new_texture_data),
-                None => {
-                    if !allow_data_loss {
-                        return Err(TextureAtlasError::ResizeFailedNotEnoughSpace);
-                    }
-                    None
-                }
-            }
-        } else {
-            None
-        };
-
-        // Copy data from old textures to new textures
-        let (new_textures, new_texture_views) =
-            Self::create_texture_and_view(device, &self.formats, new_size);
-
-        let location_map = new_texture_id_to_location.iter().map(|entry| {
-            let id = entry.key();
-            let new_location = entry.value();
-            let old_location = self
-                .state
-                .texture_id_to_location
-                .get(id)
-                .expect("New allocation map was constructed from old allocation map, so it should contain all ids");
-            (*old_location, *new_location)
-        });
-
-        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("TextureAtlas Resize Command Encoder"),
-        });
 
-        Self::copy_texture_data(
-            &mut command_encoder,
-            &self.textures,
-            &self.texture_views,
-            &new_textures,
-            &new_texture_views,
-            location_map,
+    #[test]
+    fn block_layout_covers_whole_blocks() {
+        assert_eq!(helper::block_snap(&FORMATS), 1);
+        assert_eq!(
+            helper::block_snap(&[
+                wgpu::TextureFormat::R8Unorm,
+                wgpu::TextureFormat::Bc7RgbaUnorm,
+            ]),
+            4
+        );
+        assert_eq!(
+            helper::block_snap(&[wgpu::TextureFormat::Astc {
+                block: wgpu::AstcBlock::B6x5,
+                channel: wgpu::AstcChannel::Unorm,
+            }]),
+            30
         );
 
-        // Submit the command encoder to copy data
-        queue.submit(Some(command_encoder.finish()));
-
-        // Update the atlas state
-        self.textures = new_textures;
-        self.texture_views = new_texture_views;
-        self.size = new_size;
-
-        if let Some((ref texture, location, alloc_id)) = return_texture {
-            new_texture_id_to_location.insert(texture.inner.texture_id, location);
-            new_texture_id_to_alloc_id.insert(texture.inner.texture_id, alloc_id);
-            new_usage += location.bounds.area() as usize;
-        }
-
-        self.state.allocators = new_allocators;
-        self.state.texture_id_to_location = new_texture_id_to_location;
-        self.state.texture_id_to_alloc_id = new_texture_id_to_alloc_id;
-        self.state.usage = std::sync::atomic::AtomicUsize::new(new_usage);
+        // 5x3 texels of BC7 are 2x1 blocks of 16 bytes
+        let layout = helper::BlockLayout::new(wgpu::TextureFormat::Bc7RgbaUnorm, [5, 3]).unwrap();
+        assert_eq!(layout.blocks, [2, 1]);
+        assert_eq!(layout.bytes_per_row, 32);
+        assert_eq!(layout.extent(), extent(8, 4, 1));
 
-        Ok(return_texture.map(|(texture, _, _)| texture))
+        let layout = helper::BlockLayout::new(wgpu::TextureFormat::R8Unorm, [5, 3]).unwrap();
+        assert_eq!(layout.bytes_per_row, 5);
+        assert_eq!(layout.extent(), extent(5, 3, 1));
     }
 
-    #[allow(clippy::type_complexity)] // This function is for internal use only, so I think it's not a problem.
-    fn reallocate_existing(
-        &self,
-        new_allocators: &mut [Mutex<AtlasAllocator>],
-        new_atlas_size: wgpu::Extent3d,
-        allow_data_loss: bool,
-    ) -> Result<
-        (
-            DashMap<TextureId, TextureLocation>,
-            DashMap<TextureId, AllocId>,
-            usize,
-        ),
-        TextureAtlasError,
-    > {
-        let new_texture_id_to_location = DashMap::new();
-        let new_texture_id_to_alloc_id = DashMap::new();
-        let mut new_usage = 0;
-
-        'for_each_textures: for (texture_id, size) in self
-            .state
-            .texture_id_to_location
-            .iter()
-            .map(|entry| (*entry.key(), entry.value().size()))
-        {
-            let allocate_request = Size::new(size[0] as i32, size[1] as i32);
-
-            for (page_index, allocator) in new_allocators.iter_mut().enumerate() {
-                if let Some(alloc) = allocator.lock().allocate(allocate_request) {
-                    let bounds = alloc.rectangle;
-                    let uvs = euclid::Box2D::new(
-                        euclid::Point2D::new(
-                            (bounds.min.x as f32) / (new_atlas_size.width as f32),
-                            (bounds.min.y as f32) / (new_atlas_size.height as f32),
-                        ),
-                        euclid::Point2D::new(
-                            (bounds.max.x as f32) / (new_atlas_size.width as f32),
-                            (bounds.max.y as f32) / (new_atlas_size.height as f32),
-                        ),
-                    );
-
-                    let location = TextureLocation {
-                        page_index: page_index as u32,
-                        bounds,
-                        uv: uvs,
-                    };
-
-                    new_texture_id_to_location.insert(texture_id, location);
-                    new_texture_id_to_alloc_id.insert(texture_id, alloc.id);
-                    new_usage += location.bounds.area() as usize;
-                    continue 'for_each_textures;
-                }
-            }
-
-            // If we reach here, it means we couldn't allocate the texture in any page.
-            if !allow_data_loss {
-                return Err(TextureAtlasError::ResizeFailedNotEnoughSpace);
-            }
-        }
-
-        Ok((
-            new_texture_id_to_location,
-            new_texture_id_to_alloc_id,
-            new_usage,
-        ))
+    #[tokio::test]
+    async fn grows_incrementally_past_threshold() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let atlas = TextureAtlas::new(&device, &queue, extent(64, 64, 1), &FORMATS);
+
+        // 64 * 56 > 75% of 64 * 64
+        let first = atlas.allocate([64, 56]).unwrap();
+        assert!(atlas.is_resizing());
+        assert_eq!(atlas.size(), extent(128, 128, 1));
+        assert_eq!(atlas.generation(), 0);
+
+        // still placed in the bound textures while resizing
+        let second = atlas.allocate([8, 8]).unwrap();
+        assert_eq!(second.position_in_atlas().unwrap().1.max.x, 8.0 / 64.0);
+
+        // a small budget copies one region per call
+        assert!(matches!(
+            atlas.migrate(1),
+            MigrationStatus::InProgress { .. }
+        ));
+        assert_eq!(atlas.migrate(1), MigrationStatus::Done);
+        assert!(!atlas.is_resizing());
+        assert_eq!(atlas.generation(), 1);
+
+        // locations now refer to the new textures
+        assert_eq!(first.uv().unwrap().width(), 64.0 / 128.0);
+        assert_eq!(atlas.usage(), 64 * 56 + 8 * 8);
     }
 
-    fn allocate_new(
-        &self,
-        new_allocators: &mut [Mutex<AtlasAllocator>],
-        new_atlas_size: wgpu::Extent3d,
-        size: [u32; 2],
-    ) -> Result<Option<(Texture, TextureLocation, AllocId)>, TextureAtlasError> {
-        let allocate_request = Size::new(size[0] as i32, size[1] as i32);
-
-        for (page_index, allocator) in new_allocators.iter_mut().enumerate() {
-            if let Some(alloc) = allocator.lock().allocate(allocate_request) {
-                let bounds = alloc.rectangle;
-                let uvs = euclid::Box2D::new(
-                    euclid::Point2D::new(
-                        (bounds.min.x as f32) / (new_atlas_size.width as f32),
-                        (bounds.min.y as f32) / (new_atlas_size.height as f32),
-                    ),
-                    euclid::Point2D::new(
-                        (bounds.max.x as f32) / (new_atlas_size.width as f32),
-                        (bounds.max.y as f32) / (new_atlas_size.height as f32),
-                    ),
-                );
+    #[tokio::test]
+    async fn allocation_finishes_resize_when_bound_textures_are_full() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let atlas = TextureAtlas::new(&device, &queue, extent(64, 64, 1), &FORMATS);
 
-                let location = TextureLocation {
-                    page_index: page_index as u32,
-                    bounds,
-                    uv: uvs,
-                };
+        let _first = atlas.allocate([64, 56]).unwrap();
+        assert!(atlas.is_resizing());
 
-                // Create a new TextureId and Texture
-                let texture_id = TextureId {
-                    texture_uuid: Uuid::new_v4(),
-                };
-                let texture = Texture {
-                    inner: Arc::new(TextureInner {
-                        texture_id,
-                        atlas: self.weak_self.clone(),
-                        size,
-                        formats: self.formats.clone(),
-                    }),
-                };
-
-                return Ok(Some((texture, location, alloc.id)));
-            }
-        }
-
-        Ok(None)
-    }
-}
-
-
-// helper functions
-impl TextureAtlasOldImpl {
-    fn create_texture_and_view(
-        device: &wgpu::Device,
-        formats: &[wgpu::TextureFormat],
-        page_size: wgpu::Extent3d,
-    ) -> (Vec<wgpu::Texture>, Vec<wgpu::TextureView>) {
-        let mut textures = Vec::with_capacity(formats.len());
-        let mut texture_views = Vec::with_capacity(formats.len());
-
-        for &format in formats {
-            let texture_label = format!("texture_atlas_texture_{format:?}");
-            let texture_view_label = format!("texture_atlas_texture_view_{format:?}");
-
-            let texture_descriptor = wgpu::TextureDescriptor {
-                label: Some(&texture_label),
-                size: page_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::COPY_DST
-                    | wgpu::TextureUsages::COPY_SRC,
-                view_formats: &[],
-            };
-            let texture = device.create_texture(&texture_descriptor);
-            let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
-                label: Some(&texture_view_label),
-                ..wgpu::TextureViewDescriptor::default()
-            });
-            textures.push(texture);
-            texture_views.push(texture_view);
-        }
-
-        (textures, texture_views)
+        // does not fit in the old 64x64 textures
+        let large = atlas.allocate([64, 64]).unwrap();
+        assert_eq!(atlas.generation(), 1);
+        assert_eq!(large.uv().unwrap().width(), 64.0 / 128.0);
     }
 
-    // Leave unused args to make refactoring easier.
-    fn copy_texture_data(
-        encoder: &mut wgpu::CommandEncoder,
-        old_textures: &[wgpu::Texture],
-        _old_texture_views: &[wgpu::TextureView],
-        new_textures: &[wgpu::Texture],
-        _new_texture_views: &[wgpu::TextureView],
-        location_map: impl Iterator<Item = (TextureLocation, TextureLocation)>,
-    ) {
-        for (old_location, new_location) in location_map {
-            for (old_texture, new_texture) in old_textures.iter().zip(new_textures.iter()) {
-                let old_origin = wgpu::Origin3d {
-                    x: old_location.bounds.min.x as u32,
-                    y: old_location.bounds.min.y as u32,
-                    z: old_location.page_index,
-                };
-
-                let new_origin = wgpu::Origin3d {
-                    x: new_location.bounds.min.x as u32,
-                    y: new_location.bounds.min.y as u32,
-                    z: new_location.page_index,
-                };
-
-                let size = old_location.size();
-
-                encoder.copy_texture_to_texture(
-                    wgpu::TexelCopyTextureInfo {
-                        texture: old_texture,
-                        mip_level: 0,
-                        origin: old_origin,
-                        aspect: wgpu::TextureAspect::All,
-                    },
-                    wgpu::TexelCopyTextureInfo {
-                        texture: new_texture,
-                        mip_level: 0,
-                        origin: new_origin,
-                        aspect: wgpu::TextureAspect::All,
-                    },
-                    wgpu::Extent3d {
-                        width: size[0],
-                        height: size[1],
-                        depth_or_array_layers: 1,
-                    },
-                );
-            }
-        }
+    #[tokio::test]
+    async fn writes_during_resize_are_migrated_again() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let atlas = TextureAtlas::new(&device, &queue, extent(64, 64, 1), &FORMATS);
+
+        let region = atlas.allocate([4, 4]).unwrap();
+        atlas.resize(extent(128, 128, 1)).unwrap();
+        assert!(matches!(
+            atlas.resize(extent(256, 256, 1)),
+            Err(TextureAtlasError::ResizeInProgress)
+        ));
+
+        // copy the region, then write it again
+        let pending = |atlas: &TextureAtlas| match &*atlas.state.read() {
+            TextureAtlasState::Resize(resize) => resize.pending.len(),
+            TextureAtlasState::Solid(_) => 0,
+        };
+        let _other = atlas.allocate([4, 4]).unwrap();
+        atlas.migrate(16);
+        assert_eq!(pending(&atlas), 1);
+        region
+            .write_data(&queue, &[&[0; 4 * 4 * 4], &[0; 4 * 4]])
+            .unwrap();
+        assert_eq!(pending(&atlas), 2);
+
+        // dropping a pending region removes it from the queue
+        drop(region);
+        assert_eq!(pending(&atlas), 1);
+        atlas.finish_resize();
+        assert_eq!(atlas.usage(), 4 * 4);
     }
 }
-
-*/
-
-/// `DeallocationErrorTextureNotFound` only be used in this file.
-struct DeallocationErrorTextureNotFound;
-
-#[derive(Error, Debug)]
-pub enum TextureAtlasError {
-    #[error("Allocation failed because there was not enough space in the atlas.")]
-    AllocationFailedNotEnoughSpace,
-    #[error("Resizing the atlas failed because there was not enough space for all the textures.")]
-    ResizeFailedNotEnoughSpace,
-}
@@ -0,0 +1,417 @@
+//! Staged uploads of buffer and texture data.
+//!
+//! An [`UploadRing`] copies data into staging buffers that stay mapped for CPU writes, and
+//! records the copies to their destinations into one command buffer. [`UploadRing::flush`]
+//! submits them as a frame. The staging buffers of a frame are only written again once the
+//! GPU has finished that frame, so CPU writes never overwrite data a copy still reads.
+//!
+//! Unlike `queue.write_*`, copies do not run before every submission: flush the ring before
+//! submitting work that reads the destinations. A ring is shared by handing the same `Arc` to
+//! every user, e.g. with [`TextureAtlas::set_upload_ring`]; texture atlases flush their ring
+//! before they render to or copy their textures.
+//!
+//! [`TextureAtlas::set_upload_ring`]: crate::texture_atlas::TextureAtlas::set_upload_ring
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use log::{trace, warn};
+use parking_lot::Mutex;
+
+use crate::device_loss_recoverable::DeviceLossRecoverable;
+use crate::memory::{TrackedBytes, UPLOAD_STAGING};
+
+/// Size of the staging buffers; larger writes get a buffer of their own.
+pub const DEFAULT_CHUNK_SIZE: u64 = 1 << 20;
+
+/// A staging buffer.
+struct Chunk {
+    buffer: wgpu::Buffer,
+    // bytes written since it was mapped
+    cursor: u64,
+    _memory: TrackedBytes,
+}
+
+impl Chunk {
+    fn new(device: &wgpu::Device, size: u64) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("upload ring staging buffer"),
+            size,
+            usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        let mut memory = TrackedBytes::new(&UPLOAD_STAGING);
+        memory.set(size);
+        Self {
+            buffer,
+            cursor: 0,
+            _memory: memory,
+        }
+    }
+
+    /// Offset of `len` more bytes aligned to `alignment`, if they fit.
+    fn reserve(&mut self, len: u64, alignment: u64) -> Option<u64> {
+        let offset = self.cursor.next_multiple_of(alignment);
+        let end = offset.checked_add(len)?;
+        (end <= self.buffer.size()).then(|| {
+            self.cursor = end;
+            offset
+        })
+    }
+}
+
+/// The staging buffers of a submitted frame, waiting to be mapped again.
+struct Frame {
+    chunks: Vec<Chunk>,
+    // chunks not mapped yet; the frame is done at zero
+    unmapped: Arc<AtomicUsize>,
+    failed: Arc<AtomicBool>,
+}
+
+struct RingState {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    // mapped and empty, oldest frame first
+    free: VecDeque<Chunk>,
+    // written since the last flush, the last one is filled further
+    written: Vec<Chunk>,
+    in_flight: VecDeque<Frame>,
+    encoder: Option<wgpu::CommandEncoder>,
+}
+
+impl RingState {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self {
+            device: device.clone(),
+            queue: queue.clone(),
+            free: VecDeque::new(),
+            written: Vec::new(),
+            in_flight: VecDeque::new(),
+            encoder: None,
+        }
+    }
+}
+
+/// Stages uploads in a ring of mapped buffers. See the [module documentation](self).
+pub struct UploadRing {
+    chunk_size: u64,
+    state: Mutex<RingState>,
+}
+
+impl UploadRing {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::with_chunk_size(device, queue, DEFAULT_CHUNK_SIZE)
+    }
+
+    pub fn with_chunk_size(device: &wgpu::Device, queue: &wgpu::Queue, chunk_size: u64) -> Self {
+        Self {
+            chunk_size: chunk_size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            state: Mutex::new(RingState::new(device, queue)),
+        }
+    }
+
+    /// Frames flushed whose copies the GPU has not finished yet.
+    pub fn frames_in_flight(&self) -> usize {
+        let mut state = self.state.lock();
+        self.recall(&mut state);
+        state.in_flight.len()
+    }
+
+    /// Stages a copy of `data` to `offset` of `buffer`, like `queue.write_buffer`.
+    ///
+    /// `buffer` needs the `COPY_DST` usage; `offset` and the length of `data` must be
+    /// multiples of [`wgpu::COPY_BUFFER_ALIGNMENT`].
+    pub fn write_buffer(&self, buffer: &wgpu::Buffer, offset: wgpu::BufferAddress, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let mut state = self.state.lock();
+        let (staging, staging_offset) = self.stage(
+            &mut state,
+            data.len() as u64,
+            wgpu::COPY_BUFFER_ALIGNMENT,
+            |mapped| mapped.copy_from_slice(data),
+        );
+        Self::encoder(&mut state).copy_buffer_to_buffer(
+            &staging,
+            staging_offset,
+            buffer,
+            offset,
+            data.len() as u64,
+        );
+    }
+
+    /// Stages a copy of `data` to `texture`, like `queue.write_texture`.
+    ///
+    /// Rows of any pitch are accepted; they are repacked to the alignment buffer copies need.
+    pub fn write_texture(
+        &self,
+        texture: wgpu::TexelCopyTextureInfo<'_>,
+        data: &[u8],
+        layout: wgpu::TexelCopyBufferLayout,
+        size: wgpu::Extent3d,
+    ) {
+        let format = texture.texture.format();
+        let (block_width, block_height) = format.block_dimensions();
+        let Some(block_bytes) = format.block_copy_size(Some(texture.aspect)) else {
+            warn!("UploadRing::write_texture: {format:?} cannot be copied from a buffer");
+            return;
+        };
+        let row_bytes = u64::from(size.width.div_ceil(block_width) * block_bytes);
+        let rows = u64::from(size.height.div_ceil(block_height));
+        let layers = u64::from(size.depth_or_array_layers);
+        if row_bytes == 0 || rows == 0 || layers == 0 {
+            return;
+        }
+        let source_pitch = layout.bytes_per_row.map_or(row_bytes, u64::from);
+        let source_rows = layout
+            .rows_per_image
+            .map_or(rows, |rows| u64::from(rows.div_ceil(block_height)));
+        let last =
+            layout.offset + source_pitch * (source_rows * (layers - 1) + rows - 1) + row_bytes;
+        if (data.len() as u64) < last {
+            warn!(
+                "UploadRing::write_texture: {} bytes of data, {last} needed",
+                data.len()
+            );
+            return;
+        }
+
+        let pitch = row_bytes.next_multiple_of(u64::from(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT));
+        let mut state = self.state.lock();
+        let (staging, staging_offset) = self.stage(
+            &mut state,
+            pitch * rows * layers,
+            u64::from(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+            |mapped| {
+                for layer in 0..layers {
+                    for row in 0..rows {
+                        let from =
+                            (layout.offset + source_pitch * (source_rows * layer + row)) as usize;
+                        let to = (pitch * (rows * layer + row)) as usize;
+                        mapped[to..to + row_bytes as usize]
+                            .copy_from_slice(&data[from..from + row_bytes as usize]);
+                    }
+                }
+            },
+        );
+        Self::encoder(&mut state).copy_buffer_to_texture(
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: staging_offset,
+                    bytes_per_row: Some(pitch as u32),
+                    rows_per_image: Some((rows * u64::from(block_height)) as u32),
+                },
+            },
+            texture,
+            size,
+        );
+    }
+
+    /// Submits the staged copies. Call before submitting work that reads their destinations.
+    pub fn flush(&self) {
+        let mut state = self.state.lock();
+        let Some(encoder) = state.encoder.take() else {
+            return;
+        };
+        let chunks = std::mem::take(&mut state.written);
+        for chunk in &chunks {
+            chunk.buffer.unmap();
+        }
+        state.queue.submit(Some(encoder.finish()));
+
+        // mapping waits for the GPU to finish the copies, which fences the frame
+        let unmapped = Arc::new(AtomicUsize::new(chunks.len()));
+        let failed = Arc::new(AtomicBool::new(false));
+        for chunk in &chunks {
+            let unmapped = unmapped.clone();
+            let failed = failed.clone();
+            chunk
+                .buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Write, move |result| {
+                    if result.is_err() {
+                        failed.store(true, Ordering::Release);
+                    }
+                    unmapped.fetch_sub(1, Ordering::AcqRel);
+                });
+        }
+        trace!(
+            "UploadRing::flush: submitted {} staging buffers",
+            chunks.len()
+        );
+        state.in_flight.push_back(Frame {
+            chunks,
+            unmapped,
+            failed,
+        });
+    }
+
+    fn encoder(state: &mut RingState) -> &mut wgpu::CommandEncoder {
+        let device = &state.device;
+        state.encoder.get_or_insert_with(|| {
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("upload ring encoder"),
+            })
+        })
+    }
+
+    /// Reserves `len` bytes of staging memory, fills them with `write` and returns their
+    /// buffer and offset.
+    fn stage(
+        &self,
+        state: &mut RingState,
+        len: u64,
+        alignment: u64,
+        write: impl FnOnce(&mut [u8]),
+    ) -> (wgpu::Buffer, u64) {
+        let reserved = state
+            .written
+            .last_mut()
+            .and_then(|chunk| Some((chunk.buffer.clone(), chunk.reserve(len, alignment)?)));
+        let (buffer, offset) = match reserved {
+            Some(reserved) => reserved,
+            None => {
+                self.recall(state);
+                let free = (len <= self.chunk_size)
+                    .then(|| state.free.pop_front())
+                    .flatten();
+                let mut chunk = free.unwrap_or_else(|| {
+                    let size = self
+                        .chunk_size
+                        .max(len.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT));
+                    Chunk::new(&state.device, size)
+                });
+                // a fresh chunk starts aligned and holds at least `len` bytes
+                let offset = chunk.reserve(len, alignment).unwrap_or_default();
+                let buffer = chunk.buffer.clone();
+                state.written.push(chunk);
+                (buffer, offset)
+            }
+        };
+        write(&mut buffer.slice(offset..offset + len).get_mapped_range_mut());
+        (buffer, offset)
+    }
+
+    /// Returns the chunks of frames the GPU has finished to the free list.
+    fn recall(&self, state: &mut RingState) {
+        let _ = state.device.poll(wgpu::PollType::Poll);
+        while let Some(frame) = state.in_flight.front() {
+            if frame.unmapped.load(Ordering::Acquire) > 0 {
+                break;
+            }
+            let Some(frame) = state.in_flight.pop_front() else {
+                break;
+            };
+            if frame.failed.load(Ordering::Acquire) {
+                warn!("UploadRing: mapping staging buffers failed, dropping them");
+                continue;
+            }
+            // larger chunks of single writes are not kept
+            state.free.extend(
+                frame
+                    .chunks
+                    .into_iter()
+                    .filter(|chunk| chunk.buffer.size() == self.chunk_size)
+                    .map(|mut chunk| {
+                        chunk.cursor = 0;
+                        chunk
+                    }),
+            );
+        }
+    }
+}
+
+/// Staged copies and staging buffers of the lost device are dropped.
+impl DeviceLossRecoverable for UploadRing {
+    fn recover(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        *self.state.lock() = RingState::new(device, queue);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn chunks_are_reused_once_their_frame_is_done() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let ring = UploadRing::with_chunk_size(&device, &queue, 256);
+        let target = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 1024,
+            usage: wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        ring.write_buffer(&target, 0, &[1; 200]);
+        // does not fit behind the first write
+        ring.write_buffer(&target, 256, &[2; 100]);
+        // larger than a chunk
+        ring.write_buffer(&target, 512, &[3; 400]);
+        assert_eq!(ring.state.lock().written.len(), 3);
+        ring.flush();
+        assert!(ring.state.lock().written.is_empty());
+
+        device.poll(wgpu::PollType::Wait).unwrap();
+        assert_eq!(ring.frames_in_flight(), 0);
+        // the oversized chunk was dropped
+        assert_eq!(ring.state.lock().free.len(), 2);
+
+        ring.write_buffer(&target, 0, &[4; 16]);
+        assert_eq!(ring.state.lock().free.len(), 1);
+        ring.flush();
+    }
+
+    #[tokio::test]
+    async fn texture_rows_are_repacked_to_the_copy_alignment() {
+        let (_instance, _adapter, device, queue) = crate::wgpu_utils::noop_wgpu().await;
+        let ring = UploadRing::new(&device, &queue);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: 3,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        ring.write_texture(
+            texture.as_image_copy(),
+            &[1, 2, 3, 0, 4, 5, 6],
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4),
+                rows_per_image: None,
+            },
+            texture.size(),
+        );
+        {
+            let state = ring.state.lock();
+            let chunk = &state.written[0];
+            assert_eq!(chunk.cursor, 2 * 256);
+            let mapped = chunk.buffer.slice(..).get_mapped_range();
+            assert_eq!(&mapped[..3], &[1, 2, 3]);
+            assert_eq!(&mapped[256..259], &[4, 5, 6]);
+        }
+        ring.flush();
+
+        // not enough data: nothing is staged
+        ring.write_texture(
+            texture.as_image_copy(),
+            &[1, 2, 3],
+            wgpu::TexelCopyBufferLayout::default(),
+            texture.size(),
+        );
+        assert!(ring.state.lock().encoder.is_none());
+    }
+}
@@ -1,4 +1,31 @@
+//! Devices for tests.
+//!
+//! Available in debug builds and, in any profile, with the `testing` feature, so release
+//! test runs and downstream crates can use them as well:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! gpu-utils = { workspace = true, features = ["testing"] }
+//! ```
+//!
+//! The noop device accepts every call and executes nothing, which makes it deterministic and
+//! available everywhere, including CI without a GPU. Use [`headless_wgpu`] when a test needs
+//! to read back what the GPU actually rendered.
+
+/// A device on wgpu's noop backend with the default limits.
 pub async fn noop_wgpu() -> (wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue) {
+    noop_wgpu_with_limits(wgpu::Limits::default()).await
+}
+
+/// A device on wgpu's noop backend that enforces `limits`, e.g. to test behavior at small
+/// texture or buffer sizes independently of the machine.
+///
+/// # Panics
+///
+/// If `limits` exceed what the noop adapter supports.
+pub async fn noop_wgpu_with_limits(
+    limits: wgpu::Limits,
+) -> (wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue) {
     let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
         backends: wgpu::Backends::NOOP,
         backend_options: wgpu::BackendOptions {
@@ -14,9 +41,78 @@ pub async fn noop_wgpu() -> (wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::
         .expect("Failed to find noop adapter");
 
     let (device, queue) = adapter
-        .request_device(&Default::default())
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("noop test device"),
+            required_limits: limits,
+            ..Default::default()
+        })
         .await
         .expect("Failed to create device");
 
     (instance, adapter, device, queue)
 }
+
+/// A device on the default adapter of this machine, or `None` if there is none
+/// (e.g. on CI without a GPU).
+pub async fn real_wgpu() -> Option<(wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    headless_wgpu(wgpu::Limits::default(), wgpu::Features::empty()).await
+}
+
+/// A device on a real adapter of this machine without any surface, with exactly `limits` and
+/// `features` so results do not depend on what the adapter supports beyond them.
+///
+/// Prefers a software adapter (e.g. llvmpipe or WARP) when one is available, since it renders
+/// the same on every machine, and falls back to the default adapter otherwise. Returns `None`
+/// if no adapter supports `limits` and `features`.
+pub async fn headless_wgpu(
+    limits: wgpu::Limits,
+    features: wgpu::Features,
+) -> Option<(wgpu::Instance, wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+
+    let supports = |adapter: &wgpu::Adapter| {
+        adapter.features().contains(features) && limits.check_limits(&adapter.limits())
+    };
+
+    let software = instance
+        .enumerate_adapters(wgpu::Backends::all())
+        .into_iter()
+        .find(|adapter| {
+            adapter.get_info().device_type == wgpu::DeviceType::Cpu && supports(adapter)
+        });
+    let adapter = match software {
+        Some(adapter) => adapter,
+        None => instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .ok()
+            .filter(supports)?,
+    };
+
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor {
+            label: Some("headless test device"),
+            required_features: features,
+            required_limits: limits,
+            ..Default::default()
+        })
+        .await
+        .ok()?;
+
+    Some((instance, adapter, device, queue))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noop_device_enforces_requested_limits() {
+        let limits = wgpu::Limits {
+            max_texture_dimension_2d: 256,
+            ..wgpu::Limits::downlevel_defaults()
+        };
+        let (_, _, device, _) = futures::executor::block_on(noop_wgpu_with_limits(limits));
+        assert_eq!(device.limits().max_texture_dimension_2d, 256);
+    }
+}
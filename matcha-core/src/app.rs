@@ -3,7 +3,7 @@ use log::{debug, trace};
 
 use super::{
     backend::Backend, color::Color, device_input::mouse_state::MousePrimaryButton,
-    ui::component::Component, winit_instance::WinitInstanceBuilder,
+    render_backend::RenderBackend, ui::component::Component, winit_instance::WinitInstanceBuilder,
 };
 use std::{num::NonZeroUsize, time::Duration};
 
@@ -60,6 +60,7 @@ where
     where
         NewMessage: Send + Sync + 'static,
         NewB: Backend<Event> + Clone + Send + Sync + 'static,
+        InnerEvent: Send + 'static,
     {
         debug!("App::with_backend: swapping backend for new type");
         let mut new_builder = WinitInstanceBuilder::new(component, backend);
@@ -69,15 +70,34 @@ where
         new_builder.init_size = self.builder.init_size;
         new_builder.maximized = self.builder.maximized;
         new_builder.full_screen = self.builder.full_screen;
+        new_builder.transparent = self.builder.transparent;
+        new_builder.click_through = self.builder.click_through;
+        new_builder.decorations = self.builder.decorations;
+        new_builder.icon = self.builder.icon;
+        new_builder.window_effect = self.builder.window_effect;
+        new_builder.render_backend = self.builder.render_backend;
         new_builder.power_preference = self.builder.power_preference;
+        new_builder.adapter_selection = self.builder.adapter_selection;
         new_builder.base_color = self.builder.base_color;
         new_builder.surface_preferred_format = self.builder.surface_preferred_format;
+        new_builder.surface_alpha_mode = self.builder.surface_alpha_mode;
+        new_builder.hdr_output = self.builder.hdr_output;
+        new_builder.max_frame_latency = self.builder.max_frame_latency;
+        new_builder.occlusion_culling = self.builder.occlusion_culling;
         new_builder.double_click_threshold = self.builder.double_click_threshold;
         new_builder.long_press_threshold = self.builder.long_press_threshold;
         new_builder.mouse_primary_button = self.builder.mouse_primary_button;
         new_builder.scroll_pixel_per_line = self.builder.scroll_pixel_per_line;
         new_builder.default_font_size = self.builder.default_font_size;
+        new_builder.fonts = self.builder.fonts;
         new_builder.debug_config = self.builder.debug_config;
+        new_builder.run_in_background = self.builder.run_in_background;
+        new_builder.power_saving = self.builder.power_saving;
+        new_builder.localization = self.builder.localization;
+        new_builder.cache_budget = self.builder.cache_budget;
+        new_builder.record_input = self.builder.record_input;
+        new_builder.replay_input = self.builder.replay_input;
+        // shortcuts, menus and the tray icon are typed by the old message type and cannot be carried over
 
         App {
             builder: new_builder,
@@ -116,11 +136,67 @@ where
         self
     }
 
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.builder = self.builder.transparent(transparent);
+        self
+    }
+
+    /// Whether the OS draws a title bar and border around the window. Without them the app
+    /// draws its own, see [`crate::window_control`].
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.builder = self.builder.decorations(decorations);
+        self
+    }
+
+    /// Icon of the window in its title bar and the taskbar, e.g. from
+    /// [`WindowIcon::from_image_bytes`](crate::window_icon::WindowIcon::from_image_bytes) with
+    /// an embedded PNG.
+    pub fn window_icon(mut self, icon: crate::window_icon::WindowIcon) -> Self {
+        self.builder = self.builder.window_icon(icon);
+        self
+    }
+
+    /// Material the platform draws behind the window, like Windows mica or macOS blur. The
+    /// window becomes transparent; keep `base_color` translucent to let it show. See
+    /// [`crate::window_effect`].
+    pub fn window_effect(mut self, effect: crate::window_effect::WindowEffect) -> Self {
+        self.builder = self.builder.window_effect(effect);
+        self
+    }
+
+    /// Let clicks on parts of the window without widgets through to the windows below, for
+    /// transparent, custom shaped windows. See [`crate::input_region`].
+    pub fn click_through(mut self, click_through: bool) -> Self {
+        self.builder = self.builder.click_through(click_through);
+        self
+    }
+
+    /// Chooses how frames are rendered and presented, e.g. on the CPU with
+    /// [`RenderBackend::Software`] where there is no GPU.
+    ///
+    /// **[`RenderBackend::Software`] still needs a wgpu adapter with
+    /// `wgpu::Features::PUSH_CONSTANTS`**: only compositing moves to the CPU, widgets keep
+    /// drawing their content with wgpu. On machines without a GPU install a software adapter
+    /// such as Mesa's llvmpipe (Vulkan or GL); without one [`App::run`] fails with an
+    /// [`AppRunError`] saying so.
+    pub fn render_backend(mut self, render_backend: RenderBackend) -> Self {
+        self.builder = self.builder.render_backend(render_backend);
+        self
+    }
+
     pub fn power_preference(mut self, preference: wgpu::PowerPreference) -> Self {
         self.builder = self.builder.power_preference(preference);
         self
     }
 
+    /// Runs on a specific GPU, e.g. one picked from
+    /// [`enumerate_adapters`](crate::render_backend::enumerate_adapters) in settings. Falls back
+    /// to the adapter preferred by `power_preference` when it is not found.
+    pub fn adapter(mut self, selection: crate::render_backend::AdapterSelection) -> Self {
+        self.builder = self.builder.adapter(selection);
+        self
+    }
+
     pub fn base_color(mut self, color: Color) -> Self {
         self.builder = self.builder.base_color(color);
         self
@@ -131,6 +207,42 @@ where
         self
     }
 
+    pub fn surface_alpha_mode(mut self, alpha_mode: wgpu::CompositeAlphaMode) -> Self {
+        self.builder = self.builder.surface_alpha_mode(alpha_mode);
+        self
+    }
+
+    /// Render to an HDR (scRGB) surface when the display supports it. Colors brighter than
+    /// `1.0` are tone-mapped on displays without HDR support.
+    pub fn hdr_output(mut self, hdr: bool) -> Self {
+        self.builder = self.builder.hdr_output(hdr);
+        self
+    }
+
+    /// Frames the surface queues ahead of the display, at least 1. The default of 1 has the
+    /// lowest input latency; more frames smooth out uneven frame times. Change it at runtime
+    /// with `ApplicationContext::set_max_frame_latency` and watch the effect with
+    /// `present_stats`, see [`present_timing`](crate::present_timing).
+    pub fn max_frame_latency(mut self, latency: u32) -> Self {
+        self.builder = self.builder.max_frame_latency(latency);
+        self
+    }
+
+    /// How windows follow continuous resizing, e.g.
+    /// `ResizeStrategy::Debounce(Duration::from_millis(100))` to stretch the last frame while
+    /// the user drags a window edge and lay out once they stop.
+    pub fn resize_strategy(mut self, strategy: crate::resize_strategy::ResizeStrategy) -> Self {
+        self.builder = self.builder.resize_strategy(strategy);
+        self
+    }
+
+    /// Skip drawing widgets that opaque widgets in front of them cover completely, see
+    /// `renderer::CoreRenderer::with_occlusion_culling`.
+    pub fn occlusion_culling(mut self, enabled: bool) -> Self {
+        self.builder = self.builder.occlusion_culling(enabled);
+        self
+    }
+
     pub fn double_click_threshold(mut self, duration: Duration) -> Self {
         self.builder = self.builder.double_click_threshold(duration);
         self
@@ -151,11 +263,111 @@ where
         self
     }
 
+    /// App-wide keyboard shortcuts. A matching key press is consumed before it reaches the
+    /// widgets and its message is passed to the root component's `update_fn`.
+    ///
+    /// Shortcuts produce `Message`s, so call this after `with_backend`, which changes the
+    /// message type and starts with an empty registry.
+    pub fn shortcuts(mut self, shortcuts: crate::shortcut::ShortcutRegistry<Message>) -> Self {
+        self.builder = self.builder.shortcuts(shortcuts);
+        self
+    }
+
+    /// Menus of the window's menu bar. Activated items send their message to the root
+    /// component's `update_fn`, and the shortcuts of the items are added to the app shortcuts.
+    ///
+    /// The menu bar is shown natively on Windows and macOS with the `native-menu` feature.
+    /// Like `shortcuts`, call this after `with_backend`.
+    pub fn menu_bar(mut self, menu_bar: crate::menu::MenuBar<Message>) -> Self {
+        self.builder = self.builder.menu_bar(menu_bar);
+        self
+    }
+
+    /// Icon shown in the system tray. Items of its menu and left clicks on the icon send their
+    /// message to the root component's `update_fn`; by default a left click also shows the
+    /// hidden windows again.
+    ///
+    /// The tray icon is shown on Windows and macOS with the `tray-icon` feature.
+    /// Like `shortcuts`, call this after `with_backend`.
+    pub fn tray(mut self, tray: crate::tray::Tray<Message>) -> Self {
+        self.builder = self.builder.tray(tray);
+        self
+    }
+
+    /// Write the input the window receives to the file at `path`, replacing it, e.g. to attach
+    /// a reproducible session to a bug report. See [`input_recording`](crate::input_recording).
+    pub fn record_input(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.builder = self.builder.record_input(path);
+        self
+    }
+
+    /// Replay `recording` against the window once it is set up, in addition to the input the
+    /// user gives meanwhile.
+    pub fn replay_input(
+        mut self,
+        recording: crate::input_recording::InputRecording,
+        speed: crate::input_recording::ReplaySpeed,
+    ) -> Self {
+        self.builder = self.builder.replay_input(recording, speed);
+        self
+    }
+
+    /// Hide windows instead of closing them when the user closes them, so the application
+    /// keeps running in the background. Exit with `ApplicationContext::exit`, e.g. from a
+    /// tray menu item.
+    pub fn run_in_background(mut self, run_in_background: bool) -> Self {
+        self.builder = self.builder.run_in_background(run_in_background);
+        self
+    }
+
+    /// Frame rate limit and animation pause while no window is in use. By default the app
+    /// renders at most 10 frames per second in the background, see
+    /// [`power_saving`](crate::power_saving).
+    pub fn power_saving(mut self, power_saving: crate::power_saving::PowerSaving) -> Self {
+        self.builder = self.builder.power_saving(power_saving);
+        self
+    }
+
     pub fn default_font_size(mut self, size: f32) -> Self {
         self.builder = self.builder.default_font_size(size);
         self
     }
 
+    /// Adds a font file or collection in memory, e.g. one bundled with `include_bytes!`. It is
+    /// available from the first frame; add fonts at runtime with
+    /// `ApplicationContext::register_font_bytes`.
+    pub fn font_bytes(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.builder = self.builder.font_bytes(bytes);
+        self
+    }
+
+    /// Adds every font file in `path` and its subdirectories, see [`Self::font_bytes`].
+    pub fn font_dir(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.builder = self.builder.font_dir(path);
+        self
+    }
+
+    /// Resolves the message keys of [`tr!`](crate::tr) and `WidgetContext::tr`.
+    pub fn localizer(mut self, localizer: impl crate::localization::Localizer) -> Self {
+        self.builder = self.builder.localizer(localizer);
+        self
+    }
+
+    /// Initial locale, e.g. `"en-US"`. Change it at runtime with
+    /// `ApplicationContext::set_locale`.
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.builder = self.builder.locale(locale);
+        self
+    }
+
+    /// Memory limit in bytes of the cached layouts and render nodes of all windows. The least
+    /// recently used caches of widgets that are not drawn are evicted beyond it. Defaults to
+    /// [`DEFAULT_CACHE_BUDGET`](crate::cache_budget::DEFAULT_CACHE_BUDGET).
+    pub fn cache_budget(mut self, budget_bytes: usize) -> Self {
+        self.builder = self.builder.cache_budget(budget_bytes);
+        self
+    }
+
     /// Inject a shared DebugConfig instance.
     pub fn debug_config(mut self, cfg: crate::debug_config::DebugConfig) -> Self {
         self.builder = self.builder.debug_config(cfg);
@@ -186,6 +398,22 @@ where
         self
     }
 
+    /// Overlap each window's model update and layout with the GPU submission and present of
+    /// its previous frame. Lowers latency on heavy UIs, at the cost of the next frame's layout
+    /// possibly being redone when the window is resized meanwhile.
+    pub fn pipelined_rendering(mut self, v: bool) -> Self {
+        self.builder = self.builder.pipelined_rendering(v);
+        self
+    }
+
+    /// Paint yellow and black stripes along the edges of widgets whose children are laid out
+    /// beyond them, see [`overflow`](crate::ui::overflow). Overflow is logged as a warning
+    /// either way.
+    pub fn show_layout_overflow(mut self, v: bool) -> Self {
+        self.builder = self.builder.show_layout_overflow(v);
+        self
+    }
+
     pub fn run(self) -> Result<(), AppRunError> {
         debug!("App::run: building WinitInstance");
         let mut winit_app = self.builder.build()?;
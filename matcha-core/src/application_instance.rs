@@ -1,12 +1,17 @@
 use std::{collections::HashMap, sync::Arc};
 
-use gpu_utils::device_loss_recoverable::DeviceLossRecoverable;
-use renderer::CoreRenderer;
-
 use crate::{
     backend::Backend,
     color::Color,
     context::{ApplicationCommand, GlobalResources},
+    cursor::Cursor,
+    device_input::DeviceInputData,
+    input_recording::{InputRecording, RecordedInput, ReplaySpeed},
+    lifecycle::LifecycleEvent,
+    power_saving::{Background, PowerSaving},
+    render_backend::Compositor,
+    ui::HitTestPath,
+    window_control::WindowControl,
     window_ui::{WindowUi, WindowUiConfig},
 };
 
@@ -26,16 +31,26 @@ pub struct ApplicationInstance<
 
     // todo: make this per-window?
     base_color: Color,
-    renderer: CoreRenderer,
+    compositor: Compositor,
 
     backend: Arc<B>,
 
+    // hide windows instead of closing them when the user closes them
+    run_in_background: bool,
+    // lower frame rate while no window is in use
+    power_saving: PowerSaving,
+    background: Background,
+
     benchmarker: tokio::sync::Mutex<utils::benchmark::Benchmark>,
 
+    // replayed once the windows are set up, see `input_recording`
+    input_replay: parking_lot::Mutex<Option<(InputRecording, ReplaySpeed)>>,
+
     frame_count: std::sync::atomic::AtomicU64,
 
     device_lost_callback_id: parking_lot::Mutex<Option<gpu_utils::gpu::CallbackId>>,
     device_recover_callback_id: parking_lot::Mutex<Option<gpu_utils::gpu::CallbackId>>,
+    device_recover_failed_callback_id: parking_lot::Mutex<Option<gpu_utils::gpu::CallbackId>>,
 
     // exit signal is used to stop the rendering loop gracefully.
     // this task handle is used to kill the rendering loop task when needed.
@@ -45,14 +60,24 @@ pub struct ApplicationInstance<
 impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send + Sync + 'static>
     ApplicationInstance<Message, Event, B>
 {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         tokio_runtime: tokio::runtime::Runtime,
         global_resources: GlobalResources,
         windows: Vec<WindowUiConfig<Message, Event>>,
         base_color: Color,
-        renderer: CoreRenderer,
+        compositor: Compositor,
         backend: Arc<B>,
+        run_in_background: bool,
+        power_saving: PowerSaving,
     ) -> Arc<Self> {
+        // the CPU compositor keeps no GPU resources of its own
+        if let Compositor::Gpu(renderer) = &compositor {
+            global_resources
+                .device_recovery()
+                .register(renderer.clone());
+        }
+
         let app = Arc::new(Self {
             tokio_runtime,
             global_resources,
@@ -61,12 +86,17 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
             )),
             not_started_uis: tokio::sync::Mutex::new(windows),
             base_color,
-            renderer,
+            compositor,
             backend,
+            run_in_background,
+            power_saving,
+            background: Background::new(),
             benchmarker: tokio::sync::Mutex::new(utils::benchmark::Benchmark::new(120)),
+            input_replay: parking_lot::Mutex::new(None),
             frame_count: std::sync::atomic::AtomicU64::new(0),
             device_lost_callback_id: parking_lot::Mutex::new(None),
             device_recover_callback_id: parking_lot::Mutex::new(None),
+            device_recover_failed_callback_id: parking_lot::Mutex::new(None),
             render_loop_task_handle: tokio::sync::Mutex::new(None),
         });
 
@@ -82,6 +112,9 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
                         log::warn!("GPU device lost: reason={e:?} info={s}");
 
                         if let Some(app_clone) = app_weak.upgrade() {
+                            // stop rendering until a replacement device is available
+                            app_clone.global_resources.device_recovery().mark_lost();
+
                             // invalidate all caches to avoid using invalid resources
                             for window in app_clone.windows.blocking_read().values() {
                                 window.invalidate_widget_render_cache();
@@ -97,26 +130,35 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
                         log::info!("GPU device recovered");
 
                         if let Some(app) = app_weak.upgrade() {
-                            // texture atlas
-                            app.global_resources
-                                .texture_atlas()
-                                .recover(&device, &queue);
-
-                            // stencil atlas
-                            app.global_resources
-                                .stencil_atlas()
-                                .recover(&device, &queue);
-
-                            // gpu resources
-                            app.global_resources.gpu_resource().recover(&device, &queue);
-
-                            // core renderer
-                            app.renderer.recover(&device, &queue);
+                            // atlases, gpu resources, widget renderers and the core renderer
+                            app.global_resources.device_recovery().recover_with(
+                                &device,
+                                &queue,
+                                || {
+                                    // surfaces and widget-owned gpu state
+                                    for window in app.windows.blocking_read().values() {
+                                        window.update_gpu_device(&device, &queue);
+                                    }
+                                },
+                            );
                         }
                     });
 
+            let app_weak = Arc::downgrade(&app);
+            let device_recover_failed_cbid = app
+                .global_resources
+                .gpu()
+                .add_device_recover_failed_callback(move |e| {
+                    log::error!("GPU device recovery failed: {e}");
+
+                    if let Some(app) = app_weak.upgrade() {
+                        app.global_resources.device_recovery().mark_failed();
+                    }
+                });
+
             *app.device_lost_callback_id.lock() = Some(device_lost_cbid);
             *app.device_recover_callback_id.lock() = Some(devoce_lost_recover_cbid);
+            *app.device_recover_failed_callback_id.lock() = Some(device_recover_failed_cbid);
         }
 
         app
@@ -161,6 +203,17 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         });
     }
 
+    pub fn winit_windows(&self) -> Vec<Arc<winit::window::Window>> {
+        self.tokio_runtime.block_on(async {
+            self.windows
+                .read()
+                .await
+                .values()
+                .map(|window| window.winit_window())
+                .collect()
+        })
+    }
+
     pub fn call_all_setups(&self) {
         log::trace!("ApplicationInstance::call_all_setups: calling setup on all windows");
         self.tokio_runtime.block_on(async {
@@ -188,7 +241,16 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
                 return;
             };
 
+            if self.run_in_background
+                && matches!(event, winit::event::WindowEvent::CloseRequested)
+            {
+                log::debug!("ApplicationInstance::window_event: hiding window id={window_id:?} instead of closing it");
+                window.set_visible(false);
+                return;
+            }
+
             log::trace!("ApplicationInstance::window_event: delivering event to window");
+            let focus_lost = matches!(event, winit::event::WindowEvent::Focused(false));
 
             if let winit::event::WindowEvent::Resized(physical_size) = event {
                 log::trace!("ApplicationInstance::window_event: resize detected {}x{}", physical_size.width, physical_size.height);
@@ -205,6 +267,75 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
                 log::trace!("ApplicationInstance::window_event: widget produced event, forwarding to backend");
                 self.backend.send_event(event).await;
             }
+
+            // a locked pointer is released with the focus
+            if focus_lost && window.unlock_pointer() {
+                log::debug!("ApplicationInstance::window_event: focus loss released the locked pointer");
+                let event = window
+                    .device_input(
+                        DeviceInputData::PointerUnlocked,
+                        self.tokio_runtime.handle(),
+                        &self.global_resources,
+                    )
+                    .await;
+                if let Some(event) = event {
+                    self.backend.send_event(event).await;
+                }
+            }
+
+            drop(windows);
+            self.update_background().await;
+        });
+    }
+
+    /// Moves the app to the background when no window is in use any more, and back to the
+    /// foreground when one is.
+    async fn update_background(&self) {
+        let windows = self.windows.read().await;
+        let now = std::time::Instant::now();
+
+        let event =
+            if !windows.is_empty() && windows.values().all(|window| window.is_in_background()) {
+                if !self.background.enter(now) {
+                    return;
+                }
+                if self.power_saving.pause_animations {
+                    self.global_resources.frame_clock().pause(now);
+                }
+                LifecycleEvent::Background
+            } else {
+                if self.background.leave(now).is_none() {
+                    return;
+                }
+                self.global_resources.frame_clock().unpause(now);
+                LifecycleEvent::Foreground
+            };
+
+        for window in windows.values() {
+            window
+                .notify_lifecycle(event, self.tokio_runtime.handle(), &self.global_resources)
+                .await;
+        }
+    }
+
+    /// Relative mouse motion, passed to the windows whose pointer is locked.
+    pub fn pointer_delta(&self, delta: [f32; 2]) {
+        self.tokio_runtime.block_on(async {
+            for window in self.windows.read().await.values() {
+                if !window.is_pointer_locked() {
+                    continue;
+                }
+                let event = window
+                    .device_input(
+                        DeviceInputData::PointerMotion { delta },
+                        self.tokio_runtime.handle(),
+                        &self.global_resources,
+                    )
+                    .await;
+                if let Some(event) = event {
+                    self.backend.send_event(event).await;
+                }
+            }
         });
     }
 
@@ -225,6 +356,35 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         });
     }
 
+    /// Passes the events the widgets of a window produced outside of input handling to the app.
+    pub fn flush_deferred_events(&self, window_id: winit::window::WindowId) {
+        self.tokio_runtime.block_on(async {
+            let windows = self.windows.read().await;
+            let Some(window) = windows.get(&window_id) else {
+                log::trace!(
+                    "ApplicationInstance::flush_deferred_events: no matching window for id={window_id:?}"
+                );
+                return;
+            };
+            let events = window
+                .take_deferred_events(self.tokio_runtime.handle(), &self.global_resources)
+                .await;
+            for event in events {
+                self.backend.send_event(event).await;
+            }
+        });
+    }
+
+    /// Raw pointer motion, which click-through windows receive while the pointer is over
+    /// other windows.
+    pub fn pointer_motion(&self) {
+        self.tokio_runtime.block_on(async {
+            for window in self.windows.read().await.values() {
+                window.restore_hittest().await;
+            }
+        });
+    }
+
     pub fn user_event(self: &Arc<Self>, message: Message) {
         log::trace!("ApplicationInstance::user_event: received user event");
         let app_instance = self.clone();
@@ -242,18 +402,182 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         });
     }
 
+    /// The widgets under `position` in the window `window_id`, see [`WindowUi::hit_test`].
+    /// Empty when there is no such window.
+    pub fn hit_test(&self, window_id: winit::window::WindowId, position: [f32; 2]) -> HitTestPath {
+        self.tokio_runtime.block_on(async {
+            match self.windows.read().await.get(&window_id) {
+                Some(window) => {
+                    window
+                        .hit_test(
+                            position,
+                            self.tokio_runtime.handle(),
+                            &self.global_resources,
+                        )
+                        .await
+                }
+                None => {
+                    log::trace!(
+                        "ApplicationInstance::hit_test: no matching window for id={window_id:?}"
+                    );
+                    HitTestPath::default()
+                }
+            }
+        })
+    }
+
     pub fn try_recv_command(
         &self,
     ) -> Result<ApplicationCommand, tokio::sync::mpsc::error::TryRecvError> {
         self.global_resources.try_recv_command()
     }
 
+    pub fn set_window_visible(&self, window_id: winit::window::WindowId, visible: bool) {
+        log::debug!(
+            "ApplicationInstance::set_window_visible: window id={window_id:?} visible={visible}"
+        );
+        self.tokio_runtime.block_on(async {
+            if let Some(window) = self.windows.read().await.get(&window_id) {
+                window.set_visible(visible);
+            } else {
+                log::warn!(
+                    "ApplicationInstance::set_window_visible: no window found for id={window_id:?}"
+                );
+            }
+        });
+    }
+
+    pub fn control_window(&self, window_id: winit::window::WindowId, control: WindowControl) {
+        log::debug!("ApplicationInstance::control_window: window id={window_id:?} {control:?}");
+        self.tokio_runtime.block_on(async {
+            if let Some(window) = self.windows.read().await.get(&window_id) {
+                window.control(control);
+            } else {
+                log::warn!(
+                    "ApplicationInstance::control_window: no window found for id={window_id:?}"
+                );
+            }
+        });
+    }
+
+    pub fn set_max_frame_latency(&self, window_id: winit::window::WindowId, latency: u32) {
+        log::debug!(
+            "ApplicationInstance::set_max_frame_latency: window id={window_id:?} latency={latency}"
+        );
+        self.tokio_runtime.block_on(async {
+            if let Some(window) = self.windows.read().await.get(&window_id) {
+                window
+                    .set_max_frame_latency(latency, &self.global_resources.gpu().device())
+                    .await;
+            } else {
+                log::warn!(
+                    "ApplicationInstance::set_max_frame_latency: no window found for id={window_id:?}"
+                );
+            }
+        });
+    }
+
+    pub fn apply_custom_cursor(
+        &self,
+        window_id: winit::window::WindowId,
+        cursor: &Cursor,
+        platform_cursor: winit::window::CustomCursor,
+    ) {
+        self.tokio_runtime.block_on(async {
+            if let Some(window) = self.windows.read().await.get(&window_id) {
+                window.apply_custom_cursor(cursor, platform_cursor);
+            } else {
+                log::warn!(
+                    "ApplicationInstance::apply_custom_cursor: no window found for id={window_id:?}"
+                );
+            }
+        });
+    }
+
+    pub fn show_all_windows(&self) {
+        log::debug!("ApplicationInstance::show_all_windows: showing hidden windows");
+        self.tokio_runtime.block_on(async {
+            for window in self.windows.read().await.values() {
+                if window.is_hidden() {
+                    window.set_visible(true);
+                }
+            }
+        });
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.global_resources.is_suspended()
+    }
+
+    /// Pauses rendering and the animation clock, drops all surfaces and notifies components.
+    pub fn suspend(&self) {
+        if !self.global_resources.suspend() {
+            return;
+        }
+        log::info!("ApplicationInstance::suspend: suspending application");
+        self.tokio_runtime.block_on(async {
+            for window in self.windows.read().await.values() {
+                window
+                    .suspend(self.tokio_runtime.handle(), &self.global_resources)
+                    .await;
+            }
+        });
+    }
+
+    /// Rebuilds GPU resources and surfaces, notifies components and resumes rendering.
+    pub fn resume(&self) {
+        if !self.global_resources.is_suspended() {
+            return;
+        }
+        log::info!("ApplicationInstance::resume: resuming application");
+
+        // the platform may discard GPU memory of a suspended app, so rebuild everything that
+        // is registered for device recovery. a lost device is rebuilt by the gpu callbacks.
+        let gpu = self.global_resources.gpu();
+        if !gpu.is_device_lost() && !gpu.is_recovering() {
+            for window in self.windows.blocking_read().values() {
+                window.invalidate_widget_render_cache();
+            }
+            let (device, queue) = (gpu.device(), gpu.queue());
+            self.global_resources
+                .device_recovery()
+                .recover_with(&device, &queue, || {
+                    for window in self.windows.blocking_read().values() {
+                        window.update_gpu_device(&device, &queue);
+                    }
+                });
+        }
+
+        self.tokio_runtime.block_on(async {
+            for window in self.windows.read().await.values() {
+                window
+                    .resume(self.tokio_runtime.handle(), &self.global_resources)
+                    .await;
+            }
+        });
+
+        // wakes the rendering loop
+        self.global_resources.resume();
+    }
+
+    /// Rebuilds the view of every window after the locale or the fonts changed.
+    pub fn rebuild_all_windows(&self) {
+        log::debug!("ApplicationInstance::rebuild_all_windows: rebuilding all windows");
+        self.tokio_runtime.block_on(async {
+            for window in self.windows.read().await.values() {
+                window.rebuild_view().await;
+            }
+        });
+    }
+
     pub fn close_window(&self, window_id: winit::window::WindowId) {
         log::info!("ApplicationInstance::close_window: closing window id={window_id:?}");
         self.tokio_runtime.block_on(async {
             let mut windows = self.windows.write().await;
             if let Some(window) = windows.remove(&window_id) {
                 drop(window);
+                // pending captures of the window resolve as cancelled
+                drop(self.global_resources.captures().take(window_id));
                 log::info!("ApplicationInstance::close_window: window id={window_id:?} closed");
             } else {
                 log::warn!(
@@ -264,6 +588,75 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
     }
 }
 
+/// Input replay.
+impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send + Sync + 'static>
+    ApplicationInstance<Message, Event, B>
+{
+    pub(crate) fn set_input_replay(&self, recording: InputRecording, speed: ReplaySpeed) {
+        *self.input_replay.lock() = Some((recording, speed));
+    }
+
+    /// Starts replaying the recording set with `set_input_replay`, once.
+    pub fn start_input_replay(self: &Arc<Self>) {
+        let Some((recording, speed)) = self.input_replay.lock().take() else {
+            return;
+        };
+        log::info!(
+            "ApplicationInstance::start_input_replay: replaying {} inputs over {:?} at {speed:?}",
+            recording.events().len(),
+            recording.duration()
+        );
+        let app = Arc::downgrade(self);
+        self.tokio_runtime.spawn(async move {
+            let start = tokio::time::Instant::now();
+            let mut replayers = HashMap::new();
+            for event in recording.events() {
+                // `sleep` waits indefinitely for delays past the end of the clock
+                tokio::time::sleep(speed.delay(event.at()).saturating_sub(start.elapsed())).await;
+                let Some(app) = app.upgrade() else {
+                    return;
+                };
+
+                let windows = app.windows.read().await;
+                for (window_id, window) in windows.iter() {
+                    if let RecordedInput::Resized {
+                        size: [width, height],
+                    } = event.input()
+                    {
+                        window.request_inner_size(*width, *height);
+                        continue;
+                    }
+                    let replayer = match replayers.entry(*window_id) {
+                        std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                        std::collections::hash_map::Entry::Vacant(entry) => {
+                            let Some(replayer) = window.input_replayer().await else {
+                                continue;
+                            };
+                            entry.insert(replayer)
+                        }
+                    };
+                    for data in replayer.replay(event) {
+                        let event = window
+                            .replayed_input(
+                                data,
+                                replayer.pointer_position(),
+                                app.tokio_runtime.handle(),
+                                &app.global_resources,
+                            )
+                            .await;
+                        if let Some(event) = event {
+                            app.backend.send_event(event).await;
+                        }
+                    }
+                }
+                drop(windows);
+                tokio::task::yield_now().await;
+            }
+            log::info!("ApplicationInstance::start_input_replay: replay finished");
+        });
+    }
+}
+
 /// Async rendering loop.
 impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send + Sync + 'static>
     ApplicationInstance<Message, Event, B>
@@ -311,29 +704,104 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
                 Err(tokio::sync::oneshot::error::TryRecvError::Empty) => (),
             }
 
+            // nothing is rendered while the app is suspended
+            if self.global_resources.is_suspended() {
+                log::debug!("ApplicationInstance::rendering_loop: paused while suspended");
+                tokio::select! {
+                    _ = self.global_resources.lifecycle().wait_until_resumed() => continue,
+                    _ = &mut exit_signal => {
+                        log::info!(
+                            "ApplicationInstance::rendering_loop: exit signal received while suspended, stopping rendering loop"
+                        );
+                        break;
+                    }
+                }
+            }
+
+            // the device is lost or being rebuilt; wait for recovery.
+            if !self.global_resources.device_recovery().is_ready() {
+                log::debug!(
+                    "ApplicationInstance::rendering_loop: paused until the device is recovered"
+                );
+                tokio::select! {
+                    _ = self.global_resources.device_recovery().wait_until_ready() => continue,
+                    _ = &mut exit_signal => {
+                        log::info!(
+                            "ApplicationInstance::rendering_loop: exit signal received during device recovery, stopping rendering loop"
+                        );
+                        break;
+                    }
+                }
+            }
+
+            // expire toasts and keep their animations running
+            self.global_resources
+                .toasts()
+                .tick(std::time::Instant::now());
+            // timer callbacks run before the frame they expired in is rendered
+            self.global_resources.fire_timers();
+
             {
                 let windows = self.windows.read().await;
+                let mut rendered = false;
                 for window in windows.values() {
-                    if !window.needs_render().await {
-                        continue;
+                    if window.needs_render().await {
+                        // all windows rendered in this iteration share one frame time
+                        if !rendered {
+                            self.global_resources.tick_frame();
+                        }
+
+                        let mut benchmarker = self.benchmarker.lock().await;
+                        let frame = window.render(
+                            self.tokio_runtime.handle(),
+                            &self.global_resources,
+                            &self.base_color,
+                            &self.compositor,
+                            &mut benchmarker,
+                        );
+                        crate::profiling::profile_future!(
+                            frame,
+                            "frame",
+                            window = ?window.window_id(),
+                            frame = self.frame_count.load(std::sync::atomic::Ordering::Acquire)
+                        )
+                        .await;
+                        rendered = true;
                     }
 
+                    // captures use the cached render nodes, so idle windows serve them too
                     window
-                        .render(
+                        .serve_captures(
                             self.tokio_runtime.handle(),
                             &self.global_resources,
-                            &self.base_color,
-                            &self.renderer,
-                            &mut *self.benchmarker.lock().await,
+                            &self.compositor,
                         )
                         .await;
                 }
+                if rendered {
+                    // evict caches that did not fit the budget this frame
+                    self.global_resources.cache_budget().enforce();
+                    crate::profiling::frame_finished();
+                }
             }
 
             self.frame_count
                 .fetch_add(1, std::sync::atomic::Ordering::AcqRel);
 
-            tokio::task::yield_now().await;
+            match self.power_saving.frame_interval() {
+                Some(interval) if self.background.is_active() => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(interval) => (),
+                        _ = &mut exit_signal => {
+                            log::info!(
+                                "ApplicationInstance::rendering_loop: exit signal received in the background, stopping rendering loop"
+                            );
+                            break;
+                        }
+                    }
+                }
+                _ => tokio::task::yield_now().await,
+            }
         }
 
         {
@@ -0,0 +1,795 @@
+//! Driving a UI from tests.
+//!
+//! [`UiDriver`] runs a component without a window: it builds and lays out the widget tree,
+//! injects synthetic pointer and keyboard input, waits until the UI is idle and answers
+//! queries about the laid-out tree. Integration tests can so exercise real interaction flows
+//! through the same widget code that runs in an app.
+//!
+//! ```ignore
+//! let gpu = Gpu::new(GpuDescriptor::default()).await?;
+//! let counter = Component::new(Some("counter"), 0, view).event_fn(on_event);
+//! let model = counter.model_accessor();
+//!
+//! let mut driver = UiDriver::new(gpu, counter, [400, 300]).await?;
+//! driver.click_label("increment")?;
+//! driver.settle().await?;
+//! assert_eq!(model.read(|count| *count).await, 1);
+//! ```
+//!
+//! The driver needs a GPU because widgets render during a frame, but nothing is presented.
+//! Time is simulated: waiting for animations advances the animation clock frame by frame
+//! instead of sleeping, and clicks, double clicks and long presses do not depend on how fast
+//! the test runs.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use gpu_utils::gpu::Gpu;
+use log::{debug, trace};
+use thiserror::Error;
+use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateFlag};
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, MouseButton, MouseScrollDelta},
+    keyboard::{Key, ModifiersState, NativeKeyCode, PhysicalKey},
+};
+
+use crate::{
+    capture::{CaptureError, CaptureRenderer},
+    context::{ApplicationCommand, GlobalResources, WidgetContext},
+    device_input::{
+        DeviceInput, DeviceInputData, KeyboardState, MouseState, mouse_state::MousePrimaryButton,
+    },
+    input_recording::{InputRecording, InputReplayer, RecordedInput},
+    metrics::Constraints,
+    ui::{AnyWidgetFrame, Background, HitTestPath, WidgetSnapshot, component::AnyComponent, focus},
+};
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+const MAX_SETTLE_FRAMES: usize = 600;
+const DOUBLE_CLICK_THRESHOLD: Duration = Duration::from_millis(300);
+const LONG_PRESS_THRESHOLD: Duration = Duration::from_millis(500);
+const SCROLL_PIXEL_PER_LINE: f32 = 40.0;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AutomationError {
+    #[error("no widget labeled '{0}' is laid out")]
+    WidgetNotFound(String),
+    #[error("the ui was still updating after {frames} frames")]
+    NotSettled { frames: usize },
+}
+
+/// Runs a component headlessly for tests. See the [module documentation](self).
+pub struct UiDriver<Message: 'static, Event: 'static> {
+    resources: GlobalResources,
+    tokio_handle: tokio::runtime::Handle,
+
+    component: Box<dyn AnyComponent<Message, Event>>,
+    widget: Option<Box<dyn AnyWidgetFrame<Event>>>,
+    model_update_detector: UpdateFlag,
+
+    viewport_size: [u32; 2],
+    // widgets render against a background; nothing is drawn into it
+    background: wgpu::Texture,
+    // draws captured subtrees, created on the first capture request
+    capture_renderer: Option<renderer::CoreRenderer>,
+
+    mouse_state: MouseState,
+    keyboard_state: KeyboardState,
+    // simulated time of the injected input
+    input_time: Instant,
+
+    events: Vec<Event>,
+    exit_requested: bool,
+    frame_interval: Duration,
+    max_settle_frames: usize,
+}
+
+impl<Message: 'static, Event: 'static> UiDriver<Message, Event> {
+    /// Sets up `component` in a viewport of `viewport_size` pixels and waits until its first
+    /// frame is idle. Must be called inside a tokio runtime.
+    pub async fn new(
+        gpu: Arc<Gpu>,
+        component: impl AnyComponent<Message, Event>,
+        viewport_size: [u32; 2],
+    ) -> Result<Self, AutomationError> {
+        debug!("UiDriver::new: viewport {viewport_size:?}");
+        let background = create_background(&gpu.device(), viewport_size);
+        let mut driver = Self {
+            resources: GlobalResources::new(gpu),
+            tokio_handle: tokio::runtime::Handle::current(),
+            component: Box::new(component),
+            widget: None,
+            model_update_detector: UpdateFlag::new(),
+            viewport_size,
+            background,
+            capture_renderer: None,
+            mouse_state: new_mouse_state(),
+            keyboard_state: KeyboardState::new(),
+            input_time: Instant::now(),
+            events: Vec::new(),
+            exit_requested: false,
+            frame_interval: FRAME_INTERVAL,
+            max_settle_frames: MAX_SETTLE_FRAMES,
+        };
+
+        driver.component.setup(&driver.application_context());
+        driver.settle().await?;
+        Ok(driver)
+    }
+
+    /// Simulated time between two frames while settling. Defaults to 16 ms.
+    pub fn set_frame_interval(&mut self, interval: Duration) {
+        self.frame_interval = interval;
+    }
+
+    /// Frames [`settle`](Self::settle) renders before giving up. Defaults to 600.
+    pub fn set_max_settle_frames(&mut self, frames: usize) {
+        self.max_settle_frames = frames;
+    }
+
+    pub fn viewport_size(&self) -> [u32; 2] {
+        self.viewport_size
+    }
+
+    /// Resizes the viewport. Takes effect on the next [`settle`](Self::settle).
+    pub fn resize(&mut self, viewport_size: [u32; 2]) {
+        self.viewport_size = viewport_size;
+        self.background = create_background(&self.resources.gpu().device(), viewport_size);
+        if let Some(widget) = &mut self.widget {
+            widget.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        }
+    }
+
+    /// The shared resources of the driven UI, e.g. to set the locale.
+    pub fn resources(&self) -> &GlobalResources {
+        &self.resources
+    }
+}
+
+/// Waiting for the UI.
+impl<Message: 'static, Event: 'static> UiDriver<Message, Event> {
+    /// Renders frames until no model update, redraw or animation is pending and returns how
+    /// many frames it took.
+    ///
+    /// Each frame advances the animation clock by the frame interval. Work spawned by widgets
+    /// gets a chance to run between frames, but the driver does not wait for it to finish
+    /// unless it requests a redraw.
+    pub async fn settle(&mut self) -> Result<usize, AutomationError> {
+        for frame in 1..=self.max_settle_frames {
+            self.step().await;
+
+            if !self.is_busy() {
+                trace!("UiDriver::settle: idle after {frame} frame(s)");
+                return Ok(frame);
+            }
+            self.advance_time(self.frame_interval);
+        }
+
+        debug!(
+            "UiDriver::settle: still busy after {} frames",
+            self.max_settle_frames
+        );
+        Err(AutomationError::NotSettled {
+            frames: self.max_settle_frames,
+        })
+    }
+
+    /// Moves the simulated time forward without rendering, e.g. to let a timeout expire.
+    pub fn advance_time(&mut self, by: Duration) {
+        self.resources.advance_clock(by);
+        self.resources.fire_timers();
+        self.input_time += by;
+    }
+
+    /// Whether a model update or redraw is pending.
+    pub fn is_busy(&self) -> bool {
+        self.model_update_detector.is_true()
+            || self
+                .widget
+                .as_ref()
+                .is_none_or(|widget| widget.need_redraw())
+    }
+
+    // one frame, after which spawned work such as widget preparation gets to run
+    async fn step(&mut self) {
+        self.handle_commands().await;
+        self.frame().await;
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn handle_commands(&mut self) {
+        while let Some(command) = self.resources.try_recv_command_async().await {
+            match command {
+                ApplicationCommand::Exit => self.exit_requested = true,
+                ApplicationCommand::LocaleChanged | ApplicationCommand::FontsChanged => {
+                    if let Some(widget) = &mut self.widget {
+                        widget.invalidate_render_cache();
+                    }
+                    self.model_update_detector.set_true();
+                }
+                ApplicationCommand::HitTest {
+                    position, sender, ..
+                } => {
+                    let _ = sender.send(self.hit_test(position));
+                }
+                ApplicationCommand::FlushDeferredEvents { .. } => {
+                    let ctx = self.widget_context();
+                    if let Some(widget) = &mut self.widget {
+                        self.events.extend(widget.take_deferred_events(&ctx));
+                    }
+                }
+                // there is no window
+                ApplicationCommand::CloseWindow { .. }
+                | ApplicationCommand::SetWindowVisible { .. }
+                | ApplicationCommand::ShowAllWindows
+                | ApplicationCommand::ControlWindow { .. }
+                | ApplicationCommand::SetMaxFrameLatency { .. }
+                | ApplicationCommand::CreateCursor { .. } => {}
+            }
+        }
+    }
+
+    /// Builds or updates the widget tree, then lays it out and renders it.
+    async fn frame(&mut self) {
+        if self.widget.is_none() || self.model_update_detector.is_true() {
+            let localization = self.widget_context().localization();
+            let dom = self.component.view(localization).await;
+            if let Some(widget) = &mut self.widget
+                && widget.update_widget_tree(&*dom).await.is_err()
+            {
+                self.widget = None;
+            }
+            let widget = self.widget.get_or_insert_with(|| dom.build_widget_tree());
+            crate::ui::global_key::release_detached().await;
+
+            self.model_update_detector = UpdateFlag::new();
+            widget
+                .set_model_update_notifier(&self.model_update_detector.notifier())
+                .await;
+            widget.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        }
+
+        self.resources.tick_frame();
+        self.resources.fire_timers();
+        let ctx = self.widget_context();
+        let viewport_size = self.viewport_size.map(|v| v as f32);
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+
+        let constraints = Constraints::new([0.0, viewport_size[0]], [0.0, viewport_size[1]]);
+        let preferred_size = widget.measure(&constraints, &ctx);
+        let final_size = [
+            preferred_size[0].clamp(0.0, viewport_size[0]),
+            preferred_size[1].clamp(0.0, viewport_size[1]),
+        ];
+        widget.arrange(final_size, &ctx);
+        widget.update_lifecycle(Some([[0.0, 0.0], final_size]), &ctx);
+        widget.prepare(Some([[0.0, 0.0], final_size]), &ctx);
+
+        let view = self.background.create_view(&Default::default());
+        widget.render(Background::new(&view, [0.0, 0.0]), &ctx);
+        self.serve_captures();
+        self.resources.cache_budget().enforce();
+    }
+
+    /// Serves the subtrees requested with `WidgetContext::capture_subtree` and `export_svg`,
+    /// waiting for the readbacks since nothing else runs meanwhile.
+    fn serve_captures(&mut self) {
+        let requests = self
+            .resources
+            .captures()
+            .take(winit::window::WindowId::dummy());
+        if requests.is_empty() {
+            return;
+        }
+        let device = self.resources.gpu().device();
+        let capture_renderer = CaptureRenderer {
+            renderer: Some(
+                self.capture_renderer
+                    .get_or_insert_with(|| renderer::CoreRenderer::new(&device)),
+            ),
+            device: device.clone(),
+            queue: self.resources.gpu().queue(),
+            texture_atlas: self.resources.texture_atlas(),
+            stencil_atlas_texture: self.resources.stencil_atlas().texture(),
+        };
+        for request in requests {
+            let subtree = self
+                .widget
+                .as_ref()
+                .and_then(|widget| widget.find_rendered(None, &request.target))
+                .ok_or_else(|| CaptureError::WidgetNotFound(request.target.clone()));
+            capture_renderer.start(subtree, request.output).finish();
+        }
+    }
+}
+
+/// Injecting input.
+///
+/// Input is delivered to the tree as laid out by the last [`settle`](Self::settle); call it
+/// after each step to let the UI react.
+impl<Message: 'static, Event: 'static> UiDriver<Message, Event> {
+    /// Delivers `data` with the pointer at its current position.
+    pub fn inject(&mut self, data: DeviceInputData) {
+        self.deliver(data, self.mouse_state.position());
+    }
+
+    fn deliver(&mut self, data: DeviceInputData, pointer_position: [f32; 2]) {
+        let ctx = self.widget_context();
+        let Some(widget) = &mut self.widget else {
+            return;
+        };
+        let input = DeviceInput::new(pointer_position, data, None);
+        // Tab moves keyboard focus as in a window
+        if let DeviceInputData::Keyboard(key_input) = input.event()
+            && focus::tab_navigation(&mut **widget, key_input, &ctx)
+        {
+            return;
+        }
+        if let Some(event) = widget.device_input(&input, &ctx) {
+            self.events.push(event);
+        }
+    }
+
+    /// Sends `message` to the component's update function.
+    pub fn send(&mut self, message: &Message) {
+        self.component.update(message, &self.application_context());
+    }
+
+    pub fn mouse_move(&mut self, position: [f32; 2]) {
+        let data = self.mouse_state.cursor_moved(PhysicalPosition::new(
+            position[0] as f64,
+            position[1] as f64,
+        ));
+        self.inject(data);
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton) {
+        self.mouse_button(button, ElementState::Pressed);
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton) {
+        self.mouse_button(button, ElementState::Released);
+    }
+
+    /// Moves the pointer to `position` and clicks the left button once.
+    pub fn click(&mut self, position: [f32; 2]) {
+        self.click_with(position, MouseButton::Left, 1);
+    }
+
+    pub fn right_click(&mut self, position: [f32; 2]) {
+        self.click_with(position, MouseButton::Right, 1);
+    }
+
+    pub fn double_click(&mut self, position: [f32; 2]) {
+        self.click_with(position, MouseButton::Left, 2);
+    }
+
+    /// Presses the left button at `position`, holds it until it counts as a long press and
+    /// releases it.
+    pub fn long_press(&mut self, position: [f32; 2]) {
+        self.mouse_move(position);
+        self.separate_from_last_click();
+        self.mouse_down(MouseButton::Left);
+        self.input_time += LONG_PRESS_THRESHOLD;
+        for data in self.mouse_state.long_pressing_detection_at(self.input_time) {
+            self.inject(data);
+        }
+        self.mouse_up(MouseButton::Left);
+    }
+
+    /// Presses the left button at `from`, moves to `to` in `steps` moves and releases it.
+    pub fn drag(&mut self, from: [f32; 2], to: [f32; 2], steps: usize) {
+        self.mouse_move(from);
+        self.separate_from_last_click();
+        self.mouse_down(MouseButton::Left);
+        let steps = steps.max(1);
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            self.mouse_move([
+                from[0] + (to[0] - from[0]) * t,
+                from[1] + (to[1] - from[1]) * t,
+            ]);
+        }
+        self.mouse_up(MouseButton::Left);
+    }
+
+    /// Scrolls by `delta` pixels with the pointer at `position`.
+    pub fn scroll(&mut self, position: [f32; 2], delta: [f32; 2]) {
+        self.mouse_move(position);
+        let data =
+            self.mouse_state
+                .mouse_wheel(MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+                    delta[0] as f64,
+                    delta[1] as f64,
+                )));
+        self.inject(data);
+    }
+
+    /// Clicks the center of the first widget labeled `label`.
+    pub fn click_label(&mut self, label: &str) -> Result<(), AutomationError> {
+        let center = self.find(label)?.center();
+        self.click(center);
+        Ok(())
+    }
+
+    /// Modifier keys held from now on.
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.keyboard_state.modifiers_changed(modifiers);
+    }
+
+    pub fn key_down(&mut self, physical_key: PhysicalKey, logical_key: Key) {
+        let text = key_text(&logical_key);
+        let data = self.keyboard_state.synthetic_input(
+            physical_key,
+            logical_key,
+            text.as_deref(),
+            ElementState::Pressed,
+        );
+        self.inject(data);
+    }
+
+    pub fn key_up(&mut self, physical_key: PhysicalKey, logical_key: Key) {
+        let data = self.keyboard_state.synthetic_input(
+            physical_key,
+            logical_key,
+            None,
+            ElementState::Released,
+        );
+        self.inject(data);
+    }
+
+    /// Presses and releases `physical_key`, which produces `logical_key`.
+    pub fn press_key(&mut self, physical_key: PhysicalKey, logical_key: Key) {
+        self.key_down(physical_key, logical_key.clone());
+        self.key_up(physical_key, logical_key);
+    }
+
+    /// Types `text` one character at a time, as key presses with that text.
+    pub fn type_text(&mut self, text: &str) {
+        let physical_key = PhysicalKey::Unidentified(NativeKeyCode::Unidentified);
+        for c in text.chars() {
+            let key = Key::Character(c.to_string().into());
+            self.press_key(physical_key, key);
+        }
+    }
+
+    fn mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        if let Some(data) = self
+            .mouse_state
+            .mouse_input_at(button, state, self.input_time)
+        {
+            self.inject(data);
+        }
+    }
+
+    fn click_with(&mut self, position: [f32; 2], button: MouseButton, count: u32) {
+        self.mouse_move(position);
+        self.separate_from_last_click();
+        for _ in 0..count {
+            self.mouse_down(button);
+            self.mouse_up(button);
+        }
+    }
+
+    // keeps a new gesture from continuing the click combo of the previous one
+    fn separate_from_last_click(&mut self) {
+        self.input_time += DOUBLE_CLICK_THRESHOLD + Duration::from_millis(1);
+    }
+}
+
+/// Replaying recorded input.
+impl<Message: 'static, Event: 'static> UiDriver<Message, Event> {
+    /// Replays `recording` in simulated time, rendering frames at the frame interval between
+    /// its inputs, and settles after the last one. Recorded resizes resize the viewport.
+    ///
+    /// The pointer and keys of the recording are tracked apart from those of the other input
+    /// methods. See [`input_recording`](crate::input_recording).
+    pub async fn replay(&mut self, recording: &InputRecording) -> Result<(), AutomationError> {
+        debug!(
+            "UiDriver::replay: {} inputs over {:?}",
+            recording.events().len(),
+            recording.duration()
+        );
+        let mut replayer = InputReplayer::new(new_mouse_state());
+        let mut now = Duration::ZERO;
+        for event in recording.events() {
+            while !self.frame_interval.is_zero() && now + self.frame_interval <= event.at() {
+                self.step().await;
+                self.advance_time(self.frame_interval);
+                now += self.frame_interval;
+            }
+            self.advance_time(event.at().saturating_sub(now));
+            now = now.max(event.at());
+
+            if let RecordedInput::Resized { size } = event.input() {
+                self.resize(*size);
+                continue;
+            }
+            for data in replayer.replay(event) {
+                self.deliver(data, replayer.pointer_position());
+            }
+        }
+        self.settle().await.map(|_| ())
+    }
+}
+
+/// Querying the UI.
+impl<Message: 'static, Event: 'static> UiDriver<Message, Event> {
+    /// The widgets under `position`, see [`hit_test`](crate::ui::hit_test()).
+    pub fn hit_test(&self, position: [f32; 2]) -> HitTestPath {
+        match &self.widget {
+            Some(widget) => crate::ui::hit_test(&**widget, position, &self.widget_context()),
+            None => HitTestPath::default(),
+        }
+    }
+
+    /// The laid-out widget tree.
+    pub fn snapshot(&self) -> Option<WidgetSnapshot> {
+        self.widget
+            .as_deref()
+            .and_then(|widget| crate::ui::snapshot(widget))
+    }
+
+    /// The first laid-out widget labeled `label`.
+    pub fn find(&self, label: &str) -> Result<WidgetSnapshot, AutomationError> {
+        self.snapshot()
+            .and_then(|snapshot| snapshot.find(label).cloned())
+            .ok_or_else(|| AutomationError::WidgetNotFound(label.to_string()))
+    }
+
+    /// Events emitted by the component since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Whether the UI asked the app to exit.
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    fn widget_context(&self) -> WidgetContext {
+        self.resources.headless_widget_context(&self.tokio_handle)
+    }
+
+    fn application_context(&self) -> crate::context::ApplicationContext {
+        self.resources
+            .headless_application_context(&self.tokio_handle)
+    }
+}
+
+fn new_mouse_state() -> MouseState {
+    MouseState::new(
+        DOUBLE_CLICK_THRESHOLD,
+        LONG_PRESS_THRESHOLD,
+        MousePrimaryButton::Left,
+        SCROLL_PIXEL_PER_LINE,
+    )
+    .expect("double click threshold is shorter than long press threshold")
+}
+
+fn create_background(device: &wgpu::Device, [width, height]: [u32; 2]) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("UiDriver background"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+/// Text a key press produces.
+fn key_text(key: &Key) -> Option<String> {
+    match key {
+        Key::Character(c) => Some(c.to_string()),
+        Key::Named(named) => named.to_text().map(str::to_string),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use renderer::render_node::RenderNode;
+
+    use crate::{
+        context::ApplicationContext,
+        device_input::{ElementState, MouseInput, MouseLogicalButton},
+        metrics::Arrangement,
+        ui::{
+            ChildFrameLinker, Dom, InvalidationHandle, Widget, WidgetFrame,
+            component::{Component, ModelAccessor},
+            widget::AnyWidget,
+        },
+    };
+
+    async fn noop_gpu() -> Arc<Gpu> {
+        let (instance, adapter, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        Gpu::from_device(
+            instance,
+            adapter,
+            device,
+            queue,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+        )
+    }
+
+    /// A 100x40 button that emits its label when clicked and, while `animated`, redraws on
+    /// every frame.
+    struct Pad {
+        label: &'static str,
+        animated: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Dom<String> for Pad {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::new(
+                Some(self.label.to_string()),
+                vec![],
+                vec![],
+                PadNode {
+                    label: self.label,
+                    animated: self.animated,
+                    linker: None,
+                },
+            ))
+        }
+    }
+
+    struct PadNode {
+        label: &'static str,
+        animated: bool,
+        linker: Option<ChildFrameLinker>,
+    }
+
+    impl Widget<Pad, String, ()> for PadNode {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a Pad,
+            cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, (), u128)> {
+            if self.animated != dom.animated {
+                self.animated = dom.animated;
+                if let Some(handle) = cache_invalidator {
+                    handle.redraw_next_frame();
+                }
+            }
+            vec![]
+        }
+
+        fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+            self.linker = Some(linker);
+        }
+
+        fn device_input(
+            &mut self,
+            bounds: [f32; 2],
+            event: &DeviceInput,
+            _: &mut [(&mut dyn AnyWidget<String>, &mut (), &Arrangement)],
+            _: InvalidationHandle,
+            _: &WidgetContext,
+        ) -> Option<String> {
+            let inside = event.mouse_position().is_some_and(|[x, y]| {
+                (0.0..=bounds[0]).contains(&x) && (0.0..=bounds[1]).contains(&y)
+            });
+            match event.event() {
+                DeviceInputData::MouseInput {
+                    event:
+                        Some(MouseInput::Click {
+                            click_state: ElementState::Released(_),
+                            button: MouseLogicalButton::Primary,
+                        }),
+                    ..
+                } if inside => Some(self.label.to_string()),
+                _ => None,
+            }
+        }
+
+        fn measure(
+            &self,
+            _: &Constraints,
+            _: &[(&dyn AnyWidget<String>, &())],
+            _: &WidgetContext,
+        ) -> [f32; 2] {
+            [100.0, 40.0]
+        }
+
+        fn arrange(
+            &self,
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &())],
+            _: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &(), &Arrangement)],
+            _: Background,
+            _: &WidgetContext,
+        ) -> RenderNode {
+            if self.animated
+                && let Some(linker) = &self.linker
+            {
+                linker.redraw_next_frame();
+            }
+            RenderNode::new()
+        }
+    }
+
+    /// A pad that counts its clicks in the model, passes them on and animates once clicked
+    /// if `animate_when_clicked`.
+    fn counter(animate_when_clicked: bool) -> Component<u32, (), String> {
+        Component::new(Some("counter"), 0, move |count: &u32| {
+            Box::new(Pad {
+                label: "increment",
+                animated: animate_when_clicked && *count > 0,
+            }) as Box<dyn Dom<String>>
+        })
+        .event_fn(
+            |event: String, model: &ModelAccessor<u32>, _: &ApplicationContext| {
+                let model = model.clone();
+                tokio::spawn(async move { model.update(|count| *count += 1).await });
+                Some(event)
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn clicking_a_labeled_button_updates_the_model_and_emits_an_event() {
+        let counter = counter(false);
+        let model = counter.model_accessor();
+        let mut driver = UiDriver::new(noop_gpu().await, counter, [400, 300])
+            .await
+            .unwrap();
+        assert_eq!(
+            driver.find("increment").unwrap().bounds,
+            [[0.0, 0.0], [100.0, 40.0]]
+        );
+
+        driver.click_label("increment").unwrap();
+        driver.settle().await.unwrap();
+        assert_eq!(driver.take_events(), ["increment"]);
+        assert_eq!(model.read(|count| *count).await, 1);
+
+        // outside of the button
+        driver.click([200.0, 200.0]);
+        driver.settle().await.unwrap();
+        assert!(driver.take_events().is_empty());
+        assert_eq!(
+            driver.click_label("decrement"),
+            Err(AutomationError::WidgetNotFound("decrement".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn settling_gives_up_while_a_widget_redraws_every_frame() {
+        let mut driver = UiDriver::new(noop_gpu().await, counter(true), [400, 300])
+            .await
+            .unwrap();
+        driver.set_max_settle_frames(10);
+
+        driver.click_label("increment").unwrap();
+        assert_eq!(
+            driver.settle().await,
+            Err(AutomationError::NotSettled { frames: 10 })
+        );
+        assert!(driver.is_busy());
+    }
+}
@@ -0,0 +1,321 @@
+//! Memory accounting and eviction of the layout and render caches of widget frames.
+//!
+//! Every [`WidgetFrame`](crate::ui::WidgetFrame) reports the estimated size of its cached
+//! arrangements and render node to the application's [`CacheBudget`]. Once per frame, after
+//! all windows rendered, the budget sums up what is cached and, if that exceeds the limit,
+//! evicts the least recently used caches until it fits again.
+//!
+//! Only caches whose render node is referenced by nothing else are evicted: a node that is
+//! part of a window's last frame or of an ancestor's cached node stays in memory anyway, and
+//! dropping the cache entry would only cost a re-render. In practice this evicts widgets that
+//! are kept in the tree but no longer drawn, e.g. hidden pages or pooled list items.
+//!
+//! Hit, miss and eviction counters are available as [`CacheStats`] through
+//! [`WidgetContext::cache_stats`](crate::context::WidgetContext::cache_stats) and
+//! [`ApplicationContext::cache_stats`](crate::context::ApplicationContext::cache_stats).
+
+use std::sync::Weak;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use log::{debug, trace};
+use parking_lot::Mutex;
+
+/// Default limit of [`CacheBudget`]: 256 MiB.
+pub const DEFAULT_CACHE_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Size and recency of one cache, as reported to the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CacheUsage {
+    /// Estimated bytes held by the cache.
+    pub bytes: usize,
+    /// Budget tick of the frame that last used the cache.
+    pub last_used: u64,
+    /// Whether evicting the cache frees its memory.
+    pub evictable: bool,
+}
+
+/// A cache whose memory is managed by a [`CacheBudget`].
+pub(crate) trait BudgetedCache: Send + Sync {
+    /// Current usage, or `None` while the cache is in use on another thread.
+    fn usage(&self) -> Option<CacheUsage>;
+
+    /// Drops the cached data. Returns `false` if the cache is in use and was left untouched.
+    fn evict(&self) -> bool;
+}
+
+/// Snapshot of the counters of a [`CacheBudget`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of registered caches.
+    pub entries: usize,
+    /// Estimated bytes held by all caches at the end of the last frame.
+    pub used_bytes: usize,
+    /// The limit caches are evicted down to.
+    pub budget_bytes: usize,
+    pub render_hits: u64,
+    pub render_misses: u64,
+    pub layout_hits: u64,
+    pub layout_misses: u64,
+    /// Caches evicted to stay within the budget since the application started.
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Share of render cache lookups that hit, or `None` before the first lookup.
+    pub fn render_hit_rate(&self) -> Option<f32> {
+        hit_rate(self.render_hits, self.render_misses)
+    }
+
+    /// Share of layout cache lookups that hit, or `None` before the first lookup.
+    pub fn layout_hit_rate(&self) -> Option<f32> {
+        hit_rate(self.layout_hits, self.layout_misses)
+    }
+}
+
+fn hit_rate(hits: u64, misses: u64) -> Option<f32> {
+    let total = hits + misses;
+    (total > 0).then(|| hits as f32 / total as f32)
+}
+
+/// Global memory budget of widget caches. Shared by all windows of an application.
+pub struct CacheBudget {
+    budget: AtomicUsize,
+    // incremented by `enforce`, i.e. once per frame
+    tick: AtomicU64,
+    caches: Mutex<Vec<Weak<dyn BudgetedCache>>>,
+
+    // results of the last `enforce`
+    entries: AtomicUsize,
+    used: AtomicUsize,
+
+    render_hits: AtomicU64,
+    render_misses: AtomicU64,
+    layout_hits: AtomicU64,
+    layout_misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl Default for CacheBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_BUDGET)
+    }
+}
+
+impl CacheBudget {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget: AtomicUsize::new(budget_bytes),
+            tick: AtomicU64::new(0),
+            caches: Mutex::new(Vec::new()),
+            entries: AtomicUsize::new(0),
+            used: AtomicUsize::new(0),
+            render_hits: AtomicU64::new(0),
+            render_misses: AtomicU64::new(0),
+            layout_hits: AtomicU64::new(0),
+            layout_misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget.load(Ordering::Relaxed)
+    }
+
+    /// Changes the limit. Takes effect at the end of the next frame.
+    pub fn set_budget(&self, budget_bytes: usize) {
+        self.budget.store(budget_bytes, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.load(Ordering::Relaxed),
+            used_bytes: self.used.load(Ordering::Relaxed),
+            budget_bytes: self.budget(),
+            render_hits: self.render_hits.load(Ordering::Relaxed),
+            render_misses: self.render_misses.load(Ordering::Relaxed),
+            layout_hits: self.layout_hits.load(Ordering::Relaxed),
+            layout_misses: self.layout_misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The tick caches record as their last use. Caches used in the current tick are never
+    /// evicted.
+    pub(crate) fn tick(&self) -> u64 {
+        self.tick.load(Ordering::Acquire)
+    }
+
+    /// Starts tracking `cache`. It is forgotten once dropped.
+    pub(crate) fn register(&self, cache: Weak<dyn BudgetedCache>) {
+        self.caches.lock().push(cache);
+    }
+
+    pub(crate) fn record_render(&self, hit: bool) {
+        let counter = if hit {
+            &self.render_hits
+        } else {
+            &self.render_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_layout(&self, hit: bool) {
+        let counter = if hit {
+            &self.layout_hits
+        } else {
+            &self.layout_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Evicts least recently used caches until the total fits the budget, then starts the
+    /// next tick. Called once per frame after all windows rendered.
+    pub(crate) fn enforce(&self) {
+        let mut caches = self.caches.lock();
+        let current = self.tick.fetch_add(1, Ordering::AcqRel);
+
+        let mut used = 0usize;
+        let mut candidates = Vec::new();
+        caches.retain(|cache| {
+            let Some(cache) = cache.upgrade() else {
+                return false;
+            };
+            if let Some(usage) = cache.usage() {
+                used += usage.bytes;
+                if usage.evictable && usage.last_used < current {
+                    candidates.push((usage.last_used, usage.bytes, cache));
+                }
+            }
+            true
+        });
+
+        let budget = self.budget();
+        if used > budget {
+            debug!(
+                "CacheBudget::enforce: {used} bytes cached over budget of {budget}, {} candidates",
+                candidates.len()
+            );
+            candidates.sort_by_key(|(last_used, _, _)| *last_used);
+            for (_, bytes, cache) in candidates {
+                if used <= budget {
+                    break;
+                }
+                if cache.evict() {
+                    used -= bytes;
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        trace!(
+            "CacheBudget::enforce: {} caches hold {used} bytes",
+            caches.len()
+        );
+        self.entries.store(caches.len(), Ordering::Relaxed);
+        self.used.store(used, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    struct TestCache {
+        state: Mutex<Option<CacheUsage>>,
+    }
+
+    impl TestCache {
+        fn new(bytes: usize, last_used: u64) -> Arc<Self> {
+            Arc::new(Self {
+                state: Mutex::new(Some(CacheUsage {
+                    bytes,
+                    last_used,
+                    evictable: true,
+                })),
+            })
+        }
+
+        fn is_evicted(&self) -> bool {
+            self.state.lock().is_none()
+        }
+    }
+
+    impl BudgetedCache for TestCache {
+        fn usage(&self) -> Option<CacheUsage> {
+            Some(self.state.lock().unwrap_or(CacheUsage {
+                bytes: 0,
+                last_used: 0,
+                evictable: false,
+            }))
+        }
+
+        fn evict(&self) -> bool {
+            *self.state.lock() = None;
+            true
+        }
+    }
+
+    fn register(budget: &CacheBudget, cache: &Arc<TestCache>) {
+        let cache: Arc<dyn BudgetedCache> = cache.clone();
+        budget.register(Arc::downgrade(&cache));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_until_within_budget() {
+        let budget = CacheBudget::new(250);
+        // advance to tick 3 so the caches below are from earlier frames
+        for _ in 0..3 {
+            budget.enforce();
+        }
+
+        let oldest = TestCache::new(100, 0);
+        let older = TestCache::new(100, 1);
+        let recent = TestCache::new(100, 2);
+        for cache in [&recent, &oldest, &older] {
+            register(&budget, cache);
+        }
+
+        budget.enforce();
+        assert!(oldest.is_evicted());
+        assert!(!older.is_evicted());
+        assert!(!recent.is_evicted());
+
+        let stats = budget.stats();
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.used_bytes, 200);
+        assert_eq!(stats.evictions, 1);
+    }
+
+    #[test]
+    fn keeps_caches_used_in_the_current_frame() {
+        let budget = CacheBudget::new(0);
+        let current = TestCache::new(100, budget.tick());
+        register(&budget, &current);
+
+        budget.enforce();
+        assert!(!current.is_evicted());
+        assert_eq!(budget.stats().used_bytes, 100);
+    }
+
+    #[test]
+    fn forgets_dropped_caches_and_counts_lookups() {
+        let budget = CacheBudget::default();
+        let cache = TestCache::new(100, 0);
+        register(&budget, &cache);
+        drop(cache);
+
+        budget.record_render(true);
+        budget.record_render(true);
+        budget.record_render(false);
+        budget.record_layout(false);
+        budget.enforce();
+
+        let stats = budget.stats();
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.used_bytes, 0);
+        assert_eq!(stats.render_hit_rate(), Some(2.0 / 3.0));
+        assert_eq!(stats.layout_hit_rate(), Some(0.0));
+    }
+}
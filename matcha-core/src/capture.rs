@@ -0,0 +1,502 @@
+//! Rendering a widget subtree into an image or an SVG document.
+//!
+//! [`WidgetContext::capture_subtree`](crate::context::WidgetContext::capture_subtree) renders
+//! the widget with a label (or a key) into an offscreen texture at its laid-out size, times a
+//! scale, and reads it back into an [`RgbaImage`], e.g. for an "export as PNG" feature:
+//!
+//! ```ignore
+//! let capture = ctx.capture_subtree("chart", 2.0);
+//! tokio::spawn(async move {
+//!     if let Ok(image) = capture.await {
+//!         let _ = image.save("chart.png");
+//!     }
+//! });
+//! ```
+//!
+//! Requests are served by the render loop of the window, from the render node the widget
+//! cached in its last frame, so the widget must have been laid out and rendered at least once.
+//! The border box of the widget is captured, without its margin. Pixels are sRGB encoded over
+//! a transparent background.
+//!
+//! [`WidgetContext::export_svg`](crate::context::WidgetContext::export_svg) writes the same
+//! subtree as an SVG document instead, for printing or report generation; convert it to PDF
+//! with any SVG tool. Clips, stencils and opacity stay vector structure, while widget content
+//! is embedded as the images it was rasterized into, see [`renderer::svg_export`].
+
+use std::sync::Arc;
+
+use gpu_utils::texture_atlas::TextureAtlas;
+use log::{debug, trace, warn};
+use parking_lot::Mutex;
+use renderer::{CoreRenderer, RenderNode};
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+pub use image::RgbaImage;
+
+const CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// The widget to capture: the first one in depth-first order with the label, or with the key
+/// among its siblings, or the root widget of the window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureTarget {
+    Label(String),
+    Id(u128),
+    Root,
+}
+
+impl CaptureTarget {
+    pub(crate) fn matches(&self, label: Option<&str>, id: Option<u128>) -> bool {
+        match self {
+            Self::Label(target) => label == Some(target.as_str()),
+            Self::Id(target) => id == Some(*target),
+            // only the root widget has no key
+            Self::Root => id.is_none(),
+        }
+    }
+}
+
+impl From<&str> for CaptureTarget {
+    fn from(label: &str) -> Self {
+        Self::Label(label.to_string())
+    }
+}
+
+impl From<String> for CaptureTarget {
+    fn from(label: String) -> Self {
+        Self::Label(label)
+    }
+}
+
+impl From<u128> for CaptureTarget {
+    fn from(id: u128) -> Self {
+        Self::Id(id)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CaptureError {
+    #[error("no rendered widget matches {0:?}")]
+    WidgetNotFound(CaptureTarget),
+    #[error("the widget has an empty size")]
+    Empty,
+    #[error("the capture of {width}x{height} pixels exceeds the texture limit of {limit}")]
+    TooLarge { width: u32, height: u32, limit: u32 },
+    #[error("rendering the capture failed: {0}")]
+    Render(String),
+    #[error("the window was closed before the capture was rendered")]
+    Cancelled,
+}
+
+/// The render node a widget cached in its last frame, found by
+/// [`AnyWidgetFrame::find_rendered`](crate::ui::AnyWidgetFrame::find_rendered).
+pub struct CapturedSubtree {
+    pub(crate) node: Arc<RenderNode>,
+    // border box of the widget in the node's coordinates
+    pub(crate) border_box: [[f32; 2]; 2],
+}
+
+pub(crate) struct CaptureRequest {
+    pub window_id: winit::window::WindowId,
+    pub target: CaptureTarget,
+    pub output: CaptureOutput,
+}
+
+/// What a capture produces, and where to send it.
+pub(crate) enum CaptureOutput {
+    Image {
+        scale: f32,
+        sender: oneshot::Sender<Result<RgbaImage, CaptureError>>,
+    },
+    Svg {
+        sender: oneshot::Sender<Result<String, CaptureError>>,
+    },
+}
+
+/// Capture requests of all windows, waiting for the render loop.
+#[derive(Default)]
+pub(crate) struct CaptureQueue {
+    requests: Mutex<Vec<CaptureRequest>>,
+}
+
+impl CaptureQueue {
+    pub fn push(&self, request: CaptureRequest) {
+        self.requests.lock().push(request);
+    }
+
+    /// Removes and returns the requests for `window_id`.
+    pub fn take(&self, window_id: winit::window::WindowId) -> Vec<CaptureRequest> {
+        let mut requests = self.requests.lock();
+        if requests.is_empty() {
+            return Vec::new();
+        }
+        let (taken, kept) = std::mem::take(&mut *requests)
+            .into_iter()
+            .partition(|request| request.window_id == window_id);
+        *requests = kept;
+        taken
+    }
+}
+
+/// GPU resources a capture is rendered with.
+pub(crate) struct CaptureRenderer<'a> {
+    /// `None` rasterizes captures on the CPU, see
+    /// [`RenderBackend::Software`](crate::render_backend::RenderBackend::Software).
+    pub renderer: Option<&'a CoreRenderer>,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub texture_atlas: &'a TextureAtlas,
+    pub stencil_atlas_texture: wgpu::Texture,
+}
+
+impl CaptureRenderer<'_> {
+    /// Submits the GPU work for `output` of `subtree`. Finishing the returned capture blocks,
+    /// so run it off the render loop.
+    pub fn start(
+        &self,
+        subtree: Result<CapturedSubtree, CaptureError>,
+        output: CaptureOutput,
+    ) -> PendingCapture {
+        match output {
+            CaptureOutput::Image { scale, sender } => PendingCapture::Image {
+                readback: subtree.and_then(|subtree| self.render(&subtree, scale)),
+                sender,
+            },
+            CaptureOutput::Svg { sender } => PendingCapture::Svg {
+                export: subtree.map(|subtree| SvgExport {
+                    subtree,
+                    device: self.device.clone(),
+                    queue: self.queue.clone(),
+                }),
+                sender,
+            },
+        }
+    }
+
+    /// Renders `subtree` at `scale` and submits a copy into a mappable buffer, or prepares it
+    /// for the CPU. The returned readback blocks until the GPU finished, so run it off the
+    /// render loop.
+    pub fn render(
+        &self,
+        subtree: &CapturedSubtree,
+        scale: f32,
+    ) -> Result<CaptureReadback, CaptureError> {
+        let [min, max] = subtree.border_box;
+        let logical_size = [(max[0] - min[0]) * scale, (max[1] - min[1]) * scale];
+        let [width, height] = logical_size.map(|v| v.ceil().max(0.0) as u32);
+        if width == 0 || height == 0 {
+            return Err(CaptureError::Empty);
+        }
+        let limit = self.device.limits().max_texture_dimension_2d;
+        if width > limit || height > limit {
+            return Err(CaptureError::TooLarge {
+                width,
+                height,
+                limit,
+            });
+        }
+        trace!("CaptureRenderer::render: {width}x{height} pixels at scale {scale}");
+
+        // move the border box to the origin and scale it to the target
+        let transform = nalgebra::Matrix4::new_scaling(scale)
+            * nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(-min[0], -min[1], 0.0));
+        let node = RenderNode::new().add_child(subtree.node.clone(), transform);
+
+        let Some(renderer) = self.renderer else {
+            return Ok(CaptureReadback::Software {
+                device: self.device.clone(),
+                queue: self.queue.clone(),
+                node,
+                size: [width, height],
+            });
+        };
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Capture Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: CAPTURE_FORMAT,
+            // sampled by backdrop blurs
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // a failed layer is drawn as an ordinary subtree, so keep rendering
+        if let Err(e) = renderer.render_layers(
+            &self.device,
+            &self.queue,
+            &node,
+            self.texture_atlas,
+            &self.stencil_atlas_texture,
+        ) {
+            warn!("CaptureRenderer::render: rendering layers failed: {e:?}");
+        }
+        renderer
+            .render(
+                &self.device,
+                &self.queue,
+                CAPTURE_FORMAT,
+                &view,
+                [width as f32, height as f32],
+                &node,
+                wgpu::Color::TRANSPARENT,
+                &self.texture_atlas.texture(),
+                &self.stencil_atlas_texture,
+            )
+            .map_err(|e| CaptureError::Render(format!("{e:?}")))?;
+
+        // buffer rows must be aligned for the copy
+        let row_pitch = width * 4;
+        let padded_row_pitch = row_pitch.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Readback Buffer"),
+            size: u64::from(padded_row_pitch) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_pitch),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        Ok(CaptureReadback::Gpu {
+            device: self.device.clone(),
+            buffer,
+            size: [width, height],
+            padded_row_pitch,
+        })
+    }
+}
+
+/// A capture waiting for the GPU.
+pub(crate) enum PendingCapture {
+    Image {
+        readback: Result<CaptureReadback, CaptureError>,
+        sender: oneshot::Sender<Result<RgbaImage, CaptureError>>,
+    },
+    Svg {
+        export: Result<SvgExport, CaptureError>,
+        sender: oneshot::Sender<Result<String, CaptureError>>,
+    },
+}
+
+impl PendingCapture {
+    /// Waits for the GPU and sends the result to the requester.
+    pub fn finish(self) {
+        match self {
+            Self::Image { readback, sender } => {
+                send_capture(sender, readback.and_then(CaptureReadback::read));
+            }
+            Self::Svg { export, sender } => {
+                send_capture(sender, export.and_then(SvgExport::write));
+            }
+        }
+    }
+}
+
+fn send_capture<T>(
+    sender: oneshot::Sender<Result<T, CaptureError>>,
+    result: Result<T, CaptureError>,
+) {
+    if let Err(e) = &result {
+        warn!("PendingCapture::finish: {e}");
+    }
+    let _ = sender.send(result);
+}
+
+/// A subtree to write as SVG, reading its textures back from the GPU.
+pub(crate) struct SvgExport {
+    subtree: CapturedSubtree,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+impl SvgExport {
+    fn write(self) -> Result<String, CaptureError> {
+        let [min, max] = self.subtree.border_box;
+        if max[0] <= min[0] || max[1] <= min[1] {
+            return Err(CaptureError::Empty);
+        }
+        renderer::export_svg(
+            &self.subtree.node,
+            self.subtree.border_box,
+            &self.device,
+            &self.queue,
+        )
+        .map_err(|e| CaptureError::Render(e.to_string()))
+    }
+}
+
+/// A rendered capture on its way back from the GPU.
+pub(crate) enum CaptureReadback {
+    Gpu {
+        device: wgpu::Device,
+        buffer: wgpu::Buffer,
+        size: [u32; 2],
+        padded_row_pitch: u32,
+    },
+    // rasterized when read, which reads the textures back from the GPU
+    Software {
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        node: RenderNode,
+        size: [u32; 2],
+    },
+}
+
+impl CaptureReadback {
+    /// Waits for the GPU and copies the pixels into an image.
+    pub fn read(self) -> Result<RgbaImage, CaptureError> {
+        match self {
+            Self::Gpu {
+                device,
+                buffer,
+                size,
+                padded_row_pitch,
+            } => read_buffer(&device, &buffer, size, padded_row_pitch),
+            Self::Software {
+                device,
+                queue,
+                node,
+                size: [width, height],
+            } => {
+                let mut pixmap =
+                    renderer::tiny_skia::Pixmap::new(width, height).ok_or(CaptureError::Empty)?;
+                renderer::SoftwareRenderer::new().render(
+                    &device,
+                    &queue,
+                    &mut pixmap,
+                    &node,
+                    wgpu::Color::TRANSPARENT,
+                );
+                let pixels = pixmap
+                    .pixels()
+                    .iter()
+                    .flat_map(|pixel| {
+                        let color = pixel.demultiply();
+                        [color.red(), color.green(), color.blue(), color.alpha()]
+                    })
+                    .collect();
+                debug!("CaptureReadback::read: rasterized {width}x{height} pixels");
+                RgbaImage::from_raw(width, height, pixels)
+                    .ok_or_else(|| CaptureError::Render("capture has the wrong size".to_string()))
+            }
+        }
+    }
+}
+
+fn read_buffer(
+    device: &wgpu::Device,
+    buffer: &wgpu::Buffer,
+    [width, height]: [u32; 2],
+    padded_row_pitch: u32,
+) -> Result<RgbaImage, CaptureError> {
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device
+        .poll(wgpu::PollType::Wait)
+        .map_err(|e| CaptureError::Render(e.to_string()))?;
+    receiver
+        .recv()
+        .map_err(|e| CaptureError::Render(e.to_string()))?
+        .map_err(|e| CaptureError::Render(e.to_string()))?;
+
+    let row_pitch = width as usize * 4;
+    let pixels = {
+        let mapped = slice.get_mapped_range();
+        mapped
+            .chunks(padded_row_pitch as usize)
+            .flat_map(|row| &row[..row_pitch])
+            .copied()
+            .collect()
+    };
+    buffer.unmap();
+
+    debug!("CaptureReadback::read: read {width}x{height} pixels");
+    RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| CaptureError::Render("readback has the wrong size".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        window_id: winit::window::WindowId,
+        label: &str,
+    ) -> (
+        CaptureRequest,
+        oneshot::Receiver<Result<RgbaImage, CaptureError>>,
+    ) {
+        let (sender, receiver) = oneshot::channel();
+        let request = CaptureRequest {
+            window_id,
+            target: label.into(),
+            output: CaptureOutput::Image { scale: 1.0, sender },
+        };
+        (request, receiver)
+    }
+
+    #[test]
+    fn takes_only_the_requests_of_a_window() {
+        let window = winit::window::WindowId::dummy();
+        let other = winit::window::WindowId::from(1);
+        let queue = CaptureQueue::default();
+        let (first, _first) = request(window, "first");
+        let (second, _second) = request(other, "second");
+        queue.push(first);
+        queue.push(second);
+
+        let taken = queue.take(window);
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].target, CaptureTarget::Label("first".to_string()));
+        assert!(queue.take(window).is_empty());
+        assert_eq!(queue.take(other).len(), 1);
+    }
+
+    #[test]
+    fn targets_match_label_or_id() {
+        assert!(CaptureTarget::from("chart").matches(Some("chart"), Some(3)));
+        assert!(!CaptureTarget::from("chart").matches(None, Some(3)));
+        assert!(CaptureTarget::from(3u128).matches(None, Some(3)));
+        assert!(!CaptureTarget::from(3u128).matches(Some("chart"), None));
+        assert!(CaptureTarget::Root.matches(Some("chart"), None));
+        assert!(!CaptureTarget::Root.matches(None, Some(3)));
+    }
+}
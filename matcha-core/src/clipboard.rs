@@ -0,0 +1,46 @@
+//! Text on the system clipboard.
+//!
+//! Failures, such as a system without a clipboard, are logged and read as an empty
+//! clipboard, since widgets have nothing better to do about them.
+
+use std::cell::RefCell;
+
+use log::warn;
+
+thread_local! {
+    // connections are opened on first use, per thread as they are not `Send` everywhere
+    static CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+}
+
+fn with_clipboard<R>(
+    f: impl FnOnce(&mut arboard::Clipboard) -> Result<R, arboard::Error>,
+) -> Option<R> {
+    CLIPBOARD.with_borrow_mut(|clipboard| {
+        if clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(opened) => *clipboard = Some(opened),
+                Err(e) => {
+                    warn!("clipboard: failed to open the clipboard: {e}");
+                    return None;
+                }
+            }
+        }
+        match f(clipboard.as_mut()?) {
+            Ok(value) => Some(value),
+            Err(arboard::Error::ContentNotAvailable) => None,
+            Err(e) => {
+                warn!("clipboard: {e}");
+                None
+            }
+        }
+    })
+}
+
+/// The text on the clipboard, `None` if it holds something else or nothing.
+pub fn read_text() -> Option<String> {
+    with_clipboard(|clipboard| clipboard.get_text())
+}
+
+pub fn write_text(text: &str) {
+    with_clipboard(|clipboard| clipboard.set_text(text));
+}
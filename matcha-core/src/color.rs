@@ -35,7 +35,7 @@ macro_rules! convert_srgb_u8 {
         if *$x <= 0.0031308 {
             (*$x * 12.92 * 255.0).round() as u8
         } else {
-            ((1.055 * $x.powf(-2.4) - 0.055) * 255.0).round() as u8
+            ((1.055 * $x.powf(1.0 / 2.4) - 0.055) * 255.0).round() as u8
         }
     };
 }
@@ -235,4 +235,573 @@ impl Color {
         let [r, g, b, a] = self.to_rgba_f64();
         wgpu::Color { r, g, b, a }
     }
+
+    /// Converts to a `wgpu::Color` with the color channels multiplied by alpha,
+    /// as expected by surfaces using `CompositeAlphaMode::PreMultiplied`.
+    pub fn to_wgpu_color_premultiplied(&self) -> wgpu::Color {
+        let [r, g, b, a] = self.to_rgba_f64();
+        wgpu::Color {
+            r: r * a,
+            g: g * a,
+            b: b * a,
+            a,
+        }
+    }
+
+    /// Scales the linear color channels by `intensity`, keeping alpha.
+    ///
+    /// Values above `1.0` author HDR colors brighter than SDR white; see [`DisplayColorSpace`].
+    pub fn with_intensity(&self, intensity: f32) -> Color {
+        let [r, g, b, a] = self.to_rgba_f32();
+        Color::RgbaF32 {
+            r: r * intensity,
+            g: g * intensity,
+            b: b * intensity,
+            a,
+        }
+    }
+
+    /// Whether any linear color channel exceeds SDR white.
+    pub fn is_hdr(&self) -> bool {
+        let [r, g, b, _] = self.to_rgba_f32();
+        r.max(g).max(b) > 1.0
+    }
+}
+
+// MARK: display
+
+/// Color space of a window's render target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisplayColorSpace {
+    /// 8-bit sRGB. Channels above `1.0` are tone-mapped.
+    #[default]
+    Srgb,
+    /// Extended linear sRGB in a 16-bit float surface, where `1.0` is SDR white and
+    /// larger values are shown brighter on HDR displays.
+    ScRgb,
+}
+
+impl DisplayColorSpace {
+    pub fn is_hdr(&self) -> bool {
+        matches!(self, DisplayColorSpace::ScRgb)
+    }
+
+    /// Converts a color authored in extended linear sRGB for this target.
+    ///
+    /// Returns the color unchanged on HDR targets and tone-maps it otherwise.
+    pub fn map_color(&self, color: Color) -> Color {
+        match self {
+            DisplayColorSpace::ScRgb => color,
+            DisplayColorSpace::Srgb => tone_map(color),
+        }
+    }
+}
+
+/// Maps HDR colors into the SDR range `[0, 1]`.
+///
+/// SDR colors are returned unchanged. Brighter colors keep their hue and fade towards white
+/// as they get brighter, instead of clipping each channel separately.
+pub fn tone_map(color: Color) -> Color {
+    let [r, g, b, a] = color.to_rgba_f32();
+    let peak = r.max(g).max(b);
+    if peak <= 1.0 {
+        return color;
+    }
+
+    // 0 at SDR white, approaching 1 for very bright colors
+    let whiteness = 1.0 - 1.0 / peak;
+    let [r, g, b] = [r, g, b].map(|c| {
+        let c = c.max(0.0) / peak;
+        c + (1.0 - c) * whiteness
+    });
+    Color::RgbaF32 { r, g, b, a }
+}
+
+// MARK: color spaces
+
+/// Decodes an sRGB-encoded channel to linear light.
+pub fn srgb_to_linear(x: f32) -> f32 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear channel with the sRGB transfer function.
+pub fn linear_to_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Hue, saturation and lightness of the sRGB-encoded color. Hue is in degrees `[0, 360)`,
+/// the other channels in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+    pub alpha: f32,
+}
+
+/// Hue, saturation and value of the sRGB-encoded color. Hue is in degrees `[0, 360)`,
+/// the other channels in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+    pub alpha: f32,
+}
+
+/// The perceptual OkLab space: lightness `l` in `[0, 1]` and the opponent axes `a` (green-red)
+/// and `b` (blue-yellow), roughly within `[-0.4, 0.4]`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+    pub alpha: f32,
+}
+
+/// OkLab in polar form: lightness, chroma and hue in degrees `[0, 360)`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+    pub alpha: f32,
+}
+
+/// Space in which [`Color::lerp`] interpolates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorSpace {
+    /// sRGB-encoded channels, like CSS gradients by default.
+    Srgb,
+    /// Linear light; physically correct blending, but midpoints look bright.
+    LinearRgb,
+    Hsl,
+    Hsv,
+    /// Perceptually even transitions without hue shifts.
+    #[default]
+    Oklab,
+    /// Like [`Oklab`](ColorSpace::Oklab) but keeps chroma, going around the hue circle.
+    Oklch,
+}
+
+/// Hue in degrees and chroma of sRGB-encoded channels, shared by HSL and HSV.
+fn hue_and_chroma(r: f32, g: f32, b: f32) -> (f32, f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+    let hue = if chroma <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    (hue, chroma, max, min)
+}
+
+/// sRGB-encoded channels of a hue with the given chroma, offset by `m`.
+fn from_hue_and_chroma(h: f32, chroma: f32, m: f32) -> [f32; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    let [r, g, b] = match h as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    [r + m, g + m, b + m]
+}
+
+fn lerp_f32(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Interpolates hues in degrees along the shorter way around the circle.
+fn lerp_hue(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 180.0).rem_euclid(360.0) - 180.0;
+    (a + delta * t).rem_euclid(360.0)
+}
+
+impl Color {
+    /// Builds a color from linear channels.
+    pub const fn linear(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Color::RgbaF32 { r, g, b, a }
+    }
+
+    /// Builds a color from sRGB-encoded channels in `[0, 1]`.
+    pub fn from_srgba_f32([r, g, b, a]: [f32; 4]) -> Self {
+        Color::RgbaF32 {
+            r: srgb_to_linear(r),
+            g: srgb_to_linear(g),
+            b: srgb_to_linear(b),
+            a,
+        }
+    }
+
+    /// sRGB-encoded channels in `[0, 1]`; the float variants hold linear channels.
+    pub fn to_srgba_f32(&self) -> [f32; 4] {
+        match self {
+            Color::Rgb8USrgb { r, g, b } => {
+                [*r as f32 / 255.0, *g as f32 / 255.0, *b as f32 / 255.0, 1.0]
+            }
+            Color::Rgba8USrgb { r, g, b, a } => [
+                *r as f32 / 255.0,
+                *g as f32 / 255.0,
+                *b as f32 / 255.0,
+                *a as f32 / 255.0,
+            ],
+            _ => {
+                let [r, g, b, a] = self.to_rgba_f32();
+                [linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a]
+            }
+        }
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.to_rgba_f32()[3]
+    }
+
+    /// The same color with its alpha replaced.
+    pub fn with_alpha(&self, alpha: f32) -> Self {
+        let [r, g, b, _] = self.to_rgba_f32();
+        Color::RgbaF32 {
+            r,
+            g,
+            b,
+            a: alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn to_hsl(&self) -> Hsl {
+        let [r, g, b, alpha] = self.to_srgba_f32();
+        let (h, chroma, max, min) = hue_and_chroma(r, g, b);
+        let l = (max + min) / 2.0;
+        let s = if chroma <= 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl { h, s, l, alpha }
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        let [r, g, b, alpha] = self.to_srgba_f32();
+        let (h, chroma, max, _) = hue_and_chroma(r, g, b);
+        let s = if max <= 0.0 { 0.0 } else { chroma / max };
+        Hsv {
+            h,
+            s,
+            v: max,
+            alpha,
+        }
+    }
+
+    pub fn to_oklab(&self) -> Oklab {
+        let [r, g, b, alpha] = self.to_rgba_f32();
+        let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+        let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+        let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+        let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+        Oklab {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+            alpha,
+        }
+    }
+
+    pub fn to_oklch(&self) -> Oklch {
+        Oklch::from(self.to_oklab())
+    }
+
+    /// Mixes `self` (at `t = 0`) and `other` (at `t = 1`) in `space`. Alpha is interpolated
+    /// linearly. Hues of achromatic colors are ignored, so that mixing with gray or white
+    /// does not swing through unrelated hues.
+    pub fn lerp(&self, other: &Color, t: f32, space: ColorSpace) -> Color {
+        match space {
+            ColorSpace::Srgb => {
+                let a = self.to_srgba_f32();
+                let b = other.to_srgba_f32();
+                Color::from_srgba_f32(std::array::from_fn(|i| lerp_f32(a[i], b[i], t)))
+            }
+            ColorSpace::LinearRgb => {
+                let a = self.to_rgba_f32();
+                let b = other.to_rgba_f32();
+                Color::from(std::array::from_fn::<f32, 4, _>(|i| {
+                    lerp_f32(a[i], b[i], t)
+                }))
+            }
+            ColorSpace::Hsl => {
+                let (a, b) = (self.to_hsl(), other.to_hsl());
+                let (ha, hb) = shared_hues(a.h, a.s, b.h, b.s);
+                Color::from(Hsl {
+                    h: lerp_hue(ha, hb, t),
+                    s: lerp_f32(a.s, b.s, t),
+                    l: lerp_f32(a.l, b.l, t),
+                    alpha: lerp_f32(a.alpha, b.alpha, t),
+                })
+            }
+            ColorSpace::Hsv => {
+                let (a, b) = (self.to_hsv(), other.to_hsv());
+                let (ha, hb) = shared_hues(a.h, a.s, b.h, b.s);
+                Color::from(Hsv {
+                    h: lerp_hue(ha, hb, t),
+                    s: lerp_f32(a.s, b.s, t),
+                    v: lerp_f32(a.v, b.v, t),
+                    alpha: lerp_f32(a.alpha, b.alpha, t),
+                })
+            }
+            ColorSpace::Oklab => {
+                let (a, b) = (self.to_oklab(), other.to_oklab());
+                Color::from(Oklab {
+                    l: lerp_f32(a.l, b.l, t),
+                    a: lerp_f32(a.a, b.a, t),
+                    b: lerp_f32(a.b, b.b, t),
+                    alpha: lerp_f32(a.alpha, b.alpha, t),
+                })
+            }
+            ColorSpace::Oklch => {
+                let (a, b) = (self.to_oklch(), other.to_oklch());
+                let (ha, hb) = shared_hues(a.h, a.c, b.h, b.c);
+                Color::from(Oklch {
+                    l: lerp_f32(a.l, b.l, t),
+                    c: lerp_f32(a.c, b.c, t),
+                    h: lerp_hue(ha, hb, t),
+                    alpha: lerp_f32(a.alpha, b.alpha, t),
+                })
+            }
+        }
+    }
+
+    /// Raises the OkLab lightness by `amount` (0.0 to 1.0), keeping hue and chroma.
+    pub fn lighten(&self, amount: f32) -> Color {
+        let mut lch = self.to_oklch();
+        lch.l = (lch.l + amount).clamp(0.0, 1.0);
+        Color::from(lch)
+    }
+
+    /// Lowers the OkLab lightness by `amount` (0.0 to 1.0), keeping hue and chroma.
+    pub fn darken(&self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+}
+
+/// Hues to interpolate between; an achromatic side takes the hue of the other.
+fn shared_hues(ha: f32, ca: f32, hb: f32, cb: f32) -> (f32, f32) {
+    const EPSILON: f32 = 1e-4;
+    match (ca <= EPSILON, cb <= EPSILON) {
+        (true, false) => (hb, hb),
+        (false, true) => (ha, ha),
+        _ => (ha, hb),
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(Hsl { h, s, l, alpha }: Hsl) -> Self {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let [r, g, b] = from_hue_and_chroma(h, chroma, l - chroma / 2.0);
+        Color::from_srgba_f32([r, g, b, alpha])
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(Hsv { h, s, v, alpha }: Hsv) -> Self {
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let chroma = v * s;
+        let [r, g, b] = from_hue_and_chroma(h, chroma, v - chroma);
+        Color::from_srgba_f32([r, g, b, alpha])
+    }
+}
+
+impl From<Oklab> for Color {
+    /// Colors outside of the sRGB gamut are clamped per channel.
+    fn from(Oklab { l, a, b, alpha }: Oklab) -> Self {
+        let l_ = l + 0.396_337_78 * a + 0.215_803_76 * b;
+        let m_ = l - 0.105_561_346 * a - 0.063_854_17 * b;
+        let s_ = l - 0.089_484_18 * a - 1.291_485_5 * b;
+        let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+        Color::RgbaF32 {
+            r: (4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s).clamp(0.0, 1.0),
+            g: (-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s).clamp(0.0, 1.0),
+            b: (-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s).clamp(0.0, 1.0),
+            a: alpha,
+        }
+    }
+}
+
+impl From<Oklch> for Color {
+    fn from(lch: Oklch) -> Self {
+        Color::from(Oklab::from(lch))
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(Oklab { l, a, b, alpha }: Oklab) -> Self {
+        Oklch {
+            l,
+            c: (a * a + b * b).sqrt(),
+            h: b.atan2(a).to_degrees().rem_euclid(360.0),
+            alpha,
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(Oklch { l, c, h, alpha }: Oklch) -> Self {
+        let (sin, cos) = h.to_radians().sin_cos();
+        Oklab {
+            l,
+            a: c * cos,
+            b: c * sin,
+            alpha,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f32; 4], b: [f32; 4]) {
+        for (x, y) in a.iter().zip(&b) {
+            assert!((x - y).abs() < 2e-3, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn srgb_bytes_round_trip() {
+        for value in [0u8, 1, 10, 64, 128, 200, 255] {
+            let linear = Color::rgb(value, value, value).to_rgba_f32();
+            let back = Color::from(linear).to_rgba_u8();
+            assert_eq!(back, [value, value, value, 255]);
+        }
+    }
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        let orange = Color::rgb(255, 128, 0);
+
+        let hsl = orange.to_hsl();
+        assert!((hsl.h - 30.1).abs() < 0.1);
+        assert!((hsl.s - 1.0).abs() < 1e-3);
+        assert!((hsl.l - 0.5).abs() < 1e-3);
+        assert_close(Color::from(hsl).to_srgba_f32(), orange.to_srgba_f32());
+
+        let hsv = orange.to_hsv();
+        assert!((hsv.v - 1.0).abs() < 1e-3);
+        assert_close(Color::from(hsv).to_srgba_f32(), orange.to_srgba_f32());
+
+        let gray = Color::rgb(128, 128, 128).to_hsl();
+        assert_eq!(gray.s, 0.0);
+    }
+
+    #[test]
+    fn oklab_matches_reference() {
+        // reference values from the OkLab definition
+        let white = Color::rgb(255, 255, 255).to_oklab();
+        assert!((white.l - 1.0).abs() < 1e-3);
+        assert!(white.a.abs() < 1e-3 && white.b.abs() < 1e-3);
+
+        let red = Color::rgb(255, 0, 0).to_oklch();
+        assert!((red.l - 0.628).abs() < 1e-3);
+        assert!((red.c - 0.2577).abs() < 1e-3);
+        assert!((red.h - 29.23).abs() < 0.1);
+
+        let blue = Color::rgb(0, 0, 255);
+        assert_close(
+            Color::from(blue.to_oklch()).to_rgba_f32(),
+            blue.to_rgba_f32(),
+        );
+    }
+
+    #[test]
+    fn lerp_between_spaces() {
+        let red = Color::rgb(255, 0, 0);
+        let blue = Color::rgb(0, 0, 255);
+
+        for space in [
+            ColorSpace::Srgb,
+            ColorSpace::LinearRgb,
+            ColorSpace::Hsl,
+            ColorSpace::Hsv,
+            ColorSpace::Oklab,
+            ColorSpace::Oklch,
+        ] {
+            assert_close(red.lerp(&blue, 0.0, space).to_rgba_f32(), red.to_rgba_f32());
+            assert_close(blue.lerp(&red, 1.0, space).to_rgba_f32(), red.to_rgba_f32());
+        }
+
+        // red to blue goes the short way through magenta in HSL
+        let mid = red.lerp(&blue, 0.5, ColorSpace::Hsl).to_hsl();
+        assert!((mid.h - 300.0).abs() < 0.5);
+
+        // gray keeps the hue of the other side
+        let gray = Color::rgb(128, 128, 128);
+        let tinted = gray.lerp(&blue, 0.5, ColorSpace::Hsl).to_hsl();
+        assert!((tinted.h - 240.0).abs() < 0.5);
+
+        let half = red
+            .with_alpha(0.0)
+            .lerp(&red, 0.5, ColorSpace::Oklab)
+            .alpha();
+        assert!((half - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lighten_and_darken_keep_hue() {
+        let base = Color::rgb(40, 100, 200);
+        let lighter = base.lighten(0.1).to_oklch();
+        let darker = base.darken(0.1).to_oklch();
+        let lch = base.to_oklch();
+
+        assert!((lighter.l - (lch.l + 0.1)).abs() < 1e-2);
+        assert!((darker.l - (lch.l - 0.1)).abs() < 1e-3);
+        assert!((darker.h - lch.h).abs() < 0.5);
+        assert_eq!(
+            Color::rgb(255, 255, 255).lighten(0.5).to_rgba_u8(),
+            [255; 4]
+        );
+    }
+
+    #[test]
+    fn tone_map_keeps_sdr_and_hue() {
+        let sdr = Color::rgb(40, 100, 200);
+        assert_eq!(tone_map(sdr), sdr);
+        assert_eq!(
+            DisplayColorSpace::ScRgb.map_color(sdr.with_intensity(4.0)),
+            sdr.with_intensity(4.0)
+        );
+
+        let bright = Color::RgbaF32 {
+            r: 4.0,
+            g: 2.0,
+            b: 0.0,
+            a: 0.5,
+        };
+        assert!(bright.is_hdr());
+        let mapped = DisplayColorSpace::Srgb.map_color(bright);
+        assert!(!mapped.is_hdr());
+        let [r, g, b, a] = mapped.to_rgba_f32();
+        assert_eq!(r, 1.0);
+        assert!(r > g && g > b && b > 0.0);
+        assert_eq!(a, 0.5);
+
+        // brighter colors come closer to white
+        let brighter = tone_map(bright.with_intensity(4.0)).to_rgba_f32();
+        assert!(brighter[2] > b);
+    }
 }
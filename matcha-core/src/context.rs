@@ -1,7 +1,9 @@
 use fxhash::FxBuildHasher;
 use gpu_utils::gpu::Gpu;
-use gpu_utils::gpu_type_map::GpuTypeMap;
+use gpu_utils::gpu_type_map::{GpuTypeMap, RendererRegistry, WidgetRenderer};
+use gpu_utils::memory::MemoryReport;
 use gpu_utils::texture_atlas::TextureAtlas;
+use gpu_utils::upload::UploadRing;
 use log::{debug, trace, warn};
 use parking_lot::RwLock;
 use parking_lot::lock_api::RwLockReadGuard;
@@ -9,19 +11,56 @@ use std::sync::{Arc, Weak};
 use std::time::Duration;
 use utils::type_map::TypeMap;
 
+use crate::cache_budget::{CacheBudget, CacheStats};
+use crate::capture::{
+    CaptureError, CaptureOutput, CaptureQueue, CaptureRequest, CaptureTarget, RgbaImage,
+};
+use crate::color::{Color, DisplayColorSpace};
+use crate::cursor::{Cursor, CursorIcon, CustomCursor};
 use crate::debug_config::DebugConfig;
+use crate::device_input::{ImePurpose, Theme};
+use crate::device_recovery::DeviceRecoveryManager;
+use crate::fonts::FontRegistry;
+use crate::frame_clock::{FrameClock, FrameTime};
+use crate::lifecycle::Lifecycle;
+use crate::localization::{Localization, MessageArg};
+use crate::present_timing::PresentStats;
+use crate::resource_loader::{LoadError, LoadHandle, Priority, ResourceLoader, ResourceSource};
+use crate::test_kit::HeadlessWindow;
+use crate::timer::{TimerHandle, TimerQueue};
+use crate::toast::{Toast, ToastCenter, ToastId, ToastState, ToastSubscription};
+use crate::ui::{AsyncInvalidationHandle, HitTestPath};
+use crate::window_control::{ResizeDirection, WindowControl};
+use crate::window_effect::WindowEffect;
+use crate::window_icon::WindowIcon;
 use crate::window_surface::WindowSurface;
+use crate::worker_pool::WorkerPool;
 
 pub struct GlobalResources {
     gpu: Arc<Gpu>,
 
     texture: Arc<TextureAtlas>,
     stencil: Arc<TextureAtlas>,
+    upload: Arc<UploadRing>,
     gpu_resource: Arc<GpuTypeMap>,
+    renderers: Arc<RendererRegistry>,
     any_resource: Arc<TypeMap>,
 
-    current_time: Arc<RwLock<std::time::Instant>>,
+    device_recovery: DeviceRecoveryManager,
+    lifecycle: Lifecycle,
+
+    toasts: Arc<ToastCenter>,
+    localization: Arc<Localization>,
+    fonts: Arc<FontRegistry>,
+    captures: Arc<CaptureQueue>,
+
+    worker_pool: Arc<WorkerPool>,
+    resource_loader: Arc<ResourceLoader>,
+
+    frame_clock: Arc<FrameClock>,
+    timers: Arc<TimerQueue>,
     debug_config: Arc<RwLock<DebugConfig>>,
+    cache_budget: Arc<CacheBudget>,
 
     command_receiver: tokio::sync::Mutex<tokio::sync::mpsc::UnboundedReceiver<ApplicationCommand>>,
     command_sender: tokio::sync::mpsc::UnboundedSender<ApplicationCommand>,
@@ -55,12 +94,28 @@ impl GlobalResources {
             TextureAtlas::DEFAULT_MARGIN_PX,
         );
 
+        // atlas uploads and widget uploads share one ring, flushed once per frame
+        let upload = Arc::new(UploadRing::new(&gpu.device(), &gpu.queue()));
+        texture.set_upload_ring(upload.clone());
+        stencil.set_upload_ring(upload.clone());
+
         let gpu_resource = Arc::new(GpuTypeMap::new());
+        let renderers = Arc::new(RendererRegistry::new());
         let any_resource = Arc::new(TypeMap::new());
 
-        let current_time = Arc::new(RwLock::new(std::time::Instant::now()));
+        // shared gpu resources are rebuilt in this order after device loss
+        let device_recovery = DeviceRecoveryManager::new();
+        device_recovery.register(upload.clone());
+        device_recovery.register(texture.clone());
+        device_recovery.register(stencil.clone());
+        device_recovery.register(gpu_resource.clone());
+        device_recovery.register(renderers.clone());
+
+        let frame_clock = Arc::new(FrameClock::new(std::time::Instant::now()));
         let debug_config = Arc::new(RwLock::new(DebugConfig::default()));
 
+        let localization = Arc::new(Localization::default());
+
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         trace!("GlobalResources::new: command channel initialized");
@@ -69,10 +124,22 @@ impl GlobalResources {
             gpu,
             texture,
             stencil,
+            upload,
             gpu_resource,
+            renderers,
             any_resource,
-            current_time,
+            device_recovery,
+            lifecycle: Lifecycle::new(),
+            toasts: Arc::new(ToastCenter::new()),
+            localization,
+            fonts: Arc::new(FontRegistry::new()),
+            captures: Arc::new(CaptureQueue::default()),
+            worker_pool: Arc::new(WorkerPool::default()),
+            resource_loader: Arc::new(ResourceLoader::default()),
+            frame_clock,
+            timers: Arc::new(TimerQueue::new()),
             debug_config,
+            cache_budget: Arc::new(CacheBudget::default()),
             command_receiver: tokio::sync::Mutex::new(rx),
             command_sender: tx,
         };
@@ -82,6 +149,32 @@ impl GlobalResources {
     }
 }
 
+impl GlobalResources {
+    /// Replaces the default localization, e.g. with the one configured on the app builder.
+    pub(crate) fn with_localization(mut self, localization: Localization) -> Self {
+        self.localization = Arc::new(localization);
+        self
+    }
+
+    /// Replaces the empty font registry with the one configured on the app builder.
+    pub(crate) fn with_fonts(mut self, fonts: FontRegistry) -> Self {
+        self.fonts = Arc::new(fonts);
+        self
+    }
+
+    /// Replaces the default debug configuration with the one configured on the app builder.
+    pub(crate) fn with_debug_config(mut self, debug_config: DebugConfig) -> Self {
+        self.debug_config = Arc::new(RwLock::new(debug_config));
+        self
+    }
+
+    /// Sets the memory budget of widget caches, see [`crate::cache_budget`].
+    pub(crate) fn with_cache_budget(self, budget_bytes: usize) -> Self {
+        self.cache_budget.set_budget(budget_bytes);
+        self
+    }
+}
+
 impl GlobalResources {
     pub fn gpu(&self) -> &Gpu {
         &self.gpu
@@ -91,20 +184,110 @@ impl GlobalResources {
         &self.texture
     }
 
+    /// Shared handle of the texture atlas, for GPU work that outlives the borrow of `self`.
+    pub(crate) fn texture_atlas_handle(&self) -> Arc<TextureAtlas> {
+        self.texture.clone()
+    }
+
     pub fn stencil_atlas(&self) -> &TextureAtlas {
         &self.stencil
     }
 
+    /// The ring that stages the uploads of the atlases and widgets.
+    pub fn upload_ring(&self) -> &UploadRing {
+        &self.upload
+    }
+
     pub fn gpu_resource(&self) -> &GpuTypeMap {
         &self.gpu_resource
     }
 
+    pub fn renderers(&self) -> &RendererRegistry {
+        &self.renderers
+    }
+
     pub fn any_resource(&self) -> &TypeMap {
         &self.any_resource
     }
 
+    pub fn device_recovery(&self) -> &DeviceRecoveryManager {
+        &self.device_recovery
+    }
+
+    pub fn toasts(&self) -> &ToastCenter {
+        &self.toasts
+    }
+
+    pub(crate) fn captures(&self) -> &CaptureQueue {
+        &self.captures
+    }
+
+    pub fn localization(&self) -> &Localization {
+        &self.localization
+    }
+
+    pub fn fonts(&self) -> &FontRegistry {
+        &self.fonts
+    }
+
+    pub fn worker_pool(&self) -> &WorkerPool {
+        &self.worker_pool
+    }
+
+    pub fn resource_loader(&self) -> &Arc<ResourceLoader> {
+        &self.resource_loader
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.lifecycle.is_suspended()
+    }
+
+    pub(crate) fn lifecycle(&self) -> &Lifecycle {
+        &self.lifecycle
+    }
+
+    /// Enters the suspended state and stops the animation clock.
+    /// Returns `false` if already suspended.
+    pub(crate) fn suspend(&self) -> bool {
+        self.lifecycle.suspend(std::time::Instant::now())
+    }
+
+    /// Leaves the suspended state. The animation clock continues from where it stopped.
+    /// Returns `false` if the app was not suspended.
+    pub(crate) fn resume(&self) -> bool {
+        let Some(paused) = self.lifecycle.resume(std::time::Instant::now()) else {
+            return false;
+        };
+        self.frame_clock.skip(paused);
+        true
+    }
+
     pub fn current_time(&self) -> Duration {
-        self.current_time.read().elapsed()
+        self.frame_clock.animation_time()
+    }
+
+    pub fn frame_clock(&self) -> &FrameClock {
+        &self.frame_clock
+    }
+
+    /// Starts a new frame on the frame clock. Called once per rendered frame, before any
+    /// window renders.
+    pub(crate) fn tick_frame(&self) -> FrameTime {
+        self.frame_clock.tick(std::time::Instant::now())
+    }
+
+    /// Fires the timers that expired by the current animation time, see [`crate::timer`].
+    pub(crate) fn fire_timers(&self) -> usize {
+        self.timers.fire(self.frame_clock.animation_time())
+    }
+
+    /// Moves the animation clock forward by `by` without waiting.
+    pub(crate) fn advance_clock(&self, by: Duration) {
+        self.frame_clock.advance(by);
+    }
+
+    pub fn cache_budget(&self) -> &CacheBudget {
+        &self.cache_budget
     }
 
     pub(crate) fn debug_config(&self) -> RwLockReadGuard<'_, parking_lot::RawRwLock, DebugConfig> {
@@ -117,6 +300,11 @@ impl GlobalResources {
         self.command_receiver.blocking_lock().try_recv()
     }
 
+    /// Like [`try_recv_command`](Self::try_recv_command), for use inside the async runtime.
+    pub(crate) async fn try_recv_command_async(&self) -> Option<ApplicationCommand> {
+        self.command_receiver.lock().await.try_recv().ok()
+    }
+
     // pub fn command_receiver(
     //     &self,
     // ) -> &tokio::sync::mpsc::UnboundedReceiver<ApplicationCommand> {
@@ -135,36 +323,97 @@ impl GlobalResources {
         window_surface: &Arc<RwLock<WindowSurface>>,
     ) -> Option<WidgetContext> {
         trace!("GlobalResources::widget_context: creating widget context");
-        Some(WidgetContext {
+        Some(self.widget_context_for(
+            task_executor,
+            Arc::downgrade(window_surface),
+            window_surface.read().window_id(),
+        ))
+    }
+
+    pub fn application_context(
+        &self,
+        task_executor: &tokio::runtime::Handle,
+        window_surface: &Arc<RwLock<WindowSurface>>,
+    ) -> Option<ApplicationContext> {
+        trace!("GlobalResources::application_context: creating application context");
+        Some(self.application_context_for(
+            task_executor,
+            Arc::downgrade(window_surface),
+            window_surface.read().window_id(),
+        ))
+    }
+
+    /// Widget context of a UI that has no window, e.g. one driven by
+    /// [`UiDriver`](crate::automation::UiDriver). `dpi` and `viewport_size` are `None`.
+    pub(crate) fn headless_widget_context(
+        &self,
+        task_executor: &tokio::runtime::Handle,
+    ) -> WidgetContext {
+        self.widget_context_for(task_executor, Weak::new(), winit::window::WindowId::dummy())
+    }
+
+    /// Application context of a UI that has no window. Window commands are queued but refer
+    /// to no window.
+    pub(crate) fn headless_application_context(
+        &self,
+        task_executor: &tokio::runtime::Handle,
+    ) -> ApplicationContext {
+        self.application_context_for(task_executor, Weak::new(), winit::window::WindowId::dummy())
+    }
+
+    fn widget_context_for(
+        &self,
+        task_executor: &tokio::runtime::Handle,
+        window_surface: Weak<RwLock<WindowSurface>>,
+        window_id: winit::window::WindowId,
+    ) -> WidgetContext {
+        WidgetContext {
             task_executor: task_executor.clone(),
-            window_surface: Arc::downgrade(window_surface),
-            current_time: Arc::downgrade(&self.current_time),
+            window_surface,
+            headless_window: Weak::new(),
+            frame_clock: Arc::downgrade(&self.frame_clock),
+            timers: Arc::downgrade(&self.timers),
             debug_config: Arc::downgrade(&self.debug_config),
+            cache_budget: Arc::downgrade(&self.cache_budget),
             gpu: Arc::downgrade(&self.gpu),
             texture_atlas: Arc::downgrade(&self.texture),
             stencil_atlas: Arc::downgrade(&self.stencil),
+            upload_ring: Arc::downgrade(&self.upload),
             gpu_resource: Arc::downgrade(&self.gpu_resource),
+            renderers: Arc::downgrade(&self.renderers),
             any_resource: Arc::downgrade(&self.any_resource),
+            toasts: Arc::downgrade(&self.toasts),
+            localization: Arc::downgrade(&self.localization),
+            fonts: Arc::downgrade(&self.fonts),
+            captures: Arc::downgrade(&self.captures),
+            worker_pool: Arc::downgrade(&self.worker_pool),
+            resource_loader: Arc::downgrade(&self.resource_loader),
             scoped_config: AnyConfig::new(),
-            window_id: window_surface.read().window_id(),
+            window_id,
             command_sender: self.command_sender.downgrade(),
-        })
+        }
     }
 
-    pub fn application_context(
+    fn application_context_for(
         &self,
         task_executor: &tokio::runtime::Handle,
-        window_surface: &Arc<RwLock<WindowSurface>>,
-    ) -> Option<ApplicationContext> {
-        trace!("GlobalResources::application_context: creating application context");
-        Some(ApplicationContext {
+        window_surface: Weak<RwLock<WindowSurface>>,
+        window_id: winit::window::WindowId,
+    ) -> ApplicationContext {
+        ApplicationContext {
             task_executor: task_executor.clone(),
-            window_surface: Arc::downgrade(window_surface),
+            window_surface,
             debug_config: Arc::downgrade(&self.debug_config),
-            current_time: Arc::downgrade(&self.current_time),
-            window_id: window_surface.read().window_id(),
+            frame_clock: Arc::downgrade(&self.frame_clock),
+            timers: Arc::downgrade(&self.timers),
+            cache_budget: Arc::downgrade(&self.cache_budget),
+            toasts: Arc::downgrade(&self.toasts),
+            localization: Arc::downgrade(&self.localization),
+            fonts: Arc::downgrade(&self.fonts),
+            resource_loader: Arc::downgrade(&self.resource_loader),
+            window_id,
             command_sender: self.command_sender.downgrade(),
-        })
+        }
     }
 }
 
@@ -183,16 +432,38 @@ pub struct WidgetContext {
 
     // ui rendering
     window_surface: Weak<RwLock<WindowSurface>>,
-    current_time: Weak<RwLock<std::time::Instant>>,
+    // stands in for the window in tests, see `test_kit`
+    headless_window: Weak<HeadlessWindow>,
+    frame_clock: Weak<FrameClock>,
+    timers: Weak<TimerQueue>,
     debug_config: Weak<RwLock<DebugConfig>>,
+    cache_budget: Weak<CacheBudget>,
 
     // gpu resources
     gpu: Weak<Gpu>,
     texture_atlas: Weak<TextureAtlas>,
     stencil_atlas: Weak<TextureAtlas>,
+    upload_ring: Weak<UploadRing>,
     gpu_resource: Weak<GpuTypeMap>,
+    renderers: Weak<RendererRegistry>,
     any_resource: Weak<TypeMap>,
 
+    // notifications
+    toasts: Weak<ToastCenter>,
+
+    // translated strings
+    localization: Weak<Localization>,
+    // fonts registered by the app
+    fonts: Weak<FontRegistry>,
+
+    // subtrees to render into images, see `capture`
+    captures: Weak<CaptureQueue>,
+
+    // background threads for tessellation and rasterization
+    worker_pool: Weak<WorkerPool>,
+    // files and urls loaded in the background
+    resource_loader: Weak<ResourceLoader>,
+
     // nested config
     scoped_config: AnyConfig,
 
@@ -204,6 +475,22 @@ pub struct WidgetContext {
 }
 
 impl WidgetContext {
+    pub(crate) fn task_executor(&self) -> &tokio::runtime::Handle {
+        &self.task_executor
+    }
+
+    /// `None` in contexts without application resources, e.g. in tests.
+    pub(crate) fn cache_budget(&self) -> Option<Arc<CacheBudget>> {
+        self.cache_budget.upgrade()
+    }
+
+    /// Generations of the texture and stencil atlases, see [`TextureAtlas::generation`].
+    /// They change when the atlases recover and every region handed out before goes stale.
+    pub(crate) fn atlas_generations(&self) -> [u64; 2] {
+        [&self.texture_atlas, &self.stencil_atlas]
+            .map(|atlas| atlas.upgrade().map_or(0, |atlas| atlas.generation()))
+    }
+
     pub(crate) fn application_context(&self) -> ApplicationContext {
         trace!(
             "WidgetContext::application_context: promoting widget context to application context"
@@ -212,7 +499,13 @@ impl WidgetContext {
             task_executor: self.task_executor.clone(),
             window_surface: self.window_surface.clone(),
             debug_config: self.debug_config.clone(),
-            current_time: self.current_time.clone(),
+            frame_clock: self.frame_clock.clone(),
+            timers: self.timers.clone(),
+            cache_budget: self.cache_budget.clone(),
+            toasts: self.toasts.clone(),
+            localization: self.localization.clone(),
+            fonts: self.fonts.clone(),
+            resource_loader: self.resource_loader.clone(),
             window_id: self.window_id,
             command_sender: self.command_sender.clone(),
         }
@@ -232,16 +525,53 @@ impl WidgetContext {
         self.gpu.upgrade().unwrap().queue()
     }
 
+    /// Returns the features enabled on the device.
+    pub fn device_features(&self) -> wgpu::Features {
+        *self.gpu.upgrade().unwrap().features()
+    }
+
     /// Provides access to a type-safe, shared resource storage.
     pub fn any_resource(&self) -> Arc<TypeMap> {
         self.any_resource.upgrade().unwrap().clone()
     }
 
+    /// The shared pool for CPU-heavy work such as tessellation, see [`crate::worker_pool`].
+    pub fn worker_pool(&self) -> Arc<WorkerPool> {
+        self.worker_pool.upgrade().unwrap()
+    }
+
+    /// Loads `source` in the background, see [`crate::resource_loader`]. `on_complete`
+    /// redraws the widget when the load finished; dropping the handle cancels the load.
+    pub fn load_resource(
+        &self,
+        source: impl Into<ResourceSource>,
+        priority: Priority,
+        on_complete: AsyncInvalidationHandle,
+    ) -> LoadHandle {
+        load_resource(
+            &self.resource_loader,
+            source.into(),
+            priority,
+            Some(on_complete),
+            &self.task_executor,
+        )
+    }
+
     /// Provides access to a type-safe, shared GPU resource storage which can recover from device loss.
     pub fn gpu_resource(&self) -> Arc<GpuTypeMap> {
         self.gpu_resource.upgrade().unwrap().clone()
     }
 
+    /// Returns the shared instance of a widget renderer, creating it on first use.
+    ///
+    /// Instances are rebuilt automatically after the GPU device is recovered.
+    pub fn renderer<T: WidgetRenderer>(&self) -> Arc<T> {
+        let gpu = self.gpu.upgrade().unwrap();
+        gpu.with_device_queue(|device, queue| {
+            self.renderers.upgrade().unwrap().get::<T>(device, queue)
+        })
+    }
+
     /// Returns the texture format of the surface.
     pub fn surface_format(&self) -> Option<wgpu::TextureFormat> {
         self.window_surface
@@ -249,6 +579,24 @@ impl WidgetContext {
             .map(|surface| surface.read().format())
     }
 
+    /// Returns the color space of the window surface; sRGB when there is no window.
+    pub fn color_space(&self) -> DisplayColorSpace {
+        self.window_surface
+            .upgrade()
+            .map_or(DisplayColorSpace::Srgb, |surface| {
+                surface.read().color_space()
+            })
+    }
+
+    /// Converts a color authored in extended linear sRGB for drawing into the texture atlas.
+    ///
+    /// The atlas stores 8-bit sRGB regardless of the window, so HDR colors are tone-mapped
+    /// here; only colors written straight to the surface, like the window's base color, keep
+    /// their extended range on HDR windows.
+    pub fn display_color(&self, color: Color) -> Color {
+        DisplayColorSpace::Srgb.map_color(color)
+    }
+
     /// Returns the texture format for color used by the texture atlas.
     pub fn texture_format(&self) -> wgpu::TextureFormat {
         self.texture_atlas.upgrade().unwrap().format()
@@ -269,24 +617,282 @@ impl WidgetContext {
         self.stencil_atlas.upgrade().unwrap().clone()
     }
 
+    /// Returns the ring that stages GPU uploads. Staged uploads are submitted before the
+    /// frame is rendered; flush the ring before submitting own work that reads them.
+    pub fn upload_ring(&self) -> Arc<UploadRing> {
+        self.upload_ring.upgrade().unwrap()
+    }
+
     /// Returns the DPI scaling factor of the window.
     pub fn dpi(&self) -> Option<f64> {
-        self.window_surface
-            .upgrade()
-            .map(|surface| surface.read().dpi())
+        match self.window_surface.upgrade() {
+            Some(surface) => Some(surface.read().dpi()),
+            None => self.headless_window.upgrade().map(|w| w.scale_factor()),
+        }
     }
 
     /// Returns the logical size of the viewport.
     pub fn viewport_size(&self) -> Option<[f32; 2]> {
-        self.window_surface.upgrade().map(|surface| {
-            let size = surface.read().inner_size();
-            [size.width as f32, size.height as f32]
-        })
+        match self.window_surface.upgrade() {
+            Some(surface) => {
+                let size = surface.read().inner_size();
+                Some([size.width as f32, size.height as f32])
+            }
+            None => self.headless_window.upgrade().map(|w| w.viewport_size()),
+        }
+    }
+
+    /// When the frames of the window were presented, how many vsyncs they missed and what
+    /// the last one drew, see [`present_timing`](crate::present_timing). `None` without a
+    /// window.
+    pub fn present_stats(&self) -> Option<PresentStats> {
+        self.window_surface
+            .upgrade()
+            .map(|surface| surface.read().present_stats())
+    }
+
+    /// Returns the light or dark theme of the window, or `None` if the platform does not
+    /// report one.
+    pub fn theme(&self) -> Option<Theme> {
+        match self.window_surface.upgrade() {
+            Some(surface) => surface.read().window().theme(),
+            None => self.headless_window.upgrade().map(|w| w.theme()),
+        }
+    }
+
+    /// Changes the mouse pointer over the window to a system or custom cursor. See
+    /// [`cursor`](crate::cursor).
+    pub fn set_cursor(&self, cursor: impl Into<Cursor>) {
+        let cursor = cursor.into();
+        let Some(surface) = self.window_surface.upgrade() else {
+            return;
+        };
+        if surface.read().set_cursor(cursor.clone()) {
+            return;
+        }
+        // the platform cursor of a custom cursor can only be created on the event loop
+        if let Cursor::Custom(cursor) = cursor
+            && let Some(sender) = self.command_sender.upgrade()
+        {
+            let command = ApplicationCommand::CreateCursor {
+                id: self.window_id,
+                cursor,
+            };
+            if sender.send(command).is_err() {
+                warn!("WidgetContext::set_cursor: command receiver dropped");
+            }
+        }
+    }
+
+    /// Changes the mouse pointer over the window to one of the platform theme.
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.set_cursor(icon);
+    }
+
+    /// Enables input method events for text of `purpose`, or disables them with `None`.
+    ///
+    /// Text inputs enable it when they gain focus. With [`ImePurpose::Password`] the platform
+    /// may turn composition off or hide it.
+    pub fn set_ime(&self, purpose: Option<ImePurpose>) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().set_ime(purpose);
+        }
+    }
+
+    /// Hides and locks the pointer, e.g. for a 3D viewport. While it is locked widgets
+    /// receive relative motion as [`DeviceInputData::PointerMotion`].
+    ///
+    /// The lock is released by [`unlock_pointer`](Self::unlock_pointer), when the window
+    /// loses focus or when Escape is pressed; the latter two send
+    /// [`DeviceInputData::PointerUnlocked`]. Returns `false` if the platform does not
+    /// support it.
+    ///
+    /// [`DeviceInputData::PointerMotion`]: crate::device_input::DeviceInputData::PointerMotion
+    /// [`DeviceInputData::PointerUnlocked`]: crate::device_input::DeviceInputData::PointerUnlocked
+    pub fn lock_pointer(&self) -> bool {
+        self.window_surface
+            .upgrade()
+            .is_some_and(|surface| surface.read().lock_pointer())
+    }
+
+    pub fn unlock_pointer(&self) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().unlock_pointer();
+        }
+    }
+
+    pub fn is_pointer_locked(&self) -> bool {
+        self.window_surface
+            .upgrade()
+            .is_some_and(|surface| surface.read().is_pointer_locked())
     }
 
-    /// Returns the current absolute time since the application started.
+    /// Starts moving the window with the pointer, for a custom title bar. Call it while
+    /// handling a primary button press.
+    pub fn drag_window(&self) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().drag_window();
+        }
+    }
+
+    /// Starts resizing the window towards `direction` with the pointer. Call it while
+    /// handling a primary button press.
+    pub fn drag_resize_window(&self, direction: ResizeDirection) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().drag_resize_window(direction);
+        }
+    }
+
+    /// Minimizes, maximizes or restores the window right away.
+    pub fn control_window(&self, control: WindowControl) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().control(control);
+        }
+    }
+
+    /// Returns the animation time right now: the time since the application started,
+    /// excluding time spent suspended.
+    ///
+    /// Prefer [`frame`](Self::frame) for animations, so all widgets of a frame agree.
     pub fn current_time(&self) -> Duration {
-        self.current_time.upgrade().unwrap().read().elapsed()
+        self.frame_clock.upgrade().unwrap().animation_time()
+    }
+
+    /// Timing of the frame being rendered: its index, timestamp, delta and animation time.
+    /// See [`crate::frame_clock`].
+    pub fn frame(&self) -> FrameTime {
+        self.frame_clock.upgrade().unwrap().frame()
+    }
+
+    /// Calls `callback` on the rendering loop once `delay` of animation time has passed.
+    /// Dropping the handle cancels the timer, see [`crate::timer`].
+    pub fn set_timeout(
+        &self,
+        delay: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        start_timer(&self.timers, &self.frame_clock, delay, None, callback)
+    }
+
+    /// Calls `callback` on the rendering loop every `interval` of animation time until the
+    /// handle is dropped.
+    pub fn set_interval(
+        &self,
+        interval: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        start_timer(
+            &self.timers,
+            &self.frame_clock,
+            interval,
+            Some(interval),
+            callback,
+        )
+    }
+
+    /// Memory use and hit rates of the layout and render caches, see
+    /// [`crate::cache_budget`].
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_budget
+            .upgrade()
+            .map(|budget| budget.stats())
+            .unwrap_or_default()
+    }
+
+    /// GPU memory held by texture atlases and renderer buffers, see [`gpu_utils::memory`].
+    ///
+    /// Measures every atlas, so query it at most once per frame.
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport::collect()
+    }
+
+    /// Toasts of the current window, oldest first.
+    pub fn toasts(&self) -> Vec<ToastState> {
+        self.toasts.upgrade().map_or_else(Vec::new, |toasts| {
+            toasts.toasts(self.window_id, std::time::Instant::now())
+        })
+    }
+
+    pub fn dismiss_toast(&self, id: ToastId) {
+        if let Some(toasts) = self.toasts.upgrade() {
+            toasts.dismiss(id, std::time::Instant::now());
+        }
+    }
+
+    /// Renders the widget matching `target` in the current window into an image, `scale`
+    /// times its laid-out size. See [`crate::capture`].
+    ///
+    /// The request is served by the render loop, so await the result outside of the frame,
+    /// e.g. in a spawned task.
+    pub fn capture_subtree(
+        &self,
+        target: impl Into<CaptureTarget>,
+        scale: f32,
+    ) -> impl Future<Output = Result<RgbaImage, CaptureError>> + Send + 'static {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.request_capture(target.into(), CaptureOutput::Image { scale, sender });
+        async move { receiver.await.unwrap_or(Err(CaptureError::Cancelled)) }
+    }
+
+    /// Writes the widget matching `target` in the current window as an SVG document, e.g. with
+    /// [`CaptureTarget::Root`] to print the whole window. See [`crate::capture`].
+    ///
+    /// Served by the render loop like [`capture_subtree`](Self::capture_subtree).
+    pub fn export_svg(
+        &self,
+        target: impl Into<CaptureTarget>,
+    ) -> impl Future<Output = Result<String, CaptureError>> + Send + 'static {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.request_capture(target.into(), CaptureOutput::Svg { sender });
+        async move { receiver.await.unwrap_or(Err(CaptureError::Cancelled)) }
+    }
+
+    fn request_capture(&self, target: CaptureTarget, output: CaptureOutput) {
+        let request = CaptureRequest {
+            window_id: self.window_id,
+            target,
+            output,
+        };
+        match self.captures.upgrade() {
+            Some(captures) => captures.push(request),
+            None => warn!("WidgetContext::request_capture: capture queue unavailable"),
+        }
+    }
+
+    /// Calls `listener` whenever the toasts of the current window change or animate,
+    /// until the returned subscription is dropped.
+    pub fn subscribe_toasts(
+        &self,
+        listener: impl Fn() + Send + Sync + 'static,
+    ) -> Option<ToastSubscription> {
+        let toasts = self.toasts.upgrade()?;
+        Some(toasts.subscribe(self.window_id, listener))
+    }
+
+    /// The current locale, e.g. `"en-US"`.
+    pub fn locale(&self) -> Option<String> {
+        self.localization
+            .upgrade()
+            .map(|localization| localization.locale())
+    }
+
+    /// The localization of the app, which `tr!` in view functions translates with.
+    pub(crate) fn localization(&self) -> Option<Arc<Localization>> {
+        self.localization.upgrade()
+    }
+
+    /// The message `key` in the current locale; the key itself when it is missing.
+    pub fn tr(&self, key: &str, args: &[(&str, MessageArg)]) -> String {
+        match self.localization.upgrade() {
+            Some(localization) => localization.translate(key, args),
+            None => key.to_string(),
+        }
+    }
+
+    /// The fonts registered by the app, for text renderers to load before shaping. `None` in
+    /// contexts without application resources, e.g. in tests.
+    pub fn fonts(&self) -> Option<Arc<FontRegistry>> {
+        self.fonts.upgrade()
     }
 
     pub(crate) fn debug_config_always_rebuild_widget(&self) -> bool {
@@ -320,6 +926,14 @@ impl WidgetContext {
             .read()
             .disable_render_node_cache()
     }
+
+    pub(crate) fn debug_config_show_layout_overflow(&self) -> bool {
+        self.debug_config
+            .upgrade()
+            .unwrap()
+            .read()
+            .show_layout_overflow()
+    }
 }
 
 /// ApplicationHandler is owned by the window / WinitInstance and holds the
@@ -331,7 +945,13 @@ pub struct ApplicationContext {
 
     window_surface: Weak<RwLock<WindowSurface>>,
     debug_config: Weak<RwLock<DebugConfig>>,
-    current_time: Weak<RwLock<std::time::Instant>>,
+    frame_clock: Weak<FrameClock>,
+    timers: Weak<TimerQueue>,
+    cache_budget: Weak<CacheBudget>,
+    toasts: Weak<ToastCenter>,
+    localization: Weak<Localization>,
+    fonts: Weak<FontRegistry>,
+    resource_loader: Weak<ResourceLoader>,
 
     window_id: winit::window::WindowId,
 
@@ -345,10 +965,50 @@ pub enum ApplicationCommand {
     Exit,
     /// Close window with given ID.
     CloseWindow { id: winit::window::WindowId },
+    /// Hide or show the window with given ID.
+    SetWindowVisible {
+        id: winit::window::WindowId,
+        visible: bool,
+    },
+    /// Show every hidden window.
+    ShowAllWindows,
+    /// Minimize, maximize or restore the window with given ID.
+    ControlWindow {
+        id: winit::window::WindowId,
+        control: WindowControl,
+    },
+    /// Queue at most `latency` frames on the surface of the window with given ID.
+    SetMaxFrameLatency {
+        id: winit::window::WindowId,
+        latency: u32,
+    },
+    /// The locale changed; every window rebuilds and lays out its view again.
+    LocaleChanged,
+    /// A font was registered; every window rebuilds and lays out its view again.
+    FontsChanged,
+    /// Create the platform cursor of `cursor` and show it in the window with given ID if it
+    /// is still the requested cursor.
+    CreateCursor {
+        id: winit::window::WindowId,
+        cursor: CustomCursor,
+    },
+    /// Send the widgets under `position` in the window with given ID to `sender`.
+    HitTest {
+        id: winit::window::WindowId,
+        position: [f32; 2],
+        sender: tokio::sync::oneshot::Sender<HitTestPath>,
+    },
+    /// A component in the window with given ID produced events for its parent outside of
+    /// input handling; pass them up to the app.
+    FlushDeferredEvents { id: winit::window::WindowId },
     // future: Custom(Box<dyn FnOnce(&mut AppState) + Send>), etc.
 }
 
 impl ApplicationContext {
+    pub(crate) fn task_executor(&self) -> &tokio::runtime::Handle {
+        &self.task_executor
+    }
+
     /// Enqueue a Exit command.
     /// This will signal the entire application to exit gracefully.
     pub fn exit(&self) {
@@ -373,9 +1033,304 @@ impl ApplicationContext {
         }
     }
 
+    /// Hide the current window. The application keeps running, so it can be shown again
+    /// with `show_all_windows` (e.g. from a tray icon).
+    pub fn hide_current_window(&self) {
+        self.send_command(
+            ApplicationCommand::SetWindowVisible {
+                id: self.window_id,
+                visible: false,
+            },
+            "hide_current_window",
+        );
+    }
+
+    /// Minimizes, maximizes or restores the current window, e.g. from the buttons of a custom
+    /// title bar. See [`crate::window_control`].
+    pub fn control_current_window(&self, control: WindowControl) {
+        self.send_command(
+            ApplicationCommand::ControlWindow {
+                id: self.window_id,
+                control,
+            },
+            "control_current_window",
+        );
+    }
+
+    /// Changes the icon of the current window, or removes it with `None`. See
+    /// [`window_icon`](crate::window_icon).
+    pub fn set_window_icon(&self, icon: Option<WindowIcon>) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().set_icon(icon.as_ref());
+        }
+    }
+
+    /// Changes the material behind the current window. See
+    /// [`window_effect`](crate::window_effect).
+    pub fn set_window_effect(&self, effect: WindowEffect) {
+        if let Some(surface) = self.window_surface.upgrade() {
+            surface.read().set_effect(effect);
+        }
+    }
+
+    /// When the frames of the current window were presented, see
+    /// [`present_timing`](crate::present_timing).
+    pub fn present_stats(&self) -> Option<PresentStats> {
+        self.window_surface
+            .upgrade()
+            .map(|surface| surface.read().present_stats())
+    }
+
+    /// Changes how many frames the surface of the current window queues ahead of the display,
+    /// at least 1. See [`App::max_frame_latency`](crate::app::App::max_frame_latency).
+    pub fn set_max_frame_latency(&self, latency: u32) {
+        self.send_command(
+            ApplicationCommand::SetMaxFrameLatency {
+                id: self.window_id,
+                latency,
+            },
+            "set_max_frame_latency",
+        );
+    }
+
+    /// The widgets under `position` (window coordinates, as in pointer events) in the current
+    /// window from the root to the innermost one, e.g. to build a context menu for whatever
+    /// was right-clicked. See [`hit_test`](crate::ui::hit_test()).
+    ///
+    /// The request is served by the event loop, so await the result outside of an update, e.g.
+    /// in a spawned task. Empty when the window is gone.
+    pub fn hit_test(
+        &self,
+        position: [f32; 2],
+    ) -> impl Future<Output = HitTestPath> + Send + 'static {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        self.send_command(
+            ApplicationCommand::HitTest {
+                id: self.window_id,
+                position,
+                sender,
+            },
+            "hit_test",
+        );
+        async move { receiver.await.unwrap_or_default() }
+    }
+
+    /// Show every window hidden with `hide_current_window` or by closing it in background mode.
+    pub fn show_all_windows(&self) {
+        self.send_command(ApplicationCommand::ShowAllWindows, "show_all_windows");
+    }
+
+    /// Shows `toast` in the current window. It is drawn by the `ToastOverlay` around the
+    /// window content.
+    pub fn notify(&self, toast: Toast) -> Option<ToastId> {
+        let toasts = self.toasts.upgrade()?;
+        trace!("ApplicationContext::notify: title={}", toast.title);
+        Some(toasts.post(self.window_id, toast, std::time::Instant::now()))
+    }
+
+    /// Hides a toast posted with `notify` before its duration ends.
+    pub fn dismiss_toast(&self, id: ToastId) {
+        if let Some(toasts) = self.toasts.upgrade() {
+            toasts.dismiss(id, std::time::Instant::now());
+        }
+    }
+
+    /// Timing of the current frame, see [`WidgetContext::frame`].
+    pub fn frame(&self) -> Option<FrameTime> {
+        self.frame_clock.upgrade().map(|clock| clock.frame())
+    }
+
+    /// Calls `callback` once `delay` of animation time has passed, see
+    /// [`WidgetContext::set_timeout`].
+    pub fn set_timeout(
+        &self,
+        delay: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        start_timer(&self.timers, &self.frame_clock, delay, None, callback)
+    }
+
+    /// Calls `callback` every `interval` of animation time, see
+    /// [`WidgetContext::set_interval`].
+    pub fn set_interval(
+        &self,
+        interval: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        start_timer(
+            &self.timers,
+            &self.frame_clock,
+            interval,
+            Some(interval),
+            callback,
+        )
+    }
+
+    /// Completes once `delay` of animation time has passed, e.g. for a
+    /// [`Command::perform`](crate::ui::command::Command::perform). Completes immediately
+    /// without timers, e.g. in tests.
+    pub fn sleep(&self, delay: Duration) -> impl Future<Output = ()> + Send + 'static {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let mut sender = Some(sender);
+        let handle = self.set_timeout(delay, move || {
+            if let Some(sender) = sender.take() {
+                let _ = sender.send(());
+            }
+        });
+        async move {
+            let _handle = handle;
+            let _ = receiver.await;
+        }
+    }
+
+    /// Loads `source` in the background, e.g. to await it in a
+    /// [`Command::perform`](crate::ui::command::Command::perform) with
+    /// [`LoadHandle::wait`]. See [`crate::resource_loader`].
+    pub fn load_resource(
+        &self,
+        source: impl Into<ResourceSource>,
+        priority: Priority,
+    ) -> LoadHandle {
+        load_resource(
+            &self.resource_loader,
+            source.into(),
+            priority,
+            None,
+            &self.task_executor,
+        )
+    }
+
+    /// The application's resource loader, e.g. to register a URL
+    /// [`Fetcher`](crate::resource_loader::Fetcher) in `setup_fn`.
+    pub fn resource_loader(&self) -> Option<Arc<ResourceLoader>> {
+        self.resource_loader.upgrade()
+    }
+
+    /// Yields every `interval` of animation time, e.g. for a
+    /// [`Command::stream`](crate::ui::command::Command::stream). Ticks missed while the loop
+    /// slept are yielded once.
+    pub fn interval(&self, interval: Duration) -> impl futures::Stream<Item = ()> + Send + 'static {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let handle = self.set_interval(interval, move || {
+            let _ = sender.send(());
+        });
+        futures::stream::unfold((receiver, handle), |(mut receiver, handle)| async move {
+            receiver.recv().await.map(|tick| (tick, (receiver, handle)))
+        })
+    }
+
+    /// Memory use and hit rates of the layout and render caches, see
+    /// [`WidgetContext::cache_stats`].
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache_budget.upgrade().map(|budget| budget.stats())
+    }
+
+    /// GPU memory held by texture atlases and renderer buffers, see
+    /// [`WidgetContext::memory_report`].
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport::collect()
+    }
+
+    /// Changes the memory budget of the layout and render caches at runtime.
+    pub fn set_cache_budget(&self, budget_bytes: usize) {
+        if let Some(budget) = self.cache_budget.upgrade() {
+            budget.set_budget(budget_bytes);
+        }
+    }
+
+    /// The current locale, e.g. `"en-US"`.
+    pub fn locale(&self) -> Option<String> {
+        self.localization
+            .upgrade()
+            .map(|localization| localization.locale())
+    }
+
+    /// Switches the locale of the app. Every window rebuilds its view, then lays out and
+    /// redraws the whole tree since translated strings change length.
+    pub fn set_locale(&self, locale: impl Into<String>) {
+        let Some(localization) = self.localization.upgrade() else {
+            warn!("ApplicationContext::set_locale: localization unavailable");
+            return;
+        };
+        if localization.set_locale(locale) {
+            self.send_command(ApplicationCommand::LocaleChanged, "set_locale");
+        }
+    }
+
+    /// Adds a font file or collection in memory to the fonts of the app. Text that may use
+    /// it is shaped, laid out and drawn again.
+    pub fn register_font_bytes(&self, bytes: impl Into<Vec<u8>>) {
+        let Some(fonts) = self.fonts.upgrade() else {
+            warn!("ApplicationContext::register_font_bytes: font registry unavailable");
+            return;
+        };
+        fonts.register_bytes(bytes);
+        self.send_command(ApplicationCommand::FontsChanged, "register_font_bytes");
+    }
+
+    /// Adds every font file in `path` and its subdirectories to the fonts of the app, see
+    /// [`Self::register_font_bytes`].
+    pub fn register_font_dir(&self, path: impl Into<std::path::PathBuf>) {
+        let Some(fonts) = self.fonts.upgrade() else {
+            warn!("ApplicationContext::register_font_dir: font registry unavailable");
+            return;
+        };
+        fonts.register_dir(path);
+        self.send_command(ApplicationCommand::FontsChanged, "register_font_dir");
+    }
+
+    /// Asks the event loop to collect the events components of the current window hold for
+    /// their parents, see [`AnyWidgetFrame::take_deferred_events`].
+    ///
+    /// [`AnyWidgetFrame::take_deferred_events`]: crate::ui::AnyWidgetFrame::take_deferred_events
+    pub(crate) fn flush_deferred_events(&self) {
+        self.send_command(
+            ApplicationCommand::FlushDeferredEvents { id: self.window_id },
+            "flush_deferred_events",
+        );
+    }
+
+    fn send_command(&self, command: ApplicationCommand, caller: &str) {
+        if let Some(sender) = self.command_sender.upgrade()
+            && let Ok(_) = sender.send(command)
+        {
+            trace!("ApplicationContext::{caller}: command sent");
+        } else {
+            warn!("ApplicationContext::{caller}: command sender unavailable");
+        }
+    }
+
     // future: push_custom, query_with_oneshot, etc.
 }
 
+fn start_timer(
+    timers: &Weak<TimerQueue>,
+    frame_clock: &Weak<FrameClock>,
+    delay: Duration,
+    interval: Option<Duration>,
+    callback: impl FnMut() + Send + 'static,
+) -> TimerHandle {
+    match (timers.upgrade(), frame_clock.upgrade()) {
+        (Some(timers), Some(clock)) => {
+            timers.start(clock.animation_time(), delay, interval, callback)
+        }
+        _ => TimerHandle::inactive(),
+    }
+}
+
+fn load_resource(
+    loader: &Weak<ResourceLoader>,
+    source: ResourceSource,
+    priority: Priority,
+    on_complete: Option<AsyncInvalidationHandle>,
+    runtime: &tokio::runtime::Handle,
+) -> LoadHandle {
+    match loader.upgrade() {
+        Some(loader) => loader.load(source, priority, on_complete, runtime),
+        None => LoadHandle::failed(source, LoadError::ShutDown, runtime),
+    }
+}
+
 #[derive(Default, Clone)]
 pub(crate) struct AnyConfig {
     configs: std::collections::HashMap<
@@ -443,75 +1398,52 @@ impl AnyConfig {
 }
 
 impl WidgetContext {
-    /// Create a minimal WidgetContext suitable for unit tests.
-    ///
-    /// This constructs local placeholder resources and returns a WidgetContext that
-    /// tests can use without a real GPU / GlobalResources. It intentionally uses
-    /// weak references where appropriate so the returned context is self-contained.
-    pub(crate) fn new_for_tests() -> Self {
-        use parking_lot::RwLock as PLRwLock;
-        use std::sync::Arc as StdArc;
-
-        // task executor: prefer existing tokio handle, otherwise create a
-        // dedicated current-thread runtime and leak it so the handle remains valid
-        trace!("WidgetContext::new_for_tests: constructing test context");
-        let task_executor: tokio::runtime::Handle = match tokio::runtime::Handle::try_current() {
-            Ok(h) => h,
-            Err(_) => {
-                // Build a current-thread runtime and leak it for the test lifetime.
-                let rt = tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()
-                    .expect("failed to build test runtime");
-                let handle = rt.handle().clone();
-                // Enter the runtime to set it as the current reactor for this thread.
-                // Leak both the runtime and the enter guard so the reactor stays active
-                // for the lifetime of the test process.
-                let guard = rt.enter();
-                Box::leak(Box::new(guard));
-                Box::leak(Box::new(rt));
-                handle
-            }
-        };
-
-        // window surface is not available in tests; use an empty Weak reference
-        let window_surface_weak: std::sync::Weak<PLRwLock<crate::window_surface::WindowSurface>> =
-            std::sync::Weak::new();
-
-        let debug_cfg = StdArc::new(PLRwLock::new(crate::debug_config::DebugConfig::default()));
-        let debug_cfg_weak = StdArc::downgrade(&debug_cfg);
-        Box::leak(Box::new(debug_cfg));
-
-        let current_time = StdArc::new(PLRwLock::new(std::time::Instant::now()));
-        let current_time_weak = StdArc::downgrade(&current_time);
-        Box::leak(Box::new(current_time));
-
-        // Other shared resources: create Weak placeholders
-        let gpu_weak = std::sync::Weak::new();
-        let texture_atlas_weak = std::sync::Weak::new();
-        let stencil_atlas_weak = std::sync::Weak::new();
-        let gpu_resource_weak = std::sync::Weak::new();
-        let any_resource_weak = std::sync::Weak::new();
-
-        // command sender/receiver pair for test context
-        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel::<ApplicationCommand>();
-        let command_sender_weak = tx.downgrade();
-        // keep sender alive so WeakUnboundedSender::upgrade() succeeds in tests
-        Box::leak(Box::new(tx));
-
+    /// A context without any resources but the command channel. Methods that need the GPU
+    /// or shared resources panic.
+    pub(crate) fn detached(
+        task_executor: tokio::runtime::Handle,
+        command_sender: &tokio::sync::mpsc::UnboundedSender<ApplicationCommand>,
+    ) -> Self {
         WidgetContext {
             task_executor,
-            window_surface: window_surface_weak,
-            current_time: current_time_weak,
-            debug_config: debug_cfg_weak,
-            gpu: gpu_weak,
-            texture_atlas: texture_atlas_weak,
-            stencil_atlas: stencil_atlas_weak,
-            gpu_resource: gpu_resource_weak,
-            any_resource: any_resource_weak,
+            window_surface: Weak::new(),
+            headless_window: Weak::new(),
+            frame_clock: Weak::new(),
+            timers: Weak::new(),
+            debug_config: Weak::new(),
+            cache_budget: Weak::new(),
+            gpu: Weak::new(),
+            texture_atlas: Weak::new(),
+            stencil_atlas: Weak::new(),
+            upload_ring: Weak::new(),
+            gpu_resource: Weak::new(),
+            renderers: Weak::new(),
+            any_resource: Weak::new(),
+            toasts: Weak::new(),
+            localization: Weak::new(),
+            fonts: Weak::new(),
+            captures: Weak::new(),
+            worker_pool: Weak::new(),
+            resource_loader: Weak::new(),
             scoped_config: AnyConfig::new(),
             window_id: winit::window::WindowId::dummy(),
-            command_sender: command_sender_weak,
+            command_sender: command_sender.downgrade(),
         }
     }
+
+    /// Points the context at the clock, timers, debug flags and window of a
+    /// [`TestContext`](crate::test_kit::TestContext).
+    pub(crate) fn with_test_resources(
+        mut self,
+        frame_clock: &Arc<FrameClock>,
+        timers: &Arc<TimerQueue>,
+        debug_config: &Arc<RwLock<DebugConfig>>,
+        headless_window: &Arc<HeadlessWindow>,
+    ) -> Self {
+        self.frame_clock = Arc::downgrade(frame_clock);
+        self.timers = Arc::downgrade(timers);
+        self.debug_config = Arc::downgrade(debug_config);
+        self.headless_window = Arc::downgrade(headless_window);
+        self
+    }
 }
@@ -0,0 +1,146 @@
+//! The look of the mouse pointer.
+//!
+//! Widgets change it with [`WidgetContext::set_cursor`](crate::context::WidgetContext::set_cursor)
+//! while the pointer is over them, e.g. to a hand over a link, and set it back to
+//! [`CursorIcon::Default`] when it leaves. The icon stays until the next change, so only the
+//! widget that changed it should reset it.
+//!
+//! Besides the [`CursorIcon`]s of the platform theme, a [`CustomCursor`] shows an image with
+//! a hotspot. Create it once, e.g. in the widget, and reuse it: the platform cursor is created
+//! on the event loop the first time it is set, so the first change may lag by an event.
+
+use std::sync::{Arc, OnceLock};
+
+use parking_lot::Mutex;
+use thiserror::Error;
+
+pub use winit::window::CursorIcon;
+
+/// A mouse pointer: one of the platform theme or a custom image.
+#[derive(Debug, Clone)]
+pub enum Cursor {
+    System(CursorIcon),
+    Custom(CustomCursor),
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self::System(CursorIcon::Default)
+    }
+}
+
+impl From<CursorIcon> for Cursor {
+    fn from(icon: CursorIcon) -> Self {
+        Self::System(icon)
+    }
+}
+
+impl From<CustomCursor> for Cursor {
+    fn from(cursor: CustomCursor) -> Self {
+        Self::Custom(cursor)
+    }
+}
+
+impl Cursor {
+    /// Whether both are the same system icon or the same custom cursor.
+    pub(crate) fn same_as(&self, other: &Cursor) -> bool {
+        match (self, other) {
+            (Self::System(a), Self::System(b)) => a == b,
+            (Self::Custom(a), Self::Custom(b)) => Arc::ptr_eq(&a.inner, &b.inner),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CursorImageError {
+    #[error("failed to decode cursor image: {0}")]
+    Decode(#[from] image::ImageError),
+    #[error("invalid cursor image: {0}")]
+    BadImage(#[from] winit::window::BadImage),
+}
+
+/// A mouse pointer showing an image. Clones share the platform cursor.
+#[derive(Debug, Clone)]
+pub struct CustomCursor {
+    inner: Arc<CustomCursorInner>,
+}
+
+#[derive(Debug)]
+struct CustomCursorInner {
+    // taken when the platform cursor is created
+    source: Mutex<Option<winit::window::CustomCursorSource>>,
+    created: OnceLock<winit::window::CustomCursor>,
+}
+
+impl CustomCursor {
+    /// Creates a cursor from `width * height` RGBA8 pixels. `hotspot` is the pixel that points
+    /// at the pointer position, from the top-left of the image.
+    pub fn from_rgba(
+        rgba: impl Into<Vec<u8>>,
+        width: u16,
+        height: u16,
+        hotspot: [u16; 2],
+    ) -> Result<Self, CursorImageError> {
+        let source =
+            winit::window::CustomCursor::from_rgba(rgba, width, height, hotspot[0], hotspot[1])?;
+        Ok(Self {
+            inner: Arc::new(CustomCursorInner {
+                source: Mutex::new(Some(source)),
+                created: OnceLock::new(),
+            }),
+        })
+    }
+
+    /// Creates a cursor from an encoded image, e.g. a PNG embedded with `include_bytes!`.
+    pub fn from_image_bytes(bytes: &[u8], hotspot: [u16; 2]) -> Result<Self, CursorImageError> {
+        let image = image::load_from_memory(bytes)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        // winit rejects images larger than `u16::MAX` anyway
+        let width = u16::try_from(width).unwrap_or(u16::MAX);
+        let height = u16::try_from(height).unwrap_or(u16::MAX);
+        Self::from_rgba(image.into_raw(), width, height, hotspot)
+    }
+
+    /// The platform cursor, if it was created already.
+    pub(crate) fn platform_cursor(&self) -> Option<winit::window::CustomCursor> {
+        self.inner.created.get().cloned()
+    }
+
+    /// Creates the platform cursor. Only possible on the event loop.
+    pub(crate) fn create_platform_cursor(
+        &self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+    ) -> Option<winit::window::CustomCursor> {
+        if let Some(cursor) = self.platform_cursor() {
+            return Some(cursor);
+        }
+        let source = self.inner.source.lock().take()?;
+        let cursor = event_loop.create_custom_cursor(source);
+        Some(self.inner.created.get_or_init(|| cursor).clone())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_of_a_custom_cursor_are_the_same_cursor() {
+        let cursor = CustomCursor::from_rgba(vec![255; 4 * 4], 2, 2, [1, 1]).unwrap();
+        let other = CustomCursor::from_rgba(vec![255; 4 * 4], 2, 2, [1, 1]).unwrap();
+
+        assert!(Cursor::from(cursor.clone()).same_as(&cursor.clone().into()));
+        assert!(!Cursor::from(cursor).same_as(&other.into()));
+        assert!(Cursor::default().same_as(&CursorIcon::Default.into()));
+    }
+
+    #[test]
+    fn rejects_hotspot_outside_the_image() {
+        assert!(matches!(
+            CustomCursor::from_rgba(vec![255; 4 * 4], 2, 2, [2, 0]),
+            Err(CursorImageError::BadImage(_))
+        ));
+    }
+}
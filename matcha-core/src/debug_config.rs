@@ -7,13 +7,13 @@ pub(crate) struct DebugConfig {
     disable_layout_measure_cache: AtomicBool,
     disable_layout_arrange_cache: AtomicBool,
     disable_render_node_cache: AtomicBool,
+    pipelined_rendering: AtomicBool,
+    show_layout_overflow: AtomicBool,
 }
 
-
-
 impl Default for DebugConfig {
     fn default() -> Self {
-        Self::new(false, false, false, false)
+        Self::new(false, false, false, false, false, false)
     }
 }
 
@@ -23,12 +23,16 @@ impl DebugConfig {
         disable_layout_measure_cache: bool,
         disable_layout_arrange_cache: bool,
         disable_render_node_cache: bool,
+        pipelined_rendering: bool,
+        show_layout_overflow: bool,
     ) -> Self {
         Self {
             always_rebuild_widget: AtomicBool::new(always_rebuild_widget),
             disable_layout_measure_cache: AtomicBool::new(disable_layout_measure_cache),
             disable_layout_arrange_cache: AtomicBool::new(disable_layout_arrange_cache),
             disable_render_node_cache: AtomicBool::new(disable_render_node_cache),
+            pipelined_rendering: AtomicBool::new(pipelined_rendering),
+            show_layout_overflow: AtomicBool::new(show_layout_overflow),
         }
     }
 
@@ -66,4 +70,24 @@ impl DebugConfig {
         self.disable_render_node_cache
             .store(value, Ordering::Relaxed);
     }
+
+    /// Whether a window updates and lays out its next frame while the GPU work of the
+    /// previous one is submitted and presented in the background.
+    pub fn pipelined_rendering(&self) -> bool {
+        self.pipelined_rendering.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_pipelined_rendering(&self, value: bool) {
+        self.pipelined_rendering.store(value, Ordering::Relaxed);
+    }
+
+    /// Whether edges that children overflow are painted with stripes, see
+    /// [`overflow`](crate::ui::overflow).
+    pub fn show_layout_overflow(&self) -> bool {
+        self.show_layout_overflow.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_show_layout_overflow(&self, value: bool) {
+        self.show_layout_overflow.store(value, Ordering::Relaxed);
+    }
 }
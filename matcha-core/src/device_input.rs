@@ -14,8 +14,10 @@ pub use key_input::{Key, KeyCode, KeyInput, KeyLocation, ModifiersState, Physica
 pub use key_state::KeyboardState;
 pub use mouse_input::MouseInput;
 pub use mouse_input::MouseLogicalButton;
+pub use mouse_input::PointerPosition;
 pub use mouse_state::MouseState;
-pub use winit::window::Theme;
+pub use winit::event::Ime;
+pub use winit::window::{ImePurpose, Theme};
 
 // MARK: Event
 
@@ -29,6 +31,8 @@ pub struct DeviceInput {
     mouse_view_port_position: [f32; 2],
     left_multiplied_transform: nalgebra::Matrix4<f32>,
     left_multiplied_transform_inv: Option<nalgebra::Matrix4<f32>>,
+    // the pointer is treated as outside of the receiving widget, see `with_pointer_outside`.
+    pointer_outside: bool,
     // relative event.
     relative: DeviceInputData,
 }
@@ -47,6 +51,7 @@ impl DeviceInput {
             mouse_view_port_position: mouse_position,
             left_multiplied_transform: nalgebra::Matrix4::identity(),
             left_multiplied_transform_inv: Some(nalgebra::Matrix4::identity()),
+            pointer_outside: false,
             relative: event,
         }
     }
@@ -55,6 +60,23 @@ impl DeviceInput {
         let mut new = self.clone();
         new.left_multiplied_transform = self.left_multiplied_transform * child_affine;
         new.left_multiplied_transform_inv = new.left_multiplied_transform.try_inverse();
+        if let DeviceInputData::MouseInput { position, .. } = &mut new.relative {
+            position.local = to_local(new.left_multiplied_transform_inv, position.window)
+                .unwrap_or([f32::INFINITY, f32::INFINITY]);
+        }
+        new
+    }
+
+    /// The same input with the pointer outside of the widget receiving it: [`mouse_position`]
+    /// returns `None` for it and its descendants.
+    ///
+    /// Containers whose [hit shape](crate::ui::hit_test::HitShape) excludes the pointer pass
+    /// this on, so children do not react to a pointer over their bounds but outside the shape.
+    ///
+    /// [`mouse_position`]: Self::mouse_position
+    pub fn with_pointer_outside(&self) -> Self {
+        let mut new = self.clone();
+        new.pointer_outside = true;
         new
     }
 
@@ -66,15 +88,31 @@ impl DeviceInput {
 
 /// getter
 impl DeviceInput {
+    /// The pointer in the coordinates of the widget receiving this input, including all
+    /// transforms of its ancestors.
     pub fn mouse_position(&self) -> Option<[f32; 2]> {
-        let relative_position = self.left_multiplied_transform_inv?
-            * nalgebra::Vector4::new(
-                self.mouse_view_port_position[0],
-                self.mouse_view_port_position[1],
-                0.0,
-                1.0,
-            );
-        Some([relative_position.x, relative_position.y])
+        if self.pointer_outside {
+            return None;
+        }
+        self.viewport_to_local(self.mouse_view_port_position)
+    }
+
+    /// Converts a position in the window to the coordinates of the widget receiving this input.
+    pub fn viewport_to_local(&self, position: [f32; 2]) -> Option<[f32; 2]> {
+        to_local(self.left_multiplied_transform_inv, position)
+    }
+
+    /// Converts a position in the coordinates of the widget receiving this input to the window.
+    pub fn local_to_viewport(&self, position: [f32; 2]) -> [f32; 2] {
+        let viewport_position = self.left_multiplied_transform
+            * nalgebra::Vector4::new(position[0], position[1], 0.0, 1.0);
+        [viewport_position.x, viewport_position.y]
+    }
+
+    /// The transforms of all ancestors of the widget receiving this input, combined: maps its
+    /// coordinates to the window.
+    pub fn local_to_viewport_transform(&self) -> nalgebra::Matrix4<f32> {
+        self.left_multiplied_transform
     }
 
     pub fn raw_event(&self) -> &DeviceInputData {
@@ -85,6 +123,18 @@ impl DeviceInput {
         self.raw_winit.as_ref()
     }
 
+    /// The pointer of a mouse input in the window and in the coordinates of the widget
+    /// receiving it. `None` for other input and when the pointer is treated as outside.
+    pub fn pointer_position(&self) -> Option<PointerPosition> {
+        if self.pointer_outside {
+            return None;
+        }
+        match &self.relative {
+            DeviceInputData::MouseInput { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
     pub fn mouse_view_port_position(&self) -> [f32; 2] {
         self.mouse_view_port_position
     }
@@ -94,6 +144,12 @@ impl DeviceInput {
     }
 }
 
+/// Applies the inverse of a widget's transform to a window position.
+fn to_local(inverse: Option<nalgebra::Matrix4<f32>>, position: [f32; 2]) -> Option<[f32; 2]> {
+    let local = inverse? * nalgebra::Vector4::new(position[0], position[1], 0.0, 1.0);
+    Some([local.x, local.y])
+}
+
 // todo: implement: on_drag_start / on_drag_end, on_focus / on_blur
 
 /// Mouse click event
@@ -333,6 +389,17 @@ impl DeviceInput {
         }
     }
 
+    /// Relative mouse motion while the pointer is locked.
+    pub fn on_pointer_motion<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce([f32; 2]) -> R,
+    {
+        match &self.relative {
+            DeviceInputData::PointerMotion { delta } => Some(f(*delta)),
+            _ => None,
+        }
+    }
+
     pub fn on_key_down<F, R>(&self, f: F) -> Option<R>
     where
         F: FnOnce(&KeyInput) -> R,
@@ -365,6 +432,17 @@ impl DeviceInput {
         }
     }
 
+    /// Input method events: the text being composed (preedit) and the text committed.
+    pub fn on_ime<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&Ime) -> R,
+    {
+        match &self.relative {
+            DeviceInputData::Ime(ime) => Some(f(ime)),
+            _ => None,
+        }
+    }
+
     pub fn on_file_drop<F, R>(&self, f: F) -> Option<R>
     where
         F: FnOnce(&PathBuf) -> R,
@@ -405,15 +483,87 @@ pub enum DeviceInputData {
     },
     FileHoverCancelled,
     Keyboard(KeyInput),
-    /// not implemented yet
-    Ime,
+    /// Composition and committed text from an input method. Only sent to windows where a
+    /// widget has enabled it with `WidgetContext::set_ime`.
+    Ime(Ime),
+    /// Pointer movement, buttons and scrolling. Drag origins are in window coordinates.
     MouseInput {
+        /// Where the pointer is, converted to the receiving widget as the input is passed down.
+        position: PointerPosition,
         dragging_from_primary: Option<[f32; 2]>,
         dragging_from_secondary: Option<[f32; 2]>,
         dragging_from_middle: Option<[f32; 2]>,
         event: Option<MouseInput>,
     },
+    /// Relative motion of the mouse while the pointer is locked with
+    /// `WidgetContext::lock_pointer`, in the unaccelerated units of the device.
+    PointerMotion {
+        delta: [f32; 2],
+    },
+    /// The locked pointer was released by focus loss or Escape, not by the widget.
+    PointerUnlocked,
     /// not implemented yet
     Touch,
     Theme(Theme),
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn mouse_move(position: [f32; 2]) -> DeviceInput {
+        DeviceInput::new(
+            position,
+            DeviceInputData::MouseInput {
+                position: PointerPosition::new(position),
+                dragging_from_primary: None,
+                dragging_from_secondary: None,
+                dragging_from_middle: None,
+                event: None,
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn pointer_position_follows_transforms() {
+        let input = mouse_move([30.0, 50.0]);
+        assert_eq!(
+            input.pointer_position(),
+            Some(PointerPosition::new([30.0, 50.0]))
+        );
+
+        let translated = input.transform(nalgebra::Matrix4::new_translation(
+            &nalgebra::Vector3::new(10.0, 20.0, 0.0),
+        ));
+        let scaled = translated.transform(nalgebra::Matrix4::new_nonuniform_scaling(
+            &nalgebra::Vector3::new(2.0, 2.0, 1.0),
+        ));
+        let position = scaled.pointer_position().unwrap();
+        assert_eq!(position.window, [30.0, 50.0]);
+        assert_eq!(position.local, [10.0, 15.0]);
+        assert_eq!(scaled.mouse_position(), Some(position.local));
+
+        let collapsed = input.transform(nalgebra::Matrix4::zeros());
+        assert!(collapsed.pointer_position().unwrap().local[0].is_infinite());
+        assert_eq!(scaled.with_pointer_outside().pointer_position(), None);
+    }
+
+    #[test]
+    fn relative_motion_is_not_transformed() {
+        let motion = DeviceInput::new(
+            [30.0, 50.0],
+            DeviceInputData::PointerMotion { delta: [3.0, -2.0] },
+            None,
+        );
+        let scaled = motion.transform(nalgebra::Matrix4::new_nonuniform_scaling(
+            &nalgebra::Vector3::new(2.0, 2.0, 1.0),
+        ));
+        assert_eq!(scaled.on_pointer_motion(|delta| delta), Some([3.0, -2.0]));
+        assert_eq!(
+            mouse_move([30.0, 50.0]).on_pointer_motion(|delta| delta),
+            None
+        );
+    }
+}
@@ -1,18 +1,102 @@
 use super::{ElementState, KeyboardState};
-use winit::{event::KeyEvent as RawKeyEvent, keyboard::NamedKey};
+use winit::{
+    event::{ElementState as RawElementState, KeyEvent as RawKeyEvent},
+    keyboard::{NamedKey, NativeKeyCode, SmolStr},
+};
 
 pub use winit::keyboard::{Key, KeyCode, KeyLocation, ModifiersState, PhysicalKey};
 
 /// A keyboard event.
 ///
-/// This struct contains the raw `winit::event::KeyEvent` and a snapshot of the
+/// This struct contains the key that triggered the event and a snapshot of the
 /// entire keyboard state at the moment the event occurred.
+///
+/// A key has two identities: the [physical key](Self::physical_key), its position on the
+/// keyboard, and the [logical key](Self::logical_key), what the keyboard layout maps it to.
+/// Text entry uses [`text`](Self::text), which already includes the accent of a preceding dead
+/// key; shortcuts use [`shortcut_key`](Self::shortcut_key), which works across layouts.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeyInput {
-    pub winit: RawKeyEvent,
+    physical_key: PhysicalKey,
+    logical_key: Key,
+    key_without_modifiers: Key,
+    text: Option<SmolStr>,
+    location: KeyLocation,
+    state: RawElementState,
+    repeat: bool,
+    repeat_count: u32,
+    composed_with: Option<char>,
+    // `None` for synthetic events; boxed since the fields above already copy most of it
+    winit: Option<Box<RawKeyEvent>>,
     pub snapshot: KeyboardState,
 }
 
+impl KeyInput {
+    pub(crate) fn from_winit(event: RawKeyEvent, snapshot: KeyboardState) -> Self {
+        Self {
+            physical_key: event.physical_key,
+            logical_key: event.logical_key.clone(),
+            key_without_modifiers: winit_key_without_modifiers(&event),
+            text: event.text.clone(),
+            location: event.location,
+            state: event.state,
+            repeat: event.repeat,
+            repeat_count: 0,
+            composed_with: None,
+            winit: Some(Box::new(event)),
+            snapshot,
+        }
+    }
+
+    /// A key event that did not come from the platform, e.g. one injected by a test driver.
+    ///
+    /// winit key events cannot be constructed outside winit, so synthetic events carry the
+    /// key data without a raw event. The key without modifiers is the lowercased logical key.
+    pub fn synthetic(
+        physical_key: PhysicalKey,
+        logical_key: Key,
+        text: Option<&str>,
+        state: RawElementState,
+        snapshot: KeyboardState,
+    ) -> Self {
+        Self {
+            physical_key,
+            key_without_modifiers: unshifted_key(&logical_key),
+            logical_key,
+            text: text.map(SmolStr::new),
+            location: KeyLocation::Standard,
+            state,
+            repeat: false,
+            repeat_count: 0,
+            composed_with: None,
+            winit: None,
+            snapshot,
+        }
+    }
+
+    /// The same event as the `repeat_count`-th autorepeat of its key, composed with the dead
+    /// key `composed_with`, as tracked by the [`KeyboardState`].
+    pub(crate) fn with_sequence(mut self, repeat_count: u32, composed_with: Option<char>) -> Self {
+        self.repeat = repeat_count > 0;
+        self.repeat_count = repeat_count;
+        self.composed_with = composed_with;
+        self
+    }
+
+    /// The same event at `location` and with the repeat flag of the event it replays, see
+    /// [`input_recording`](crate::input_recording).
+    pub(crate) fn with_location_and_repeat(mut self, location: KeyLocation, repeat: bool) -> Self {
+        self.location = location;
+        self.repeat = repeat;
+        self
+    }
+
+    /// The winit event this input was created from, `None` for synthetic events.
+    pub fn raw_winit(&self) -> Option<&RawKeyEvent> {
+        self.winit.as_deref()
+    }
+}
+
 // --- Methods for the key that triggered the event ---
 
 impl KeyInput {
@@ -28,33 +112,208 @@ impl KeyInput {
     // todo: Implement a rest of keys
 }
 
-/// Raw information about the key that triggered this event.
-///
-/// These methods delegate directly to the underlying `winit::event::KeyEvent`.
+/// Information about the key that triggered this event.
 impl KeyInput {
     pub fn physical_key(&self) -> PhysicalKey {
-        self.winit.physical_key
+        self.physical_key
     }
 
     pub fn logical_key(&self) -> &Key {
-        &self.winit.logical_key
+        &self.logical_key
     }
 
     pub fn text(&self) -> Option<&str> {
-        self.winit.text.as_deref()
+        self.text.as_deref()
     }
 
     pub fn location(&self) -> KeyLocation {
-        self.winit.location
+        self.location
     }
 
+    /// The state of the key, with the number of presses since the key went down: `1` for the
+    /// first press and one more for every autorepeat.
     pub fn state(&self) -> ElementState {
-        self.winit.state.into()
+        let count = match self.state {
+            RawElementState::Pressed => self.repeat_count + 1,
+            RawElementState::Released => 1,
+        };
+        ElementState::from_winit_state_with_count(self.state, count)
     }
 
+    /// Returns `true` if this press was generated by the platform's autorepeat of a held key.
     pub fn is_repeat(&self) -> bool {
-        self.winit.repeat
+        self.repeat
+    }
+
+    /// Autorepeats of the held key up to this one, `0` for the initial press and releases.
+    pub fn repeat_count(&self) -> u32 {
+        self.repeat_count
+    }
+
+    /// The key code of the physical key, `None` for keys winit does not identify.
+    pub fn key_code(&self) -> Option<KeyCode> {
+        match self.physical_key {
+            PhysicalKey::Code(key_code) => Some(key_code),
+            PhysicalKey::Unidentified(_) => None,
+        }
+    }
+
+    /// The platform scancode of the physical key, `None` where the platform does not report
+    /// one.
+    pub fn scancode(&self) -> Option<u32> {
+        match self.physical_key {
+            PhysicalKey::Unidentified(native) => match native {
+                NativeKeyCode::Unidentified => None,
+                NativeKeyCode::Android(code) | NativeKeyCode::Xkb(code) => Some(code),
+                NativeKeyCode::MacOS(code) | NativeKeyCode::Windows(code) => Some(u32::from(code)),
+            },
+            physical_key => platform_scancode(physical_key),
+        }
+    }
+
+    /// The logical key as if no modifiers were held, e.g. `a` for Shift+A and `1` for `!` on
+    /// a US layout. Unlike the logical key, this is never a dead key.
+    pub fn key_without_modifiers(&self) -> &Key {
+        &self.key_without_modifiers
+    }
+
+    /// The key to match shortcuts against: the [key without
+    /// modifiers](Self::key_without_modifiers), except that letter and digit keys producing a
+    /// non-ASCII character, e.g. on Cyrillic or Greek layouts, report the character at the same
+    /// position of a US layout, so `Ctrl+C` works whichever layout is active.
+    pub fn shortcut_key(&self) -> Key {
+        if let Key::Character(c) = &self.key_without_modifiers
+            && !c.is_ascii()
+            && let Some(latin) = self.key_code().and_then(latin_character)
+        {
+            return Key::Character(SmolStr::new_inline(latin));
+        }
+        self.key_without_modifiers.clone()
     }
+
+    /// Returns `true` if this is a dead key, which produces no text itself but adds an accent
+    /// to the next character.
+    pub fn is_dead_key(&self) -> bool {
+        matches!(self.logical_key, Key::Dead(_))
+    }
+
+    /// The accent of the dead key pressed before this key, whose composed character is in
+    /// [`text`](Self::text), e.g. `´` for `é`. If the accent does not combine with this key,
+    /// the text has both, e.g. `´x`.
+    pub fn composed_with(&self) -> Option<char> {
+        self.composed_with
+    }
+}
+
+fn unshifted_key(key: &Key) -> Key {
+    match key {
+        Key::Character(c) => Key::Character(c.to_lowercase().into()),
+        Key::Dead(Some(accent)) => Key::Character(accent.to_string().into()),
+        key => key.clone(),
+    }
+}
+
+#[cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(
+        unix,
+        not(any(target_os = "ios", target_os = "android", target_os = "emscripten"))
+    )
+))]
+fn winit_key_without_modifiers(event: &RawKeyEvent) -> Key {
+    use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
+    event.key_without_modifiers()
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(
+        unix,
+        not(any(target_os = "ios", target_os = "android", target_os = "emscripten"))
+    )
+)))]
+fn winit_key_without_modifiers(event: &RawKeyEvent) -> Key {
+    unshifted_key(&event.logical_key)
+}
+
+#[cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(
+        unix,
+        not(any(
+            target_os = "ios",
+            target_os = "android",
+            target_os = "emscripten",
+            target_os = "redox"
+        ))
+    )
+))]
+fn platform_scancode(physical_key: PhysicalKey) -> Option<u32> {
+    use winit::platform::scancode::PhysicalKeyExtScancode;
+    physical_key.to_scancode()
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(
+        unix,
+        not(any(
+            target_os = "ios",
+            target_os = "android",
+            target_os = "emscripten",
+            target_os = "redox"
+        ))
+    )
+)))]
+fn platform_scancode(_physical_key: PhysicalKey) -> Option<u32> {
+    None
+}
+
+/// The character of a letter or digit key on a US layout.
+fn latin_character(key_code: KeyCode) -> Option<&'static str> {
+    Some(match key_code {
+        KeyCode::KeyA => "a",
+        KeyCode::KeyB => "b",
+        KeyCode::KeyC => "c",
+        KeyCode::KeyD => "d",
+        KeyCode::KeyE => "e",
+        KeyCode::KeyF => "f",
+        KeyCode::KeyG => "g",
+        KeyCode::KeyH => "h",
+        KeyCode::KeyI => "i",
+        KeyCode::KeyJ => "j",
+        KeyCode::KeyK => "k",
+        KeyCode::KeyL => "l",
+        KeyCode::KeyM => "m",
+        KeyCode::KeyN => "n",
+        KeyCode::KeyO => "o",
+        KeyCode::KeyP => "p",
+        KeyCode::KeyQ => "q",
+        KeyCode::KeyR => "r",
+        KeyCode::KeyS => "s",
+        KeyCode::KeyT => "t",
+        KeyCode::KeyU => "u",
+        KeyCode::KeyV => "v",
+        KeyCode::KeyW => "w",
+        KeyCode::KeyX => "x",
+        KeyCode::KeyY => "y",
+        KeyCode::KeyZ => "z",
+        KeyCode::Digit0 => "0",
+        KeyCode::Digit1 => "1",
+        KeyCode::Digit2 => "2",
+        KeyCode::Digit3 => "3",
+        KeyCode::Digit4 => "4",
+        KeyCode::Digit5 => "5",
+        KeyCode::Digit6 => "6",
+        KeyCode::Digit7 => "7",
+        KeyCode::Digit8 => "8",
+        KeyCode::Digit9 => "9",
+        _ => return None,
+    })
 }
 
 // --- Methods for the keyboard state at the moment of the event ---
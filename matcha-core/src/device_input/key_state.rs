@@ -1,10 +1,15 @@
 use super::{DeviceInputData, KeyInput};
 use std::collections::VecDeque;
+use winit::keyboard::{Key, NamedKey, PhysicalKey};
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub struct KeyboardState {
     press_order: VecDeque<(winit::keyboard::KeyCode, winit::keyboard::Key)>,
     modifiers: winit::keyboard::ModifiersState,
+    // autorepeats since the last press of a new key
+    repeat_count: u32,
+    // the accent of a dead key waiting for the key it combines with
+    dead_key: Option<char>,
 }
 
 // `KeyboardState` assumes winit issues `ModifiersChanged` followed by `KeyboardInput`.
@@ -17,32 +22,106 @@ impl KeyboardState {
         self.modifiers = modifiers;
     }
 
+    /// Records a key event from winit.
+    ///
+    /// Keys without a key code, e.g. keys winit does not know, are passed on with their native
+    /// scancode but not tracked as pressed.
     pub fn keyboard_input(&mut self, key_event: winit::event::KeyEvent) -> Option<DeviceInputData> {
-        let winit::keyboard::PhysicalKey::Code(key_code) = key_event.physical_key else {
-            return None;
-        };
-        match key_event.state {
-            winit::event::ElementState::Pressed => {
-                self.press_order
-                    .push_back((key_code, key_event.logical_key.clone()));
-            }
-            winit::event::ElementState::Released => {
-                if let Some(pos) = self
+        let (repeat_count, composed_with) = self.key_changed(
+            key_event.physical_key,
+            &key_event.logical_key,
+            key_event.state,
+            key_event.repeat,
+        );
+        Some(DeviceInputData::Keyboard(
+            KeyInput::from_winit(key_event, self.clone())
+                .with_sequence(repeat_count, composed_with),
+        ))
+    }
+
+    /// Records a key event that did not come from winit.
+    ///
+    /// Keys without a key code, e.g. characters of typed text, are passed on but not tracked
+    /// as pressed. A press of a key that is still held counts as an autorepeat.
+    pub fn synthetic_input(
+        &mut self,
+        physical_key: winit::keyboard::PhysicalKey,
+        logical_key: winit::keyboard::Key,
+        text: Option<&str>,
+        state: winit::event::ElementState,
+    ) -> DeviceInputData {
+        let repeat = state == winit::event::ElementState::Pressed
+            && matches!(physical_key, PhysicalKey::Code(key_code) if self.is_physical_pressed(&key_code));
+        let (repeat_count, composed_with) =
+            self.key_changed(physical_key, &logical_key, state, repeat);
+        DeviceInputData::Keyboard(
+            KeyInput::synthetic(physical_key, logical_key, text, state, self.clone())
+                .with_sequence(repeat_count, composed_with),
+        )
+    }
+
+    /// Updates the state for a key event and returns its repeat count and the dead key it
+    /// was composed with.
+    fn key_changed(
+        &mut self,
+        physical_key: PhysicalKey,
+        logical_key: &Key,
+        state: winit::event::ElementState,
+        repeat: bool,
+    ) -> (u32, Option<char>) {
+        if state == winit::event::ElementState::Released {
+            if let PhysicalKey::Code(key_code) = physical_key
+                && let Some(pos) = self
                     .press_order
                     .iter()
                     .position(|(code, _)| *code == key_code)
-                {
-                    self.press_order.remove(pos);
-                }
+            {
+                self.press_order.remove(pos);
             }
+            return (0, None);
         }
-        Some(DeviceInputData::Keyboard(KeyInput {
-            winit: key_event,
-            snapshot: self.clone(),
-        }))
+
+        if repeat {
+            self.repeat_count += 1;
+            return (self.repeat_count, None);
+        }
+        self.repeat_count = 0;
+        if let PhysicalKey::Code(key_code) = physical_key {
+            // a key whose release was missed, e.g. while the window was unfocused, is pressed
+            // again rather than listed twice
+            self.press_order.retain(|(code, _)| *code != key_code);
+            self.press_order.push_back((key_code, logical_key.clone()));
+        }
+
+        let composed_with = match logical_key {
+            Key::Dead(accent) => {
+                self.dead_key = *accent;
+                None
+            }
+            // modifiers, e.g. Shift for a capital letter, leave the dead key waiting
+            Key::Named(named) if is_modifier(*named) => None,
+            _ => self.dead_key.take(),
+        };
+        (0, composed_with)
     }
 }
 
+fn is_modifier(key: NamedKey) -> bool {
+    matches!(
+        key,
+        NamedKey::Shift
+            | NamedKey::Control
+            | NamedKey::Alt
+            | NamedKey::AltGraph
+            | NamedKey::Super
+            | NamedKey::Meta
+            | NamedKey::Hyper
+            | NamedKey::Fn
+            | NamedKey::CapsLock
+            | NamedKey::NumLock
+    )
+}
+
 impl KeyboardState {
     pub fn is_physical_pressed(&self, key: &winit::keyboard::KeyCode) -> bool {
         self.press_order.iter().any(|(code, _)| code == key)
@@ -61,6 +140,12 @@ impl KeyboardState {
     pub fn press_order(&self) -> Vec<(winit::keyboard::KeyCode, winit::keyboard::Key)> {
         self.press_order.iter().cloned().collect()
     }
+
+    /// The accent of a dead key that was pressed and waits for the key it combines with, e.g.
+    /// to show it in a text field before the composed character arrives.
+    pub fn pending_dead_key(&self) -> Option<char> {
+        self.dead_key
+    }
 }
 
 #[cfg(test)]
@@ -272,4 +357,125 @@ mod tests {
         assert!(ks.is_physical_pressed(&KeyCode::KeyA));
         assert_eq!(ks.modifiers(), modifiers);
     }
+
+    #[test]
+    fn synthetic_input_tracks_key_codes() {
+        use winit::event::ElementState;
+        use winit::keyboard::{NativeKeyCode, PhysicalKey};
+
+        let mut ks = KeyboardState::new();
+        let DeviceInputData::Keyboard(input) = ks.synthetic_input(
+            PhysicalKey::Code(KeyCode::KeyA),
+            Key::Character("a".into()),
+            Some("a"),
+            ElementState::Pressed,
+        ) else {
+            panic!("expected a keyboard event");
+        };
+        assert_eq!(input.text(), Some("a"));
+        assert!(input.raw_winit().is_none());
+        assert!(input.is_physical_pressed(KeyCode::KeyA));
+
+        // typed characters without a key code are not tracked
+        ks.synthetic_input(
+            PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+            Key::Character("é".into()),
+            Some("é"),
+            ElementState::Pressed,
+        );
+        assert_eq!(ks.press_order().len(), 1);
+
+        ks.synthetic_input(
+            PhysicalKey::Code(KeyCode::KeyA),
+            Key::Character("a".into()),
+            None,
+            ElementState::Released,
+        );
+        assert!(ks.press_order().is_empty());
+    }
+
+    #[test]
+    fn autorepeats_are_counted_without_duplicating_keys() {
+        use crate::device_input::ElementState as CountedState;
+        use winit::event::ElementState;
+
+        let mut ks = KeyboardState::new();
+        let press = |ks: &mut KeyboardState, key_code, state| {
+            let DeviceInputData::Keyboard(input) = ks.synthetic_input(
+                PhysicalKey::Code(key_code),
+                Key::Character("a".into()),
+                None,
+                state,
+            ) else {
+                panic!("expected a keyboard event");
+            };
+            input
+        };
+
+        let first = press(&mut ks, KeyCode::KeyA, ElementState::Pressed);
+        assert!(!first.is_repeat());
+        assert_eq!(first.state(), CountedState::Pressed(1));
+        press(&mut ks, KeyCode::KeyA, ElementState::Pressed);
+        let third = press(&mut ks, KeyCode::KeyA, ElementState::Pressed);
+        assert!(third.is_repeat());
+        assert_eq!(third.repeat_count(), 2);
+        assert_eq!(third.state(), CountedState::Pressed(3));
+        assert_eq!(ks.press_order().len(), 1);
+
+        // a new key starts counting again
+        let other = press(&mut ks, KeyCode::KeyB, ElementState::Pressed);
+        assert_eq!(other.repeat_count(), 0);
+
+        press(&mut ks, KeyCode::KeyA, ElementState::Released);
+        assert!(!ks.is_physical_pressed(&KeyCode::KeyA));
+        assert!(ks.is_physical_pressed(&KeyCode::KeyB));
+    }
+
+    #[test]
+    fn dead_keys_compose_with_the_next_key() {
+        use winit::event::ElementState;
+
+        let mut ks = KeyboardState::new();
+        let input = |ks: &mut KeyboardState, key_code, logical_key, text| {
+            let DeviceInputData::Keyboard(input) = ks.synthetic_input(
+                PhysicalKey::Code(key_code),
+                logical_key,
+                text,
+                ElementState::Pressed,
+            ) else {
+                panic!("expected a keyboard event");
+            };
+            input
+        };
+
+        let dead = input(&mut ks, KeyCode::Quote, Key::Dead(Some('´')), None);
+        assert!(dead.is_dead_key());
+        assert_eq!(dead.key_without_modifiers(), &Key::Character("´".into()));
+        assert_eq!(ks.pending_dead_key(), Some('´'));
+
+        // Shift for a capital letter keeps the accent waiting
+        input(
+            &mut ks,
+            KeyCode::ShiftLeft,
+            Key::Named(NamedKey::Shift),
+            None,
+        );
+        let composed = input(
+            &mut ks,
+            KeyCode::KeyE,
+            Key::Character("É".into()),
+            Some("É"),
+        );
+        assert_eq!(composed.composed_with(), Some('´'));
+        assert_eq!(composed.text(), Some("É"));
+        assert_eq!(ks.pending_dead_key(), None);
+
+        let plain = input(
+            &mut ks,
+            KeyCode::KeyX,
+            Key::Character("x".into()),
+            Some("x"),
+        );
+        assert_eq!(plain.composed_with(), None);
+    }
 }
@@ -23,3 +23,23 @@ pub enum MouseInput {
         delta: [f32; 2],
     },
 }
+
+/// The pointer of a mouse input, in the window and in the coordinates of the widget receiving it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointerPosition {
+    /// Position in the window, from its top left corner.
+    pub window: [f32; 2],
+    /// Position relative to the receiving widget, including all transforms of its ancestors.
+    /// Infinite when a transform on the way cannot be inverted.
+    pub local: [f32; 2],
+}
+
+impl PointerPosition {
+    /// A position at the root of the widget tree, where local and window coordinates are the same.
+    pub fn new(window: [f32; 2]) -> Self {
+        Self {
+            window,
+            local: window,
+        }
+    }
+}
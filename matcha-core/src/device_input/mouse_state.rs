@@ -1,4 +1,4 @@
-use super::{ButtonState, DeviceInputData, MouseInput, MouseLogicalButton};
+use super::{ButtonState, DeviceInputData, MouseInput, MouseLogicalButton, PointerPosition};
 
 use std::time::{Duration, Instant};
 use winit::{
@@ -164,32 +164,17 @@ impl MouseState {
             self.forward_dragging_from = Some(prev_position);
         }
 
-        Self::new_mouse_event(
-            self.dragging_from_primary,
-            self.dragging_from_secondary,
-            self.dragging_from_middle,
-            None,
-        )
+        self.mouse_event(None)
     }
 
     /// Generates a `CursorEntered` event.
     pub fn cursor_entered(&self) -> DeviceInputData {
-        Self::new_mouse_event(
-            self.dragging_from_primary,
-            self.dragging_from_secondary,
-            self.dragging_from_middle,
-            Some(MouseInput::Entered),
-        )
+        self.mouse_event(Some(MouseInput::Entered))
     }
 
     /// Generates a `CursorLeft` event.
     pub fn cursor_left(&self) -> DeviceInputData {
-        Self::new_mouse_event(
-            self.dragging_from_primary,
-            self.dragging_from_secondary,
-            self.dragging_from_middle,
-            Some(MouseInput::Left),
-        )
+        self.mouse_event(Some(MouseInput::Left))
     }
 
     /// Generates a `MouseScroll` event.
@@ -199,21 +184,26 @@ impl MouseState {
             MouseScrollDelta::PixelDelta(PhysicalPosition { x, y }) => [x as f32, y as f32],
         };
 
-        Self::new_mouse_event(
-            self.dragging_from_primary,
-            self.dragging_from_secondary,
-            self.dragging_from_middle,
-            Some(MouseInput::Scroll { delta }),
-        )
+        self.mouse_event(Some(MouseInput::Scroll { delta }))
     }
 
     pub fn mouse_input(
         &mut self,
         physical_button: WinitMouseButton,
         state: winit::event::ElementState,
+    ) -> Option<DeviceInputData> {
+        self.mouse_input_at(physical_button, state, Instant::now())
+    }
+
+    /// [`mouse_input`](Self::mouse_input) at the given time, for input with a simulated clock.
+    pub(crate) fn mouse_input_at(
+        &mut self,
+        physical_button: WinitMouseButton,
+        state: winit::event::ElementState,
+        now: Instant,
     ) -> Option<DeviceInputData> {
         match state {
-            winit::event::ElementState::Pressed => self.button_pressed(physical_button),
+            winit::event::ElementState::Pressed => self.button_pressed(physical_button, now),
             winit::event::ElementState::Released => self.button_released(physical_button),
         }
     }
@@ -221,23 +211,20 @@ impl MouseState {
     /// Handles a mouse button press event.
     ///
     /// It updates the click combo count and status for the given button and generates a `Pressed` event.
-    fn button_pressed(&mut self, physical_button: WinitMouseButton) -> Option<DeviceInputData> {
-        let now = Instant::now();
-
+    fn button_pressed(
+        &mut self,
+        physical_button: WinitMouseButton,
+        now: Instant,
+    ) -> Option<DeviceInputData> {
         let logical_button = self.to_logical_button(physical_button)?;
         let combo_duration = self.combo_duration;
         let (button_state, _) = self.get_mut_button_state(logical_button);
         let click_state = button_state.press(now, combo_duration);
 
-        Some(Self::new_mouse_event(
-            self.dragging_from_primary,
-            self.dragging_from_secondary,
-            self.dragging_from_middle,
-            Some(MouseInput::Click {
-                click_state,
-                button: logical_button,
-            }),
-        ))
+        Some(self.mouse_event(Some(MouseInput::Click {
+            click_state,
+            button: logical_button,
+        })))
     }
 
     /// Handles a mouse button release event.
@@ -249,15 +236,10 @@ impl MouseState {
         let click_state = button_state.release();
         *dragging_from = None;
 
-        Some(Self::new_mouse_event(
-            self.dragging_from_primary,
-            self.dragging_from_secondary,
-            self.dragging_from_middle,
-            Some(MouseInput::Click {
-                click_state,
-                button: logical_button,
-            }),
-        ))
+        Some(self.mouse_event(Some(MouseInput::Click {
+            click_state,
+            button: logical_button,
+        })))
     }
 
     /// Detects long presses for all mouse buttons.
@@ -266,8 +248,11 @@ impl MouseState {
     /// held down for the `long_press_duration` without being dragged, and if so, generates
     /// a `LongPressed` event.
     pub fn long_pressing_detection(&mut self) -> Vec<DeviceInputData> {
-        let now = Instant::now();
+        self.long_pressing_detection_at(Instant::now())
+    }
 
+    /// [`long_pressing_detection`](Self::long_pressing_detection) at the given time.
+    pub(crate) fn long_pressing_detection_at(&mut self, now: Instant) -> Vec<DeviceInputData> {
         let mut events = Vec::new();
         let buttons = [
             (
@@ -297,28 +282,23 @@ impl MouseState {
             ),
         ];
 
-        let dragging_primary = self.dragging_from_primary;
-        let dragging_secondary = self.dragging_from_secondary;
-        let dragging_middle = self.dragging_from_middle;
-
+        let mut long_presses = Vec::new();
         for (logical_button, button_state, dragging_from) in buttons {
             if dragging_from.is_none() {
                 if let Some(click_state) =
                     button_state.detect_long_press(now, self.long_press_duration)
                 {
-                    let event = Self::new_mouse_event(
-                        dragging_primary,
-                        dragging_secondary,
-                        dragging_middle,
-                        Some(MouseInput::Click {
-                            click_state,
-                            button: logical_button,
-                        }),
-                    );
-                    events.push(event);
+                    long_presses.push((logical_button, click_state));
                 }
             }
         }
+
+        for (button, click_state) in long_presses {
+            events.push(self.mouse_event(Some(MouseInput::Click {
+                click_state,
+                button,
+            })));
+        }
         events
     }
 }
@@ -370,17 +350,13 @@ impl MouseState {
         }
     }
 
-    /// A helper function to create a `ConcreteEvent::MouseEvent`.
-    fn new_mouse_event(
-        dragging_from_primary: Option<[f32; 2]>,
-        dragging_from_secondary: Option<[f32; 2]>,
-        dragging_from_middle: Option<[f32; 2]>,
-        event: Option<MouseInput>,
-    ) -> DeviceInputData {
+    /// A mouse event at the current position with the current drag state.
+    fn mouse_event(&self, event: Option<MouseInput>) -> DeviceInputData {
         DeviceInputData::MouseInput {
-            dragging_from_primary,
-            dragging_from_secondary,
-            dragging_from_middle,
+            position: PointerPosition::new(self.position),
+            dragging_from_primary: self.dragging_from_primary,
+            dragging_from_secondary: self.dragging_from_secondary,
+            dragging_from_middle: self.dragging_from_middle,
             event,
         }
     }
@@ -424,6 +400,7 @@ mod tests {
                 .mouse_input(b, WinitElementState::Pressed)
                 .unwrap();
             let expected = DeviceInputData::MouseInput {
+                position: PointerPosition::new([0.0, 0.0]),
                 dragging_from_primary: None,
                 dragging_from_secondary: None,
                 dragging_from_middle: None,
@@ -440,6 +417,7 @@ mod tests {
                 .mouse_input(b, WinitElementState::Released)
                 .unwrap();
             let expected = DeviceInputData::MouseInput {
+                position: PointerPosition::new([0.0, 0.0]),
                 dragging_from_primary: None,
                 dragging_from_secondary: None,
                 dragging_from_middle: None,
@@ -462,6 +440,7 @@ mod tests {
                 .mouse_input(b, WinitElementState::Pressed)
                 .unwrap();
             let expected = DeviceInputData::MouseInput {
+                position: PointerPosition::new([0.0, 0.0]),
                 dragging_from_primary: None,
                 dragging_from_secondary: None,
                 dragging_from_middle: None,
@@ -482,6 +461,7 @@ mod tests {
 
             let events = mouse_state.long_pressing_detection();
             let expected = DeviceInputData::MouseInput {
+                position: PointerPosition::new([0.0, 0.0]),
                 dragging_from_primary: None,
                 dragging_from_secondary: None,
                 dragging_from_middle: None,
@@ -511,6 +491,7 @@ mod tests {
 
         let event = mouse_state.cursor_moved(PhysicalPosition::new(0.0, 0.0));
         let expected_event = DeviceInputData::MouseInput {
+            position: PointerPosition::new([0.0, 0.0]),
             dragging_from_primary: None,
             dragging_from_secondary: None,
             dragging_from_middle: None,
@@ -523,6 +504,7 @@ mod tests {
 
         let event = mouse_state.cursor_moved(PhysicalPosition::new(1.0, 1.0));
         let expected_event = DeviceInputData::MouseInput {
+            position: PointerPosition::new([1.0, 1.0]),
             dragging_from_primary: if logical_b == MouseLogicalButton::Primary {
                 Some([0.0, 0.0])
             } else {
@@ -552,6 +534,7 @@ mod tests {
             .mouse_input(b, WinitElementState::Released)
             .unwrap();
         let expected_event = DeviceInputData::MouseInput {
+            position: PointerPosition::new([1.0, 1.0]),
             dragging_from_primary: None,
             dragging_from_secondary: None,
             dragging_from_middle: None,
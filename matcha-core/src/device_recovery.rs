@@ -0,0 +1,215 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+
+use gpu_utils::{device_loss_recoverable::DeviceLossRecoverable, gpu::CallbackId};
+use log::{debug, trace, warn};
+use parking_lot::Mutex;
+
+/// State of the GPU device as seen by the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    /// The device is usable and rendering runs normally.
+    Ready,
+    /// The device was lost; rendering is suspended until a new device is available.
+    Lost,
+    /// A replacement device was acquired and resources are being rebuilt.
+    Recovering,
+    /// Requesting a replacement device failed. Rendering stays suspended.
+    Failed,
+}
+
+/// Central bookkeeping for device-loss recovery.
+///
+/// GPU resources that outlive a single frame (atlases, pipelines, buffer atlases, ...) register
+/// themselves here. When the `Gpu` reports a replacement device, [`recover`](Self::recover)
+/// calls `DeviceLossRecoverable::recover` on every registered resource in registration order.
+/// The rendering loop waits on [`wait_until_ready`](Self::wait_until_ready) while no valid
+/// device exists.
+pub struct DeviceRecoveryManager {
+    state: tokio::sync::watch::Sender<DeviceState>,
+    // number of successful recoveries
+    generation: AtomicU64,
+    resources: Mutex<Vec<(CallbackId, Arc<dyn DeviceLossRecoverable + Send + Sync>)>>,
+}
+
+impl Default for DeviceRecoveryManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceRecoveryManager {
+    pub fn new() -> Self {
+        trace!("DeviceRecoveryManager::new: creating recovery manager");
+        Self {
+            state: tokio::sync::watch::Sender::new(DeviceState::Ready),
+            generation: AtomicU64::new(0),
+            resources: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Registers a resource to be recovered after device loss.
+    pub fn register(&self, resource: Arc<dyn DeviceLossRecoverable + Send + Sync>) -> CallbackId {
+        let id = CallbackId::new();
+        self.resources.lock().push((id, resource));
+        trace!("DeviceRecoveryManager::register: registered resource id={id:?}");
+        id
+    }
+
+    /// Removes a previously registered resource. Returns `false` if the id is unknown.
+    pub fn unregister(&self, id: CallbackId) -> bool {
+        let mut resources = self.resources.lock();
+        let len = resources.len();
+        resources.retain(|(resource_id, _)| *resource_id != id);
+        len != resources.len()
+    }
+
+    pub fn state(&self) -> DeviceState {
+        *self.state.borrow()
+    }
+
+    /// Returns `true` when rendering may use the current device.
+    pub fn is_ready(&self) -> bool {
+        self.state() == DeviceState::Ready
+    }
+
+    /// Waits until rendering may use the device again, i.e. until the next successful
+    /// [`recover`](Self::recover). Returns immediately while the device is ready.
+    pub async fn wait_until_ready(&self) {
+        let mut receiver = self.state.subscribe();
+        // the sender lives as long as `self`, so this cannot fail
+        let _ = receiver
+            .wait_for(|state| *state == DeviceState::Ready)
+            .await;
+    }
+
+    /// How many times the device has been recovered.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Records that the device was lost. Rendering is suspended until [`recover`](Self::recover).
+    pub fn mark_lost(&self) {
+        warn!("DeviceRecoveryManager::mark_lost: device lost, suspending rendering");
+        self.state.send_replace(DeviceState::Lost);
+    }
+
+    /// Records that no replacement device could be acquired.
+    pub fn mark_failed(&self) {
+        warn!("DeviceRecoveryManager::mark_failed: device recovery failed");
+        self.state.send_replace(DeviceState::Failed);
+    }
+
+    /// Rebuilds every registered resource against the new device and resumes rendering.
+    pub fn recover(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.recover_with(device, queue, || {});
+    }
+
+    /// Like [`recover`](Self::recover), but runs `after_resources` once all registered resources
+    /// are rebuilt and before rendering resumes (e.g. to reconfigure surfaces).
+    pub fn recover_with(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        after_resources: impl FnOnce(),
+    ) {
+        self.state.send_replace(DeviceState::Recovering);
+
+        // clone the list so resources may register / unregister from within `recover`
+        let resources: Vec<_> = self
+            .resources
+            .lock()
+            .iter()
+            .map(|(_, resource)| resource.clone())
+            .collect();
+        debug!(
+            "DeviceRecoveryManager::recover: recovering {} resources",
+            resources.len()
+        );
+        for resource in resources {
+            resource.recover(device, queue);
+        }
+
+        after_resources();
+
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        self.state.send_replace(DeviceState::Ready);
+        debug!("DeviceRecoveryManager::recover: rendering resumed, generation={generation}");
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[derive(Default)]
+    struct Counter {
+        recovered: AtomicUsize,
+    }
+
+    impl DeviceLossRecoverable for Counter {
+        fn recover(&self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
+            self.recovered.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn recover_calls_registered_resources_and_resumes() {
+        let (_instance, _adapter, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let manager = DeviceRecoveryManager::new();
+        let a = Arc::new(Counter::default());
+        let b = Arc::new(Counter::default());
+        manager.register(a.clone());
+        let b_id = manager.register(b.clone());
+
+        assert!(manager.is_ready());
+        manager.mark_lost();
+        assert_eq!(manager.state(), DeviceState::Lost);
+        assert!(!manager.is_ready());
+
+        manager.recover(&device, &queue);
+        assert!(manager.is_ready());
+        assert_eq!(manager.generation(), 1);
+        assert_eq!(a.recovered.load(Ordering::SeqCst), 1);
+        assert_eq!(b.recovered.load(Ordering::SeqCst), 1);
+
+        assert!(manager.unregister(b_id));
+        assert!(!manager.unregister(b_id));
+        manager.recover(&device, &queue);
+        assert_eq!(a.recovered.load(Ordering::SeqCst), 2);
+        assert_eq!(b.recovered.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn wait_until_ready_returns_after_recovery() {
+        let (_instance, _adapter, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let manager = Arc::new(DeviceRecoveryManager::new());
+        // returns immediately while ready
+        manager.wait_until_ready().await;
+
+        manager.mark_lost();
+        let waiter = {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.wait_until_ready().await })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        manager.recover(&device, &queue);
+        waiter.await.unwrap();
+    }
+
+    #[test]
+    fn failed_recovery_keeps_rendering_suspended() {
+        let manager = DeviceRecoveryManager::new();
+        manager.mark_lost();
+        manager.mark_failed();
+        assert_eq!(manager.state(), DeviceState::Failed);
+        assert!(!manager.is_ready());
+        assert_eq!(manager.generation(), 0);
+    }
+}
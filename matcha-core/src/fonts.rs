@@ -0,0 +1,101 @@
+//! Fonts registered by the app in addition to the system fonts.
+//!
+//! Apps bundle fonts with [`App::font_bytes`](crate::app::App::font_bytes) and
+//! [`App::font_dir`](crate::app::App::font_dir), so they are available from the first frame,
+//! or add them at runtime with
+//! [`ApplicationContext::register_font_bytes`](crate::context::ApplicationContext::register_font_bytes).
+//!
+//! The [`FontRegistry`] only collects the sources; text renderers load them into their own
+//! font databases. The registry is append-only, so a renderer remembers the
+//! [`generation`](FontRegistry::generation) it loaded and, before shaping, loads the
+//! [`sources_since`](FontRegistry::sources_since) then. A font added at runtime can change
+//! which face a family or a fallback resolves to, so renderers also drop the text they shaped
+//! before, and every window lays out and redraws the whole tree.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::debug;
+use parking_lot::RwLock;
+
+/// Where a registered font comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontSource {
+    /// A font file or collection in memory.
+    Bytes(Arc<Vec<u8>>),
+    /// Every font file in a directory and its subdirectories.
+    Dir(PathBuf),
+}
+
+/// The fonts registered by the app, in registration order.
+#[derive(Debug, Default)]
+pub struct FontRegistry {
+    sources: RwLock<Vec<FontSource>>,
+}
+
+impl FontRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a font file or collection in memory.
+    pub fn register_bytes(&self, bytes: impl Into<Vec<u8>>) {
+        self.register(FontSource::Bytes(Arc::new(bytes.into())));
+    }
+
+    /// Registers every font file in `path` and its subdirectories.
+    pub fn register_dir(&self, path: impl Into<PathBuf>) {
+        self.register(FontSource::Dir(path.into()));
+    }
+
+    fn register(&self, source: FontSource) {
+        let mut sources = self.sources.write();
+        debug!(
+            "FontRegistry::register: adding font source #{}",
+            sources.len()
+        );
+        sources.push(source);
+    }
+
+    /// Changes whenever a font is registered. Starts at 0 for an empty registry.
+    pub fn generation(&self) -> usize {
+        self.sources.read().len()
+    }
+
+    /// The sources registered after the registry was at `generation`.
+    pub fn sources_since(&self, generation: usize) -> Vec<FontSource> {
+        let sources = self.sources.read();
+        sources.get(generation..).unwrap_or_default().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_are_loaded_once_per_generation() {
+        let registry = FontRegistry::new();
+        assert_eq!(registry.generation(), 0);
+        assert!(registry.sources_since(0).is_empty());
+
+        registry.register_bytes(vec![1, 2, 3]);
+        registry.register_dir("fonts");
+        assert_eq!(registry.generation(), 2);
+        assert_eq!(
+            registry.sources_since(0),
+            vec![
+                FontSource::Bytes(Arc::new(vec![1, 2, 3])),
+                FontSource::Dir(PathBuf::from("fonts")),
+            ]
+        );
+
+        // a renderer that already loaded the first source only gets the second
+        assert_eq!(
+            registry.sources_since(1),
+            vec![FontSource::Dir(PathBuf::from("fonts"))]
+        );
+        assert!(registry.sources_since(2).is_empty());
+        assert!(registry.sources_since(5).is_empty());
+    }
+}
@@ -0,0 +1,204 @@
+//! Frame timing for widgets and animations.
+//!
+//! The [`FrameClock`] is ticked once before each rendered frame. Widgets read the current
+//! [`FrameTime`] through [`WidgetContext::frame`](crate::context::WidgetContext::frame), so
+//! every widget of a frame sees the same timestamp and delta no matter how long the frame
+//! takes to render.
+//!
+//! The animation time starts at zero when the application starts and stops while it is
+//! suspended, see [`lifecycle`](crate::lifecycle), and while it is paused in the background,
+//! see [`power_saving`](crate::power_saving). Deltas are measured in animation time, so the
+//! first frame after a resume does not jump by the time spent in the background.
+
+use std::time::{Duration, Instant};
+
+use log::{trace, warn};
+use parking_lot::RwLock;
+
+/// Timing of one frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTime {
+    /// Number of frames rendered before this one.
+    pub index: u64,
+    /// Wall-clock time at which the frame started.
+    pub timestamp: Instant,
+    /// Animation time passed since the previous frame; zero for the first frame.
+    pub delta: Duration,
+    /// Time since the application started, excluding time spent suspended. Never decreases.
+    pub animation_time: Duration,
+}
+
+/// The clock frames and animations are timed by. Shared by all windows of an application.
+pub struct FrameClock {
+    // animation time is measured from here; moved forward by the time spent suspended
+    origin: RwLock<Instant>,
+    // the current frame, and whether the clock has been ticked yet
+    frame: RwLock<(FrameTime, bool)>,
+    // animation time stands still from here until the clock is unpaused
+    paused_at: RwLock<Option<Instant>>,
+}
+
+impl FrameClock {
+    pub(crate) fn new(now: Instant) -> Self {
+        Self {
+            origin: RwLock::new(now),
+            frame: RwLock::new((
+                FrameTime {
+                    index: 0,
+                    timestamp: now,
+                    delta: Duration::ZERO,
+                    animation_time: Duration::ZERO,
+                },
+                false,
+            )),
+            paused_at: RwLock::new(None),
+        }
+    }
+
+    /// The animation time right now, which may be later than the current frame's.
+    pub fn animation_time(&self) -> Duration {
+        self.animation_time_at(Instant::now())
+    }
+
+    fn animation_time_at(&self, now: Instant) -> Duration {
+        let now = self.paused_at.read().unwrap_or(now);
+        now.saturating_duration_since(*self.origin.read())
+    }
+
+    /// The frame being rendered, or the last one between frames.
+    pub fn frame(&self) -> FrameTime {
+        self.frame.read().0
+    }
+
+    /// Starts a new frame at `now`.
+    pub(crate) fn tick(&self, now: Instant) -> FrameTime {
+        let animation_time = self.animation_time_at(now);
+        let mut guard = self.frame.write();
+        let (frame, started) = &mut *guard;
+
+        *frame = if *started {
+            // a timestamp taken before a resume falls in the skipped period; never go back
+            let animation_time = animation_time.max(frame.animation_time);
+            FrameTime {
+                index: frame.index + 1,
+                timestamp: now,
+                delta: animation_time - frame.animation_time,
+                animation_time,
+            }
+        } else {
+            *started = true;
+            FrameTime {
+                index: 0,
+                timestamp: now,
+                delta: Duration::ZERO,
+                animation_time,
+            }
+        };
+        trace!("FrameClock::tick: {:?}", *frame);
+        *frame
+    }
+
+    /// Excludes `paused` from the animation time, after the app was suspended that long.
+    pub(crate) fn skip(&self, paused: Duration) {
+        if self.paused_at.read().is_some() {
+            // the time is excluded when the clock is unpaused
+            return;
+        }
+        let mut origin = self.origin.write();
+        *origin += paused;
+    }
+
+    /// Stops the animation time at `now`; frames ticked while paused have a zero delta.
+    /// Returns `false` if already paused.
+    pub(crate) fn pause(&self, now: Instant) -> bool {
+        let mut paused_at = self.paused_at.write();
+        if paused_at.is_some() {
+            return false;
+        }
+        *paused_at = Some(now);
+        true
+    }
+
+    /// Lets the animation time continue from where [`pause`](Self::pause) stopped it.
+    pub(crate) fn unpause(&self, now: Instant) {
+        let paused_at = self.paused_at.write().take();
+        if let Some(paused_at) = paused_at {
+            self.skip(now.saturating_duration_since(paused_at));
+        }
+    }
+
+    /// Moves the animation time forward by `by` without waiting.
+    pub(crate) fn advance(&self, by: Duration) {
+        let mut origin = self.origin.write();
+        match origin.checked_sub(by) {
+            Some(earlier) => *origin = earlier,
+            None => warn!("FrameClock::advance: cannot advance the clock by {by:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_count_frames_and_measure_deltas() {
+        let start = Instant::now();
+        let clock = FrameClock::new(start);
+
+        let first = clock.tick(start + Duration::from_millis(5));
+        assert_eq!(first.index, 0);
+        assert_eq!(first.delta, Duration::ZERO);
+        assert_eq!(first.animation_time, Duration::from_millis(5));
+
+        let second = clock.tick(start + Duration::from_millis(21));
+        assert_eq!(second.index, 1);
+        assert_eq!(second.delta, Duration::from_millis(16));
+        assert_eq!(clock.frame(), second);
+    }
+
+    #[test]
+    fn suspended_time_is_not_counted() {
+        let start = Instant::now();
+        let clock = FrameClock::new(start);
+        clock.tick(start + Duration::from_millis(10));
+
+        // suspended for a minute
+        clock.skip(Duration::from_secs(60));
+        let resumed = clock.tick(start + Duration::from_secs(60) + Duration::from_millis(26));
+        assert_eq!(resumed.animation_time, Duration::from_millis(26));
+        assert_eq!(resumed.delta, Duration::from_millis(16));
+
+        // a frame timestamped inside the skipped period does not go back in time
+        let late = clock.tick(start + Duration::from_millis(20));
+        assert_eq!(late.animation_time, Duration::from_millis(26));
+        assert_eq!(late.delta, Duration::ZERO);
+    }
+
+    #[test]
+    fn paused_clock_ticks_without_advancing() {
+        let start = Instant::now();
+        let clock = FrameClock::new(start);
+        clock.tick(start + Duration::from_millis(10));
+
+        assert!(clock.pause(start + Duration::from_millis(20)));
+        assert!(!clock.pause(start + Duration::from_millis(30)));
+        let paused = clock.tick(start + Duration::from_secs(5));
+        assert_eq!(paused.animation_time, Duration::from_millis(20));
+
+        clock.unpause(start + Duration::from_secs(10));
+        let resumed = clock.tick(start + Duration::from_secs(10) + Duration::from_millis(16));
+        assert_eq!(resumed.animation_time, Duration::from_millis(36));
+        assert_eq!(resumed.delta, Duration::from_millis(16));
+    }
+
+    #[test]
+    fn advance_moves_animation_time_forward() {
+        let start = Instant::now();
+        let clock = FrameClock::new(start);
+        clock.advance(Duration::from_secs(2));
+        assert!(clock.animation_time() >= Duration::from_secs(2));
+        assert_eq!(clock.tick(start).animation_time, Duration::from_secs(2));
+    }
+}
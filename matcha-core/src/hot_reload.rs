@@ -0,0 +1,681 @@
+//! Hot reloading of view functions from a dynamic library.
+//!
+//! The view function of a [`Component`](crate::ui::Component) can live in a separate crate
+//! built as a `dylib`. [`HotReloadView`] loads it, watches the library file and swaps in the new
+//! build whenever it changes on disk. The model stays in the host process, so its state
+//! survives every reload; the component is then re-viewed and its widget tree rebuilt.
+//!
+//! The library exports the view function unmangled, with the Rust ABI:
+//!
+//! ```ignore
+//! #[unsafe(no_mangle)]
+//! pub fn matcha_view(model: &MyModel) -> Box<dyn Dom<MyEvent>> {
+//!     Box::new(Text::new(&model.title))
+//! }
+//! ```
+//!
+//! Host and library must be built by the same compiler against the same build of matcha and
+//! share the model and event types. Loading a library is inherently unsafe: a library that
+//! does not follow these rules causes undefined behavior.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, trace, warn};
+use parking_lot::{Mutex, RwLock};
+use renderer::RenderNode;
+use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateNotifier};
+
+use crate::{
+    capture::{CaptureTarget, CapturedSubtree},
+    context::WidgetContext,
+    device_input::DeviceInput,
+    metrics::Constraints,
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, HitTestEntry, ModelAccessor, UpdateWidgetError,
+        WidgetSnapshot,
+    },
+};
+
+/// Default symbol name looked up by [`HotReloadView::load`].
+pub const DEFAULT_VIEW_SYMBOL: &str = "matcha_view";
+
+type ViewFnPtr<Model, Event> = fn(&Model) -> Box<dyn Dom<Event>>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HotReloadError {
+    #[error("failed to access the view library: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to load the view library: {0}")]
+    Load(#[from] libloading::Error),
+}
+
+/// A view function loaded from a dynamic library that is reloaded when the file changes.
+///
+/// Clones share the loaded library.
+pub struct HotReloadView<Model: 'static, Event: 'static> {
+    inner: Arc<HotReloadInner<Model, Event>>,
+}
+
+impl<Model: 'static, Event: 'static> Clone for HotReloadView<Model, Event> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+struct HotReloadInner<Model: 'static, Event: 'static> {
+    source: Box<dyn ViewSource<Model, Event>>,
+    current: RwLock<LoadedView<Model, Event>>,
+    // Widgets built by an older library still run its code, so libraries are never unloaded.
+    retired: Mutex<Vec<libloading::Library>>,
+}
+
+struct LoadedView<Model: 'static, Event: 'static> {
+    view: ViewFnPtr<Model, Event>,
+    // incremented on every successful reload
+    generation: u64,
+    modified: SystemTime,
+    // `None` for builds that are not loaded from a library
+    library: Option<libloading::Library>,
+}
+
+/// Where the builds of a view function come from.
+trait ViewSource<Model: 'static, Event: 'static>: std::fmt::Debug + Send + Sync {
+    /// When the latest build was written.
+    fn modified(&self) -> Result<SystemTime, HotReloadError>;
+
+    /// Loads the latest build.
+    fn load(&self, generation: u64) -> Result<LoadedView<Model, Event>, HotReloadError>;
+}
+
+/// A view function exported by a library on disk.
+#[derive(Debug)]
+struct LibrarySource {
+    path: PathBuf,
+    symbol: String,
+}
+
+impl<Model: 'static, Event: 'static> ViewSource<Model, Event> for LibrarySource {
+    fn modified(&self) -> Result<SystemTime, HotReloadError> {
+        Ok(std::fs::metadata(&self.path)?.modified()?)
+    }
+
+    fn load(&self, generation: u64) -> Result<LoadedView<Model, Event>, HotReloadError> {
+        // SAFETY: the caller of `HotReloadView::load` vouched for every build of this library.
+        unsafe { load_view(&self.path, &self.symbol, generation) }
+    }
+}
+
+/// Holds back a reload until the library stopped changing for one poll, so that a build that
+/// is still being written is not loaded.
+#[derive(Debug, Default)]
+struct ReloadDebounce {
+    // the modification time seen on the previous poll
+    pending: Option<SystemTime>,
+}
+
+impl ReloadDebounce {
+    /// Whether to load the build written at `modified` while the one written at `loaded` is
+    /// active.
+    fn should_reload(&mut self, loaded: SystemTime, modified: SystemTime) -> bool {
+        if modified <= loaded {
+            self.pending = None;
+            return false;
+        }
+        if self.pending == Some(modified) {
+            self.pending = None;
+            true
+        } else {
+            self.pending = Some(modified);
+            false
+        }
+    }
+}
+
+impl<Model: Send + Sync + 'static, Event: 'static> HotReloadView<Model, Event> {
+    /// Loads the view function named [`DEFAULT_VIEW_SYMBOL`] from the library at `path`.
+    ///
+    /// # Safety
+    ///
+    /// The library must export a function with that name and the signature
+    /// `fn(&Model) -> Box<dyn Dom<Event>>`, built as described in the [module docs](self).
+    pub unsafe fn load(path: impl AsRef<Path>) -> Result<Self, HotReloadError> {
+        unsafe { Self::load_symbol(path, DEFAULT_VIEW_SYMBOL) }
+    }
+
+    /// Like [`load`](Self::load), with a custom symbol name.
+    ///
+    /// # Safety
+    ///
+    /// See [`load`](Self::load).
+    pub unsafe fn load_symbol(
+        path: impl AsRef<Path>,
+        symbol: &str,
+    ) -> Result<Self, HotReloadError> {
+        Self::from_source(LibrarySource {
+            path: path.as_ref().to_path_buf(),
+            symbol: symbol.to_string(),
+        })
+    }
+
+    fn from_source(
+        source: impl ViewSource<Model, Event> + 'static,
+    ) -> Result<Self, HotReloadError> {
+        let current = source.load(0)?;
+        debug!("HotReloadView::from_source: loaded {source:?}");
+
+        Ok(Self {
+            inner: Arc::new(HotReloadInner {
+                source: Box::new(source),
+                current: RwLock::new(current),
+                retired: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+
+    /// How many times the library has been reloaded.
+    pub fn generation(&self) -> u64 {
+        self.inner.current.read().generation
+    }
+
+    /// Reloads the library if the file changed since it was last loaded.
+    ///
+    /// Returns `true` when a new build was loaded. On error the previous build stays active.
+    pub fn reload_if_changed(&self) -> Result<bool, HotReloadError> {
+        let modified = self.inner.source.modified()?;
+        if modified <= self.inner.current.read().modified {
+            return Ok(false);
+        }
+        self.reload().map(|()| true)
+    }
+
+    /// Like [`reload_if_changed`](Self::reload_if_changed), but only once `debounce` saw the
+    /// same build on two polls in a row.
+    fn reload_debounced(&self, debounce: &mut ReloadDebounce) -> Result<bool, HotReloadError> {
+        let modified = self.inner.source.modified()?;
+        if !debounce.should_reload(self.inner.current.read().modified, modified) {
+            return Ok(false);
+        }
+        self.reload().map(|()| true)
+    }
+
+    fn reload(&self) -> Result<(), HotReloadError> {
+        let generation = self.generation() + 1;
+        let loaded = self.inner.source.load(generation)?;
+        let previous = std::mem::replace(&mut *self.inner.current.write(), loaded);
+        self.inner.retired.lock().extend(previous.library);
+
+        debug!(
+            "HotReloadView::reload: reloaded {:?}, generation={generation}",
+            self.inner.source
+        );
+        Ok(())
+    }
+
+    /// Builds the Dom for `model` with the current build of the library.
+    pub fn view(&self, model: &Model) -> Box<dyn Dom<Event>> {
+        // read both together so the Dom is tagged with the build that made it
+        let (view, generation) = {
+            let current = self.inner.current.read();
+            (current.view, current.generation)
+        };
+        Box::new(HotReloadDom {
+            generation,
+            dom: view(model),
+        })
+    }
+
+    /// Returns a view function to pass to [`Component::new`](crate::ui::Component::new).
+    pub fn view_fn(&self) -> impl Fn(&Model) -> Box<dyn Dom<Event>> + Send + Sync + 'static {
+        let this = self.clone();
+        move |model| this.view(model)
+    }
+
+    /// Polls the library file every `interval` on a background thread and re-views the
+    /// component owning `model` after each reload.
+    ///
+    /// Typically called from the component's setup function. The thread ends when every
+    /// handle to this view has been dropped.
+    pub fn watch(&self, model: ModelAccessor<Model>, interval: Duration) {
+        let weak = Arc::downgrade(&self.inner);
+        let spawned = std::thread::Builder::new()
+            .name("matcha-hot-reload".to_string())
+            .spawn(move || {
+                let mut debounce = ReloadDebounce::default();
+                loop {
+                    std::thread::sleep(interval);
+                    let Some(inner) = weak.upgrade() else {
+                        trace!("HotReloadView::watch: view dropped, stopping");
+                        return;
+                    };
+                    match (HotReloadView { inner }).reload_debounced(&mut debounce) {
+                        // an empty update marks the model dirty, which re-runs the view
+                        Ok(true) => futures::executor::block_on(model.update(|_| {})),
+                        Ok(false) => {}
+                        // e.g. the build was replaced while loading; retry on the next tick
+                        Err(e) => warn!("HotReloadView::watch: reload failed: {e}"),
+                    }
+                }
+            });
+
+        if let Err(e) = spawned {
+            warn!("HotReloadView::watch: failed to spawn watcher thread: {e}");
+        }
+    }
+}
+
+/// Loads `symbol` from a private copy of the library at `path`.
+///
+/// Loaders cache libraries by path and some platforms lock loaded files, so every build is
+/// copied into the temp dir before loading.
+unsafe fn load_view<Model: 'static, Event: 'static>(
+    path: &Path,
+    symbol: &str,
+    generation: u64,
+) -> Result<LoadedView<Model, Event>, HotReloadError> {
+    let modified = std::fs::metadata(path)?.modified()?;
+
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "view".to_string());
+    let copy = std::env::temp_dir().join(format!(
+        "matcha-hot-reload-{}-{generation}-{file_name}",
+        std::process::id()
+    ));
+    std::fs::copy(path, &copy)?;
+
+    let library = unsafe { libloading::Library::new(&copy)? };
+    let view = unsafe { *library.get::<ViewFnPtr<Model, Event>>(symbol.as_bytes())? };
+
+    Ok(LoadedView {
+        view,
+        generation,
+        modified,
+        library: Some(library),
+    })
+}
+
+// MARK: Dom / Widget
+
+/// Dom produced by [`HotReloadView::view`]. Remembers which build of the library created it.
+struct HotReloadDom<Event: 'static> {
+    generation: u64,
+    dom: Box<dyn Dom<Event>>,
+}
+
+#[async_trait::async_trait]
+impl<Event: 'static> Dom<Event> for HotReloadDom<Event> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<Event>> {
+        Box::new(HotReloadWidget {
+            generation: self.generation,
+            widget_tree: self.dom.build_widget_tree(),
+            dirty_flags: None,
+        })
+    }
+}
+
+/// Diffs updates from the same build of the library like any other widget, but rebuilds the
+/// whole subtree after a reload: widget types of two builds may share a `TypeId` while having
+/// different layouts, so diffing across builds is unsound.
+struct HotReloadWidget<Event: 'static> {
+    generation: u64,
+    widget_tree: Box<dyn AnyWidgetFrame<Event>>,
+    dirty_flags: Option<(BackPropDirty, BackPropDirty)>,
+}
+
+impl<Event: 'static> AnyWidget<Event> for HotReloadWidget<Event> {
+    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<Event> {
+        self.widget_tree.device_input(event, ctx)
+    }
+
+    fn is_inside(&self, position: [f32; 2], ctx: &WidgetContext) -> bool {
+        self.widget_tree.is_inside(position, ctx)
+    }
+
+    fn measure(&self, constraints: &Constraints, ctx: &WidgetContext) -> [f32; 2] {
+        self.widget_tree.measure(constraints, ctx)
+    }
+
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        self.widget_tree.baseline(constraints, ctx)
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.min_intrinsic_width(height, ctx)
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.max_intrinsic_width(height, ctx)
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.min_intrinsic_height(width, ctx)
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.max_intrinsic_height(width, ctx)
+    }
+
+    fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
+        self.widget_tree.render(background, ctx)
+    }
+}
+
+#[async_trait::async_trait]
+impl<Event: 'static> AnyWidgetFrame<Event> for HotReloadWidget<Event> {
+    fn label(&self) -> Option<&str> {
+        self.widget_tree.label()
+    }
+
+    fn need_redraw(&self) -> bool {
+        self.widget_tree.need_redraw()
+    }
+
+    async fn update_widget_tree(&mut self, dom: &dyn Dom<Event>) -> Result<(), UpdateWidgetError> {
+        let dom = (dom as &dyn std::any::Any)
+            .downcast_ref::<HotReloadDom<Event>>()
+            .ok_or(UpdateWidgetError::TypeMismatch)?;
+
+        if dom.generation == self.generation
+            && self.widget_tree.update_widget_tree(&*dom.dom).await.is_ok()
+        {
+            return Ok(());
+        }
+
+        debug!(
+            "HotReloadWidget::update_widget_tree: rebuilding for generation {}",
+            dom.generation
+        );
+        self.generation = dom.generation;
+        self.widget_tree = dom.dom.build_widget_tree();
+        if let Some((rearrange, redraw)) = &self.dirty_flags {
+            self.widget_tree
+                .update_dirty_flags(rearrange.clone(), redraw.clone());
+            rearrange.mark_dirty();
+            redraw.mark_dirty();
+        }
+        Ok(())
+    }
+
+    async fn set_model_update_notifier(&self, notifier: &UpdateNotifier) {
+        self.widget_tree.set_model_update_notifier(notifier).await;
+    }
+
+    fn arrange(&self, bounds: [f32; 2], ctx: &WidgetContext) {
+        self.widget_tree.arrange(bounds, ctx)
+    }
+
+    fn update_dirty_flags(&mut self, rearrange_flags: BackPropDirty, redraw_flags: BackPropDirty) {
+        self.dirty_flags = Some((rearrange_flags.clone(), redraw_flags.clone()));
+        self.widget_tree
+            .update_dirty_flags(rearrange_flags, redraw_flags);
+    }
+
+    fn invalidate_render_cache(&mut self) {
+        self.widget_tree.invalidate_render_cache();
+    }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.widget_tree.update_gpu_device(device, queue);
+    }
+
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.widget_tree.update_lifecycle(visible_rect, ctx);
+    }
+
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.widget_tree.prepare(visible_rect, ctx);
+    }
+
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool {
+        self.widget_tree
+            .hit_test(id, position, to_window, ctx, path)
+    }
+
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot> {
+        self.widget_tree.snapshot(id, to_window)
+    }
+
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree> {
+        self.widget_tree.find_rendered(id, target)
+    }
+
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext) {
+        self.widget_tree.set_focus(path, ctx);
+    }
+
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<Event> {
+        self.widget_tree.take_deferred_events(ctx)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::Arrangement,
+        test_kit::TestContext,
+        ui::{Component, InvalidationHandle, Widget, WidgetFrame, component::AnyComponent},
+    };
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    // stands in for a library on disk; `publish` writes a new build
+    #[derive(Debug, Clone)]
+    struct FakeLibrary {
+        build: Arc<Mutex<(ViewFnPtr<u32, String>, SystemTime)>>,
+    }
+
+    impl FakeLibrary {
+        fn new(view: ViewFnPtr<u32, String>) -> Self {
+            Self {
+                build: Arc::new(Mutex::new((view, at(1)))),
+            }
+        }
+
+        fn publish(&self, view: ViewFnPtr<u32, String>, modified: SystemTime) {
+            *self.build.lock() = (view, modified);
+        }
+    }
+
+    impl ViewSource<u32, String> for FakeLibrary {
+        fn modified(&self) -> Result<SystemTime, HotReloadError> {
+            Ok(self.build.lock().1)
+        }
+
+        fn load(&self, generation: u64) -> Result<LoadedView<u32, String>, HotReloadError> {
+            let (view, modified) = *self.build.lock();
+            Ok(LoadedView {
+                view,
+                generation,
+                modified,
+                library: None,
+            })
+        }
+    }
+
+    // two builds of a view function: a square of the model's size, then a wide rectangle
+    fn square(count: &u32) -> Box<dyn Dom<String>> {
+        Box::new(Leaf([*count as f32, *count as f32]))
+    }
+
+    fn wide(count: &u32) -> Box<dyn Dom<String>> {
+        Box::new(Leaf([*count as f32 * 2.0, *count as f32]))
+    }
+
+    struct Leaf([f32; 2]);
+
+    impl Dom<String> for Leaf {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::<Leaf, _, String, ()>::new(
+                None,
+                vec![],
+                vec![],
+                LeafNode(self.0),
+            ))
+        }
+    }
+
+    struct LeafNode([f32; 2]);
+
+    impl Widget<Leaf, String, ()> for LeafNode {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a Leaf,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, (), u128)> {
+            self.0 = dom.0;
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut (), &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &())],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            self.0
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &())],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &(), &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::new()
+        }
+    }
+
+    #[test]
+    fn debounce_waits_until_the_build_stops_changing() {
+        let mut debounce = ReloadDebounce::default();
+
+        // unchanged
+        assert!(!debounce.should_reload(at(10), at(10)));
+        // written, and still being written on the next poll
+        assert!(!debounce.should_reload(at(10), at(11)));
+        assert!(!debounce.should_reload(at(10), at(12)));
+        // unchanged for a poll
+        assert!(debounce.should_reload(at(10), at(12)));
+    }
+
+    #[test]
+    fn debounce_forgets_a_build_that_was_replaced_by_the_loaded_one() {
+        let mut debounce = ReloadDebounce::default();
+
+        assert!(!debounce.should_reload(at(10), at(11)));
+        // loaded in the meantime, e.g. by `reload_if_changed`
+        assert!(!debounce.should_reload(at(11), at(11)));
+        assert!(!debounce.should_reload(at(11), at(12)));
+        assert!(debounce.should_reload(at(11), at(12)));
+    }
+
+    #[test]
+    fn reloads_only_newer_builds() {
+        let library = FakeLibrary::new(square);
+        let view = HotReloadView::from_source(library.clone()).unwrap();
+
+        assert!(!view.reload_if_changed().unwrap());
+        assert_eq!(view.generation(), 0);
+
+        library.publish(wide, at(2));
+        assert!(view.reload_if_changed().unwrap());
+        assert!(!view.reload_if_changed().unwrap());
+        assert_eq!(view.generation(), 1);
+
+        // an older build, e.g. restored from a backup
+        library.publish(square, at(0));
+        assert!(!view.reload_if_changed().unwrap());
+        assert_eq!(view.generation(), 1);
+    }
+
+    #[test]
+    fn debounced_reload_loads_the_build_on_the_second_poll() {
+        let library = FakeLibrary::new(square);
+        let view = HotReloadView::from_source(library.clone()).unwrap();
+        let mut debounce = ReloadDebounce::default();
+
+        library.publish(wide, at(2));
+        assert!(!view.reload_debounced(&mut debounce).unwrap());
+        assert_eq!(view.generation(), 0);
+        assert!(view.reload_debounced(&mut debounce).unwrap());
+        assert_eq!(view.generation(), 1);
+        assert!(!view.reload_debounced(&mut debounce).unwrap());
+    }
+
+    #[tokio::test]
+    async fn the_model_survives_a_reload() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let constraints = Constraints::new([0.0, 100.0], [0.0, 100.0]);
+
+        let library = FakeLibrary::new(square);
+        let view = HotReloadView::from_source(library.clone()).unwrap();
+        let component = Component::<u32, (), String, String>::new(None, 3, view.view_fn());
+        let model = component.model_accessor();
+
+        let mut widget_tree = component.view(None).await.build_widget_tree();
+        widget_tree.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        assert_eq!(widget_tree.measure(&constraints, ctx), [3.0, 3.0]);
+
+        model.update(|count| *count = 5).await;
+        widget_tree
+            .update_widget_tree(&*component.view(None).await)
+            .await
+            .unwrap();
+        assert_eq!(widget_tree.measure(&constraints, ctx), [5.0, 5.0]);
+
+        library.publish(wide, at(2));
+        assert!(view.reload_if_changed().unwrap());
+        widget_tree
+            .update_widget_tree(&*component.view(None).await)
+            .await
+            .unwrap();
+
+        // the new build renders the state the old one left behind
+        assert_eq!(widget_tree.measure(&constraints, ctx), [10.0, 5.0]);
+        assert_eq!(model.read(|count| *count).await, 5);
+    }
+}
@@ -0,0 +1,590 @@
+//! Recording and replaying input sessions.
+//!
+//! An [`InputRecorder`] writes the input a window receives to a file as it arrives, one JSON
+//! object per line with the time since the recording started. Loaded as an
+//! [`InputRecording`], the session can be replayed
+//!
+//! - against the app with [`App::replay_input`](crate::app::App::replay_input), at the
+//!   original speed or faster, e.g. to reproduce a bug report,
+//! - headlessly with [`UiDriver::replay`](crate::automation::UiDriver::replay) in simulated
+//!   time, e.g. in a regression or performance test.
+//!
+//! ```ignore
+//! App::new(root).record_input("session.jsonl").run()?;
+//!
+//! let recording = InputRecording::load("session.jsonl")?;
+//! App::new(root).replay_input(recording, ReplaySpeed::Scaled(4.0)).run()?;
+//! ```
+//!
+//! The raw input is recorded, before clicks and drags are recognized. Replaying recognizes
+//! them again from the recorded times instead of the times the input is delivered, so double
+//! clicks and long presses come out the same at any speed. A long press is recognized when
+//! the input after it is replayed.
+//!
+//! Every line is flushed when it is written, so the recording of a session that crashed is
+//! complete up to the crash.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use log::{debug, trace, warn};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, Ime, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{Key, KeyLocation, ModifiersState, PhysicalKey},
+    window::Theme,
+};
+
+use crate::device_input::{DeviceInputData, KeyboardState, MouseState};
+
+const FORMAT: &str = "matcha-input-recording";
+const VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum RecordingError {
+    #[error("failed to access the recording: {0}")]
+    Io(#[from] io::Error),
+    #[error("not an input recording of version {VERSION}")]
+    UnsupportedFormat,
+    #[error("invalid input on line {line}: {source}")]
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+/// One input a window received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedInput {
+    /// The pointer moved to `position`, in physical pixels from the top left of the window.
+    CursorMoved {
+        position: [f64; 2],
+    },
+    CursorEntered,
+    CursorLeft,
+    MouseButton {
+        button: MouseButton,
+        state: ElementState,
+    },
+    MouseWheel {
+        delta: MouseScrollDelta,
+    },
+    /// Relative motion of a locked pointer.
+    PointerMotion {
+        delta: [f32; 2],
+    },
+    Key {
+        physical_key: PhysicalKey,
+        logical_key: Key,
+        text: Option<String>,
+        location: KeyLocation,
+        state: ElementState,
+        repeat: bool,
+    },
+    Modifiers {
+        modifiers: ModifiersState,
+    },
+    Ime {
+        ime: Ime,
+    },
+    /// The inner size of the window changed, in physical pixels.
+    Resized {
+        size: [u32; 2],
+    },
+    Focused {
+        focused: bool,
+    },
+    Theme {
+        theme: Theme,
+    },
+    HoveredFile {
+        path: PathBuf,
+    },
+    HoveredFileCancelled,
+    DroppedFile {
+        path: PathBuf,
+    },
+}
+
+impl RecordedInput {
+    /// The input of `event`, `None` for events that are not input, e.g. redraw requests.
+    ///
+    /// Close requests are not recorded, so that replaying a session does not close the app.
+    pub fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        let input = match event {
+            WindowEvent::CursorMoved { position, .. } => Self::CursorMoved {
+                position: [position.x, position.y],
+            },
+            WindowEvent::CursorEntered { .. } => Self::CursorEntered,
+            WindowEvent::CursorLeft { .. } => Self::CursorLeft,
+            WindowEvent::MouseInput { state, button, .. } => Self::MouseButton {
+                button: *button,
+                state: *state,
+            },
+            WindowEvent::MouseWheel { delta, .. } => Self::MouseWheel { delta: *delta },
+            WindowEvent::KeyboardInput { event, .. } => Self::Key {
+                physical_key: event.physical_key,
+                logical_key: event.logical_key.clone(),
+                text: event.text.as_ref().map(|text| text.to_string()),
+                location: event.location,
+                state: event.state,
+                repeat: event.repeat,
+            },
+            WindowEvent::ModifiersChanged(modifiers) => Self::Modifiers {
+                modifiers: modifiers.state(),
+            },
+            WindowEvent::Ime(ime) => Self::Ime { ime: ime.clone() },
+            WindowEvent::Resized(size) => Self::Resized {
+                size: [size.width, size.height],
+            },
+            WindowEvent::Focused(focused) => Self::Focused { focused: *focused },
+            WindowEvent::ThemeChanged(theme) => Self::Theme { theme: *theme },
+            WindowEvent::HoveredFile(path) => Self::HoveredFile { path: path.clone() },
+            WindowEvent::HoveredFileCancelled => Self::HoveredFileCancelled,
+            WindowEvent::DroppedFile(path) => Self::DroppedFile { path: path.clone() },
+            _ => return None,
+        };
+        Some(input)
+    }
+}
+
+/// An input and when it was received.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    // microseconds since the recording started
+    at_us: u64,
+    #[serde(flatten)]
+    input: RecordedInput,
+}
+
+impl RecordedEvent {
+    pub fn new(at: Duration, input: RecordedInput) -> Self {
+        Self {
+            at_us: at.as_micros().try_into().unwrap_or(u64::MAX),
+            input,
+        }
+    }
+
+    /// Time since the recording started.
+    pub fn at(&self) -> Duration {
+        Duration::from_micros(self.at_us)
+    }
+
+    pub fn input(&self) -> &RecordedInput {
+        &self.input
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+}
+
+impl Header {
+    fn current() -> Self {
+        Self {
+            format: FORMAT.to_string(),
+            version: VERSION,
+        }
+    }
+}
+
+/// A recorded input session. See the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputRecording {
+    events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `input` at `at` since the start, or at the time of the last event if that is
+    /// later, e.g. to script a session for a test.
+    pub fn push(&mut self, at: Duration, input: RecordedInput) {
+        let at = at.max(self.duration());
+        self.events.push(RecordedEvent::new(at, input));
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Time of the last event.
+    pub fn duration(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, RecordedEvent::at)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let file = File::open(path.as_ref())?;
+        Self::read(io::BufReader::new(file))
+    }
+
+    /// Reads a recording written by [`InputRecorder`] or [`write`](Self::write).
+    ///
+    /// An incomplete last line, as left by a session that crashed while writing it, is
+    /// skipped.
+    pub fn read(mut reader: impl BufRead) -> Result<Self, RecordingError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let complete = content.ends_with('\n');
+
+        let mut lines = content.lines().enumerate().peekable();
+        let header: Header = match lines.next() {
+            Some((_, line)) => serde_json::from_str(line)
+                .map_err(|source| RecordingError::Parse { line: 1, source })?,
+            None => return Err(RecordingError::UnsupportedFormat),
+        };
+        if header.format != FORMAT || header.version != VERSION {
+            return Err(RecordingError::UnsupportedFormat);
+        }
+
+        let mut events = Vec::new();
+        while let Some((index, line)) = lines.next() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<RecordedEvent>(line) {
+                Ok(event) => events.push(event),
+                Err(source) if !complete && lines.peek().is_none() => {
+                    warn!("InputRecording::read: skipping incomplete last line: {source}");
+                }
+                Err(source) => {
+                    return Err(RecordingError::Parse {
+                        line: index + 1,
+                        source,
+                    });
+                }
+            }
+        }
+        debug!("InputRecording::read: {} events", events.len());
+        Ok(Self { events })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let mut writer = BufWriter::new(File::create(path.as_ref())?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        write_line(&mut writer, &Header::current())?;
+        for event in &self.events {
+            write_line(&mut writer, event)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_line(writer: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")
+}
+
+/// Writes the input of a session to a file as it is received.
+pub struct InputRecorder {
+    start: Instant,
+    writer: parking_lot::Mutex<BufWriter<File>>,
+    // recording stops at the first write error
+    failed: AtomicBool,
+}
+
+impl InputRecorder {
+    /// Creates or truncates the file at `path` and starts the recording now.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_line(&mut writer, &Header::current())?;
+        writer.flush()?;
+        debug!(
+            "InputRecorder::create: recording input to {}",
+            path.display()
+        );
+        Ok(Self {
+            start: Instant::now(),
+            writer: parking_lot::Mutex::new(writer),
+            failed: AtomicBool::new(false),
+        })
+    }
+
+    pub fn record(&self, input: RecordedInput) {
+        if self.failed.load(Ordering::Acquire) {
+            return;
+        }
+        trace!("InputRecorder::record: {input:?}");
+        let event = RecordedEvent::new(self.start.elapsed(), input);
+        let mut writer = self.writer.lock();
+        if let Err(e) = write_line(&mut *writer, &event).and_then(|()| writer.flush()) {
+            warn!("InputRecorder::record: stopped recording after a write error: {e}");
+            self.failed.store(true, Ordering::Release);
+        }
+    }
+
+    /// Records the input of `event`, see [`RecordedInput::from_window_event`].
+    pub fn record_window_event(&self, event: &WindowEvent) {
+        if let Some(input) = RecordedInput::from_window_event(event) {
+            self.record(input);
+        }
+    }
+}
+
+/// How fast [`App::replay_input`](crate::app::App::replay_input) replays a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// With the recorded pauses between inputs.
+    #[default]
+    Original,
+    /// With the recorded pauses divided by the factor, e.g. `Scaled(4.0)` for four times as
+    /// fast.
+    Scaled(f64),
+    /// Each input as soon as the previous one was handled.
+    Unthrottled,
+}
+
+impl ReplaySpeed {
+    /// The time from the start of the replay to an input recorded at `at`. Saturates at
+    /// [`Duration::MAX`] for tiny factors.
+    pub fn delay(self, at: Duration) -> Duration {
+        match self {
+            Self::Original => at,
+            Self::Scaled(factor) if factor > 0.0 => {
+                Duration::try_from_secs_f64(at.as_secs_f64() / factor).unwrap_or(Duration::MAX)
+            }
+            Self::Scaled(_) => at,
+            Self::Unthrottled => Duration::ZERO,
+        }
+    }
+}
+
+/// Turns recorded input back into the input widgets receive, recognizing clicks at the
+/// recorded times.
+pub(crate) struct InputReplayer {
+    mouse_state: MouseState,
+    keyboard_state: KeyboardState,
+    // the recording's start on the clock of the mouse state
+    start: Instant,
+}
+
+impl InputReplayer {
+    pub fn new(mouse_state: MouseState) -> Self {
+        Self {
+            mouse_state,
+            keyboard_state: KeyboardState::new(),
+            start: Instant::now(),
+        }
+    }
+
+    /// The pointer position in the window after the inputs replayed so far.
+    pub fn pointer_position(&self) -> [f32; 2] {
+        self.mouse_state.position()
+    }
+
+    /// The inputs `event` results in, after the long presses that were recognized before it.
+    /// Resizes result in none; they are up to the caller.
+    pub fn replay(&mut self, event: &RecordedEvent) -> Vec<DeviceInputData> {
+        let now = self.start + event.at();
+        let mut inputs = self.mouse_state.long_pressing_detection_at(now);
+        let input = match event.input() {
+            RecordedInput::CursorMoved { position } => Some(
+                self.mouse_state
+                    .cursor_moved(PhysicalPosition::new(position[0], position[1])),
+            ),
+            RecordedInput::CursorEntered => Some(self.mouse_state.cursor_entered()),
+            RecordedInput::CursorLeft => Some(self.mouse_state.cursor_left()),
+            RecordedInput::MouseButton { button, state } => {
+                self.mouse_state.mouse_input_at(*button, *state, now)
+            }
+            RecordedInput::MouseWheel { delta } => Some(self.mouse_state.mouse_wheel(*delta)),
+            RecordedInput::PointerMotion { delta } => {
+                Some(DeviceInputData::PointerMotion { delta: *delta })
+            }
+            RecordedInput::Key {
+                physical_key,
+                logical_key,
+                text,
+                location,
+                state,
+                repeat,
+            } => {
+                let input = self.keyboard_state.synthetic_input(
+                    *physical_key,
+                    logical_key.clone(),
+                    text.as_deref(),
+                    *state,
+                );
+                Some(match input {
+                    DeviceInputData::Keyboard(key_input) => DeviceInputData::Keyboard(
+                        key_input.with_location_and_repeat(*location, *repeat),
+                    ),
+                    input => input,
+                })
+            }
+            RecordedInput::Modifiers { modifiers } => {
+                self.keyboard_state.modifiers_changed(*modifiers);
+                None
+            }
+            RecordedInput::Ime { ime } => Some(DeviceInputData::Ime(ime.clone())),
+            RecordedInput::Resized { .. } => None,
+            RecordedInput::Focused { focused } => Some(DeviceInputData::WindowFocus(*focused)),
+            RecordedInput::Theme { theme } => Some(DeviceInputData::Theme(*theme)),
+            RecordedInput::HoveredFile { path } => Some(DeviceInputData::FileHover {
+                path_buf: path.clone(),
+            }),
+            RecordedInput::HoveredFileCancelled => Some(DeviceInputData::FileHoverCancelled),
+            RecordedInput::DroppedFile { path } => Some(DeviceInputData::FileDrop {
+                path_buf: path.clone(),
+            }),
+        };
+        inputs.extend(input);
+        inputs
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use winit::keyboard::KeyCode;
+
+    use super::*;
+    use crate::device_input::{
+        ElementState as ClickState, MouseInput, mouse_state::MousePrimaryButton,
+    };
+
+    fn replayer() -> InputReplayer {
+        InputReplayer::new(
+            MouseState::new(
+                Duration::from_millis(300),
+                Duration::from_millis(500),
+                MousePrimaryButton::Left,
+                40.0,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn click_state(input: &DeviceInputData) -> Option<ClickState> {
+        match input {
+            DeviceInputData::MouseInput {
+                event: Some(MouseInput::Click { click_state, .. }),
+                ..
+            } => Some(*click_state),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn recordings_survive_a_round_trip() {
+        let mut recording = InputRecording::new();
+        recording.push(
+            Duration::from_millis(5),
+            RecordedInput::CursorMoved {
+                position: [10.0, 20.5],
+            },
+        );
+        recording.push(
+            Duration::from_millis(40),
+            RecordedInput::Key {
+                physical_key: PhysicalKey::Code(KeyCode::KeyA),
+                logical_key: Key::Character("a".into()),
+                text: Some("a".to_string()),
+                location: KeyLocation::Standard,
+                state: ElementState::Pressed,
+                repeat: false,
+            },
+        );
+        // earlier than the last event
+        recording.push(
+            Duration::from_millis(30),
+            RecordedInput::Modifiers {
+                modifiers: ModifiersState::SHIFT | ModifiersState::CONTROL,
+            },
+        );
+        assert_eq!(recording.duration(), Duration::from_millis(40));
+
+        let mut bytes = Vec::new();
+        recording.write(&mut bytes).unwrap();
+        assert_eq!(InputRecording::read(&bytes[..]).unwrap(), recording);
+
+        // a crash while the last line was written
+        bytes.extend_from_slice(b"{\"at_us\":41000,\"type\":\"cursor_mo");
+        assert_eq!(InputRecording::read(&bytes[..]).unwrap(), recording);
+
+        assert!(matches!(
+            InputRecording::read(&b"{\"format\":\"other\",\"version\":1}\n"[..]),
+            Err(RecordingError::UnsupportedFormat)
+        ));
+    }
+
+    #[test]
+    fn clicks_are_recognized_at_the_recorded_times() {
+        let press = |at| {
+            RecordedEvent::new(
+                Duration::from_millis(at),
+                RecordedInput::MouseButton {
+                    button: MouseButton::Left,
+                    state: ElementState::Pressed,
+                },
+            )
+        };
+        let release = |at| {
+            RecordedEvent::new(
+                Duration::from_millis(at),
+                RecordedInput::MouseButton {
+                    button: MouseButton::Left,
+                    state: ElementState::Released,
+                },
+            )
+        };
+
+        let mut double_click = replayer();
+        let clicks: Vec<_> = [press(0), release(50), press(100), release(150)]
+            .iter()
+            .flat_map(|event| double_click.replay(event))
+            .filter_map(|input| click_state(&input))
+            .collect();
+        assert_eq!(
+            clicks,
+            [
+                ClickState::Pressed(1),
+                ClickState::Released(1),
+                ClickState::Pressed(2),
+                ClickState::Released(2)
+            ]
+        );
+
+        // held past the long press threshold: recognized before the release is replayed
+        let mut long_press = replayer();
+        let clicks: Vec<_> = [press(1000), release(1800)]
+            .iter()
+            .flat_map(|event| long_press.replay(event))
+            .filter_map(|input| click_state(&input))
+            .collect();
+        assert_eq!(clicks[1], ClickState::LongPressed(1));
+    }
+
+    #[test]
+    fn replay_speed_scales_the_delay() {
+        let at = Duration::from_secs(2);
+        assert_eq!(ReplaySpeed::Original.delay(at), at);
+        assert_eq!(
+            ReplaySpeed::Scaled(4.0).delay(at),
+            Duration::from_millis(500)
+        );
+        assert_eq!(ReplaySpeed::Scaled(0.0).delay(at), at);
+        assert_eq!(ReplaySpeed::Scaled(1e-300).delay(at), Duration::MAX);
+        assert_eq!(ReplaySpeed::Scaled(f64::INFINITY).delay(at), Duration::ZERO);
+        assert_eq!(ReplaySpeed::Scaled(f64::NAN).delay(at), at);
+        assert_eq!(ReplaySpeed::Unthrottled.delay(at), Duration::ZERO);
+    }
+}
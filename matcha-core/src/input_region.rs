@@ -0,0 +1,209 @@
+//! Click-through for custom shaped windows.
+//!
+//! A transparent window that only draws a floating widget still catches every click on its
+//! empty parts. With [`App::click_through`](crate::app::App::click_through) it only takes
+//! pointer input where its widgets are, and clicks elsewhere go to the windows below.
+//!
+//! The [`InputRegion`] is the window's hit mask: the points where
+//! [`hit_test`](crate::ui::hit_test) finds a widget, sampled on a grid of [`CELL_SIZE`] and
+//! merged into rectangles. It is computed again whenever the layout changes. The root widget
+//! is laid out at its preferred size, so a root smaller than the window leaves the rest
+//! click-through, and widgets with their own [`is_inside`](crate::ui::AnyWidget::is_inside)
+//! shape the region further.
+//!
+//! winit can only make a whole window click-through, so the window is switched as the pointer
+//! moves in and out of the region. A click-through window receives no pointer events; raw
+//! pointer motion switches it back until the next position is known. Windows and X11 report
+//! raw motion to background windows; elsewhere the window catches clicks again once it is
+//! focused.
+
+use log::trace;
+
+use crate::{
+    context::WidgetContext,
+    ui::{AnyWidgetFrame, WidgetSnapshot, hit_test, snapshot},
+};
+
+/// Resolution of the hit mask in window coordinates.
+pub const CELL_SIZE: f32 = 4.0;
+
+/// The part of a window that takes pointer input, as `[min, max]` rectangles in window
+/// coordinates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputRegion {
+    rects: Vec<[[f32; 2]; 2]>,
+}
+
+impl InputRegion {
+    pub fn from_rects(rects: impl IntoIterator<Item = [[f32; 2]; 2]>) -> Self {
+        Self {
+            rects: rects.into_iter().collect(),
+        }
+    }
+
+    /// Samples `is_hit` at the centers of `cell`-sized squares covering `size` and merges the
+    /// hit squares into rectangles.
+    pub fn from_mask(size: [f32; 2], cell: f32, is_hit: impl Fn([f32; 2]) -> bool) -> Self {
+        let columns = (size[0] / cell).ceil() as usize;
+        let rows = (size[1] / cell).ceil() as usize;
+        let center = |index: usize, length: f32| ((index as f32 + 0.5) * cell).min(length);
+
+        let mut rects: Vec<[[f32; 2]; 2]> = Vec::new();
+        // runs of the previous row as (first column, end column, index in `rects`)
+        let mut open: Vec<(usize, usize, usize)> = Vec::new();
+        for row in 0..rows {
+            let y = center(row, size[1]);
+            let bottom = ((row + 1) as f32 * cell).min(size[1]);
+
+            let mut next_open = Vec::with_capacity(open.len());
+            let mut run_start = None;
+            for column in 0..=columns {
+                let hit = column < columns && is_hit([center(column, size[0]), y]);
+                match (hit, run_start) {
+                    (true, None) => run_start = Some(column),
+                    (false, Some(start)) => {
+                        run_start = None;
+                        // a run spanning the same columns as one above extends its rectangle
+                        let index = match open.iter().find(|run| run.0 == start && run.1 == column)
+                        {
+                            Some(&(_, _, index)) => {
+                                rects[index][1][1] = bottom;
+                                index
+                            }
+                            None => {
+                                rects.push([
+                                    [start as f32 * cell, row as f32 * cell],
+                                    [(column as f32 * cell).min(size[0]), bottom],
+                                ]);
+                                rects.len() - 1
+                            }
+                        };
+                        next_open.push((start, column, index));
+                    }
+                    _ => {}
+                }
+            }
+            open = next_open;
+        }
+
+        Self { rects }
+    }
+
+    /// The hit mask of the laid-out tree rooted at `root` in a window of `size`.
+    pub fn from_widget<E: 'static>(
+        root: &dyn AnyWidgetFrame<E>,
+        size: [f32; 2],
+        ctx: &WidgetContext,
+    ) -> Self {
+        Self::from_mask(size, CELL_SIZE, |position| {
+            !hit_test(root, position, ctx).is_empty()
+        })
+    }
+
+    pub fn rects(&self) -> &[[[f32; 2]; 2]] {
+        &self.rects
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn contains(&self, position: [f32; 2]) -> bool {
+        self.rects.iter().any(|[min, max]| {
+            (min[0]..max[0]).contains(&position[0]) && (min[1]..max[1]).contains(&position[1])
+        })
+    }
+}
+
+/// Click-through state of one window.
+pub(crate) struct ClickThrough {
+    // the layout `region` was computed from
+    layout: Option<WidgetSnapshot>,
+    region: InputRegion,
+    // whether the window currently catches the pointer
+    hittest: bool,
+}
+
+impl ClickThrough {
+    pub fn new() -> Self {
+        Self {
+            layout: None,
+            region: InputRegion::default(),
+            hittest: true,
+        }
+    }
+
+    /// Computes the region again if the layout of `root` changed.
+    pub fn update_region<E: 'static>(
+        &mut self,
+        root: &dyn AnyWidgetFrame<E>,
+        size: [f32; 2],
+        ctx: &WidgetContext,
+    ) {
+        let layout = snapshot(root);
+        if layout == self.layout {
+            return;
+        }
+        self.region = InputRegion::from_widget(root, size, ctx);
+        self.layout = layout;
+        trace!(
+            "ClickThrough::update_region: {} rectangles",
+            self.region.rects().len()
+        );
+    }
+
+    /// The pointer moved to `position` over the window. Returns the new hittest state of the
+    /// window if it changes.
+    pub fn pointer_moved(&mut self, position: [f32; 2]) -> Option<bool> {
+        self.set_hittest(self.region.contains(position))
+    }
+
+    /// The pointer moved somewhere while the window may be click-through. Catches it again
+    /// to learn its position.
+    pub fn restore(&mut self) -> Option<bool> {
+        self.set_hittest(true)
+    }
+
+    fn set_hittest(&mut self, hittest: bool) -> Option<bool> {
+        (self.hittest != hittest).then(|| {
+            self.hittest = hittest;
+            hittest
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_is_merged_into_rectangles() {
+        // a 20x12 square and a 4x4 dot, in a window whose size is not a multiple of the cell
+        let region = InputRegion::from_mask([30.0, 30.0], 4.0, |[x, y]| {
+            (x < 20.0 && y < 12.0) || ((24.0..28.0).contains(&x) && (24.0..28.0).contains(&y))
+        });
+        assert_eq!(
+            region.rects(),
+            &[[[0.0, 0.0], [20.0, 12.0]], [[24.0, 24.0], [28.0, 28.0]]]
+        );
+        assert!(region.contains([19.0, 11.0]));
+        assert!(!region.contains([22.0, 5.0]));
+
+        assert!(InputRegion::from_mask([30.0, 30.0], 4.0, |_| false).is_empty());
+        let full = InputRegion::from_mask([30.0, 30.0], 4.0, |_| true);
+        assert_eq!(full.rects(), &[[[0.0, 0.0], [30.0, 30.0]]]);
+    }
+
+    #[test]
+    fn hittest_follows_the_pointer() {
+        let mut click_through = ClickThrough::new();
+        click_through.region = InputRegion::from_rects([[[0.0, 0.0], [10.0, 10.0]]]);
+
+        assert_eq!(click_through.pointer_moved([5.0, 5.0]), None);
+        assert_eq!(click_through.pointer_moved([15.0, 5.0]), Some(false));
+        assert_eq!(click_through.pointer_moved([16.0, 5.0]), None);
+        assert_eq!(click_through.restore(), Some(true));
+        assert_eq!(click_through.restore(), None);
+    }
+}
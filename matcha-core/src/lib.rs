@@ -8,14 +8,44 @@ mod window_ui;
 mod winit_instance;
 
 // widget system
+pub mod automation;
 pub mod backend;
+pub mod cache_budget;
+pub mod capture;
 pub mod context;
+pub mod device_recovery;
+pub mod fonts;
+pub mod frame_clock;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
+pub mod lifecycle;
+pub mod localization;
+pub mod power_saving;
+pub mod present_timing;
+pub mod render_backend;
+pub mod resource_loader;
+pub mod test_kit;
+pub mod timer;
 pub mod ui;
+pub mod worker_pool;
 // debug / profiling config
 pub mod debug_config;
+pub mod profiling;
 
 // winit event handling
+pub mod clipboard;
+pub mod cursor;
 pub mod device_input;
+pub mod input_recording;
+pub mod input_region;
+pub mod menu;
+pub mod resize_strategy;
+pub mod shortcut;
+pub mod toast;
+pub mod tray;
+pub mod window_control;
+pub mod window_effect;
+pub mod window_icon;
 
 // types
 pub mod color;
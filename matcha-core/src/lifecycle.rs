@@ -0,0 +1,119 @@
+//! Suspend / resume of the whole application.
+//!
+//! Mobile platforms suspend the app when it moves to the background and destroy its native
+//! windows; laptops may do the same on lid close. While suspended, matcha drops the window
+//! surfaces, pauses the rendering loop and stops the animation clock. On resume it recreates
+//! the surfaces, rebuilds GPU resources through the
+//! [`DeviceRecoveryManager`](crate::device_recovery::DeviceRecoveryManager) and continues
+//! animations where they stopped.
+//!
+//! Components are told about both transitions with a [`LifecycleEvent`], see
+//! [`Component::lifecycle_fn`](crate::ui::Component::lifecycle_fn).
+
+use std::time::{Duration, Instant};
+
+use log::debug;
+use parking_lot::Mutex;
+
+/// A lifecycle transition of the application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    /// The app moved to the background. Surfaces are gone and nothing is rendered.
+    Suspended,
+    /// The app is back in the foreground and rendering again.
+    Resumed,
+    /// Every window is unfocused, minimized or covered. The app keeps running at a lower
+    /// frame rate, see [`power_saving`](crate::power_saving).
+    Background,
+    /// A window came back to the foreground, e.g. a chance to refresh data that went stale.
+    Foreground,
+}
+
+/// Tracks whether the application is suspended.
+pub(crate) struct Lifecycle {
+    suspended: tokio::sync::watch::Sender<bool>,
+    suspended_at: Mutex<Option<Instant>>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            suspended: tokio::sync::watch::Sender::new(false),
+            suspended_at: Mutex::new(None),
+        }
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        *self.suspended.borrow()
+    }
+
+    /// Enters the suspended state. Returns `false` if already suspended.
+    pub fn suspend(&self, now: Instant) -> bool {
+        let mut suspended_at = self.suspended_at.lock();
+        if suspended_at.is_some() {
+            return false;
+        }
+        *suspended_at = Some(now);
+        self.suspended.send_replace(true);
+        debug!("Lifecycle::suspend: application suspended");
+        true
+    }
+
+    /// Leaves the suspended state and returns how long it lasted, or `None` if the app was not
+    /// suspended.
+    pub fn resume(&self, now: Instant) -> Option<Duration> {
+        let suspended_at = self.suspended_at.lock().take()?;
+        self.suspended.send_replace(false);
+        let paused = now.saturating_duration_since(suspended_at);
+        debug!("Lifecycle::resume: application resumed after {paused:?}");
+        Some(paused)
+    }
+
+    /// Waits until the application is not suspended.
+    pub async fn wait_until_resumed(&self) {
+        let mut receiver = self.suspended.subscribe();
+        // the sender lives as long as `self`, so this cannot fail
+        let _ = receiver.wait_for(|suspended| !suspended).await;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspend_and_resume_report_the_paused_duration() {
+        let lifecycle = Lifecycle::new();
+        let start = Instant::now();
+        assert!(!lifecycle.is_suspended());
+        assert_eq!(lifecycle.resume(start), None);
+
+        assert!(lifecycle.suspend(start));
+        assert!(lifecycle.is_suspended());
+        // a second suspend keeps the original time
+        assert!(!lifecycle.suspend(start + Duration::from_secs(1)));
+
+        let paused = lifecycle.resume(start + Duration::from_secs(3)).unwrap();
+        assert_eq!(paused, Duration::from_secs(3));
+        assert!(!lifecycle.is_suspended());
+    }
+
+    #[tokio::test]
+    async fn wait_until_resumed_returns_after_resume() {
+        let lifecycle = std::sync::Arc::new(Lifecycle::new());
+        // returns immediately while running
+        lifecycle.wait_until_resumed().await;
+
+        lifecycle.suspend(Instant::now());
+        let waiter = {
+            let lifecycle = lifecycle.clone();
+            tokio::spawn(async move { lifecycle.wait_until_resumed().await })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        lifecycle.resume(Instant::now());
+        waiter.await.unwrap();
+    }
+}
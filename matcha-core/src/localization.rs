@@ -0,0 +1,518 @@
+//! Localized strings.
+//!
+//! Messages are looked up by key through a pluggable [`Localizer`], e.g. one backed by fluent
+//! bundles. [`MessageTable`] is a simple in-memory implementation that replaces `{name}`
+//! placeholders with arguments.
+//!
+//! The app holds one [`Localization`] with the localizer and the current locale, next to its
+//! other resources. View functions do not receive a context, so they translate through the
+//! [`tr!`](crate::tr) macro, which uses the localization of the window whose view is being
+//! built:
+//!
+//! ```ignore
+//! text(tr!("greeting", name = model.user.as_str()))
+//! ```
+//!
+//! [`ApplicationContext::set_locale`](crate::context::ApplicationContext::set_locale) switches
+//! the locale at runtime. Every window then rebuilds its view and lays out and redraws the
+//! whole tree, since translated strings change length.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::{debug, trace};
+use parking_lot::RwLock;
+
+/// A value substituted into a message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageArg {
+    String(String),
+    Number(f64),
+}
+
+impl fmt::Display for MessageArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageArg::String(s) => f.write_str(s),
+            MessageArg::Number(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+impl From<&str> for MessageArg {
+    fn from(value: &str) -> Self {
+        MessageArg::String(value.to_string())
+    }
+}
+
+impl From<String> for MessageArg {
+    fn from(value: String) -> Self {
+        MessageArg::String(value)
+    }
+}
+
+impl From<&String> for MessageArg {
+    fn from(value: &String) -> Self {
+        MessageArg::String(value.clone())
+    }
+}
+
+macro_rules! message_arg_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for MessageArg {
+                fn from(value: $t) -> Self {
+                    MessageArg::Number(value as f64)
+                }
+            }
+        )*
+    };
+}
+
+message_arg_from_number!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Resolves message keys to localized strings.
+pub trait Localizer: Send + Sync + 'static {
+    /// The message `key` in `locale` with `args` substituted, or `None` if there is no such
+    /// message.
+    fn format(&self, locale: &str, key: &str, args: &[(&str, MessageArg)]) -> Option<String>;
+}
+
+/// In-memory messages per locale.
+///
+/// `{name}` in a message is replaced by the argument called `name`; `{{` and `}}` produce
+/// literal braces. A key missing in `"de-AT"` is looked up in `"de"`, then in the fallback
+/// locale.
+#[derive(Debug, Clone, Default)]
+pub struct MessageTable {
+    messages: HashMap<String, HashMap<String, String>>,
+    fallback_locale: Option<String>,
+}
+
+impl MessageTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a message for `locale`.
+    pub fn message(
+        mut self,
+        locale: impl Into<String>,
+        key: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        self.insert(locale, key, message);
+        self
+    }
+
+    pub fn insert(
+        &mut self,
+        locale: impl Into<String>,
+        key: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.messages
+            .entry(locale.into())
+            .or_default()
+            .insert(key.into(), message.into());
+    }
+
+    /// Locale searched when a message is missing in the requested locale.
+    pub fn fallback_locale(mut self, locale: impl Into<String>) -> Self {
+        self.fallback_locale = Some(locale.into());
+        self
+    }
+
+    fn lookup(&self, locale: &str, key: &str) -> Option<&str> {
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+        [
+            Some(locale),
+            Some(language),
+            self.fallback_locale.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .find_map(|locale| self.messages.get(locale)?.get(key))
+        .map(String::as_str)
+    }
+}
+
+impl Localizer for MessageTable {
+    fn format(&self, locale: &str, key: &str, args: &[(&str, MessageArg)]) -> Option<String> {
+        let message = self.lookup(locale, key)?;
+
+        let mut formatted = String::with_capacity(message.len());
+        let mut rest = message;
+        while let Some(start) = rest.find(['{', '}']) {
+            formatted.push_str(&rest[..start]);
+            let brace = &rest[start..];
+            if brace.starts_with("{{") || brace.starts_with("}}") {
+                formatted.push_str(&brace[..1]);
+                rest = &brace[2..];
+            } else if let Some(end) = brace.find('}')
+                && brace.starts_with('{')
+            {
+                let name = brace[1..end].trim();
+                match args.iter().find(|(arg, _)| *arg == name) {
+                    Some((_, value)) => formatted.push_str(&value.to_string()),
+                    // keep unknown placeholders visible
+                    None => formatted.push_str(&brace[..=end]),
+                }
+                rest = &brace[end + 1..];
+            } else {
+                formatted.push_str(&brace[..1]);
+                rest = &brace[1..];
+            }
+        }
+        formatted.push_str(rest);
+
+        Some(formatted)
+    }
+}
+
+/// The localizer and current locale of the app.
+pub struct Localization {
+    localizer: RwLock<Arc<dyn Localizer>>,
+    locale: RwLock<String>,
+    // incremented whenever translations may change
+    generation: AtomicU64,
+}
+
+impl Default for Localization {
+    fn default() -> Self {
+        Self::new(MessageTable::new(), "en")
+    }
+}
+
+impl Localization {
+    pub fn new(localizer: impl Localizer, locale: impl Into<String>) -> Self {
+        Self {
+            localizer: RwLock::new(Arc::new(localizer)),
+            locale: RwLock::new(locale.into()),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn locale(&self) -> String {
+        self.locale.read().clone()
+    }
+
+    /// Switches the locale. Returns `false` if it is already `locale`.
+    pub fn set_locale(&self, locale: impl Into<String>) -> bool {
+        let locale = locale.into();
+        let mut current = self.locale.write();
+        if *current == locale {
+            return false;
+        }
+        debug!("Localization::set_locale: {current} -> {locale}");
+        *current = locale;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        true
+    }
+
+    pub fn set_localizer(&self, localizer: impl Localizer) {
+        *self.localizer.write() = Arc::new(localizer);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Incremented whenever the locale or the localizer changes.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// The message `key` in the current locale, or `None` if it is missing.
+    pub fn try_translate(&self, key: &str, args: &[(&str, MessageArg)]) -> Option<String> {
+        let localizer = self.localizer.read().clone();
+        localizer.format(&self.locale.read(), key, args)
+    }
+
+    /// The message `key` in the current locale. Falls back to the key itself so that missing
+    /// translations stay visible.
+    pub fn translate(&self, key: &str, args: &[(&str, MessageArg)]) -> String {
+        self.try_translate(key, args).unwrap_or_else(|| {
+            trace!("Localization::translate: missing message key={key}");
+            key.to_string()
+        })
+    }
+}
+
+thread_local! {
+    // the localization of the view being built on this thread, used by `tr!`
+    static CURRENT: RefCell<Option<Arc<Localization>>> = const { RefCell::new(None) };
+}
+
+/// Puts the localization of the enclosing view back when dropped, also when the view panics.
+struct RestoreOuter(Option<Arc<Localization>>);
+
+impl Drop for RestoreOuter {
+    fn drop(&mut self) {
+        let outer = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = outer);
+    }
+}
+
+/// Builds a view with `localization` as the one used by [`tr!`](crate::tr).
+pub(crate) fn view_scope<R>(
+    localization: Option<Arc<Localization>>,
+    view: impl FnOnce() -> R,
+) -> R {
+    let _outer = RestoreOuter(CURRENT.with(|current| current.replace(localization)));
+    view()
+}
+
+/// The localization of the view being built, or `None` outside of view functions.
+pub fn current() -> Option<Arc<Localization>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Translates `key` with the localization of the view being built. Use [`tr!`](crate::tr)
+/// instead of calling this directly.
+pub fn tr(key: &str, args: &[(&str, MessageArg)]) -> String {
+    match current() {
+        Some(localization) => localization.translate(key, args),
+        None => key.to_string(),
+    }
+}
+
+/// The decimal separator of numbers written in `locale`, e.g. `','` for `"de-DE"` and `'.'`
+/// for `"en-US"`. Unknown locales use `'.'`.
+pub fn decimal_separator(locale: &str) -> char {
+    // languages that write a decimal comma, and regions where they do not
+    const COMMA: &[&str] = &[
+        "af", "az", "be", "bg", "bs", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fo",
+        "fr", "gl", "hr", "hu", "hy", "id", "is", "it", "ka", "kk", "ky", "lt", "lv", "mk", "mn",
+        "nb", "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sq", "sr", "sv", "tr", "uk",
+        "uz", "vi",
+    ];
+    const POINT_REGIONS: &[&str] = &["de-ch", "de-li", "es-mx", "es-us", "it-ch"];
+
+    let locale = locale.to_ascii_lowercase().replace('_', "-");
+    let language = locale.split('-').next().unwrap_or_default();
+    let comma = COMMA.contains(&language)
+        && !POINT_REGIONS
+            .iter()
+            .any(|region| locale.starts_with(region));
+    if comma { ',' } else { '.' }
+}
+
+/// The decimal separator of the locale of the view being built, `'.'` outside of view
+/// functions.
+pub fn current_decimal_separator() -> char {
+    current().map_or('.', |localization| {
+        decimal_separator(&localization.locale())
+    })
+}
+
+/// A day of the week.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    /// Monday is 0 and Sunday 6.
+    pub fn days_from_monday(self) -> u32 {
+        self as u32
+    }
+
+    /// The weekday `days` after Monday, wrapping around.
+    pub fn from_days_from_monday(days: u32) -> Self {
+        const ALL: [Weekday; 7] = [
+            Weekday::Monday,
+            Weekday::Tuesday,
+            Weekday::Wednesday,
+            Weekday::Thursday,
+            Weekday::Friday,
+            Weekday::Saturday,
+            Weekday::Sunday,
+        ];
+        ALL[days as usize % 7]
+    }
+}
+
+/// The day weeks start on in `locale`, e.g. Sunday for `"en-US"` and Monday for `"de-DE"`.
+/// Locales without a region use the region where the language is mostly spoken. Unknown
+/// locales start on Monday.
+pub fn first_day_of_week(locale: &str) -> Weekday {
+    // regions where weeks do not start on Monday
+    const SUNDAY: &[&str] = &[
+        "ag", "as", "bd", "br", "bs", "bt", "bw", "bz", "ca", "co", "dm", "do", "et", "gt", "gu",
+        "hk", "hn", "id", "il", "in", "jm", "jp", "ke", "kh", "kr", "la", "mh", "mm", "mo", "mt",
+        "mx", "mz", "ni", "np", "pa", "pe", "ph", "pk", "pr", "pt", "py", "sa", "sg", "sv", "th",
+        "tt", "tw", "um", "us", "ve", "vi", "ws", "ye", "za", "zw",
+    ];
+    const SATURDAY: &[&str] = &[
+        "ae", "af", "bh", "dj", "dz", "eg", "iq", "ir", "jo", "kw", "ly", "om", "qa", "sd", "sy",
+    ];
+    // the region assumed for a language without one
+    const LIKELY_REGIONS: &[(&str, &str)] = &[
+        ("ar", "eg"),
+        ("en", "us"),
+        ("fa", "ir"),
+        ("he", "il"),
+        ("hi", "in"),
+        ("ja", "jp"),
+        ("ko", "kr"),
+        ("pt", "br"),
+        ("th", "th"),
+    ];
+
+    let locale = locale.to_ascii_lowercase().replace('_', "-");
+    let mut subtags = locale.split('-');
+    let language = subtags.next().unwrap_or_default();
+    // the region follows the language and an optional script, e.g. "zh-hant-tw"
+    let region = subtags.find(|subtag| subtag.len() == 2).or_else(|| {
+        LIKELY_REGIONS
+            .iter()
+            .find(|(likely, _)| *likely == language)
+            .map(|(_, region)| *region)
+    });
+
+    match region {
+        Some(region) if SUNDAY.contains(&region) => Weekday::Sunday,
+        Some(region) if SATURDAY.contains(&region) => Weekday::Saturday,
+        _ => Weekday::Monday,
+    }
+}
+
+/// The first day of the week in the locale of the view being built, Monday outside of view
+/// functions.
+pub fn current_first_day_of_week() -> Weekday {
+    current().map_or(Weekday::Monday, |localization| {
+        first_day_of_week(&localization.locale())
+    })
+}
+
+/// Translates a message key with the localization of the window whose view is being built.
+/// Outside of view functions the key is returned as is; widgets translate through
+/// `WidgetContext::tr` instead.
+///
+/// ```ignore
+/// tr!("quit")
+/// tr!("items-selected", count = selection.len())
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($key:expr $(,)?) => {
+        $crate::localization::tr($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::localization::tr(
+            $key,
+            &[$((
+                stringify!($name),
+                $crate::localization::MessageArg::from($value),
+            )),+],
+        )
+    };
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn table() -> MessageTable {
+        MessageTable::new()
+            .fallback_locale("en")
+            .message("en", "greeting", "Hello, {name}!")
+            .message("en", "quit", "Quit")
+            .message("de", "greeting", "Hallo, {name}!")
+            .message("en", "braces", "{{literal}} {missing} {count}")
+    }
+
+    #[test]
+    fn message_table_formats_and_falls_back() {
+        let table = table();
+        let args = [("name", MessageArg::from("Ada"))];
+
+        assert_eq!(
+            table.format("de-AT", "greeting", &args).unwrap(),
+            "Hallo, Ada!"
+        );
+        // missing in German, found in the fallback locale
+        assert_eq!(table.format("de", "quit", &[]).unwrap(), "Quit");
+        assert_eq!(table.format("fr", "unknown", &[]), None);
+
+        assert_eq!(
+            table
+                .format("en", "braces", &[("count", MessageArg::from(3))])
+                .unwrap(),
+            "{literal} {missing} 3"
+        );
+    }
+
+    #[test]
+    fn localization_switches_locale() {
+        let localization = Localization::new(table(), "en");
+        let args = [("name", MessageArg::from("Ada"))];
+        assert_eq!(localization.translate("greeting", &args), "Hello, Ada!");
+        assert_eq!(localization.translate("missing-key", &[]), "missing-key");
+
+        assert!(localization.set_locale("de"));
+        assert!(!localization.set_locale("de"));
+        assert_eq!(localization.generation(), 1);
+        assert_eq!(localization.translate("greeting", &args), "Hallo, Ada!");
+    }
+
+    #[test]
+    fn decimal_separators_follow_the_locale() {
+        assert_eq!(decimal_separator("en-US"), '.');
+        assert_eq!(decimal_separator("ja"), '.');
+        assert_eq!(decimal_separator("de-DE"), ',');
+        assert_eq!(decimal_separator("fr_FR"), ',');
+        assert_eq!(decimal_separator("de-CH"), '.');
+        assert_eq!(decimal_separator(""), '.');
+    }
+
+    #[test]
+    fn weeks_start_on_the_day_of_the_region() {
+        assert_eq!(first_day_of_week("en-US"), Weekday::Sunday);
+        assert_eq!(first_day_of_week("en-GB"), Weekday::Monday);
+        assert_eq!(first_day_of_week("en"), Weekday::Sunday);
+        assert_eq!(first_day_of_week("de_DE"), Weekday::Monday);
+        assert_eq!(first_day_of_week("zh-Hant-TW"), Weekday::Sunday);
+        assert_eq!(first_day_of_week("ar-EG"), Weekday::Saturday);
+        assert_eq!(first_day_of_week(""), Weekday::Monday);
+        assert_eq!(Weekday::from_days_from_monday(13), Weekday::Sunday);
+    }
+
+    #[test]
+    fn tr_macro_uses_the_localization_of_the_view() {
+        let english = Arc::new(Localization::new(table(), "en"));
+        let german = Arc::new(Localization::new(table(), "de"));
+        view_scope(Some(english), || {
+            assert_eq!(crate::tr!("quit"), "Quit");
+            // nested views restore the outer localization afterwards
+            view_scope(Some(german), || {
+                assert_eq!(crate::tr!("greeting", name = "Ada"), "Hallo, Ada!");
+            });
+            assert_eq!(crate::tr!("greeting", name = "Ada",), "Hello, Ada!");
+        });
+        assert_eq!(crate::tr!("quit"), "quit");
+        assert_eq!(current_decimal_separator(), '.');
+    }
+
+    #[test]
+    fn panicking_view_restores_the_outer_localization() {
+        let english = Arc::new(Localization::new(table(), "en"));
+        let german = Arc::new(Localization::new(table(), "de"));
+        view_scope(Some(english), || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                view_scope(Some(german), || panic!("view failed"))
+            }));
+            assert!(result.is_err());
+            assert_eq!(crate::tr!("greeting", name = "Ada"), "Hello, Ada!");
+        });
+        assert!(current().is_none());
+    }
+}
@@ -0,0 +1,274 @@
+//! Menu descriptions.
+//!
+//! A [`Menu`] is a tree of labelled actions that produce messages. The same description is
+//! used for the native menu bar of the window ([`MenuBar`], installed with
+//! [`App::menu_bar`](crate::app::App::menu_bar)) and for in-window menus such as the
+//! `ContextMenu` widget.
+
+use std::sync::Arc;
+
+use crate::shortcut::{Shortcut, ShortcutRegistry};
+
+pub(crate) mod native;
+
+/// A selectable menu entry.
+pub struct MenuAction<T> {
+    label: String,
+    shortcut: Option<Shortcut>,
+    enabled: bool,
+    action: Arc<dyn Fn() -> T + Send + Sync>,
+}
+
+impl<T> Clone for MenuAction<T> {
+    fn clone(&self) -> Self {
+        Self {
+            label: self.label.clone(),
+            shortcut: self.shortcut.clone(),
+            enabled: self.enabled,
+            action: Arc::clone(&self.action),
+        }
+    }
+}
+
+impl<T> MenuAction<T> {
+    pub fn new(label: &str, action: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        Self {
+            label: label.to_string(),
+            shortcut: None,
+            enabled: true,
+            action: Arc::new(action),
+        }
+    }
+
+    /// Shows `shortcut` next to the label. In a [`MenuBar`] the shortcut is also registered
+    /// as an app shortcut, so it triggers the action while the menu is closed.
+    pub fn shortcut(mut self, shortcut: Shortcut) -> Self {
+        self.shortcut = Some(shortcut);
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// The shortcut shown next to the label.
+    pub fn accelerator(&self) -> Option<&Shortcut> {
+        self.shortcut.as_ref()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Produces the message of this entry.
+    pub fn trigger(&self) -> T {
+        (self.action)()
+    }
+}
+
+pub enum MenuItem<T> {
+    Action(MenuAction<T>),
+    Separator,
+    Submenu(Menu<T>),
+}
+
+impl<T> Clone for MenuItem<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action(action) => Self::Action(action.clone()),
+            Self::Separator => Self::Separator,
+            Self::Submenu(menu) => Self::Submenu(menu.clone()),
+        }
+    }
+}
+
+impl<T> MenuItem<T> {
+    /// `true` for entries that can be highlighted and activated.
+    pub fn is_selectable(&self) -> bool {
+        match self {
+            Self::Action(action) => action.enabled,
+            Self::Separator => false,
+            Self::Submenu(menu) => menu.enabled,
+        }
+    }
+}
+
+/// A titled list of menu items.
+pub struct Menu<T> {
+    title: String,
+    enabled: bool,
+    items: Vec<MenuItem<T>>,
+}
+
+impl<T> Clone for Menu<T> {
+    fn clone(&self) -> Self {
+        Self {
+            title: self.title.clone(),
+            enabled: self.enabled,
+            items: self.items.clone(),
+        }
+    }
+}
+
+impl<T> Menu<T> {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            enabled: true,
+            items: Vec::new(),
+        }
+    }
+
+    /// Adds an action with the given label.
+    pub fn item(self, label: &str, action: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        self.action(MenuAction::new(label, action))
+    }
+
+    pub fn action(mut self, action: MenuAction<T>) -> Self {
+        self.items.push(MenuItem::Action(action));
+        self
+    }
+
+    pub fn separator(mut self) -> Self {
+        self.items.push(MenuItem::Separator);
+        self
+    }
+
+    pub fn submenu(mut self, menu: Menu<T>) -> Self {
+        self.items.push(MenuItem::Submenu(menu));
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn items(&self) -> &[MenuItem<T>] {
+        &self.items
+    }
+
+    /// Calls `f` for every enabled action in this menu and its enabled submenus.
+    pub fn for_each_action(&self, f: &mut impl FnMut(&MenuAction<T>)) {
+        if !self.enabled {
+            return;
+        }
+        for item in &self.items {
+            match item {
+                MenuItem::Action(action) if action.enabled => f(action),
+                MenuItem::Submenu(menu) => menu.for_each_action(f),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// The menus shown in the menu bar of the application window.
+///
+/// The native menu bar is available on Windows and macOS with the `native-menu` feature.
+/// Elsewhere the menus are not shown, but their shortcuts still work.
+pub struct MenuBar<T> {
+    menus: Vec<Menu<T>>,
+}
+
+impl<T> Clone for MenuBar<T> {
+    fn clone(&self) -> Self {
+        Self {
+            menus: self.menus.clone(),
+        }
+    }
+}
+
+impl<T> Default for MenuBar<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> MenuBar<T> {
+    pub fn new() -> Self {
+        Self { menus: Vec::new() }
+    }
+
+    pub fn menu(mut self, menu: Menu<T>) -> Self {
+        self.menus.push(menu);
+        self
+    }
+
+    pub fn menus(&self) -> &[Menu<T>] {
+        &self.menus
+    }
+
+    /// Registers the shortcuts of all enabled actions in `registry`.
+    pub(crate) fn register_shortcuts(&self, registry: &ShortcutRegistry<T>)
+    where
+        T: 'static,
+    {
+        for menu in &self.menus {
+            menu.for_each_action(&mut |action| {
+                if let Some(shortcut) = &action.shortcut {
+                    let action = Arc::clone(&action.action);
+                    registry.register(shortcut.clone(), move || action());
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use winit::keyboard::{Key, ModifiersState};
+
+    #[test]
+    fn menu_bar_registers_enabled_shortcuts() {
+        let menu_bar = MenuBar::new()
+            .menu(
+                Menu::new("File")
+                    .action(MenuAction::new("Save", || "save").shortcut("Ctrl+S".parse().unwrap()))
+                    .separator()
+                    .action(
+                        MenuAction::new("Print", || "print")
+                            .shortcut("Ctrl+P".parse().unwrap())
+                            .enabled(false),
+                    ),
+            )
+            .menu(Menu::new("Edit").submenu(Menu::new("Find").action(
+                MenuAction::new("Find next", || "find next").shortcut("F3".parse().unwrap()),
+            )));
+
+        let registry = ShortcutRegistry::new();
+        menu_bar.register_shortcuts(&registry);
+
+        let ctrl = ModifiersState::CONTROL;
+        assert_eq!(
+            registry.dispatch_key(ctrl, &Key::Character("s".into())),
+            Some("save")
+        );
+        assert_eq!(
+            registry.dispatch_key(ctrl, &Key::Character("p".into())),
+            None
+        );
+        assert_eq!(
+            registry.dispatch_key(
+                ModifiersState::empty(),
+                &Key::Named(winit::keyboard::NamedKey::F3)
+            ),
+            Some("find next")
+        );
+    }
+}
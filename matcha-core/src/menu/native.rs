@@ -0,0 +1,331 @@
+//! Native menu bar of the application window.
+//!
+//! Backed by `muda` on Windows and macOS when the `native-menu` feature is enabled. Other
+//! configurations use a stub that shows nothing.
+
+// the platform-independent half of the native backends, see `MenuActions`
+#[cfg(any(
+    test,
+    all(
+        feature = "native-menu",
+        any(target_os = "windows", target_os = "macos")
+    )
+))]
+mod actions {
+    use std::collections::HashMap;
+
+    use crate::menu::MenuAction;
+
+    /// The actions behind native menu items, by the id given to each item.
+    ///
+    /// Activated items are turned into messages here rather than by the platform backend, so
+    /// the menu bar and the tray menu map the same way everywhere.
+    pub(crate) struct MenuActions<T> {
+        actions: HashMap<String, MenuAction<T>>,
+    }
+
+    impl<T> MenuActions<T> {
+        pub(crate) fn new() -> Self {
+            Self {
+                actions: HashMap::new(),
+            }
+        }
+
+        /// Registers `action` and returns the id to give its native item.
+        pub(crate) fn register(&mut self, action: &MenuAction<T>) -> String {
+            let id = format!("matcha-menu-{}", self.actions.len());
+            self.actions.insert(id.clone(), action.clone());
+            id
+        }
+
+        /// The message of the item with `id`. `None` for unknown items and disabled actions.
+        pub(crate) fn trigger(&self, id: &str) -> Option<T> {
+            self.actions
+                .get(id)
+                .filter(|action| action.is_enabled())
+                .map(MenuAction::trigger)
+        }
+
+        pub(crate) fn len(&self) -> usize {
+            self.actions.len()
+        }
+    }
+}
+
+#[cfg(any(
+    test,
+    all(
+        feature = "native-menu",
+        any(target_os = "windows", target_os = "macos")
+    )
+))]
+pub(crate) use actions::MenuActions;
+
+#[cfg(all(
+    feature = "native-menu",
+    any(target_os = "windows", target_os = "macos")
+))]
+mod platform {
+    use log::{debug, trace, warn};
+    use muda::MenuEvent;
+    use winit::window::{Window, WindowId};
+
+    use super::MenuActions;
+    use crate::menu::{Menu, MenuBar, MenuItem};
+
+    pub(crate) struct NativeMenu<T> {
+        menu: muda::Menu,
+        actions: MenuActions<T>,
+        attached: Vec<WindowId>,
+    }
+
+    impl<T> NativeMenu<T> {
+        pub(crate) fn new(menu_bar: &MenuBar<T>) -> Self {
+            let menu = muda::Menu::new();
+            let mut actions = MenuActions::new();
+
+            for submenu in menu_bar.menus() {
+                let result =
+                    build_submenu(submenu, &mut actions).and_then(|native| menu.append(&native));
+                if let Err(e) = result {
+                    warn!(
+                        "NativeMenu::new: failed to build menu '{}': {e}",
+                        submenu.title()
+                    );
+                }
+            }
+
+            debug!(
+                "NativeMenu::new: built menu bar with {} actions",
+                actions.len()
+            );
+            Self {
+                menu,
+                actions,
+                attached: Vec::new(),
+            }
+        }
+
+        /// Shows the menu bar on `window`. Windows that already show it are skipped.
+        pub(crate) fn attach(&mut self, window: &Window) {
+            if self.attached.contains(&window.id()) {
+                return;
+            }
+
+            #[cfg(target_os = "windows")]
+            {
+                use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+                let handle = match window.window_handle() {
+                    Ok(handle) => handle.as_raw(),
+                    Err(e) => {
+                        warn!("NativeMenu::attach: window handle not available: {e}");
+                        return;
+                    }
+                };
+                let RawWindowHandle::Win32(handle) = handle else {
+                    warn!("NativeMenu::attach: not a Win32 window");
+                    return;
+                };
+                // SAFETY: the handle belongs to a live window created by this event loop.
+                if let Err(e) = unsafe { self.menu.init_for_hwnd(handle.hwnd.get()) } {
+                    warn!("NativeMenu::attach: failed to attach menu bar: {e}");
+                    return;
+                }
+            }
+
+            #[cfg(target_os = "macos")]
+            {
+                // the application has a single menu bar shared by all windows
+                if self.attached.is_empty() {
+                    self.menu.init_for_nsapp();
+                }
+            }
+
+            trace!(
+                "NativeMenu::attach: attached to window id={:?}",
+                window.id()
+            );
+            self.attached.push(window.id());
+        }
+
+        /// Builds a popup menu (e.g. for a tray icon) whose actions are reported by `poll`.
+        #[cfg(feature = "tray-icon")]
+        pub(crate) fn context_menu(&mut self, menu: &Menu<T>) -> Option<muda::Submenu> {
+            build_submenu(menu, &mut self.actions)
+                .inspect_err(|e| {
+                    warn!(
+                        "NativeMenu::context_menu: failed to build menu '{}': {e}",
+                        menu.title()
+                    )
+                })
+                .ok()
+        }
+
+        /// Returns the message of the next activated menu item, if any.
+        pub(crate) fn poll(&self) -> Option<T> {
+            while let Ok(event) = MenuEvent::receiver().try_recv() {
+                if let Some(message) = self.actions.trigger(&event.id.0) {
+                    return Some(message);
+                }
+                trace!("NativeMenu::poll: ignoring menu id {:?}", event.id);
+            }
+            None
+        }
+    }
+
+    fn build_submenu<T>(
+        menu: &Menu<T>,
+        actions: &mut MenuActions<T>,
+    ) -> muda::Result<muda::Submenu> {
+        let submenu = muda::Submenu::new(menu.title(), menu.is_enabled());
+
+        for item in menu.items() {
+            match item {
+                MenuItem::Action(action) => {
+                    let accelerator = action.accelerator().and_then(|shortcut| {
+                        shortcut
+                            .to_string()
+                            .parse::<muda::accelerator::Accelerator>()
+                            .inspect_err(|e| {
+                                warn!("NativeMenu: cannot show shortcut {shortcut} natively: {e}")
+                            })
+                            .ok()
+                    });
+                    let native = muda::MenuItem::with_id(
+                        actions.register(action),
+                        action.label(),
+                        action.is_enabled(),
+                        accelerator,
+                    );
+                    submenu.append(&native)?;
+                }
+                MenuItem::Separator => {
+                    submenu.append(&muda::PredefinedMenuItem::separator())?;
+                }
+                MenuItem::Submenu(menu) => {
+                    submenu.append(&build_submenu(menu, actions)?)?;
+                }
+            }
+        }
+
+        Ok(submenu)
+    }
+}
+
+#[cfg(not(all(
+    feature = "native-menu",
+    any(target_os = "windows", target_os = "macos")
+)))]
+mod platform {
+    use log::debug;
+    use winit::window::Window;
+
+    use crate::menu::MenuBar;
+
+    pub(crate) struct NativeMenu<T> {
+        _marker: std::marker::PhantomData<fn() -> T>,
+    }
+
+    impl<T> NativeMenu<T> {
+        pub(crate) fn new(menu_bar: &MenuBar<T>) -> Self {
+            if !menu_bar.menus().is_empty() {
+                debug!(
+                    "NativeMenu::new: native menus are not available in this build; only their shortcuts are active"
+                );
+            }
+            Self {
+                _marker: std::marker::PhantomData,
+            }
+        }
+
+        pub(crate) fn attach(&mut self, _window: &Window) {}
+
+        pub(crate) fn poll(&self) -> Option<T> {
+            None
+        }
+    }
+}
+
+pub(crate) use platform::NativeMenu;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::{Menu, MenuAction, MenuBar, MenuItem};
+
+    // registers the actions of `menu` like the native backends do while building it
+    fn register_all(
+        menu: &Menu<&'static str>,
+        actions: &mut MenuActions<&'static str>,
+    ) -> Vec<String> {
+        let mut ids = Vec::new();
+        for item in menu.items() {
+            match item {
+                MenuItem::Action(action) => ids.push(actions.register(action)),
+                MenuItem::Separator => {}
+                MenuItem::Submenu(menu) => ids.extend(register_all(menu, actions)),
+            }
+        }
+        ids
+    }
+
+    #[test]
+    fn activated_items_produce_their_messages() {
+        let menu = Menu::new("Tray")
+            .item("Open", || "open")
+            .separator()
+            .submenu(Menu::new("Status").item("Away", || "away"))
+            .item("Quit", || "quit");
+        let mut actions = MenuActions::new();
+        let ids = register_all(&menu, &mut actions);
+
+        assert_eq!(actions.len(), 3);
+        let messages: Vec<_> = ids.iter().map(|id| actions.trigger(id)).collect();
+        assert_eq!(messages, [Some("open"), Some("away"), Some("quit")]);
+    }
+
+    #[test]
+    fn disabled_and_unknown_items_produce_nothing() {
+        let menu = Menu::new("Tray")
+            .action(MenuAction::new("Sync", || "sync").enabled(false))
+            .item("Quit", || "quit");
+        let mut actions = MenuActions::new();
+        let ids = register_all(&menu, &mut actions);
+
+        assert_eq!(actions.trigger(&ids[0]), None);
+        assert_eq!(actions.trigger(&ids[1]), Some("quit"));
+        assert_eq!(actions.trigger("another-menu-item"), None);
+    }
+
+    #[test]
+    fn menus_registered_together_get_distinct_ids() {
+        let menu_bar = MenuBar::new()
+            .menu(Menu::new("File").item("Quit", || "quit"))
+            .menu(Menu::new("Help").item("About", || "about"));
+        let tray_menu = Menu::new("Tray").item("Quit", || "tray quit");
+        let mut actions = MenuActions::new();
+
+        let mut ids: Vec<String> = menu_bar
+            .menus()
+            .iter()
+            .flat_map(|menu| register_all(menu, &mut actions))
+            .collect();
+        ids.extend(register_all(&tray_menu, &mut actions));
+
+        let messages: Vec<_> = ids.iter().map(|id| actions.trigger(id)).collect();
+        assert_eq!(messages, [Some("quit"), Some("about"), Some("tray quit")]);
+    }
+
+    #[cfg(not(all(
+        feature = "native-menu",
+        any(target_os = "windows", target_os = "macos")
+    )))]
+    #[test]
+    fn without_native_menus_nothing_is_activated() {
+        let menu_bar = MenuBar::new().menu(Menu::new("File").item("Quit", || "quit"));
+        let native_menu = NativeMenu::new(&menu_bar);
+
+        assert_eq!(native_menu.poll(), None);
+    }
+}
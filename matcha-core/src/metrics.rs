@@ -206,6 +206,10 @@ impl QRect {
     }
 }
 
+/// Maxima from this many pixels on mean "no limit": constraints are quantized to integers,
+/// so an infinite maximum is stored as a huge finite value.
+const UNBOUNDED_EXTENT: f32 = 1.0e6;
+
 /// A struct that represents the constraints for a widget's size.
 /// This is passed from parent to child to define the available space.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -306,10 +310,36 @@ impl Constraints {
     pub const fn min_size(&self) -> [f32; 2] {
         [self.min_width(), self.min_height()]
     }
+
+    /// Constraints from zero to no limit on both axes.
+    pub fn unbounded() -> Self {
+        Self::new([0.0, f32::INFINITY], [0.0, f32::INFINITY])
+    }
+
+    /// Whether the maximum width and height are unbounded.
+    pub fn is_unbounded(&self) -> [bool; 2] {
+        self.max_size().map(Self::is_unbounded_extent)
+    }
+
+    /// The maximum size, `None` on axes without a limit.
+    pub fn max_finite(&self) -> [Option<f32>; 2] {
+        self.max_size()
+            .map(|max| (!Self::is_unbounded_extent(max)).then_some(max))
+    }
+
+    /// Whether `extent`, e.g. a maximum of some constraints less a margin, stands for "no
+    /// limit".
+    pub fn is_unbounded_extent(extent: f32) -> bool {
+        extent >= UNBOUNDED_EXTENT
+    }
 }
 
 /// Arrangement for a child after layout pass.
-/// Holds the allocated size and transform matrices for rendering/hit-testing.
+/// Holds the allocated size and transform matrices for rendering/hit-testing, and how the
+/// child is clipped and faded.
+///
+/// `WidgetFrame` applies `clip` and `opacity` to the children it hands to a `Widget`
+/// implementation, so containers only need to set them.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Arrangement {
     /// size allocated to the child (width, height)
@@ -320,6 +350,10 @@ pub struct Arrangement {
     /// inverse of `affine` when invertible. If `None`, the affine collapses at least
     /// one axis and the child is effectively invisible / non-hit-testable in that axis.
     pub affine_inv: Option<Matrix4<f32>>,
+    /// if true, the child is not drawn outside of `size` and does not take pointer input there.
+    pub clip: bool,
+    /// opacity of the child from 0.0 (invisible) to 1.0 (opaque). The child still takes input.
+    pub opacity: f32,
 }
 
 impl Default for Arrangement {
@@ -328,6 +362,8 @@ impl Default for Arrangement {
             size: [0.0, 0.0],
             affine: Matrix4::identity(),
             affine_inv: Some(Matrix4::identity()),
+            clip: false,
+            opacity: 1.0,
         }
     }
 }
@@ -341,9 +377,30 @@ impl Arrangement {
             size,
             affine,
             affine_inv,
+            clip: false,
+            opacity: 1.0,
         }
     }
 
+    /// Clips the child to its allocated `size`.
+    pub fn with_clip(mut self, clip: bool) -> Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Fades the child, `opacity` is clamped to `0.0..=1.0`.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Returns true if `clip` is set and the local `position` lies outside of `size`.
+    pub fn clips(&self, local: [f32; 2]) -> bool {
+        self.clip
+            && !((0.0..=self.size[0]).contains(&local[0])
+                && (0.0..=self.size[1]).contains(&local[1]))
+    }
+
     /// Transforms a global `position` (window coordinates, origin top-left) into
     /// this child's local coordinates (origin = child's top-left).
     ///
@@ -395,6 +452,14 @@ mod tests {
         (a[0] - b[0]).abs() < EPS && (a[1] - b[1]).abs() < EPS
     }
 
+    #[test]
+    fn unbounded_maxima_have_no_finite_value() {
+        let constraints = Constraints::new([0.0, 120.0], [0.0, f32::INFINITY]);
+        assert_eq!(constraints.is_unbounded(), [false, true]);
+        assert_eq!(constraints.max_finite(), [Some(120.0), None]);
+        assert_eq!(Constraints::unbounded().is_unbounded(), [true, true]);
+    }
+
     #[test]
     fn arrangement_identity_roundtrip_contains() {
         // identity affine, no translation
@@ -469,4 +534,17 @@ mod tests {
         // contains is false
         assert!(!arr.contains([1.0, 1.0]));
     }
+
+    #[test]
+    fn arrangement_clip_and_opacity() {
+        let arr = Arrangement::new([10.0, 10.0], Matrix4::identity());
+        assert!(!arr.clips([20.0, 5.0]));
+
+        let arr = arr.with_clip(true).with_opacity(1.5);
+        assert!(arr.clips([20.0, 5.0]));
+        assert!(arr.clips([5.0, -1.0]));
+        assert!(!arr.clips([10.0, 0.0]));
+        assert_eq!(arr.opacity, 1.0);
+        assert_eq!(arr.with_opacity(-1.0).opacity, 0.0);
+    }
 }
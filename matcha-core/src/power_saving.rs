@@ -0,0 +1,148 @@
+//! Lower frame rate while the app is in the background.
+//!
+//! The app is in the background when none of its windows is in use: each one is unfocused,
+//! minimized, covered or hidden. The rendering loop then renders at most
+//! [`PowerSaving::background_fps`] frames per second, and with
+//! [`PowerSaving::pause_animations`] the [`FrameClock`](crate::frame_clock::FrameClock) stands
+//! still until a window is back in use. Covered and minimized windows are not rendered at all.
+//!
+//! Components are told about both transitions with
+//! [`LifecycleEvent::Background`](crate::lifecycle::LifecycleEvent::Background) and
+//! [`LifecycleEvent::Foreground`](crate::lifecycle::LifecycleEvent::Foreground), e.g. to stop
+//! polling a server in the background and to refresh stale data when the user comes back.
+//!
+//! Configure it with [`App::power_saving`](crate::app::App::power_saving).
+
+use std::time::{Duration, Instant};
+
+use log::debug;
+use parking_lot::Mutex;
+
+/// How the app saves power while it is in the background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerSaving {
+    /// Frame rate limit in the background. `None` renders as often as in the foreground.
+    pub background_fps: Option<f32>,
+    /// Stops the animation clock in the background, so animations continue where they were
+    /// when a window is back in use.
+    pub pause_animations: bool,
+}
+
+impl Default for PowerSaving {
+    fn default() -> Self {
+        Self {
+            background_fps: Some(10.0),
+            pause_animations: false,
+        }
+    }
+}
+
+impl PowerSaving {
+    /// Renders in the background like in the foreground. Background and foreground events
+    /// are still sent.
+    pub fn disabled() -> Self {
+        Self {
+            background_fps: None,
+            pause_animations: false,
+        }
+    }
+
+    pub fn background_fps(mut self, fps: Option<f32>) -> Self {
+        self.background_fps = fps;
+        self
+    }
+
+    pub fn pause_animations(mut self, pause: bool) -> Self {
+        self.pause_animations = pause;
+        self
+    }
+
+    /// The time between two frames in the background, if limited. Limits too low for a
+    /// `Duration` wait for [`Duration::MAX`].
+    pub(crate) fn frame_interval(&self) -> Option<Duration> {
+        self.background_fps
+            .filter(|fps| fps.is_finite() && *fps > 0.0)
+            .map(|fps| Duration::try_from_secs_f64(1.0 / f64::from(fps)).unwrap_or(Duration::MAX))
+    }
+}
+
+/// Tracks whether the app is in the background.
+pub(crate) struct Background {
+    since: Mutex<Option<Instant>>,
+}
+
+impl Background {
+    pub fn new() -> Self {
+        Self {
+            since: Mutex::new(None),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.since.lock().is_some()
+    }
+
+    /// Moves the app to the background. Returns `false` if it already was.
+    pub fn enter(&self, now: Instant) -> bool {
+        let mut since = self.since.lock();
+        if since.is_some() {
+            return false;
+        }
+        *since = Some(now);
+        debug!("Background::enter: application moved to the background");
+        true
+    }
+
+    /// Brings the app back to the foreground. Returns how long it was in the background, or
+    /// `None` if it was not.
+    pub fn leave(&self, now: Instant) -> Option<Duration> {
+        let since = self.since.lock().take()?;
+        let duration = now.saturating_duration_since(since);
+        debug!("Background::leave: application back in the foreground after {duration:?}");
+        Some(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_interval_follows_the_fps_limit() {
+        assert_eq!(
+            PowerSaving::default().frame_interval(),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(PowerSaving::disabled().frame_interval(), None);
+        // nonsensical limits do not stall the loop
+        assert_eq!(
+            PowerSaving::default()
+                .background_fps(Some(0.0))
+                .frame_interval(),
+            None
+        );
+        assert_eq!(
+            PowerSaving::default()
+                .background_fps(Some(1e-20))
+                .frame_interval(),
+            Some(Duration::MAX)
+        );
+    }
+
+    #[test]
+    fn background_is_entered_and_left_once() {
+        let start = Instant::now();
+        let background = Background::new();
+        assert_eq!(background.leave(start), None);
+
+        assert!(background.enter(start));
+        assert!(!background.enter(start + Duration::from_secs(1)));
+        assert!(background.is_active());
+
+        assert_eq!(
+            background.leave(start + Duration::from_secs(3)),
+            Some(Duration::from_secs(3))
+        );
+        assert!(!background.is_active());
+    }
+}
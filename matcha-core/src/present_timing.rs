@@ -0,0 +1,153 @@
+//! Presentation timing of a window's frames.
+//!
+//! Every window measures when its frames are handed to the compositor and reports it as
+//! [`PresentStats`] through
+//! [`WidgetContext::present_stats`](crate::context::WidgetContext::present_stats) and
+//! [`ApplicationContext::present_stats`](crate::context::ApplicationContext::present_stats).
+//! Latency-sensitive apps, e.g. audio tools with meters, use it together with
+//! [`App::max_frame_latency`](crate::app::App::max_frame_latency) to tune the pipeline.
+//! The stats also carry the [`RenderStats`] of the last frame, such as its draw calls, for
+//! performance overlays.
+//!
+//! wgpu does not report when the display actually shows a frame, so the present time is when
+//! presenting returned on the CPU. With vsync that follows the refresh of the display
+//! closely once frames render back to back, since acquiring the next surface texture waits
+//! for a free swap chain image. Missed vsyncs are derived from the refresh rate of the monitor
+//! the window is on; they are not counted where the platform does not report it.
+
+use std::time::{Duration, Instant};
+
+use log::trace;
+use parking_lot::Mutex;
+use renderer::RenderStats;
+
+/// Frames queued on the surface by default: the lowest latency.
+pub const DEFAULT_MAX_FRAME_LATENCY: u32 = 1;
+
+/// Presentation statistics of a window.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PresentStats {
+    /// Frames presented since the window was created.
+    pub presented_frames: u64,
+    /// When the last frame was presented.
+    pub last_present: Option<Instant>,
+    /// Time between the last two presents, if the last frame was rendered right after the
+    /// one before.
+    pub frame_interval: Option<Duration>,
+    /// Refresh interval of the monitor the window is on, if the platform reports it.
+    pub refresh_interval: Option<Duration>,
+    /// Vsyncs that passed without a new frame while frames were rendered back to back.
+    pub missed_vsyncs: u64,
+    /// What the renderer drew for the last frame of the window.
+    pub render_stats: RenderStats,
+}
+
+/// Collects the [`PresentStats`] of one window. Shared with the frames in flight, which may
+/// be presented off the render loop.
+#[derive(Debug, Default)]
+pub(crate) struct PresentTiming {
+    stats: Mutex<PresentStats>,
+}
+
+impl PresentTiming {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stats(&self) -> PresentStats {
+        *self.stats.lock()
+    }
+
+    pub fn set_refresh_interval(&self, refresh_interval: Option<Duration>) {
+        self.stats.lock().refresh_interval = refresh_interval;
+    }
+
+    pub fn rendered(&self, render_stats: RenderStats) {
+        self.stats.lock().render_stats = render_stats;
+    }
+
+    /// Records a frame whose surface texture was acquired at `acquired_at` and that was
+    /// presented at `presented_at`.
+    pub fn presented(&self, acquired_at: Instant, presented_at: Instant) {
+        let mut stats = self.stats.lock();
+        // a frame acquired within one refresh of the last present followed it right away;
+        // after a longer pause the window was idle, which is no missed vsync
+        let interval = stats
+            .last_present
+            .filter(|&last| match stats.refresh_interval {
+                Some(refresh) => acquired_at.saturating_duration_since(last) <= refresh,
+                None => true,
+            })
+            .map(|last| presented_at.saturating_duration_since(last));
+
+        if let (Some(interval), Some(refresh)) = (interval, stats.refresh_interval)
+            && !refresh.is_zero()
+        {
+            let vsyncs = (interval.as_secs_f64() / refresh.as_secs_f64()).round() as u64;
+            let missed = vsyncs.saturating_sub(1);
+            if missed > 0 {
+                trace!("PresentTiming::presented: missed {missed} vsyncs in {interval:?}");
+            }
+            stats.missed_vsyncs += missed;
+        }
+
+        stats.presented_frames += 1;
+        stats.last_present = Some(presented_at);
+        stats.frame_interval = interval;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REFRESH: Duration = Duration::from_micros(16_667);
+
+    #[test]
+    fn missed_vsyncs_are_counted_between_back_to_back_frames() {
+        let timing = PresentTiming::new();
+        timing.set_refresh_interval(Some(REFRESH));
+        let start = Instant::now();
+
+        timing.presented(start, start + REFRESH);
+        // on time
+        timing.presented(start + REFRESH, start + REFRESH * 2);
+        // took three refreshes
+        timing.presented(start + REFRESH * 2, start + REFRESH * 5);
+
+        let stats = timing.stats();
+        assert_eq!(stats.presented_frames, 3);
+        assert_eq!(stats.missed_vsyncs, 2);
+        assert_eq!(stats.frame_interval, Some(REFRESH * 3));
+        assert_eq!(stats.last_present, Some(start + REFRESH * 5));
+    }
+
+    #[test]
+    fn an_idle_window_misses_no_vsyncs() {
+        let timing = PresentTiming::new();
+        timing.set_refresh_interval(Some(REFRESH));
+        let start = Instant::now();
+
+        timing.presented(start, start + REFRESH);
+        // the next frame was requested a second later
+        let later = start + Duration::from_secs(1);
+        timing.presented(later, later + REFRESH);
+
+        let stats = timing.stats();
+        assert_eq!(stats.missed_vsyncs, 0);
+        assert_eq!(stats.frame_interval, None);
+    }
+
+    #[test]
+    fn without_a_refresh_rate_only_intervals_are_reported() {
+        let timing = PresentTiming::new();
+        let start = Instant::now();
+
+        timing.presented(start, start + REFRESH);
+        timing.presented(start + REFRESH, start + REFRESH * 4);
+
+        let stats = timing.stats();
+        assert_eq!(stats.missed_vsyncs, 0);
+        assert_eq!(stats.frame_interval, Some(REFRESH * 3));
+    }
+}
@@ -0,0 +1,124 @@
+//! Frame profiling with `tracing` spans.
+//!
+//! With the `profiling` feature, every frame is recorded as a `frame` span containing the
+//! view, `update_widget_tree`, `measure`, `arrange`, `prepare` and `render` phases of each
+//! window, down to the individual widgets and the render passes of the renderer. Any
+//! `tracing` subscriber can consume them; [`init_chrome_trace`] writes them to a file that
+//! `chrome://tracing` or Perfetto can open.
+//!
+//! ```ignore
+//! let _trace = matcha_core::profiling::init_chrome_trace("trace.json")?;
+//! App::new(root).run()?;
+//! // the file is complete once `_trace` is dropped
+//! ```
+//!
+//! Without the feature the spans compile to nothing.
+
+#[cfg(feature = "profiling")]
+use std::path::Path;
+
+#[cfg(feature = "profiling")]
+use parking_lot::Mutex;
+#[cfg(feature = "profiling")]
+use thiserror::Error;
+
+/// Enters a `tracing` span until the end of the scope when the `profiling` feature is enabled.
+///
+/// Must not be held across an `.await`; use [`profile_future!`] for async work.
+#[cfg(feature = "profiling")]
+macro_rules! profile_span {
+    ($name:literal $(, $($fields:tt)*)?) => {
+        tracing::trace_span!($name $(, $($fields)*)?).entered()
+    };
+}
+
+#[cfg(not(feature = "profiling"))]
+macro_rules! profile_span {
+    ($name:literal $(, $($fields:tt)*)?) => {
+        $crate::profiling::NoSpan
+    };
+}
+
+/// Runs `$future` inside a `tracing` span when the `profiling` feature is enabled.
+#[cfg(feature = "profiling")]
+macro_rules! profile_future {
+    ($future:expr, $name:literal $(, $($fields:tt)*)?) => {{
+        let span = tracing::trace_span!($name $(, $($fields)*)?);
+        tracing::Instrument::instrument($future, span)
+    }};
+}
+
+#[cfg(not(feature = "profiling"))]
+macro_rules! profile_future {
+    ($future:expr, $name:literal $(, $($fields:tt)*)?) => {
+        $future
+    };
+}
+
+pub(crate) use profile_future;
+pub(crate) use profile_span;
+
+#[cfg(not(feature = "profiling"))]
+pub(crate) struct NoSpan;
+
+#[cfg(feature = "profiling")]
+#[derive(Error, Debug)]
+pub enum ProfilingError {
+    #[error("a global tracing subscriber is already installed")]
+    SubscriberAlreadySet,
+}
+
+// flushed after every frame, dropped by `ChromeTraceGuard`
+#[cfg(feature = "profiling")]
+static CHROME_FLUSH_GUARD: Mutex<Option<tracing_chrome::FlushGuard>> =
+    parking_lot::const_mutex(None);
+
+/// Records all spans into `path` in the Chrome trace event format.
+///
+/// Installs a global `tracing` subscriber, so it fails if one is already set. The recorded
+/// events are written out after each frame; the file becomes valid JSON once the returned
+/// guard is dropped.
+#[cfg(feature = "profiling")]
+pub fn init_chrome_trace(path: impl AsRef<Path>) -> Result<ChromeTraceGuard, ProfilingError> {
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+        .file(path.as_ref())
+        .include_args(true)
+        .build();
+    tracing_subscriber::registry()
+        .with(layer)
+        .try_init()
+        .map_err(|_| ProfilingError::SubscriberAlreadySet)?;
+
+    log::debug!(
+        "profiling::init_chrome_trace: writing trace to {}",
+        path.as_ref().display()
+    );
+    *CHROME_FLUSH_GUARD.lock() = Some(guard);
+    Ok(ChromeTraceGuard { _private: () })
+}
+
+/// Finishes the trace file of [`init_chrome_trace`] when dropped.
+#[cfg(feature = "profiling")]
+#[must_use = "the trace file is finished when the guard is dropped"]
+pub struct ChromeTraceGuard {
+    _private: (),
+}
+
+#[cfg(feature = "profiling")]
+impl Drop for ChromeTraceGuard {
+    fn drop(&mut self) {
+        // dropping the flush guard writes the remaining events and closes the file
+        CHROME_FLUSH_GUARD.lock().take();
+    }
+}
+
+/// Called by the rendering loop after each frame.
+pub(crate) fn frame_finished() {
+    #[cfg(feature = "profiling")]
+    if let Some(guard) = CHROME_FLUSH_GUARD.lock().as_ref() {
+        guard.flush();
+    }
+}
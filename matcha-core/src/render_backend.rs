@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use renderer::{CoreRenderer, SoftwareRenderer};
+
+pub use gpu_utils::gpu::{AdapterDescription, AdapterSelection};
+
+/// Selects how frames are rendered and presented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderBackend {
+    /// Use a hardware GPU adapter.
+    #[default]
+    Hardware,
+    /// Composite the render tree on the CPU with tiny-skia and present it with softbuffer.
+    ///
+    /// Intended for CI and remote shells without a GPU. Only the compositing of the render
+    /// tree moves to the CPU: widgets still draw their content into the texture atlas with wgpu,
+    /// so **a wgpu adapter with push constants is still required**. Software ones such as
+    /// llvmpipe qualify; without any, [`App::run`](crate::app::App::run) fails with an error
+    /// saying so.
+    /// No wgpu surface is created. Expect much lower frame rates.
+    Software,
+    /// Use a hardware GPU adapter of these wgpu backends only, e.g. `wgpu::Backends::VULKAN`.
+    Only(wgpu::Backends),
+}
+
+impl RenderBackend {
+    /// wgpu backends to probe for an adapter.
+    pub(crate) fn wgpu_backends(self) -> wgpu::Backends {
+        match self {
+            RenderBackend::Hardware => wgpu::Backends::PRIMARY,
+            // any adapter will do for widget content, including llvmpipe behind GL
+            RenderBackend::Software => wgpu::Backends::all(),
+            RenderBackend::Only(backends) => backends,
+        }
+    }
+
+    /// Features the adapter must offer.
+    pub(crate) fn required_features(self) -> wgpu::Features {
+        match self {
+            // the CPU compositor does not run the instance shaders
+            RenderBackend::Software => wgpu::Features::PUSH_CONSTANTS,
+            RenderBackend::Hardware | RenderBackend::Only(_) => {
+                wgpu::Features::VERTEX_WRITABLE_STORAGE | wgpu::Features::PUSH_CONSTANTS
+            }
+        }
+    }
+
+    pub(crate) fn is_software(self) -> bool {
+        self == RenderBackend::Software
+    }
+}
+
+/// Composites render trees into frames, on the GPU or on the CPU.
+#[derive(Clone)]
+pub(crate) enum Compositor {
+    Gpu(Arc<CoreRenderer>),
+    Software(Arc<SoftwareRenderer>),
+}
+
+impl Compositor {
+    pub(crate) fn last_frame_stats(&self) -> renderer::RenderStats {
+        match self {
+            Compositor::Gpu(renderer) => renderer.last_frame_stats(),
+            Compositor::Software(renderer) => renderer.last_frame_stats(),
+        }
+    }
+}
+
+/// Lists the adapters the app can run on with `render_backend`, e.g. for a GPU picker in
+/// settings. Pass the chosen one to `App::adapter` as [`AdapterSelection::Index`].
+pub fn enumerate_adapters(render_backend: RenderBackend) -> Vec<AdapterDescription> {
+    gpu_utils::gpu::Gpu::enumerate_adapters(render_backend.wgpu_backends())
+}
@@ -0,0 +1,161 @@
+//! How a window follows continuous resizing.
+//!
+//! Laying out the whole widget tree at every intermediate size makes dragging a window edge
+//! stutter on heavy UIs. The deferring strategies draw the last laid-out frame scaled to the
+//! new size instead and lay out again once the size settles or at a capped rate. Set one
+//! with [`App::resize_strategy`](crate::app::App::resize_strategy).
+
+use std::time::{Duration, Instant};
+
+/// When a resized window lays out its widgets again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeStrategy {
+    /// Lay out at every new size.
+    #[default]
+    Immediate,
+    /// Scale the last frame while resizing and lay out once the size has not changed for
+    /// this long.
+    Debounce(Duration),
+    /// Scale the last frame while resizing and lay out at most once per this interval.
+    Throttle(Duration),
+}
+
+/// What a window does with its current size in a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ResizeAction {
+    /// Lay out the widgets at the current size.
+    Relayout,
+    /// Draw the frame laid out at `from` scaled to the current size, and render again
+    /// after `retry_in` to lay out.
+    Scale { from: [f32; 2], retry_in: Duration },
+}
+
+/// Layout timing of one window under a [`ResizeStrategy`].
+#[derive(Debug, Default)]
+pub(crate) struct ResizeState {
+    strategy: ResizeStrategy,
+    // size of the last layout
+    laid_out: Option<[f32; 2]>,
+    last_resize: Option<Instant>,
+    last_layout: Option<Instant>,
+    // a resize has not been drawn yet
+    pending: bool,
+    // when a scaled frame is due to be laid out
+    relayout_at: Option<Instant>,
+}
+
+impl ResizeState {
+    pub fn new(strategy: ResizeStrategy) -> Self {
+        Self {
+            strategy,
+            ..Default::default()
+        }
+    }
+
+    pub fn resized(&mut self, now: Instant) {
+        self.last_resize = Some(now);
+        self.pending = true;
+    }
+
+    /// Whether a resize is waiting to be drawn or laid out.
+    pub fn needs_render(&self, now: Instant) -> bool {
+        self.pending || self.relayout_at.is_some_and(|at| at <= now)
+    }
+
+    /// Whether to lay out at `size` now.
+    pub fn action(&self, size: [f32; 2], now: Instant) -> ResizeAction {
+        let Some(from) = self.laid_out else {
+            return ResizeAction::Relayout;
+        };
+        // a frame laid out at zero size has nothing to scale
+        if from == size || from.contains(&0.0) {
+            return ResizeAction::Relayout;
+        }
+
+        let (since, wait) = match self.strategy {
+            ResizeStrategy::Immediate => return ResizeAction::Relayout,
+            ResizeStrategy::Debounce(delay) => (self.last_resize, delay),
+            ResizeStrategy::Throttle(interval) => (self.last_layout, interval),
+        };
+        let elapsed = since.map_or(Duration::MAX, |since| now.saturating_duration_since(since));
+        match wait.checked_sub(elapsed) {
+            Some(retry_in) if !retry_in.is_zero() => ResizeAction::Scale { from, retry_in },
+            _ => ResizeAction::Relayout,
+        }
+    }
+
+    pub fn laid_out(&mut self, size: [f32; 2], now: Instant) {
+        self.laid_out = Some(size);
+        self.last_layout = Some(now);
+        self.pending = false;
+        self.relayout_at = None;
+    }
+
+    pub fn scaled(&mut self, relayout_at: Instant) {
+        self.pending = false;
+        self.relayout_at = Some(relayout_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debounce_waits_for_the_size_to_settle() {
+        let start = Instant::now();
+        let mut state = ResizeState::new(ResizeStrategy::Debounce(Duration::from_millis(100)));
+        assert_eq!(state.action([100.0, 100.0], start), ResizeAction::Relayout);
+        state.laid_out([100.0, 100.0], start);
+
+        state.resized(start + Duration::from_millis(10));
+        assert_eq!(
+            state.action([120.0, 100.0], start + Duration::from_millis(50)),
+            ResizeAction::Scale {
+                from: [100.0, 100.0],
+                retry_in: Duration::from_millis(60),
+            }
+        );
+        assert!(state.needs_render(start + Duration::from_millis(50)));
+        state.scaled(start + Duration::from_millis(110));
+        assert!(!state.needs_render(start + Duration::from_millis(60)));
+        assert!(state.needs_render(start + Duration::from_millis(110)));
+
+        // every resize restarts the delay
+        state.resized(start + Duration::from_millis(90));
+        assert!(matches!(
+            state.action([140.0, 100.0], start + Duration::from_millis(150)),
+            ResizeAction::Scale { .. }
+        ));
+        assert_eq!(
+            state.action([140.0, 100.0], start + Duration::from_millis(190)),
+            ResizeAction::Relayout
+        );
+    }
+
+    #[test]
+    fn throttle_caps_the_layout_rate() {
+        let start = Instant::now();
+        let mut state = ResizeState::new(ResizeStrategy::Throttle(Duration::from_millis(50)));
+        state.laid_out([100.0, 100.0], start);
+
+        state.resized(start + Duration::from_millis(10));
+        assert!(matches!(
+            state.action([120.0, 100.0], start + Duration::from_millis(20)),
+            ResizeAction::Scale { .. }
+        ));
+        // continuous resizing still lays out once per interval
+        state.resized(start + Duration::from_millis(55));
+        assert_eq!(
+            state.action([130.0, 100.0], start + Duration::from_millis(60)),
+            ResizeAction::Relayout
+        );
+
+        let mut immediate = ResizeState::new(ResizeStrategy::Immediate);
+        immediate.laid_out([100.0, 100.0], start);
+        assert_eq!(
+            immediate.action([120.0, 100.0], start),
+            ResizeAction::Relayout
+        );
+    }
+}
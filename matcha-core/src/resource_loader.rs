@@ -0,0 +1,552 @@
+//! Shared loading of files and URLs in the background.
+//!
+//! Widgets that show remote or large resources, e.g. images of a gallery, request them from
+//! the application's [`ResourceLoader`] instead of reading them on their own. The loader runs
+//! a limited number of loads at a time and starts the pending request with the highest
+//! [`Priority`] first, so what is on screen loads before what is scrolled away:
+//!
+//! ```ignore
+//! fn prepare(&mut self, _bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+//!     // prepare only runs for visible widgets
+//!     let load = self.load.get_or_insert_with(|| {
+//!         ctx.load_resource(self.url.as_str(), Priority::Visible, self.redraw.clone())
+//!     });
+//!     load.set_priority(Priority::Visible);
+//!     None
+//! }
+//!
+//! fn render(&self, ...) -> RenderNode {
+//!     match self.load.as_ref().and_then(|load| load.result()) {
+//!         Some(Ok(bytes)) => /* decode and draw */,
+//!         Some(Err(_)) | None => /* placeholder */,
+//!     }
+//! }
+//! ```
+//!
+//! When a load completes, the widget's [`AsyncInvalidationHandle`] asks for a redraw. Dropping
+//! the [`LoadHandle`], e.g. with the widget, cancels the load: a pending request is removed
+//! from the queue and a running one is aborted.
+//!
+//! Files are read on a blocking thread. URLs are fetched by the [`Fetcher`] the app registers
+//! with [`ResourceLoader::set_fetcher`], so the app chooses its HTTP client; without one, URL
+//! loads fail with [`LoadError::NoFetcher`].
+
+use std::{
+    cmp::Reverse,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, AtomicU8, Ordering},
+    },
+};
+
+use log::{debug, trace};
+use parking_lot::{Mutex, RwLock};
+use thiserror::Error;
+
+use crate::ui::AsyncInvalidationHandle;
+
+/// Number of loads that run at the same time by default.
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+type FetchFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, LoadError>> + Send>>;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    #[error("failed to read {path}: {message}")]
+    Io { path: PathBuf, message: String },
+    #[error("no fetcher is registered to load {url}")]
+    NoFetcher { url: String },
+    #[error("failed to fetch {url}: {message}")]
+    Fetch { url: String, message: String },
+    #[error("the load was cancelled")]
+    Cancelled,
+    #[error("the resource loader has shut down")]
+    ShutDown,
+}
+
+/// Where a resource is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ResourceSource {
+    File(PathBuf),
+    Url(String),
+}
+
+impl From<&str> for ResourceSource {
+    /// `http://` and `https://` are URLs, `file://` and everything else are paths.
+    fn from(source: &str) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            ResourceSource::Url(source.to_string())
+        } else {
+            ResourceSource::File(PathBuf::from(
+                source.strip_prefix("file://").unwrap_or(source),
+            ))
+        }
+    }
+}
+
+impl From<String> for ResourceSource {
+    fn from(source: String) -> Self {
+        ResourceSource::from(source.as_str())
+    }
+}
+
+impl From<PathBuf> for ResourceSource {
+    fn from(path: PathBuf) -> Self {
+        ResourceSource::File(path)
+    }
+}
+
+/// Order in which pending loads start. Requests of the same priority start in the order they
+/// were made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Priority {
+    /// Prefetching, e.g. the next page of a list.
+    Background,
+    #[default]
+    Normal,
+    /// Shown on screen right now.
+    Visible,
+}
+
+impl Priority {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Priority::Background,
+            1 => Priority::Normal,
+            _ => Priority::Visible,
+        }
+    }
+}
+
+/// Fetches the bytes behind a URL, e.g. with the app's HTTP client.
+///
+/// Implemented for closures `Fn(String) -> impl Future<Output = Result<Vec<u8>, LoadError>>`.
+pub trait Fetcher: Send + Sync + 'static {
+    fn fetch(&self, url: &str) -> FetchFuture;
+}
+
+impl<F, Fut> Fetcher for F
+where
+    F: Fn(String) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Vec<u8>, LoadError>> + Send + 'static,
+{
+    fn fetch(&self, url: &str) -> FetchFuture {
+        Box::pin(self(url.to_string()))
+    }
+}
+
+struct Request {
+    source: ResourceSource,
+    // requests of the same priority start in this order
+    sequence: u64,
+    priority: AtomicU8,
+    result: Mutex<Option<Result<Arc<Vec<u8>>, LoadError>>>,
+    finished: tokio::sync::Notify,
+    // set once the load runs
+    task: Mutex<Option<tokio::task::AbortHandle>>,
+    cancelled: AtomicBool,
+    on_complete: Option<AsyncInvalidationHandle>,
+    runtime: tokio::runtime::Handle,
+}
+
+impl Request {
+    fn priority(&self) -> Priority {
+        Priority::from_u8(self.priority.load(Ordering::Acquire))
+    }
+
+    fn complete(&self, result: Result<Arc<Vec<u8>>, LoadError>) {
+        *self.result.lock() = Some(result);
+        self.finished.notify_waiters();
+        if let Some(on_complete) = &self.on_complete {
+            on_complete.redraw_next_frame();
+        }
+    }
+}
+
+struct Queue {
+    pending: Vec<Arc<Request>>,
+    running: usize,
+    max_concurrent: usize,
+    next_sequence: u64,
+}
+
+/// Loads files and URLs for the whole application with a limited number of loads at a time.
+/// See the [module documentation](self).
+pub struct ResourceLoader {
+    queue: Mutex<Queue>,
+    fetcher: RwLock<Option<Arc<dyn Fetcher>>>,
+}
+
+impl Default for ResourceLoader {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENT)
+    }
+}
+
+impl ResourceLoader {
+    /// A loader that runs at most `max_concurrent` loads at a time (at least one).
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            queue: Mutex::new(Queue {
+                pending: Vec::new(),
+                running: 0,
+                max_concurrent: max_concurrent.max(1),
+                next_sequence: 0,
+            }),
+            fetcher: RwLock::new(None),
+        }
+    }
+
+    /// Fetches URLs with `fetcher` from now on.
+    pub fn set_fetcher(&self, fetcher: impl Fetcher) {
+        *self.fetcher.write() = Some(Arc::new(fetcher));
+    }
+
+    /// Changes how many loads run at a time. Running loads are not interrupted.
+    pub fn set_max_concurrent(self: &Arc<Self>, max_concurrent: usize) {
+        self.queue.lock().max_concurrent = max_concurrent.max(1);
+        self.pump();
+    }
+
+    /// Number of loads that wait for a free slot.
+    pub fn pending(&self) -> usize {
+        self.queue.lock().pending.len()
+    }
+
+    /// Number of loads running right now.
+    pub fn running(&self) -> usize {
+        self.queue.lock().running
+    }
+
+    /// Queues a load of `source` on `runtime`. `on_complete` is asked to redraw when the load
+    /// finishes.
+    pub(crate) fn load(
+        self: &Arc<Self>,
+        source: ResourceSource,
+        priority: Priority,
+        on_complete: Option<AsyncInvalidationHandle>,
+        runtime: &tokio::runtime::Handle,
+    ) -> LoadHandle {
+        let request = {
+            let mut queue = self.queue.lock();
+            let request = Arc::new(Request {
+                source,
+                sequence: queue.next_sequence,
+                priority: AtomicU8::new(priority as u8),
+                result: Mutex::new(None),
+                finished: tokio::sync::Notify::new(),
+                task: Mutex::new(None),
+                cancelled: AtomicBool::new(false),
+                on_complete,
+                runtime: runtime.clone(),
+            });
+            queue.next_sequence += 1;
+            queue.pending.push(request.clone());
+            request
+        };
+        trace!(
+            "ResourceLoader::load: queued {:?} at {priority:?}",
+            request.source
+        );
+        self.pump();
+
+        LoadHandle {
+            request,
+            loader: Arc::downgrade(self),
+        }
+    }
+
+    /// Starts pending loads, highest priority first, while slots are free.
+    fn pump(self: &Arc<Self>) {
+        loop {
+            let request = {
+                let mut queue = self.queue.lock();
+                if queue.running >= queue.max_concurrent {
+                    return;
+                }
+                let Some(index) = queue
+                    .pending
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, request)| (request.priority(), Reverse(request.sequence)))
+                    .map(|(index, _)| index)
+                else {
+                    return;
+                };
+                queue.running += 1;
+                queue.pending.remove(index)
+            };
+            self.start(request);
+        }
+    }
+
+    fn start(self: &Arc<Self>, request: Arc<Request>) {
+        trace!("ResourceLoader::start: loading {:?}", request.source);
+        let fetch = self.fetch(&request.source);
+        let slot = Slot(self.clone());
+        let task = request.runtime.spawn({
+            let request = request.clone();
+            async move {
+                // frees the slot when the load finishes or is aborted
+                let _slot = slot;
+                let result = fetch.await.map(Arc::new);
+                request.complete(result);
+            }
+        });
+
+        *request.task.lock() = Some(task.abort_handle());
+        // cancelled while the task was being spawned
+        if request.cancelled.load(Ordering::Acquire) {
+            task.abort();
+        }
+    }
+
+    fn fetch(&self, source: &ResourceSource) -> FetchFuture {
+        match source {
+            ResourceSource::File(path) => {
+                let path = path.clone();
+                Box::pin(async move {
+                    let read = {
+                        let path = path.clone();
+                        tokio::task::spawn_blocking(move || std::fs::read(path))
+                    };
+                    match read.await {
+                        Ok(Ok(bytes)) => Ok(bytes),
+                        Ok(Err(e)) => Err(LoadError::Io {
+                            path,
+                            message: e.to_string(),
+                        }),
+                        Err(e) => Err(LoadError::Io {
+                            path,
+                            message: e.to_string(),
+                        }),
+                    }
+                })
+            }
+            ResourceSource::Url(url) => match self.fetcher.read().clone() {
+                Some(fetcher) => fetcher.fetch(url),
+                None => {
+                    let url = url.clone();
+                    Box::pin(async move { Err(LoadError::NoFetcher { url }) })
+                }
+            },
+        }
+    }
+
+    fn cancel(self: &Arc<Self>, request: &Arc<Request>) {
+        request.cancelled.store(true, Ordering::Release);
+        let was_pending = {
+            let mut queue = self.queue.lock();
+            let before = queue.pending.len();
+            queue
+                .pending
+                .retain(|pending| !Arc::ptr_eq(pending, request));
+            queue.pending.len() != before
+        };
+        if was_pending {
+            debug!(
+                "ResourceLoader::cancel: dropped pending load of {:?}",
+                request.source
+            );
+        } else if let Some(task) = &*request.task.lock() {
+            task.abort();
+        }
+    }
+}
+
+/// A running load slot, released when the load task ends.
+struct Slot(Arc<ResourceLoader>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.queue.lock().running -= 1;
+        self.0.pump();
+    }
+}
+
+/// A requested load. Dropping the handle cancels the load.
+#[must_use = "the load is cancelled when its handle is dropped"]
+pub struct LoadHandle {
+    request: Arc<Request>,
+    loader: Weak<ResourceLoader>,
+}
+
+impl LoadHandle {
+    /// A handle to a load that failed before it was queued, e.g. without a loader.
+    pub(crate) fn failed(
+        source: ResourceSource,
+        error: LoadError,
+        runtime: &tokio::runtime::Handle,
+    ) -> Self {
+        let request = Arc::new(Request {
+            source,
+            sequence: 0,
+            priority: AtomicU8::new(Priority::Normal as u8),
+            result: Mutex::new(Some(Err(error))),
+            finished: tokio::sync::Notify::new(),
+            task: Mutex::new(None),
+            cancelled: AtomicBool::new(false),
+            on_complete: None,
+            runtime: runtime.clone(),
+        });
+        Self {
+            request,
+            loader: Weak::new(),
+        }
+    }
+
+    pub fn source(&self) -> &ResourceSource {
+        &self.request.source
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.request.priority()
+    }
+
+    /// Moves a pending load up or down the queue, e.g. when its widget scrolls into view.
+    pub fn set_priority(&self, priority: Priority) {
+        self.request
+            .priority
+            .store(priority as u8, Ordering::Release);
+    }
+
+    /// The loaded bytes or the error, once the load finished.
+    pub fn result(&self) -> Option<Result<Arc<Vec<u8>>, LoadError>> {
+        self.request.result.lock().clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.request.result.lock().is_some()
+    }
+
+    /// Waits until the load finished.
+    pub async fn wait(&self) -> Result<Arc<Vec<u8>>, LoadError> {
+        loop {
+            let finished = self.request.finished.notified();
+            if let Some(result) = self.result() {
+                return result;
+            }
+            finished.await;
+        }
+    }
+
+    /// Stops the load. A finished load keeps its result.
+    pub fn cancel(&self) {
+        if self.is_finished() {
+            return;
+        }
+        if let Some(loader) = self.loader.upgrade() {
+            loader.cancel(&self.request);
+        }
+        self.request.complete(Err(LoadError::Cancelled));
+    }
+}
+
+impl Drop for LoadHandle {
+    fn drop(&mut self) {
+        if !self.is_finished()
+            && let Some(loader) = self.loader.upgrade()
+        {
+            loader.cancel(&self.request);
+        }
+    }
+}
+
+impl std::fmt::Debug for LoadHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadHandle")
+            .field("source", &self.request.source)
+            .field("priority", &self.priority())
+            .field("finished", &self.is_finished())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn recording_loader(max_concurrent: usize) -> (Arc<ResourceLoader>, Arc<Mutex<Vec<String>>>) {
+        let loader = Arc::new(ResourceLoader::new(max_concurrent));
+        let fetched = Arc::new(Mutex::new(Vec::new()));
+        loader.set_fetcher({
+            let fetched = fetched.clone();
+            move |url: String| {
+                fetched.lock().push(url.clone());
+                async move { Ok(url.into_bytes()) }
+            }
+        });
+        (loader, fetched)
+    }
+
+    #[tokio::test]
+    async fn pending_loads_start_by_priority() {
+        let (loader, fetched) = recording_loader(1);
+        let runtime = tokio::runtime::Handle::current();
+        let load =
+            |url: &str, priority| loader.load(ResourceSource::from(url), priority, None, &runtime);
+
+        // the first load takes the only slot right away
+        let first = load("https://a", Priority::Normal);
+        let background = load("https://b", Priority::Background);
+        let visible = load("https://c", Priority::Visible);
+        let dropped = load("https://d", Priority::Visible);
+        let raised = load("https://e", Priority::Background);
+        raised.set_priority(Priority::Normal);
+        assert_eq!(loader.pending(), 4);
+
+        drop(dropped);
+        assert_eq!(loader.pending(), 3);
+
+        assert_eq!(*first.wait().await.unwrap(), b"https://a".to_vec());
+        background.wait().await.unwrap();
+        visible.wait().await.unwrap();
+        raised.wait().await.unwrap();
+        assert_eq!(
+            *fetched.lock(),
+            ["https://a", "https://c", "https://e", "https://b"]
+        );
+        assert_eq!(loader.running(), 0);
+    }
+
+    #[tokio::test]
+    async fn files_are_read_and_urls_need_a_fetcher() {
+        let path =
+            std::env::temp_dir().join(format!("matcha-resource-loader-{}.bin", std::process::id()));
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+
+        let loader = Arc::new(ResourceLoader::default());
+        let runtime = tokio::runtime::Handle::current();
+        let file = loader.load(path.clone().into(), Priority::Normal, None, &runtime);
+        let url = loader.load(
+            "https://example.com/a.png".into(),
+            Priority::Normal,
+            None,
+            &runtime,
+        );
+
+        assert_eq!(*file.wait().await.unwrap(), vec![1, 2, 3]);
+        assert!(matches!(url.wait().await, Err(LoadError::NoFetcher { .. })));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sources_are_parsed_from_strings() {
+        assert_eq!(
+            ResourceSource::from("https://example.com/a.png"),
+            ResourceSource::Url("https://example.com/a.png".to_string())
+        );
+        assert_eq!(
+            ResourceSource::from("file:///tmp/a.png"),
+            ResourceSource::File(PathBuf::from("/tmp/a.png"))
+        );
+        assert_eq!(
+            ResourceSource::from("assets/a.png"),
+            ResourceSource::File(PathBuf::from("assets/a.png"))
+        );
+    }
+}
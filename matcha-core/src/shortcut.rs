@@ -0,0 +1,443 @@
+//! Keyboard shortcuts.
+//!
+//! A [`ShortcutRegistry`] maps key combinations such as `Ctrl+S` to messages. Registries can
+//! be installed on the [`App`](crate::app::App), where matches are delivered to the root
+//! component's `update_fn`, or on a [`Component`](crate::ui::component::Component), where
+//! matches are passed to its `event_fn` as inner events. Either way a matching key press is
+//! consumed before it reaches the widgets.
+//!
+//! Entries can belong to a named context (e.g. `"editor"`) and are only active while that
+//! context is enabled, so the same key can mean different things depending on the UI state.
+//!
+//! Key presses match by their logical key or by their [shortcut
+//! key](crate::device_input::KeyInput::shortcut_key), so `Ctrl+Shift+1` matches although Shift
+//! turns the key into `!`, and `Ctrl+C` matches on a Cyrillic layout.
+
+use std::{collections::HashSet, fmt, str::FromStr, sync::Arc};
+
+use parking_lot::RwLock;
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::device_input::{ElementState, KeyInput};
+
+/// A key combined with an exact set of modifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    modifiers: ModifiersState,
+    key: Key,
+}
+
+impl Shortcut {
+    /// The platform's main shortcut modifier: `Super` (Command) on macOS, `Control` elsewhere.
+    pub const PRIMARY: ModifiersState = if cfg!(target_os = "macos") {
+        ModifiersState::SUPER
+    } else {
+        ModifiersState::CONTROL
+    };
+
+    pub fn new(modifiers: ModifiersState, key: Key) -> Self {
+        Self {
+            modifiers,
+            key: normalize_key(key),
+        }
+    }
+
+    /// `key` with the platform's primary modifier, e.g. `Ctrl+S` on Windows and `Cmd+S` on macOS.
+    pub fn primary(key: Key) -> Self {
+        Self::new(Self::PRIMARY, key)
+    }
+
+    pub fn modifiers(&self) -> ModifiersState {
+        self.modifiers
+    }
+
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// Returns `true` if `key` pressed with exactly `modifiers` triggers this shortcut.
+    ///
+    /// Characters are compared case-insensitively, so `Ctrl+Shift+Z` matches although the
+    /// logical key reported while Shift is held is `Z`.
+    pub fn matches_key(&self, modifiers: ModifiersState, key: &Key) -> bool {
+        self.modifiers == modifiers && self.key == normalize_key(key.clone())
+    }
+
+    /// Returns `true` if `input` is a press (or repeat) of this shortcut.
+    pub fn matches(&self, input: &KeyInput) -> bool {
+        matches!(input.state(), ElementState::Pressed(_))
+            && (self.matches_key(input.modifiers(), input.logical_key())
+                || self.matches_key(input.modifiers(), &input.shortcut_key()))
+    }
+}
+
+fn normalize_key(key: Key) -> Key {
+    match key {
+        Key::Character(c) => Key::Character(c.to_lowercase().into()),
+        key => key,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ShortcutParseError {
+    #[error("shortcut is empty")]
+    Empty,
+    #[error("unknown modifier `{0}`")]
+    UnknownModifier(String),
+    #[error("unknown key `{0}`")]
+    UnknownKey(String),
+}
+
+/// Parses `+` separated shortcuts such as `"Ctrl+S"`, `"Primary+Shift+Z"` or `"Alt+F4"`.
+///
+/// Modifiers are `Ctrl`/`Control`, `Shift`, `Alt`/`Option`, `Super`/`Cmd`/`Command`/`Meta`
+/// and `Primary`/`CmdOrCtrl` for [`Shortcut::PRIMARY`]. The last part is the key: a single
+/// character, `Plus`, or a named key like `Enter`, `Escape`, `Tab`, `Space`, `Delete`,
+/// `ArrowUp` or `F1`..`F12`. Names are case-insensitive.
+impl FromStr for Shortcut {
+    type Err = ShortcutParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        // "Ctrl++" names the plus key
+        if parts.len() >= 2
+            && parts[parts.len() - 1].is_empty()
+            && parts[parts.len() - 2].is_empty()
+        {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+
+        let Some((key, modifiers)) = parts.split_last() else {
+            return Err(ShortcutParseError::Empty);
+        };
+        if key.is_empty() {
+            return Err(ShortcutParseError::Empty);
+        }
+
+        let mut state = ModifiersState::empty();
+        for modifier in modifiers {
+            state |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" | "option" => ModifiersState::ALT,
+                "super" | "cmd" | "command" | "meta" | "win" => ModifiersState::SUPER,
+                "primary" | "cmdorctrl" => Shortcut::PRIMARY,
+                _ => return Err(ShortcutParseError::UnknownModifier(modifier.to_string())),
+            };
+        }
+
+        Ok(Shortcut::new(state, parse_key(key)?))
+    }
+}
+
+fn parse_key(name: &str) -> Result<Key, ShortcutParseError> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Character(c.to_string().into()));
+    }
+
+    let named = match name.to_ascii_lowercase().as_str() {
+        "plus" => return Ok(Key::Character("+".into())),
+        "enter" | "return" => NamedKey::Enter,
+        "escape" | "esc" => NamedKey::Escape,
+        "tab" => NamedKey::Tab,
+        "space" => NamedKey::Space,
+        "backspace" => NamedKey::Backspace,
+        "delete" | "del" => NamedKey::Delete,
+        "insert" | "ins" => NamedKey::Insert,
+        "home" => NamedKey::Home,
+        "end" => NamedKey::End,
+        "pageup" => NamedKey::PageUp,
+        "pagedown" => NamedKey::PageDown,
+        "arrowup" | "up" => NamedKey::ArrowUp,
+        "arrowdown" | "down" => NamedKey::ArrowDown,
+        "arrowleft" | "left" => NamedKey::ArrowLeft,
+        "arrowright" | "right" => NamedKey::ArrowRight,
+        "f1" => NamedKey::F1,
+        "f2" => NamedKey::F2,
+        "f3" => NamedKey::F3,
+        "f4" => NamedKey::F4,
+        "f5" => NamedKey::F5,
+        "f6" => NamedKey::F6,
+        "f7" => NamedKey::F7,
+        "f8" => NamedKey::F8,
+        "f9" => NamedKey::F9,
+        "f10" => NamedKey::F10,
+        "f11" => NamedKey::F11,
+        "f12" => NamedKey::F12,
+        _ => return Err(ShortcutParseError::UnknownKey(name.to_string())),
+    };
+    Ok(Key::Named(named))
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (ModifiersState::CONTROL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(flag) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match &self.key {
+            Key::Character(c) => write!(f, "{}", c.to_uppercase()),
+            Key::Named(named) => write!(f, "{named:?}"),
+            key => write!(f, "{key:?}"),
+        }
+    }
+}
+
+// MARK: registry
+
+type Action<T> = Arc<dyn Fn() -> T + Send + Sync>;
+
+struct Entry<T> {
+    shortcut: Shortcut,
+    context: Option<String>,
+    action: Action<T>,
+}
+
+struct Registry<T> {
+    entries: Vec<Entry<T>>,
+    active_contexts: HashSet<String>,
+}
+
+/// Shared table of shortcuts and the messages they produce.
+///
+/// Clones share the same table, so a handle kept in a model can register shortcuts or switch
+/// contexts while the app is running.
+pub struct ShortcutRegistry<T> {
+    inner: Arc<RwLock<Registry<T>>>,
+}
+
+impl<T> Clone for ShortcutRegistry<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Default for ShortcutRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ShortcutRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Registry {
+                entries: Vec::new(),
+                active_contexts: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Registers a shortcut that is always active.
+    pub fn register(
+        &self,
+        shortcut: Shortcut,
+        action: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &Self {
+        self.push(shortcut, None, Arc::new(action));
+        self
+    }
+
+    /// Registers a shortcut that is only active while `context` is enabled.
+    pub fn register_in_context(
+        &self,
+        context: &str,
+        shortcut: Shortcut,
+        action: impl Fn() -> T + Send + Sync + 'static,
+    ) -> &Self {
+        self.push(shortcut, Some(context.to_string()), Arc::new(action));
+        self
+    }
+
+    fn push(&self, shortcut: Shortcut, context: Option<String>, action: Action<T>) {
+        self.inner.write().entries.push(Entry {
+            shortcut,
+            context,
+            action,
+        });
+    }
+
+    /// Removes every entry for `shortcut`, in all contexts.
+    pub fn unregister(&self, shortcut: &Shortcut) {
+        self.inner
+            .write()
+            .entries
+            .retain(|entry| &entry.shortcut != shortcut);
+    }
+
+    pub fn enable_context(&self, context: &str) {
+        self.inner
+            .write()
+            .active_contexts
+            .insert(context.to_string());
+    }
+
+    pub fn disable_context(&self, context: &str) {
+        self.inner.write().active_contexts.remove(context);
+    }
+
+    pub fn is_context_enabled(&self, context: &str) -> bool {
+        self.inner.read().active_contexts.contains(context)
+    }
+
+    /// Returns the message for `key` pressed with `modifiers`, if an active entry matches.
+    ///
+    /// When several active entries match, the most recently registered one wins.
+    pub fn dispatch_key(&self, modifiers: ModifiersState, key: &Key) -> Option<T> {
+        let action = {
+            let registry = self.inner.read();
+            registry
+                .entries
+                .iter()
+                .rev()
+                .find(|entry| {
+                    entry
+                        .context
+                        .as_ref()
+                        .is_none_or(|context| registry.active_contexts.contains(context))
+                        && entry.shortcut.matches_key(modifiers, key)
+                })
+                .map(|entry| Arc::clone(&entry.action))?
+        };
+        // the action runs without the lock so it may use the registry itself
+        Some(action())
+    }
+
+    /// Returns the message for a key press event. Releases never match.
+    pub fn dispatch(&self, input: &KeyInput) -> Option<T> {
+        if !matches!(input.state(), ElementState::Pressed(_)) {
+            return None;
+        }
+        self.dispatch_key(input.modifiers(), input.logical_key())
+            .or_else(|| {
+                let shortcut_key = input.shortcut_key();
+                (shortcut_key != *input.logical_key())
+                    .then(|| self.dispatch_key(input.modifiers(), &shortcut_key))
+                    .flatten()
+            })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_shortcuts() {
+        let save: Shortcut = "Ctrl+S".parse().unwrap();
+        assert_eq!(save.modifiers(), ModifiersState::CONTROL);
+        assert_eq!(save.key(), &Key::Character("s".into()));
+
+        let redo: Shortcut = "primary + shift + z".parse().unwrap();
+        assert_eq!(redo.modifiers(), Shortcut::PRIMARY | ModifiersState::SHIFT);
+
+        assert_eq!(
+            "Alt+F4".parse::<Shortcut>().unwrap().key(),
+            &Key::Named(NamedKey::F4)
+        );
+        assert_eq!(
+            "Ctrl++".parse::<Shortcut>().unwrap().key(),
+            &Key::Character("+".into())
+        );
+
+        assert_eq!("".parse::<Shortcut>(), Err(ShortcutParseError::Empty));
+        assert_eq!(
+            "Hyper+A".parse::<Shortcut>(),
+            Err(ShortcutParseError::UnknownModifier("Hyper".to_string()))
+        );
+        assert_eq!(
+            "Ctrl+Foo".parse::<Shortcut>(),
+            Err(ShortcutParseError::UnknownKey("Foo".to_string()))
+        );
+    }
+
+    #[test]
+    fn matching_requires_exact_modifiers() {
+        let shortcut: Shortcut = "Ctrl+Shift+Z".parse().unwrap();
+        let ctrl_shift = ModifiersState::CONTROL | ModifiersState::SHIFT;
+
+        assert!(shortcut.matches_key(ctrl_shift, &Key::Character("Z".into())));
+        assert!(!shortcut.matches_key(ModifiersState::CONTROL, &Key::Character("z".into())));
+        assert!(!shortcut.matches_key(
+            ctrl_shift | ModifiersState::ALT,
+            &Key::Character("z".into())
+        ));
+    }
+
+    #[test]
+    fn registry_respects_contexts_and_registration_order() {
+        let registry = ShortcutRegistry::new();
+        let ctrl_s: Shortcut = "Ctrl+S".parse().unwrap();
+        registry.register(ctrl_s.clone(), || "save");
+        registry.register_in_context("editor", ctrl_s.clone(), || "save file");
+
+        let s = Key::Character("s".into());
+        assert_eq!(
+            registry.dispatch_key(ModifiersState::CONTROL, &s),
+            Some("save")
+        );
+
+        registry.enable_context("editor");
+        assert_eq!(
+            registry.dispatch_key(ModifiersState::CONTROL, &s),
+            Some("save file")
+        );
+
+        registry.disable_context("editor");
+        assert_eq!(
+            registry.dispatch_key(ModifiersState::CONTROL, &s),
+            Some("save")
+        );
+        assert_eq!(registry.dispatch_key(ModifiersState::empty(), &s), None);
+
+        registry.unregister(&ctrl_s);
+        assert_eq!(registry.dispatch_key(ModifiersState::CONTROL, &s), None);
+    }
+
+    #[test]
+    fn key_presses_match_across_layouts() {
+        use crate::device_input::{DeviceInputData, KeyCode, KeyboardState, PhysicalKey};
+
+        let press = |modifiers, key_code, logical_key: Key| {
+            let mut state = KeyboardState::new();
+            state.modifiers_changed(modifiers);
+            let DeviceInputData::Keyboard(input) = state.synthetic_input(
+                PhysicalKey::Code(key_code),
+                logical_key,
+                None,
+                winit::event::ElementState::Pressed,
+            ) else {
+                panic!("expected a keyboard event");
+            };
+            input
+        };
+
+        let copy: Shortcut = "Ctrl+C".parse().unwrap();
+        let cyrillic = press(
+            ModifiersState::CONTROL,
+            KeyCode::KeyC,
+            Key::Character("с".into()),
+        );
+        assert!(copy.matches(&cyrillic));
+        assert!(!copy.matches(&press(
+            ModifiersState::CONTROL,
+            KeyCode::KeyV,
+            Key::Character("м".into())
+        )));
+
+        let registry = ShortcutRegistry::new();
+        registry.register(copy, || "copy");
+        assert_eq!(registry.dispatch(&cyrillic), Some("copy"));
+    }
+}
@@ -0,0 +1,414 @@
+//! Unit testing widgets.
+//!
+//! A [`TestContext`] provides the [`WidgetContext`] a widget's `measure`, `arrange` and
+//! `render` get, without a window or an application:
+//!
+//! ```ignore
+//! let test = TestContext::builder()
+//!     .scale_factor(2.0)
+//!     .theme(Theme::Dark)
+//!     .noop_gpu()
+//!     .build();
+//! let ctx = test.widget_context();
+//!
+//! let mut widget = Button::new(Text::new("OK")).build_widget_tree();
+//! widget.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+//! let size = widget.measure(&Constraints::new([0.0, 100.0], [0.0, 100.0]), ctx);
+//! assert!(size[0] <= 100.0);
+//!
+//! test.advance(Duration::from_millis(500));
+//! widget.arrange(size, ctx);
+//! let node = widget.render(test.background(), ctx);
+//! ```
+//!
+//! The animation clock only moves with [`TestContext::advance`], so animations render the
+//! same on every run. Timers fire as the clock passes their deadlines.
+//!
+//! Without a GPU, the methods of the context that need one panic. The noop GPU accepts every
+//! call and executes nothing, which is enough to allocate atlas regions and record render
+//! nodes; pass a real [`Gpu`] with [`TestContextBuilder::gpu`] to read back pixels.
+//! [`TestContext::background`] is the background to render on.
+
+use std::{
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use gpu_utils::gpu::Gpu;
+use log::trace;
+use parking_lot::RwLock;
+
+use crate::{
+    context::{ApplicationCommand, ApplicationContext, GlobalResources, WidgetContext},
+    debug_config::DebugConfig,
+    device_input::Theme,
+    frame_clock::{FrameClock, FrameTime},
+    timer::TimerQueue,
+    ui::Background,
+};
+
+const DEFAULT_VIEWPORT_SIZE: [f32; 2] = [800.0, 600.0];
+
+/// The properties of a window a test context pretends to render into.
+pub(crate) struct HeadlessWindow {
+    scale_factor: RwLock<f64>,
+    viewport_size: RwLock<[f32; 2]>,
+    theme: RwLock<Theme>,
+}
+
+impl HeadlessWindow {
+    pub fn scale_factor(&self) -> f64 {
+        *self.scale_factor.read()
+    }
+
+    pub fn viewport_size(&self) -> [f32; 2] {
+        *self.viewport_size.read()
+    }
+
+    pub fn theme(&self) -> Theme {
+        *self.theme.read()
+    }
+}
+
+/// Configures a [`TestContext`].
+pub struct TestContextBuilder {
+    scale_factor: f64,
+    viewport_size: [f32; 2],
+    theme: Theme,
+    start: Option<Instant>,
+    debug_config: DebugConfig,
+    gpu: Option<Arc<Gpu>>,
+}
+
+impl Default for TestContextBuilder {
+    fn default() -> Self {
+        Self {
+            scale_factor: 1.0,
+            viewport_size: DEFAULT_VIEWPORT_SIZE,
+            theme: Theme::Light,
+            start: None,
+            debug_config: DebugConfig::default(),
+            gpu: None,
+        }
+    }
+}
+
+impl TestContextBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reported by [`WidgetContext::dpi`]. Defaults to 1.0.
+    pub fn scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Reported by [`WidgetContext::viewport_size`]. Defaults to 800 x 600.
+    pub fn viewport_size(mut self, width: f32, height: f32) -> Self {
+        self.viewport_size = [width, height];
+        self
+    }
+
+    /// Reported by [`WidgetContext::theme`]. Defaults to light.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Wall-clock time of the first frame, for widgets that compare
+    /// [`FrameTime::timestamp`]s with other instants. Defaults to when the context is built.
+    pub fn start_time(mut self, start: Instant) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /// Rebuilds widgets on every update instead of updating them in place.
+    pub fn always_rebuild_widget(self, value: bool) -> Self {
+        self.debug_config.set_always_rebuild_widget(value);
+        self
+    }
+
+    /// Measures widgets on every call instead of returning cached sizes.
+    pub fn disable_layout_measure_cache(self, value: bool) -> Self {
+        self.debug_config.set_disable_layout_measure_cache(value);
+        self
+    }
+
+    /// Arranges widgets on every call instead of returning cached arrangements.
+    pub fn disable_layout_arrange_cache(self, value: bool) -> Self {
+        self.debug_config.set_disable_layout_arrange_cache(value);
+        self
+    }
+
+    /// Renders widgets on every call instead of returning cached render nodes.
+    pub fn disable_render_node_cache(self, value: bool) -> Self {
+        self.debug_config.set_disable_render_node_cache(value);
+        self
+    }
+
+    /// Paints stripes along the edges that children overflow, see
+    /// [`overflow`](crate::ui::overflow).
+    pub fn show_layout_overflow(self, value: bool) -> Self {
+        self.debug_config.set_show_layout_overflow(value);
+        self
+    }
+
+    /// Renders with `gpu`, which also provides the texture atlases and upload ring.
+    pub fn gpu(mut self, gpu: Arc<Gpu>) -> Self {
+        self.gpu = Some(gpu);
+        self
+    }
+
+    /// Renders with a device on wgpu's noop backend, see [`gpu_utils::wgpu_utils`].
+    #[cfg(any(debug_assertions, feature = "testing"))]
+    pub fn noop_gpu(self) -> Self {
+        let (instance, adapter, device, queue) =
+            futures::executor::block_on(gpu_utils::wgpu_utils::noop_wgpu());
+        self.gpu(Gpu::from_device(
+            instance,
+            adapter,
+            device,
+            queue,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+        ))
+    }
+
+    /// Uses the tokio runtime of the calling thread if there is one, and otherwise a runtime
+    /// shared by the test contexts of the process.
+    pub fn build(self) -> TestContext {
+        trace!(
+            "TestContextBuilder::build: scale factor {}, viewport {:?}, gpu: {}",
+            self.scale_factor,
+            self.viewport_size,
+            self.gpu.is_some()
+        );
+        let task_executor = runtime_handle();
+
+        let start = self.start.unwrap_or_else(Instant::now);
+        let frame_clock = Arc::new(FrameClock::new(start));
+        // the animation time only moves with `advance`
+        frame_clock.pause(start);
+        let timers = Arc::new(TimerQueue::new());
+        let debug_config = Arc::new(RwLock::new(self.debug_config));
+        let window = Arc::new(HeadlessWindow {
+            scale_factor: RwLock::new(self.scale_factor),
+            viewport_size: RwLock::new(self.viewport_size),
+            theme: RwLock::new(self.theme),
+        });
+
+        let (command_sender, _) = tokio::sync::mpsc::unbounded_channel::<ApplicationCommand>();
+        let background = self.gpu.as_deref().map(background_view);
+        let resources = self.gpu.map(GlobalResources::new);
+        let widget_context = match &resources {
+            Some(resources) => resources.headless_widget_context(&task_executor),
+            None => WidgetContext::detached(task_executor, &command_sender),
+        }
+        .with_test_resources(&frame_clock, &timers, &debug_config, &window);
+
+        TestContext {
+            widget_context,
+            resources,
+            background,
+            frame_clock,
+            timers,
+            debug_config,
+            window,
+            start,
+            elapsed: RwLock::new(Duration::ZERO),
+            _command_sender: command_sender,
+        }
+    }
+}
+
+/// Owns what a [`WidgetContext`] for tests refers to. See the [module documentation](self).
+pub struct TestContext {
+    widget_context: WidgetContext,
+    // the contexts only hold weak references to these
+    resources: Option<GlobalResources>,
+    background: Option<wgpu::TextureView>,
+    frame_clock: Arc<FrameClock>,
+    timers: Arc<TimerQueue>,
+    debug_config: Arc<RwLock<DebugConfig>>,
+    window: Arc<HeadlessWindow>,
+    // keeps the weak command senders of contexts without resources upgradable
+    _command_sender: tokio::sync::mpsc::UnboundedSender<ApplicationCommand>,
+
+    start: Instant,
+    elapsed: RwLock<Duration>,
+}
+
+impl TestContext {
+    pub fn builder() -> TestContextBuilder {
+        TestContextBuilder::new()
+    }
+
+    pub fn widget_context(&self) -> &WidgetContext {
+        &self.widget_context
+    }
+
+    pub fn application_context(&self) -> ApplicationContext {
+        self.widget_context.application_context()
+    }
+
+    /// The resources shared with the GPU, if the context has one.
+    pub fn resources(&self) -> Option<&GlobalResources> {
+        self.resources.as_ref()
+    }
+
+    /// A background to render widgets on: a transparent 1x1 texture on the GPU of the
+    /// context.
+    ///
+    /// # Panics
+    /// If the context has no GPU.
+    pub fn background(&self) -> Background<'_> {
+        let view = self
+            .background
+            .as_ref()
+            .expect("TestContext::background needs a GPU");
+        Background::new(view, [0.0, 0.0])
+    }
+
+    /// Moves the animation time forward by `by`, starts a frame and fires the timers that
+    /// expired. Returns the new frame, whose delta is `by` after the first one.
+    pub fn advance(&self, by: Duration) -> FrameTime {
+        let elapsed = {
+            let mut elapsed = self.elapsed.write();
+            *elapsed += by;
+            *elapsed
+        };
+        self.frame_clock.advance(by);
+        let frame = self.frame_clock.tick(self.start + elapsed);
+        self.timers.fire(frame.animation_time);
+        frame
+    }
+
+    /// The frame widgets currently see.
+    pub fn frame(&self) -> FrameTime {
+        self.frame_clock.frame()
+    }
+
+    pub fn set_scale_factor(&self, scale_factor: f64) {
+        *self.window.scale_factor.write() = scale_factor;
+    }
+
+    pub fn set_viewport_size(&self, width: f32, height: f32) {
+        *self.window.viewport_size.write() = [width, height];
+    }
+
+    pub fn set_theme(&self, theme: Theme) {
+        *self.window.theme.write() = theme;
+    }
+
+    /// Enables or disables all layout and render caches at once, e.g. to compare a widget's
+    /// cached and uncached output.
+    pub fn set_caches_disabled(&self, disabled: bool) {
+        let debug_config = self.debug_config.read();
+        debug_config.set_disable_layout_measure_cache(disabled);
+        debug_config.set_disable_layout_arrange_cache(disabled);
+        debug_config.set_disable_render_node_cache(disabled);
+    }
+}
+
+fn background_view(gpu: &Gpu) -> wgpu::TextureView {
+    gpu.device()
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("TestContext Background"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+// shared by the test contexts created outside of a tokio runtime
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+fn runtime_handle() -> tokio::runtime::Handle {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        return handle;
+    }
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build test runtime")
+        })
+        .handle()
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn the_clock_only_moves_when_advanced() {
+        let test = TestContext::builder()
+            .scale_factor(2.0)
+            .theme(Theme::Dark)
+            .build();
+        let ctx = test.widget_context();
+        assert_eq!(ctx.dpi(), Some(2.0));
+        assert_eq!(ctx.theme(), Some(Theme::Dark));
+        assert_eq!(ctx.viewport_size(), Some(DEFAULT_VIEWPORT_SIZE));
+
+        assert_eq!(ctx.current_time(), Duration::ZERO);
+        let first = test.advance(Duration::from_millis(16));
+        assert_eq!(first.animation_time, Duration::from_millis(16));
+        let second = test.advance(Duration::from_millis(16));
+        assert_eq!(second.index, 1);
+        assert_eq!(second.delta, Duration::from_millis(16));
+        assert_eq!(ctx.frame(), second);
+
+        test.set_scale_factor(1.5);
+        assert_eq!(ctx.dpi(), Some(1.5));
+    }
+
+    #[test]
+    fn timers_fire_as_the_clock_passes_them() {
+        let test = TestContext::builder().build();
+        let count = Arc::new(AtomicUsize::new(0));
+        let _timer = test
+            .widget_context()
+            .set_interval(Duration::from_millis(100), {
+                let count = count.clone();
+                move || {
+                    count.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+
+        test.advance(Duration::from_millis(99));
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        test.advance(Duration::from_millis(1));
+        test.advance(Duration::from_millis(100));
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn the_noop_gpu_provides_resources_and_a_background() {
+        let test = TestContext::builder().noop_gpu().build();
+        assert!(test.resources().is_some());
+
+        let background = test.background();
+        assert_eq!(background.position(), [0.0, 0.0]);
+        assert_eq!(background.translate([4.0, 2.0]).position(), [4.0, 2.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a GPU")]
+    fn the_background_needs_a_gpu() {
+        let _ = TestContext::builder().build().background();
+    }
+}
@@ -0,0 +1,215 @@
+//! Timeouts and intervals for widgets and components.
+//!
+//! Timers run on the animation time of the [`FrameClock`](crate::frame_clock::FrameClock), so
+//! they stand still while the app is suspended or its animations are paused in the background,
+//! and a timer that expires during a frame fires before that frame is rendered. Callbacks run
+//! on the rendering loop, in the order of their deadlines.
+//!
+//! Starting a timer returns a [`TimerHandle`]. The timer is cancelled when the handle is
+//! dropped, so a widget keeps the handle in its node and the timer ends with the widget:
+//!
+//! ```ignore
+//! let redraw = cache_invalidator.detach();
+//! self.blink = Some(ctx.set_interval(Duration::from_millis(500), move || {
+//!     redraw.redraw_next_frame();
+//! }));
+//! ```
+//!
+//! Components receive ticks as messages through a [`Command`](crate::ui::command::Command),
+//! which is cancelled with the component:
+//!
+//! ```ignore
+//! Message::Start => Command::stream(app_ctx.interval(Duration::from_secs(1)).map(|()| Message::Tick)),
+//! Message::Save => Command::perform(app_ctx.sleep(Duration::from_secs(5)).map(|()| Message::Saved)),
+//! ```
+
+use std::{
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use log::trace;
+use parking_lot::Mutex;
+
+type Callback = dyn FnMut() + Send;
+
+struct Timer {
+    callback: Mutex<Box<Callback>>,
+    // animation time at which the timer fires next
+    deadline: Mutex<Duration>,
+    interval: Option<Duration>,
+    done: AtomicBool,
+}
+
+/// Keeps a timer running. Dropping the handle cancels the timer.
+#[must_use = "the timer is cancelled when its handle is dropped"]
+pub struct TimerHandle {
+    timer: Option<Arc<Timer>>,
+}
+
+impl TimerHandle {
+    /// A handle to no timer, returned where timers are not available.
+    pub(crate) fn inactive() -> Self {
+        Self { timer: None }
+    }
+
+    /// Stops the timer. A timeout that already fired is unaffected.
+    pub fn cancel(&self) {
+        if let Some(timer) = &self.timer {
+            timer.done.store(true, Ordering::Release);
+        }
+    }
+
+    /// Whether the timer will fire again.
+    pub fn is_active(&self) -> bool {
+        self.timer
+            .as_ref()
+            .is_some_and(|timer| !timer.done.load(Ordering::Acquire))
+    }
+}
+
+impl std::fmt::Debug for TimerHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimerHandle")
+            .field("active", &self.is_active())
+            .finish()
+    }
+}
+
+/// The running timers of an application, fired by the rendering loop.
+#[derive(Default)]
+pub(crate) struct TimerQueue {
+    timers: Mutex<Vec<Weak<Timer>>>,
+}
+
+impl TimerQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a timer that fires `delay` after the animation time `now`, and then every
+    /// `interval` if given.
+    pub fn start(
+        &self,
+        now: Duration,
+        delay: Duration,
+        interval: Option<Duration>,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        let timer = Arc::new(Timer {
+            callback: Mutex::new(Box::new(callback)),
+            deadline: Mutex::new(now + delay),
+            interval,
+            done: AtomicBool::new(false),
+        });
+        self.timers.lock().push(Arc::downgrade(&timer));
+        TimerHandle { timer: Some(timer) }
+    }
+
+    /// Calls the callbacks of the timers due at the animation time `now`. An interval that
+    /// missed several ticks, e.g. while the loop slept in the background, fires once.
+    /// Returns the number of callbacks called.
+    pub fn fire(&self, now: Duration) -> usize {
+        let mut due: Vec<(Duration, Arc<Timer>)> = Vec::new();
+        self.timers.lock().retain(|timer| {
+            let Some(timer) = timer.upgrade() else {
+                return false;
+            };
+            if timer.done.load(Ordering::Acquire) {
+                return false;
+            }
+            let deadline = *timer.deadline.lock();
+            if deadline <= now {
+                due.push((deadline, timer));
+            }
+            true
+        });
+        due.sort_by_key(|(deadline, _)| *deadline);
+
+        // the queue is unlocked, so callbacks may start and cancel timers
+        let mut fired = 0;
+        for (deadline, timer) in due {
+            // an earlier callback may have cancelled it
+            if timer.done.load(Ordering::Acquire) {
+                continue;
+            }
+            match timer.interval {
+                Some(interval) => {
+                    let next = deadline + interval;
+                    *timer.deadline.lock() = if next > now { next } else { now + interval };
+                }
+                None => timer.done.store(true, Ordering::Release),
+            }
+            let mut callback = timer.callback.lock();
+            (*callback)();
+            fired += 1;
+        }
+        if fired > 0 {
+            trace!("TimerQueue::fire: fired {fired} timer(s)");
+        }
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    fn counter() -> (Arc<AtomicUsize>, impl FnMut() + Send + 'static) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let callback = {
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        (count, callback)
+    }
+
+    #[test]
+    fn timeouts_fire_once_and_intervals_repeat() {
+        let queue = TimerQueue::new();
+        let ms = Duration::from_millis;
+        let (timeout_count, timeout) = counter();
+        let (interval_count, interval) = counter();
+        let timeout = queue.start(ms(0), ms(100), None, timeout);
+        let interval = queue.start(ms(0), ms(40), Some(ms(40)), interval);
+
+        assert_eq!(queue.fire(ms(39)), 0);
+        assert_eq!(queue.fire(ms(40)), 1);
+        assert_eq!(queue.fire(ms(100)), 2);
+        assert!(!timeout.is_active());
+        assert!(interval.is_active());
+        assert_eq!(queue.fire(ms(200)), 1);
+
+        // the missed ticks at 160 and 200 were coalesced, the next one is at 240
+        assert_eq!(queue.fire(ms(239)), 0);
+        assert_eq!(queue.fire(ms(240)), 1);
+        assert_eq!(timeout_count.load(Ordering::SeqCst), 1);
+        assert_eq!(interval_count.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn dropping_or_cancelling_the_handle_stops_the_timer() {
+        let queue = TimerQueue::new();
+        let ms = Duration::from_millis;
+        let (count, callback) = counter();
+        let dropped = queue.start(ms(0), ms(10), Some(ms(10)), callback);
+        drop(dropped);
+        assert_eq!(queue.fire(ms(10)), 0);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+        assert!(queue.timers.lock().is_empty());
+
+        let (count, callback) = counter();
+        let cancelled = queue.start(ms(0), ms(10), Some(ms(10)), callback);
+        cancelled.cancel();
+        assert!(!cancelled.is_active());
+        assert_eq!(queue.fire(ms(10)), 0);
+        assert_eq!(count.load(Ordering::SeqCst), 0);
+    }
+}
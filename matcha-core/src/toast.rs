@@ -0,0 +1,353 @@
+//! Toast notifications.
+//!
+//! Update functions post a [`Toast`] with
+//! [`ApplicationContext::notify`](crate::context::ApplicationContext::notify). Toasts are kept
+//! per window by the [`ToastCenter`] of the application, which also expires them after their
+//! duration. They are drawn by a toast overlay widget (`ToastOverlay` in matcha-widgets)
+//! placed around the window content; windows without one do not show toasts.
+
+use std::{
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use winit::window::WindowId;
+
+/// Length of the animation that shows a toast.
+pub const TOAST_ENTER_DURATION: Duration = Duration::from_millis(200);
+/// Length of the animation that hides a dismissed or expired toast.
+pub const TOAST_EXIT_DURATION: Duration = Duration::from_millis(200);
+
+const DEFAULT_TOAST_DURATION: Duration = Duration::from_secs(4);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Severity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub title: String,
+    pub body: String,
+    /// How long the toast stays before it hides itself. `None` keeps it until it is clicked.
+    pub duration: Option<Duration>,
+    pub severity: Severity,
+}
+
+impl Default for Toast {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            body: String::new(),
+            duration: Some(DEFAULT_TOAST_DURATION),
+            severity: Severity::Info,
+        }
+    }
+}
+
+impl Toast {
+    pub fn new(title: &str, body: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ToastId(u64);
+
+/// A toast as it should be drawn at a given time.
+#[derive(Debug, Clone)]
+pub struct ToastState {
+    pub id: ToastId,
+    pub toast: Toast,
+    /// 0.0 when the toast is not visible, 1.0 when it is fully shown. Moves between the two
+    /// during the enter and exit animations.
+    pub visibility: f32,
+    /// `true` once the toast was dismissed or expired and is playing its exit animation.
+    pub closing: bool,
+}
+
+struct Entry {
+    id: ToastId,
+    window: WindowId,
+    toast: Toast,
+    shown_at: Instant,
+    dismissed_at: Option<Instant>,
+}
+
+impl Entry {
+    fn visibility(&self, now: Instant) -> f32 {
+        let entered = progress(
+            now.saturating_duration_since(self.shown_at),
+            TOAST_ENTER_DURATION,
+        );
+        match self.dismissed_at {
+            Some(dismissed_at) => {
+                let exited = progress(
+                    now.saturating_duration_since(dismissed_at),
+                    TOAST_EXIT_DURATION,
+                );
+                entered.min(1.0 - exited)
+            }
+            None => entered,
+        }
+    }
+
+    fn is_animating(&self, now: Instant) -> bool {
+        now < self.shown_at + TOAST_ENTER_DURATION
+            || self
+                .dismissed_at
+                .is_some_and(|dismissed_at| now < dismissed_at + TOAST_EXIT_DURATION)
+    }
+
+    fn is_gone(&self, now: Instant) -> bool {
+        self.dismissed_at
+            .is_some_and(|dismissed_at| now >= dismissed_at + TOAST_EXIT_DURATION)
+    }
+}
+
+fn progress(elapsed: Duration, total: Duration) -> f32 {
+    if total.is_zero() {
+        1.0
+    } else {
+        (elapsed.as_secs_f32() / total.as_secs_f32()).clamp(0.0, 1.0)
+    }
+}
+
+type Listener = Arc<dyn Fn() + Send + Sync>;
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    entries: Vec<Entry>,
+    next_listener_id: u64,
+    listeners: Vec<(u64, WindowId, Listener)>,
+}
+
+/// Toasts of all windows of the application.
+#[derive(Default)]
+pub struct ToastCenter {
+    inner: Mutex<Inner>,
+}
+
+impl ToastCenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn post(&self, window: WindowId, toast: Toast, now: Instant) -> ToastId {
+        let id = {
+            let mut inner = self.inner.lock();
+            let id = ToastId(inner.next_id);
+            inner.next_id += 1;
+            inner.entries.push(Entry {
+                id,
+                window,
+                toast,
+                shown_at: now,
+                dismissed_at: None,
+            });
+            id
+        };
+        self.notify(&[window]);
+        id
+    }
+
+    /// Starts the exit animation of the toast. Does nothing if it is already closing.
+    pub(crate) fn dismiss(&self, id: ToastId, now: Instant) {
+        let window = {
+            let mut inner = self.inner.lock();
+            inner
+                .entries
+                .iter_mut()
+                .find(|entry| entry.id == id && entry.dismissed_at.is_none())
+                .map(|entry| {
+                    entry.dismissed_at = Some(now);
+                    entry.window
+                })
+        };
+        if let Some(window) = window {
+            self.notify(&[window]);
+        }
+    }
+
+    /// Toasts of `window`, oldest first.
+    pub(crate) fn toasts(&self, window: WindowId, now: Instant) -> Vec<ToastState> {
+        self.inner
+            .lock()
+            .entries
+            .iter()
+            .filter(|entry| entry.window == window && !entry.is_gone(now))
+            .map(|entry| ToastState {
+                id: entry.id,
+                toast: entry.toast.clone(),
+                visibility: entry.visibility(now),
+                closing: entry.dismissed_at.is_some(),
+            })
+            .collect()
+    }
+
+    /// Calls `listener` whenever the toasts of `window` change or animate.
+    pub(crate) fn subscribe(
+        self: &Arc<Self>,
+        window: WindowId,
+        listener: impl Fn() + Send + Sync + 'static,
+    ) -> ToastSubscription {
+        let mut inner = self.inner.lock();
+        let id = inner.next_listener_id;
+        inner.next_listener_id += 1;
+        inner.listeners.push((id, window, Arc::new(listener)));
+        ToastSubscription {
+            center: Arc::downgrade(self),
+            id,
+        }
+    }
+
+    /// Expires toasts whose duration has passed, drops finished ones and notifies the windows
+    /// whose toasts are animating. Called by the rendering loop once per frame.
+    pub(crate) fn tick(&self, now: Instant) {
+        let mut windows = Vec::new();
+        {
+            let mut inner = self.inner.lock();
+            for entry in &mut inner.entries {
+                if entry.dismissed_at.is_none()
+                    && let Some(duration) = entry.toast.duration
+                    && now >= entry.shown_at + duration
+                {
+                    entry.dismissed_at = Some(entry.shown_at + duration);
+                }
+                if (entry.is_animating(now) || entry.is_gone(now))
+                    && !windows.contains(&entry.window)
+                {
+                    windows.push(entry.window);
+                }
+            }
+            inner.entries.retain(|entry| !entry.is_gone(now));
+        }
+        if !windows.is_empty() {
+            self.notify(&windows);
+        }
+    }
+
+    fn notify(&self, windows: &[WindowId]) {
+        // call the listeners outside of the lock; they may read the toasts
+        let listeners: Vec<Listener> = self
+            .inner
+            .lock()
+            .listeners
+            .iter()
+            .filter(|(_, window, _)| windows.contains(window))
+            .map(|(_, _, listener)| Arc::clone(listener))
+            .collect();
+        for listener in listeners {
+            listener();
+        }
+    }
+}
+
+/// Keeps a toast listener registered. Dropping it unsubscribes.
+pub struct ToastSubscription {
+    center: Weak<ToastCenter>,
+    id: u64,
+}
+
+impl Drop for ToastSubscription {
+    fn drop(&mut self) {
+        if let Some(center) = self.center.upgrade() {
+            center
+                .inner
+                .lock()
+                .listeners
+                .retain(|(id, _, _)| *id != self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn toasts_expire_and_animate_out() {
+        let center = Arc::new(ToastCenter::new());
+        let window = WindowId::dummy();
+        let start = Instant::now();
+
+        let notified = Arc::new(AtomicUsize::new(0));
+        let subscription = center.subscribe(window, {
+            let notified = Arc::clone(&notified);
+            move || {
+                notified.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let toast = Toast {
+            duration: Some(Duration::from_secs(1)),
+            ..Toast::new("Saved", "")
+        };
+        let id = center.post(window, toast, start);
+        assert_eq!(notified.load(Ordering::Relaxed), 1);
+
+        let states = center.toasts(window, start + TOAST_ENTER_DURATION / 2);
+        assert_eq!(states.len(), 1);
+        assert!((states[0].visibility - 0.5).abs() < 1e-3);
+
+        // fully shown and idle: no notification
+        let idle = start + Duration::from_millis(500);
+        center.tick(idle);
+        assert_eq!(notified.load(Ordering::Relaxed), 1);
+        assert_eq!(center.toasts(window, idle)[0].visibility, 1.0);
+
+        // expired: exit animation runs, then the toast is dropped
+        let expired = start + Duration::from_secs(1) + TOAST_EXIT_DURATION / 2;
+        center.tick(expired);
+        let states = center.toasts(window, expired);
+        assert_eq!(states[0].id, id);
+        assert!(states[0].closing);
+        assert!(states[0].visibility < 1.0);
+
+        let gone = start + Duration::from_secs(2);
+        center.tick(gone);
+        assert!(center.toasts(window, gone).is_empty());
+
+        let count = notified.load(Ordering::Relaxed);
+        drop(subscription);
+        center.post(window, Toast::new("again", ""), gone);
+        assert_eq!(notified.load(Ordering::Relaxed), count);
+    }
+
+    #[test]
+    fn dismissed_sticky_toast_closes() {
+        let center = Arc::new(ToastCenter::new());
+        let window = WindowId::dummy();
+        let start = Instant::now();
+
+        let toast = Toast {
+            duration: None,
+            ..Toast::new("Error", "disk full")
+        };
+        let id = center.post(window, toast, start);
+
+        let later = start + Duration::from_secs(60);
+        center.tick(later);
+        assert!(!center.toasts(window, later)[0].closing);
+
+        center.dismiss(id, later);
+        let closing = center.toasts(window, later + TOAST_EXIT_DURATION / 2);
+        assert!(closing[0].closing);
+
+        let gone = later + TOAST_EXIT_DURATION;
+        center.tick(gone);
+        assert!(center.toasts(window, gone).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+//! System tray icon.
+//!
+//! A [`Tray`] describes the icon shown in the notification area, its tooltip and popup menu,
+//! and what happens when it is clicked. Install it with [`App::tray`](crate::app::App::tray);
+//! together with [`App::run_in_background`](crate::app::App::run_in_background) the
+//! application keeps running while all of its windows are hidden and is brought back by
+//! clicking the icon.
+//!
+//! The tray icon is available on Windows and macOS with the `tray-icon` feature. Elsewhere it
+//! is not shown.
+
+use std::sync::Arc;
+
+use crate::menu::Menu;
+
+pub(crate) mod native;
+
+pub struct Tray<T> {
+    icon_rgba: Vec<u8>,
+    icon_size: [u32; 2],
+    tooltip: Option<String>,
+    menu: Option<Menu<T>>,
+    on_click: Option<Arc<dyn Fn() -> T + Send + Sync>>,
+    show_windows_on_click: bool,
+}
+
+impl<T> Clone for Tray<T> {
+    fn clone(&self) -> Self {
+        Self {
+            icon_rgba: self.icon_rgba.clone(),
+            icon_size: self.icon_size,
+            tooltip: self.tooltip.clone(),
+            menu: self.menu.clone(),
+            on_click: self.on_click.clone(),
+            show_windows_on_click: self.show_windows_on_click,
+        }
+    }
+}
+
+impl<T> Tray<T> {
+    /// Creates a tray icon from `width * height` RGBA8 pixels.
+    pub fn new(rgba: Vec<u8>, width: u32, height: u32) -> Self {
+        Self {
+            icon_rgba: rgba,
+            icon_size: [width, height],
+            tooltip: None,
+            menu: None,
+            on_click: None,
+            show_windows_on_click: true,
+        }
+    }
+
+    pub fn tooltip(mut self, tooltip: &str) -> Self {
+        self.tooltip = Some(tooltip.to_string());
+        self
+    }
+
+    /// The menu shown when the icon is right-clicked. Its title is not displayed.
+    pub fn menu(mut self, menu: Menu<T>) -> Self {
+        self.menu = Some(menu);
+        self
+    }
+
+    /// Message sent to the application when the icon is left-clicked.
+    pub fn on_click(mut self, f: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        self.on_click = Some(Arc::new(f));
+        self
+    }
+
+    /// Whether a left click shows the hidden windows again. Defaults to `true`.
+    pub fn show_windows_on_click(mut self, show: bool) -> Self {
+        self.show_windows_on_click = show;
+        self
+    }
+
+    pub fn icon_rgba(&self) -> &[u8] {
+        &self.icon_rgba
+    }
+
+    pub fn icon_size(&self) -> [u32; 2] {
+        self.icon_size
+    }
+
+    pub fn tooltip_text(&self) -> Option<&str> {
+        self.tooltip.as_deref()
+    }
+
+    pub fn popup_menu(&self) -> Option<&Menu<T>> {
+        self.menu.as_ref()
+    }
+
+    pub fn shows_windows_on_click(&self) -> bool {
+        self.show_windows_on_click
+    }
+
+    /// Produces the click message, if any.
+    pub fn click(&self) -> Option<T> {
+        self.on_click.as_ref().map(|f| f())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn tray() -> Tray<&'static str> {
+        Tray::new(vec![0; 16 * 16 * 4], 16, 16)
+    }
+
+    #[test]
+    fn a_click_shows_the_windows_by_default() {
+        let tray = tray();
+
+        assert!(tray.shows_windows_on_click());
+        assert_eq!(tray.click(), None);
+    }
+
+    #[test]
+    fn a_click_sends_its_message() {
+        let tray = tray().on_click(|| "clicked").show_windows_on_click(false);
+
+        assert!(!tray.shows_windows_on_click());
+        assert_eq!(tray.click(), Some("clicked"));
+        // every click produces the message again
+        assert_eq!(tray.click(), Some("clicked"));
+    }
+
+    #[test]
+    fn menu_items_map_to_their_messages() {
+        let tray = tray().menu(
+            Menu::new("")
+                .item("Show", || "show")
+                .item("Quit", || "quit"),
+        );
+
+        let mut messages = Vec::new();
+        tray.popup_menu()
+            .unwrap()
+            .for_each_action(&mut |action| messages.push(action.trigger()));
+        assert_eq!(messages, ["show", "quit"]);
+    }
+}
@@ -0,0 +1,114 @@
+//! System tray icon of the application.
+//!
+//! Backed by `tray-icon` on Windows and macOS when the `tray-icon` feature is enabled. Other
+//! configurations use a stub that shows nothing.
+
+#[cfg(all(feature = "tray-icon", any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use log::{debug, trace, warn};
+    use tray_icon::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+
+    use crate::{menu::native::NativeMenu, tray::Tray};
+
+    pub(crate) struct NativeTray<T> {
+        tray: Tray<T>,
+        icon: Option<tray_icon::TrayIcon>,
+    }
+
+    impl<T> NativeTray<T> {
+        /// Shows the tray icon. Items of its menu are reported by `native_menu.poll()`.
+        pub(crate) fn new(tray: Tray<T>, native_menu: &mut NativeMenu<T>) -> Self {
+            let [width, height] = tray.icon_size();
+            let mut builder = TrayIconBuilder::new();
+
+            match tray_icon::Icon::from_rgba(tray.icon_rgba().to_vec(), width, height) {
+                Ok(icon) => builder = builder.with_icon(icon),
+                Err(e) => warn!("NativeTray::new: invalid icon: {e}"),
+            }
+            if let Some(tooltip) = tray.tooltip_text() {
+                builder = builder.with_tooltip(tooltip);
+            }
+            if let Some(menu) = tray.popup_menu()
+                && let Some(menu) = native_menu.context_menu(menu)
+            {
+                builder = builder
+                    .with_menu(Box::new(menu))
+                    .with_menu_on_left_click(false);
+            }
+
+            let icon = builder
+                .build()
+                .inspect_err(|e| warn!("NativeTray::new: failed to create tray icon: {e}"))
+                .ok();
+            debug!("NativeTray::new: tray icon created={}", icon.is_some());
+
+            Self { tray, icon }
+        }
+
+        /// Returns the tray description if the icon was left-clicked since the last call.
+        pub(crate) fn poll(&self) -> Option<&Tray<T>> {
+            let icon = self.icon.as_ref()?;
+            let mut clicked = false;
+            while let Ok(event) = TrayIconEvent::receiver().try_recv() {
+                trace!("NativeTray::poll: event={event:?}");
+                if let TrayIconEvent::Click {
+                    id,
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } = event
+                    && &id == icon.id()
+                {
+                    clicked = true;
+                }
+            }
+            clicked.then_some(&self.tray)
+        }
+    }
+}
+
+#[cfg(not(all(feature = "tray-icon", any(target_os = "windows", target_os = "macos"))))]
+mod platform {
+    use log::debug;
+
+    use crate::{menu::native::NativeMenu, tray::Tray};
+
+    pub(crate) struct NativeTray<T> {
+        _tray: Tray<T>,
+    }
+
+    impl<T> NativeTray<T> {
+        pub(crate) fn new(tray: Tray<T>, _native_menu: &mut NativeMenu<T>) -> Self {
+            debug!("NativeTray::new: tray icons are not available in this build");
+            Self { _tray: tray }
+        }
+
+        pub(crate) fn poll(&self) -> Option<&Tray<T>> {
+            None
+        }
+    }
+}
+
+pub(crate) use platform::NativeTray;
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(all(feature = "tray-icon", any(target_os = "windows", target_os = "macos"))))]
+    #[test]
+    fn without_tray_icons_clicks_are_never_reported() {
+        use super::NativeTray;
+        use crate::{
+            menu::{Menu, MenuBar, native::NativeMenu},
+            tray::Tray,
+        };
+
+        let mut native_menu = NativeMenu::new(&MenuBar::new());
+        let tray = Tray::new(vec![0; 4], 1, 1)
+            .on_click(|| "clicked")
+            .menu(Menu::new("").item("Quit", || "quit"));
+        let native_tray = NativeTray::new(tray, &mut native_menu);
+
+        assert!(native_tray.poll().is_none());
+        assert_eq!(native_menu.poll(), None);
+    }
+}
@@ -3,8 +3,48 @@ pub use background::Background;
 
 pub mod widget;
 pub use widget::{
-    AnyWidget, AnyWidgetFrame, Dom, InvalidationHandle, UpdateWidgetError, Widget, WidgetFrame,
+    AnyWidget, AnyWidgetFrame, AsyncInvalidationHandle, ChildFrameLinker, Dom, InvalidationHandle,
+    OwnedChildrenUpdate, PrepareFuture, UpdateWidgetError, Widget, WidgetFrame,
 };
 
+pub mod keyed;
+
+pub mod global_key;
+pub use global_key::{GlobalKey, global_keyed};
+
+pub mod overflow;
+
+pub mod hit_test;
+pub use hit_test::{
+    AlphaMask, HitShape, HitTestEntry, HitTestPath, WidgetSnapshot, hit_test, snapshot,
+};
+
+pub mod elevation;
+pub use elevation::Elevation;
+
+pub mod focus;
+pub use focus::{FocusDirection, FocusState, move_focus};
+
+pub mod layout_style;
+pub use layout_style::{Edges, LayoutStyle};
+
+pub mod popup;
+pub use popup::{Align, PopupPosition, Side};
+
+pub mod signal;
+pub use signal::{Signal, tracked};
+
+pub mod memo;
+pub use memo::memo;
+
+pub mod command;
+pub use command::Command;
+
+pub mod schedule;
+pub use schedule::DispatchPolicy;
+
 pub mod component;
 pub use component::{Component, ComponentDom, ComponentWidget, ModelAccessor};
+
+pub mod error_boundary;
+pub use error_boundary::{BoundaryError, BoundaryPhase, ErrorBoundary};
@@ -0,0 +1,164 @@
+//! Side effects returned from a component's `update_fn`.
+//!
+//! Instead of spawning tasks by hand, `update_fn` returns a [`Command`] describing the async
+//! work that follows a message. The component runtime runs it and feeds every message it
+//! produces back into `update_fn`:
+//!
+//! ```ignore
+//! Component::new(Some("search"), model, view).update_fn(|message, model, _| match message {
+//!     Message::Search(query) => {
+//!         let query = query.clone();
+//!         Command::perform(async move { Message::Results(search(&query).await) })
+//!     }
+//!     Message::Results(results) => {
+//!         // `update_fn` is sync, so the model is updated by a command as well
+//!         let (model, results) = (model.clone(), results.clone());
+//!         Command::perform(async move {
+//!             model.update(|m| m.results = results).await;
+//!             None
+//!         })
+//!     }
+//! })
+//! ```
+//!
+//! Closures returning `()` keep working; `()` converts to [`Command::none`].
+//!
+//! Commands that are still running when the component is dropped are cancelled.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+
+type BoxFuture<M> = Pin<Box<dyn Future<Output = Option<M>> + Send>>;
+type BoxStream<M> = Pin<Box<dyn Stream<Item = M> + Send>>;
+
+pub(crate) enum Action<M> {
+    Future(BoxFuture<M>),
+    Stream(BoxStream<M>),
+}
+
+/// Async work to run after a message was handled. See the [module documentation](self).
+#[must_use = "a command does nothing unless it is returned from `update_fn`"]
+pub struct Command<M> {
+    actions: Vec<Action<M>>,
+}
+
+impl<M> Default for Command<M> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<M> From<()> for Command<M> {
+    fn from(_: ()) -> Self {
+        Self::none()
+    }
+}
+
+impl<M> Command<M> {
+    /// Does nothing.
+    pub fn none() -> Self {
+        Self {
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub(crate) fn into_actions(self) -> Vec<Action<M>> {
+        self.actions
+    }
+}
+
+impl<M: Send + 'static> Command<M> {
+    /// Runs all `commands` concurrently.
+    pub fn batch(commands: impl IntoIterator<Item = Command<M>>) -> Self {
+        Self {
+            actions: commands
+                .into_iter()
+                .flat_map(|command| command.actions)
+                .collect(),
+        }
+    }
+
+    /// Runs `future` and passes the message it resolves to, if any, to `update_fn`.
+    ///
+    /// The output may be a plain message or an `Option` of one.
+    pub fn perform<F, O>(future: F) -> Self
+    where
+        F: Future<Output = O> + Send + 'static,
+        O: Into<Option<M>>,
+    {
+        Self {
+            actions: vec![Action::Future(Box::pin(async move { future.await.into() }))],
+        }
+    }
+
+    /// Passes `message` to `update_fn` right after the current update.
+    pub fn message(message: M) -> Self {
+        Self::perform(async move { message })
+    }
+
+    /// Passes every item of `stream` to `update_fn` until the stream ends or the component
+    /// is dropped, e.g. to subscribe to a channel or a timer.
+    pub fn stream(stream: impl Stream<Item = M> + Send + 'static) -> Self {
+        Self {
+            actions: vec![Action::Stream(Box::pin(stream))],
+        }
+    }
+
+    /// Converts the produced messages, e.g. to embed a child's commands in its parent.
+    pub fn map<N: Send + 'static>(
+        self,
+        f: impl Fn(M) -> N + Clone + Send + Sync + 'static,
+    ) -> Command<N> {
+        Command {
+            actions: self
+                .actions
+                .into_iter()
+                .map(|action| match action {
+                    Action::Future(future) => {
+                        let f = f.clone();
+                        Action::Future(Box::pin(async move { future.await.map(f) }))
+                    }
+                    Action::Stream(stream) => Action::Stream(Box::pin(stream.map(f.clone()))),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    async fn collect(command: Command<u32>) -> Vec<u32> {
+        let mut messages = Vec::new();
+        for action in command.into_actions() {
+            match action {
+                Action::Future(future) => messages.extend(future.await),
+                Action::Stream(stream) => messages.extend(stream.collect::<Vec<_>>().await),
+            }
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn batch_and_map_keep_every_message() {
+        let command = Command::batch([
+            Command::none(),
+            Command::message(1),
+            Command::perform(async { None::<u32> }),
+            Command::stream(futures::stream::iter([2, 3])),
+        ])
+        .map(|m| m * 10);
+        assert_eq!(collect(command).await, vec![10, 20, 30]);
+
+        let unit: Command<u32> = ().into();
+        assert!(unit.is_none());
+    }
+}
@@ -1,26 +1,42 @@
 use std::{
     any::Any,
+    collections::VecDeque,
     sync::{Arc, atomic::AtomicBool},
+    time::Duration,
 };
 
 use crate::{
+    capture::{CaptureTarget, CapturedSubtree},
     context::{ApplicationContext, WidgetContext},
-    device_input::DeviceInput,
+    device_input::{DeviceInput, DeviceInputData},
+    lifecycle::LifecycleEvent,
+    localization::Localization,
     metrics::Constraints,
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, UpdateWidgetError},
+    shortcut::ShortcutRegistry,
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, HitTestEntry, UpdateWidgetError,
+        WidgetSnapshot,
+        command::{Action, Command},
+        memo::{MemoCache, view_scope},
+        schedule::{DispatchPolicy, EventScheduler},
+    },
 };
 
+use futures::StreamExt;
+use log::trace;
 use renderer::RenderNode;
 use tokio::sync::{Mutex, RwLock, RwLockReadGuard};
 use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateNotifier};
 
 type SetupFn<Model> = dyn Fn(&ModelAccessor<Model>, &ApplicationContext) + Send + Sync;
 type UpdateFn<Model, Message> =
-    dyn Fn(&Message, &ModelAccessor<Model>, &ApplicationContext) + Send + Sync;
+    dyn Fn(&Message, &ModelAccessor<Model>, &ApplicationContext) -> Command<Message> + Send + Sync;
 type InputFn<Model> =
     dyn Fn(&DeviceInput, &ModelAccessor<Model>, &ApplicationContext) + Send + Sync;
 type EventFn<Model, Event, InnerEvent> =
     dyn Fn(InnerEvent, &ModelAccessor<Model>, &ApplicationContext) -> Option<Event> + Send + Sync;
+type LifecycleFn<Model> =
+    dyn Fn(LifecycleEvent, &ModelAccessor<Model>, &ApplicationContext) + Send + Sync;
 type ViewFn<Model, InnerEvent> = dyn Fn(&Model) -> Box<dyn Dom<InnerEvent>> + Send + Sync;
 
 fn default_input_function<Model: Send + Sync + 'static>(
@@ -48,13 +64,23 @@ pub struct Component<
     // setup function
     setup: Box<SetupFn<Model>>,
     // update model with message
-    update: Box<UpdateFn<Model, Message>>,
+    update: Arc<UpdateFn<Model, Message>>,
+    // commands returned from `update`, cancelled on drop
+    commands: Arc<CommandTasks>,
     // update model with device event
     input: Arc<InputFn<Model>>,
     // update model with inner event and can emit new event
     event: Arc<EventFn<Model, Event, InnerEvent>>,
+    // holds back debounced and throttled inner events
+    scheduler: Arc<EventScheduler<InnerEvent>>,
+    // react to the app being suspended or resumed
+    lifecycle: Box<LifecycleFn<Model>>,
+    // key combinations mapped to inner events
+    shortcuts: ShortcutRegistry<InnerEvent>,
     // view function
     view: Box<ViewFn<Model, InnerEvent>>,
+    // subtrees memoized by `view`, see [`crate::ui::memo`]
+    memo: Arc<parking_lot::Mutex<MemoCache>>,
 }
 
 /// constructor
@@ -71,10 +97,27 @@ impl<Model: Send + Sync + 'static, Message, Event: 'static, InnerEvent: 'static>
             model: Arc::new(RwLock::new(model)),
             model_update_flag: Arc::new(UpdateFlag::new(false)),
             setup: Box::new(|_: &ModelAccessor<Model>, _: &ApplicationContext| {}),
-            update: Box::new(|_: &Message, _: &ModelAccessor<Model>, _: &ApplicationContext| {}),
+            update: Arc::new(
+                |_: &Message, _: &ModelAccessor<Model>, _: &ApplicationContext| Command::none(),
+            ),
+            commands: Arc::new(CommandTasks::default()),
             input: Arc::new(default_input_function),
             event: Arc::new(|_: InnerEvent, _: &ModelAccessor<Model>, _: &ApplicationContext| None),
+            scheduler: Arc::new(EventScheduler::default()),
+            lifecycle: Box::new(
+                |_: LifecycleEvent, _: &ModelAccessor<Model>, _: &ApplicationContext| {},
+            ),
+            shortcuts: ShortcutRegistry::new(),
             view: Box::new(view),
+            memo: Arc::new(parking_lot::Mutex::new(MemoCache::default())),
+        }
+    }
+
+    /// Access to the model, e.g. for a test that checks the state after driving the UI.
+    pub fn model_accessor(&self) -> ModelAccessor<Model> {
+        ModelAccessor {
+            model: Arc::clone(&self.model),
+            update_flag: Arc::clone(&self.model_update_flag),
         }
     }
 
@@ -86,11 +129,17 @@ impl<Model: Send + Sync + 'static, Message, Event: 'static, InnerEvent: 'static>
         self
     }
 
-    pub fn update_fn(
+    /// Handles a message. The returned [`Command`] runs after the update and the messages it
+    /// produces come back here; closures returning `()` run no command.
+    pub fn update_fn<C: Into<Command<Message>>>(
         mut self,
-        f: impl Fn(&Message, &ModelAccessor<Model>, &ApplicationContext) + Send + Sync + 'static,
+        f: impl Fn(&Message, &ModelAccessor<Model>, &ApplicationContext) -> C + Send + Sync + 'static,
     ) -> Self {
-        self.update = Box::new(f);
+        self.update = Arc::new(
+            move |message: &Message, model: &ModelAccessor<Model>, app_ctx: &ApplicationContext| {
+                f(message, model, app_ctx).into()
+            },
+        );
         self
     }
 
@@ -102,6 +151,27 @@ impl<Model: Send + Sync + 'static, Message, Event: 'static, InnerEvent: 'static>
         self
     }
 
+    /// Called when the app is suspended or resumed, e.g. to pause playback or save state, and
+    /// when it moves to the background or back to the foreground, e.g. to refresh data.
+    ///
+    /// Animations driven by the context's current time pause on their own.
+    pub fn lifecycle_fn(
+        mut self,
+        f: impl Fn(LifecycleEvent, &ModelAccessor<Model>, &ApplicationContext) + Send + Sync + 'static,
+    ) -> Self {
+        self.lifecycle = Box::new(f);
+        self
+    }
+
+    /// Keyboard shortcuts of this component. A key press that matches is consumed before it
+    /// reaches the component's widgets and its inner event is passed to `event_fn`.
+    ///
+    /// Enclosing components see key presses first, so their shortcuts take precedence.
+    pub fn shortcuts(mut self, shortcuts: ShortcutRegistry<InnerEvent>) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+
     pub fn event_fn<NewEventType: 'static>(
         self,
         f: impl Fn(InnerEvent, &ModelAccessor<Model>, &ApplicationContext) -> Option<NewEventType>
@@ -115,19 +185,62 @@ impl<Model: Send + Sync + 'static, Message, Event: 'static, InnerEvent: 'static>
             model_update_flag: self.model_update_flag,
             setup: self.setup,
             update: self.update,
+            commands: self.commands,
             input: self.input,
             event: Arc::new(f),
+            scheduler: self.scheduler,
+            lifecycle: self.lifecycle,
+            shortcuts: self.shortcuts,
             view: self.view,
+            memo: self.memo,
         }
     }
 }
 
+/// event scheduling, see [`crate::ui::schedule`]
+impl<Model: Send + Sync + 'static, Message, Event: 'static, InnerEvent: Send + 'static>
+    Component<Model, Message, Event, InnerEvent>
+{
+    /// Passes inner events matching `filter` to `event_fn` only once no newer event of the
+    /// same variant arrived for `delay`.
+    pub fn debounce(
+        self,
+        delay: Duration,
+        filter: impl Fn(&InnerEvent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.schedule(DispatchPolicy::Debounce(delay), filter)
+    }
+
+    /// Passes at most one inner event matching `filter` per variant and `interval` to
+    /// `event_fn`; the latest of the held back events follows when the interval ends.
+    pub fn throttle(
+        self,
+        interval: Duration,
+        filter: impl Fn(&InnerEvent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.schedule(DispatchPolicy::Throttle(interval), filter)
+    }
+
+    /// Holds back inner events matching `filter` according to `policy`. Rules are checked in
+    /// the order they were added.
+    pub fn schedule(
+        mut self,
+        policy: DispatchPolicy,
+        filter: impl Fn(&InnerEvent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.scheduler = Arc::new(self.scheduler.with_rule(policy, filter));
+        self
+    }
+}
+
 #[async_trait::async_trait]
 pub trait AnyComponent<Message, Event: 'static>: Send + Sync + 'static {
     fn label(&self) -> Option<&str>;
     fn setup(&self, app_ctx: &ApplicationContext);
     fn update(&self, message: &Message, app_ctx: &ApplicationContext);
-    async fn view(&self) -> Box<dyn Dom<Event>>;
+    fn lifecycle(&self, event: LifecycleEvent, app_ctx: &ApplicationContext);
+    /// Builds the view, with `localization` used by [`tr!`](crate::tr) in the view function.
+    async fn view(&self, localization: Option<Arc<Localization>>) -> Box<dyn Dom<Event>>;
 }
 
 #[async_trait::async_trait]
@@ -135,7 +248,7 @@ impl<
     Model: Send + Sync + 'static,
     Message: Send + 'static,
     Event: Send + 'static,
-    InnerEvent: 'static,
+    InnerEvent: Send + 'static,
 > AnyComponent<Message, Event> for Component<Model, Message, Event, InnerEvent>
 {
     fn label(&self) -> Option<&str> {
@@ -152,15 +265,34 @@ impl<
     }
 
     fn update(&self, message: &Message, app_ctx: &ApplicationContext) {
+        let runner = CommandRunner {
+            update: Arc::clone(&self.update),
+            model_access: ModelAccessor {
+                model: Arc::clone(&self.model),
+                update_flag: Arc::clone(&self.model_update_flag),
+            },
+            app_ctx: app_ctx.clone(),
+            tasks: Arc::downgrade(&self.commands),
+        };
+        runner.update(message);
+    }
+
+    fn lifecycle(&self, event: LifecycleEvent, app_ctx: &ApplicationContext) {
         let model_accessor = ModelAccessor {
             model: Arc::clone(&self.model),
             update_flag: Arc::clone(&self.model_update_flag),
         };
 
-        (self.update)(message, &model_accessor, app_ctx);
+        (self.lifecycle)(event, &model_accessor, app_ctx);
     }
 
-    async fn view(&self) -> Box<dyn Dom<Event>> {
+    async fn view(&self, localization: Option<Arc<Localization>>) -> Box<dyn Dom<Event>> {
+        let dom_tree = {
+            let model = self.model.read().await;
+            crate::localization::view_scope(localization, || {
+                view_scope(&self.memo, || (self.view)(&model))
+            })
+        };
         Box::new(ComponentDom {
             label: self.label.clone(),
             model_access: ModelAccessor {
@@ -169,7 +301,9 @@ impl<
             },
             input: Arc::clone(&self.input),
             event: Arc::clone(&self.event),
-            dom_tree: (self.view)(&*self.model.read().await),
+            scheduler: Arc::clone(&self.scheduler),
+            shortcuts: self.shortcuts.clone(),
+            dom_tree,
         })
     }
 }
@@ -213,6 +347,81 @@ impl<Model: 'static> ModelAccessor<Model> {
     }
 }
 
+/// Tasks running the commands of a component. Aborted when the component is dropped.
+#[derive(Default)]
+struct CommandTasks {
+    handles: parking_lot::Mutex<Vec<tokio::task::AbortHandle>>,
+}
+
+impl Drop for CommandTasks {
+    fn drop(&mut self) {
+        let handles = self.handles.get_mut();
+        if !handles.is_empty() {
+            trace!(
+                "CommandTasks::drop: cancelling {} command(s)",
+                handles.len()
+            );
+        }
+        for handle in handles.drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+/// Runs `update` and the commands it returns, feeding produced messages back into `update`.
+struct CommandRunner<Model: 'static, Message> {
+    update: Arc<UpdateFn<Model, Message>>,
+    model_access: ModelAccessor<Model>,
+    app_ctx: ApplicationContext,
+    tasks: std::sync::Weak<CommandTasks>,
+}
+
+impl<Model: 'static, Message> Clone for CommandRunner<Model, Message> {
+    fn clone(&self) -> Self {
+        Self {
+            update: Arc::clone(&self.update),
+            model_access: self.model_access.clone(),
+            app_ctx: self.app_ctx.clone(),
+            tasks: self.tasks.clone(),
+        }
+    }
+}
+
+impl<Model: Send + Sync + 'static, Message: Send + 'static> CommandRunner<Model, Message> {
+    fn update(&self, message: &Message) {
+        let command = (self.update)(message, &self.model_access, &self.app_ctx);
+        if command.is_none() {
+            return;
+        }
+        for action in command.into_actions() {
+            let runner = self.clone();
+            match action {
+                Action::Future(future) => self.spawn(async move {
+                    if let Some(message) = future.await {
+                        runner.update(&message);
+                    }
+                }),
+                Action::Stream(mut stream) => self.spawn(async move {
+                    while let Some(message) = stream.next().await {
+                        runner.update(&message);
+                    }
+                }),
+            }
+        }
+    }
+
+    fn spawn(&self, task: impl std::future::Future<Output = ()> + Send + 'static) {
+        // the component is gone, nothing would receive the messages
+        let Some(tasks) = self.tasks.upgrade() else {
+            return;
+        };
+        let handle = self.app_ctx.task_executor().spawn(task).abort_handle();
+        let mut handles = tasks.handles.lock();
+        handles.retain(|handle| !handle.is_finished());
+        handles.push(handle);
+    }
+}
+
 /// manage component update state and `UpdateNotifier`
 struct UpdateFlag {
     updated: AtomicBool,
@@ -258,12 +467,14 @@ pub struct ComponentDom<Model: Send + Sync + 'static, Event: 'static, InnerEvent
     model_access: ModelAccessor<Model>,
     input: Arc<InputFn<Model>>,
     event: Arc<EventFn<Model, Event, InnerEvent>>,
+    scheduler: Arc<EventScheduler<InnerEvent>>,
+    shortcuts: ShortcutRegistry<InnerEvent>,
 
     dom_tree: Box<dyn Dom<InnerEvent>>,
 }
 
 #[async_trait::async_trait]
-impl<Model: Send + Sync + 'static, Event: 'static, InnerEvent: 'static> Dom<Event>
+impl<Model: Send + Sync + 'static, Event: Send + 'static, InnerEvent: Send + 'static> Dom<Event>
     for ComponentDom<Model, Event, InnerEvent>
 {
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<Event>> {
@@ -272,6 +483,9 @@ impl<Model: Send + Sync + 'static, Event: 'static, InnerEvent: 'static> Dom<Even
             model_access: self.model_access.clone(),
             input: Arc::clone(&self.input),
             event: Arc::clone(&self.event),
+            scheduler: Arc::clone(&self.scheduler),
+            outbox: Arc::new(parking_lot::Mutex::new(VecDeque::new())),
+            shortcuts: self.shortcuts.clone(),
             widget_tree: self.dom_tree.build_widget_tree(),
         })
     }
@@ -295,18 +509,56 @@ pub struct ComponentWidget<
     model_access: ModelAccessor<Model>,
     input: Arc<InputFn<Model>>,
     event: Arc<EventFn<Model, Event, InnerEvent>>,
+    scheduler: Arc<EventScheduler<InnerEvent>>,
+    // events for the parent produced by held back inner events
+    outbox: Arc<parking_lot::Mutex<VecDeque<Event>>>,
+    shortcuts: ShortcutRegistry<InnerEvent>,
 
     widget_tree: Box<dyn AnyWidgetFrame<InnerEvent>>,
 }
 
-impl<Model: Send + Sync + 'static, Event: 'static, InnerEvent: 'static> AnyWidget<Event>
-    for ComponentWidget<Model, Event, InnerEvent>
+impl<Model: Send + Sync + 'static, Event: Send + 'static, InnerEvent: Send + 'static>
+    ComponentWidget<Model, Event, InnerEvent>
+{
+    /// Passes an inner event to `event_fn`, or holds it back if a scheduling rule matches.
+    fn handle_inner_event(
+        &self,
+        event: InnerEvent,
+        app_ctx: &ApplicationContext,
+        ctx: &WidgetContext,
+    ) -> Option<Event> {
+        let deliver = {
+            let event_fn = Arc::clone(&self.event);
+            let model_access = self.model_access.clone();
+            let app_ctx = app_ctx.clone();
+            let outbox = Arc::clone(&self.outbox);
+            move |e| {
+                if let Some(event) = event_fn(e, &model_access, &app_ctx) {
+                    outbox.lock().push_back(event);
+                    // the window collects it with `take_deferred_events`
+                    app_ctx.flush_deferred_events();
+                }
+            }
+        };
+        self.scheduler
+            .dispatch(event, ctx.task_executor(), deliver)
+            .and_then(|e| (self.event)(e, &self.model_access, app_ctx))
+    }
+}
+
+impl<Model: Send + Sync + 'static, Event: Send + 'static, InnerEvent: Send + 'static>
+    AnyWidget<Event> for ComponentWidget<Model, Event, InnerEvent>
 {
     fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<Event> {
-        (self.input)(event, &self.model_access, &ctx.application_context());
+        let app_ctx = ctx.application_context();
+        (self.input)(event, &self.model_access, &app_ctx);
 
-        let inner_event = self.widget_tree.device_input(event, ctx);
-        inner_event.and_then(|e| (self.event)(e, &self.model_access, &ctx.application_context()))
+        let shortcut_event = match event.event() {
+            DeviceInputData::Keyboard(key_input) => self.shortcuts.dispatch(key_input),
+            _ => None,
+        };
+        let inner_event = shortcut_event.or_else(|| self.widget_tree.device_input(event, ctx));
+        inner_event.and_then(|e| self.handle_inner_event(e, &app_ctx, ctx))
     }
 
     fn is_inside(&self, position: [f32; 2], ctx: &WidgetContext) -> bool {
@@ -317,14 +569,34 @@ impl<Model: Send + Sync + 'static, Event: 'static, InnerEvent: 'static> AnyWidge
         self.widget_tree.measure(constraints, ctx)
     }
 
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        self.widget_tree.baseline(constraints, ctx)
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.min_intrinsic_width(height, ctx)
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.max_intrinsic_width(height, ctx)
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.min_intrinsic_height(width, ctx)
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.max_intrinsic_height(width, ctx)
+    }
+
     fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
         self.widget_tree.render(background, ctx)
     }
 }
 
 #[async_trait::async_trait]
-impl<Model: Send + Sync + 'static, Event: 'static, InnerEvent: 'static> AnyWidgetFrame<Event>
-    for ComponentWidget<Model, Event, InnerEvent>
+impl<Model: Send + Sync + 'static, Event: Send + 'static, InnerEvent: Send + 'static>
+    AnyWidgetFrame<Event> for ComponentWidget<Model, Event, InnerEvent>
 {
     fn label(&self) -> Option<&str> {
         self.label.as_deref()
@@ -369,4 +641,257 @@ impl<Model: Send + Sync + 'static, Event: 'static, InnerEvent: 'static> AnyWidge
     fn invalidate_render_cache(&mut self) {
         self.widget_tree.invalidate_render_cache();
     }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.widget_tree.update_gpu_device(device, queue);
+    }
+
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.widget_tree.update_lifecycle(visible_rect, ctx);
+    }
+
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.widget_tree.prepare(visible_rect, ctx);
+    }
+
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool {
+        self.widget_tree
+            .hit_test(id, position, to_window, ctx, path)
+    }
+
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot> {
+        self.widget_tree.snapshot(id, to_window)
+    }
+
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree> {
+        self.widget_tree.find_rendered(id, target)
+    }
+
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext) {
+        self.widget_tree.set_focus(path, ctx);
+    }
+
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<Event> {
+        let app_ctx = ctx.application_context();
+        // events of nested components are inner events here
+        let mut events: Vec<Event> = self
+            .widget_tree
+            .take_deferred_events(ctx)
+            .into_iter()
+            .filter_map(|e| self.handle_inner_event(e, &app_ctx, ctx))
+            .collect();
+        events.extend(self.outbox.lock().drain(..));
+        events
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{test_kit::TestContext, ui::Dom};
+
+    enum Message {
+        Add(u32),
+        AddLater(u32),
+        Subscribe,
+    }
+
+    fn counter() -> Component<u32, Message, ()> {
+        fn view(_: &u32) -> Box<dyn Dom<()>> {
+            unimplemented!("not rendered in these tests")
+        }
+        Component::new(None, 0, view).update_fn(|message: &Message, model, _| match message {
+            Message::Add(n) => {
+                let (model, n) = (model.clone(), *n);
+                Command::perform(async move {
+                    model.update(|count| *count += n).await;
+                    None
+                })
+            }
+            Message::AddLater(n) => Command::message(Message::Add(*n)),
+            Message::Subscribe => Command::none(),
+        })
+    }
+
+    async fn wait_for(model: &ModelAccessor<u32>, expected: u32) {
+        for _ in 0..100 {
+            if model.read(|count| *count).await == expected {
+                return;
+            }
+            tokio::task::yield_now().await;
+        }
+        panic!("count did not reach {expected}");
+    }
+
+    #[tokio::test]
+    async fn commands_feed_messages_back_into_update() {
+        let component = counter();
+        let model = component.model_accessor();
+        let test = TestContext::builder().build();
+        let app_ctx = test.application_context();
+
+        component.update(&Message::AddLater(2), &app_ctx);
+        component.update(&Message::Add(1), &app_ctx);
+        wait_for(&model, 3).await;
+    }
+
+    #[tokio::test]
+    async fn dropping_the_component_cancels_streams() {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let receiver = std::sync::Mutex::new(Some(receiver));
+        let component = counter().update_fn(move |message: &Message, model, _| {
+            let model = model.clone();
+            match message {
+                Message::Subscribe => {
+                    let mut receiver = receiver.lock().unwrap().take().unwrap();
+                    Command::stream(futures::stream::poll_fn(move |cx| receiver.poll_recv(cx)))
+                        .map(Message::Add)
+                }
+                Message::Add(n) => {
+                    let n = *n;
+                    Command::perform(async move {
+                        model.update(|count| *count += n).await;
+                        None
+                    })
+                }
+                Message::AddLater(_) => Command::none(),
+            }
+        });
+        let model = component.model_accessor();
+        let test = TestContext::builder().build();
+        let app_ctx = test.application_context();
+
+        component.update(&Message::Subscribe, &app_ctx);
+        sender.send(5).unwrap();
+        wait_for(&model, 5).await;
+
+        drop(component);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        // the subscription ended with the component, so the receiver is gone
+        assert!(sender.send(1).is_err());
+    }
+
+    // a widget without content, for components whose view does not matter
+    struct Blank;
+
+    impl<E: 'static> Dom<E> for Blank {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<E>> {
+            Box::new(crate::ui::WidgetFrame::<Blank, _, E, ()>::new(
+                None,
+                vec![],
+                vec![],
+                Blank,
+            ))
+        }
+    }
+
+    impl<E: 'static> crate::ui::Widget<Blank, E, ()> for Blank {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a Blank,
+            _cache_invalidator: Option<crate::ui::InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<E>, (), u128)> {
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<E>, &mut (), &crate::metrics::Arrangement)],
+            _cache_invalidator: crate::ui::InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<E> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<E>, &())],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            [0.0, 0.0]
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<E>, &())],
+            _ctx: &WidgetContext,
+        ) -> Vec<crate::metrics::Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<E>, &(), &crate::metrics::Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::new()
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Picked {
+        Item(u32),
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn debounced_events_of_nested_components_reach_the_window() {
+        use winit::{
+            event::ElementState,
+            keyboard::{Key, KeyCode, ModifiersState, PhysicalKey},
+        };
+
+        let shortcuts = ShortcutRegistry::new();
+        shortcuts.register(
+            crate::shortcut::Shortcut::new(ModifiersState::empty(), Key::Character("a".into())),
+            || 7,
+        );
+        let inner = Component::<(), (), u32, u32>::new(None, (), |_| Box::new(Blank))
+            .shortcuts(shortcuts)
+            .event_fn(|n, _, _| Some(Picked::Item(n)))
+            .debounce(Duration::from_millis(100), |_| true);
+        let inner_dom = parking_lot::Mutex::new(Some(inner.view(None).await));
+        let outer = Component::<(), (), Picked, Picked>::new(None, (), move |_| {
+            inner_dom.lock().take().unwrap()
+        })
+        .event_fn(|Picked::Item(n), _, _| Some(n * 2));
+
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let mut widget = outer.view(None).await.build_widget_tree();
+        let press = crate::device_input::KeyboardState::new().synthetic_input(
+            PhysicalKey::Code(KeyCode::KeyA),
+            Key::Character("a".into()),
+            Some("a"),
+            ElementState::Pressed,
+        );
+        let input = DeviceInput::new([0.0, 0.0], press, None);
+
+        assert_eq!(widget.device_input(&input, &ctx), None);
+        assert!(widget.take_deferred_events(&ctx).is_empty());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        // the inner component's event went through the outer component's `event_fn`
+        assert_eq!(widget.take_deferred_events(&ctx), vec![14]);
+        assert!(widget.take_deferred_events(&ctx).is_empty());
+    }
 }
@@ -0,0 +1,198 @@
+//! Consistent depth cues for cards, menus and dialogs.
+//!
+//! An [`Elevation`] level stands for a distance above the surface below. Each level maps to
+//! two shadows, a key shadow cast downwards by a light above the screen and a faint ambient
+//! shadow all around, and to a z-order bias that paints the widget over its lower siblings.
+//! Set it with [`LayoutStyle::elevation`](super::LayoutStyle::elevation);
+//! [`WidgetFrame`](super::WidgetFrame) draws the shadows under the border box, rounded like
+//! `clip_radius`.
+//!
+//! The bias only changes paint order among siblings. Hit testing keeps the order of the
+//! children, so overlapping siblings should not rely on elevation to receive input.
+
+use renderer::{
+    render_node::RenderNode,
+    widgets_renderer::box_shadow::{BoxShadow, Shadow, TargetData},
+};
+
+use crate::context::WidgetContext;
+
+/// One shadow of an elevation level, relative to the box that casts it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowPreset {
+    /// Offset of the shadow from the box, in pixels.
+    pub offset: [f32; 2],
+    /// Standard deviation of the blur, in pixels.
+    pub blur: f32,
+    /// Growth of the box on each side before it is blurred, in pixels.
+    pub spread: f32,
+    /// Opacity of the shadow under the box.
+    pub opacity: f32,
+}
+
+const fn key(offset_y: f32, blur: f32) -> ShadowPreset {
+    ShadowPreset {
+        offset: [0.0, offset_y],
+        blur,
+        spread: 0.0,
+        opacity: 0.22,
+    }
+}
+
+const fn ambient(blur: f32, spread: f32) -> ShadowPreset {
+    ShadowPreset {
+        offset: [0.0, 0.0],
+        blur,
+        spread,
+        opacity: 0.12,
+    }
+}
+
+/// Key and ambient shadow of levels 1 to [`Elevation::MAX`].
+const PRESETS: [[ShadowPreset; 2]; Elevation::MAX as usize] = [
+    [key(1.0, 1.5), ambient(2.0, 0.0)],
+    [key(2.0, 2.5), ambient(4.0, 0.0)],
+    [key(4.0, 4.0), ambient(8.0, 1.0)],
+    [key(6.0, 6.0), ambient(12.0, 1.0)],
+    [key(10.0, 9.0), ambient(18.0, 2.0)],
+];
+
+/// Height of a widget above the surface below. Level 0 is flat and casts no shadow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Elevation(u8);
+
+impl Elevation {
+    /// The highest level with a shadow of its own; higher levels cast the same shadow but
+    /// keep their z-order bias.
+    pub const MAX: u8 = 5;
+
+    pub const fn new(level: u8) -> Self {
+        Self(level)
+    }
+
+    pub const fn level(self) -> u8 {
+        self.0
+    }
+
+    /// The key and the ambient shadow, or none at level 0.
+    pub fn shadows(self) -> &'static [ShadowPreset] {
+        match self.0 {
+            0 => &[],
+            level => &PRESETS[usize::from(level.min(Self::MAX)) - 1],
+        }
+    }
+
+    /// Z-index added to the widget's render node, so higher widgets are painted over lower
+    /// siblings.
+    pub fn z_bias(self) -> i32 {
+        i32::from(self.0)
+    }
+
+    /// How far the shadows reach beyond the box on any side.
+    pub fn reach(self) -> f32 {
+        self.shadows()
+            .iter()
+            .map(|preset| {
+                let offset = preset.offset[0].abs().max(preset.offset[1].abs());
+                offset + preset.spread + 3.0 * preset.blur
+            })
+            .fold(0.0, f32::max)
+    }
+
+    /// Renders the shadows of a box of `size` with corners rounded by `corner_radius`.
+    /// The node's origin is the box's origin. `None` at level 0 or when no texture can be
+    /// allocated.
+    pub fn shadow_node(
+        self,
+        size: [f32; 2],
+        corner_radius: f32,
+        ctx: &WidgetContext,
+    ) -> Option<RenderNode> {
+        let presets = self.shadows();
+        if presets.is_empty() || size[0] <= 0.0 || size[1] <= 0.0 {
+            return None;
+        }
+
+        let reach = self.reach().ceil();
+        let texture_size = [
+            (size[0] + 2.0 * reach).ceil() as u32,
+            (size[1] + 2.0 * reach).ceil() as u32,
+        ];
+        let region = ctx
+            .texture_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), texture_size)
+            .ok()?;
+
+        let shadows: Vec<Shadow> = presets
+            .iter()
+            .map(|preset| {
+                let [x, y] = preset.offset;
+                Shadow {
+                    rect: [
+                        reach + x - preset.spread,
+                        reach + y - preset.spread,
+                        reach + x + size[0] + preset.spread,
+                        reach + y + size[1] + preset.spread,
+                    ],
+                    color: [0.0, 0.0, 0.0, preset.opacity],
+                    // spreading a sharp box keeps its corners sharp
+                    corner_radius: if corner_radius > 0.0 {
+                        corner_radius + preset.spread
+                    } else {
+                        0.0
+                    },
+                    blur: preset.blur,
+                }
+            })
+            .collect();
+
+        let mut encoder = ctx
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Elevation Shadow Encoder"),
+            });
+        {
+            let mut render_pass = region.begin_render_pass(&mut encoder).ok()?;
+            ctx.renderer::<BoxShadow>().render(
+                &mut render_pass,
+                TargetData {
+                    target_size: region.texture_size(),
+                    target_format: region.format(),
+                },
+                &shadows,
+                &ctx.device(),
+            );
+        }
+        ctx.queue().submit(Some(encoder.finish()));
+
+        Some(RenderNode::new().with_texture(
+            region,
+            [texture_size[0] as f32, texture_size[1] as f32],
+            nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(-reach, -reach, 0.0)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn higher_levels_cast_wider_shadows_and_paint_later() {
+        assert!(Elevation::new(0).shadows().is_empty());
+        assert_eq!(Elevation::new(0).reach(), 0.0);
+
+        for level in 1..Elevation::MAX {
+            let lower = Elevation::new(level);
+            let higher = Elevation::new(level + 1);
+            assert!(lower.reach() < higher.reach());
+            assert!(lower.z_bias() < higher.z_bias());
+        }
+
+        // beyond the last preset only the bias grows
+        let max = Elevation::new(Elevation::MAX);
+        let above = Elevation::new(Elevation::MAX + 3);
+        assert_eq!(above.shadows(), max.shadows());
+        assert!(above.z_bias() > max.z_bias());
+    }
+}
@@ -0,0 +1,586 @@
+//! Containing failures of a subtree.
+//!
+//! A panic in a widget unwinds through the whole window, so a bug in one panel takes down the
+//! frame. Wrapping the panel in an [`ErrorBoundary`] catches panics raised while its subtree is
+//! built, updated, laid out, prepared, rendered or handles input. From then on the boundary
+//! shows its fallback instead, and reports the failure as an event:
+//!
+//! ```ignore
+//! ErrorBoundary::new(chart(model), Text::new("The chart could not be shown"))
+//!     .on_error(|error| Some(Message::ChartFailed(error.to_string())))
+//!     .reset_key(model.chart_revision)
+//! ```
+//!
+//! The failed subtree is dropped. It is built again when the boundary gets a new
+//! [`reset_key`](ErrorBoundary::reset_key), e.g. after the app reloaded the data that broke it.
+
+use std::{
+    any::Any,
+    collections::VecDeque,
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::Arc,
+};
+
+use futures::FutureExt;
+use log::{error, trace};
+use parking_lot::Mutex;
+use renderer::RenderNode;
+use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateNotifier};
+
+use crate::{
+    capture::{CaptureTarget, CapturedSubtree},
+    context::WidgetContext,
+    device_input::DeviceInput,
+    metrics::Constraints,
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, HitTestEntry, UpdateWidgetError, WidgetSnapshot,
+    },
+};
+
+type ErrorFn<E> = dyn Fn(&BoundaryError) -> Option<E> + Send + Sync;
+
+/// The phase in which a subtree failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoundaryPhase {
+    Build,
+    Update,
+    Layout,
+    Prepare,
+    Render,
+    Input,
+}
+
+impl std::fmt::Display for BoundaryPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Build => "build",
+            Self::Update => "update",
+            Self::Layout => "layout",
+            Self::Prepare => "prepare",
+            Self::Render => "render",
+            Self::Input => "input",
+        })
+    }
+}
+
+/// A failure caught by an [`ErrorBoundary`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("panicked during {phase}: {message}")]
+pub struct BoundaryError {
+    pub phase: BoundaryPhase,
+    /// The panic message, if the panic had one.
+    pub message: String,
+}
+
+impl BoundaryError {
+    fn from_panic(phase: BoundaryPhase, payload: &(dyn Any + Send)) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            String::from("unknown panic payload")
+        };
+        Self { phase, message }
+    }
+}
+
+// MARK: DOM
+
+/// Shows `fallback` in place of `content` once `content` panicked.
+pub struct ErrorBoundary<E: 'static> {
+    label: Option<String>,
+    content: Box<dyn Dom<E>>,
+    fallback: Box<dyn Dom<E>>,
+    on_error: Option<Arc<ErrorFn<E>>>,
+    reset_key: u64,
+}
+
+impl<E: 'static> ErrorBoundary<E> {
+    pub fn new(content: impl Dom<E>, fallback: impl Dom<E>) -> Self {
+        Self {
+            label: None,
+            content: Box::new(content),
+            fallback: Box::new(fallback),
+            on_error: None,
+            reset_key: 0,
+        }
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Turns a caught failure into an event for the parent, delivered with the next input.
+    pub fn on_error(
+        mut self,
+        f: impl Fn(&BoundaryError) -> Option<E> + Send + Sync + 'static,
+    ) -> Self {
+        self.on_error = Some(Arc::new(f));
+        self
+    }
+
+    /// Builds the content again after a failure when the key differs from the previous view.
+    pub fn reset_key(mut self, key: u64) -> Self {
+        self.reset_key = key;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: Send + 'static> Dom<E> for ErrorBoundary<E> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<E>> {
+        let mut widget = ErrorBoundaryWidget {
+            label: self.label.clone(),
+            content: None,
+            fallback: self.fallback.build_widget_tree(),
+            on_error: self.on_error.clone(),
+            reset_key: self.reset_key,
+            failure: Mutex::new(None),
+            outbox: Mutex::new(VecDeque::new()),
+            dirty_flags: None,
+        };
+        match catch_unwind(AssertUnwindSafe(|| self.content.build_widget_tree())) {
+            Ok(content) => widget.content = Some(content),
+            Err(payload) => widget.fail(BoundaryPhase::Build, payload),
+        }
+        Box::new(widget)
+    }
+}
+
+// MARK: Widget
+
+pub struct ErrorBoundaryWidget<E: 'static> {
+    label: Option<String>,
+    // `None` once the content failed to build or was dropped after a failure
+    content: Option<Box<dyn AnyWidgetFrame<E>>>,
+    fallback: Box<dyn AnyWidgetFrame<E>>,
+    on_error: Option<Arc<ErrorFn<E>>>,
+    reset_key: u64,
+    failure: Mutex<Option<BoundaryError>>,
+    // events produced by failures, delivered with the next input
+    outbox: Mutex<VecDeque<E>>,
+    dirty_flags: Option<(BackPropDirty, BackPropDirty)>,
+}
+
+impl<E: Send + 'static> ErrorBoundaryWidget<E> {
+    /// The failure that replaced the content with the fallback, if any.
+    pub fn failure(&self) -> Option<BoundaryError> {
+        self.failure.lock().clone()
+    }
+
+    fn is_failed(&self) -> bool {
+        self.failure.lock().is_some()
+    }
+
+    fn fail(&self, phase: BoundaryPhase, payload: Box<dyn Any + Send>) {
+        let failure = BoundaryError::from_panic(phase, &*payload);
+        error!(
+            "ErrorBoundary {:?}: content {failure}; showing the fallback",
+            self.label
+        );
+        if let Some(event) = self.on_error.as_ref().and_then(|f| f(&failure)) {
+            self.outbox.lock().push_back(event);
+        }
+        *self.failure.lock() = Some(failure);
+        // the fallback takes the place of the content
+        if let Some((rearrange_flags, redraw_flags)) = &self.dirty_flags {
+            rearrange_flags.mark_dirty();
+            redraw_flags.mark_dirty();
+        }
+    }
+
+    /// Runs `f` on the content, or on the fallback once the content failed.
+    fn with_active<R>(&self, phase: BoundaryPhase, f: impl Fn(&dyn AnyWidgetFrame<E>) -> R) -> R {
+        if !self.is_failed()
+            && let Some(content) = &self.content
+        {
+            match catch_unwind(AssertUnwindSafe(|| f(&**content))) {
+                Ok(result) => return result,
+                Err(payload) => self.fail(phase, payload),
+            }
+        }
+        f(&*self.fallback)
+    }
+
+    fn with_active_mut<R>(
+        &mut self,
+        phase: BoundaryPhase,
+        mut f: impl FnMut(&mut dyn AnyWidgetFrame<E>) -> R,
+    ) -> R {
+        self.drop_failed_content();
+        if let Some(content) = &mut self.content {
+            match catch_unwind(AssertUnwindSafe(|| f(&mut **content))) {
+                Ok(result) => return result,
+                Err(payload) => {
+                    self.fail(phase, payload);
+                    self.drop_failed_content();
+                }
+            }
+        }
+        f(&mut *self.fallback)
+    }
+
+    fn drop_failed_content(&mut self) {
+        if self.is_failed()
+            && let Some(content) = self.content.take()
+        {
+            // the failed subtree may be in a state its `Drop` does not expect
+            if catch_unwind(AssertUnwindSafe(|| drop(content))).is_err() {
+                error!(
+                    "ErrorBoundary {:?}: dropping the failed content panicked",
+                    self.label
+                );
+            }
+        }
+    }
+
+    fn build_content(&mut self, dom: &dyn Dom<E>) {
+        match catch_unwind(AssertUnwindSafe(|| dom.build_widget_tree())) {
+            Ok(mut content) => {
+                if let Some((rearrange_flags, redraw_flags)) = &self.dirty_flags {
+                    content.update_dirty_flags(rearrange_flags.clone(), redraw_flags.clone());
+                }
+                self.content = Some(content);
+            }
+            Err(payload) => self.fail(BoundaryPhase::Build, payload),
+        }
+    }
+}
+
+impl<E: Send + 'static> AnyWidget<E> for ErrorBoundaryWidget<E> {
+    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<E> {
+        self.with_active_mut(BoundaryPhase::Input, |widget_tree| {
+            widget_tree.device_input(event, ctx)
+        })
+        .or_else(|| self.outbox.lock().pop_front())
+    }
+
+    fn is_inside(&self, position: [f32; 2], ctx: &WidgetContext) -> bool {
+        self.with_active(BoundaryPhase::Input, |widget_tree| {
+            widget_tree.is_inside(position, ctx)
+        })
+    }
+
+    fn measure(&self, constraints: &Constraints, ctx: &WidgetContext) -> [f32; 2] {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.measure(constraints, ctx)
+        })
+    }
+
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.baseline(constraints, ctx)
+        })
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.min_intrinsic_width(height, ctx)
+        })
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.max_intrinsic_width(height, ctx)
+        })
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.min_intrinsic_height(width, ctx)
+        })
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.max_intrinsic_height(width, ctx)
+        })
+    }
+
+    fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
+        self.with_active(BoundaryPhase::Render, |widget_tree| {
+            widget_tree.render(background, ctx)
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: Send + 'static> AnyWidgetFrame<E> for ErrorBoundaryWidget<E> {
+    fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    fn need_redraw(&self) -> bool {
+        self.with_active(BoundaryPhase::Render, |widget_tree| {
+            widget_tree.need_redraw()
+        })
+    }
+
+    async fn update_widget_tree(&mut self, dom: &dyn Dom<E>) -> Result<(), UpdateWidgetError> {
+        let dom = (dom as &dyn Any)
+            .downcast_ref::<ErrorBoundary<E>>()
+            .ok_or(UpdateWidgetError::TypeMismatch)?;
+
+        self.label = dom.label.clone();
+        self.on_error = dom.on_error.clone();
+
+        if let Err(UpdateWidgetError::TypeMismatch) =
+            self.fallback.update_widget_tree(&*dom.fallback).await
+        {
+            self.fallback = dom.fallback.build_widget_tree();
+            if let Some((rearrange_flags, redraw_flags)) = &self.dirty_flags {
+                self.fallback
+                    .update_dirty_flags(rearrange_flags.clone(), redraw_flags.clone());
+            }
+        }
+
+        self.drop_failed_content();
+        if self.reset_key != dom.reset_key && self.failure.lock().take().is_some() {
+            trace!(
+                "ErrorBoundary {:?}: reset key changed, building the content again",
+                self.label
+            );
+        }
+        self.reset_key = dom.reset_key;
+        if self.is_failed() {
+            return Ok(());
+        }
+
+        match self.content.as_mut() {
+            Some(content) => {
+                match AssertUnwindSafe(content.update_widget_tree(&*dom.content))
+                    .catch_unwind()
+                    .await
+                {
+                    Ok(Ok(())) => {}
+                    Ok(Err(UpdateWidgetError::TypeMismatch)) => self.build_content(&*dom.content),
+                    Err(payload) => {
+                        self.fail(BoundaryPhase::Update, payload);
+                        self.drop_failed_content();
+                    }
+                }
+            }
+            None => self.build_content(&*dom.content),
+        }
+        Ok(())
+    }
+
+    async fn set_model_update_notifier(&self, notifier: &UpdateNotifier) {
+        if let Some(content) = &self.content {
+            content.set_model_update_notifier(notifier).await;
+        }
+        self.fallback.set_model_update_notifier(notifier).await;
+    }
+
+    fn arrange(&self, bounds: [f32; 2], ctx: &WidgetContext) {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.arrange(bounds, ctx)
+        })
+    }
+
+    fn update_dirty_flags(&mut self, rearrange_flags: BackPropDirty, redraw_flags: BackPropDirty) {
+        // content and fallback take the same place in the layout
+        if let Some(content) = &mut self.content {
+            content.update_dirty_flags(rearrange_flags.clone(), redraw_flags.clone());
+        }
+        self.fallback
+            .update_dirty_flags(rearrange_flags.clone(), redraw_flags.clone());
+        self.dirty_flags = Some((rearrange_flags, redraw_flags));
+    }
+
+    fn invalidate_render_cache(&mut self) {
+        if let Some(content) = &mut self.content {
+            content.invalidate_render_cache();
+        }
+        self.fallback.invalidate_render_cache();
+    }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.with_active_mut(BoundaryPhase::Render, |widget_tree| {
+            widget_tree.update_gpu_device(device, queue)
+        });
+    }
+
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.with_active_mut(BoundaryPhase::Update, |widget_tree| {
+            widget_tree.update_lifecycle(visible_rect, ctx)
+        });
+    }
+
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.with_active_mut(BoundaryPhase::Prepare, |widget_tree| {
+            widget_tree.prepare(visible_rect, ctx)
+        });
+    }
+
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool {
+        // `path` is only extended by a successful hit test, so a panic leaves it unchanged
+        let hit = std::cell::RefCell::new(Vec::new());
+        let is_hit = self.with_active(BoundaryPhase::Input, |widget_tree| {
+            let mut hit = hit.borrow_mut();
+            hit.clear();
+            widget_tree.hit_test(id, position, to_window, ctx, &mut hit)
+        });
+        path.append(&mut hit.borrow_mut());
+        is_hit
+    }
+
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot> {
+        self.with_active(BoundaryPhase::Layout, |widget_tree| {
+            widget_tree.snapshot(id, to_window)
+        })
+    }
+
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree> {
+        self.with_active(BoundaryPhase::Render, |widget_tree| {
+            widget_tree.find_rendered(id, target)
+        })
+    }
+
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext) {
+        self.with_active_mut(BoundaryPhase::Input, |widget_tree| {
+            widget_tree.set_focus(path, ctx)
+        });
+    }
+
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<E> {
+        let mut events = self.with_active_mut(BoundaryPhase::Input, |widget_tree| {
+            widget_tree.take_deferred_events(ctx)
+        });
+        events.extend(self.outbox.lock().drain(..));
+        events
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{
+        metrics::Arrangement,
+        test_kit::TestContext,
+        ui::{InvalidationHandle, Widget, WidgetFrame},
+    };
+
+    // a leaf of the given size, or one that panics when measured
+    struct Leaf(Option<[f32; 2]>);
+
+    impl Dom<String> for Leaf {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::<Leaf, _, String, ()>::new(
+                None,
+                vec![],
+                vec![],
+                LeafNode(self.0),
+            ))
+        }
+    }
+
+    struct LeafNode(Option<[f32; 2]>);
+
+    impl Widget<Leaf, String, ()> for LeafNode {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a Leaf,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, (), u128)> {
+            self.0 = dom.0;
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut (), &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &())],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            self.0.expect("broken leaf")
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &())],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &(), &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::new()
+        }
+    }
+
+    fn boundary(content: Option<[f32; 2]>, reset_key: u64) -> ErrorBoundary<String> {
+        ErrorBoundary::new(Leaf(content), Leaf(Some([1.0, 1.0])))
+            .on_error(|error| Some(error.to_string()))
+            .reset_key(reset_key)
+    }
+
+    fn measure(widget_tree: &dyn AnyWidgetFrame<String>) -> [f32; 2] {
+        let constraints = Constraints::new([0.0, 100.0], [0.0, 100.0]);
+        let test = TestContext::builder().build();
+        widget_tree.measure(&constraints, test.widget_context())
+    }
+
+    #[tokio::test]
+    async fn failed_content_is_replaced_until_the_reset_key_changes() {
+        let mut widget_tree = boundary(None, 0).build_widget_tree();
+        widget_tree.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        assert_eq!(measure(&*widget_tree), [1.0, 1.0]);
+
+        // the same key keeps the fallback even though the content would work now
+        widget_tree
+            .update_widget_tree(&boundary(Some([5.0, 5.0]), 0))
+            .await
+            .unwrap();
+        assert_eq!(measure(&*widget_tree), [1.0, 1.0]);
+
+        widget_tree
+            .update_widget_tree(&boundary(Some([5.0, 5.0]), 1))
+            .await
+            .unwrap();
+        assert_eq!(measure(&*widget_tree), [5.0, 5.0]);
+    }
+
+    #[test]
+    fn panic_messages_are_kept() {
+        let payload = catch_unwind(|| panic!("index {} out of range", 3)).unwrap_err();
+        let error = BoundaryError::from_panic(BoundaryPhase::Render, &*payload);
+        assert_eq!(error.message, "index 3 out of range");
+        assert_eq!(
+            error.to_string(),
+            "panicked during render: index 3 out of range"
+        );
+    }
+}
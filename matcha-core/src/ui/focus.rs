@@ -0,0 +1,308 @@
+//! Moving keyboard focus between widgets with Tab and Shift+Tab.
+//!
+//! Widgets that take keyboard focus, e.g. text fields, say so with
+//! [`Widget::accepts_focus`](super::Widget::accepts_focus). Tab moves focus to the next of them
+//! and Shift+Tab to the previous one, wrapping around at the ends. By default they follow the
+//! order of the tree; [`LayoutStyle::tab_index`](super::LayoutStyle::tab_index) puts widgets
+//! with a positive index first, in ascending order, and takes widgets with a negative index
+//! out of the Tab order, so they are only focused by clicking.
+//!
+//! A widget with [`LayoutStyle::focus_scope`](super::LayoutStyle::focus_scope), e.g. a dialog,
+//! keeps focus inside: while one of its descendants has focus, Tab cycles through the
+//! focusable widgets of the scope only. Scopes nest, and the innermost one around the focused
+//! widget applies. Without a focused widget, Tab starts at the first widget of the window.
+//!
+//! A focused widget that [captures Tab](super::Widget::captures_tab), e.g. a multi-line text
+//! area, gets the key press instead; Ctrl+Tab moves focus out of it. The order is computed
+//! from the [`WidgetSnapshot`] of the last frame, so hidden widgets are skipped.
+
+use log::trace;
+use winit::keyboard::NamedKey;
+
+use crate::{
+    context::WidgetContext,
+    device_input::{ElementState, Key, KeyInput},
+};
+
+use super::{AnyWidgetFrame, WidgetSnapshot, hit_test::snapshot};
+
+/// Keyboard focus of a widget that takes focus, as recorded in a [`WidgetSnapshot`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FocusState {
+    pub focused: bool,
+    /// The widget handles Tab itself while focused.
+    pub captures_tab: bool,
+}
+
+/// Where focus moves from the focused widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    Next,
+    Previous,
+}
+
+impl FocusDirection {
+    /// The direction a Tab press moves focus in: backwards with Shift. `None` for other keys
+    /// and for Tab with Alt or Super, which the platform may use to switch windows.
+    pub fn from_key(key: &KeyInput) -> Option<Self> {
+        let is_tab_press = matches!(key.state(), ElementState::Pressed(_))
+            && *key.logical_key() == Key::Named(NamedKey::Tab);
+        if !is_tab_press || key.alt_held() || key.super_held() {
+            return None;
+        }
+        Some(if key.shift_held() {
+            Self::Previous
+        } else {
+            Self::Next
+        })
+    }
+}
+
+/// The widget focus moves to from the focused widget of `root` in `direction`, as the keys
+/// from the children of `root` down to it. `None` if no widget in reach takes focus.
+pub fn next_focus(root: &WidgetSnapshot, direction: FocusDirection) -> Option<Vec<u128>> {
+    let current = focused_path(root, &mut Vec::new());
+    let (scope, scope_path) = match &current {
+        Some(path) => innermost_scope(root, path),
+        None => (root, Vec::new()),
+    };
+
+    let mut candidates = Vec::new();
+    collect_focusable(scope, &mut scope_path.clone(), &mut candidates);
+    // positive indices first; the sort is stable, so the rest keep their tree order
+    candidates.retain(|(_, tab_index)| tab_index.is_none_or(|index| index >= 0));
+    candidates.sort_by_key(|(_, tab_index)| match *tab_index {
+        Some(index) if index > 0 => (0, index),
+        _ => (1, 0),
+    });
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let len = candidates.len();
+    let position =
+        current.and_then(|current| candidates.iter().position(|(path, _)| *path == current));
+    let target = match (position, direction) {
+        (Some(position), FocusDirection::Next) => (position + 1) % len,
+        (Some(position), FocusDirection::Previous) => (position + len - 1) % len,
+        (None, FocusDirection::Next) => 0,
+        (None, FocusDirection::Previous) => len - 1,
+    };
+    Some(candidates.swap_remove(target).0)
+}
+
+/// Moves focus in the tree rooted at `root` from its focused widget in `direction`, using the
+/// layout of the last frame. Returns `false` and leaves focus unchanged if no widget in reach
+/// takes focus.
+pub fn move_focus<E: 'static>(
+    root: &mut dyn AnyWidgetFrame<E>,
+    direction: FocusDirection,
+    ctx: &WidgetContext,
+) -> bool {
+    let Some(path) = snapshot(root).and_then(|tree| next_focus(&tree, direction)) else {
+        return false;
+    };
+    trace!("move_focus: moving focus {direction:?} to {path:?}");
+    root.set_focus(Some(&path), ctx);
+    true
+}
+
+/// Moves focus for `key` if it is a Tab press the focused widget does not capture. Returns
+/// whether focus moved, in which case the key press goes no further.
+pub(crate) fn tab_navigation<E: 'static>(
+    root: &mut dyn AnyWidgetFrame<E>,
+    key: &KeyInput,
+    ctx: &WidgetContext,
+) -> bool {
+    let Some(direction) = FocusDirection::from_key(key) else {
+        return false;
+    };
+    let Some(tree) = snapshot(root) else {
+        return false;
+    };
+    let captured = tree
+        .focused()
+        .and_then(|widget| widget.focus)
+        .is_some_and(|focus| focus.captures_tab);
+    if captured && !key.ctrl_held() {
+        return false;
+    }
+    let Some(path) = next_focus(&tree, direction) else {
+        return false;
+    };
+    trace!("tab_navigation: moving focus {direction:?} to {path:?}");
+    root.set_focus(Some(&path), ctx);
+    true
+}
+
+/// Keys from the children of `widget` down to the focused widget.
+fn focused_path(widget: &WidgetSnapshot, path: &mut Vec<u128>) -> Option<Vec<u128>> {
+    if widget.focus.is_some_and(|focus| focus.focused) {
+        return Some(path.clone());
+    }
+    for child in &widget.children {
+        let Some(id) = child.id else {
+            continue;
+        };
+        path.push(id);
+        let found = focused_path(child, path);
+        path.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    None
+}
+
+/// The innermost focus scope above the widget at `path`, with its own path. The root when
+/// there is none.
+fn innermost_scope<'a>(root: &'a WidgetSnapshot, path: &[u128]) -> (&'a WidgetSnapshot, Vec<u128>) {
+    let mut scope = (root, 0);
+    let mut widget = root;
+    for (depth, id) in path.iter().enumerate() {
+        if widget.focus_scope {
+            scope = (widget, depth);
+        }
+        match widget.children.iter().find(|child| child.id == Some(*id)) {
+            Some(child) => widget = child,
+            None => break,
+        }
+    }
+    (scope.0, path[..scope.1].to_vec())
+}
+
+/// Appends the path and tab index of every widget taking focus in the subtree of `widget` at
+/// `path`, in tree order.
+fn collect_focusable(
+    widget: &WidgetSnapshot,
+    path: &mut Vec<u128>,
+    out: &mut Vec<(Vec<u128>, Option<i32>)>,
+) {
+    if widget.focus.is_some() {
+        out.push((path.clone(), widget.tab_index));
+    }
+    for child in &widget.children {
+        let Some(id) = child.id else {
+            continue;
+        };
+        path.push(id);
+        collect_focusable(child, path, out);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn widget(id: u128, children: Vec<WidgetSnapshot>) -> WidgetSnapshot {
+        WidgetSnapshot {
+            label: None,
+            id: Some(id),
+            bounds: [[0.0, 0.0], [10.0, 10.0]],
+            focus: None,
+            tab_index: None,
+            focus_scope: false,
+            children,
+        }
+    }
+
+    fn field(id: u128) -> WidgetSnapshot {
+        WidgetSnapshot {
+            focus: Some(FocusState::default()),
+            ..widget(id, Vec::new())
+        }
+    }
+
+    fn root(children: Vec<WidgetSnapshot>) -> WidgetSnapshot {
+        WidgetSnapshot {
+            id: None,
+            ..widget(0, children)
+        }
+    }
+
+    fn focus(mut tree: WidgetSnapshot, path: &[u128]) -> WidgetSnapshot {
+        let mut widget = &mut tree;
+        for id in path {
+            widget = widget
+                .children
+                .iter_mut()
+                .find(|child| child.id == Some(*id))
+                .expect("path leads to a widget");
+        }
+        widget.focus = Some(FocusState {
+            focused: true,
+            captures_tab: false,
+        });
+        tree
+    }
+
+    #[test]
+    fn tab_follows_the_tree_and_wraps_around() {
+        let tree = root(vec![field(1), widget(2, vec![field(3), field(4)])]);
+        assert_eq!(next_focus(&tree, FocusDirection::Next), Some(vec![1]));
+        assert_eq!(
+            next_focus(&tree, FocusDirection::Previous),
+            Some(vec![2, 4])
+        );
+
+        let focused = focus(tree.clone(), &[1]);
+        assert_eq!(next_focus(&focused, FocusDirection::Next), Some(vec![2, 3]));
+        let focused = focus(tree, &[2, 4]);
+        assert_eq!(next_focus(&focused, FocusDirection::Next), Some(vec![1]));
+        assert_eq!(
+            next_focus(&focused, FocusDirection::Previous),
+            Some(vec![2, 3])
+        );
+
+        assert_eq!(
+            next_focus(&root(vec![widget(1, Vec::new())]), FocusDirection::Next),
+            None
+        );
+    }
+
+    #[test]
+    fn positive_tab_indices_come_first_and_negative_ones_are_skipped() {
+        let indexed = |id, index| WidgetSnapshot {
+            tab_index: Some(index),
+            ..field(id)
+        };
+        let tree = root(vec![
+            field(1),
+            indexed(2, 2),
+            indexed(3, -1),
+            indexed(4, 1),
+            field(5),
+        ]);
+
+        let mut order = Vec::new();
+        let mut current = tree.clone();
+        for _ in 0..4 {
+            let next = next_focus(&current, FocusDirection::Next).unwrap_or_default();
+            order.push(next[0]);
+            current = focus(tree.clone(), &next);
+        }
+        assert_eq!(order, vec![4, 2, 1, 5]);
+    }
+
+    #[test]
+    fn focus_scopes_keep_tab_inside() {
+        let dialog = WidgetSnapshot {
+            focus_scope: true,
+            ..widget(2, vec![field(3), field(4)])
+        };
+        let tree = root(vec![field(1), dialog, field(5)]);
+
+        let focused = focus(tree.clone(), &[2, 4]);
+        assert_eq!(next_focus(&focused, FocusDirection::Next), Some(vec![2, 3]));
+        let focused = focus(tree.clone(), &[2, 3]);
+        assert_eq!(
+            next_focus(&focused, FocusDirection::Previous),
+            Some(vec![2, 4])
+        );
+
+        // outside the scope, Tab enters it like any other widget
+        let focused = focus(tree, &[1]);
+        assert_eq!(next_focus(&focused, FocusDirection::Next), Some(vec![2, 3]));
+    }
+}
@@ -0,0 +1,561 @@
+//! Moving widget subtrees between parents.
+//!
+//! Children are matched to their old widgets per parent, so a `Dom` node that moves to another
+//! parent gets a freshly built widget and loses its state. A subtree wrapped in
+//! [`global_keyed`] is matched by its [`GlobalKey`] across the whole view instead: wherever
+//! the key shows up in the next view, the existing widgets are moved there and updated, keeping
+//! their state and caches. This is what drag-to-reorder lists and dockable panels need:
+//!
+//! ```ignore
+//! fn view(model: &Model) -> Box<dyn Dom<Event>> {
+//!     let panel = global_keyed(GlobalKey::new(&"inspector"), inspector(model));
+//!     // the inspector keeps its scroll position and input state when docked elsewhere
+//!     if model.inspector_on_side {
+//!         Box::new(Row::new().push(editor(model)).push(panel))
+//!     } else {
+//!         Box::new(Column::new().push(editor(model)).push(panel))
+//!     }
+//! }
+//! ```
+//!
+//! A key must appear at most once in a view. Widgets moved into a freshly built parent are
+//! updated once the update of the window finished, since building cannot wait for them; subtrees
+//! whose key disappeared from the view are dropped then.
+
+use std::any::{Any, TypeId};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+use futures::future::BoxFuture;
+use log::{trace, warn};
+use parking_lot::Mutex;
+use renderer::RenderNode;
+use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateNotifier};
+
+use crate::{
+    capture::{CaptureTarget, CapturedSubtree},
+    context::WidgetContext,
+    device_input::DeviceInput,
+    metrics::Constraints,
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, HitTestEntry, UpdateWidgetError,
+        WidgetSnapshot, keyed,
+    },
+};
+
+/// Identifies a widget subtree across the whole view. See the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlobalKey(u128);
+
+impl GlobalKey {
+    /// Derives a key from a stable value. Equal values give equal keys within a process.
+    pub fn new<K: Hash + ?Sized>(key: &K) -> Self {
+        Self(keyed::child_id(key))
+    }
+
+    /// A key different from every other key made with `unique`. Store it in the model to
+    /// reuse it across views.
+    pub fn unique() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(u128::MAX - u128::from(NEXT.fetch_add(1, Ordering::Relaxed)))
+    }
+}
+
+/// Wraps `dom` so that its widgets follow `key` to wherever it appears in the next view.
+pub fn global_keyed<E: 'static>(key: GlobalKey, dom: Box<dyn Dom<E>>) -> Box<dyn Dom<E>> {
+    Box::new(GlobalKeyedDom {
+        key,
+        dom: Arc::from(dom),
+    })
+}
+
+// the widgets of a key, taken out while they are updated or moved to another parent
+type Slot<E> = Mutex<Option<Box<dyn AnyWidgetFrame<E>>>>;
+
+// an `Arc<Slot<E>>`
+type AnySlot = Arc<dyn Any + Send + Sync>;
+
+// the live slot of every key, per event type
+static REGISTRY: LazyLock<Mutex<fxhash::FxHashMap<(TypeId, GlobalKey), AnySlot>>> =
+    LazyLock::new(Default::default);
+
+// updates of moved widgets that were adopted while building, erased over the event type
+type DeferredUpdate = Box<dyn FnOnce() -> BoxFuture<'static, ()> + Send>;
+
+static DEFERRED: LazyLock<Mutex<Vec<DeferredUpdate>>> = LazyLock::new(Default::default);
+
+fn register<E: 'static>(key: GlobalKey, slot: &Arc<Slot<E>>) {
+    let slot: AnySlot = slot.clone();
+    REGISTRY.lock().insert((TypeId::of::<E>(), key), slot);
+}
+
+/// Takes the widgets of `key` from their current parent, or from the subtrees that left the
+/// view in this update.
+fn adopt<E: 'static>(key: GlobalKey) -> Option<Box<dyn AnyWidgetFrame<E>>> {
+    let slot = REGISTRY
+        .lock()
+        .get(&(TypeId::of::<E>(), key))?
+        .clone()
+        .downcast::<Slot<E>>()
+        .ok()?;
+    slot.lock().take()
+}
+
+/// Queues the update of the widgets in `slot`, moved into a freshly built parent, to `dom`.
+fn defer_update<E: 'static>(slot: &Arc<Slot<E>>, dom: &Arc<dyn Dom<E>>) {
+    let (slot, dom) = (slot.clone(), dom.clone());
+    DEFERRED
+        .lock()
+        .push(Box::new(move || -> BoxFuture<'static, ()> {
+            Box::pin(async move {
+                // not locked across the update, which may move other keyed subtrees
+                let taken = slot.lock().take();
+                let Some(mut widget_tree) = taken else {
+                    return;
+                };
+                if widget_tree.update_widget_tree(&*dom).await.is_err() {
+                    widget_tree = dom.build_widget_tree();
+                }
+                *slot.lock() = Some(widget_tree);
+            })
+        }));
+}
+
+/// Applies the updates of widgets moved into freshly built parents, then drops the subtrees
+/// whose key is no longer used by any widget. Called once a window finished updating its
+/// widget tree.
+pub(crate) async fn release_detached() {
+    // deferred updates may build parents that adopt more widgets
+    loop {
+        let deferred = std::mem::take(&mut *DEFERRED.lock());
+        if deferred.is_empty() {
+            break;
+        }
+        trace!(
+            "global_key::release_detached: updating {} moved subtrees",
+            deferred.len()
+        );
+        for update in deferred {
+            update().await;
+        }
+    }
+
+    loop {
+        let detached: Vec<_> = {
+            let mut registry = REGISTRY.lock();
+            let keys: Vec<_> = registry
+                .iter()
+                .filter(|(_, slot)| Arc::strong_count(slot) == 1)
+                .map(|(key, _)| *key)
+                .collect();
+            keys.iter().filter_map(|key| registry.remove(key)).collect()
+        };
+        if detached.is_empty() {
+            return;
+        }
+        trace!(
+            "global_key::release_detached: dropping {} subtrees",
+            detached.len()
+        );
+        // subtrees may hold keyed subtrees themselves, which are detached by dropping them
+        drop(detached);
+    }
+}
+
+pub struct GlobalKeyedDom<E: 'static> {
+    key: GlobalKey,
+    // shared with a deferred update of the widgets it adopts
+    dom: Arc<dyn Dom<E>>,
+}
+
+#[async_trait::async_trait]
+impl<E: 'static> Dom<E> for GlobalKeyedDom<E> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<E>> {
+        match adopt::<E>(self.key) {
+            Some(widget_tree) => {
+                trace!("GlobalKeyedDom: moving the widgets of {:?}", self.key);
+                let widget = GlobalKeyedWidget::new(self.key, widget_tree);
+                // building cannot wait for the update, so it runs once the window's update finished
+                defer_update(&widget.slot, &self.dom);
+                Box::new(widget)
+            }
+            None => Box::new(GlobalKeyedWidget::new(
+                self.key,
+                self.dom.build_widget_tree(),
+            )),
+        }
+    }
+}
+
+pub struct GlobalKeyedWidget<E: 'static> {
+    key: GlobalKey,
+    // shared with the registry, so that another parent can take the widgets
+    slot: Arc<Slot<E>>,
+    // label of the widgets, which cannot be borrowed through the slot
+    label: Option<String>,
+}
+
+impl<E: 'static> GlobalKeyedWidget<E> {
+    fn new(key: GlobalKey, widget_tree: Box<dyn AnyWidgetFrame<E>>) -> Self {
+        let label = widget_tree.label().map(str::to_string);
+        let slot = Arc::new(Mutex::new(Some(widget_tree)));
+        register(key, &slot);
+        Self { key, slot, label }
+    }
+}
+
+impl<E: 'static> AnyWidget<E> for GlobalKeyedWidget<E> {
+    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<E> {
+        self.slot.lock().as_mut()?.device_input(event, ctx)
+    }
+
+    fn is_inside(&self, position: [f32; 2], ctx: &WidgetContext) -> bool {
+        self.slot
+            .lock()
+            .as_ref()
+            .is_some_and(|widget_tree| widget_tree.is_inside(position, ctx))
+    }
+
+    fn measure(&self, constraints: &Constraints, ctx: &WidgetContext) -> [f32; 2] {
+        match self.slot.lock().as_ref() {
+            Some(widget_tree) => widget_tree.measure(constraints, ctx),
+            None => constraints.min_size(),
+        }
+    }
+
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        self.slot.lock().as_ref()?.baseline(constraints, ctx)
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.slot.lock().as_ref().map_or(0.0, |widget_tree| {
+            widget_tree.min_intrinsic_width(height, ctx)
+        })
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.slot.lock().as_ref().map_or(0.0, |widget_tree| {
+            widget_tree.max_intrinsic_width(height, ctx)
+        })
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.slot.lock().as_ref().map_or(0.0, |widget_tree| {
+            widget_tree.min_intrinsic_height(width, ctx)
+        })
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.slot.lock().as_ref().map_or(0.0, |widget_tree| {
+            widget_tree.max_intrinsic_height(width, ctx)
+        })
+    }
+
+    fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
+        match self.slot.lock().as_ref() {
+            Some(widget_tree) => widget_tree.render(background, ctx),
+            None => Arc::new(RenderNode::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: 'static> AnyWidgetFrame<E> for GlobalKeyedWidget<E> {
+    fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    fn need_redraw(&self) -> bool {
+        self.slot
+            .lock()
+            .as_ref()
+            .is_some_and(|widget_tree| widget_tree.need_redraw())
+    }
+
+    async fn update_widget_tree(&mut self, dom: &dyn Dom<E>) -> Result<(), UpdateWidgetError> {
+        let dom = (dom as &dyn Any)
+            .downcast_ref::<GlobalKeyedDom<E>>()
+            .filter(|dom| dom.key == self.key)
+            .ok_or(UpdateWidgetError::TypeMismatch)?;
+
+        // not locked across the update, which may move other keyed subtrees
+        let taken = self.slot.lock().take();
+        let widget_tree = match taken {
+            Some(mut widget_tree) => match widget_tree.update_widget_tree(&*dom.dom).await {
+                Ok(()) => widget_tree,
+                Err(UpdateWidgetError::TypeMismatch) => dom.dom.build_widget_tree(),
+            },
+            None => {
+                warn!(
+                    "GlobalKeyedWidget: {:?} is used more than once in the view; building its widgets again",
+                    self.key
+                );
+                dom.dom.build_widget_tree()
+            }
+        };
+        self.label = widget_tree.label().map(str::to_string);
+        *self.slot.lock() = Some(widget_tree);
+        Ok(())
+    }
+
+    async fn set_model_update_notifier(&self, notifier: &UpdateNotifier) {
+        // not locked across the await
+        let taken = self.slot.lock().take();
+        if let Some(widget_tree) = taken {
+            widget_tree.set_model_update_notifier(notifier).await;
+            *self.slot.lock() = Some(widget_tree);
+        }
+    }
+
+    fn arrange(&self, bounds: [f32; 2], ctx: &WidgetContext) {
+        if let Some(widget_tree) = self.slot.lock().as_ref() {
+            widget_tree.arrange(bounds, ctx);
+        }
+    }
+
+    fn update_dirty_flags(&mut self, rearrange_flags: BackPropDirty, redraw_flags: BackPropDirty) {
+        if let Some(widget_tree) = self.slot.lock().as_mut() {
+            widget_tree.update_dirty_flags(rearrange_flags, redraw_flags);
+        }
+    }
+
+    fn invalidate_render_cache(&mut self) {
+        if let Some(widget_tree) = self.slot.lock().as_mut() {
+            widget_tree.invalidate_render_cache();
+        }
+    }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if let Some(widget_tree) = self.slot.lock().as_mut() {
+            widget_tree.update_gpu_device(device, queue);
+        }
+    }
+
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        if let Some(widget_tree) = self.slot.lock().as_mut() {
+            widget_tree.update_lifecycle(visible_rect, ctx);
+        }
+    }
+
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        if let Some(widget_tree) = self.slot.lock().as_mut() {
+            widget_tree.prepare(visible_rect, ctx);
+        }
+    }
+
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool {
+        self.slot
+            .lock()
+            .as_ref()
+            .is_some_and(|widget_tree| widget_tree.hit_test(id, position, to_window, ctx, path))
+    }
+
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot> {
+        self.slot.lock().as_ref()?.snapshot(id, to_window)
+    }
+
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree> {
+        self.slot.lock().as_ref()?.find_rendered(id, target)
+    }
+
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext) {
+        if let Some(widget_tree) = self.slot.lock().as_mut() {
+            widget_tree.set_focus(path, ctx);
+        }
+    }
+
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<E> {
+        self.slot
+            .lock()
+            .as_mut()
+            .map_or_else(Vec::new, |widget_tree| {
+                widget_tree.take_deferred_events(ctx)
+            })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::{
+        metrics::Arrangement,
+        ui::{InvalidationHandle, Widget, WidgetFrame},
+    };
+
+    struct Leaf {
+        builds: Arc<AtomicUsize>,
+    }
+
+    impl Dom<()> for Leaf {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<()>> {
+            self.builds.fetch_add(1, Ordering::SeqCst);
+            Box::new(WidgetFrame::<Leaf, _, (), ()>::new(
+                Some("leaf".to_string()),
+                vec![],
+                vec![],
+                Container,
+            ))
+        }
+    }
+
+    // a parent with keyed children
+    struct Parent {
+        children: Vec<(Box<dyn Dom<()>>, u128)>,
+    }
+
+    impl Dom<()> for Parent {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<()>> {
+            Box::new(WidgetFrame::<Parent, _, (), ()>::new(
+                None,
+                self.children
+                    .iter()
+                    .map(|(child, _)| (child.build_widget_tree(), ()))
+                    .collect(),
+                self.children.iter().map(|(_, id)| *id).collect(),
+                Container,
+            ))
+        }
+    }
+
+    struct Container;
+
+    impl<D: Dom<()>> Widget<D, (), ()> for Container {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a D,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<()>, (), u128)> {
+            (dom as &dyn Any)
+                .downcast_ref::<Parent>()
+                .map(|parent| {
+                    parent
+                        .children
+                        .iter()
+                        .map(|(child, id)| (&**child, (), *id))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<()>, &mut (), &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<()> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<()>, &())],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            [0.0, 0.0]
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            children: &[(&dyn AnyWidget<()>, &())],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            children
+                .iter()
+                .map(|_| Arrangement::new([0.0, 0.0], nalgebra::Matrix4::identity()))
+                .collect()
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<()>, &(), &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::new()
+        }
+    }
+
+    fn view(key: GlobalKey, builds: &Arc<AtomicUsize>, in_first: bool) -> Parent {
+        view_with_ids(key, builds, in_first, [1, 2])
+    }
+
+    fn view_with_ids(
+        key: GlobalKey,
+        builds: &Arc<AtomicUsize>,
+        in_first: bool,
+        ids: [u128; 2],
+    ) -> Parent {
+        let leaf = global_keyed(
+            key,
+            Box::new(Leaf {
+                builds: builds.clone(),
+            }),
+        );
+        let (first, second) = if in_first {
+            (vec![(leaf, 7)], vec![])
+        } else {
+            (vec![], vec![(leaf, 7)])
+        };
+        let parent = |children| -> Box<dyn Dom<()>> { Box::new(Parent { children }) };
+        Parent {
+            children: vec![(parent(first), ids[0]), (parent(second), ids[1])],
+        }
+    }
+
+    // both directions in one test: `release_detached` sweeps every key of the process
+    #[tokio::test]
+    async fn moves_the_widgets_to_the_new_parent() {
+        let key = GlobalKey::unique();
+        let builds = Arc::new(AtomicUsize::new(0));
+        let mut root = view(key, &builds, true).build_widget_tree();
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        // the old parent is updated first and leaves the widgets behind
+        root.update_widget_tree(&view(key, &builds, false))
+            .await
+            .unwrap();
+        release_detached().await;
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        // the new parent is updated first and takes the widgets from the old one
+        root.update_widget_tree(&view(key, &builds, true))
+            .await
+            .unwrap();
+        release_detached().await;
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        // a freshly built parent takes the widgets and updates them afterwards
+        root.update_widget_tree(&view_with_ids(key, &builds, false, [1, 3]))
+            .await
+            .unwrap();
+        assert!(!DEFERRED.lock().is_empty());
+        release_detached().await;
+        assert!(DEFERRED.lock().is_empty());
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        // a key that left the view is dropped
+        drop(root);
+        release_detached().await;
+        assert!(adopt::<()>(key).is_none());
+    }
+}
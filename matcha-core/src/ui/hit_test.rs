@@ -0,0 +1,375 @@
+//! Finding the widgets under a point.
+//!
+//! Useful for context menus, tooltips and debugging tools that need to know what the pointer
+//! is over without routing an event through the tree. The result uses the layout of the last
+//! frame and follows [`is_inside`](super::AnyWidget::is_inside), so it agrees with how pointer
+//! events are delivered.
+//!
+//! [`WidgetSnapshot`] describes the whole laid-out tree instead, e.g. to locate a widget by
+//! its label in a test.
+//!
+//! Positions are carried into each widget's own coordinates through the transforms of its
+//! ancestors, so rotated or scaled widgets are hit where they appear. [`HitShape`] narrows a
+//! widget's rectangle down to the shape it actually draws.
+
+use std::sync::Arc;
+
+use gpu_utils::texture_atlas::{AtlasRegion, RegionError};
+
+use crate::context::WidgetContext;
+
+use super::{AnyWidgetFrame, focus::FocusState};
+
+/// One widget on a [`HitTestPath`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitTestEntry {
+    /// Label of the widget, see [`AnyWidgetFrame::label`].
+    pub label: Option<String>,
+    /// Key of the widget among its siblings. `None` for the root.
+    pub id: Option<u128>,
+    /// Bounding box of the widget as `[min, max]` in window coordinates, without margin.
+    pub bounds: [[f32; 2]; 2],
+    /// The tested point in the widget's local coordinates.
+    pub local_position: [f32; 2],
+}
+
+/// The widgets under a point, from the root to the innermost one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HitTestPath {
+    entries: Vec<HitTestEntry>,
+}
+
+impl HitTestPath {
+    /// The innermost widget under the point.
+    pub fn target(&self) -> Option<&HitTestEntry> {
+        self.entries.last()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> &[HitTestEntry] {
+        &self.entries
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HitTestEntry> {
+        self.entries.iter()
+    }
+
+    /// The innermost widget on the path with `label`.
+    pub fn find_label(&self, label: &str) -> Option<&HitTestEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.label.as_deref() == Some(label))
+    }
+
+    /// Labels on the path, from the root. Unlabeled widgets are skipped.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.label.as_deref())
+    }
+}
+
+impl IntoIterator for HitTestPath {
+    type Item = HitTestEntry;
+    type IntoIter = std::vec::IntoIter<HitTestEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// The widgets of the tree rooted at `root` under `position` (window coordinates).
+///
+/// Empty when the point is outside the root or the tree has not been laid out yet.
+pub fn hit_test<E: 'static>(
+    root: &dyn AnyWidgetFrame<E>,
+    position: [f32; 2],
+    ctx: &WidgetContext,
+) -> HitTestPath {
+    let mut entries = Vec::new();
+    root.hit_test(
+        None,
+        position,
+        &nalgebra::Matrix4::identity(),
+        ctx,
+        &mut entries,
+    );
+    HitTestPath { entries }
+}
+
+/// A laid-out widget and its children.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidgetSnapshot {
+    pub label: Option<String>,
+    /// Key of the widget among its siblings. `None` for the root.
+    pub id: Option<u128>,
+    /// Bounding box of the widget as `[min, max]` in window coordinates, without margin.
+    pub bounds: [[f32; 2]; 2],
+    /// Keyboard focus of the widget, `None` if it does not take focus.
+    pub focus: Option<FocusState>,
+    /// [`LayoutStyle::tab_index`](super::LayoutStyle::tab_index) of the widget.
+    pub tab_index: Option<i32>,
+    /// [`LayoutStyle::focus_scope`](super::LayoutStyle::focus_scope) of the widget.
+    pub focus_scope: bool,
+    /// Children that have been laid out, in drawing order.
+    pub children: Vec<WidgetSnapshot>,
+}
+
+impl WidgetSnapshot {
+    /// The first widget with `label` in depth-first order, including `self`.
+    pub fn find(&self, label: &str) -> Option<&WidgetSnapshot> {
+        if self.label.as_deref() == Some(label) {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(label))
+    }
+
+    /// All widgets with `label` in depth-first order, including `self`.
+    pub fn find_all(&self, label: &str) -> Vec<&WidgetSnapshot> {
+        let mut found = Vec::new();
+        self.visit(&mut |widget| {
+            if widget.label.as_deref() == Some(label) {
+                found.push(widget);
+            }
+        });
+        found
+    }
+
+    /// The widget with keyboard focus, including `self`.
+    pub fn focused(&self) -> Option<&WidgetSnapshot> {
+        if self.focus.is_some_and(|focus| focus.focused) {
+            return Some(self);
+        }
+        self.children.iter().find_map(WidgetSnapshot::focused)
+    }
+
+    /// Calls `f` on `self` and every descendant in depth-first order.
+    pub fn visit<'a>(&'a self, f: &mut impl FnMut(&'a WidgetSnapshot)) {
+        f(self);
+        for child in &self.children {
+            child.visit(f);
+        }
+    }
+
+    /// Center of [`bounds`](Self::bounds).
+    pub fn center(&self) -> [f32; 2] {
+        let [min, max] = self.bounds;
+        [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0]
+    }
+}
+
+/// The laid-out tree rooted at `root`, or `None` if it has not been laid out yet.
+pub fn snapshot<E: 'static>(root: &dyn AnyWidgetFrame<E>) -> Option<WidgetSnapshot> {
+    root.snapshot(None, &nalgebra::Matrix4::identity())
+}
+
+// MARK: Hit shapes
+
+/// The part of a widget's bounds that counts as the widget for hit testing.
+///
+/// Positions are relative to the top left corner of the bounds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum HitShape {
+    /// The whole bounds.
+    #[default]
+    Rect,
+    /// The bounds with rounded corners, radii from the top left corner clockwise.
+    RoundedRect { radii: [f32; 4] },
+    /// The ellipse inscribed in the bounds.
+    Ellipse,
+    /// The inside of a polygon in pixels, by the even-odd rule.
+    Polygon(Arc<[[f32; 2]]>),
+    /// Where an alpha mask stretched over the bounds is more opaque than `threshold`.
+    AlphaMask { mask: AlphaMask, threshold: u8 },
+}
+
+impl HitShape {
+    pub fn contains(&self, position: [f32; 2], bounds: [f32; 2]) -> bool {
+        let [x, y] = position;
+        let [width, height] = bounds;
+        if !(0.0 <= x && x <= width && 0.0 <= y && y <= height) {
+            return false;
+        }
+
+        match self {
+            HitShape::Rect => true,
+            HitShape::RoundedRect { radii } => {
+                let max_radius = width.min(height) / 2.0;
+                // corner centers, inset by their radius from top left, top right, ...
+                let corners = [
+                    ([0.0, 0.0], [1.0, 1.0]),
+                    ([width, 0.0], [-1.0, 1.0]),
+                    ([width, height], [-1.0, -1.0]),
+                    ([0.0, height], [1.0, -1.0]),
+                ];
+                corners
+                    .iter()
+                    .zip(radii)
+                    .all(|(&(corner, inward), &radius)| {
+                        let radius = radius.clamp(0.0, max_radius);
+                        let center = [
+                            corner[0] + inward[0] * radius,
+                            corner[1] + inward[1] * radius,
+                        ];
+                        // only the square between the corner and the center is rounded
+                        let in_corner =
+                            (x - center[0]) * inward[0] < 0.0 && (y - center[1]) * inward[1] < 0.0;
+                        !in_corner || (x - center[0]).hypot(y - center[1]) <= radius
+                    })
+            }
+            HitShape::Ellipse => {
+                let [rx, ry] = [width / 2.0, height / 2.0];
+                if rx <= 0.0 || ry <= 0.0 {
+                    return false;
+                }
+                let [dx, dy] = [(x - rx) / rx, (y - ry) / ry];
+                dx * dx + dy * dy <= 1.0
+            }
+            HitShape::Polygon(points) => {
+                let mut inside = false;
+                for (i, &[x1, y1]) in points.iter().enumerate() {
+                    let [x0, y0] = points[(i + points.len() - 1) % points.len()];
+                    if (y0 > y) != (y1 > y) && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+            HitShape::AlphaMask { mask, threshold } => mask.alpha_at(position, bounds) > *threshold,
+        }
+    }
+}
+
+/// Opacity of an image, for hitting only the parts of a widget that are drawn.
+#[derive(Clone, PartialEq)]
+pub struct AlphaMask {
+    size: [u32; 2],
+    alpha: Arc<[u8]>,
+}
+
+impl std::fmt::Debug for AlphaMask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlphaMask")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AlphaMask {
+    /// A mask of one alpha value per pixel, in rows. `None` if `alpha` does not have
+    /// `size[0] * size[1]` values.
+    pub fn new(size: [u32; 2], alpha: impl Into<Arc<[u8]>>) -> Option<Self> {
+        let alpha = alpha.into();
+        (alpha.len() == size[0] as usize * size[1] as usize).then_some(Self { size, alpha })
+    }
+
+    /// The alpha channel of 8-bit RGBA or BGRA pixels.
+    pub fn from_rgba8(size: [u32; 2], pixels: &[u8]) -> Option<Self> {
+        Self::new(
+            size,
+            pixels.chunks_exact(4).map(|p| p[3]).collect::<Vec<_>>(),
+        )
+    }
+
+    /// The alpha channel of what was drawn into `region` of the texture atlas.
+    ///
+    /// Reads the region back from the GPU and waits for it, so build the mask once, not
+    /// for every hit test. The region must have an 8-bit RGBA or BGRA format.
+    pub fn from_region(
+        region: &AtlasRegion,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Self, RegionError> {
+        use wgpu::TextureFormat;
+        let format = region.format();
+        if !matches!(
+            format,
+            TextureFormat::Rgba8Unorm
+                | TextureFormat::Rgba8UnormSrgb
+                | TextureFormat::Bgra8Unorm
+                | TextureFormat::Bgra8UnormSrgb
+        ) {
+            return Err(RegionError::DataConsistencyError(format!(
+                "Alpha masks cannot be read from regions of format {format:?}"
+            )));
+        }
+        let pixels = region.read_data(device, queue)?;
+        Self::from_rgba8(region.texture_size(), &pixels).ok_or_else(|| {
+            RegionError::DataConsistencyError("Region data does not match its size".to_string())
+        })
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Alpha at `position` with the mask stretched over `bounds`, 0 outside of them.
+    pub fn alpha_at(&self, position: [f32; 2], bounds: [f32; 2]) -> u8 {
+        let [width, height] = self.size;
+        if width == 0 || height == 0 || bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return 0;
+        }
+        let u = position[0] / bounds[0];
+        let v = position[1] / bounds[1];
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return 0;
+        }
+        let column = ((u * width as f32) as u32).min(width - 1);
+        let row = ((v * height as f32) as u32).min(height - 1);
+        self.alpha[(row * width + column) as usize]
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_narrow_the_bounds() {
+        let bounds = [100.0, 50.0];
+        assert!(HitShape::Rect.contains([0.0, 50.0], bounds));
+        assert!(!HitShape::Rect.contains([100.5, 10.0], bounds));
+
+        let rounded = HitShape::RoundedRect {
+            radii: [20.0, 0.0, 100.0, 0.0],
+        };
+        assert!(!rounded.contains([2.0, 2.0], bounds));
+        assert!(rounded.contains([20.0, 2.0], bounds));
+        assert!(rounded.contains([99.0, 1.0], bounds));
+        // the radius is clamped to half the height
+        assert!(!rounded.contains([98.0, 48.0], bounds));
+        assert!(rounded.contains([75.0, 48.0], bounds));
+
+        assert!(HitShape::Ellipse.contains([50.0, 25.0], bounds));
+        assert!(HitShape::Ellipse.contains([1.0, 25.0], bounds));
+        assert!(!HitShape::Ellipse.contains([5.0, 5.0], bounds));
+
+        // a triangle with its tip at the bottom center
+        let triangle = HitShape::Polygon(Arc::from([[0.0, 0.0], [100.0, 0.0], [50.0, 50.0]]));
+        assert!(triangle.contains([50.0, 40.0], bounds));
+        assert!(!triangle.contains([10.0, 40.0], bounds));
+    }
+
+    #[test]
+    fn alpha_masks_stretch_over_the_bounds() {
+        // opaque on the left, transparent on the right
+        let mask = AlphaMask::from_rgba8([2, 1], &[0, 0, 0, 255, 0, 0, 0, 0]).unwrap();
+        let shape = HitShape::AlphaMask {
+            mask: mask.clone(),
+            threshold: 127,
+        };
+        assert!(shape.contains([10.0, 30.0], [40.0, 40.0]));
+        assert!(!shape.contains([30.0, 30.0], [40.0, 40.0]));
+        assert_eq!(mask.alpha_at([40.0, 40.0], [40.0, 40.0]), 0);
+        assert!(AlphaMask::new([2, 2], vec![0; 3]).is_none());
+    }
+}
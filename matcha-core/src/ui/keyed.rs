@@ -0,0 +1,195 @@
+use std::hash::{Hash, Hasher};
+
+/// Derives a child id from a stable item key.
+///
+/// Collection widgets should identify children by what they show rather than by their
+/// position, so that inserting, removing or reordering items keeps the widgets (and their
+/// state and caches) of the unaffected items. Equal keys give equal ids within a process.
+pub fn child_id<K: Hash + ?Sized>(key: &K) -> u128 {
+    let half = |seed: u64| {
+        let mut hasher = fxhash::FxHasher64::default();
+        seed.hash(&mut hasher);
+        key.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    ((half(0x6b65_7965_645f_6869) as u128) << 64) | half(0x6b65_7965_645f_6c6f) as u128
+}
+
+/// Where a child of the new sequence comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChildSource {
+    /// the old child at this position, which stays in order relative to the other kept ones.
+    Kept(usize),
+    /// the old child at this position, which changed its relative order.
+    Moved(usize),
+    /// no old child has the id.
+    Inserted,
+}
+
+impl ChildSource {
+    /// Position of the old child to reuse, if any.
+    pub(crate) fn old_position(self) -> Option<usize> {
+        match self {
+            ChildSource::Kept(position) | ChildSource::Moved(position) => Some(position),
+            ChildSource::Inserted => None,
+        }
+    }
+}
+
+/// How the children of a frame changed between two updates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ChildDiff {
+    /// source of every new child, in new order.
+    pub(crate) sources: Vec<ChildSource>,
+    /// old positions of the children that are gone, ascending.
+    pub(crate) removed: Vec<usize>,
+}
+
+impl ChildDiff {
+    pub(crate) fn kept(&self) -> usize {
+        self.count(|source| matches!(source, ChildSource::Kept(_)))
+    }
+
+    pub(crate) fn moved(&self) -> usize {
+        self.count(|source| matches!(source, ChildSource::Moved(_)))
+    }
+
+    pub(crate) fn inserted(&self) -> usize {
+        self.count(|source| matches!(source, ChildSource::Inserted))
+    }
+
+    /// `true` when the child sequence is unchanged.
+    pub(crate) fn is_unchanged(&self) -> bool {
+        self.removed.is_empty()
+            && self
+                .sources
+                .iter()
+                .all(|source| matches!(source, ChildSource::Kept(_)))
+    }
+
+    fn count(&self, f: impl Fn(&ChildSource) -> bool) -> usize {
+        self.sources.iter().filter(|source| f(source)).count()
+    }
+}
+
+/// Compares two child id sequences.
+///
+/// Children are matched by id. The largest set of matched children that keeps its relative
+/// order (the longest common subsequence, found as the longest increasing run of old
+/// positions in O(n log n)) is kept; every other matched child is moved. Duplicate ids only
+/// match their first occurrence.
+pub(crate) fn diff_child_ids(old: &[u128], new: &[u128]) -> ChildDiff {
+    let mut old_positions = fxhash::FxHashMap::default();
+    for (position, id) in old.iter().enumerate() {
+        old_positions.entry(*id).or_insert(position);
+    }
+
+    // old position of every new child, or `None` when inserted
+    let matched: Vec<Option<usize>> = new.iter().map(|id| old_positions.remove(id)).collect();
+
+    // patience sorting: tails[k] is the new index ending the increasing run of length k + 1
+    // with the smallest old position; previous links every run to its shorter prefix
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; new.len()];
+    for (index, position) in matched.iter().enumerate() {
+        let Some(position) = *position else {
+            continue;
+        };
+        let k = tails.partition_point(|&tail| matched[tail] < Some(position));
+        previous[index] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(index);
+        } else {
+            tails[k] = index;
+        }
+    }
+
+    let mut kept = vec![false; new.len()];
+    let mut next = tails.last().copied();
+    while let Some(index) = next {
+        kept[index] = true;
+        next = previous[index];
+    }
+
+    let sources = matched
+        .iter()
+        .zip(kept)
+        .map(|(position, kept)| match (*position, kept) {
+            (Some(position), true) => ChildSource::Kept(position),
+            (Some(position), false) => ChildSource::Moved(position),
+            (None, _) => ChildSource::Inserted,
+        })
+        .collect();
+
+    // including later occurrences of duplicate ids, which never match
+    let mut reused = vec![false; old.len()];
+    for position in matched.iter().flatten() {
+        reused[*position] = true;
+    }
+    let removed = (0..old.len())
+        .filter(|&position| !reused[position])
+        .collect();
+
+    ChildDiff { sources, removed }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_id_is_stable_and_distinguishes_keys() {
+        assert_eq!(child_id("item-1"), child_id("item-1"));
+        assert_eq!(child_id(&42u64), child_id(&42u64));
+        assert_ne!(child_id("item-1"), child_id("item-2"));
+    }
+
+    #[test]
+    fn diff_detects_moves_inserts_and_removals() {
+        assert!(diff_child_ids(&[1, 2, 3], &[1, 2, 3]).is_unchanged());
+
+        // moving one item to the front keeps the others in place
+        assert_eq!(
+            diff_child_ids(&[1, 2, 3, 4], &[4, 1, 2, 3]),
+            ChildDiff {
+                sources: vec![
+                    ChildSource::Moved(3),
+                    ChildSource::Kept(0),
+                    ChildSource::Kept(1),
+                    ChildSource::Kept(2),
+                ],
+                removed: vec![],
+            }
+        );
+
+        assert_eq!(
+            diff_child_ids(&[1, 2, 3], &[1, 5, 3, 6]),
+            ChildDiff {
+                sources: vec![
+                    ChildSource::Kept(0),
+                    ChildSource::Inserted,
+                    ChildSource::Kept(2),
+                    ChildSource::Inserted,
+                ],
+                removed: vec![1],
+            }
+        );
+
+        // reversing keeps only a single item in order
+        let reversed = diff_child_ids(&[1, 2, 3, 4], &[4, 3, 2, 1]);
+        assert_eq!((reversed.kept(), reversed.moved()), (1, 3));
+
+        // a repeated id only matches its first occurrence
+        let repeated = diff_child_ids(&[1, 1, 2], &[1, 2, 1]);
+        assert_eq!(
+            repeated.sources,
+            vec![
+                ChildSource::Kept(0),
+                ChildSource::Kept(2),
+                ChildSource::Inserted
+            ]
+        );
+        assert_eq!(repeated.removed, vec![1]);
+    }
+}
@@ -0,0 +1,475 @@
+//! Spacing and size limits that every widget supports.
+//!
+//! A [`LayoutStyle`] is applied by [`WidgetFrame`](super::WidgetFrame) around the widget
+//! implementation: the widget is measured, arranged and rendered inside the space left by
+//! the margin and padding, so widgets do not need to implement spacing themselves.
+//!
+//! Sizes follow the border-box model: `min_size`, `max_size` and `aspect_ratio` describe the
+//! widget including its padding but without its margin. The constraints of the parent win
+//! over the style's limits.
+//!
+//! `visible` and `display` hide a widget without removing it from the tree, so its state
+//! survives toggling. A widget that is not visible keeps its place in the layout but draws
+//! nothing and receives no input; a widget that is not displayed also measures to the smallest
+//! size its parent allows, like an empty widget.
+//!
+//! `clip_radius` clips what the widget and its descendants draw to its border box with rounded
+//! corners, e.g. images inside a card. Input outside the rounded box passes through.
+//!
+//! `elevation` lifts the widget above its siblings: it casts the shadows of its
+//! [`Elevation`] level under the border box and is painted over lower siblings.
+//!
+//! `tab_index` and `focus_scope` change how Tab moves keyboard focus through the widget and
+//! its descendants, see [`focus`](super::focus).
+
+use super::elevation::Elevation;
+use crate::metrics::Constraints;
+
+/// Space on each side of a box, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Edges {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Edges {
+    pub const ZERO: Edges = Edges::all(0.0);
+
+    pub const fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub const fn all(value: f32) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    pub const fn symmetric(vertical: f32, horizontal: f32) -> Self {
+        Self::new(vertical, horizontal, vertical, horizontal)
+    }
+
+    /// Sum of the left and right edges.
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    /// Sum of the top and bottom edges.
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+
+    fn size(&self) -> [f32; 2] {
+        [self.horizontal(), self.vertical()]
+    }
+}
+
+/// Padding, margin, size limits and visibility of a widget. See the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutStyle {
+    /// Space between the widget's bounds and its content; part of the widget for hit testing.
+    pub padding: Edges,
+    /// Space around the widget's bounds; not part of the widget.
+    pub margin: Edges,
+    /// Smallest `[width, height]` of the widget, including padding.
+    pub min_size: [Option<f32>; 2],
+    /// Largest `[width, height]` of the widget, including padding.
+    pub max_size: [Option<f32>; 2],
+    /// Width divided by height of the widget, including padding.
+    pub aspect_ratio: Option<f32>,
+    /// Whether the widget is drawn and receives input. Hidden widgets keep their layout.
+    pub visible: bool,
+    /// Whether the widget takes part in the layout. Implies not visible when `false`.
+    pub display: bool,
+    /// Radius of the rounded border box the widget and its descendants are clipped to.
+    /// `None` does not clip.
+    pub clip_radius: Option<f32>,
+    /// Shadow and paint order of the widget. Level 0 is flat.
+    pub elevation: Elevation,
+    /// Position of the widget in the Tab order: positive indices come first in ascending
+    /// order, then 0 and `None` in tree order. Negative indices are skipped by Tab.
+    pub tab_index: Option<i32>,
+    /// Whether Tab cycles within the widget while focus is inside it, e.g. in a dialog.
+    pub focus_scope: bool,
+}
+
+impl Default for LayoutStyle {
+    fn default() -> Self {
+        Self {
+            padding: Edges::ZERO,
+            margin: Edges::ZERO,
+            min_size: [None; 2],
+            max_size: [None; 2],
+            aspect_ratio: None,
+            visible: true,
+            display: true,
+            clip_radius: None,
+            elevation: Elevation::new(0),
+            tab_index: None,
+            focus_scope: false,
+        }
+    }
+}
+
+impl LayoutStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn padding(mut self, padding: Edges) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn margin(mut self, margin: Edges) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn min_width(mut self, width: f32) -> Self {
+        self.min_size[0] = Some(width.max(0.0));
+        self
+    }
+
+    pub fn min_height(mut self, height: f32) -> Self {
+        self.min_size[1] = Some(height.max(0.0));
+        self
+    }
+
+    pub fn max_width(mut self, width: f32) -> Self {
+        self.max_size[0] = Some(width.max(0.0));
+        self
+    }
+
+    pub fn max_height(mut self, height: f32) -> Self {
+        self.max_size[1] = Some(height.max(0.0));
+        self
+    }
+
+    /// Width divided by height. Ignored unless positive.
+    pub fn aspect_ratio(mut self, ratio: f32) -> Self {
+        self.aspect_ratio = (ratio > 0.0).then_some(ratio);
+        self
+    }
+
+    /// Hides the widget while keeping its place in the layout.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Removes the widget from the layout while keeping its state.
+    pub fn display(mut self, display: bool) -> Self {
+        self.display = display;
+        self
+    }
+
+    /// Clips the widget and its descendants to its border box with corners rounded by
+    /// `radius`. A radius of 0 clips to the rectangle.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.clip_radius = Some(radius.max(0.0));
+        self
+    }
+
+    /// Casts the shadow of elevation `level` and paints the widget over lower siblings.
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.elevation = Elevation::new(level);
+        self
+    }
+
+    /// Sets the position of the widget in the Tab order, see the `tab_index` field.
+    pub fn tab_index(mut self, index: i32) -> Self {
+        self.tab_index = Some(index);
+        self
+    }
+
+    /// Makes the widget a focus scope: Tab cycles through the focusable widgets inside it
+    /// while one of them has focus.
+    pub fn focus_scope(mut self) -> Self {
+        self.focus_scope = true;
+        self
+    }
+
+    /// `true` when the style does not change how the widget is laid out and drawn.
+    pub fn is_empty(&self) -> bool {
+        // the tab order only matters to focus navigation
+        Self {
+            tab_index: None,
+            focus_scope: false,
+            ..*self
+        } == Self::default()
+    }
+
+    /// Whether the widget is drawn and receives input.
+    pub(crate) fn is_shown(&self) -> bool {
+        self.visible && self.display
+    }
+
+    /// Position of the content's origin in the widget's outer (margin) box.
+    pub(crate) fn content_offset(&self) -> [f32; 2] {
+        [
+            self.margin.left + self.padding.left,
+            self.margin.top + self.padding.top,
+        ]
+    }
+
+    /// Size of the content when the outer box is `bounds`.
+    pub(crate) fn content_bounds(&self, bounds: [f32; 2]) -> [f32; 2] {
+        let insets = [
+            self.margin.horizontal() + self.padding.horizontal(),
+            self.margin.vertical() + self.padding.vertical(),
+        ];
+        [
+            (bounds[0] - insets[0]).max(0.0),
+            (bounds[1] - insets[1]).max(0.0),
+        ]
+    }
+
+    /// The border box as `[min, max]` relative to the outer box of size `bounds`.
+    pub(crate) fn border_box(&self, bounds: [f32; 2]) -> [[f32; 2]; 2] {
+        [
+            [self.margin.left, self.margin.top],
+            [
+                bounds[0] - self.margin.right,
+                bounds[1] - self.margin.bottom,
+            ],
+        ]
+    }
+
+    /// Whether `position`, relative to the content's origin, lies inside the border box.
+    pub(crate) fn border_box_contains(&self, bounds: [f32; 2], position: [f32; 2]) -> bool {
+        let content = self.content_bounds(bounds);
+        -self.padding.left <= position[0]
+            && position[0] <= content[0] + self.padding.right
+            && -self.padding.top <= position[1]
+            && position[1] <= content[1] + self.padding.bottom
+    }
+
+    /// Whether `position`, relative to the content's origin, is cut off by `clip_radius`.
+    pub(crate) fn clips(&self, bounds: [f32; 2], position: [f32; 2]) -> bool {
+        let Some(radius) = self.clip_radius else {
+            return false;
+        };
+        if !self.border_box_contains(bounds, position) {
+            return true;
+        }
+        let content = self.content_bounds(bounds);
+        let min = [-self.padding.left, -self.padding.top];
+        let max = [
+            content[0] + self.padding.right,
+            content[1] + self.padding.bottom,
+        ];
+        let radius = radius
+            .min((max[0] - min[0]) / 2.0)
+            .min((max[1] - min[1]) / 2.0);
+        // distance to the center of the nearest corner's circle
+        let [dx, dy] = std::array::from_fn(|axis| {
+            let nearest = position[axis].clamp(min[axis] + radius, max[axis] - radius);
+            position[axis] - nearest
+        });
+        dx * dx + dy * dy > radius * radius
+    }
+
+    /// `[min, max]` per axis of the border box within the outer `constraints`.
+    fn border_box_limits(&self, constraints: &Constraints) -> [[f32; 2]; 2] {
+        let margin = self.margin.size();
+        let mut limits = [constraints.width(), constraints.height()];
+        for (axis, [min, max]) in limits.iter_mut().enumerate() {
+            *max = (*max - margin[axis]).max(0.0);
+            *min = (*min - margin[axis]).clamp(0.0, *max);
+            if let Some(style_min) = self.min_size[axis] {
+                *min = style_min.clamp(*min, *max);
+            }
+            if let Some(style_max) = self.max_size[axis] {
+                *max = style_max.clamp(*min, *max);
+            }
+        }
+
+        if let Some(ratio) = self.aspect_ratio {
+            // pick the largest size with the ratio that fits, then make it tight
+            let [[min_w, max_w], [min_h, max_h]] = limits;
+            let mut width = if !Constraints::is_unbounded_extent(max_w) {
+                max_w
+            } else if !Constraints::is_unbounded_extent(max_h) {
+                max_h * ratio
+            } else {
+                min_w.max(min_h * ratio)
+            };
+            let mut height = width / ratio;
+            if height > max_h {
+                height = max_h;
+                width = height * ratio;
+            }
+            width = width.clamp(min_w, max_w);
+            height = (width / ratio).clamp(min_h, max_h);
+            limits = [[width, width], [height, height]];
+        }
+
+        limits
+    }
+
+    /// Constraints for the widget implementation, given the constraints of the outer box.
+    pub(crate) fn content_constraints(&self, constraints: &Constraints) -> Constraints {
+        if self.is_empty() {
+            return *constraints;
+        }
+        let padding = self.padding.size();
+        let limits = self.border_box_limits(constraints);
+        let [width, height] = std::array::from_fn(|axis| {
+            let [min, max] = limits[axis];
+            let max = (max - padding[axis]).max(0.0);
+            [(min - padding[axis]).clamp(0.0, max), max]
+        });
+        Constraints::new(width, height)
+    }
+
+    /// Size of the outer box, given the size the widget measured for its content.
+    pub(crate) fn outer_size(&self, content_size: [f32; 2], constraints: &Constraints) -> [f32; 2] {
+        if self.is_empty() {
+            return content_size;
+        }
+        let padding = self.padding.size();
+        let margin = self.margin.size();
+        let limits = self.border_box_limits(constraints);
+        std::array::from_fn(|axis| {
+            let [min, max] = limits[axis];
+            (content_size[axis] + padding[axis]).clamp(min, max) + margin[axis]
+        })
+    }
+
+    /// Intrinsic extent along `axis` (0: width, 1: height) given the outer `extent` along the
+    /// other axis, wrapping `content` which computes the widget's own intrinsic size.
+    pub(crate) fn intrinsic(
+        &self,
+        axis: usize,
+        extent: f32,
+        content: impl FnOnce(f32) -> f32,
+    ) -> f32 {
+        if self.is_empty() {
+            return content(extent);
+        }
+        let other = 1 - axis;
+        let padding = self.padding.size();
+        let margin = self.margin.size();
+        let border_extent = (extent - margin[other]).max(0.0);
+
+        let border = match self.aspect_ratio {
+            Some(ratio) if !Constraints::is_unbounded_extent(border_extent) => {
+                if axis == 0 {
+                    border_extent * ratio
+                } else {
+                    border_extent / ratio
+                }
+            }
+            _ => content((border_extent - padding[other]).max(0.0)) + padding[axis],
+        };
+        let min = self.min_size[axis].unwrap_or(0.0);
+        let max = self.max_size[axis].unwrap_or(f32::INFINITY).max(min);
+        border.clamp(min, max) + margin[axis]
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_style_is_transparent() {
+        let style = LayoutStyle::default();
+        let constraints = Constraints::new([10.0, 100.0], [0.0, 50.0]);
+        assert_eq!(style.content_constraints(&constraints), constraints);
+        assert_eq!(style.outer_size([30.0, 20.0], &constraints), [30.0, 20.0]);
+        assert_eq!(style.content_offset(), [0.0, 0.0]);
+        assert_eq!(style.intrinsic(0, 50.0, |h| h * 2.0), 100.0);
+    }
+
+    #[test]
+    fn visibility_is_part_of_the_style() {
+        let style = LayoutStyle::new();
+        assert!(style.visible && style.display && style.is_shown());
+        assert!(!style.visible(false).is_shown());
+        assert!(!style.display(false).is_shown());
+        assert!(!style.display(false).is_empty());
+    }
+
+    #[test]
+    fn clip_radius_cuts_off_the_corners() {
+        let style = LayoutStyle::new()
+            .padding(Edges::all(5.0))
+            .margin(Edges::all(2.0))
+            .clip_radius(10.0);
+        // border box from -5 to 45 relative to the content
+        let bounds = [54.0, 54.0];
+        assert!(!style.clips(bounds, [20.0, -5.0]));
+        assert!(!style.clips(bounds, [-2.0, -2.0]));
+        assert!(style.clips(bounds, [-4.0, -4.0]));
+        assert!(!style.clips(bounds, [42.0, 42.0]));
+        assert!(style.clips(bounds, [44.0, 44.0]));
+        assert!(style.clips(bounds, [20.0, 46.0]));
+        assert!(!LayoutStyle::new().clips(bounds, [-4.0, -4.0]));
+    }
+
+    #[test]
+    fn padding_and_margin_deflate_the_content() {
+        let style = LayoutStyle::new()
+            .padding(Edges::all(5.0))
+            .margin(Edges::symmetric(2.0, 10.0));
+        let constraints = Constraints::new([0.0, 100.0], [0.0, 100.0]);
+
+        let content = style.content_constraints(&constraints);
+        assert_eq!(
+            content.max_size(),
+            [100.0 - 20.0 - 10.0, 100.0 - 4.0 - 10.0]
+        );
+        assert_eq!(style.outer_size([30.0, 20.0], &constraints), [60.0, 34.0]);
+        assert_eq!(style.content_offset(), [15.0, 7.0]);
+        assert_eq!(style.content_bounds([60.0, 34.0]), [30.0, 20.0]);
+
+        // padding is part of the widget, margin is not
+        assert!(style.border_box_contains([60.0, 34.0], [-5.0, -5.0]));
+        assert!(!style.border_box_contains([60.0, 34.0], [-6.0, 0.0]));
+
+        // content size 10 + padding 10 + margin 20
+        assert_eq!(style.intrinsic(0, 100.0, |_| 10.0), 40.0);
+    }
+
+    #[test]
+    fn size_limits_within_parent_constraints() {
+        let style = LayoutStyle::new().min_width(50.0).max_height(40.0);
+        let constraints = Constraints::new([0.0, 200.0], [0.0, 200.0]);
+
+        let content = style.content_constraints(&constraints);
+        assert_eq!(content.width(), [50.0, 200.0]);
+        assert_eq!(content.height(), [0.0, 40.0]);
+        assert_eq!(style.outer_size([10.0, 100.0], &constraints), [50.0, 40.0]);
+
+        // the parent wins over the style
+        let tight = Constraints::from_boundary([30.0, 60.0]);
+        assert_eq!(style.outer_size([10.0, 10.0], &tight), [30.0, 60.0]);
+    }
+
+    #[test]
+    fn aspect_ratio_fills_the_available_width() {
+        let style = LayoutStyle::new().aspect_ratio(2.0);
+
+        let constraints = Constraints::new([0.0, 100.0], [0.0, 200.0]);
+        assert_eq!(style.outer_size([1.0, 1.0], &constraints), [100.0, 50.0]);
+
+        // limited by height
+        let constraints = Constraints::new([0.0, 100.0], [0.0, 20.0]);
+        assert_eq!(style.outer_size([1.0, 1.0], &constraints), [40.0, 20.0]);
+
+        // unbounded width follows the height
+        let constraints = Constraints::new([0.0, f32::INFINITY], [0.0, 30.0]);
+        assert_eq!(style.outer_size([1.0, 1.0], &constraints), [60.0, 30.0]);
+
+        assert_eq!(style.intrinsic(1, 80.0, |_| 0.0), 40.0);
+    }
+}
@@ -0,0 +1,201 @@
+//! Memoized view subtrees.
+//!
+//! [`memo`] caches the `Dom` a closure builds and hands out the cached one as long as the key
+//! stays equal, so expensive subtrees such as long lists or charts are neither built nor
+//! diffed again when an unrelated part of the model changes:
+//!
+//! ```ignore
+//! fn view(model: &Model) -> Box<dyn Dom<Event>> {
+//!     Box::new(Column::new()
+//!         .push(Text::new(&model.status))
+//!         .push(memo(model.points.version(), || chart(&model.points))))
+//! }
+//! ```
+//!
+//! Entries are kept per component and call site; a call site inside a loop keeps one entry
+//! per distinct key. Entries that were not used by the last view are dropped.
+//! Outside of a component's view function `memo` always builds.
+//!
+//! Like [`tracked`](super::signal::tracked), the key must capture everything the closure
+//! reads, or the subtree shows stale content.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::hash::{Hash, Hasher};
+use std::panic::Location;
+use std::sync::Arc;
+
+use log::trace;
+use parking_lot::Mutex;
+use smallvec::smallvec;
+
+use crate::ui::{
+    Dom,
+    signal::{TrackedDom, next_version},
+};
+
+struct Entry {
+    key: Box<dyn Any + Send + Sync>,
+    // an `Arc<dyn Dom<E>>`
+    dom: Box<dyn Any + Send + Sync>,
+    // identifies `dom` to the widget, which skips its update while it stays the same
+    version: u64,
+    used: bool,
+}
+
+/// Memoized doms of one component.
+#[derive(Default)]
+pub(crate) struct MemoCache {
+    entries: fxhash::FxHashMap<(&'static Location<'static>, u64), Entry>,
+}
+
+impl MemoCache {
+    /// Drops the entries the last view did not use.
+    fn end_view(&mut self) {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, entry| std::mem::take(&mut entry.used));
+        if self.entries.len() != before {
+            trace!(
+                "MemoCache::end_view: dropped {} unused entries",
+                before - self.entries.len()
+            );
+        }
+    }
+}
+
+thread_local! {
+    // the cache of the component whose view is being built on this thread
+    static CURRENT: RefCell<Option<Arc<Mutex<MemoCache>>>> = const { RefCell::new(None) };
+}
+
+/// Puts the cache of the enclosing view back when dropped, also when the view panics.
+struct RestoreOuter(Option<Arc<Mutex<MemoCache>>>);
+
+impl Drop for RestoreOuter {
+    fn drop(&mut self) {
+        let outer = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = outer);
+    }
+}
+
+/// Whether a component's view is being built on this thread.
+pub(crate) fn in_view() -> bool {
+    CURRENT.with(|current| current.borrow().is_some())
+}
+
+/// Builds a view with `cache` as the target of [`memo`] calls.
+pub(crate) fn view_scope<R>(cache: &Arc<Mutex<MemoCache>>, view: impl FnOnce() -> R) -> R {
+    let result = {
+        // nested components restore the outer cache afterwards
+        let _outer = RestoreOuter(CURRENT.with(|current| current.replace(Some(Arc::clone(cache)))));
+        view()
+    };
+    cache.lock().end_view();
+    result
+}
+
+/// Returns the `Dom` built by `build`, reusing the one from the previous view while `key` is
+/// equal. See the [module documentation](self).
+#[track_caller]
+pub fn memo<E, K>(key: K, build: impl FnOnce() -> Box<dyn Dom<E>>) -> Box<dyn Dom<E>>
+where
+    E: 'static,
+    K: PartialEq + Hash + Send + Sync + 'static,
+{
+    let Some(cache) = CURRENT.with(|current| current.borrow().clone()) else {
+        return build();
+    };
+    let slot = (Location::caller(), {
+        let mut hasher = fxhash::FxHasher64::default();
+        key.hash(&mut hasher);
+        hasher.finish()
+    });
+
+    if let Some(entry) = cache.lock().entries.get_mut(&slot)
+        && entry.key.downcast_ref::<K>() == Some(&key)
+        && let Some(dom) = entry.dom.downcast_ref::<Arc<dyn Dom<E>>>()
+    {
+        entry.used = true;
+        return Box::new(TrackedDom::new(smallvec![entry.version], Arc::clone(dom)));
+    }
+
+    // the cache is not locked while building, `build` may memoize as well
+    let dom: Arc<dyn Dom<E>> = Arc::from(build());
+    let version = next_version();
+    cache.lock().entries.insert(
+        slot,
+        Entry {
+            key: Box::new(key),
+            dom: Box::new(Arc::clone(&dom)),
+            version,
+            used: true,
+        },
+    );
+    Box::new(TrackedDom::new(smallvec![version], dom))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::ui::AnyWidgetFrame;
+
+    struct Leaf;
+
+    impl Dom<()> for Leaf {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<()>> {
+            unreachable!("memo only stores doms, these tests never build widgets")
+        }
+    }
+
+    #[test]
+    fn builds_again_only_for_new_keys() {
+        let cache = Arc::new(Mutex::new(MemoCache::default()));
+        let builds = std::cell::Cell::new(0);
+        let view = |keys: &[u32]| {
+            view_scope(&cache, || {
+                for key in keys {
+                    memo(*key, || {
+                        builds.set(builds.get() + 1);
+                        Box::new(Leaf) as Box<dyn Dom<()>>
+                    });
+                }
+            })
+        };
+
+        view(&[1, 2]);
+        assert_eq!(builds.get(), 2);
+        view(&[1, 2]);
+        assert_eq!(builds.get(), 2);
+
+        // 2 is dropped after a view without it
+        view(&[1, 3]);
+        assert_eq!(builds.get(), 3);
+        view(&[1, 2]);
+        assert_eq!(builds.get(), 4);
+        assert_eq!(cache.lock().entries.len(), 2);
+
+        // no caching outside of a view
+        memo(1, || Box::new(Leaf) as Box<dyn Dom<()>>);
+        assert_eq!(cache.lock().entries.len(), 2);
+    }
+
+    #[test]
+    fn panicking_view_restores_the_outer_cache() {
+        let outer = Arc::new(Mutex::new(MemoCache::default()));
+        let inner = Arc::new(Mutex::new(MemoCache::default()));
+
+        view_scope(&outer, || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                view_scope(&inner, || panic!("view failed"))
+            }));
+            assert!(result.is_err());
+            memo(1, || Box::new(Leaf) as Box<dyn Dom<()>>);
+        });
+
+        assert_eq!(outer.lock().entries.len(), 1);
+        assert!(inner.lock().entries.is_empty());
+        assert!(CURRENT.with(|current| current.borrow().is_none()));
+    }
+}
@@ -0,0 +1,237 @@
+//! Detecting children laid out beyond their parent.
+//!
+//! A child that is placed partly outside its parent's content box, e.g. because it measured
+//! larger than the constraints it was given, draws over its siblings. `WidgetFrame` checks
+//! every arrangement it computes and logs a warning naming the parent and the overflowing
+//! children when it starts to overflow.
+//!
+//! With [`App::show_layout_overflow`](crate::app::App::show_layout_overflow) the overflowing
+//! edges are also painted with yellow and black stripes. Widgets that clip or scroll their
+//! children on purpose opt out with [`Widget::allows_overflow`](super::Widget::allows_overflow).
+
+use std::fmt;
+
+use log::warn;
+use renderer::RenderNode;
+
+use crate::{context::WidgetContext, metrics::Arrangement};
+
+/// Overflow smaller than this is rounding, not a layout bug.
+const TOLERANCE: f32 = 0.5;
+/// Width of the striped band painted along an overflowing edge.
+const BAND_WIDTH: f32 = 8.0;
+/// Width of one stripe of the band.
+const STRIPE_WIDTH: u32 = 4;
+
+const YELLOW: [u8; 4] = [0xff, 0xd6, 0x00, 0xff];
+const BLACK: [u8; 4] = [0x1a, 0x1a, 0x1a, 0xff];
+
+/// How far children extend beyond each edge of their parent's content box.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct Overflow {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Overflow {
+    /// The overflow of the rectangle `[min, max]` out of the box from the origin to `bounds`.
+    fn of_rect(bounds: [f32; 2], [min, max]: [[f32; 2]; 2]) -> Self {
+        let beyond = |amount: f32| if amount > TOLERANCE { amount } else { 0.0 };
+        Self {
+            left: beyond(-min[0]),
+            top: beyond(-min[1]),
+            right: beyond(max[0] - bounds[0]),
+            bottom: beyond(max[1] - bounds[1]),
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Bands `[min, max]` along the overflowing edges of the box from the origin to `bounds`.
+    fn bands(&self, bounds: [f32; 2]) -> Vec<[[f32; 2]; 2]> {
+        let [width, height] = bounds;
+        let band = [BAND_WIDTH.min(width), BAND_WIDTH.min(height)];
+        [
+            (self.left > 0.0, [[0.0, 0.0], [band[0], height]]),
+            (self.top > 0.0, [[0.0, 0.0], [width, band[1]]]),
+            (self.right > 0.0, [[width - band[0], 0.0], [width, height]]),
+            (
+                self.bottom > 0.0,
+                [[0.0, height - band[1]], [width, height]],
+            ),
+        ]
+        .into_iter()
+        .filter(|(overflows, [min, max])| *overflows && min[0] < max[0] && min[1] < max[1])
+        .map(|(_, band)| band)
+        .collect()
+    }
+
+    /// Striped bands along the overflowing edges of the box from the origin to `bounds`.
+    pub fn indicator(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<RenderNode> {
+        let bands = self.bands(bounds);
+        if bands.is_empty() {
+            return None;
+        }
+
+        let device = ctx.device();
+        let queue = ctx.queue();
+        let mut node = RenderNode::new();
+        for [min, max] in bands {
+            let size = [
+                (max[0] - min[0]).ceil() as u32,
+                (max[1] - min[1]).ceil() as u32,
+            ];
+            let region = match ctx.texture_atlas().allocate(&device, &queue, size) {
+                Ok(region) => region,
+                Err(e) => {
+                    warn!("Overflow::indicator: failed to allocate the stripes: {e}");
+                    continue;
+                }
+            };
+            // stripes continue across the bands, so corners line up
+            let origin = [min[0] as u32, min[1] as u32];
+            if let Err(e) = region.write_data(&queue, &stripes(size, origin)) {
+                warn!("Overflow::indicator: failed to upload the stripes: {e}");
+                continue;
+            }
+            node.push_child(
+                RenderNode::new().with_texture(
+                    region,
+                    [size[0] as f32, size[1] as f32],
+                    nalgebra::Matrix4::identity(),
+                ),
+                nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(min[0], min[1], 0.0)),
+            );
+        }
+        Some(node)
+    }
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let edges = [
+            ("left", self.left),
+            ("top", self.top),
+            ("right", self.right),
+            ("bottom", self.bottom),
+        ];
+        let mut first = true;
+        for (edge, amount) in edges.into_iter().filter(|(_, amount)| *amount > 0.0) {
+            if !first {
+                f.write_str(", ")?;
+            }
+            write!(f, "{edge} by {amount:.1}px")?;
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+/// The overflow of `children` out of the content box from the origin to `bounds`, and the
+/// labels of the children that overflow.
+pub(crate) fn children_overflow<'a>(
+    bounds: [f32; 2],
+    children: impl IntoIterator<Item = (Option<&'a str>, &'a Arrangement)>,
+) -> (Overflow, Vec<String>) {
+    let mut overflow = Overflow::default();
+    let mut labels = Vec::new();
+    for (label, arrangement) in children {
+        let rect = super::widget::bounding_box(&arrangement.affine, [[0.0, 0.0], arrangement.size]);
+        let child = Overflow::of_rect(bounds, rect);
+        if !child.is_none() {
+            overflow = overflow.max(child);
+            labels.push(label.unwrap_or("<unnamed>").to_string());
+        }
+    }
+    (overflow, labels)
+}
+
+/// RGBA pixels of diagonal yellow and black stripes, for a texture of `size` at `origin`.
+fn stripes(size: [u32; 2], origin: [u32; 2]) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(size[0] as usize * size[1] as usize * 4);
+    for y in origin[1]..origin[1] + size[1] {
+        for x in origin[0]..origin[0] + size[0] {
+            let color = if ((x + y) / STRIPE_WIDTH).is_multiple_of(2) {
+                YELLOW
+            } else {
+                BLACK
+            };
+            pixels.extend_from_slice(&color);
+        }
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix4, Vector3};
+
+    use super::*;
+
+    fn at(x: f32, y: f32, size: [f32; 2]) -> Arrangement {
+        Arrangement::new(size, Matrix4::new_translation(&Vector3::new(x, y, 0.0)))
+    }
+
+    #[test]
+    fn reports_the_edges_children_extend_beyond() {
+        let fits = at(0.0, 0.0, [100.0, 20.0]);
+        let too_wide = at(50.0, 0.0, [62.0, 20.0]);
+        let rounded = at(0.0, 80.2, [10.0, 20.0]);
+        let (overflow, labels) = children_overflow(
+            [100.0, 100.0],
+            [
+                (Some("fits"), &fits),
+                (Some("too wide"), &too_wide),
+                (Some("rounded"), &rounded),
+            ],
+        );
+        assert_eq!(
+            overflow,
+            Overflow {
+                right: 12.0,
+                ..Overflow::default()
+            }
+        );
+        assert_eq!(labels, ["too wide"]);
+        assert_eq!(overflow.to_string(), "right by 12.0px");
+
+        let (overflow, labels) = children_overflow([100.0, 100.0], [(None, &fits)]);
+        assert!(overflow.is_none());
+        assert!(labels.is_empty());
+    }
+
+    #[test]
+    fn bands_cover_the_overflowing_edges() {
+        let overflow = Overflow {
+            top: 3.0,
+            right: 12.0,
+            ..Overflow::default()
+        };
+        assert_eq!(
+            overflow.bands([100.0, 50.0]),
+            [[[0.0, 0.0], [100.0, 8.0]], [[92.0, 0.0], [100.0, 50.0]]]
+        );
+        // an empty box has nothing to paint
+        assert_eq!(overflow.bands([4.0, 0.0]), Vec::<[[f32; 2]; 2]>::new());
+    }
+
+    #[test]
+    fn stripes_run_diagonally() {
+        let pixels = stripes([8, 1], [0, 3]);
+        let colors: Vec<_> = pixels.chunks(4).map(|pixel| pixel == YELLOW).collect();
+        assert_eq!(colors, [true, false, false, false, false, true, true, true]);
+    }
+}
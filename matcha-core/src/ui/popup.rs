@@ -0,0 +1,282 @@
+//! Placement of popups next to an anchor.
+//!
+//! Dropdowns, tooltips and menus are shown next to an anchor, such as a button, a text caret
+//! or the pointer, and have to stay inside the window. A [`PopupPosition`] describes where a
+//! popup prefers to go, and [`place`](PopupPosition::place) resolves it against the window:
+//!
+//! 1. the popup is put on a [`Side`] of the anchor, aligned to it as given by [`Align`] and
+//!    moved by the gap and shift,
+//! 2. where it does not fit, it flips to the opposite side or alignment if that has more room,
+//! 3. whatever still sticks out is clamped into the window.
+//!
+//! ```ignore
+//! // a dropdown below its button, right-aligned when there is no room to the right
+//! let placement = PopupPosition::new(Side::Bottom, Align::Start)
+//!     .gap(2.0)
+//!     .place([[0.0, 0.0], button_size], list_size, window_bounds(event, ctx));
+//! ```
+//!
+//! Rectangles are `[min, max]` corners in the coordinates of the widget that shows the popup.
+//! A popup rendered by that widget moves along when it is scrolled, so it stays attached to
+//! the anchor. What changes is where the window lies in those coordinates: widgets keep the
+//! [`window_bounds`] of their latest input and place their popups again when it differs,
+//! e.g. after a scroll or a resize.
+
+use crate::{context::WidgetContext, device_input::DeviceInput};
+
+/// The edge of the anchor a popup is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn opposite(self) -> Self {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// The axis a popup on this side is moved away from the anchor along: 0 for x, 1 for y.
+    fn axis(self) -> usize {
+        match self {
+            Side::Left | Side::Right => 0,
+            Side::Top | Side::Bottom => 1,
+        }
+    }
+}
+
+/// How a popup is aligned to the anchor along the anchor's edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Align {
+    /// The popup's left (or top) edge is aligned with the anchor's.
+    Start,
+    Center,
+    /// The popup's right (or bottom) edge is aligned with the anchor's.
+    End,
+}
+
+impl Align {
+    pub fn opposite(self) -> Self {
+        match self {
+            Align::Start => Align::End,
+            Align::Center => Align::Center,
+            Align::End => Align::Start,
+        }
+    }
+}
+
+/// Where a popup prefers to go relative to its anchor. See the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopupPosition {
+    side: Side,
+    align: Align,
+    gap: f32,
+    shift: f32,
+    flip: bool,
+    clamp: bool,
+}
+
+impl Default for PopupPosition {
+    /// Below the anchor, left-aligned, like a dropdown list.
+    fn default() -> Self {
+        Self::new(Side::Bottom, Align::Start)
+    }
+}
+
+impl PopupPosition {
+    pub const fn new(side: Side, align: Align) -> Self {
+        Self {
+            side,
+            align,
+            gap: 0.0,
+            shift: 0.0,
+            flip: true,
+            clamp: true,
+        }
+    }
+
+    /// Distance between the anchor and the popup.
+    pub const fn gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    /// Moves the popup along the anchor's edge, towards the end for positive values. Mirrored
+    /// when the alignment flips.
+    pub const fn shift(mut self, shift: f32) -> Self {
+        self.shift = shift;
+        self
+    }
+
+    /// Whether the popup may flip to the opposite side or alignment. Enabled by default.
+    pub const fn flip(mut self, flip: bool) -> Self {
+        self.flip = flip;
+        self
+    }
+
+    /// Whether the popup is moved into the window where it still sticks out. Enabled by
+    /// default.
+    pub const fn clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    /// Places a popup of `size` next to `anchor`, inside `window` if given.
+    pub fn place(
+        &self,
+        anchor: [[f32; 2]; 2],
+        size: [f32; 2],
+        window: Option<[[f32; 2]; 2]>,
+    ) -> Placement {
+        let main = self.side.axis();
+        let cross = 1 - main;
+        let range = |axis: usize| window.map(|[min, max]| [min[axis], max[axis]]);
+
+        let main_start = |side: Side| match side {
+            Side::Top | Side::Left => anchor[0][main] - self.gap - size[main],
+            Side::Bottom | Side::Right => anchor[1][main] + self.gap,
+        };
+        let cross_start = |align: Align| match align {
+            Align::Start => anchor[0][cross] + self.shift,
+            Align::Center => (anchor[0][cross] + anchor[1][cross] - size[cross]) / 2.0 + self.shift,
+            Align::End => anchor[1][cross] - size[cross] - self.shift,
+        };
+
+        let mut side = self.side;
+        let mut align = self.align;
+        if self.flip
+            && let Some(range) = range(main)
+        {
+            side = better(side, side.opposite(), size[main], range, main_start);
+        }
+        if self.flip
+            && let Some(range) = range(cross)
+        {
+            align = better(align, align.opposite(), size[cross], range, cross_start);
+        }
+
+        let mut origin = [0.0; 2];
+        origin[main] = main_start(side);
+        origin[cross] = cross_start(align);
+        if self.clamp {
+            for axis in [main, cross] {
+                if let Some([min, max]) = range(axis) {
+                    // a popup larger than the window keeps its start visible
+                    origin[axis] = origin[axis].min(max - size[axis]).max(min);
+                }
+            }
+        }
+
+        Placement {
+            origin,
+            side,
+            align,
+        }
+    }
+}
+
+/// Keeps `preferred` unless it sticks out of `range` and `alternative` sticks out less.
+fn better<T: Copy>(
+    preferred: T,
+    alternative: T,
+    length: f32,
+    range: [f32; 2],
+    start: impl Fn(T) -> f32,
+) -> T {
+    let overflow = |start: f32| (range[0] - start).max(0.0) + (start + length - range[1]).max(0.0);
+    let preferred_overflow = overflow(start(preferred));
+    if preferred_overflow > 0.0 && overflow(start(alternative)) < preferred_overflow {
+        alternative
+    } else {
+        preferred
+    }
+}
+
+/// The resolved position of a popup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Top left corner of the popup.
+    pub origin: [f32; 2],
+    /// The side the popup ended up on, which differs from the preferred one after a flip.
+    pub side: Side,
+    pub align: Align,
+}
+
+/// The window as a rectangle in the coordinates of the widget that received `event`.
+///
+/// For a rotated or skewed widget this is the bounding box of the window's corners.
+pub fn window_bounds(event: &DeviceInput, ctx: &WidgetContext) -> Option<[[f32; 2]; 2]> {
+    let [width, height] = ctx.viewport_size()?;
+    let mut bounds = [[f32::INFINITY; 2], [f32::NEG_INFINITY; 2]];
+    for corner in [[0.0, 0.0], [width, 0.0], [0.0, height], [width, height]] {
+        let local = event.viewport_to_local(corner)?;
+        bounds = [
+            [bounds[0][0].min(local[0]), bounds[0][1].min(local[1])],
+            [bounds[1][0].max(local[0]), bounds[1][1].max(local[1])],
+        ];
+    }
+    Some(bounds)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Option<[[f32; 2]; 2]> = Some([[0.0, 0.0], [400.0, 300.0]]);
+
+    #[test]
+    fn places_on_the_preferred_side_when_it_fits() {
+        let button = [[100.0, 50.0], [180.0, 70.0]];
+        let below = PopupPosition::default().gap(2.0).shift(4.0);
+        assert_eq!(
+            below.place(button, [120.0, 80.0], WINDOW),
+            Placement {
+                origin: [104.0, 72.0],
+                side: Side::Bottom,
+                align: Align::Start,
+            }
+        );
+
+        let tooltip = PopupPosition::new(Side::Top, Align::Center).gap(4.0);
+        assert_eq!(
+            tooltip.place(button, [60.0, 20.0], WINDOW).origin,
+            [110.0, 26.0]
+        );
+    }
+
+    #[test]
+    fn flips_and_clamps_at_the_window_edges() {
+        // a dropdown near the bottom right corner opens upwards and right-aligned
+        let button = [[300.0, 260.0], [380.0, 280.0]];
+        let placement = PopupPosition::default()
+            .shift(4.0)
+            .place(button, [120.0, 80.0], WINDOW);
+        assert_eq!(placement.side, Side::Top);
+        assert_eq!(placement.align, Align::End);
+        assert_eq!(placement.origin, [256.0, 180.0]);
+
+        // too tall for either side: takes the side with more room, clamped into the window
+        let tall = PopupPosition::default().place(button, [120.0, 290.0], WINDOW);
+        assert_eq!(tall.side, Side::Top);
+        assert_eq!(tall.origin, [260.0, 0.0]);
+
+        // without a window only the preferred placement is used
+        let free = PopupPosition::default().place(button, [120.0, 80.0], None);
+        assert_eq!(free.origin, [300.0, 280.0]);
+
+        let pinned =
+            PopupPosition::default()
+                .flip(false)
+                .clamp(false)
+                .place(button, [120.0, 80.0], WINDOW);
+        assert_eq!(pinned.origin, [300.0, 280.0]);
+    }
+}
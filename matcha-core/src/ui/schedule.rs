@@ -0,0 +1,303 @@
+//! Debounced and throttled event dispatch.
+//!
+//! Some inner events come in bursts where only the latest one matters, e.g. a search field
+//! emitting an event per keystroke. A [`Component`](super::Component) can hold such events
+//! back before they reach its `event_fn`:
+//!
+//! ```ignore
+//! Component::new(Some("search"), model, view)
+//!     .event_fn(on_event)
+//!     // search only after typing paused for 300ms
+//!     .debounce(Duration::from_millis(300), |e| matches!(e, Inner::QueryChanged(_)))
+//!     // at most one scroll sync per frame
+//!     .throttle(Duration::from_millis(16), |e| matches!(e, Inner::Scrolled(_)))
+//! ```
+//!
+//! Pending events are kept per rule and enum variant: a newer event replaces the pending one
+//! of the same variant and cancels its timer, while events of other variants are unaffected.
+//! Timers run on the component's tokio runtime.
+//!
+//! An event delivered later runs `event_fn` outside of input handling. If `event_fn` returns
+//! an event for the parent, the component asks its window to collect it right away, see
+//! [`AnyWidgetFrame::take_deferred_events`](super::AnyWidgetFrame::take_deferred_events).
+//! Components further up pass it through their own `event_fn` and rules as with any input.
+
+use std::collections::HashMap;
+use std::mem::Discriminant;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::trace;
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+/// How events matching a rule are held back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchPolicy {
+    /// Deliver only the last event of a burst, once no new event came for the duration.
+    Debounce(Duration),
+    /// Deliver at most one event per interval: the first one right away, and the latest of
+    /// the rest when the interval ends.
+    Throttle(Duration),
+}
+
+type Filter<E> = dyn Fn(&E) -> bool + Send + Sync;
+
+struct Rule<E> {
+    filter: Arc<Filter<E>>,
+    policy: DispatchPolicy,
+}
+
+impl<E> Clone for Rule<E> {
+    fn clone(&self) -> Self {
+        Self {
+            filter: Arc::clone(&self.filter),
+            policy: self.policy,
+        }
+    }
+}
+
+struct Slot<E> {
+    // the latest event waiting for its timer
+    pending: Option<E>,
+    timer: Option<tokio::task::AbortHandle>,
+    // end of the current throttle interval
+    interval_end: Option<Instant>,
+}
+
+type SlotKey<E> = (usize, Discriminant<E>);
+
+/// Holds back events according to a list of rules. The first matching rule applies; events
+/// matching none are handled right away.
+pub(crate) struct EventScheduler<E> {
+    rules: Vec<Rule<E>>,
+    slots: Arc<Mutex<HashMap<SlotKey<E>, Slot<E>>>>,
+}
+
+impl<E> Default for EventScheduler<E> {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            slots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<E> Drop for EventScheduler<E> {
+    fn drop(&mut self) {
+        // pending events of a dropped component are discarded
+        for slot in self.slots.lock().values() {
+            if let Some(timer) = &slot.timer {
+                timer.abort();
+            }
+        }
+    }
+}
+
+impl<E: Send + 'static> EventScheduler<E> {
+    /// A scheduler with the rules of `self` followed by a new one.
+    pub fn with_rule(
+        &self,
+        policy: DispatchPolicy,
+        filter: impl Fn(&E) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let mut rules = self.rules.clone();
+        rules.push(Rule {
+            filter: Arc::new(filter),
+            policy,
+        });
+        Self {
+            rules,
+            slots: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns `event` if it is to be handled now. Otherwise it is held back, and `deliver` is
+    /// called with it (or a newer event of the same variant) from a timer task.
+    pub fn dispatch(
+        &self,
+        event: E,
+        runtime: &tokio::runtime::Handle,
+        deliver: impl Fn(E) + Send + Sync + 'static,
+    ) -> Option<E> {
+        let Some((index, rule)) = self
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| (rule.filter)(&event))
+        else {
+            return Some(event);
+        };
+        let key = (index, std::mem::discriminant(&event));
+        let mut slots = self.slots.lock();
+        let slot = slots.entry(key).or_insert_with(|| Slot {
+            pending: None,
+            timer: None,
+            interval_end: None,
+        });
+
+        match rule.policy {
+            DispatchPolicy::Debounce(delay) => {
+                if let Some(timer) = slot.timer.take() {
+                    trace!("EventScheduler::dispatch: debounced event superseded");
+                    timer.abort();
+                }
+                slot.pending = Some(event);
+                let timer = runtime.spawn(fire(
+                    Arc::downgrade(&self.slots),
+                    key,
+                    Instant::now() + delay,
+                    None,
+                    deliver,
+                ));
+                slot.timer = Some(timer.abort_handle());
+                None
+            }
+            DispatchPolicy::Throttle(interval) => {
+                let now = Instant::now();
+                if slot.interval_end.is_none_or(|end| end <= now) {
+                    slot.interval_end = Some(now + interval);
+                    return Some(event);
+                }
+                // replaces an older event still waiting for the end of the interval
+                slot.pending = Some(event);
+                if slot.timer.is_none()
+                    && let Some(end) = slot.interval_end
+                {
+                    let timer = runtime.spawn(fire(
+                        Arc::downgrade(&self.slots),
+                        key,
+                        end,
+                        Some(interval),
+                        deliver,
+                    ));
+                    slot.timer = Some(timer.abort_handle());
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Delivers the pending event of `key` at `at`. With `next_interval`, the delivery starts a
+/// new throttle interval.
+async fn fire<E>(
+    slots: std::sync::Weak<Mutex<HashMap<SlotKey<E>, Slot<E>>>>,
+    key: SlotKey<E>,
+    at: Instant,
+    next_interval: Option<Duration>,
+    deliver: impl Fn(E),
+) {
+    tokio::time::sleep_until(at).await;
+    let Some(slots) = slots.upgrade() else {
+        return;
+    };
+    let event = {
+        let mut slots = slots.lock();
+        let Some(slot) = slots.get_mut(&key) else {
+            return;
+        };
+        slot.timer = None;
+        if let Some(interval) = next_interval {
+            slot.interval_end = Some(Instant::now() + interval);
+        }
+        slot.pending.take()
+    };
+    if let Some(event) = event {
+        deliver(event);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Inner {
+        Query(&'static str),
+        Scroll(u32),
+        Click,
+    }
+
+    fn recorder() -> (Arc<Mutex<Vec<Inner>>>, impl Fn(Inner) + Send + Sync + Clone) {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let sink = delivered.clone();
+        (delivered, move |event| sink.lock().push(event))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn debounce_delivers_the_last_event_after_a_pause() {
+        let runtime = tokio::runtime::Handle::current();
+        let scheduler = EventScheduler::default()
+            .with_rule(DispatchPolicy::Debounce(Duration::from_millis(300)), |e| {
+                matches!(e, Inner::Query(_))
+            });
+        let (delivered, deliver) = recorder();
+
+        for query in ["m", "ma", "mat"] {
+            assert_eq!(
+                scheduler.dispatch(Inner::Query(query), &runtime, deliver.clone()),
+                None
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        // other events pass through
+        assert_eq!(
+            scheduler.dispatch(Inner::Click, &runtime, deliver.clone()),
+            Some(Inner::Click)
+        );
+        assert!(delivered.lock().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(*delivered.lock(), vec![Inner::Query("mat")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttle_delivers_the_first_event_and_the_latest_per_interval() {
+        let runtime = tokio::runtime::Handle::current();
+        let scheduler = EventScheduler::default()
+            .with_rule(DispatchPolicy::Throttle(Duration::from_millis(100)), |e| {
+                matches!(e, Inner::Scroll(_))
+            });
+        let (delivered, deliver) = recorder();
+
+        assert_eq!(
+            scheduler.dispatch(Inner::Scroll(1), &runtime, deliver.clone()),
+            Some(Inner::Scroll(1))
+        );
+        for offset in 2..5 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert_eq!(
+                scheduler.dispatch(Inner::Scroll(offset), &runtime, deliver.clone()),
+                None
+            );
+        }
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(*delivered.lock(), vec![Inner::Scroll(4)]);
+
+        // the trailing delivery started a new interval
+        assert_eq!(
+            scheduler.dispatch(Inner::Scroll(5), &runtime, deliver.clone()),
+            None
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*delivered.lock(), vec![Inner::Scroll(4), Inner::Scroll(5)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dropping_the_scheduler_cancels_pending_events() {
+        let runtime = tokio::runtime::Handle::current();
+        let scheduler = EventScheduler::default()
+            .with_rule(DispatchPolicy::Debounce(Duration::from_millis(10)), |_| {
+                true
+            });
+        let (delivered, deliver) = recorder();
+
+        scheduler.dispatch(Inner::Click, &runtime, deliver);
+        drop(scheduler);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(delivered.lock().is_empty());
+    }
+}
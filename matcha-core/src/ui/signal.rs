@@ -0,0 +1,386 @@
+//! Fine-grained change tracking for component models.
+//!
+//! Any model update makes the component rebuild its view and diff the new `Dom` against the
+//! widget tree. For large views most of that work is wasted: a list whose items did not change
+//! is diffed item by item anyway.
+//!
+//! Wrapping model fields in [`Signal`]s lets the runtime tell which parts changed. Every write
+//! to a signal gives it a new [`version`](Signal::version); a subtree wrapped in [`tracked`]
+//! is only built and diffed when one of the versions it depends on differs from the previous
+//! update.
+//!
+//! ```ignore
+//! struct Model {
+//!     items: Signal<Vec<Item>>,
+//!     status: Signal<String>,
+//! }
+//!
+//! fn view(model: &Model) -> Box<dyn Dom<Event>> {
+//!     Box::new(Column::new()
+//!         .push(Text::new(&model.status))
+//!         // not diffed when only the status changed
+//!         .push(tracked([model.items.version()], || item_list(&model.items))))
+//! }
+//! ```
+//!
+//! The dependencies must cover everything the subtree is built from; a subtree that reads a
+//! value not listed keeps showing its old state.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::trace;
+use renderer::RenderNode;
+use smallvec::SmallVec;
+use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateNotifier};
+
+use crate::{
+    capture::{CaptureTarget, CapturedSubtree},
+    context::WidgetContext,
+    device_input::DeviceInput,
+    metrics::Constraints,
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, HitTestEntry, UpdateWidgetError,
+        WidgetSnapshot,
+        memo::{in_view, memo},
+    },
+};
+
+// shared by all signals, so versions of different signals never collide
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) fn next_version() -> u64 {
+    NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A model value that records when it was written.
+///
+/// Reads go through `Deref`; every write, including [`get_mut`](Self::get_mut), assigns a new
+/// version even if the value stays equal. Use [`set_if_changed`](Self::set_if_changed) to
+/// keep the version for equal values.
+#[derive(Debug, Clone)]
+pub struct Signal<T> {
+    value: T,
+    version: u64,
+}
+
+impl<T: Default> Default for Signal<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> std::ops::Deref for Signal<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> From<T> for Signal<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            version: next_version(),
+        }
+    }
+
+    /// Identifies the current value. Changes on every write.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.version = next_version();
+    }
+
+    /// Sets `value` and returns `true` if it differs from the current one. An equal value
+    /// keeps the version, so tracked subtrees are not diffed.
+    pub fn set_if_changed(&mut self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        if self.value == value {
+            return false;
+        }
+        self.set(value);
+        true
+    }
+
+    pub fn update(&mut self, f: impl FnOnce(&mut T)) {
+        f(&mut self.value);
+        self.version = next_version();
+    }
+
+    /// Mutable access. Counts as a write.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.version = next_version();
+        &mut self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+pub(crate) type Dependencies = SmallVec<[u64; 4]>;
+
+/// Returns the `Dom` built by `dom`, which is only called and whose widgets are only updated
+/// when `dependencies` changed since the previous view. Pass the [versions](Signal::version)
+/// of the signals the subtree is built from. See the [module documentation](self).
+///
+/// Like [`memo`], the built `Dom` is cached per component and call site; outside of a
+/// component's view `dom` is called every time.
+#[track_caller]
+pub fn tracked<E: 'static>(
+    dependencies: impl IntoIterator<Item = u64>,
+    dom: impl FnOnce() -> Box<dyn Dom<E>>,
+) -> Box<dyn Dom<E>> {
+    let dependencies: Dependencies = dependencies.into_iter().collect();
+    if in_view() {
+        // the cached dom is handed out again while the versions are unchanged
+        return memo(dependencies, dom);
+    }
+    Box::new(TrackedDom::new(dependencies, Arc::from(dom())))
+}
+
+pub struct TrackedDom<E: 'static> {
+    dependencies: Dependencies,
+    // shared so that memoized doms can be handed out again, see `memo`
+    dom: Arc<dyn Dom<E>>,
+}
+
+impl<E: 'static> TrackedDom<E> {
+    pub(crate) fn new(dependencies: Dependencies, dom: Arc<dyn Dom<E>>) -> Self {
+        Self { dependencies, dom }
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: 'static> Dom<E> for TrackedDom<E> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<E>> {
+        Box::new(TrackedWidget {
+            dependencies: self.dependencies.clone(),
+            widget_tree: self.dom.build_widget_tree(),
+        })
+    }
+}
+
+pub struct TrackedWidget<E: 'static> {
+    // dependencies of the dom the widgets were last updated from
+    dependencies: Dependencies,
+    widget_tree: Box<dyn AnyWidgetFrame<E>>,
+}
+
+impl<E: 'static> TrackedWidget<E> {
+    /// Records `dependencies` and returns whether the subtree has to be updated.
+    fn dependencies_changed(&mut self, dependencies: &Dependencies) -> bool {
+        if self.dependencies == *dependencies {
+            return false;
+        }
+        self.dependencies.clone_from(dependencies);
+        true
+    }
+}
+
+impl<E: 'static> AnyWidget<E> for TrackedWidget<E> {
+    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<E> {
+        self.widget_tree.device_input(event, ctx)
+    }
+
+    fn is_inside(&self, position: [f32; 2], ctx: &WidgetContext) -> bool {
+        self.widget_tree.is_inside(position, ctx)
+    }
+
+    fn measure(&self, constraints: &Constraints, ctx: &WidgetContext) -> [f32; 2] {
+        self.widget_tree.measure(constraints, ctx)
+    }
+
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        self.widget_tree.baseline(constraints, ctx)
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.min_intrinsic_width(height, ctx)
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.max_intrinsic_width(height, ctx)
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.min_intrinsic_height(width, ctx)
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.widget_tree.max_intrinsic_height(width, ctx)
+    }
+
+    fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
+        self.widget_tree.render(background, ctx)
+    }
+}
+
+#[async_trait::async_trait]
+impl<E: 'static> AnyWidgetFrame<E> for TrackedWidget<E> {
+    fn label(&self) -> Option<&str> {
+        self.widget_tree.label()
+    }
+
+    fn need_redraw(&self) -> bool {
+        self.widget_tree.need_redraw()
+    }
+
+    async fn update_widget_tree(&mut self, dom: &dyn Dom<E>) -> Result<(), UpdateWidgetError> {
+        let dom = (dom as &dyn std::any::Any)
+            .downcast_ref::<TrackedDom<E>>()
+            .ok_or(UpdateWidgetError::TypeMismatch)?;
+
+        if !self.dependencies_changed(&dom.dependencies) {
+            trace!("TrackedWidget::update_widget_tree: dependencies unchanged, skipping subtree");
+            return Ok(());
+        }
+
+        if let Err(UpdateWidgetError::TypeMismatch) =
+            self.widget_tree.update_widget_tree(&*dom.dom).await
+        {
+            self.widget_tree = dom.dom.build_widget_tree();
+        }
+        Ok(())
+    }
+
+    async fn set_model_update_notifier(&self, notifier: &UpdateNotifier) {
+        self.widget_tree.set_model_update_notifier(notifier).await;
+    }
+
+    fn arrange(&self, bounds: [f32; 2], ctx: &WidgetContext) {
+        self.widget_tree.arrange(bounds, ctx)
+    }
+
+    fn update_dirty_flags(&mut self, rearrange_flags: BackPropDirty, redraw_flags: BackPropDirty) {
+        self.widget_tree
+            .update_dirty_flags(rearrange_flags, redraw_flags);
+    }
+
+    fn invalidate_render_cache(&mut self) {
+        self.widget_tree.invalidate_render_cache();
+    }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.widget_tree.update_gpu_device(device, queue);
+    }
+
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.widget_tree.update_lifecycle(visible_rect, ctx);
+    }
+
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        self.widget_tree.prepare(visible_rect, ctx);
+    }
+
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool {
+        self.widget_tree
+            .hit_test(id, position, to_window, ctx, path)
+    }
+
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot> {
+        self.widget_tree.snapshot(id, to_window)
+    }
+
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree> {
+        self.widget_tree.find_rendered(id, target)
+    }
+
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext) {
+        self.widget_tree.set_focus(path, ctx);
+    }
+
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<E> {
+        self.widget_tree.take_deferred_events(ctx)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::ui::memo::{MemoCache, view_scope};
+
+    #[test]
+    fn writes_change_the_version() {
+        let mut signal = Signal::new(vec![1, 2]);
+        let initial = signal.version();
+
+        assert!(!signal.set_if_changed(vec![1, 2]));
+        assert_eq!(signal.version(), initial);
+
+        signal.update(|items| items.push(3));
+        let updated = signal.version();
+        assert_ne!(updated, initial);
+        assert_eq!(*signal, vec![1, 2, 3]);
+
+        signal.get_mut().clear();
+        assert_ne!(signal.version(), updated);
+
+        // distinct signals never share a version
+        assert_ne!(Signal::new(0).version(), Signal::new(0).version());
+    }
+
+    struct Leaf;
+
+    impl Dom<()> for Leaf {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<()>> {
+            unreachable!("these tests never build widgets")
+        }
+    }
+
+    #[test]
+    fn builds_again_only_when_a_dependency_changed() {
+        let cache = Arc::new(parking_lot::Mutex::new(MemoCache::default()));
+        let builds = std::cell::Cell::new(0);
+        let build = || {
+            builds.set(builds.get() + 1);
+            Box::new(Leaf) as Box<dyn Dom<()>>
+        };
+        let mut items = Signal::new(vec![1]);
+        let status = Signal::new("ready");
+
+        let view = |items: &Signal<Vec<u32>>| {
+            view_scope(&cache, || {
+                tracked([items.version(), status.version()], build);
+            })
+        };
+
+        view(&items);
+        view(&items);
+        assert_eq!(builds.get(), 1);
+
+        items.update(|items| items.push(2));
+        view(&items);
+        assert_eq!(builds.get(), 2);
+
+        // outside of a view every call builds
+        tracked([items.version()], build);
+        tracked([items.version()], build);
+        assert_eq!(builds.get(), 4);
+    }
+}
@@ -1,4 +1,9 @@
-use std::{any::Any, sync::Arc};
+use std::{
+    any::Any,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Weak},
+};
 
 use log::{debug, trace, warn};
 use parking_lot::Mutex;
@@ -7,14 +12,27 @@ use smallvec::SmallVec;
 use utils::{back_prop_dirty::BackPropDirty, cache::Cache, update_flag::UpdateNotifier};
 
 use crate::{
+    cache_budget::{BudgetedCache, CacheUsage},
+    capture::{CaptureTarget, CapturedSubtree},
     context::WidgetContext,
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints, QSize},
-    ui::Background,
+    ui::{
+        Background, HitTestEntry, LayoutStyle, WidgetSnapshot,
+        focus::FocusState,
+        keyed,
+        overflow::{Overflow, children_overflow},
+    },
 };
 
 const SMALLVEC_INLINE_CAPACITY: usize = 16;
 
+/// Future returned by [`Widget::prepare`].
+pub type PrepareFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Future returned by [`Widget::update_owned_children`].
+pub type OwnedChildrenUpdate<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
 /// Lightweight handle passed into widget update / event handlers allowing them
 /// to request layout or visual invalidation without touching internal caches.
 ///
@@ -26,15 +44,15 @@ const SMALLVEC_INLINE_CAPACITY: usize = 16;
 /// - Requests are frame-scoped: flags are consumed internally during `measure/arrange`
 ///   (layout) or after a successful `render` (redraw).
 /// - Do NOT store this handle beyond the synchronous call; it borrows internal flags.
+///   Async tasks use [`detach`](Self::detach) to get a handle they can keep.
 ///
 /// Future:
 /// - Could evolve into an enum-based invalidation or generation counter if finer
 ///   granularity / statistics are needed.
-/// - Async widgets: if future async tasks need to trigger invalidation, they will
-///   use a cloneable channel-based handle instead of this borrowed form.
 pub struct InvalidationHandle<'a> {
     need_rearrange: &'a BackPropDirty,
     need_redraw: &'a BackPropDirty,
+    shared_dirty_flags: &'a Arc<Mutex<Option<DirtyFlags>>>,
 }
 
 impl<'a> InvalidationHandle<'a> {
@@ -46,12 +64,96 @@ impl<'a> InvalidationHandle<'a> {
     pub fn redraw_next_frame(&self) {
         self.need_redraw.mark_dirty();
     }
+
+    /// A handle to the same widget that can be stored and used from any thread.
+    pub fn detach(&self) -> AsyncInvalidationHandle {
+        AsyncInvalidationHandle {
+            dirty_flags: Arc::downgrade(self.shared_dirty_flags),
+        }
+    }
+}
+
+/// Cloneable, `Send` counterpart of [`InvalidationHandle`] for work that finishes outside of
+/// the widget's synchronous calls, e.g. an image decoded on a worker or data arrived from the
+/// network.
+///
+/// Requests from any thread are picked up on the next frame. The handle keeps targeting the
+/// widget when it is relinked into the tree, and does nothing once the widget is dropped.
+#[derive(Clone)]
+pub struct AsyncInvalidationHandle {
+    dirty_flags: Weak<Mutex<Option<DirtyFlags>>>,
+}
+
+impl AsyncInvalidationHandle {
+    pub fn relayout_next_frame(&self) {
+        self.with_flags(|flags| {
+            flags.need_rearrange.mark_dirty();
+            flags.need_redraw.mark_dirty();
+        });
+    }
+
+    pub fn redraw_next_frame(&self) {
+        self.with_flags(|flags| flags.need_redraw.mark_dirty());
+    }
+
+    /// Returns `false` once the widget has been dropped.
+    pub fn is_alive(&self) -> bool {
+        self.dirty_flags.strong_count() > 0
+    }
+
+    fn with_flags(&self, f: impl FnOnce(&DirtyFlags)) {
+        if let Some(dirty_flags) = self.dirty_flags.upgrade()
+            && let Some(flags) = &*dirty_flags.lock()
+        {
+            f(flags);
+        }
+    }
+}
+
+/// Links child frames that a widget owns itself, instead of returning them from
+/// [`Widget::update_widget`], into the invalidation tree of the owning frame.
+///
+/// Handed to [`Widget::link_owned_children`]. Unlike [`InvalidationHandle`] it may be stored;
+/// widgets that build children lazily (e.g. virtualized lists) keep it and call
+/// [`link`](Self::link) on every frame they create.
+#[derive(Clone)]
+pub struct ChildFrameLinker {
+    need_rearrange: BackPropDirty,
+    need_redraw: BackPropDirty,
+}
+
+impl ChildFrameLinker {
+    /// Makes `frame` report its invalidations to the owning widget.
+    /// Must be called before the frame is measured, arranged or rendered.
+    pub fn link<E: 'static>(&self, frame: &mut dyn AnyWidgetFrame<E>) {
+        frame.update_dirty_flags(
+            self.need_rearrange.make_child(),
+            self.need_redraw.make_child(),
+        );
+    }
+
+    pub fn relayout_next_frame(&self) {
+        self.need_rearrange.mark_dirty();
+        self.need_redraw.mark_dirty();
+    }
+
+    pub fn redraw_next_frame(&self) {
+        self.need_redraw.mark_dirty();
+    }
 }
 
 #[async_trait::async_trait]
 pub trait Dom<E>: Send + Sync + Any {
     /// Builds the corresponding stateful `Widget` tree from this `Dom` node.
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<E>>;
+
+    /// Padding, margin and size limits applied around the widget by its [`WidgetFrame`].
+    ///
+    /// Descriptors that support a layout style return it here and also pass it to
+    /// [`WidgetFrame::with_layout_style`] when building; it is read again on every update.
+    fn layout_style(&self) -> LayoutStyle {
+        LayoutStyle::default()
+    }
 }
 
 pub trait Widget<D: Dom<E>, E: 'static = (), ChildSetting: PartialEq + 'static = ()>:
@@ -96,6 +198,80 @@ pub trait Widget<D: Dom<E>, E: 'static = (), ChildSetting: PartialEq + 'static =
         ctx: &WidgetContext,
     ) -> [f32; 2];
 
+    /// Distance from the top edge to the first text baseline when measured with `constraints`.
+    ///
+    /// Widgets without text content return `None` (the default). Containers that want to take
+    /// part in baseline alignment should forward the baseline of the appropriate child,
+    /// offset by the child's position.
+    fn baseline(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<E>, &ChildSetting)],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        let _ = (constraints, children, ctx);
+        None
+    }
+
+    /// The smallest width this widget can take without overflowing its content,
+    /// given `height` as the available height.
+    ///
+    /// Defaults to `max_intrinsic_width`; widgets whose content can wrap or shrink should override it.
+    fn min_intrinsic_width(
+        &self,
+        height: f32,
+        children: &[(&dyn AnyWidget<E>, &ChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.max_intrinsic_width(height, children, ctx)
+    }
+
+    /// The width beyond which increasing the width does not decrease the height,
+    /// given `height` as the available height.
+    ///
+    /// Defaults to measuring with unbounded width.
+    fn max_intrinsic_width(
+        &self,
+        height: f32,
+        children: &[(&dyn AnyWidget<E>, &ChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.measure(
+            &Constraints::new([0.0, f32::INFINITY], [0.0, height]),
+            children,
+            ctx,
+        )[0]
+    }
+
+    /// The smallest height this widget can take without overflowing its content,
+    /// given `width` as the available width.
+    ///
+    /// Defaults to `max_intrinsic_height`.
+    fn min_intrinsic_height(
+        &self,
+        width: f32,
+        children: &[(&dyn AnyWidget<E>, &ChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.max_intrinsic_height(width, children, ctx)
+    }
+
+    /// The preferred height of this widget given `width` as the available width.
+    ///
+    /// Defaults to measuring with unbounded height.
+    fn max_intrinsic_height(
+        &self,
+        width: f32,
+        children: &[(&dyn AnyWidget<E>, &ChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.measure(
+            &Constraints::new([0.0, width], [0.0, f32::INFINITY]),
+            children,
+            ctx,
+        )[1]
+    }
+
     /// The length of returned Vector must match the number of children.
     fn arrange(
         &self,
@@ -104,6 +280,13 @@ pub trait Widget<D: Dom<E>, E: 'static = (), ChildSetting: PartialEq + 'static =
         ctx: &WidgetContext,
     ) -> Vec<Arrangement>;
 
+    /// Whether children may be arranged beyond `bounds` on purpose, e.g. by a widget that
+    /// clips or scrolls them. Otherwise such children are reported as layout overflow, see
+    /// [`overflow`](super::overflow).
+    fn allows_overflow(&self) -> bool {
+        false
+    }
+
     fn render(
         &self,
         bounds: [f32; 2],
@@ -111,6 +294,116 @@ pub trait Widget<D: Dom<E>, E: 'static = (), ChildSetting: PartialEq + 'static =
         background: Background,
         ctx: &WidgetContext,
     ) -> RenderNode;
+
+    /// A hash of everything besides its size that `render` depends on, or `None` to rely on
+    /// redraw requests alone.
+    ///
+    /// The render cache is keyed on the size and this hash: the widget is re-rendered when the
+    /// hash changes even if no redraw was requested, and a redraw request is skipped when the
+    /// hash and all child frames are unchanged. Widgets rendering frames they own themselves
+    /// (see [`link_owned_children`](Self::link_owned_children)) should keep the default.
+    fn content_hash(&self) -> Option<u64> {
+        None
+    }
+
+    /// Called after the GPU device was lost and replaced.
+    ///
+    /// Widgets that own GPU objects outside of the shared atlases and renderers
+    /// (buffers, textures, bind groups) must recreate them here. Render caches are
+    /// cleared by the framework.
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let _ = (device, queue);
+    }
+
+    /// Called whenever the frame is attached to an invalidation tree, before its first layout.
+    ///
+    /// Widgets that own child frames outside of `update_widget` must keep `linker` and link
+    /// every such frame with it; the framework does not see those frames, so the widget also
+    /// forwards `prepare` and `update_gpu_device` to them.
+    fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+        let _ = linker;
+    }
+
+    /// Updates the child frames the widget owns itself, right after `update_widget`.
+    ///
+    /// Widgets that keep frames outside of `update_widget` (see
+    /// [`link_owned_children`](Self::link_owned_children)) return a future that awaits
+    /// `update_widget_tree` on them. Return `None` when there is nothing to update.
+    fn update_owned_children(&mut self) -> Option<OwnedChildrenUpdate<'_>> {
+        None
+    }
+
+    /// Optional asynchronous preparation that runs before `render`.
+    ///
+    /// Called by the rendering loop after layout on every frame in which the widget is visible,
+    /// its subtree was updated, laid out again or marked for redraw, and no earlier preparation
+    /// is still running. Return `None` when there is nothing to do.
+    /// The returned future runs on the async runtime without blocking rendering; it must hand
+    /// its results back through state shared with the widget (e.g. an `Arc<Mutex<_>>`), and
+    /// `render` should draw a placeholder until they are available. The widget is redrawn when
+    /// the future completes. It is cancelled when the widget scrolls fully out of view or is
+    /// removed from the tree.
+    fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        let _ = (bounds, ctx);
+        None
+    }
+
+    /// Called once when the widget joins the live tree, on the first frame it is laid out.
+    ///
+    /// Use it to start timers or subscriptions that should only run while the widget exists.
+    fn on_mount(&mut self, ctx: &WidgetContext) {
+        let _ = ctx;
+    }
+
+    /// Called once when a mounted widget leaves the live tree: removed or replaced by a
+    /// parent update, or dropped together with its window.
+    ///
+    /// Release resources eagerly here (atlas regions, timers) instead of waiting for `Drop`.
+    fn on_unmount(&mut self) {}
+
+    /// Called when the widget scrolls into (`true`) or out of (`false`) the visible area.
+    ///
+    /// A freshly mounted widget counts as hidden, so the first call reports `true` once it
+    /// appears on screen.
+    fn on_visibility_changed(&mut self, visible: bool, ctx: &WidgetContext) {
+        let _ = (visible, ctx);
+    }
+
+    /// Called after the mount and visibility hooks, before `prepare`, on the frames in which
+    /// `prepare` would be called.
+    ///
+    /// Widgets that own widget frames outside of their children (e.g. recycled list items)
+    /// must forward `AnyWidgetFrame::update_lifecycle` to them here.
+    fn update_lifecycle(&mut self, bounds: [f32; 2], ctx: &WidgetContext) {
+        let _ = (bounds, ctx);
+    }
+
+    /// Whether the widget takes keyboard focus, so Tab stops at it. See [`focus`](super::focus).
+    fn accepts_focus(&self) -> bool {
+        false
+    }
+
+    /// Whether the widget has keyboard focus, e.g. after it was clicked.
+    fn is_focused(&self) -> bool {
+        false
+    }
+
+    /// Gives the widget keyboard focus or takes it away when Tab moves focus. Only called on
+    /// widgets that [accept focus](Self::accepts_focus).
+    fn set_focused(
+        &mut self,
+        focused: bool,
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) {
+        let _ = (focused, cache_invalidator, ctx);
+    }
+
+    /// Whether the widget handles Tab itself while focused, e.g. to insert a tab character.
+    /// Ctrl+Tab moves focus out of it.
+    fn captures_tab(&self) -> bool {
+        false
+    }
 }
 
 /// Make trait object that can be used from widget implement.
@@ -121,6 +414,16 @@ pub trait AnyWidget<E: 'static> {
 
     fn measure(&self, constraints: &Constraints, ctx: &WidgetContext) -> [f32; 2];
 
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32>;
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32;
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32;
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32;
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32;
+
     fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode>;
 }
 
@@ -145,6 +448,60 @@ pub trait AnyWidgetFrame<E: 'static>: AnyWidget<E> + std::any::Any + Send + Sync
     fn update_dirty_flags(&mut self, rearrange_flags: BackPropDirty, redraw_flags: BackPropDirty);
 
     fn invalidate_render_cache(&mut self);
+
+    /// Propagates a recovered GPU device down the tree and clears render caches.
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue);
+
+    /// Reports mounting and visibility changes in this subtree to `Widget::on_mount` and
+    /// `Widget::on_visibility_changed`. Must be called after `arrange` and before `prepare`.
+    ///
+    /// `visible_rect` is the on-screen part of the widget as `[min, max]` in local
+    /// coordinates, or `None` when the widget is entirely off-screen. Subtrees that were not
+    /// updated, laid out again or marked for redraw since the last call are skipped.
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext);
+
+    /// Runs the prepare phase for this subtree. Must be called after `update_lifecycle`.
+    ///
+    /// `visible_rect` is as in [`update_lifecycle`](Self::update_lifecycle), and unchanged
+    /// subtrees are skipped the same way.
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext);
+
+    /// Appends the widgets under `position` (local coordinates) to `path`, this widget first,
+    /// using the layout of the last frame. Returns `false` and leaves `path` unchanged when
+    /// nothing in this subtree is hit.
+    ///
+    /// `id` is the key of this widget among its siblings and `to_window` maps its local
+    /// coordinates to window coordinates. Use [`hit_test()`](super::hit_test()) on the root.
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool;
+
+    /// Describes this subtree as laid out in the last frame, or `None` if it has not been laid
+    /// out. `id` and `to_window` are as in [`hit_test`](Self::hit_test).
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot>;
+
+    /// The render node cached in the last frame by the first widget of this subtree, in
+    /// depth-first order, that matches `target`. `id` is as in [`hit_test`](Self::hit_test).
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree>;
+
+    /// Gives keyboard focus to the widget at `path`, the keys from the children of this widget
+    /// down to the target, and takes it from every other widget in this subtree. An empty path
+    /// is this widget; `None` takes focus from the whole subtree. See [`focus`](super::focus).
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext);
+
+    /// Takes the events for the parent that this subtree produced outside of input handling,
+    /// e.g. from a debounced component event. A component that produces one asks its window to
+    /// collect them, see [`schedule`](super::schedule).
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<E>;
 }
 
 /// Represents an error that can occur when updating a `Widget` tree.
@@ -169,19 +526,228 @@ where
     // we separate child ids from their settings and arrangement because they are used independently.
     children_id: Vec<u128>, // hash
 
+    /// padding, margin and size limits applied around `widget_impl`.
+    layout_style: LayoutStyle,
+
     // dirty flags
     // need_rearrange: BackPropDirty,
     // need_redraw: BackPropDirty,
     dirty_flags: Option<DirtyFlags>,
+    // copy of `dirty_flags` that `AsyncInvalidationHandle`s refer to
+    shared_dirty_flags: Arc<Mutex<Option<DirtyFlags>>>,
+
+    /// cache, shared with the cache budget that may evict it.
+    cache: Arc<Mutex<WidgetFrameCache>>,
+
+    /// in-flight `Widget::prepare` future.
+    prepare_task: Option<PrepareTask>,
+
+    // lifecycle state reported through `Widget::on_mount` / `on_visibility_changed`
+    mounted: bool,
+    visible: bool,
 
-    /// cache
-    cache: Mutex<WidgetFrameCache>,
+    // what `update_lifecycle` and `prepare` last walked this subtree with; `None` until the
+    // tree changes are walked again
+    lifecycle_walk: Option<WalkKey>,
+    prepare_walk: Option<WalkKey>,
 
     /// impl the widget process.
     widget_impl: W,
     _dom_type: std::marker::PhantomData<D>,
 }
 
+impl<D, W, E, ChildSetting> Drop for WidgetFrame<D, W, E, ChildSetting>
+where
+    D: Dom<E> + Send + Sync + 'static,
+    W: Widget<D, E, ChildSetting> + Send + Sync + 'static,
+    E: 'static,
+    ChildSetting: Send + Sync + PartialEq + Clone + 'static,
+{
+    fn drop(&mut self) {
+        // frames leave the live tree by being dropped: removed or replaced children in
+        // `update_widget_tree`, or the whole tree when its window closes.
+        if self.mounted {
+            trace!("Unmounting widget '{}'", self.log_label());
+            self.widget_impl.on_unmount();
+        }
+    }
+}
+
+/// Aborts the spawned preparation when dropped.
+struct PrepareTask {
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for PrepareTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Axis-aligned bounding box of the rectangle `[min, max]` after `transform`.
+pub(super) fn bounding_box(
+    transform: &nalgebra::Matrix4<f32>,
+    [min, max]: [[f32; 2]; 2],
+) -> [[f32; 2]; 2] {
+    let corners = [
+        [min[0], min[1]],
+        [min[0], max[1]],
+        [max[0], min[1]],
+        [max[0], max[1]],
+    ]
+    .map(|[x, y]| transform * nalgebra::Vector4::new(x, y, 0.0, 1.0));
+    let mut bb = [[f32::INFINITY; 2], [f32::NEG_INFINITY; 2]];
+    for c in corners {
+        for axis in 0..2 {
+            bb[0][axis] = bb[0][axis].min(c[axis]);
+            bb[1][axis] = bb[1][axis].max(c[axis]);
+        }
+    }
+    bb
+}
+
+/// Visible part of a child in its local coordinates, given the visible part of its parent.
+fn child_visible_rect(
+    parent_visible_rect: [[f32; 2]; 2],
+    arrangement: &Arrangement,
+) -> Option<[[f32; 2]; 2]> {
+    let affine_inv = arrangement.affine_inv.as_ref()?;
+
+    // child bounds in parent coordinates, clipped to the visible part of the parent
+    let bounds = bounding_box(&arrangement.affine, [[0.0, 0.0], arrangement.size]);
+    let clipped = [
+        [
+            bounds[0][0].max(parent_visible_rect[0][0]),
+            bounds[0][1].max(parent_visible_rect[0][1]),
+        ],
+        [
+            bounds[1][0].min(parent_visible_rect[1][0]),
+            bounds[1][1].min(parent_visible_rect[1][1]),
+        ],
+    ];
+    if clipped[0][0] >= clipped[1][0] || clipped[0][1] >= clipped[1][1] {
+        return None;
+    }
+
+    let local = bounding_box(affine_inv, clipped);
+    Some([
+        [local[0][0].max(0.0), local[0][1].max(0.0)],
+        [
+            local[1][0].min(arrangement.size[0]),
+            local[1][1].min(arrangement.size[1]),
+        ],
+    ])
+}
+
+/// A child as handed to a [`Widget`] implementation: applies the clip and opacity of its
+/// [`Arrangement`] to what it renders and to the pointer input it receives.
+struct ArrangedChild<'a, T: 'static> {
+    child: ChildRef<'a, T>,
+    arrangement: &'a Arrangement,
+}
+
+enum ChildRef<'a, T: 'static> {
+    Shared(&'a dyn AnyWidget<T>),
+    Exclusive(&'a mut dyn AnyWidget<T>),
+}
+
+impl<'a, T: 'static> ArrangedChild<'a, T> {
+    fn shared(child: &'a dyn AnyWidget<T>, arrangement: &'a Arrangement) -> Self {
+        Self {
+            child: ChildRef::Shared(child),
+            arrangement,
+        }
+    }
+
+    fn exclusive(child: &'a mut dyn AnyWidget<T>, arrangement: &'a Arrangement) -> Self {
+        Self {
+            child: ChildRef::Exclusive(child),
+            arrangement,
+        }
+    }
+
+    fn child(&self) -> &dyn AnyWidget<T> {
+        match &self.child {
+            ChildRef::Shared(child) => *child,
+            ChildRef::Exclusive(child) => &**child,
+        }
+    }
+}
+
+impl<T: 'static> AnyWidget<T> for ArrangedChild<'_, T> {
+    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<T> {
+        // shared children are only handed out for passes that cannot take input
+        let ChildRef::Exclusive(child) = &mut self.child else {
+            return None;
+        };
+        if event
+            .mouse_position()
+            .is_some_and(|position| self.arrangement.clips(position))
+        {
+            child.device_input(&event.with_pointer_outside(), ctx)
+        } else {
+            child.device_input(event, ctx)
+        }
+    }
+
+    fn is_inside(&self, position: [f32; 2], ctx: &WidgetContext) -> bool {
+        !self.arrangement.clips(position) && self.child().is_inside(position, ctx)
+    }
+
+    fn measure(&self, constraints: &Constraints, ctx: &WidgetContext) -> [f32; 2] {
+        self.child().measure(constraints, ctx)
+    }
+
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        self.child().baseline(constraints, ctx)
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.child().min_intrinsic_width(height, ctx)
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.child().max_intrinsic_width(height, ctx)
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.child().min_intrinsic_height(width, ctx)
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.child().max_intrinsic_height(width, ctx)
+    }
+
+    fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
+        let node = self.child().render(background, ctx);
+        let arrangement = self.arrangement;
+        if !arrangement.clip && arrangement.opacity >= 1.0 {
+            return node;
+        }
+
+        // the wrapper takes the child's place among its siblings
+        let node = RenderNode::new()
+            .with_z_index(node.z_index())
+            .add_child(node, nalgebra::Matrix4::identity())
+            .with_opacity(arrangement.opacity);
+        if arrangement.clip {
+            Arc::new(node.with_clip(arrangement.size))
+        } else {
+            Arc::new(node)
+        }
+    }
+}
+
+/// Kind of intrinsic size query. Used as part of the intrinsic size cache key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum IntrinsicDimension {
+    MinWidth,
+    MaxWidth,
+    MinHeight,
+    MaxHeight,
+}
+
+#[derive(Clone)]
 struct DirtyFlags {
     need_rearrange: BackPropDirty,
     need_redraw: BackPropDirty,
@@ -190,10 +756,95 @@ struct DirtyFlags {
 struct WidgetFrameCache {
     /// cache the output of measure method.
     measure: Cache<Constraints, [f32; 2]>,
+    /// cache the output of baseline method.
+    baseline: Cache<Constraints, Option<f32>>,
+    /// cache the output of intrinsic size queries keyed by (dimension, extent bits).
+    /// Parents typically ask several different queries per layout pass, so this keeps every entry
+    /// until the next relayout.
+    intrinsic: fxhash::FxHashMap<(IntrinsicDimension, u32), f32>,
     /// cache the output of layout method.
     layout: Cache<QSize, Vec<Arrangement>>,
     /// cache the output of render method.
-    render: Cache<QSize, Arc<RenderNode>>,
+    render: Cache<RenderKey, Arc<RenderNode>>,
+    /// bumped on every redraw request of a widget without a content hash.
+    render_generation: u64,
+    /// bumped whenever `layout` is recomputed.
+    layout_generation: u64,
+
+    /// estimated bytes of `layout` and `render`, reported to the cache budget.
+    memory_size: usize,
+    /// cache budget tick of the last arrange or render.
+    last_used: u64,
+    /// whether the cache is registered with the cache budget.
+    budgeted: bool,
+    /// flags of the owning frame, to request a relayout after eviction.
+    dirty_flags: Weak<Mutex<Option<DirtyFlags>>>,
+    /// how far children extend beyond the content box in the cached layout.
+    overflow: Overflow,
+}
+
+impl WidgetFrameCache {
+    fn estimated_memory_size(&self) -> usize {
+        let layout = self.layout.get().map_or(0, |(_, arrangement)| {
+            arrangement.capacity() * std::mem::size_of::<Arrangement>()
+        });
+        let render = self
+            .render
+            .get()
+            .map_or(0, |(_, node)| node.owned_memory_size());
+        layout + render
+    }
+}
+
+impl BudgetedCache for Mutex<WidgetFrameCache> {
+    fn usage(&self) -> Option<CacheUsage> {
+        let cache = self.try_lock()?;
+        Some(CacheUsage {
+            bytes: cache.memory_size,
+            last_used: cache.last_used,
+            // a node still referenced is on screen or in an ancestor's cache
+            evictable: cache
+                .render
+                .get()
+                .is_some_and(|(_, node)| Arc::strong_count(node) == 1),
+        })
+    }
+
+    fn evict(&self) -> bool {
+        let Some(mut cache) = self.try_lock() else {
+            return false;
+        };
+        cache.measure.clear();
+        cache.baseline.clear();
+        cache.intrinsic.clear();
+        cache.layout.clear();
+        cache.render.clear();
+        cache.memory_size = 0;
+        // render and input need the arrangement; have it recomputed before the next use
+        if let Some(flags) = cache.dirty_flags.upgrade()
+            && let Some(flags) = &*flags.lock()
+        {
+            flags.need_rearrange.mark_dirty();
+        }
+        true
+    }
+}
+
+/// Key of the render cache.
+#[derive(Clone, Copy, PartialEq)]
+struct RenderKey {
+    size: QSize,
+    generation: u64,
+    content_hash: Option<u64>,
+    // cached nodes sample garbage once the atlases recover and move their regions
+    atlas_generations: [u64; 2],
+}
+
+/// What the lifecycle and prepare walks of a subtree depend on besides its dirty flags.
+#[derive(Clone, Copy, PartialEq)]
+struct WalkKey {
+    visible_rect: Option<[[f32; 2]; 2]>,
+    layout_generation: u64,
 }
 
 impl<D, W, E, ChildSetting> WidgetFrame<D, W, E, ChildSetting>
@@ -214,64 +865,334 @@ where
             label.as_deref().unwrap_or("<unnamed>")
         );
 
+        let shared_dirty_flags = Arc::new(Mutex::new(None));
+        let dirty_flags = Arc::downgrade(&shared_dirty_flags);
+
         Self {
             label,
             children,
             children_id,
+            layout_style: LayoutStyle::default(),
             dirty_flags: None,
-            cache: Mutex::new(WidgetFrameCache {
+            shared_dirty_flags,
+            cache: Arc::new(Mutex::new(WidgetFrameCache {
                 measure: Cache::new(),
+                baseline: Cache::new(),
+                intrinsic: fxhash::FxHashMap::default(),
                 layout: Cache::new(),
                 render: Cache::new(),
-            }),
+                render_generation: 0,
+                layout_generation: 0,
+                memory_size: 0,
+                last_used: 0,
+                budgeted: false,
+                dirty_flags,
+                overflow: Overflow::default(),
+            })),
+            prepare_task: None,
+            mounted: false,
+            visible: false,
+            lifecycle_walk: None,
+            prepare_walk: None,
             widget_impl,
             _dom_type: std::marker::PhantomData,
         }
     }
 
-    fn log_label(&self) -> &str {
-        let label = self.label.as_deref().unwrap_or("<unnamed>");
-        trace!("log_label() called, returning '{}'", label);
-        label
+    /// Applies `layout_style` around the widget. Descriptors call this with the style they
+    /// return from [`Dom::layout_style`].
+    pub fn with_layout_style(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
     }
-}
 
-impl<D, W, T, ChildSetting> AnyWidget<T> for WidgetFrame<D, W, T, ChildSetting>
-where
-    D: Dom<T> + Send + Sync + 'static,
-    W: Widget<D, T, ChildSetting> + Send + Sync + 'static,
-    T: 'static,
-    ChildSetting: Send + Sync + PartialEq + Clone + 'static,
-{
-    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<T> {
-        let Some(dirty_flags) = &self.dirty_flags else {
-            warn!(
-                "Widget '{}' received device_input before dirty flags were initialized.",
-                self.log_label()
-            );
-            return None;
-        };
+    /// Makes the next `update_lifecycle` and `prepare` visit the whole subtree again.
+    fn forget_walks(&mut self) {
+        self.lifecycle_walk = None;
+        self.prepare_walk = None;
+    }
 
-        let label = self.log_label();
-        trace!("Processing device_input for widget '{}'", label);
+    /// The key of a walk reaching this widget with `visible_rect`, or `None` when the last walk
+    /// (`last`) saw the same and nothing in the subtree was marked for redraw since.
+    fn walk_key(
+        &self,
+        visible_rect: Option<[[f32; 2]; 2]>,
+        last: Option<WalkKey>,
+    ) -> Option<WalkKey> {
+        let walk = WalkKey {
+            visible_rect,
+            layout_generation: self.cache.lock().layout_generation,
+        };
+        (last != Some(walk) || self.need_redraw()).then_some(walk)
+    }
 
-        let cache = self.cache.lock();
+    /// The size of the content box, inside margin and padding, once the widget was laid out.
+    fn content_bounds(&self) -> Option<[f32; 2]> {
+        let bounds = self
+            .cache
+            .lock()
+            .layout
+            .get()
+            .map(|(size, _)| <[f32; 2]>::from(size))?;
+        Some(self.layout_style.content_bounds(bounds))
+    }
 
-        let Some((&actual_bounds, arrangement)) = cache.layout.get() else {
-            // not rendered yet, cannot process event.
-            return None;
+    /// The visible parts of the children in their local coordinates, given the visible part
+    /// of this widget: all `None` when it is off-screen, and `None` overall when it is visible
+    /// but was not laid out yet.
+    fn child_visible_rects(
+        &self,
+        visible_rect: Option<[[f32; 2]; 2]>,
+    ) -> Option<Vec<Option<[[f32; 2]; 2]>>> {
+        let Some(visible_rect) = visible_rect else {
+            return Some(Vec::new());
         };
+        let cache = self.cache.lock();
+        let (bounds, arrangement) = cache.layout.get()?;
+
+        // children are placed relative to the content, inside margin and padding
+        let bounds = self.layout_style.content_bounds(bounds.into());
+        let offset = self.layout_style.content_offset();
+        let visible_rect = [
+            [
+                (visible_rect[0][0] - offset[0]).max(0.0),
+                (visible_rect[0][1] - offset[1]).max(0.0),
+            ],
+            [
+                (visible_rect[1][0] - offset[0]).min(bounds[0]),
+                (visible_rect[1][1] - offset[1]).min(bounds[1]),
+            ],
+        ];
+        Some(
+            arrangement
+                .iter()
+                .map(|arrangement| child_visible_rect(visible_rect, arrangement))
+                .collect(),
+        )
+    }
 
-        let actual_bounds: [f32; 2] = actual_bounds.into();
+    /// Translation from the outer box to the widget's content.
+    fn content_transform(&self) -> nalgebra::Matrix4<f32> {
+        let [x, y] = self.layout_style.content_offset();
+        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0))
+    }
 
-        let mut children_with_arrangement: SmallVec<
-            [(&mut dyn AnyWidget<T>, &mut ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
-        > = self
-            .children
-            .iter_mut()
-            .zip(arrangement.iter())
-            .map(|((child, setting), arr)| (&mut **child as &mut dyn AnyWidget<T>, setting, arr))
-            .collect();
+    fn log_label(&self) -> &str {
+        let label = self.label.as_deref().unwrap_or("<unnamed>");
+        trace!("log_label() called, returning '{}'", label);
+        label
+    }
+
+    /// Marks the cache as used in this frame for the cache budget, and re-estimates its size
+    /// when `changed`.
+    fn account_cache(&self, cache: &mut WidgetFrameCache, changed: bool, ctx: &WidgetContext) {
+        let Some(budget) = ctx.cache_budget() else {
+            return;
+        };
+        if changed {
+            cache.memory_size = cache.estimated_memory_size();
+        }
+        cache.last_used = budget.tick();
+        if !cache.budgeted {
+            let entry: Arc<dyn BudgetedCache> = self.cache.clone();
+            budget.register(Arc::downgrade(&entry));
+            cache.budgeted = true;
+        }
+    }
+
+    /// Renders the widget with its layout style applied, without the overflow indicator.
+    fn render_content(
+        &self,
+        bounds: [f32; 2],
+        arrangement: &[Arrangement],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> Arc<RenderNode> {
+        let content_bounds = self.layout_style.content_bounds(bounds);
+        let arranged_children: SmallVec<[ArrangedChild<E>; SMALLVEC_INLINE_CAPACITY]> = self
+            .children
+            .iter()
+            .zip(arrangement)
+            .map(|((c, _), a)| ArrangedChild::shared(&**c, a))
+            .collect();
+        let children_triples: SmallVec<
+            [(&dyn AnyWidget<E>, &ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
+        > = arranged_children
+            .iter()
+            .zip(&self.children)
+            .map(|(c, (_, s))| (c as &dyn AnyWidget<E>, s, c.arrangement))
+            .collect();
+
+        if self.layout_style.is_empty() {
+            return Arc::new(
+                self.widget_impl
+                    .render(bounds, &children_triples, background, ctx),
+            );
+        }
+
+        let node = self.widget_impl.render(
+            content_bounds,
+            &children_triples,
+            background.translate(self.layout_style.content_offset()),
+            ctx,
+        );
+        let translation = |[x, y]: [f32; 2]| {
+            nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0))
+        };
+        let [min, max] = self.layout_style.border_box(bounds);
+        let border_size = [max[0] - min[0], max[1] - min[1]];
+        let node = match self.layout_style.clip_radius {
+            None => RenderNode::new().add_child(node, self.content_transform()),
+            Some(radius) => {
+                // clips start at the node's origin, so the clipped node sits at the border box
+                let padding = &self.layout_style.padding;
+                let clipped = RenderNode::new()
+                    .add_child(node, translation([padding.left, padding.top]))
+                    .with_rounded_clip(border_size, radius);
+                RenderNode::new().add_child(clipped, translation(min))
+            }
+        };
+
+        let elevation = self.layout_style.elevation;
+        if elevation.level() == 0 {
+            return Arc::new(node);
+        }
+        // the shadow lies outside the clip, under the content
+        let mut elevated = RenderNode::new().with_z_index(elevation.z_bias());
+        let corner_radius = self.layout_style.clip_radius.unwrap_or(0.0);
+        if let Some(shadow) = elevation.shadow_node(border_size, corner_radius, ctx) {
+            elevated.push_child(shadow, translation(min));
+        }
+        elevated.push_child(node, nalgebra::Matrix4::identity());
+        Arc::new(elevated)
+    }
+
+    fn intrinsic_size(
+        &self,
+        dimension: IntrinsicDimension,
+        extent: f32,
+        ctx: &WidgetContext,
+    ) -> f32 {
+        let Some(dirty_flags) = &self.dirty_flags else {
+            return 0.0;
+        };
+
+        let label = self.log_label();
+        trace!(
+            "Querying intrinsic {:?} of widget '{}' with extent {}",
+            dimension, label, extent
+        );
+
+        let mut cache = self.cache.lock();
+
+        if dirty_flags.need_rearrange.take_dirty() {
+            debug!(
+                "intrinsic size invalidated by need_rearrange for '{}'",
+                label
+            );
+            cache.measure.clear();
+            cache.baseline.clear();
+            cache.intrinsic.clear();
+            cache.layout.clear();
+        }
+
+        if ctx.debug_config_disable_layout_measure_cache() {
+            cache.intrinsic.clear();
+        }
+
+        *cache
+            .intrinsic
+            .entry((dimension, extent.to_bits()))
+            .or_insert_with(|| {
+                if !self.layout_style.display {
+                    return 0.0;
+                }
+                let children: SmallVec<
+                    [(&dyn AnyWidget<E>, &ChildSetting); SMALLVEC_INLINE_CAPACITY],
+                > = self
+                    .children
+                    .iter()
+                    .map(|(child, setting)| (&**child as &dyn AnyWidget<E>, setting))
+                    .collect();
+
+                let axis = match dimension {
+                    IntrinsicDimension::MinWidth | IntrinsicDimension::MaxWidth => 0,
+                    IntrinsicDimension::MinHeight | IntrinsicDimension::MaxHeight => 1,
+                };
+                self.layout_style
+                    .intrinsic(axis, extent, |extent| match dimension {
+                        IntrinsicDimension::MinWidth => {
+                            self.widget_impl.min_intrinsic_width(extent, &children, ctx)
+                        }
+                        IntrinsicDimension::MaxWidth => {
+                            self.widget_impl.max_intrinsic_width(extent, &children, ctx)
+                        }
+                        IntrinsicDimension::MinHeight => self
+                            .widget_impl
+                            .min_intrinsic_height(extent, &children, ctx),
+                        IntrinsicDimension::MaxHeight => self
+                            .widget_impl
+                            .max_intrinsic_height(extent, &children, ctx),
+                    })
+            })
+    }
+}
+
+impl<D, W, T, ChildSetting> AnyWidget<T> for WidgetFrame<D, W, T, ChildSetting>
+where
+    D: Dom<T> + Send + Sync + 'static,
+    W: Widget<D, T, ChildSetting> + Send + Sync + 'static,
+    T: 'static,
+    ChildSetting: Send + Sync + PartialEq + Clone + 'static,
+{
+    fn device_input(&mut self, event: &DeviceInput, ctx: &WidgetContext) -> Option<T> {
+        let Some(dirty_flags) = &self.dirty_flags else {
+            warn!(
+                "Widget '{}' received device_input before dirty flags were initialized.",
+                self.log_label()
+            );
+            return None;
+        };
+
+        let label = self.log_label();
+        trace!("Processing device_input for widget '{}'", label);
+
+        if !self.layout_style.is_shown() {
+            return None;
+        }
+
+        let cache = self.cache.lock();
+
+        let Some((&actual_bounds, arrangement)) = cache.layout.get() else {
+            // not rendered yet, cannot process event.
+            return None;
+        };
+
+        let actual_bounds = self.layout_style.content_bounds(actual_bounds.into());
+        let transformed;
+        let event = if self.layout_style.is_empty() {
+            event
+        } else {
+            transformed = event.transform(self.content_transform());
+            &transformed
+        };
+
+        let mut arranged_children: SmallVec<
+            [(ArrangedChild<T>, &mut ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
+        > = self
+            .children
+            .iter_mut()
+            .zip(arrangement.iter())
+            .map(|((child, setting), arr)| {
+                (ArrangedChild::exclusive(&mut **child, arr), setting, arr)
+            })
+            .collect();
+        let mut children_with_arrangement: SmallVec<
+            [(&mut dyn AnyWidget<T>, &mut ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
+        > = arranged_children
+            .iter_mut()
+            .map(|(child, setting, arr)| (child as &mut dyn AnyWidget<T>, &mut **setting, *arr))
+            .collect();
 
         self.widget_impl.device_input(
             actual_bounds,
@@ -280,6 +1201,7 @@ where
             InvalidationHandle {
                 need_rearrange: &dirty_flags.need_rearrange,
                 need_redraw: &dirty_flags.need_redraw,
+                shared_dirty_flags: &self.shared_dirty_flags,
             },
             ctx,
         )
@@ -292,6 +1214,10 @@ where
             label, position
         );
 
+        if !self.layout_style.is_shown() {
+            return false;
+        }
+
         let cache = self.cache.lock();
 
         let Some((&actual_bounds, arrangement)) = cache.layout.get() else {
@@ -300,20 +1226,36 @@ where
             return false;
         };
 
-        let actual_bounds: [f32; 2] = actual_bounds.into();
+        let outer_bounds: [f32; 2] = actual_bounds.into();
+        let actual_bounds = self.layout_style.content_bounds(outer_bounds);
+        let offset = self.layout_style.content_offset();
+        let position = [position[0] - offset[0], position[1] - offset[1]];
+        if self.layout_style.clips(outer_bounds, position) {
+            return false;
+        }
 
-        let children_triples: SmallVec<
-            [(&dyn AnyWidget<T>, &ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
-        > = self
+        let arranged_children: SmallVec<[ArrangedChild<T>; SMALLVEC_INLINE_CAPACITY]> = self
             .children
             .iter()
             .zip(arrangement)
-            .map(|((c, s), a)| (&**c as &dyn AnyWidget<T>, s, a))
+            .map(|((c, _), a)| ArrangedChild::shared(&**c, a))
+            .collect();
+        let children_triples: SmallVec<
+            [(&dyn AnyWidget<T>, &ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
+        > = arranged_children
+            .iter()
+            .zip(&self.children)
+            .map(|(c, (_, s))| (c as &dyn AnyWidget<T>, s, c.arrangement))
             .collect();
 
+        // padding belongs to the widget, margin does not
         let inside = self
             .widget_impl
-            .is_inside(actual_bounds, position, &children_triples, ctx);
+            .is_inside(actual_bounds, position, &children_triples, ctx)
+            || (self.layout_style.padding != Default::default()
+                && self
+                    .layout_style
+                    .border_box_contains(outer_bounds, position));
 
         trace!("is_inside result for widget '{}': {}", label, inside);
         inside
@@ -325,6 +1267,7 @@ where
         };
 
         let label = self.log_label();
+        let _span = crate::profiling::profile_span!("measure", widget = label);
         trace!("Measuring widget '{}'", label);
         debug!(
             "measure start for widget '{}' constraints={:?} children={}",
@@ -339,6 +1282,8 @@ where
         if dirty_flags.need_rearrange.take_dirty() {
             debug!("measure invalidated by need_rearrange for '{}'", label);
             cache.measure.clear();
+            cache.baseline.clear();
+            cache.intrinsic.clear();
             // we cannot partially ensure both arrange() and measure() to be called so we need to clear both caches.
             cache.layout.clear();
         }
@@ -351,18 +1296,84 @@ where
         }
 
         let (_, size) = cache.measure.get_or_insert_with(constraints, || {
+            if !self.layout_style.display {
+                return constraints.min_size();
+            }
             let children: SmallVec<[(&dyn AnyWidget<T>, &ChildSetting); SMALLVEC_INLINE_CAPACITY]> =
                 self.children
                     .iter()
                     .map(|(child, setting)| (&**child as &dyn AnyWidget<T>, setting))
                     .collect();
 
-            self.widget_impl.measure(constraints, &children, ctx)
+            let content_constraints = self.layout_style.content_constraints(constraints);
+            let content_size = self
+                .widget_impl
+                .measure(&content_constraints, &children, ctx);
+            self.layout_style.outer_size(content_size, constraints)
         });
         debug!("measure result for widget '{}' -> size={:?}", label, *size);
         *size
     }
 
+    fn baseline(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<f32> {
+        let Some(dirty_flags) = &self.dirty_flags else {
+            return None;
+        };
+
+        let label = self.log_label();
+        trace!("Querying baseline of widget '{}'", label);
+
+        let mut cache = self.cache.lock();
+
+        // baseline shares its invalidation with measure, so clear every layout cache together.
+        if dirty_flags.need_rearrange.take_dirty() {
+            debug!("baseline invalidated by need_rearrange for '{}'", label);
+            cache.measure.clear();
+            cache.baseline.clear();
+            cache.intrinsic.clear();
+            cache.layout.clear();
+        }
+
+        if ctx.debug_config_disable_layout_measure_cache() {
+            cache.baseline.clear();
+        }
+
+        let (_, baseline) = cache.baseline.get_or_insert_with(constraints, || {
+            if !self.layout_style.display {
+                return None;
+            }
+            let children: SmallVec<[(&dyn AnyWidget<T>, &ChildSetting); SMALLVEC_INLINE_CAPACITY]> =
+                self.children
+                    .iter()
+                    .map(|(child, setting)| (&**child as &dyn AnyWidget<T>, setting))
+                    .collect();
+
+            let content_constraints = self.layout_style.content_constraints(constraints);
+            let top = self.layout_style.content_offset()[1];
+            self.widget_impl
+                .baseline(&content_constraints, &children, ctx)
+                .map(|baseline| baseline + top)
+        });
+        trace!("baseline result for widget '{}' -> {:?}", label, *baseline);
+        *baseline
+    }
+
+    fn min_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.intrinsic_size(IntrinsicDimension::MinWidth, height, ctx)
+    }
+
+    fn max_intrinsic_width(&self, height: f32, ctx: &WidgetContext) -> f32 {
+        self.intrinsic_size(IntrinsicDimension::MaxWidth, height, ctx)
+    }
+
+    fn min_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.intrinsic_size(IntrinsicDimension::MinHeight, width, ctx)
+    }
+
+    fn max_intrinsic_height(&self, width: f32, ctx: &WidgetContext) -> f32 {
+        self.intrinsic_size(IntrinsicDimension::MaxHeight, width, ctx)
+    }
+
     // todo: add error type
     fn render(&self, background: Background, ctx: &WidgetContext) -> Arc<RenderNode> {
         let Some(dirty_flags) = &self.dirty_flags else {
@@ -370,6 +1381,7 @@ where
         };
 
         let label = self.log_label();
+        let _span = crate::profiling::profile_span!("render", widget = label);
         trace!("Rendering widget '{}'", label);
 
         let cache = &mut *self.cache.lock();
@@ -377,12 +1389,33 @@ where
         let Some((q_size, arrangement)) = cache.layout.get() else {
             return Arc::new(RenderNode::new());
         };
+        if !self.layout_style.is_shown() {
+            // children keep their render flags for when the widget is shown again
+            let _ = dirty_flags.need_rearrange.take_dirty();
+            let _ = dirty_flags.need_redraw.take_dirty();
+            return Arc::new(RenderNode::new());
+        }
         let bounds: [f32; 2] = q_size.into();
+        let content_bounds = self.layout_style.content_bounds(bounds);
 
+        let content_hash = self.widget_impl.content_hash();
         if dirty_flags.need_redraw.take_dirty() {
-            // redraw needed, clear render cache
-            cache.render.clear();
+            match content_hash {
+                // the request may come from this widget or any descendant
+                None => cache.render_generation = cache.render_generation.wrapping_add(1),
+                Some(_) => {
+                    if self.children.iter().any(|(child, _)| child.need_redraw()) {
+                        cache.render.clear();
+                    }
+                }
+            }
         }
+        let render_key = RenderKey {
+            size: QSize::from(bounds),
+            generation: cache.render_generation,
+            content_hash,
+            atlas_generations: ctx.atlas_generations(),
+        };
 
         // Decide whether to recompute render each time: if so, clear persistent render cache
         // before get_or_insert_with so it gets recomputed and written into the cache.
@@ -390,28 +1423,41 @@ where
             cache.render.clear();
         }
 
-        // Default: use persistent render cache (possibly cleared above to force recompute).
-        let (_, node) = cache.render.get_or_insert_with(&QSize::from(bounds), || {
-            let children_triples: SmallVec<
-                [(&dyn AnyWidget<T>, &ChildSetting, &Arrangement); SMALLVEC_INLINE_CAPACITY],
-            > = self
-                .children
-                .iter()
-                .zip(arrangement)
-                .map(|((c, s), a)| (&**c as &dyn AnyWidget<T>, s, a))
-                .collect();
+        let hit = cache
+            .render
+            .get()
+            .is_some_and(|(key, _)| *key == render_key);
+        if let Some(budget) = ctx.cache_budget() {
+            budget.record_render(hit);
+        }
 
-            Arc::new(
-                self.widget_impl
-                    .render(bounds, &children_triples, background, ctx),
-            )
+        let overflow = cache.overflow;
+
+        // Default: use persistent render cache (possibly cleared above to force recompute).
+        let (_, node) = cache.render.get_or_insert_with(&render_key, || {
+            let node = self.render_content(bounds, arrangement, background, ctx);
+            if overflow.is_none() || !ctx.debug_config_show_layout_overflow() {
+                return node;
+            }
+            match overflow.indicator(content_bounds, ctx) {
+                Some(indicator) => Arc::new(
+                    RenderNode::new()
+                        .with_z_index(node.z_index())
+                        .add_child(node, nalgebra::Matrix4::identity())
+                        .add_child(indicator, self.content_transform()),
+                ),
+                None => node,
+            }
         });
+        let node = node.clone();
+
+        self.account_cache(cache, !hit, ctx);
 
         // consume flags
         let _ = dirty_flags.need_rearrange.take_dirty();
         let _ = dirty_flags.need_redraw.take_dirty();
 
-        node.clone()
+        node
     }
 }
 
@@ -452,39 +1498,92 @@ where
         trace!("Updating widget tree for widget '{}'", label);
 
         // update current hierarchy widget
-        let children = self.widget_impl.update_widget(
-            dom,
-            self.dirty_flags.as_ref().map(|flags| InvalidationHandle {
-                need_rearrange: &flags.need_rearrange,
-                need_redraw: &flags.need_redraw,
-            }),
-        );
+        let children = {
+            // children are updated across awaits below, outside of this span
+            let _span = crate::profiling::profile_span!(
+                "update_widget",
+                widget = self.label.as_deref().unwrap_or("<unnamed>")
+            );
+            self.widget_impl.update_widget(
+                dom,
+                self.dirty_flags.as_ref().map(|flags| InvalidationHandle {
+                    need_rearrange: &flags.need_rearrange,
+                    need_redraw: &flags.need_redraw,
+                    shared_dirty_flags: &self.shared_dirty_flags,
+                }),
+            )
+        };
+
+        if let Some(update) = self.widget_impl.update_owned_children() {
+            update.await;
+        }
 
         // update children widget
 
         let mut need_rearrange = false;
+        let mut need_redraw = false;
+
+        let layout_style = <D as Dom<T>>::layout_style(dom);
+        if layout_style != self.layout_style {
+            // showing, hiding or lifting a widget or changing its tab order keeps the layout
+            let same_layout = LayoutStyle {
+                visible: self.layout_style.visible,
+                elevation: self.layout_style.elevation,
+                tab_index: self.layout_style.tab_index,
+                focus_scope: self.layout_style.focus_scope,
+                ..layout_style
+            } == self.layout_style;
+            self.layout_style = layout_style;
+            if same_layout {
+                need_redraw = true;
+            } else {
+                need_rearrange = true;
+            }
+        }
 
-        // collect old children and its ids
-
-        let old_children = std::mem::take(&mut self.children);
-        let old_children_id = std::mem::take(&mut self.children_id);
+        // match the old children by id: kept and moved ones are reused, the rest is dropped
+
+        let new_children_id: Vec<u128> = children.iter().map(|(_, _, id)| *id).collect();
+        let diff = keyed::diff_child_ids(&self.children_id, &new_children_id);
+        if !diff.is_unchanged() {
+            debug!(
+                "children of '{}' changed: kept={} moved={} inserted={} removed={}",
+                self.log_label(),
+                diff.kept(),
+                diff.moved(),
+                diff.inserted(),
+                diff.removed.len()
+            );
+            // children inserted, removed or reordered
+            need_rearrange = true;
+        }
 
-        let mut old_children_map = old_children
-            .into_iter()
-            .zip(old_children_id.iter())
-            .map(|((child, setting), id)| (*id, (child, setting)))
-            .collect::<fxhash::FxHashMap<_, _>>();
+        let mut old_children: Vec<Option<(Box<dyn AnyWidgetFrame<T>>, ChildSetting)>> =
+            std::mem::take(&mut self.children)
+                .into_iter()
+                .map(Some)
+                .collect();
+        self.children_id = new_children_id;
 
         // update
 
-        // Potential future use:
-        // Collect new child id sequence for diff algorithms that may want
-        // an O(n) LCS / move-detection optimization. Currently unused.
-        // Prefixed with underscore to silence warnings.
-        let _new_children_id = children.iter().map(|(_, _, id)| *id).collect::<Vec<_>>();
+        // children share an old widget by id, so a repeated id gets a freshly built widget
+        // for every occurrence after the first.
+        if log::log_enabled!(log::Level::Warn) {
+            let mut seen = fxhash::FxHashSet::default();
+            if let Some((_, _, id)) = children.iter().find(|(_, _, id)| !seen.insert(*id)) {
+                warn!(
+                    "Widget '{}' has duplicate child id {}; use keyed children (`keyed::child_id`) to give children stable, unique ids",
+                    self.log_label(),
+                    id
+                );
+            }
+        }
 
-        for (child_dom, setting, id) in children {
-            let mut old_pair = old_children_map.remove(&id);
+        for ((child_dom, setting, _), source) in children.into_iter().zip(&diff.sources) {
+            let mut old_pair = source
+                .old_position()
+                .and_then(|position| old_children[position].take());
 
             // check child identity
             if let Some((old_child, _)) = &mut old_pair
@@ -513,28 +1612,26 @@ where
             // push to self.children
             if let Some((old_child, _)) = old_pair {
                 self.children.push((old_child, setting));
-                self.children_id.push(id);
             } else {
                 let new_child = child_dom.build_widget_tree();
                 self.children.push((new_child, setting));
-                self.children_id.push(id);
                 need_rearrange = true;
             }
         }
+        // the children at `diff.removed` are dropped with `old_children`
 
-        if !old_children_map.is_empty() {
-            // children removed
-            need_rearrange = true;
-        }
-
-        if self.children_id != old_children_id {
-            // children reordered
-            need_rearrange = true;
+        if let Some(dirty_flags) = &self.dirty_flags {
+            if need_rearrange {
+                dirty_flags.need_rearrange.mark_dirty();
+            }
+            if need_rearrange || need_redraw {
+                dirty_flags.need_redraw.mark_dirty();
+            }
         }
-
-        if need_rearrange && let Some(dirty_flags) = &self.dirty_flags {
-            dirty_flags.need_rearrange.mark_dirty();
-            dirty_flags.need_redraw.mark_dirty();
+        // new children need mounting and changed ones may have work to prepare; a change in
+        // the subtree marked this widget for redraw on the way up
+        if self.need_redraw() {
+            self.forget_walks();
         }
 
         Ok(())
@@ -557,6 +1654,7 @@ where
         };
 
         let label = self.log_label();
+        let _span = crate::profiling::profile_span!("arrange", widget = label);
         trace!("Arranging widget '{}'", label);
         debug!(
             "arrange start for widget '{}' bounds={:?} children={}",
@@ -571,6 +1669,8 @@ where
             // arrangement changed, need to redraw
             debug!("arrange invalidated by need_rearrange for '{}'", label);
             cache.measure.clear();
+            cache.baseline.clear();
+            cache.intrinsic.clear();
             cache.layout.clear();
         }
 
@@ -582,10 +1682,24 @@ where
 
         // We need to track whether the render cache needs to be cleared due to layout eviction.
         let mut should_clear_render = false;
+        // set when the arrangement is recomputed
+        let mut overflow = None;
+
+        let hit = cache
+            .layout
+            .get()
+            .is_some_and(|(q_size, _)| *q_size == QSize::from(bounds));
+        if let Some(budget) = ctx.cache_budget() {
+            budget.record_layout(hit);
+        }
 
         cache.layout.get_or_insert_with_eviction_callback(
             &QSize::from(bounds),
             || {
+                // a widget out of the layout leaves its children unarranged
+                if !self.layout_style.display {
+                    return Vec::new();
+                }
                 // calc arrangement
                 let children: SmallVec<
                     [(&dyn AnyWidget<T>, &ChildSetting); SMALLVEC_INLINE_CAPACITY],
@@ -594,11 +1708,21 @@ where
                     .iter()
                     .map(|(child, setting)| (&**child as &dyn AnyWidget<T>, setting))
                     .collect();
-                let arrangement = self.widget_impl.arrange(bounds, &children, ctx);
+                let content_bounds = self.layout_style.content_bounds(bounds);
+                let arrangement = self.widget_impl.arrange(content_bounds, &children, ctx);
                 // update child arrangements
                 for ((child, _), arrangement) in self.children.iter().zip(arrangement.iter()) {
                     child.arrange(arrangement.size, ctx);
                 }
+                if !self.widget_impl.allows_overflow() && self.layout_style.clip_radius.is_none() {
+                    overflow = Some(children_overflow(
+                        content_bounds,
+                        self.children
+                            .iter()
+                            .map(|(child, _)| child.label())
+                            .zip(&arrangement),
+                    ));
+                }
                 arrangement
             },
             |_, _| {
@@ -619,11 +1743,34 @@ where
             }
         }
 
+        if let Some((overflow, children)) = overflow {
+            if cache.overflow.is_none() && !overflow.is_none() {
+                warn!(
+                    "layout overflow in '{}' with content bounds {:?}: children '{}' extend {}",
+                    label,
+                    self.layout_style.content_bounds(bounds),
+                    children.join("', '"),
+                    overflow
+                );
+            }
+            if cache.overflow != overflow {
+                // the indicator follows the overflow
+                should_clear_render |= ctx.debug_config_show_layout_overflow();
+                cache.overflow = overflow;
+            }
+        }
+
         // Now that the mutable borrow of layout has ended, clear the render cache if requested.
         if should_clear_render {
             debug!("evict render cache due to layout eviction for '{}'", label);
             cache.render.clear();
         }
+
+        if !hit {
+            cache.layout_generation = cache.layout_generation.wrapping_add(1);
+        }
+
+        self.account_cache(&mut cache, !hit, ctx);
     }
 
     fn update_dirty_flags(&mut self, rearrange_flags: BackPropDirty, redraw_flags: BackPropDirty) {
@@ -632,6 +1779,7 @@ where
             need_rearrange: rearrange_flags,
             need_redraw: redraw_flags,
         });
+        *self.shared_dirty_flags.lock() = Some(dirty_flags.clone());
 
         for (child, _) in &mut self.children {
             // NOTE:
@@ -643,6 +1791,11 @@ where
                 dirty_flags.need_redraw.make_child(),
             );
         }
+
+        self.widget_impl.link_owned_children(ChildFrameLinker {
+            need_rearrange: dirty_flags.need_rearrange.clone(),
+            need_redraw: dirty_flags.need_redraw.clone(),
+        });
     }
 
     fn invalidate_render_cache(&mut self) {
@@ -654,53 +1807,313 @@ where
         let mut cache = self.cache.lock();
         cache.render.clear();
     }
-}
 
-#[cfg(test)]
-#[allow(clippy::unwrap_used)]
-mod tests {
-    use super::*;
-    use super::{Constraints, DeviceInput};
-    use utils::back_prop_dirty::BackPropDirty;
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        trace!("update_gpu_device for widget '{}'", self.log_label());
+        for (child, _) in &mut self.children {
+            child.update_gpu_device(device, queue);
+        }
 
-    #[derive(Debug, Clone, PartialEq, Default)]
-    struct MockSetting {
-        value: i32,
-    }
+        self.widget_impl.update_gpu_device(device, queue);
+        self.forget_walks();
 
-    #[derive(Clone)]
-    struct MockDom {
-        id: u128,
-        children: Vec<(MockDom, MockSetting)>,
+        let mut cache = self.cache.lock();
+        cache.render.clear();
     }
 
-    #[async_trait::async_trait]
-    impl Dom<String> for MockDom {
-        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
-            Box::new(WidgetFrame::new(
-                None,
-                self.children
-                    .iter()
-                    .map(|(child, setting)| (child.build_widget_tree(), setting.clone()))
-                    .collect(),
-                self.children.iter().map(|(c, _)| c.id).collect(),
-                MockWidget,
-            ))
+    fn update_lifecycle(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        if self.dirty_flags.is_none() {
+            return;
         }
-    }
-
-    struct MockWidget;
+        // hidden widgets are not visible anywhere
+        let visible_rect = visible_rect.filter(|_| self.layout_style.is_shown());
+        let Some(walk) = self.walk_key(visible_rect, self.lifecycle_walk) else {
+            return;
+        };
+        self.lifecycle_walk = Some(walk);
 
-    impl Widget<MockDom, String, MockSetting> for MockWidget {
-        fn update_widget<'a>(
-            &mut self,
-            dom: &'a MockDom,
-            _cache_invalidator: Option<InvalidationHandle>,
-        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
-            dom.children
-                .iter()
-                .map(|(child, setting)| (child as &dyn Dom<String>, setting.clone(), child.id))
-                .collect()
+        if !self.mounted {
+            trace!("Mounting widget '{}'", self.log_label());
+            self.mounted = true;
+            self.widget_impl.on_mount(ctx);
+        }
+        if visible_rect.is_some() != self.visible {
+            self.visible = visible_rect.is_some();
+            trace!(
+                "Visibility of widget '{}' changed to {}",
+                self.log_label(),
+                self.visible
+            );
+            self.widget_impl.on_visibility_changed(self.visible, ctx);
+        }
+        if visible_rect.is_some()
+            && let Some(bounds) = self.content_bounds()
+        {
+            self.widget_impl.update_lifecycle(bounds, ctx);
+        }
+
+        let Some(child_rects) = self.child_visible_rects(visible_rect) else {
+            return;
+        };
+        for (index, (child, _)) in self.children.iter_mut().enumerate() {
+            child.update_lifecycle(child_rects.get(index).copied().flatten(), ctx);
+        }
+    }
+
+    fn prepare(&mut self, visible_rect: Option<[[f32; 2]; 2]>, ctx: &WidgetContext) {
+        let Some(dirty_flags) = &self.dirty_flags else {
+            return;
+        };
+        let visible_rect = visible_rect.filter(|_| self.layout_style.is_shown());
+        let Some(walk) = self.walk_key(visible_rect, self.prepare_walk) else {
+            return;
+        };
+        self.prepare_walk = Some(walk);
+        let _span = crate::profiling::profile_span!("prepare", widget = self.log_label());
+
+        if visible_rect.is_none() {
+            if self.prepare_task.take().is_some() {
+                trace!(
+                    "Cancelling preparation of widget '{}': not visible",
+                    self.log_label()
+                );
+            }
+        } else if let Some(bounds) = self.content_bounds() {
+            if self
+                .prepare_task
+                .as_ref()
+                .is_some_and(|task| task.handle.is_finished())
+            {
+                self.prepare_task = None;
+            }
+
+            if self.prepare_task.is_none()
+                && let Some(future) = self.widget_impl.prepare(bounds, ctx)
+            {
+                trace!("Spawning preparation of widget '{}'", self.log_label());
+                let need_redraw = dirty_flags.need_redraw.clone();
+                let handle = ctx.task_executor().spawn(async move {
+                    future.await;
+                    need_redraw.mark_dirty();
+                });
+                self.prepare_task = Some(PrepareTask { handle });
+            }
+        }
+
+        let Some(child_rects) = self.child_visible_rects(visible_rect) else {
+            return;
+        };
+        for (index, (child, _)) in self.children.iter_mut().enumerate() {
+            child.prepare(child_rects.get(index).copied().flatten(), ctx);
+        }
+    }
+
+    fn hit_test(
+        &self,
+        id: Option<u128>,
+        position: [f32; 2],
+        to_window: &nalgebra::Matrix4<f32>,
+        ctx: &WidgetContext,
+        path: &mut Vec<HitTestEntry>,
+    ) -> bool {
+        if !self.layout_style.is_shown() {
+            return false;
+        }
+        let (outer_bounds, arrangement): ([f32; 2], Vec<Arrangement>) = {
+            let cache = self.cache.lock();
+            let Some((&bounds, arrangement)) = cache.layout.get() else {
+                // not laid out yet
+                return false;
+            };
+            (bounds.into(), arrangement.clone())
+        };
+
+        let inside = self.is_inside(position, ctx);
+
+        let len = path.len();
+        path.push(HitTestEntry {
+            label: self.label.clone(),
+            id,
+            bounds: bounding_box(to_window, self.layout_style.border_box(outer_bounds)),
+            local_position: position,
+        });
+
+        let offset = self.layout_style.content_offset();
+        let content_position = [position[0] - offset[0], position[1] - offset[1]];
+        let content_to_window = to_window * self.content_transform();
+        // the clip hides the children there too
+        let clipped = self.layout_style.clips(outer_bounds, content_position);
+
+        // later children are drawn on top
+        for ((child, _), (child_id, arrangement)) in self
+            .children
+            .iter()
+            .zip(self.children_id.iter().zip(&arrangement))
+            .rev()
+            .filter(|_| !clipped)
+        {
+            let child_position = arrangement.to_local(content_position);
+            if arrangement.affine_inv.is_none() || arrangement.clips(child_position) {
+                continue;
+            }
+            if child.hit_test(
+                Some(*child_id),
+                child_position,
+                &(content_to_window * arrangement.affine),
+                ctx,
+                path,
+            ) {
+                return true;
+            }
+        }
+
+        if !inside {
+            path.truncate(len);
+        }
+        inside
+    }
+
+    fn snapshot(
+        &self,
+        id: Option<u128>,
+        to_window: &nalgebra::Matrix4<f32>,
+    ) -> Option<WidgetSnapshot> {
+        if !self.layout_style.is_shown() {
+            return None;
+        }
+        let (outer_bounds, arrangement): ([f32; 2], Vec<Arrangement>) = {
+            let cache = self.cache.lock();
+            let (&bounds, arrangement) = cache.layout.get()?;
+            (bounds.into(), arrangement.clone())
+        };
+
+        let content_to_window = to_window * self.content_transform();
+        let children = self
+            .children
+            .iter()
+            .zip(self.children_id.iter().zip(&arrangement))
+            .filter_map(|((child, _), (child_id, arrangement))| {
+                child.snapshot(Some(*child_id), &(content_to_window * arrangement.affine))
+            })
+            .collect();
+
+        Some(WidgetSnapshot {
+            label: self.label.clone(),
+            id,
+            bounds: bounding_box(to_window, self.layout_style.border_box(outer_bounds)),
+            focus: self.widget_impl.accepts_focus().then(|| FocusState {
+                focused: self.widget_impl.is_focused(),
+                captures_tab: self.widget_impl.captures_tab(),
+            }),
+            tab_index: self.layout_style.tab_index,
+            focus_scope: self.layout_style.focus_scope,
+            children,
+        })
+    }
+
+    fn find_rendered(&self, id: Option<u128>, target: &CaptureTarget) -> Option<CapturedSubtree> {
+        if !self.layout_style.is_shown() {
+            return None;
+        }
+        if target.matches(self.label.as_deref(), id) {
+            let cache = self.cache.lock();
+            let (&bounds, _) = cache.layout.get()?;
+            let (_, node) = cache.render.get()?;
+            return Some(CapturedSubtree {
+                node: node.clone(),
+                border_box: self.layout_style.border_box(bounds.into()),
+            });
+        }
+        self.children
+            .iter()
+            .zip(&self.children_id)
+            .find_map(|((child, _), child_id)| child.find_rendered(Some(*child_id), target))
+    }
+
+    fn set_focus(&mut self, path: Option<&[u128]>, ctx: &WidgetContext) {
+        let focused = path.is_some_and(<[u128]>::is_empty);
+        if self.widget_impl.accepts_focus()
+            && self.widget_impl.is_focused() != focused
+            && let Some(dirty_flags) = &self.dirty_flags
+        {
+            trace!(
+                "Widget '{}' {} focus",
+                self.label.as_deref().unwrap_or("<unnamed>"),
+                if focused { "gains" } else { "loses" }
+            );
+            self.widget_impl.set_focused(
+                focused,
+                InvalidationHandle {
+                    need_rearrange: &dirty_flags.need_rearrange,
+                    need_redraw: &dirty_flags.need_redraw,
+                    shared_dirty_flags: &self.shared_dirty_flags,
+                },
+                ctx,
+            );
+        }
+
+        for ((child, _), child_id) in self.children.iter_mut().zip(&self.children_id) {
+            let child_path = path
+                .and_then(<[u128]>::split_first)
+                .filter(|(id, _)| *id == child_id)
+                .map(|(_, rest)| rest);
+            child.set_focus(child_path, ctx);
+        }
+    }
+
+    fn take_deferred_events(&mut self, ctx: &WidgetContext) -> Vec<T> {
+        self.children
+            .iter_mut()
+            .flat_map(|(child, _)| child.take_deferred_events(ctx))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use super::{Constraints, DeviceInput};
+    use utils::back_prop_dirty::BackPropDirty;
+
+    #[derive(Debug, Clone, PartialEq, Default)]
+    struct MockSetting {
+        value: i32,
+    }
+
+    #[derive(Clone)]
+    struct MockDom {
+        id: u128,
+        children: Vec<(MockDom, MockSetting)>,
+    }
+
+    #[async_trait::async_trait]
+    impl Dom<String> for MockDom {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::new(
+                None,
+                self.children
+                    .iter()
+                    .map(|(child, setting)| (child.build_widget_tree(), setting.clone()))
+                    .collect(),
+                self.children.iter().map(|(c, _)| c.id).collect(),
+                MockWidget,
+            ))
+        }
+    }
+
+    struct MockWidget;
+
+    impl Widget<MockDom, String, MockSetting> for MockWidget {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a MockDom,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            dom.children
+                .iter()
+                .map(|(child, setting)| (child as &dyn Dom<String>, setting.clone(), child.id))
+                .collect()
         }
 
         fn device_input(
@@ -1004,100 +2417,68 @@ mod tests {
         );
     }
 
-    #[tokio::test]
-    async fn test_update_change_setting() {
-        let initial_dom = MockDom {
-            id: 0,
-            children: vec![(
-                MockDom {
-                    id: 1,
-                    children: vec![],
-                },
-                MockSetting { value: 10 },
-            )],
-        };
-
-        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = initial_dom.build_widget_tree();
-        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
-        let widget_frame_concrete = (&mut *widget_frame as &mut dyn Any)
-            .downcast_mut::<MockWidgetFrame>()
-            .unwrap();
-        assert!(
-            !widget_frame_concrete
-                .dirty_flags
-                .as_ref()
-                .unwrap()
-                .need_rearrange
-                .is_dirty()
-        );
-
-        // Update with setting changed
-        let updated_dom = MockDom {
-            id: 0,
-            children: vec![(
-                MockDom {
-                    id: 1,
-                    children: vec![],
-                },
-                MockSetting { value: 99 }, // Changed value
-            )],
-        };
-
-        widget_frame.update_widget_tree(&updated_dom).await.unwrap();
-
-        let widget_frame_concrete = (&mut *widget_frame as &mut dyn Any)
-            .downcast_mut::<MockWidgetFrame>()
-            .unwrap();
-        assert_eq!(widget_frame_concrete.children.len(), 1);
-        assert_eq!(widget_frame_concrete.children_id, vec![1]);
-        let (_, setting) = &widget_frame_concrete.children[0];
-        assert_eq!(setting.value, 99);
-        assert!(
-            widget_frame_concrete
-                .dirty_flags
-                .as_ref()
-                .unwrap()
-                .need_rearrange
-                .is_dirty()
-        );
+    // a list of leaves identified by key, each remembering the key it was built for
+    struct KeyedList {
+        leaves: Vec<KeyedLeaf>,
     }
 
-    // --- Added Tests ---
-
-    use crate::context::WidgetContext;
-    use std::{
-        mem::MaybeUninit,
-        sync::{
-            Arc,
-            atomic::{AtomicUsize, Ordering},
-        },
-    };
+    struct KeyedLeaf {
+        key: &'static str,
+    }
 
-    // Helper to create a dummy WidgetContext for tests that don't depend on real GPU resources.
-    /// Create a minimal WidgetContext suitable for unit tests that don't require real GPU
-    /// resources. This delegates to the centralized test helper on `context.rs`.
-    fn create_mock_widget_context() -> WidgetContext {
-        crate::context::WidgetContext::new_for_tests()
+    struct LeafState {
+        built_for: &'static str,
+        updates: usize,
     }
 
-    #[derive(Default)]
-    struct CallCount {
-        measure: AtomicUsize,
+    impl Dom<String> for KeyedList {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::new(
+                None,
+                self.leaves
+                    .iter()
+                    .map(|leaf| (leaf.build_widget_tree(), MockSetting::default()))
+                    .collect(),
+                self.leaves
+                    .iter()
+                    .map(|leaf| keyed::child_id(leaf.key))
+                    .collect(),
+                ListWidget,
+            ))
+        }
     }
 
-    struct MockWidgetWithCallCount {
-        call_count: Arc<CallCount>,
+    impl Dom<String> for KeyedLeaf {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::<KeyedLeaf, _, String, MockSetting>::new(
+                None,
+                vec![],
+                vec![],
+                LeafState {
+                    built_for: self.key,
+                    updates: 0,
+                },
+            ))
+        }
     }
 
-    impl Widget<MockDom, String, MockSetting> for MockWidgetWithCallCount {
+    struct ListWidget;
+
+    impl Widget<KeyedList, String, MockSetting> for ListWidget {
         fn update_widget<'a>(
             &mut self,
-            dom: &'a MockDom,
+            dom: &'a KeyedList,
             _cache_invalidator: Option<InvalidationHandle>,
         ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
-            dom.children
+            dom.leaves
                 .iter()
-                .map(|(child, setting)| (child as &dyn Dom<String>, setting.clone(), child.id))
+                .map(|leaf| {
+                    (
+                        leaf as &dyn Dom<String>,
+                        MockSetting::default(),
+                        keyed::child_id(leaf.key),
+                    )
+                })
                 .collect()
         }
 
@@ -1112,24 +2493,13 @@ mod tests {
             None
         }
 
-        fn is_inside(
-            &self,
-            _bounds: [f32; 2],
-            _position: [f32; 2],
-            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
-            _ctx: &WidgetContext,
-        ) -> bool {
-            true
-        }
-
         fn measure(
             &self,
             _constraints: &Constraints,
             _children: &[(&dyn AnyWidget<String>, &MockSetting)],
             _ctx: &WidgetContext,
         ) -> [f32; 2] {
-            self.call_count.measure.fetch_add(1, Ordering::SeqCst);
-            [100.0, 100.0]
+            [0.0, 0.0]
         }
 
         fn arrange(
@@ -1152,124 +2522,111 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_measure_cache_behavior() {
-        // NOTE: This test cannot be async because the mock context setup is not Send.
-        // We create dummy resources on the stack using MaybeUninit for safety.
-        let ctx = create_mock_widget_context();
-
-        let call_count = Arc::new(CallCount::default());
-        let widget_impl = MockWidgetWithCallCount {
-            call_count: Arc::clone(&call_count),
-        };
-        let mut widget_frame = WidgetFrame::new(None, vec![], vec![], widget_impl);
-        widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
-
-        let constraints = Constraints::new([0.0, 200.0], [0.0, 200.0]);
-
-        // 1. First call, should execute measure
-        widget_frame.measure(&constraints, &ctx);
-        assert_eq!(call_count.measure.load(Ordering::SeqCst), 1);
+    impl Widget<KeyedLeaf, String, MockSetting> for LeafState {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a KeyedLeaf,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            self.updates += 1;
+            vec![]
+        }
 
-        // 2. Second call with same constraints, should hit cache
-        widget_frame.measure(&constraints, &ctx);
-        assert_eq!(call_count.measure.load(Ordering::SeqCst), 1);
-
-        // 3. Mark for rearrange, should invalidate cache and re-measure
-        widget_frame
-            .dirty_flags
-            .as_ref()
-            .unwrap()
-            .need_rearrange
-            .mark_dirty();
-        widget_frame.measure(&constraints, &ctx);
-        assert_eq!(call_count.measure.load(Ordering::SeqCst), 2);
-
-        // 4. Call again, should be cached now
-        widget_frame.measure(&constraints, &ctx);
-        assert_eq!(call_count.measure.load(Ordering::SeqCst), 2);
-    }
-
-    struct WidgetRequestingRearrange;
-    impl Widget<MockDom, String, MockSetting> for WidgetRequestingRearrange {
-        fn update_widget<'a>(
-            &mut self,
-            dom: &'a MockDom,
-            cache_invalidator: Option<InvalidationHandle>,
-        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
-            if let Some(invalidator) = cache_invalidator {
-                invalidator.relayout_next_frame();
-            }
-            dom.children
-                .iter()
-                .map(|(child, setting)| (child as &dyn Dom<String>, setting.clone(), child.id))
-                .collect()
-        }
         fn device_input(
             &mut self,
-            _: [f32; 2],
-            _: &DeviceInput,
-            _: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
-            _: InvalidationHandle,
-            _: &WidgetContext,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
         ) -> Option<String> {
             None
         }
-        fn is_inside(
-            &self,
-            _: [f32; 2],
-            _: [f32; 2],
-            _: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
-            _: &WidgetContext,
-        ) -> bool {
-            true
-        }
+
         fn measure(
             &self,
-            _: &Constraints,
-            _: &[(&dyn AnyWidget<String>, &MockSetting)],
-            _: &WidgetContext,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
         ) -> [f32; 2] {
             [0.0, 0.0]
         }
+
         fn arrange(
             &self,
-            _: [f32; 2],
-            _: &[(&dyn AnyWidget<String>, &MockSetting)],
-            _: &WidgetContext,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
         ) -> Vec<Arrangement> {
             vec![]
         }
+
         fn render(
             &self,
-            _: [f32; 2],
-            _: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
-            _: Background,
-            _: &WidgetContext,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
         ) -> RenderNode {
             RenderNode::default()
         }
     }
 
+    fn keyed_list(keys: &[&'static str]) -> KeyedList {
+        KeyedList {
+            leaves: keys.iter().map(|&key| KeyedLeaf { key }).collect(),
+        }
+    }
+
     #[tokio::test]
-    async fn test_rearrange_request_from_widget() {
-        let dom = MockDom {
+    async fn test_update_keeps_state_of_reordered_keyed_children() {
+        let mut widget_frame = keyed_list(&["a", "b", "c", "d"]).build_widget_tree();
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+
+        // "d" moves to the front, "b" is removed and "e" inserted
+        widget_frame
+            .update_widget_tree(&keyed_list(&["d", "a", "e", "c"]))
+            .await
+            .unwrap();
+
+        let list = (&mut *widget_frame as &mut dyn Any)
+            .downcast_mut::<WidgetFrame<KeyedList, ListWidget, String, MockSetting>>()
+            .unwrap();
+        let states: Vec<_> = list
+            .children
+            .iter_mut()
+            .map(|(child, _)| {
+                let leaf = (&mut **child as &mut dyn Any)
+                    .downcast_mut::<WidgetFrame<KeyedLeaf, LeafState, String, MockSetting>>()
+                    .unwrap();
+                (leaf.widget_impl.built_for, leaf.widget_impl.updates)
+            })
+            .collect();
+        // every remaining child keeps the widget it was built with
+        assert_eq!(states, vec![("d", 1), ("a", 1), ("e", 0), ("c", 1)]);
+        assert!(list.dirty_flags.as_ref().unwrap().need_rearrange.is_dirty());
+    }
+
+    #[tokio::test]
+    async fn test_update_change_setting() {
+        let initial_dom = MockDom {
             id: 0,
-            children: vec![],
+            children: vec![(
+                MockDom {
+                    id: 1,
+                    children: vec![],
+                },
+                MockSetting { value: 10 },
+            )],
         };
-        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = Box::new(WidgetFrame::new(
-            None,
-            vec![],
-            vec![],
-            WidgetRequestingRearrange,
-        ));
-        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
 
-        let frame_impl_before = (&*widget_frame as &dyn Any)
-            .downcast_ref::<WidgetFrame<MockDom, WidgetRequestingRearrange, String, MockSetting>>()
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = initial_dom.build_widget_tree();
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+        let widget_frame_concrete = (&mut *widget_frame as &mut dyn Any)
+            .downcast_mut::<MockWidgetFrame>()
             .unwrap();
         assert!(
-            !frame_impl_before
+            !widget_frame_concrete
                 .dirty_flags
                 .as_ref()
                 .unwrap()
@@ -1277,48 +2634,453 @@ mod tests {
                 .is_dirty()
         );
 
-        // update_widget is called, which should trigger rearrange_next_frame()
-        widget_frame.update_widget_tree(&dom).await.unwrap();
+        // Update with setting changed
+        let updated_dom = MockDom {
+            id: 0,
+            children: vec![(
+                MockDom {
+                    id: 1,
+                    children: vec![],
+                },
+                MockSetting { value: 99 }, // Changed value
+            )],
+        };
 
-        let frame_impl_after = (&*widget_frame as &dyn Any)
-            .downcast_ref::<WidgetFrame<MockDom, WidgetRequestingRearrange, String, MockSetting>>()
+        widget_frame.update_widget_tree(&updated_dom).await.unwrap();
+
+        let widget_frame_concrete = (&mut *widget_frame as &mut dyn Any)
+            .downcast_mut::<MockWidgetFrame>()
             .unwrap();
+        assert_eq!(widget_frame_concrete.children.len(), 1);
+        assert_eq!(widget_frame_concrete.children_id, vec![1]);
+        let (_, setting) = &widget_frame_concrete.children[0];
+        assert_eq!(setting.value, 99);
         assert!(
-            frame_impl_after
+            widget_frame_concrete
                 .dirty_flags
                 .as_ref()
                 .unwrap()
                 .need_rearrange
                 .is_dirty()
         );
-        assert!(widget_frame.need_redraw());
+    }
+
+    // --- Added Tests ---
+
+    use crate::{context::WidgetContext, test_kit::TestContext};
+    use std::{
+        mem::MaybeUninit,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    #[derive(Default)]
+    struct CallCount {
+        measure: AtomicUsize,
+        baseline: AtomicUsize,
+    }
+
+    struct MockWidgetWithCallCount {
+        call_count: Arc<CallCount>,
+    }
+
+    impl Widget<MockDom, String, MockSetting> for MockWidgetWithCallCount {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a MockDom,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            dom.children
+                .iter()
+                .map(|(child, setting)| (child as &dyn Dom<String>, setting.clone(), child.id))
+                .collect()
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn is_inside(
+            &self,
+            _bounds: [f32; 2],
+            _position: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _ctx: &WidgetContext,
+        ) -> bool {
+            true
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            self.call_count.measure.fetch_add(1, Ordering::SeqCst);
+            [100.0, 100.0]
+        }
+
+        fn baseline(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> Option<f32> {
+            self.call_count.baseline.fetch_add(1, Ordering::SeqCst);
+            Some(80.0)
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::default()
+        }
     }
 
     #[tokio::test]
-    async fn test_redraw_flag_cleared_after_render() {
-        // This test must be non-async due to the use of `MaybeUninit` for mock context.
-        let ctx = create_mock_widget_context();
+    async fn test_measure_cache_behavior() {
+        // NOTE: This test cannot be async because the mock context setup is not Send.
+        // We create dummy resources on the stack using MaybeUninit for safety.
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
 
-        let dom = MockDom {
-            id: 0,
-            children: vec![],
+        let call_count = Arc::new(CallCount::default());
+        let widget_impl = MockWidgetWithCallCount {
+            call_count: Arc::clone(&call_count),
         };
-        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = dom.build_widget_tree();
-
-        // 1. Initialize with dirty flags set to true, simulating initial state.
+        let mut widget_frame = WidgetFrame::new(None, vec![], vec![], widget_impl);
         widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
-        assert!(widget_frame.need_redraw(), "Should need redraw initially");
 
-        // 2. Call render, which should consume the dirty flags.
-        let texture_view = MaybeUninit::<wgpu::TextureView>::uninit();
-        let background = Background::new(unsafe { texture_view.assume_init_ref() }, [0.0, 0.0]);
-        widget_frame.arrange([800.0, 600.0], &ctx);
-        let _ = widget_frame.render(background, &ctx);
+        let constraints = Constraints::new([0.0, 200.0], [0.0, 200.0]);
 
-        // 3. Verify that the redraw flag is now false.
-        assert!(
-            !widget_frame.need_redraw(),
-            "Redraw flag should be cleared after render"
+        // 1. First call, should execute measure
+        widget_frame.measure(&constraints, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 1);
+
+        // 2. Second call with same constraints, should hit cache
+        widget_frame.measure(&constraints, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 1);
+
+        // 3. Mark for rearrange, should invalidate cache and re-measure
+        widget_frame
+            .dirty_flags
+            .as_ref()
+            .unwrap()
+            .need_rearrange
+            .mark_dirty();
+        widget_frame.measure(&constraints, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 2);
+
+        // 4. Call again, should be cached now
+        widget_frame.measure(&constraints, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_baseline_cache_behavior() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+
+        let call_count = Arc::new(CallCount::default());
+        let widget_impl = MockWidgetWithCallCount {
+            call_count: Arc::clone(&call_count),
+        };
+        let mut widget_frame = WidgetFrame::new(None, vec![], vec![], widget_impl);
+        widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+
+        let constraints = Constraints::new([0.0, 200.0], [0.0, 200.0]);
+
+        assert_eq!(widget_frame.baseline(&constraints, &ctx), Some(80.0));
+        assert_eq!(call_count.baseline.load(Ordering::SeqCst), 1);
+
+        // Cached for the same constraints.
+        assert_eq!(widget_frame.baseline(&constraints, &ctx), Some(80.0));
+        assert_eq!(call_count.baseline.load(Ordering::SeqCst), 1);
+
+        // Rearrange invalidates baseline together with measure.
+        widget_frame.measure(&constraints, &ctx);
+        widget_frame
+            .dirty_flags
+            .as_ref()
+            .unwrap()
+            .need_rearrange
+            .mark_dirty();
+        widget_frame.baseline(&constraints, &ctx);
+        assert_eq!(call_count.baseline.load(Ordering::SeqCst), 2);
+        widget_frame.measure(&constraints, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_layout_style_wraps_widget() {
+        use crate::ui::{Edges, LayoutStyle};
+
+        let test = TestContext::builder().build();
+
+        let ctx = test.widget_context();
+
+        let widget_impl = MockWidgetWithCallCount {
+            call_count: Arc::new(CallCount::default()),
+        };
+        let mut widget_frame = WidgetFrame::new(None, vec![], vec![], widget_impl)
+            .with_layout_style(
+                LayoutStyle::new()
+                    .padding(Edges::all(10.0))
+                    .margin(Edges::symmetric(0.0, 5.0))
+                    .max_height(110.0),
+            );
+        widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+
+        let constraints = Constraints::new([0.0, 500.0], [0.0, 500.0]);
+
+        // content 100x100, padding 10 on each side, margin 5 left and right, height capped
+        assert_eq!(widget_frame.measure(&constraints, &ctx), [130.0, 110.0]);
+        assert_eq!(widget_frame.baseline(&constraints, &ctx), Some(90.0));
+        assert_eq!(widget_frame.max_intrinsic_width(500.0, &ctx), 130.0);
+    }
+
+    #[tokio::test]
+    async fn test_hidden_widget_keeps_layout_and_undisplayed_widget_collapses() {
+        use crate::ui::LayoutStyle;
+
+        let test = TestContext::builder().build();
+
+        let ctx = test.widget_context();
+        let constraints = Constraints::new([10.0, 500.0], [0.0, 500.0]);
+
+        let frame = |style: LayoutStyle| {
+            let mut frame = WidgetFrame::new(
+                None,
+                vec![],
+                vec![],
+                MockWidgetWithCallCount {
+                    call_count: Arc::new(CallCount::default()),
+                },
+            )
+            .with_layout_style(style);
+            frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+            frame
+        };
+
+        let invisible = frame(LayoutStyle::new().visible(false));
+        assert_eq!(invisible.measure(&constraints, &ctx), [100.0, 100.0]);
+        invisible.arrange([100.0, 100.0], &ctx);
+        assert!(!invisible.is_inside([50.0, 50.0], &ctx));
+        assert!(crate::ui::hit_test(&invisible, [50.0, 50.0], &ctx).is_empty());
+        assert!(crate::ui::snapshot(&invisible).is_none());
+
+        let undisplayed = frame(LayoutStyle::new().display(false));
+        assert_eq!(undisplayed.measure(&constraints, &ctx), [10.0, 0.0]);
+        assert_eq!(undisplayed.baseline(&constraints, &ctx), None);
+        assert_eq!(undisplayed.max_intrinsic_width(500.0, &ctx), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_intrinsic_size_cache_behavior() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+
+        let call_count = Arc::new(CallCount::default());
+        let widget_impl = MockWidgetWithCallCount {
+            call_count: Arc::clone(&call_count),
+        };
+        let mut widget_frame = WidgetFrame::new(None, vec![], vec![], widget_impl);
+        widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+
+        // Default implementation falls back to an unbounded measure.
+        assert_eq!(widget_frame.max_intrinsic_width(50.0, &ctx), 100.0);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 1);
+
+        // Same query hits the cache.
+        assert_eq!(widget_frame.max_intrinsic_width(50.0, &ctx), 100.0);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 1);
+
+        // Different dimension / extent are cached separately.
+        assert_eq!(widget_frame.max_intrinsic_height(50.0, &ctx), 100.0);
+        assert_eq!(widget_frame.max_intrinsic_width(60.0, &ctx), 100.0);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 3);
+        widget_frame.max_intrinsic_width(50.0, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 3);
+
+        // Rearrange clears every intrinsic entry.
+        widget_frame
+            .dirty_flags
+            .as_ref()
+            .unwrap()
+            .need_rearrange
+            .mark_dirty();
+        widget_frame.max_intrinsic_width(50.0, &ctx);
+        assert_eq!(call_count.measure.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_baseline_defaults_to_none() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = MockDom {
+            id: 0,
+            children: vec![],
+        }
+        .build_widget_tree();
+        widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+
+        let constraints = Constraints::new([0.0, 200.0], [0.0, 200.0]);
+        assert_eq!(widget_frame.baseline(&constraints, &ctx), None);
+    }
+
+    struct WidgetRequestingRearrange;
+    impl Widget<MockDom, String, MockSetting> for WidgetRequestingRearrange {
+        fn update_widget<'a>(
+            &mut self,
+            dom: &'a MockDom,
+            cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            if let Some(invalidator) = cache_invalidator {
+                invalidator.relayout_next_frame();
+            }
+            dom.children
+                .iter()
+                .map(|(child, setting)| (child as &dyn Dom<String>, setting.clone(), child.id))
+                .collect()
+        }
+        fn device_input(
+            &mut self,
+            _: [f32; 2],
+            _: &DeviceInput,
+            _: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _: InvalidationHandle,
+            _: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+        fn is_inside(
+            &self,
+            _: [f32; 2],
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _: &WidgetContext,
+        ) -> bool {
+            true
+        }
+        fn measure(
+            &self,
+            _: &Constraints,
+            _: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _: &WidgetContext,
+        ) -> [f32; 2] {
+            [0.0, 0.0]
+        }
+        fn arrange(
+            &self,
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+        fn render(
+            &self,
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _: Background,
+            _: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rearrange_request_from_widget() {
+        let dom = MockDom {
+            id: 0,
+            children: vec![],
+        };
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = Box::new(WidgetFrame::new(
+            None,
+            vec![],
+            vec![],
+            WidgetRequestingRearrange,
+        ));
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+
+        let frame_impl_before = (&*widget_frame as &dyn Any)
+            .downcast_ref::<WidgetFrame<MockDom, WidgetRequestingRearrange, String, MockSetting>>()
+            .unwrap();
+        assert!(
+            !frame_impl_before
+                .dirty_flags
+                .as_ref()
+                .unwrap()
+                .need_rearrange
+                .is_dirty()
+        );
+
+        // update_widget is called, which should trigger rearrange_next_frame()
+        widget_frame.update_widget_tree(&dom).await.unwrap();
+
+        let frame_impl_after = (&*widget_frame as &dyn Any)
+            .downcast_ref::<WidgetFrame<MockDom, WidgetRequestingRearrange, String, MockSetting>>()
+            .unwrap();
+        assert!(
+            frame_impl_after
+                .dirty_flags
+                .as_ref()
+                .unwrap()
+                .need_rearrange
+                .is_dirty()
+        );
+        assert!(widget_frame.need_redraw());
+    }
+
+    #[tokio::test]
+    async fn test_redraw_flag_cleared_after_render() {
+        // This test must be non-async due to the use of `MaybeUninit` for mock context.
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+
+        let dom = MockDom {
+            id: 0,
+            children: vec![],
+        };
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = dom.build_widget_tree();
+
+        // 1. Initialize with dirty flags set to true, simulating initial state.
+        widget_frame.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        assert!(widget_frame.need_redraw(), "Should need redraw initially");
+
+        // 2. Call render, which should consume the dirty flags.
+        let texture_view = MaybeUninit::<wgpu::TextureView>::uninit();
+        let background = Background::new(unsafe { texture_view.assume_init_ref() }, [0.0, 0.0]);
+        widget_frame.arrange([800.0, 600.0], &ctx);
+        let _ = widget_frame.render(background, &ctx);
+
+        // 3. Verify that the redraw flag is now false.
+        assert!(
+            !widget_frame.need_redraw(),
+            "Redraw flag should be cleared after render"
         );
 
         // 4. Verify that another render call does not change the state (flag remains false).
@@ -1329,4 +3091,684 @@ mod tests {
             "Redraw flag should remain false after a second render"
         );
     }
+
+    struct HashedWidget {
+        content_hash: Arc<std::sync::atomic::AtomicU64>,
+        renders: Arc<AtomicUsize>,
+    }
+
+    impl Widget<MockDom, String, MockSetting> for HashedWidget {
+        fn update_widget<'a>(
+            &mut self,
+            _: &'a MockDom,
+            _: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            vec![]
+        }
+        fn device_input(
+            &mut self,
+            _: [f32; 2],
+            _: &DeviceInput,
+            _: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _: InvalidationHandle,
+            _: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+        fn measure(
+            &self,
+            _: &Constraints,
+            _: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _: &WidgetContext,
+        ) -> [f32; 2] {
+            [0.0, 0.0]
+        }
+        fn arrange(
+            &self,
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+        fn render(
+            &self,
+            _: [f32; 2],
+            _: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _: Background,
+            _: &WidgetContext,
+        ) -> RenderNode {
+            self.renders.fetch_add(1, Ordering::SeqCst);
+            RenderNode::default()
+        }
+        fn content_hash(&self) -> Option<u64> {
+            Some(self.content_hash.load(Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_render_cache_keyed_on_content_hash() {
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let content_hash = Arc::new(std::sync::atomic::AtomicU64::new(1));
+        let renders = Arc::new(AtomicUsize::new(0));
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = Box::new(WidgetFrame::new(
+            None,
+            vec![],
+            vec![],
+            HashedWidget {
+                content_hash: content_hash.clone(),
+                renders: renders.clone(),
+            },
+        ));
+        let need_redraw = BackPropDirty::new(false);
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), need_redraw.clone());
+
+        let background = test.background();
+        widget_frame.arrange([100.0, 100.0], &ctx);
+        let _ = widget_frame.render(background, &ctx);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+
+        // a redraw request with unchanged content reuses the cache
+        need_redraw.mark_dirty();
+        let _ = widget_frame.render(background, &ctx);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+
+        // changed content is rendered even without a request
+        content_hash.store(2, Ordering::SeqCst);
+        let _ = widget_frame.render(background, &ctx);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_render_cache_misses_after_atlas_recovery() {
+        use gpu_utils::device_loss_recoverable::DeviceLossRecoverable;
+
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let renders = Arc::new(AtomicUsize::new(0));
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = Box::new(WidgetFrame::new(
+            None,
+            vec![],
+            vec![],
+            HashedWidget {
+                content_hash: Arc::new(std::sync::atomic::AtomicU64::new(1)),
+                renders: renders.clone(),
+            },
+        ));
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+
+        let background = test.background();
+        widget_frame.arrange([100.0, 100.0], ctx);
+        let _ = widget_frame.render(background, ctx);
+        let _ = widget_frame.render(background, ctx);
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+
+        // regions allocated before the recovery are stale
+        let resources = test.resources().unwrap();
+        let gpu = resources.gpu();
+        resources
+            .texture_atlas()
+            .recover(&gpu.device(), &gpu.queue());
+        let _ = widget_frame.render(background, ctx);
+        assert_eq!(renders.load(Ordering::SeqCst), 2);
+    }
+
+    struct PreparingWidget {
+        started: Arc<AtomicUsize>,
+        gate: Option<tokio::sync::oneshot::Receiver<()>>,
+        cancelled: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    struct SetOnDrop(Arc<std::sync::atomic::AtomicBool>);
+
+    impl Drop for SetOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    impl Widget<MockDom, String, MockSetting> for PreparingWidget {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a MockDom,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            [100.0, 100.0]
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::default()
+        }
+
+        fn prepare(&mut self, _bounds: [f32; 2], _ctx: &WidgetContext) -> Option<PrepareFuture> {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            let guard = SetOnDrop(self.cancelled.clone());
+            match self.gate.take() {
+                Some(gate) => Some(Box::pin(async move {
+                    let _ = gate.await;
+                    std::mem::forget(guard);
+                })),
+                None => Some(Box::pin(async move {
+                    let _guard = guard;
+                    std::future::pending::<()>().await;
+                })),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prepare_redraws_on_completion_and_cancels_when_hidden() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let started = Arc::new(AtomicUsize::new(0));
+        let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = Box::new(WidgetFrame::new(
+            None,
+            vec![],
+            vec![],
+            PreparingWidget {
+                started: started.clone(),
+                gate: Some(rx),
+                cancelled: cancelled.clone(),
+            },
+        ));
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+        widget_frame.arrange([100.0, 100.0], &ctx);
+        let visible = Some([[0.0, 0.0], [100.0, 100.0]]);
+
+        // 1. the first preparation is spawned and not restarted while in flight
+        widget_frame.prepare(visible, &ctx);
+        widget_frame.prepare(Some([[0.0, 0.0], [50.0, 50.0]]), &ctx);
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+        assert!(!widget_frame.need_redraw());
+
+        // 2. completion requests a redraw
+        tx.send(()).unwrap();
+        for _ in 0..16 {
+            if widget_frame.need_redraw() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(widget_frame.need_redraw());
+
+        // 3. a new preparation is cancelled once the widget is off-screen
+        widget_frame.prepare(visible, &ctx);
+        assert_eq!(started.load(Ordering::SeqCst), 2);
+        widget_frame.prepare(None, &ctx);
+        for _ in 0..16 {
+            if cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_child_visible_rect() {
+        let parent = [[0.0, 0.0], [100.0, 100.0]];
+        let translate = |x: f32, y: f32| {
+            Arrangement::new(
+                [50.0, 50.0],
+                nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0)),
+            )
+        };
+
+        // fully visible
+        assert_eq!(
+            child_visible_rect(parent, &translate(10.0, 10.0)),
+            Some([[0.0, 0.0], [50.0, 50.0]])
+        );
+        // partially visible
+        assert_eq!(
+            child_visible_rect(parent, &translate(75.0, -25.0)),
+            Some([[0.0, 25.0], [25.0, 50.0]])
+        );
+        // off-screen
+        assert_eq!(child_visible_rect(parent, &translate(150.0, 0.0)), None);
+    }
+
+    #[derive(Default)]
+    struct LifecycleLog {
+        events: parking_lot::Mutex<Vec<&'static str>>,
+    }
+
+    struct LifecycleWidget {
+        log: Arc<LifecycleLog>,
+    }
+
+    impl Widget<MockDom, String, MockSetting> for LifecycleWidget {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a MockDom,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            [100.0, 100.0]
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::default()
+        }
+
+        fn on_mount(&mut self, _ctx: &WidgetContext) {
+            self.log.events.lock().push("mount");
+        }
+
+        fn on_unmount(&mut self) {
+            self.log.events.lock().push("unmount");
+        }
+
+        fn on_visibility_changed(&mut self, visible: bool, _ctx: &WidgetContext) {
+            self.log
+                .events
+                .lock()
+                .push(if visible { "visible" } else { "hidden" });
+        }
+
+        fn prepare(&mut self, _bounds: [f32; 2], _ctx: &WidgetContext) -> Option<PrepareFuture> {
+            self.log.events.lock().push("prepare");
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_hooks() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let log = Arc::new(LifecycleLog::default());
+        let mut widget_frame: Box<dyn AnyWidgetFrame<String>> = Box::new(WidgetFrame::new(
+            None,
+            vec![],
+            vec![],
+            LifecycleWidget { log: log.clone() },
+        ));
+
+        // never laid out: dropping does not unmount
+        drop(Box::new(
+            WidgetFrame::<MockDom, _, String, MockSetting>::new(
+                None,
+                vec![],
+                vec![],
+                LifecycleWidget { log: log.clone() },
+            ),
+        ));
+        assert!(log.events.lock().is_empty());
+
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+        widget_frame.arrange([100.0, 100.0], &ctx);
+        let visible = Some([[0.0, 0.0], [100.0, 100.0]]);
+
+        let frame = |widget_frame: &mut Box<dyn AnyWidgetFrame<String>>, visible_rect| {
+            widget_frame.update_lifecycle(visible_rect, &ctx);
+            widget_frame.prepare(visible_rect, &ctx);
+        };
+        frame(&mut widget_frame, visible);
+        // nothing changed: both walks skip the widget
+        frame(&mut widget_frame, visible);
+        frame(&mut widget_frame, None);
+        frame(&mut widget_frame, visible);
+        // a tree update that changed nothing keeps skipping it after the flags are relinked
+        let dom = MockDom {
+            id: 0,
+            children: vec![],
+        };
+        widget_frame.update_widget_tree(&dom).await.unwrap();
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+        frame(&mut widget_frame, visible);
+        // a redraw makes the walks visit it again
+        widget_frame.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(true));
+        frame(&mut widget_frame, visible);
+        drop(widget_frame);
+
+        assert_eq!(
+            *log.events.lock(),
+            vec![
+                "mount", "visible", "prepare", "hidden", "visible", "prepare", "prepare", "unmount"
+            ]
+        );
+    }
+
+    struct OwnerWidget {
+        linker: Arc<parking_lot::Mutex<Option<ChildFrameLinker>>>,
+    }
+
+    impl Widget<MockDom, String, MockSetting> for OwnerWidget {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a MockDom,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            [0.0, 0.0]
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::default()
+        }
+
+        fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+            *self.linker.lock() = Some(linker);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_owned_children_propagate_invalidation() {
+        let linker = Arc::new(parking_lot::Mutex::new(None));
+        let mut owner = WidgetFrame::new(
+            None,
+            vec![],
+            vec![],
+            OwnerWidget {
+                linker: linker.clone(),
+            },
+        );
+        let rearrange = BackPropDirty::new(false);
+        let redraw = BackPropDirty::new(false);
+        owner.update_dirty_flags(rearrange.clone(), redraw.clone());
+
+        let linker = linker
+            .lock()
+            .clone()
+            .expect("linker is handed out with dirty flags");
+        let mut owned = MockDom {
+            id: 1,
+            children: vec![],
+        }
+        .build_widget_tree();
+        linker.link(&mut *owned);
+        assert!(!rearrange.is_dirty());
+
+        // adding a child to the owned frame invalidates the owner
+        owned
+            .update_widget_tree(&MockDom {
+                id: 1,
+                children: vec![(
+                    MockDom {
+                        id: 2,
+                        children: vec![],
+                    },
+                    MockSetting::default(),
+                )],
+            })
+            .await
+            .unwrap();
+        assert!(rearrange.is_dirty());
+        assert!(redraw.is_dirty());
+    }
+
+    /// Places each child at `x = setting.value * 50` with size 40x40.
+    struct RowWidget {
+        size: [f32; 2],
+    }
+
+    impl Widget<MockDom, String, MockSetting> for RowWidget {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a MockDom,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<String>, MockSetting, u128)> {
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<String>, &mut MockSetting, &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<String> {
+            None
+        }
+
+        fn is_inside(
+            &self,
+            bounds: [f32; 2],
+            position: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _ctx: &WidgetContext,
+        ) -> bool {
+            (0.0..bounds[0]).contains(&position[0]) && (0.0..bounds[1]).contains(&position[1])
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            self.size
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            children: &[(&dyn AnyWidget<String>, &MockSetting)],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            children
+                .iter()
+                .map(|(_, setting)| {
+                    Arrangement::new(
+                        [40.0, 40.0],
+                        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+                            setting.value as f32 * 50.0,
+                            0.0,
+                            0.0,
+                        )),
+                    )
+                })
+                .collect()
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<String>, &MockSetting, &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::default()
+        }
+    }
+
+    #[test]
+    fn test_hit_test_reports_path_under_point() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let leaf = |label: &str| -> Box<dyn AnyWidgetFrame<String>> {
+            Box::new(WidgetFrame::<MockDom, _, String, MockSetting>::new(
+                Some(label.to_string()),
+                vec![],
+                vec![],
+                RowWidget { size: [40.0, 40.0] },
+            ))
+        };
+        let mut root: Box<dyn AnyWidgetFrame<String>> = Box::new(
+            WidgetFrame::<MockDom, _, String, MockSetting>::new(
+                Some("root".to_string()),
+                vec![
+                    (leaf("a"), MockSetting { value: 0 }),
+                    (leaf("b"), MockSetting { value: 1 }),
+                ],
+                vec![1, 2],
+                RowWidget {
+                    size: [100.0, 40.0],
+                },
+            )
+            .with_layout_style(LayoutStyle::new().padding(crate::ui::Edges::all(5.0))),
+        );
+        root.update_dirty_flags(BackPropDirty::new(false), BackPropDirty::new(false));
+
+        // not laid out yet
+        assert!(crate::ui::hit_test(&*root, [10.0, 10.0], &ctx).is_empty());
+
+        root.arrange([110.0, 50.0], &ctx);
+
+        // child "b" sits at 50 inside the padding of 5
+        let path = crate::ui::hit_test(&*root, [65.0, 15.0], &ctx);
+        assert_eq!(path.labels().collect::<Vec<_>>(), vec!["root", "b"]);
+        let target = path.target().unwrap();
+        assert_eq!(target.id, Some(2));
+        assert_eq!(target.bounds, [[55.0, 5.0], [95.0, 45.0]]);
+        assert_eq!(target.local_position, [10.0, 10.0]);
+        assert_eq!(path.entries()[0].bounds, [[0.0, 0.0], [110.0, 50.0]]);
+
+        // the gap between the children only hits the root
+        let path = crate::ui::hit_test(&*root, [50.0, 15.0], &ctx);
+        assert_eq!(path.labels().collect::<Vec<_>>(), vec!["root"]);
+
+        assert!(crate::ui::hit_test(&*root, [200.0, 15.0], &ctx).is_empty());
+
+        let snapshot = crate::ui::snapshot(&*root).unwrap();
+        assert_eq!(snapshot.children.len(), 2);
+        let b = snapshot.find("b").unwrap();
+        assert_eq!(b.id, Some(2));
+        assert_eq!(b.center(), [75.0, 25.0]);
+        assert_eq!(snapshot.find_all("a").len(), 1);
+    }
+
+    #[test]
+    fn async_invalidation_handle_marks_flags_from_another_thread() {
+        let need_rearrange = BackPropDirty::new(false);
+        let need_redraw = BackPropDirty::new(false);
+        let shared_dirty_flags = Arc::new(Mutex::new(Some(DirtyFlags {
+            need_rearrange: need_rearrange.clone(),
+            need_redraw: need_redraw.clone(),
+        })));
+        let handle = InvalidationHandle {
+            need_rearrange: &need_rearrange,
+            need_redraw: &need_redraw,
+            shared_dirty_flags: &shared_dirty_flags,
+        }
+        .detach();
+
+        let remote = handle.clone();
+        std::thread::spawn(move || remote.redraw_next_frame())
+            .join()
+            .unwrap();
+        assert!(need_redraw.take_dirty());
+        assert!(!need_rearrange.is_dirty());
+
+        // relinking the widget redirects the handle
+        let relinked = BackPropDirty::new(false);
+        *shared_dirty_flags.lock() = Some(DirtyFlags {
+            need_rearrange: relinked.clone(),
+            need_redraw: relinked.clone(),
+        });
+        handle.relayout_next_frame();
+        assert!(relinked.is_dirty());
+        assert!(!need_rearrange.is_dirty());
+
+        drop(shared_dirty_flags);
+        assert!(!handle.is_alive());
+        handle.redraw_next_frame();
+    }
 }
@@ -0,0 +1,144 @@
+//! Window controls for custom title bars.
+//!
+//! With [`App::decorations(false)`](crate::app::App::decorations) the OS draws neither title
+//! bar nor border, and the app draws its own:
+//!
+//! - moving: [`WidgetContext::drag_window`](crate::context::WidgetContext::drag_window) starts
+//!   an OS window move from a pointer press, as the `WindowDragArea` widget does,
+//! - resizing: [`WidgetContext::drag_resize_window`](crate::context::WidgetContext::drag_resize_window)
+//!   starts a resize towards a [`ResizeDirection`], as the `ResizeBorder` widget does,
+//! - buttons: [`WidgetContext::control_window`](crate::context::WidgetContext::control_window)
+//!   applies a [`WindowControl`] on a press, as the `WindowControlButton` widget does, and
+//!   [`ApplicationContext::control_current_window`](crate::context::ApplicationContext::control_current_window)
+//!   or `close_current_window` do the same from `update_fn`.
+
+pub use winit::window::ResizeDirection;
+
+/// A change of the window state requested by the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowControl {
+    Minimize,
+    Maximize,
+    /// Leaves the minimized or maximized state.
+    Restore,
+    /// Maximizes the window, or restores it if it is maximized.
+    ToggleMaximize,
+}
+
+/// The minimized and maximized flags a [`WindowControl`] sets; `None` leaves a flag as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WindowStateChange {
+    pub minimized: Option<bool>,
+    pub maximized: Option<bool>,
+}
+
+impl WindowControl {
+    /// What the control changes on a window that is `maximized` or not.
+    pub(crate) fn state_change(self, maximized: bool) -> WindowStateChange {
+        let (minimized, maximized) = match self {
+            WindowControl::Minimize => (Some(true), None),
+            WindowControl::Maximize => (None, Some(true)),
+            WindowControl::Restore => (Some(false), Some(false)),
+            WindowControl::ToggleMaximize => (None, Some(!maximized)),
+        };
+        WindowStateChange {
+            minimized,
+            maximized,
+        }
+    }
+}
+
+/// The edge or corner whose resize border of `width` contains `position`, in an area of
+/// `size`. `None` inside the border and outside the area.
+pub fn resize_direction(size: [f32; 2], position: [f32; 2], width: f32) -> Option<ResizeDirection> {
+    let within = (0.0..=size[0]).contains(&position[0]) && (0.0..=size[1]).contains(&position[1]);
+    if !within {
+        return None;
+    }
+    let west = position[0] < width;
+    let east = position[0] > size[0] - width;
+    let north = position[1] < width;
+    let south = position[1] > size[1] - width;
+    match (north, south, west, east) {
+        (true, _, true, _) => Some(ResizeDirection::NorthWest),
+        (true, _, _, true) => Some(ResizeDirection::NorthEast),
+        (_, true, true, _) => Some(ResizeDirection::SouthWest),
+        (_, true, _, true) => Some(ResizeDirection::SouthEast),
+        (true, ..) => Some(ResizeDirection::North),
+        (_, true, ..) => Some(ResizeDirection::South),
+        (_, _, true, _) => Some(ResizeDirection::West),
+        (.., true) => Some(ResizeDirection::East),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: [f32; 2] = [200.0, 100.0];
+    const WIDTH: f32 = 6.0;
+
+    #[test]
+    fn edges_resize_towards_their_side() {
+        let at = |x, y| resize_direction(SIZE, [x, y], WIDTH);
+
+        assert_eq!(at(100.0, 2.0), Some(ResizeDirection::North));
+        assert_eq!(at(100.0, 98.0), Some(ResizeDirection::South));
+        assert_eq!(at(2.0, 50.0), Some(ResizeDirection::West));
+        assert_eq!(at(198.0, 50.0), Some(ResizeDirection::East));
+    }
+
+    #[test]
+    fn corners_resize_diagonally() {
+        let at = |x, y| resize_direction(SIZE, [x, y], WIDTH);
+
+        assert_eq!(at(2.0, 2.0), Some(ResizeDirection::NorthWest));
+        assert_eq!(at(198.0, 2.0), Some(ResizeDirection::NorthEast));
+        assert_eq!(at(2.0, 98.0), Some(ResizeDirection::SouthWest));
+        assert_eq!(at(198.0, 98.0), Some(ResizeDirection::SouthEast));
+        // the very corner belongs to the border too
+        assert_eq!(at(0.0, 0.0), Some(ResizeDirection::NorthWest));
+        assert_eq!(at(200.0, 100.0), Some(ResizeDirection::SouthEast));
+    }
+
+    #[test]
+    fn the_inside_and_outside_do_not_resize() {
+        let at = |x, y| resize_direction(SIZE, [x, y], WIDTH);
+
+        assert_eq!(at(100.0, 50.0), None);
+        // on the inner edge of the border
+        assert_eq!(at(6.0, 6.0), None);
+        assert_eq!(at(-1.0, 50.0), None);
+        assert_eq!(at(100.0, 101.0), None);
+    }
+
+    #[test]
+    fn controls_change_the_window_state() {
+        let change = |minimized, maximized| WindowStateChange {
+            minimized,
+            maximized,
+        };
+
+        assert_eq!(
+            WindowControl::Minimize.state_change(false),
+            change(Some(true), None)
+        );
+        assert_eq!(
+            WindowControl::Maximize.state_change(false),
+            change(None, Some(true))
+        );
+        assert_eq!(
+            WindowControl::Restore.state_change(true),
+            change(Some(false), Some(false))
+        );
+        assert_eq!(
+            WindowControl::ToggleMaximize.state_change(false),
+            change(None, Some(true))
+        );
+        assert_eq!(
+            WindowControl::ToggleMaximize.state_change(true),
+            change(None, Some(false))
+        );
+    }
+}
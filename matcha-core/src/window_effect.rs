@@ -0,0 +1,120 @@
+//! Platform materials behind a window: Windows mica and acrylic, and the blur of macOS and
+//! KDE Wayland.
+//!
+//! Set an effect for the main window with [`App::window_effect`](crate::app::App::window_effect)
+//! and change it at runtime with
+//! [`ApplicationContext::set_window_effect`](crate::context::ApplicationContext::set_window_effect).
+//!
+//! The effect is drawn by the compositor behind the window's surface, so a window with an
+//! effect is always transparent and prefers a premultiplied surface alpha mode. The base color
+//! and widget backgrounds are composited over it: keep them translucent where the material
+//! should show through, e.g. a `base_color` with an alpha of 0.5 tints the material.
+//!
+//! Effects a platform does not have fall back to a similar one, or to a plain transparent
+//! window.
+
+use winit::window::{Window, WindowAttributes};
+
+/// A material the platform draws behind the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WindowEffect {
+    #[default]
+    None,
+    /// Blurs what is behind the window. Acrylic on Windows.
+    Blur,
+    /// Windows 11 mica, tinted by the desktop wallpaper, for long-lived windows. Blur elsewhere.
+    Mica,
+    /// Mica with a stronger tint, for windows with tabs in the title bar. Blur elsewhere.
+    TabbedMica,
+    /// Windows 11 acrylic, a blur of what is behind the window, for transient windows such as
+    /// popups. Blur elsewhere.
+    Acrylic,
+}
+
+impl WindowEffect {
+    pub fn is_none(self) -> bool {
+        self == Self::None
+    }
+
+    /// Requests the effect for a window that is not created yet.
+    pub(crate) fn apply_to_attributes(self, attributes: WindowAttributes) -> WindowAttributes {
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowAttributesExtWindows;
+            attributes.with_system_backdrop(self.backdrop())
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            attributes.with_blur(!self.is_none())
+        }
+    }
+
+    /// Changes the effect of a created window.
+    pub(crate) fn apply(self, window: &Window) {
+        #[cfg(target_os = "windows")]
+        {
+            use winit::platform::windows::WindowExtWindows;
+            window.set_system_backdrop(self.backdrop());
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            window.set_blur(!self.is_none());
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn backdrop(self) -> winit::platform::windows::BackdropType {
+        use winit::platform::windows::BackdropType;
+        match self {
+            Self::None => BackdropType::Auto,
+            Self::Mica => BackdropType::MainWindow,
+            Self::TabbedMica => BackdropType::TabbedWindow,
+            Self::Blur | Self::Acrylic => BackdropType::TransientWindow,
+        }
+    }
+
+    /// The surface alpha mode for `requested`: an effect needs the surface's alpha, so the
+    /// default and opaque modes are replaced by a supported blending mode.
+    pub(crate) fn alpha_mode(
+        self,
+        requested: wgpu::CompositeAlphaMode,
+        supported: &[wgpu::CompositeAlphaMode],
+    ) -> wgpu::CompositeAlphaMode {
+        use wgpu::CompositeAlphaMode::{Auto, Opaque, PostMultiplied, PreMultiplied};
+        if self.is_none() || !matches!(requested, Auto | Opaque) {
+            return requested;
+        }
+        [PreMultiplied, PostMultiplied]
+            .into_iter()
+            .find(|mode| supported.contains(mode))
+            .unwrap_or(requested)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_prefer_a_premultiplied_surface() {
+        use wgpu::CompositeAlphaMode::{Auto, Inherit, Opaque, PostMultiplied, PreMultiplied};
+
+        let supported = [Opaque, PostMultiplied, PreMultiplied];
+        assert_eq!(
+            WindowEffect::Mica.alpha_mode(Auto, &supported),
+            PreMultiplied
+        );
+        assert_eq!(
+            WindowEffect::Blur.alpha_mode(Opaque, &[Opaque, PostMultiplied]),
+            PostMultiplied
+        );
+        // an explicit blending mode is kept
+        assert_eq!(
+            WindowEffect::Acrylic.alpha_mode(Inherit, &supported),
+            Inherit
+        );
+        // nothing to blend with
+        assert_eq!(WindowEffect::Blur.alpha_mode(Auto, &[Opaque]), Auto);
+        assert_eq!(WindowEffect::None.alpha_mode(Auto, &supported), Auto);
+    }
+}
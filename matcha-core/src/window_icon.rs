@@ -0,0 +1,71 @@
+//! The icon of a window, shown in its title bar and the taskbar.
+//!
+//! Set it for the main window with [`App::window_icon`](crate::app::App::window_icon) and
+//! change it at runtime with
+//! [`ApplicationContext::set_window_icon`](crate::context::ApplicationContext::set_window_icon).
+//! Platforms without window icons, e.g. macOS where the icon comes from the app bundle, ignore
+//! it.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WindowIconError {
+    #[error("failed to decode window icon: {0}")]
+    Decode(#[from] image::ImageError),
+    #[error("invalid window icon: {0}")]
+    BadIcon(#[from] winit::window::BadIcon),
+}
+
+/// Pixels of a window icon.
+#[derive(Debug, Clone)]
+pub struct WindowIcon {
+    icon: winit::window::Icon,
+}
+
+impl WindowIcon {
+    /// Creates an icon from `width * height` RGBA8 pixels.
+    pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, WindowIconError> {
+        Ok(Self {
+            icon: winit::window::Icon::from_rgba(rgba, width, height)?,
+        })
+    }
+
+    /// Creates an icon from an encoded image, e.g. a PNG embedded with `include_bytes!`.
+    pub fn from_image_bytes(bytes: &[u8]) -> Result<Self, WindowIconError> {
+        let image = image::load_from_memory(bytes)?.into_rgba8();
+        let (width, height) = image.dimensions();
+        Self::from_rgba(image.into_raw(), width, height)
+    }
+
+    pub(crate) fn winit_icon(&self) -> winit::window::Icon {
+        self.icon.clone()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_rgba_of_wrong_length() {
+        assert!(WindowIcon::from_rgba(vec![0; 4 * 4], 2, 2).is_ok());
+        assert!(matches!(
+            WindowIcon::from_rgba(vec![0; 3], 2, 2),
+            Err(WindowIconError::BadIcon(_))
+        ));
+    }
+
+    #[test]
+    fn decodes_embedded_png() {
+        let mut png = Vec::new();
+        image::RgbaImage::new(3, 2)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert!(WindowIcon::from_image_bytes(&png).is_ok());
+        assert!(matches!(
+            WindowIcon::from_image_bytes(b"not an image"),
+            Err(WindowIconError::Decode(_))
+        ));
+    }
+}
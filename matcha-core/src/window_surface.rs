@@ -1,11 +1,20 @@
+use crate::color::DisplayColorSpace;
+use crate::cursor::Cursor;
+use crate::device_input::ImePurpose;
+use crate::present_timing::{DEFAULT_MAX_FRAME_LATENCY, PresentStats, PresentTiming};
+use crate::window_control::{ResizeDirection, WindowControl};
+use crate::window_effect::WindowEffect;
+use crate::window_icon::WindowIcon;
 use gpu_utils::gpu::Gpu;
-use log::{debug, trace};
+use log::{debug, trace, warn};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use thiserror::Error;
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event_loop::ActiveEventLoop,
-    window::{Fullscreen, Window},
+    window::{CursorGrabMode, Fullscreen, Window},
 };
 
 #[derive(Debug, Clone)]
@@ -14,6 +23,14 @@ pub struct WindowSurfaceConfig {
     size: PhysicalSize<u32>,
     maximized: bool,
     fullscreen: bool,
+    transparent: bool,
+    decorations: bool,
+    alpha_mode: wgpu::CompositeAlphaMode,
+    hdr: bool,
+    max_frame_latency: u32,
+    icon: Option<WindowIcon>,
+    effect: WindowEffect,
+    software: bool,
 }
 
 impl Default for WindowSurfaceConfig {
@@ -30,6 +47,14 @@ impl WindowSurfaceConfig {
             size: PhysicalSize::new(800, 600),
             maximized: false,
             fullscreen: false,
+            transparent: false,
+            decorations: true,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            hdr: false,
+            max_frame_latency: DEFAULT_MAX_FRAME_LATENCY,
+            icon: None,
+            effect: WindowEffect::None,
+            software: false,
         }
     }
 
@@ -56,6 +81,60 @@ impl WindowSurfaceConfig {
         self.fullscreen = fullscreen;
     }
 
+    /// Requests a window with a transparent background so that translucent pixels
+    /// of the surface show the desktop behind the window.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        trace!("WindowSurfaceConfig::set_transparent: transparent={transparent}");
+        self.transparent = transparent;
+    }
+
+    /// Whether the OS draws a title bar and border around the window.
+    pub fn set_decorations(&mut self, decorations: bool) {
+        trace!("WindowSurfaceConfig::set_decorations: decorations={decorations}");
+        self.decorations = decorations;
+    }
+
+    /// Sets the composite alpha mode requested for the surface.
+    ///
+    /// Falls back to `Auto` when the surface does not support the requested mode.
+    pub fn set_alpha_mode(&mut self, alpha_mode: wgpu::CompositeAlphaMode) {
+        trace!("WindowSurfaceConfig::set_alpha_mode: alpha_mode={alpha_mode:?}");
+        self.alpha_mode = alpha_mode;
+    }
+
+    /// Requests an HDR surface (`Rgba16Float`, scRGB) when the display supports one.
+    ///
+    /// Falls back to an 8-bit sRGB surface otherwise; HDR colors are tone-mapped then.
+    pub fn set_hdr(&mut self, hdr: bool) {
+        trace!("WindowSurfaceConfig::set_hdr: hdr={hdr}");
+        self.hdr = hdr;
+    }
+
+    /// Frames the surface queues ahead of the display, at least 1. More frames smooth out
+    /// uneven frame times at the cost of input latency.
+    pub fn set_max_frame_latency(&mut self, latency: u32) {
+        trace!("WindowSurfaceConfig::set_max_frame_latency: latency={latency}");
+        self.max_frame_latency = latency.max(1);
+    }
+
+    pub fn set_icon(&mut self, icon: Option<WindowIcon>) {
+        trace!("WindowSurfaceConfig::set_icon: icon={}", icon.is_some());
+        self.icon = icon;
+    }
+
+    /// Material the platform draws behind the window. Implies a transparent window.
+    pub fn set_effect(&mut self, effect: WindowEffect) {
+        trace!("WindowSurfaceConfig::set_effect: effect={effect:?}");
+        self.effect = effect;
+    }
+
+    /// Presents frames composited on the CPU instead of creating a wgpu surface, see
+    /// [`RenderBackend::Software`](crate::render_backend::RenderBackend::Software).
+    pub fn set_software(&mut self, software: bool) {
+        trace!("WindowSurfaceConfig::set_software: software={software}");
+        self.software = software;
+    }
+
     pub fn title(&self) -> &str {
         &self.title
     }
@@ -72,6 +151,38 @@ impl WindowSurfaceConfig {
         self.fullscreen
     }
 
+    pub fn transparent(&self) -> bool {
+        self.transparent
+    }
+
+    pub fn decorations(&self) -> bool {
+        self.decorations
+    }
+
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.alpha_mode
+    }
+
+    pub fn hdr(&self) -> bool {
+        self.hdr
+    }
+
+    pub fn max_frame_latency(&self) -> u32 {
+        self.max_frame_latency
+    }
+
+    pub fn icon(&self) -> Option<&WindowIcon> {
+        self.icon.as_ref()
+    }
+
+    pub fn effect(&self) -> WindowEffect {
+        self.effect
+    }
+
+    pub fn software(&self) -> bool {
+        self.software
+    }
+
     pub fn start_window(
         &self,
         event_loop: &ActiveEventLoop,
@@ -82,7 +193,11 @@ impl WindowSurfaceConfig {
         let window_attributes = Window::default_attributes()
             .with_title(&self.title)
             .with_inner_size(self.size)
-            .with_maximized(self.maximized);
+            .with_maximized(self.maximized)
+            .with_transparent(self.transparent || !self.effect.is_none())
+            .with_decorations(self.decorations)
+            .with_window_icon(self.icon.as_ref().map(WindowIcon::winit_icon));
+        let window_attributes = self.effect.apply_to_attributes(window_attributes);
 
         let window = Arc::new(event_loop.create_window(window_attributes)?);
         trace!(
@@ -94,16 +209,50 @@ impl WindowSurfaceConfig {
             window.set_fullscreen(Some(Fullscreen::Borderless(None)));
         }
 
+        if self.software {
+            return self.start_software_window(window);
+        }
+
         let surface = gpu.instance().create_surface(window.clone())?;
         trace!("WindowSurfaceConfig::start_window: surface created");
 
-        let if_preferred_format_supported = surface
-            .get_capabilities(gpu.adapter())
-            .formats
-            .contains(&gpu.preferred_surface_format());
-        trace!(
-            "WindowSurfaceConfig::start_window: preferred_format_supported={if_preferred_format_supported}"
+        let capabilities = surface.get_capabilities(gpu.adapter());
+
+        let selected_format = select_surface_format(
+            &capabilities.formats,
+            gpu.preferred_surface_format(),
+            self.hdr,
         );
+        trace!("WindowSurfaceConfig::start_window: selected_format={selected_format:?}");
+        if self.hdr && selected_format.is_none_or(|(_, space)| !space.is_hdr()) {
+            warn!(
+                "WindowSurfaceConfig::start_window: HDR output is not supported (formats: {:?}), falling back to SDR",
+                capabilities.formats
+            );
+        }
+
+        let requested_alpha_mode = self
+            .effect
+            .alpha_mode(self.alpha_mode, &capabilities.alpha_modes);
+        let alpha_mode = if capabilities.alpha_modes.contains(&requested_alpha_mode) {
+            requested_alpha_mode
+        } else {
+            warn!(
+                "WindowSurfaceConfig::start_window: alpha mode {:?} is not supported (supported: {:?}), falling back to Auto",
+                requested_alpha_mode, capabilities.alpha_modes
+            );
+            wgpu::CompositeAlphaMode::Auto
+        };
+
+        // Allow sampling the surface so backdrop blurs can read what is drawn below them.
+        let usage = if capabilities
+            .usages
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        };
 
         let mut surface_config = surface
             .get_default_config(
@@ -112,41 +261,165 @@ impl WindowSurfaceConfig {
                 window.inner_size().height,
             )
             .map(|mut config| {
-                config.usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+                config.usage = usage;
                 config.present_mode = wgpu::PresentMode::AutoVsync;
-                config.desired_maximum_frame_latency = 1;
-                config.alpha_mode = wgpu::CompositeAlphaMode::Auto;
+                config.desired_maximum_frame_latency = self.max_frame_latency;
+                config.alpha_mode = alpha_mode;
                 config
             })
             .ok_or(WindowSurfaceError::SurfaceConfiguration)?;
         trace!(
-            "WindowSurfaceConfig::start_window: default config width={} height={} format={:?}",
-            surface_config.width, surface_config.height, surface_config.format
+            "WindowSurfaceConfig::start_window: default config width={} height={} format={:?} alpha_mode={:?}",
+            surface_config.width,
+            surface_config.height,
+            surface_config.format,
+            surface_config.alpha_mode
         );
 
-        if if_preferred_format_supported {
-            surface_config.format = gpu.preferred_surface_format();
-            trace!(
-                "WindowSurfaceConfig::start_window: applying preferred format {:?}",
-                surface_config.format
-            );
-        }
+        let color_space = match selected_format {
+            Some((format, color_space)) => {
+                surface_config.format = format;
+                trace!(
+                    "WindowSurfaceConfig::start_window: applying format {format:?} ({color_space:?})"
+                );
+                color_space
+            }
+            None => DisplayColorSpace::Srgb,
+        };
 
         surface.configure(&gpu.device(), &surface_config);
         trace!("WindowSurfaceConfig::start_window: surface configured");
 
         Ok(WindowSurface {
             window,
-            surface,
+            surface: Some(surface),
+            software_surface: None,
+            software: false,
             surface_config,
+            transparent: self.transparent,
+            requested_alpha_mode: self.alpha_mode,
+            requested_hdr: self.hdr,
+            color_space,
+            cursor: parking_lot::Mutex::new(Cursor::default()),
+            pointer_locked: AtomicBool::new(false),
+            effect: parking_lot::Mutex::new(self.effect),
+            present_timing: Arc::new(PresentTiming::new()),
         })
     }
+
+    fn start_software_window(
+        &self,
+        window: Arc<Window>,
+    ) -> Result<WindowSurface, WindowSurfaceError> {
+        let software_surface = SoftwareSurface::new(&window)?;
+        trace!("WindowSurfaceConfig::start_window: software surface created");
+
+        // describes the softbuffer surface; it is never passed to wgpu
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            width: window.inner_size().width,
+            height: window.inner_size().height,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: self.max_frame_latency,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: Vec::new(),
+        };
+        if self.hdr {
+            warn!("WindowSurfaceConfig::start_window: HDR output is not supported in software");
+        }
+
+        Ok(WindowSurface {
+            window,
+            surface: None,
+            software_surface: Some(Arc::new(software_surface)),
+            software: true,
+            surface_config,
+            transparent: self.transparent,
+            requested_alpha_mode: self.alpha_mode,
+            requested_hdr: self.hdr,
+            color_space: DisplayColorSpace::Srgb,
+            cursor: parking_lot::Mutex::new(Cursor::default()),
+            pointer_locked: AtomicBool::new(false),
+            effect: parking_lot::Mutex::new(self.effect),
+            present_timing: Arc::new(PresentTiming::new()),
+        })
+    }
+}
+
+/// A window surface the CPU writes frames into with softbuffer, see
+/// [`RenderBackend::Software`](crate::render_backend::RenderBackend::Software).
+pub(crate) struct SoftwareSurface {
+    // softbuffer surfaces are not `Sync`
+    surface: parking_lot::Mutex<softbuffer::Surface<Arc<Window>, Arc<Window>>>,
+}
+
+impl SoftwareSurface {
+    fn new(window: &Arc<Window>) -> Result<Self, WindowSurfaceError> {
+        // the surface keeps its own handle to the display
+        let context = softbuffer::Context::new(window.clone())?;
+        let surface = softbuffer::Surface::new(&context, window.clone())?;
+        Ok(Self {
+            surface: parking_lot::Mutex::new(surface),
+        })
+    }
+
+    /// Shows `frame`, whose premultiplied pixels are composited over black since the surface
+    /// is opaque. The surface takes the size of the frame.
+    pub(crate) fn present(
+        &self,
+        frame: &renderer::tiny_skia::Pixmap,
+    ) -> Result<(), softbuffer::SoftBufferError> {
+        let (Some(width), Some(height)) = (
+            std::num::NonZeroU32::new(frame.width()),
+            std::num::NonZeroU32::new(frame.height()),
+        ) else {
+            return Ok(());
+        };
+        let mut surface = self.surface.lock();
+        surface.resize(width, height)?;
+        let mut buffer = surface.buffer_mut()?;
+        for (pixel, rgba) in buffer.iter_mut().zip(frame.data().chunks_exact(4)) {
+            *pixel = (u32::from(rgba[0]) << 16) | (u32::from(rgba[1]) << 8) | u32::from(rgba[2]);
+        }
+        buffer.present()
+    }
+}
+
+/// Picks the surface format among the supported `formats`.
+///
+/// Returns `None` to keep the surface's default format.
+fn select_surface_format(
+    formats: &[wgpu::TextureFormat],
+    preferred: wgpu::TextureFormat,
+    hdr: bool,
+) -> Option<(wgpu::TextureFormat, DisplayColorSpace)> {
+    if hdr && formats.contains(&wgpu::TextureFormat::Rgba16Float) {
+        Some((wgpu::TextureFormat::Rgba16Float, DisplayColorSpace::ScRgb))
+    } else if formats.contains(&preferred) {
+        Some((preferred, DisplayColorSpace::Srgb))
+    } else {
+        None
+    }
 }
 
 pub struct WindowSurface {
     window: Arc<Window>,
-    surface: wgpu::Surface<'static>,
+    // `None` while the application is suspended, and always in software
+    surface: Option<wgpu::Surface<'static>>,
+    // replaces `surface` in software, `None` while the application is suspended
+    software_surface: Option<Arc<SoftwareSurface>>,
+    software: bool,
     surface_config: wgpu::SurfaceConfiguration,
+    transparent: bool,
+    requested_alpha_mode: wgpu::CompositeAlphaMode,
+    requested_hdr: bool,
+    color_space: DisplayColorSpace,
+    // the cursor last requested, which a custom one may not be created for yet
+    cursor: parking_lot::Mutex<Cursor>,
+    pointer_locked: AtomicBool,
+    effect: parking_lot::Mutex<WindowEffect>,
+    present_timing: Arc<PresentTiming>,
 }
 
 impl WindowSurface {
@@ -179,11 +452,48 @@ impl WindowSurface {
 
         self.surface_config.width = size.width;
         self.surface_config.height = size.height;
+        let Some(surface) = &self.surface else {
+            trace!("WindowSurface::set_surface_size: no surface while suspended");
+            return;
+        };
         trace!(
             "WindowSurface::set_surface_size: configuring surface to {}x{}",
             size.width, size.height
         );
-        self.surface.configure(device, &self.surface_config);
+        surface.configure(device, &self.surface_config);
+    }
+
+    /// Reconfigures the surface to queue at most `latency` frames, see
+    /// [`WindowSurfaceConfig::set_max_frame_latency`].
+    pub fn set_max_frame_latency(&mut self, latency: u32, device: &wgpu::Device) {
+        let latency = latency.max(1);
+        if self.surface_config.desired_maximum_frame_latency == latency {
+            return;
+        }
+        debug!("WindowSurface::set_max_frame_latency: latency={latency}");
+        self.surface_config.desired_maximum_frame_latency = latency;
+        if let Some(surface) = &self.surface {
+            surface.configure(device, &self.surface_config);
+        }
+    }
+
+    pub fn max_frame_latency(&self) -> u32 {
+        self.surface_config.desired_maximum_frame_latency
+    }
+
+    pub fn present_stats(&self) -> PresentStats {
+        self.present_timing.stats()
+    }
+
+    /// Shared with the frames in flight, which record when they were presented.
+    pub(crate) fn present_timing(&self) -> &Arc<PresentTiming> {
+        &self.present_timing
+    }
+
+    /// Refresh interval of the monitor the window is on, if the platform reports it.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        let millihertz = self.window.current_monitor()?.refresh_rate_millihertz()?;
+        (millihertz > 0).then(|| Duration::from_secs_f64(1000.0 / f64::from(millihertz)))
     }
 
     pub fn set_maximized(&self, maximized: bool) {
@@ -191,6 +501,147 @@ impl WindowSurface {
         self.window.set_maximized(maximized);
     }
 
+    pub fn set_visible(&self, visible: bool) {
+        trace!("WindowSurface::set_visible: visible={visible}");
+        self.window.set_visible(visible);
+        if visible {
+            self.window.focus_window();
+        }
+    }
+
+    /// Changes the material behind the window.
+    ///
+    /// The surface alpha mode is chosen when the window is created, so the effect only shows
+    /// through if the window was created with an effect or a blending alpha mode.
+    pub fn set_effect(&self, effect: WindowEffect) {
+        trace!("WindowSurface::set_effect: effect={effect:?}");
+        self.window
+            .set_transparent(self.transparent || !effect.is_none());
+        effect.apply(&self.window);
+        *self.effect.lock() = effect;
+    }
+
+    pub fn set_icon(&self, icon: Option<&WindowIcon>) {
+        trace!("WindowSurface::set_icon: icon={}", icon.is_some());
+        self.window
+            .set_window_icon(icon.map(WindowIcon::winit_icon));
+    }
+
+    /// Changes the mouse pointer over the window.
+    ///
+    /// Returns `false` if `cursor` is a custom cursor whose platform cursor is not created yet;
+    /// it is shown once the event loop created it, see [`apply_custom_cursor`](Self::apply_custom_cursor).
+    pub fn set_cursor(&self, cursor: Cursor) -> bool {
+        trace!("WindowSurface::set_cursor: cursor={cursor:?}");
+        let applied = match &cursor {
+            Cursor::System(icon) => {
+                self.window.set_cursor(*icon);
+                true
+            }
+            Cursor::Custom(custom) => match custom.platform_cursor() {
+                Some(platform_cursor) => {
+                    self.window.set_cursor(platform_cursor);
+                    true
+                }
+                None => false,
+            },
+        };
+        *self.cursor.lock() = cursor;
+        applied
+    }
+
+    /// Shows the custom cursor the event loop created for `cursor`, unless the cursor changed
+    /// again in the meantime.
+    pub fn apply_custom_cursor(
+        &self,
+        cursor: &Cursor,
+        platform_cursor: winit::window::CustomCursor,
+    ) {
+        if self.cursor.lock().same_as(cursor) {
+            trace!("WindowSurface::apply_custom_cursor: showing created cursor");
+            self.window.set_cursor(platform_cursor);
+        }
+    }
+
+    /// Hides the pointer and keeps it in the window, for relative motion.
+    ///
+    /// Locks the pointer in place where the platform supports it and confines it to the
+    /// window otherwise. Returns `false` if neither is supported.
+    pub fn lock_pointer(&self) -> bool {
+        trace!("WindowSurface::lock_pointer");
+        let grabbed = self
+            .window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined));
+        if let Err(e) = grabbed {
+            warn!("WindowSurface::lock_pointer: not supported: {e}");
+            return false;
+        }
+        self.window.set_cursor_visible(false);
+        self.pointer_locked.store(true, Ordering::Relaxed);
+        true
+    }
+
+    /// Releases a locked pointer. Returns `false` if it was not locked.
+    pub fn unlock_pointer(&self) -> bool {
+        if !self.pointer_locked.swap(false, Ordering::Relaxed) {
+            return false;
+        }
+        trace!("WindowSurface::unlock_pointer");
+        if let Err(e) = self.window.set_cursor_grab(CursorGrabMode::None) {
+            warn!("WindowSurface::unlock_pointer: failed to release the pointer: {e}");
+        }
+        self.window.set_cursor_visible(true);
+        true
+    }
+
+    pub fn is_pointer_locked(&self) -> bool {
+        self.pointer_locked.load(Ordering::Relaxed)
+    }
+
+    pub fn set_ime(&self, purpose: Option<ImePurpose>) {
+        trace!("WindowSurface::set_ime: purpose={purpose:?}");
+        self.window.set_ime_allowed(purpose.is_some());
+        if let Some(purpose) = purpose {
+            self.window.set_ime_purpose(purpose);
+        }
+    }
+
+    /// Starts moving the window with the pointer. Only works while a mouse button is held.
+    pub fn drag_window(&self) {
+        trace!("WindowSurface::drag_window");
+        if let Err(e) = self.window.drag_window() {
+            warn!("WindowSurface::drag_window: failed to start moving the window: {e}");
+        }
+    }
+
+    /// Starts resizing the window with the pointer. Only works while a mouse button is held.
+    pub fn drag_resize_window(&self, direction: ResizeDirection) {
+        trace!("WindowSurface::drag_resize_window: direction={direction:?}");
+        if let Err(e) = self.window.drag_resize_window(direction) {
+            warn!("WindowSurface::drag_resize_window: failed to start resizing the window: {e}");
+        }
+    }
+
+    pub fn control(&self, control: WindowControl) {
+        trace!("WindowSurface::control: control={control:?}");
+        let change = control.state_change(self.window.is_maximized());
+        if let Some(minimized) = change.minimized {
+            self.window.set_minimized(minimized);
+        }
+        if let Some(maximized) = change.maximized {
+            self.window.set_maximized(maximized);
+        }
+    }
+
+    /// Whether the window takes pointer input; clicks go to the windows below otherwise.
+    pub fn set_cursor_hittest(&self, hittest: bool) {
+        trace!("WindowSurface::set_cursor_hittest: hittest={hittest}");
+        if let Err(e) = self.window.set_cursor_hittest(hittest) {
+            warn!("WindowSurface::set_cursor_hittest: not supported: {e}");
+        }
+    }
+
     pub fn set_fullscreen(&self, fullscreen: bool) {
         trace!("WindowSurface::set_fullscreen: fullscreen={fullscreen}");
         if fullscreen {
@@ -209,11 +660,65 @@ impl WindowSurface {
 
         self.surface_config.width = self.window.inner_size().width;
         self.surface_config.height = self.window.inner_size().height;
+        let Some(surface) = &self.surface else {
+            trace!("WindowSurface::reconfigure_surface: no surface while suspended");
+            return;
+        };
         trace!(
             "WindowSurface::reconfigure_surface: new size {}x{}",
             self.surface_config.width, self.surface_config.height
         );
-        self.surface.configure(device, &self.surface_config);
+        surface.configure(device, &self.surface_config);
+    }
+
+    /// Drops the surface. The native window may be destroyed while the application is
+    /// suspended, so the surface must not outlive the suspension.
+    pub fn drop_surface(&mut self) {
+        let software_surface = self.software_surface.take();
+        if self.surface.take().is_some() || software_surface.is_some() {
+            debug!("WindowSurface::drop_surface: surface dropped");
+        }
+    }
+
+    /// Creates and configures a new surface after [`drop_surface`](Self::drop_surface).
+    /// Does nothing if the surface exists.
+    pub fn recreate_surface(&mut self, gpu: &Gpu) -> Result<(), WindowSurfaceError> {
+        if self.has_surface() {
+            return Ok(());
+        }
+        if self.is_software() {
+            self.software_surface = Some(Arc::new(SoftwareSurface::new(&self.window)?));
+            debug!("WindowSurface::recreate_surface: software surface recreated");
+            return Ok(());
+        }
+
+        let surface = gpu.instance().create_surface(self.window.clone())?;
+        debug!("WindowSurface::recreate_surface: surface recreated");
+
+        // the window may have been resized while suspended
+        let size = self.window.inner_size();
+        if size.width != 0 && size.height != 0 {
+            self.surface_config.width = size.width;
+            self.surface_config.height = size.height;
+        }
+        surface.configure(&gpu.device(), &self.surface_config);
+        self.surface = Some(surface);
+        Ok(())
+    }
+
+    pub fn has_surface(&self) -> bool {
+        self.surface.is_some() || self.software_surface.is_some()
+    }
+
+    /// Whether frames are composited on the CPU, see [`WindowSurfaceConfig::set_software`].
+    pub fn is_software(&self) -> bool {
+        self.software
+    }
+
+    /// The surface frames composited on the CPU are presented to, `None` while suspended or
+    /// when frames are rendered with wgpu.
+    pub(crate) fn software_surface(&self) -> Option<Arc<SoftwareSurface>> {
+        self.software_surface.clone()
     }
 
     pub fn request_redraw(&self) {
@@ -222,13 +727,27 @@ impl WindowSurface {
     }
 
     pub fn current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
-        self.surface.get_current_texture()
+        match &self.surface {
+            Some(surface) => surface.get_current_texture(),
+            // reported as lost so that callers skip the frame
+            None => Err(wgpu::SurfaceError::Lost),
+        }
     }
 
     pub fn format(&self) -> wgpu::TextureFormat {
         self.surface_config.format
     }
 
+    /// The alpha mode the surface was actually configured with.
+    pub fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.surface_config.alpha_mode
+    }
+
+    /// The color space of the configured surface.
+    pub fn color_space(&self) -> DisplayColorSpace {
+        self.color_space
+    }
+
     pub fn inner_size(&self) -> PhysicalSize<u32> {
         self.window.inner_size()
     }
@@ -255,6 +774,15 @@ impl WindowSurface {
             size: self.window.inner_size(),
             maximized: self.window.is_maximized(),
             fullscreen: self.window.fullscreen().is_some(),
+            transparent: self.transparent,
+            decorations: self.window.is_decorated(),
+            alpha_mode: self.requested_alpha_mode,
+            hdr: self.requested_hdr,
+            max_frame_latency: self.surface_config.desired_maximum_frame_latency,
+            // the icon cannot be read back from the window
+            icon: None,
+            effect: *self.effect.lock(),
+            software: self.software,
         }
     }
 }
@@ -267,4 +795,34 @@ pub enum WindowSurfaceError {
     CreateSurface(#[from] wgpu::CreateSurfaceError),
     #[error("Failed to get surface configuration")]
     SurfaceConfiguration,
+    #[error("Failed to create software surface: {0}")]
+    SoftwareSurface(#[from] softbuffer::SoftBufferError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hdr_format_is_selected_only_when_requested_and_supported() {
+        use wgpu::TextureFormat::{Bgra8UnormSrgb, Rgba16Float};
+
+        let formats = [Bgra8UnormSrgb, Rgba16Float];
+        assert_eq!(
+            select_surface_format(&formats, Bgra8UnormSrgb, true),
+            Some((Rgba16Float, DisplayColorSpace::ScRgb))
+        );
+        assert_eq!(
+            select_surface_format(&formats, Bgra8UnormSrgb, false),
+            Some((Bgra8UnormSrgb, DisplayColorSpace::Srgb))
+        );
+        assert_eq!(
+            select_surface_format(&[Bgra8UnormSrgb], Bgra8UnormSrgb, true),
+            Some((Bgra8UnormSrgb, DisplayColorSpace::Srgb))
+        );
+        assert_eq!(
+            select_surface_format(&[Rgba16Float], Bgra8UnormSrgb, false),
+            None
+        );
+    }
 }
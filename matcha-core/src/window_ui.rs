@@ -1,33 +1,50 @@
 use core::panic;
-use std::sync::{
-    Arc,
-    atomic::{AtomicU8, Ordering},
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
 };
 
 use gpu_utils::gpu::Gpu;
+use gpu_utils::texture_atlas::TextureAtlas;
 use log::{debug, trace, warn};
 use parking_lot::RwLock;
-use renderer::{RenderNode, core_renderer};
-use tokio::task;
+use renderer::{FrameGraph, RenderNode, SoftwareRenderer, core_renderer};
 use utils::{back_prop_dirty::BackPropDirty, update_flag::UpdateFlag};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::keyboard::NamedKey;
 
 use crate::{
-    context::GlobalResources,
+    capture::{CaptureError, CaptureRenderer},
+    context::{GlobalResources, WidgetContext},
+    cursor::Cursor,
     device_input::{
-        DeviceInput, DeviceInputData, KeyboardState, MouseState,
+        DeviceInput, DeviceInputData, ElementState, Key, KeyboardState, MouseState,
         mouse_state::{MousePrimaryButton, MouseStateConfig},
         window_state::WindowState,
     },
+    input_recording::{InputRecorder, InputReplayer, RecordedInput},
+    input_region::ClickThrough,
+    lifecycle::LifecycleEvent,
     metrics::Constraints,
-    ui::{AnyWidgetFrame, Background, component::AnyComponent},
-    window_surface::{WindowSurface, WindowSurfaceConfig},
+    present_timing::PresentTiming,
+    profiling::{profile_future, profile_span},
+    render_backend::Compositor,
+    resize_strategy::{ResizeAction, ResizeState, ResizeStrategy},
+    shortcut::ShortcutRegistry,
+    ui::{AnyWidgetFrame, Background, HitTestPath, component::AnyComponent, focus, hit_test},
+    window_control::WindowControl,
+    window_effect::WindowEffect,
+    window_icon::WindowIcon,
+    window_surface::{SoftwareSurface, WindowSurface, WindowSurfaceConfig},
 };
 
 pub struct WindowUiConfig<Message: 'static, Event: 'static> {
     window: WindowSurfaceConfig,
 
-    surface_guard: SurfaceLock,
+    surface_guard: Arc<SurfaceLock>,
 
     component: Box<dyn AnyComponent<Message, Event>>,
     widget: tokio::sync::Mutex<Option<Box<dyn AnyWidgetFrame<Event>>>>,
@@ -37,12 +54,16 @@ pub struct WindowUiConfig<Message: 'static, Event: 'static> {
     mouse_state_config: MouseStateConfig,
     mouse_state: tokio::sync::Mutex<MouseState>,
     keyboard_state: tokio::sync::Mutex<KeyboardState>,
+    shortcuts: ShortcutRegistry<Message>,
+    input_recorder: Option<Arc<InputRecorder>>,
+    click_through: bool,
+    resize_strategy: ResizeStrategy,
 }
 
 pub struct WindowUi<Message: 'static, Event: 'static> {
     window: Arc<RwLock<WindowSurface>>,
 
-    surface_guard: SurfaceLock,
+    surface_guard: Arc<SurfaceLock>,
 
     // ui
     component: Box<dyn AnyComponent<Message, Event>>,
@@ -54,58 +75,177 @@ pub struct WindowUi<Message: 'static, Event: 'static> {
     mouse_state_config: MouseStateConfig,
     mouse_state: tokio::sync::Mutex<MouseState>,
     keyboard_state: tokio::sync::Mutex<KeyboardState>,
+    shortcuts: ShortcutRegistry<Message>,
+    // writes the received input to a file, see `input_recording`
+    input_recorder: Option<Arc<InputRecorder>>,
+    // input region of a click-through window, see `input_region`
+    click_through: Option<tokio::sync::Mutex<ClickThrough>>,
+
+    // hidden windows keep their state but are not rendered
+    hidden: AtomicBool,
+    // set when the window is shown again so that its surface is redrawn
+    shown: AtomicBool,
+    // focus and occlusion reported by the platform, see `is_in_background`
+    focused: AtomicBool,
+    occluded: AtomicBool,
+    // the last rendered frame; keeps the caches of what is on screen from being evicted
+    // by the cache budget while the window is idle. Scaled while a resize is deferred.
+    presented: parking_lot::Mutex<Option<Arc<RenderNode>>>,
+    resize: parking_lot::Mutex<ResizeState>,
 }
 
+// serializes rendering with surface configuration; waiters are parked until the holder is done
 struct SurfaceLock {
-    state: AtomicU8,
+    mutex: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl SurfaceLock {
-    const STATE_IDLE: u8 = 0;
-    const STATE_RENDERING: u8 = 1;
-    const STATE_CONFIGURING: u8 = 2;
-
     fn new() -> Self {
         Self {
-            state: AtomicU8::new(Self::STATE_IDLE),
+            mutex: Arc::new(tokio::sync::Mutex::new(())),
         }
     }
 
-    async fn lock_for_render(&self) -> SurfaceLockGuard<'_> {
-        self.lock_with_state(Self::STATE_RENDERING).await
+    async fn lock_for_render(&self) -> SurfaceLockGuard {
+        self.mutex.clone().lock_owned().await
     }
 
-    async fn lock_for_configure(&self) -> SurfaceLockGuard<'_> {
-        self.lock_with_state(Self::STATE_CONFIGURING).await
+    async fn lock_for_configure(&self) -> SurfaceLockGuard {
+        self.mutex.clone().lock_owned().await
     }
+}
 
-    async fn lock_with_state(&self, state: u8) -> SurfaceLockGuard<'_> {
-        loop {
-            if self
-                .state
-                .compare_exchange(Self::STATE_IDLE, state, Ordering::AcqRel, Ordering::Relaxed)
-                .is_ok()
-            {
-                return SurfaceLockGuard { lock: self };
+// owned, so that a frame submitted in the background keeps the surface locked until presented
+type SurfaceLockGuard = tokio::sync::OwnedMutexGuard<()>;
+
+/// Where a frame is composited and shown.
+enum FrameTarget {
+    Gpu {
+        renderer: Arc<core_renderer::CoreRenderer>,
+        surface_texture: wgpu::SurfaceTexture,
+        surface_format: wgpu::TextureFormat,
+    },
+    Software {
+        renderer: Arc<SoftwareRenderer>,
+        surface: Arc<SoftwareSurface>,
+    },
+}
+
+/// The GPU work of a rendered frame. Owns everything it needs, so that it can be submitted
+/// off the render loop in pipelined mode.
+struct FrameSubmission {
+    target: FrameTarget,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    texture_atlas: Arc<TextureAtlas>,
+    stencil_atlas_texture: wgpu::Texture,
+    viewport_size: [f32; 2],
+    render_node: Arc<RenderNode>,
+    load_color: wgpu::Color,
+    acquired_at: Instant,
+    present_timing: Arc<PresentTiming>,
+}
+
+impl FrameSubmission {
+    /// Records and submits the frame graph, or rasterizes and shows the frame in software.
+    fn submit(&self) {
+        let (renderer, surface_texture, surface_format) = match &self.target {
+            FrameTarget::Gpu {
+                renderer,
+                surface_texture,
+                surface_format,
+            } => (renderer, surface_texture, *surface_format),
+            FrameTarget::Software { renderer, surface } => {
+                self.rasterize(renderer, surface);
+                return;
             }
+        };
 
-            std::hint::spin_loop();
-            task::yield_now().await;
+        let _span = profile_span!("frame_graph");
+        let texture_atlas_texture = self.texture_atlas.texture();
+        let surface_view = surface_texture
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut frame_graph = FrameGraph::new();
+        let surface = frame_graph.surface("surface");
+        let layers = frame_graph.transient("layer caches");
+
+        frame_graph.pass("layer caches").writes(layers).run(|ctx| {
+            // a failed layer is drawn as an ordinary subtree, so keep rendering
+            if let Err(e) = renderer.render_layers(
+                ctx.device,
+                ctx.queue,
+                &self.render_node,
+                &self.texture_atlas,
+                &self.stencil_atlas_texture,
+            ) {
+                warn!("WindowUi::render: rendering layers failed: {e:?}");
+            }
+            Ok(())
+        });
+
+        frame_graph
+            .pass("ui")
+            .reads(layers)
+            .writes(surface)
+            .run(|ctx| {
+                renderer.render(
+                    ctx.device,
+                    ctx.queue,
+                    surface_format,
+                    &surface_view,
+                    self.viewport_size,
+                    &self.render_node,
+                    self.load_color,
+                    &texture_atlas_texture,
+                    &self.stencil_atlas_texture,
+                )?;
+                self.present_timing.rendered(renderer.last_frame_stats());
+                Ok(())
+            });
+
+        match frame_graph.execute(&self.device, &self.queue) {
+            Ok(report) => trace!(
+                "WindowUi::render: recorded {} passes in {:?}",
+                report.passes.len(),
+                report.total_cpu_time()
+            ),
+            Err(e) => warn!("WindowUi::render: rendering failed: {e}"),
         }
     }
 
-    fn release(&self) {
-        self.state.store(Self::STATE_IDLE, Ordering::Release);
+    /// Draws the frame on the CPU and shows it right away, since a softbuffer buffer cannot
+    /// outlive the lock on its surface.
+    fn rasterize(&self, renderer: &SoftwareRenderer, surface: &SoftwareSurface) {
+        let _span = profile_span!("rasterize");
+        let [width, height] = self.viewport_size.map(|v| v as u32);
+        let Some(mut pixmap) = renderer::tiny_skia::Pixmap::new(width, height) else {
+            trace!("WindowUi::render: skipping the empty frame");
+            return;
+        };
+        let stats = renderer.render(
+            &self.device,
+            &self.queue,
+            &mut pixmap,
+            &self.render_node,
+            self.load_color,
+        );
+        self.present_timing.rendered(stats);
+        if let Err(e) = surface.present(&pixmap) {
+            warn!("WindowUi::render: presenting the software frame failed: {e}");
+        }
     }
-}
 
-struct SurfaceLockGuard<'a> {
-    lock: &'a SurfaceLock,
-}
-
-impl Drop for SurfaceLockGuard<'_> {
-    fn drop(&mut self) {
-        self.lock.release();
+    fn present(self) {
+        if let FrameTarget::Gpu {
+            surface_texture, ..
+        } = self.target
+        {
+            surface_texture.present();
+        }
+        self.present_timing
+            .presented(self.acquired_at, Instant::now());
     }
 }
 
@@ -123,7 +263,7 @@ impl<Message: 'static, Event: 'static> WindowUiConfig<Message, Event> {
         trace!("WindowUi::new: initializing window UI");
         Ok(Self {
             window: WindowSurfaceConfig::new(),
-            surface_guard: SurfaceLock::new(),
+            surface_guard: Arc::new(SurfaceLock::new()),
             component,
             model_update_detector: tokio::sync::Mutex::new(UpdateFlag::new()),
             widget: tokio::sync::Mutex::new(None),
@@ -135,6 +275,10 @@ impl<Message: 'static, Event: 'static> WindowUiConfig<Message, Event> {
                     .ok_or(WindowUiError::InvalidDuration)?,
             ),
             keyboard_state: tokio::sync::Mutex::new(KeyboardState::new()),
+            shortcuts: ShortcutRegistry::new(),
+            input_recorder: None,
+            click_through: false,
+            resize_strategy: ResizeStrategy::default(),
         })
     }
 
@@ -175,6 +319,54 @@ impl<Message: 'static, Event: 'static> WindowUiConfig<Message, Event> {
         self.window.set_fullscreen(fullscreen);
     }
 
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.window.set_transparent(transparent);
+    }
+
+    pub fn set_decorations(&mut self, decorations: bool) {
+        self.window.set_decorations(decorations);
+    }
+
+    pub fn set_surface_alpha_mode(&mut self, alpha_mode: wgpu::CompositeAlphaMode) {
+        self.window.set_alpha_mode(alpha_mode);
+    }
+
+    pub fn set_hdr(&mut self, hdr: bool) {
+        self.window.set_hdr(hdr);
+    }
+
+    pub fn set_max_frame_latency(&mut self, latency: u32) {
+        self.window.set_max_frame_latency(latency);
+    }
+
+    pub fn set_software(&mut self, software: bool) {
+        self.window.set_software(software);
+    }
+
+    pub fn set_icon(&mut self, icon: Option<WindowIcon>) {
+        self.window.set_icon(icon);
+    }
+
+    pub fn set_effect(&mut self, effect: WindowEffect) {
+        self.window.set_effect(effect);
+    }
+
+    pub fn set_shortcuts(&mut self, shortcuts: ShortcutRegistry<Message>) {
+        self.shortcuts = shortcuts;
+    }
+
+    pub fn set_input_recorder(&mut self, recorder: Option<Arc<InputRecorder>>) {
+        self.input_recorder = recorder;
+    }
+
+    pub fn set_click_through(&mut self, click_through: bool) {
+        self.click_through = click_through;
+    }
+
+    pub fn set_resize_strategy(&mut self, strategy: ResizeStrategy) {
+        self.resize_strategy = strategy;
+    }
+
     pub async fn start_window(
         self,
         winit_event_loop: &winit::event_loop::ActiveEventLoop,
@@ -192,6 +384,10 @@ impl<Message: 'static, Event: 'static> WindowUiConfig<Message, Event> {
             mouse_state_config,
             mouse_state,
             keyboard_state,
+            shortcuts,
+            input_recorder,
+            click_through,
+            resize_strategy,
         } = self;
 
         let start_result = {
@@ -210,6 +406,15 @@ impl<Message: 'static, Event: 'static> WindowUiConfig<Message, Event> {
                 mouse_state_config,
                 mouse_state,
                 keyboard_state,
+                shortcuts,
+                input_recorder,
+                click_through: click_through.then(|| tokio::sync::Mutex::new(ClickThrough::new())),
+                hidden: AtomicBool::new(false),
+                shown: AtomicBool::new(false),
+                focused: AtomicBool::new(true),
+                occluded: AtomicBool::new(false),
+                presented: parking_lot::Mutex::new(None),
+                resize: parking_lot::Mutex::new(ResizeState::new(resize_strategy)),
             }),
             Err(err) => Err((
                 WindowUiConfig {
@@ -222,6 +427,10 @@ impl<Message: 'static, Event: 'static> WindowUiConfig<Message, Event> {
                     mouse_state_config,
                     mouse_state,
                     keyboard_state,
+                    shortcuts,
+                    input_recorder,
+                    click_through,
+                    resize_strategy,
                 },
                 err,
             )),
@@ -267,10 +476,50 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         self.window.read().set_fullscreen(fullscreen);
     }
 
+    pub fn control(&self, control: WindowControl) {
+        self.window.read().control(control);
+    }
+
+    pub fn apply_custom_cursor(
+        &self,
+        cursor: &Cursor,
+        platform_cursor: winit::window::CustomCursor,
+    ) {
+        self.window
+            .read()
+            .apply_custom_cursor(cursor, platform_cursor);
+    }
+
     pub fn window_id(&self) -> winit::window::WindowId {
         self.window.read().window_id()
     }
 
+    /// Hides or shows the window. A hidden window is not rendered until it is shown again.
+    pub fn set_visible(&self, visible: bool) {
+        self.hidden.store(!visible, Ordering::Release);
+        self.window.read().set_visible(visible);
+        if visible {
+            self.shown.store(true, Ordering::Release);
+            self.request_redraw();
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden.load(Ordering::Acquire)
+    }
+
+    /// Whether the window is out of the user's way: unfocused, minimized, covered or hidden.
+    pub fn is_in_background(&self) -> bool {
+        self.is_hidden()
+            || !self.focused.load(Ordering::Acquire)
+            || self.occluded.load(Ordering::Acquire)
+            || self.window.read().window().is_minimized() == Some(true)
+    }
+
+    pub fn winit_window(&self) -> Arc<winit::window::Window> {
+        self.window.read().window().clone()
+    }
+
     pub async fn resize_window(&self, new_size: PhysicalSize<u32>, device: &wgpu::Device) {
         trace!(
             "WindowUi::resize_window: new_size={}x{}",
@@ -278,6 +527,12 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         );
         let _surface_guard = self.surface_guard.lock_for_configure().await;
         self.window.write().set_surface_size(new_size, device);
+        self.resize.lock().resized(Instant::now());
+    }
+
+    pub async fn set_max_frame_latency(&self, latency: u32, device: &wgpu::Device) {
+        let _surface_guard = self.surface_guard.lock_for_configure().await;
+        self.window.write().set_max_frame_latency(latency, device);
     }
 
     pub fn request_redraw(&self) {
@@ -297,9 +552,15 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
 
     /// Returns true if a render should be performed.
     /// Render is required when the model update flag or animation update flag is true,
-    /// or when the widget is not yet initialized.
+    /// or when the widget is not yet initialized. Hidden and fully covered windows are never
+    /// rendered.
     pub async fn needs_render(&self) -> bool {
-        self.model_update_detector.lock().await.is_true()
+        if self.is_hidden() || self.occluded.load(Ordering::Acquire) {
+            return false;
+        }
+        self.shown.swap(false, Ordering::AcqRel)
+            || self.resize.lock().needs_render(Instant::now())
+            || self.model_update_detector.lock().await.is_true()
             || self
                 .widget
                 .lock()
@@ -313,67 +574,220 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         tokio_handle: &tokio::runtime::Handle,
         resource: &GlobalResources,
         base_color: &crate::color::Color,
-        core_renderer: &core_renderer::CoreRenderer,
+        compositor: &Compositor,
         benchmark: &mut utils::benchmark::Benchmark,
     ) {
         trace!("WindowUi::render: begin");
 
-        let _surface_guard = self.surface_guard.lock_for_render().await;
-
-        {
-            // get surface texture, format, viewport size
-            let (surface_texture, surface_format, viewport_size) = {
-                let mut window_guard = self.window.upgradable_read();
-                match self.acquire_surface(&mut window_guard, resource) {
-                    Some(v) => v,
-                    None => return,
-                }
-            };
-
-            let surface_texture_view = surface_texture.texture.create_view(&Default::default());
-
-            // placeholder background
-            // TODO: use black transparent texture as root background
-            let background = Background::new(&surface_texture_view, [0.0, 0.0]);
+        let pipelined = resource.debug_config().pipelined_rendering();
 
+        // In pipelined mode the previous frame may still be submitting and presenting while it
+        // holds the surface lock; update and lay out the widget tree in the meantime.
+        let mut laid_out = None;
+        if pipelined {
             let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
                 trace!("WindowUi::render: widget context not available, skipping render");
                 return;
             };
+            let viewport_size = {
+                let size = self.window.read().inner_size();
+                [size.width as f32, size.height as f32]
+            };
+            let action = self.resize.lock().action(viewport_size, Instant::now());
+            if action == ResizeAction::Relayout {
+                self.ensure_widget_ready(&ctx, benchmark).await;
+                self.layout(viewport_size, &ctx, benchmark).await;
+                laid_out = Some(viewport_size);
+            }
+        }
 
-            // Ensure widget tree is initialized or updated
-            self.ensure_widget_ready(benchmark).await;
+        let surface_guard = self.surface_guard.lock_for_render().await;
 
-            // Layout and render
-            let render_node = self
-                .layout_and_render(viewport_size, background, &ctx, benchmark)
-                .await;
+        // get surface texture, format, viewport size
+        let (target, viewport_size) = match compositor {
+            Compositor::Gpu(renderer) => {
+                let mut window_guard = self.window.upgradable_read();
+                let Some((surface_texture, surface_format, viewport_size)) =
+                    self.acquire_surface(&mut window_guard, resource)
+                else {
+                    return;
+                };
+                let target = FrameTarget::Gpu {
+                    renderer: renderer.clone(),
+                    surface_texture,
+                    surface_format,
+                };
+                (target, viewport_size)
+            }
+            Compositor::Software(renderer) => {
+                let window = self.window.read();
+                let Some(surface) = window.software_surface() else {
+                    trace!("WindowUi::render: no software surface while suspended");
+                    return;
+                };
+                let size = window.inner_size();
+                let target = FrameTarget::Software {
+                    renderer: renderer.clone(),
+                    surface,
+                };
+                (target, [size.width as f32, size.height as f32])
+            }
+        };
+        let acquired_at = Instant::now();
+        let present_timing = {
+            let window = self.window.read();
+            // the window may have moved to a monitor with another refresh rate
+            window
+                .present_timing()
+                .set_refresh_interval(window.refresh_interval());
+            window.present_timing().clone()
+        };
 
-            let render_rst = core_renderer.render(
-                &resource.gpu().device(),
-                &resource.gpu().queue(),
-                surface_format,
-                &surface_texture
-                    .texture
-                    .create_view(&wgpu::TextureViewDescriptor::default()),
-                viewport_size,
-                &render_node,
-                base_color.to_wgpu_color(),
-                &resource.texture_atlas().texture(),
-                &resource.stencil_atlas().texture(),
-            );
-
-            if let Err(e) = render_rst {
-                warn!("WindowUi::render: rendering failed: {e:?}");
+        let background_texture;
+        let background_view = match &target {
+            FrameTarget::Gpu {
+                surface_texture, ..
+            } => surface_texture.texture.create_view(&Default::default()),
+            FrameTarget::Software { .. } => {
+                background_texture = software_background(&resource.gpu().device());
+                background_texture.create_view(&Default::default())
             }
+        };
 
-            // Present surface via blocking task to avoid blocking async runtime
-            tokio::task::spawn_blocking(|| surface_texture.present())
-                .await
-                .expect("present surface task panicked.");
+        // placeholder background
+        // TODO: use black transparent texture as root background
+        let background = Background::new(&background_view, [0.0, 0.0]);
+
+        let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
+            trace!("WindowUi::render: widget context not available, skipping render");
+            return;
+        };
+
+        let now = Instant::now();
+        let action = match laid_out {
+            Some(size) if size == viewport_size => ResizeAction::Relayout,
+            _ => self.resize.lock().action(viewport_size, now),
+        };
+        let presented = self.presented.lock().clone();
+        let render_node = match (action, presented) {
+            // while a resize is deferred, stretch the last laid-out frame over the window
+            (ResizeAction::Scale { from, retry_in }, Some(presented)) => {
+                trace!("WindowUi::render: scaling the {from:?} frame, relayout in {retry_in:?}");
+                self.resize.lock().scaled(now + retry_in);
+                let scale = nalgebra::Matrix4::new_nonuniform_scaling(&nalgebra::Vector3::new(
+                    viewport_size[0] / from[0],
+                    viewport_size[1] / from[1],
+                    1.0,
+                ));
+                Arc::new(RenderNode::new().add_child(presented, scale))
+            }
+            _ => {
+                // Ensure widget tree is initialized or updated, and lay it out unless that was
+                // done ahead for the same size (the window may have been resized in between)
+                if laid_out != Some(viewport_size) {
+                    self.ensure_widget_ready(&ctx, benchmark).await;
+                    self.layout(viewport_size, &ctx, benchmark).await;
+                }
+                self.resize.lock().laid_out(viewport_size, now);
+                let render_node = self.render_widgets(background, &ctx, benchmark).await;
+                self.update_input_region(viewport_size, &ctx).await;
+                *self.presented.lock() = Some(render_node.clone());
+                render_node
+            }
+        };
+
+        // base_color may be translucent; premultiply when the compositor expects it.
+        // It is cleared straight into the surface, so it keeps HDR values on HDR windows.
+        let (alpha_mode, color_space) = {
+            let window = self.window.read();
+            (window.alpha_mode(), window.color_space())
+        };
+        let base_color = color_space.map_color(*base_color);
+        let load_color = match alpha_mode {
+            wgpu::CompositeAlphaMode::PreMultiplied => base_color.to_wgpu_color_premultiplied(),
+            _ => base_color.to_wgpu_color(),
+        };
+
+        let frame = FrameSubmission {
+            target,
+            device: resource.gpu().device(),
+            queue: resource.gpu().queue(),
+            texture_atlas: resource.texture_atlas_handle(),
+            stencil_atlas_texture: resource.stencil_atlas().texture(),
+            viewport_size,
+            render_node,
+            load_color,
+            acquired_at,
+            present_timing,
+        };
+
+        if pipelined {
+            // the frame keeps the surface locked until it is presented, so the next frame
+            // only waits for it right before acquiring the surface
+            tokio_handle.spawn_blocking(move || {
+                let _surface_guard = surface_guard;
+                frame.submit();
+                frame.present();
+            });
+            return;
         }
 
+        // Present surface via blocking task to avoid blocking async runtime
+        let frame = match frame.target {
+            FrameTarget::Gpu { .. } => {
+                frame.submit();
+                tokio::task::spawn_blocking(move || frame.present())
+            }
+            // rasterizing reads textures back from the GPU and blocks
+            FrameTarget::Software { .. } => tokio::task::spawn_blocking(move || {
+                frame.submit();
+                frame.present();
+            }),
+        };
+        profile_future!(frame, "present")
+            .await
+            .expect("present surface task panicked.");
+
         // surface_guard keeps configuration serialized with render duration.
+        drop(surface_guard);
+    }
+
+    /// Renders the subtrees requested for this window with
+    /// [`WidgetContext::capture_subtree`]. GPU readbacks finish on blocking tasks.
+    pub async fn serve_captures(
+        &self,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+        compositor: &Compositor,
+    ) {
+        let requests = resource.captures().take(self.window_id());
+        if requests.is_empty() {
+            return;
+        }
+        debug!("WindowUi::serve_captures: {} requests", requests.len());
+
+        // serializes with a frame still being submitted in pipelined mode
+        let _surface_guard = self.surface_guard.lock_for_render().await;
+        let widget = self.widget.lock().await;
+        let capture_renderer = CaptureRenderer {
+            renderer: match compositor {
+                Compositor::Gpu(renderer) => Some(renderer.as_ref()),
+                Compositor::Software(_) => None,
+            },
+            device: resource.gpu().device(),
+            queue: resource.gpu().queue(),
+            texture_atlas: resource.texture_atlas(),
+            stencil_atlas_texture: resource.stencil_atlas().texture(),
+        };
+
+        for request in requests {
+            let subtree = widget
+                .as_ref()
+                .and_then(|widget| widget.find_rendered(None, &request.target))
+                .ok_or_else(|| CaptureError::WidgetNotFound(request.target.clone()));
+            let pending = capture_renderer.start(subtree, request.output);
+            tokio_handle.spawn_blocking(move || pending.finish());
+        }
     }
 
     // Acquire surface/format/viewport with all recovery paths encapsulated
@@ -414,8 +828,11 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
                         panic!("out of memory");
                     }
                     wgpu::SurfaceError::Other => {
-                        warn!("WindowUi::render: surface returned unknown error");
-                        panic!("unknown error at wgpu surface");
+                        // typically reported while the device is lost; the recovery
+                        // manager reconfigures the surface once a new device exists.
+                        warn!("WindowUi::render: surface returned unknown error, skipping frame");
+                        window_guard.request_redraw();
+                        return None;
                     }
                 }
             }
@@ -427,7 +844,11 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
     }
 
     // Ensure widget tree is built or updated as needed
-    async fn ensure_widget_ready(&self, benchmark: &mut utils::benchmark::Benchmark) {
+    async fn ensure_widget_ready(
+        &self,
+        ctx: &crate::context::WidgetContext,
+        benchmark: &mut utils::benchmark::Benchmark,
+    ) {
         let mut widget_lock = self.widget.lock().await;
         let mut model_update_detector_lock = self.model_update_detector.lock().await;
 
@@ -435,7 +856,10 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
             // directly build widget tree from dom
             trace!("WindowUi::render: building widget tree");
             let dom = benchmark
-                .with_async("create_dom", self.component.view())
+                .with_async(
+                    "create_dom",
+                    profile_future!(self.component.view(ctx.localization()), "view"),
+                )
                 .await;
             let widget =
                 widget_lock.insert(benchmark.with("create_widget", || dom.build_widget_tree()));
@@ -451,12 +875,18 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
             // Widget update is required
             trace!("WindowUi::render: updating widget tree");
             let dom = benchmark
-                .with_async("create_dom", self.component.view())
+                .with_async(
+                    "create_dom",
+                    profile_future!(self.component.view(ctx.localization()), "view"),
+                )
                 .await;
 
             if let Some(widget) = widget_lock.as_mut()
                 && benchmark
-                    .with_async("update_widget", widget.update_widget_tree(&*dom))
+                    .with_async(
+                        "update_widget",
+                        profile_future!(widget.update_widget_tree(&*dom), "update_widget_tree"),
+                    )
                     .await
                     .is_err()
             {
@@ -464,6 +894,8 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
             }
 
             let widget = widget_lock.get_or_insert_with(|| dom.build_widget_tree());
+            // keyed subtrees that did not move to another parent
+            crate::ui::global_key::release_detached().await;
 
             // set model update notifier
             *model_update_detector_lock = UpdateFlag::new();
@@ -475,14 +907,13 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         }
     }
 
-    // Layout pass and render node creation
-    async fn layout_and_render<'a>(
-        &'a self,
+    // Layout pass
+    async fn layout(
+        &self,
         viewport_size: [f32; 2],
-        background: Background<'a>,
         ctx: &crate::context::WidgetContext,
         benchmark: &mut utils::benchmark::Benchmark,
-    ) -> Arc<RenderNode> {
+    ) {
         let mut widget_lock = self.widget.lock().await;
 
         let widget = widget_lock.as_mut().expect("widget initialized above");
@@ -497,9 +928,70 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         ];
 
         benchmark.with("layout_arrange", || widget.arrange(final_size, ctx));
+        // mount and visibility hooks run before the prepare tasks they may influence
+        benchmark.with("widget_lifecycle", || {
+            widget.update_lifecycle(Some([[0.0, 0.0], final_size]), ctx)
+        });
+        benchmark.with("widget_prepare", || {
+            widget.prepare(Some([[0.0, 0.0], final_size]), ctx)
+        });
+    }
+
+    // Render node creation, after `layout`
+    async fn render_widgets<'a>(
+        &'a self,
+        background: Background<'a>,
+        ctx: &crate::context::WidgetContext,
+        benchmark: &mut utils::benchmark::Benchmark,
+    ) -> Arc<RenderNode> {
+        let mut widget_lock = self.widget.lock().await;
+
+        let widget = widget_lock.as_mut().expect("widget initialized above");
+
         benchmark.with("widget_render", || widget.render(background, ctx))
     }
 
+    // the hit mask of a click-through window follows the layout
+    async fn update_input_region(&self, viewport_size: [f32; 2], ctx: &WidgetContext) {
+        let Some(click_through) = &self.click_through else {
+            return;
+        };
+        if let Some(widget) = self.widget.lock().await.as_deref() {
+            click_through
+                .lock()
+                .await
+                .update_region(widget, viewport_size, ctx);
+        }
+    }
+
+    // click-through windows catch the pointer only over their input region
+    async fn update_hittest(&self, event: &DeviceInput) {
+        let Some(click_through) = &self.click_through else {
+            return;
+        };
+        let hittest = match event.raw_event() {
+            DeviceInputData::MouseInput { .. } => click_through
+                .lock()
+                .await
+                .pointer_moved(event.mouse_view_port_position()),
+            DeviceInputData::WindowFocus(true) => click_through.lock().await.restore(),
+            _ => None,
+        };
+        if let Some(hittest) = hittest {
+            self.window.read().set_cursor_hittest(hittest);
+        }
+    }
+
+    /// Lets a click-through window catch the pointer again, to learn where it moved.
+    pub(crate) async fn restore_hittest(&self) {
+        let Some(click_through) = &self.click_through else {
+            return;
+        };
+        if let Some(hittest) = click_through.lock().await.restore() {
+            self.window.read().set_cursor_hittest(hittest);
+        }
+    }
+
     async fn convert_winit_to_window_event(
         &self,
         window_event: winit::event::WindowEvent,
@@ -563,7 +1055,7 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
                     .modifiers_changed(modifiers.state());
                 None
             }
-            winit::event::WindowEvent::Ime(_) => Some(DeviceInputData::Ime),
+            winit::event::WindowEvent::Ime(ime) => Some(DeviceInputData::Ime(ime.clone())),
 
             // mouse events
             winit::event::WindowEvent::CursorMoved { position, .. } => {
@@ -611,6 +1103,23 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         resource: &GlobalResources,
     ) -> Option<Event> {
         trace!("WindowUi::window_event: received {window_event:?}");
+        if let Some(recorder) = &self.input_recorder {
+            recorder.record_window_event(&window_event);
+        }
+        match window_event {
+            winit::event::WindowEvent::Focused(focused) => {
+                self.focused.store(focused, Ordering::Release);
+            }
+            winit::event::WindowEvent::Occluded(occluded) => {
+                // what was drawn while covered was skipped, so draw it when uncovered
+                let was_occluded = self.occluded.swap(occluded, Ordering::AcqRel);
+                if was_occluded && !occluded {
+                    self.shown.store(true, Ordering::Release);
+                }
+            }
+            _ => {}
+        }
+
         let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
             trace!("WindowUi::window_event: widget context not available, skipping event");
             return None;
@@ -633,22 +1142,127 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
             }
         };
 
-        let event = self
+        let Some(event) = self
             .convert_winit_to_window_event(window_event, get_window_size, get_window_position)
-            .await;
+            .await
+        else {
+            trace!("WindowUi::window_event: no device input");
+            return None;
+        };
+        self.dispatch(event, &ctx, tokio_handle, resource).await
+    }
+
+    /// Passes input replayed from a recording to the widgets like input from the window, with
+    /// the pointer at `pointer_position`. See [`input_recording`](crate::input_recording).
+    pub async fn replayed_input(
+        &self,
+        device_input_data: DeviceInputData,
+        pointer_position: [f32; 2],
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) -> Option<Event> {
+        let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
+            trace!("WindowUi::replayed_input: widget context not available, skipping event");
+            return None;
+        };
+        let event = DeviceInput::new(pointer_position, device_input_data, None);
+        self.dispatch(event, &ctx, tokio_handle, resource).await
+    }
 
-        if let (Some(widget), Some(event)) = (self.widget.lock().await.as_mut(), event) {
-            let result = widget.device_input(&event, &ctx);
+    /// Recognizes clicks in replayed input with the current mouse settings of the window.
+    pub async fn input_replayer(&self) -> Option<InputReplayer> {
+        let mouse_state = self.mouse_state.lock().await;
+        let config = MouseStateConfig {
+            primary_button: mouse_state.primary_button(),
+            pixel_per_line: mouse_state.scroll_pixel_per_line(),
+            ..self.mouse_state_config
+        };
+        config.init().map(InputReplayer::new)
+    }
+
+    async fn dispatch(
+        &self,
+        event: DeviceInput,
+        ctx: &WidgetContext,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) -> Option<Event> {
+        self.update_hittest(&event).await;
+
+        // Escape releases a locked pointer instead of reaching the app
+        let event = if is_escape_press(&event) && self.window.read().unlock_pointer() {
+            trace!("WindowUi::dispatch: Escape released the locked pointer");
+            DeviceInput::new(
+                event.mouse_view_port_position(),
+                DeviceInputData::PointerUnlocked,
+                None,
+            )
+        } else {
+            event
+        };
+
+        // app shortcuts take the key press before the widgets see it
+        if let DeviceInputData::Keyboard(key_input) = event.event()
+            && let Some(message) = self.shortcuts.dispatch(key_input)
+        {
+            trace!("WindowUi::dispatch: key press matched an app shortcut");
+            self.user_event(&message, tokio_handle, resource);
+            return None;
+        }
+
+        if let Some(widget) = self.widget.lock().await.as_mut() {
+            // Tab moves keyboard focus unless the focused widget takes it
+            if let DeviceInputData::Keyboard(key_input) = event.event()
+                && focus::tab_navigation(&mut **widget, key_input, ctx)
+            {
+                return None;
+            }
+            let result = widget.device_input(&event, ctx);
             if result.is_some() {
-                trace!("WindowUi::window_event: widget produced event");
+                trace!("WindowUi::dispatch: widget produced event");
             }
             result
         } else {
-            trace!("WindowUi::window_event: no widget or no device input");
+            trace!("WindowUi::dispatch: no widget");
             None
         }
     }
 
+    /// Passes input that does not come from a window event, e.g. relative pointer motion,
+    /// to the widgets.
+    pub async fn device_input(
+        &self,
+        device_input_data: DeviceInputData,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) -> Option<Event> {
+        let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
+            trace!("WindowUi::device_input: widget context not available, skipping event");
+            return None;
+        };
+        if let (Some(recorder), DeviceInputData::PointerMotion { delta }) =
+            (&self.input_recorder, &device_input_data)
+        {
+            recorder.record(RecordedInput::PointerMotion { delta: *delta });
+        }
+        let mouse_position = self.mouse_state.lock().await.position();
+        let device_input = DeviceInput::new(mouse_position, device_input_data, None);
+        self.widget
+            .lock()
+            .await
+            .as_mut()?
+            .device_input(&device_input, &ctx)
+    }
+
+    /// Releases a locked pointer. Returns `false` if it was not locked.
+    pub fn unlock_pointer(&self) -> bool {
+        self.window.read().unlock_pointer()
+    }
+
+    pub fn is_pointer_locked(&self) -> bool {
+        self.window.read().is_pointer_locked()
+    }
+
     pub async fn poll_mouse_state(
         &self,
         tokio_handle: &tokio::runtime::Handle,
@@ -694,6 +1308,23 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
         produced_events
     }
 
+    /// The events the widgets produced for the app outside of input handling, see
+    /// [`AnyWidgetFrame::take_deferred_events`].
+    pub async fn take_deferred_events(
+        &self,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) -> Vec<Event> {
+        let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
+            trace!("WindowUi::take_deferred_events: widget context not available");
+            return Vec::new();
+        };
+        match self.widget.lock().await.as_mut() {
+            Some(widget) => widget.take_deferred_events(&ctx),
+            None => Vec::new(),
+        }
+    }
+
     pub fn user_event(
         &self,
         user_event: &Message,
@@ -708,6 +1339,94 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
 
         self.component.update(user_event, &app_ctx);
     }
+
+    /// The widgets under `position` (window coordinates, as in pointer events) from the root to
+    /// the innermost one, using the layout of the last frame.
+    pub async fn hit_test(
+        &self,
+        position: [f32; 2],
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) -> HitTestPath {
+        let Some(ctx) = resource.widget_context(tokio_handle, &self.window) else {
+            trace!("WindowUi::hit_test: widget context not available");
+            return HitTestPath::default();
+        };
+        match self.widget.lock().await.as_deref() {
+            Some(widget) => hit_test(widget, position, &ctx),
+            None => HitTestPath::default(),
+        }
+    }
+}
+
+impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
+    /// Rebuilds the view on the next frame and lays out and draws the whole tree again, since
+    /// text changes size after the locale or the fonts changed.
+    pub(crate) async fn rebuild_view(&self) {
+        trace!("WindowUi::rebuild_view: scheduling view rebuild");
+        if let Some(widget) = self.widget.lock().await.as_mut() {
+            widget.invalidate_render_cache();
+        }
+        // a model update rebuilds the dom and marks every widget dirty
+        self.model_update_detector.lock().await.set_true();
+    }
+
+    /// Drops the surface and tells the component that the app was suspended.
+    pub(crate) async fn suspend(
+        &self,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) {
+        trace!("WindowUi::suspend: dropping surface");
+        {
+            // wait for a frame in flight to finish
+            let _surface_guard = self.surface_guard.lock_for_configure().await;
+            self.window.write().drop_surface();
+        }
+
+        if let Some(app_ctx) = resource.application_context(tokio_handle, &self.window) {
+            self.component
+                .lifecycle(LifecycleEvent::Suspended, &app_ctx);
+        }
+    }
+
+    /// Recreates the surface, schedules a redraw and tells the component that the app was
+    /// resumed.
+    pub(crate) async fn resume(
+        &self,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) {
+        trace!("WindowUi::resume: recreating surface");
+        {
+            let _surface_guard = self.surface_guard.lock_for_configure().await;
+            if let Err(e) = self.window.write().recreate_surface(resource.gpu()) {
+                warn!("WindowUi::resume: failed to recreate surface: {e}");
+            }
+        }
+        self.shown.store(true, Ordering::Release);
+
+        if let Some(app_ctx) = resource.application_context(tokio_handle, &self.window) {
+            self.component.lifecycle(LifecycleEvent::Resumed, &app_ctx);
+        }
+    }
+
+    /// Tells the component that the app moved to the background or came back to the
+    /// foreground. Coming back redraws the window with what changed in the meantime.
+    pub(crate) async fn notify_lifecycle(
+        &self,
+        event: LifecycleEvent,
+        tokio_handle: &tokio::runtime::Handle,
+        resource: &GlobalResources,
+    ) {
+        trace!("WindowUi::notify_lifecycle: {event:?}");
+        if event == LifecycleEvent::Foreground {
+            self.shown.store(true, Ordering::Release);
+        }
+        if let Some(app_ctx) = resource.application_context(tokio_handle, &self.window) {
+            self.component.lifecycle(event, &app_ctx);
+        }
+    }
 }
 
 impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
@@ -719,4 +1438,111 @@ impl<Message: 'static, Event: 'static> WindowUi<Message, Event> {
             widget.invalidate_render_cache();
         }
     }
+
+    /// only call this in gpu device recover callback
+    pub(crate) fn update_gpu_device(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        trace!("WindowUi::update_gpu_device: rebinding window to the recovered device");
+        {
+            let mut window = self.window.write();
+            window.reconfigure_surface(device);
+            window.request_redraw();
+        }
+
+        let mut widget_lock = self.widget.blocking_lock();
+        if let Some(widget) = widget_lock.as_mut() {
+            widget.update_gpu_device(device, queue);
+        }
+    }
+}
+
+/// Widgets render against a background, which the CPU compositor has no texture for.
+fn software_background(device: &wgpu::Device) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Software Background"),
+        size: wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+fn is_escape_press(event: &DeviceInput) -> bool {
+    matches!(
+        event.event(),
+        DeviceInputData::Keyboard(key_input)
+            if matches!(key_input.state(), ElementState::Pressed(_))
+                && *key_input.logical_key() == Key::Named(NamedKey::Escape)
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{
+        future::Future,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use super::SurfaceLock;
+
+    #[tokio::test]
+    async fn next_frame_waits_for_the_present_without_polling() {
+        let lock = Arc::new(SurfaceLock::new());
+        let polls = Arc::new(AtomicUsize::new(0));
+
+        // the previous frame keeps the surface locked until it is presented
+        let presenting = lock.lock_for_render().await;
+
+        let next_frame = tokio::spawn({
+            let lock = lock.clone();
+            let polls = polls.clone();
+            async move {
+                let mut locking = Box::pin(lock.lock_for_render());
+                std::future::poll_fn(|cx| {
+                    polls.fetch_add(1, Ordering::Relaxed);
+                    locking.as_mut().poll(cx)
+                })
+                .await
+            }
+        });
+
+        // simulated present
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+        }
+        assert!(!next_frame.is_finished());
+        assert_eq!(polls.load(Ordering::Relaxed), 1);
+
+        drop(presenting);
+        let _guard = next_frame.await.unwrap();
+        assert_eq!(polls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn configuring_waits_for_the_frame() {
+        let lock = Arc::new(SurfaceLock::new());
+        let frame = lock.lock_for_render().await;
+
+        let configure = tokio::spawn({
+            let lock = lock.clone();
+            async move {
+                let _guard = lock.lock_for_configure().await;
+            }
+        });
+        tokio::task::yield_now().await;
+        assert!(!configure.is_finished());
+
+        drop(frame);
+        configure.await.unwrap();
+    }
 }
@@ -4,6 +4,8 @@ use crate::{
     application_instance::ApplicationInstance,
     backend::Backend,
     context::ApplicationCommand,
+    menu::native::NativeMenu,
+    tray::{Tray, native::NativeTray},
     window_surface::{self},
     window_ui::WindowUiError,
 };
@@ -23,6 +25,12 @@ pub struct WinitInstance<
 > {
     application_instance: Arc<ApplicationInstance<Message, Event, B>>,
     render_loop_exit_signal: Option<tokio::sync::oneshot::Sender<()>>,
+    native_menu: NativeMenu<Message>,
+    // the tray icon is created once the event loop is running
+    tray: Option<Tray<Message>>,
+    native_tray: Option<NativeTray<Message>>,
+    // click-through windows need pointer motion over other windows, see `input_region`
+    click_through: bool,
 }
 
 // MARK: render
@@ -47,6 +55,40 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
                     );
                     self.application_instance.close_window(id);
                 }
+                ApplicationCommand::SetWindowVisible { id, visible } => {
+                    self.application_instance.set_window_visible(id, visible);
+                }
+                ApplicationCommand::ShowAllWindows => {
+                    self.application_instance.show_all_windows();
+                }
+                ApplicationCommand::ControlWindow { id, control } => {
+                    self.application_instance.control_window(id, control);
+                }
+                ApplicationCommand::SetMaxFrameLatency { id, latency } => {
+                    self.application_instance.set_max_frame_latency(id, latency);
+                }
+                ApplicationCommand::LocaleChanged | ApplicationCommand::FontsChanged => {
+                    self.application_instance.rebuild_all_windows();
+                }
+                ApplicationCommand::HitTest {
+                    id,
+                    position,
+                    sender,
+                } => {
+                    let _ = sender.send(self.application_instance.hit_test(id, position));
+                }
+                ApplicationCommand::FlushDeferredEvents { id } => {
+                    self.application_instance.flush_deferred_events(id);
+                }
+                ApplicationCommand::CreateCursor { id, cursor } => {
+                    if let Some(platform_cursor) = cursor.create_platform_cursor(event_loop) {
+                        self.application_instance.apply_custom_cursor(
+                            id,
+                            &cursor.into(),
+                            platform_cursor,
+                        );
+                    }
+                }
             }
         }
     }
@@ -63,11 +105,29 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
     // MARK: resumed
 
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        // coming back from `suspended`: windows and setups already exist
+        if self.application_instance.is_suspended() {
+            self.application_instance.resume();
+            return;
+        }
+
         // start window
         self.application_instance.start_all_windows(event_loop);
+        if self.click_through {
+            event_loop.listen_device_events(winit::event_loop::DeviceEvents::Always);
+        }
+
+        for window in self.application_instance.winit_windows() {
+            self.native_menu.attach(&window);
+        }
+
+        if let Some(tray) = self.tray.take() {
+            self.native_tray = Some(NativeTray::new(tray, &mut self.native_menu));
+        }
 
         // call setup function
         self.application_instance.call_all_setups();
+        self.application_instance.start_input_replay();
 
         // start rendering loop
         if let Some(render_loop_exit_signal) = self.application_instance.start_rendering_loop() {
@@ -97,6 +157,21 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
 
         self.application_instance.poll_mouse_state();
 
+        // activated native menu items (including the tray menu)
+        while let Some(message) = self.native_menu.poll() {
+            self.application_instance.user_event(message);
+        }
+
+        // tray icon clicks
+        if let Some(tray) = self.native_tray.as_ref().and_then(|tray| tray.poll()) {
+            if tray.shows_windows_on_click() {
+                self.application_instance.show_all_windows();
+            }
+            if let Some(message) = tray.click() {
+                self.application_instance.user_event(message);
+            }
+        }
+
         // handle winit instance commands
         self.handle_commands(event_loop);
     }
@@ -116,7 +191,14 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         event: winit::event::DeviceEvent,
     ) {
         log::trace!("WinitInstance::device_event: device_id={device_id:?} event={event:?}",);
-        let _ = (event_loop, device_id, event);
+        if let winit::event::DeviceEvent::MouseMotion { delta } = event {
+            if self.click_through {
+                self.application_instance.pointer_motion();
+            }
+            self.application_instance
+                .pointer_delta([delta.0 as f32, delta.1 as f32]);
+        }
+        let _ = (event_loop, device_id);
     }
 
     fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
@@ -127,6 +209,7 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
     fn suspended(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         log::trace!("WinitInstance::suspended");
         let _ = event_loop;
+        self.application_instance.suspend();
     }
 
     fn exiting(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
@@ -144,10 +227,21 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
 pub enum InitError {
     #[error("Failed to initialize tokio runtime")]
     TokioRuntime,
-    #[error("Failed to initialize GPU")]
-    Gpu,
+    #[error("Failed to initialize GPU: {0}")]
+    Gpu(#[from] gpu_utils::gpu::GpuError),
+    /// [`RenderBackend::Software`](crate::render_backend::RenderBackend::Software) found no
+    /// wgpu adapter with push constants to draw widget content with.
+    #[error(
+        "The software backend still needs a wgpu adapter with push constants for widget content, \
+         such as Mesa's llvmpipe (Vulkan or GL), and none was found: {0}"
+    )]
+    SoftwareAdapter(#[source] gpu_utils::gpu::GpuError),
+    #[error("GPU adapter '{0}' cannot read storage buffers in vertex shaders")]
+    UnsupportedAdapter(String),
     #[error(transparent)]
     WindowUi(#[from] WindowUiError),
     #[error(transparent)]
     WindowSurface(#[from] window_surface::WindowSurfaceError),
+    #[error("Failed to start input recording: {0}")]
+    InputRecording(#[from] crate::input_recording::RecordingError),
 }
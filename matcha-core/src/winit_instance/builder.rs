@@ -1,14 +1,30 @@
-use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+use std::{num::NonZeroUsize, path::PathBuf, sync::Arc, time::Duration};
 
-use log::{debug, trace};
+use log::{debug, trace, warn};
 
-use crate::{debug_config::DebugConfig, ui::component::AnyComponent, window_ui::WindowUiConfig};
+use crate::{
+    debug_config::DebugConfig,
+    fonts::FontRegistry,
+    input_recording::{InputRecorder, InputRecording, ReplaySpeed},
+    localization::{Localization, Localizer},
+    menu::{MenuBar, native::NativeMenu},
+    power_saving::PowerSaving,
+    present_timing::DEFAULT_MAX_FRAME_LATENCY,
+    resize_strategy::ResizeStrategy,
+    shortcut::ShortcutRegistry,
+    tray::Tray,
+    ui::component::AnyComponent,
+    window_effect::WindowEffect,
+    window_icon::WindowIcon,
+    window_ui::WindowUiConfig,
+};
 use winit::dpi::PhysicalSize;
 
 use crate::{
     backend::Backend,
     color::Color,
     device_input::mouse_state::MousePrimaryButton,
+    render_backend::{AdapterSelection, Compositor, RenderBackend},
     winit_instance::{InitError, WinitInstance},
 };
 
@@ -20,6 +36,7 @@ const BASE_COLOR: Color = Color::TRANSPARENT;
 const PREFERRED_SURFACE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
 const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
 const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+const SURFACE_ALPHA_MODE: wgpu::CompositeAlphaMode = wgpu::CompositeAlphaMode::Auto;
 
 // input
 const DOUBLE_CLICK_THRESHOLD: Duration = Duration::from_millis(300);
@@ -43,17 +60,42 @@ pub struct WinitInstanceBuilder<
     pub(crate) init_size: PhysicalSize<u32>,
     pub(crate) maximized: bool,
     pub(crate) full_screen: bool,
+    pub(crate) transparent: bool,
+    pub(crate) click_through: bool,
+    pub(crate) decorations: bool,
+    pub(crate) icon: Option<WindowIcon>,
+    pub(crate) window_effect: WindowEffect,
+    pub(crate) resize_strategy: ResizeStrategy,
     // render settings
+    pub(crate) render_backend: RenderBackend,
     pub(crate) power_preference: wgpu::PowerPreference,
+    pub(crate) adapter_selection: AdapterSelection,
     pub(crate) base_color: Color,
     pub(crate) surface_preferred_format: wgpu::TextureFormat,
+    pub(crate) surface_alpha_mode: wgpu::CompositeAlphaMode,
+    pub(crate) hdr_output: bool,
+    pub(crate) max_frame_latency: u32,
+    pub(crate) occlusion_culling: bool,
     // input settings
     pub(crate) double_click_threshold: Duration,
     pub(crate) long_press_threshold: Duration,
     pub(crate) mouse_primary_button: MousePrimaryButton,
     pub(crate) scroll_pixel_per_line: f32,
+    pub(crate) shortcuts: ShortcutRegistry<Message>,
+    pub(crate) menu_bar: MenuBar<Message>,
+    pub(crate) record_input: Option<PathBuf>,
+    pub(crate) replay_input: Option<(InputRecording, ReplaySpeed)>,
+    // background settings
+    pub(crate) tray: Option<Tray<Message>>,
+    pub(crate) run_in_background: bool,
+    pub(crate) power_saving: PowerSaving,
     // font settings
     pub(crate) default_font_size: f32,
+    pub(crate) fonts: FontRegistry,
+    // translated strings
+    pub(crate) localization: Localization,
+    // memory limit of widget caches in bytes
+    pub(crate) cache_budget: usize,
     // debug / profiling config
     pub(crate) debug_config: DebugConfig,
 }
@@ -108,14 +150,36 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
             init_size: PhysicalSize::new(800, 600),
             maximized: false,
             full_screen: false,
+            transparent: false,
+            click_through: false,
+            resize_strategy: ResizeStrategy::Immediate,
+            decorations: true,
+            icon: None,
+            window_effect: WindowEffect::None,
+            render_backend: RenderBackend::default(),
             power_preference: POWER_PREFERENCE,
+            adapter_selection: AdapterSelection::Automatic,
             base_color: BASE_COLOR,
             surface_preferred_format: PREFERRED_SURFACE_FORMAT,
+            surface_alpha_mode: SURFACE_ALPHA_MODE,
+            hdr_output: false,
+            max_frame_latency: DEFAULT_MAX_FRAME_LATENCY,
+            occlusion_culling: false,
             double_click_threshold: DOUBLE_CLICK_THRESHOLD,
             long_press_threshold: LONG_PRESS_THRESHOLD,
             mouse_primary_button: MOUSE_PRIMARY_BUTTON,
             scroll_pixel_per_line: SCROLL_PIXEL_PER_LINE,
+            shortcuts: ShortcutRegistry::new(),
+            menu_bar: MenuBar::new(),
+            record_input: None,
+            replay_input: None,
+            tray: None,
+            run_in_background: false,
+            power_saving: PowerSaving::default(),
             default_font_size: DEFAULT_FONT_SIZE,
+            fonts: FontRegistry::new(),
+            localization: Localization::default(),
+            cache_budget: crate::cache_budget::DEFAULT_CACHE_BUDGET,
             debug_config: DebugConfig::default(),
         }
     }
@@ -154,11 +218,62 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         self
     }
 
+    /// Create the window with a transparent background.
+    ///
+    /// Combine with a translucent `base_color` and a supporting `surface_alpha_mode`
+    /// (usually `PreMultiplied`) to let the desktop show through the window.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Whether the OS draws a title bar and border. Without them the app provides its own,
+    /// see [`crate::window_control`].
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Icon of the window, see [`crate::window_icon`].
+    pub fn window_icon(mut self, icon: WindowIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Material the platform draws behind the window, see [`crate::window_effect`].
+    /// Makes the window transparent.
+    pub fn window_effect(mut self, effect: WindowEffect) -> Self {
+        self.window_effect = effect;
+        self
+    }
+
+    /// Only take pointer input where the window's widgets are, see [`crate::input_region`].
+    pub fn click_through(mut self, click_through: bool) -> Self {
+        self.click_through = click_through;
+        self
+    }
+
+    /// How the window follows continuous resizing, see [`crate::resize_strategy`].
+    pub fn resize_strategy(mut self, strategy: ResizeStrategy) -> Self {
+        self.resize_strategy = strategy;
+        self
+    }
+
+    pub fn render_backend(mut self, render_backend: RenderBackend) -> Self {
+        self.render_backend = render_backend;
+        self
+    }
+
     pub fn power_preference(mut self, preference: wgpu::PowerPreference) -> Self {
         self.power_preference = preference;
         self
     }
 
+    pub fn adapter(mut self, selection: AdapterSelection) -> Self {
+        self.adapter_selection = selection;
+        self
+    }
+
     pub fn base_color(mut self, color: Color) -> Self {
         self.base_color = color;
         self
@@ -169,6 +284,35 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         self
     }
 
+    /// Composite alpha mode of the window surface. Unsupported modes fall back to `Auto`.
+    pub fn surface_alpha_mode(mut self, alpha_mode: wgpu::CompositeAlphaMode) -> Self {
+        self.surface_alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Render to an HDR (scRGB) surface when the display supports it.
+    ///
+    /// Colors brighter than `1.0` are tone-mapped on displays without HDR support.
+    pub fn hdr_output(mut self, hdr: bool) -> Self {
+        self.hdr_output = hdr;
+        self
+    }
+
+    /// Frames the surface queues ahead of the display, at least 1. See
+    /// [`present_timing`](crate::present_timing).
+    pub fn max_frame_latency(mut self, latency: u32) -> Self {
+        self.max_frame_latency = latency;
+        self
+    }
+
+    /// Skip drawing widgets that opaque widgets in front of them cover completely.
+    ///
+    /// See [`renderer::CoreRenderer::with_occlusion_culling`].
+    pub fn occlusion_culling(mut self, enabled: bool) -> Self {
+        self.occlusion_culling = enabled;
+        self
+    }
+
     pub fn double_click_threshold(mut self, duration: Duration) -> Self {
         self.double_click_threshold = duration;
         self
@@ -189,11 +333,71 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         self
     }
 
+    pub fn shortcuts(mut self, shortcuts: ShortcutRegistry<Message>) -> Self {
+        self.shortcuts = shortcuts;
+        self
+    }
+
+    pub fn menu_bar(mut self, menu_bar: MenuBar<Message>) -> Self {
+        self.menu_bar = menu_bar;
+        self
+    }
+
+    pub fn record_input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record_input = Some(path.into());
+        self
+    }
+
+    pub fn replay_input(mut self, recording: InputRecording, speed: ReplaySpeed) -> Self {
+        self.replay_input = Some((recording, speed));
+        self
+    }
+
+    pub fn tray(mut self, tray: Tray<Message>) -> Self {
+        self.tray = Some(tray);
+        self
+    }
+
+    pub fn run_in_background(mut self, run_in_background: bool) -> Self {
+        self.run_in_background = run_in_background;
+        self
+    }
+
+    pub fn power_saving(mut self, power_saving: PowerSaving) -> Self {
+        self.power_saving = power_saving;
+        self
+    }
+
     pub fn default_font_size(mut self, size: f32) -> Self {
         self.default_font_size = size;
         self
     }
 
+    pub fn font_bytes(self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.fonts.register_bytes(bytes);
+        self
+    }
+
+    pub fn font_dir(self, path: impl Into<PathBuf>) -> Self {
+        self.fonts.register_dir(path);
+        self
+    }
+
+    pub fn localizer(self, localizer: impl Localizer) -> Self {
+        self.localization.set_localizer(localizer);
+        self
+    }
+
+    pub fn locale(self, locale: impl Into<String>) -> Self {
+        self.localization.set_locale(locale);
+        self
+    }
+
+    pub fn cache_budget(mut self, budget_bytes: usize) -> Self {
+        self.cache_budget = budget_bytes;
+        self
+    }
+
     /// Provide a DebugConfig instance to the builder.
     pub fn debug_config(mut self, cfg: DebugConfig) -> Self {
         self.debug_config = cfg;
@@ -224,6 +428,18 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         self
     }
 
+    /// Convenience: toggle pipelined rendering.
+    pub fn pipelined_rendering(self, v: bool) -> Self {
+        self.debug_config.set_pipelined_rendering(v);
+        self
+    }
+
+    /// Convenience: toggle the layout overflow indicators.
+    pub fn show_layout_overflow(self, v: bool) -> Self {
+        self.debug_config.set_show_layout_overflow(v);
+        self
+    }
+
     // --- Build ---
 
     pub fn build(self) -> Result<WinitInstance<Message, Event, B>, InitError> {
@@ -238,19 +454,44 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         // 2) Initialize GPU
         let gpu = tokio_runtime
             .block_on(gpu_utils::gpu::Gpu::new(gpu_utils::gpu::GpuDescriptor {
-                backends: wgpu::Backends::PRIMARY,
+                backends: self.render_backend.wgpu_backends(),
                 power_preference: self.power_preference,
-                required_features: wgpu::Features::VERTEX_WRITABLE_STORAGE
-                    | wgpu::Features::PUSH_CONSTANTS,
+                adapter_selection: self.adapter_selection,
+                required_features: self.render_backend.required_features(),
+                // compressed image formats, see `matcha_widgets::style::image`
+                optional_features: wgpu::Features::TEXTURE_COMPRESSION_BC
+                    | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+                    | wgpu::Features::TEXTURE_COMPRESSION_ASTC,
                 required_limits: None,
                 preferred_surface_format: self.surface_preferred_format,
                 auto_recover_enabled: false,
             }))
-            .map_err(|_| InitError::Gpu)?;
-        debug!("WinitInstanceBuilder::build: GPU initialized successfully");
+            .map_err(|e| {
+                warn!(
+                    "WinitInstanceBuilder::build: failed to initialize {:?} GPU backend: {e}",
+                    self.render_backend
+                );
+                match e {
+                    gpu_utils::gpu::GpuError::NoAdapter { .. }
+                    | gpu_utils::gpu::GpuError::AdapterFeatureUnsupported { .. }
+                        if self.render_backend.is_software() =>
+                    {
+                        InitError::SoftwareAdapter(e)
+                    }
+                    e => InitError::Gpu(e),
+                }
+            })?;
+        debug!(
+            "WinitInstanceBuilder::build: GPU initialized successfully with {:?} backend",
+            self.render_backend
+        );
 
         // 3) Global resources
-        let resource = crate::context::GlobalResources::new(gpu);
+        let resource = crate::context::GlobalResources::new(gpu)
+            .with_localization(self.localization)
+            .with_fonts(self.fonts)
+            .with_cache_budget(self.cache_budget)
+            .with_debug_config(self.debug_config);
         trace!("WinitInstanceBuilder::build: global resources created");
 
         // 4) Create Window UI and apply builder settings
@@ -268,14 +509,50 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         window_ui.init_size(self.init_size.width, self.init_size.height);
         window_ui.set_maximized(self.maximized);
         window_ui.set_fullscreen(self.full_screen);
+        window_ui.set_transparent(self.transparent);
+        window_ui.set_click_through(self.click_through);
+        window_ui.set_resize_strategy(self.resize_strategy);
+        window_ui.set_decorations(self.decorations);
+        window_ui.set_icon(self.icon);
+        window_ui.set_effect(self.window_effect);
+        window_ui.set_surface_alpha_mode(self.surface_alpha_mode);
+        window_ui.set_hdr(self.hdr_output);
+        window_ui.set_max_frame_latency(self.max_frame_latency);
+        window_ui.set_software(self.render_backend.is_software());
+        // menu shortcuts work even where the menu bar itself cannot be shown
+        self.menu_bar.register_shortcuts(&self.shortcuts);
+        window_ui.set_shortcuts(self.shortcuts);
+        if let Some(path) = &self.record_input {
+            window_ui.set_input_recorder(Some(Arc::new(InputRecorder::create(path)?)));
+        }
+        let native_menu = NativeMenu::new(&self.menu_bar);
         trace!(
             "WinitInstanceBuilder::build: configured window title='{}' size={}x{}",
             self.title, self.init_size.width, self.init_size.height
         );
 
         // 5) Renderer
-        let renderer = renderer::CoreRenderer::new(&resource.gpu().device());
-        trace!("WinitInstanceBuilder::build: renderer initialized");
+        let compositor = if self.render_backend.is_software() {
+            trace!("WinitInstanceBuilder::build: compositing on the CPU");
+            Compositor::Software(Arc::new(renderer::SoftwareRenderer::new()))
+        } else {
+            if !renderer::CoreRenderer::supports_adapter(resource.gpu().adapter()) {
+                let name = resource.gpu().adapter().get_info().name;
+                warn!(
+                    "WinitInstanceBuilder::build: adapter '{name}' cannot read storage buffers in vertex shaders"
+                );
+                return Err(InitError::UnsupportedAdapter(name));
+            }
+            let culling_mode = renderer::CullingMode::for_adapter(resource.gpu().adapter());
+            let renderer =
+                renderer::CoreRenderer::with_culling_mode(&resource.gpu().device(), culling_mode)
+                    .with_occlusion_culling(self.occlusion_culling);
+            trace!(
+                "WinitInstanceBuilder::build: renderer initialized with {culling_mode:?} culling, occlusion culling {}",
+                self.occlusion_culling
+            );
+            Compositor::Gpu(Arc::new(renderer))
+        };
 
         // 6) Build instance (single-window Vec 管理)
         debug!("WinitInstanceBuilder::build: finalizing instance");
@@ -288,9 +565,14 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
             resource,
             vec![window_ui],
             self.base_color,
-            renderer,
+            compositor,
             backend,
+            self.run_in_background,
+            self.power_saving,
         );
+        if let Some((recording, speed)) = self.replay_input {
+            app_instance.set_input_replay(recording, speed);
+        }
 
         // Prepare a oneshot sender for controlling the render loop lifecycle.
         let (exit_signal_sender, _exit_signal_receiver) = tokio::sync::oneshot::channel::<()>();
@@ -298,6 +580,10 @@ impl<Message: Send + 'static, Event: Send + 'static, B: Backend<Event> + Send +
         Ok(WinitInstance {
             application_instance: app_instance,
             render_loop_exit_signal: Some(exit_signal_sender),
+            native_menu,
+            tray: self.tray,
+            native_tray: None,
+            click_through: self.click_through,
         })
     }
 }
@@ -0,0 +1,286 @@
+//! Background threads for CPU-heavy rendering work.
+//!
+//! Tessellating paths and rasterizing glyph outlines can take long enough to make a frame
+//! miss its deadline. Widgets and styles move such work to the shared [`WorkerPool`] from
+//! their `prepare` step and await the returned [`WorkerTask`]; the result is handed back to
+//! the widget, which uploads it to the GPU when it renders next.
+//!
+//! ```ignore
+//! fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+//!     let task = ctx.worker_pool().spawn(move |_| tessellate(&path, bounds));
+//!     let mesh = self.mesh.clone();
+//!     Some(Box::pin(async move {
+//!         if let Ok(result) = task.await {
+//!             *mesh.lock() = Some(result);
+//!         }
+//!     }))
+//! }
+//! ```
+//!
+//! Dropping a [`WorkerTask`] cancels it: a job that has not started is skipped, and a
+//! running job can stop early by checking [`CancelToken::is_cancelled`]. Since the framework
+//! drops a widget's preparation together with the widget, work for removed widgets does not
+//! keep the pool busy.
+
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use log::{debug, trace, warn};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Upper bound of the default thread count; the rest of the cores are left to the renderer and
+/// the async runtime.
+const MAX_DEFAULT_THREADS: usize = 4;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerError {
+    #[error("the job was cancelled before it finished")]
+    Cancelled,
+    #[error("the job panicked")]
+    Panicked,
+    #[error("the worker pool has shut down")]
+    ShutDown,
+}
+
+/// A fixed set of threads running jobs in submission order.
+pub struct WorkerPool {
+    sender: Mutex<Option<std::sync::mpsc::Sender<Job>>>,
+    threads: usize,
+    // submitted jobs that have not finished yet
+    pending: Arc<AtomicUsize>,
+}
+
+impl Default for WorkerPool {
+    /// One thread per core minus one, at most four.
+    fn default() -> Self {
+        let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+        Self::new(cores.saturating_sub(1).clamp(1, MAX_DEFAULT_THREADS))
+    }
+}
+
+impl WorkerPool {
+    /// Starts a pool with `threads` worker threads (at least one).
+    pub fn new(threads: usize) -> Self {
+        let threads = threads.max(1);
+        debug!("WorkerPool::new: starting {threads} worker thread(s)");
+
+        let (sender, receiver) = std::sync::mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0..threads {
+            let receiver = receiver.clone();
+            let spawned = std::thread::Builder::new()
+                .name(format!("matcha-worker-{index}"))
+                .spawn(move || {
+                    loop {
+                        // hold the lock only while waiting, so other workers can pick up jobs
+                        let job = receiver.lock().recv();
+                        match job {
+                            Ok(job) => job(),
+                            // the pool was dropped
+                            Err(_) => break,
+                        }
+                    }
+                    trace!("WorkerPool: worker {index} stopped");
+                });
+            if let Err(e) = spawned {
+                warn!("WorkerPool::new: failed to spawn worker thread {index}: {e}");
+            }
+        }
+
+        Self {
+            sender: Mutex::new(Some(sender)),
+            threads,
+            pending: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Jobs submitted but not finished, including running ones.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    /// Runs `job` on a worker thread. Await the returned task for its result.
+    ///
+    /// The job receives the task's [`CancelToken`]; long jobs should check it between steps.
+    pub fn spawn<T, F>(&self, job: F) -> WorkerTask<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&CancelToken) -> T + Send + 'static,
+    {
+        let (result_sender, result) = tokio::sync::oneshot::channel();
+        let cancel = CancelToken::new();
+
+        let token = cancel.clone();
+        let pending = self.pending.clone();
+        let wrapped: Job = Box::new(move || {
+            let outcome = if token.is_cancelled() {
+                Err(WorkerError::Cancelled)
+            } else {
+                std::panic::catch_unwind(AssertUnwindSafe(|| job(&token))).map_err(|_| {
+                    warn!("WorkerPool: job panicked");
+                    WorkerError::Panicked
+                })
+            };
+            pending.fetch_sub(1, Ordering::AcqRel);
+            // the task may have been dropped in the meantime
+            let _ = result_sender.send(outcome);
+        });
+
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        let sent = self
+            .sender
+            .lock()
+            .as_ref()
+            .is_some_and(|sender| sender.send(wrapped).is_ok());
+        if !sent {
+            // the job was dropped unsent; the task reports the shut down pool
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+            debug!("WorkerPool::spawn: pool has shut down, job discarded");
+        }
+
+        WorkerTask { result, cancel }
+    }
+
+    /// Stops accepting jobs. Queued jobs still run; workers exit once the queue is empty.
+    pub fn shutdown(&self) {
+        if self.sender.lock().take().is_some() {
+            debug!("WorkerPool::shutdown: no longer accepting jobs");
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Shared flag telling a job that its result is no longer wanted.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// The result of a job on the [`WorkerPool`]. Cancels the job when dropped.
+#[must_use = "dropping a worker task cancels its job"]
+pub struct WorkerTask<T> {
+    result: tokio::sync::oneshot::Receiver<Result<T, WorkerError>>,
+    cancel: CancelToken,
+}
+
+impl<T> WorkerTask<T> {
+    /// Asks the job to stop. Awaiting the task afterwards reports
+    /// [`WorkerError::Cancelled`] unless the job already finished.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    /// The result if the job has finished, without waiting.
+    pub fn try_take(&mut self) -> Option<Result<T, WorkerError>> {
+        match self.result.try_recv() {
+            Ok(outcome) => Some(outcome),
+            Err(tokio::sync::oneshot::error::TryRecvError::Empty) => None,
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed) => {
+                Some(Err(WorkerError::ShutDown))
+            }
+        }
+    }
+}
+
+impl<T> Future for WorkerTask<T> {
+    type Output = Result<T, WorkerError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.result)
+            .poll(cx)
+            .map(|outcome| outcome.unwrap_or(Err(WorkerError::ShutDown)))
+    }
+}
+
+impl<T> Drop for WorkerTask<T> {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn spawned_jobs_return_their_results() {
+        let pool = WorkerPool::new(2);
+        let tasks: Vec<_> = (0..8).map(|i| pool.spawn(move |_| i * i)).collect();
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+        assert_eq!(results, vec![0, 1, 4, 9, 16, 25, 36, 49]);
+        assert_eq!(pool.pending(), 0);
+    }
+
+    #[tokio::test]
+    async fn dropped_tasks_are_skipped() {
+        let pool = WorkerPool::new(1);
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        // occupy the only worker
+        let blocker = pool.spawn(move |_| blocked.recv().unwrap());
+
+        let ran = Arc::new(AtomicBool::new(false));
+        let dropped = {
+            let ran = ran.clone();
+            pool.spawn(move |_| ran.store(true, Ordering::Release))
+        };
+        drop(dropped);
+
+        let cancelled = pool.spawn(|_| ());
+        cancelled.cancel();
+
+        release.send(()).unwrap();
+        blocker.await.unwrap();
+        assert_eq!(cancelled.await, Err(WorkerError::Cancelled));
+        assert!(!ran.load(Ordering::Acquire));
+    }
+
+    #[tokio::test]
+    async fn panics_and_shutdown_are_reported() {
+        let pool = WorkerPool::new(1);
+        let task = pool.spawn(|_| -> u32 { panic!("job failed") });
+        assert_eq!(task.await, Err(WorkerError::Panicked));
+        // the worker survives the panic
+        assert_eq!(pool.spawn(|_| 1).await, Ok(1));
+
+        pool.shutdown();
+        assert_eq!(pool.spawn(|_| 1).await, Err(WorkerError::ShutDown));
+    }
+}
@@ -0,0 +1,315 @@
+//! Procedural macros for matcha.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, quote};
+use syn::{
+    Expr, Ident, Pat, Token, braced, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+};
+
+/// Builds a Dom tree with a nested, declarative syntax.
+///
+/// The macro expands to the ordinary builder calls, so it accepts any Dom type and evaluates
+/// to the type of the root node (box it where a `Box<dyn Dom<_>>` is needed).
+///
+/// - A node is any expression that produces a Dom, e.g. `Text::new("hi")` or
+///   `Button::new(...).on_click(|| Message::Clicked)` for event handlers.
+/// - A node followed by `{ ... }` is a container. Its comma separated children are added with
+///   `.push(child)` in order.
+/// - `if cond { ... } else { ... }` adds the children of the branch taken; `else` is optional
+///   and `else if` chains are allowed.
+/// - `for pat in iter { ... }` adds the children once per item.
+/// - `for pat in iter key(expr) { child }` adds a single child per item with
+///   `.push_keyed(&expr, child)`, so items keep their widgets when the list is reordered.
+///
+/// ```ignore
+/// let dom = view! {
+///     Column::new(None) {
+///         Text::new("Todo"),
+///         if model.items.is_empty() {
+///             Text::new("nothing to do"),
+///         },
+///         for item in &model.items key(item.id) {
+///             Button::new(Text::new(&item.title)).on_click({
+///                 let id = item.id;
+///                 move || Message::Toggle(id)
+///             })
+///         },
+///     }
+/// };
+/// ```
+#[proc_macro]
+pub fn view(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse::<Node>(input) {
+        Ok(node) => node.into_token_stream().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+mod kw {
+    syn::custom_keyword!(key);
+}
+
+/// `expr` or `expr { children }`.
+struct Node {
+    expr: Expr,
+    children: Option<Vec<Child>>,
+}
+
+enum Child {
+    Node(Node),
+    If {
+        cond: Expr,
+        then_children: Vec<Child>,
+        else_children: Option<Vec<Child>>,
+    },
+    For {
+        pat: Pat,
+        iter: Expr,
+        children: Vec<Child>,
+    },
+    KeyedFor {
+        pat: Pat,
+        iter: Expr,
+        key: Expr,
+        node: Box<Node>,
+    },
+}
+
+impl Parse for Node {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // a brace after the expression opens the children, as in `if cond { ... }`
+        let expr = Expr::parse_without_eager_brace(input)?;
+        let children = if input.peek(syn::token::Brace) {
+            Some(parse_children_block(input)?)
+        } else {
+            None
+        };
+        Ok(Node { expr, children })
+    }
+}
+
+impl Parse for Child {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![if]) {
+            parse_if(input)
+        } else if input.peek(Token![for]) {
+            input.parse::<Token![for]>()?;
+            let pat = Pat::parse_multi_with_leading_vert(input)?;
+            input.parse::<Token![in]>()?;
+            let iter = Expr::parse_without_eager_brace(input)?;
+            if input.peek(kw::key) {
+                input.parse::<kw::key>()?;
+                let key_content;
+                parenthesized!(key_content in input);
+                let key = key_content.parse::<Expr>()?;
+
+                let content;
+                let brace = braced!(content in input);
+                let node = Box::new(content.parse::<Node>()?);
+                content.parse::<Option<Token![,]>>()?;
+                if !content.is_empty() {
+                    return Err(syn::Error::new(
+                        brace.span.join(),
+                        "a keyed loop must produce exactly one node per item",
+                    ));
+                }
+                return Ok(Child::KeyedFor {
+                    pat,
+                    iter,
+                    key,
+                    node,
+                });
+            }
+            let children = parse_children_block(input)?;
+            Ok(Child::For {
+                pat,
+                iter,
+                children,
+            })
+        } else {
+            input.parse().map(Child::Node)
+        }
+    }
+}
+
+fn parse_if(input: ParseStream) -> syn::Result<Child> {
+    input.parse::<Token![if]>()?;
+    let cond = Expr::parse_without_eager_brace(input)?;
+    let then_children = parse_children_block(input)?;
+    let else_children = if input.peek(Token![else]) {
+        input.parse::<Token![else]>()?;
+        if input.peek(Token![if]) {
+            Some(vec![parse_if(input)?])
+        } else {
+            Some(parse_children_block(input)?)
+        }
+    } else {
+        None
+    };
+    Ok(Child::If {
+        cond,
+        then_children,
+        else_children,
+    })
+}
+
+fn parse_children_block(input: ParseStream) -> syn::Result<Vec<Child>> {
+    let content;
+    braced!(content in input);
+    let children = Punctuated::<Child, Token![,]>::parse_terminated(&content)?;
+    Ok(children.into_iter().collect())
+}
+
+// MARK: expansion
+
+/// Name of the container being built. Mixed-site hygiene keeps it out of reach of user code.
+fn parent_ident() -> Ident {
+    Ident::new("parent", Span::mixed_site())
+}
+
+impl ToTokens for Node {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let expr = &self.expr;
+        match &self.children {
+            None => expr.to_tokens(tokens),
+            Some(children) => {
+                let parent = parent_ident();
+                let statements = expand_children(children);
+                tokens.extend(quote! {
+                    {
+                        let #parent = #expr;
+                        #(#statements)*
+                        #parent
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// One `let parent = ...;` statement per child.
+fn expand_children(children: &[Child]) -> Vec<TokenStream> {
+    let parent = parent_ident();
+    children
+        .iter()
+        .map(|child| match child {
+            Child::Node(node) => quote! {
+                let #parent = #parent.push(#node);
+            },
+            Child::If {
+                cond,
+                then_children,
+                else_children,
+            } => {
+                let then_statements = expand_children(then_children);
+                let else_statements = expand_children(else_children.as_deref().unwrap_or(&[]));
+                quote! {
+                    let #parent = if #cond {
+                        #(#then_statements)*
+                        #parent
+                    } else {
+                        #(#else_statements)*
+                        #parent
+                    };
+                }
+            }
+            Child::KeyedFor {
+                pat,
+                iter,
+                key,
+                node,
+            } => {
+                quote! {
+                    let #parent = {
+                        let mut #parent = #parent;
+                        for #pat in #iter {
+                            #parent = #parent.push_keyed(&(#key), #node);
+                        }
+                        #parent
+                    };
+                }
+            }
+            Child::For {
+                pat,
+                iter,
+                children,
+            } => {
+                let statements = expand_children(children);
+                quote! {
+                    let #parent = {
+                        let mut #parent = #parent;
+                        for #pat in #iter {
+                            #parent = {
+                                #(#statements)*
+                                #parent
+                            };
+                        }
+                        #parent
+                    };
+                }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(input: TokenStream) -> String {
+        syn::parse2::<Node>(input)
+            .expect("valid view syntax")
+            .into_token_stream()
+            .to_string()
+    }
+
+    #[test]
+    fn plain_node_expands_to_itself() {
+        assert_eq!(
+            expand(quote! { Text::new("hi") }),
+            quote! { Text::new("hi") }.to_string()
+        );
+    }
+
+    #[test]
+    fn container_pushes_children_in_order() {
+        let expanded = expand(quote! {
+            Column::new(None) {
+                Text::new("a"),
+                Row::new(None) { Text::new("b") },
+            }
+        });
+        let a = expanded
+            .find("push (Text :: new (\"a\"))")
+            .expect("first child");
+        let row = expanded.find("Row :: new (None)").expect("second child");
+        assert!(a < row);
+    }
+
+    #[test]
+    fn conditionals_and_loops() {
+        let expanded = expand(quote! {
+            Column::new(None) {
+                if show { Text::new("a") } else if other { Text::new("b") },
+                for item in items key(item.id) { Text::new(&item.name) },
+                for i in 0..3 { Text::new("x"), Text::new("y") },
+            }
+        });
+        assert!(expanded.contains("if show"));
+        assert!(expanded.contains("if other"));
+        assert!(expanded.contains("push_keyed (& (item . id) , Text :: new (& item . name))"));
+        assert!(expanded.contains("for i in 0 .. 3"));
+    }
+
+    #[test]
+    fn keyed_loop_requires_a_single_child() {
+        let result = syn::parse2::<Node>(quote! {
+            Column::new(None) {
+                for item in items key(item.id) { Text::new("a"), Text::new("b") },
+            }
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Editable text for the text input widgets.
+//!
+//! An [`Editor`] holds the text in a [`Rope`], so edits and line lookups stay cheap in large
+//! documents, together with the selection and the undo history. Positions are char indices.
+//! It knows nothing about layout; moving the caret between visual lines is left to widgets.
+
+use std::ops::Range;
+
+pub use ropey::Rope;
+
+/// Undo steps kept by default.
+const HISTORY_LIMIT: usize = 1000;
+
+/// A selected range of text. `head` is the end that moves, where the caret is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: usize,
+    pub head: usize,
+}
+
+impl Selection {
+    pub const fn caret(index: usize) -> Self {
+        Self {
+            anchor: index,
+            head: index,
+        }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.anchor.min(self.head)..self.anchor.max(self.head)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+}
+
+/// One undoable change: `removed` at `start` was replaced by `inserted`.
+#[derive(Clone, Debug)]
+struct Edit {
+    start: usize,
+    removed: String,
+    inserted: String,
+    before: Selection,
+    after: Selection,
+}
+
+#[derive(Clone, Debug)]
+pub struct Editor {
+    text: Rope,
+    selection: Selection,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    // whether typing may extend the last edit instead of starting a new undo step
+    typing: bool,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new(Rope::new())
+    }
+}
+
+impl Editor {
+    /// An editor with the caret at the end of `text`.
+    pub fn new(text: Rope) -> Self {
+        let end = text.len_chars();
+        Self {
+            text,
+            selection: Selection::caret(end),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            typing: false,
+        }
+    }
+
+    pub fn text(&self) -> &Rope {
+        &self.text
+    }
+
+    pub fn selection(&self) -> Selection {
+        self.selection
+    }
+
+    pub fn selected_text(&self) -> String {
+        self.text.slice(self.selection.range()).to_string()
+    }
+
+    /// Replaces the whole text, e.g. when the app changes it, and forgets the history.
+    pub fn set_text(&mut self, text: Rope) {
+        self.text = text;
+        self.selection = self.clamp(self.selection);
+        self.undo.clear();
+        self.redo.clear();
+        self.typing = false;
+    }
+
+    pub fn select(&mut self, selection: Selection) {
+        self.selection = self.clamp(selection);
+        self.typing = false;
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(Selection {
+            anchor: 0,
+            head: self.text.len_chars(),
+        });
+    }
+
+    /// Moves the caret to `index`, extending the selection if `extend`.
+    pub fn move_to(&mut self, index: usize, extend: bool) {
+        let anchor = if extend { self.selection.anchor } else { index };
+        self.select(Selection {
+            anchor,
+            head: index,
+        });
+    }
+
+    pub fn move_left(&mut self, extend: bool) {
+        let range = self.selection.range();
+        if !extend && !range.is_empty() {
+            self.move_to(range.start, false);
+        } else {
+            self.move_to(self.selection.head.saturating_sub(1), extend);
+        }
+    }
+
+    pub fn move_right(&mut self, extend: bool) {
+        let range = self.selection.range();
+        if !extend && !range.is_empty() {
+            self.move_to(range.end, false);
+        } else {
+            self.move_to(self.selection.head + 1, extend);
+        }
+    }
+
+    /// Moves the caret to the start of its line.
+    pub fn move_line_start(&mut self, extend: bool) {
+        let line = self.text.char_to_line(self.selection.head);
+        self.move_to(self.text.line_to_char(line), extend);
+    }
+
+    /// Moves the caret to the end of its line, before the line break.
+    pub fn move_line_end(&mut self, extend: bool) {
+        let line = self.text.char_to_line(self.selection.head);
+        self.move_to(self.line_end(line), extend);
+    }
+
+    /// Char index of the end of `line`, before its line break.
+    pub fn line_end(&self, line: usize) -> usize {
+        let start = self.text.line_to_char(line);
+        let content = self.text.line(line);
+        let mut len = content.len_chars();
+        for ending in ['\n', '\r'] {
+            if len > 0 && content.char(len - 1) == ending {
+                len -= 1;
+            }
+        }
+        start + len
+    }
+
+    /// Replaces the selection with `text`. Typed characters are merged into one undo step
+    /// per word.
+    pub fn insert(&mut self, text: &str) {
+        let range = self.selection.range();
+        let typed = range.is_empty() && text.chars().count() == 1;
+        let merge = typed && self.typing && !text.starts_with(char::is_whitespace);
+
+        if merge && let Some(last) = self.undo.last_mut() {
+            self.text.insert(range.start, text);
+            last.inserted.push_str(text);
+            self.selection = Selection::caret(range.start + 1);
+            last.after = self.selection;
+            self.redo.clear();
+            return;
+        }
+
+        self.replace(range, text);
+        self.typing = typed;
+    }
+
+    /// Deletes the selection, or the character before the caret.
+    pub fn backspace(&mut self) {
+        let mut range = self.selection.range();
+        if range.is_empty() {
+            range.start = range.start.saturating_sub(1);
+        }
+        if !range.is_empty() {
+            self.replace(range, "");
+        }
+    }
+
+    /// Deletes the selection, or the character after the caret.
+    pub fn delete(&mut self) {
+        let mut range = self.selection.range();
+        if range.is_empty() {
+            range.end = (range.end + 1).min(self.text.len_chars());
+        }
+        if !range.is_empty() {
+            self.replace(range, "");
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Reverts the last edit. Returns `false` if there is none.
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        let inserted = edit.start..edit.start + edit.inserted.chars().count();
+        self.text.remove(inserted);
+        self.text.insert(edit.start, &edit.removed);
+        self.selection = edit.before;
+        self.typing = false;
+        self.redo.push(edit);
+        true
+    }
+
+    /// Applies the last undone edit again. Returns `false` if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        let removed = edit.start..edit.start + edit.removed.chars().count();
+        self.text.remove(removed);
+        self.text.insert(edit.start, &edit.inserted);
+        self.selection = edit.after;
+        self.typing = false;
+        self.undo.push(edit);
+        true
+    }
+
+    fn replace(&mut self, range: Range<usize>, text: &str) {
+        let before = self.selection;
+        let removed = self.text.slice(range.clone()).to_string();
+        self.text.remove(range.clone());
+        self.text.insert(range.start, text);
+        self.selection = Selection::caret(range.start + text.chars().count());
+
+        self.undo.push(Edit {
+            start: range.start,
+            removed,
+            inserted: text.to_string(),
+            before,
+            after: self.selection,
+        });
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.typing = false;
+    }
+
+    fn clamp(&self, selection: Selection) -> Selection {
+        let len = self.text.len_chars();
+        Selection {
+            anchor: selection.anchor.min(len),
+            head: selection.head.min(len),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn type_text(editor: &mut Editor, text: &str) {
+        for c in text.chars() {
+            editor.insert(c.encode_utf8(&mut [0; 4]));
+        }
+    }
+
+    #[test]
+    fn edits_replace_the_selection() {
+        let mut editor = Editor::new(Rope::from_str("hello world"));
+        editor.select(Selection {
+            anchor: 6,
+            head: 11,
+        });
+        assert_eq!(editor.selected_text(), "world");
+
+        editor.insert("there");
+        assert_eq!(editor.text().to_string(), "hello there");
+        assert_eq!(editor.selection(), Selection::caret(11));
+
+        editor.move_left(true);
+        editor.move_left(true);
+        editor.backspace();
+        assert_eq!(editor.text().to_string(), "hello the");
+
+        editor.move_to(0, false);
+        editor.delete();
+        assert_eq!(editor.text().to_string(), "ello the");
+        editor.move_left(false);
+        editor.backspace();
+        assert_eq!(editor.text().to_string(), "ello the");
+    }
+
+    #[test]
+    fn lines_end_before_their_break() {
+        let mut editor = Editor::new(Rope::from_str("one\r\ntwo\nthree"));
+        editor.move_to(6, false);
+        editor.move_line_start(false);
+        assert_eq!(editor.selection(), Selection::caret(5));
+        editor.move_line_end(true);
+        assert_eq!(editor.selected_text(), "two");
+        assert_eq!(editor.line_end(0), 3);
+        assert_eq!(editor.line_end(2), 14);
+    }
+
+    #[test]
+    fn typing_is_undone_word_by_word() {
+        let mut editor = Editor::default();
+        type_text(&mut editor, "hello world");
+        assert_eq!(editor.text().to_string(), "hello world");
+
+        assert!(editor.undo());
+        assert_eq!(editor.text().to_string(), "hello");
+        assert!(editor.undo());
+        assert_eq!(editor.text().to_string(), "");
+        assert!(!editor.undo());
+
+        assert!(editor.redo());
+        assert!(editor.redo());
+        assert_eq!(editor.text().to_string(), "hello world");
+        assert_eq!(editor.selection(), Selection::caret(11));
+
+        // a new edit drops the undone ones
+        editor.undo();
+        editor.insert("!");
+        assert!(!editor.can_redo());
+        assert_eq!(editor.text().to_string(), "hello!");
+
+        // moving the caret starts a new step
+        editor.move_to(0, false);
+        type_text(&mut editor, "oh");
+        editor.undo();
+        assert_eq!(editor.text().to_string(), "hello!");
+        assert_eq!(editor.selection(), Selection::caret(0));
+    }
+}
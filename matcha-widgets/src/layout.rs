@@ -1,7 +1,12 @@
 pub mod column;
+pub mod dock;
 pub mod grid;
+pub mod hit_area;
+pub mod lazy_column;
 pub mod padding;
 pub mod position;
 pub mod row;
 pub mod space;
+pub mod split_pane;
+pub(crate) mod virtual_rows;
 pub mod visibility;
@@ -1,11 +1,14 @@
+use std::hash::Hash;
+
 use matcha_core::context::WidgetContext;
 use matcha_core::metrics::{Arrangement, Constraints};
 use nalgebra::Matrix4;
 
+use matcha_core::ui::keyed::child_id;
 use matcha_core::ui::widget::InvalidationHandle;
 use matcha_core::{
     device_input::DeviceInput,
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, Widget, WidgetFrame},
+    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame},
 };
 use renderer::render_node::RenderNode;
 
@@ -20,9 +23,11 @@ where
     T: Send + 'static,
 {
     label: Option<String>,
+    layout_style: LayoutStyle,
     justify_content: JustifyContent,
     align_items: AlignItems,
-    items: Vec<Box<dyn Dom<T>>>,
+    /// children with their ids.
+    items: Vec<(Box<dyn Dom<T>>, u128)>,
 }
 
 impl<T> Column<T>
@@ -32,6 +37,7 @@ where
     pub fn new(label: Option<&str>) -> Self {
         Self {
             label: label.map(String::from),
+            layout_style: LayoutStyle::default(),
             justify_content: JustifyContent::FlexStart {
                 gap: GrowSize::Fixed(Size::px(0.0)),
             },
@@ -40,6 +46,24 @@ where
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    /// Clips the widget and its children to its border box with corners rounded by
+    /// `radius`, see [`LayoutStyle::clip_radius`]. Call after `layout`, which replaces it.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.layout_style = self.layout_style.clip_radius(radius);
+        self
+    }
+
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.layout_style = self.layout_style.elevation(level);
+        self
+    }
+
     pub fn justify_content(mut self, justify_content: JustifyContent) -> Self {
         self.justify_content = justify_content;
         self
@@ -50,8 +74,36 @@ where
         self
     }
 
+    /// Push a child identified by its position.
     pub fn push(mut self, item: impl Dom<T>) -> Self {
-        self.items.push(Box::new(item));
+        let id = self.items.len() as u128;
+        self.items.push((Box::new(item), id));
+        self
+    }
+
+    /// Push a child identified by `key`, so it keeps its widget when other children are
+    /// inserted, removed or reordered. Keys must be unique within the column.
+    pub fn push_keyed<K: Hash + ?Sized>(mut self, key: &K, item: impl Dom<T>) -> Self {
+        self.items.push((Box::new(item), child_id(key)));
+        self
+    }
+
+    /// Push one keyed child per element of `iter`.
+    pub fn children_keyed<I, K, D>(
+        mut self,
+        iter: I,
+        key: impl Fn(&I::Item) -> K,
+        dom: impl Fn(I::Item) -> D,
+    ) -> Self
+    where
+        I: IntoIterator,
+        K: Hash,
+        D: Dom<T>,
+    {
+        for item in iter {
+            let id = child_id(&key(&item));
+            self.items.push((Box::new(dom(item)), id));
+        }
         self
     }
 }
@@ -65,21 +117,28 @@ where
         let mut children_and_settings = Vec::new();
         let mut child_ids = Vec::new();
 
-        for (index, item) in self.items.iter().enumerate() {
+        for (item, id) in &self.items {
             let child_widget = item.build_widget_tree();
             children_and_settings.push((child_widget, ()));
-            child_ids.push(index as u128);
+            child_ids.push(*id);
         }
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children_and_settings,
-            child_ids,
-            ColumnNode {
-                justify_content: self.justify_content.clone(),
-                align_items: self.align_items,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                ColumnNode {
+                    justify_content: self.justify_content.clone(),
+                    align_items: self.align_items,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -91,6 +150,29 @@ pub struct ColumnNode {
 }
 
 impl ColumnNode {
+    /// Sum the children's intrinsic heights and add the gaps that do not depend on free space.
+    fn intrinsic_height_with_gaps(
+        &self,
+        child_heights: impl Iterator<Item = f32>,
+        width: f32,
+        ctx: &WidgetContext,
+    ) -> f32 {
+        let (total_child_height, child_count) =
+            child_heights.fold((0.0f32, 0usize), |(total, count), h| (total + h, count + 1));
+
+        // No free space is available when sizing to content, so grow / space-* gaps collapse to zero.
+        let (gap, _offset) = self.calc_gap_and_offset(
+            &self.justify_content,
+            total_child_height,
+            total_child_height,
+            width,
+            child_count,
+            ctx,
+        );
+
+        total_child_height + gap * child_count.saturating_sub(1) as f32
+    }
+
     /// Calculate gap and initial offset for given justify_content (vertical axis).
     /// - container_size: full available height
     /// - total_child_height: sum of measured child heights
@@ -222,8 +304,7 @@ where
 
         dom.items
             .iter()
-            .enumerate()
-            .map(|(index, item)| (item.as_ref(), (), index as u128))
+            .map(|(item, id)| (item.as_ref(), (), *id))
             .collect()
     }
 
@@ -298,6 +379,60 @@ where
         ]
     }
 
+    fn min_intrinsic_width(
+        &self,
+        height: f32,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        children
+            .iter()
+            .map(|(child, _)| child.min_intrinsic_width(height, ctx))
+            .fold(0.0, f32::max)
+    }
+
+    fn max_intrinsic_width(
+        &self,
+        height: f32,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        children
+            .iter()
+            .map(|(child, _)| child.max_intrinsic_width(height, ctx))
+            .fold(0.0, f32::max)
+    }
+
+    fn min_intrinsic_height(
+        &self,
+        width: f32,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.intrinsic_height_with_gaps(
+            children
+                .iter()
+                .map(|(child, _)| child.min_intrinsic_height(width, ctx)),
+            width,
+            ctx,
+        )
+    }
+
+    fn max_intrinsic_height(
+        &self,
+        width: f32,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.intrinsic_height_with_gaps(
+            children
+                .iter()
+                .map(|(child, _)| child.max_intrinsic_height(width, ctx)),
+            width,
+            ctx,
+        )
+    }
+
     fn arrange(
         &self,
         bounds: [f32; 2],
@@ -337,7 +472,7 @@ where
         for (index, &child_size) in child_sizes.iter().enumerate() {
             // Calculate x offset based on align_items (cross-axis)
             let x_offset = match self.align_items {
-                AlignItems::Start => 0.0,
+                AlignItems::Start | AlignItems::Baseline => 0.0,
                 AlignItems::End => bounds[0] - child_size[0],
                 AlignItems::Center => (bounds[0] - child_size[0]) / 2.0,
             };
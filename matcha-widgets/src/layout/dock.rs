@@ -0,0 +1,806 @@
+use std::sync::{Arc, OnceLock};
+
+use matcha_core::{
+    color::Color,
+    context::WidgetContext,
+    device_input::{DeviceInput, DeviceInputData, ElementState, MouseInput, MouseLogicalButton},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, GlobalKey, InvalidationHandle, LayoutStyle,
+        Widget, WidgetFrame, global_keyed, keyed::child_id,
+    },
+};
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+
+use crate::layout::space::Space;
+use crate::layout::split_pane::{SplitPane, pane_rects};
+use crate::style::{Style, solid_box::SolidBox};
+use crate::widget::tabs::{ContentBuilder, STRIP_HEIGHT, Tabs};
+
+mod dock_layout;
+pub use dock_layout::{
+    DockLayout, DockLayoutError, DockTarget, DockTree, DockZone, FloatingGroup, GroupId, TabGroup,
+};
+
+/// Minimum size of the panes of docked splits, in pixels.
+const MIN_PANE_SIZE: f32 = 48.0;
+/// Height of the bar above a floating group that moves it.
+const GRIP_HEIGHT: f32 = 10.0;
+/// Distance from the edges of the dock within which a panel is docked along the whole edge.
+const EDGE_ZONE: f32 = 24.0;
+/// Share of a group covered by the zones that dock beside it rather than into it.
+const SIDE_ZONE: f32 = 0.25;
+/// Size of the floating group made by dropping a panel outside of any drop zone.
+const FLOATING_SIZE: [f32; 2] = [320.0, 240.0];
+/// Namespace of the global keys that keep the widgets of panels while they move.
+const PANEL_KEY: &str = "matcha-dock-panel";
+
+const FRAME_COLOR: Color = Color::RgbaF32 {
+    r: 0.78,
+    g: 0.78,
+    b: 0.78,
+    a: 1.0,
+};
+const GRIP_COLOR: Color = Color::RgbaF32 {
+    r: 0.86,
+    g: 0.86,
+    b: 0.86,
+    a: 1.0,
+};
+const PANEL_COLOR: Color = Color::RgbaF32 {
+    r: 0.99,
+    g: 0.99,
+    b: 0.99,
+    a: 1.0,
+};
+const ACCENT_COLOR: Color = Color::RgbaF32 {
+    r: 0.20,
+    g: 0.47,
+    b: 0.90,
+    a: 1.0,
+};
+const DROP_ZONE_COLOR: Color = Color::RgbaF32 {
+    r: 0.20,
+    g: 0.47,
+    b: 0.90,
+    a: 0.25,
+};
+
+type LayoutHandler<T> = Arc<dyn Fn(DockLayout) -> T + Send + Sync>;
+/// panel dragged out of a tab strip, written by the strip and taken by the dock.
+type DraggedOut = Arc<Mutex<Option<String>>>;
+/// `None` for the docked panels, the index for a floating group.
+type ChildSlot = Option<usize>;
+
+// MARK: DOM
+
+struct Panel<T> {
+    id: String,
+    title: String,
+    builder: ContentBuilder<T>,
+}
+
+/// Panels arranged in tab groups that the user can rearrange, as in IDEs.
+///
+/// Groups are docked side by side with [`SplitPane`] dividers or float above them. Dragging a
+/// tab away from its strip picks the panel up: while it is dragged the drop zone under the
+/// pointer is highlighted, and dropping it docks it along an edge of the dock, beside a group
+/// or into a group as a tab. Dropped elsewhere, it floats in a new group. Floating groups are
+/// moved by the bar above their tabs.
+///
+/// The arrangement is a [`DockLayout`] owned by the model: every change made by the user
+/// emits [`on_change`](Dock::on_change) with the new layout, and the view passes it back with
+/// [`Dock::new`]. Without `on_change` the layout is fixed. The widgets of a panel are kept
+/// while it is moved, so it keeps its scroll position, text input and so on.
+///
+/// ```ignore
+/// Dock::new(model.dock_layout.clone())
+///     .panel("files", "Files", || file_tree())
+///     .panel("editor", "Editor", || editor())
+///     .on_change(Message::DockChanged)
+/// ```
+pub struct Dock<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    dock_layout: DockLayout,
+    panels: Vec<Panel<T>>,
+    closable: bool,
+    on_change: Option<LayoutHandler<T>>,
+    /// tab groups of the layout, composed on first use.
+    children: OnceLock<Vec<(Box<dyn Dom<T>>, ChildSlot, u128)>>,
+}
+
+impl<T: Send + Sync + 'static> Dock<T> {
+    pub fn new(dock_layout: DockLayout) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            dock_layout,
+            panels: Vec::new(),
+            closable: false,
+            on_change: None,
+            children: OnceLock::new(),
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Adds the panel named `id` in the layout. Panels of the layout without one show their
+    /// id and nothing else; panels not in the layout are not shown.
+    pub fn panel<D: Dom<T>>(
+        mut self,
+        id: &str,
+        title: &str,
+        content: impl Fn() -> D + Send + Sync + 'static,
+    ) -> Self {
+        self.panels.push(Panel {
+            id: id.to_string(),
+            title: title.to_string(),
+            builder: Arc::new(move || Box::new(content()) as Box<dyn Dom<T>>),
+        });
+        self
+    }
+
+    /// Shows a close button on every tab that removes the panel from the layout. Default is
+    /// `false`.
+    pub fn closable(mut self, closable: bool) -> Self {
+        self.closable = closable;
+        self
+    }
+
+    /// Message emitted with the new layout whenever the user changes it.
+    pub fn on_change(mut self, f: impl Fn(DockLayout) -> T + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(f));
+        self
+    }
+
+    fn children(&self, dragged_out: &DraggedOut) -> &[(Box<dyn Dom<T>>, ChildSlot, u128)] {
+        self.children.get_or_init(|| {
+            let changes = self.on_change.clone().map(|on_change| Changes {
+                layout: Arc::new(self.dock_layout.clone()),
+                on_change,
+            });
+            let mut children = Vec::new();
+            if let Some(root) = &self.dock_layout.root {
+                let docked = self.compose_tree(root, &mut Vec::new(), &changes, dragged_out);
+                children.push((docked, None, 0));
+            }
+            for (index, floating) in self.dock_layout.floating.iter().enumerate() {
+                let group = self.compose_group(
+                    &floating.group,
+                    GroupId::Floating(index),
+                    &changes,
+                    dragged_out,
+                );
+                // floating groups are reordered when raised, so they are told apart by a panel
+                let id = child_id(&floating.group.panels.first());
+                children.push((group, Some(index), id));
+            }
+            children
+        })
+    }
+
+    fn compose_tree(
+        &self,
+        tree: &DockTree,
+        path: &mut Vec<usize>,
+        changes: &Option<Changes<T>>,
+        dragged_out: &DraggedOut,
+    ) -> Box<dyn Dom<T>> {
+        match tree {
+            DockTree::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => {
+                path.push(0);
+                let first = self.compose_tree(first, path, changes, dragged_out);
+                path.pop();
+                path.push(1);
+                let second = self.compose_tree(second, path, changes, dragged_out);
+                path.pop();
+                let mut split = SplitPane::from_boxed(*direction, first, second)
+                    .ratio(*ratio)
+                    .min_sizes(MIN_PANE_SIZE, MIN_PANE_SIZE);
+                if let Some(changes) = changes.clone() {
+                    let path = path.clone();
+                    split = split.on_resize(move |ratio| {
+                        changes.emit(|layout| layout.set_ratio(&path, ratio))
+                    });
+                }
+                Box::new(split)
+            }
+            DockTree::Tabs(group) => {
+                self.compose_group(group, GroupId::Docked(path.clone()), changes, dragged_out)
+            }
+        }
+    }
+
+    fn compose_group(
+        &self,
+        group: &TabGroup,
+        id: GroupId,
+        changes: &Option<Changes<T>>,
+        dragged_out: &DraggedOut,
+    ) -> Box<dyn Dom<T>> {
+        let mut tabs = Tabs::new(group.selected).keep_alive(true);
+        for name in &group.panels {
+            let panel = self.panels.iter().find(|panel| panel.id == *name);
+            let title = panel.map_or(name.as_str(), |panel| panel.title.as_str());
+            let builder = panel.map(|panel| panel.builder.clone());
+            let key = GlobalKey::new(&(PANEL_KEY, name.as_str()));
+            tabs = tabs.push_tab(
+                child_id(name.as_str()),
+                title,
+                Arc::new(move || match &builder {
+                    Some(builder) => global_keyed(key, builder()),
+                    None => Space::new(None) as Box<dyn Dom<T>>,
+                }),
+            );
+        }
+
+        let Some(changes) = changes else {
+            return Box::new(tabs);
+        };
+        let panels = Arc::new(group.panels.clone());
+        tabs = tabs
+            .on_select({
+                let (changes, id) = (changes.clone(), id.clone());
+                move |index| changes.emit(|layout| layout.select(&id, index))
+            })
+            .on_reorder({
+                let changes = changes.clone();
+                move |from, to| changes.emit(|layout| layout.reorder(&id, from, to))
+            })
+            .drag_out_handler({
+                let (panels, dragged_out) = (panels.clone(), dragged_out.clone());
+                Arc::new(move |index| {
+                    *dragged_out.lock() = panels.get(index).cloned();
+                    None
+                })
+            });
+        if self.closable {
+            let changes = changes.clone();
+            tabs = tabs.on_close(move |index| {
+                changes.emit(|layout| {
+                    if let Some(panel) = panels.get(index) {
+                        layout.remove_panel(panel);
+                    }
+                })
+            });
+        }
+        Box::new(tabs)
+    }
+}
+
+/// The layout a dock was built with and where to send changes of it.
+struct Changes<T> {
+    layout: Arc<DockLayout>,
+    on_change: LayoutHandler<T>,
+}
+
+impl<T> Clone for Changes<T> {
+    fn clone(&self) -> Self {
+        Self {
+            layout: self.layout.clone(),
+            on_change: self.on_change.clone(),
+        }
+    }
+}
+
+impl<T> Changes<T> {
+    fn emit(&self, edit: impl FnOnce(&mut DockLayout)) -> T {
+        let mut layout = (*self.layout).clone();
+        edit(&mut layout);
+        (self.on_change)(layout)
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for Dock<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        let node = DockNode {
+            layout: self.dock_layout.clone(),
+            on_change: self.on_change.clone(),
+            dragged_out: DraggedOut::default(),
+            gesture: None,
+            captured: None,
+        };
+        let (children, ids): (Vec<_>, Vec<_>) = self
+            .children(&node.dragged_out)
+            .iter()
+            .map(|(dom, slot, id)| ((dom.build_widget_tree(), *slot), *id))
+            .unzip();
+
+        Box::new(
+            WidgetFrame::new(self.label.clone(), children, ids, node)
+                .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+enum Gesture {
+    /// a floating group is moved by its grip.
+    MoveFloating {
+        index: usize,
+        /// pointer relative to the group's corner.
+        grab: [f32; 2],
+        position: [f32; 2],
+    },
+    /// a panel dragged out of its tab strip.
+    DragPanel { panel: String, target: DockTarget },
+}
+
+type Rect = ([f32; 2], [f32; 2]);
+
+fn contains((origin, size): Rect, position: [f32; 2]) -> bool {
+    (origin[0]..origin[0] + size[0]).contains(&position[0])
+        && (origin[1]..origin[1] + size[1]).contains(&position[1])
+}
+
+/// The part of `rect` covered by `zone`, with side zones taking `share` of it.
+fn zone_rect((origin, size): Rect, zone: DockZone, share: f32) -> Rect {
+    let [width, height] = size;
+    match zone {
+        DockZone::Left => (origin, [width * share, height]),
+        DockZone::Right => (
+            [origin[0] + width * (1.0 - share), origin[1]],
+            [width * share, height],
+        ),
+        DockZone::Top => (origin, [width, height * share]),
+        DockZone::Bottom => (
+            [origin[0], origin[1] + height * (1.0 - share)],
+            [width, height * share],
+        ),
+        DockZone::Center => (origin, size),
+    }
+}
+
+pub struct DockNode<T> {
+    layout: DockLayout,
+    on_change: Option<LayoutHandler<T>>,
+    dragged_out: DraggedOut,
+    gesture: Option<Gesture>,
+    /// child the primary button went down on; it gets the pointer until the button is released.
+    captured: Option<ChildSlot>,
+}
+
+impl<T> DockNode<T> {
+    /// Frame of a floating group, grip included, kept inside `bounds`.
+    fn floating_rect(&self, index: usize, bounds: [f32; 2]) -> Option<Rect> {
+        let floating = self.layout.floating.get(index)?;
+        let position = match self.gesture {
+            Some(Gesture::MoveFloating {
+                index: moved,
+                position,
+                ..
+            }) if moved == index => position,
+            _ => floating.position,
+        };
+        let min_size = [MIN_PANE_SIZE, GRIP_HEIGHT + STRIP_HEIGHT];
+        let size: [f32; 2] =
+            std::array::from_fn(|axis| floating.size[axis].max(min_size[axis]).min(bounds[axis]));
+        let origin =
+            std::array::from_fn(|axis| position[axis].min(bounds[axis] - size[axis]).max(0.0));
+        Some((origin, size))
+    }
+
+    /// Front-most floating group under `position`.
+    fn floating_at(&self, position: [f32; 2], bounds: [f32; 2]) -> Option<usize> {
+        (0..self.layout.floating.len()).rev().find(|index| {
+            self.floating_rect(*index, bounds)
+                .is_some_and(|rect| contains(rect, position))
+        })
+    }
+
+    /// Rects of the docked groups, as laid out by the split panes.
+    fn docked_groups(&self, bounds: [f32; 2]) -> Vec<(Vec<usize>, Rect)> {
+        fn visit(
+            tree: &DockTree,
+            path: &mut Vec<usize>,
+            rect: Rect,
+            out: &mut Vec<(Vec<usize>, Rect)>,
+        ) {
+            match tree {
+                DockTree::Split {
+                    direction,
+                    ratio,
+                    first,
+                    second,
+                } => {
+                    let (origin, size) = rect;
+                    let panes = pane_rects(*direction, *ratio, [MIN_PANE_SIZE; 2], size);
+                    for (branch, (pane, (offset, size))) in
+                        [first, second].into_iter().zip(panes).enumerate()
+                    {
+                        path.push(branch);
+                        let origin = [origin[0] + offset[0], origin[1] + offset[1]];
+                        visit(pane, path, (origin, size), out);
+                        path.pop();
+                    }
+                }
+                DockTree::Tabs(_) => out.push((path.clone(), rect)),
+            }
+        }
+
+        let mut groups = Vec::new();
+        if let Some(root) = &self.layout.root {
+            visit(root, &mut Vec::new(), ([0.0, 0.0], bounds), &mut groups);
+        }
+        groups
+    }
+
+    /// Where a panel dropped at `position` goes.
+    fn drop_target(&self, position: [f32; 2], bounds: [f32; 2]) -> DockTarget {
+        let floating = DockTarget::Floating {
+            position: [
+                position[0] - FLOATING_SIZE[0] / 2.0,
+                position[1] - GRIP_HEIGHT / 2.0,
+            ],
+            size: FLOATING_SIZE,
+        };
+        if !contains(([0.0, 0.0], bounds), position) {
+            return floating;
+        }
+        if let Some(index) = self.floating_at(position, bounds) {
+            return DockTarget::Group(GroupId::Floating(index), DockZone::Center);
+        }
+        if self.layout.root.is_none() {
+            return DockTarget::Edge(DockZone::Center);
+        }
+
+        let edges = [
+            (DockZone::Left, position[0]),
+            (DockZone::Right, bounds[0] - position[0]),
+            (DockZone::Top, position[1]),
+            (DockZone::Bottom, bounds[1] - position[1]),
+        ];
+        if let Some((zone, _)) = edges
+            .iter()
+            .filter(|(_, distance)| *distance < EDGE_ZONE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        {
+            return DockTarget::Edge(*zone);
+        }
+
+        let Some((path, (origin, size))) = self
+            .docked_groups(bounds)
+            .into_iter()
+            .find(|(_, rect)| contains(*rect, position))
+        else {
+            return floating;
+        };
+        // relative distances to the sides of the group
+        let u = (position[0] - origin[0]) / size[0].max(1.0);
+        let v = (position[1] - origin[1]) / size[1].max(1.0);
+        let sides = [
+            (DockZone::Left, u),
+            (DockZone::Right, 1.0 - u),
+            (DockZone::Top, v),
+            (DockZone::Bottom, 1.0 - v),
+        ];
+        let zone = sides
+            .iter()
+            .filter(|(_, distance)| *distance < SIDE_ZONE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(DockZone::Center, |(zone, _)| *zone);
+        DockTarget::Group(GroupId::Docked(path), zone)
+    }
+
+    /// Area highlighted while a panel is dragged over `target`.
+    fn target_rect(&self, target: &DockTarget, bounds: [f32; 2]) -> Option<Rect> {
+        match target {
+            DockTarget::Edge(zone) => Some(zone_rect(([0.0, 0.0], bounds), *zone, 0.25)),
+            DockTarget::Group(GroupId::Docked(path), zone) => self
+                .docked_groups(bounds)
+                .into_iter()
+                .find(|(group, _)| group == path)
+                .map(|(_, rect)| zone_rect(rect, *zone, 0.5)),
+            DockTarget::Group(GroupId::Floating(index), _) => self.floating_rect(*index, bounds),
+            DockTarget::Floating { position, size } => Some((*position, *size)),
+        }
+    }
+
+    /// Ends a gesture, emitting the layout it made.
+    fn finish(&self, gesture: Gesture) -> Option<T> {
+        let on_change = self.on_change.as_ref()?;
+        let mut layout = self.layout.clone();
+        match gesture {
+            Gesture::MoveFloating {
+                index, position, ..
+            } => {
+                layout.move_floating(index, position);
+                layout.raise_floating(index);
+            }
+            Gesture::DragPanel { panel, target, .. } => {
+                if !layout.move_panel(&panel, &target) {
+                    return None;
+                }
+            }
+        }
+        (layout != self.layout).then(|| on_change(layout))
+    }
+
+    fn render_rect(
+        &self,
+        (origin, size): Rect,
+        fill: Color,
+        frame: Color,
+        grip: bool,
+        ctx: &WidgetContext,
+    ) -> Option<(RenderNode, nalgebra::Matrix4<f32>)> {
+        let texture_size = [size[0].ceil() as u32, size[1].ceil() as u32];
+        if texture_size[0] == 0 || texture_size[1] == 0 {
+            return None;
+        }
+        let region = ctx
+            .texture_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), texture_size)
+            .ok()?;
+
+        let mut encoder = ctx
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Dock Render Encoder"),
+            });
+
+        SolidBox { color: frame }.draw(&mut encoder, &region, size, [0.0, 0.0], ctx);
+        let inner = [(size[0] - 2.0).max(0.0), (size[1] - 2.0).max(0.0)];
+        SolidBox { color: fill }.draw(&mut encoder, &region, inner, [1.0, 1.0], ctx);
+        if grip {
+            SolidBox { color: GRIP_COLOR }.draw(
+                &mut encoder,
+                &region,
+                [inner[0], GRIP_HEIGHT - 1.0],
+                [1.0, 1.0],
+                ctx,
+            );
+        }
+
+        ctx.queue().submit(Some(encoder.finish()));
+
+        Some((
+            RenderNode::new().with_texture(region, size, nalgebra::Matrix4::identity()),
+            nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(origin[0], origin[1], 0.0)),
+        ))
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<Dock<T>, T, ChildSlot> for DockNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a Dock<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, ChildSlot, u128)> {
+        if self.layout != dom.dock_layout {
+            self.layout = dom.dock_layout.clone();
+            // a gesture on parts of the layout that are gone ends
+            let stale = match &self.gesture {
+                Some(Gesture::MoveFloating { index, .. }) => *index >= self.layout.floating.len(),
+                Some(Gesture::DragPanel { panel, .. }) => !self.layout.contains(panel),
+                None => false,
+            };
+            if stale {
+                self.gesture = None;
+            }
+            if let Some(handle) = cache_invalidator {
+                handle.relayout_next_frame();
+            }
+        }
+        self.on_change = dom.on_change.clone();
+
+        dom.children(&self.dragged_out)
+            .iter()
+            .map(|(child, slot, id)| (child.as_ref(), *slot, *id))
+            .collect()
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut ChildSlot, &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let pointer = match event.event() {
+            DeviceInputData::MouseInput { .. } => event.mouse_position(),
+            _ => None,
+        };
+        let primary = match event.event() {
+            DeviceInputData::MouseInput {
+                event:
+                    Some(MouseInput::Click {
+                        click_state,
+                        button: MouseLogicalButton::Primary,
+                    }),
+                ..
+            } => Some(click_state),
+            _ => None,
+        };
+
+        if let Some(position) = pointer {
+            // the gesture in progress takes all pointer input
+            if let Some(ElementState::Released(_)) = primary
+                && let Some(gesture) = self.gesture.take()
+            {
+                cache_invalidator.relayout_next_frame();
+                return self.finish(gesture);
+            }
+            if self.gesture.is_some() {
+                let drop_target = self.drop_target(position, bounds);
+                match &mut self.gesture {
+                    Some(Gesture::MoveFloating {
+                        grab, position: at, ..
+                    }) => {
+                        *at = [position[0] - grab[0], position[1] - grab[1]];
+                        cache_invalidator.relayout_next_frame();
+                    }
+                    Some(Gesture::DragPanel { target, .. }) => {
+                        *target = drop_target;
+                        cache_invalidator.redraw_next_frame();
+                    }
+                    None => {}
+                }
+                return None;
+            }
+
+            if let Some(ElementState::Pressed(_)) = primary {
+                let floating = self.floating_at(position, bounds);
+                if let Some(index) = floating
+                    && let Some((origin, _)) = self.floating_rect(index, bounds)
+                    && position[1] - origin[1] < GRIP_HEIGHT
+                {
+                    if self.on_change.is_some() {
+                        self.gesture = Some(Gesture::MoveFloating {
+                            index,
+                            grab: [position[0] - origin[0], position[1] - origin[1]],
+                            position: origin,
+                        });
+                        cache_invalidator.redraw_next_frame();
+                    }
+                    return None;
+                }
+                self.captured = Some(floating);
+            }
+        }
+
+        // the pointer belongs to the child it went down on, or else to the one under it
+        let target = match (pointer, self.captured) {
+            (Some(_), Some(captured)) => Some(captured),
+            (Some(position), None) => Some(self.floating_at(position, bounds)),
+            (None, _) => None,
+        };
+        if let Some(ElementState::Released(_)) = primary {
+            self.captured = None;
+        }
+
+        let mut message = None;
+        // front-most first
+        for (child, slot, arrangement) in children.iter_mut().rev() {
+            let child_event = if target.is_none_or(|target| target == **slot) {
+                event.transform(arrangement.affine)
+            } else {
+                event.with_pointer_outside().transform(arrangement.affine)
+            };
+            if let Some(child_message) = child.device_input(&child_event, ctx) {
+                message = Some(child_message);
+                break;
+            }
+        }
+
+        if let Some(panel) = self.dragged_out.lock().take()
+            && let Some(position) = pointer
+        {
+            self.captured = None;
+            self.gesture = Some(Gesture::DragPanel {
+                panel,
+                target: self.drop_target(position, bounds),
+            });
+            cache_invalidator.redraw_next_frame();
+        }
+
+        message
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &ChildSlot)],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let max = [constraints.max_width(), constraints.max_height()];
+        let min = [constraints.min_width(), constraints.min_height()];
+
+        // fill the available space; fall back to the docked panels where it is unbounded
+        let docked = children
+            .iter()
+            .find(|(_, slot)| slot.is_none())
+            .map_or([0.0, 0.0], |(child, _)| child.measure(constraints, ctx));
+        let finite = constraints.max_finite();
+        std::array::from_fn(|axis| {
+            finite[axis]
+                .unwrap_or(docked[axis])
+                .clamp(min[axis], max[axis])
+        })
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &ChildSlot)],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        children
+            .iter()
+            .map(|(_, slot)| match slot {
+                None => Arrangement::new(bounds, nalgebra::Matrix4::identity()),
+                Some(index) => {
+                    let (origin, size) = self
+                        .floating_rect(*index, bounds)
+                        .unwrap_or(([0.0, 0.0], [0.0, 0.0]));
+                    // inside the frame, below the grip
+                    Arrangement::new(
+                        [
+                            (size[0] - 2.0).max(0.0),
+                            (size[1] - GRIP_HEIGHT - 1.0).max(0.0),
+                        ],
+                        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+                            origin[0] + 1.0,
+                            origin[1] + GRIP_HEIGHT,
+                            0.0,
+                        )),
+                    )
+                }
+            })
+            .collect()
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &ChildSlot, &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        for (child, slot, arrangement) in children {
+            if let Some(index) = slot
+                && let Some(rect) = self.floating_rect(*index, bounds)
+                && let Some((frame, affine)) =
+                    self.render_rect(rect, PANEL_COLOR, FRAME_COLOR, true, ctx)
+            {
+                render_node.push_child(frame, affine);
+            }
+            render_node.push_child(child.render(background, ctx), arrangement.affine);
+        }
+
+        if let Some(Gesture::DragPanel { target, .. }) = &self.gesture
+            && let Some(rect) = self.target_rect(target, bounds)
+            && let Some((highlight, affine)) =
+                self.render_rect(rect, DROP_ZONE_COLOR, ACCENT_COLOR, false, ctx)
+        {
+            render_node.push_child(highlight, affine);
+        }
+
+        render_node
+    }
+}
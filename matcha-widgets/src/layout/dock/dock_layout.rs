@@ -0,0 +1,864 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::layout::split_pane::SplitDirection;
+
+/// Ratio of a split made by docking a panel along an edge of the whole dock.
+const EDGE_RATIO: f32 = 0.25;
+/// Ratio of a split made by docking a panel beside a group.
+const SIDE_RATIO: f32 = 0.5;
+/// Name a panel has between being inserted at its new place and removed from its old one.
+const MOVING: &str = "\0moving";
+
+/// Where a dragged panel is dropped, relative to a group or to the whole dock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DockZone {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Into the group as a new tab.
+    Center,
+}
+
+impl DockZone {
+    /// Direction of the split made by docking at this side, and whether the docked panel
+    /// becomes its first pane.
+    fn split(self) -> Option<(SplitDirection, bool)> {
+        match self {
+            DockZone::Left => Some((SplitDirection::Horizontal, true)),
+            DockZone::Right => Some((SplitDirection::Horizontal, false)),
+            DockZone::Top => Some((SplitDirection::Vertical, true)),
+            DockZone::Bottom => Some((SplitDirection::Vertical, false)),
+            DockZone::Center => None,
+        }
+    }
+}
+
+/// Panels shown as tabs at one place of a [`DockLayout`]. Panels are named by the ids given
+/// to [`Dock::panel`](super::Dock::panel).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabGroup {
+    pub panels: Vec<String>,
+    pub selected: usize,
+}
+
+impl TabGroup {
+    /// A group showing the first of `panels`.
+    pub fn new<S: Into<String>>(panels: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            panels: panels.into_iter().map(Into::into).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn selected_panel(&self) -> Option<&str> {
+        self.panels.get(self.selected).map(String::as_str)
+    }
+
+    fn push(&mut self, panel: &str) {
+        self.panels.push(panel.to_string());
+        self.selected = self.panels.len() - 1;
+    }
+
+    fn remove(&mut self, panel: &str) -> bool {
+        let Some(index) = self.panels.iter().position(|p| p == panel) else {
+            return false;
+        };
+        self.panels.remove(index);
+        // keep the selected panel, or select its right neighbor when it was removed
+        if index < self.selected || self.selected >= self.panels.len() {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        true
+    }
+}
+
+/// The docked part of a [`DockLayout`]: tab groups separated by [`SplitPane`] dividers.
+///
+/// [`SplitPane`]: crate::layout::split_pane::SplitPane
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockTree {
+    Split {
+        direction: SplitDirection,
+        /// share of the space given to `first`.
+        ratio: f32,
+        first: Box<DockTree>,
+        second: Box<DockTree>,
+    },
+    Tabs(TabGroup),
+}
+
+impl DockTree {
+    pub fn tabs<S: Into<String>>(panels: impl IntoIterator<Item = S>) -> Self {
+        DockTree::Tabs(TabGroup::new(panels))
+    }
+
+    pub fn split(direction: SplitDirection, ratio: f32, first: DockTree, second: DockTree) -> Self {
+        DockTree::Split {
+            direction,
+            ratio: ratio.clamp(0.0, 1.0),
+            first: Box::new(first),
+            second: Box::new(second),
+        }
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&DockTree> {
+        match (path, self) {
+            ([], node) => Some(node),
+            ([branch, rest @ ..], DockTree::Split { first, second, .. }) => match branch {
+                0 => first.node(rest),
+                1 => second.node(rest),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut DockTree> {
+        match (path, self) {
+            ([], node) => Some(node),
+            ([branch, rest @ ..], DockTree::Split { first, second, .. }) => match branch {
+                0 => first.node_mut(rest),
+                1 => second.node_mut(rest),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Calls `f` with the path and contents of every group, in order.
+    fn for_each_group<'a>(
+        &'a self,
+        path: &mut Vec<usize>,
+        f: &mut impl FnMut(&[usize], &'a TabGroup),
+    ) {
+        match self {
+            DockTree::Split { first, second, .. } => {
+                for (branch, child) in [first, second].into_iter().enumerate() {
+                    path.push(branch);
+                    child.for_each_group(path, f);
+                    path.pop();
+                }
+            }
+            DockTree::Tabs(group) => f(path, group),
+        }
+    }
+
+    /// Removes `panel` and the groups and splits left empty. Returns what is left of the tree
+    /// and whether the panel was found.
+    fn without(self, panel: &str) -> (Option<DockTree>, bool) {
+        match self {
+            DockTree::Tabs(mut group) => {
+                let found = group.remove(panel);
+                let left = (!group.panels.is_empty()).then_some(DockTree::Tabs(group));
+                (left, found)
+            }
+            DockTree::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first, found) = first.without(panel);
+                let (second, found) = if found {
+                    (Some(*second), true)
+                } else {
+                    second.without(panel)
+                };
+                let left = match (first, second) {
+                    (Some(first), Some(second)) => Some(DockTree::Split {
+                        direction,
+                        ratio,
+                        first: Box::new(first),
+                        second: Box::new(second),
+                    }),
+                    (first, second) => first.or(second),
+                };
+                (left, found)
+            }
+        }
+    }
+}
+
+/// A tab group floating above the docked panels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatingGroup {
+    pub group: TabGroup,
+    /// Top left corner in the dock, in pixels.
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+}
+
+/// Address of a tab group in a [`DockLayout`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GroupId {
+    /// A group in the docked tree, by the panes taken from the root: `0` for the first pane of
+    /// a split and `1` for the second.
+    Docked(Vec<usize>),
+    /// A floating group by its index.
+    Floating(usize),
+}
+
+/// Where [`DockLayout::move_panel`] and [`DockLayout::insert_panel`] put a panel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockTarget {
+    /// Along an edge of the whole dock. Docking at the center adds the panel to the first
+    /// docked group; in an empty dock every zone makes the panel the only docked group.
+    Edge(DockZone),
+    /// Into or beside a group. Floating groups take panels only as tabs.
+    Group(GroupId, DockZone),
+    /// Into a new floating group.
+    Floating { position: [f32; 2], size: [f32; 2] },
+}
+
+/// Arrangement of the panels of a [`Dock`](super::Dock): a tree of docked tab groups and the
+/// floating groups above it, front-most last.
+///
+/// The layout is owned by the model and changed by the dock through its `on_change` message.
+/// It converts to a text with [`Display`] and back with [`FromStr`] to be saved between runs:
+///
+/// ```text
+/// dock (split h 0.25 (tabs 0 "files" "outline") (tabs 0 "editor"))
+/// float 200 120 320 240 (tabs 0 "search")
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DockLayout {
+    pub root: Option<DockTree>,
+    pub floating: Vec<FloatingGroup>,
+}
+
+impl DockLayout {
+    pub fn new(root: DockTree) -> Self {
+        Self {
+            root: Some(root),
+            floating: Vec::new(),
+        }
+    }
+
+    /// Adds a floating group.
+    pub fn float(mut self, group: TabGroup, position: [f32; 2], size: [f32; 2]) -> Self {
+        self.floating.push(FloatingGroup {
+            group,
+            position,
+            size,
+        });
+        self
+    }
+
+    /// Every group with its address: the docked ones in order, then the floating ones.
+    pub fn groups(&self) -> Vec<(GroupId, &TabGroup)> {
+        let mut groups = Vec::new();
+        if let Some(root) = &self.root {
+            root.for_each_group(&mut Vec::new(), &mut |path, group| {
+                groups.push((GroupId::Docked(path.to_vec()), group));
+            });
+        }
+        groups.extend(
+            self.floating
+                .iter()
+                .enumerate()
+                .map(|(index, floating)| (GroupId::Floating(index), &floating.group)),
+        );
+        groups
+    }
+
+    pub fn group(&self, id: &GroupId) -> Option<&TabGroup> {
+        match id {
+            GroupId::Docked(path) => match self.root.as_ref()?.node(path)? {
+                DockTree::Tabs(group) => Some(group),
+                DockTree::Split { .. } => None,
+            },
+            GroupId::Floating(index) => self.floating.get(*index).map(|f| &f.group),
+        }
+    }
+
+    fn group_mut(&mut self, id: &GroupId) -> Option<&mut TabGroup> {
+        match id {
+            GroupId::Docked(path) => match self.root.as_mut()?.node_mut(path)? {
+                DockTree::Tabs(group) => Some(group),
+                DockTree::Split { .. } => None,
+            },
+            GroupId::Floating(index) => self.floating.get_mut(*index).map(|f| &mut f.group),
+        }
+    }
+
+    /// The group showing `panel` and the panel's index in it.
+    pub fn find(&self, panel: &str) -> Option<(GroupId, usize)> {
+        self.groups().into_iter().find_map(|(id, group)| {
+            let index = group.panels.iter().position(|p| p == panel)?;
+            Some((id, index))
+        })
+    }
+
+    pub fn contains(&self, panel: &str) -> bool {
+        self.find(panel).is_some()
+    }
+
+    /// Shows the panel at `index` of a group.
+    pub fn select(&mut self, group: &GroupId, index: usize) {
+        if let Some(group) = self.group_mut(group)
+            && index < group.panels.len()
+        {
+            group.selected = index;
+        }
+    }
+
+    /// Moves the panel at `from` of a group to `to`, keeping the same panel selected.
+    pub fn reorder(&mut self, group: &GroupId, from: usize, to: usize) {
+        let Some(group) = self.group_mut(group) else {
+            return;
+        };
+        if from >= group.panels.len() || to >= group.panels.len() {
+            return;
+        }
+        let selected = group.panels[group.selected.min(group.panels.len() - 1)].clone();
+        let panel = group.panels.remove(from);
+        group.panels.insert(to, panel);
+        group.selected = group
+            .panels
+            .iter()
+            .position(|p| *p == selected)
+            .unwrap_or(0);
+    }
+
+    /// Sets the ratio of the split at `path` in the docked tree.
+    pub fn set_ratio(&mut self, path: &[usize], ratio: f32) {
+        if let Some(DockTree::Split { ratio: old, .. }) =
+            self.root.as_mut().and_then(|root| root.node_mut(path))
+        {
+            *old = ratio.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Moves the floating group at `index` to `position`.
+    pub fn move_floating(&mut self, index: usize, position: [f32; 2]) {
+        if let Some(floating) = self.floating.get_mut(index) {
+            floating.position = position;
+        }
+    }
+
+    /// Brings the floating group at `index` to the front.
+    pub fn raise_floating(&mut self, index: usize) {
+        if index < self.floating.len() {
+            let floating = self.floating.remove(index);
+            self.floating.push(floating);
+        }
+    }
+
+    /// Removes `panel`, dropping the groups and splits left empty. Returns whether it was
+    /// in the layout.
+    pub fn remove_panel(&mut self, panel: &str) -> bool {
+        if let Some(root) = self.root.take() {
+            let (root, found) = root.without(panel);
+            self.root = root;
+            if found {
+                return true;
+            }
+        }
+        for index in 0..self.floating.len() {
+            if self.floating[index].group.remove(panel) {
+                if self.floating[index].group.panels.is_empty() {
+                    self.floating.remove(index);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Adds `panel` at `target` and selects it. Returns `false` without changes when the
+    /// panel is already in the layout or the target group does not exist.
+    pub fn insert_panel(&mut self, panel: &str, target: &DockTarget) -> bool {
+        if self.contains(panel) {
+            return false;
+        }
+        self.insert(panel, target)
+    }
+
+    /// Moves `panel` to `target`, keeping the widgets of its content. Returns whether the
+    /// layout changed.
+    pub fn move_panel(&mut self, panel: &str, target: &DockTarget) -> bool {
+        let Some((source, _)) = self.find(panel) else {
+            return false;
+        };
+        if let DockTarget::Group(group, DockZone::Center) = target
+            && *group == source
+        {
+            return false;
+        }
+        // the target is addressed in the current layout, so the panel is put there before it
+        // is removed from its old place, which may collapse groups and splits
+        if !self.insert(MOVING, target) {
+            return false;
+        }
+        self.remove_panel(panel);
+        if let Some((group, index)) = self.find(MOVING)
+            && let Some(group) = self.group_mut(&group)
+        {
+            group.panels[index] = panel.to_string();
+        }
+        true
+    }
+
+    fn insert(&mut self, panel: &str, target: &DockTarget) -> bool {
+        match target {
+            DockTarget::Edge(zone) => match (self.root.take(), zone.split()) {
+                (None, _) => self.root = Some(DockTree::tabs([panel])),
+                (Some(mut root), None) => {
+                    let mut first = None;
+                    root.for_each_group(&mut Vec::new(), &mut |path, _| {
+                        first.get_or_insert_with(|| path.to_vec());
+                    });
+                    if let Some(DockTree::Tabs(group)) = first.and_then(|path| root.node_mut(&path))
+                    {
+                        group.push(panel);
+                    }
+                    self.root = Some(root);
+                }
+                (Some(root), Some((direction, panel_first))) => {
+                    let new = DockTree::tabs([panel]);
+                    self.root = Some(if panel_first {
+                        DockTree::split(direction, EDGE_RATIO, new, root)
+                    } else {
+                        DockTree::split(direction, 1.0 - EDGE_RATIO, root, new)
+                    });
+                }
+            },
+            DockTarget::Group(id, zone) => match (id, zone.split()) {
+                (GroupId::Docked(path), Some((direction, panel_first))) => {
+                    let Some(node) = self.root.as_mut().and_then(|root| root.node_mut(path)) else {
+                        return false;
+                    };
+                    if !matches!(node, DockTree::Tabs(_)) {
+                        return false;
+                    }
+                    let new = DockTree::tabs([panel]);
+                    let old = std::mem::replace(node, DockTree::tabs([panel]));
+                    *node = if panel_first {
+                        DockTree::split(direction, SIDE_RATIO, new, old)
+                    } else {
+                        DockTree::split(direction, SIDE_RATIO, old, new)
+                    };
+                }
+                _ => {
+                    let Some(group) = self.group_mut(id) else {
+                        return false;
+                    };
+                    group.push(panel);
+                }
+            },
+            DockTarget::Floating { position, size } => self.floating.push(FloatingGroup {
+                group: TabGroup::new([panel]),
+                position: *position,
+                size: *size,
+            }),
+        }
+        true
+    }
+}
+
+// MARK: Text format
+
+impl Display for DockLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dock ")?;
+        match &self.root {
+            Some(root) => write_tree(f, root)?,
+            None => write!(f, "-")?,
+        }
+        for floating in &self.floating {
+            let [x, y] = floating.position;
+            let [width, height] = floating.size;
+            write!(f, "\nfloat {x} {y} {width} {height} ")?;
+            write_group(f, &floating.group)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_tree(f: &mut fmt::Formatter<'_>, tree: &DockTree) -> fmt::Result {
+    match tree {
+        DockTree::Split {
+            direction,
+            ratio,
+            first,
+            second,
+        } => {
+            let direction = match direction {
+                SplitDirection::Horizontal => "h",
+                SplitDirection::Vertical => "v",
+            };
+            write!(f, "(split {direction} {ratio} ")?;
+            write_tree(f, first)?;
+            write!(f, " ")?;
+            write_tree(f, second)?;
+            write!(f, ")")
+        }
+        DockTree::Tabs(group) => write_group(f, group),
+    }
+}
+
+fn write_group(f: &mut fmt::Formatter<'_>, group: &TabGroup) -> fmt::Result {
+    write!(f, "(tabs {}", group.selected)?;
+    for panel in &group.panels {
+        write!(f, " \"")?;
+        for c in panel.chars() {
+            if matches!(c, '"' | '\\') {
+                write!(f, "\\")?;
+            }
+            write!(f, "{c}")?;
+        }
+        write!(f, "\"")?;
+    }
+    write!(f, ")")
+}
+
+/// Why a text is not a [`DockLayout`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DockLayoutError {
+    /// The text ended before the layout did.
+    UnexpectedEnd,
+    /// A word that does not belong where it is, which is given.
+    Unexpected(String),
+    /// A panel name is missing its closing quote.
+    UnterminatedString,
+    /// A group has no panels or selects one it does not have.
+    InvalidGroup,
+    /// A panel appears more than once, which is given.
+    DuplicatePanel(String),
+}
+
+impl Display for DockLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DockLayoutError::UnexpectedEnd => write!(f, "unexpected end of the dock layout"),
+            DockLayoutError::Unexpected(token) => write!(f, "unexpected `{token}`"),
+            DockLayoutError::UnterminatedString => write!(f, "unterminated panel name"),
+            DockLayoutError::InvalidGroup => write!(f, "tab group without panels to select"),
+            DockLayoutError::DuplicatePanel(panel) => {
+                write!(f, "panel \"{panel}\" appears more than once")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DockLayoutError {}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    Name(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, DockLayoutError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next().ok_or(DockLayoutError::UnterminatedString)? {
+                        '"' => break,
+                        '\\' => name.push(chars.next().ok_or(DockLayoutError::UnterminatedString)?),
+                        c => name.push(c),
+                    }
+                }
+                tokens.push(Token::Name(name));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek()
+                    && !c.is_whitespace()
+                    && !matches!(c, '(' | ')' | '"')
+                {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: std::vec::IntoIter<Token>,
+    panels: Vec<String>,
+}
+
+impl Parser {
+    fn next(&mut self) -> Result<Token, DockLayoutError> {
+        self.tokens.next().ok_or(DockLayoutError::UnexpectedEnd)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), DockLayoutError> {
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err(unexpected(token))
+        }
+    }
+
+    fn word(&mut self) -> Result<String, DockLayoutError> {
+        match self.next()? {
+            Token::Word(word) => Ok(word),
+            token => Err(unexpected(token)),
+        }
+    }
+
+    fn number<N: FromStr>(&mut self) -> Result<N, DockLayoutError> {
+        let word = self.word()?;
+        word.parse().map_err(|_| DockLayoutError::Unexpected(word))
+    }
+
+    fn tree(&mut self) -> Result<DockTree, DockLayoutError> {
+        self.expect(Token::Open)?;
+        match self.word()?.as_str() {
+            "split" => {
+                let direction = match self.word()?.as_str() {
+                    "h" => SplitDirection::Horizontal,
+                    "v" => SplitDirection::Vertical,
+                    word => return Err(DockLayoutError::Unexpected(word.to_string())),
+                };
+                let ratio: f32 = self.number()?;
+                if !(0.0..=1.0).contains(&ratio) {
+                    return Err(DockLayoutError::Unexpected(ratio.to_string()));
+                }
+                let first = self.tree()?;
+                let second = self.tree()?;
+                self.expect(Token::Close)?;
+                Ok(DockTree::split(direction, ratio, first, second))
+            }
+            "tabs" => self.group_body().map(DockTree::Tabs),
+            word => Err(DockLayoutError::Unexpected(word.to_string())),
+        }
+    }
+
+    fn group(&mut self) -> Result<TabGroup, DockLayoutError> {
+        self.expect(Token::Open)?;
+        match self.word()?.as_str() {
+            "tabs" => self.group_body(),
+            word => Err(DockLayoutError::Unexpected(word.to_string())),
+        }
+    }
+
+    // the rest of a group after `(tabs`
+    fn group_body(&mut self) -> Result<TabGroup, DockLayoutError> {
+        let selected = self.number()?;
+        let mut panels = Vec::new();
+        loop {
+            match self.next()? {
+                Token::Close => break,
+                Token::Name(panel) => {
+                    if self.panels.contains(&panel) {
+                        return Err(DockLayoutError::DuplicatePanel(panel));
+                    }
+                    self.panels.push(panel.clone());
+                    panels.push(panel);
+                }
+                token => return Err(unexpected(token)),
+            }
+        }
+        if selected >= panels.len() {
+            return Err(DockLayoutError::InvalidGroup);
+        }
+        Ok(TabGroup { panels, selected })
+    }
+}
+
+fn unexpected(token: Token) -> DockLayoutError {
+    DockLayoutError::Unexpected(match token {
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+        Token::Word(word) => word,
+        Token::Name(name) => format!("\"{name}\""),
+    })
+}
+
+impl FromStr for DockLayout {
+    type Err = DockLayoutError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?.into_iter(),
+            panels: Vec::new(),
+        };
+        parser.expect(Token::Word("dock".to_string()))?;
+        let root = match parser.tokens.as_slice().first() {
+            Some(Token::Word(word)) if word == "-" => {
+                parser.next()?;
+                None
+            }
+            _ => Some(parser.tree()?),
+        };
+        let mut floating = Vec::new();
+        while let Some(token) = parser.tokens.next() {
+            if token != Token::Word("float".to_string()) {
+                return Err(unexpected(token));
+            }
+            let position = [parser.number()?, parser.number()?];
+            let size = [parser.number()?, parser.number()?];
+            floating.push(FloatingGroup {
+                group: parser.group()?,
+                position,
+                size,
+            });
+        }
+        Ok(DockLayout { root, floating })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn ide() -> DockLayout {
+        DockLayout::new(DockTree::split(
+            SplitDirection::Horizontal,
+            0.25,
+            DockTree::tabs(["files", "outline"]),
+            DockTree::split(
+                SplitDirection::Vertical,
+                0.75,
+                DockTree::tabs(["editor"]),
+                DockTree::tabs(["terminal"]),
+            ),
+        ))
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let layout = ide().float(TabGroup::new(["se\"arch"]), [200.0, 120.5], [320.0, 240.0]);
+        let text = layout.to_string();
+        assert_eq!(
+            text,
+            "dock (split h 0.25 (tabs 0 \"files\" \"outline\") \
+             (split v 0.75 (tabs 0 \"editor\") (tabs 0 \"terminal\")))\n\
+             float 200 120.5 320 240 (tabs 0 \"se\\\"arch\")"
+        );
+        assert_eq!(text.parse::<DockLayout>().unwrap(), layout);
+        assert_eq!(
+            "dock -".parse::<DockLayout>().unwrap(),
+            DockLayout::default()
+        );
+
+        assert_eq!(
+            "dock (tabs 0 \"a\" \"a\")".parse::<DockLayout>(),
+            Err(DockLayoutError::DuplicatePanel("a".to_string()))
+        );
+        assert_eq!(
+            "dock (tabs 1 \"a\")".parse::<DockLayout>(),
+            Err(DockLayoutError::InvalidGroup)
+        );
+        assert_eq!(
+            "dock (split x 0.5".parse::<DockLayout>(),
+            Err(DockLayoutError::Unexpected("x".to_string()))
+        );
+        assert_eq!(
+            "dock (tabs 0 \"a".parse::<DockLayout>(),
+            Err(DockLayoutError::UnterminatedString)
+        );
+    }
+
+    #[test]
+    fn moving_a_panel_collapses_what_it_leaves_empty() {
+        let mut layout = ide();
+        // the terminal's group and its split disappear
+        assert!(layout.move_panel(
+            "terminal",
+            &DockTarget::Group(GroupId::Docked(vec![0]), DockZone::Center)
+        ));
+        assert_eq!(
+            layout.root,
+            Some(DockTree::split(
+                SplitDirection::Horizontal,
+                0.25,
+                DockTree::Tabs(TabGroup {
+                    panels: vec!["files".into(), "outline".into(), "terminal".into()],
+                    selected: 2,
+                }),
+                DockTree::tabs(["editor"]),
+            ))
+        );
+
+        assert!(layout.move_panel("outline", &DockTarget::Edge(DockZone::Right)));
+        assert_eq!(layout.find("outline"), Some((GroupId::Docked(vec![1]), 0)));
+        assert_eq!(
+            layout
+                .group(&GroupId::Docked(vec![0, 0]))
+                .unwrap()
+                .selected_panel(),
+            Some("terminal")
+        );
+
+        assert!(layout.move_panel(
+            "editor",
+            &DockTarget::Floating {
+                position: [10.0, 10.0],
+                size: [100.0, 100.0],
+            }
+        ));
+        assert_eq!(layout.find("editor"), Some((GroupId::Floating(0), 0)));
+        assert_eq!(
+            layout.root,
+            Some(DockTree::split(
+                SplitDirection::Horizontal,
+                0.75,
+                DockTree::Tabs(TabGroup {
+                    panels: vec!["files".into(), "terminal".into()],
+                    selected: 1,
+                }),
+                DockTree::tabs(["outline"]),
+            ))
+        );
+
+        // docking beside a group splits it; the floating group is dropped
+        assert!(layout.move_panel(
+            "editor",
+            &DockTarget::Group(GroupId::Docked(vec![1]), DockZone::Top)
+        ));
+        assert!(layout.floating.is_empty());
+        assert_eq!(
+            layout.find("editor"),
+            Some((GroupId::Docked(vec![1, 0]), 0))
+        );
+        assert_eq!(
+            layout.find("outline"),
+            Some((GroupId::Docked(vec![1, 1]), 0))
+        );
+    }
+
+    #[test]
+    fn removing_every_panel_empties_the_dock() {
+        let mut layout = ide();
+        for panel in ["files", "outline", "editor", "terminal"] {
+            assert!(layout.remove_panel(panel));
+        }
+        assert!(!layout.remove_panel("files"));
+        assert_eq!(layout, DockLayout::default());
+
+        assert!(layout.insert_panel("editor", &DockTarget::Edge(DockZone::Left)));
+        assert_eq!(layout, DockLayout::new(DockTree::tabs(["editor"])));
+        assert!(!layout.insert_panel("editor", &DockTarget::Edge(DockZone::Left)));
+    }
+
+    #[test]
+    fn reordering_keeps_the_selected_panel() {
+        let mut layout = DockLayout::new(DockTree::tabs(["a", "b", "c"]));
+        let group = GroupId::Docked(Vec::new());
+        layout.select(&group, 1);
+        layout.reorder(&group, 0, 2);
+        let group = layout.group(&group).unwrap();
+        assert_eq!(group.panels, ["b", "c", "a"]);
+        assert_eq!(group.selected_panel(), Some("b"));
+    }
+}
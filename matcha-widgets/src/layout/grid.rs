@@ -5,7 +5,7 @@ use matcha_core::ui::widget::InvalidationHandle;
 use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, Widget, WidgetFrame},
+    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame},
 };
 use renderer::render_node::RenderNode;
 
@@ -18,6 +18,7 @@ use crate::types::{
 
 pub struct Grid<T: Send + 'static> {
     label: Option<String>,
+    layout_style: LayoutStyle,
     template_columns: Vec<GrowSize>,
     template_rows: Vec<GrowSize>,
     gap_columns: Size,
@@ -35,6 +36,7 @@ impl<T: Send + 'static> Grid<T> {
     pub fn new() -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             template_columns: Vec::new(),
             template_rows: Vec::new(),
             gap_columns: Size::px(0.0),
@@ -43,6 +45,24 @@ impl<T: Send + 'static> Grid<T> {
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    /// Clips the widget and its children to its border box with corners rounded by
+    /// `radius`, see [`LayoutStyle::clip_radius`]. Call after `layout`, which replaces it.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.layout_style = self.layout_style.clip_radius(radius);
+        self
+    }
+
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.layout_style = self.layout_style.elevation(level);
+        self
+    }
+
     pub fn label(mut self, label: Option<String>) -> Self {
         self.label = label;
         self
@@ -99,19 +119,26 @@ impl<T: Send + 'static> Dom<T> for Grid<T> {
             child_ids.push(index as u128);
         }
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children_and_settings,
-            child_ids,
-            GridNode {
-                template_columns: self.template_columns.clone(),
-                template_rows: self.template_rows.clone(),
-                gap_columns: self.gap_columns.clone(),
-                gap_rows: self.gap_rows.clone(),
-                column_ranges: Vec::new(),
-                row_ranges: Vec::new(),
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                GridNode {
+                    template_columns: self.template_columns.clone(),
+                    template_rows: self.template_rows.clone(),
+                    gap_columns: self.gap_columns.clone(),
+                    gap_rows: self.gap_rows.clone(),
+                    column_ranges: Vec::new(),
+                    row_ranges: Vec::new(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -0,0 +1,201 @@
+use matcha_core::context::WidgetContext;
+use matcha_core::{
+    device_input::DeviceInput,
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, HitShape, InvalidationHandle, LayoutStyle,
+        Widget, WidgetFrame,
+    },
+};
+use renderer::render_node::RenderNode;
+
+/// Limits where its content can be hit to a [`HitShape`], e.g. the circle of a round
+/// button or the opaque pixels of an image.
+///
+/// Outside the shape the content is not hit, and it receives pointer input as if the pointer
+/// were outside of it, so it neither hovers nor takes clicks there. The shape covers the
+/// bounds of the content and follows its transforms.
+pub struct HitArea<T>
+where
+    T: Send + 'static,
+{
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    shape: HitShape,
+    content: Option<Box<dyn Dom<T>>>,
+}
+
+impl<T> HitArea<T>
+where
+    T: Send + 'static,
+{
+    pub fn new(shape: HitShape) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            shape,
+            content: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn shape(mut self, shape: HitShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn content(mut self, content: impl Dom<T>) -> Self {
+        self.content = Some(Box::new(content));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> Dom<T> for HitArea<T>
+where
+    T: Send + 'static,
+{
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        let mut children_and_settings = Vec::new();
+        let mut child_ids = Vec::new();
+
+        if let Some(content_widget) = self.content.as_ref().map(|c| c.build_widget_tree()) {
+            children_and_settings.push((content_widget, ()));
+            child_ids.push(0);
+        }
+
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                HitAreaNode {
+                    shape: self.shape.clone(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+pub struct HitAreaNode {
+    shape: HitShape,
+}
+
+impl<T> Widget<HitArea<T>, T, ()> for HitAreaNode
+where
+    T: Send + 'static,
+{
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a HitArea<T>,
+        _cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        // the shape is only used for input, nothing to redraw
+        self.shape = dom.shape.clone();
+
+        dom.content
+            .as_ref()
+            .map(|c| (c.as_ref(), (), 0))
+            .into_iter()
+            .collect()
+    }
+
+    fn device_input(
+        &mut self,
+        _bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let (child, _, arrangement) = children.first_mut()?;
+        let child_event = event.transform(arrangement.affine);
+        let hit = child_event
+            .mouse_position()
+            .is_some_and(|position| self.shape.contains(position, arrangement.size));
+        if hit {
+            child.device_input(&child_event, ctx)
+        } else {
+            child.device_input(&child_event.with_pointer_outside(), ctx)
+        }
+    }
+
+    fn is_inside(
+        &self,
+        _bounds: [f32; 2],
+        position: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        ctx: &WidgetContext,
+    ) -> bool {
+        let Some((child, _, arrangement)) = children.first() else {
+            return false;
+        };
+        let local_position = arrangement.to_local(position);
+        self.shape.contains(local_position, arrangement.size)
+            && child.is_inside(local_position, ctx)
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        if let Some((child, _)) = children.first() {
+            child.measure(constraints, ctx)
+        } else {
+            [0.0, 0.0]
+        }
+    }
+
+    fn baseline(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        let (child, _) = children.first()?;
+        child.baseline(constraints, ctx)
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        if children.is_empty() {
+            vec![]
+        } else {
+            vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+        }
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        if let Some((child, _, arrangement)) = children.first() {
+            return RenderNode::new().add_child(child.render(background, ctx), arrangement.affine);
+        }
+        RenderNode::default()
+    }
+}
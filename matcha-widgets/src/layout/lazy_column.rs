@@ -0,0 +1,423 @@
+use std::sync::Arc;
+
+use matcha_core::context::WidgetContext;
+use matcha_core::metrics::{Arrangement, Constraints};
+use nalgebra::Matrix4;
+
+use matcha_core::ui::widget::InvalidationHandle;
+use matcha_core::{
+    device_input::DeviceInput,
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, ChildFrameLinker, Dom, LayoutStyle,
+        OwnedChildrenUpdate, PrepareFuture, Widget, WidgetFrame,
+    },
+};
+use renderer::render_node::RenderNode;
+
+use super::virtual_rows::{self, VirtualRows};
+
+type ItemBuilder<T> = Arc<dyn Fn(usize) -> Box<dyn Dom<T>> + Send + Sync>;
+
+// MARK: DOM
+
+/// Vertical list that only builds the items in view.
+///
+/// Every item has the same height, so the visible range is known without measuring. Items
+/// are built from `builder` when they scroll into view (plus `overscan` items on each side).
+/// Frames of items that leave it are updated in the background to show the items next to
+/// the kept ones, and are reused when those scroll in. The list scrolls with the mouse wheel
+/// and should be given a bounded height.
+///
+/// Items are rebuilt from `builder` whenever the list is updated, so they should be plain
+/// views of the model; `Component`s inside items do not receive model update notifications.
+pub struct LazyColumn<T>
+where
+    T: Send + 'static,
+{
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    item_count: usize,
+    item_height: f32,
+    overscan: usize,
+    builder: ItemBuilder<T>,
+}
+
+impl<T> LazyColumn<T>
+where
+    T: Send + 'static,
+{
+    pub fn new(
+        item_count: usize,
+        item_height: f32,
+        builder: impl Fn(usize) -> Box<dyn Dom<T>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            item_count,
+            item_height: item_height.max(0.0),
+            overscan: 2,
+            builder: Arc::new(builder),
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Number of items kept alive above and below the visible ones. Default is 2.
+    pub fn overscan(mut self, overscan: usize) -> Self {
+        self.overscan = overscan;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> Dom<T> for LazyColumn<T>
+where
+    T: Send + 'static,
+{
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                LazyColumnNode::new(self),
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct LazyColumnNode<T>
+where
+    T: Send + 'static,
+{
+    item_count: usize,
+    item_height: f32,
+    overscan: usize,
+    scroll_offset: f32,
+    /// one cell per row: the item.
+    items: VirtualRows<T>,
+}
+
+impl<T> LazyColumnNode<T>
+where
+    T: Send + 'static,
+{
+    fn new(dom: &LazyColumn<T>) -> Self {
+        let mut items = VirtualRows::new();
+        items.set_builders(vec![dom.builder.clone()], dom.item_count);
+        Self {
+            item_count: dom.item_count,
+            item_height: dom.item_height,
+            overscan: dom.overscan,
+            scroll_offset: 0.0,
+            items,
+        }
+    }
+
+    fn max_scroll_offset(&self, viewport_height: f32) -> f32 {
+        (self.item_count as f32 * self.item_height - viewport_height).max(0.0)
+    }
+
+    /// The scroll offset limited to the current content, which may have shrunk since scrolling.
+    fn clamped_scroll_offset(&self, viewport_height: f32) -> f32 {
+        self.scroll_offset
+            .min(self.max_scroll_offset(viewport_height))
+    }
+
+    /// Indices of the items that intersect the viewport, extended by `overscan`.
+    fn visible_range(&self, viewport_height: f32) -> std::ops::Range<usize> {
+        virtual_rows::visible_range(
+            self.item_count,
+            self.item_height,
+            self.clamped_scroll_offset(viewport_height),
+            viewport_height,
+            self.overscan,
+        )
+    }
+
+    /// The on-screen part of an item placed at `arrangement`, or `None` when it is off-screen.
+    fn item_visible_rect(
+        &self,
+        arrangement: &Arrangement,
+        bounds: [f32; 2],
+    ) -> Option<[[f32; 2]; 2]> {
+        let top = arrangement.affine[(1, 3)];
+        let visible_rect = [
+            [0.0, (-top).max(0.0)],
+            [bounds[0], (bounds[1] - top).min(self.item_height)],
+        ];
+        (visible_rect[0][1] < visible_rect[1][1] && bounds[0] > 0.0).then_some(visible_rect)
+    }
+
+    /// Brings the built items in line with the visible range and lays them out.
+    fn sync_items(&self, bounds: [f32; 2], ctx: &WidgetContext) {
+        let offset = self.clamped_scroll_offset(bounds[1]);
+        self.items.layout(
+            self.visible_range(bounds[1]),
+            |index, _| {
+                let y = index as f32 * self.item_height - offset;
+                Arrangement::new(
+                    [bounds[0], self.item_height],
+                    Matrix4::new_translation(&nalgebra::Vector3::new(0.0, y, 0.0)),
+                )
+            },
+            ctx,
+        );
+    }
+}
+
+impl<T> Widget<LazyColumn<T>, T, ()> for LazyColumnNode<T>
+where
+    T: Send + 'static,
+{
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a LazyColumn<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.item_count = dom.item_count;
+        self.item_height = dom.item_height;
+        self.overscan = dom.overscan;
+        self.items
+            .set_builders(vec![dom.builder.clone()], dom.item_count);
+
+        if let Some(handle) = cache_invalidator {
+            handle.relayout_next_frame();
+        }
+
+        vec![]
+    }
+
+    fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+        self.items.link(linker);
+    }
+
+    fn update_owned_children(&mut self) -> Option<OwnedChildrenUpdate<'_>> {
+        self.items.update_live()
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let inside = event.mouse_position().is_some_and(|position| {
+            0.0 <= position[0]
+                && position[0] <= bounds[0]
+                && 0.0 <= position[1]
+                && position[1] <= bounds[1]
+        });
+
+        if inside && let Some(delta) = event.on_scroll(|delta| delta) {
+            let offset = (self.clamped_scroll_offset(bounds[1]) - delta[1]).max(0.0);
+            if offset != self.scroll_offset {
+                self.scroll_offset = offset;
+                cache_invalidator.relayout_next_frame();
+            }
+            return None;
+        }
+
+        // items scrolled partly out of view do not see the pointer outside the viewport
+        let outside;
+        let event = if inside {
+            event
+        } else {
+            outside = event.with_pointer_outside();
+            &outside
+        };
+
+        self.items.device_input(event, ctx)
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        // only the built items can be asked for their width
+        let item_constraints = Constraints::new(
+            [0.0, constraints.max_width()],
+            [self.item_height, self.item_height],
+        );
+        let width = self
+            .items
+            .live()
+            .iter()
+            .flat_map(|row| &row.cells)
+            .map(|(frame, _)| frame.measure(&item_constraints, ctx)[0])
+            .fold(constraints.min_width(), f32::max);
+
+        [
+            width.min(constraints.max_width()),
+            (self.item_count as f32 * self.item_height)
+                .clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        self.sync_items(bounds, ctx);
+
+        // items are owned by this widget, not by the frame
+        vec![]
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        for (frame, arrangement) in self.items.live().iter().flat_map(|row| &row.cells) {
+            let item_node = frame.render(background, ctx);
+            render_node = render_node.add_child(item_node, arrangement.affine);
+        }
+
+        // items at the edges are only partly in view
+        render_node.with_clip(bounds)
+    }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.items.update_gpu_device(device, queue);
+    }
+
+    fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        self.items.prepare(
+            self.visible_range(bounds[1]),
+            self.item_count,
+            self.overscan,
+            |arrangement| self.item_visible_rect(arrangement, bounds),
+            ctx,
+        )
+    }
+
+    fn update_lifecycle(&mut self, bounds: [f32; 2], ctx: &WidgetContext) {
+        self.items.update_lifecycle(
+            |arrangement| self.item_visible_rect(arrangement, bounds),
+            ctx,
+        );
+    }
+
+    fn on_visibility_changed(&mut self, visible: bool, ctx: &WidgetContext) {
+        // neither `update_lifecycle` nor `prepare` is called while the list is hidden, so hide
+        // the items here.
+        if !visible {
+            self.items.hide(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use matcha_core::test_kit::TestContext;
+
+    use super::*;
+    use crate::layout::space::Space;
+
+    const BOUNDS: [f32; 2] = [100.0, 30.0];
+
+    fn node(item_count: usize, overscan: usize) -> LazyColumnNode<()> {
+        LazyColumnNode::new(
+            &LazyColumn::new(item_count, 10.0, |_| Space::new(None)).overscan(overscan),
+        )
+    }
+
+    /// Index and frame address of every built item.
+    fn live_frames(node: &LazyColumnNode<()>) -> Vec<(usize, *const ())> {
+        node.items
+            .live()
+            .iter()
+            .map(|row| {
+                (
+                    row.index,
+                    &*row.cells[0].0 as *const dyn AnyWidgetFrame<()> as *const (),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_visible_range() {
+        let mut list = node(100, 2);
+        assert_eq!(list.visible_range(35.0), 0..6);
+
+        list.scroll_offset = 200.0;
+        assert_eq!(list.visible_range(35.0), 18..26);
+
+        // clamped to the end of the list, which may have shrunk since scrolling
+        list.scroll_offset = 10_000.0;
+        assert_eq!(list.visible_range(35.0), 94..100);
+
+        let mut list = node(100, 0);
+        list.scroll_offset = 5.0;
+        assert_eq!(list.visible_range(30.0), 0..4);
+
+        assert_eq!(node(0, 2).visible_range(35.0), 0..0);
+    }
+
+    #[tokio::test]
+    async fn test_pooled_frames_are_reused_across_a_scroll() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let mut list = node(100, 0);
+
+        list.sync_items(BOUNDS, ctx);
+        let first = live_frames(&list);
+        assert_eq!(
+            first.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+
+        // the first screen goes to the pool
+        list.scroll_offset = 30.0;
+        list.sync_items(BOUNDS, ctx);
+        assert_eq!(
+            live_frames(&list)
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>(),
+            [3, 4, 5]
+        );
+
+        // pooled frames are updated for the items next to the visible ones
+        list.prepare(BOUNDS, ctx).unwrap().await;
+
+        list.scroll_offset = 40.0;
+        list.sync_items(BOUNDS, ctx);
+        let scrolled = live_frames(&list);
+        assert_eq!(
+            scrolled.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            [4, 5, 6]
+        );
+        assert_eq!(scrolled[2].1, first[1].1);
+    }
+}
@@ -4,7 +4,10 @@ use matcha_core::context::WidgetContext;
 use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, Widget, WidgetFrame},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, LayoutStyle, Widget,
+        WidgetFrame,
+    },
 };
 use renderer::render_node::RenderNode;
 
@@ -13,6 +16,7 @@ where
     T: Send + 'static,
 {
     label: Option<String>,
+    layout_style: LayoutStyle,
     top: f32,
     right: f32,
     bottom: f32,
@@ -27,6 +31,7 @@ where
     pub fn new() -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             top: 0.0,
             right: 0.0,
             bottom: 0.0,
@@ -35,6 +40,24 @@ where
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    /// Clips the widget and its children to its border box with corners rounded by
+    /// `radius`, see [`LayoutStyle::clip_radius`]. Call after `layout`, which replaces it.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.layout_style = self.layout_style.clip_radius(radius);
+        self
+    }
+
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.layout_style = self.layout_style.elevation(level);
+        self
+    }
+
     pub fn top(mut self, top: f32) -> Self {
         self.top = top;
         self
@@ -75,17 +98,24 @@ where
             child_ids.push(0);
         }
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children_and_settings,
-            child_ids,
-            PaddingNode {
-                top: self.top,
-                right: self.right,
-                bottom: self.bottom,
-                left: self.left,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                PaddingNode {
+                    top: self.top,
+                    right: self.right,
+                    bottom: self.bottom,
+                    left: self.left,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
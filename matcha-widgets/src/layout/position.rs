@@ -4,7 +4,10 @@ use matcha_core::context::WidgetContext;
 use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, Widget, WidgetFrame},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, LayoutStyle, Widget,
+        WidgetFrame,
+    },
 };
 use renderer::render_node::RenderNode;
 
@@ -12,6 +15,7 @@ use renderer::render_node::RenderNode;
 
 pub struct Position<T: Send + 'static> {
     label: Option<String>,
+    layout_style: LayoutStyle,
     left: Option<f32>,
     top: Option<f32>,
     right: Option<f32>,
@@ -23,6 +27,7 @@ impl<T: Send + 'static> Position<T> {
     pub fn new() -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             left: None,
             top: None,
             right: None,
@@ -31,6 +36,24 @@ impl<T: Send + 'static> Position<T> {
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    /// Clips the widget and its children to its border box with corners rounded by
+    /// `radius`, see [`LayoutStyle::clip_radius`]. Call after `layout`, which replaces it.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.layout_style = self.layout_style.clip_radius(radius);
+        self
+    }
+
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.layout_style = self.layout_style.elevation(level);
+        self
+    }
+
     pub fn left(mut self, left: f32) -> Self {
         self.left = Some(left);
         self
@@ -68,17 +91,24 @@ impl<T: Send + 'static> Dom<T> for Position<T> {
             child_ids.push(0);
         }
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children_and_settings,
-            child_ids,
-            PositionNode {
-                left: self.left,
-                top: self.top,
-                right: self.right,
-                bottom: self.bottom,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                PositionNode {
+                    left: self.left,
+                    top: self.top,
+                    right: self.right,
+                    bottom: self.bottom,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -1,8 +1,13 @@
+use std::hash::Hash;
+
 use matcha_core::context::WidgetContext;
 use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, Widget, WidgetFrame},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, LayoutStyle, Widget,
+        WidgetFrame, keyed::child_id,
+    },
 };
 use renderer::render_node::RenderNode;
 
@@ -15,14 +20,23 @@ use crate::types::{
 
 // MARK: DOM
 
+/// Per-child settings of [`Row`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RowChildSetting {
+    /// Overrides the row's `align_items` for this child.
+    pub align_self: Option<AlignItems>,
+}
+
 pub struct Row<T>
 where
     T: Send + 'static,
 {
     label: Option<String>,
+    layout_style: LayoutStyle,
     justify_content: JustifyContent,
     align_items: AlignItems,
-    items: Vec<Box<dyn Dom<T>>>,
+    /// children with their settings and ids.
+    items: Vec<(Box<dyn Dom<T>>, RowChildSetting, u128)>,
 }
 
 impl<T> Row<T>
@@ -32,6 +46,7 @@ where
     pub fn new(label: Option<&str>) -> Self {
         Self {
             label: label.map(String::from),
+            layout_style: LayoutStyle::default(),
             justify_content: JustifyContent::FlexStart {
                 gap: GrowSize::Fixed(Size::px(0.0)),
             },
@@ -40,6 +55,24 @@ where
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    /// Clips the widget and its children to its border box with corners rounded by
+    /// `radius`, see [`LayoutStyle::clip_radius`]. Call after `layout`, which replaces it.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.layout_style = self.layout_style.clip_radius(radius);
+        self
+    }
+
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.layout_style = self.layout_style.elevation(level);
+        self
+    }
+
     pub fn justify_content(mut self, justify_content: JustifyContent) -> Self {
         self.justify_content = justify_content;
         self
@@ -50,8 +83,52 @@ where
         self
     }
 
+    /// Push a child identified by its position.
     pub fn push(mut self, item: impl Dom<T>) -> Self {
-        self.items.push(Box::new(item));
+        let id = self.items.len() as u128;
+        self.items
+            .push((Box::new(item), RowChildSetting::default(), id));
+        self
+    }
+
+    /// Push a child whose cross-axis alignment overrides the row's `align_items`.
+    pub fn push_with_align(mut self, item: impl Dom<T>, align_self: AlignItems) -> Self {
+        let id = self.items.len() as u128;
+        self.items.push((
+            Box::new(item),
+            RowChildSetting {
+                align_self: Some(align_self),
+            },
+            id,
+        ));
+        self
+    }
+
+    /// Push a child identified by `key`, so it keeps its widget when other children are
+    /// inserted, removed or reordered. Keys must be unique within the row.
+    pub fn push_keyed<K: Hash + ?Sized>(mut self, key: &K, item: impl Dom<T>) -> Self {
+        self.items
+            .push((Box::new(item), RowChildSetting::default(), child_id(key)));
+        self
+    }
+
+    /// Push one keyed child per element of `iter`.
+    pub fn children_keyed<I, K, D>(
+        mut self,
+        iter: I,
+        key: impl Fn(&I::Item) -> K,
+        dom: impl Fn(I::Item) -> D,
+    ) -> Self
+    where
+        I: IntoIterator,
+        K: Hash,
+        D: Dom<T>,
+    {
+        for item in iter {
+            let id = child_id(&key(&item));
+            self.items
+                .push((Box::new(dom(item)), RowChildSetting::default(), id));
+        }
         self
     }
 }
@@ -65,21 +142,28 @@ where
         let mut children_and_settings = Vec::new();
         let mut child_ids = Vec::new();
 
-        for (index, item) in self.items.iter().enumerate() {
+        for (item, setting, id) in &self.items {
             let child_widget = item.build_widget_tree();
-            children_and_settings.push((child_widget, ()));
-            child_ids.push(index as u128);
+            children_and_settings.push((child_widget, *setting));
+            child_ids.push(*id);
         }
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children_and_settings,
-            child_ids,
-            RowNode {
-                justify_content: self.justify_content.clone(),
-                align_items: self.align_items,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                RowNode {
+                    justify_content: self.justify_content.clone(),
+                    align_items: self.align_items,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -91,6 +175,59 @@ pub struct RowNode {
 }
 
 impl RowNode {
+    fn child_align(&self, setting: &RowChildSetting) -> AlignItems {
+        setting.align_self.unwrap_or(self.align_items)
+    }
+
+    /// Collect the baselines of baseline-aligned children.
+    ///
+    /// Returns the baseline of each child (`None` if the child is not baseline-aligned or does not
+    /// report one) and the shared baseline the children are aligned to.
+    fn baselines<T: 'static>(
+        &self,
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
+        constraints: &Constraints,
+        ctx: &WidgetContext,
+    ) -> (Vec<Option<f32>>, Option<f32>) {
+        let baselines: Vec<Option<f32>> = children
+            .iter()
+            .map(|(child, setting)| {
+                if self.child_align(setting) == AlignItems::Baseline {
+                    child.baseline(constraints, ctx)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let shared = baselines.iter().flatten().copied().reduce(f32::max);
+
+        (baselines, shared)
+    }
+
+    /// Sum the children's intrinsic widths and add the gaps that do not depend on free space.
+    fn intrinsic_width_with_gaps(
+        &self,
+        child_widths: impl Iterator<Item = f32>,
+        height: f32,
+        ctx: &WidgetContext,
+    ) -> f32 {
+        let (total_child_width, child_count) =
+            child_widths.fold((0.0f32, 0usize), |(total, count), w| (total + w, count + 1));
+
+        // No free space is available when sizing to content, so grow / space-* gaps collapse to zero.
+        let (gap, _offset) = self.calc_gap_and_offset(
+            &self.justify_content,
+            total_child_width,
+            total_child_width,
+            height,
+            child_count,
+            ctx,
+        );
+
+        total_child_width + gap * child_count.saturating_sub(1) as f32
+    }
+
     /// Calculate gap and initial offset for given justify_content.
     /// - container_size: full available width
     /// - total_child_width: sum of measured child widths
@@ -195,7 +332,7 @@ impl RowNode {
     }
 }
 
-impl<T> Widget<Row<T>, T, ()> for RowNode
+impl<T> Widget<Row<T>, T, RowChildSetting> for RowNode
 where
     T: Send + 'static,
 {
@@ -203,7 +340,7 @@ where
         &mut self,
         dom: &'a Row<T>,
         cache_invalidator: Option<InvalidationHandle>,
-    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+    ) -> Vec<(&'a dyn Dom<T>, RowChildSetting, u128)> {
         // Use the same conservative change-detection as Column: treat certain
         // JustifyContent variants as equivalent to avoid unnecessary relayouts.
         let justify_content_changed = !matches!(
@@ -231,8 +368,7 @@ where
 
         dom.items
             .iter()
-            .enumerate()
-            .map(|(index, item)| (item.as_ref(), (), index as u128))
+            .map(|(item, setting, id)| (item.as_ref(), *setting, *id))
             .collect()
     }
 
@@ -240,7 +376,7 @@ where
         &mut self,
         _bounds: [f32; 2],
         event: &DeviceInput,
-        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        children: &mut [(&mut dyn AnyWidget<T>, &mut RowChildSetting, &Arrangement)],
         _cache_invalidator: InvalidationHandle,
         ctx: &WidgetContext,
     ) -> Option<T> {
@@ -258,7 +394,7 @@ where
         &self,
         bounds: [f32; 2],
         position: [f32; 2],
-        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _children: &[(&dyn AnyWidget<T>, &RowChildSetting, &Arrangement)],
         _ctx: &WidgetContext,
     ) -> bool {
         0.0 <= position[0]
@@ -270,7 +406,7 @@ where
     fn measure(
         &self,
         constraints: &Constraints,
-        children: &[(&dyn AnyWidget<T>, &())],
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
         ctx: &WidgetContext,
     ) -> [f32; 2] {
         if children.is_empty() {
@@ -280,11 +416,18 @@ where
         let mut total_child_width = 0.0f32;
         let mut max_child_height = 0.0f32;
 
+        let (baselines, shared_baseline) = self.baselines(children, constraints, ctx);
+
         // Measure all children using final constraints (same approach as Column)
-        for (child, _) in children {
+        for ((child, _), baseline) in children.iter().zip(&baselines) {
             let child_size = child.measure(constraints, ctx);
             total_child_width += child_size[0];
             max_child_height = max_child_height.max(child_size[1]);
+
+            // baseline-aligned children are shifted down, so they may extend the row.
+            if let (Some(baseline), Some(shared)) = (baseline, shared_baseline) {
+                max_child_height = max_child_height.max(shared - baseline + child_size[1]);
+            }
         }
 
         // Compute gap using helper (accounts for Grow and space distribution)
@@ -307,10 +450,74 @@ where
         ]
     }
 
+    fn baseline(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        let (_, shared_baseline) = self.baselines(children, constraints, ctx);
+        shared_baseline
+    }
+
+    fn min_intrinsic_width(
+        &self,
+        height: f32,
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.intrinsic_width_with_gaps(
+            children
+                .iter()
+                .map(|(child, _)| child.min_intrinsic_width(height, ctx)),
+            height,
+            ctx,
+        )
+    }
+
+    fn max_intrinsic_width(
+        &self,
+        height: f32,
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        self.intrinsic_width_with_gaps(
+            children
+                .iter()
+                .map(|(child, _)| child.max_intrinsic_width(height, ctx)),
+            height,
+            ctx,
+        )
+    }
+
+    fn min_intrinsic_height(
+        &self,
+        width: f32,
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        children
+            .iter()
+            .map(|(child, _)| child.min_intrinsic_height(width, ctx))
+            .fold(0.0, f32::max)
+    }
+
+    fn max_intrinsic_height(
+        &self,
+        width: f32,
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
+        ctx: &WidgetContext,
+    ) -> f32 {
+        children
+            .iter()
+            .map(|(child, _)| child.max_intrinsic_height(width, ctx))
+            .fold(0.0, f32::max)
+    }
+
     fn arrange(
         &self,
         bounds: [f32; 2],
-        children: &[(&dyn AnyWidget<T>, &())],
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting)],
         ctx: &WidgetContext,
     ) -> Vec<Arrangement> {
         if children.is_empty() {
@@ -344,18 +551,26 @@ where
             ctx,
         );
 
+        let (baselines, shared_baseline) = self.baselines(children, &child_constraints, ctx);
+
         let mut accumulate_width = offset;
         let mut arrangements = Vec::with_capacity(children.len());
 
-        for child_size in &child_sizes {
+        for (((_, setting), child_size), baseline) in
+            children.iter().zip(&child_sizes).zip(&baselines)
+        {
             let child_width = child_size[0];
             let child_height = child_size[1];
 
             // Vertical alignment
-            let y = match self.align_items {
+            let y = match self.child_align(setting) {
                 AlignItems::Start => 0.0,
                 AlignItems::End => (bounds[1] - child_height).max(0.0),
                 AlignItems::Center => ((bounds[1] - child_height) / 2.0).max(0.0),
+                AlignItems::Baseline => match (baseline, shared_baseline) {
+                    (Some(baseline), Some(shared)) => shared - baseline,
+                    _ => 0.0,
+                },
             };
 
             let arrangement = Arrangement::new(
@@ -377,7 +592,7 @@ where
     fn render(
         &self,
         _bounds: [f32; 2],
-        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        children: &[(&dyn AnyWidget<T>, &RowChildSetting, &Arrangement)],
         background: Background,
         ctx: &WidgetContext,
     ) -> RenderNode {
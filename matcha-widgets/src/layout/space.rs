@@ -2,7 +2,10 @@ use matcha_core::context::WidgetContext;
 use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, Widget, WidgetFrame},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, LayoutStyle, Widget,
+        WidgetFrame,
+    },
 };
 use renderer::render_node::RenderNode;
 
@@ -11,6 +14,7 @@ use crate::types::size::{ChildSize, Size};
 /// DOM node: Space
 pub struct Space {
     label: Option<String>,
+    layout_style: LayoutStyle,
     width: Size,
     height: Size,
 }
@@ -19,11 +23,18 @@ impl Space {
     pub fn new(label: Option<&str>) -> Box<Self> {
         Box::new(Self {
             label: label.map(|s| s.to_string()),
+            layout_style: LayoutStyle::default(),
             width: Size::px(0.0),
             height: Size::px(0.0),
         })
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
     pub fn width(mut self, width: Size) -> Self {
         self.width = width;
         self
@@ -38,16 +49,23 @@ impl Space {
 #[async_trait::async_trait]
 impl<T: Send + 'static> Dom<T> for Space {
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            vec![],
-            vec![],
-            SpaceNode {
-                label: self.label.clone(),
-                width: self.width.clone(),
-                height: self.height.clone(),
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                SpaceNode {
+                    label: self.label.clone(),
+                    width: self.width.clone(),
+                    height: self.height.clone(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -0,0 +1,568 @@
+use std::sync::Arc;
+
+use matcha_core::{
+    color::Color,
+    context::WidgetContext,
+    device_input::{DeviceInput, DeviceInputData, ElementState, MouseInput, MouseLogicalButton},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, LayoutStyle, Widget,
+        WidgetFrame,
+    },
+};
+use renderer::render_node::RenderNode;
+
+use crate::style::{Style, drawn_node, solid_box::SolidBox};
+
+pub(crate) const DIVIDER_THICKNESS: f32 = 6.0;
+const DEFAULT_RATIO: f32 = 0.5;
+
+const DIVIDER_COLOR: Color = Color::RgbaF32 {
+    r: 0.90,
+    g: 0.90,
+    b: 0.90,
+    a: 1.0,
+};
+const DIVIDER_LINE_COLOR: Color = Color::RgbaF32 {
+    r: 0.78,
+    g: 0.78,
+    b: 0.78,
+    a: 1.0,
+};
+const DIVIDER_ACTIVE_COLOR: Color = Color::RgbaF32 {
+    r: 0.20,
+    g: 0.47,
+    b: 0.90,
+    a: 1.0,
+};
+
+type RatioHandler<T> = Arc<dyn Fn(f32) -> T + Send + Sync>;
+
+/// Direction in which the two panes of a [`SplitPane`] are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SplitDirection {
+    /// First pane on the left, second on the right, with a vertical divider between them.
+    #[default]
+    Horizontal,
+    /// First pane on top, second below, with a horizontal divider between them.
+    Vertical,
+}
+
+impl SplitDirection {
+    /// Index of the main axis in `[width, height]`.
+    pub(crate) fn axis(self) -> usize {
+        match self {
+            SplitDirection::Horizontal => 0,
+            SplitDirection::Vertical => 1,
+        }
+    }
+}
+
+// MARK: DOM
+
+/// Two panes separated by a divider that can be dragged to resize them.
+///
+/// The split is described by a ratio: the share of the space (without the divider) given to
+/// the first pane. The widget keeps the ratio while the user drags the divider and reports it
+/// with [`on_resize`](SplitPane::on_resize) when the drag ends, so the model can store it and
+/// pass it back with [`ratio`](SplitPane::ratio), e.g. to restore a saved layout. A new ratio
+/// from the view replaces the one set by dragging.
+///
+/// Double-clicking the divider resets the ratio to [`default_ratio`](SplitPane::default_ratio).
+pub struct SplitPane<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    direction: SplitDirection,
+    ratio: f32,
+    default_ratio: f32,
+    min_sizes: [f32; 2],
+    on_resize: Option<RatioHandler<T>>,
+    first: Box<dyn Dom<T>>,
+    second: Box<dyn Dom<T>>,
+}
+
+impl<T: Send + Sync + 'static> SplitPane<T> {
+    pub fn new(direction: SplitDirection, first: impl Dom<T>, second: impl Dom<T>) -> Self {
+        Self::from_boxed(direction, Box::new(first), Box::new(second))
+    }
+
+    pub(crate) fn from_boxed(
+        direction: SplitDirection,
+        first: Box<dyn Dom<T>>,
+        second: Box<dyn Dom<T>>,
+    ) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            direction,
+            ratio: DEFAULT_RATIO,
+            default_ratio: DEFAULT_RATIO,
+            min_sizes: [0.0, 0.0],
+            on_resize: None,
+            first,
+            second,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Share of the space given to the first pane, between 0.0 and 1.0. Default is 0.5.
+    pub fn ratio(mut self, ratio: f32) -> Self {
+        self.ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Ratio restored by double-clicking the divider. Default is 0.5.
+    pub fn default_ratio(mut self, ratio: f32) -> Self {
+        self.default_ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Minimum sizes of the first and second pane along the split direction, in pixels.
+    /// When both do not fit, the first pane's minimum wins.
+    pub fn min_sizes(mut self, first: f32, second: f32) -> Self {
+        self.min_sizes = [first.max(0.0), second.max(0.0)];
+        self
+    }
+
+    /// Message emitted with the new ratio when a drag of the divider ends or the divider is
+    /// reset by double-clicking.
+    pub fn on_resize(mut self, f: impl Fn(f32) -> T + Send + Sync + 'static) -> Self {
+        self.on_resize = Some(Arc::new(f));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for SplitPane<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![
+                    (self.first.build_widget_tree(), ()),
+                    (self.second.build_widget_tree(), ()),
+                ],
+                vec![0, 1],
+                SplitPaneNode {
+                    direction: self.direction,
+                    ratio: self.ratio,
+                    dom_ratio: self.ratio,
+                    default_ratio: self.default_ratio,
+                    min_sizes: self.min_sizes,
+                    on_resize: self.on_resize.clone(),
+                    hovered: false,
+                    drag_offset: None,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+/// Offsets and sizes of the two panes of a [`SplitPane`] laid out in `bounds`.
+pub(crate) fn pane_rects(
+    direction: SplitDirection,
+    ratio: f32,
+    min_sizes: [f32; 2],
+    bounds: [f32; 2],
+) -> [([f32; 2], [f32; 2]); 2] {
+    let node = SplitPaneNode::<()> {
+        direction,
+        ratio,
+        dom_ratio: ratio,
+        default_ratio: ratio,
+        min_sizes,
+        on_resize: None,
+        hovered: false,
+        drag_offset: None,
+    };
+    let [first, second] = node.pane_sizes(bounds);
+    let mut second_offset = [0.0, 0.0];
+    second_offset[direction.axis()] = first[direction.axis()] + DIVIDER_THICKNESS;
+    [([0.0, 0.0], first), (second_offset, second)]
+}
+
+// MARK: Widget
+
+pub struct SplitPaneNode<T> {
+    direction: SplitDirection,
+    /// ratio currently shown; follows drags of the divider.
+    ratio: f32,
+    /// last ratio passed by the view, to tell a new ratio from the model apart from an
+    /// unchanged one.
+    dom_ratio: f32,
+    default_ratio: f32,
+    min_sizes: [f32; 2],
+    on_resize: Option<RatioHandler<T>>,
+    hovered: bool,
+    /// distance from the pointer to the start of the divider while it is dragged.
+    drag_offset: Option<f32>,
+}
+
+impl<T> SplitPaneNode<T> {
+    /// Space shared by the two panes along the main axis.
+    fn available(&self, bounds: [f32; 2]) -> f32 {
+        (bounds[self.direction.axis()] - DIVIDER_THICKNESS).max(0.0)
+    }
+
+    /// Size of the first pane along the main axis, respecting the minimum sizes.
+    fn first_size(&self, bounds: [f32; 2]) -> f32 {
+        let available = self.available(bounds);
+        let max = (available - self.min_sizes[1]).max(0.0);
+        (available * self.ratio)
+            .round()
+            .min(max)
+            .max(self.min_sizes[0].min(available))
+    }
+
+    /// Ratio that puts the start of the divider at `position` along the main axis.
+    fn ratio_at(&self, position: f32, bounds: [f32; 2]) -> f32 {
+        let available = self.available(bounds);
+        if available <= 0.0 {
+            return self.ratio;
+        }
+        let max = (available - self.min_sizes[1]).max(0.0);
+        let first = position.min(max).max(self.min_sizes[0].min(available));
+        first / available
+    }
+
+    fn on_divider(&self, position: [f32; 2], bounds: [f32; 2]) -> bool {
+        let axis = self.direction.axis();
+        let start = self.first_size(bounds);
+        (start..=start + DIVIDER_THICKNESS).contains(&position[axis])
+            && (0.0..=bounds[1 - axis]).contains(&position[1 - axis])
+    }
+
+    fn pane_sizes(&self, bounds: [f32; 2]) -> [[f32; 2]; 2] {
+        let axis = self.direction.axis();
+        let first = self.first_size(bounds);
+        let second = (self.available(bounds) - first).max(0.0);
+        let mut sizes = [bounds, bounds];
+        sizes[0][axis] = first;
+        sizes[1][axis] = second;
+        sizes
+    }
+
+    fn offset(&self, along: f32) -> nalgebra::Matrix4<f32> {
+        let translation = match self.direction {
+            SplitDirection::Horizontal => nalgebra::Vector3::new(along, 0.0, 0.0),
+            SplitDirection::Vertical => nalgebra::Vector3::new(0.0, along, 0.0),
+        };
+        nalgebra::Matrix4::new_translation(&translation)
+    }
+
+    fn render_divider(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<RenderNode> {
+        let axis = self.direction.axis();
+        let mut size = bounds;
+        size[axis] = DIVIDER_THICKNESS;
+        drawn_node(size, ctx, |encoder, region| {
+            SolidBox {
+                color: DIVIDER_COLOR,
+            }
+            .draw(encoder, region, size, [0.0, 0.0], ctx);
+
+            // a thin line in the middle, highlighted while hovered or dragged
+            let active = self.hovered || self.drag_offset.is_some();
+            let line_thickness = if active { 2.0 } else { 1.0 };
+            let mut line_size = size;
+            line_size[axis] = line_thickness;
+            let mut line_offset = [0.0, 0.0];
+            line_offset[axis] = ((DIVIDER_THICKNESS - line_thickness) / 2.0).round();
+            SolidBox {
+                color: if active {
+                    DIVIDER_ACTIVE_COLOR
+                } else {
+                    DIVIDER_LINE_COLOR
+                },
+            }
+            .draw(encoder, region, line_size, line_offset, ctx);
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<SplitPane<T>, T, ()> for SplitPaneNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a SplitPane<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        let mut relayout = self.direction != dom.direction || self.min_sizes != dom.min_sizes;
+        if dom.ratio != self.dom_ratio {
+            self.dom_ratio = dom.ratio;
+            // a drag in progress keeps control over the divider
+            if self.drag_offset.is_none() && dom.ratio != self.ratio {
+                self.ratio = dom.ratio;
+                relayout = true;
+            }
+        }
+        self.direction = dom.direction;
+        self.default_ratio = dom.default_ratio;
+        self.min_sizes = dom.min_sizes;
+        self.on_resize = dom.on_resize.clone();
+
+        if relayout && let Some(handle) = cache_invalidator {
+            handle.relayout_next_frame();
+        }
+
+        vec![(dom.first.as_ref(), (), 0), (dom.second.as_ref(), (), 1)]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let axis = self.direction.axis();
+
+        if matches!(event.event(), DeviceInputData::MouseInput { .. })
+            && let Some(position) = event.mouse_position()
+        {
+            let hovered = self.on_divider(position, bounds);
+            if hovered != self.hovered {
+                self.hovered = hovered;
+                cache_invalidator.redraw_next_frame();
+            }
+
+            match event.event() {
+                DeviceInputData::MouseInput {
+                    event:
+                        Some(MouseInput::Click {
+                            click_state,
+                            button: MouseLogicalButton::Primary,
+                        }),
+                    ..
+                } => match click_state {
+                    ElementState::Pressed(count) if hovered => {
+                        if *count >= 2 {
+                            self.drag_offset = None;
+                            self.ratio = self.default_ratio;
+                            cache_invalidator.relayout_next_frame();
+                            return self.on_resize.as_ref().map(|f| f(self.ratio));
+                        }
+                        self.drag_offset = Some(position[axis] - self.first_size(bounds));
+                        cache_invalidator.redraw_next_frame();
+                        return None;
+                    }
+                    ElementState::Released(_) if self.drag_offset.is_some() => {
+                        self.drag_offset = None;
+                        cache_invalidator.redraw_next_frame();
+                        return self.on_resize.as_ref().map(|f| f(self.ratio));
+                    }
+                    _ => {}
+                },
+                DeviceInputData::MouseInput {
+                    dragging_from_primary: Some(_),
+                    ..
+                } => {
+                    if let Some(offset) = self.drag_offset {
+                        let ratio = self.ratio_at(position[axis] - offset, bounds);
+                        if ratio != self.ratio {
+                            self.ratio = ratio;
+                            cache_invalidator.relayout_next_frame();
+                        }
+                        return None;
+                    }
+                }
+                _ => {}
+            }
+
+            // the divider does not pass pointer events to the panes
+            if hovered || self.drag_offset.is_some() {
+                return None;
+            }
+        }
+
+        for (child, _, arrangement) in children.iter_mut() {
+            let child_event = event.transform(arrangement.affine);
+            if let Some(message) = child.device_input(&child_event, ctx) {
+                return Some(message);
+            }
+        }
+        None
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let axis = self.direction.axis();
+        let max = [constraints.max_width(), constraints.max_height()];
+        let min = [constraints.min_width(), constraints.min_height()];
+        let finite = constraints.max_finite();
+
+        // fill the available space; fall back to the panes' sizes where it is unbounded
+        let child_sizes: Vec<[f32; 2]> = children
+            .iter()
+            .map(|(child, _)| child.measure(constraints, ctx))
+            .collect();
+        let mut size = [0.0; 2];
+        size[axis] = finite[axis].unwrap_or_else(|| {
+            child_sizes
+                .iter()
+                .zip(self.min_sizes)
+                .map(|(s, min)| s[axis].max(min))
+                .sum::<f32>()
+                + DIVIDER_THICKNESS
+        });
+        size[1 - axis] = finite[1 - axis]
+            .unwrap_or_else(|| child_sizes.iter().map(|s| s[1 - axis]).fold(0.0, f32::max));
+
+        [size[0].clamp(min[0], max[0]), size[1].clamp(min[1], max[1])]
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        let [first, second] = self.pane_sizes(bounds);
+        let axis = self.direction.axis();
+        vec![
+            Arrangement::new(first, nalgebra::Matrix4::identity()),
+            Arrangement::new(second, self.offset(first[axis] + DIVIDER_THICKNESS)),
+        ]
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        for (child, _, arrangement) in children {
+            render_node.push_child(child.render(background, ctx), arrangement.affine);
+        }
+
+        if let Some(divider) = self.render_divider(bounds, ctx) {
+            render_node.push_child(divider, self.offset(self.first_size(bounds)));
+        }
+
+        render_node
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use matcha_core::test_kit::TestContext;
+    use utils::back_prop_dirty::BackPropDirty;
+
+    use super::*;
+    use crate::{layout::space::Space, types::size::Size};
+
+    fn node(ratio: f32, min_sizes: [f32; 2]) -> SplitPaneNode<()> {
+        SplitPaneNode {
+            direction: SplitDirection::Horizontal,
+            ratio,
+            dom_ratio: ratio,
+            default_ratio: ratio,
+            min_sizes,
+            on_resize: None,
+            hovered: false,
+            drag_offset: None,
+        }
+    }
+
+    fn space(width: f32, height: f32) -> Space {
+        Space::new(None)
+            .width(Size::px(width))
+            .height(Size::px(height))
+    }
+
+    #[test]
+    fn test_first_size_respects_min_sizes() {
+        // 100px shared by the panes
+        let bounds = [106.0, 50.0];
+        assert_eq!(node(0.5, [0.0, 0.0]).first_size(bounds), 50.0);
+        assert_eq!(node(0.1, [30.0, 0.0]).first_size(bounds), 30.0);
+        assert_eq!(node(0.9, [0.0, 30.0]).first_size(bounds), 70.0);
+        // when both minimums do not fit, the first pane's wins
+        assert_eq!(node(0.5, [80.0, 60.0]).first_size(bounds), 80.0);
+        // but it never exceeds the space there is
+        assert_eq!(node(0.5, [200.0, 0.0]).first_size(bounds), 100.0);
+        assert_eq!(node(0.5, [10.0, 10.0]).first_size([4.0, 50.0]), 0.0);
+    }
+
+    #[test]
+    fn test_ratio_at() {
+        let bounds = [106.0, 50.0];
+        let pane = node(0.5, [10.0, 20.0]);
+        assert_eq!(pane.ratio_at(25.0, bounds), 0.25);
+        // clamped to the minimum sizes
+        assert_eq!(pane.ratio_at(2.0, bounds), 0.1);
+        assert_eq!(pane.ratio_at(95.0, bounds), 0.8);
+        // without space the ratio is kept
+        assert_eq!(pane.ratio_at(2.0, [6.0, 50.0]), 0.5);
+    }
+
+    #[test]
+    fn test_pane_rects() {
+        assert_eq!(
+            pane_rects(SplitDirection::Horizontal, 0.5, [80.0, 60.0], [106.0, 50.0]),
+            [([0.0, 0.0], [80.0, 50.0]), ([86.0, 0.0], [20.0, 50.0])]
+        );
+        assert_eq!(
+            pane_rects(SplitDirection::Vertical, 0.25, [0.0, 0.0], [50.0, 206.0]),
+            [([0.0, 0.0], [50.0, 50.0]), ([0.0, 56.0], [50.0, 150.0])]
+        );
+        // the second pane gets nothing when the first one's minimum takes all the space
+        assert_eq!(
+            pane_rects(
+                SplitDirection::Horizontal,
+                0.5,
+                [200.0, 10.0],
+                [106.0, 50.0]
+            ),
+            [([0.0, 0.0], [100.0, 50.0]), ([106.0, 0.0], [0.0, 50.0])]
+        );
+    }
+
+    #[test]
+    fn test_measure_under_unbounded_constraints() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let mut pane = SplitPane::<()>::new(
+            SplitDirection::Horizontal,
+            space(30.0, 20.0),
+            space(40.0, 10.0),
+        )
+        .min_sizes(50.0, 0.0)
+        .build_widget_tree();
+        pane.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+
+        // the panes' sizes, at least their minimums, plus the divider
+        assert_eq!(pane.measure(&Constraints::unbounded(), ctx), [96.0, 20.0]);
+        // a bounded axis is filled
+        assert_eq!(
+            pane.measure(&Constraints::new([0.0, f32::INFINITY], [0.0, 100.0]), ctx),
+            [96.0, 100.0]
+        );
+        assert_eq!(
+            pane.measure(&Constraints::new([0.0, 300.0], [0.0, f32::INFINITY]), ctx),
+            [300.0, 20.0]
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! Rows of equal height of which only those in view are built, shared by
+//! [`LazyColumn`](super::lazy_column::LazyColumn) and [`Table`](crate::widget::table::Table).
+//!
+//! A row is one frame per cell builder: a single item in a lazy column, one cell per column in
+//! a table. Rows that leave the visible range go to a pool, and `prepare` updates pooled
+//! frames in the background to show the rows next to the kept ones, so scrolling by a few
+//! rows reuses them instead of building new frames.
+
+use std::{ops::Range, sync::Arc};
+
+use matcha_core::{
+    context::WidgetContext,
+    device_input::DeviceInput,
+    metrics::Arrangement,
+    ui::{AnyWidgetFrame, ChildFrameLinker, Dom, OwnedChildrenUpdate, PrepareFuture},
+};
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+
+pub(crate) type CellBuilder<T> = Arc<dyn Fn(usize) -> Box<dyn Dom<T>> + Send + Sync>;
+
+type Cells<T> = Vec<Box<dyn AnyWidgetFrame<T>>>;
+
+/// Indices of the rows that intersect a viewport of `viewport_height` scrolled down by
+/// `offset`, extended by `overscan` rows on each side.
+pub(crate) fn visible_range(
+    row_count: usize,
+    row_height: f32,
+    offset: f32,
+    viewport_height: f32,
+    overscan: usize,
+) -> Range<usize> {
+    if row_count == 0 || row_height <= 0.0 {
+        return 0..0;
+    }
+
+    let first = (offset / row_height).floor() as usize;
+    let last = ((offset + viewport_height) / row_height).ceil() as usize;
+
+    first.saturating_sub(overscan)..(last + overscan).min(row_count)
+}
+
+pub(crate) struct VirtualRows<T> {
+    builders: Vec<CellBuilder<T>>,
+    linker: Option<ChildFrameLinker>,
+    // shared with the preparation that recycles pooled rows
+    rows: Arc<Mutex<Rows<T>>>,
+}
+
+struct Rows<T> {
+    /// built rows sorted by index, with the arrangement of each cell from the last layout.
+    live: Vec<LiveRow<T>>,
+    /// cells of rows that scrolled out of view, waiting to be reused.
+    pool: Vec<Cells<T>>,
+    /// pooled cells already updated to show the row of the index, by `prepare`.
+    recycled: Vec<(usize, Cells<T>)>,
+    /// bumped when the builders change, which makes recycling in flight outdated.
+    generation: u64,
+}
+
+pub(crate) struct LiveRow<T> {
+    pub index: usize,
+    pub cells: Vec<(Box<dyn AnyWidgetFrame<T>>, Arrangement)>,
+}
+
+impl<T: Send + 'static> VirtualRows<T> {
+    pub fn new() -> Self {
+        Self {
+            builders: Vec::new(),
+            linker: None,
+            rows: Arc::new(Mutex::new(Rows {
+                live: Vec::new(),
+                pool: Vec::new(),
+                recycled: Vec::new(),
+                generation: 0,
+            })),
+        }
+    }
+
+    /// Takes over the cell builders of an updated widget with `row_count` rows.
+    ///
+    /// The builders may produce different content now: recycled rows are outdated and the
+    /// rows in view are refreshed by [`update_live`](Self::update_live). When the number of
+    /// cells per row changes, every row is dropped.
+    pub fn set_builders(&mut self, builders: Vec<CellBuilder<T>>, row_count: usize) {
+        let mut rows = self.rows.lock();
+        let Rows {
+            live,
+            pool,
+            recycled,
+            generation,
+        } = &mut *rows;
+        if builders.len() != self.builders.len() {
+            live.clear();
+            pool.clear();
+            recycled.clear();
+        }
+        live.retain(|row| row.index < row_count);
+        pool.extend(recycled.drain(..).map(|(_, cells)| cells));
+        *generation += 1;
+        drop(rows);
+
+        self.builders = builders;
+    }
+
+    pub fn link(&mut self, linker: ChildFrameLinker) {
+        let mut rows = self.rows.lock();
+        let Rows {
+            live,
+            pool,
+            recycled,
+            ..
+        } = &mut *rows;
+        for row in live {
+            for (cell, _) in &mut row.cells {
+                linker.link(&mut **cell);
+            }
+        }
+        for cells in pool
+            .iter_mut()
+            .chain(recycled.iter_mut().map(|(_, cells)| cells))
+        {
+            for cell in cells {
+                linker.link(&mut **cell);
+            }
+        }
+        drop(rows);
+        self.linker = Some(linker);
+    }
+
+    /// The built rows, sorted by index.
+    pub fn live(&self) -> MappedMutexGuard<'_, Vec<LiveRow<T>>> {
+        MutexGuard::map(self.rows.lock(), |rows| &mut rows.live)
+    }
+
+    /// Builds the rows of `range`, releases the others and arranges every cell at what
+    /// `place(row, cell)` returns.
+    pub fn layout(
+        &self,
+        range: Range<usize>,
+        mut place: impl FnMut(usize, usize) -> Arrangement,
+        ctx: &WidgetContext,
+    ) {
+        let mut rows = self.rows.lock();
+        let Rows {
+            live,
+            pool,
+            recycled,
+            ..
+        } = &mut *rows;
+
+        let mut previous = std::mem::take(live).into_iter().peekable();
+        for index in range.clone() {
+            // release rows above the range
+            while let Some(row) = previous.next_if(|row| row.index < index) {
+                pool.push(row.cells.into_iter().map(|(cell, _)| cell).collect());
+            }
+
+            let cells = match previous.next_if(|row| row.index == index) {
+                Some(row) => row.cells.into_iter().map(|(cell, _)| cell).collect(),
+                None => self.obtain_cells(index, recycled),
+            };
+            live.push(LiveRow {
+                index,
+                cells: cells
+                    .into_iter()
+                    .map(|cell| (cell, Arrangement::default()))
+                    .collect(),
+            });
+        }
+        // release rows below the range
+        pool.extend(previous.map(|row| row.cells.into_iter().map(|(cell, _)| cell).collect()));
+
+        // keep about one screen of spare rows
+        pool.truncate(range.len());
+
+        for row in live.iter_mut() {
+            for (column, (cell, arrangement)) in row.cells.iter_mut().enumerate() {
+                *arrangement = place(row.index, column);
+                cell.arrange(arrangement.size, ctx);
+            }
+        }
+    }
+
+    /// Creates the cells for row `index`, taking cells recycled for it when there are some.
+    fn obtain_cells(&self, index: usize, recycled: &mut Vec<(usize, Cells<T>)>) -> Cells<T> {
+        if let Some(position) = recycled.iter().position(|(i, _)| *i == index) {
+            return recycled.swap_remove(position).1;
+        }
+
+        self.builders
+            .iter()
+            .map(|builder| build_cell(&*builder(index), self.linker.as_ref()))
+            .collect()
+    }
+
+    /// Updates the rows in view to the current builders.
+    pub fn update_live(&mut self) -> Option<OwnedChildrenUpdate<'_>> {
+        // not locked across the updates
+        let mut live = std::mem::take(&mut self.rows.lock().live);
+        if live.is_empty() {
+            return None;
+        }
+
+        Some(Box::pin(async move {
+            for row in &mut live {
+                let mut cells: Cells<T> = row.cells.drain(..).map(|(cell, _)| cell).collect();
+                refresh_cells(&self.builders, self.linker.as_ref(), row.index, &mut cells).await;
+                row.cells = cells
+                    .into_iter()
+                    .map(|cell| (cell, Arrangement::default()))
+                    .collect();
+            }
+            self.rows.lock().live = live;
+        }))
+    }
+
+    /// Passes `event` to the cells, the last ones first, until one returns a message.
+    pub fn device_input(&self, event: &DeviceInput, ctx: &WidgetContext) -> Option<T> {
+        for row in self.rows.lock().live.iter_mut().rev() {
+            for (cell, arrangement) in row.cells.iter_mut().rev() {
+                let cell_event = event.transform(arrangement.affine);
+                if let Some(result) = cell.device_input(&cell_event, ctx) {
+                    return Some(result);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn update_gpu_device(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.for_each_cell(|cell, _| cell.update_gpu_device(device, queue));
+    }
+
+    /// Runs `update_lifecycle` on every cell, with the on-screen part `visible_rect` gives
+    /// for the cells in view.
+    pub fn update_lifecycle(
+        &self,
+        visible_rect: impl Fn(&Arrangement) -> Option<[[f32; 2]; 2]>,
+        ctx: &WidgetContext,
+    ) {
+        self.for_each_cell(|cell, arrangement| {
+            cell.update_lifecycle(arrangement.and_then(&visible_rect), ctx)
+        });
+    }
+
+    /// Runs `prepare` on every cell like [`update_lifecycle`](Self::update_lifecycle), then
+    /// updates pooled rows in the background to show the rows next to `range`, which scroll
+    /// in next.
+    pub fn prepare(
+        &self,
+        range: Range<usize>,
+        row_count: usize,
+        overscan: usize,
+        visible_rect: impl Fn(&Arrangement) -> Option<[[f32; 2]; 2]>,
+        ctx: &WidgetContext,
+    ) -> Option<PrepareFuture> {
+        self.for_each_cell(|cell, arrangement| {
+            cell.prepare(arrangement.and_then(&visible_rect), ctx)
+        });
+
+        let mut rows = self.rows.lock();
+        let Rows {
+            pool,
+            recycled,
+            generation,
+            ..
+        } = &mut *rows;
+
+        let spare = overscan.max(1);
+        let next: Vec<usize> = (range.start.saturating_sub(spare)..range.start)
+            .chain(range.end..(range.end + spare).min(row_count))
+            .collect();
+        let (kept, outdated): (Vec<_>, Vec<_>) = std::mem::take(recycled)
+            .into_iter()
+            .partition(|(index, _)| next.contains(index));
+        *recycled = kept;
+        pool.extend(outdated.into_iter().map(|(_, cells)| cells));
+
+        let mut taken = Vec::new();
+        for &index in &next {
+            if !recycled.iter().any(|(i, _)| *i == index)
+                && let Some(cells) = pool.pop()
+            {
+                taken.push((index, cells));
+            }
+        }
+        if taken.is_empty() {
+            return None;
+        }
+
+        let shared = self.rows.clone();
+        let builders = self.builders.clone();
+        let linker = self.linker.clone();
+        let generation = *generation;
+        Some(Box::pin(async move {
+            for (index, cells) in &mut taken {
+                refresh_cells(&builders, linker.as_ref(), *index, cells).await;
+            }
+
+            let mut rows = shared.lock();
+            if rows.generation == generation {
+                rows.recycled.extend(taken);
+            } else {
+                rows.pool.extend(taken.into_iter().map(|(_, cells)| cells));
+            }
+        }))
+    }
+
+    /// Reports the cells in view as hidden, for when the widget itself is hidden and neither
+    /// `update_lifecycle` nor `prepare` is called.
+    pub fn hide(&self, ctx: &WidgetContext) {
+        for row in &mut self.rows.lock().live {
+            for (cell, _) in &mut row.cells {
+                cell.update_lifecycle(None, ctx);
+                cell.prepare(None, ctx);
+            }
+        }
+    }
+
+    /// Calls `f` with every cell, live or pooled, and the arrangement of the live ones.
+    fn for_each_cell(&self, mut f: impl FnMut(&mut dyn AnyWidgetFrame<T>, Option<&Arrangement>)) {
+        let mut rows = self.rows.lock();
+        let Rows {
+            live,
+            pool,
+            recycled,
+            ..
+        } = &mut *rows;
+        for row in live {
+            for (cell, arrangement) in &mut row.cells {
+                f(&mut **cell, Some(arrangement));
+            }
+        }
+        for cells in pool
+            .iter_mut()
+            .chain(recycled.iter_mut().map(|(_, cells)| cells))
+        {
+            for cell in cells {
+                f(&mut **cell, None);
+            }
+        }
+    }
+}
+
+fn build_cell<T: 'static>(
+    dom: &dyn Dom<T>,
+    linker: Option<&ChildFrameLinker>,
+) -> Box<dyn AnyWidgetFrame<T>> {
+    let mut frame = dom.build_widget_tree();
+    if let Some(linker) = linker {
+        linker.link(&mut *frame);
+    }
+    frame
+}
+
+/// Updates the cells of a row to show row `index`, rebuilding those whose widget type changed.
+async fn refresh_cells<T: 'static>(
+    builders: &[CellBuilder<T>],
+    linker: Option<&ChildFrameLinker>,
+    index: usize,
+    cells: &mut [Box<dyn AnyWidgetFrame<T>>],
+) {
+    for (builder, cell) in builders.iter().zip(cells.iter_mut()) {
+        let dom = builder(index);
+        if cell.update_widget_tree(&*dom).await.is_err() {
+            *cell = build_cell(&*dom, linker);
+        }
+    }
+}
@@ -2,7 +2,10 @@ use matcha_core::context::WidgetContext;
 use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
-    ui::{AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, Widget, WidgetFrame},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, Dom, InvalidationHandle, LayoutStyle, Widget,
+        WidgetFrame,
+    },
 };
 use renderer::render_node::RenderNode;
 
@@ -21,6 +24,7 @@ where
     T: Send + 'static,
 {
     label: Option<String>,
+    layout_style: LayoutStyle,
     visibility: VisibilityState,
     content: Option<Box<dyn Dom<T>>>,
 }
@@ -32,11 +36,18 @@ where
     pub fn new() -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             visibility: VisibilityState::Visible,
             content: None,
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
     pub fn label(mut self, label: &str) -> Self {
         self.label = Some(label.to_string());
         self
@@ -82,14 +93,21 @@ where
             child_ids.push(0);
         }
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children_and_settings,
-            child_ids,
-            VisibilityNode {
-                visibility: self.visibility,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children_and_settings,
+                child_ids,
+                VisibilityNode {
+                    visibility: self.visibility,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -1,5 +1,7 @@
 pub mod buffer;
+pub mod editor;
 pub mod layout;
+pub mod stencil_mask;
 pub mod style;
 pub mod types;
 pub mod widget;
@@ -0,0 +1,333 @@
+//! Stencil masks rasterized from vector shapes.
+//!
+//! A widget that clips its content to a shape asks [`StencilMasks`] for a mask of the shape
+//! at the size it is drawn at and attaches it with `RenderNode::with_stencil`:
+//!
+//! ```ignore
+//! let mask = StencilMasks::of(ctx).mask(&MaskShape::RoundedRect { radius: 8.0 }, size, ctx)?;
+//! node.with_stencil(mask, [size[0] as f32, size[1] as f32], nalgebra::Matrix4::identity())
+//! ```
+//!
+//! Masks are rasterized on the CPU with antialiased edges into the stencil atlas the first
+//! time a shape is requested at a size, and shared by every widget asking for the same
+//! `(shape, size)` while one of them holds the region.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use gpu_utils::{
+    device_loss_recoverable::DeviceLossRecoverable,
+    texture_atlas::{AtlasRegion, WeakAtlasRegion},
+};
+use matcha_core::context::WidgetContext;
+use parking_lot::Mutex;
+
+/// Quantize factor of shape coordinates in cache keys, like the sub-pixel quantization of
+/// `QSize`.
+const KEY_QUANTIZE: f32 = 256.0;
+
+/// Sub-scanlines sampled per row of the mask.
+const SUBSAMPLES: usize = 4;
+
+/// Largest distance in pixels between a curve and the polygon it is flattened to.
+const FLATTEN_TOLERANCE: f32 = 0.1;
+
+/// A shape filling the mask, in pixels of the mask with the origin at the top left.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MaskShape {
+    /// The whole mask with its corners rounded by `radius`.
+    RoundedRect { radius: f32 },
+    /// The ellipse touching the edges of the mask; a circle in a square mask.
+    Ellipse,
+    /// A closed polygon, filled with the non-zero rule.
+    Polygon(Vec<[f32; 2]>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ShapeKey {
+    RoundedRect(i32),
+    Ellipse,
+    Polygon(Vec<[i32; 2]>),
+}
+
+impl MaskShape {
+    fn key(&self) -> ShapeKey {
+        let quantize = |v: f32| (v * KEY_QUANTIZE).round() as i32;
+        match self {
+            MaskShape::RoundedRect { radius } => ShapeKey::RoundedRect(quantize(*radius)),
+            MaskShape::Ellipse => ShapeKey::Ellipse,
+            MaskShape::Polygon(points) => ShapeKey::Polygon(
+                points
+                    .iter()
+                    .map(|&[x, y]| [quantize(x), quantize(y)])
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The outline of the shape in a mask of `size`.
+    fn outline(&self, size: [u32; 2]) -> Vec<[f32; 2]> {
+        let [width, height] = [size[0] as f32, size[1] as f32];
+        match self {
+            MaskShape::RoundedRect { radius } => {
+                let radius = radius.clamp(0.0, width.min(height) / 2.0);
+                if radius == 0.0 {
+                    return vec![[0.0, 0.0], [width, 0.0], [width, height], [0.0, height]];
+                }
+                let segments = arc_segments(radius, std::f32::consts::FRAC_PI_2);
+                // corner centers clockwise from the top left, with the angle their arc starts at
+                let corners = [
+                    ([radius, radius], std::f32::consts::PI),
+                    ([width - radius, radius], 1.5 * std::f32::consts::PI),
+                    ([width - radius, height - radius], 0.0),
+                    ([radius, height - radius], std::f32::consts::FRAC_PI_2),
+                ];
+                corners
+                    .iter()
+                    .flat_map(|&([cx, cy], start)| {
+                        (0..=segments).map(move |i| {
+                            let angle =
+                                start + std::f32::consts::FRAC_PI_2 * i as f32 / segments as f32;
+                            [cx + radius * angle.cos(), cy + radius * angle.sin()]
+                        })
+                    })
+                    .collect()
+            }
+            MaskShape::Ellipse => {
+                let [rx, ry] = [width / 2.0, height / 2.0];
+                let segments = arc_segments(rx.max(ry), std::f32::consts::TAU);
+                (0..segments)
+                    .map(|i| {
+                        let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+                        [rx + rx * angle.cos(), ry + ry * angle.sin()]
+                    })
+                    .collect()
+            }
+            MaskShape::Polygon(points) => points.clone(),
+        }
+    }
+}
+
+/// Segments an arc of `radius` spanning `angle` is flattened to.
+fn arc_segments(radius: f32, angle: f32) -> u32 {
+    if radius <= FLATTEN_TOLERANCE {
+        return 1;
+    }
+    let step = 2.0 * (1.0 - FLATTEN_TOLERANCE / radius).acos();
+    ((angle / step).ceil() as u32).clamp(1, 1024)
+}
+
+/// Coverage of `shape` in a mask of `size`, one byte per pixel, row by row.
+pub fn rasterize(shape: &MaskShape, size: [u32; 2]) -> Vec<u8> {
+    let [width, height] = [size[0] as usize, size[1] as usize];
+    let outline = shape.outline(size);
+    let mut coverage = vec![0.0f32; width * height];
+
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    for row in 0..height {
+        let pixels = &mut coverage[row * width..(row + 1) * width];
+        for sample in 0..SUBSAMPLES {
+            let y = row as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+
+            crossings.clear();
+            for (i, &[ax, ay]) in outline.iter().enumerate() {
+                let [bx, by] = outline[(i + 1) % outline.len()];
+                if (ay <= y) != (by <= y) {
+                    let x = ax + (y - ay) * (bx - ax) / (by - ay);
+                    crossings.push((x, if by > ay { 1 } else { -1 }));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                if winding != 0 {
+                    add_span(pixels, pair[0].0, pair[1].0);
+                }
+            }
+        }
+    }
+
+    coverage
+        .into_iter()
+        .map(|c| (c.min(1.0) * 255.0).round() as u8)
+        .collect()
+}
+
+/// Adds the horizontal coverage of one sub-scanline from `x0` to `x1` to `pixels`.
+fn add_span(pixels: &mut [f32], x0: f32, x1: f32) {
+    let x0 = x0.max(0.0);
+    let x1 = x1.min(pixels.len() as f32);
+    if x0 >= x1 {
+        return;
+    }
+    for (px, pixel) in pixels
+        .iter_mut()
+        .enumerate()
+        .take(x1.ceil() as usize)
+        .skip(x0.floor() as usize)
+    {
+        let overlap = x1.min(px as f32 + 1.0) - x0.max(px as f32);
+        *pixel += overlap / SUBSAMPLES as f32;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct MaskKey {
+    shape: ShapeKey,
+    size: [u32; 2],
+}
+
+/// Stencil masks rasterized from shapes. Get the shared instance with [`StencilMasks::of`].
+#[derive(Default)]
+pub struct StencilMasks {
+    // a mask lives as long as a widget holds its region
+    masks: Mutex<HashMap<MaskKey, WeakAtlasRegion, fxhash::FxBuildHasher>>,
+}
+
+impl DeviceLossRecoverable for StencilMasks {
+    fn recover(&self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
+        log::info!("StencilMasks: recovering from device loss");
+        self.masks.lock().clear();
+    }
+}
+
+impl StencilMasks {
+    /// The masks shared by the app.
+    pub fn of(ctx: &WidgetContext) -> Arc<Self> {
+        ctx.gpu_resource().get_or_insert_default::<Self>()
+    }
+
+    /// The mask of `shape` at `size` in the stencil atlas, rasterizing it on a miss. `None`
+    /// for an empty size or if the atlas is full.
+    pub fn mask(
+        &self,
+        shape: &MaskShape,
+        size: [u32; 2],
+        ctx: &WidgetContext,
+    ) -> Option<AtlasRegion> {
+        if size[0] == 0 || size[1] == 0 {
+            return None;
+        }
+        let key = MaskKey {
+            shape: shape.key(),
+            size,
+        };
+        if let Some(region) = self
+            .masks
+            .lock()
+            .get(&key)
+            .and_then(WeakAtlasRegion::upgrade)
+            .filter(AtlasRegion::is_valid)
+        {
+            return Some(region);
+        }
+
+        // rasterize without holding the lock
+        let coverage = rasterize(shape, size);
+        let region = ctx
+            .stencil_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), size)
+            .inspect_err(|e| log::warn!("StencilMasks: failed to allocate a {size:?} mask: {e}"))
+            .ok()?;
+        if let Err(e) = region.write_data(&ctx.queue(), &coverage) {
+            log::warn!("StencilMasks: failed to upload a {size:?} mask: {e}");
+            return None;
+        }
+        log::trace!("StencilMasks: rasterized {shape:?} at {size:?}");
+
+        let mut masks = self.masks.lock();
+        masks.retain(|_, region| region.is_alive());
+        masks.insert(key, region.downgrade());
+        Some(region)
+    }
+
+    /// Number of masks alive.
+    pub fn len(&self) -> usize {
+        self.masks
+            .lock()
+            .values()
+            .filter(|region| region.is_alive())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(mask: &[u8], size: [u32; 2], [x, y]: [u32; 2]) -> u8 {
+        mask[(y * size[0] + x) as usize]
+    }
+
+    #[test]
+    fn rectangles_cover_whole_pixels() {
+        let size = [4, 3];
+        let mask = rasterize(&MaskShape::RoundedRect { radius: 0.0 }, size);
+        assert_eq!(mask, vec![255; 12]);
+
+        // a polygon covering half of the second column
+        let mask = rasterize(
+            &MaskShape::Polygon(vec![[0.0, 0.0], [1.5, 0.0], [1.5, 3.0], [0.0, 3.0]]),
+            size,
+        );
+        assert_eq!(at(&mask, size, [0, 1]), 255);
+        assert_eq!(at(&mask, size, [1, 1]), 128);
+        assert_eq!(at(&mask, size, [2, 1]), 0);
+    }
+
+    #[test]
+    fn rounded_corners_and_ellipses_are_antialiased() {
+        let size = [32, 32];
+        let rounded = rasterize(&MaskShape::RoundedRect { radius: 8.0 }, size);
+        assert_eq!(at(&rounded, size, [0, 0]), 0);
+        assert_eq!(at(&rounded, size, [16, 0]), 255);
+        assert_eq!(at(&rounded, size, [16, 16]), 255);
+        // the arc crosses the pixel diagonal from the corner
+        let edge = at(&rounded, size, [2, 2]);
+        assert!(0 < edge && edge < 255, "{edge}");
+
+        let circle = rasterize(&MaskShape::Ellipse, size);
+        assert_eq!(at(&circle, size, [0, 0]), 0);
+        assert_eq!(at(&circle, size, [31, 31]), 0);
+        assert_eq!(at(&circle, size, [16, 16]), 255);
+        // the area is close to that of the circle
+        let area: f32 = circle.iter().map(|&c| f32::from(c) / 255.0).sum();
+        let expected = std::f32::consts::PI * 16.0 * 16.0;
+        assert!((area - expected).abs() < expected * 0.01, "{area}");
+    }
+
+    #[test]
+    fn overlapping_contours_fill_with_the_non_zero_rule() {
+        let size = [3, 1];
+        // a clockwise polygon winding twice around its middle
+        let mask = rasterize(
+            &MaskShape::Polygon(vec![
+                [0.0, 0.0],
+                [2.0, 0.0],
+                [2.0, 1.0],
+                [1.0, 1.0],
+                [1.0, 0.0],
+                [3.0, 0.0],
+                [3.0, 1.0],
+                [0.0, 1.0],
+            ]),
+            size,
+        );
+        assert_eq!(mask, vec![255, 255, 255]);
+    }
+
+    #[test]
+    fn shapes_differing_below_the_quantization_share_a_key() {
+        let a = MaskShape::RoundedRect { radius: 4.0 };
+        let b = MaskShape::RoundedRect { radius: 4.0001 };
+        let c = MaskShape::RoundedRect { radius: 4.5 };
+        assert_eq!(a.key(), b.key());
+        assert_ne!(a.key(), c.key());
+    }
+}
@@ -10,7 +10,9 @@ use gpu_utils::texture_atlas::atlas_simple::atlas::AtlasRegion;
 use matcha_core::{
     context::WidgetContext,
     metrics::{Constraints, QRect},
+    ui::PrepareFuture,
 };
+use renderer::render_node::RenderNode;
 
 /// A trait that defines the visual appearance and drawing logic of a widget.
 ///
@@ -55,6 +57,25 @@ pub trait Style: Send + Sync {
         offset: [f32; 2],
         ctx: &WidgetContext,
     );
+
+    /// Starts work that `draw` needs for `bounds`, e.g. tessellation on the
+    /// [worker pool](matcha_core::worker_pool), before the widget renders.
+    ///
+    /// Widgets forward this from [`Widget::prepare`](matcha_core::ui::Widget::prepare). Return
+    /// `None` when there is nothing left to do; `draw` must not rely on the work having
+    /// finished.
+    fn prepare(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        let _ = (bounds, ctx);
+        None
+    }
+
+    /// Whether `draw` covers every pixel of `bounds` with a fully opaque color. Widgets mark
+    /// such textures with [`RenderNode::with_opaque_texture`] so the renderer can skip what
+    /// they hide.
+    fn is_opaque(&self, bounds: [f32; 2], ctx: &WidgetContext) -> bool {
+        let _ = (bounds, ctx);
+        false
+    }
 }
 
 impl Style for Vec<Arc<dyn Style>> {
@@ -92,4 +113,74 @@ impl Style for Vec<Arc<dyn Style>> {
             style.draw(encoder, target, boundary_size, offset, ctx);
         }
     }
+
+    fn prepare(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        let futures: Vec<PrepareFuture> = self
+            .iter()
+            .filter_map(|style| style.prepare(bounds, ctx))
+            .collect();
+        if futures.is_empty() {
+            return None;
+        }
+        Some(Box::pin(async move {
+            futures::future::join_all(futures).await;
+        }))
+    }
+
+    fn is_opaque(&self, bounds: [f32; 2], ctx: &WidgetContext) -> bool {
+        // styles drawn over an opaque one blend with it and keep it opaque
+        self.iter().any(|style| style.is_opaque(bounds, ctx))
+    }
+}
+
+/// Draws `style` into a texture atlas region of `size` and returns a node showing it.
+///
+/// Returns `None` when `size` is empty or the region cannot be allocated.
+pub(crate) fn style_node(
+    style: &(impl Style + ?Sized),
+    size: [f32; 2],
+    ctx: &WidgetContext,
+) -> Option<RenderNode> {
+    let node = drawn_node(size, ctx, |encoder, region| {
+        style.draw(encoder, region, size, [0.0, 0.0], ctx);
+    })?;
+    Some(if style.is_opaque(size, ctx) {
+        node.with_opaque_texture()
+    } else {
+        node
+    })
+}
+
+/// Allocates a texture atlas region of `size`, records `draw` into it and returns a node
+/// showing it, for widgets that draw several styles into one texture.
+///
+/// Returns `None` when `size` is empty or the region cannot be allocated.
+pub(crate) fn drawn_node(
+    size: [f32; 2],
+    ctx: &WidgetContext,
+    draw: impl FnOnce(&mut wgpu::CommandEncoder, &AtlasRegion),
+) -> Option<RenderNode> {
+    let texture_size = [size[0].ceil() as u32, size[1].ceil() as u32];
+    if texture_size[0] == 0 || texture_size[1] == 0 {
+        return None;
+    }
+    let region = ctx
+        .texture_atlas()
+        .allocate(&ctx.device(), &ctx.queue(), texture_size)
+        .ok()?;
+
+    let mut encoder = ctx
+        .device()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Style Render Encoder"),
+        });
+    draw(&mut encoder, &region);
+    ctx.queue().submit(Some(encoder.finish()));
+
+    Some(RenderNode::new().with_texture(region, size, nalgebra::Matrix4::identity()))
+}
+
+/// Moves a child node by `x`, `y`.
+pub(crate) fn translation(x: f32, y: f32) -> nalgebra::Matrix4<f32> {
+    nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0))
 }
@@ -1,8 +1,8 @@
+use std::borrow::Cow;
 use std::sync::Arc;
 
 use crate::style::Style;
-use dashmap::DashMap;
-use gpu_utils::device_loss_recoverable::DeviceLossRecoverable;
+use gpu_utils::texture_atlas::AtlasRegion;
 use image::EncodableLayout;
 use matcha_core::{
     context::WidgetContext,
@@ -12,17 +12,16 @@ use renderer::widgets_renderer::texture_copy::{RenderData, TargetData, TextureCo
 
 use crate::types::size::{ChildSize, Size};
 
-#[derive(Default)]
-struct ImageCache {
-    map: DashMap<ImageCacheKey, ImageCacheData, fxhash::FxBuildHasher>,
-}
+mod compressed;
+mod decode_cache;
+use compressed::Container;
+pub use decode_cache::{
+    DEFAULT_BUDGET, EvictionReason, ImageDecodeCache, ImageEviction, ResampleQuality,
+};
 
-impl DeviceLossRecoverable for ImageCache {
-    fn recover(&self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
-        log::info!("ImageCache: recovering from device loss");
-        self.map.clear();
-    }
-}
+/// Decoded sizes are rounded up to a multiple of this, so that small size changes (e.g. while
+/// resizing a window) reuse the decoded image.
+const DOWNSCALE_STEP: u32 = 64;
 
 #[derive(Clone, PartialEq)]
 pub enum ImageSource {
@@ -45,6 +44,26 @@ impl ImageSource {
             },
         }
     }
+
+    /// The container if this is a GPU-compressed image (KTX2 or Basis Universal).
+    fn compressed_container(&self) -> Option<Container> {
+        match self {
+            ImageSource::Path(path) => Container::from_path(path),
+            ImageSource::StaticSlice { data } => Container::detect(data),
+            ImageSource::Arc(data) => Container::detect(data),
+        }
+    }
+
+    fn bytes(&self) -> Option<Cow<'_, [u8]>> {
+        match self {
+            ImageSource::Path(path) => std::fs::read(path)
+                .map_err(|e| log::warn!("Image: failed to read {path}: {e}"))
+                .ok()
+                .map(Cow::Owned),
+            ImageSource::StaticSlice { data } => Some(Cow::Borrowed(data)),
+            ImageSource::Arc(data) => Some(Cow::Borrowed(data.as_slice())),
+        }
+    }
 }
 
 impl From<&str> for ImageSource {
@@ -93,11 +112,6 @@ enum ImageCacheKey {
     },
 }
 
-struct ImageCacheData {
-    /// None if the image failed to load
-    texture: Option<wgpu::Texture>,
-}
-
 // MARK: Image Construct
 
 pub enum HAlign {
@@ -117,6 +131,8 @@ pub struct Image {
     image: ImageSource,
     size: [Size; 2],
     offset: [Size; 2],
+    downscale: bool,
+    resample_quality: ResampleQuality,
 }
 
 impl Image {
@@ -125,9 +141,23 @@ impl Image {
             image: source.into(),
             size: [Size::child_w(1.0), Size::child_h(1.0)],
             offset: [Size::px(0.0), Size::px(0.0)],
+            downscale: true,
+            resample_quality: ResampleQuality::default(),
         }
     }
 
+    /// Decode the image at its original resolution even when it is displayed smaller.
+    pub fn full_resolution(mut self) -> Self {
+        self.downscale = false;
+        self
+    }
+
+    /// Filter used when the image is decoded below its original resolution.
+    pub fn resample_quality(mut self, quality: ResampleQuality) -> Self {
+        self.resample_quality = quality;
+        self
+    }
+
     pub fn stretch_to_boundary(mut self) -> Self {
         self.size = [Size::parent_w(1.0), Size::parent_h(1.0)];
         self
@@ -247,34 +277,15 @@ impl Image {
     }
 }
 
-impl Image {
-    fn key(&self) -> ImageCacheKey {
-        self.image.to_key()
-    }
-}
-
 // helper methods
 impl Image {
-    fn with_image<R>(&self, ctx: &WidgetContext, f: impl FnOnce(&wgpu::Texture) -> R) -> Option<R> {
-        let cache_map = ctx.gpu_resource().get_or_insert_default::<ImageCache>();
-        let image_cache = cache_map
-            .map
-            .entry(self.key())
-            .or_insert_with(|| load_image_to_texture(&self.image, ctx));
-
-        let Some(image) = &image_cache.value().texture else {
-            return None;
-        };
-        Some(f(image))
-    }
-
     fn calc_layout(
         &self,
         boundary: [f32; 2],
-        pic_texture: &wgpu::Texture,
+        original_size: [u32; 2],
         ctx: &WidgetContext,
     ) -> QRect {
-        let image_size = [pic_texture.width() as f32, pic_texture.height() as f32];
+        let image_size = [original_size[0] as f32, original_size[1] as f32];
 
         let size_x = self.size[0].size(boundary, &mut ChildSize::new(|| image_size), ctx);
         let size_y = self.size[1].size(boundary, &mut ChildSize::new(|| image_size), ctx);
@@ -283,6 +294,19 @@ impl Image {
 
         QRect::new([offset_x, offset_y], [size_x, size_y])
     }
+
+    /// Size to decode the image at when it is displayed at `displayed` pixels.
+    ///
+    /// Compressed images are always used at their original size.
+    fn decode_size(&self, original_size: [u32; 2], displayed: [f32; 2]) -> [u32; 2] {
+        if !self.downscale || self.image.compressed_container().is_some() {
+            return original_size;
+        }
+        [0, 1].map(|axis| {
+            let wanted = displayed[axis].max(1.0).ceil() as u32;
+            (wanted.div_ceil(DOWNSCALE_STEP) * DOWNSCALE_STEP).clamp(1, original_size[axis].max(1))
+        })
+    }
 }
 
 // MARK: Style implementation
@@ -290,8 +314,9 @@ impl Image {
 impl Style for Image {
     fn required_region(&self, constraints: &Constraints, ctx: &WidgetContext) -> Option<QRect> {
         let boundary_size = constraints.max_size();
+        let original_size = ImageDecodeCache::of(ctx).dimensions(&self.image)?;
 
-        self.with_image(ctx, |texture| self.calc_layout(boundary_size, texture, ctx))
+        Some(self.calc_layout(boundary_size, original_size, ctx))
     }
 
     fn is_inside(&self, position: [f32; 2], boundary_size: [f32; 2], ctx: &WidgetContext) -> bool {
@@ -313,61 +338,155 @@ impl Style for Image {
     ) {
         let target_size = target.texture_size();
         let target_format = target.format();
-        self.with_image(ctx, |texture| {
-            let rect: QRect = self.calc_layout(boundary_size, texture, ctx);
-
-            let draw_offset = [rect.min_x() - offset[0], rect.min_y() - offset[1]];
-            let draw_size = [rect.width(), rect.height()];
-
-            // begin a render pass targeting the atlas region so the renderer can create its own passes if needed
-            let mut render_pass = match target.begin_render_pass(encoder) {
-                Ok(rp) => rp,
-                Err(_) => return,
-            };
-
-            let texture_copy = TextureCopy::default();
-            texture_copy.render(
-                &mut render_pass,
-                TargetData {
-                    target_size,
-                    target_format,
-                },
-                RenderData {
-                    source_texture_view: &texture
-                        .create_view(&wgpu::TextureViewDescriptor::default()),
-                    source_texture_position_min: [draw_offset[0], draw_offset[1]],
-                    source_texture_position_max: [
-                        draw_offset[0] + draw_size[0],
-                        draw_offset[1] + draw_size[1],
-                    ],
-                    color_transformation: None,
-                    color_offset: None,
-                },
-                &ctx.device(),
-            );
-        });
+
+        let decode_cache = ImageDecodeCache::of(ctx);
+        let Some(original_size) = decode_cache.dimensions(&self.image) else {
+            return;
+        };
+        let rect: QRect = self.calc_layout(boundary_size, original_size, ctx);
+
+        let decode_size = self.decode_size(original_size, [rect.width(), rect.height()]);
+        let Some(decoded) =
+            decode_cache.texture(&self.image, decode_size, self.resample_quality, ctx)
+        else {
+            return;
+        };
+        decode_cache.retain_for(&self.image, decode_size, self.resample_quality, target);
+        let Some((source_view, source_uv)) = decoded.view() else {
+            return;
+        };
+
+        let draw_offset = [rect.min_x() - offset[0], rect.min_y() - offset[1]];
+        let draw_size = [rect.width(), rect.height()];
+
+        // begin a render pass targeting the atlas region so the renderer can create its own passes if needed
+        let mut render_pass = match target.begin_render_pass(encoder) {
+            Ok(rp) => rp,
+            Err(_) => return,
+        };
+
+        let texture_copy = ctx.renderer::<TextureCopy>();
+        texture_copy.render(
+            &mut render_pass,
+            TargetData {
+                target_size,
+                target_format,
+            },
+            RenderData {
+                source_texture_view: &source_view,
+                source_texture_position_min: [draw_offset[0], draw_offset[1]],
+                source_texture_position_max: [
+                    draw_offset[0] + draw_size[0],
+                    draw_offset[1] + draw_size[1],
+                ],
+                source_uv,
+                color_transformation: None,
+                color_offset: None,
+            },
+            &ctx.device(),
+        );
     }
 }
 
-fn load_image_to_texture(image_source: &ImageSource, ctx: &WidgetContext) -> ImageCacheData {
+/// A decoded image on the GPU.
+#[derive(Clone)]
+enum DecodedImage {
+    /// An RGBA texture of its own.
+    Texture(wgpu::Texture),
+    /// Block-compressed data in a compressed atlas page.
+    Compressed(AtlasRegion),
+}
+
+impl DecodedImage {
+    /// GPU memory used by the image.
+    fn bytes(&self) -> usize {
+        let (size, format) = match self {
+            DecodedImage::Texture(texture) => {
+                ([texture.width(), texture.height()], texture.format())
+            }
+            DecodedImage::Compressed(region) => (region.texture_size(), region.format()),
+        };
+        let (block_width, block_height) = format.block_dimensions();
+        let block_bytes = format.block_copy_size(None).unwrap_or(4);
+        (size[0].div_ceil(block_width) * size[1].div_ceil(block_height) * block_bytes) as usize
+    }
+
+    /// A 2D view to sample the image from, and the UV bounds of the image within it.
+    fn view(&self) -> Option<(wgpu::TextureView, Option<[[f32; 2]; 2]>)> {
+        match self {
+            DecodedImage::Texture(texture) => Some((
+                texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                None,
+            )),
+            DecodedImage::Compressed(region) => {
+                let (view, uv) = region.page_view().ok()?;
+                Some((view, Some([[uv.min.x, uv.min.y], [uv.max.x, uv.max.y]])))
+            }
+        }
+    }
+}
+
+/// Decodes the image at `size` and uploads it. `None` if the image could not be loaded.
+fn load_image(
+    image_source: &ImageSource,
+    size: [u32; 2],
+    quality: ResampleQuality,
+    ctx: &WidgetContext,
+) -> Option<DecodedImage> {
+    if let Some(container) = image_source.compressed_container() {
+        return load_compressed_image(image_source, container, ctx);
+    }
+
     // load the image from the source
 
     let dynamic_image = match image_source {
         ImageSource::Path(path) => image::open(path).ok(),
         ImageSource::StaticSlice { data, .. } => image::load_from_memory(data).ok(),
         ImageSource::Arc(data) => image::load_from_memory(data).ok(),
-    };
+    }?;
 
-    let Some(dynamic_image) = dynamic_image else {
-        // If the image could not be loaded, return an empty cache entry
-        return ImageCacheData { texture: None };
+    let dynamic_image = if [dynamic_image.width(), dynamic_image.height()] == size {
+        dynamic_image
+    } else {
+        dynamic_image.resize_exact(size[0], size[1], quality.filter())
     };
 
     // Create a texture and upload image data
     let (image, format) = prepare_image_and_format(dynamic_image);
-    ImageCacheData {
-        texture: Some(make_cache(image, format, ctx)),
+    let (width, height) = image.dimensions();
+    Some(DecodedImage::Texture(make_texture(
+        image.as_bytes(),
+        [width, height],
+        format,
+        ctx,
+    )))
+}
+
+/// Uploads a KTX2 or Basis Universal image at its original size.
+fn load_compressed_image(
+    image_source: &ImageSource,
+    container: Container,
+    ctx: &WidgetContext,
+) -> Option<DecodedImage> {
+    let data = image_source.bytes()?;
+    let image = compressed::decode(container, &data, ctx.device_features())?;
+
+    // decoded to RGBA because the device supports no suitable compression
+    if !image.format.is_compressed() {
+        return Some(DecodedImage::Texture(make_texture(
+            &image.data,
+            image.size,
+            image.format,
+            ctx,
+        )));
     }
+
+    let region = ImageDecodeCache::of(ctx).allocate_compressed(image.format, image.size, ctx)?;
+    region
+        .write_data(&ctx.queue(), &image.data)
+        .map_err(|e| log::warn!("Image: failed to upload {:?} image: {e}", image.format))
+        .ok()?;
+    Some(DecodedImage::Compressed(region))
 }
 
 fn prepare_image_and_format(
@@ -382,14 +501,13 @@ fn prepare_image_and_format(
     (image_rgba8, wgpu::TextureFormat::Rgba8UnormSrgb)
 }
 
-fn make_cache(
-    image: image::ImageBuffer<image::Rgba<u8>, Vec<u8>>,
+/// Creates a texture from RGBA8 `data`.
+fn make_texture(
+    data: &[u8],
+    [width, height]: [u32; 2],
     format: wgpu::TextureFormat,
     ctx: &WidgetContext,
 ) -> wgpu::Texture {
-    let (width, height) = image.dimensions();
-    let data = image.as_bytes();
-
     let device = ctx.device();
     let queue = ctx.queue();
 
@@ -409,7 +527,7 @@ fn make_cache(
         view_formats: &[],
     });
 
-    // We converted image bytes to RGBA8 in prepare_image_and_format, so use 4 bytes per pixel.]
+    // All images are RGBA8 by now, so use 4 bytes per pixel.
     queue.write_texture(
         wgpu::TexelCopyTextureInfo {
             texture: &texture,
@@ -0,0 +1,415 @@
+//! KTX2 and Basis Universal images.
+//!
+//! Compressed images are uploaded in a block-compressed format the device supports and kept in
+//! compressed atlas pages; on devices without texture compression they are decoded to RGBA.
+//! Basis Universal data (`.basis` files and UASTC KTX2 files) is transcoded to BC7, ASTC or
+//! ETC2, while KTX2 files already holding BCn, ETC2 or ASTC blocks are uploaded as they are.
+//!
+//! Decoding needs the `ktx2` feature. Only the first mip level is used, and ETC1S data in KTX2
+//! files (BasisLZ supercompression) is not supported; `.basis` files may hold either encoding.
+
+use std::path::Path;
+
+const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const BASIS_MAGIC: [u8; 2] = *b"sB";
+
+/// File formats holding GPU-compressed images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Container {
+    Ktx2,
+    Basis,
+}
+
+impl Container {
+    pub(super) fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&KTX2_MAGIC) {
+            Some(Container::Ktx2)
+        } else if data.starts_with(&BASIS_MAGIC) {
+            Some(Container::Basis)
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?;
+        if extension.eq_ignore_ascii_case("ktx2") {
+            Some(Container::Ktx2)
+        } else if extension.eq_ignore_ascii_case("basis") {
+            Some(Container::Basis)
+        } else {
+            None
+        }
+    }
+}
+
+/// Pixel data ready for upload: compressed blocks, or RGBA8 after a fallback.
+pub(super) struct CompressedImage {
+    pub format: wgpu::TextureFormat,
+    pub size: [u32; 2],
+    pub data: Vec<u8>,
+}
+
+/// Format that Basis Universal data is transcoded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "ktx2"), allow(dead_code))]
+enum Target {
+    Bc7,
+    Astc4x4,
+    Etc2,
+    Rgba8,
+}
+
+#[cfg_attr(not(feature = "ktx2"), allow(dead_code))]
+impl Target {
+    /// The best format the device can sample.
+    fn for_features(features: wgpu::Features) -> Self {
+        if features.contains(wgpu::Features::TEXTURE_COMPRESSION_BC) {
+            Target::Bc7
+        } else if features.contains(wgpu::Features::TEXTURE_COMPRESSION_ASTC) {
+            Target::Astc4x4
+        } else if features.contains(wgpu::Features::TEXTURE_COMPRESSION_ETC2) {
+            Target::Etc2
+        } else {
+            Target::Rgba8
+        }
+    }
+
+    fn format(self) -> wgpu::TextureFormat {
+        match self {
+            Target::Bc7 => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+            Target::Astc4x4 => wgpu::TextureFormat::Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: wgpu::AstcChannel::UnormSrgb,
+            },
+            Target::Etc2 => wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+            Target::Rgba8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+}
+
+/// Original size of the image, read from its header.
+pub(super) fn dimensions(container: Container, data: &[u8]) -> Option<[u32; 2]> {
+    match container {
+        // pixelWidth and pixelHeight follow the identifier, vkFormat and typeSize
+        Container::Ktx2 => {
+            let read = |offset: usize| {
+                let bytes = data.get(offset..offset + 4)?;
+                Some(u32::from_le_bytes(bytes.try_into().ok()?))
+            };
+            let width = read(20)?;
+            // 1D textures store a height of 0
+            let height = read(24)?.max(1);
+            (width > 0).then_some([width, height])
+        }
+        Container::Basis => basis_dimensions(data),
+    }
+}
+
+#[cfg(not(feature = "ktx2"))]
+fn basis_dimensions(_data: &[u8]) -> Option<[u32; 2]> {
+    log::warn!("Image: Basis Universal images need the `ktx2` feature");
+    None
+}
+
+#[cfg(not(feature = "ktx2"))]
+pub(super) fn decode(
+    container: Container,
+    _data: &[u8],
+    _features: wgpu::Features,
+) -> Option<CompressedImage> {
+    log::warn!("Image: {container:?} images need the `ktx2` feature");
+    None
+}
+
+#[cfg(feature = "ktx2")]
+use decoding::basis_dimensions;
+#[cfg(feature = "ktx2")]
+pub(super) use decoding::decode;
+
+#[cfg(feature = "ktx2")]
+mod decoding {
+    use std::borrow::Cow;
+    use std::sync::Once;
+
+    use basis_universal::{
+        DecodeFlags, LowLevelUastcTranscoder, SliceParametersUastc, TranscodeParameters,
+        Transcoder, TranscoderBlockFormat, TranscoderTextureFormat,
+    };
+    use log::warn;
+    use wgpu::TextureFormat as F;
+
+    use super::{CompressedImage, Container, Target};
+
+    /// KTX2 formats uploaded as they are when the device supports them.
+    const NATIVE_FORMATS: [(ktx2::Format, wgpu::TextureFormat); 14] = [
+        (ktx2::Format::R8G8B8A8_SRGB, F::Rgba8UnormSrgb),
+        (ktx2::Format::R8G8B8A8_UNORM, F::Rgba8Unorm),
+        (ktx2::Format::BC1_RGBA_SRGB_BLOCK, F::Bc1RgbaUnormSrgb),
+        (ktx2::Format::BC1_RGBA_UNORM_BLOCK, F::Bc1RgbaUnorm),
+        (ktx2::Format::BC3_SRGB_BLOCK, F::Bc3RgbaUnormSrgb),
+        (ktx2::Format::BC3_UNORM_BLOCK, F::Bc3RgbaUnorm),
+        (ktx2::Format::BC7_SRGB_BLOCK, F::Bc7RgbaUnormSrgb),
+        (ktx2::Format::BC7_UNORM_BLOCK, F::Bc7RgbaUnorm),
+        (
+            ktx2::Format::ETC2_R8G8B8A8_SRGB_BLOCK,
+            F::Etc2Rgba8UnormSrgb,
+        ),
+        (ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK, F::Etc2Rgba8Unorm),
+        (ktx2::Format::ETC2_R8G8B8_SRGB_BLOCK, F::Etc2Rgb8UnormSrgb),
+        (ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK, F::Etc2Rgb8Unorm),
+        (
+            ktx2::Format::ASTC_4x4_SRGB_BLOCK,
+            F::Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: wgpu::AstcChannel::UnormSrgb,
+            },
+        ),
+        (
+            ktx2::Format::ASTC_4x4_UNORM_BLOCK,
+            F::Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: wgpu::AstcChannel::Unorm,
+            },
+        ),
+    ];
+
+    impl Target {
+        fn texture_format(self) -> TranscoderTextureFormat {
+            match self {
+                Target::Bc7 => TranscoderTextureFormat::BC7_RGBA,
+                Target::Astc4x4 => TranscoderTextureFormat::ASTC_4x4_RGBA,
+                Target::Etc2 => TranscoderTextureFormat::ETC2_RGBA,
+                Target::Rgba8 => TranscoderTextureFormat::RGBA32,
+            }
+        }
+
+        fn block_format(self) -> TranscoderBlockFormat {
+            match self {
+                Target::Bc7 => TranscoderBlockFormat::BC7,
+                Target::Astc4x4 => TranscoderBlockFormat::ASTC_4x4,
+                Target::Etc2 => TranscoderBlockFormat::ETC2_RGBA,
+                Target::Rgba8 => TranscoderBlockFormat::RGBA32,
+            }
+        }
+    }
+
+    fn init_transcoder() {
+        static INIT: Once = Once::new();
+        INIT.call_once(basis_universal::transcoder_init);
+    }
+
+    pub(in super::super) fn decode(
+        container: Container,
+        data: &[u8],
+        features: wgpu::Features,
+    ) -> Option<CompressedImage> {
+        match container {
+            Container::Ktx2 => decode_ktx2(data, features),
+            Container::Basis => transcode_basis(data, Target::for_features(features)),
+        }
+    }
+
+    pub(super) fn basis_dimensions(data: &[u8]) -> Option<[u32; 2]> {
+        init_transcoder();
+        let level = Transcoder::new().image_level_description(data, 0, 0)?;
+        Some([level.original_width, level.original_height])
+    }
+
+    fn decode_ktx2(data: &[u8], features: wgpu::Features) -> Option<CompressedImage> {
+        let reader = ktx2::Reader::new(data)
+            .map_err(|e| warn!("Image: failed to read KTX2 image: {e:?}"))
+            .ok()?;
+        let header = reader.header();
+        let size = [header.pixel_width, header.pixel_height.max(1)];
+        let level = reader.levels().next()?;
+
+        let level_data = match header.supercompression_scheme {
+            None => Cow::Borrowed(level.data),
+            Some(ktx2::SupercompressionScheme::Zstandard) => {
+                Cow::Owned(decompress_zstd(level.data)?)
+            }
+            Some(scheme) => {
+                warn!("Image: KTX2 supercompression {scheme:?} is not supported");
+                return None;
+            }
+        };
+
+        // an undefined format means Basis Universal data; ETC1S was rejected above
+        let Some(format) = header.format else {
+            return transcode_uastc(&level_data, size, Target::for_features(features));
+        };
+        let Some(&(_, native)) = NATIVE_FORMATS.iter().find(|(ktx2, _)| *ktx2 == format) else {
+            warn!("Image: KTX2 format {format:?} is not supported");
+            return None;
+        };
+
+        if features.contains(native.required_features()) {
+            Some(CompressedImage {
+                format: native,
+                size,
+                data: level_data.into_owned(),
+            })
+        } else {
+            decode_on_cpu(native, &level_data, size)
+        }
+    }
+
+    fn decompress_zstd(data: &[u8]) -> Option<Vec<u8>> {
+        use std::io::Read;
+
+        let mut decoder = ruzstd::decoding::StreamingDecoder::new(data)
+            .map_err(|e| warn!("Image: invalid zstd data in KTX2 image: {e}"))
+            .ok()?;
+        let mut decompressed = Vec::new();
+        decoder
+            .read_to_end(&mut decompressed)
+            .map_err(|e| warn!("Image: failed to decompress KTX2 image: {e}"))
+            .ok()?;
+        Some(decompressed)
+    }
+
+    fn transcode_basis(data: &[u8], target: Target) -> Option<CompressedImage> {
+        init_transcoder();
+        let mut transcoder = Transcoder::new();
+        let level = transcoder.image_level_description(data, 0, 0)?;
+        transcoder
+            .prepare_transcoding(data)
+            .map_err(|_| warn!("Image: invalid Basis Universal image"))
+            .ok()?;
+        let transcoded = transcoder.transcode_image_level(
+            data,
+            target.texture_format(),
+            TranscodeParameters {
+                image_index: 0,
+                level_index: 0,
+                ..Default::default()
+            },
+        );
+        transcoder.end_transcoding();
+
+        Some(CompressedImage {
+            format: target.format(),
+            size: [level.original_width, level.original_height],
+            data: transcoded
+                .map_err(|e| warn!("Image: failed to transcode Basis Universal image: {e:?}"))
+                .ok()?,
+        })
+    }
+
+    fn transcode_uastc(data: &[u8], size: [u32; 2], target: Target) -> Option<CompressedImage> {
+        init_transcoder();
+        let parameters = SliceParametersUastc {
+            num_blocks_x: size[0].div_ceil(4),
+            num_blocks_y: size[1].div_ceil(4),
+            has_alpha: true,
+            original_width: size[0],
+            original_height: size[1],
+        };
+        let transcoded = LowLevelUastcTranscoder::new()
+            .transcode_slice(
+                data,
+                parameters,
+                DecodeFlags::HIGH_QUALITY,
+                target.block_format(),
+            )
+            .map_err(|e| warn!("Image: failed to transcode UASTC image: {e:?}"))
+            .ok()?;
+
+        Some(CompressedImage {
+            format: target.format(),
+            size,
+            data: transcoded,
+        })
+    }
+
+    /// Decodes blocks the device cannot sample to RGBA8.
+    fn decode_on_cpu(
+        format: wgpu::TextureFormat,
+        data: &[u8],
+        size: [u32; 2],
+    ) -> Option<CompressedImage> {
+        let [width, height] = size.map(|x| x as usize);
+        let mut pixels = vec![0u32; width * height];
+        let decoded = match format {
+            F::Bc1RgbaUnorm | F::Bc1RgbaUnormSrgb => {
+                texture2ddecoder::decode_bc1(data, width, height, &mut pixels)
+            }
+            F::Bc3RgbaUnorm | F::Bc3RgbaUnormSrgb => {
+                texture2ddecoder::decode_bc3(data, width, height, &mut pixels)
+            }
+            F::Bc7RgbaUnorm | F::Bc7RgbaUnormSrgb => {
+                texture2ddecoder::decode_bc7(data, width, height, &mut pixels)
+            }
+            F::Etc2Rgb8Unorm | F::Etc2Rgb8UnormSrgb => {
+                texture2ddecoder::decode_etc2_rgb(data, width, height, &mut pixels)
+            }
+            F::Etc2Rgba8Unorm | F::Etc2Rgba8UnormSrgb => {
+                texture2ddecoder::decode_etc2_rgba8(data, width, height, &mut pixels)
+            }
+            F::Astc { .. } => texture2ddecoder::decode_astc(data, width, height, 4, 4, &mut pixels),
+            // uncompressed formats never need decoding
+            _ => return None,
+        };
+        decoded
+            .map_err(|e| warn!("Image: failed to decode {format:?} image: {e}"))
+            .ok()?;
+
+        let format = if format.is_srgb() {
+            F::Rgba8UnormSrgb
+        } else {
+            F::Rgba8Unorm
+        };
+        // the decoder writes BGRA
+        let data = pixels
+            .iter()
+            .flat_map(|pixel| {
+                let [b, g, r, a] = pixel.to_le_bytes();
+                [r, g, b, a]
+            })
+            .collect();
+        Some(CompressedImage { format, size, data })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containers_are_detected_by_magic_and_extension() {
+        let mut ktx2 = KTX2_MAGIC.to_vec();
+        // vkFormat, typeSize, pixelWidth, pixelHeight
+        ktx2.extend([0; 8]);
+        ktx2.extend(100u32.to_le_bytes());
+        ktx2.extend(0u32.to_le_bytes());
+
+        assert_eq!(Container::detect(&ktx2), Some(Container::Ktx2));
+        assert_eq!(dimensions(Container::Ktx2, &ktx2), Some([100, 1]));
+        assert_eq!(Container::detect(b"sB\x13\x00"), Some(Container::Basis));
+        assert_eq!(Container::detect(b"\x89PNG\r\n\x1a\n"), None);
+
+        assert_eq!(Container::from_path("a/b.KTX2"), Some(Container::Ktx2));
+        assert_eq!(Container::from_path("b.basis"), Some(Container::Basis));
+        assert_eq!(Container::from_path("c.png"), None);
+    }
+
+    #[test]
+    fn transcode_target_prefers_bc7_and_falls_back_to_rgba() {
+        use wgpu::Features as Ft;
+
+        let all = Ft::TEXTURE_COMPRESSION_BC
+            | Ft::TEXTURE_COMPRESSION_ASTC
+            | Ft::TEXTURE_COMPRESSION_ETC2;
+        assert_eq!(Target::for_features(all), Target::Bc7);
+        assert_eq!(
+            Target::for_features(Ft::TEXTURE_COMPRESSION_ETC2),
+            Target::Etc2
+        );
+        assert_eq!(Target::for_features(Ft::empty()), Target::Rgba8);
+        assert!(!Target::Rgba8.format().is_compressed());
+    }
+}
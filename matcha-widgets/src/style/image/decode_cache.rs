@@ -0,0 +1,489 @@
+//! Decoded images shared by all [`Image`](super::Image) styles.
+//!
+//! Images are decoded at the size they are displayed at (rounded up, never above their
+//! original resolution) and kept as GPU textures until the cache exceeds its byte budget.
+//! Entries remember the atlas regions that were drawn from them; under memory pressure,
+//! images whose regions have all been dropped are evicted before ones that are still on
+//! screen. Block-compressed images live in compressed atlas pages owned by the cache.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use gpu_utils::{
+    device_loss_recoverable::DeviceLossRecoverable,
+    texture_atlas::{
+        AtlasManager, AtlasRegion, MemoryAllocateStrategy, TextureAtlas, WeakAtlasRegion,
+    },
+};
+use matcha_core::context::WidgetContext;
+use parking_lot::{Mutex, RwLock};
+
+use super::{DecodedImage, ImageCacheKey, ImageSource};
+
+/// Default [`ImageDecodeCache::budget`]: 256 MiB.
+pub const DEFAULT_BUDGET: usize = 256 * 1024 * 1024;
+
+/// Page size of the compressed atlases.
+const COMPRESSED_PAGE_SIZE: u32 = 2048;
+
+/// Filter used when an image is decoded below its original resolution.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ResampleQuality {
+    /// Fastest; blocky when shrinking a lot.
+    Nearest,
+    Bilinear,
+    #[default]
+    Bicubic,
+    /// Sharpest and slowest.
+    Lanczos,
+}
+
+impl ResampleQuality {
+    pub(super) fn filter(self) -> image::imageops::FilterType {
+        match self {
+            ResampleQuality::Nearest => image::imageops::FilterType::Nearest,
+            ResampleQuality::Bilinear => image::imageops::FilterType::Triangle,
+            ResampleQuality::Bicubic => image::imageops::FilterType::CatmullRom,
+            ResampleQuality::Lanczos => image::imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+/// Why a decoded image left the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+    /// The cache was over its budget.
+    Budget,
+    /// [`ImageDecodeCache::trim_unused`] removed it because nothing drawn from it is alive.
+    Unused,
+    /// [`ImageDecodeCache::clear`] was called.
+    Cleared,
+    /// The GPU device was lost together with the texture.
+    DeviceLost,
+}
+
+/// A decoded image removed from the cache, passed to [`ImageDecodeCache::on_evict`] callbacks.
+#[derive(Clone)]
+pub struct ImageEviction {
+    pub source: ImageSource,
+    /// Size the image was decoded at.
+    pub size: [u32; 2],
+    /// GPU memory the image occupied.
+    pub bytes: usize,
+    pub reason: EvictionReason,
+}
+
+type EvictionCallback = dyn Fn(&ImageEviction) + Send + Sync;
+
+/// Decoded images within a byte budget. Get the shared instance with [`ImageDecodeCache::of`].
+pub struct ImageDecodeCache {
+    entries: Mutex<Entries<Option<DecodedImage>>>,
+    // original sizes, read from the image headers
+    dimensions: DashMap<ImageCacheKey, Option<[u32; 2]>, fxhash::FxBuildHasher>,
+    eviction_callbacks: RwLock<Vec<Arc<EvictionCallback>>>,
+    // one atlas per compressed format, created on first use
+    compressed_atlases: Mutex<Option<AtlasManager>>,
+}
+
+impl Default for ImageDecodeCache {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Entries::new(DEFAULT_BUDGET)),
+            dimensions: DashMap::default(),
+            eviction_callbacks: RwLock::new(Vec::new()),
+            compressed_atlases: Mutex::new(None),
+        }
+    }
+}
+
+impl DeviceLossRecoverable for ImageDecodeCache {
+    fn recover(&self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
+        log::info!("ImageDecodeCache: recovering from device loss");
+        let evicted = self.entries.lock().clear(EvictionReason::DeviceLost);
+        // the atlases belong to the lost device
+        self.compressed_atlases.lock().take();
+        self.notify(&evicted);
+    }
+}
+
+impl ImageDecodeCache {
+    /// The cache shared by the app.
+    pub fn of(ctx: &WidgetContext) -> Arc<Self> {
+        ctx.gpu_resource().get_or_insert_default::<Self>()
+    }
+
+    /// Upper bound of the GPU memory used by decoded images, in bytes.
+    pub fn budget(&self) -> usize {
+        self.entries.lock().budget
+    }
+
+    /// Changes the budget, evicting images until the cache fits.
+    ///
+    /// An image larger than the whole budget is still decoded, but only kept until the next
+    /// image is needed.
+    pub fn set_budget(&self, bytes: usize) {
+        let evicted = {
+            let mut entries = self.entries.lock();
+            entries.budget = bytes;
+            entries.evict_to_budget(None)
+        };
+        self.notify(&evicted);
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.entries.lock().used
+    }
+
+    /// Number of decoded images.
+    pub fn len(&self) -> usize {
+        self.entries.lock().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls `callback` for every image evicted from now on.
+    pub fn on_evict(&self, callback: impl Fn(&ImageEviction) + Send + Sync + 'static) {
+        self.eviction_callbacks.write().push(Arc::new(callback));
+    }
+
+    /// Evicts the images whose atlas regions have all been dropped.
+    pub fn trim_unused(&self) {
+        let evicted = self.entries.lock().evict_unused();
+        self.notify(&evicted);
+    }
+
+    /// Evicts all decoded images.
+    pub fn clear(&self) {
+        let evicted = self.entries.lock().clear(EvictionReason::Cleared);
+        self.notify(&evicted);
+    }
+
+    /// Original size of the image, without decoding it. `None` if it cannot be read.
+    pub(super) fn dimensions(&self, source: &ImageSource) -> Option<[u32; 2]> {
+        *self
+            .dimensions
+            .entry(source.to_key())
+            .or_insert_with(|| read_dimensions(source))
+    }
+
+    /// The image decoded at `size`, decoding it on a miss.
+    pub(super) fn texture(
+        &self,
+        source: &ImageSource,
+        size: [u32; 2],
+        quality: ResampleQuality,
+        ctx: &WidgetContext,
+    ) -> Option<DecodedImage> {
+        let key = EntryKey {
+            source: source.to_key(),
+            size,
+            quality,
+        };
+        if let Some(image) = self.entries.lock().get(&key) {
+            return image;
+        }
+
+        // decode without holding the lock; failures are cached as well
+        let image = super::load_image(source, size, quality, ctx);
+        let bytes = image.as_ref().map_or(0, DecodedImage::bytes);
+        log::trace!("ImageDecodeCache: decoded {key:?} ({bytes} bytes)");
+
+        let evicted = self
+            .entries
+            .lock()
+            .insert(key, source.clone(), image.clone(), bytes);
+        self.notify(&evicted);
+        image
+    }
+
+    /// Allocates `size` in the atlas for the compressed `format`. The region is aligned to
+    /// whole compression blocks.
+    pub(super) fn allocate_compressed(
+        &self,
+        format: wgpu::TextureFormat,
+        size: [u32; 2],
+        ctx: &WidgetContext,
+    ) -> Option<AtlasRegion> {
+        let mut atlases = self.compressed_atlases.lock();
+        let atlases = atlases.get_or_insert_with(|| {
+            AtlasManager::new(
+                Arc::new(ctx.device()),
+                Arc::new(ctx.queue()),
+                MemoryAllocateStrategy {
+                    initial_pages: 1,
+                    resize_threshold: Some(0.8),
+                    resize_factor: 2.0,
+                    shrink_threshold: 0.2,
+                    shrink_factor: 0.5,
+                },
+                wgpu::Extent3d {
+                    width: COMPRESSED_PAGE_SIZE,
+                    height: COMPRESSED_PAGE_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                TextureAtlas::DEFAULT_MARGIN_PX,
+            )
+        });
+        atlases
+            .allocate(format, size)
+            .map_err(|e| log::warn!("ImageDecodeCache: cannot store {format:?} image: {e}"))
+            .ok()
+    }
+
+    /// Records that `region` was drawn from the image decoded at `size`, keeping the image
+    /// preferred over unused ones while the region lives.
+    pub(super) fn retain_for(
+        &self,
+        source: &ImageSource,
+        size: [u32; 2],
+        quality: ResampleQuality,
+        region: &AtlasRegion,
+    ) {
+        let key = EntryKey {
+            source: source.to_key(),
+            size,
+            quality,
+        };
+        self.entries.lock().retain_region(&key, region.downgrade());
+    }
+
+    fn notify(&self, evicted: &[ImageEviction]) {
+        if evicted.is_empty() {
+            return;
+        }
+        let callbacks = self.eviction_callbacks.read().clone();
+        for eviction in evicted {
+            log::debug!(
+                "ImageDecodeCache: evicted {}x{} image ({} bytes, {:?})",
+                eviction.size[0],
+                eviction.size[1],
+                eviction.bytes,
+                eviction.reason
+            );
+            for callback in &callbacks {
+                callback(eviction);
+            }
+        }
+    }
+}
+
+fn read_dimensions(source: &ImageSource) -> Option<[u32; 2]> {
+    fn from_memory(data: &[u8]) -> Option<(u32, u32)> {
+        image::ImageReader::new(std::io::Cursor::new(data))
+            .with_guessed_format()
+            .ok()?
+            .into_dimensions()
+            .ok()
+    }
+
+    if let Some(container) = source.compressed_container() {
+        return super::compressed::dimensions(container, &source.bytes()?);
+    }
+
+    let (width, height) = match source {
+        ImageSource::Path(path) => image::image_dimensions(path).ok()?,
+        ImageSource::StaticSlice { data } => from_memory(data)?,
+        ImageSource::Arc(data) => from_memory(data)?,
+    };
+    Some([width, height])
+}
+
+// MARK: bookkeeping
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EntryKey {
+    source: ImageCacheKey,
+    size: [u32; 2],
+    quality: ResampleQuality,
+}
+
+struct Entry<T> {
+    source: ImageSource,
+    value: T,
+    bytes: usize,
+    last_used: u64,
+    // atlas regions drawn from this image
+    regions: Vec<WeakAtlasRegion>,
+}
+
+impl<T> Entry<T> {
+    fn in_use(&mut self) -> bool {
+        self.regions.retain(WeakAtlasRegion::is_alive);
+        !self.regions.is_empty()
+    }
+}
+
+/// Least recently used entries within a byte budget, preferring to keep entries in use.
+struct Entries<T> {
+    budget: usize,
+    used: usize,
+    tick: u64,
+    map: HashMap<EntryKey, Entry<T>, fxhash::FxBuildHasher>,
+}
+
+impl<T: Clone> Entries<T> {
+    fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            used: 0,
+            tick: 0,
+            map: HashMap::default(),
+        }
+    }
+
+    fn get(&mut self, key: &EntryKey) -> Option<T> {
+        self.tick += 1;
+        let entry = self.map.get_mut(key)?;
+        entry.last_used = self.tick;
+        Some(entry.value.clone())
+    }
+
+    /// Inserts an entry and evicts others until the budget is met.
+    fn insert(
+        &mut self,
+        key: EntryKey,
+        source: ImageSource,
+        value: T,
+        bytes: usize,
+    ) -> Vec<ImageEviction> {
+        self.tick += 1;
+        let entry = Entry {
+            source,
+            value,
+            bytes,
+            last_used: self.tick,
+            regions: Vec::new(),
+        };
+        self.used += bytes;
+        if let Some(old) = self.map.insert(key.clone(), entry) {
+            // decoded concurrently by another style
+            self.used -= old.bytes;
+        }
+        self.evict_to_budget(Some(&key))
+    }
+
+    fn retain_region(&mut self, key: &EntryKey, region: WeakAtlasRegion) {
+        if let Some(entry) = self.map.get_mut(key) {
+            entry.regions.retain(WeakAtlasRegion::is_alive);
+            entry.regions.push(region);
+        }
+    }
+
+    /// Evicts unused entries first, each group least recently used first. `keep` is never
+    /// evicted.
+    fn evict_to_budget(&mut self, keep: Option<&EntryKey>) -> Vec<ImageEviction> {
+        let mut evicted = Vec::new();
+        while self.used > self.budget {
+            let victim = self
+                .map
+                .iter_mut()
+                .filter(|(key, _)| Some(*key) != keep)
+                .map(|(key, entry)| ((entry.in_use(), entry.last_used), key))
+                .min_by_key(|(order, _)| *order)
+                .map(|(_, key)| key.clone());
+            let Some(victim) = victim else {
+                break;
+            };
+            evicted.extend(self.remove(&victim, EvictionReason::Budget));
+        }
+        evicted
+    }
+
+    fn evict_unused(&mut self) -> Vec<ImageEviction> {
+        let unused: Vec<EntryKey> = self
+            .map
+            .iter_mut()
+            .filter_map(|(key, entry)| (!entry.in_use()).then(|| key.clone()))
+            .collect();
+        unused
+            .iter()
+            .filter_map(|key| self.remove(key, EvictionReason::Unused))
+            .collect()
+    }
+
+    fn clear(&mut self, reason: EvictionReason) -> Vec<ImageEviction> {
+        let keys: Vec<EntryKey> = self.map.keys().cloned().collect();
+        keys.iter()
+            .filter_map(|key| self.remove(key, reason))
+            .collect()
+    }
+
+    fn remove(&mut self, key: &EntryKey, reason: EvictionReason) -> Option<ImageEviction> {
+        let entry = self.map.remove(key)?;
+        self.used -= entry.bytes;
+        Some(ImageEviction {
+            source: entry.source,
+            size: key.size,
+            bytes: entry.bytes,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn key(path: &str) -> EntryKey {
+        EntryKey {
+            source: ImageCacheKey::Path(path.to_string()),
+            size: [10, 10],
+            quality: ResampleQuality::default(),
+        }
+    }
+
+    fn insert(entries: &mut Entries<()>, path: &str, bytes: usize) -> Vec<String> {
+        entries
+            .insert(key(path), ImageSource::from(path), (), bytes)
+            .into_iter()
+            .map(|eviction| match eviction.source {
+                ImageSource::Path(path) => path,
+                _ => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn evicts_least_recently_used_over_budget() {
+        let mut entries = Entries::new(300);
+        assert!(insert(&mut entries, "a", 100).is_empty());
+        assert!(insert(&mut entries, "b", 100).is_empty());
+        assert!(insert(&mut entries, "c", 100).is_empty());
+
+        // touching "a" makes "b" the oldest
+        assert!(entries.get(&key("a")).is_some());
+        assert_eq!(insert(&mut entries, "d", 100), vec!["b"]);
+        assert_eq!(entries.used, 300);
+
+        // an entry larger than the budget evicts everything else but stays itself
+        let mut evicted = insert(&mut entries, "huge", 500);
+        evicted.sort();
+        assert_eq!(evicted, vec!["a", "c", "d"]);
+        assert_eq!(entries.map.len(), 1);
+        assert_eq!(entries.used, 500);
+    }
+
+    #[test]
+    fn budget_changes_and_clear_report_evictions() {
+        let mut entries = Entries::new(1000);
+        insert(&mut entries, "a", 400);
+        insert(&mut entries, "b", 400);
+
+        entries.budget = 500;
+        let evicted = entries.evict_to_budget(None);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].reason, EvictionReason::Budget);
+        assert!(entries.get(&key("b")).is_some());
+
+        // nothing was drawn from "b", so it counts as unused
+        let evicted = entries.evict_unused();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].reason, EvictionReason::Unused);
+        assert_eq!(entries.used, 0);
+        assert!(entries.clear(EvictionReason::Cleared).is_empty());
+    }
+}
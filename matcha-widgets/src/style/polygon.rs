@@ -6,6 +6,7 @@ use matcha_core::{
     color::Color,
     context::WidgetContext,
     metrics::{QRect, QSize},
+    ui::PrepareFuture,
 };
 use parking_lot::Mutex;
 use renderer::{
@@ -25,7 +26,9 @@ pub struct Polygon {
     polygon: Arc<PolygonFn>,
     adaptive_affine: Arc<AdaptFn>,
     cache_the_mesh: bool,
-    caches: Mutex<utils::cache::Cache<CacheKey, Caches>>,
+    tessellate_in_background: bool,
+    // shared with the tessellation job started by `prepare`
+    caches: Arc<Mutex<utils::cache::Cache<CacheKey, Caches>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -81,7 +84,8 @@ impl Clone for Polygon {
             polygon: self.polygon.clone(),
             adaptive_affine: self.adaptive_affine.clone(),
             cache_the_mesh: self.cache_the_mesh,
-            caches: Mutex::new(utils::cache::Cache::default()),
+            tessellate_in_background: self.tessellate_in_background,
+            caches: Arc::new(Mutex::new(utils::cache::Cache::default())),
         }
     }
 }
@@ -92,7 +96,8 @@ impl Polygon {
             polygon: Arc::new(move |_, _| mesh.clone()),
             adaptive_affine: Arc::new(|_, _| nalgebra::Matrix4::identity()),
             cache_the_mesh: true,
-            caches: Mutex::new(utils::cache::Cache::default()),
+            tessellate_in_background: false,
+            caches: Arc::new(Mutex::new(utils::cache::Cache::default())),
         }
     }
 
@@ -104,7 +109,8 @@ impl Polygon {
             polygon: Arc::new(polygon),
             adaptive_affine: Arc::new(|_, _| nalgebra::Matrix4::identity()),
             cache_the_mesh: true,
-            caches: Mutex::new(utils::cache::Cache::default()),
+            tessellate_in_background: false,
+            caches: Arc::new(Mutex::new(utils::cache::Cache::default())),
         }
     }
 
@@ -118,8 +124,25 @@ impl Polygon {
 
     pub fn do_not_cache_mesh(mut self) -> Self {
         self.cache_the_mesh = false;
+        self.tessellate_in_background = false;
         self
     }
+
+    /// Builds the mesh on the [worker pool](matcha_core::worker_pool) instead of while the
+    /// frame renders. Nothing is drawn until the mesh for the current size is ready.
+    ///
+    /// Implies caching the mesh, overriding an earlier
+    /// [`do_not_cache_mesh`](Self::do_not_cache_mesh).
+    pub fn tessellate_in_background(mut self) -> Self {
+        self.cache_the_mesh = true;
+        self.tessellate_in_background = true;
+        self
+    }
+
+    // in background mode, a missing mesh is built by `prepare`, not on demand
+    fn mesh_pending(&self, cache: &utils::cache::Cache<CacheKey, Caches>, key: &CacheKey) -> bool {
+        self.tessellate_in_background && !cache.get().is_some_and(|(k, _)| k == key)
+    }
 }
 
 // MARK: Style
@@ -136,6 +159,9 @@ impl Style for Polygon {
         let key = CacheKey::new(boundary, &adaptive_affine);
 
         let mut cache = self.caches.lock();
+        if self.mesh_pending(&cache, &key) {
+            return None;
+        }
 
         if self.cache_the_mesh {
             let (_k, v) = cache.get_or_insert_with(&key, || Caches {
@@ -259,6 +285,9 @@ impl Style for Polygon {
         let key = CacheKey::new(boundary_size, &adaptive_affine);
 
         let mut cache = self.caches.lock();
+        if self.mesh_pending(&cache, &key) {
+            return false;
+        }
 
         // obtain mesh either from cache or freshly computed
         let mesh = if self.cache_the_mesh {
@@ -361,6 +390,9 @@ impl Style for Polygon {
         // compute adaptive affine and include in cache key
         let adaptive_affine = (self.adaptive_affine)(boundary_size, ctx);
         let key = CacheKey::new(boundary_size, &adaptive_affine);
+        if self.mesh_pending(&cache, &key) {
+            return;
+        }
 
         let mesh = if self.cache_the_mesh {
             cache
@@ -375,7 +407,7 @@ impl Style for Polygon {
             (self.polygon)(boundary_size, ctx)
         };
 
-        let renderer = ctx.any_resource().get_or_insert_default::<VertexColor>();
+        let renderer = ctx.renderer::<VertexColor>();
 
         // build ColorVertex list and indices safely
         let (vertices, indices): (Vec<ColorVertex>, Vec<u16>) = match &mesh {
@@ -387,7 +419,7 @@ impl Style for Polygon {
                     .iter()
                     .map(|v| ColorVertex {
                         position: nalgebra::Point3::new(v.position[0], v.position[1], 0.0),
-                        color: v.color.to_rgba_f32(),
+                        color: ctx.display_color(v.color).to_rgba_f32(),
                     })
                     .collect();
                 let indices = (0..vertices.len() - 2)
@@ -403,7 +435,7 @@ impl Style for Polygon {
                     .iter()
                     .map(|v| ColorVertex {
                         position: nalgebra::Point3::new(v.position[0], v.position[1], 0.0),
-                        color: v.color.to_rgba_f32(),
+                        color: ctx.display_color(v.color).to_rgba_f32(),
                     })
                     .collect();
                 let indices = (0..vertices.len() as u16).collect();
@@ -417,7 +449,7 @@ impl Style for Polygon {
                     .iter()
                     .map(|v| ColorVertex {
                         position: nalgebra::Point3::new(v.position[0], v.position[1], 0.0),
-                        color: v.color.to_rgba_f32(),
+                        color: ctx.display_color(v.color).to_rgba_f32(),
                     })
                     .collect();
                 let indices = (1..vertices.len() - 1)
@@ -433,7 +465,7 @@ impl Style for Polygon {
                     .iter()
                     .map(|v| ColorVertex {
                         position: nalgebra::Point3::new(v.position[0], v.position[1], 0.0),
-                        color: v.color.to_rgba_f32(),
+                        color: ctx.display_color(v.color).to_rgba_f32(),
                     })
                     .collect();
                 (color_vertices, indices.clone())
@@ -463,6 +495,31 @@ impl Style for Polygon {
             &ctx.device(),
         );
     }
+
+    fn prepare(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        if !self.tessellate_in_background {
+            return None;
+        }
+
+        let adaptive_affine = (self.adaptive_affine)(bounds, ctx);
+        let key = CacheKey::new(bounds, &adaptive_affine);
+        if !self.mesh_pending(&self.caches.lock(), &key) {
+            return None;
+        }
+
+        let polygon = self.polygon.clone();
+        let worker_ctx = ctx.clone();
+        let task = ctx
+            .worker_pool()
+            .spawn(move |_| polygon(bounds, &worker_ctx));
+
+        let caches = self.caches.clone();
+        Some(Box::pin(async move {
+            if let Ok(mesh) = task.await {
+                caches.lock().set(key, Caches { mesh, rect: None });
+            }
+        }))
+    }
 }
 
 fn is_inside_of_triangle(position: [f32; 2], triangle: [[f32; 2]; 3]) -> bool {
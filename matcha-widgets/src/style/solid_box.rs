@@ -35,6 +35,10 @@ impl Style for SolidBox {
             && position[1] <= boundary_size[1]
     }
 
+    fn is_opaque(&self, _bounds: [f32; 2], ctx: &WidgetContext) -> bool {
+        ctx.display_color(self.color).is_opaque()
+    }
+
     fn draw(
         &self,
         encoder: &mut wgpu::CommandEncoder,
@@ -45,7 +49,7 @@ impl Style for SolidBox {
     ) {
         let target_size = target.texture_size();
         let target_format = target.format();
-        let renderer = ctx.any_resource().get_or_insert_default::<VertexColor>();
+        let renderer = ctx.renderer::<VertexColor>();
 
         // create a render pass targeting the atlas region so implementations can use multiple passes if needed
         let mut render_pass = match target.begin_render_pass(encoder) {
@@ -53,22 +57,23 @@ impl Style for SolidBox {
             Err(_) => return,
         };
 
+        let color = ctx.display_color(self.color).to_rgba_f32();
         let vertices = [
             ColorVertex {
                 position: nalgebra::Point3::new(0.0, 0.0, 0.0),
-                color: self.color.to_rgba_f32(),
+                color,
             },
             ColorVertex {
                 position: nalgebra::Point3::new(boundary_size[0], 0.0, 0.0),
-                color: self.color.to_rgba_f32(),
+                color,
             },
             ColorVertex {
                 position: nalgebra::Point3::new(boundary_size[0], boundary_size[1], 0.0),
-                color: self.color.to_rgba_f32(),
+                color,
             },
             ColorVertex {
                 position: nalgebra::Point3::new(0.0, boundary_size[1], 0.0),
-                color: self.color.to_rgba_f32(),
+                color,
             },
         ];
 
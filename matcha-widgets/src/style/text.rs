@@ -1,8 +1,9 @@
 use crate::style::Style;
 use gpu_utils::texture_atlas::atlas_simple::atlas::AtlasRegion;
 use matcha_core::metrics::QSize;
-use matcha_core::{color::Color, context::WidgetContext};
-use parking_lot::Mutex;
+use matcha_core::{color::Color, context::WidgetContext, fonts::FontSource, ui::PrepareFuture};
+use parking_lot::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub use glyphon::cosmic_text::Stretch as TextStretch;
 pub use glyphon::cosmic_text::Style as TextStyle;
@@ -101,11 +102,43 @@ impl Sentence {
     }
 }
 
+/// Distance between the baselines of consecutive lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineHeight {
+    /// Exactly this many pixels.
+    Exact(f32),
+    /// This factor times the font size.
+    Multiple(f32),
+}
+
+impl LineHeight {
+    pub fn resolve(self, font_size: f32) -> f32 {
+        match self {
+            LineHeight::Exact(height) => height,
+            LineHeight::Multiple(factor) => font_size * factor,
+        }
+    }
+}
+
+impl From<f32> for LineHeight {
+    fn from(height: f32) -> Self {
+        LineHeight::Exact(height)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct TextDesc {
     pub texts: Vec<Sentence>,
     pub font_size: f32,
-    pub line_height: f32,
+    pub line_height: LineHeight,
+    /// Extra space after every glyph.
+    pub letter_spacing: f32,
+    /// Extra space after every space character, on top of `letter_spacing`.
+    pub word_spacing: f32,
+    /// Extra space between paragraphs, i.e. after every line feed.
+    pub paragraph_spacing: f32,
+    /// Distance between tab stops in space widths.
+    pub tab_width: u16,
 }
 
 impl TextDesc {
@@ -113,7 +146,11 @@ impl TextDesc {
         Self {
             texts,
             font_size: 14.0,
-            line_height: 20.0,
+            line_height: LineHeight::Exact(20.0),
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            paragraph_spacing: 0.0,
+            tab_width: 8,
         }
     }
 
@@ -122,8 +159,28 @@ impl TextDesc {
         self
     }
 
-    pub fn line_height(mut self, height: f32) -> Self {
-        self.line_height = height;
+    pub fn line_height(mut self, height: impl Into<LineHeight>) -> Self {
+        self.line_height = height.into();
+        self
+    }
+
+    pub fn letter_spacing(mut self, spacing: f32) -> Self {
+        self.letter_spacing = spacing;
+        self
+    }
+
+    pub fn word_spacing(mut self, spacing: f32) -> Self {
+        self.word_spacing = spacing;
+        self
+    }
+
+    pub fn paragraph_spacing(mut self, spacing: f32) -> Self {
+        self.paragraph_spacing = spacing;
+        self
+    }
+
+    pub fn tab_width(mut self, spaces: u16) -> Self {
+        self.tab_width = spaces;
         self
     }
 
@@ -140,6 +197,9 @@ impl TextDesc {
 /// **To prevent deadlock, must lock in the order of font_system -> swash_cache -> cache -> text_atlas.**
 struct TextShared {
     font_system: Mutex<glyphon::FontSystem>,
+    // generation of the app's font registry loaded into `font_system`, changed only while it
+    // is locked
+    loaded_fonts: AtomicUsize,
     swash_cache: Mutex<glyphon::SwashCache>,
     cache: Mutex<glyphon::Cache>,
     text_atlas: Mutex<glyphon::TextAtlas>,
@@ -155,24 +215,122 @@ impl TextShared {
 
         Self {
             font_system: Mutex::new(font_system),
+            loaded_fonts: AtomicUsize::new(0),
             swash_cache: Mutex::new(swash_cache),
             cache: Mutex::new(cache),
             text_atlas: Mutex::new(text_atlas),
         }
     }
+
+    /// Locks the font system for shaping, after loading the fonts the app registered since
+    /// the last call.
+    fn lock_font_system(&self, ctx: &WidgetContext) -> MutexGuard<'_, glyphon::FontSystem> {
+        let mut font_system = self.font_system.lock();
+        let Some(fonts) = ctx.fonts() else {
+            return font_system;
+        };
+
+        let loaded = self.loaded_fonts.load(Ordering::Acquire);
+        let sources = fonts.sources_since(loaded);
+        if sources.is_empty() {
+            return font_system;
+        }
+        // `db_mut` also drops the cached font matches, so fallbacks are resolved again
+        let db = font_system.db_mut();
+        for source in &sources {
+            match source {
+                FontSource::Bytes(bytes) => {
+                    db.load_font_source(glyphon::cosmic_text::fontdb::Source::Binary(
+                        bytes.clone(),
+                    ));
+                }
+                FontSource::Dir(path) => db.load_fonts_dir(path),
+            }
+        }
+        self.loaded_fonts
+            .store(loaded + sources.len(), Ordering::Release);
+        font_system
+    }
 }
 
 pub struct Text {
     // text info
     pub texts: Vec<Sentence>,
     pub font_size: f32,
-    pub line_height: f32,
+    pub line_height: LineHeight,
+    pub letter_spacing: f32,
+    pub word_spacing: f32,
+    pub paragraph_spacing: f32,
+    pub tab_width: u16,
 
     // rendering context (needs `wgpu::Device` or `GlyphonShared` so cannot be created in `new()`)
-    buffer: utils::cache::RwCache<QSize, glyphon::Buffer>,
-    text_area_size: utils::cache::RwCache<QSize, [f32; 2]>,
+    paragraphs: utils::cache::RwCache<ShapeKey, Vec<Paragraph>>,
+    text_area_size: utils::cache::RwCache<ShapeKey, [f32; 2]>,
     viewport: utils::cache::RwCache<QSize, glyphon::Viewport>,
     text_renderer: utils::cache::RwCache<QSize, glyphon::TextRenderer>,
+    // layout whose glyphs were handed to the worker pool for rasterization
+    rasterized: Mutex<Option<ShapeKey>>,
+}
+
+/// The size text is shaped within and the generation of the app's font registry it is shaped
+/// with, so registering a font shapes the text again.
+type ShapeKey = (QSize, usize);
+
+fn shape_key(size: [f32; 2], ctx: &WidgetContext) -> ShapeKey {
+    let fonts = ctx.fonts().map_or(0, |fonts| fonts.generation());
+    (QSize::from(size), fonts)
+}
+
+/// Where a sentence lies on one line of a [`Text`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SentenceBounds {
+    /// Index of the sentence in [`TextDesc::texts`].
+    pub sentence: usize,
+    /// `[min, max]` corners, spanning the height of the line.
+    pub rect: [[f32; 2]; 2],
+    pub baseline: f32,
+}
+
+impl SentenceBounds {
+    pub fn contains(&self, position: [f32; 2]) -> bool {
+        let [min, max] = self.rect;
+        (min[0]..=max[0]).contains(&position[0]) && (min[1]..=max[1]).contains(&position[1])
+    }
+}
+
+/// Widest and tallest a label measured with [`measured_text`] gets without a limit of its
+/// own.
+pub(crate) const MAX_LABEL_EXTENT: f32 = 4096.0;
+
+/// Builds the style for a label of one sentence and measures it, wrapped at `max_width`.
+pub(crate) fn measured_text(
+    sentence: Sentence,
+    font_size: f32,
+    line_height: impl Into<LineHeight>,
+    max_width: f32,
+    ctx: &WidgetContext,
+) -> (Text, [f32; 2]) {
+    let text = Text::new(
+        &TextDesc::new(vec![sentence])
+            .font_size(font_size)
+            .line_height(line_height),
+    );
+    let constraints =
+        matcha_core::metrics::Constraints::new([0.0, max_width], [0.0, MAX_LABEL_EXTENT]);
+    let size = text
+        .required_region(&constraints, ctx)
+        .map_or([0.0, 0.0], |rect| [rect.width(), rect.height()]);
+    (text, size)
+}
+
+/// A paragraph shaped in its own buffer, `top` pixels below the top of the text.
+///
+/// cosmic-text has no paragraph spacing, so paragraphs are laid out one below the other here.
+struct Paragraph {
+    top: f32,
+    /// byte offset of the paragraph in the concatenated sentences.
+    start: usize,
+    buffer: glyphon::Buffer,
 }
 
 impl Text {
@@ -181,81 +339,329 @@ impl Text {
             texts: desc.texts.clone(),
             font_size: desc.font_size,
             line_height: desc.line_height,
-            buffer: utils::cache::RwCache::new(),
+            letter_spacing: desc.letter_spacing,
+            word_spacing: desc.word_spacing,
+            paragraph_spacing: desc.paragraph_spacing,
+            tab_width: desc.tab_width,
+            paragraphs: utils::cache::RwCache::new(),
             text_area_size: utils::cache::RwCache::new(),
             viewport: utils::cache::RwCache::new(),
             text_renderer: utils::cache::RwCache::new(),
+            rasterized: Mutex::new(None),
         }
     }
 
     pub fn eq_desc(&self, desc: &TextDesc) -> bool {
         self.texts == desc.texts
             && (self.font_size - desc.font_size).abs() < f32::EPSILON
-            && (self.line_height - desc.line_height).abs() < f32::EPSILON
+            && self.line_height == desc.line_height
+            && (self.letter_spacing - desc.letter_spacing).abs() < f32::EPSILON
+            && (self.word_spacing - desc.word_spacing).abs() < f32::EPSILON
+            && (self.paragraph_spacing - desc.paragraph_spacing).abs() < f32::EPSILON
+            && self.tab_width == desc.tab_width
     }
-}
 
-impl Style for Text {
-    fn required_region(
+    /// Distance from the top of the text area to the baseline of the first line.
+    ///
+    /// Returns `None` when the text has no shaped lines (e.g. empty text).
+    pub fn first_baseline(
         &self,
         constraints: &matcha_core::metrics::Constraints,
         ctx: &WidgetContext,
-    ) -> Option<matcha_core::metrics::QRect> {
+    ) -> Option<f32> {
+        // make sure the buffer for these constraints is shaped.
+        self.required_region(constraints, ctx)?;
+
         let q_size = QSize::from(constraints.max_size());
+        let cached = self.paragraphs.get()?;
+        let ((size, _), paragraphs) = &*cached;
+        if *size != q_size {
+            return None;
+        }
 
-        let (_, buffer) = &*self.buffer.get_or_insert_with(&q_size, || {
-            let size = constraints.max_size();
+        let paragraph = paragraphs.first()?;
+        paragraph
+            .buffer
+            .layout_runs()
+            .next()
+            .map(|run| paragraph.top + run.line_y)
+    }
 
-            let glyphon_shared = ctx
-                .any_resource()
-                .get_or_insert_with(|| TextShared::setup(&ctx.device(), &ctx.queue()));
+    /// Boxes around the glyphs of each sentence, one per line the sentence spans, laid out
+    /// within `constraints`.
+    pub fn sentence_bounds(
+        &self,
+        constraints: &matcha_core::metrics::Constraints,
+        ctx: &WidgetContext,
+    ) -> Vec<SentenceBounds> {
+        if self.required_region(constraints, ctx).is_none() {
+            return Vec::new();
+        }
 
-            let mut font_system = glyphon_shared.font_system.lock();
+        let q_size = QSize::from(constraints.max_size());
+        let Some(cached) = self.paragraphs.get() else {
+            return Vec::new();
+        };
+        let ((size, _), paragraphs) = &*cached;
+        if *size != q_size {
+            return Vec::new();
+        }
 
-            let mut buffer = glyphon::Buffer::new(
-                &mut font_system,
-                glyphon::Metrics::new(self.font_size, self.line_height),
-            );
-            buffer.set_size(&mut font_system, Some(size[0]), Some(size[1]));
+        let mut bounds: Vec<SentenceBounds> = Vec::new();
+        for paragraph in paragraphs {
+            for run in paragraph.buffer.layout_runs() {
+                let top = paragraph.top + run.line_top;
+                let bottom = top + run.line_height;
+                let baseline = paragraph.top + run.line_y;
+                let line_start = bounds.len();
+                for glyph in run.glyphs {
+                    // glyphs of a sentence are contiguous within a line unless bidi text
+                    // reorders them, in which case the sentence gets several boxes
+                    let on_this_line = bounds.len() > line_start;
+                    match bounds.last_mut() {
+                        Some(last) if on_this_line && last.sentence == glyph.metadata => {
+                            last.rect[0][0] = last.rect[0][0].min(glyph.x);
+                            last.rect[1][0] = last.rect[1][0].max(glyph.x + glyph.w);
+                        }
+                        _ => bounds.push(SentenceBounds {
+                            sentence: glyph.metadata,
+                            rect: [[glyph.x, top], [glyph.x + glyph.w, bottom]],
+                            baseline,
+                        }),
+                    }
+                }
+            }
+        }
+        bounds
+    }
+
+    /// Runs `f` on the paragraphs laid out within `constraints`.
+    fn with_paragraphs<R>(
+        &self,
+        constraints: &matcha_core::metrics::Constraints,
+        ctx: &WidgetContext,
+        f: impl FnOnce(&[Paragraph]) -> R,
+    ) -> Option<R> {
+        self.required_region(constraints, ctx)?;
+
+        let cached = self.paragraphs.get()?;
+        let ((size, _), paragraphs) = &*cached;
+        (*size == QSize::from(constraints.max_size())).then(|| f(paragraphs))
+    }
+
+    /// Byte offset into the concatenated sentences of the caret position closest to
+    /// `position`.
+    pub fn hit(
+        &self,
+        constraints: &matcha_core::metrics::Constraints,
+        position: [f32; 2],
+        ctx: &WidgetContext,
+    ) -> Option<usize> {
+        self.with_paragraphs(constraints, ctx, |paragraphs| {
+            // the paragraph whose area, including the spacing below it, holds `position`
+            let paragraph = paragraphs
+                .iter()
+                .rev()
+                .find(|paragraph| paragraph.top <= position[1])
+                .or(paragraphs.first())?;
+            let cursor = paragraph
+                .buffer
+                .hit(position[0], position[1] - paragraph.top)?;
+            Some(paragraph.start + cursor.index)
+        })
+        .flatten()
+    }
+
+    /// The caret at byte `offset` as a vertical line from `[x, top]` to `[x, bottom]`.
+    ///
+    /// Positions within a line are those of left-to-right text.
+    pub fn caret(
+        &self,
+        constraints: &matcha_core::metrics::Constraints,
+        offset: usize,
+        ctx: &WidgetContext,
+    ) -> Option<[[f32; 2]; 2]> {
+        self.with_paragraphs(constraints, ctx, |paragraphs| {
+            let paragraph = paragraphs
+                .iter()
+                .rev()
+                .find(|paragraph| paragraph.start <= offset)?;
+            let index = offset - paragraph.start;
+
+            let mut runs = paragraph.buffer.layout_runs().peekable();
+            while let Some(run) = runs.next() {
+                let end = run.glyphs.last().map_or(0, |glyph| glyph.end);
+                // the caret at a wrap goes to the start of the next line
+                if index < end || runs.peek().is_none() {
+                    let x = run_x(&run, index);
+                    let top = paragraph.top + run.line_top;
+                    return Some([[x, top], [x, top + run.line_height]]);
+                }
+            }
+            None
+        })
+        .flatten()
+    }
+
+    /// Boxes covering the bytes in `range`, one per line.
+    pub fn range_rects(
+        &self,
+        constraints: &matcha_core::metrics::Constraints,
+        range: std::ops::Range<usize>,
+        ctx: &WidgetContext,
+    ) -> Vec<[[f32; 2]; 2]> {
+        self.with_paragraphs(constraints, ctx, |paragraphs| {
+            let mut rects = Vec::new();
+            for paragraph in paragraphs {
+                for run in paragraph.buffer.layout_runs() {
+                    let (Some(first), Some(last)) = (run.glyphs.first(), run.glyphs.last()) else {
+                        continue;
+                    };
+                    let run_range = paragraph.start + first.start..paragraph.start + last.end;
+                    let start = range.start.max(run_range.start);
+                    let end = range.end.min(run_range.end);
+                    if start >= end {
+                        continue;
+                    }
+                    let top = paragraph.top + run.line_top;
+                    rects.push([
+                        [run_x(&run, start - paragraph.start), top],
+                        [run_x(&run, end - paragraph.start), top + run.line_height],
+                    ]);
+                }
+            }
+            rects
+        })
+        .unwrap_or_default()
+    }
 
+    /// Shapes the text into paragraphs within `size`.
+    fn shape(&self, font_system: &mut glyphon::FontSystem, size: [f32; 2]) -> Vec<Paragraph> {
+        let metrics =
+            glyphon::Metrics::new(self.font_size, self.line_height.resolve(self.font_size));
+
+        let mut paragraphs = Vec::new();
+        let mut top = 0.0;
+        let mut start = 0;
+        for spans in self.spans() {
+            let len: usize = spans.iter().map(|(text, ..)| text.len()).sum();
+            let mut buffer = glyphon::Buffer::new(font_system, metrics);
+            buffer.set_size(font_system, Some(size[0]), Some(size[1]));
+            buffer.set_tab_width(font_system, self.tab_width);
             buffer.set_rich_text(
-                &mut font_system,
-                self.texts.iter().map(|e| {
-                    (
-                        e.text.as_str(),
-                        glyphon::Attrs {
-                            family: (&e.family).into(),
-                            stretch: e.stretch,
-                            style: e.style,
-                            weight: e.weight,
-                            color_opt: Some({
-                                let c = e.color.to_rgba_u8();
-                                glyphon::Color::rgba(c[0], c[1], c[2], c[3])
-                            }),
-                            // defaults
-                            metadata: 0,
-                            cache_key_flags: glyphon::cosmic_text::CacheKeyFlags::empty(),
-                            metrics_opt: None,
-                            letter_spacing_opt: Some(glyphon::cosmic_text::LetterSpacing(0.0)),
-                            font_features: glyphon::cosmic_text::FontFeatures::default(),
-                        },
-                    )
-                }),
+                font_system,
+                spans
+                    .into_iter()
+                    .map(|(text, index, is_space)| (text, self.attrs(index, is_space))),
                 &glyphon::Attrs::new(),
                 glyphon::cosmic_text::Shaping::Advanced,
                 None,
             );
+            buffer.shape_until_scroll(font_system, false);
 
-            buffer.shape_until_scroll(&mut font_system, false);
+            let (_, height) = get_shaped_buffer_size(&buffer);
+            paragraphs.push(Paragraph { top, start, buffer });
+            top += height + self.paragraph_spacing;
+            // the line feed between paragraphs
+            start += len + 1;
+        }
+        paragraphs
+    }
 
-            buffer
-        });
+    /// The sentences split into paragraphs at line feeds, as spans of text with the index of
+    /// their sentence. With word spacing, runs of spaces become spans of their own, flagged
+    /// with `true`.
+    fn spans(&self) -> Vec<Vec<(&str, usize, bool)>> {
+        let mut paragraphs = Vec::new();
+        let mut current = Vec::new();
+        for (index, sentence) in self.texts.iter().enumerate() {
+            for (i, line) in sentence.text.split('\n').enumerate() {
+                if i > 0 {
+                    paragraphs.push(std::mem::take(&mut current));
+                }
+                if self.word_spacing == 0.0 {
+                    current.push((line, index, false));
+                } else {
+                    current.extend(
+                        space_runs(line)
+                            .into_iter()
+                            .map(|(run, is_space)| (run, index, is_space)),
+                    );
+                }
+            }
+        }
+        paragraphs.push(current);
+        paragraphs
+    }
 
-        let (_, text_area_size) = &*self.text_area_size.get_or_insert_with(&q_size, || {
-            let (w, h) = get_shaped_buffer_size(buffer);
-            [w, h]
+    fn attrs(&self, index: usize, is_space: bool) -> glyphon::Attrs<'_> {
+        let sentence = &self.texts[index];
+
+        // cosmic-text takes letter spacing in ems
+        let spacing = self.letter_spacing + if is_space { self.word_spacing } else { 0.0 };
+        let letter_spacing = spacing / self.font_size;
+
+        glyphon::Attrs {
+            family: (&sentence.family).into(),
+            stretch: sentence.stretch,
+            style: sentence.style,
+            weight: sentence.weight,
+            color_opt: Some({
+                let c = sentence.color.to_rgba_u8();
+                glyphon::Color::rgba(c[0], c[1], c[2], c[3])
+            }),
+            letter_spacing_opt: (letter_spacing != 0.0)
+                .then_some(glyphon::cosmic_text::LetterSpacing(letter_spacing)),
+            // glyphs remember their sentence for `sentence_bounds`
+            metadata: index,
+            // defaults
+            cache_key_flags: glyphon::cosmic_text::CacheKeyFlags::empty(),
+            metrics_opt: None,
+            font_features: glyphon::cosmic_text::FontFeatures::default(),
+        }
+    }
+}
+
+/// Splits `text` into alternating runs of spaces and other characters.
+fn space_runs(text: &str) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut in_spaces = None;
+    for (i, c) in text.char_indices() {
+        let is_space = c == ' ';
+        if in_spaces.is_some_and(|in_spaces| in_spaces != is_space) {
+            runs.push((&text[start..i], !is_space));
+            start = i;
+        }
+        in_spaces = Some(is_space);
+    }
+    if let Some(is_space) = in_spaces {
+        runs.push((&text[start..], is_space));
+    }
+    runs
+}
+
+impl Style for Text {
+    fn required_region(
+        &self,
+        constraints: &matcha_core::metrics::Constraints,
+        ctx: &WidgetContext,
+    ) -> Option<matcha_core::metrics::QRect> {
+        let key = shape_key(constraints.max_size(), ctx);
+
+        let (_, paragraphs) = &*self.paragraphs.get_or_insert_with(&key, || {
+            let glyphon_shared = ctx
+                .any_resource()
+                .get_or_insert_with(|| TextShared::setup(&ctx.device(), &ctx.queue()));
+
+            let mut font_system = glyphon_shared.lock_font_system(ctx);
+            self.shape(&mut font_system, constraints.max_size())
         });
 
+        let (_, text_area_size) = &*self
+            .text_area_size
+            .get_or_insert_with(&key, || get_shaped_size(paragraphs));
+
         Some(matcha_core::metrics::QRect::new(
             [0.0, 0.0],
             [text_area_size[0], text_area_size[1]],
@@ -270,61 +676,26 @@ impl Style for Text {
         offset: [f32; 2],
         ctx: &WidgetContext,
     ) {
-        // Reuse shaped buffer and renderer where possible. Observe lock order:
+        // Reuse shaped buffers and renderer where possible. Observe lock order:
         // font_system -> swash_cache -> cache -> text_atlas
         let size = boundary_size;
         let q_size = QSize::from(size);
+        let key = shape_key(size, ctx);
 
         let glyphon_shared = ctx
             .any_resource()
             .get_or_insert_with(|| TextShared::setup(&ctx.device(), &ctx.queue()));
 
         // 1) Acquire locks in required order
-        let mut font_system = glyphon_shared.font_system.lock();
+        let mut font_system = glyphon_shared.lock_font_system(ctx);
         let mut swash_cache = glyphon_shared.swash_cache.lock();
         let cache = glyphon_shared.cache.lock();
         let mut text_atlas = glyphon_shared.text_atlas.lock();
 
-        // 2) Obtain or create the buffer (mutable)
-        let (_, buffer) = &mut *self.buffer.get_or_insert_with(&q_size, || {
-            let mut b = glyphon::Buffer::new(
-                &mut font_system,
-                glyphon::Metrics::new(self.font_size, self.line_height),
-            );
-            b.set_size(&mut font_system, Some(size[0]), Some(size[1]));
-            b
-        });
-
-        // Ensure buffer size and content reflect the current boundary
-        buffer.set_size(&mut font_system, Some(size[0]), Some(size[1]));
-        buffer.set_rich_text(
-            &mut font_system,
-            self.texts.iter().map(|e| {
-                (
-                    e.text.as_str(),
-                    glyphon::Attrs {
-                        family: (&e.family).into(),
-                        stretch: e.stretch,
-                        style: e.style,
-                        weight: e.weight,
-                        color_opt: Some({
-                            let c = e.color.to_rgba_u8();
-                            glyphon::Color::rgba(c[0], c[1], c[2], c[3])
-                        }),
-                        // defaults
-                        metadata: 0,
-                        cache_key_flags: glyphon::cosmic_text::CacheKeyFlags::empty(),
-                        metrics_opt: None,
-                        letter_spacing_opt: None,
-                        font_features: glyphon::cosmic_text::FontFeatures::default(),
-                    },
-                )
-            }),
-            &glyphon::Attrs::new(),
-            glyphon::cosmic_text::Shaping::Advanced,
-            None,
-        );
-        buffer.shape_until_scroll(&mut font_system, false);
+        // 2) Obtain or shape the paragraphs for the current boundary
+        let (_, paragraphs) = &*self
+            .paragraphs
+            .get_or_insert_with(&key, || self.shape(&mut font_system, size));
 
         // 3) Prepare viewport and text_renderer, caching them in RwOption to avoid recreation
         let target_size = target.texture_size();
@@ -349,12 +720,12 @@ impl Style for Text {
             )
         });
 
-        // 4) Build TextArea mapped into the target region.
+        // 4) Build a TextArea per paragraph mapped into the target region.
         // Use offset as the top-left position within the target region.
-        let text_area = glyphon::TextArea {
-            buffer,
+        let text_areas = paragraphs.iter().map(|paragraph| glyphon::TextArea {
+            buffer: &paragraph.buffer,
             left: offset[0],
-            top: offset[1],
+            top: offset[1] + paragraph.top,
             scale: 1.0,
             bounds: glyphon::TextBounds {
                 left: 0,
@@ -364,7 +735,7 @@ impl Style for Text {
             },
             default_color: glyphon::Color::rgba(128, 128, 128, 255),
             custom_glyphs: &[],
-        };
+        });
 
         // 5) Call prepare to ensure glyphs are rasterized into glyphon's atlas and vertex buffer is populated.
         if text_renderer
@@ -374,7 +745,7 @@ impl Style for Text {
                 &mut font_system,
                 &mut text_atlas,
                 viewport,
-                [text_area],
+                text_areas,
                 &mut swash_cache,
             )
             .is_err()
@@ -400,6 +771,81 @@ impl Style for Text {
         // 7) Trim atlas usage flags so glyphon can evict unused glyphs later.
         text_atlas.trim();
     }
+
+    /// Rasterizes the glyphs of the shaped text on the worker pool, so that `draw` finds them
+    /// in the glyph cache instead of rasterizing them while the frame renders.
+    fn prepare(&self, _bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        let cached = self.paragraphs.get()?;
+        let (key, paragraphs) = &*cached;
+        {
+            let mut rasterized = self.rasterized.lock();
+            if *rasterized == Some(*key) {
+                return None;
+            }
+            *rasterized = Some(*key);
+        }
+
+        // `draw` places the text at the origin of its region
+        let glyphs: std::collections::HashSet<glyphon::cosmic_text::CacheKey> = paragraphs
+            .iter()
+            .flat_map(|paragraph| {
+                paragraph.buffer.layout_runs().flat_map(move |run| {
+                    let line_y = paragraph.top + run.line_y;
+                    run.glyphs
+                        .iter()
+                        .map(move |glyph| glyph.physical((0.0, line_y), 1.0).cache_key)
+                })
+            })
+            .collect();
+        if glyphs.is_empty() {
+            return None;
+        }
+
+        let glyphon_shared = ctx
+            .any_resource()
+            .get_or_insert_with(|| TextShared::setup(&ctx.device(), &ctx.queue()));
+        let task = ctx.worker_pool().spawn(move |cancel| {
+            for cache_key in glyphs {
+                if cancel.is_cancelled() {
+                    return;
+                }
+                // lock per glyph so that text drawn meanwhile is not blocked for long
+                let mut font_system = glyphon_shared.font_system.lock();
+                let mut swash_cache = glyphon_shared.swash_cache.lock();
+                swash_cache.get_image(&mut font_system, cache_key);
+            }
+        });
+
+        Some(Box::pin(async move {
+            let _ = task.await;
+        }))
+    }
+}
+
+/// The x position of the caret before byte `index` of the run's buffer line.
+fn run_x(run: &glyphon::cosmic_text::LayoutRun, index: usize) -> f32 {
+    for glyph in run.glyphs {
+        if index <= glyph.start {
+            return glyph.x;
+        }
+        if index < glyph.end {
+            // inside a cluster of several characters, e.g. a ligature
+            let fraction = (index - glyph.start) as f32 / (glyph.end - glyph.start) as f32;
+            return glyph.x + glyph.w * fraction;
+        }
+    }
+    run.glyphs.last().map_or(0.0, |glyph| glyph.x + glyph.w)
+}
+
+/// Width of the widest line and height of all paragraphs.
+fn get_shaped_size(paragraphs: &[Paragraph]) -> [f32; 2] {
+    let mut size = [0.0f32; 2];
+    for paragraph in paragraphs {
+        let (width, height) = get_shaped_buffer_size(&paragraph.buffer);
+        size[0] = size[0].max(width);
+        size[1] = paragraph.top + height;
+    }
+    size
 }
 
 fn get_shaped_buffer_size(buffer: &glyphon::Buffer) -> (f32, f32) {
@@ -420,3 +866,47 @@ fn get_shaped_buffer_size(buffer: &glyphon::Buffer) -> (f32, f32) {
 
     (max_width, buffer.metrics().line_height * (lines as f32))
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_split_at_line_feeds_and_spaces() {
+        assert_eq!(
+            space_runs("a  bc d "),
+            vec![
+                ("a", false),
+                ("  ", true),
+                ("bc", false),
+                (" ", true),
+                ("d", false),
+                (" ", true)
+            ]
+        );
+        assert!(space_runs("").is_empty());
+
+        let desc = TextDesc::new(vec![
+            Sentence::new("one two\nthree"),
+            Sentence::new(" four"),
+        ]);
+        let text = Text::new(&desc);
+        fn texts(spans: Vec<Vec<(&str, usize, bool)>>) -> Vec<Vec<&str>> {
+            spans
+                .into_iter()
+                .map(|paragraph| paragraph.into_iter().map(|(text, ..)| text).collect())
+                .collect()
+        }
+        assert_eq!(
+            texts(text.spans()),
+            vec![vec!["one two"], vec!["three", " four"]]
+        );
+
+        let text = Text::new(&desc.word_spacing(2.0));
+        assert_eq!(
+            texts(text.spans()),
+            vec![vec!["one", " ", "two"], vec!["three", " ", "four"]]
+        );
+    }
+}
@@ -198,7 +198,6 @@ impl TextCosmic<'_> {
 
     fn render_to_texture(&self, size: [u32; 2], data: &[u8], ctx: &WidgetContext) -> wgpu::Texture {
         let device = ctx.device();
-        let queue = ctx.queue();
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("TextCosmic Texture"),
             size: wgpu::Extent3d {
@@ -240,7 +239,9 @@ impl TextCosmic<'_> {
                 .copy_from_slice(&data[src_off..src_off + src_row_bytes]);
         }
 
-        queue.write_texture(
+        // sampled by the caller's next submission
+        let ring = ctx.upload_ring();
+        ring.write_texture(
             wgpu::TexelCopyTextureInfo {
                 texture: &texture,
                 mip_level: 0,
@@ -259,6 +260,7 @@ impl TextCosmic<'_> {
                 depth_or_array_layers: 1,
             },
         );
+        ring.flush();
 
         texture
     }
@@ -427,6 +429,7 @@ impl Style for TextCosmic<'static> {
                     cache_in_memory.size[0] as f32,
                     cache_in_memory.size[1] as f32,
                 ],
+                source_uv: None,
                 color_transformation: None,
                 color_offset: None,
             },
@@ -34,9 +34,7 @@ impl Style for ViewportClear {
         ctx: &WidgetContext,
     ) {
         let target_format = target.format();
-        let renderer = ctx
-            .any_resource()
-            .get_or_insert_default::<RendererViewportClear>();
+        let renderer = ctx.renderer::<RendererViewportClear>();
 
         let mut render_pass = match target.begin_render_pass(encoder) {
             Ok(rp) => rp,
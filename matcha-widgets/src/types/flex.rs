@@ -24,4 +24,8 @@ pub enum AlignItems {
     Start,
     End,
     Center,
+    /// Align children so that their first text baselines line up.
+    /// Children that do not report a baseline are placed at the start.
+    /// Only meaningful for horizontal containers; vertical containers treat it as `Start`.
+    Baseline,
 }
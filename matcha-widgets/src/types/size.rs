@@ -162,6 +162,96 @@ impl Size {
     }
 }
 
+// MARK: calc
+
+/// Sizes combine like CSS `calc()`: `Size::parent_w(1.0) - Size::px(20.0)` is resolved
+/// during measure from the same parent size, child size and context as its operands.
+impl Size {
+    /// Specify size in percent of parent width.
+    pub fn percent_w(percent: f32) -> Self {
+        Self::parent_w(percent / 100.0)
+    }
+
+    /// Specify size in percent of parent height.
+    pub fn percent_h(percent: f32) -> Self {
+        Self::parent_h(percent / 100.0)
+    }
+
+    /// Combine two sizes with `f`, e.g. `a.calc(b, f32::hypot)`.
+    pub fn calc(&self, other: &Size, f: impl Fn(f32, f32) -> f32 + Send + Sync + 'static) -> Self {
+        let (a, b) = (self.f.clone(), other.f.clone());
+        Self {
+            f: Arc::new(move |parent_size, child_size, ctx| {
+                f(
+                    a(parent_size, child_size, ctx),
+                    b(parent_size, child_size, ctx),
+                )
+            }),
+        }
+    }
+
+    /// The smaller of the two sizes, like CSS `min()`.
+    pub fn min(&self, other: &Size) -> Self {
+        self.calc(other, f32::min)
+    }
+
+    /// The larger of the two sizes, like CSS `max()`.
+    pub fn max(&self, other: &Size) -> Self {
+        self.calc(other, f32::max)
+    }
+
+    /// This size limited to `[min, max]`, like CSS `clamp()`. `min` wins when the bounds cross.
+    pub fn clamp(&self, min: &Size, max: &Size) -> Self {
+        self.min(max).max(min)
+    }
+}
+
+impl std::ops::Add for Size {
+    type Output = Size;
+
+    fn add(self, rhs: Size) -> Size {
+        self.calc(&rhs, |a, b| a + b)
+    }
+}
+
+impl std::ops::Sub for Size {
+    type Output = Size;
+
+    fn sub(self, rhs: Size) -> Size {
+        self.calc(&rhs, |a, b| a - b)
+    }
+}
+
+impl std::ops::Mul<f32> for Size {
+    type Output = Size;
+
+    fn mul(self, rhs: f32) -> Size {
+        let f = self.f;
+        Size {
+            f: Arc::new(move |parent_size, child_size, ctx| f(parent_size, child_size, ctx) * rhs),
+        }
+    }
+}
+
+impl std::ops::Div<f32> for Size {
+    type Output = Size;
+
+    fn div(self, rhs: f32) -> Size {
+        let f = self.f;
+        Size {
+            f: Arc::new(move |parent_size, child_size, ctx| f(parent_size, child_size, ctx) / rhs),
+        }
+    }
+}
+
+impl std::ops::Neg for Size {
+    type Output = Size;
+
+    fn neg(self) -> Size {
+        self * -1.0
+    }
+}
+
 impl Size {
     pub fn size(
         &self,
@@ -187,3 +277,107 @@ impl PartialEq for Size {
         Arc::ptr_eq(&self.f, &other.f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use matcha_core::test_kit::TestContext;
+
+    use super::*;
+
+    const PARENT: [f32; 2] = [400.0, 200.0];
+
+    fn resolve(size: &Size, test: &TestContext) -> f32 {
+        size.size(
+            PARENT,
+            &mut ChildSize::with_size([60.0, 30.0]),
+            test.widget_context(),
+        )
+    }
+
+    #[test]
+    fn parent_width_minus_pixels() {
+        let test = TestContext::builder().build();
+
+        assert_eq!(
+            resolve(&(Size::parent_w(1.0) - Size::px(20.0)), &test),
+            380.0
+        );
+        assert_eq!(
+            resolve(&(Size::parent_h(0.5) + Size::px(20.0)), &test),
+            120.0
+        );
+    }
+
+    #[test]
+    fn scales_and_negates() {
+        let test = TestContext::builder().build();
+
+        assert_eq!(resolve(&(Size::parent_w(1.0) * 0.5), &test), 200.0);
+        assert_eq!(resolve(&(Size::parent_w(1.0) / 3.0), &test), 400.0 / 3.0);
+        assert_eq!(resolve(&-Size::px(20.0), &test), -20.0);
+        assert_eq!(
+            resolve(&((Size::parent_w(1.0) - Size::px(40.0)) / 2.0), &test),
+            180.0
+        );
+    }
+
+    #[test]
+    fn nested_min_and_max() {
+        let test = TestContext::builder().build();
+
+        let size = Size::parent_w(1.0)
+            .min(&Size::px(300.0))
+            .max(&Size::parent_h(1.0).min(&Size::px(250.0)));
+        assert_eq!(resolve(&size, &test), 300.0);
+
+        let size = Size::px(100.0)
+            .max(&Size::parent_h(0.25))
+            .min(&Size::px(80.0).max(&Size::parent_w(0.1)));
+        assert_eq!(resolve(&size, &test), 80.0);
+    }
+
+    #[test]
+    fn clamp_keeps_the_size_within_bounds() {
+        let test = TestContext::builder().build();
+        let (min, max) = (Size::px(100.0), Size::px(300.0));
+
+        assert_eq!(resolve(&Size::px(50.0).clamp(&min, &max), &test), 100.0);
+        assert_eq!(resolve(&Size::px(200.0).clamp(&min, &max), &test), 200.0);
+        assert_eq!(
+            resolve(&Size::parent_w(1.0).clamp(&min, &max), &test),
+            300.0
+        );
+    }
+
+    #[test]
+    fn clamp_with_crossed_bounds_uses_the_minimum() {
+        let test = TestContext::builder().build();
+
+        let size = Size::parent_w(1.0).clamp(&Size::px(150.0), &Size::px(50.0));
+        assert_eq!(resolve(&size, &test), 150.0);
+    }
+
+    #[test]
+    fn percent_of_the_parent() {
+        let test = TestContext::builder().build();
+
+        assert_eq!(resolve(&Size::percent_w(25.0), &test), 100.0);
+        assert_eq!(resolve(&Size::percent_h(50.0), &test), 100.0);
+        assert_eq!(
+            resolve(&(Size::percent_w(100.0) - Size::percent_h(100.0)), &test),
+            200.0
+        );
+    }
+
+    #[test]
+    fn viewport_and_content_units_combine() {
+        let test = TestContext::builder().viewport_size(1000.0, 500.0).build();
+
+        assert_eq!(resolve(&(Size::vw(0.5) - Size::vh(0.5)), &test), 250.0);
+        assert_eq!(resolve(&(Size::child_w(1.0) + Size::px(16.0)), &test), 76.0);
+        assert_eq!(
+            resolve(&Size::child_h(2.0).max(&Size::percent_h(10.0)), &test),
+            60.0
+        );
+    }
+}
@@ -1,5 +1,21 @@
 pub mod button;
+pub mod canvas;
+pub mod chart;
+#[cfg(feature = "syntect")]
+pub mod code_view;
+pub mod context_menu;
+pub mod date_picker;
 pub mod image;
+pub mod number_input;
+pub mod performance_hud;
 pub mod plain;
+pub mod rich_text;
+pub mod table;
+pub mod tabs;
 pub mod template_widget;
 pub mod text;
+pub mod text_area;
+pub mod text_edit;
+pub mod toast_overlay;
+pub mod viewport_3d;
+pub mod window_controls;
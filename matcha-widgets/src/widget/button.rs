@@ -7,7 +7,7 @@ use matcha_core::{
     context::WidgetContext,
     device_input::{DeviceInput, DeviceInputData, ElementState, MouseInput, MouseLogicalButton},
     ui::{
-        AnyWidgetFrame, Background, Dom, Widget, WidgetFrame,
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
         widget::{AnyWidget, InvalidationHandle},
     },
 };
@@ -19,6 +19,7 @@ use crate::style::solid_box::SolidBox;
 
 pub struct Button<T> {
     label: Option<String>,
+    layout_style: LayoutStyle,
     content: Box<dyn Dom<T>>,
     on_click: Option<Arc<dyn Fn() -> T + Send + Sync>>,
 }
@@ -27,11 +28,18 @@ impl<T: 'static> Button<T> {
     pub fn new(content: impl Dom<T>) -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             content: Box::new(content),
             on_click: None,
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
     pub fn label(mut self, label: &str) -> Self {
         self.label = Some(label.to_string());
         self
@@ -49,15 +57,22 @@ impl<T: 'static> Button<T> {
 #[async_trait::async_trait]
 impl<T: Send + Sync + 'static> Dom<T> for Button<T> {
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            vec![(self.content.build_widget_tree(), ())],
-            vec![0], // Use a fixed ID for the single child
-            ButtonNode {
-                on_click: self.on_click.clone(),
-                state: ButtonState::Normal,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0], // Use a fixed ID for the single child
+                ButtonNode {
+                    on_click: self.on_click.clone(),
+                    state: ButtonState::Normal,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -0,0 +1,915 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::style::image::{Image, ImageSource};
+use crate::style::polygon::{Mesh, Polygon, Vertex};
+use crate::style::text::{MAX_LABEL_EXTENT, Sentence, measured_text};
+use crate::style::{Style, style_node, translation};
+
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::{
+    device_input::DeviceInput,
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+
+/// Largest distance in pixels between a curve and the segments it is drawn with.
+const TOLERANCE: f32 = 0.25;
+/// How far a sharp corner of a stroke may reach, in stroke widths.
+const MITER_LIMIT: f32 = 4.0;
+/// Triangles drawn by one mesh; `Polygon` indexes vertices with `u16`.
+const MESH_TRIANGLES: usize = u16::MAX as usize / 3;
+
+// MARK: Path
+
+/// An outline of straight and curved segments, for [`Painter::fill_path`] and
+/// [`Painter::stroke_path`].
+///
+/// Each [`move_to`](Self::move_to) starts a new subpath.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Segment {
+    MoveTo([f32; 2]),
+    LineTo([f32; 2]),
+    QuadTo([f32; 2], [f32; 2]),
+    CubicTo([f32; 2], [f32; 2], [f32; 2]),
+    Close,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(mut self, point: [f32; 2]) -> Self {
+        self.segments.push(Segment::MoveTo(point));
+        self
+    }
+
+    pub fn line_to(mut self, point: [f32; 2]) -> Self {
+        self.segments.push(Segment::LineTo(point));
+        self
+    }
+
+    /// A quadratic Bézier curve to `point`.
+    pub fn quad_to(mut self, control: [f32; 2], point: [f32; 2]) -> Self {
+        self.segments.push(Segment::QuadTo(control, point));
+        self
+    }
+
+    /// A cubic Bézier curve to `point`.
+    pub fn cubic_to(mut self, control1: [f32; 2], control2: [f32; 2], point: [f32; 2]) -> Self {
+        self.segments
+            .push(Segment::CubicTo(control1, control2, point));
+        self
+    }
+
+    /// Connects the subpath back to its start.
+    pub fn close(mut self) -> Self {
+        self.segments.push(Segment::Close);
+        self
+    }
+
+    /// The subpaths as polylines, and whether each is closed.
+    fn flatten(&self) -> Vec<(Vec<[f32; 2]>, bool)> {
+        let mut subpaths = Vec::new();
+        let mut points: Vec<[f32; 2]> = Vec::new();
+        let mut finish = |points: &mut Vec<[f32; 2]>, closed: bool| {
+            if points.len() > 1 {
+                subpaths.push((std::mem::take(points), closed));
+            }
+            points.clear();
+        };
+
+        for segment in &self.segments {
+            let current = points.last().copied().unwrap_or([0.0, 0.0]);
+            match *segment {
+                Segment::MoveTo(point) => {
+                    finish(&mut points, false);
+                    points.push(point);
+                }
+                Segment::LineTo(point) => {
+                    if points.is_empty() {
+                        points.push(current);
+                    }
+                    points.push(point);
+                }
+                Segment::QuadTo(control, point) => {
+                    if points.is_empty() {
+                        points.push(current);
+                    }
+                    let steps = curve_steps(&[current, control, point]);
+                    points.extend((1..=steps).map(|step| {
+                        let t = step as f32 / steps as f32;
+                        let s = 1.0 - t;
+                        blend(&[(current, s * s), (control, 2.0 * s * t), (point, t * t)])
+                    }));
+                }
+                Segment::CubicTo(control1, control2, point) => {
+                    if points.is_empty() {
+                        points.push(current);
+                    }
+                    let steps = curve_steps(&[current, control1, control2, point]);
+                    points.extend((1..=steps).map(|step| {
+                        let t = step as f32 / steps as f32;
+                        let s = 1.0 - t;
+                        blend(&[
+                            (current, s * s * s),
+                            (control1, 3.0 * s * s * t),
+                            (control2, 3.0 * s * t * t),
+                            (point, t * t * t),
+                        ])
+                    }));
+                }
+                Segment::Close => {
+                    let start = points.first().copied();
+                    finish(&mut points, true);
+                    // drawing continues from the start of the closed subpath
+                    points.extend(start);
+                }
+            }
+        }
+        finish(&mut points, false);
+
+        subpaths
+    }
+}
+
+/// Segments for a curve with the given control polygon, so that it stays within
+/// [`TOLERANCE`] of the curve.
+fn curve_steps(control: &[[f32; 2]]) -> usize {
+    let length: f32 = control.windows(2).map(|w| distance(w[0], w[1])).sum();
+    ((length / TOLERANCE).sqrt().ceil() as usize).clamp(1, 256)
+}
+
+fn blend(weighted: &[([f32; 2], f32)]) -> [f32; 2] {
+    weighted.iter().fold([0.0, 0.0], |sum, (point, weight)| {
+        [sum[0] + point[0] * weight, sum[1] + point[1] * weight]
+    })
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    (b[0] - a[0]).hypot(b[1] - a[1])
+}
+
+// MARK: Painter
+
+/// Records the drawing of a [`Canvas`].
+///
+/// Coordinates are in pixels from the top left corner of the canvas. Later commands are drawn
+/// over earlier ones.
+#[derive(Debug, Default)]
+pub struct Painter {
+    commands: Vec<Command>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+    Rect { position: [f32; 2], size: [f32; 2] },
+    Circle { center: [f32; 2], radius: f32 },
+    Path(Path),
+}
+
+#[derive(Clone, PartialEq)]
+enum Command {
+    Fill {
+        shape: Shape,
+        color: Color,
+    },
+    Stroke {
+        shape: Shape,
+        width: f32,
+        color: Color,
+    },
+    Text {
+        position: [f32; 2],
+        text: String,
+        font_size: f32,
+        color: Color,
+    },
+    Image {
+        position: [f32; 2],
+        size: [f32; 2],
+        source: ImageSource,
+    },
+}
+
+impl std::fmt::Debug for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Fill { shape, .. } => f.debug_tuple("Fill").field(shape).finish(),
+            Command::Stroke { shape, width, .. } => {
+                f.debug_tuple("Stroke").field(shape).field(width).finish()
+            }
+            Command::Text { position, text, .. } => {
+                f.debug_tuple("Text").field(position).field(text).finish()
+            }
+            Command::Image { position, size, .. } => {
+                f.debug_tuple("Image").field(position).field(size).finish()
+            }
+        }
+    }
+}
+
+impl Painter {
+    pub fn fill_rect(&mut self, position: [f32; 2], size: [f32; 2], color: Color) {
+        self.fill(Shape::Rect { position, size }, color);
+    }
+
+    /// Strokes the outline of a rectangle, centered on its edges.
+    pub fn stroke_rect(&mut self, position: [f32; 2], size: [f32; 2], width: f32, color: Color) {
+        self.stroke(Shape::Rect { position, size }, width, color);
+    }
+
+    pub fn fill_circle(&mut self, center: [f32; 2], radius: f32, color: Color) {
+        self.fill(Shape::Circle { center, radius }, color);
+    }
+
+    pub fn stroke_circle(&mut self, center: [f32; 2], radius: f32, width: f32, color: Color) {
+        self.stroke(Shape::Circle { center, radius }, width, color);
+    }
+
+    /// Fills each subpath of `path` as if it were closed.
+    ///
+    /// Subpaths are filled separately, so they cannot cut holes into each other, and a
+    /// subpath that crosses itself may be filled only in part.
+    pub fn fill_path(&mut self, path: &Path, color: Color) {
+        self.fill(Shape::Path(path.clone()), color);
+    }
+
+    /// Strokes `path` with mitered corners and flat ends.
+    pub fn stroke_path(&mut self, path: &Path, width: f32, color: Color) {
+        self.stroke(Shape::Path(path.clone()), width, color);
+    }
+
+    /// Draws a line of text with its top left corner at `position`.
+    pub fn text(&mut self, position: [f32; 2], text: &str, font_size: f32, color: Color) {
+        self.commands.push(Command::Text {
+            position,
+            text: text.to_string(),
+            font_size,
+            color,
+        });
+    }
+
+    /// Draws an image stretched to `size`.
+    pub fn image(&mut self, position: [f32; 2], size: [f32; 2], source: impl Into<ImageSource>) {
+        self.commands.push(Command::Image {
+            position,
+            size,
+            source: source.into(),
+        });
+    }
+
+    fn fill(&mut self, shape: Shape, color: Color) {
+        self.commands.push(Command::Fill { shape, color });
+    }
+
+    fn stroke(&mut self, shape: Shape, width: f32, color: Color) {
+        self.commands.push(Command::Stroke {
+            shape,
+            width,
+            color,
+        });
+    }
+}
+
+// MARK: Tessellation
+
+impl Shape {
+    /// The outlines of the shape, and whether each is closed.
+    fn outlines(&self) -> Vec<(Vec<[f32; 2]>, bool)> {
+        match self {
+            Shape::Rect { position, size } => {
+                let [x, y] = *position;
+                let [w, h] = *size;
+                vec![(vec![[x, y], [x + w, y], [x + w, y + h], [x, y + h]], true)]
+            }
+            Shape::Circle { center, radius } => {
+                let radius = radius.abs();
+                if radius <= 0.0 {
+                    return vec![];
+                }
+                // segments whose middle stays within the tolerance of the circle
+                let steps = if radius > TOLERANCE {
+                    (PI / (1.0 - TOLERANCE / radius).acos()).ceil() as usize
+                } else {
+                    8
+                };
+                let steps = steps.clamp(8, 512);
+                let points = (0..steps)
+                    .map(|step| {
+                        let angle = step as f32 / steps as f32 * 2.0 * PI;
+                        [
+                            center[0] + radius * angle.cos(),
+                            center[1] + radius * angle.sin(),
+                        ]
+                    })
+                    .collect();
+                vec![(points, true)]
+            }
+            Shape::Path(path) => path.flatten(),
+        }
+    }
+
+    /// Vertices of the triangles covering the shape.
+    fn fill_triangles(&self) -> Vec<[f32; 2]> {
+        self.outlines()
+            .into_iter()
+            .flat_map(|(points, _)| {
+                let points = without_repeats(&points, true);
+                triangulate(&points)
+                    .into_iter()
+                    .flat_map(move |triangle| triangle.map(|index| points[index]))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Vertices of the triangles covering a stroke of the outlines of the shape.
+    fn stroke_triangles(&self, width: f32) -> Vec<[f32; 2]> {
+        self.outlines()
+            .into_iter()
+            .flat_map(|(points, closed)| stroke(&without_repeats(&points, closed), closed, width))
+            .collect()
+    }
+}
+
+/// `points` without consecutive duplicates, and without a last point equal to the first if
+/// the outline is `closed`.
+fn without_repeats(points: &[[f32; 2]], closed: bool) -> Vec<[f32; 2]> {
+    let mut result: Vec<[f32; 2]> = Vec::with_capacity(points.len());
+    for &point in points {
+        if result.last() != Some(&point) {
+            result.push(point);
+        }
+    }
+    if closed && result.len() > 1 && result.first() == result.last() {
+        result.pop();
+    }
+    result
+}
+
+fn cross(o: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+}
+
+/// Splits a simple polygon into triangles by ear clipping, as indices into `points`.
+///
+/// Gives up on the part it cannot split if the polygon crosses itself.
+fn triangulate(points: &[[f32; 2]]) -> Vec<[usize; 3]> {
+    let area: f32 = (0..points.len())
+        .map(|i| cross([0.0, 0.0], points[i], points[(i + 1) % points.len()]))
+        .sum();
+    if points.len() < 3 || area == 0.0 {
+        return vec![];
+    }
+    let orientation = area.signum();
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        let ear = (0..len).find(|&i| {
+            let [a, b, c] = [
+                remaining[(i + len - 1) % len],
+                remaining[i],
+                remaining[(i + 1) % len],
+            ];
+            let [pa, pb, pc] = [points[a], points[b], points[c]];
+            if cross(pa, pb, pc) * orientation <= 0.0 {
+                return false;
+            }
+            // no other corner may lie within the ear
+            !remaining.iter().any(|&other| {
+                other != a
+                    && other != b
+                    && other != c
+                    && cross(pa, pb, points[other]) * orientation >= 0.0
+                    && cross(pb, pc, points[other]) * orientation >= 0.0
+                    && cross(pc, pa, points[other]) * orientation >= 0.0
+            })
+        });
+        let Some(i) = ear else {
+            return triangles;
+        };
+        triangles.push([
+            remaining[(i + len - 1) % len],
+            remaining[i],
+            remaining[(i + 1) % len],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+/// Vertices of the triangles covering a polyline stroked `width` wide.
+fn stroke(points: &[[f32; 2]], closed: bool, width: f32) -> Vec<[f32; 2]> {
+    let len = points.len();
+    if len < 2 || width <= 0.0 {
+        return vec![];
+    }
+    let segments = if closed { len } else { len - 1 };
+    let normals: Vec<[f32; 2]> = (0..segments)
+        .map(|i| {
+            let [a, b] = [points[i], points[(i + 1) % len]];
+            let length = distance(a, b);
+            [-(b[1] - a[1]) / length, (b[0] - a[0]) / length]
+        })
+        .collect();
+
+    // both sides of the stroke at each point, mitered where segments meet
+    let half_width = width / 2.0;
+    let sides: Vec<[[f32; 2]; 2]> = (0..len)
+        .map(|i| {
+            let before = if closed || i > 0 {
+                normals[(i + segments - 1) % segments]
+            } else {
+                normals[0]
+            };
+            let after = if closed || i < segments {
+                normals[i % segments]
+            } else {
+                normals[segments - 1]
+            };
+            let sum = [before[0] + after[0], before[1] + after[1]];
+            let length = sum[0].hypot(sum[1]);
+            let miter = if length > 1e-3 {
+                let miter = [sum[0] / length, sum[1] / length];
+                let cos = miter[0] * after[0] + miter[1] * after[1];
+                let scale = half_width / cos.max(1.0 / MITER_LIMIT);
+                [miter[0] * scale, miter[1] * scale]
+            } else {
+                // the stroke turns back on itself
+                [after[0] * half_width, after[1] * half_width]
+            };
+            let point = points[i];
+            [
+                [point[0] + miter[0], point[1] + miter[1]],
+                [point[0] - miter[0], point[1] - miter[1]],
+            ]
+        })
+        .collect();
+
+    (0..segments)
+        .flat_map(|i| {
+            let [a_left, a_right] = sides[i];
+            let [b_left, b_right] = sides[(i + 1) % len];
+            [a_left, a_right, b_right, a_left, b_right, b_left]
+        })
+        .collect()
+}
+
+// MARK: DOM
+
+/// A widget drawn with paths, rectangles, circles, text and images.
+///
+/// The drawing is recorded by a [`Painter`] when the canvas is built, so it can be written
+/// like immediate-mode code from the app's state:
+///
+/// ```ignore
+/// Canvas::new(|painter| {
+///     painter.fill_rect([0.0, 0.0], [200.0, 100.0], Color::rgb(240, 240, 240));
+///     painter.stroke_circle([100.0, 50.0], 40.0, 2.0, Color::rgb(40, 120, 200));
+///     painter.text([8.0, 8.0], "hello", 14.0, Color::rgb(0, 0, 0));
+/// })
+/// ```
+///
+/// Between updates the recorded commands are compared with the previous ones, and only
+/// commands that changed are drawn again; an unchanged canvas does not redraw at all. The
+/// drawing is not clipped to the bounds of the widget.
+pub struct Canvas {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    size: Option<[f32; 2]>,
+    commands: Vec<Command>,
+}
+
+impl Canvas {
+    pub fn new(draw: impl FnOnce(&mut Painter)) -> Self {
+        let mut painter = Painter::default();
+        draw(&mut painter);
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            size: None,
+            commands: painter.commands,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Size of the canvas. Without it the canvas is as large as its drawing, reaching from
+    /// the origin.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.size = Some([width, height]);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for Canvas {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                CanvasNode {
+                    size: self.size,
+                    commands: self.commands.clone(),
+                    rendered: Mutex::new(vec![None; self.commands.len()]),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct CanvasNode {
+    size: Option<[f32; 2]>,
+    commands: Vec<Command>,
+    /// the drawing of each command, kept until the command changes.
+    rendered: Mutex<Vec<Option<Rendered>>>,
+}
+
+#[derive(Clone)]
+struct Rendered {
+    /// the color the command was drawn with, which changes with the color scheme.
+    color: Option<[f32; 4]>,
+    node: Arc<RenderNode>,
+}
+
+/// For each of `new`, the index of an equal command in `old` whose drawing it can take over.
+///
+/// Commands are matched at the same index first, so edits in place are cheap; commands that
+/// moved are looked up among the rest.
+fn matches(old: &[Command], new: &[Command]) -> Vec<Option<usize>> {
+    let mut taken = vec![false; old.len()];
+    let mut result: Vec<Option<usize>> = new
+        .iter()
+        .enumerate()
+        .map(|(i, command)| {
+            (old.get(i) == Some(command)).then(|| {
+                taken[i] = true;
+                i
+            })
+        })
+        .collect();
+    for (i, command) in new.iter().enumerate() {
+        if result[i].is_none()
+            && let Some(j) = (0..old.len()).find(|&j| !taken[j] && old[j] == *command)
+        {
+            taken[j] = true;
+            result[i] = Some(j);
+        }
+    }
+    result
+}
+
+impl CanvasNode {
+    /// The bottom right corner of the drawing.
+    fn extent(&self, ctx: &WidgetContext) -> [f32; 2] {
+        self.commands
+            .iter()
+            .map(|command| match command {
+                Command::Fill { shape, .. } => shape_extent(shape, 0.0),
+                Command::Stroke { shape, width, .. } => shape_extent(shape, width / 2.0),
+                Command::Text {
+                    position,
+                    text,
+                    font_size,
+                    color,
+                } => {
+                    let (_, size) = measured_text(
+                        Sentence::new(text).color(*color),
+                        *font_size,
+                        font_size * 1.25,
+                        MAX_LABEL_EXTENT,
+                        ctx,
+                    );
+                    [position[0] + size[0], position[1] + size[1]]
+                }
+                Command::Image { position, size, .. } => {
+                    [position[0] + size[0], position[1] + size[1]]
+                }
+            })
+            .fold([0.0, 0.0], |extent, corner| {
+                [extent[0].max(corner[0]), extent[1].max(corner[1])]
+            })
+    }
+
+    fn render_command(&self, command: &Command, ctx: &WidgetContext) -> RenderNode {
+        match command {
+            Command::Fill { shape, color } => mesh_node(&shape.fill_triangles(), *color, ctx),
+            Command::Stroke {
+                shape,
+                width,
+                color,
+            } => mesh_node(&shape.stroke_triangles(*width), *color, ctx),
+            Command::Text {
+                position,
+                text,
+                font_size,
+                color,
+            } => {
+                let (text, size) = measured_text(
+                    Sentence::new(text).color(*color),
+                    *font_size,
+                    font_size * 1.25,
+                    MAX_LABEL_EXTENT,
+                    ctx,
+                );
+                style_node(&text, size, ctx)
+                    .map(|node| {
+                        RenderNode::new().add_child(node, translation(position[0], position[1]))
+                    })
+                    .unwrap_or_default()
+            }
+            Command::Image {
+                position,
+                size,
+                source,
+            } => style_node(
+                &Image::new(source.clone()).stretch_to_boundary(),
+                *size,
+                ctx,
+            )
+            .map(|node| RenderNode::new().add_child(node, translation(position[0], position[1])))
+            .unwrap_or_default(),
+        }
+    }
+}
+
+/// The bottom right corner of `shape`, widened by `margin`.
+fn shape_extent(shape: &Shape, margin: f32) -> [f32; 2] {
+    shape
+        .outlines()
+        .iter()
+        .flat_map(|(points, _)| points)
+        .fold([0.0, 0.0], |extent, point| {
+            [
+                extent[0].max(point[0] + margin),
+                extent[1].max(point[1] + margin),
+            ]
+        })
+}
+
+fn command_color(command: &Command) -> Option<Color> {
+    match command {
+        Command::Fill { color, .. }
+        | Command::Stroke { color, .. }
+        | Command::Text { color, .. } => Some(*color),
+        Command::Image { .. } => None,
+    }
+}
+
+/// Draws triangles of one color, each texture covering the bounding box of its triangles.
+fn mesh_node(vertices: &[[f32; 2]], color: Color, ctx: &WidgetContext) -> RenderNode {
+    let mut render_node = RenderNode::new();
+    for chunk in vertices.chunks(MESH_TRIANGLES * 3) {
+        let (min, max) = chunk.iter().fold(
+            ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]),
+            |(min, max), p| {
+                (
+                    [min[0].min(p[0]), min[1].min(p[1])],
+                    [max[0].max(p[0]), max[1].max(p[1])],
+                )
+            },
+        );
+        let origin = [min[0].floor(), min[1].floor()];
+        let size = [max[0].ceil() - origin[0], max[1].ceil() - origin[1]];
+        if !(size[0] > 0.0 && size[1] > 0.0) {
+            continue;
+        }
+
+        let polygon = Polygon::new(Mesh::TriangleList {
+            vertices: chunk
+                .iter()
+                .map(|p| Vertex {
+                    position: [p[0] - origin[0], p[1] - origin[1]],
+                    color,
+                })
+                .collect(),
+        });
+        if let Some(node) = style_node(&polygon, size, ctx) {
+            render_node.push_child(node, translation(origin[0], origin[1]));
+        }
+    }
+    render_node
+}
+
+impl<T: Send + Sync + 'static> Widget<Canvas, T, ()> for CanvasNode {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a Canvas,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        if self.commands != dom.commands {
+            let rendered = self.rendered.get_mut();
+            let mut previous = std::mem::take(rendered);
+            *rendered = matches(&self.commands, &dom.commands)
+                .into_iter()
+                .map(|index| index.and_then(|index| previous[index].take()))
+                .collect();
+            self.commands = dom.commands.clone();
+
+            if let Some(handle) = cache_invalidator {
+                // the drawing decides the size unless it is set
+                if self.size.is_none() || dom.size.is_none() {
+                    handle.relayout_next_frame();
+                } else {
+                    handle.redraw_next_frame();
+                }
+            }
+        } else if self.size != dom.size
+            && let Some(handle) = cache_invalidator
+        {
+            handle.relayout_next_frame();
+        }
+        self.size = dom.size;
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let size = self.size.unwrap_or_else(|| self.extent(ctx));
+        [
+            size[0].clamp(constraints.min_width(), constraints.max_width()),
+            size[1].clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        _bounds: [f32; 2],
+        _event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        _ctx: &WidgetContext,
+    ) -> Option<T> {
+        None
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        let mut rendered = self.rendered.lock();
+        rendered.resize(self.commands.len(), None);
+
+        for (command, rendered) in self.commands.iter().zip(rendered.iter_mut()) {
+            let color = command_color(command).map(|color| ctx.display_color(color).to_rgba_f32());
+            let node = match rendered {
+                Some(cached) if cached.color == color && cached.node.is_valid() => {
+                    cached.node.clone()
+                }
+                _ => {
+                    let node = Arc::new(self.render_command(command, ctx));
+                    *rendered = Some(Rendered {
+                        color,
+                        node: node.clone(),
+                    });
+                    node
+                }
+            };
+            render_node.push_child(node, Matrix4::identity());
+        }
+
+        render_node
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn area(triangles: &[[f32; 2]]) -> f32 {
+        triangles
+            .chunks(3)
+            .map(|t| cross(t[0], t[1], t[2]).abs() / 2.0)
+            .sum()
+    }
+
+    #[test]
+    fn concave_paths_are_filled_exactly() {
+        // an L of three unit squares, in both orientations
+        let l = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 2.0],
+            [0.0, 2.0],
+        ];
+        let mut path = Path::new().move_to(l[0]);
+        for &point in &l[1..] {
+            path = path.line_to(point);
+        }
+        let forward = Shape::Path(path.close()).fill_triangles();
+        assert_eq!(forward.len(), 4 * 3);
+        assert_eq!(area(&forward), 3.0);
+
+        let mut reversed = l.to_vec();
+        reversed.reverse();
+        assert_eq!(triangulate(&reversed).len(), 4);
+    }
+
+    #[test]
+    fn strokes_are_mitered() {
+        let rect = Shape::Rect {
+            position: [0.0, 0.0],
+            size: [10.0, 10.0],
+        };
+        // a ring from 1 outside to 1 inside of the edges
+        assert!((area(&rect.stroke_triangles(2.0)) - (144.0 - 64.0)).abs() < 1e-3);
+
+        let line = Path::new().move_to([0.0, 0.0]).line_to([5.0, 0.0]);
+        assert_eq!(area(&Shape::Path(line).stroke_triangles(2.0)), 10.0);
+    }
+
+    #[test]
+    fn curves_are_flattened_within_tolerance() {
+        let path = Path::new()
+            .move_to([0.0, 0.0])
+            .quad_to([50.0, 100.0], [100.0, 0.0]);
+        let subpaths = path.flatten();
+        assert_eq!(subpaths.len(), 1);
+        let (points, closed) = &subpaths[0];
+        assert!(!closed);
+        assert_eq!(points.first(), Some(&[0.0, 0.0]));
+        assert_eq!(points.last(), Some(&[100.0, 0.0]));
+        // the curve peaks at y = 50
+        let peak = points.iter().map(|p| p[1]).fold(0.0, f32::max);
+        assert!((peak - 50.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn unchanged_commands_keep_their_drawing() {
+        let record = |draw: fn(&mut Painter)| {
+            let mut painter = Painter::default();
+            draw(&mut painter);
+            painter.commands
+        };
+        let old = record(|p| {
+            p.fill_rect([0.0, 0.0], [1.0, 1.0], Color::rgb(0, 0, 0));
+            p.fill_circle([5.0, 5.0], 2.0, Color::rgb(0, 0, 0));
+            p.text([0.0, 0.0], "a", 12.0, Color::rgb(0, 0, 0));
+        });
+        let new = record(|p| {
+            p.fill_circle([5.0, 5.0], 2.0, Color::rgb(0, 0, 0));
+            p.fill_rect([0.0, 0.0], [1.0, 1.0], Color::rgb(0, 0, 0));
+            p.text([0.0, 0.0], "b", 12.0, Color::rgb(0, 0, 0));
+            p.fill_circle([5.0, 5.0], 2.0, Color::rgb(0, 0, 0));
+        });
+        assert_eq!(matches(&old, &new), vec![Some(1), Some(0), None, None]);
+    }
+}
@@ -0,0 +1,679 @@
+use std::sync::Arc;
+
+use crate::style::solid_box::SolidBox;
+use crate::style::text::{MAX_LABEL_EXTENT, Sentence, Text, measured_text};
+use crate::style::{Style, style_node, translation};
+
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::localization::decimal_separator;
+use matcha_core::{
+    device_input::DeviceInput,
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+use renderer::widgets_renderer::series::{
+    RenderData, SeriesBuffer, SeriesKind, SeriesRenderer, TargetData,
+};
+
+/// Size of the chart where the constraints leave it open.
+const DEFAULT_SIZE: [f32; 2] = [320.0, 240.0];
+/// Space between the plot and the tick labels, and around the chart.
+const GAP: f32 = 6.0;
+/// Length of the tick marks outside the plot.
+const TICK_LENGTH: f32 = 4.0;
+
+// MARK: Series
+
+/// Points of a [`Chart`] and how they are drawn.
+///
+/// The points are shared, so passing the same `Arc` to every rebuilt chart does not copy
+/// them.
+#[derive(Clone, Debug)]
+pub struct Series {
+    kind: SeriesKind,
+    points: Arc<[[f32; 2]]>,
+    color: Color,
+    width: f32,
+    baseline: f32,
+}
+
+impl PartialEq for Series {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.color == other.color
+            && self.width == other.width
+            && self.baseline == other.baseline
+            && (Arc::ptr_eq(&self.points, &other.points) || self.points == other.points)
+    }
+}
+
+impl Series {
+    fn new(kind: SeriesKind, points: Arc<[[f32; 2]]>, color: Color, width: f32) -> Self {
+        Self {
+            kind,
+            points,
+            color,
+            width,
+            baseline: 0.0,
+        }
+    }
+
+    /// A line through `points`, 1.5 pixels wide.
+    pub fn line(points: impl Into<Arc<[[f32; 2]]>>, color: Color) -> Self {
+        Self::new(SeriesKind::Line, points.into(), color, 1.5)
+    }
+
+    /// The region between the line through `points` and the baseline.
+    pub fn area(points: impl Into<Arc<[[f32; 2]]>>, color: Color) -> Self {
+        Self::new(SeriesKind::Area, points.into(), color, 0.0)
+    }
+
+    /// A bar from the baseline to each point, 0.8 wide in x units.
+    pub fn bars(points: impl Into<Arc<[[f32; 2]]>>, color: Color) -> Self {
+        Self::new(SeriesKind::Bars, points.into(), color, 0.8)
+    }
+
+    /// Width of a line in pixels, or of the bars in x units.
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// The y that areas and bars extend to. 0 by default.
+    pub fn baseline(mut self, baseline: f32) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Smallest and largest x and y the series covers, including its baseline and bars.
+    fn extent(&self) -> Option<[[f32; 2]; 2]> {
+        let mut extent: Option<[[f32; 2]; 2]> = None;
+        for &[x, y] in self
+            .points
+            .iter()
+            .filter(|p| p[0].is_finite() && p[1].is_finite())
+        {
+            let [min, max] = extent.get_or_insert([[x, y], [x, y]]);
+            *min = [min[0].min(x), min[1].min(y)];
+            *max = [max[0].max(x), max[1].max(y)];
+        }
+        let [min, max] = extent.as_mut()?;
+        if self.kind != SeriesKind::Line {
+            min[1] = min[1].min(self.baseline);
+            max[1] = max[1].max(self.baseline);
+        }
+        if self.kind == SeriesKind::Bars {
+            min[0] -= self.width / 2.0;
+            max[0] += self.width / 2.0;
+        }
+        extent
+    }
+}
+
+// MARK: Axes
+
+/// Round numbers within `range`, about `count` of them, and the digits after the decimal
+/// point needed to tell them apart.
+fn ticks(range: [f32; 2], count: usize) -> (Vec<f32>, usize) {
+    let [min, max] = [f64::from(range[0]), f64::from(range[1])];
+    let span = max - min;
+    if !(span > 0.0 && span.is_finite()) || count == 0 {
+        return (vec![], 0);
+    }
+
+    let step = nice_step(span / count as f64);
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    let first = (min / step - 1e-9).ceil() as i64;
+    let last = (max / step + 1e-9).floor() as i64;
+    let ticks = (first..=last)
+        .map(|index| {
+            let value = index as f64 * step;
+            // no "-0"
+            if index == 0 { 0.0 } else { value as f32 }
+        })
+        .collect();
+    (ticks, decimals)
+}
+
+/// The smallest of 1, 2 and 5 times a power of ten that is at least `step`.
+fn nice_step(step: f64) -> f64 {
+    let magnitude = 10f64.powf(step.log10().floor());
+    let nice = match step / magnitude {
+        f if f <= 1.0 => 1.0,
+        f if f <= 2.0 => 2.0,
+        f if f <= 5.0 => 5.0,
+        _ => 10.0,
+    };
+    nice * magnitude
+}
+
+/// `range` widened to round numbers, as a y axis is shown.
+fn rounded_range(range: [f32; 2], count: usize) -> [f32; 2] {
+    let span = f64::from(range[1] - range[0]);
+    if !(span > 0.0 && span.is_finite()) || count == 0 {
+        return range;
+    }
+    let step = nice_step(span / count as f64);
+    [
+        ((f64::from(range[0]) / step).floor() * step) as f32,
+        ((f64::from(range[1]) / step).ceil() * step) as f32,
+    ]
+}
+
+/// `range`, or a unit around it if it is a single value.
+fn non_empty(range: [f32; 2]) -> [f32; 2] {
+    if range[1] > range[0] {
+        range
+    } else {
+        [range[0] - 0.5, range[0] + 0.5]
+    }
+}
+
+fn tick_label(value: f32, decimals: usize, separator: char) -> String {
+    let text = format!("{value:.decimals$}");
+    if separator == '.' {
+        text
+    } else {
+        text.replace('.', &separator.to_string())
+    }
+}
+
+/// Look of the axes of a [`Chart`].
+#[derive(Clone, Debug, PartialEq)]
+struct AxesStyle {
+    x_range: Option<[f32; 2]>,
+    y_range: Option<[f32; 2]>,
+    x_ticks: usize,
+    y_ticks: usize,
+    font_size: f32,
+    text_color: Color,
+    axis_color: Color,
+    grid_color: Color,
+}
+
+// MARK: DOM
+
+/// A plot of data [`Series`] with axes, tick labels and grid lines.
+///
+/// The ranges of the axes cover all series unless they are set; the y axis is widened to
+/// round numbers. Series are drawn in order, clipped to the plot, with their points kept on
+/// the GPU so thousands of them redraw cheaply. Tick labels use the decimal separator of the
+/// app's locale.
+pub struct Chart {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    series: Vec<Series>,
+    axes: AxesStyle,
+}
+
+impl Default for Chart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chart {
+    pub fn new() -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            series: Vec::new(),
+            axes: AxesStyle {
+                x_range: None,
+                y_range: None,
+                x_ticks: 5,
+                y_ticks: 5,
+                font_size: 12.0,
+                text_color: Color::rgb(80, 80, 80),
+                axis_color: Color::rgb(120, 120, 120),
+                grid_color: Color::rgb(230, 230, 230),
+            },
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Adds a series, drawn over the ones added before.
+    pub fn series(mut self, series: Series) -> Self {
+        self.series.push(series);
+        self
+    }
+
+    /// Shows x from `min` to `max` instead of the range of the series.
+    pub fn x_range(mut self, min: f32, max: f32) -> Self {
+        self.axes.x_range = Some([min, max]);
+        self
+    }
+
+    /// Shows y from `min` to `max` instead of the range of the series.
+    pub fn y_range(mut self, min: f32, max: f32) -> Self {
+        self.axes.y_range = Some([min, max]);
+        self
+    }
+
+    /// About how many ticks each axis gets. 5 by default; 0 hides the ticks of the axis.
+    pub fn ticks(mut self, x: usize, y: usize) -> Self {
+        self.axes.x_ticks = x;
+        self.axes.y_ticks = y;
+        self
+    }
+
+    /// Font size of the tick labels.
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.axes.font_size = size;
+        self
+    }
+
+    pub fn text_color(mut self, color: Color) -> Self {
+        self.axes.text_color = color;
+        self
+    }
+
+    pub fn axis_color(mut self, color: Color) -> Self {
+        self.axes.axis_color = color;
+        self
+    }
+
+    pub fn grid_color(mut self, color: Color) -> Self {
+        self.axes.grid_color = color;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for Chart {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                ChartNode {
+                    series: self.series.clone(),
+                    axes: self.axes.clone(),
+                    buffers: Mutex::new(Vec::new()),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct ChartNode {
+    series: Vec<Series>,
+    axes: AxesStyle,
+    /// the points of `series` on the GPU, in the same order.
+    buffers: Mutex<Vec<SeriesBuffer>>,
+}
+
+/// Where the parts of a chart go within its bounds.
+struct ChartLayout {
+    /// top left corner and size of the plot.
+    origin: [f32; 2],
+    size: [f32; 2],
+    x_range: [f32; 2],
+    y_range: [f32; 2],
+    /// ticks with their labels and label sizes.
+    x_ticks: Vec<(f32, Text, [f32; 2])>,
+    y_ticks: Vec<(f32, Text, [f32; 2])>,
+}
+
+impl ChartLayout {
+    /// `pixel = point * scale + offset` within the plot.
+    fn scale_offset(&self) -> ([f32; 2], [f32; 2]) {
+        let scale = [
+            self.size[0] / (self.x_range[1] - self.x_range[0]),
+            -self.size[1] / (self.y_range[1] - self.y_range[0]),
+        ];
+        let offset = [-self.x_range[0] * scale[0], -self.y_range[1] * scale[1]];
+        (scale, offset)
+    }
+}
+
+impl ChartNode {
+    fn ranges(&self) -> ([f32; 2], [f32; 2]) {
+        let extent = self
+            .series
+            .iter()
+            .filter_map(Series::extent)
+            .reduce(|a, b| {
+                [
+                    [a[0][0].min(b[0][0]), a[0][1].min(b[0][1])],
+                    [a[1][0].max(b[1][0]), a[1][1].max(b[1][1])],
+                ]
+            })
+            .unwrap_or([[0.0, 0.0], [1.0, 1.0]]);
+
+        let x_range = self.axes.x_range.unwrap_or([extent[0][0], extent[1][0]]);
+        let y_range = self.axes.y_range.unwrap_or_else(|| {
+            rounded_range(non_empty([extent[0][1], extent[1][1]]), self.axes.y_ticks)
+        });
+        (non_empty(x_range), non_empty(y_range))
+    }
+
+    fn layout(&self, bounds: [f32; 2], ctx: &WidgetContext) -> ChartLayout {
+        let (x_range, y_range) = self.ranges();
+        let separator = ctx
+            .locale()
+            .map_or('.', |locale| decimal_separator(&locale));
+        let labeled = |range: [f32; 2], count: usize| {
+            let (values, decimals) = ticks(range, count);
+            values
+                .into_iter()
+                .map(|value| {
+                    let (text, size) = measured_text(
+                        Sentence::new(tick_label(value, decimals, separator))
+                            .color(self.axes.text_color),
+                        self.axes.font_size,
+                        self.axes.font_size * 1.25,
+                        MAX_LABEL_EXTENT,
+                        ctx,
+                    );
+                    (value, text, size)
+                })
+                .collect::<Vec<_>>()
+        };
+        let x_ticks = labeled(x_range, self.axes.x_ticks);
+        let y_ticks = labeled(y_range, self.axes.y_ticks);
+
+        let label_width = y_ticks
+            .iter()
+            .map(|(_, _, size)| size[0])
+            .fold(0.0, f32::max);
+        let label_height = x_ticks
+            .iter()
+            .chain(&y_ticks)
+            .map(|(_, _, size)| size[1])
+            .fold(0.0, f32::max);
+        let last_label_width = x_ticks.last().map_or(0.0, |(_, _, size)| size[0]);
+
+        // room for the labels, which are centered on their ticks
+        let origin = [
+            (GAP + label_width + TICK_LENGTH + GAP).round(),
+            (GAP + label_height / 2.0).round(),
+        ];
+        let size = [
+            bounds[0] - origin[0] - GAP.max(last_label_width / 2.0),
+            bounds[1] - origin[1] - TICK_LENGTH - GAP - label_height - GAP,
+        ];
+
+        ChartLayout {
+            origin,
+            size: [size[0].max(0.0).floor(), size[1].max(0.0).floor()],
+            x_range,
+            y_range,
+            x_ticks,
+            y_ticks,
+        }
+    }
+
+    /// Draws the series into a texture of the plot's size, which clips them to the plot.
+    fn render_series(&self, layout: &ChartLayout, ctx: &WidgetContext) -> Option<RenderNode> {
+        let size = layout.size;
+        let texture_size = [size[0] as u32, size[1] as u32];
+        if texture_size[0] == 0 || texture_size[1] == 0 || self.series.is_empty() {
+            return None;
+        }
+        let region = ctx
+            .texture_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), texture_size)
+            .ok()?;
+
+        let series_renderer = ctx.renderer::<SeriesRenderer>();
+        let mut buffers = self.buffers.lock();
+        buffers.resize_with(self.series.len(), SeriesBuffer::new);
+        for (series, buffer) in self.series.iter().zip(buffers.iter_mut()) {
+            series_renderer.store(buffer, &series.points);
+        }
+        series_renderer.flash(&ctx.device(), &ctx.queue());
+
+        let mut encoder = ctx
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Chart Render Encoder"),
+            });
+        {
+            let mut render_pass = region.begin_render_pass(&mut encoder).ok()?;
+            let (scale, offset) = layout.scale_offset();
+            for (series, buffer) in self.series.iter().zip(buffers.iter()) {
+                series_renderer.render(
+                    &mut render_pass,
+                    TargetData {
+                        target_size: region.texture_size(),
+                        target_format: region.format(),
+                    },
+                    RenderData {
+                        series: buffer,
+                        kind: series.kind,
+                        color: ctx.display_color(series.color).to_rgba_f32(),
+                        scale,
+                        offset,
+                        width: series.width,
+                        baseline: series.baseline,
+                    },
+                    &ctx.device(),
+                );
+            }
+        }
+        ctx.queue().submit(Some(encoder.finish()));
+
+        Some(RenderNode::new().with_texture(
+            region,
+            size,
+            translation(layout.origin[0], layout.origin[1]),
+        ))
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<Chart, T, ()> for ChartNode {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a Chart,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        if self.series != dom.series || self.axes != dom.axes {
+            self.series = dom.series.clone();
+            self.axes = dom.axes.clone();
+            if let Some(handle) = cache_invalidator {
+                handle.redraw_next_frame();
+            }
+        }
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let size = |max: f32, min: f32, default: f32| {
+            if max.is_finite() {
+                max
+            } else {
+                default.max(min)
+            }
+        };
+        [
+            size(
+                constraints.max_width(),
+                constraints.min_width(),
+                DEFAULT_SIZE[0],
+            ),
+            size(
+                constraints.max_height(),
+                constraints.min_height(),
+                DEFAULT_SIZE[1],
+            ),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        _bounds: [f32; 2],
+        _event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        _ctx: &WidgetContext,
+    ) -> Option<T> {
+        None
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        if bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return render_node;
+        }
+
+        let layout = self.layout(bounds, ctx);
+        let (scale, offset) = layout.scale_offset();
+        let [left, top] = layout.origin;
+        let [width, height] = layout.size;
+        let bottom = top + height;
+        let grid = SolidBox {
+            color: self.axes.grid_color,
+        };
+        let axis = SolidBox {
+            color: self.axes.axis_color,
+        };
+
+        // grid lines across the plot
+        let x_pixels = layout
+            .x_ticks
+            .iter()
+            .map(|(x, _, _)| left + (x * scale[0] + offset[0]).round())
+            .collect::<Vec<_>>();
+        let y_pixels = layout
+            .y_ticks
+            .iter()
+            .map(|(y, _, _)| top + (y * scale[1] + offset[1]).round())
+            .collect::<Vec<_>>();
+        if let Some(node) = style_node(&grid, [1.0, height], ctx) {
+            for &x in &x_pixels {
+                render_node.push_child(node.clone(), translation(x, top));
+            }
+        }
+        if let Some(node) = style_node(&grid, [width, 1.0], ctx) {
+            for &y in &y_pixels {
+                render_node.push_child(node.clone(), translation(left, y));
+            }
+        }
+
+        if let Some(node) = self.render_series(&layout, ctx) {
+            render_node.push_child(node, Matrix4::identity());
+        }
+
+        // axes along the left and bottom edges, with ticks outside the plot
+        if let Some(node) = style_node(&axis, [1.0, height + TICK_LENGTH], ctx) {
+            render_node.push_child(node, translation(left, top));
+        }
+        if let Some(node) = style_node(&axis, [width + TICK_LENGTH, 1.0], ctx) {
+            render_node.push_child(node, translation(left - TICK_LENGTH, bottom - 1.0));
+        }
+        if let Some(node) = style_node(&axis, [1.0, TICK_LENGTH], ctx) {
+            for &x in &x_pixels {
+                render_node.push_child(node.clone(), translation(x, bottom));
+            }
+        }
+        if let Some(node) = style_node(&axis, [TICK_LENGTH, 1.0], ctx) {
+            for &y in &y_pixels {
+                render_node.push_child(node.clone(), translation(left - TICK_LENGTH, y));
+            }
+        }
+
+        // tick labels, centered on their ticks
+        for ((_, label, size), x) in layout.x_ticks.iter().zip(&x_pixels) {
+            if let Some(node) = style_node(label, *size, ctx) {
+                render_node.push_child(
+                    node,
+                    translation((x - size[0] / 2.0).round(), bottom + TICK_LENGTH + GAP),
+                );
+            }
+        }
+        for ((_, label, size), y) in layout.y_ticks.iter().zip(&y_pixels) {
+            if let Some(node) = style_node(label, *size, ctx) {
+                render_node.push_child(
+                    node,
+                    translation(
+                        (left - TICK_LENGTH - GAP - size[0]).round(),
+                        (y - size[1] / 2.0).round(),
+                    ),
+                );
+            }
+        }
+
+        render_node
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_fall_on_round_numbers() {
+        assert_eq!(
+            ticks([0.0, 10.0], 5),
+            (vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0], 0)
+        );
+        assert_eq!(ticks([-0.3, 0.35], 4), (vec![-0.2, 0.0, 0.2], 1));
+        assert_eq!(ticks([1.0, 1.0], 5), (vec![], 0));
+        assert_eq!(nice_step(30.0), 50.0);
+        assert_eq!(nice_step(700.0), 1000.0);
+        assert_eq!(rounded_range([0.3, 9.2], 5), [0.0, 10.0]);
+    }
+
+    #[test]
+    fn extents_cover_baselines_and_bars() {
+        let bars = Series::bars(vec![[1.0, 3.0], [2.0, 5.0]], Color::rgb(0, 0, 0)).baseline(1.0);
+        assert_eq!(bars.extent(), Some([[0.6, 1.0], [2.4, 5.0]]));
+
+        let line = Series::line(
+            vec![[0.0, 2.0], [f32::NAN, 9.0], [4.0, 3.0]],
+            Color::rgb(0, 0, 0),
+        );
+        assert_eq!(line.extent(), Some([[0.0, 2.0], [4.0, 3.0]]));
+        assert_eq!(Series::area(vec![], Color::rgb(0, 0, 0)).extent(), None);
+    }
+}
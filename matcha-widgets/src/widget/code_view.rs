@@ -0,0 +1,541 @@
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+
+use crate::style::solid_box::SolidBox;
+use crate::style::text::{Sentence, TextDesc, TextFamily, TextStyle, TextWeight};
+use crate::style::{Style, style_node, translation};
+
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::{
+    device_input::DeviceInput,
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use parking_lot::Mutex;
+use renderer::render_node::{LayerCache, RenderNode};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Space between the line numbers and the edges of the gutter.
+const GUTTER_PADDING: f32 = 8.0;
+/// Space between the gutter and the code.
+const CODE_PADDING: f32 = 8.0;
+
+static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+static DEFAULT_THEME: OnceLock<Arc<CodeTheme>> = OnceLock::new();
+
+// MARK: Theme
+
+/// Colors of a [`CodeView`]: a syntect theme for the code and toolkit colors for the rest.
+#[derive(Clone, Debug)]
+pub struct CodeTheme {
+    pub background: Color,
+    /// Color of text the theme has no rule for.
+    pub foreground: Color,
+    pub gutter: Color,
+    pub line_number: Color,
+    highlighting: Theme,
+}
+
+impl CodeTheme {
+    /// Takes the editor colors from `theme`'s settings where it has them.
+    pub fn new(theme: Theme) -> Self {
+        let settings = &theme.settings;
+        let background = settings
+            .background
+            .map_or(Color::rgb(255, 255, 255), to_color);
+        let foreground = settings.foreground.map_or(Color::rgb(0, 0, 0), to_color);
+        Self {
+            background,
+            foreground,
+            gutter: settings.gutter.map_or(background, to_color),
+            line_number: settings.gutter_foreground.map_or(foreground, to_color),
+            highlighting: theme,
+        }
+    }
+
+    /// One of the themes bundled with syntect, such as `"InspiredGitHub"` or
+    /// `"base16-ocean.dark"`.
+    pub fn bundled(name: &str) -> Option<Self> {
+        ThemeSet::load_defaults().themes.remove(name).map(Self::new)
+    }
+
+    /// The style of a highlighted piece of code.
+    pub fn sentence(&self, text: &str, style: syntect::highlighting::Style) -> Sentence {
+        let mut sentence = Sentence::new(text)
+            .family(TextFamily::Monospace)
+            .color(to_color(style.foreground));
+        if style.font_style.contains(FontStyle::BOLD) {
+            sentence = sentence.weight(TextWeight::BOLD);
+        }
+        if style.font_style.contains(FontStyle::ITALIC) {
+            sentence = sentence.style(TextStyle::Italic);
+        }
+        sentence
+    }
+}
+
+impl Default for CodeTheme {
+    /// The bundled `"InspiredGitHub"` theme.
+    fn default() -> Self {
+        Self::bundled("InspiredGitHub").unwrap_or_else(|| Self::new(Theme::default()))
+    }
+}
+
+fn to_color(color: syntect::highlighting::Color) -> Color {
+    Color::rgba(color.r, color.g, color.b, color.a as f32 / 255.0)
+}
+
+/// Highlights `source` line by line, as the syntax found for `language` (a name or file
+/// extension) sees it. Unknown languages stay plain text.
+fn highlight(source: &str, language: &str, theme: &CodeTheme) -> Vec<Vec<Sentence>> {
+    let syntaxes = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+    let syntax = syntaxes
+        .find_syntax_by_token(language)
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let mut highlighter = HighlightLines::new(syntax, &theme.highlighting);
+
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(source) {
+        let spans = match highlighter.highlight_line(line, syntaxes) {
+            Ok(spans) => spans
+                .into_iter()
+                .map(|(style, text)| theme.sentence(trim_line_ending(text), style))
+                .filter(|sentence| !sentence.text.is_empty())
+                .collect(),
+            // the grammar failed on this line, show it as it is
+            Err(_) => vec![
+                Sentence::new(trim_line_ending(line))
+                    .family(TextFamily::Monospace)
+                    .color(theme.foreground),
+            ],
+        };
+        lines.push(spans);
+    }
+    // a trailing line feed starts one more, empty line
+    if source.is_empty() || source.ends_with('\n') {
+        lines.push(Vec::new());
+    }
+    lines
+}
+
+fn trim_line_ending(text: &str) -> &str {
+    text.trim_end_matches(['\n', '\r'])
+}
+
+/// Lines that intersect a viewport scrolled down by `offset`.
+fn visible_lines(
+    line_count: usize,
+    line_height: f32,
+    offset: f32,
+    viewport_height: f32,
+) -> Range<usize> {
+    if line_height <= 0.0 {
+        return 0..0;
+    }
+    let first = (offset / line_height).floor() as usize;
+    let last = ((offset + viewport_height) / line_height).ceil() as usize;
+    first.min(line_count)..last.min(line_count)
+}
+
+// MARK: DOM
+
+/// Read-only, syntax highlighted source code with line numbers.
+///
+/// The code is highlighted with syntect when it, the language or the theme changes, and only
+/// the lines in view are shaped and drawn, so long files stay cheap to scroll. Lines are not
+/// wrapped; the view scrolls in both directions with the mouse wheel and should be given a
+/// bounded size.
+pub struct CodeView {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    source: Arc<str>,
+    language: String,
+    theme: Arc<CodeTheme>,
+    font_size: f32,
+    line_height: f32,
+    line_numbers: bool,
+}
+
+impl CodeView {
+    /// `language` is a syntax name or file extension, e.g. `"rs"` or `"Python"`.
+    pub fn new(source: impl Into<Arc<str>>, language: &str) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            source: source.into(),
+            language: language.to_string(),
+            theme: DEFAULT_THEME
+                .get_or_init(|| Arc::new(CodeTheme::default()))
+                .clone(),
+            font_size: 13.0,
+            line_height: 18.0,
+            line_numbers: true,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Keep the same `Arc` across views: the code is highlighted again when it changes.
+    pub fn theme(mut self, theme: Arc<CodeTheme>) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    pub fn line_height(mut self, height: f32) -> Self {
+        self.line_height = height;
+        self
+    }
+
+    /// Whether the gutter with line numbers is shown. Enabled by default.
+    pub fn line_numbers(mut self, enabled: bool) -> Self {
+        self.line_numbers = enabled;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for CodeView {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                CodeViewNode {
+                    source: self.source.clone(),
+                    language: self.language.clone(),
+                    theme: self.theme.clone(),
+                    font_size: self.font_size,
+                    line_height: self.line_height,
+                    line_numbers: self.line_numbers,
+                    lines: highlight(&self.source, &self.language, &self.theme),
+                    scroll: [0.0, 0.0],
+                    visible: Mutex::new(Vec::new()),
+                    gutter_width: Mutex::new(None),
+                    layer: LayerCache::new(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct CodeViewNode {
+    source: Arc<str>,
+    language: String,
+    theme: Arc<CodeTheme>,
+    font_size: f32,
+    line_height: f32,
+    line_numbers: bool,
+
+    /// highlighted lines of `source`.
+    lines: Vec<Vec<Sentence>>,
+    scroll: [f32; 2],
+    /// shaped lines in view, sorted by index.
+    visible: Mutex<Vec<VisibleLine>>,
+    /// gutter width for a number of digits.
+    gutter_width: Mutex<Option<(usize, f32)>>,
+    layer: LayerCache,
+}
+
+struct VisibleLine {
+    index: usize,
+    code: crate::style::text::Text,
+    number: crate::style::text::Text,
+}
+
+impl CodeViewNode {
+    fn text(&self, sentences: Vec<Sentence>) -> crate::style::text::Text {
+        crate::style::text::Text::new(
+            &TextDesc::new(sentences)
+                .font_size(self.font_size)
+                .line_height(self.line_height)
+                .tab_width(4),
+        )
+    }
+
+    fn line_number(&self, index: usize) -> crate::style::text::Text {
+        self.text(vec![
+            Sentence::new((index + 1).to_string())
+                .family(TextFamily::Monospace)
+                .color(self.theme.line_number),
+        ])
+    }
+
+    fn gutter_width(&self, ctx: &WidgetContext) -> f32 {
+        if !self.line_numbers {
+            return 0.0;
+        }
+        let digits = self.lines.len().max(1).to_string().len();
+        let mut cached = self.gutter_width.lock();
+        if let Some((cached_digits, width)) = *cached
+            && cached_digits == digits
+        {
+            return width;
+        }
+        let widest = self.text(vec![
+            Sentence::new("0".repeat(digits)).family(TextFamily::Monospace),
+        ]);
+        let width = text_size(&widest, ctx)[0] + 2.0 * GUTTER_PADDING;
+        *cached = Some((digits, width));
+        width
+    }
+
+    fn max_scroll(&self, bounds: [f32; 2], ctx: &WidgetContext) -> [f32; 2] {
+        // only the lines in view are measured, so the width follows what is shown
+        let code_width = self
+            .visible
+            .lock()
+            .iter()
+            .map(|line| text_size(&line.code, ctx)[0])
+            .fold(0.0, f32::max);
+        let viewport_width = bounds[0] - self.gutter_width(ctx) - CODE_PADDING;
+        [
+            (code_width - viewport_width).max(0.0),
+            (self.lines.len() as f32 * self.line_height - bounds[1]).max(0.0),
+        ]
+    }
+
+    /// Shapes the lines that came into view and drops those that left it.
+    fn sync_visible(&self, range: Range<usize>) {
+        let mut visible = self.visible.lock();
+        let mut previous = std::mem::take(&mut *visible).into_iter().peekable();
+        for index in range {
+            while previous.next_if(|line| line.index < index).is_some() {}
+            let line = match previous.next_if(|line| line.index == index) {
+                Some(line) => line,
+                None => VisibleLine {
+                    index,
+                    code: self.text(self.lines[index].clone()),
+                    number: self.line_number(index),
+                },
+            };
+            visible.push(line);
+        }
+    }
+}
+
+fn text_size(text: &crate::style::text::Text, ctx: &WidgetContext) -> [f32; 2] {
+    text.required_region(&Constraints::unbounded(), ctx)
+        .map_or([0.0, 0.0], |rect| [rect.width(), rect.height()])
+}
+
+impl<T: Send + Sync + 'static> Widget<CodeView, T, ()> for CodeViewNode {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a CodeView,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        let text_changed = self.source != dom.source
+            || self.language != dom.language
+            || !Arc::ptr_eq(&self.theme, &dom.theme);
+        let metrics_changed = self.font_size != dom.font_size
+            || self.line_height != dom.line_height
+            || self.line_numbers != dom.line_numbers;
+
+        if text_changed {
+            self.lines = highlight(&dom.source, &dom.language, &dom.theme);
+            self.source = dom.source.clone();
+            self.language = dom.language.clone();
+            self.theme = dom.theme.clone();
+        }
+        self.font_size = dom.font_size;
+        self.line_height = dom.line_height;
+        self.line_numbers = dom.line_numbers;
+
+        if text_changed || metrics_changed {
+            self.visible.get_mut().clear();
+            *self.gutter_width.get_mut() = None;
+            if let Some(handle) = cache_invalidator {
+                handle.relayout_next_frame();
+            }
+        }
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        [
+            constraints.max_width(),
+            (self.lines.len() as f32 * self.line_height)
+                .clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let inside = event.mouse_position().is_some_and(|position| {
+            0.0 <= position[0]
+                && position[0] <= bounds[0]
+                && 0.0 <= position[1]
+                && position[1] <= bounds[1]
+        });
+
+        if inside && let Some(delta) = event.on_scroll(|delta| delta) {
+            let max = self.max_scroll(bounds, ctx);
+            let scroll = [
+                (self.scroll[0] - delta[0]).clamp(0.0, max[0]),
+                (self.scroll[1] - delta[1]).clamp(0.0, max[1]),
+            ];
+            if scroll != self.scroll {
+                self.scroll = scroll;
+                cache_invalidator.redraw_next_frame();
+            }
+        }
+
+        None
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        if bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return render_node;
+        }
+
+        // the text may have shrunk since scrolling
+        let max_scroll = self.max_scroll(bounds, ctx);
+        let scroll = [
+            self.scroll[0].min(max_scroll[0]),
+            self.scroll[1].min(max_scroll[1]),
+        ];
+        self.sync_visible(visible_lines(
+            self.lines.len(),
+            self.line_height,
+            scroll[1],
+            bounds[1],
+        ));
+        let gutter_width = self.gutter_width(ctx);
+
+        let background = SolidBox {
+            color: self.theme.background,
+        };
+        if let Some(node) = style_node(&background, bounds, ctx) {
+            render_node.push_child(node, Matrix4::identity());
+        }
+
+        let visible = self.visible.lock();
+        for line in visible.iter() {
+            let y = line.index as f32 * self.line_height - scroll[1];
+            if let Some(node) = style_node(&line.code, text_size(&line.code, ctx), ctx) {
+                render_node.push_child(
+                    node,
+                    translation(gutter_width + CODE_PADDING - scroll[0], y),
+                );
+            }
+        }
+
+        // the gutter covers code scrolled to the left
+        if self.line_numbers {
+            let gutter = SolidBox {
+                color: self.theme.gutter,
+            };
+            if let Some(node) = style_node(&gutter, [gutter_width, bounds[1]], ctx) {
+                render_node.push_child(node, Matrix4::identity());
+            }
+            for line in visible.iter() {
+                let y = line.index as f32 * self.line_height - scroll[1];
+                let size = text_size(&line.number, ctx);
+                if let Some(node) = style_node(&line.number, size, ctx) {
+                    render_node.push_child(
+                        node,
+                        translation(gutter_width - GUTTER_PADDING - size[0], y),
+                    );
+                }
+            }
+        }
+
+        // the layer clips lines that stick out of the view
+        render_node.with_layer_cache(&self.layer, bounds)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_each_line() {
+        let theme = CodeTheme::default();
+        let lines = highlight("fn main() {\n    let x = 1;\n}\n", "rs", &theme);
+        assert_eq!(lines.len(), 4);
+        assert!(lines[3].is_empty());
+
+        let text = |line: &Vec<Sentence>| {
+            line.iter()
+                .map(|sentence| sentence.text.as_str())
+                .collect::<String>()
+        };
+        assert_eq!(text(&lines[0]), "fn main() {");
+        assert_eq!(text(&lines[1]), "    let x = 1;");
+        // the keyword is styled apart from the name
+        assert_ne!(lines[0][0].color, lines[0][1].color);
+
+        let plain = highlight("a\r\nb", "no such language", &theme);
+        assert_eq!(plain.iter().map(text).collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn only_lines_in_view_are_visible() {
+        assert_eq!(visible_lines(100, 20.0, 0.0, 50.0), 0..3);
+        assert_eq!(visible_lines(100, 20.0, 30.0, 50.0), 1..4);
+        assert_eq!(visible_lines(100, 20.0, 1990.0, 50.0), 99..100);
+        assert_eq!(visible_lines(0, 20.0, 0.0, 50.0), 0..0);
+    }
+}
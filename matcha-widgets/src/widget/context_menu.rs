@@ -0,0 +1,882 @@
+use crate::style::Style;
+use matcha_core::metrics::{Arrangement, Constraints};
+use matcha_core::{
+    color::Color,
+    context::WidgetContext,
+    device_input::{
+        DeviceInput, DeviceInputData, ElementState, Key, MouseInput, MouseLogicalButton,
+    },
+    menu::{Menu, MenuItem},
+    ui::{
+        Align, AnyWidgetFrame, Background, Dom, Elevation, LayoutStyle, PopupPosition, Side,
+        Widget, WidgetFrame, popup,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use renderer::render_node::RenderNode;
+use winit::keyboard::NamedKey;
+
+use crate::style::{
+    solid_box::SolidBox,
+    text::{MAX_LABEL_EXTENT, Sentence, Text, measured_text},
+};
+
+const FONT_SIZE: f32 = 14.0;
+const LINE_HEIGHT: f32 = 20.0;
+const ROW_HEIGHT: f32 = 26.0;
+const SEPARATOR_HEIGHT: f32 = 9.0;
+const PADDING: f32 = 4.0;
+const ROW_PADDING_X: f32 = 12.0;
+const HINT_GAP: f32 = 32.0;
+const MIN_WIDTH: f32 = 140.0;
+const ELEVATION: Elevation = Elevation::new(3);
+
+const PANEL_COLOR: Color = Color::RgbaF32 {
+    r: 0.98,
+    g: 0.98,
+    b: 0.98,
+    a: 1.0,
+};
+const BORDER_COLOR: Color = Color::RgbaF32 {
+    r: 0.75,
+    g: 0.75,
+    b: 0.75,
+    a: 1.0,
+};
+const HIGHLIGHT_COLOR: Color = Color::RgbaF32 {
+    r: 0.80,
+    g: 0.87,
+    b: 0.98,
+    a: 1.0,
+};
+const SEPARATOR_COLOR: Color = Color::RgbaF32 {
+    r: 0.85,
+    g: 0.85,
+    b: 0.85,
+    a: 1.0,
+};
+const TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 1.0,
+};
+const DISABLED_TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 0.6,
+    g: 0.6,
+    b: 0.6,
+    a: 1.0,
+};
+
+// MARK: DOM
+
+/// Shows `menu` as a popup when `content` is right-clicked.
+///
+/// The menu opens at the pointer and is drawn above the content. Items are chosen with the
+/// mouse or with the arrow keys, `Enter` and `Escape`; submenus open to the side. Menus are
+/// kept inside the window, opening to the other side where there is no room, and follow the
+/// content when it is scrolled. While the menu is open it takes all input, and a click outside
+/// closes it.
+///
+/// The popup is part of this widget's render output, so widgets drawn after it (later
+/// siblings) can cover it.
+pub struct ContextMenu<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    content: Box<dyn Dom<T>>,
+    menu: Menu<T>,
+}
+
+impl<T: 'static> ContextMenu<T> {
+    pub fn new(content: impl Dom<T>, menu: Menu<T>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            content: Box::new(content),
+            menu,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for ContextMenu<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0],
+                ContextMenuNode {
+                    menu: self.menu.clone(),
+                    panels: Vec::new(),
+                    window: None,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct ContextMenuNode<T> {
+    menu: Menu<T>,
+    /// open menus; the first is the root menu, the others are cascaded submenus.
+    panels: Vec<Panel>,
+    /// the window in widget coordinates as of the latest input, to keep the panels inside.
+    window: Option<[[f32; 2]; 2]>,
+}
+
+struct Panel {
+    /// indices of the submenu items leading from the root menu to this one.
+    path: Vec<usize>,
+    /// what the panel is placed next to: the pointer for the root menu, and the submenu item
+    /// of the parent panel for others.
+    anchor: [[f32; 2]; 2],
+    /// top left corner in widget coordinates.
+    origin: [f32; 2],
+    size: [f32; 2],
+    rows: Vec<Row>,
+    highlighted: Option<usize>,
+}
+
+struct Row {
+    top: f32,
+    height: f32,
+    selectable: bool,
+    is_submenu: bool,
+    /// label and the text at the right edge (shortcut or submenu arrow), with their sizes.
+    label: Option<(Text, [f32; 2])>,
+    hint: Option<(Text, [f32; 2])>,
+}
+
+impl Panel {
+    fn contains(&self, position: [f32; 2]) -> bool {
+        self.origin[0] <= position[0]
+            && position[0] <= self.origin[0] + self.size[0]
+            && self.origin[1] <= position[1]
+            && position[1] <= self.origin[1] + self.size[1]
+    }
+
+    fn row_at(&self, position: [f32; 2]) -> Option<usize> {
+        if !self.contains(position) {
+            return None;
+        }
+        let y = position[1] - self.origin[1];
+        self.rows
+            .iter()
+            .position(|row| row.top <= y && y < row.top + row.height)
+    }
+
+    /// Next selectable row after (or before, with `step` -1) the highlighted one, wrapping around.
+    fn step_highlight(&self, step: isize) -> Option<usize> {
+        let len = self.rows.len() as isize;
+        let start = match self.highlighted {
+            Some(index) => index as isize,
+            None if step > 0 => -1,
+            None => len,
+        };
+        (1..=len)
+            .map(|offset| (start + step * offset).rem_euclid(len) as usize)
+            .find(|&index| self.rows[index].selectable)
+    }
+}
+
+fn menu_at<'a, T>(root: &'a Menu<T>, path: &[usize]) -> Option<&'a Menu<T>> {
+    path.iter()
+        .try_fold(root, |menu, &index| match menu.items().get(index) {
+            Some(MenuItem::Submenu(submenu)) => Some(submenu),
+            _ => None,
+        })
+}
+
+// the root menu opens below and to the right of the pointer
+const ROOT_POSITION: PopupPosition = PopupPosition::new(Side::Bottom, Align::Start);
+// submenus line up their first item with the submenu item
+const SUBMENU_POSITION: PopupPosition =
+    PopupPosition::new(Side::Right, Align::Start).shift(-PADDING);
+
+impl<T> ContextMenuNode<T> {
+    fn layout_panel(&self, path: Vec<usize>, ctx: &WidgetContext) -> Panel {
+        let items = menu_at(&self.menu, &path).map_or(&[][..], |menu| menu.items());
+
+        let mut rows = Vec::with_capacity(items.len());
+        let mut top = PADDING;
+        let mut width = MIN_WIDTH;
+        for item in items {
+            let color = if item.is_selectable() {
+                TEXT_COLOR
+            } else {
+                DISABLED_TEXT_COLOR
+            };
+            let measured = |text: &str| {
+                measured_text(
+                    Sentence::new(text).color(color),
+                    FONT_SIZE,
+                    LINE_HEIGHT,
+                    MAX_LABEL_EXTENT,
+                    ctx,
+                )
+            };
+            let (label, hint) = match item {
+                MenuItem::Action(action) => (
+                    Some(measured(action.label())),
+                    action
+                        .accelerator()
+                        .map(|shortcut| measured(&shortcut.to_string())),
+                ),
+                MenuItem::Submenu(menu) => (Some(measured(menu.title())), Some(measured("›"))),
+                MenuItem::Separator => (None, None),
+            };
+
+            let content_width = label.as_ref().map_or(0.0, |(_, size)| size[0])
+                + hint.as_ref().map_or(0.0, |(_, size)| HINT_GAP + size[0]);
+            width = width.max(content_width + 2.0 * ROW_PADDING_X);
+
+            let height = if label.is_some() {
+                ROW_HEIGHT
+            } else {
+                SEPARATOR_HEIGHT
+            };
+            rows.push(Row {
+                top,
+                height,
+                selectable: item.is_selectable(),
+                is_submenu: matches!(item, MenuItem::Submenu(_)),
+                label,
+                hint,
+            });
+            top += height;
+        }
+
+        Panel {
+            path,
+            anchor: [[0.0, 0.0]; 2],
+            origin: [0.0, 0.0],
+            size: [width + 2.0 * PADDING, top + PADDING],
+            rows,
+            highlighted: None,
+        }
+    }
+
+    /// Opens the root menu at `position`, closing any open panels.
+    fn open_root(&mut self, position: [f32; 2], ctx: &WidgetContext) {
+        let mut panel = self.layout_panel(vec![], ctx);
+        panel.anchor = [position, position];
+        self.panels.clear();
+        self.panels.push(panel);
+        self.place_panels();
+    }
+
+    /// Opens the submenu at `row` of the panel `panel_index`, closing deeper panels.
+    fn open_submenu(&mut self, panel_index: usize, row: usize, ctx: &WidgetContext) {
+        self.panels.truncate(panel_index + 1);
+        let mut path = self.panels[panel_index].path.clone();
+        path.push(row);
+        let panel = self.layout_panel(path, ctx);
+        self.panels.push(panel);
+        self.place_panels();
+    }
+
+    /// Positions the open panels next to their anchors and inside the window.
+    fn place_panels(&mut self) {
+        for index in 0..self.panels.len() {
+            let position = if index == 0 {
+                ROOT_POSITION
+            } else {
+                // submenus follow their parent, which may just have moved
+                let parent = &self.panels[index - 1];
+                let Some(row) = self.panels[index]
+                    .path
+                    .last()
+                    .and_then(|&row| parent.rows.get(row))
+                else {
+                    continue;
+                };
+                let top = parent.origin[1] + row.top;
+                let anchor = [
+                    [parent.origin[0] + PADDING, top],
+                    [
+                        parent.origin[0] + parent.size[0] - PADDING,
+                        top + row.height,
+                    ],
+                ];
+                self.panels[index].anchor = anchor;
+                SUBMENU_POSITION
+            };
+            let panel = &mut self.panels[index];
+            panel.origin = position.place(panel.anchor, panel.size, self.window).origin;
+        }
+    }
+
+    /// Activates `row` of the panel `panel_index`: opens a submenu or returns the message.
+    fn activate(&mut self, panel_index: usize, row: usize, ctx: &WidgetContext) -> Option<T> {
+        let panel = &self.panels[panel_index];
+        let item = menu_at(&self.menu, &panel.path)?.items().get(row)?;
+        match item {
+            MenuItem::Action(action) if action.is_enabled() => {
+                let message = action.trigger();
+                self.panels.clear();
+                Some(message)
+            }
+            MenuItem::Submenu(menu) if menu.is_enabled() => {
+                self.open_submenu(panel_index, row, ctx);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Handles input while the menu is open. Returns whether the menu needs to be redrawn.
+    fn open_menu_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        ctx: &WidgetContext,
+    ) -> (bool, Option<T>) {
+        if let Some(key) = event.on_key_down(|key| key.logical_key().clone()) {
+            return self.key_input(&key, ctx);
+        }
+
+        let Some(position) = event.mouse_position() else {
+            return (false, None);
+        };
+
+        match event.event() {
+            DeviceInputData::MouseInput {
+                event:
+                    Some(MouseInput::Click {
+                        click_state: ElementState::Pressed(_),
+                        button,
+                    }),
+                ..
+            } => self.press(bounds, position, *button, ctx),
+            DeviceInputData::MouseInput { .. } => {
+                let Some(panel_index) = self.panel_at(position) else {
+                    return (false, None);
+                };
+                let row = self.panels[panel_index]
+                    .row_at(position)
+                    .filter(|&row| self.panels[panel_index].rows[row].selectable);
+                if row.is_none() || self.panels[panel_index].highlighted == row {
+                    return (false, None);
+                }
+
+                self.panels[panel_index].highlighted = row;
+                self.panels.truncate(panel_index + 1);
+                if let Some(row) = row
+                    && self.panels[panel_index].rows[row].is_submenu
+                {
+                    self.open_submenu(panel_index, row, ctx);
+                }
+                (true, None)
+            }
+            _ => (false, None),
+        }
+    }
+
+    /// The topmost open panel at `position`.
+    fn panel_at(&self, position: [f32; 2]) -> Option<usize> {
+        self.panels
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, panel)| panel.contains(position).then_some(index))
+    }
+
+    /// Handles a mouse button pressed at `position` while the menu is open.
+    fn press(
+        &mut self,
+        bounds: [f32; 2],
+        position: [f32; 2],
+        button: MouseLogicalButton,
+        ctx: &WidgetContext,
+    ) -> (bool, Option<T>) {
+        match self.panel_at(position) {
+            Some(panel_index) if button == MouseLogicalButton::Primary => {
+                match self.panels[panel_index].row_at(position) {
+                    Some(row) => (true, self.activate(panel_index, row, ctx)),
+                    None => (false, None),
+                }
+            }
+            Some(_) => (false, None),
+            None => {
+                // a click elsewhere closes the menu; a right-click on the content reopens it
+                self.panels.clear();
+                if button == MouseLogicalButton::Secondary
+                    && (0.0..=bounds[0]).contains(&position[0])
+                    && (0.0..=bounds[1]).contains(&position[1])
+                {
+                    self.open_root(position, ctx);
+                }
+                (true, None)
+            }
+        }
+    }
+
+    fn key_input(&mut self, key: &Key, ctx: &WidgetContext) -> (bool, Option<T>) {
+        let Some(panel_index) = self.panels.len().checked_sub(1) else {
+            return (false, None);
+        };
+        let Key::Named(named) = key else {
+            return (false, None);
+        };
+
+        match named {
+            NamedKey::Escape => {
+                self.panels.pop();
+                (true, None)
+            }
+            NamedKey::ArrowLeft if panel_index > 0 => {
+                self.panels.pop();
+                (true, None)
+            }
+            NamedKey::ArrowDown | NamedKey::ArrowUp => {
+                let step = if *named == NamedKey::ArrowDown { 1 } else { -1 };
+                let panel = &mut self.panels[panel_index];
+                panel.highlighted = panel.step_highlight(step);
+                (true, None)
+            }
+            NamedKey::ArrowRight | NamedKey::Enter | NamedKey::Space => {
+                let Some(row) = self.panels[panel_index].highlighted else {
+                    return (false, None);
+                };
+                if *named == NamedKey::ArrowRight && !self.panels[panel_index].rows[row].is_submenu
+                {
+                    return (false, None);
+                }
+                let message = self.activate(panel_index, row, ctx);
+                // keyboard users start on the first entry of a submenu
+                if let Some(submenu) = self.panels.get_mut(panel_index + 1) {
+                    submenu.highlighted = submenu.step_highlight(1);
+                }
+                (true, message)
+            }
+            _ => (false, None),
+        }
+    }
+
+    fn render_panel(&self, panel: &Panel, ctx: &WidgetContext) -> Option<RenderNode> {
+        let texture_size = [panel.size[0].ceil() as u32, panel.size[1].ceil() as u32];
+        let region = ctx
+            .texture_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), texture_size)
+            .ok()?;
+
+        let mut encoder = ctx
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ContextMenu Render Encoder"),
+            });
+
+        // border, then the panel inset by one pixel
+        SolidBox {
+            color: BORDER_COLOR,
+        }
+        .draw(&mut encoder, &region, panel.size, [0.0, 0.0], ctx);
+        SolidBox { color: PANEL_COLOR }.draw(
+            &mut encoder,
+            &region,
+            [panel.size[0] - 2.0, panel.size[1] - 2.0],
+            [1.0, 1.0],
+            ctx,
+        );
+
+        let row_width = panel.size[0] - 2.0 * PADDING;
+        for (index, row) in panel.rows.iter().enumerate() {
+            if panel.highlighted == Some(index) {
+                SolidBox {
+                    color: HIGHLIGHT_COLOR,
+                }
+                .draw(
+                    &mut encoder,
+                    &region,
+                    [row_width, row.height],
+                    [PADDING, row.top],
+                    ctx,
+                );
+            }
+
+            let Some((label, label_size)) = &row.label else {
+                SolidBox {
+                    color: SEPARATOR_COLOR,
+                }
+                .draw(
+                    &mut encoder,
+                    &region,
+                    [row_width - 2.0 * ROW_PADDING_X, 1.0],
+                    [
+                        PADDING + ROW_PADDING_X,
+                        row.top + (row.height / 2.0).floor(),
+                    ],
+                    ctx,
+                );
+                continue;
+            };
+
+            let text_top = row.top + ((row.height - label_size[1]) / 2.0).round();
+            label.draw(
+                &mut encoder,
+                &region,
+                *label_size,
+                [PADDING + ROW_PADDING_X, text_top],
+                ctx,
+            );
+            if let Some((hint, hint_size)) = &row.hint {
+                hint.draw(
+                    &mut encoder,
+                    &region,
+                    *hint_size,
+                    [PADDING + row_width - ROW_PADDING_X - hint_size[0], text_top],
+                    ctx,
+                );
+            }
+        }
+
+        ctx.queue().submit(Some(encoder.finish()));
+
+        Some(RenderNode::new().with_texture(
+            region,
+            panel.size,
+            nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+                panel.origin[0],
+                panel.origin[1],
+                0.0,
+            )),
+        ))
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<ContextMenu<T>, T, ()> for ContextMenuNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a ContextMenu<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.menu = dom.menu.clone();
+
+        // the open panels show the old items
+        if !self.panels.is_empty() {
+            self.panels.clear();
+            if let Some(handle) = cache_invalidator {
+                handle.redraw_next_frame();
+            }
+        }
+
+        vec![(&*dom.content, (), 0)]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        if let Some((content, _)) = children.first() {
+            content.measure(constraints, ctx)
+        } else {
+            [0.0, 0.0]
+        }
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        // the window moves relative to the widget when it is scrolled or the window resized
+        let window = popup::window_bounds(event, ctx);
+        if window.is_some() && window != self.window {
+            self.window = window;
+            if !self.panels.is_empty() {
+                self.place_panels();
+                cache_invalidator.redraw_next_frame();
+            }
+        }
+
+        if !self.panels.is_empty() {
+            let (redraw, message) = self.open_menu_input(bounds, event, ctx);
+            if redraw {
+                cache_invalidator.redraw_next_frame();
+            }
+            return message;
+        }
+
+        let opened = event
+            .on_secondary_click(|_| ())
+            .and_then(|()| event.mouse_position())
+            .filter(|position| {
+                (0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1])
+            });
+        if let Some(position) = opened {
+            self.open_root(position, ctx);
+            cache_invalidator.redraw_next_frame();
+            return None;
+        }
+
+        if let Some((content, _, arrangement)) = children.first_mut() {
+            let content_event = event.transform(arrangement.affine);
+            return content.device_input(&content_event, ctx);
+        }
+        None
+    }
+
+    fn is_inside(
+        &self,
+        bounds: [f32; 2],
+        position: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _ctx: &WidgetContext,
+    ) -> bool {
+        ((0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1]))
+            || self.panels.iter().any(|panel| panel.contains(position))
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        if let Some((content, _, arrangement)) = children.first() {
+            render_node.push_child(content.render(background, ctx), arrangement.affine);
+        }
+
+        for panel in &self.panels {
+            if let Some(shadow) = ELEVATION.shadow_node(panel.size, 0.0, ctx) {
+                let [x, y] = panel.origin;
+                render_node.push_child(
+                    shadow,
+                    nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0)),
+                );
+            }
+            if let Some(panel_node) = self.render_panel(panel, ctx) {
+                render_node.push_child(panel_node, nalgebra::Matrix4::identity());
+            }
+        }
+
+        render_node
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use matcha_core::{menu::MenuAction, test_kit::TestContext};
+
+    const BOUNDS: [f32; 2] = [400.0, 300.0];
+
+    fn row(selectable: bool) -> Row {
+        Row {
+            top: 0.0,
+            height: ROW_HEIGHT,
+            selectable,
+            is_submenu: false,
+            label: None,
+            hint: None,
+        }
+    }
+
+    fn panel(rows: Vec<Row>, highlighted: Option<usize>) -> Panel {
+        Panel {
+            path: vec![],
+            anchor: [[0.0, 0.0]; 2],
+            origin: [0.0, 0.0],
+            size: [MIN_WIDTH, 100.0],
+            rows,
+            highlighted,
+        }
+    }
+
+    // Cut, separator, disabled Paste, Share > (Mail, Chat), Delete
+    fn edit_menu() -> Menu<&'static str> {
+        Menu::new("Edit")
+            .item("Cut", || "cut")
+            .separator()
+            .action(MenuAction::new("Paste", || "paste").enabled(false))
+            .submenu(
+                Menu::new("Share")
+                    .item("Mail", || "mail")
+                    .item("Chat", || "chat"),
+            )
+            .item("Delete", || "delete")
+    }
+
+    fn menu_node() -> ContextMenuNode<&'static str> {
+        ContextMenuNode {
+            menu: edit_menu(),
+            panels: Vec::new(),
+            window: None,
+        }
+    }
+
+    fn key(node: &mut ContextMenuNode<&'static str>, named: NamedKey, ctx: &WidgetContext) {
+        node.key_input(&Key::Named(named), ctx);
+    }
+
+    #[test]
+    fn the_highlight_skips_rows_that_cannot_be_selected_and_wraps_around() {
+        // item, separator, disabled item, item
+        let rows = || vec![row(true), row(false), row(false), row(true)];
+
+        assert_eq!(panel(rows(), None).step_highlight(1), Some(0));
+        assert_eq!(panel(rows(), None).step_highlight(-1), Some(3));
+        assert_eq!(panel(rows(), Some(0)).step_highlight(1), Some(3));
+        assert_eq!(panel(rows(), Some(3)).step_highlight(1), Some(0));
+        assert_eq!(panel(rows(), Some(0)).step_highlight(-1), Some(3));
+        assert_eq!(panel(rows(), Some(3)).step_highlight(-1), Some(0));
+
+        assert_eq!(panel(vec![row(false)], None).step_highlight(1), None);
+        assert_eq!(panel(vec![], None).step_highlight(1), None);
+    }
+
+    #[test]
+    fn menus_are_found_along_the_submenu_path() {
+        let menu = edit_menu()
+            .submenu(Menu::new("More").submenu(Menu::new("Deeper").item("Last", || "last")));
+
+        assert_eq!(menu_at(&menu, &[]).unwrap().title(), "Edit");
+        assert_eq!(menu_at(&menu, &[3]).unwrap().title(), "Share");
+        assert_eq!(menu_at(&menu, &[5, 0]).unwrap().title(), "Deeper");
+        // not a submenu, or no item at all
+        assert!(menu_at(&menu, &[0]).is_none());
+        assert!(menu_at(&menu, &[3, 0]).is_none());
+        assert!(menu_at(&menu, &[9]).is_none());
+    }
+
+    #[test]
+    fn keys_open_and_close_submenus() {
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let mut node = menu_node();
+        node.open_root([10.0, 10.0], ctx);
+
+        // past the separator and the disabled item to the submenu
+        key(&mut node, NamedKey::ArrowDown, ctx);
+        key(&mut node, NamedKey::ArrowDown, ctx);
+        assert_eq!(node.panels[0].highlighted, Some(3));
+
+        key(&mut node, NamedKey::ArrowRight, ctx);
+        assert_eq!(node.panels.len(), 2);
+        assert_eq!(node.panels[1].path, [3]);
+        assert_eq!(node.panels[1].highlighted, Some(0));
+
+        key(&mut node, NamedKey::ArrowLeft, ctx);
+        assert_eq!(node.panels.len(), 1);
+
+        key(&mut node, NamedKey::Enter, ctx);
+        assert_eq!(node.panels.len(), 2);
+        key(&mut node, NamedKey::Escape, ctx);
+        assert_eq!(node.panels.len(), 1);
+
+        // the root menu only closes with Escape
+        key(&mut node, NamedKey::ArrowLeft, ctx);
+        assert_eq!(node.panels.len(), 1);
+        key(&mut node, NamedKey::Escape, ctx);
+        assert!(node.panels.is_empty());
+    }
+
+    #[test]
+    fn enter_triggers_the_highlighted_action_and_closes_the_menu() {
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let mut node = menu_node();
+        node.open_root([10.0, 10.0], ctx);
+
+        // ArrowRight only opens submenus
+        key(&mut node, NamedKey::ArrowUp, ctx);
+        assert_eq!(node.panels[0].highlighted, Some(4));
+        assert_eq!(
+            node.key_input(&Key::Named(NamedKey::ArrowRight), ctx),
+            (false, None)
+        );
+
+        assert_eq!(
+            node.key_input(&Key::Named(NamedKey::Enter), ctx),
+            (true, Some("delete"))
+        );
+        assert!(node.panels.is_empty());
+    }
+
+    #[test]
+    fn an_outside_click_closes_the_menu_and_a_right_click_reopens_it() {
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let mut node = menu_node();
+        node.open_root([10.0, 10.0], ctx);
+        let origin = node.panels[0].origin;
+        assert_eq!(origin, [10.0, 10.0]);
+
+        // a click on the panel outside of any row keeps it open
+        let padding = [origin[0] + 1.0, origin[1] + 1.0];
+        assert_eq!(
+            node.press(BOUNDS, padding, MouseLogicalButton::Primary, ctx),
+            (false, None)
+        );
+        assert_eq!(node.panels.len(), 1);
+
+        let outside = [390.0, 5.0];
+        assert_eq!(
+            node.press(BOUNDS, outside, MouseLogicalButton::Primary, ctx),
+            (true, None)
+        );
+        assert!(node.panels.is_empty());
+
+        node.open_root([10.0, 10.0], ctx);
+        node.press(BOUNDS, outside, MouseLogicalButton::Secondary, ctx);
+        assert_eq!(node.panels.len(), 1);
+        assert_eq!(node.panels[0].anchor, [outside, outside]);
+
+        // outside of the content the menu only closes
+        node.press(BOUNDS, [500.0, 5.0], MouseLogicalButton::Secondary, ctx);
+        assert!(node.panels.is_empty());
+    }
+
+    #[test]
+    fn clicking_a_row_triggers_its_action() {
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let mut node = menu_node();
+        node.open_root([10.0, 10.0], ctx);
+
+        let [x, y] = node.panels[0].origin;
+        let cut = [x + ROW_PADDING_X, y + PADDING + ROW_HEIGHT / 2.0];
+        assert_eq!(
+            node.press(BOUNDS, cut, MouseLogicalButton::Primary, ctx),
+            (true, Some("cut"))
+        );
+        assert!(node.panels.is_empty());
+    }
+}
@@ -0,0 +1,1267 @@
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::text_edit::{FieldStyle, LineField};
+use crate::style::solid_box::SolidBox;
+use crate::style::text::{MAX_LABEL_EXTENT, Sentence, measured_text};
+use crate::style::{Style, style_node, translation};
+
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::localization::{Weekday, current_first_day_of_week};
+use matcha_core::{
+    device_input::{DeviceInput, DeviceInputData, Key},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        Align, AnyWidgetFrame, Background, Dom, LayoutStyle, PopupPosition, Side, Widget,
+        WidgetFrame, popup,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use renderer::render_node::RenderNode;
+use winit::keyboard::NamedKey;
+
+/// Size of a day in the calendar, and of the navigation buttons.
+const CELL: f32 = 30.0;
+const PADDING: f32 = 6.0;
+/// Weeks shown in the calendar, enough for every month.
+const WEEKS: usize = 6;
+
+const CALENDAR_POSITION: PopupPosition = PopupPosition::new(Side::Bottom, Align::End).gap(2.0);
+
+const PANEL_COLOR: Color = Color::RgbaF32 {
+    r: 0.98,
+    g: 0.98,
+    b: 0.98,
+    a: 1.0,
+};
+const BORDER_COLOR: Color = Color::RgbaF32 {
+    r: 0.75,
+    g: 0.75,
+    b: 0.75,
+    a: 1.0,
+};
+const HIGHLIGHT_COLOR: Color = Color::RgbaF32 {
+    r: 0.80,
+    g: 0.87,
+    b: 0.98,
+    a: 1.0,
+};
+const SELECTED_COLOR: Color = Color::RgbaF32 {
+    r: 0.20,
+    g: 0.45,
+    b: 0.85,
+    a: 1.0,
+};
+const SELECTED_TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+const MUTED_TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 0.6,
+    g: 0.6,
+    b: 0.6,
+    a: 1.0,
+};
+const DISABLED_TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 0.8,
+    g: 0.8,
+    b: 0.8,
+    a: 1.0,
+};
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+// MARK: Date
+
+/// A day of the proleptic Gregorian calendar, between the years 1 and 9999.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    pub const MIN: Date = Date {
+        year: 1,
+        month: 1,
+        day: 1,
+    };
+    pub const MAX: Date = Date {
+        year: 9999,
+        month: 12,
+        day: 31,
+    };
+
+    /// The date, or `None` if there is no such day.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        let valid = (Self::MIN.year..=Self::MAX.year).contains(&year)
+            && (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day);
+        valid.then_some(Self { year, month, day })
+    }
+
+    /// The current date in UTC.
+    pub fn today() -> Self {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() / 86_400);
+        Self::from_days(days as i64)
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// 1 for January.
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        Weekday::from_days_from_monday((self.days() + 3).rem_euclid(7) as u32)
+    }
+
+    /// The date `days` later, or earlier for negative values, saturating at the limits.
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.days().saturating_add(days))
+    }
+
+    /// The date `months` later, or earlier for negative values, saturating at the limits.
+    /// The day is clamped to the length of the month, so Jan 31 plus one month is Feb 28
+    /// or 29.
+    pub fn add_months(self, months: i32) -> Self {
+        let index = i64::from(self.year) * 12 + i64::from(self.month) - 1 + i64::from(months);
+        let year = index.div_euclid(12);
+        if year < i64::from(Self::MIN.year) {
+            return Self::MIN;
+        }
+        if year > i64::from(Self::MAX.year) {
+            return Self::MAX;
+        }
+        let year = year as i32;
+        let month = index.rem_euclid(12) as u32 + 1;
+        Self {
+            year,
+            month,
+            day: self.day.min(days_in_month(year, month)),
+        }
+    }
+
+    /// The first day of the month.
+    pub fn first_of_month(self) -> Self {
+        Self { day: 1, ..self }
+    }
+
+    /// Days since 1970-01-01.
+    fn days(&self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+        let day_of_year = (153 * shifted_month + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    fn from_days(days: i64) -> Self {
+        let days = days.clamp(Self::MIN.days(), Self::MAX.days()) + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+}
+
+impl Display for Date {
+    /// ISO 8601, e.g. `2024-02-29`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Days in `month` (1 to 12) of `year`.
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The order of the parts of a written date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DateOrder {
+    /// `2024-02-29`
+    YearMonthDay,
+    /// `29.02.2024`
+    DayMonthYear,
+    /// `02/29/2024`
+    MonthDayYear,
+}
+
+/// How a [`DatePicker`] writes and reads dates: the order of the parts and the separator
+/// between them. Years have four digits; months and days are written with two and read with
+/// one or two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DateFormat {
+    pub order: DateOrder,
+    pub separator: char,
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        Self::ISO
+    }
+}
+
+impl DateFormat {
+    /// `YYYY-MM-DD`
+    pub const ISO: DateFormat = DateFormat::new(DateOrder::YearMonthDay, '-');
+
+    pub const fn new(order: DateOrder, separator: char) -> Self {
+        Self { order, separator }
+    }
+
+    pub fn format(&self, date: Date) -> String {
+        let year = format!("{:04}", date.year);
+        let month = format!("{:02}", date.month);
+        let day = format!("{:02}", date.day);
+        let parts = match self.order {
+            DateOrder::YearMonthDay => [year, month, day],
+            DateOrder::DayMonthYear => [day, month, year],
+            DateOrder::MonthDayYear => [month, day, year],
+        };
+        parts.join(&self.separator.to_string())
+    }
+
+    /// The date written in `text`, or `None` if it is not one in this format.
+    pub fn parse(&self, text: &str) -> Option<Date> {
+        let parts: Vec<&str> = text.trim().split(self.separator).collect();
+        let [first, second, third] = parts[..] else {
+            return None;
+        };
+        let (year, month, day) = match self.order {
+            DateOrder::YearMonthDay => (first, second, third),
+            DateOrder::DayMonthYear => (third, second, first),
+            DateOrder::MonthDayYear => (third, first, second),
+        };
+        let number = |part: &str, digits: std::ops::RangeInclusive<usize>| {
+            (digits.contains(&part.len()) && part.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| part.parse::<u32>().ok())
+                .flatten()
+        };
+        Date::new(
+            number(year, 4..=4)? as i32,
+            number(month, 1..=2)?,
+            number(day, 1..=2)?,
+        )
+    }
+}
+
+/// Why the text of a [`DatePicker`] is not an acceptable date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateError {
+    /// The text is not a date in the picker's format.
+    Invalid,
+    /// The date is before the earliest one, which is given.
+    BeforeMin(Date),
+    /// The date is after the latest one, which is given.
+    AfterMax(Date),
+}
+
+impl Display for DateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateError::Invalid => write!(f, "not a valid date"),
+            DateError::BeforeMin(min) => write!(f, "must be on or after {min}"),
+            DateError::AfterMax(max) => write!(f, "must be on or before {max}"),
+        }
+    }
+}
+
+impl std::error::Error for DateError {}
+
+/// The earliest and latest dates that can be picked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Limits {
+    min: Option<Date>,
+    max: Option<Date>,
+}
+
+impl Limits {
+    fn check(&self, date: Date) -> Result<Date, DateError> {
+        match (self.min, self.max) {
+            (Some(min), _) if date < min => Err(DateError::BeforeMin(min)),
+            (_, Some(max)) if date > max => Err(DateError::AfterMax(max)),
+            _ => Ok(date),
+        }
+    }
+
+    fn contains(&self, date: Date) -> bool {
+        self.check(date).is_ok()
+    }
+
+    fn clamp(&self, date: Date) -> Date {
+        match self.check(date) {
+            Err(DateError::BeforeMin(min)) => min,
+            Err(DateError::AfterMax(max)) => max,
+            _ => date,
+        }
+    }
+}
+
+// MARK: DOM
+
+type DateHandler<T> = Arc<dyn Fn(Date) -> T + Send + Sync>;
+type ErrorHandler<T> = Arc<dyn Fn(DateError) -> T + Send + Sync>;
+
+/// A text field for a date, with a button that opens a calendar to pick it from.
+///
+/// Typed text is read in the picker's [format](Self::format). While it is not a date within
+/// the limits the field shows the invalid background and [`on_invalid`](Self::on_invalid) is
+/// emitted; Enter or leaving the field then restores the last valid date.
+///
+/// The calendar opens below the field, or with Alt+Down while the field is focused. It shows
+/// one month at a time, its weeks starting on the first day of the week of the app's locale.
+/// The buttons in its header go to the previous and next month and year. Days are picked with
+/// the mouse or with the arrow keys and Enter; Page Up and Down change the month, with Shift
+/// the year, and Escape or a click outside closes the calendar. Days outside the limits
+/// cannot be picked.
+///
+/// Like [`ContextMenu`](super::context_menu::ContextMenu), the calendar is part of this
+/// widget's render output, so later siblings can cover it.
+pub struct DatePicker<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    value: Date,
+    limits: Limits,
+    format: DateFormat,
+    first_day: Weekday,
+    month_names: [String; 12],
+    weekday_names: [String; 7],
+    style: FieldStyle,
+    invalid_background: Color,
+    button_color: Color,
+    on_change: Option<DateHandler<T>>,
+    on_invalid: Option<ErrorHandler<T>>,
+}
+
+impl<T> DatePicker<T> {
+    pub fn new(value: Date) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            value,
+            limits: Limits::default(),
+            format: DateFormat::default(),
+            first_day: current_first_day_of_week(),
+            month_names: MONTH_NAMES.map(str::to_string),
+            weekday_names: WEEKDAY_NAMES.map(str::to_string),
+            style: FieldStyle::default(),
+            invalid_background: Color::rgb(255, 228, 228),
+            button_color: Color::rgb(230, 230, 230),
+            on_change: None,
+            on_invalid: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn min(mut self, min: Date) -> Self {
+        self.limits.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: Date) -> Self {
+        self.limits.max = Some(max);
+        self
+    }
+
+    /// How the date is written in the field. [ISO 8601](DateFormat::ISO) by default.
+    pub fn format(mut self, format: DateFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Overrides the first day of the week of the app's locale.
+    pub fn first_day_of_week(mut self, weekday: Weekday) -> Self {
+        self.first_day = weekday;
+        self
+    }
+
+    /// Names shown in the calendar header, from January. English by default.
+    pub fn month_names(mut self, names: [&str; 12]) -> Self {
+        self.month_names = names.map(str::to_string);
+        self
+    }
+
+    /// Short names shown above the days of the calendar, from Monday. English by default.
+    pub fn weekday_names(mut self, names: [&str; 7]) -> Self {
+        self.weekday_names = names.map(str::to_string);
+        self
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.style.font_size = size;
+        self
+    }
+
+    pub fn line_height(mut self, height: f32) -> Self {
+        self.style.line_height = height;
+        self
+    }
+
+    /// Color of the text, the caret and the calendar.
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = color;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.style.background = color;
+        self
+    }
+
+    /// Background of the field while its text is not a valid date.
+    pub fn invalid_background(mut self, color: Color) -> Self {
+        self.invalid_background = color;
+        self
+    }
+
+    /// Background of the button that opens the calendar.
+    pub fn button_color(mut self, color: Color) -> Self {
+        self.button_color = color;
+        self
+    }
+
+    /// Called with the new date when it is typed or picked.
+    pub fn on_change(mut self, f: impl Fn(Date) -> T + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(f));
+        self
+    }
+
+    /// Called when the typed text becomes invalid, or invalid for another reason.
+    pub fn on_invalid(mut self, f: impl Fn(DateError) -> T + Send + Sync + 'static) -> Self {
+        self.on_invalid = Some(Arc::new(f));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for DatePicker<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                DatePickerNode {
+                    value: self.value,
+                    limits: self.limits,
+                    format: self.format,
+                    first_day: self.first_day,
+                    month_names: self.month_names.clone(),
+                    weekday_names: self.weekday_names.clone(),
+                    style: self.style.clone(),
+                    invalid_background: self.invalid_background,
+                    button_color: self.button_color,
+                    on_change: self.on_change.clone(),
+                    on_invalid: self.on_invalid.clone(),
+                    field: LineField::new(&self.format.format(self.value), self.style.clone()),
+                    error: None,
+                    calendar: None,
+                    window: None,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Calendar
+
+/// The open calendar popup.
+struct Calendar {
+    /// the first day of the month shown.
+    month: Date,
+    /// the day under the pointer or chosen with the arrow keys.
+    highlighted: Option<Date>,
+    /// top left corner in widget coordinates.
+    origin: [f32; 2],
+}
+
+/// A part of the calendar that reacts to clicks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CalendarHit {
+    PreviousYear,
+    PreviousMonth,
+    NextMonth,
+    NextYear,
+    Day(Date),
+}
+
+impl Calendar {
+    const SIZE: [f32; 2] = [
+        2.0 * PADDING + 7.0 * CELL,
+        // header, weekday names and the weeks
+        2.0 * PADDING + (2 + WEEKS) as f32 * CELL,
+    ];
+
+    /// The first day shown, in the week of the first of the month.
+    fn grid_start(&self, first_day: Weekday) -> Date {
+        let offset =
+            (self.month.weekday().days_from_monday() + 7 - first_day.days_from_monday()) % 7;
+        self.month.add_days(-i64::from(offset))
+    }
+
+    fn hit(&self, position: [f32; 2], first_day: Weekday) -> Option<CalendarHit> {
+        let x = position[0] - self.origin[0] - PADDING;
+        let y = position[1] - self.origin[1] - PADDING;
+        if x < 0.0 || y < 0.0 || x >= 7.0 * CELL {
+            return None;
+        }
+        let column = (x / CELL) as usize;
+        let row = (y / CELL) as usize;
+        match (row, column) {
+            (0, 0) => Some(CalendarHit::PreviousYear),
+            (0, 1) => Some(CalendarHit::PreviousMonth),
+            (0, 5) => Some(CalendarHit::NextMonth),
+            (0, 6) => Some(CalendarHit::NextYear),
+            (row, column) if (2..2 + WEEKS).contains(&row) => {
+                let index = (row - 2) * 7 + column;
+                Some(CalendarHit::Day(
+                    self.grid_start(first_day).add_days(index as i64),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn contains(&self, position: [f32; 2]) -> bool {
+        (self.origin[0]..=self.origin[0] + Self::SIZE[0]).contains(&position[0])
+            && (self.origin[1]..=self.origin[1] + Self::SIZE[1]).contains(&position[1])
+    }
+
+    /// Highlights `date`, showing its month.
+    fn highlight(&mut self, date: Date) {
+        self.highlighted = Some(date);
+        self.month = date.first_of_month();
+    }
+}
+
+// MARK: Widget
+
+pub struct DatePickerNode<T> {
+    /// date of the last dom or the last one emitted.
+    value: Date,
+    limits: Limits,
+    format: DateFormat,
+    first_day: Weekday,
+    month_names: [String; 12],
+    weekday_names: [String; 7],
+    style: FieldStyle,
+    invalid_background: Color,
+    button_color: Color,
+    on_change: Option<DateHandler<T>>,
+    on_invalid: Option<ErrorHandler<T>>,
+
+    field: LineField,
+    /// why the text is not a valid date.
+    error: Option<DateError>,
+    calendar: Option<Calendar>,
+    /// the window in widget coordinates as of the latest input, to keep the calendar inside.
+    window: Option<[[f32; 2]; 2]>,
+}
+
+impl<T> DatePickerNode<T> {
+    /// Width of the calendar button, which is square.
+    fn button_width(bounds: [f32; 2]) -> f32 {
+        bounds[1].min(bounds[0] / 3.0)
+    }
+
+    fn field_bounds(bounds: [f32; 2]) -> [f32; 2] {
+        [bounds[0] - Self::button_width(bounds), bounds[1]]
+    }
+
+    fn field_style(&self) -> FieldStyle {
+        let mut style = self.style.clone();
+        if self.error.is_some() {
+            style.background = self.invalid_background;
+        }
+        style
+    }
+
+    /// Shows the value in the field and forgets any error. Returns whether anything changed.
+    fn show_value(&mut self) -> bool {
+        let text = self.format.format(self.value);
+        let changed = self.field.set_text(&text) | self.error.take().is_some();
+        self.field.set_style(&self.field_style());
+        changed
+    }
+
+    fn parse(&self, text: &str) -> Result<Date, DateError> {
+        let date = self.format.parse(text).ok_or(DateError::Invalid)?;
+        self.limits.check(date)
+    }
+
+    /// Checks the typed text. Returns the new date, or the error if it is a new one.
+    fn validate(&mut self) -> Result<Option<Date>, Option<DateError>> {
+        let result = self.parse(&self.field.text());
+        let previous = self.error;
+        self.error = result.err();
+        self.field.set_style(&self.field_style());
+        match result {
+            Ok(date) if date != self.value => {
+                self.value = date;
+                Ok(Some(date))
+            }
+            Ok(_) => Ok(None),
+            Err(error) => Err((previous != Some(error)).then_some(error)),
+        }
+    }
+
+    /// Sets the date picked in the calendar and closes it. Returns it if it changed.
+    fn pick(&mut self, date: Date) -> Option<Date> {
+        self.calendar = None;
+        let changed = date != self.value;
+        self.value = date;
+        self.show_value();
+        changed.then_some(date)
+    }
+
+    fn open_calendar(&mut self, bounds: [f32; 2]) {
+        // start on the typed date if there is one
+        let date = self.parse(&self.field.text()).unwrap_or(self.value);
+        let mut calendar = Calendar {
+            month: date.first_of_month(),
+            highlighted: None,
+            origin: [0.0, 0.0],
+        };
+        calendar.highlight(date);
+        self.calendar = Some(calendar);
+        self.place_calendar(bounds);
+    }
+
+    fn place_calendar(&mut self, bounds: [f32; 2]) {
+        if let Some(calendar) = &mut self.calendar {
+            calendar.origin = CALENDAR_POSITION
+                .place([[0.0, 0.0], bounds], Calendar::SIZE, self.window)
+                .origin;
+        }
+    }
+
+    /// Handles input while the calendar is open. Returns whether it needs to be redrawn, and
+    /// the picked date.
+    fn calendar_input(&mut self, event: &DeviceInput) -> (bool, Option<Date>) {
+        let first_day = self.first_day;
+        let limits = self.limits;
+        let Some(calendar) = &mut self.calendar else {
+            return (false, None);
+        };
+
+        if let Some((key, shift)) =
+            event.on_key_down(|key| (key.logical_key().clone(), key.shift_held()))
+        {
+            let Key::Named(named) = key else {
+                return (false, None);
+            };
+            let current = calendar.highlighted.unwrap_or(calendar.month);
+            let moved = match named {
+                NamedKey::Escape => {
+                    self.calendar = None;
+                    return (true, None);
+                }
+                NamedKey::Enter | NamedKey::Space => {
+                    return match calendar.highlighted.filter(|date| limits.contains(*date)) {
+                        Some(date) => (true, self.pick(date)),
+                        None => (false, None),
+                    };
+                }
+                NamedKey::ArrowLeft => current.add_days(-1),
+                NamedKey::ArrowRight => current.add_days(1),
+                NamedKey::ArrowUp => current.add_days(-7),
+                NamedKey::ArrowDown => current.add_days(7),
+                NamedKey::PageUp => current.add_months(if shift { -12 } else { -1 }),
+                NamedKey::PageDown => current.add_months(if shift { 12 } else { 1 }),
+                _ => return (false, None),
+            };
+            calendar.highlight(limits.clamp(moved));
+            return (true, None);
+        }
+
+        let Some(position) = event.mouse_position() else {
+            return (false, None);
+        };
+        let hit = calendar.hit(position, first_day);
+
+        if event.on_click(|_| ()).is_some() {
+            if !calendar.contains(position) {
+                self.calendar = None;
+                return (true, None);
+            }
+            let months = match hit {
+                Some(CalendarHit::PreviousYear) => -12,
+                Some(CalendarHit::PreviousMonth) => -1,
+                Some(CalendarHit::NextMonth) => 1,
+                Some(CalendarHit::NextYear) => 12,
+                Some(CalendarHit::Day(date)) if limits.contains(date) => {
+                    return (true, self.pick(date));
+                }
+                _ => return (false, None),
+            };
+            calendar.month = calendar.month.add_months(months);
+            return (true, None);
+        }
+
+        if matches!(event.event(), DeviceInputData::MouseInput { .. }) {
+            let hovered = match hit {
+                Some(CalendarHit::Day(date)) if limits.contains(date) => Some(date),
+                _ => None,
+            };
+            if hovered.is_some() && hovered != calendar.highlighted {
+                calendar.highlighted = hovered;
+                return (true, None);
+            }
+        }
+        (false, None)
+    }
+
+    fn render_calendar(&self, calendar: &Calendar, ctx: &WidgetContext) -> Option<RenderNode> {
+        let size = Calendar::SIZE;
+        let texture_size = [size[0].ceil() as u32, size[1].ceil() as u32];
+        let region = ctx
+            .texture_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), texture_size)
+            .ok()?;
+
+        let mut encoder = ctx
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("DatePicker Render Encoder"),
+            });
+
+        // border, then the panel inset by one pixel
+        SolidBox {
+            color: BORDER_COLOR,
+        }
+        .draw(&mut encoder, &region, size, [0.0, 0.0], ctx);
+        SolidBox { color: PANEL_COLOR }.draw(
+            &mut encoder,
+            &region,
+            [size[0] - 2.0, size[1] - 2.0],
+            [1.0, 1.0],
+            ctx,
+        );
+
+        // draws `text` centered in the box at `origin` of `width` and one cell high
+        let centered = |encoder: &mut wgpu::CommandEncoder,
+                        text: &str,
+                        color: Color,
+                        origin: [f32; 2],
+                        width: f32| {
+            let (text, text_size) = measured_text(
+                Sentence::new(text).color(color),
+                self.style.font_size,
+                self.style.line_height,
+                MAX_LABEL_EXTENT,
+                ctx,
+            );
+            text.draw(
+                encoder,
+                &region,
+                text_size,
+                [
+                    origin[0] + ((width - text_size[0]) / 2.0).round(),
+                    origin[1] + ((CELL - text_size[1]) / 2.0).round(),
+                ],
+                ctx,
+            );
+        };
+
+        // header
+        let month = calendar.month;
+        let title = format!(
+            "{} {}",
+            self.month_names[month.month() as usize - 1],
+            month.year()
+        );
+        centered(
+            &mut encoder,
+            &title,
+            self.style.color,
+            [PADDING + 2.0 * CELL, PADDING],
+            3.0 * CELL,
+        );
+        for (column, arrow) in [(0, "«"), (1, "‹"), (5, "›"), (6, "»")] {
+            centered(
+                &mut encoder,
+                arrow,
+                self.style.color,
+                [PADDING + column as f32 * CELL, PADDING],
+                CELL,
+            );
+        }
+
+        // weekday names
+        let first_day = self.first_day;
+        for column in 0..7 {
+            let weekday =
+                Weekday::from_days_from_monday(first_day.days_from_monday() + column as u32);
+            centered(
+                &mut encoder,
+                &self.weekday_names[weekday.days_from_monday() as usize],
+                MUTED_TEXT_COLOR,
+                [PADDING + column as f32 * CELL, PADDING + CELL],
+                CELL,
+            );
+        }
+
+        // days
+        let start = calendar.grid_start(first_day);
+        for index in 0..7 * WEEKS {
+            let date = start.add_days(index as i64);
+            let origin = [
+                PADDING + (index % 7) as f32 * CELL,
+                PADDING + (2 + index / 7) as f32 * CELL,
+            ];
+
+            let selected = date == self.value;
+            let fill = if selected {
+                Some(SELECTED_COLOR)
+            } else if calendar.highlighted == Some(date) {
+                Some(HIGHLIGHT_COLOR)
+            } else {
+                None
+            };
+            if let Some(color) = fill {
+                SolidBox { color }.draw(
+                    &mut encoder,
+                    &region,
+                    [CELL - 2.0, CELL - 2.0],
+                    [origin[0] + 1.0, origin[1] + 1.0],
+                    ctx,
+                );
+            }
+
+            let color = if !self.limits.contains(date) {
+                DISABLED_TEXT_COLOR
+            } else if selected {
+                SELECTED_TEXT_COLOR
+            } else if date.month() != month.month() {
+                MUTED_TEXT_COLOR
+            } else {
+                self.style.color
+            };
+            centered(&mut encoder, &date.day().to_string(), color, origin, CELL);
+        }
+
+        ctx.queue().submit(Some(encoder.finish()));
+
+        Some(RenderNode::new().with_texture(
+            region,
+            size,
+            translation(calendar.origin[0], calendar.origin[1]),
+        ))
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<DatePicker<T>, T, ()> for DatePickerNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a DatePicker<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        let calendar_changed = self.limits != dom.limits
+            || self.first_day != dom.first_day
+            || self.month_names != dom.month_names
+            || self.weekday_names != dom.weekday_names;
+        self.limits = dom.limits;
+        self.format = dom.format;
+        self.first_day = dom.first_day;
+        self.month_names = dom.month_names.clone();
+        self.weekday_names = dom.weekday_names.clone();
+        self.invalid_background = dom.invalid_background;
+        self.button_color = dom.button_color;
+        self.on_change = dom.on_change.clone();
+        self.on_invalid = dom.on_invalid.clone();
+
+        let relayout = self.style != dom.style;
+        if relayout {
+            self.style = dom.style.clone();
+        }
+        let mut redraw = self.field.set_style(&self.field_style())
+            || (calendar_changed && self.calendar.is_some());
+
+        // text being typed is kept while it reads as the date; the rest is reformatted, e.g.
+        // after the format changed
+        let typed = self.parse(&self.field.text());
+        if dom.value != self.value || (!self.field.is_focused() && self.error.is_none()) {
+            if dom.value != self.value && self.calendar.is_some() {
+                redraw = true;
+            }
+            self.value = dom.value;
+            if typed != Ok(dom.value) || !self.field.is_focused() {
+                redraw |= self.show_value();
+            }
+        }
+
+        if let Some(handle) = cache_invalidator {
+            if relayout {
+                handle.relayout_next_frame();
+            } else if redraw {
+                handle.redraw_next_frame();
+            }
+        }
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        [
+            constraints.max_width(),
+            self.field
+                .height()
+                .clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn baseline(
+        &self,
+        _constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        self.field.baseline(ctx)
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        // the window moves relative to the widget when it is scrolled or the window resized
+        let window = popup::window_bounds(event, ctx);
+        if window.is_some() && window != self.window {
+            self.window = window;
+            if self.calendar.is_some() {
+                self.place_calendar(bounds);
+                cache_invalidator.redraw_next_frame();
+            }
+        }
+
+        let was_open = self.calendar.is_some();
+        if let Some(calendar) = &self.calendar {
+            // a click outside closes the calendar and goes on to the field
+            let passed_on = event.on_click(|_| ()).is_some()
+                && event
+                    .mouse_position()
+                    .is_some_and(|position| !calendar.contains(position));
+            let (redraw, picked) = self.calendar_input(event);
+            if redraw {
+                cache_invalidator.redraw_next_frame();
+            }
+            if let Some(date) = picked {
+                return self.on_change.as_ref().map(|on_change| on_change(date));
+            }
+            if !passed_on {
+                return None;
+            }
+        }
+
+        let field_bounds = Self::field_bounds(bounds);
+        let focused = self.field.is_focused();
+        let input = self
+            .field
+            .device_input(field_bounds, event, &cache_invalidator, ctx);
+
+        let button = !was_open
+            && event
+                .on_click(|_| ())
+                .and_then(|()| event.mouse_position())
+                .is_some_and(|position| {
+                    (field_bounds[0]..=bounds[0]).contains(&position[0])
+                        && (0.0..=bounds[1]).contains(&position[1])
+                });
+        let key = event
+            .on_key_down(|key| {
+                key.alt_held() && matches!(key.logical_key(), Key::Named(NamedKey::ArrowDown))
+            })
+            .unwrap_or(false)
+            && self.field.is_focused();
+        if button || key {
+            self.open_calendar(bounds);
+            cache_invalidator.redraw_next_frame();
+            return None;
+        }
+
+        let mut result = Ok(None);
+        if input.edited {
+            result = self.validate();
+            cache_invalidator.redraw_next_frame();
+        } else if input.submitted || (focused && !self.field.is_focused()) {
+            // leaving the field drops text that is not a valid date
+            self.show_value();
+            cache_invalidator.redraw_next_frame();
+        }
+
+        match result {
+            Ok(Some(date)) => self.on_change.as_ref().map(|on_change| on_change(date)),
+            Err(Some(error)) => self.on_invalid.as_ref().map(|on_invalid| on_invalid(error)),
+            _ => None,
+        }
+    }
+
+    fn is_inside(
+        &self,
+        bounds: [f32; 2],
+        position: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _ctx: &WidgetContext,
+    ) -> bool {
+        ((0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1]))
+            || self
+                .calendar
+                .as_ref()
+                .is_some_and(|calendar| calendar.contains(position))
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        if bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return render_node;
+        }
+
+        let field_bounds = Self::field_bounds(bounds);
+        render_node.push_child(self.field.render(field_bounds, ctx), Matrix4::identity());
+
+        // the calendar button, one pixel apart from the field
+        let button_size = [Self::button_width(bounds) - 1.0, bounds[1]];
+        let x = field_bounds[0] + 1.0;
+        let background = SolidBox {
+            color: self.button_color,
+        };
+        if let Some(node) = style_node(&background, button_size, ctx) {
+            render_node.push_child(node, translation(x, 0.0));
+        }
+        let (arrow, size) = measured_text(
+            Sentence::new("\u{25BE}").color(self.style.color),
+            self.style.font_size,
+            self.style.line_height,
+            MAX_LABEL_EXTENT,
+            ctx,
+        );
+        if let Some(node) = style_node(&arrow, size, ctx) {
+            render_node.push_child(
+                node,
+                translation(
+                    x + (button_size[0] - size[0]) / 2.0,
+                    (button_size[1] - size[1]) / 2.0,
+                ),
+            );
+        }
+
+        if let Some(calendar) = &self.calendar
+            && let Some(node) = self.render_calendar(calendar, ctx)
+        {
+            render_node.push_child(node, Matrix4::identity());
+        }
+
+        render_node
+    }
+
+    fn accepts_focus(&self) -> bool {
+        true
+    }
+
+    fn is_focused(&self) -> bool {
+        self.field.is_focused()
+    }
+
+    fn set_focused(
+        &mut self,
+        focused: bool,
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) {
+        self.field.set_focused(focused, &cache_invalidator, ctx);
+        if !focused {
+            // leaving the field drops text that is not a valid date
+            self.show_value();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn dates_are_validated_and_counted() {
+        assert!(Date::new(2024, 2, 29).is_some());
+        assert!(Date::new(2023, 2, 29).is_none());
+        assert!(Date::new(1900, 2, 29).is_none());
+        assert!(Date::new(2000, 2, 29).is_some());
+        assert!(Date::new(2024, 13, 1).is_none());
+        assert!(Date::new(0, 1, 1).is_none());
+
+        assert_eq!(date(1970, 1, 1).weekday(), Weekday::Thursday);
+        assert_eq!(date(2024, 2, 29).weekday(), Weekday::Thursday);
+        assert_eq!(date(2024, 2, 28).add_days(2), date(2024, 3, 1));
+        assert_eq!(date(2024, 1, 1).add_days(-1), date(2023, 12, 31));
+        assert_eq!(date(2024, 1, 31).add_months(1), date(2024, 2, 29));
+        assert_eq!(date(2024, 3, 15).add_months(-15), date(2022, 12, 15));
+        assert_eq!(Date::MAX.add_days(1), Date::MAX);
+        assert_eq!(Date::MIN.add_months(-1), Date::MIN);
+        assert_eq!(date(5, 6, 7).to_string(), "0005-06-07");
+    }
+
+    #[test]
+    fn formats_read_what_they_write() {
+        let german = DateFormat::new(DateOrder::DayMonthYear, '.');
+        let us = DateFormat::new(DateOrder::MonthDayYear, '/');
+        let leap_day = date(2024, 2, 29);
+        assert_eq!(DateFormat::ISO.format(leap_day), "2024-02-29");
+        assert_eq!(german.format(leap_day), "29.02.2024");
+        assert_eq!(us.format(leap_day), "02/29/2024");
+
+        assert_eq!(german.parse(" 1.3.2024 "), Some(date(2024, 3, 1)));
+        assert_eq!(us.parse("2/29/2024"), Some(leap_day));
+        assert_eq!(DateFormat::ISO.parse("2023-02-29"), None);
+        assert_eq!(DateFormat::ISO.parse("24-02-01"), None);
+        assert_eq!(DateFormat::ISO.parse("2024-+2-01"), None);
+        assert_eq!(DateFormat::ISO.parse("2024/02/01"), None);
+        assert_eq!(DateFormat::ISO.parse(""), None);
+    }
+
+    #[test]
+    fn weeks_start_on_the_first_day() {
+        // March 2024 starts on a Friday
+        let calendar = Calendar {
+            month: date(2024, 3, 1),
+            highlighted: None,
+            origin: [10.0, 20.0],
+        };
+        assert_eq!(calendar.grid_start(Weekday::Monday), date(2024, 2, 26));
+        assert_eq!(calendar.grid_start(Weekday::Sunday), date(2024, 2, 25));
+        assert_eq!(calendar.grid_start(Weekday::Friday), date(2024, 3, 1));
+
+        let cell = |row: usize, column: usize| {
+            [
+                10.0 + PADDING + (column as f32 + 0.5) * CELL,
+                20.0 + PADDING + (row as f32 + 0.5) * CELL,
+            ]
+        };
+        assert_eq!(
+            calendar.hit(cell(2, 4), Weekday::Monday),
+            Some(CalendarHit::Day(date(2024, 3, 1)))
+        );
+        assert_eq!(
+            calendar.hit(cell(7, 6), Weekday::Monday),
+            Some(CalendarHit::Day(date(2024, 4, 7)))
+        );
+        assert_eq!(
+            calendar.hit(cell(0, 1), Weekday::Monday),
+            Some(CalendarHit::PreviousMonth)
+        );
+        assert_eq!(calendar.hit(cell(0, 3), Weekday::Monday), None);
+        assert_eq!(calendar.hit(cell(1, 3), Weekday::Monday), None);
+
+        let limits = Limits {
+            min: Some(date(2024, 3, 5)),
+            max: None,
+        };
+        assert_eq!(
+            limits.check(date(2024, 3, 1)),
+            Err(DateError::BeforeMin(date(2024, 3, 5)))
+        );
+        assert_eq!(limits.clamp(date(2024, 3, 1)), date(2024, 3, 5));
+    }
+}
@@ -4,7 +4,7 @@ use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
     ui::{
-        AnyWidgetFrame, Background, Dom, Widget, WidgetFrame,
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
         widget::{AnyWidget, InvalidationHandle},
     },
 };
@@ -17,6 +17,7 @@ use nalgebra::Matrix4;
 
 pub struct Image {
     label: Option<String>,
+    layout_style: LayoutStyle,
     image_style: style::image::Image,
 }
 
@@ -24,10 +25,17 @@ impl Image {
     pub fn new(image: impl Into<style::image::ImageSource>) -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             image_style: style::image::Image::new(image),
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
     pub fn label(mut self, label: &str) -> Self {
         self.label = Some(label.to_string());
         self
@@ -37,19 +45,37 @@ impl Image {
         self.image_style = self.image_style.size(size);
         self
     }
+
+    /// Decode the image at its original resolution even when it is displayed smaller.
+    pub fn full_resolution(mut self) -> Self {
+        self.image_style = self.image_style.full_resolution();
+        self
+    }
+
+    pub fn resample_quality(mut self, quality: style::image::ResampleQuality) -> Self {
+        self.image_style = self.image_style.resample_quality(quality);
+        self
+    }
 }
 
 #[async_trait::async_trait]
 impl<T: Send + Sync + 'static> Dom<T> for Image {
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            vec![],
-            vec![],
-            ImageNode {
-                image_style: self.image_style.clone(),
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                ImageNode {
+                    image_style: self.image_style.clone(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
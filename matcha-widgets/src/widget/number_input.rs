@@ -0,0 +1,661 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::text_edit::{FieldStyle, LineField};
+use crate::style::solid_box::SolidBox;
+use crate::style::text::{Sentence, TextDesc};
+use crate::style::{Style, style_node, translation};
+
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::localization::current_decimal_separator;
+use matcha_core::{
+    device_input::{DeviceInput, Key},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use renderer::render_node::RenderNode;
+use winit::keyboard::NamedKey;
+
+/// Steps taken by Page Up and Page Down.
+const PAGE_STEPS: i32 = 10;
+
+// MARK: Number
+
+/// A number type a [`NumberInput`] can edit. Implemented for the primitive integers and
+/// floats.
+pub trait Number:
+    Copy + PartialOrd + Display + FromStr + std::fmt::Debug + Send + Sync + 'static
+{
+    /// The step used unless one is set.
+    const ONE: Self;
+
+    /// `self` moved by `count` steps, saturating at the limits of the type.
+    fn offset(self, step: Self, count: i32) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const ONE: Self = 1;
+
+            fn offset(self, step: Self, count: i32) -> Self {
+                let count_abs = Self::try_from(count.unsigned_abs()).unwrap_or(Self::MAX);
+                let delta = step.saturating_mul(count_abs);
+                if count < 0 {
+                    self.saturating_sub(delta)
+                } else {
+                    self.saturating_add(delta)
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_float {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const ONE: Self = 1.0;
+
+            fn offset(self, step: Self, count: i32) -> Self {
+                self + step * count as Self
+            }
+        }
+    )*};
+}
+
+impl_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_float!(f32, f64);
+
+/// Why the text of a [`NumberInput`] is not an acceptable value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberError<N> {
+    /// The text is not a number of the input's type.
+    Invalid,
+    /// The number is below the minimum, which is given.
+    BelowMin(N),
+    /// The number is above the maximum, which is given.
+    AboveMax(N),
+}
+
+impl<N: Display> Display for NumberError<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberError::Invalid => write!(f, "not a valid number"),
+            NumberError::BelowMin(min) => write!(f, "must be at least {min}"),
+            NumberError::AboveMax(max) => write!(f, "must be at most {max}"),
+        }
+    }
+}
+
+impl<N: std::fmt::Debug + Display> std::error::Error for NumberError<N> {}
+
+/// The value and limits of a [`NumberInput`], with its text conversions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Bounds<N> {
+    min: Option<N>,
+    max: Option<N>,
+    step: N,
+    /// digits after the decimal separator, those of `step` if `None`.
+    precision: Option<usize>,
+}
+
+impl<N: Number> Bounds<N> {
+    fn format(&self, value: N, separator: char) -> String {
+        let precision = self.precision.unwrap_or_else(|| {
+            let step = self.step.to_string();
+            step.split_once('.')
+                .map_or(0, |(_, fraction)| fraction.len())
+        });
+        // the precision is ignored for integers
+        let text = format!("{value:.precision$}");
+        if separator == '.' {
+            text
+        } else {
+            text.replace('.', &separator.to_string())
+        }
+    }
+
+    fn parse(&self, text: &str, separator: char) -> Result<N, NumberError<N>> {
+        let text = text.trim();
+        // no `inf`, `NaN` or exponents, and only the separator of the locale
+        if text.chars().any(char::is_alphabetic) || (separator != '.' && text.contains('.')) {
+            return Err(NumberError::Invalid);
+        }
+        let value = text
+            .replace(separator, ".")
+            .parse::<N>()
+            .map_err(|_| NumberError::Invalid)?;
+        match (self.min, self.max) {
+            (Some(min), _) if value < min => Err(NumberError::BelowMin(min)),
+            (_, Some(max)) if value > max => Err(NumberError::AboveMax(max)),
+            _ => Ok(value),
+        }
+    }
+
+    fn clamp(&self, value: N) -> N {
+        match (self.min, self.max) {
+            (Some(min), _) if value < min => min,
+            (_, Some(max)) if value > max => max,
+            _ => value,
+        }
+    }
+}
+
+// MARK: DOM
+
+type ValueHandler<T, N> = Arc<dyn Fn(N) -> T + Send + Sync>;
+type ErrorHandler<T, N> = Arc<dyn Fn(NumberError<N>) -> T + Send + Sync>;
+
+/// A text field for a number, with buttons to step it down and up.
+///
+/// Arrow Up and Down, Page Up and Down and the mouse wheel step the value while the field is
+/// focused. Typed text is parsed with the decimal separator of the app's locale. While it is
+/// not a number within the limits the field shows the invalid background and
+/// [`on_invalid`](Self::on_invalid) is emitted; Enter or leaving the field then restores the
+/// last valid value.
+pub struct NumberInput<T, N: Number> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    value: N,
+    bounds: Bounds<N>,
+    separator: char,
+    style: FieldStyle,
+    invalid_background: Color,
+    button_color: Color,
+    on_change: Option<ValueHandler<T, N>>,
+    on_invalid: Option<ErrorHandler<T, N>>,
+}
+
+impl<T, N: Number> NumberInput<T, N> {
+    pub fn new(value: N) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            value,
+            bounds: Bounds {
+                min: None,
+                max: None,
+                step: N::ONE,
+                precision: None,
+            },
+            separator: current_decimal_separator(),
+            style: FieldStyle::default(),
+            invalid_background: Color::rgb(255, 228, 228),
+            button_color: Color::rgb(230, 230, 230),
+            on_change: None,
+            on_invalid: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn min(mut self, min: N) -> Self {
+        self.bounds.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: N) -> Self {
+        self.bounds.max = Some(max);
+        self
+    }
+
+    /// Amount added or removed by one step. 1 by default.
+    pub fn step(mut self, step: N) -> Self {
+        self.bounds.step = step;
+        self
+    }
+
+    /// Digits shown after the decimal separator. By default those of the step.
+    pub fn precision(mut self, digits: usize) -> Self {
+        self.bounds.precision = Some(digits);
+        self
+    }
+
+    /// Overrides the decimal separator of the app's locale.
+    pub fn decimal_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.style.font_size = size;
+        self
+    }
+
+    pub fn line_height(mut self, height: f32) -> Self {
+        self.style.line_height = height;
+        self
+    }
+
+    /// Color of the text, the caret and the button labels.
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = color;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.style.background = color;
+        self
+    }
+
+    /// Background of the field while its text is not a valid value.
+    pub fn invalid_background(mut self, color: Color) -> Self {
+        self.invalid_background = color;
+        self
+    }
+
+    pub fn button_color(mut self, color: Color) -> Self {
+        self.button_color = color;
+        self
+    }
+
+    /// Called with the new value when it is typed or stepped.
+    pub fn on_change(mut self, f: impl Fn(N) -> T + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(f));
+        self
+    }
+
+    /// Called when the typed text becomes invalid, or invalid for another reason.
+    pub fn on_invalid(mut self, f: impl Fn(NumberError<N>) -> T + Send + Sync + 'static) -> Self {
+        self.on_invalid = Some(Arc::new(f));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static, N: Number> Dom<T> for NumberInput<T, N> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                NumberInputNode {
+                    value: self.value,
+                    bounds: self.bounds,
+                    separator: self.separator,
+                    style: self.style.clone(),
+                    invalid_background: self.invalid_background,
+                    button_color: self.button_color,
+                    on_change: self.on_change.clone(),
+                    on_invalid: self.on_invalid.clone(),
+                    field: LineField::new(
+                        &self.bounds.format(self.value, self.separator),
+                        self.style.clone(),
+                    ),
+                    error: None,
+                    buttons: button_labels(&self.style),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+fn button_labels(style: &FieldStyle) -> [crate::style::text::Text; 2] {
+    ["\u{2212}", "+"].map(|label| {
+        crate::style::text::Text::new(
+            &TextDesc::new(vec![Sentence::new(label).color(style.color)])
+                .font_size(style.font_size)
+                .line_height(style.line_height),
+        )
+    })
+}
+
+// MARK: Widget
+
+pub struct NumberInputNode<T, N: Number> {
+    /// value of the last dom or the last one emitted.
+    value: N,
+    bounds: Bounds<N>,
+    separator: char,
+    style: FieldStyle,
+    invalid_background: Color,
+    button_color: Color,
+    on_change: Option<ValueHandler<T, N>>,
+    on_invalid: Option<ErrorHandler<T, N>>,
+
+    field: LineField,
+    /// why the text is not a valid value.
+    error: Option<NumberError<N>>,
+    /// labels of the step down and up buttons.
+    buttons: [crate::style::text::Text; 2],
+}
+
+impl<T, N: Number> NumberInputNode<T, N> {
+    /// Width of each button, which are square.
+    fn button_width(bounds: [f32; 2]) -> f32 {
+        bounds[1].min(bounds[0] / 3.0)
+    }
+
+    fn field_bounds(bounds: [f32; 2]) -> [f32; 2] {
+        [bounds[0] - 2.0 * Self::button_width(bounds), bounds[1]]
+    }
+
+    fn field_style(&self) -> FieldStyle {
+        let mut style = self.style.clone();
+        if self.error.is_some() {
+            style.background = self.invalid_background;
+        }
+        style
+    }
+
+    /// Shows the value in the field and forgets any error. Returns whether anything changed.
+    fn show_value(&mut self) -> bool {
+        let text = self.bounds.format(self.value, self.separator);
+        let changed = self.field.set_text(&text) | self.error.take().is_some();
+        self.field.set_style(&self.field_style());
+        changed
+    }
+
+    /// Moves the value by `count` steps from what the field shows.
+    fn step(&mut self, count: i32) -> Option<N> {
+        let base = self
+            .bounds
+            .parse(&self.field.text(), self.separator)
+            .unwrap_or(self.value);
+        let value = self.bounds.clamp(base.offset(self.bounds.step, count));
+        let changed = value != self.value;
+        self.value = value;
+        self.show_value();
+        changed.then_some(value)
+    }
+
+    /// Checks the typed text. Returns the new value, or the error if it is a new one.
+    fn validate(&mut self) -> Result<Option<N>, Option<NumberError<N>>> {
+        let result = self.bounds.parse(&self.field.text(), self.separator);
+        let previous = self.error;
+        self.error = result.err();
+        self.field.set_style(&self.field_style());
+        match result {
+            Ok(value) if value != self.value => {
+                self.value = value;
+                Ok(Some(value))
+            }
+            Ok(_) => Ok(None),
+            Err(error) => Err((previous != Some(error)).then_some(error)),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static, N: Number> Widget<NumberInput<T, N>, T, ()>
+    for NumberInputNode<T, N>
+{
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a NumberInput<T, N>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.bounds = dom.bounds;
+        self.separator = dom.separator;
+        self.invalid_background = dom.invalid_background;
+        self.button_color = dom.button_color;
+        self.on_change = dom.on_change.clone();
+        self.on_invalid = dom.on_invalid.clone();
+
+        let relayout = self.style != dom.style;
+        if relayout {
+            self.style = dom.style.clone();
+            self.buttons = button_labels(&self.style);
+        }
+        let mut redraw = self.field.set_style(&self.field_style());
+
+        // text being typed is kept while it reads as the value; the rest is reformatted, e.g.
+        // after the locale changed
+        let typed = self.bounds.parse(&self.field.text(), self.separator);
+        if dom.value != self.value || (!self.field.is_focused() && self.error.is_none()) {
+            self.value = dom.value;
+            if typed != Ok(dom.value) || !self.field.is_focused() {
+                redraw |= self.show_value();
+            }
+        }
+
+        if let Some(handle) = cache_invalidator {
+            if relayout {
+                handle.relayout_next_frame();
+            } else if redraw {
+                handle.redraw_next_frame();
+            }
+        }
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        [
+            constraints.max_width(),
+            self.field
+                .height()
+                .clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn baseline(
+        &self,
+        _constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        self.field.baseline(ctx)
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let field_bounds = Self::field_bounds(bounds);
+        let focused = self.field.is_focused();
+        let input = self
+            .field
+            .device_input(field_bounds, event, &cache_invalidator, ctx);
+
+        // steps from the buttons, the keyboard and the wheel
+        let button = event
+            .on_click(|_| ())
+            .and_then(|()| event.mouse_position())
+            .filter(|position| (0.0..=bounds[1]).contains(&position[1]))
+            .and_then(|position| {
+                let button_width = Self::button_width(bounds);
+                match position[0] - field_bounds[0] {
+                    x if (0.0..button_width).contains(&x) => Some(-1),
+                    x if (button_width..=2.0 * button_width).contains(&x) => Some(1),
+                    _ => None,
+                }
+            });
+        let key = event
+            .on_key_down(|key| match key.logical_key() {
+                Key::Named(NamedKey::ArrowUp) => Some(1),
+                Key::Named(NamedKey::ArrowDown) => Some(-1),
+                Key::Named(NamedKey::PageUp) => Some(PAGE_STEPS),
+                Key::Named(NamedKey::PageDown) => Some(-PAGE_STEPS),
+                _ => None,
+            })
+            .flatten()
+            .filter(|_| self.field.is_focused());
+        let wheel = event
+            .on_scroll(|delta| delta[1])
+            .filter(|delta| *delta != 0.0 && self.field.is_focused())
+            .filter(|_| {
+                event
+                    .mouse_position()
+                    .is_some_and(|position| (0.0..=field_bounds[0]).contains(&position[0]))
+            })
+            .map(|delta| if delta > 0.0 { 1 } else { -1 });
+
+        let mut result = Ok(None);
+        if let Some(count) = button.or(key).or(wheel) {
+            result = Ok(self.step(count));
+            cache_invalidator.redraw_next_frame();
+        } else if input.edited {
+            result = self.validate();
+            cache_invalidator.redraw_next_frame();
+        } else if input.submitted || (focused && !self.field.is_focused()) {
+            // leaving the field drops text that is not a valid value
+            self.show_value();
+            cache_invalidator.redraw_next_frame();
+        }
+
+        match result {
+            Ok(Some(value)) => self.on_change.as_ref().map(|on_change| on_change(value)),
+            Err(Some(error)) => self.on_invalid.as_ref().map(|on_invalid| on_invalid(error)),
+            _ => None,
+        }
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        if bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return render_node;
+        }
+
+        let field_bounds = Self::field_bounds(bounds);
+        render_node.push_child(self.field.render(field_bounds, ctx), Matrix4::identity());
+
+        let button_width = Self::button_width(bounds);
+        let background = SolidBox {
+            color: self.button_color,
+        };
+        // one pixel between the buttons
+        let button_size = [button_width - 1.0, bounds[1]];
+        for (index, label) in self.buttons.iter().enumerate() {
+            let x = field_bounds[0] + index as f32 * button_width + 1.0;
+            if let Some(node) = style_node(&background, button_size, ctx) {
+                render_node.push_child(node, translation(x, 0.0));
+            }
+            let size = label
+                .required_region(&Constraints::from_boundary(button_size), ctx)
+                .map_or([0.0, 0.0], |rect| [rect.width(), rect.height()]);
+            if let Some(node) = style_node(label, size, ctx) {
+                render_node.push_child(
+                    node,
+                    translation(
+                        x + (button_size[0] - size[0]) / 2.0,
+                        (button_size[1] - size[1]) / 2.0,
+                    ),
+                );
+            }
+        }
+
+        render_node
+    }
+
+    fn accepts_focus(&self) -> bool {
+        true
+    }
+
+    fn is_focused(&self) -> bool {
+        self.field.is_focused()
+    }
+
+    fn set_focused(
+        &mut self,
+        focused: bool,
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) {
+        self.field.set_focused(focused, &cache_invalidator, ctx);
+        if !focused {
+            // leaving the field drops text that is not a valid value
+            self.show_value();
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn bounds<N: Number>(min: N, max: N, step: N) -> Bounds<N> {
+        Bounds {
+            min: Some(min),
+            max: Some(max),
+            step,
+            precision: None,
+        }
+    }
+
+    #[test]
+    fn integers_parse_within_limits() {
+        let bounds = bounds(-5, 10, 1);
+        assert_eq!(bounds.parse(" 7 ", '.'), Ok(7));
+        assert_eq!(bounds.parse("11", '.'), Err(NumberError::AboveMax(10)));
+        assert_eq!(bounds.parse("-6", '.'), Err(NumberError::BelowMin(-5)));
+        assert_eq!(bounds.parse("1.5", '.'), Err(NumberError::Invalid));
+        assert_eq!(bounds.parse("", '.'), Err(NumberError::Invalid));
+        assert_eq!(bounds.format(3, ','), "3");
+        assert_eq!(bounds.clamp(20), 10);
+    }
+
+    #[test]
+    fn floats_use_the_locale_separator() {
+        let bounds = bounds(0.0, 1.0, 0.05);
+        assert_eq!(bounds.parse("0,25", ','), Ok(0.25));
+        assert_eq!(bounds.parse("0.25", ','), Err(NumberError::Invalid));
+        assert_eq!(bounds.parse("NaN", '.'), Err(NumberError::Invalid));
+        assert_eq!(bounds.parse("inf", '.'), Err(NumberError::Invalid));
+        // shown with the digits of the step
+        assert_eq!(bounds.format(0.1 + 0.2, ','), "0,30");
+        assert_eq!(
+            Bounds {
+                precision: Some(1),
+                ..bounds
+            }
+            .format(0.26, '.'),
+            "0.3"
+        );
+    }
+
+    #[test]
+    fn steps_saturate() {
+        assert_eq!(250u8.offset(10, 1), 255);
+        assert_eq!(3u8.offset(2, -2), 0);
+        assert_eq!(i32::MIN.offset(1, -1), i32::MIN);
+        assert_eq!(0.5f64.offset(0.25, -3), -0.25);
+    }
+}
@@ -0,0 +1,373 @@
+use std::time::Duration;
+
+use crate::style::Style;
+use gpu_utils::memory::MemoryReport;
+use matcha_core::metrics::{Arrangement, Constraints};
+use matcha_core::{
+    cache_budget::CacheStats,
+    color::Color,
+    context::WidgetContext,
+    device_input::DeviceInput,
+    present_timing::PresentStats,
+    timer::TimerHandle,
+    ui::{
+        AnyWidgetFrame, Background, ChildFrameLinker, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use renderer::render_node::RenderNode;
+
+use crate::style::{
+    solid_box::SolidBox,
+    text::{Sentence, Text, TextDesc},
+};
+
+const MARGIN: f32 = 8.0;
+const PADDING: f32 = 6.0;
+const FONT_SIZE: f32 = 12.0;
+const LINE_HEIGHT: f32 = 16.0;
+const MAX_WIDTH: f32 = 320.0;
+
+const PANEL_COLOR: Color = Color::RgbaF32 {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 0.7,
+};
+const TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+// MARK: DOM
+
+/// Shows the draw calls, instances and frame time of the window, the render and layout caches
+/// and the GPU memory of the process in a panel over the top left corner of `content`.
+///
+/// The numbers are the [`PresentStats`] of the last frame, the [`CacheStats`] and the
+/// [`MemoryReport`] of the atlases and renderer buffers. They refresh every `interval`, half
+/// a second by default. Refreshing draws a new frame, so the HUD keeps an idle window
+/// rendering at that rate.
+pub struct PerformanceHud<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    content: Box<dyn Dom<T>>,
+    interval: Duration,
+}
+
+impl<T: 'static> PerformanceHud<T> {
+    pub fn new(content: impl Dom<T>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            content: Box::new(content),
+            interval: Duration::from_millis(500),
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// How often the numbers refresh.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for PerformanceHud<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0],
+                PerformanceHudNode {
+                    interval: self.interval,
+                    linker: None,
+                    timer: None,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct PerformanceHudNode {
+    interval: Duration,
+    linker: Option<ChildFrameLinker>,
+    timer: Option<TimerHandle>,
+}
+
+impl PerformanceHudNode {
+    fn start_timer(&mut self, ctx: &WidgetContext) {
+        if let Some(linker) = self.linker.clone() {
+            self.timer = Some(ctx.set_interval(self.interval, move || linker.redraw_next_frame()));
+        }
+    }
+
+    fn render_panel(
+        &self,
+        stats: &PresentStats,
+        cache: &CacheStats,
+        memory: &MemoryReport,
+        ctx: &WidgetContext,
+    ) -> Option<RenderNode> {
+        let text = Text::new(
+            &TextDesc::new(vec![
+                Sentence::new(hud_text(stats, cache, memory)).color(TEXT_COLOR),
+            ])
+            .font_size(FONT_SIZE)
+            .line_height(LINE_HEIGHT),
+        );
+        let text_size = text
+            .required_region(&Constraints::new([0.0, MAX_WIDTH], [0.0, 4096.0]), ctx)
+            .map(|rect| [rect.width(), rect.height()])?;
+        let size = [text_size[0] + 2.0 * PADDING, text_size[1] + 2.0 * PADDING];
+
+        let texture_size = [size[0].ceil() as u32, size[1].ceil() as u32];
+        let region = ctx
+            .texture_atlas()
+            .allocate(&ctx.device(), &ctx.queue(), texture_size)
+            .ok()?;
+
+        let mut encoder = ctx
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("PerformanceHud Render Encoder"),
+            });
+        SolidBox { color: PANEL_COLOR }.draw(&mut encoder, &region, size, [0.0, 0.0], ctx);
+        text.draw(&mut encoder, &region, text_size, [PADDING, PADDING], ctx);
+        ctx.queue().submit(Some(encoder.finish()));
+
+        Some(RenderNode::new().with_texture(
+            region,
+            size,
+            nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(MARGIN, MARGIN, 0.0)),
+        ))
+    }
+}
+
+/// The lines the HUD shows for `stats`, `cache` and `memory`.
+fn hud_text(stats: &PresentStats, cache: &CacheStats, memory: &MemoryReport) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+
+    let render = &stats.render_stats;
+    let visible = render.visible_instances.unwrap_or(render.instances);
+    let frame = stats.frame_interval.map_or("-".to_string(), |interval| {
+        format!("{:.1} ms", interval.as_secs_f64() * 1000.0)
+    });
+    format!(
+        "draw calls: {}\ninstances: {visible} / {}\nblurs: {}\nframe: {frame}\n\
+         caches: {:.1} / {:.1} MiB, {} entries, {} evicted\n{memory}",
+        render.draw_calls,
+        render.instances,
+        render.backdrop_blurs,
+        cache.used_bytes as f64 / MIB,
+        cache.budget_bytes as f64 / MIB,
+        cache.entries,
+        cache.evictions,
+    )
+}
+
+impl<T: Send + Sync + 'static> Widget<PerformanceHud<T>, T, ()> for PerformanceHudNode {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a PerformanceHud<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        if self.interval != dom.interval {
+            self.interval = dom.interval;
+            // restarted by `update_lifecycle` with the new interval
+            self.timer = None;
+            if let Some(handle) = cache_invalidator {
+                handle.redraw_next_frame();
+            }
+        }
+
+        vec![(&*dom.content, (), 0)]
+    }
+
+    fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+        self.linker = Some(linker);
+    }
+
+    fn on_mount(&mut self, ctx: &WidgetContext) {
+        self.start_timer(ctx);
+    }
+
+    fn on_unmount(&mut self) {
+        self.timer = None;
+    }
+
+    fn update_lifecycle(&mut self, _bounds: [f32; 2], ctx: &WidgetContext) {
+        if self.timer.is_none() {
+            self.start_timer(ctx);
+        }
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        if let Some((content, _)) = children.first() {
+            content.measure(constraints, ctx)
+        } else {
+            [0.0, 0.0]
+        }
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+    }
+
+    fn device_input(
+        &mut self,
+        _bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        // the panel lets input through to the content
+        if let Some((content, _, arrangement)) = children.first_mut() {
+            let content_event = event.transform(arrangement.affine);
+            return content.device_input(&content_event, ctx);
+        }
+        None
+    }
+
+    fn is_inside(
+        &self,
+        bounds: [f32; 2],
+        position: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _ctx: &WidgetContext,
+    ) -> bool {
+        (0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1])
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        if let Some((content, _, arrangement)) = children.first() {
+            render_node.push_child(content.render(background, ctx), arrangement.affine);
+        }
+
+        if let Some(stats) = ctx.present_stats()
+            && let Some(panel) =
+                self.render_panel(&stats, &ctx.cache_stats(), &ctx.memory_report(), ctx)
+        {
+            render_node.push_child(panel, nalgebra::Matrix4::identity());
+        }
+
+        render_node
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use gpu_utils::memory::FormatMemory;
+    use renderer::RenderStats;
+
+    #[test]
+    fn hud_text_shows_the_stats_of_the_last_frame() {
+        const MIB: u64 = 1024 * 1024;
+
+        let stats = PresentStats {
+            frame_interval: Some(Duration::from_micros(16_700)),
+            render_stats: RenderStats {
+                instances: 12,
+                visible_instances: Some(9),
+                draw_calls: 2,
+                backdrop_blurs: 1,
+                ..RenderStats::default()
+            },
+            ..PresentStats::default()
+        };
+        let memory = MemoryReport {
+            textures: vec![
+                FormatMemory {
+                    format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                    textures: 2,
+                    bytes: 8 * MIB,
+                    used_bytes: 3 * MIB,
+                },
+                FormatMemory {
+                    format: wgpu::TextureFormat::Stencil8,
+                    textures: 1,
+                    bytes: MIB,
+                    used_bytes: MIB / 2,
+                },
+            ],
+            buffer_atlas_bytes: 2 * MIB,
+            renderer_buffer_bytes: MIB / 2,
+            upload_staging_bytes: 0,
+        };
+        let cache = CacheStats {
+            entries: 42,
+            used_bytes: 3 * MIB as usize / 2,
+            budget_bytes: 64 * MIB as usize,
+            evictions: 7,
+            ..CacheStats::default()
+        };
+
+        assert_eq!(
+            hud_text(&stats, &cache, &memory),
+            "draw calls: 2\ninstances: 9 / 12\nblurs: 1\nframe: 16.7 ms\n\
+             caches: 1.5 / 64.0 MiB, 42 entries, 7 evicted\n\
+             GPU memory: 11.5 MiB\n  \
+             atlas Rgba8UnormSrgb: 8.0 MiB (3.0 MiB used)\n  \
+             atlas Stencil8: 1.0 MiB (0.5 MiB used)\n  \
+             buffer atlases: 2.0 MiB\n  \
+             renderer buffers: 0.5 MiB\n  \
+             upload staging: 0.0 MiB"
+        );
+        assert_eq!(
+            hud_text(
+                &PresentStats::default(),
+                &CacheStats::default(),
+                &MemoryReport::default()
+            ),
+            "draw calls: 0\ninstances: 0 / 0\nblurs: 0\nframe: -\n\
+             caches: 0.0 / 0.0 MiB, 0 entries, 0 evicted\n\
+             GPU memory: 0.0 MiB\n  \
+             buffer atlases: 0.0 MiB\n  \
+             renderer buffers: 0.0 MiB\n  \
+             upload staging: 0.0 MiB"
+        );
+    }
+}
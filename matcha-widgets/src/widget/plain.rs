@@ -6,7 +6,7 @@ use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
     ui::{
-        AnyWidgetFrame, Background, Dom, Widget, WidgetFrame,
+        AnyWidgetFrame, Background, Dom, LayoutStyle, PrepareFuture, Widget, WidgetFrame,
         widget::{AnyWidget, InvalidationHandle},
     },
 };
@@ -18,21 +18,43 @@ use crate::{buffer::Buffer, types::size::Size};
 
 pub struct Plain<T> {
     label: Option<String>,
+    layout_style: LayoutStyle,
     style: Vec<Arc<dyn Style>>,
     content: Option<Box<dyn Dom<T>>>,
     size: [Size; 2],
+    backdrop_blur: Option<f32>,
 }
 
 impl<T> Plain<T> {
     pub fn new(label: Option<&str>) -> Box<Self> {
         Box::new(Self {
             label: label.map(|s| s.to_string()),
+            layout_style: LayoutStyle::default(),
             style: Vec::new(),
             content: None,
             size: [Size::child_w(1.0), Size::child_h(1.0)],
+            backdrop_blur: None,
         })
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    /// Clips the widget and its children to its border box with corners rounded by
+    /// `radius`, see [`LayoutStyle::clip_radius`]. Call after `layout`, which replaces it.
+    pub fn clip_radius(mut self, radius: f32) -> Self {
+        self.layout_style = self.layout_style.clip_radius(radius);
+        self
+    }
+
+    pub fn elevation(mut self, level: u8) -> Self {
+        self.layout_style = self.layout_style.elevation(level);
+        self
+    }
+
     pub fn style(mut self, style: impl Style + 'static) -> Self {
         self.style.push(Arc::new(style));
         self
@@ -47,6 +69,15 @@ impl<T> Plain<T> {
         self.size = size;
         self
     }
+
+    /// Blur what is drawn behind this widget with the given radius (in pixels)
+    /// before drawing the styles, for acrylic-style translucent panels.
+    ///
+    /// See [`RenderNode::with_backdrop_blur`].
+    pub fn backdrop_blur(mut self, radius: f32) -> Self {
+        self.backdrop_blur = Some(radius);
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -60,17 +91,25 @@ impl<T: Send + Sync + 'static> Dom<T> for Plain<T> {
             .collect();
         let child_ids = self.content.as_ref().map(|_| 0).into_iter().collect();
 
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            children,
-            child_ids,
-            PlainNode {
-                style: self.style.clone(),
-                size: self.size.clone(),
-                buffer: Buffer::new(self.style.clone()),
-                _phantom: std::marker::PhantomData,
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                children,
+                child_ids,
+                PlainNode {
+                    style: self.style.clone(),
+                    size: self.size.clone(),
+                    backdrop_blur: self.backdrop_blur,
+                    buffer: Buffer::new(self.style.clone()),
+                    _phantom: std::marker::PhantomData,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -79,6 +118,7 @@ impl<T: Send + Sync + 'static> Dom<T> for Plain<T> {
 pub struct PlainNode<T> {
     style: Vec<Arc<dyn Style>>,
     size: [Size; 2],
+    backdrop_blur: Option<f32>,
     buffer: Buffer,
     _phantom: std::marker::PhantomData<T>,
 }
@@ -96,6 +136,7 @@ impl<T: Send + Sync + 'static> Widget<Plain<T>, T, ()> for PlainNode<T> {
         }
         self.style = dom.style.clone();
         self.size = dom.size.clone();
+        self.backdrop_blur = dom.backdrop_blur;
         self.buffer = Buffer::new(self.style.clone());
 
         dom.content
@@ -174,6 +215,9 @@ impl<T: Send + Sync + 'static> Widget<Plain<T>, T, ()> for PlainNode<T> {
         let size = arrangement.size;
 
         let mut render_node = RenderNode::new();
+        if let Some(radius) = self.backdrop_blur {
+            render_node = render_node.with_backdrop_blur(size, radius);
+        }
 
         if size[0] > 0.0 && size[1] > 0.0 {
             let texture_size = [size[0].ceil() as u32, size[1].ceil() as u32];
@@ -194,12 +238,20 @@ impl<T: Send + Sync + 'static> Widget<Plain<T>, T, ()> for PlainNode<T> {
                 ctx.queue().submit(Some(encoder.finish()));
                 render_node =
                     render_node.with_texture(style_region, size, nalgebra::Matrix4::identity());
+                if self.style.is_opaque(size, ctx) {
+                    render_node = render_node.with_opaque_texture();
+                }
             }
         }
 
-        let child_node = child.render(background, ctx);
+        let child_offset = [arrangement.affine[(0, 3)], arrangement.affine[(1, 3)]];
+        let child_node = child.render(background.translate(child_offset), ctx);
         render_node.push_child(child_node, arrangement.affine);
 
         render_node
     }
+
+    fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        self.style.prepare(bounds, ctx)
+    }
 }
@@ -0,0 +1,405 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::style::solid_box::SolidBox;
+use crate::style::style_node;
+use crate::style::text::{LineHeight, Sentence, SentenceBounds, TextDesc};
+
+use matcha_core::context::WidgetContext;
+use matcha_core::{
+    cursor::CursorIcon,
+    device_input::DeviceInput,
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, PrepareFuture, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use renderer::render_node::RenderNode;
+
+// MARK: DOM
+
+/// Text made of differently styled sentences, some of which are links.
+///
+/// Hovering a link shows the hand pointer and underlines it; clicking it emits its payload.
+pub struct RichText<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    desc: TextDesc,
+    // payload of each sentence in `desc` that is a link
+    links: Vec<Option<T>>,
+}
+
+impl<T> Default for RichText<T> {
+    fn default() -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            desc: TextDesc::new(vec![]),
+            links: vec![],
+        }
+    }
+}
+
+impl<T> RichText<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Appends plain text.
+    pub fn span(mut self, sentence: Sentence) -> Self {
+        self.desc.push_element(sentence);
+        self.links.push(None);
+        self
+    }
+
+    /// Appends a link that emits `payload` when clicked.
+    pub fn link(mut self, sentence: Sentence, payload: T) -> Self {
+        self.desc.push_element(sentence);
+        self.links.push(Some(payload));
+        self
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.desc = self.desc.font_size(size);
+        self
+    }
+
+    pub fn line_height(mut self, height: impl Into<LineHeight>) -> Self {
+        self.desc = self.desc.line_height(height);
+        self
+    }
+
+    pub fn letter_spacing(mut self, spacing: f32) -> Self {
+        self.desc = self.desc.letter_spacing(spacing);
+        self
+    }
+
+    pub fn word_spacing(mut self, spacing: f32) -> Self {
+        self.desc = self.desc.word_spacing(spacing);
+        self
+    }
+
+    pub fn paragraph_spacing(mut self, spacing: f32) -> Self {
+        self.desc = self.desc.paragraph_spacing(spacing);
+        self
+    }
+
+    pub fn tab_width(mut self, spaces: u16) -> Self {
+        self.desc = self.desc.tab_width(spaces);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Clone + Send + Sync + 'static> Dom<T> for RichText<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                RichTextNode {
+                    style: crate::style::text::Text::new(&self.desc),
+                    links: self.links.clone(),
+                    link_bounds: Mutex::new(None),
+                    hovered: None,
+                    pressed: None,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct RichTextNode<T> {
+    style: crate::style::text::Text,
+    links: Vec<Option<T>>,
+    // where the links are laid out, for the bounds they were laid out in
+    link_bounds: Mutex<Option<([f32; 2], Arc<[SentenceBounds]>)>>,
+    // link sentence under the pointer
+    hovered: Option<usize>,
+    // link sentence the primary button was pressed on
+    pressed: Option<usize>,
+}
+
+impl<T: Clone> RichTextNode<T> {
+    /// Lays the text out only when `bounds` changed since the last call.
+    fn link_bounds(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Arc<[SentenceBounds]> {
+        let mut cached = self.link_bounds.lock();
+        if let Some((cached_bounds, links)) = &*cached
+            && *cached_bounds == bounds
+        {
+            return links.clone();
+        }
+        let links: Arc<[SentenceBounds]> = self
+            .style
+            .sentence_bounds(&Constraints::from_boundary(bounds), ctx)
+            .into_iter()
+            .filter(|b| self.links.get(b.sentence).is_some_and(Option::is_some))
+            .collect();
+        *cached = Some((bounds, links.clone()));
+        links
+    }
+
+    fn link_at(&self, bounds: [f32; 2], position: [f32; 2], ctx: &WidgetContext) -> Option<usize> {
+        self.link_bounds(bounds, ctx)
+            .iter()
+            .find(|b| b.contains(position))
+            .map(|b| b.sentence)
+    }
+
+    /// Moves the hover to `link`. Returns whether it changed.
+    fn hover(&mut self, link: Option<usize>) -> bool {
+        let changed = link != self.hovered;
+        self.hovered = link;
+        changed
+    }
+
+    /// Ends a click on `link`, returning the payload when it started on the same link.
+    fn release(&mut self, link: Option<usize>) -> Option<T> {
+        let pressed = self.pressed.take()?;
+        (Some(pressed) == link)
+            .then(|| self.links[pressed].clone())
+            .flatten()
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Widget<RichText<T>, T, ()> for RichTextNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a RichText<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        if !self.style.eq_desc(&dom.desc) {
+            self.style = crate::style::text::Text::new(&dom.desc);
+            *self.link_bounds.get_mut() = None;
+            if let Some(handle) = cache_invalidator {
+                handle.relayout_next_frame();
+            }
+        }
+        self.links = dom.links.clone();
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let rect = self.style.required_region(constraints, ctx);
+        if let Some(rect) = rect {
+            [rect.width(), rect.height()]
+        } else {
+            [0.0, 0.0]
+        }
+    }
+
+    fn baseline(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        self.style.first_baseline(constraints, ctx)
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let link = event
+            .mouse_position()
+            .and_then(|position| self.link_at(bounds, position, ctx));
+
+        if self.hover(link) {
+            // only reset the pointer when leaving a link, other widgets may have changed it
+            ctx.set_cursor_icon(if link.is_some() {
+                CursorIcon::Pointer
+            } else {
+                CursorIcon::Default
+            });
+            cache_invalidator.redraw_next_frame();
+        }
+
+        if event.on_click(|_| ()).is_some() {
+            self.pressed = link;
+        }
+        if event.on_click_released(|_| ()).is_some() {
+            return self.release(link);
+        }
+
+        None
+    }
+
+    fn is_inside(
+        &self,
+        bounds: [f32; 2],
+        position: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        ctx: &WidgetContext,
+    ) -> bool {
+        self.style.is_inside(position, bounds, ctx)
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let size = <Self as Widget<RichText<T>, T, ()>>::measure(
+            self,
+            &Constraints::from_boundary(bounds),
+            &[],
+            ctx,
+        );
+        let mut render_node = style_node(&self.style, size, ctx).unwrap_or_default();
+
+        // underline the hovered link
+        if let Some(hovered) = self.hovered {
+            let thickness = (self.style.font_size / 14.0).max(1.0);
+            let color = self.style.texts[hovered].color;
+            for link in self
+                .link_bounds(bounds, ctx)
+                .iter()
+                .filter(|b| b.sentence == hovered)
+            {
+                let underline_size = [link.rect[1][0] - link.rect[0][0], thickness];
+                if let Some(underline) = style_node(&SolidBox { color }, underline_size, ctx) {
+                    render_node.push_child(
+                        underline,
+                        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+                            link.rect[0][0],
+                            link.baseline + thickness,
+                            0.0,
+                        )),
+                    );
+                }
+            }
+        }
+
+        render_node
+    }
+
+    fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        self.style.prepare(bounds, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matcha_core::test_kit::TestContext;
+
+    use super::*;
+
+    const BOUNDS: [f32; 2] = [200.0, 40.0];
+
+    /// "see [docs] or [examples]" with the second link wrapped onto the next line, laid out
+    /// in `BOUNDS`.
+    fn node() -> RichTextNode<&'static str> {
+        let dom = RichText::new()
+            .span(Sentence::new("see "))
+            .link(Sentence::new("docs"), "docs")
+            .span(Sentence::new(" or "))
+            .link(Sentence::new("examples"), "examples");
+        let link = |sentence, rect| SentenceBounds {
+            sentence,
+            rect,
+            baseline: rect[1][1] - 4.0,
+        };
+        let links: Arc<[SentenceBounds]> = Arc::new([
+            link(1, [[30.0, 0.0], [60.0, 20.0]]),
+            link(3, [[90.0, 0.0], [200.0, 20.0]]),
+            link(3, [[0.0, 20.0], [40.0, 40.0]]),
+        ]);
+        RichTextNode {
+            style: crate::style::text::Text::new(&dom.desc),
+            links: dom.links,
+            link_bounds: Mutex::new(Some((BOUNDS, links))),
+            hovered: None,
+            pressed: None,
+        }
+    }
+
+    #[test]
+    fn finds_the_link_under_the_pointer() {
+        let test = TestContext::builder().build();
+        let ctx = test.widget_context();
+        let node = node();
+
+        assert_eq!(node.link_at(BOUNDS, [45.0, 10.0], ctx), Some(1));
+        assert_eq!(node.link_at(BOUNDS, [100.0, 10.0], ctx), Some(3));
+        // the wrapped part of a link is part of it too
+        assert_eq!(node.link_at(BOUNDS, [10.0, 30.0], ctx), Some(3));
+        // plain text and empty space are not
+        assert_eq!(node.link_at(BOUNDS, [10.0, 10.0], ctx), None);
+        assert_eq!(node.link_at(BOUNDS, [80.0, 30.0], ctx), None);
+    }
+
+    #[test]
+    fn hovering_a_link_marks_it() {
+        let mut node = node();
+        assert!(node.hover(Some(1)));
+        assert_eq!(node.hovered, Some(1));
+        assert!(!node.hover(Some(1)));
+        assert!(node.hover(None));
+        assert_eq!(node.hovered, None);
+    }
+
+    #[test]
+    fn clicking_a_link_emits_its_payload() {
+        let mut node = node();
+        node.pressed = Some(3);
+        assert_eq!(node.release(Some(3)), Some("examples"));
+        // the click ended with the release
+        assert_eq!(node.release(Some(3)), None);
+    }
+
+    #[test]
+    fn releasing_over_another_link_emits_nothing() {
+        let mut node = node();
+        node.pressed = Some(1);
+        assert_eq!(node.release(Some(3)), None);
+        node.pressed = Some(1);
+        assert_eq!(node.release(None), None);
+        // releasing without a press on a link
+        assert_eq!(node.release(Some(1)), None);
+    }
+}
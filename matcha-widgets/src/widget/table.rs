@@ -0,0 +1,956 @@
+use std::sync::Arc;
+
+use matcha_core::{
+    color::Color,
+    context::WidgetContext,
+    device_input::{DeviceInput, DeviceInputData, ElementState, MouseInput, MouseLogicalButton},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidget, AnyWidgetFrame, Background, ChildFrameLinker, Dom, LayoutStyle,
+        OwnedChildrenUpdate, PrepareFuture, Widget, WidgetFrame, widget::InvalidationHandle,
+    },
+};
+use nalgebra::Matrix4;
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+
+use crate::{
+    layout::virtual_rows::{self, CellBuilder, VirtualRows},
+    style::{
+        Style, drawn_node,
+        solid_box::SolidBox,
+        text::{Sentence, Text, TextDesc, TextWeight},
+    },
+};
+
+const HEADER_HEIGHT: f32 = 32.0;
+const FONT_SIZE: f32 = 14.0;
+const LINE_HEIGHT: f32 = 20.0;
+const CELL_PADDING_X: f32 = 10.0;
+/// distance from a column's right edge within which a header press starts a resize.
+const RESIZE_GRAB: f32 = 4.0;
+const SORT_MARKER_WIDTH: f32 = 8.0;
+const SORT_MARKER_GAP: f32 = 6.0;
+const DEFAULT_MIN_COLUMN_WIDTH: f32 = 24.0;
+
+const HEADER_COLOR: Color = Color::RgbaF32 {
+    r: 0.93,
+    g: 0.93,
+    b: 0.93,
+    a: 1.0,
+};
+const HOVER_HEADER_COLOR: Color = Color::RgbaF32 {
+    r: 0.88,
+    g: 0.88,
+    b: 0.88,
+    a: 1.0,
+};
+const BORDER_COLOR: Color = Color::RgbaF32 {
+    r: 0.78,
+    g: 0.78,
+    b: 0.78,
+    a: 1.0,
+};
+const ACCENT_COLOR: Color = Color::RgbaF32 {
+    r: 0.20,
+    g: 0.47,
+    b: 0.90,
+    a: 1.0,
+};
+const TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 1.0,
+};
+
+type SortHandler<T> = Arc<dyn Fn(usize, SortOrder) -> T + Send + Sync>;
+type ResizeHandler<T> = Arc<dyn Fn(usize, f32) -> T + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    pub fn reversed(self) -> Self {
+        match self {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
+        }
+    }
+}
+
+/// How the width of a [`TableColumn`] is decided.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnWidth {
+    /// A fixed width in pixels.
+    Fixed(f32),
+    /// A share of the width left over by fixed columns, weighted against the other
+    /// flexible columns.
+    Flex(f32),
+}
+
+// MARK: DOM
+
+/// A column of a [`Table`].
+pub struct TableColumn<T> {
+    title: String,
+    width: ColumnWidth,
+    min_width: f32,
+    sortable: bool,
+    cell: CellBuilder<T>,
+}
+
+impl<T: 'static> TableColumn<T> {
+    /// A column whose cell in row `index` is built by `cell(index)`.
+    pub fn new<D: Dom<T>>(
+        title: &str,
+        width: ColumnWidth,
+        cell: impl Fn(usize) -> D + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            title: title.to_string(),
+            width,
+            min_width: DEFAULT_MIN_COLUMN_WIDTH,
+            sortable: true,
+            cell: Arc::new(move |index| Box::new(cell(index)) as Box<dyn Dom<T>>),
+        }
+    }
+
+    /// Smallest width the column gets from layout or resizing. Default is 24px.
+    pub fn min_width(mut self, min_width: f32) -> Self {
+        self.min_width = min_width.max(0.0);
+        self
+    }
+
+    /// Whether clicking the header emits a sort message. Default is `true`.
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+}
+
+/// A grid of rows under a header row that stays in place while the rows scroll.
+///
+/// Like [`LazyColumn`](crate::layout::lazy_column::LazyColumn), every row has the same height
+/// and only the cells of the rows in view are built, so tables with many rows stay cheap.
+/// Cells of rows that scroll out are updated in the background to show the rows next to
+/// the kept ones. Cells are rebuilt from their column's builder whenever the table is updated.
+///
+/// Sorting is done by the model: clicking the header of a sortable column emits the
+/// [`on_sort`](Table::on_sort) message, and the view passes the current sort back with
+/// [`sorted_by`](Table::sorted_by) so the header can show it. Columns are resized by dragging
+/// the right edge of their header; the new width is kept by the table and reported with
+/// [`on_column_resize`](Table::on_column_resize) when the drag ends.
+pub struct Table<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    row_count: usize,
+    row_height: f32,
+    overscan: usize,
+    columns: Vec<TableColumn<T>>,
+    sorted_by: Option<(usize, SortOrder)>,
+    on_sort: Option<SortHandler<T>>,
+    on_column_resize: Option<ResizeHandler<T>>,
+}
+
+impl<T: Send + Sync + 'static> Table<T> {
+    pub fn new(row_count: usize, row_height: f32) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            row_count,
+            row_height: row_height.max(0.0),
+            overscan: 2,
+            columns: Vec::new(),
+            sorted_by: None,
+            on_sort: None,
+            on_column_resize: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn column(mut self, column: TableColumn<T>) -> Self {
+        self.columns.push(column);
+        self
+    }
+
+    /// Number of rows kept alive above and below the visible ones. Default is 2.
+    pub fn overscan(mut self, overscan: usize) -> Self {
+        self.overscan = overscan;
+        self
+    }
+
+    /// Column the rows are currently sorted by, shown in the header.
+    pub fn sorted_by(mut self, column: usize, order: SortOrder) -> Self {
+        self.sorted_by = Some((column, order));
+        self
+    }
+
+    /// Message emitted when the header of a sortable column is clicked, with the column's
+    /// index and the order requested: the reverse of the current order if the rows are
+    /// already sorted by that column, ascending otherwise.
+    pub fn on_sort(mut self, f: impl Fn(usize, SortOrder) -> T + Send + Sync + 'static) -> Self {
+        self.on_sort = Some(Arc::new(f));
+        self
+    }
+
+    /// Message emitted with a column's index and new width when a resize drag ends.
+    pub fn on_column_resize(mut self, f: impl Fn(usize, f32) -> T + Send + Sync + 'static) -> Self {
+        self.on_column_resize = Some(Arc::new(f));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for Table<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(self.label.clone(), vec![], vec![], TableNode::new(self))
+                .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+struct ColumnSpec {
+    title: String,
+    width: ColumnWidth,
+    min_width: f32,
+    sortable: bool,
+}
+
+struct HeaderCell {
+    text: Text,
+    text_size: [f32; 2],
+}
+
+/// What the pointer is over in the header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeaderHit {
+    Title(usize),
+    /// the right edge of a column.
+    Edge(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeaderPress {
+    Title(usize),
+    Resize {
+        column: usize,
+        start_x: f32,
+        start_width: f32,
+    },
+}
+
+pub struct TableNode<T> {
+    row_count: usize,
+    row_height: f32,
+    overscan: usize,
+    columns: Vec<ColumnSpec>,
+    sorted_by: Option<(usize, SortOrder)>,
+    on_sort: Option<SortHandler<T>>,
+    on_column_resize: Option<ResizeHandler<T>>,
+    /// widths set by dragging, overriding the columns' width policy.
+    resized: Vec<Option<f32>>,
+    /// shaped header titles; rebuilt when the titles change.
+    header: Mutex<Option<Vec<HeaderCell>>>,
+    scroll_offset: f32,
+    /// one cell per column in every row.
+    rows: VirtualRows<T>,
+    hovered: Option<HeaderHit>,
+    pressed: Option<HeaderPress>,
+}
+
+impl<T: Send + Sync + 'static> TableNode<T> {
+    fn new(dom: &Table<T>) -> Self {
+        let mut node = TableNode {
+            row_count: 0,
+            row_height: 0.0,
+            overscan: 0,
+            columns: Vec::new(),
+            sorted_by: None,
+            on_sort: None,
+            on_column_resize: None,
+            resized: Vec::new(),
+            header: Mutex::new(None),
+            scroll_offset: 0.0,
+            rows: VirtualRows::new(),
+            hovered: None,
+            pressed: None,
+        };
+        node.sync(dom);
+        node
+    }
+
+    /// Takes over the settings of `dom`.
+    fn sync(&mut self, dom: &Table<T>) {
+        let same_columns = self.columns.len() == dom.columns.len();
+        let same_titles = same_columns
+            && self
+                .columns
+                .iter()
+                .zip(&dom.columns)
+                .all(|(old, new)| old.title == new.title);
+        if !same_titles {
+            *self.header.get_mut() = None;
+        }
+        if !same_columns {
+            // the rows start over with the new columns
+            self.resized = vec![None; dom.columns.len()];
+            self.hovered = None;
+            self.pressed = None;
+        }
+        self.rows.set_builders(
+            dom.columns
+                .iter()
+                .map(|column| column.cell.clone())
+                .collect(),
+            dom.row_count,
+        );
+
+        self.columns = dom
+            .columns
+            .iter()
+            .map(|column| ColumnSpec {
+                title: column.title.clone(),
+                width: column.width,
+                min_width: column.min_width,
+                sortable: column.sortable,
+            })
+            .collect();
+        self.row_count = dom.row_count;
+        self.row_height = dom.row_height;
+        self.overscan = dom.overscan;
+        self.sorted_by = dom.sorted_by;
+        self.on_sort = dom.on_sort.clone();
+        self.on_column_resize = dom.on_column_resize.clone();
+    }
+
+    /// Widths of the columns when the table is `width` wide.
+    fn column_widths(&self, width: f32) -> Vec<f32> {
+        let fixed = |(column, resized): (&ColumnSpec, &Option<f32>)| match (*resized, column.width)
+        {
+            (Some(w), _) | (None, ColumnWidth::Fixed(w)) => Some(w.max(column.min_width)),
+            (None, ColumnWidth::Flex(_)) => None,
+        };
+        let fixed_total: f32 = self
+            .columns
+            .iter()
+            .zip(&self.resized)
+            .filter_map(fixed)
+            .sum();
+        let flex_total: f32 = self
+            .columns
+            .iter()
+            .zip(&self.resized)
+            .filter(|pair| fixed(*pair).is_none())
+            .map(|(column, _)| match column.width {
+                ColumnWidth::Flex(weight) => weight.max(0.0),
+                ColumnWidth::Fixed(_) => 0.0,
+            })
+            .sum();
+        let remaining = (width - fixed_total).max(0.0);
+
+        self.columns
+            .iter()
+            .zip(&self.resized)
+            .map(|pair| {
+                fixed(pair).unwrap_or_else(|| {
+                    let (column, _) = pair;
+                    let weight = match column.width {
+                        ColumnWidth::Flex(weight) => weight.max(0.0),
+                        ColumnWidth::Fixed(_) => 0.0,
+                    };
+                    let share = if flex_total > 0.0 {
+                        remaining * weight / flex_total
+                    } else {
+                        0.0
+                    };
+                    share.floor().max(column.min_width)
+                })
+            })
+            .collect()
+    }
+
+    /// Left edges of the columns for the given widths.
+    fn column_lefts(widths: &[f32]) -> Vec<f32> {
+        widths
+            .iter()
+            .scan(0.0, |left, width| {
+                let this = *left;
+                *left += width;
+                Some(this)
+            })
+            .collect()
+    }
+
+    fn viewport_height(&self, bounds: [f32; 2]) -> f32 {
+        (bounds[1] - HEADER_HEIGHT).max(0.0)
+    }
+
+    fn max_scroll_offset(&self, viewport_height: f32) -> f32 {
+        (self.row_count as f32 * self.row_height - viewport_height).max(0.0)
+    }
+
+    /// The scroll offset limited to the current rows, which may have shrunk since scrolling.
+    fn clamped_scroll_offset(&self, viewport_height: f32) -> f32 {
+        self.scroll_offset
+            .min(self.max_scroll_offset(viewport_height))
+    }
+
+    /// Indices of the rows that intersect the viewport, extended by `overscan`.
+    fn visible_range(&self, viewport_height: f32) -> std::ops::Range<usize> {
+        if self.columns.is_empty() {
+            return 0..0;
+        }
+
+        virtual_rows::visible_range(
+            self.row_count,
+            self.row_height,
+            self.clamped_scroll_offset(viewport_height),
+            viewport_height,
+            self.overscan,
+        )
+    }
+
+    /// Brings the built rows in line with the visible range and lays out their cells.
+    fn sync_rows(&self, bounds: [f32; 2], ctx: &WidgetContext) {
+        let viewport_height = self.viewport_height(bounds);
+        let widths = self.column_widths(bounds[0]);
+        let lefts = Self::column_lefts(&widths);
+        let offset = self.clamped_scroll_offset(viewport_height);
+        self.rows.layout(
+            self.visible_range(viewport_height),
+            |index, column| {
+                let y = HEADER_HEIGHT + index as f32 * self.row_height - offset;
+                Arrangement::new(
+                    [widths[column], self.row_height],
+                    Matrix4::new_translation(&nalgebra::Vector3::new(lefts[column], y, 0.0)),
+                )
+            },
+            ctx,
+        );
+    }
+
+    fn with_header<R>(&self, ctx: &WidgetContext, f: impl FnOnce(&[HeaderCell]) -> R) -> R {
+        let mut header = self.header.lock();
+        let cells = header.get_or_insert_with(|| {
+            self.columns
+                .iter()
+                .map(|column| {
+                    let text = Text::new(
+                        &TextDesc::new(vec![
+                            Sentence::new(&column.title)
+                                .color(TEXT_COLOR)
+                                .weight(TextWeight::BOLD),
+                        ])
+                        .font_size(FONT_SIZE)
+                        .line_height(LINE_HEIGHT),
+                    );
+                    let text_size = text
+                        .required_region(&Constraints::new([0.0, 4096.0], [0.0, 4096.0]), ctx)
+                        .map_or([0.0, 0.0], |rect| [rect.width(), rect.height()]);
+                    HeaderCell { text, text_size }
+                })
+                .collect()
+        });
+        f(cells)
+    }
+
+    fn hit_header(&self, position: [f32; 2], bounds: [f32; 2]) -> Option<HeaderHit> {
+        if !(0.0..HEADER_HEIGHT).contains(&position[1]) {
+            return None;
+        }
+        let widths = self.column_widths(bounds[0]);
+        let lefts = Self::column_lefts(&widths);
+        // edges take precedence over the titles next to them
+        let edge = lefts
+            .iter()
+            .zip(&widths)
+            .position(|(left, width)| (position[0] - (left + width)).abs() <= RESIZE_GRAB);
+        if let Some(column) = edge {
+            return Some(HeaderHit::Edge(column));
+        }
+        lefts
+            .iter()
+            .zip(&widths)
+            .position(|(left, width)| *left <= position[0] && position[0] < left + width)
+            .map(HeaderHit::Title)
+    }
+
+    fn header_input(
+        &mut self,
+        event: &DeviceInput,
+        position: [f32; 2],
+        bounds: [f32; 2],
+        cache_invalidator: &InvalidationHandle,
+    ) -> Option<T> {
+        let hit = self.hit_header(position, bounds);
+        if self.pressed.is_none() && hit != self.hovered {
+            self.hovered = hit;
+            cache_invalidator.redraw_next_frame();
+        }
+
+        match event.event() {
+            DeviceInputData::MouseInput {
+                event:
+                    Some(MouseInput::Click {
+                        click_state,
+                        button: MouseLogicalButton::Primary,
+                    }),
+                ..
+            } => match click_state {
+                ElementState::Pressed(_) => {
+                    self.pressed = match hit {
+                        Some(HeaderHit::Title(column)) => Some(HeaderPress::Title(column)),
+                        Some(HeaderHit::Edge(column)) => Some(HeaderPress::Resize {
+                            column,
+                            start_x: position[0],
+                            start_width: self.column_widths(bounds[0])[column],
+                        }),
+                        None => None,
+                    };
+                    None
+                }
+                ElementState::Released(_) => match self.pressed.take()? {
+                    HeaderPress::Title(column) if hit == Some(HeaderHit::Title(column)) => {
+                        if !self.columns[column].sortable {
+                            return None;
+                        }
+                        let order = match self.sorted_by {
+                            Some((sorted, order)) if sorted == column => order.reversed(),
+                            _ => SortOrder::Ascending,
+                        };
+                        self.on_sort.as_ref().map(|f| f(column, order))
+                    }
+                    HeaderPress::Resize { column, .. } => {
+                        cache_invalidator.redraw_next_frame();
+                        let width = self.resized[column]?;
+                        self.on_column_resize.as_ref().map(|f| f(column, width))
+                    }
+                    HeaderPress::Title(_) => None,
+                },
+                _ => None,
+            },
+            DeviceInputData::MouseInput {
+                dragging_from_primary: Some(_),
+                ..
+            } => {
+                if let Some(HeaderPress::Resize {
+                    column,
+                    start_x,
+                    start_width,
+                }) = self.pressed
+                {
+                    let width = (start_width + position[0] - start_x)
+                        .round()
+                        .max(self.columns[column].min_width);
+                    if self.resized[column] != Some(width) {
+                        self.resized[column] = Some(width);
+                        cache_invalidator.relayout_next_frame();
+                    }
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn render_header(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<RenderNode> {
+        let size = [bounds[0], HEADER_HEIGHT];
+        drawn_node(size, ctx, |encoder, region| {
+            SolidBox {
+                color: HEADER_COLOR,
+            }
+            .draw(encoder, region, size, [0.0, 0.0], ctx);
+
+            let widths = self.column_widths(bounds[0]);
+            let lefts = Self::column_lefts(&widths);
+            let active_edge = match (self.pressed, self.hovered) {
+                (Some(HeaderPress::Resize { column, .. }), _) => Some(column),
+                (None, Some(HeaderHit::Edge(column))) => Some(column),
+                _ => None,
+            };
+
+            self.with_header(ctx, |cells| {
+                for (column, ((cell, left), width)) in
+                    cells.iter().zip(&lefts).zip(&widths).enumerate()
+                {
+                    // columns that do not fit are not drawn
+                    if left + width > size[0] {
+                        break;
+                    }
+
+                    if self.hovered == Some(HeaderHit::Title(column))
+                        && self.columns[column].sortable
+                        && self.pressed.is_none()
+                    {
+                        SolidBox {
+                            color: HOVER_HEADER_COLOR,
+                        }
+                        .draw(
+                            encoder,
+                            region,
+                            [*width, HEADER_HEIGHT],
+                            [*left, 0.0],
+                            ctx,
+                        );
+                    }
+
+                    let sort = self
+                        .sorted_by
+                        .and_then(|(sorted, order)| (sorted == column).then_some(order));
+                    let marker_space = if sort.is_some() {
+                        SORT_MARKER_GAP + SORT_MARKER_WIDTH
+                    } else {
+                        0.0
+                    };
+                    let text_width =
+                        cell.text_size[0].min(width - 2.0 * CELL_PADDING_X - marker_space);
+                    if text_width > 0.0 {
+                        cell.text.draw(
+                            encoder,
+                            region,
+                            [text_width, cell.text_size[1]],
+                            [
+                                left + CELL_PADDING_X,
+                                ((HEADER_HEIGHT - cell.text_size[1]) / 2.0).round(),
+                            ],
+                            ctx,
+                        );
+                    }
+
+                    if let Some(order) = sort {
+                        // a small triangle made of bars, pointing up for ascending order
+                        let x = left + width - CELL_PADDING_X - SORT_MARKER_WIDTH;
+                        let top = ((HEADER_HEIGHT - SORT_MARKER_WIDTH / 2.0) / 2.0).round();
+                        for step in 0..4 {
+                            let bar_width = SORT_MARKER_WIDTH - 2.0 * step as f32;
+                            let y = match order {
+                                SortOrder::Ascending => top + 3.0 - step as f32,
+                                SortOrder::Descending => top + step as f32,
+                            };
+                            SolidBox {
+                                color: ACCENT_COLOR,
+                            }
+                            .draw(
+                                encoder,
+                                region,
+                                [bar_width, 1.0],
+                                [x + step as f32, y],
+                                ctx,
+                            );
+                        }
+                    }
+
+                    let (edge_color, edge_width) = if active_edge == Some(column) {
+                        (ACCENT_COLOR, 2.0)
+                    } else {
+                        (BORDER_COLOR, 1.0)
+                    };
+                    SolidBox { color: edge_color }.draw(
+                        encoder,
+                        region,
+                        [edge_width, HEADER_HEIGHT],
+                        [(left + width - edge_width).max(0.0), 0.0],
+                        ctx,
+                    );
+                }
+            });
+
+            SolidBox {
+                color: BORDER_COLOR,
+            }
+            .draw(
+                encoder,
+                region,
+                [size[0], 1.0],
+                [0.0, HEADER_HEIGHT - 1.0],
+                ctx,
+            );
+        })
+    }
+}
+
+/// The part of a cell below the header and inside the table, or `None` when none of it is.
+fn cell_visible_rect(
+    arrangement: &Arrangement,
+    bounds: [f32; 2],
+    row_height: f32,
+) -> Option<[[f32; 2]; 2]> {
+    let left = arrangement.affine[(0, 3)];
+    let top = arrangement.affine[(1, 3)];
+    let size = arrangement.size;
+    let visible_rect = [
+        [(-left).max(0.0), (HEADER_HEIGHT - top).max(0.0)],
+        [
+            (bounds[0] - left).min(size[0]),
+            (bounds[1] - top).min(row_height),
+        ],
+    ];
+    (visible_rect[0][0] < visible_rect[1][0] && visible_rect[0][1] < visible_rect[1][1])
+        .then_some(visible_rect)
+}
+
+impl<T: Send + Sync + 'static> Widget<Table<T>, T, ()> for TableNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a Table<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.sync(dom);
+
+        if let Some(handle) = cache_invalidator {
+            handle.relayout_next_frame();
+        }
+
+        vec![]
+    }
+
+    fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+        self.rows.link(linker);
+    }
+
+    fn update_owned_children(&mut self) -> Option<OwnedChildrenUpdate<'_>> {
+        self.rows.update_live()
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let position = event.mouse_position();
+        let inside = position.is_some_and(|position| {
+            0.0 <= position[0]
+                && position[0] <= bounds[0]
+                && 0.0 <= position[1]
+                && position[1] <= bounds[1]
+        });
+
+        if inside && let Some(delta) = event.on_scroll(|delta| delta) {
+            let viewport_height = self.viewport_height(bounds);
+            let offset = (self.clamped_scroll_offset(viewport_height) - delta[1]).max(0.0);
+            if offset != self.scroll_offset {
+                self.scroll_offset = offset;
+                cache_invalidator.relayout_next_frame();
+            }
+            return None;
+        }
+
+        if matches!(event.event(), DeviceInputData::MouseInput { .. })
+            && let Some(position) = position
+        {
+            let message = self.header_input(event, position, bounds, &cache_invalidator);
+            // the header and drags that started on it do not reach the cells
+            if message.is_some() || position[1] < HEADER_HEIGHT || self.pressed.is_some() {
+                return message;
+            }
+        }
+
+        self.rows.device_input(event, ctx)
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        // flexible columns take whatever is left, so only their minimum counts here
+        let width: f32 = self.column_widths(0.0).iter().sum();
+
+        [
+            width.clamp(constraints.min_width(), constraints.max_width()),
+            (HEADER_HEIGHT + self.row_count as f32 * self.row_height)
+                .clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        self.sync_rows(bounds, ctx);
+
+        // cells are owned by this widget, not by the frame
+        vec![]
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        for row in self.rows.live().iter() {
+            for (cell, arrangement) in &row.cells {
+                // rows hidden behind the header or below the table are skipped
+                let top = arrangement.affine[(1, 3)];
+                if top + self.row_height <= HEADER_HEIGHT || top >= bounds[1] {
+                    continue;
+                }
+                render_node.push_child(cell.render(background, ctx), arrangement.affine);
+            }
+        }
+
+        // the header is drawn last so that it stays on top of scrolled rows
+        if let Some(header) = self.render_header(bounds, ctx) {
+            render_node.push_child(header, Matrix4::identity());
+        }
+
+        render_node
+    }
+
+    fn update_gpu_device(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.rows.update_gpu_device(device, queue);
+    }
+
+    fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        self.rows.prepare(
+            self.visible_range(self.viewport_height(bounds)),
+            self.row_count,
+            self.overscan,
+            |arrangement| cell_visible_rect(arrangement, bounds, self.row_height),
+            ctx,
+        )
+    }
+
+    fn update_lifecycle(&mut self, bounds: [f32; 2], ctx: &WidgetContext) {
+        self.rows.update_lifecycle(
+            |arrangement| cell_visible_rect(arrangement, bounds, self.row_height),
+            ctx,
+        );
+    }
+
+    fn on_visibility_changed(&mut self, visible: bool, ctx: &WidgetContext) {
+        // neither `update_lifecycle` nor `prepare` is called while the table is hidden, so hide
+        // the cells here.
+        if !visible {
+            self.rows.hide(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::layout::space::Space;
+
+    fn column(title: &str, width: ColumnWidth) -> TableColumn<()> {
+        TableColumn::new(title, width, |_| *Space::new(None))
+    }
+
+    /// A fixed 100px column and flexible columns weighted 1 and 3.
+    fn table() -> TableNode<()> {
+        TableNode::new(
+            &Table::new(100, 20.0)
+                .column(column("Name", ColumnWidth::Fixed(100.0)))
+                .column(column("Size", ColumnWidth::Flex(1.0)))
+                .column(column("Path", ColumnWidth::Flex(3.0))),
+        )
+    }
+
+    #[test]
+    fn test_column_widths() {
+        let mut node = table();
+        // flexible columns share what the fixed one leaves
+        assert_eq!(node.column_widths(500.0), [100.0, 100.0, 300.0]);
+        // and do not shrink below their minimum
+        assert_eq!(node.column_widths(150.0), [100.0, 24.0, 37.0]);
+        assert_eq!(node.column_widths(0.0), [100.0, 24.0, 24.0]);
+
+        // a resized column is fixed at its new width
+        node.resized[2] = Some(50.0);
+        assert_eq!(node.column_widths(500.0), [100.0, 350.0, 50.0]);
+        node.resized[2] = Some(10.0);
+        assert_eq!(node.column_widths(500.0), [100.0, 376.0, 24.0]);
+
+        let node = TableNode::new(
+            &Table::new(1, 20.0)
+                .column(column("Narrow", ColumnWidth::Fixed(10.0)).min_width(40.0))
+                .column(column("Rest", ColumnWidth::Flex(1.0)).min_width(0.0)),
+        );
+        assert_eq!(node.column_widths(30.0), [40.0, 0.0]);
+    }
+
+    #[test]
+    fn test_visible_range() {
+        let mut node = table();
+        assert_eq!(node.visible_range(60.0), 0..5);
+
+        node.scroll_offset = 100.0;
+        assert_eq!(node.visible_range(60.0), 3..10);
+
+        // clamped to the last row
+        node.scroll_offset = 10_000.0;
+        assert_eq!(node.visible_range(60.0), 95..100);
+
+        let node = TableNode::new(&Table::<()>::new(100, 20.0));
+        assert_eq!(node.visible_range(60.0), 0..0);
+    }
+
+    #[test]
+    fn test_hit_header() {
+        let node = table();
+        let bounds = [500.0, 300.0];
+
+        assert_eq!(
+            node.hit_header([50.0, 10.0], bounds),
+            Some(HeaderHit::Title(0))
+        );
+        assert_eq!(
+            node.hit_header([95.0, 10.0], bounds),
+            Some(HeaderHit::Title(0))
+        );
+        // within the grab distance on either side of the edge
+        assert_eq!(
+            node.hit_header([96.0, 10.0], bounds),
+            Some(HeaderHit::Edge(0))
+        );
+        assert_eq!(
+            node.hit_header([104.0, 10.0], bounds),
+            Some(HeaderHit::Edge(0))
+        );
+        assert_eq!(
+            node.hit_header([105.0, 10.0], bounds),
+            Some(HeaderHit::Title(1))
+        );
+        // the edge of the last column reaches past the table
+        assert_eq!(
+            node.hit_header([502.0, 10.0], bounds),
+            Some(HeaderHit::Edge(2))
+        );
+        assert_eq!(node.hit_header([600.0, 10.0], bounds), None);
+        // below the header
+        assert_eq!(node.hit_header([50.0, HEADER_HEIGHT], bounds), None);
+    }
+}
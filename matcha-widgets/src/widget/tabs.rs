@@ -0,0 +1,861 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::style::Style;
+use matcha_core::metrics::{Arrangement, Constraints};
+use matcha_core::{
+    color::Color,
+    context::WidgetContext,
+    device_input::{DeviceInput, DeviceInputData, ElementState, MouseInput, MouseLogicalButton},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        keyed::child_id,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+
+use crate::style::{
+    drawn_node,
+    solid_box::SolidBox,
+    text::{Sentence, Text, TextDesc},
+};
+
+pub(crate) const STRIP_HEIGHT: f32 = 32.0;
+const FONT_SIZE: f32 = 14.0;
+const LINE_HEIGHT: f32 = 20.0;
+const TAB_PADDING_X: f32 = 14.0;
+const CLOSE_SIZE: f32 = 16.0;
+const CLOSE_GAP: f32 = 6.0;
+const INDICATOR_HEIGHT: f32 = 2.0;
+const DROP_MARKER_WIDTH: f32 = 2.0;
+/// How far above or below the strip a tab has to be dragged to be dragged out of it.
+const DRAG_OUT_DISTANCE: f32 = 24.0;
+
+const STRIP_COLOR: Color = Color::RgbaF32 {
+    r: 0.93,
+    g: 0.93,
+    b: 0.93,
+    a: 1.0,
+};
+const SELECTED_TAB_COLOR: Color = Color::RgbaF32 {
+    r: 0.99,
+    g: 0.99,
+    b: 0.99,
+    a: 1.0,
+};
+const HOVER_TAB_COLOR: Color = Color::RgbaF32 {
+    r: 0.88,
+    g: 0.88,
+    b: 0.88,
+    a: 1.0,
+};
+const ACCENT_COLOR: Color = Color::RgbaF32 {
+    r: 0.20,
+    g: 0.47,
+    b: 0.90,
+    a: 1.0,
+};
+const BORDER_COLOR: Color = Color::RgbaF32 {
+    r: 0.78,
+    g: 0.78,
+    b: 0.78,
+    a: 1.0,
+};
+const TEXT_COLOR: Color = Color::RgbaF32 {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 1.0,
+};
+const CLOSE_COLOR: Color = Color::RgbaF32 {
+    r: 0.45,
+    g: 0.45,
+    b: 0.45,
+    a: 1.0,
+};
+
+pub(crate) type ContentBuilder<T> = Arc<dyn Fn() -> Box<dyn Dom<T>> + Send + Sync>;
+type IndexHandler<T> = Arc<dyn Fn(usize) -> T + Send + Sync>;
+type ReorderHandler<T> = Arc<dyn Fn(usize, usize) -> T + Send + Sync>;
+/// may emit nothing, for containers that take over the drag themselves.
+pub(crate) type DragOutHandler<T> = Arc<dyn Fn(usize) -> Option<T> + Send + Sync>;
+
+// MARK: DOM
+
+struct Tab<T> {
+    id: u128,
+    title: String,
+    builder: ContentBuilder<T>,
+    /// content built on first use; only the selected (and kept alive) tabs are built.
+    content: OnceLock<Box<dyn Dom<T>>>,
+}
+
+impl<T> Tab<T> {
+    fn content(&self) -> &dyn Dom<T> {
+        &**self.content.get_or_init(|| (self.builder)())
+    }
+}
+
+/// A tab strip above the content of the selected tab.
+///
+/// The selected tab is owned by the model: clicking a tab emits the `on_select` message and
+/// the view passes the new index back with [`Tabs::new`]. Content is built from the tab's
+/// builder only while the tab is selected. With [`keep_alive`](Tabs::keep_alive) the widgets
+/// of tabs that were shown before stay alive (keeping scroll positions, text input, ...) and
+/// are updated like the selected one, but are neither drawn nor receive input.
+///
+/// Tabs get a close button with [`on_close`](Tabs::on_close), can be dragged to a new
+/// position with [`on_reorder`](Tabs::on_reorder) and out of the strip with
+/// [`on_drag_out`](Tabs::on_drag_out).
+pub struct Tabs<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    selected: usize,
+    tabs: Vec<Tab<T>>,
+    keep_alive: bool,
+    on_select: Option<IndexHandler<T>>,
+    on_close: Option<IndexHandler<T>>,
+    on_reorder: Option<ReorderHandler<T>>,
+    on_drag_out: Option<DragOutHandler<T>>,
+}
+
+impl<T: 'static> Tabs<T> {
+    pub fn new(selected: usize) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            selected,
+            tabs: Vec::new(),
+            keep_alive: false,
+            on_select: None,
+            on_close: None,
+            on_reorder: None,
+            on_drag_out: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Adds a tab identified by its title.
+    pub fn tab<D: Dom<T>>(
+        self,
+        title: &str,
+        content: impl Fn() -> D + Send + Sync + 'static,
+    ) -> Self {
+        self.tab_keyed(title, title, content)
+    }
+
+    /// Adds a tab identified by `key`, so that its widgets survive renaming and reordering.
+    pub fn tab_keyed<K, D>(
+        self,
+        key: &K,
+        title: &str,
+        content: impl Fn() -> D + Send + Sync + 'static,
+    ) -> Self
+    where
+        K: std::hash::Hash + ?Sized,
+        D: Dom<T>,
+    {
+        self.push_tab(
+            child_id(key),
+            title,
+            Arc::new(move || Box::new(content()) as Box<dyn Dom<T>>),
+        )
+    }
+
+    pub(crate) fn push_tab(mut self, id: u128, title: &str, builder: ContentBuilder<T>) -> Self {
+        self.tabs.push(Tab {
+            id,
+            title: title.to_string(),
+            builder,
+            content: OnceLock::new(),
+        });
+        self
+    }
+
+    /// Keep the widgets of previously shown tabs alive. Default is `false`.
+    pub fn keep_alive(mut self, keep_alive: bool) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Message emitted with the index of a tab when it is clicked.
+    pub fn on_select(mut self, f: impl Fn(usize) -> T + Send + Sync + 'static) -> Self {
+        self.on_select = Some(Arc::new(f));
+        self
+    }
+
+    /// Shows a close button on every tab, emitting this message with the tab's index.
+    pub fn on_close(mut self, f: impl Fn(usize) -> T + Send + Sync + 'static) -> Self {
+        self.on_close = Some(Arc::new(f));
+        self
+    }
+
+    /// Lets tabs be dragged within the strip. Emits `(from, to)` indices when a tab is dropped
+    /// at a new position; `to` is the index the tab should have after the move.
+    pub fn on_reorder(mut self, f: impl Fn(usize, usize) -> T + Send + Sync + 'static) -> Self {
+        self.on_reorder = Some(Arc::new(f));
+        self
+    }
+
+    /// Message emitted with the index of a tab when it is dragged away from the strip, e.g. to
+    /// move it into another window. The drag ends for the tabs at that point.
+    pub fn on_drag_out(mut self, f: impl Fn(usize) -> T + Send + Sync + 'static) -> Self {
+        self.on_drag_out = Some(Arc::new(move |index| Some(f(index))));
+        self
+    }
+
+    pub(crate) fn drag_out_handler(mut self, handler: DragOutHandler<T>) -> Self {
+        self.on_drag_out = Some(handler);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for Tabs<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        let mut node = TabsNode {
+            titles: Vec::new(),
+            selected: None,
+            kept: Vec::new(),
+            closable: false,
+            on_select: None,
+            on_close: None,
+            on_reorder: None,
+            on_drag_out: None,
+            strip: Mutex::new(None),
+            hovered: None,
+            pressed: None,
+            drop_target: None,
+        };
+        let children = node.sync(self);
+        let (children, ids): (Vec<_>, Vec<_>) = children
+            .into_iter()
+            .map(|(dom, active, id)| ((dom.build_widget_tree(), active), id))
+            .unzip();
+
+        Box::new(
+            WidgetFrame::new(self.label.clone(), children, ids, node)
+                .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct TabsNode<T> {
+    titles: Vec<(u128, String)>,
+    selected: Option<usize>,
+    /// ids of the tabs whose content stays alive while they are not selected.
+    kept: Vec<u128>,
+    closable: bool,
+    on_select: Option<IndexHandler<T>>,
+    on_close: Option<IndexHandler<T>>,
+    on_reorder: Option<ReorderHandler<T>>,
+    on_drag_out: Option<DragOutHandler<T>>,
+    /// shaped titles and tab extents; rebuilt when the titles change.
+    strip: Mutex<Option<Vec<TabHeader>>>,
+    hovered: Option<usize>,
+    /// tab under the pointer when the primary button went down, and whether it hit the close button.
+    pressed: Option<(usize, bool)>,
+    /// insertion index while a tab is dragged.
+    drop_target: Option<usize>,
+}
+
+struct TabHeader {
+    left: f32,
+    width: f32,
+    text: Text,
+    text_size: [f32; 2],
+}
+
+impl TabHeader {
+    fn contains_x(&self, x: f32) -> bool {
+        self.left <= x && x < self.left + self.width
+    }
+}
+
+impl<T: 'static> TabsNode<T> {
+    /// Takes over the settings of `dom` and returns the children to keep: the selected
+    /// tab's content (active) and, with keep-alive, the content of tabs shown before.
+    fn sync<'a>(&mut self, dom: &'a Tabs<T>) -> Vec<(&'a dyn Dom<T>, bool, u128)> {
+        let titles: Vec<(u128, String)> = dom
+            .tabs
+            .iter()
+            .map(|tab| (tab.id, tab.title.clone()))
+            .collect();
+        let closable = dom.on_close.is_some();
+        if titles != self.titles || closable != self.closable {
+            self.titles = titles;
+            self.closable = closable;
+            *self.strip.get_mut() = None;
+        }
+        self.on_select = dom.on_select.clone();
+        self.on_close = dom.on_close.clone();
+        self.on_reorder = dom.on_reorder.clone();
+        self.on_drag_out = dom.on_drag_out.clone();
+        self.selected = (dom.selected < dom.tabs.len()).then_some(dom.selected);
+        if self.hovered.is_some_and(|index| index >= dom.tabs.len()) {
+            self.hovered = None;
+        }
+
+        if dom.keep_alive {
+            if let Some(selected) = self.selected
+                && !self.kept.contains(&dom.tabs[selected].id)
+            {
+                self.kept.push(dom.tabs[selected].id);
+            }
+            self.kept
+                .retain(|id| dom.tabs.iter().any(|tab| tab.id == *id));
+        } else {
+            self.kept.clear();
+        }
+
+        dom.tabs
+            .iter()
+            .enumerate()
+            .filter(|(index, tab)| Some(*index) == self.selected || self.kept.contains(&tab.id))
+            .map(|(index, tab)| (tab.content(), Some(index) == self.selected, tab.id))
+            .collect()
+    }
+
+    fn with_strip<R>(&self, ctx: &WidgetContext, f: impl FnOnce(&[TabHeader]) -> R) -> R {
+        let mut strip = self.strip.lock();
+        let headers = strip.get_or_insert_with(|| {
+            let mut left = 0.0;
+            self.titles
+                .iter()
+                .map(|(_, title)| {
+                    let text = Text::new(
+                        &TextDesc::new(vec![Sentence::new(title).color(TEXT_COLOR)])
+                            .font_size(FONT_SIZE)
+                            .line_height(LINE_HEIGHT),
+                    );
+                    let text_size = text
+                        .required_region(&Constraints::new([0.0, 4096.0], [0.0, 4096.0]), ctx)
+                        .map_or([0.0, 0.0], |rect| [rect.width(), rect.height()]);
+                    let close = if self.closable {
+                        CLOSE_GAP + CLOSE_SIZE
+                    } else {
+                        0.0
+                    };
+                    let width = (2.0 * TAB_PADDING_X + text_size[0] + close).ceil();
+                    let header = TabHeader {
+                        left,
+                        width,
+                        text,
+                        text_size,
+                    };
+                    left += width;
+                    header
+                })
+                .collect()
+        });
+        f(headers)
+    }
+
+    fn close_button_origin(header: &TabHeader) -> [f32; 2] {
+        [
+            header.left + header.width - TAB_PADDING_X - CLOSE_SIZE,
+            ((STRIP_HEIGHT - CLOSE_SIZE) / 2.0).round(),
+        ]
+    }
+
+    /// Tab under `position` and whether the position is on its close button.
+    fn hit_tab(&self, position: [f32; 2], ctx: &WidgetContext) -> Option<(usize, bool)> {
+        if !(0.0..STRIP_HEIGHT).contains(&position[1]) {
+            return None;
+        }
+        self.with_strip(ctx, |headers| {
+            let index = headers
+                .iter()
+                .position(|header| header.contains_x(position[0]))?;
+            let on_close = self.closable && {
+                let origin = Self::close_button_origin(&headers[index]);
+                (origin[0]..=origin[0] + CLOSE_SIZE).contains(&position[0])
+                    && (origin[1]..=origin[1] + CLOSE_SIZE).contains(&position[1])
+            };
+            Some((index, on_close))
+        })
+    }
+
+    /// Index the dragged tab would get when dropped at `x`.
+    fn insertion_index(&self, from: usize, x: f32, ctx: &WidgetContext) -> usize {
+        self.with_strip(ctx, |headers| {
+            let slot = headers
+                .iter()
+                .position(|header| x < header.left + header.width / 2.0)
+                .unwrap_or(headers.len());
+            // removing the tab shifts the slots after it
+            if slot > from { slot - 1 } else { slot }
+        })
+    }
+
+    fn strip_input(
+        &mut self,
+        event: &DeviceInput,
+        position: [f32; 2],
+        ctx: &WidgetContext,
+    ) -> (bool, Option<T>) {
+        let hit = self.hit_tab(position, ctx);
+        let mut redraw = false;
+
+        let hovered = hit.map(|(index, _)| index);
+        if hovered != self.hovered {
+            self.hovered = hovered;
+            redraw = true;
+        }
+
+        match event.event() {
+            DeviceInputData::MouseInput {
+                event:
+                    Some(MouseInput::Click {
+                        click_state,
+                        button: MouseLogicalButton::Primary,
+                    }),
+                ..
+            } => match click_state {
+                ElementState::Pressed(_) => {
+                    self.pressed = hit;
+                    // tabs are selected on press, like most tab strips
+                    if let Some((index, false)) = hit
+                        && Some(index) != self.selected
+                        && let Some(f) = &self.on_select
+                    {
+                        return (redraw, Some(f(index)));
+                    }
+                    (redraw, None)
+                }
+                ElementState::Released(_) => {
+                    let pressed = self.pressed.take();
+                    let drop_target = self.drop_target.take();
+                    if drop_target.is_some() {
+                        redraw = true;
+                    }
+
+                    match (pressed, drop_target) {
+                        (Some((from, _)), Some(to)) if from != to => {
+                            let message = self.on_reorder.as_ref().map(|f| f(from, to));
+                            (redraw, message)
+                        }
+                        (Some((index, true)), _) if hit == Some((index, true)) => {
+                            let message = self.on_close.as_ref().map(|f| f(index));
+                            (redraw, message)
+                        }
+                        _ => (redraw, None),
+                    }
+                }
+                ElementState::LongPressed(_) => (redraw, None),
+            },
+            DeviceInputData::MouseInput {
+                dragging_from_primary: Some(_),
+                ..
+            } => {
+                if let Some((from, false)) = self.pressed
+                    && let Some(f) = &self.on_drag_out
+                    && !(-DRAG_OUT_DISTANCE..STRIP_HEIGHT + DRAG_OUT_DISTANCE)
+                        .contains(&position[1])
+                {
+                    let message = f(from);
+                    self.pressed = None;
+                    self.drop_target = None;
+                    return (true, message);
+                }
+                if let Some((from, false)) = self.pressed
+                    && self.on_reorder.is_some()
+                {
+                    let target = self.insertion_index(from, position[0], ctx);
+                    let target = (target != from).then_some(target);
+                    if target != self.drop_target {
+                        self.drop_target = target;
+                        redraw = true;
+                    }
+                }
+                (redraw, None)
+            }
+            _ => (redraw, None),
+        }
+    }
+
+    fn render_strip(&self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<RenderNode> {
+        let size = [bounds[0], STRIP_HEIGHT];
+        drawn_node(size, ctx, |encoder, region| {
+            SolidBox { color: STRIP_COLOR }.draw(encoder, region, size, [0.0, 0.0], ctx);
+            SolidBox {
+                color: BORDER_COLOR,
+            }
+            .draw(
+                encoder,
+                region,
+                [size[0], 1.0],
+                [0.0, STRIP_HEIGHT - 1.0],
+                ctx,
+            );
+
+            self.with_strip(ctx, |headers| {
+                // tabs that do not fit are not drawn
+                let fitting = headers
+                    .iter()
+                    .take_while(|header| header.left + header.width <= size[0]);
+                for (index, header) in fitting.enumerate() {
+                    let background = if Some(index) == self.selected {
+                        Some(SELECTED_TAB_COLOR)
+                    } else if Some(index) == self.hovered {
+                        Some(HOVER_TAB_COLOR)
+                    } else {
+                        None
+                    };
+                    if let Some(color) = background {
+                        SolidBox { color }.draw(
+                            encoder,
+                            region,
+                            [header.width, STRIP_HEIGHT],
+                            [header.left, 0.0],
+                            ctx,
+                        );
+                    }
+                    if Some(index) == self.selected {
+                        SolidBox {
+                            color: ACCENT_COLOR,
+                        }
+                        .draw(
+                            encoder,
+                            region,
+                            [header.width, INDICATOR_HEIGHT],
+                            [header.left, STRIP_HEIGHT - INDICATOR_HEIGHT],
+                            ctx,
+                        );
+                    }
+
+                    header.text.draw(
+                        encoder,
+                        region,
+                        header.text_size,
+                        [
+                            header.left + TAB_PADDING_X,
+                            ((STRIP_HEIGHT - header.text_size[1]) / 2.0).round(),
+                        ],
+                        ctx,
+                    );
+
+                    if self.closable {
+                        // a small cross made of two bars
+                        let origin = Self::close_button_origin(header);
+                        let bar = (CLOSE_SIZE / 2.0).round();
+                        let center = [origin[0] + CLOSE_SIZE / 2.0, origin[1] + CLOSE_SIZE / 2.0];
+                        SolidBox { color: CLOSE_COLOR }.draw(
+                            encoder,
+                            region,
+                            [bar, 2.0],
+                            [center[0] - bar / 2.0, center[1] - 1.0],
+                            ctx,
+                        );
+                        SolidBox { color: CLOSE_COLOR }.draw(
+                            encoder,
+                            region,
+                            [2.0, bar],
+                            [center[0] - 1.0, center[1] - bar / 2.0],
+                            ctx,
+                        );
+                    }
+                }
+
+                if let Some((from, _)) = self.pressed
+                    && let Some(target) = self.drop_target
+                {
+                    // marker at the gap the tab would be dropped into
+                    let slot = if target >= from { target + 1 } else { target };
+                    let x = headers
+                        .get(slot)
+                        .map_or_else(
+                            || headers.last().map_or(0.0, |h| h.left + h.width),
+                            |h| h.left,
+                        )
+                        .clamp(0.0, (size[0] - DROP_MARKER_WIDTH).max(0.0));
+                    SolidBox {
+                        color: ACCENT_COLOR,
+                    }
+                    .draw(
+                        encoder,
+                        region,
+                        [DROP_MARKER_WIDTH, STRIP_HEIGHT],
+                        [x, 0.0],
+                        ctx,
+                    );
+                }
+            });
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<Tabs<T>, T, bool> for TabsNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a Tabs<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, bool, u128)> {
+        let children = self.sync(dom);
+        if let Some(handle) = cache_invalidator {
+            handle.relayout_next_frame();
+        }
+        children
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &bool)],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let strip_width = self.with_strip(ctx, |headers| {
+            headers.last().map_or(0.0, |h| h.left + h.width)
+        });
+
+        let content_constraints = Constraints::new(
+            constraints.width(),
+            [
+                (constraints.min_height() - STRIP_HEIGHT).max(0.0),
+                (constraints.max_height() - STRIP_HEIGHT).max(0.0),
+            ],
+        );
+        let content = children
+            .iter()
+            .find(|(_, active)| **active)
+            .map_or([0.0, 0.0], |(child, _)| {
+                child.measure(&content_constraints, ctx)
+            });
+
+        [
+            content[0]
+                .max(strip_width)
+                .clamp(constraints.min_width(), constraints.max_width()),
+            (content[1] + STRIP_HEIGHT).clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &bool)],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        let content_size = [bounds[0], (bounds[1] - STRIP_HEIGHT).max(0.0)];
+        children
+            .iter()
+            .map(|(_, active)| {
+                // inactive tabs keep their size but are moved out of view, so they are
+                // neither prepared nor hit
+                let x = if **active { 0.0 } else { -(bounds[0] + 1.0) };
+                Arrangement::new(
+                    content_size,
+                    nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+                        x,
+                        STRIP_HEIGHT,
+                        0.0,
+                    )),
+                )
+            })
+            .collect()
+    }
+
+    fn device_input(
+        &mut self,
+        _bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut bool, &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        if matches!(event.event(), DeviceInputData::MouseInput { .. })
+            && let Some(position) = event.mouse_position()
+        {
+            let pressed = self.pressed.is_some();
+            let (redraw, message) = self.strip_input(event, position, ctx);
+            if redraw {
+                cache_invalidator.redraw_next_frame();
+            }
+            // the strip and drags that started on it do not reach the content
+            if message.is_some() || position[1] < STRIP_HEIGHT || pressed || self.pressed.is_some()
+            {
+                return message;
+            }
+        }
+
+        let (content, _, arrangement) = children.iter_mut().find(|(_, active, _)| **active)?;
+        let content_event = event.transform(arrangement.affine);
+        content.device_input(&content_event, ctx)
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &bool, &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        if let Some((content, _, arrangement)) = children.iter().find(|(_, active, _)| **active) {
+            render_node.push_child(content.render(background, ctx), arrangement.affine);
+        }
+
+        if let Some(strip) = self.render_strip(bounds, ctx) {
+            render_node.push_child(strip, nalgebra::Matrix4::identity());
+        }
+
+        render_node
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Content that counts how often its widget is built.
+    struct Probe(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl Dom<()> for Probe {
+        fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<()>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Box::new(WidgetFrame::new(None, vec![], vec![], ProbeNode))
+        }
+    }
+
+    struct ProbeNode;
+
+    impl Widget<Probe, (), ()> for ProbeNode {
+        fn update_widget<'a>(
+            &mut self,
+            _dom: &'a Probe,
+            _cache_invalidator: Option<InvalidationHandle>,
+        ) -> Vec<(&'a dyn Dom<()>, (), u128)> {
+            vec![]
+        }
+
+        fn device_input(
+            &mut self,
+            _bounds: [f32; 2],
+            _event: &DeviceInput,
+            _children: &mut [(&mut dyn AnyWidget<()>, &mut (), &Arrangement)],
+            _cache_invalidator: InvalidationHandle,
+            _ctx: &WidgetContext,
+        ) -> Option<()> {
+            None
+        }
+
+        fn measure(
+            &self,
+            _constraints: &Constraints,
+            _children: &[(&dyn AnyWidget<()>, &())],
+            _ctx: &WidgetContext,
+        ) -> [f32; 2] {
+            [0.0, 0.0]
+        }
+
+        fn arrange(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<()>, &())],
+            _ctx: &WidgetContext,
+        ) -> Vec<Arrangement> {
+            vec![]
+        }
+
+        fn render(
+            &self,
+            _bounds: [f32; 2],
+            _children: &[(&dyn AnyWidget<()>, &(), &Arrangement)],
+            _background: Background,
+            _ctx: &WidgetContext,
+        ) -> RenderNode {
+            RenderNode::new()
+        }
+    }
+
+    fn tabs(selected: usize, keep_alive: bool, builds: &[Arc<AtomicUsize>; 3]) -> Tabs<()> {
+        ["One", "Two", "Three"]
+            .into_iter()
+            .zip(builds.clone())
+            .fold(
+                Tabs::new(selected).keep_alive(keep_alive),
+                |tabs, (title, count)| tabs.tab(title, move || Probe(count.clone())),
+            )
+    }
+
+    fn counts(builds: &[Arc<AtomicUsize>; 3]) -> [usize; 3] {
+        builds.each_ref().map(|count| count.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_only_the_selected_tab_is_built() {
+        let builds = Default::default();
+        let mut frame = tabs(1, false, &builds).build_widget_tree();
+        assert_eq!(counts(&builds), [0, 1, 0]);
+
+        frame
+            .update_widget_tree(&tabs(2, false, &builds))
+            .await
+            .unwrap();
+        assert_eq!(counts(&builds), [0, 1, 1]);
+
+        // the content of a tab that is no longer selected is dropped
+        frame
+            .update_widget_tree(&tabs(1, false, &builds))
+            .await
+            .unwrap();
+        assert_eq!(counts(&builds), [0, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_keeps_widgets_across_switches() {
+        let builds = Default::default();
+        let mut frame = tabs(0, true, &builds).build_widget_tree();
+        assert_eq!(counts(&builds), [1, 0, 0]);
+
+        frame
+            .update_widget_tree(&tabs(1, true, &builds))
+            .await
+            .unwrap();
+        assert_eq!(counts(&builds), [1, 1, 0]);
+
+        // switching back reuses the widgets of the first tab
+        frame
+            .update_widget_tree(&tabs(0, true, &builds))
+            .await
+            .unwrap();
+        frame
+            .update_widget_tree(&tabs(1, true, &builds))
+            .await
+            .unwrap();
+        assert_eq!(counts(&builds), [1, 1, 0]);
+
+        // turning keep-alive off drops the tabs that are not selected
+        frame
+            .update_widget_tree(&tabs(1, false, &builds))
+            .await
+            .unwrap();
+        frame
+            .update_widget_tree(&tabs(0, false, &builds))
+            .await
+            .unwrap();
+        assert_eq!(counts(&builds), [2, 1, 0]);
+    }
+}
@@ -3,7 +3,7 @@ use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
     ui::{
-        AnyWidgetFrame, Background, Dom, Widget, WidgetFrame,
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
         widget::{AnyWidget, InvalidationHandle},
     },
 };
@@ -15,27 +15,42 @@ use renderer::render_node::RenderNode;
 
 pub struct Template {
     label: Option<String>,
+    layout_style: LayoutStyle,
 }
 
 impl Template {
     pub fn new(label: Option<&str>) -> Box<Self> {
         Box::new(Self {
             label: label.map(|s| s.to_string()),
+            layout_style: LayoutStyle::default(),
         })
     }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
 }
 
 #[async_trait::async_trait]
 impl<E: Send + 'static> Dom<E> for Template {
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<E>> {
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            vec![],
-            vec![],
-            TemplateNode {
-                label: self.label.clone(),
-            },
-        ))
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                TemplateNode {
+                    label: self.label.clone(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -7,7 +7,7 @@ use matcha_core::{
     device_input::DeviceInput,
     metrics::{Arrangement, Constraints},
     ui::{
-        AnyWidgetFrame, Background, Dom, Widget, WidgetFrame,
+        AnyWidgetFrame, Background, Dom, LayoutStyle, PrepareFuture, Widget, WidgetFrame,
         widget::{AnyWidget, InvalidationHandle},
     },
 };
@@ -17,22 +17,38 @@ use renderer::render_node::RenderNode;
 
 pub struct Text {
     label: Option<String>,
+    layout_style: LayoutStyle,
 
     sentence: crate::style::text::Sentence,
     font_size: f32,
-    line_height: f32,
+    line_height: crate::style::text::LineHeight,
+    letter_spacing: f32,
+    word_spacing: f32,
+    paragraph_spacing: f32,
+    tab_width: u16,
 }
 
 impl Text {
     pub fn new(s: &str) -> Self {
         Self {
             label: None,
+            layout_style: LayoutStyle::default(),
             sentence: crate::style::text::Sentence::new(s),
             font_size: 14.0,
-            line_height: 20.0,
+            line_height: crate::style::text::LineHeight::Exact(20.0),
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            paragraph_spacing: 0.0,
+            tab_width: 8,
         }
     }
 
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
     pub fn label(mut self, label: &str) -> Self {
         self.label = Some(label.to_string());
         self
@@ -68,30 +84,70 @@ impl Text {
         self
     }
 
-    pub fn line_height(mut self, height: f32) -> Self {
-        self.line_height = height;
+    /// Distance between baselines, in pixels or as a [`LineHeight`](crate::style::text::LineHeight).
+    pub fn line_height(mut self, height: impl Into<crate::style::text::LineHeight>) -> Self {
+        self.line_height = height.into();
+        self
+    }
+
+    /// Extra space after every glyph.
+    pub fn letter_spacing(mut self, spacing: f32) -> Self {
+        self.letter_spacing = spacing;
+        self
+    }
+
+    /// Extra space after every space character.
+    pub fn word_spacing(mut self, spacing: f32) -> Self {
+        self.word_spacing = spacing;
+        self
+    }
+
+    /// Extra space between the paragraphs separated by line feeds.
+    pub fn paragraph_spacing(mut self, spacing: f32) -> Self {
+        self.paragraph_spacing = spacing;
+        self
+    }
+
+    /// Distance between tab stops in space widths. 8 by default.
+    pub fn tab_width(mut self, spaces: u16) -> Self {
+        self.tab_width = spaces;
         self
     }
+
+    fn text_desc(&self) -> crate::style::text::TextDesc {
+        crate::style::text::TextDesc::new(vec![self.sentence.clone()])
+            .font_size(self.font_size)
+            .line_height(self.line_height)
+            .letter_spacing(self.letter_spacing)
+            .word_spacing(self.word_spacing)
+            .paragraph_spacing(self.paragraph_spacing)
+            .tab_width(self.tab_width)
+    }
 }
 
 #[async_trait::async_trait]
 impl<T: Send + Sync + 'static> Dom<T> for Text {
     fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
-        let text_desc = crate::style::text::TextDesc::new(vec![self.sentence.clone()])
-            .font_size(self.font_size)
-            .line_height(self.line_height);
-
-        Box::new(WidgetFrame::new(
-            self.label.clone(),
-            vec![],
-            vec![],
-            TextWidget {
-                clear: crate::style::viewport_clear::ViewportClear {
-                    color: matcha_core::color::Color::TRANSPARENT,
+        let text_desc = self.text_desc();
+
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                TextWidget {
+                    clear: crate::style::viewport_clear::ViewportClear {
+                        color: matcha_core::color::Color::TRANSPARENT,
+                    },
+                    style: crate::style::text::Text::new(&text_desc),
                 },
-                style: crate::style::text::Text::new(&text_desc),
-            },
-        ))
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
     }
 }
 
@@ -109,9 +165,7 @@ impl<E: Send + Sync + 'static> Widget<Text, E, ()> for TextWidget {
         cache_invalidator: Option<InvalidationHandle>,
     ) -> Vec<(&'a dyn Dom<E>, (), u128)> {
         // Build a TextDesc like Dom::build_widget_tree does and create a new style
-        let text_desc = crate::style::text::TextDesc::new(vec![dom.sentence.clone()])
-            .font_size(dom.font_size)
-            .line_height(dom.line_height);
+        let text_desc = dom.text_desc();
 
         let new_style = crate::style::text::Text::new(&text_desc);
 
@@ -142,6 +196,15 @@ impl<E: Send + Sync + 'static> Widget<Text, E, ()> for TextWidget {
         }
     }
 
+    fn baseline(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<E>, &())],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        self.style.first_baseline(constraints, ctx)
+    }
+
     fn arrange(
         &self,
         _bounds: [f32; 2],
@@ -211,4 +274,8 @@ impl<E: Send + Sync + 'static> Widget<Text, E, ()> for TextWidget {
 
         render_node
     }
+
+    fn prepare(&mut self, bounds: [f32; 2], ctx: &WidgetContext) -> Option<PrepareFuture> {
+        self.style.prepare(bounds, ctx)
+    }
 }
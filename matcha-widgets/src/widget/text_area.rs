@@ -0,0 +1,765 @@
+use std::sync::Arc;
+
+use crate::editor::{Editor, Rope};
+use crate::style::solid_box::SolidBox;
+use crate::style::text::{Sentence, TextDesc};
+use crate::style::{Style, style_node, translation};
+use crate::widget::text_edit::ImeClaim;
+
+use fxhash::{FxHashMap, FxHashSet};
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::{
+    clipboard,
+    cursor::CursorIcon,
+    device_input::{DeviceInput, Ime, ImePurpose, Key, KeyInput},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use parking_lot::{Mutex, MutexGuard};
+use renderer::render_node::{LayerCache, RenderNode};
+use winit::keyboard::NamedKey;
+
+/// Space between the edges of the area and the text.
+const PADDING: f32 = 4.0;
+
+type ChangeHandler<T> = Arc<dyn Fn(&Rope) -> T + Send + Sync>;
+
+// MARK: DOM
+
+/// A multi-line text editor.
+///
+/// Lines wrap at the width of the area, which scrolls vertically with the mouse wheel and
+/// follows the caret. The text lives in a [`Rope`] and only the lines in view are shaped, so
+/// large documents stay cheap to edit. Undo and redo are bound to Ctrl+Z and Ctrl+Y (or
+/// Ctrl+Shift+Z), and the clipboard to Ctrl+C, Ctrl+X and Ctrl+V.
+///
+/// The area edits its own copy of the text. It is replaced only when the text given here
+/// changes to something other than what was typed, so feeding [`on_change`](Self::on_change)
+/// back into the model does not reset the caret or the history.
+pub struct TextArea<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    text: Rope,
+    font_size: f32,
+    line_height: f32,
+    color: Color,
+    background: Color,
+    selection_color: Color,
+    on_change: Option<ChangeHandler<T>>,
+}
+
+impl<T> TextArea<T> {
+    pub fn new(text: impl Into<Rope>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            text: text.into(),
+            font_size: 14.0,
+            line_height: 20.0,
+            color: Color::rgb(0, 0, 0),
+            background: Color::rgb(255, 255, 255),
+            selection_color: Color::rgba(51, 144, 255, 0.3),
+            on_change: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    pub fn line_height(mut self, height: f32) -> Self {
+        self.line_height = height;
+        self
+    }
+
+    /// Color of the text and the caret.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    pub fn selection_color(mut self, color: Color) -> Self {
+        self.selection_color = color;
+        self
+    }
+
+    /// Called with the new text after every edit, undo and redo.
+    pub fn on_change(mut self, f: impl Fn(&Rope) -> T + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(f));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for TextArea<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                TextAreaNode {
+                    source: self.text.clone(),
+                    editor: Editor::new(self.text.clone()),
+                    font_size: self.font_size,
+                    line_height: self.line_height,
+                    color: self.color,
+                    background: self.background,
+                    selection_color: self.selection_color,
+                    on_change: self.on_change.clone(),
+                    revision: 0,
+                    focused: false,
+                    ime: ImeClaim::new(),
+                    hovered: false,
+                    selecting: false,
+                    scroll: 0.0,
+                    goal_x: None,
+                    layout: Mutex::new(Layout::default()),
+                    layer: LayerCache::new(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Layout
+
+/// Wrapped lines of the text at one width.
+#[derive(Default)]
+struct Layout {
+    width: f32,
+    /// text revision `heights` was computed for.
+    revision: Option<u64>,
+    /// height of each line, that of one line of text until it is shaped.
+    heights: Vec<f32>,
+    /// height of shaped lines by content, so edits keep the heights of the other lines.
+    measured: FxHashMap<String, f32>,
+    /// shaped lines by content.
+    shaped: FxHashMap<String, crate::style::text::Text>,
+}
+
+impl Layout {
+    fn height(&self) -> f32 {
+        self.heights.iter().sum()
+    }
+
+    fn top(&self, line: usize) -> f32 {
+        self.heights[..line].iter().sum()
+    }
+
+    /// The line at `y`, clamped to the first and last line, and its top.
+    fn line_at(&self, y: f32) -> (usize, f32) {
+        let mut top = 0.0;
+        for (line, height) in self.heights.iter().enumerate() {
+            if y < top + height || line + 1 == self.heights.len() {
+                return (line, top);
+            }
+            top += height;
+        }
+        (0, 0.0)
+    }
+}
+
+/// Constraints for shaping a line, which only wraps horizontally.
+fn line_constraints(width: f32) -> Constraints {
+    Constraints::new([0.0, width], [0.0, f32::INFINITY])
+}
+
+// MARK: Widget
+
+pub struct TextAreaNode<T> {
+    /// text of the last dom, to tell app changes from echoed edits.
+    source: Rope,
+    editor: Editor,
+    font_size: f32,
+    line_height: f32,
+    color: Color,
+    background: Color,
+    selection_color: Color,
+    on_change: Option<ChangeHandler<T>>,
+
+    /// bumped on every change of the text.
+    revision: u64,
+    focused: bool,
+    ime: ImeClaim,
+    hovered: bool,
+    /// the primary button was pressed in the area, dragging extends the selection.
+    selecting: bool,
+    scroll: f32,
+    /// x the caret keeps while moving up and down.
+    goal_x: Option<f32>,
+    layout: Mutex<Layout>,
+    layer: LayerCache,
+}
+
+impl<T> TextAreaNode<T> {
+    fn text_width(bounds: [f32; 2]) -> f32 {
+        (bounds[0] - 2.0 * PADDING).max(1.0)
+    }
+
+    /// Content of `line` without its line break.
+    fn line_text(&self, line: usize) -> String {
+        let text = self.editor.text();
+        text.slice(text.line_to_char(line)..self.editor.line_end(line))
+            .to_string()
+    }
+
+    /// The layout at `width`, up to date with the text.
+    fn layout(&self, width: f32) -> MutexGuard<'_, Layout> {
+        let mut layout = self.layout.lock();
+        if layout.width != width {
+            *layout = Layout {
+                width,
+                ..Layout::default()
+            };
+        }
+        if layout.revision != Some(self.revision) {
+            let mut measured = FxHashMap::default();
+            let heights = (0..self.editor.text().len_lines())
+                .map(|line| {
+                    let content = self.line_text(line);
+                    let height = layout
+                        .measured
+                        .get(&content)
+                        .copied()
+                        .unwrap_or(self.line_height);
+                    measured.insert(content, height);
+                    height
+                })
+                .collect();
+            layout.heights = heights;
+            layout.measured = measured;
+            layout.revision = Some(self.revision);
+        }
+        layout
+    }
+
+    /// Shapes `line` if needed and returns it with its height.
+    fn shape_line<'a>(
+        &self,
+        layout: &'a mut Layout,
+        line: usize,
+        ctx: &WidgetContext,
+    ) -> (&'a crate::style::text::Text, f32) {
+        let content = self.line_text(line);
+        let text = layout.shaped.entry(content.clone()).or_insert_with(|| {
+            crate::style::text::Text::new(
+                &TextDesc::new(vec![Sentence::new(content.as_str()).color(self.color)])
+                    .font_size(self.font_size)
+                    .line_height(self.line_height),
+            )
+        });
+        let height = text
+            .required_region(&line_constraints(layout.width), ctx)
+            .map_or(self.line_height, |rect| rect.height().max(self.line_height));
+        layout.heights[line] = height;
+        layout.measured.insert(content, height);
+        (text, height)
+    }
+
+    /// Char index of the caret position closest to `position` in text coordinates.
+    fn hit(&self, layout: &mut Layout, position: [f32; 2], ctx: &WidgetContext) -> usize {
+        let (line, top) = layout.line_at(position[1]);
+        let constraints = line_constraints(layout.width);
+        let (text, _) = self.shape_line(layout, line, ctx);
+        let offset = text
+            .hit(&constraints, [position[0], position[1] - top], ctx)
+            .unwrap_or(0);
+        let rope = self.editor.text();
+        rope.byte_to_char(rope.line_to_byte(line) + offset)
+    }
+
+    /// The caret before char `index` in text coordinates.
+    fn caret_rect(&self, layout: &mut Layout, index: usize, ctx: &WidgetContext) -> [[f32; 2]; 2] {
+        let rope = self.editor.text();
+        let line = rope.char_to_line(index);
+        let offset = rope.char_to_byte(index) - rope.line_to_byte(line);
+        let top = layout.top(line);
+        let constraints = line_constraints(layout.width);
+        let (text, _) = self.shape_line(layout, line, ctx);
+        let [[x, y0], [_, y1]] = text
+            .caret(&constraints, offset, ctx)
+            .unwrap_or([[0.0, 0.0], [0.0, self.line_height]]);
+        [[x, top + y0], [x, top + y1]]
+    }
+
+    fn max_scroll(&self, bounds: [f32; 2]) -> f32 {
+        let height = self.layout(Self::text_width(bounds)).height();
+        (height + 2.0 * PADDING - bounds[1]).max(0.0)
+    }
+
+    /// Char index under `position` in widget coordinates.
+    fn hit_in_view(&self, bounds: [f32; 2], position: [f32; 2], ctx: &WidgetContext) -> usize {
+        let mut layout = self.layout(Self::text_width(bounds));
+        let position = [position[0] - PADDING, position[1] - PADDING + self.scroll];
+        self.hit(&mut layout, position, ctx)
+    }
+
+    fn scroll_to_caret(&mut self, bounds: [f32; 2], ctx: &WidgetContext) {
+        let caret = {
+            let mut layout = self.layout(Self::text_width(bounds));
+            self.caret_rect(&mut layout, self.editor.selection().head, ctx)
+        };
+        let viewport = (bounds[1] - 2.0 * PADDING).max(0.0);
+        if caret[0][1] < self.scroll {
+            self.scroll = caret[0][1];
+        } else if caret[1][1] > self.scroll + viewport {
+            self.scroll = caret[1][1] - viewport;
+        }
+        self.scroll = self.scroll.clamp(0.0, self.max_scroll(bounds));
+    }
+
+    /// Moves the caret to the wrapped line above or below, keeping its x.
+    fn move_vertically(&mut self, bounds: [f32; 2], down: bool, extend: bool, ctx: &WidgetContext) {
+        let goal_x = self.goal_x;
+        let (index, x) = {
+            let mut layout = self.layout(Self::text_width(bounds));
+            let caret = self.caret_rect(&mut layout, self.editor.selection().head, ctx);
+            let x = goal_x.unwrap_or(caret[0][0]);
+            let y = if down {
+                caret[1][1] + 1.0
+            } else {
+                caret[0][1] - 1.0
+            };
+            let index = if y < 0.0 {
+                0
+            } else if y >= layout.height() {
+                self.editor.text().len_chars()
+            } else {
+                self.hit(&mut layout, [x, y], ctx)
+            };
+            (index, x)
+        };
+        self.goal_x = Some(x);
+        self.editor.move_to(index, extend);
+    }
+
+    /// Applies a key press. Returns `None` if the key does nothing here, otherwise whether
+    /// the text changed.
+    fn key_input(&mut self, key: &KeyInput, bounds: [f32; 2], ctx: &WidgetContext) -> Option<bool> {
+        let extend = key.shift_held();
+        let command = key.ctrl_held() || key.super_held();
+        let vertical = matches!(
+            key.logical_key(),
+            Key::Named(NamedKey::ArrowUp | NamedKey::ArrowDown)
+        );
+        if !vertical {
+            self.goal_x = None;
+        }
+        let length = self.editor.text().len_chars();
+
+        let handled = match key.logical_key() {
+            Key::Character(c) if command => match c.to_lowercase().as_str() {
+                "a" => {
+                    self.editor.select_all();
+                    Some(false)
+                }
+                "z" if extend => Some(self.editor.redo()),
+                "z" => Some(self.editor.undo()),
+                "y" => Some(self.editor.redo()),
+                "c" | "x" => {
+                    if self.editor.selection().is_empty() {
+                        Some(false)
+                    } else {
+                        clipboard::write_text(&self.editor.selected_text());
+                        if c.eq_ignore_ascii_case("x") {
+                            self.editor.backspace();
+                        }
+                        Some(c.eq_ignore_ascii_case("x"))
+                    }
+                }
+                "v" => match clipboard::read_text() {
+                    Some(text) if !text.is_empty() => {
+                        self.editor.insert(&text);
+                        Some(true)
+                    }
+                    _ => Some(false),
+                },
+                _ => None,
+            },
+            Key::Named(named) => match named {
+                NamedKey::Backspace => {
+                    self.editor.backspace();
+                    Some(self.editor.text().len_chars() != length)
+                }
+                NamedKey::Delete => {
+                    self.editor.delete();
+                    Some(self.editor.text().len_chars() != length)
+                }
+                NamedKey::ArrowLeft => {
+                    self.editor.move_left(extend);
+                    Some(false)
+                }
+                NamedKey::ArrowRight => {
+                    self.editor.move_right(extend);
+                    Some(false)
+                }
+                NamedKey::ArrowUp | NamedKey::ArrowDown => {
+                    self.move_vertically(bounds, *named == NamedKey::ArrowDown, extend, ctx);
+                    Some(false)
+                }
+                NamedKey::Home if command => {
+                    self.editor.move_to(0, extend);
+                    Some(false)
+                }
+                NamedKey::Home => {
+                    self.editor.move_line_start(extend);
+                    Some(false)
+                }
+                NamedKey::End if command => {
+                    self.editor.move_to(length, extend);
+                    Some(false)
+                }
+                NamedKey::End => {
+                    self.editor.move_line_end(extend);
+                    Some(false)
+                }
+                NamedKey::Enter => {
+                    self.editor.insert("\n");
+                    Some(true)
+                }
+                NamedKey::Tab => {
+                    self.editor.insert("\t");
+                    Some(true)
+                }
+                NamedKey::Escape => {
+                    self.focused = false;
+                    Some(false)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        handled.or_else(|| {
+            let text = key
+                .text()
+                .filter(|text| !command && !text.is_empty() && !text.contains(char::is_control))?;
+            self.editor.insert(text);
+            Some(true)
+        })
+    }
+
+    /// Inserts text committed by the input method; the composition is not shown before.
+    fn ime_input(&mut self, ime: &Ime) -> Option<bool> {
+        match ime {
+            Ime::Commit(text) if !text.is_empty() => {
+                self.goal_x = None;
+                self.editor.insert(text);
+                Some(true)
+            }
+            _ => Some(false),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<TextArea<T>, T, ()> for TextAreaNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a TextArea<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        let mut relayout = false;
+        if dom.text != self.source {
+            if dom.text != *self.editor.text() {
+                self.editor.set_text(dom.text.clone());
+                self.revision += 1;
+                relayout = true;
+            }
+            self.source = dom.text.clone();
+        }
+
+        if self.font_size != dom.font_size
+            || self.line_height != dom.line_height
+            || self.color != dom.color
+        {
+            self.font_size = dom.font_size;
+            self.line_height = dom.line_height;
+            self.color = dom.color;
+            *self.layout.get_mut() = Layout::default();
+            relayout = true;
+        }
+        let redraw =
+            self.background != dom.background || self.selection_color != dom.selection_color;
+        self.background = dom.background;
+        self.selection_color = dom.selection_color;
+        self.on_change = dom.on_change.clone();
+
+        if let Some(handle) = cache_invalidator {
+            if relayout {
+                handle.relayout_next_frame();
+            } else if redraw {
+                handle.redraw_next_frame();
+            }
+        }
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let width = constraints.max_width();
+        let height = self.layout(Self::text_width([width, 0.0])).height() + 2.0 * PADDING;
+        [
+            width,
+            height.clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let position = event.mouse_position();
+        let inside = position.is_some_and(|position| {
+            0.0 <= position[0]
+                && position[0] <= bounds[0]
+                && 0.0 <= position[1]
+                && position[1] <= bounds[1]
+        });
+
+        if position.is_some() && inside != self.hovered {
+            // only reset the pointer when leaving, other widgets may have changed it
+            ctx.set_cursor_icon(if inside {
+                CursorIcon::Text
+            } else {
+                CursorIcon::Default
+            });
+            self.hovered = inside;
+        }
+
+        let selection = self.editor.selection();
+        let focused = self.focused;
+
+        if event.on_click(|_| ()).is_some() {
+            self.focused = inside;
+            self.selecting = inside;
+            self.goal_x = None;
+            if let Some(position) = position.filter(|_| inside) {
+                let index = self.hit_in_view(bounds, position, ctx);
+                self.editor.move_to(index, false);
+            }
+        }
+        if event.on_click_released(|_| ()).is_some() {
+            self.selecting = false;
+        }
+        if self.selecting
+            && event.on_drag(|_, _| ()).is_some()
+            && let Some(position) = position
+        {
+            let index = self.hit_in_view(bounds, position, ctx);
+            self.editor.move_to(index, true);
+            self.scroll_to_caret(bounds, ctx);
+        }
+
+        if inside && let Some(delta) = event.on_scroll(|delta| delta) {
+            let scroll = (self.scroll - delta[1]).clamp(0.0, self.max_scroll(bounds));
+            if scroll != self.scroll {
+                self.scroll = scroll;
+                cache_invalidator.redraw_next_frame();
+            }
+        }
+
+        let mut edited = false;
+        if self.focused
+            && let Some(Some(changed)) = event
+                .on_key_down(|key| self.key_input(key, bounds, ctx))
+                .or_else(|| event.on_ime(|ime| self.ime_input(ime)))
+        {
+            edited = changed;
+            if self.focused {
+                self.scroll_to_caret(bounds, ctx);
+            }
+        }
+
+        // a click or Escape moved focus
+        self.ime
+            .follow_focus(focused, self.focused, ImePurpose::Normal, ctx);
+
+        if edited {
+            self.revision += 1;
+            cache_invalidator.relayout_next_frame();
+            return self
+                .on_change
+                .as_ref()
+                .map(|on_change| on_change(self.editor.text()));
+        }
+        if selection != self.editor.selection() || focused != self.focused {
+            cache_invalidator.redraw_next_frame();
+        }
+        None
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        if bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return render_node;
+        }
+
+        let background = SolidBox {
+            color: self.background,
+        };
+        if let Some(node) = style_node(&background, bounds, ctx) {
+            render_node.push_child(node, Matrix4::identity());
+        }
+
+        // the text may have shrunk since scrolling
+        let scroll = self.scroll.min(self.max_scroll(bounds));
+        let selection = self.editor.selection();
+        let range = selection.range();
+        let rope = self.editor.text();
+
+        let mut guard = self.layout(Self::text_width(bounds));
+        let layout = &mut *guard;
+        let constraints = line_constraints(layout.width);
+        let (mut line, mut top) = layout.line_at(scroll);
+        let mut visible = FxHashSet::default();
+        while line < layout.heights.len() && top < scroll + bounds[1] {
+            let y = PADDING + top - scroll;
+            let line_start = rope.line_to_char(line);
+            let line_end = self.editor.line_end(line);
+            let (text, height) = self.shape_line(layout, line, ctx);
+
+            // the selection goes under the text
+            if range.start < line_end && line_start < range.end {
+                let line_byte = rope.line_to_byte(line);
+                let start = rope.char_to_byte(range.start.max(line_start)) - line_byte;
+                let end = rope.char_to_byte(range.end.min(line_end)) - line_byte;
+                let highlight = SolidBox {
+                    color: self.selection_color,
+                };
+                for [min, max] in text.range_rects(&constraints, start..end, ctx) {
+                    if let Some(node) =
+                        style_node(&highlight, [max[0] - min[0], max[1] - min[1]], ctx)
+                    {
+                        render_node.push_child(node, translation(PADDING + min[0], y + min[1]));
+                    }
+                }
+            }
+
+            if let Some(rect) = text.required_region(&constraints, ctx)
+                && let Some(node) = style_node(text, [rect.width(), rect.height()], ctx)
+            {
+                render_node.push_child(node, translation(PADDING, y));
+            }
+
+            visible.insert(self.line_text(line));
+            top += height;
+            line += 1;
+        }
+        // lines out of view are shaped again when they come back
+        layout.shaped.retain(|content, _| visible.contains(content));
+
+        if self.focused {
+            let [[x, y0], [_, y1]] = self.caret_rect(layout, selection.head, ctx);
+            let caret = SolidBox { color: self.color };
+            if let Some(node) = style_node(&caret, [1.0, y1 - y0], ctx) {
+                render_node.push_child(node, translation(PADDING + x, PADDING + y0 - scroll));
+            }
+        }
+        drop(guard);
+
+        // the layer clips lines that stick out of the view
+        render_node.with_layer_cache(&self.layer, bounds)
+    }
+
+    fn accepts_focus(&self) -> bool {
+        true
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    fn set_focused(
+        &mut self,
+        focused: bool,
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) {
+        self.ime
+            .follow_focus(self.focused, focused, ImePurpose::Normal, ctx);
+        self.focused = focused;
+        self.selecting = false;
+        self.goal_x = None;
+        cache_invalidator.redraw_next_frame();
+    }
+
+    // Tab inserts a tab character
+    fn captures_tab(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_found_by_height() {
+        let layout = Layout {
+            heights: vec![20.0, 40.0, 20.0],
+            ..Layout::default()
+        };
+        assert_eq!(layout.height(), 80.0);
+        assert_eq!(layout.top(2), 60.0);
+        assert_eq!(layout.line_at(-5.0), (0, 0.0));
+        assert_eq!(layout.line_at(25.0), (1, 20.0));
+        assert_eq!(layout.line_at(59.9), (1, 20.0));
+        assert_eq!(layout.line_at(60.0), (2, 60.0));
+        assert_eq!(layout.line_at(500.0), (2, 60.0));
+    }
+}
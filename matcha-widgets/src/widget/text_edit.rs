@@ -0,0 +1,992 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::editor::{Editor, Rope};
+use crate::style::solid_box::SolidBox;
+use crate::style::text::{Sentence, TextDesc};
+use crate::style::{Style, style_node, translation};
+
+use matcha_core::color::Color;
+use matcha_core::context::WidgetContext;
+use matcha_core::{
+    clipboard,
+    cursor::CursorIcon,
+    device_input::{DeviceInput, Ime, ImePurpose, Key, KeyInput},
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use nalgebra::Matrix4;
+use parking_lot::Mutex;
+use renderer::render_node::{LayerCache, RenderNode};
+use winit::keyboard::NamedKey;
+
+/// Space between the edges of the field and the text.
+const PADDING: f32 = 4.0;
+
+type TextHandler<T> = Arc<dyn Fn(&str) -> T + Send + Sync>;
+
+// MARK: DOM
+
+/// A single-line text field.
+///
+/// The field scrolls horizontally to follow the caret. Line breaks in pasted or committed
+/// text are dropped. The shortcuts are those of [`TextArea`](super::text_area::TextArea);
+/// Enter emits [`on_submit`](Self::on_submit).
+///
+/// In [password mode](Self::password) every character is drawn as the mask character, the
+/// content cannot be copied or cut, the input method is switched to its password purpose and
+/// its composition is not drawn. [`Debug`] output of the field omits the text.
+pub struct TextEdit<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    text: String,
+    style: FieldStyle,
+    on_change: Option<TextHandler<T>>,
+    on_submit: Option<TextHandler<T>>,
+}
+
+impl<T> TextEdit<T> {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            text: text.into(),
+            style: FieldStyle::default(),
+            on_change: None,
+            on_submit: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn font_size(mut self, size: f32) -> Self {
+        self.style.font_size = size;
+        self
+    }
+
+    pub fn line_height(mut self, height: f32) -> Self {
+        self.style.line_height = height;
+        self
+    }
+
+    /// Color of the text and the caret.
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = color;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.style.background = color;
+        self
+    }
+
+    pub fn selection_color(mut self, color: Color) -> Self {
+        self.style.selection_color = color;
+        self
+    }
+
+    /// Masks the text, for passwords and other secrets.
+    pub fn password(mut self, enabled: bool) -> Self {
+        self.style.password = enabled;
+        self
+    }
+
+    /// Character drawn for each character of a password. `'•'` by default.
+    pub fn mask(mut self, mask: char) -> Self {
+        self.style.mask = mask;
+        self
+    }
+
+    /// Shows the password as it is, e.g. while a "show password" toggle is on. Copying stays
+    /// disabled.
+    pub fn reveal(mut self, reveal: bool) -> Self {
+        self.style.reveal = reveal;
+        self
+    }
+
+    /// Called with the new text after every edit, undo and redo.
+    pub fn on_change(mut self, f: impl Fn(&str) -> T + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Arc::new(f));
+        self
+    }
+
+    /// Called with the text when Enter is pressed.
+    pub fn on_submit(mut self, f: impl Fn(&str) -> T + Send + Sync + 'static) -> Self {
+        self.on_submit = Some(Arc::new(f));
+        self
+    }
+}
+
+impl<T> std::fmt::Debug for TextEdit<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("TextEdit");
+        debug.field("label", &self.label);
+        if self.style.password {
+            debug.field("text", &"<hidden>");
+        } else {
+            debug.field("text", &self.text);
+        }
+        debug
+            .field("password", &self.style.password)
+            .field("reveal", &self.style.reveal)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for TextEdit<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                TextEditNode {
+                    source: self.text.clone(),
+                    field: LineField::new(&self.text, self.style.clone()),
+                    on_change: self.on_change.clone(),
+                    on_submit: self.on_submit.clone(),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+/// `text` with its line breaks removed.
+fn single_line(text: &str) -> String {
+    text.chars().filter(|c| !matches!(c, '\n' | '\r')).collect()
+}
+
+// MARK: Field
+
+/// Look of a [`LineField`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FieldStyle {
+    pub(crate) font_size: f32,
+    pub(crate) line_height: f32,
+    pub(crate) color: Color,
+    pub(crate) background: Color,
+    pub(crate) selection_color: Color,
+    pub(crate) password: bool,
+    pub(crate) mask: char,
+    pub(crate) reveal: bool,
+}
+
+impl Default for FieldStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 14.0,
+            line_height: 20.0,
+            color: Color::rgb(0, 0, 0),
+            background: Color::rgb(255, 255, 255),
+            selection_color: Color::rgba(51, 144, 255, 0.3),
+            password: false,
+            mask: '•',
+            reveal: false,
+        }
+    }
+}
+
+/// The text input that enabled the input method last; 0 when none holds it.
+static IME_OWNER: AtomicU64 = AtomicU64::new(0);
+static NEXT_IME_CLAIM: AtomicU64 = AtomicU64::new(1);
+
+/// Turns the input method on and off as a text input gains and loses focus.
+///
+/// A click moves focus from one input to another within a single event, in either order, so
+/// an input only turns the input method off when no other input enabled it since.
+pub(crate) struct ImeClaim(u64);
+
+impl ImeClaim {
+    pub(crate) fn new() -> Self {
+        Self(NEXT_IME_CLAIM.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Enables the input method for text of `purpose`, for an input that gained focus.
+    pub(crate) fn enable(&self, purpose: ImePurpose, ctx: &WidgetContext) {
+        IME_OWNER.store(self.0, Ordering::SeqCst);
+        ctx.set_ime(Some(purpose));
+    }
+
+    /// Disables the input method, for an input that lost focus.
+    pub(crate) fn release(&self, ctx: &WidgetContext) {
+        if IME_OWNER
+            .compare_exchange(self.0, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            ctx.set_ime(None);
+        }
+    }
+
+    /// Enables or disables the input method after focus changed from `was_focused`.
+    pub(crate) fn follow_focus(
+        &self,
+        was_focused: bool,
+        focused: bool,
+        purpose: ImePurpose,
+        ctx: &WidgetContext,
+    ) {
+        match (was_focused, focused) {
+            (false, true) => self.enable(purpose, ctx),
+            (true, false) => self.release(ctx),
+            _ => {}
+        }
+    }
+}
+
+/// What a [`LineField`] did with an input event.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FieldInput {
+    /// the text changed.
+    pub(crate) edited: bool,
+    /// Enter was pressed.
+    pub(crate) submitted: bool,
+}
+
+/// Editing and drawing of a single line of text, shared by the text input widgets.
+pub(crate) struct LineField {
+    editor: Editor,
+    style: FieldStyle,
+
+    focused: bool,
+    hovered: bool,
+    /// the primary button was pressed in the field, dragging extends the selection.
+    selecting: bool,
+    /// text being composed by the input method.
+    preedit: Option<String>,
+    ime: ImeClaim,
+    scroll: f32,
+    /// the displayed string and its shaped text.
+    shaped: Mutex<Option<(String, crate::style::text::Text)>>,
+    layer: LayerCache,
+}
+
+impl std::fmt::Debug for LineField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("LineField");
+        if self.style.password {
+            debug.field("text", &"<hidden>");
+        } else {
+            debug.field("text", &self.text());
+        }
+        debug
+            .field("password", &self.style.password)
+            .field("focused", &self.focused)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where the caret and the composition are in the displayed string.
+struct Display {
+    text: String,
+    /// char index in `text` of the caret.
+    caret: usize,
+    /// chars of composition inserted at the caret.
+    preedit: usize,
+}
+
+impl Display {
+    /// Byte offset in `text` of char `index` of the edited text.
+    fn offset(&self, index: usize) -> usize {
+        let index = if index > self.caret {
+            index + self.preedit
+        } else {
+            index
+        };
+        char_to_byte(&self.text, index)
+    }
+}
+
+fn char_to_byte(text: &str, index: usize) -> usize {
+    text.char_indices()
+        .nth(index)
+        .map_or(text.len(), |(b, _)| b)
+}
+
+impl LineField {
+    pub(crate) fn new(text: &str, style: FieldStyle) -> Self {
+        Self {
+            editor: Editor::new(Rope::from_str(&single_line(text))),
+            style,
+            focused: false,
+            hovered: false,
+            selecting: false,
+            preedit: None,
+            ime: ImeClaim::new(),
+            scroll: 0.0,
+            shaped: Mutex::new(None),
+            layer: LayerCache::new(),
+        }
+    }
+
+    pub(crate) fn text(&self) -> String {
+        self.editor.text().to_string()
+    }
+
+    pub(crate) fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Gives the field keyboard focus or takes it away, as a click inside or outside does.
+    pub(crate) fn set_focused(
+        &mut self,
+        focused: bool,
+        cache_invalidator: &InvalidationHandle,
+        ctx: &WidgetContext,
+    ) {
+        if focused == self.focused {
+            return;
+        }
+        self.focused = focused;
+        self.selecting = false;
+        if !focused {
+            self.preedit = None;
+        }
+        self.ime
+            .follow_focus(!focused, focused, self.ime_purpose(), ctx);
+        cache_invalidator.redraw_next_frame();
+    }
+
+    fn ime_purpose(&self) -> ImePurpose {
+        if self.style.password {
+            ImePurpose::Password
+        } else {
+            ImePurpose::Normal
+        }
+    }
+
+    /// Replaces the text, unless the field already holds it. Returns whether it changed.
+    pub(crate) fn set_text(&mut self, text: &str) -> bool {
+        let text = single_line(text);
+        if *self.editor.text() == text.as_str() {
+            return false;
+        }
+        self.editor.set_text(Rope::from_str(&text));
+        true
+    }
+
+    /// Returns whether the style changed.
+    pub(crate) fn set_style(&mut self, style: &FieldStyle) -> bool {
+        if self.style == *style {
+            return false;
+        }
+        self.style = style.clone();
+        *self.shaped.get_mut() = None;
+        true
+    }
+
+    fn masked(&self) -> bool {
+        self.style.password && !self.style.reveal
+    }
+
+    /// The string drawn in the field: the text, masked or not, with the composition.
+    fn display(&self) -> Display {
+        let text = self.editor.text();
+        let caret = self.editor.selection().head;
+        let chars = |range: std::ops::Range<usize>| -> String {
+            if self.masked() {
+                std::iter::repeat_n(self.style.mask, range.len()).collect()
+            } else {
+                text.slice(range).to_string()
+            }
+        };
+        // password compositions are not shown, the platform may still show its own window
+        let preedit = self.preedit.as_deref().filter(|_| !self.style.password);
+        Display {
+            text: chars(0..caret) + preedit.unwrap_or_default() + &chars(caret..text.len_chars()),
+            caret,
+            preedit: preedit.map_or(0, |preedit| preedit.chars().count()),
+        }
+    }
+
+    /// Runs `f` on the shaped text of `display`.
+    fn with_shaped<R>(&self, display: &str, f: impl FnOnce(&crate::style::text::Text) -> R) -> R {
+        let mut shaped = self.shaped.lock();
+        if shaped.as_ref().is_some_and(|(text, _)| text != display) {
+            *shaped = None;
+        }
+        let (_, text) = shaped.get_or_insert_with(|| {
+            let text = crate::style::text::Text::new(
+                &TextDesc::new(vec![Sentence::new(display).color(self.style.color)])
+                    .font_size(self.style.font_size)
+                    .line_height(self.style.line_height),
+            );
+            (display.to_string(), text)
+        });
+        f(text)
+    }
+
+    /// Char index of the edited text under `x` in field coordinates.
+    fn hit(&self, x: f32, ctx: &WidgetContext) -> usize {
+        let display = self.display();
+        let offset = self.with_shaped(&display.text, |text| {
+            text.hit(
+                &Constraints::unbounded(),
+                [x - PADDING + self.scroll, self.style.line_height / 2.0],
+                ctx,
+            )
+        });
+        let index = display.text[..offset.unwrap_or(0)].chars().count();
+        // a position inside the composition goes to the caret
+        if index > display.caret {
+            index.saturating_sub(display.preedit).max(display.caret)
+        } else {
+            index
+        }
+    }
+
+    fn scroll_to_caret(&mut self, bounds: [f32; 2], ctx: &WidgetContext) {
+        let display = self.display();
+        let caret = char_to_byte(&display.text, display.caret + display.preedit);
+        let (x, width) = self.with_shaped(&display.text, |text| {
+            (
+                text.caret(&Constraints::unbounded(), caret, ctx)
+                    .map_or(0.0, |[[x, _], _]| x),
+                text.required_region(&Constraints::unbounded(), ctx)
+                    .map_or(0.0, |rect| rect.width()),
+            )
+        });
+        let viewport = (bounds[0] - 2.0 * PADDING).max(0.0);
+        if x < self.scroll {
+            self.scroll = x;
+        } else if x > self.scroll + viewport {
+            self.scroll = x - viewport;
+        }
+        self.scroll = self.scroll.clamp(0.0, (width - viewport).max(0.0));
+    }
+
+    /// Applies a key press. Returns `None` if the key does nothing here, otherwise whether
+    /// the text changed.
+    fn key_input(&mut self, key: &KeyInput) -> Option<bool> {
+        let extend = key.shift_held();
+        let command = key.ctrl_held() || key.super_held();
+        let length = self.editor.text().len_chars();
+
+        let handled = match key.logical_key() {
+            Key::Character(c) if command => match c.to_lowercase().as_str() {
+                "a" => {
+                    self.editor.select_all();
+                    Some(false)
+                }
+                "z" if extend => Some(self.editor.redo()),
+                "z" => Some(self.editor.undo()),
+                "y" => Some(self.editor.redo()),
+                // the content of a password never leaves the field
+                "c" | "x" if self.style.password => Some(false),
+                "c" | "x" => {
+                    if self.editor.selection().is_empty() {
+                        Some(false)
+                    } else {
+                        clipboard::write_text(&self.editor.selected_text());
+                        if c.eq_ignore_ascii_case("x") {
+                            self.editor.backspace();
+                        }
+                        Some(c.eq_ignore_ascii_case("x"))
+                    }
+                }
+                "v" => match clipboard::read_text().map(|text| single_line(&text)) {
+                    Some(text) if !text.is_empty() => {
+                        self.editor.insert(&text);
+                        Some(true)
+                    }
+                    _ => Some(false),
+                },
+                _ => None,
+            },
+            Key::Named(named) => match named {
+                NamedKey::Backspace => {
+                    self.editor.backspace();
+                    Some(self.editor.text().len_chars() != length)
+                }
+                NamedKey::Delete => {
+                    self.editor.delete();
+                    Some(self.editor.text().len_chars() != length)
+                }
+                NamedKey::ArrowLeft => {
+                    self.editor.move_left(extend);
+                    Some(false)
+                }
+                NamedKey::ArrowRight => {
+                    self.editor.move_right(extend);
+                    Some(false)
+                }
+                NamedKey::Home => {
+                    self.editor.move_to(0, extend);
+                    Some(false)
+                }
+                NamedKey::End => {
+                    self.editor.move_to(length, extend);
+                    Some(false)
+                }
+                NamedKey::Escape => {
+                    self.focused = false;
+                    Some(false)
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        handled.or_else(|| {
+            let text = key
+                .text()
+                .filter(|text| !command && !text.is_empty() && !text.contains(char::is_control))?;
+            self.editor.insert(text);
+            Some(true)
+        })
+    }
+
+    fn ime_input(&mut self, ime: &Ime) -> Option<bool> {
+        match ime {
+            Ime::Preedit(text, _) => {
+                self.preedit = (!text.is_empty()).then(|| text.clone());
+                Some(false)
+            }
+            Ime::Commit(text) => {
+                self.preedit = None;
+                let text = single_line(text);
+                if text.is_empty() {
+                    return Some(false);
+                }
+                self.editor.insert(&text);
+                Some(true)
+            }
+            Ime::Enabled | Ime::Disabled => {
+                self.preedit = None;
+                Some(false)
+            }
+        }
+    }
+
+    /// Handles mouse, keyboard and input method events for a field of `bounds`.
+    pub(crate) fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        cache_invalidator: &InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> FieldInput {
+        let position = event.mouse_position();
+        let inside = position.is_some_and(|position| is_within(bounds, position));
+
+        if position.is_some() && inside != self.hovered {
+            // only reset the pointer when leaving, other widgets may have changed it
+            ctx.set_cursor_icon(if inside {
+                CursorIcon::Text
+            } else {
+                CursorIcon::Default
+            });
+            self.hovered = inside;
+        }
+
+        let selection = self.editor.selection();
+        let focused = self.focused;
+        let preedit = self.preedit.clone();
+
+        if event.on_click(|_| ()).is_some() {
+            self.focused = inside;
+            self.selecting = inside;
+            if let Some(position) = position.filter(|_| inside) {
+                let index = self.hit(position[0], ctx);
+                self.editor.move_to(index, false);
+            }
+        }
+        if event.on_click_released(|_| ()).is_some() {
+            self.selecting = false;
+        }
+        if self.selecting
+            && event.on_drag(|_, _| ()).is_some()
+            && let Some(position) = position
+        {
+            let index = self.hit(position[0], ctx);
+            self.editor.move_to(index, true);
+        }
+
+        let mut input = FieldInput::default();
+        if self.focused {
+            let edited = event
+                .on_key_down(|key| {
+                    if matches!(key.logical_key(), Key::Named(NamedKey::Enter)) {
+                        input.submitted = true;
+                        return Some(false);
+                    }
+                    self.key_input(key)
+                })
+                .or_else(|| event.on_ime(|ime| self.ime_input(ime)))
+                .flatten();
+            input.edited = edited == Some(true);
+        }
+        if !self.focused {
+            self.preedit = None;
+        }
+        // a click or Escape moved focus
+        self.ime
+            .follow_focus(focused, self.focused, self.ime_purpose(), ctx);
+
+        if selection != self.editor.selection()
+            || focused != self.focused
+            || preedit != self.preedit
+            || input.edited
+        {
+            self.scroll_to_caret(bounds, ctx);
+            cache_invalidator.redraw_next_frame();
+        }
+        input
+    }
+
+    pub(crate) fn height(&self) -> f32 {
+        self.style.line_height + 2.0 * PADDING
+    }
+
+    pub(crate) fn baseline(&self, ctx: &WidgetContext) -> Option<f32> {
+        let display = self.display();
+        self.with_shaped(&display.text, |text| {
+            text.first_baseline(&Constraints::unbounded(), ctx)
+        })
+        .map(|baseline| PADDING + baseline)
+    }
+
+    pub(crate) fn render(&self, bounds: [f32; 2], ctx: &WidgetContext) -> RenderNode {
+        let mut render_node = RenderNode::new();
+        if bounds[0] <= 0.0 || bounds[1] <= 0.0 {
+            return render_node;
+        }
+
+        let background = SolidBox {
+            color: self.style.background,
+        };
+        if let Some(node) = style_node(&background, bounds, ctx) {
+            render_node.push_child(node, Matrix4::identity());
+        }
+
+        let display = self.display();
+        let selection = self.editor.selection();
+        let range = selection.range();
+        let x = PADDING - self.scroll;
+        let y = PADDING;
+
+        self.with_shaped(&display.text, |text| {
+            let constraints = Constraints::unbounded();
+
+            if !range.is_empty() {
+                let start = display.offset(range.start);
+                let end = display.offset(range.end);
+                let highlight = SolidBox {
+                    color: self.style.selection_color,
+                };
+                for [min, max] in text.range_rects(&constraints, start..end, ctx) {
+                    if let Some(node) =
+                        style_node(&highlight, [max[0] - min[0], max[1] - min[1]], ctx)
+                    {
+                        render_node.push_child(node, translation(x + min[0], y + min[1]));
+                    }
+                }
+            }
+
+            if let Some(rect) = text.required_region(&constraints, ctx)
+                && let Some(node) = style_node(text, [rect.width(), rect.height()], ctx)
+            {
+                render_node.push_child(node, translation(x, y));
+            }
+
+            if display.preedit > 0 {
+                // underline the composition
+                let start = char_to_byte(&display.text, display.caret);
+                let end = char_to_byte(&display.text, display.caret + display.preedit);
+                let underline = SolidBox {
+                    color: self.style.color,
+                };
+                for [min, max] in text.range_rects(&constraints, start..end, ctx) {
+                    if let Some(node) = style_node(&underline, [max[0] - min[0], 1.0], ctx) {
+                        render_node.push_child(node, translation(x + min[0], y + max[1] - 1.0));
+                    }
+                }
+            }
+
+            if self.focused {
+                let offset = char_to_byte(&display.text, display.caret + display.preedit);
+                let [[caret_x, y0], [_, y1]] = text
+                    .caret(&constraints, offset, ctx)
+                    .unwrap_or([[0.0, 0.0], [0.0, self.style.line_height]]);
+                let caret = SolidBox {
+                    color: self.style.color,
+                };
+                if let Some(node) = style_node(&caret, [1.0, y1 - y0], ctx) {
+                    render_node.push_child(node, translation(x + caret_x, y + y0));
+                }
+            }
+        });
+
+        // the layer clips text scrolled out of the field
+        render_node.with_layer_cache(&self.layer, bounds)
+    }
+}
+
+fn is_within(bounds: [f32; 2], position: [f32; 2]) -> bool {
+    (0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1])
+}
+
+// MARK: Widget
+
+pub struct TextEditNode<T> {
+    /// text of the last dom, to tell app changes from echoed edits.
+    source: String,
+    field: LineField,
+    on_change: Option<TextHandler<T>>,
+    on_submit: Option<TextHandler<T>>,
+}
+
+impl<T> std::fmt::Debug for TextEditNode<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TextEditNode")
+            .field("field", &self.field)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<TextEdit<T>, T, ()> for TextEditNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a TextEdit<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        let mut redraw = false;
+        if dom.text != self.source {
+            redraw |= self.field.set_text(&dom.text);
+            self.source = dom.text.clone();
+        }
+        let relayout = self.field.set_style(&dom.style);
+        self.on_change = dom.on_change.clone();
+        self.on_submit = dom.on_submit.clone();
+
+        if let Some(handle) = cache_invalidator {
+            if relayout {
+                handle.relayout_next_frame();
+            } else if redraw {
+                handle.redraw_next_frame();
+            }
+        }
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        [
+            constraints.max_width(),
+            self.field
+                .height()
+                .clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn baseline(
+        &self,
+        _constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> Option<f32> {
+        self.field.baseline(ctx)
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let input = self
+            .field
+            .device_input(bounds, event, &cache_invalidator, ctx);
+
+        if input.edited {
+            let text = self.field.text();
+            return self.on_change.as_ref().map(|on_change| on_change(&text));
+        }
+        if input.submitted {
+            let text = self.field.text();
+            return self.on_submit.as_ref().map(|on_submit| on_submit(&text));
+        }
+        None
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        self.field.render(bounds, ctx)
+    }
+
+    fn accepts_focus(&self) -> bool {
+        true
+    }
+
+    fn is_focused(&self) -> bool {
+        self.field.is_focused()
+    }
+
+    fn set_focused(
+        &mut self,
+        focused: bool,
+        cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) {
+        self.field.set_focused(focused, &cache_invalidator, ctx);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    // IME_OWNER is process-wide, tests that move focus take turns
+    static IME_LOCK: Mutex<()> = Mutex::new(());
+
+    fn password_field(text: &str) -> LineField {
+        let style = FieldStyle {
+            password: true,
+            mask: '*',
+            ..FieldStyle::default()
+        };
+        LineField::new(text, style)
+    }
+
+    #[test]
+    fn passwords_are_masked() {
+        let mut field = password_field("héllo");
+        assert_eq!(field.display().text, "*****");
+
+        field.preedit = Some("ka".to_string());
+        assert_eq!(field.display().text, "*****");
+        assert_eq!(field.display().preedit, 0);
+
+        field.style.reveal = true;
+        assert_eq!(field.display().text, "héllo");
+
+        field.style.password = false;
+        field.editor.move_to(1, false);
+        let display = field.display();
+        assert_eq!(display.text, "hkaéllo");
+        assert_eq!(display.offset(2), "hkaé".len());
+    }
+
+    #[test]
+    fn passwords_stay_out_of_debug_output() {
+        let dom = TextEdit::<()>::new("secret").password(true).reveal(true);
+        assert!(!format!("{dom:?}").contains("secret"));
+        assert!(!format!("{:?}", password_field("secret")).contains("secret"));
+        assert!(format!("{:?}", TextEdit::<()>::new("plain")).contains("plain"));
+    }
+
+    #[test]
+    fn line_breaks_are_dropped() {
+        assert_eq!(single_line("a\r\nb\nc"), "abc");
+        let mut field = LineField::new("a\nb", FieldStyle::default());
+        assert_eq!(field.text(), "ab");
+        assert!(!field.set_text("a\r\nb"));
+        assert!(field.set_text("c"));
+    }
+
+    #[test]
+    fn moving_focus_keeps_the_input_method_on() {
+        let _ime = IME_LOCK.lock();
+        let test = matcha_core::test_kit::TestContext::builder().build();
+        let ctx = test.widget_context();
+        let (a, b) = (ImeClaim::new(), ImeClaim::new());
+
+        // the newly focused input may hear of the click before the one losing focus
+        a.follow_focus(false, true, ImePurpose::Normal, ctx);
+        b.follow_focus(false, true, ImePurpose::Normal, ctx);
+        a.follow_focus(true, false, ImePurpose::Normal, ctx);
+        assert_eq!(IME_OWNER.load(Ordering::SeqCst), b.0);
+
+        b.follow_focus(true, false, ImePurpose::Normal, ctx);
+        assert_eq!(IME_OWNER.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    // the lock only keeps other tests out, nothing else in this runtime takes it
+    #[allow(clippy::await_holding_lock)]
+    async fn typed_text_reaches_the_field() {
+        use matcha_core::{
+            automation::UiDriver,
+            context::ApplicationContext,
+            ui::component::{Component, ModelAccessor},
+        };
+
+        let _ime = IME_LOCK.lock();
+        let (instance, adapter, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let gpu = gpu_utils::gpu::Gpu::from_device(
+            instance,
+            adapter,
+            device,
+            queue,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+        );
+        let form: Component<String, (), String> =
+            Component::new(None, String::new(), |name: &String| {
+                Box::new(
+                    TextEdit::new(name.clone())
+                        .label("name")
+                        .on_change(str::to_string),
+                ) as Box<dyn Dom<String>>
+            })
+            .event_fn(
+                |name: String, model: &ModelAccessor<String>, _: &ApplicationContext| {
+                    let (model, stored) = (model.clone(), name.clone());
+                    tokio::spawn(async move { model.update(|model| *model = stored).await });
+                    Some(name)
+                },
+            );
+        let model = form.model_accessor();
+        let mut driver = UiDriver::new(gpu, form, [400, 300]).await.unwrap();
+
+        // typing needs focus
+        driver.type_text("x");
+        assert!(driver.take_events().is_empty());
+
+        driver.click_label("name").unwrap();
+        driver.type_text("hi");
+        driver.settle().await.unwrap();
+        assert_eq!(driver.take_events(), ["h", "hi"]);
+        assert_eq!(model.read(String::clone).await, "hi");
+    }
+}
@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use crate::style::Style;
+use matcha_core::metrics::{Arrangement, Constraints};
+use matcha_core::{
+    color::Color,
+    context::WidgetContext,
+    device_input::DeviceInput,
+    toast::{Severity, ToastId, ToastState, ToastSubscription},
+    ui::{
+        AnyWidgetFrame, Background, ChildFrameLinker, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+};
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+
+use crate::style::{
+    drawn_node,
+    solid_box::SolidBox,
+    text::{Sentence, Text, TextWeight, measured_text},
+    translation,
+};
+
+const CARD_WIDTH: f32 = 320.0;
+const MARGIN: f32 = 16.0;
+const GAP: f32 = 8.0;
+const PADDING: f32 = 12.0;
+const ACCENT_WIDTH: f32 = 4.0;
+const TITLE_FONT_SIZE: f32 = 14.0;
+const TITLE_LINE_HEIGHT: f32 = 20.0;
+const BODY_FONT_SIZE: f32 = 13.0;
+const BODY_LINE_HEIGHT: f32 = 18.0;
+const TEXT_GAP: f32 = 2.0;
+
+const CARD_COLOR: Color = Color::RgbaF32 {
+    r: 0.98,
+    g: 0.98,
+    b: 0.98,
+    a: 1.0,
+};
+const BORDER_COLOR: Color = Color::RgbaF32 {
+    r: 0.75,
+    g: 0.75,
+    b: 0.75,
+    a: 1.0,
+};
+const TITLE_COLOR: Color = Color::RgbaF32 {
+    r: 0.1,
+    g: 0.1,
+    b: 0.1,
+    a: 1.0,
+};
+const BODY_COLOR: Color = Color::RgbaF32 {
+    r: 0.3,
+    g: 0.3,
+    b: 0.3,
+    a: 1.0,
+};
+
+fn accent_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Info => Color::RgbaF32 {
+            r: 0.20,
+            g: 0.47,
+            b: 0.90,
+            a: 1.0,
+        },
+        Severity::Success => Color::RgbaF32 {
+            r: 0.18,
+            g: 0.65,
+            b: 0.32,
+            a: 1.0,
+        },
+        Severity::Warning => Color::RgbaF32 {
+            r: 0.93,
+            g: 0.62,
+            b: 0.10,
+            a: 1.0,
+        },
+        Severity::Error => Color::RgbaF32 {
+            r: 0.85,
+            g: 0.20,
+            b: 0.18,
+            a: 1.0,
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToastCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl ToastCorner {
+    fn is_top(self) -> bool {
+        matches!(self, Self::TopLeft | Self::TopRight)
+    }
+
+    fn is_left(self) -> bool {
+        matches!(self, Self::TopLeft | Self::BottomLeft)
+    }
+}
+
+// MARK: DOM
+
+/// Shows the toasts posted with `ApplicationContext::notify` above `content`.
+///
+/// Toasts stack in a corner of the widget, newest closest to the corner. They slide in when
+/// posted and out when they expire or are clicked. Wrap the root view of a window with it.
+pub struct ToastOverlay<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    content: Box<dyn Dom<T>>,
+    corner: ToastCorner,
+}
+
+impl<T: 'static> ToastOverlay<T> {
+    pub fn new(content: impl Dom<T>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            content: Box::new(content),
+            corner: ToastCorner::default(),
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    pub fn corner(mut self, corner: ToastCorner) -> Self {
+        self.corner = corner;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for ToastOverlay<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0],
+                ToastOverlayNode {
+                    corner: self.corner,
+                    linker: None,
+                    subscription: None,
+                    cards: Mutex::new(Vec::new()),
+                    texts: Mutex::new(HashMap::new()),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct ToastOverlayNode {
+    corner: ToastCorner,
+    linker: Option<ChildFrameLinker>,
+    subscription: Option<ToastSubscription>,
+    /// cards of the last render, for hit testing.
+    cards: Mutex<Vec<Card>>,
+    /// shaped title and body of the shown toasts.
+    texts: Mutex<HashMap<ToastId, CardText>>,
+}
+
+struct Card {
+    id: ToastId,
+    origin: [f32; 2],
+    size: [f32; 2],
+}
+
+impl Card {
+    fn contains(&self, position: [f32; 2]) -> bool {
+        self.origin[0] <= position[0]
+            && position[0] <= self.origin[0] + self.size[0]
+            && self.origin[1] <= position[1]
+            && position[1] <= self.origin[1] + self.size[1]
+    }
+}
+
+struct CardText {
+    title: (Text, [f32; 2]),
+    body: Option<(Text, [f32; 2])>,
+}
+
+impl CardText {
+    fn new(state: &ToastState, ctx: &WidgetContext) -> Self {
+        let title = measured_text(
+            Sentence::new(&state.toast.title)
+                .color(TITLE_COLOR)
+                .weight(TextWeight::BOLD),
+            TITLE_FONT_SIZE,
+            TITLE_LINE_HEIGHT,
+            text_width(),
+            ctx,
+        );
+        let body = (!state.toast.body.is_empty()).then(|| {
+            measured_text(
+                Sentence::new(&state.toast.body).color(BODY_COLOR),
+                BODY_FONT_SIZE,
+                BODY_LINE_HEIGHT,
+                text_width(),
+                ctx,
+            )
+        });
+        Self { title, body }
+    }
+
+    fn card_height(&self) -> f32 {
+        let body = self
+            .body
+            .as_ref()
+            .map_or(0.0, |(_, size)| TEXT_GAP + size[1]);
+        2.0 * PADDING + self.title.1[1] + body
+    }
+}
+
+fn text_width() -> f32 {
+    CARD_WIDTH - ACCENT_WIDTH - 2.0 * PADDING
+}
+
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+impl ToastOverlayNode {
+    fn render_card(
+        &self,
+        state: &ToastState,
+        text: &CardText,
+        size: [f32; 2],
+        ctx: &WidgetContext,
+    ) -> Option<RenderNode> {
+        drawn_node(size, ctx, |encoder, region| {
+            // border, then the card inset by one pixel, then the severity accent on the left
+            SolidBox {
+                color: BORDER_COLOR,
+            }
+            .draw(encoder, region, size, [0.0, 0.0], ctx);
+            SolidBox { color: CARD_COLOR }.draw(
+                encoder,
+                region,
+                [size[0] - 2.0, size[1] - 2.0],
+                [1.0, 1.0],
+                ctx,
+            );
+            SolidBox {
+                color: accent_color(state.toast.severity),
+            }
+            .draw(
+                encoder,
+                region,
+                [ACCENT_WIDTH, size[1] - 2.0],
+                [1.0, 1.0],
+                ctx,
+            );
+
+            let left = ACCENT_WIDTH + PADDING;
+            let (title, title_size) = &text.title;
+            title.draw(
+                encoder,
+                region,
+                [text_width(), title_size[1]],
+                [left, PADDING],
+                ctx,
+            );
+            if let Some((body, body_size)) = &text.body {
+                body.draw(
+                    encoder,
+                    region,
+                    [text_width(), body_size[1]],
+                    [left, PADDING + title_size[1] + TEXT_GAP],
+                    ctx,
+                );
+            }
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<ToastOverlay<T>, T, ()> for ToastOverlayNode {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a ToastOverlay<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        if self.corner != dom.corner {
+            self.corner = dom.corner;
+            if let Some(handle) = cache_invalidator {
+                handle.redraw_next_frame();
+            }
+        }
+
+        vec![(&*dom.content, (), 0)]
+    }
+
+    fn link_owned_children(&mut self, linker: ChildFrameLinker) {
+        self.linker = Some(linker);
+    }
+
+    fn on_mount(&mut self, ctx: &WidgetContext) {
+        if let Some(linker) = self.linker.clone() {
+            self.subscription = ctx.subscribe_toasts(move || linker.redraw_next_frame());
+        }
+    }
+
+    fn on_unmount(&mut self) {
+        self.subscription = None;
+        self.texts.get_mut().clear();
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        if let Some((content, _)) = children.first() {
+            content.measure(constraints, ctx)
+        } else {
+            [0.0, 0.0]
+        }
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+    }
+
+    fn device_input(
+        &mut self,
+        _bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        if let Some(position) = event.mouse_position() {
+            let hit = self
+                .cards
+                .get_mut()
+                .iter()
+                .find(|card| card.contains(position))
+                .map(|card| card.id);
+            if let Some(id) = hit {
+                // toasts cover the content below them
+                if event.on_click(|_| ()).is_some() {
+                    ctx.dismiss_toast(id);
+                }
+                return None;
+            }
+        }
+
+        if let Some((content, _, arrangement)) = children.first_mut() {
+            let content_event = event.transform(arrangement.affine);
+            return content.device_input(&content_event, ctx);
+        }
+        None
+    }
+
+    fn is_inside(
+        &self,
+        bounds: [f32; 2],
+        position: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _ctx: &WidgetContext,
+    ) -> bool {
+        ((0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1]))
+            || self.cards.lock().iter().any(|card| card.contains(position))
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        let mut render_node = RenderNode::new();
+
+        if let Some((content, _, arrangement)) = children.first() {
+            render_node.push_child(content.render(background, ctx), arrangement.affine);
+        }
+
+        let toasts = ctx.toasts();
+        let mut texts = self.texts.lock();
+        texts.retain(|id, _| toasts.iter().any(|state| state.id == *id));
+
+        let mut cards = self.cards.lock();
+        cards.clear();
+
+        // newest first, closest to the corner
+        let mut stacked = 0.0;
+        for state in toasts.iter().rev() {
+            let text = texts
+                .entry(state.id)
+                .or_insert_with(|| CardText::new(state, ctx));
+            let size = [CARD_WIDTH, text.card_height()];
+            let shown = ease_out(state.visibility);
+
+            // slide in from (and out to) the side of the corner
+            let slide = (1.0 - shown) * (CARD_WIDTH + MARGIN);
+            let x = if self.corner.is_left() {
+                MARGIN - slide
+            } else {
+                bounds[0] - MARGIN - CARD_WIDTH + slide
+            };
+            let y = if self.corner.is_top() {
+                MARGIN + stacked
+            } else {
+                bounds[1] - MARGIN - stacked - size[1]
+            };
+            // closing toasts give up their place gradually
+            stacked += (size[1] + GAP) * if state.closing { shown } else { 1.0 };
+
+            if let Some(card_node) = self.render_card(state, text, size, ctx) {
+                render_node.push_child(card_node, translation(x, y));
+            }
+            if !state.closing {
+                cards.push(Card {
+                    id: state.id,
+                    origin: [x, y],
+                    size,
+                });
+            }
+        }
+
+        render_node
+    }
+}
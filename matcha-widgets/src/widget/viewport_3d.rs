@@ -0,0 +1,401 @@
+use std::sync::Arc;
+
+use log::warn;
+use matcha_core::{
+    context::WidgetContext,
+    device_input::DeviceInput,
+    frame_clock::FrameTime,
+    metrics::{Arrangement, Constraints},
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle, PrepareFuture},
+    },
+};
+use parking_lot::Mutex;
+use renderer::render_node::RenderNode;
+
+/// Size of a viewport without a size when its parent does not bound it.
+const DEFAULT_SIZE: [f32; 2] = [300.0, 150.0];
+
+// MARK: Frame
+
+/// What the scene renderer of a [`Viewport3D`] gets to draw a frame.
+pub struct ViewportFrame<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    /// Records the scene. It is submitted together with the copy of `target` into the
+    /// window's texture atlas; work submitted earlier through `queue` runs before it.
+    pub encoder: &'a mut wgpu::CommandEncoder,
+    /// The viewport's own texture. It keeps the previous frame until the viewport is resized,
+    /// so the scene should clear it.
+    pub target: &'a wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+    /// Size of `target` in physical pixels: the viewport's size times the scale factor.
+    pub size: [u32; 2],
+    pub time: FrameTime,
+}
+
+type Scene = dyn Fn(&mut ViewportFrame) + Send + Sync;
+type InputHandler<T> = dyn Fn(&DeviceInput, [f32; 2], &WidgetContext) -> Option<T> + Send + Sync;
+
+// MARK: DOM
+
+/// A widget that embeds a scene drawn with wgpu, e.g. the view of a game engine or a CAD
+/// model, in the layout.
+///
+/// The scene renderer draws into a texture of the viewport's size, which is composited like
+/// any other widget:
+///
+/// ```ignore
+/// Viewport3D::new(move |frame| scene.lock().draw(frame.encoder, frame.target, frame.size))
+///     .continuous(true)
+///     .on_input(|input, size, ctx| {
+///         input.on_click(|| ctx.lock_pointer());
+///         input.on_pointer_motion(|delta| Message::Orbit(delta))
+///     })
+/// ```
+///
+/// The scene is drawn again whenever the view is rebuilt, or on every frame with
+/// [`continuous`](Self::continuous). Input reaches [`on_input`](Self::on_input) in the
+/// viewport's coordinates, including relative motion while the pointer is locked.
+pub struct Viewport3D<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+
+    size: Option<[f32; 2]>,
+    continuous: bool,
+    scene: Arc<Scene>,
+    on_input: Option<Arc<InputHandler<T>>>,
+}
+
+impl<T: 'static> Viewport3D<T> {
+    pub fn new(scene: impl Fn(&mut ViewportFrame) + Send + Sync + 'static) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            size: None,
+            continuous: false,
+            scene: Arc::new(scene),
+            on_input: None,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Size of the viewport. Without it the viewport fills the space its parent allows.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.size = Some([width, height]);
+        self
+    }
+
+    /// Draws the scene on every frame while the viewport is visible, for animated scenes.
+    pub fn continuous(mut self, continuous: bool) -> Self {
+        self.continuous = continuous;
+        self
+    }
+
+    /// Handles every input the viewport receives, with the viewport's size.
+    pub fn on_input<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DeviceInput, [f32; 2], &WidgetContext) -> Option<T> + Send + Sync + 'static,
+    {
+        self.on_input = Some(Arc::new(f));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for Viewport3D<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![],
+                vec![],
+                Viewport3DNode {
+                    size: self.size,
+                    continuous: self.continuous,
+                    scene: self.scene.clone(),
+                    on_input: self.on_input.clone(),
+                    target: Mutex::new(None),
+                    rendered: Arc::new(tokio::sync::Notify::new()),
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct Viewport3DNode<T> {
+    size: Option<[f32; 2]>,
+    continuous: bool,
+    scene: Arc<Scene>,
+    on_input: Option<Arc<InputHandler<T>>>,
+    /// the texture the scene is drawn into, recreated when the size changes.
+    target: Mutex<Option<Target>>,
+    /// signalled after each render, so a continuous viewport asks for the next frame.
+    rendered: Arc<tokio::sync::Notify>,
+}
+
+struct Target {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    size: [u32; 2],
+}
+
+impl Target {
+    fn new(device: &wgpu::Device, size: [u32; 2], format: wgpu::TextureFormat) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Viewport3D Target"),
+            size: wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            size,
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<Viewport3D<T>, T, ()> for Viewport3DNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a Viewport3D<T>,
+        cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        if let Some(handle) = cache_invalidator {
+            if self.size != dom.size {
+                handle.relayout_next_frame();
+            } else {
+                // the scene depends on app state the closure cannot be compared by
+                handle.redraw_next_frame();
+            }
+        }
+        self.size = dom.size;
+        self.continuous = dom.continuous;
+        self.scene = dom.scene.clone();
+        self.on_input = dom.on_input.clone();
+
+        // No children
+        vec![]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        _: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        let max = constraints.max_finite();
+        let size = self
+            .size
+            .unwrap_or_else(|| std::array::from_fn(|axis| max[axis].unwrap_or(DEFAULT_SIZE[axis])));
+        [
+            size[0].clamp(constraints.min_width(), constraints.max_width()),
+            size[1].clamp(constraints.min_height(), constraints.max_height()),
+        ]
+    }
+
+    fn arrange(
+        &self,
+        _bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        _children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        self.on_input.as_ref()?(event, bounds, ctx)
+    }
+
+    fn render(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        _background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        self.rendered.notify_one();
+
+        let scale_factor = ctx.dpi().unwrap_or(1.0) as f32;
+        let size = [
+            (bounds[0] * scale_factor).ceil() as u32,
+            (bounds[1] * scale_factor).ceil() as u32,
+        ];
+        if size[0] == 0 || size[1] == 0 {
+            return RenderNode::new();
+        }
+        let device = ctx.device();
+        let queue = ctx.queue();
+        let format = ctx.texture_format();
+
+        let Ok(region) = ctx.texture_atlas().allocate(&device, &queue, size) else {
+            return RenderNode::new();
+        };
+
+        let mut target = self.target.lock();
+        if target
+            .as_ref()
+            .is_some_and(|target| target.size != size || target.texture.format() != format)
+        {
+            *target = None;
+        }
+        let target = target.get_or_insert_with(|| Target::new(&device, size, format));
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Viewport3D Render Encoder"),
+        });
+        (self.scene)(&mut ViewportFrame {
+            device: &device,
+            queue: &queue,
+            encoder: &mut encoder,
+            target: &target.view,
+            format,
+            size,
+            time: ctx.frame(),
+        });
+        if let Err(e) = region.copy_from_texture(&mut encoder, &target.texture) {
+            warn!("Viewport3D::render: failed to copy the scene into the atlas: {e}");
+        }
+        queue.submit(Some(encoder.finish()));
+
+        RenderNode::new().with_texture(region, bounds, nalgebra::Matrix4::identity())
+    }
+
+    fn update_gpu_device(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) {
+        *self.target.get_mut() = None;
+    }
+
+    fn prepare(&mut self, _bounds: [f32; 2], _ctx: &WidgetContext) -> Option<PrepareFuture> {
+        if !self.continuous {
+            return None;
+        }
+        // completing redraws the widget, so each rendered frame requests the next one
+        let rendered = self.rendered.clone();
+        Some(Box::pin(async move { rendered.notified().await }))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use matcha_core::test_kit::TestContext;
+    use utils::back_prop_dirty::BackPropDirty;
+
+    use super::*;
+
+    // a viewport that records the size of every frame its scene draws
+    fn recording_viewport() -> (Viewport3D<()>, Arc<Mutex<Vec<[u32; 2]>>>) {
+        let sizes = Arc::new(Mutex::new(Vec::new()));
+        let viewport = Viewport3D::new({
+            let sizes = sizes.clone();
+            move |frame| sizes.lock().push(frame.size)
+        });
+        (viewport, sizes)
+    }
+
+    fn background_view(test: &TestContext) -> wgpu::TextureView {
+        let gpu = test.resources().unwrap().gpu();
+        gpu.device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: Some("Viewport3D Test Background"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    #[test]
+    fn the_scene_gets_the_arranged_size_in_pixels() {
+        let test = TestContext::builder().scale_factor(2.0).noop_gpu().build();
+        let ctx = test.widget_context();
+        let view = background_view(&test);
+        let (viewport, sizes) = recording_viewport();
+
+        let mut widget = viewport.build_widget_tree();
+        widget.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        widget.arrange([150.5, 100.0], ctx);
+        let _ = widget.render(Background::new(&view, [0.0, 0.0]), ctx);
+
+        assert_eq!(*sizes.lock(), [[301, 200]]);
+    }
+
+    #[tokio::test]
+    async fn the_scene_reruns_only_when_resized_or_invalidated() {
+        let test = TestContext::builder().noop_gpu().build();
+        let ctx = test.widget_context();
+        let view = background_view(&test);
+        let background = Background::new(&view, [0.0, 0.0]);
+        let (viewport, sizes) = recording_viewport();
+
+        let mut widget = viewport.build_widget_tree();
+        widget.update_dirty_flags(BackPropDirty::new(true), BackPropDirty::new(true));
+        widget.arrange([200.0, 100.0], ctx);
+        let _ = widget.render(background, ctx);
+        let _ = widget.render(background, ctx);
+        assert_eq!(sizes.lock().len(), 1);
+
+        widget.arrange([240.0, 100.0], ctx);
+        let _ = widget.render(background, ctx);
+        let _ = widget.render(background, ctx);
+        assert_eq!(*sizes.lock(), [[200, 100], [240, 100]]);
+
+        widget.invalidate_render_cache();
+        let _ = widget.render(background, ctx);
+        assert_eq!(sizes.lock().len(), 3);
+
+        // a rebuilt view may draw a different scene
+        let (rebuilt, rebuilt_sizes) = recording_viewport();
+        widget.update_widget_tree(&rebuilt).await.unwrap();
+        widget.arrange([240.0, 100.0], ctx);
+        let _ = widget.render(background, ctx);
+        let _ = widget.render(background, ctx);
+        assert_eq!(*rebuilt_sizes.lock(), [[240, 100]]);
+        assert_eq!(sizes.lock().len(), 3);
+    }
+}
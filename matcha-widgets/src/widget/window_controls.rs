@@ -0,0 +1,573 @@
+//! Building blocks of a custom title bar for windows created with `App::decorations(false)`.
+//!
+//! [`WindowDragArea`] moves the window and [`ResizeBorder`] resizes it. [`WindowControlButton`]s
+//! minimize, maximize or restore it; a close button is an ordinary
+//! [`Button`](super::button::Button) whose message calls `close_current_window` in `update_fn`.
+//! Place the buttons next to the drag area rather than inside it, since a press on the area
+//! starts moving the window.
+
+use matcha_core::metrics::{Arrangement, Constraints};
+use matcha_core::{
+    context::WidgetContext,
+    device_input::DeviceInput,
+    ui::{
+        AnyWidgetFrame, Background, Dom, LayoutStyle, Widget, WidgetFrame,
+        widget::{AnyWidget, InvalidationHandle},
+    },
+    window_control::{ResizeDirection, WindowControl, resize_direction},
+};
+use renderer::render_node::RenderNode;
+
+// MARK: DOM
+
+/// Moves the window when `content` is dragged with the primary button, like a title bar.
+/// A double click maximizes or restores the window.
+pub struct WindowDragArea<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    content: Box<dyn Dom<T>>,
+    maximize_on_double_click: bool,
+}
+
+impl<T: 'static> WindowDragArea<T> {
+    pub fn new(content: impl Dom<T>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            content: Box::new(content),
+            maximize_on_double_click: true,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Whether a double click maximizes or restores the window. Enabled by default.
+    pub fn maximize_on_double_click(mut self, enabled: bool) -> Self {
+        self.maximize_on_double_click = enabled;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for WindowDragArea<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0],
+                WindowDragAreaNode {
+                    maximize_on_double_click: self.maximize_on_double_click,
+                    _phantom: std::marker::PhantomData,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+/// Resizes the window when the edges or corners of `content` are dragged with the primary
+/// button. Wrap the whole window content in it; the border lies on top of the content's edges.
+pub struct ResizeBorder<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    content: Box<dyn Dom<T>>,
+    width: f32,
+}
+
+impl<T: 'static> ResizeBorder<T> {
+    pub fn new(content: impl Dom<T>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            content: Box::new(content),
+            width: 6.0,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Width of the grab area along each edge. 6 by default.
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for ResizeBorder<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0],
+                ResizeBorderNode {
+                    width: self.width,
+                    _phantom: std::marker::PhantomData,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+/// Minimizes, maximizes or restores the window when `content` is clicked with the primary
+/// button, like the buttons of a title bar.
+pub struct WindowControlButton<T> {
+    label: Option<String>,
+    layout_style: LayoutStyle,
+    content: Box<dyn Dom<T>>,
+    control: WindowControl,
+}
+
+impl<T: 'static> WindowControlButton<T> {
+    pub fn new(control: WindowControl, content: impl Dom<T>) -> Self {
+        Self {
+            label: None,
+            layout_style: LayoutStyle::default(),
+            content: Box::new(content),
+            control,
+        }
+    }
+
+    /// Padding, margin and size limits applied around the widget.
+    pub fn layout(mut self, layout_style: LayoutStyle) -> Self {
+        self.layout_style = layout_style;
+        self
+    }
+
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Send + Sync + 'static> Dom<T> for WindowControlButton<T> {
+    fn build_widget_tree(&self) -> Box<dyn AnyWidgetFrame<T>> {
+        Box::new(
+            WidgetFrame::new(
+                self.label.clone(),
+                vec![(self.content.build_widget_tree(), ())],
+                vec![0],
+                WindowControlButtonNode {
+                    control: self.control,
+                    _phantom: std::marker::PhantomData,
+                },
+            )
+            .with_layout_style(self.layout_style),
+        )
+    }
+
+    fn layout_style(&self) -> LayoutStyle {
+        self.layout_style
+    }
+}
+
+// MARK: Widget
+
+pub struct WindowDragAreaNode<T> {
+    maximize_on_double_click: bool,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+pub struct ResizeBorderNode<T> {
+    width: f32,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+pub struct WindowControlButtonNode<T> {
+    control: WindowControl,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+fn is_within(bounds: [f32; 2], position: [f32; 2]) -> bool {
+    (0.0..=bounds[0]).contains(&position[0]) && (0.0..=bounds[1]).contains(&position[1])
+}
+
+/// What a press on a [`WindowDragArea`] does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DragAreaPress {
+    Move,
+    Control(WindowControl),
+}
+
+impl<T> WindowDragAreaNode<T> {
+    /// What the `clicks`th consecutive press at `position` does.
+    fn press(&self, bounds: [f32; 2], position: [f32; 2], clicks: u32) -> Option<DragAreaPress> {
+        if !is_within(bounds, position) {
+            return None;
+        }
+        Some(if clicks == 2 && self.maximize_on_double_click {
+            DragAreaPress::Control(WindowControl::ToggleMaximize)
+        } else {
+            DragAreaPress::Move
+        })
+    }
+}
+
+impl<T> ResizeBorderNode<T> {
+    /// The direction a press at `position` resizes the window towards.
+    fn press(&self, bounds: [f32; 2], position: [f32; 2]) -> Option<ResizeDirection> {
+        resize_direction(bounds, position, self.width)
+    }
+}
+
+impl<T> WindowControlButtonNode<T> {
+    /// The control a press at `position` applies to the window.
+    fn press(&self, bounds: [f32; 2], position: [f32; 2]) -> Option<WindowControl> {
+        is_within(bounds, position).then_some(self.control)
+    }
+}
+
+fn measure_content<T: 'static>(
+    constraints: &Constraints,
+    children: &[(&dyn AnyWidget<T>, &())],
+    ctx: &WidgetContext,
+) -> [f32; 2] {
+    if let Some((content, _)) = children.first() {
+        content.measure(constraints, ctx)
+    } else {
+        [0.0, 0.0]
+    }
+}
+
+fn content_input<T: 'static>(
+    event: &DeviceInput,
+    children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+    ctx: &WidgetContext,
+) -> Option<T> {
+    if let Some((content, _, arrangement)) = children.first_mut() {
+        return content.device_input(&event.transform(arrangement.affine), ctx);
+    }
+    None
+}
+
+fn render_content<T: 'static>(
+    children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+    background: Background,
+    ctx: &WidgetContext,
+) -> RenderNode {
+    let mut render_node = RenderNode::new();
+    if let Some((content, _, arrangement)) = children.first() {
+        render_node.push_child(content.render(background, ctx), arrangement.affine);
+    }
+    render_node
+}
+
+impl<T: Send + Sync + 'static> Widget<WindowDragArea<T>, T, ()> for WindowDragAreaNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a WindowDragArea<T>,
+        _cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.maximize_on_double_click = dom.maximize_on_double_click;
+        vec![(&*dom.content, (), 0)]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        measure_content(constraints, children, ctx)
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        if let Some(message) = content_input(event, children, ctx) {
+            return Some(message);
+        }
+
+        let pressed = event
+            .on_click(|count| count)
+            .and_then(|count| self.press(bounds, event.mouse_position()?, count));
+        match pressed {
+            Some(DragAreaPress::Control(control)) => ctx.control_window(control),
+            Some(DragAreaPress::Move) => ctx.drag_window(),
+            None => {}
+        }
+        None
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        render_content(children, background, ctx)
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<ResizeBorder<T>, T, ()> for ResizeBorderNode<T> {
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a ResizeBorder<T>,
+        _cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.width = dom.width;
+        vec![(&*dom.content, (), 0)]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        measure_content(constraints, children, ctx)
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        // a press on the border does not reach the content below it
+        let direction = event
+            .on_click(|_| ())
+            .and_then(|()| event.mouse_position())
+            .and_then(|position| self.press(bounds, position));
+        if let Some(direction) = direction {
+            ctx.drag_resize_window(direction);
+            return None;
+        }
+        content_input(event, children, ctx)
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        render_content(children, background, ctx)
+    }
+}
+
+impl<T: Send + Sync + 'static> Widget<WindowControlButton<T>, T, ()>
+    for WindowControlButtonNode<T>
+{
+    fn update_widget<'a>(
+        &mut self,
+        dom: &'a WindowControlButton<T>,
+        _cache_invalidator: Option<InvalidationHandle>,
+    ) -> Vec<(&'a dyn Dom<T>, (), u128)> {
+        self.control = dom.control;
+        vec![(&*dom.content, (), 0)]
+    }
+
+    fn measure(
+        &self,
+        constraints: &Constraints,
+        children: &[(&dyn AnyWidget<T>, &())],
+        ctx: &WidgetContext,
+    ) -> [f32; 2] {
+        measure_content(constraints, children, ctx)
+    }
+
+    fn arrange(
+        &self,
+        bounds: [f32; 2],
+        _children: &[(&dyn AnyWidget<T>, &())],
+        _ctx: &WidgetContext,
+    ) -> Vec<Arrangement> {
+        vec![Arrangement::new(bounds, nalgebra::Matrix4::identity())]
+    }
+
+    fn device_input(
+        &mut self,
+        bounds: [f32; 2],
+        event: &DeviceInput,
+        children: &mut [(&mut dyn AnyWidget<T>, &mut (), &Arrangement)],
+        _cache_invalidator: InvalidationHandle,
+        ctx: &WidgetContext,
+    ) -> Option<T> {
+        let control = event
+            .on_click(|_| ())
+            .and_then(|()| event.mouse_position())
+            .and_then(|position| self.press(bounds, position));
+        if let Some(control) = control {
+            ctx.control_window(control);
+            return None;
+        }
+        content_input(event, children, ctx)
+    }
+
+    fn render(
+        &self,
+        _bounds: [f32; 2],
+        children: &[(&dyn AnyWidget<T>, &(), &Arrangement)],
+        background: Background,
+        ctx: &WidgetContext,
+    ) -> RenderNode {
+        render_content(children, background, ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: [f32; 2] = [300.0, 32.0];
+
+    fn drag_area(maximize_on_double_click: bool) -> WindowDragAreaNode<()> {
+        WindowDragAreaNode {
+            maximize_on_double_click,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    fn button(control: WindowControl) -> WindowControlButtonNode<()> {
+        WindowControlButtonNode {
+            control,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    #[test]
+    fn pressing_the_drag_area_moves_the_window() {
+        let area = drag_area(true);
+
+        assert_eq!(
+            area.press(BOUNDS, [150.0, 16.0], 1),
+            Some(DragAreaPress::Move)
+        );
+        assert_eq!(area.press(BOUNDS, [0.0, 0.0], 1), Some(DragAreaPress::Move));
+        assert_eq!(
+            area.press(BOUNDS, [300.0, 32.0], 1),
+            Some(DragAreaPress::Move)
+        );
+        assert_eq!(area.press(BOUNDS, [301.0, 16.0], 1), None);
+        assert_eq!(area.press(BOUNDS, [150.0, -1.0], 1), None);
+    }
+
+    #[test]
+    fn double_clicking_the_drag_area_toggles_maximize() {
+        assert_eq!(
+            drag_area(true).press(BOUNDS, [150.0, 16.0], 2),
+            Some(DragAreaPress::Control(WindowControl::ToggleMaximize))
+        );
+        assert_eq!(
+            drag_area(false).press(BOUNDS, [150.0, 16.0], 2),
+            Some(DragAreaPress::Move)
+        );
+    }
+
+    #[test]
+    fn resize_border_covers_edges_and_corners() {
+        let border = ResizeBorderNode::<()> {
+            width: 4.0,
+            _phantom: std::marker::PhantomData,
+        };
+        let bounds = [100.0, 80.0];
+
+        assert_eq!(
+            border.press(bounds, [50.0, 1.0]),
+            Some(ResizeDirection::North)
+        );
+        assert_eq!(
+            border.press(bounds, [50.0, 79.0]),
+            Some(ResizeDirection::South)
+        );
+        assert_eq!(
+            border.press(bounds, [1.0, 40.0]),
+            Some(ResizeDirection::West)
+        );
+        assert_eq!(
+            border.press(bounds, [99.0, 40.0]),
+            Some(ResizeDirection::East)
+        );
+        assert_eq!(
+            border.press(bounds, [1.0, 1.0]),
+            Some(ResizeDirection::NorthWest)
+        );
+        assert_eq!(
+            border.press(bounds, [99.0, 1.0]),
+            Some(ResizeDirection::NorthEast)
+        );
+        assert_eq!(
+            border.press(bounds, [1.0, 79.0]),
+            Some(ResizeDirection::SouthWest)
+        );
+        assert_eq!(
+            border.press(bounds, [99.0, 79.0]),
+            Some(ResizeDirection::SouthEast)
+        );
+        // the content below the border gets the press
+        assert_eq!(border.press(bounds, [50.0, 40.0]), None);
+        assert_eq!(border.press(bounds, [101.0, 40.0]), None);
+    }
+
+    #[test]
+    fn each_button_applies_its_control() {
+        for control in [
+            WindowControl::Minimize,
+            WindowControl::Maximize,
+            WindowControl::Restore,
+            WindowControl::ToggleMaximize,
+        ] {
+            let button = button(control);
+            assert_eq!(button.press([24.0, 24.0], [12.0, 12.0]), Some(control));
+            assert_eq!(button.press([24.0, 24.0], [30.0, 12.0]), None);
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub use matcha_core as core;
+pub use matcha_macros::view;
 pub use matcha_widgets as widgets;
@@ -1,8 +1,13 @@
 use log::{debug, trace, warn};
 use std::sync::Arc;
 
-use crate::render_node::RenderNode;
-use gpu_utils::{device_loss_recoverable::DeviceLossRecoverable, texture_atlas};
+use crate::render_node::{LayerCache, RenderNode, layer_pixel_size};
+use crate::widgets_renderer::backdrop_blur::{BackdropBlur, BlurSource};
+use gpu_utils::{
+    device_loss_recoverable::DeviceLossRecoverable,
+    memory::{RENDERER_BUFFERS, TrackedBytes},
+    texture_atlas,
+};
 use texture_atlas::RegionError;
 use thiserror::Error;
 
@@ -14,9 +19,7 @@ const PIPELINE_CACHE_SIZE: u64 = 3;
 const COMPUTE_WORKGROUP_SIZE: u32 = 64;
 
 // PERF NOTE:
-// - BindGroup/Buffer の再利用・リング化を検討（毎フレームの生成/全量 write を抑制）
 // - 2 Compute パス（cull→command）の統合可能性検討（最後のスレッドで間接引数を書き込む）
-// - ステンシル/テクスチャの BindGroup はアトラス更新時のみ再生成
 // - カリングの多角形交差でエッジ交差のみのケース対策（必要性を確認し、線分交差チェックを追加）
 
 #[repr(C)]
@@ -39,6 +42,11 @@ const COMPUTE_WORKGROUP_SIZE: u32 = 64;
 ///   values (0.0 .. 1.0). If atlas returns pixel sizes, normalize on the host side.
 /// - `stencil_index`: index+1 of the associated stencil in the stencil data array.
 ///   0 indicates "no stencil". The shader uses `stencil_index - 1` to access the stencil.
+/// - `opacity`: multiplier of the alpha of the instance.
+/// - `clip_rect`: [min x, min y, max x, max y] in the destination coordinate space (before
+///   normalization); fragments outside are discarded.
+/// - `rounded_clip_rect` / `clip_radius`: a rectangle in the same space whose corners are
+///   rounded by `clip_radius`; fragments outside fade out. A radius of 0 disables it.
 ///
 /// NOTE: Keep Rust-side layout (#[repr(C)] + bytemuck) compatible with the WGSL
 /// `InstanceData` struct (field order, types, and padding). When changing fields,
@@ -55,7 +63,11 @@ struct InstanceData {
     /// the index of the stencil in the stencil data array.
     /// 0 if no stencil is used. Use `stencil_index - 1` in the shader.
     stencil_index: u32,
-    _padding2: u32,
+    opacity: f32,
+    clip_rect: [f32; 4],
+    rounded_clip_rect: [f32; 4],
+    clip_radius: f32,
+    _padding2: [u32; 3],
 }
 
 #[repr(C)]
@@ -98,7 +110,7 @@ struct StencilData {
 }
 
 const _: () = {
-    assert!(std::mem::size_of::<InstanceData>() == 96);
+    assert!(std::mem::size_of::<InstanceData>() == 144);
     assert!(std::mem::size_of::<StencilData>() == 176);
 };
 
@@ -110,23 +122,121 @@ struct CullingPushConstants {
     _pad: [u32; 3],
 }
 
+/// Where visibility culling of instances runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CullingMode {
+    /// Cull in a compute pass and draw with an indirect draw call.
+    #[default]
+    Gpu,
+    /// Cull on the CPU and issue an ordinary instanced draw.
+    ///
+    /// For backends without compute shaders or indirect execution (e.g. older GL).
+    /// Storage buffers must still be readable from the vertex and fragment stages, see
+    /// [`CoreRenderer::supports_adapter`].
+    Cpu,
+}
+
+impl CullingMode {
+    /// Picks [`CullingMode::Gpu`] when the adapter supports compute shaders and
+    /// indirect draws, [`CullingMode::Cpu`] otherwise.
+    pub fn for_adapter(adapter: &wgpu::Adapter) -> Self {
+        let flags = adapter.get_downlevel_capabilities().flags;
+        if flags.contains(
+            wgpu::DownlevelFlags::COMPUTE_SHADERS | wgpu::DownlevelFlags::INDIRECT_EXECUTION,
+        ) {
+            CullingMode::Gpu
+        } else {
+            CullingMode::Cpu
+        }
+    }
+}
+
+/// What [`CoreRenderer::render`] drew in its last frame.
+///
+/// Every instance uses the same pipeline, and all atlas pages are bound once as texture
+/// arrays, so painter's order never forces a pipeline or bind group switch. The node tree is
+/// drawn in one instanced draw call per batch, and batches only end at backdrop blurs (see
+/// [`RenderNode::with_backdrop_blur`]), which need everything below them drawn first.
+/// Instances after a blur that stay clear of it and of everything drawn in between join the
+/// batch before it, so a blur only costs a draw call where painter's order needs one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Instances collected from the node tree.
+    pub instances: u32,
+    /// Stencils referenced by the instances.
+    pub stencils: u32,
+    /// Instances left after CPU culling, or `None` when culling runs on the GPU.
+    pub visible_instances: Option<u32>,
+    /// Instances skipped because an opaque instance drawn later covers them.
+    pub occluded_instances: u32,
+    /// Draw calls issued, i.e. non-empty batches of instances.
+    pub draw_calls: u32,
+    /// Backdrop blurs applied to the render target.
+    pub backdrop_blurs: u32,
+}
+
 pub struct CoreRenderer {
+    culling_mode: CullingMode,
+    occlusion_culling: bool,
     inner: parking_lot::RwLock<CoreRendererInner>,
 }
 
 impl CoreRenderer {
     pub fn new(device: &wgpu::Device) -> Self {
-        let inner = CoreRendererInner::new(device);
+        Self::with_culling_mode(device, CullingMode::Gpu)
+    }
+
+    /// Whether the renderer can draw with `adapter`.
+    ///
+    /// Both culling modes read instances and stencils from storage buffers in the vertex and
+    /// fragment stages, which e.g. WebGL2 does not offer.
+    pub fn supports_adapter(adapter: &wgpu::Adapter) -> bool {
+        adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(wgpu::DownlevelFlags::VERTEX_STORAGE)
+            && adapter.limits().max_storage_buffers_per_shader_stage > 0
+    }
+
+    pub fn with_culling_mode(device: &wgpu::Device, culling_mode: CullingMode) -> Self {
+        let inner = CoreRendererInner::new(device, culling_mode);
         Self {
+            culling_mode,
+            occlusion_culling: false,
             inner: parking_lot::RwLock::new(inner),
         }
     }
+
+    /// Skips instances that an opaque instance drawn later covers completely.
+    ///
+    /// Occluders are the textures of nodes marked with
+    /// [`RenderNode::with_opaque_texture`]. The test runs on the CPU before the instances are
+    /// uploaded, so it also spares the culling pass, and only counts an instance as hidden
+    /// when a single occluder covers its bounding box. Pays off for stacked panels and
+    /// modal dialogs over full content.
+    pub fn with_occlusion_culling(mut self, enabled: bool) -> Self {
+        self.occlusion_culling = enabled;
+        self
+    }
+
+    pub fn culling_mode(&self) -> CullingMode {
+        self.culling_mode
+    }
+
+    pub fn occlusion_culling(&self) -> bool {
+        self.occlusion_culling
+    }
+
+    /// Statistics of the last frame drawn by [`render`](Self::render).
+    pub fn last_frame_stats(&self) -> RenderStats {
+        self.inner.read().frame_resources.lock().stats
+    }
 }
 
 impl DeviceLossRecoverable for CoreRenderer {
     fn recover(&self, device: &wgpu::Device, _: &wgpu::Queue) {
         debug!("CoreRenderer::recover: recovering GPU resources");
-        let new_inner = CoreRendererInner::new(device);
+        let new_inner = CoreRendererInner::new(device, self.culling_mode);
         let mut inner_lock = self.inner.write();
         *inner_lock = new_inner;
         debug!("CoreRenderer::recover: recovery complete");
@@ -134,6 +244,10 @@ impl DeviceLossRecoverable for CoreRenderer {
 }
 
 impl CoreRenderer {
+    /// Draws `render_node` into `destination_view`, cleared to `load_color` first.
+    ///
+    /// Backdrop blurs sample the destination, so its texture needs `TEXTURE_BINDING` usage;
+    /// without it they are skipped.
     #[allow(clippy::too_many_arguments)]
     pub fn render(
         &self,
@@ -152,6 +266,7 @@ impl CoreRenderer {
         texture_atlas: &wgpu::Texture,
         stencil_atlas: &wgpu::Texture,
     ) -> Result<(), TextureValidationError> {
+        let _span = crate::profile_span!("CoreRenderer::render", nodes = render_node.count());
         let inner_lock = self.inner.read();
         inner_lock.render(
             device,
@@ -163,10 +278,125 @@ impl CoreRenderer {
             load_color,
             texture_atlas,
             stencil_atlas,
+            self.occlusion_culling,
         )
     }
 }
 
+impl CoreRenderer {
+    /// Rasterizes the stale layer caches in `render_node` (see
+    /// [`RenderNode::with_layer_cache`]) into regions of `texture_atlas`.
+    ///
+    /// Call before [`render`](Self::render) with the same node; this also submits the uploads
+    /// staged in `texture_atlas`. Layers that could not be
+    /// rendered, e.g. because the atlas is full, are drawn as ordinary subtrees.
+    pub fn render_layers(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        render_node: &RenderNode,
+        texture_atlas: &texture_atlas::TextureAtlas,
+        stencil_atlas: &wgpu::Texture,
+    ) -> Result<(), TextureValidationError> {
+        let _span = crate::profile_span!("CoreRenderer::render_layers");
+        // staged region uploads land before the layers and the frame sample them
+        texture_atlas.flush_uploads();
+        let mut stale_layers = Vec::new();
+        collect_stale_layers(render_node, &mut stale_layers);
+        if stale_layers.is_empty() {
+            return Ok(());
+        }
+        trace!(
+            "CoreRenderer::render_layers: rendering {} layers",
+            stale_layers.len()
+        );
+
+        let inner_lock = self.inner.read();
+        let atlas_texture = texture_atlas.texture();
+        let format = texture_atlas.format();
+
+        // inner layers come first, so outer layers draw them as single quads
+        for (cache, size, content) in stale_layers {
+            let region = match cache.reusable_region(size) {
+                Some(region) => region,
+                None => match texture_atlas.allocate(device, queue, size) {
+                    Ok(region) => region,
+                    Err(e) => {
+                        warn!("CoreRenderer::render_layers: failed to allocate layer: {e}");
+                        cache.clear();
+                        continue;
+                    }
+                },
+            };
+
+            // the atlas is sampled while rendering, so render into a separate texture first
+            let layer_texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("ObjectRenderer Layer Texture"),
+                size: wgpu::Extent3d {
+                    width: size[0],
+                    height: size[1],
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                // sampled by backdrop blurs inside the layer
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+
+            inner_lock.render(
+                device,
+                queue,
+                format,
+                &layer_texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                [size[0] as f32, size[1] as f32],
+                &content,
+                wgpu::Color::TRANSPARENT,
+                &atlas_texture,
+                stencil_atlas,
+                self.occlusion_culling,
+            )?;
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("ObjectRenderer: Layer Copy Encoder"),
+            });
+            region.copy_from_texture(&mut encoder, &layer_texture)?;
+            queue.submit(std::iter::once(encoder.finish()));
+
+            cache.store(region, content);
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects the layers in `node` that need to be rendered, innermost first.
+///
+/// Returns `true` if any layer in the subtree is stale; an outer layer is re-rendered
+/// along with its stale inner layers.
+fn collect_stale_layers(
+    node: &RenderNode,
+    stale_layers: &mut Vec<(LayerCache, [u32; 2], RenderNode)>,
+) -> bool {
+    let mut stale = false;
+    for (child, _) in node.child_elements() {
+        stale |= collect_stale_layers(child, stale_layers);
+    }
+
+    if let Some((cache, size)) = node.layer()
+        && let Some(size) = layer_pixel_size(*size)
+        && (stale || cache.current(node, size).is_none())
+    {
+        stale_layers.push((cache.clone(), size, node.layer_content()));
+        return true;
+    }
+    stale
+}
+
 pub struct CoreRendererInner {
     // Bind Group Layouts
     texture_sampler: wgpu::Sampler,
@@ -174,14 +404,12 @@ pub struct CoreRendererInner {
     data_bind_group_layout: wgpu::BindGroupLayout,
 
     // Pipeline Layouts
-    culling_pipeline_layout: wgpu::PipelineLayout,
-    command_pipeline_layout: wgpu::PipelineLayout,
     render_pipeline_layout: wgpu::PipelineLayout,
     render_pipeline_shader_module: wgpu::ShaderModule,
 
     // Pipelines
-    culling_pipeline: wgpu::ComputePipeline,
-    command_pipeline: wgpu::ComputePipeline,
+    // `None` when culling runs on the CPU
+    gpu_culling: Option<GpuCullingPipelines>,
     render_pipeline:
         moka::sync::Cache<wgpu::TextureFormat, Arc<wgpu::RenderPipeline>, fxhash::FxBuildHasher>, // key: surface format
 
@@ -189,10 +417,59 @@ pub struct CoreRendererInner {
     atomic_counter: wgpu::Buffer,
     draw_command: wgpu::Buffer,
     draw_command_storage: wgpu::Buffer,
+
+    backdrop_blur: BackdropBlur,
+
+    // per-frame data kept alive between frames
+    frame_resources: parking_lot::Mutex<FrameResources>,
+}
+
+/// Buffers and bind groups reused across frames.
+///
+/// Buffers only grow, and bind groups are rebuilt only when a buffer is reallocated
+/// or the atlas textures change.
+struct FrameResources {
+    instances: PersistentBuffer<InstanceData>,
+    stencils: PersistentBuffer<StencilData>,
+    visible_instance_indices: PersistentBuffer<u32>,
+    data_bind_group: Option<wgpu::BindGroup>,
+    // (texture atlas, stencil atlas, bind group)
+    texture_bind_group: Option<(wgpu::Texture, wgpu::Texture, wgpu::BindGroup)>,
+    // what the last frame drew
+    stats: RenderStats,
+}
+
+impl FrameResources {
+    fn new() -> Self {
+        Self {
+            instances: PersistentBuffer::new(
+                "ObjectRenderer Instance Buffer",
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            ),
+            stencils: PersistentBuffer::new(
+                "ObjectRenderer Stencil Buffer",
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            ),
+            visible_instance_indices: PersistentBuffer::new(
+                "ObjectRenderer Visible Instances Buffer",
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            ),
+            data_bind_group: None,
+            texture_bind_group: None,
+            stats: RenderStats::default(),
+        }
+    }
+}
+
+struct GpuCullingPipelines {
+    _culling_pipeline_layout: wgpu::PipelineLayout,
+    _command_pipeline_layout: wgpu::PipelineLayout,
+    culling_pipeline: wgpu::ComputePipeline,
+    command_pipeline: wgpu::ComputePipeline,
 }
 
 impl CoreRendererInner {
-    pub fn new(device: &wgpu::Device) -> Self {
+    pub fn new(device: &wgpu::Device, culling_mode: CullingMode) -> Self {
         debug!("CoreRenderer::new: initializing renderer");
         // Sampler
         let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -308,11 +585,24 @@ impl CoreRendererInner {
                 ],
             });
 
-        let (culling_pipeline_layout, culling_pipeline) =
-            Self::create_culling_pipeline(device, &data_bind_group_layout);
-
-        let (command_pipeline_layout, command_pipeline) =
-            Self::create_command_pipeline(device, &data_bind_group_layout);
+        let gpu_culling = match culling_mode {
+            CullingMode::Gpu => {
+                let (culling_pipeline_layout, culling_pipeline) =
+                    Self::create_culling_pipeline(device, &data_bind_group_layout);
+                let (command_pipeline_layout, command_pipeline) =
+                    Self::create_command_pipeline(device, &data_bind_group_layout);
+                Some(GpuCullingPipelines {
+                    _culling_pipeline_layout: culling_pipeline_layout,
+                    _command_pipeline_layout: command_pipeline_layout,
+                    culling_pipeline,
+                    command_pipeline,
+                })
+            }
+            CullingMode::Cpu => {
+                debug!("CoreRenderer::new: compute culling disabled, culling on CPU");
+                None
+            }
+        };
 
         let (render_pipeline_layout, render_pipeline_shader_module) =
             Self::create_render_pipeline_layout(
@@ -353,16 +643,15 @@ impl CoreRendererInner {
             texture_sampler,
             texture_bind_group_layout,
             data_bind_group_layout,
-            culling_pipeline_layout,
-            command_pipeline_layout,
             render_pipeline_layout,
             render_pipeline_shader_module,
-            culling_pipeline,
-            command_pipeline,
+            gpu_culling,
             render_pipeline,
             atomic_counter,
             draw_command,
             draw_command_storage,
+            backdrop_blur: BackdropBlur::default(),
+            frame_resources: parking_lot::Mutex::new(FrameResources::new()),
         }
     }
 
@@ -510,6 +799,7 @@ impl CoreRendererInner {
         // texture atlas
         texture_atlas: &wgpu::Texture,
         stencil_atlas: &wgpu::Texture,
+        occlusion_culling: bool,
     ) -> Result<(), TextureValidationError> {
         trace!(
             "CoreRenderer::render: begin render_node_count={} surface_format={:?} destination_size={:?}",
@@ -530,17 +820,33 @@ impl CoreRendererInner {
         // }
 
         // integrate objects into a instance array
-        let (instances, stencils) = create_instance_and_stencil_data(
-            render_node,
-            texture_atlas.format(),
-            stencil_atlas.format(),
-        )?;
+        let (mut instances, stencils, occluders, mut backdrops) = {
+            let _span = crate::profile_span!("collect_instances");
+            create_instance_and_stencil_data(
+                render_node,
+                texture_atlas.format(),
+                stencil_atlas.format(),
+            )?
+        };
         trace!(
             "CoreRenderer::render: prepared {} instances and {} stencils",
             instances.len(),
             stencils.len()
         );
 
+        let collected_instances = instances.len() as u32;
+        let occluded_instances = if occlusion_culling && !occluders.is_empty() {
+            let _span = crate::profile_span!("cull_occluded");
+            let occluded = cull_occluded_instances(&mut instances, &occluders, &mut backdrops);
+            trace!(
+                "CoreRenderer::render: {occluded} instances hidden by {} occluders",
+                occluders.len()
+            );
+            occluded
+        } else {
+            0
+        };
+
         // #[cfg(debug_assertions)]
         // {
         //     println!("[CoreRenderer] instances: {instances:#?}",);
@@ -548,6 +854,11 @@ impl CoreRendererInner {
 
         if instances.is_empty() {
             trace!("CoreRenderer::render: no instances to render");
+            self.frame_resources.lock().stats = RenderStats {
+                instances: collected_instances,
+                occluded_instances,
+                ..Default::default()
+            };
             return Ok(());
         }
 
@@ -562,97 +873,14 @@ impl CoreRendererInner {
             ))
         });
 
-        // Create buffers
-        let all_instance_data_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("ObjectRenderer Instance Buffer"),
-            size: (std::mem::size_of::<InstanceData>() * instances.len()) as u64,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let all_stencil_data_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("ObjectRenderer Stencil Buffer"),
-            size: (std::mem::size_of::<StencilData>() * stencils.len().max(1)) as u64,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        let visible_instance_indices_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("ObjectRenderer Visible Instances Buffer"),
-            size: (std::mem::size_of::<u32>() * instances.len()) as u64,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        // Create bind groups
-        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("ObjectRenderer Texture Bind Group"),
-            layout: &self.texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&texture_atlas.create_view(
-                        &wgpu::TextureViewDescriptor {
-                            dimension: Some(wgpu::TextureViewDimension::D2Array),
-                            aspect: wgpu::TextureAspect::All,
-                            ..Default::default()
-                        },
-                    )),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(&stencil_atlas.create_view(
-                        &wgpu::TextureViewDescriptor {
-                            dimension: Some(wgpu::TextureViewDimension::D2Array),
-                            aspect: wgpu::TextureAspect::All,
-                            ..Default::default()
-                        },
-                    )),
-                },
-            ],
-        });
-
-        let data_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("ObjectRenderer Data Bind Group"),
-            layout: &self.data_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: all_instance_data_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: all_stencil_data_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: visible_instance_indices_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: self.atomic_counter.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: self.draw_command_storage.as_entire_binding(),
-                },
-            ],
-        });
-
-        // already checked that instances is not empty
-        queue.write_buffer(
-            &all_instance_data_buffer,
-            0,
-            bytemuck::cast_slice(&instances),
-        );
+        let frame_resources = &mut *self.frame_resources.lock();
 
+        // Upload instance / stencil data, reallocating only when the buffers are too small.
+        let mut reallocated = frame_resources.instances.upload(device, queue, &instances);
         if !stencils.is_empty() {
-            queue.write_buffer(&all_stencil_data_buffer, 0, bytemuck::cast_slice(&stencils));
+            reallocated |= frame_resources.stencils.upload(device, queue, &stencils);
         } else {
+            // the shader always reads one stencil, keep a valid default in slot 0
             let default_stencil = StencilData {
                 viewport_position: nalgebra::Matrix4::identity(),
                 viewport_position_inverse_exists: 1,
@@ -664,111 +892,550 @@ impl CoreRendererInner {
                 in_atlas_size: [0.0, 0.0],
                 _padding3: [0; 2],
             };
-            queue.write_buffer(
-                &all_stencil_data_buffer,
-                0,
-                bytemuck::bytes_of(&default_stencil),
-            );
+            reallocated |= frame_resources
+                .stencils
+                .upload(device, queue, &[default_stencil]);
+        }
+
+        let normalize_matrix = make_normalize_matrix(destination_size);
+
+        // With CPU culling the visible indices are computed here; otherwise the culling pass
+        // fills the buffer on the GPU. Backdrop blurs split the draw, which needs the visible
+        // instances in painter's order, so frames with them are culled on the CPU as well.
+        let mut batch_ends = Vec::new();
+        let cpu_visible = match self.gpu_culling {
+            Some(_) if backdrops.is_empty() => {
+                reallocated |= frame_resources
+                    .visible_instance_indices
+                    .reserve(device, instances.len());
+                None
+            }
+            _ => {
+                let visible = {
+                    let _span = crate::profile_span!("cull_on_cpu");
+                    cull_instances_on_cpu(&instances, &stencils, &normalize_matrix)
+                };
+                trace!(
+                    "CoreRenderer::render: {} of {} instances visible after CPU culling",
+                    visible.len(),
+                    instances.len()
+                );
+                let visible = if backdrops.is_empty() {
+                    batch_ends.push(visible.len());
+                    visible
+                } else {
+                    let _span = crate::profile_span!("batch_around_backdrops");
+                    let (visible, ends) = batch_around_backdrops(&instances, &visible, &backdrops);
+                    batch_ends = ends;
+                    visible
+                };
+                reallocated |= frame_resources
+                    .visible_instance_indices
+                    .reserve(device, instances.len());
+                reallocated |= frame_resources
+                    .visible_instance_indices
+                    .upload(device, queue, &visible);
+                Some(visible)
+            }
+        };
+
+        // Rebuild bind groups only when the resources they reference changed.
+        if reallocated {
+            frame_resources.data_bind_group = None;
         }
+        let data_bind_group = &*frame_resources.data_bind_group.get_or_insert_with(|| {
+            trace!("CoreRenderer::render: rebuilding data bind group");
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("ObjectRenderer Data Bind Group"),
+                layout: &self.data_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: frame_resources.instances.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: frame_resources.stencils.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: frame_resources
+                            .visible_instance_indices
+                            .buffer()
+                            .as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: self.atomic_counter.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: self.draw_command_storage.as_entire_binding(),
+                    },
+                ],
+            })
+        });
 
-        queue.write_buffer(&self.atomic_counter, 0, bytemuck::cast_slice(&[0u32]));
+        let texture_bind_group_is_current = frame_resources
+            .texture_bind_group
+            .as_ref()
+            .is_some_and(|(texture, stencil, _)| {
+                texture == texture_atlas && stencil == stencil_atlas
+            });
+        if !texture_bind_group_is_current {
+            frame_resources.texture_bind_group = None;
+        }
+        let (_, _, texture_bind_group) =
+            &*frame_resources.texture_bind_group.get_or_insert_with(|| {
+                trace!("CoreRenderer::render: rebuilding texture bind group");
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("ObjectRenderer Texture Bind Group"),
+                    layout: &self.texture_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(
+                                &texture_atlas.create_view(&wgpu::TextureViewDescriptor {
+                                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                                    aspect: wgpu::TextureAspect::All,
+                                    ..Default::default()
+                                }),
+                            ),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 2,
+                            resource: wgpu::BindingResource::TextureView(
+                                &stencil_atlas.create_view(&wgpu::TextureViewDescriptor {
+                                    dimension: Some(wgpu::TextureViewDimension::D2Array),
+                                    aspect: wgpu::TextureAspect::All,
+                                    ..Default::default()
+                                }),
+                            ),
+                        },
+                    ],
+                });
+                (texture_atlas.clone(), stencil_atlas.clone(), bind_group)
+            });
 
         let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("ObjectRenderer: Command Encoder"),
         });
         trace!("CoreRenderer::render: command encoder created");
 
-        let normalize_matrix = make_normalize_matrix(destination_size);
-        let cull_pc = CullingPushConstants {
-            normalize_matrix,
-            instance_count: instances.len() as u32,
-            _pad: [0; 3],
-        };
+        if let (Some(gpu_culling), None) = (&self.gpu_culling, &cpu_visible) {
+            queue.write_buffer(&self.atomic_counter, 0, bytemuck::cast_slice(&[0u32]));
 
-        // culling compute pass
-        {
-            let mut culling_pass =
-                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                    label: Some("ObjectRenderer: Culling Pass"),
-                    timestamp_writes: None,
-                });
-            culling_pass.set_pipeline(&self.culling_pipeline);
-            culling_pass.set_bind_group(0, &data_bind_group, &[]);
-            culling_pass.set_push_constants(0, bytemuck::bytes_of(&cull_pc));
-            culling_pass.dispatch_workgroups(
-                (instances.len() as u32).div_ceil(COMPUTE_WORKGROUP_SIZE),
-                1,
-                1,
-            );
-        }
-        trace!("CoreRenderer::render: culling pass dispatched");
+            let cull_pc = CullingPushConstants {
+                normalize_matrix,
+                instance_count: instances.len() as u32,
+                _pad: [0; 3],
+            };
 
-        // command encoding pass
-        {
-            let mut command_pass =
-                command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                    label: Some("ObjectRenderer: Command Pass"),
-                    timestamp_writes: None,
-                });
-            command_pass.set_pipeline(&self.command_pipeline);
-            command_pass.set_bind_group(0, &data_bind_group, &[]);
-            command_pass.dispatch_workgroups(1, 1, 1);
+            // culling compute pass
+            {
+                let mut culling_pass =
+                    command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("ObjectRenderer: Culling Pass"),
+                        timestamp_writes: None,
+                    });
+                culling_pass.set_pipeline(&gpu_culling.culling_pipeline);
+                culling_pass.set_bind_group(0, data_bind_group, &[]);
+                culling_pass.set_push_constants(0, bytemuck::bytes_of(&cull_pc));
+                culling_pass.dispatch_workgroups(
+                    (instances.len() as u32).div_ceil(COMPUTE_WORKGROUP_SIZE),
+                    1,
+                    1,
+                );
+            }
+            trace!("CoreRenderer::render: culling pass dispatched");
+
+            // command encoding pass
+            {
+                let mut command_pass =
+                    command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("ObjectRenderer: Command Pass"),
+                        timestamp_writes: None,
+                    });
+                command_pass.set_pipeline(&gpu_culling.command_pipeline);
+                command_pass.set_bind_group(0, data_bind_group, &[]);
+                command_pass.dispatch_workgroups(1, 1, 1);
+            }
+            trace!("CoreRenderer::render: command pass dispatched");
+
+            command_encoder.copy_buffer_to_buffer(
+                &self.draw_command_storage,
+                0,
+                &self.draw_command,
+                0,
+                std::mem::size_of::<wgpu::util::DrawIndirectArgs>() as u64,
+            );
         }
-        trace!("CoreRenderer::render: command pass dispatched");
-
-        command_encoder.copy_buffer_to_buffer(
-            &self.draw_command_storage,
-            0,
-            &self.draw_command,
-            0,
-            std::mem::size_of::<wgpu::util::DrawIndirectArgs>() as u64,
-        );
 
-        // render pass
+        // one render pass per batch, ending at the next backdrop blur
+        let destination_texture = destination_view.texture();
+        let target_size = [destination_texture.width(), destination_texture.height()];
+        let mut load = wgpu::LoadOp::Clear(load_color);
+        let mut drawn = 0;
+        let mut draw_calls = 0;
+        let mut backdrop_blurs = 0;
+        for (batch_index, backdrop) in backdrops
+            .iter()
+            .map(Some)
+            .chain(std::iter::once(None))
+            .enumerate()
         {
-            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("ObjectRenderer: Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: destination_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(load_color),
-                        store: wgpu::StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
+            // `None` draws everything the culling pass left visible
+            let batch = cpu_visible.as_ref().map(|_| {
+                let end = batch_ends[batch_index];
+                let batch = drawn as u32..end as u32;
+                drawn = end;
+                batch
             });
 
-            render_pass.set_pipeline(render_pipeline.as_ref());
-            render_pass.set_bind_group(0, &texture_bind_group, &[]);
-            render_pass.set_bind_group(1, &data_bind_group, &[]);
-            render_pass.set_push_constants(
-                wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                0,
-                bytemuck::cast_slice(normalize_matrix.as_slice()),
-            );
-            render_pass.draw_indirect(&self.draw_command, 0);
+            {
+                let mut render_pass =
+                    command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("ObjectRenderer: Render Pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: destination_view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load,
+                                store: wgpu::StoreOp::Store,
+                            },
+                            depth_slice: None,
+                        })],
+                        depth_stencil_attachment: None,
+                        occlusion_query_set: None,
+                        timestamp_writes: None,
+                    });
+
+                render_pass.set_pipeline(render_pipeline.as_ref());
+                render_pass.set_bind_group(0, texture_bind_group, &[]);
+                render_pass.set_bind_group(1, data_bind_group, &[]);
+                render_pass.set_push_constants(
+                    wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    0,
+                    bytemuck::cast_slice(normalize_matrix.as_slice()),
+                );
+                match batch {
+                    None => {
+                        render_pass.draw_indirect(&self.draw_command, 0);
+                        draw_calls += 1;
+                    }
+                    Some(batch) if batch.is_empty() => {}
+                    Some(batch) => {
+                        render_pass.draw(0..4, batch);
+                        draw_calls += 1;
+                    }
+                }
+            }
+            load = wgpu::LoadOp::Load;
+
+            if let Some(backdrop) = backdrop
+                && self.blur_backdrop(
+                    &mut command_encoder,
+                    destination_view,
+                    target_size,
+                    destination_size,
+                    backdrop,
+                    device,
+                )
+            {
+                backdrop_blurs += 1;
+            }
         }
-        trace!("CoreRenderer::render: render pass completed");
+        frame_resources.stats = RenderStats {
+            instances: collected_instances,
+            stencils: stencils.len() as u32,
+            visible_instances: cpu_visible.map(|visible| visible.len() as u32),
+            occluded_instances,
+            draw_calls,
+            backdrop_blurs,
+        };
+        trace!("CoreRenderer::render: {draw_calls} render passes completed");
 
         queue.submit(std::iter::once(command_encoder.finish()));
         trace!("CoreRenderer::render: commands submitted");
 
         Ok(())
     }
+
+    /// Blurs the pixels of `destination_view` under `backdrop` in place. Returns `false`
+    /// when the rectangle lies outside the target or the target cannot be sampled.
+    fn blur_backdrop(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        destination_view: &wgpu::TextureView,
+        target_size: [u32; 2],
+        destination_size: [f32; 2],
+        backdrop: &Backdrop,
+        device: &wgpu::Device,
+    ) -> bool {
+        // the destination space may be scaled to the pixels of the target
+        let scale = [
+            target_size[0] as f32 / destination_size[0],
+            target_size[1] as f32 / destination_size[1],
+        ];
+        let min = [0, 1].map(|axis| (backdrop.rect[axis] * scale[axis]).floor().max(0.0) as u32);
+        let max = [0, 1].map(|axis| {
+            ((backdrop.rect[axis + 2] * scale[axis]).ceil().max(0.0) as u32).min(target_size[axis])
+        });
+        if min[0] >= max[0] || min[1] >= max[1] {
+            return false;
+        }
+
+        self.backdrop_blur.blur_in_place(
+            encoder,
+            BlurSource {
+                source_texture_view: destination_view,
+                source_texture_size: target_size,
+                region_position: [min[0] as f32, min[1] as f32],
+                region_size: [max[0] - min[0], max[1] - min[1]],
+                radius: backdrop.radius * scale[0].min(scale[1]),
+            },
+            device,
+        )
+    }
+}
+
+// vertices:
+// 0 - 3
+// |   |
+// 1 - 2
+const QUAD_VERTICES: [[f32; 2]; 4] = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]];
+
+const CLIP_VERTICES: [[f32; 2]; 4] = [[-1.0, 1.0], [-1.0, -1.0], [1.0, -1.0], [1.0, 1.0]];
+
+/// CPU counterpart of `culling_main` in `renderer_cull.wgsl`.
+///
+/// Returns the indices of visible instances in ascending order, so instances keep
+/// their painter's order.
+fn cull_instances_on_cpu(
+    instances: &[InstanceData],
+    stencils: &[StencilData],
+    normalize_matrix: &nalgebra::Matrix4<f32>,
+) -> Vec<u32> {
+    let to_clip = |viewport_position: &nalgebra::Matrix4<f32>| {
+        let transform = normalize_matrix * viewport_position;
+        QUAD_VERTICES.map(|[x, y]| {
+            let p = transform * nalgebra::Vector4::new(x, y, 0.0, 1.0);
+            [p.x, p.y]
+        })
+    };
+
+    instances
+        .iter()
+        .enumerate()
+        .filter(|(_, instance)| {
+            let texture_position = to_clip(&instance.viewport_position);
+            if !is_overlapping(&texture_position, &CLIP_VERTICES) {
+                return false;
+            }
+
+            let clipped = intersect_rect(
+                instance.clip_rect,
+                transformed_rect(&instance.viewport_position, [1.0, 1.0]),
+            );
+            if clipped[0] >= clipped[2] || clipped[1] >= clipped[3] {
+                return false;
+            }
+
+            // stencil_index is index + 1, 0 means no stencil
+            let Some(stencil) = (instance.stencil_index as usize)
+                .checked_sub(1)
+                .and_then(|i| stencils.get(i))
+            else {
+                return true;
+            };
+            let stencil_position = to_clip(&stencil.viewport_position);
+            is_overlapping(&stencil_position, &CLIP_VERTICES)
+                && is_overlapping(&texture_position, &stencil_position)
+        })
+        .map(|(index, _)| index as u32)
+        .collect()
+}
+
+/// The screen rectangle an opaque instance fully covers.
+#[derive(Debug, Clone, Copy)]
+struct Occluder {
+    // index of the occluding instance; it hides instances drawn before it
+    instance: usize,
+    // [min x, min y, max x, max y] in the destination coordinate space
+    rect: [f32; 4],
+}
+
+/// A rectangle of the render target blurred before the instances from `instance` on are
+/// drawn.
+#[derive(Debug, Clone, Copy)]
+struct Backdrop {
+    // index of the first instance drawn over the blurred rectangle
+    instance: usize,
+    // [min x, min y, max x, max y] in the destination coordinate space
+    rect: [f32; 4],
+    radius: f32,
 }
 
+/// Removes the instances whose visible bounding box lies inside the rectangle of an
+/// occluder drawn after them. Returns how many were removed.
+///
+/// `occluders` and `backdrops` must be in painter's order. Removing an occluder that is
+/// hidden itself is fine: the later occluder covers everything it covered. An occluder only
+/// hides instances drawn after the last backdrop blur before it, since the blur may read
+/// them; `backdrops` are moved to the indices of the remaining instances.
+fn cull_occluded_instances(
+    instances: &mut Vec<InstanceData>,
+    occluders: &[Occluder],
+    backdrops: &mut [Backdrop],
+) -> u32 {
+    let before = instances.len();
+    let mut index = 0;
+    let mut removed_before = vec![0; backdrops.len()];
+    instances.retain(|instance| {
+        let this = index;
+        index += 1;
+
+        let bounds = intersect_rect(
+            instance.clip_rect,
+            transformed_rect(&instance.viewport_position, [1.0, 1.0]),
+        );
+        let later = occluders.partition_point(|occluder| occluder.instance <= this);
+        // the next backdrop blur after this instance
+        let next_backdrop = backdrops.partition_point(|backdrop| backdrop.instance <= this);
+        let same_batch = backdrops
+            .get(next_backdrop)
+            .map_or(occluders.len(), |backdrop| {
+                occluders.partition_point(|occluder| occluder.instance < backdrop.instance)
+            });
+        let hidden = occluders[later..same_batch].iter().any(|occluder| {
+            occluder.rect[0] <= bounds[0]
+                && occluder.rect[1] <= bounds[1]
+                && occluder.rect[2] >= bounds[2]
+                && occluder.rect[3] >= bounds[3]
+        });
+        if hidden {
+            for removed in &mut removed_before[next_backdrop..] {
+                *removed += 1;
+            }
+        }
+        !hidden
+    });
+    for (backdrop, removed) in backdrops.iter_mut().zip(removed_before) {
+        backdrop.instance -= removed;
+    }
+    (before - instances.len()) as u32
+}
+
+/// Groups the `visible` instances into one batch before each backdrop blur and one after the
+/// last, and returns them in drawing order with the end of each batch.
+///
+/// An instance drawn after a blur moves into the batch before it when it stays clear of the
+/// pixels the blur reads and writes and of every instance it would now be drawn under, which
+/// leaves the frame unchanged. Bounds are axis-aligned boxes, so rotated instances move less
+/// often than they could.
+fn batch_around_backdrops(
+    instances: &[InstanceData],
+    visible: &[u32],
+    backdrops: &[Backdrop],
+) -> (Vec<u32>, Vec<usize>) {
+    // the blur samples up to its radius around the rectangle, plus a pixel of rounding
+    let blurred = backdrops
+        .iter()
+        .map(|backdrop| {
+            let margin = backdrop.radius + 1.0;
+            [
+                backdrop.rect[0] - margin,
+                backdrop.rect[1] - margin,
+                backdrop.rect[2] + margin,
+                backdrop.rect[3] + margin,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut batches: Vec<Vec<(u32, [f32; 4])>> = vec![Vec::new(); backdrops.len() + 1];
+    let mut next = visible.iter().copied().peekable();
+    for batch in 0..batches.len() {
+        let end = backdrops
+            .get(batch)
+            .map_or(usize::MAX, |backdrop| backdrop.instance);
+        while let Some(index) = next.next_if(|&index| (index as usize) < end) {
+            let instance = &instances[index as usize];
+            let bounds = intersect_rect(
+                instance.clip_rect,
+                transformed_rect(&instance.viewport_position, [1.0, 1.0]),
+            );
+            // everything in the batches it passes was drawn before it in painter's order
+            let mut target = batch;
+            while target > 0
+                && !rects_overlap(bounds, blurred[target - 1])
+                && !batches[target]
+                    .iter()
+                    .any(|&(_, other)| rects_overlap(bounds, other))
+            {
+                target -= 1;
+            }
+            batches[target].push((index, bounds));
+        }
+    }
+
+    let mut ends = Vec::with_capacity(batches.len());
+    let mut ordered = Vec::with_capacity(visible.len());
+    for batch in batches {
+        ordered.extend(batch.into_iter().map(|(index, _)| index));
+        ends.push(ordered.len());
+    }
+    (ordered, ends)
+}
+
+fn rects_overlap(a: [f32; 4], b: [f32; 4]) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
+
+/// The rectangle `viewport_position` maps the unit quad onto, or `None` when the quad is
+/// rotated or skewed and its bounding box would claim more than it covers.
+fn axis_aligned_rect(viewport_position: &nalgebra::Matrix4<f32>) -> Option<[f32; 4]> {
+    (viewport_position[(0, 1)] == 0.0 && viewport_position[(1, 0)] == 0.0)
+        .then(|| transformed_rect(viewport_position, [1.0, 1.0]))
+}
+
+fn is_overlapping(a: &[[f32; 2]; 4], b: &[[f32; 2]; 4]) -> bool {
+    a.iter().any(|p| point_in_polygon(p, b)) || b.iter().any(|p| point_in_polygon(p, a))
+}
+
+fn point_in_polygon(point: &[f32; 2], polygon: &[[f32; 2]; 4]) -> bool {
+    // use cross product to determine if the point is inside the polygon
+    let crosses: [f32; 4] = std::array::from_fn(|i| {
+        let from = polygon[i];
+        let to = polygon[(i + 1) % 4];
+        let to_vertex = [from[0] - point[0], from[1] - point[1]];
+        let edge = [to[0] - from[0], to[1] - from[1]];
+        to_vertex[0] * edge[1] - to_vertex[1] * edge[0]
+    });
+    // a point on an edge is inside, so a quad exactly covering the target is visible
+    crosses.iter().all(|&c| c >= 0.0) || crosses.iter().all(|&c| c <= 0.0)
+}
+
+type InstancesAndStencils = (
+    Vec<InstanceData>,
+    Vec<StencilData>,
+    Vec<Occluder>,
+    Vec<Backdrop>,
+);
+
 fn create_instance_and_stencil_data(
     objects: &RenderNode,
     texture_format: wgpu::TextureFormat,
     stencil_format: wgpu::TextureFormat,
-) -> Result<(Vec<InstanceData>, Vec<StencilData>), TextureValidationError> {
+) -> Result<InstancesAndStencils, TextureValidationError> {
     trace!("CoreRenderer::create_instance_and_stencil_data: start");
     let mut instances = Vec::new();
     let mut stencils = Vec::new();
+    let mut occluders = Vec::new();
+    let mut backdrops = Vec::new();
 
     let mut texture_atlas_id = None;
     let mut stencil_atlas_id = None;
@@ -780,9 +1447,13 @@ fn create_instance_and_stencil_data(
         nalgebra::Matrix4::identity(),
         &mut instances,
         &mut stencils,
+        &mut occluders,
+        &mut backdrops,
         &mut texture_atlas_id,
         &mut stencil_atlas_id,
         0,
+        1.0,
+        NO_CLIP,
     )?;
 
     trace!(
@@ -790,7 +1461,7 @@ fn create_instance_and_stencil_data(
         instances.len(),
         stencils.len()
     );
-    Ok((instances, stencils))
+    Ok((instances, stencils, occluders, backdrops))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -801,12 +1472,67 @@ fn create_instance_and_stencil_data_recursive(
     transform: nalgebra::Matrix4<f32>,
     instances: &mut Vec<InstanceData>,
     stencils: &mut Vec<StencilData>,
+    occluders: &mut Vec<Occluder>,
+    backdrops: &mut Vec<Backdrop>,
     texture_atlas_id: &mut Option<texture_atlas::TextureAtlasId>,
     stencil_atlas_id: &mut Option<texture_atlas::TextureAtlasId>,
     // the index + 1 of the current stencil in the stencils vector.
     // 0 if no stencil is used.
     mut current_stencil: u32,
+    // opacity and clip of the ancestors
+    mut opacity: f32,
+    mut clip: Clip,
 ) -> Result<(), TextureValidationError> {
+    // a layer with up-to-date content replaces the whole subtree
+    if let Some((cache, size)) = object.layer()
+        && let Some(pixel_size) = layer_pixel_size(*size)
+        && let Some(region) = cache.current(object, pixel_size)
+    {
+        let scale = nalgebra::Matrix4::new_nonuniform_scaling(&nalgebra::Vector3::new(
+            pixel_size[0] as f32,
+            pixel_size[1] as f32,
+            1.0,
+        ));
+        return push_instance(
+            texture_format,
+            &region,
+            transform * scale,
+            instances,
+            texture_atlas_id,
+            current_stencil,
+            opacity,
+            clip,
+        );
+    }
+
+    opacity *= object.opacity();
+    if opacity <= 0.0 {
+        return Ok(());
+    }
+
+    if let Some(size) = object.clip() {
+        let rect = transformed_rect(&transform, size);
+        clip.rect = intersect_rect(clip.rect, rect);
+        if clip.rect[0] >= clip.rect[2] || clip.rect[1] >= clip.rect[3] {
+            return Ok(());
+        }
+        if object.clip_radius() > 0.0 {
+            clip.rounded_rect = rect;
+            clip.radius = object.clip_radius() * min_axis_scale(&transform);
+        }
+    }
+
+    if let Some((size, radius)) = object.backdrop_blur() {
+        let rect = intersect_rect(clip.rect, transformed_rect(&transform, size));
+        if rect[0] < rect[2] && rect[1] < rect[3] {
+            backdrops.push(Backdrop {
+                instance: instances.len(),
+                rect,
+                radius: radius * min_axis_scale(&transform),
+            });
+        }
+    }
+
     if let Some((stencil, stencil_position)) = &object.stencil() {
         if stencil.format() != stencil_format {
             warn!("CoreRenderer: stencil format mismatch");
@@ -844,32 +1570,33 @@ fn create_instance_and_stencil_data_recursive(
     }
 
     if let Some((texture, texture_position)) = &object.texture() {
-        if texture.format() != texture_format {
-            warn!("CoreRenderer: texture format mismatch");
-            return Err(TextureValidationError::FormatMismatch);
-        }
-
-        let atlas_id = texture_atlas_id.get_or_insert_with(|| texture.atlas_id());
+        let viewport_position = transform * texture_position;
+        push_instance(
+            texture_format,
+            texture,
+            viewport_position,
+            instances,
+            texture_atlas_id,
+            current_stencil,
+            opacity,
+            clip,
+        )?;
 
-        if atlas_id != &texture.atlas_id() {
-            warn!("CoreRenderer: texture atlas id mismatch");
-            return Err(TextureValidationError::AtlasIdMismatch);
+        // rounded corners leave the corners of the rectangle uncovered
+        if object.is_opaque()
+            && opacity >= 1.0
+            && current_stencil == 0
+            && clip.radius <= 0.0
+            && let Some(rect) = axis_aligned_rect(&viewport_position)
+        {
+            occluders.push(Occluder {
+                instance: instances.len() - 1,
+                rect: intersect_rect(rect, clip.rect),
+            });
         }
-
-        let (page, position_in_atlas) = texture.position_in_atlas()?;
-
-        instances.push(InstanceData {
-            viewport_position: transform * texture_position,
-            atlas_page: page,
-            in_atlas_offset: [position_in_atlas.min.x, position_in_atlas.min.y],
-            in_atlas_size: [position_in_atlas.width(), position_in_atlas.height()],
-            stencil_index: current_stencil,
-            _padding1: 0,
-            _padding2: 0,
-        });
     }
 
-    for (child, child_transform) in object.child_elements() {
+    for (child, child_transform) in object.children_in_paint_order() {
         create_instance_and_stencil_data_recursive(
             texture_format,
             stencil_format,
@@ -877,15 +1604,205 @@ fn create_instance_and_stencil_data_recursive(
             transform * child_transform,
             instances,
             stencils,
+            occluders,
+            backdrops,
             texture_atlas_id,
             stencil_atlas_id,
             current_stencil,
+            opacity,
+            clip,
         )?;
     }
 
     Ok(())
 }
 
+/// The clip of an instance: the intersection of the rectangles of its clipped ancestors, and
+/// the rounded rectangle of the innermost ancestor clipped with a radius.
+#[derive(Debug, Clone, Copy)]
+struct Clip {
+    // [min x, min y, max x, max y] in the destination coordinate space
+    rect: [f32; 4],
+    rounded_rect: [f32; 4],
+    // 0 if no ancestor clips with rounded corners
+    radius: f32,
+}
+
+/// Clip of instances outside of any clipped node.
+const NO_CLIP: Clip = Clip {
+    rect: [f32::MIN, f32::MIN, f32::MAX, f32::MAX],
+    rounded_rect: [f32::MIN, f32::MIN, f32::MAX, f32::MAX],
+    radius: 0.0,
+};
+
+/// The smaller of the factors `transform` scales the x and y axes by.
+pub(crate) fn min_axis_scale(transform: &nalgebra::Matrix4<f32>) -> f32 {
+    let x = transform[(0, 0)].hypot(transform[(1, 0)]);
+    let y = transform[(0, 1)].hypot(transform[(1, 1)]);
+    x.min(y)
+}
+
+/// Bounding box of the rectangle from the origin to `size` after `transform`.
+pub(crate) fn transformed_rect(transform: &nalgebra::Matrix4<f32>, size: [f32; 2]) -> [f32; 4] {
+    let mut rect = [
+        f32::INFINITY,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::NEG_INFINITY,
+    ];
+    for [x, y] in [[0.0, 0.0], [size[0], 0.0], [0.0, size[1]], size] {
+        let p = transform * nalgebra::Vector4::new(x, y, 0.0, 1.0);
+        rect[0] = rect[0].min(p.x);
+        rect[1] = rect[1].min(p.y);
+        rect[2] = rect[2].max(p.x);
+        rect[3] = rect[3].max(p.y);
+    }
+    rect
+}
+
+pub(crate) fn intersect_rect(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        a[0].max(b[0]),
+        a[1].max(b[1]),
+        a[2].min(b[2]),
+        a[3].min(b[3]),
+    ]
+}
+
+#[allow(clippy::too_many_arguments)]
+fn push_instance(
+    texture_format: wgpu::TextureFormat,
+    texture: &texture_atlas::AtlasRegion,
+    viewport_position: nalgebra::Matrix4<f32>,
+    instances: &mut Vec<InstanceData>,
+    texture_atlas_id: &mut Option<texture_atlas::TextureAtlasId>,
+    stencil_index: u32,
+    opacity: f32,
+    clip: Clip,
+) -> Result<(), TextureValidationError> {
+    if texture.format() != texture_format {
+        warn!("CoreRenderer: texture format mismatch");
+        return Err(TextureValidationError::FormatMismatch);
+    }
+
+    let atlas_id = texture_atlas_id.get_or_insert_with(|| texture.atlas_id());
+
+    if atlas_id != &texture.atlas_id() {
+        warn!("CoreRenderer: texture atlas id mismatch");
+        return Err(TextureValidationError::AtlasIdMismatch);
+    }
+
+    let (page, position_in_atlas) = texture.position_in_atlas()?;
+
+    instances.push(InstanceData {
+        viewport_position,
+        atlas_page: page,
+        in_atlas_offset: [position_in_atlas.min.x, position_in_atlas.min.y],
+        in_atlas_size: [position_in_atlas.width(), position_in_atlas.height()],
+        stencil_index,
+        _padding1: 0,
+        opacity,
+        clip_rect: clip.rect,
+        rounded_clip_rect: clip.rounded_rect,
+        clip_radius: clip.radius,
+        _padding2: [0; 3],
+    });
+
+    Ok(())
+}
+
+/// A GPU buffer that is reused across frames.
+///
+/// The buffer grows to the next power of two when the data does not fit and never shrinks.
+/// A CPU copy of the last upload is kept so only the range of elements that changed is
+/// written to the GPU.
+struct PersistentBuffer<T: bytemuck::Pod> {
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+    buffer: Option<wgpu::Buffer>,
+    // capacity in elements
+    capacity: usize,
+    memory: TrackedBytes,
+    // contents of the previous upload
+    shadow: Vec<T>,
+}
+
+impl<T: bytemuck::Pod> PersistentBuffer<T> {
+    fn new(label: &'static str, usage: wgpu::BufferUsages) -> Self {
+        Self {
+            label,
+            usage,
+            buffer: None,
+            capacity: 0,
+            memory: TrackedBytes::new(&RENDERER_BUFFERS),
+            shadow: Vec::new(),
+        }
+    }
+
+    /// Panics if neither `reserve` nor `upload` has been called.
+    fn buffer(&self) -> &wgpu::Buffer {
+        self.buffer
+            .as_ref()
+            .expect("PersistentBuffer::buffer: buffer is not allocated")
+    }
+
+    /// Ensures room for `len` elements. Returns `true` if the buffer was reallocated.
+    fn reserve(&mut self, device: &wgpu::Device, len: usize) -> bool {
+        if self.buffer.is_some() && len <= self.capacity {
+            return false;
+        }
+
+        let capacity = len.max(1).next_power_of_two();
+        trace!(
+            "PersistentBuffer::reserve: growing '{}' from {} to {} elements",
+            self.label, self.capacity, capacity
+        );
+        let size = (std::mem::size_of::<T>() * capacity) as u64;
+        self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(self.label),
+            size,
+            usage: self.usage,
+            mapped_at_creation: false,
+        }));
+        self.capacity = capacity;
+        self.memory.set(size);
+        // the new buffer holds no data
+        self.shadow.clear();
+        true
+    }
+
+    /// Writes `data` at the start of the buffer, uploading only the elements that differ
+    /// from the previous upload. Returns `true` if the buffer was reallocated.
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[T]) -> bool {
+        let reallocated = self.reserve(device, data.len());
+
+        let common = self.shadow.len().min(data.len());
+        let differs =
+            |i: &usize| bytemuck::bytes_of(&self.shadow[*i]) != bytemuck::bytes_of(&data[*i]);
+        let first_dirty = (0..common).find(differs).unwrap_or(common);
+        let dirty_end = if data.len() > common {
+            data.len()
+        } else {
+            (first_dirty..common)
+                .rev()
+                .find(differs)
+                .map_or(first_dirty, |i| i + 1)
+        };
+
+        if first_dirty < dirty_end {
+            queue.write_buffer(
+                self.buffer(),
+                (std::mem::size_of::<T>() * first_dirty) as u64,
+                bytemuck::cast_slice(&data[first_dirty..dirty_end]),
+            );
+        }
+
+        self.shadow.clear();
+        self.shadow.extend_from_slice(data);
+        reallocated
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum TextureValidationError {
     #[error("texture format mismatch")]
@@ -907,3 +1824,490 @@ fn make_normalize_matrix(destination_size: [f32; 2]) -> nalgebra::Matrix4<f32> {
         0.0, 0.0, 0.0, 1.0,
     )
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use gpu_utils::texture_atlas::TextureAtlas;
+
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    // two pages: the GL backend cannot view a texture with a single layer as an array
+    fn atlas(device: &wgpu::Device) -> Arc<TextureAtlas> {
+        TextureAtlas::new(
+            device,
+            wgpu::Extent3d {
+                width: 256,
+                height: 256,
+                depth_or_array_layers: 2,
+            },
+            FORMAT,
+            0,
+        )
+    }
+
+    fn filled_region(
+        atlas: &TextureAtlas,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: [u32; 2],
+        color: [u8; 4],
+    ) -> texture_atlas::AtlasRegion {
+        let region = atlas.allocate(device, queue, size).unwrap();
+        let data = color.repeat((size[0] * size[1]) as usize);
+        region.write_data(queue, &data).unwrap();
+        region
+    }
+
+    fn translation(x: f32, y: f32) -> nalgebra::Matrix4<f32> {
+        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0))
+    }
+
+    /// An unclipped instance covering `rect` ([min x, min y, max x, max y]).
+    fn instance_covering(rect: [f32; 4]) -> InstanceData {
+        InstanceData {
+            viewport_position: translation(rect[0], rect[1])
+                * nalgebra::Matrix4::new_nonuniform_scaling(&nalgebra::Vector3::new(
+                    rect[2] - rect[0],
+                    rect[3] - rect[1],
+                    1.0,
+                )),
+            clip_rect: NO_CLIP.rect,
+            rounded_clip_rect: NO_CLIP.rounded_rect,
+            opacity: 1.0,
+            ..bytemuck::Zeroable::zeroed()
+        }
+    }
+
+    #[tokio::test]
+    async fn backdrop_blur_is_applied_before_the_node_texture() {
+        let (_, _, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let atlas = atlas(&device);
+        let background = filled_region(&atlas, &device, &queue, [64, 64], [255, 0, 0, 255]);
+        let panel = filled_region(&atlas, &device, &queue, [32, 32], [0, 0, 0, 0]);
+        let root = RenderNode::new()
+            .with_texture(background, [64.0, 64.0], nalgebra::Matrix4::identity())
+            .add_child(
+                RenderNode::new()
+                    .with_backdrop_blur([32.0, 32.0], 4.0)
+                    .with_texture(panel, [32.0, 32.0], nalgebra::Matrix4::identity())
+                    .with_clip([24.0, 24.0]),
+                translation(16.0, 16.0),
+            );
+
+        let (instances, _, _, backdrops) =
+            create_instance_and_stencil_data(&root, FORMAT, FORMAT).unwrap();
+
+        assert_eq!(instances.len(), 2);
+        assert_eq!(backdrops.len(), 1);
+        // drawn over the background, under the panel
+        assert_eq!(backdrops[0].instance, 1);
+        // clipped like the node
+        assert_eq!(backdrops[0].rect, [16.0, 16.0, 40.0, 40.0]);
+        assert_eq!(backdrops[0].radius, 4.0);
+    }
+
+    #[test]
+    fn occluders_hide_only_instances_they_cover_completely() {
+        let mut instances = vec![
+            // fully covered
+            instance_covering([10.0, 10.0, 20.0, 20.0]),
+            // partially covered
+            instance_covering([30.0, 30.0, 60.0, 60.0]),
+            // uncovered
+            instance_covering([60.0, 60.0, 70.0, 70.0]),
+            instance_covering([0.0, 0.0, 50.0, 50.0]),
+        ];
+        let occluders = [Occluder {
+            instance: 3,
+            rect: [0.0, 0.0, 50.0, 50.0],
+        }];
+
+        let occluded = cull_occluded_instances(&mut instances, &occluders, &mut []);
+
+        assert_eq!(occluded, 1);
+        assert_eq!(instances.len(), 3);
+        assert_eq!(
+            transformed_rect(&instances[0].viewport_position, [1.0, 1.0]),
+            [30.0, 30.0, 60.0, 60.0]
+        );
+        assert_eq!(
+            transformed_rect(&instances[1].viewport_position, [1.0, 1.0]),
+            [60.0, 60.0, 70.0, 70.0]
+        );
+    }
+
+    #[test]
+    fn occluders_do_not_hide_instances_drawn_over_them() {
+        let mut instances = vec![
+            instance_covering([0.0, 0.0, 50.0, 50.0]),
+            instance_covering([10.0, 10.0, 20.0, 20.0]),
+        ];
+        let occluders = [Occluder {
+            instance: 0,
+            rect: [0.0, 0.0, 50.0, 50.0],
+        }];
+
+        let occluded = cull_occluded_instances(&mut instances, &occluders, &mut []);
+
+        assert_eq!(occluded, 0);
+        assert_eq!(instances.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn opaque_textures_become_occluders() {
+        let (_, _, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let atlas = atlas(&device);
+        let background = filled_region(&atlas, &device, &queue, [64, 64], [255, 0, 0, 255]);
+        let opaque = filled_region(&atlas, &device, &queue, [32, 32], [0, 0, 255, 255]);
+        let translucent = filled_region(&atlas, &device, &queue, [32, 32], [0, 0, 255, 128]);
+        let root = RenderNode::new()
+            .with_texture(background, [64.0, 64.0], nalgebra::Matrix4::identity())
+            .add_child(
+                RenderNode::new()
+                    .with_texture(opaque, [32.0, 32.0], nalgebra::Matrix4::identity())
+                    .with_opaque_texture(),
+                translation(8.0, 8.0),
+            )
+            .add_child(
+                RenderNode::new().with_texture(
+                    translucent,
+                    [32.0, 32.0],
+                    nalgebra::Matrix4::identity(),
+                ),
+                translation(16.0, 16.0),
+            );
+
+        let (instances, _, occluders, _) =
+            create_instance_and_stencil_data(&root, FORMAT, FORMAT).unwrap();
+
+        assert_eq!(instances.len(), 3);
+        assert_eq!(occluders.len(), 1);
+        assert_eq!(occluders[0].instance, 1);
+        assert_eq!(occluders[0].rect, [8.0, 8.0, 40.0, 40.0]);
+    }
+
+    #[test]
+    fn occluders_do_not_hide_instances_below_a_backdrop_blur() {
+        let mut instances = vec![
+            instance_covering([0.0, 0.0, 10.0, 10.0]),
+            instance_covering([0.0, 0.0, 10.0, 10.0]),
+            instance_covering([0.0, 0.0, 20.0, 20.0]),
+            instance_covering([0.0, 0.0, 20.0, 20.0]),
+        ];
+        let occluders = [
+            Occluder {
+                instance: 1,
+                rect: [0.0, 0.0, 10.0, 10.0],
+            },
+            Occluder {
+                instance: 3,
+                rect: [0.0, 0.0, 20.0, 20.0],
+            },
+        ];
+        // blurs what instances 0 and 1 drew
+        let mut backdrops = [Backdrop {
+            instance: 2,
+            rect: [0.0, 0.0, 20.0, 20.0],
+            radius: 4.0,
+        }];
+
+        let occluded = cull_occluded_instances(&mut instances, &occluders, &mut backdrops);
+
+        // instance 0 is hidden by instance 1 before the blur, instance 2 by instance 3 after
+        // it; instance 1 stays since the blur reads it
+        assert_eq!(occluded, 2);
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].clip_rect, NO_CLIP.rect);
+        assert_eq!(backdrops[0].instance, 1);
+    }
+
+    #[test]
+    fn instances_clear_of_a_backdrop_blur_join_the_batch_before_it() {
+        let instances = vec![
+            instance_covering([0.0, 0.0, 20.0, 20.0]),
+            // drawn over the blurred rectangle
+            instance_covering([10.0, 10.0, 30.0, 30.0]),
+            // clear of the blur, but over the instance before it
+            instance_covering([25.0, 25.0, 60.0, 60.0]),
+            // clear of both
+            instance_covering([70.0, 0.0, 90.0, 20.0]),
+        ];
+        let backdrops = [Backdrop {
+            instance: 1,
+            rect: [0.0, 0.0, 20.0, 20.0],
+            radius: 4.0,
+        }];
+
+        let (visible, ends) = batch_around_backdrops(&instances, &[0, 1, 2, 3], &backdrops);
+
+        assert_eq!(visible, [0, 3, 1, 2]);
+        assert_eq!(ends, [2, 4]);
+
+        // within the blur radius
+        let instances = vec![
+            instance_covering([0.0, 0.0, 20.0, 20.0]),
+            instance_covering([22.0, 0.0, 40.0, 20.0]),
+        ];
+        let (visible, ends) = batch_around_backdrops(&instances, &[0, 1], &backdrops);
+
+        assert_eq!(visible, [0, 1]);
+        assert_eq!(ends, [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn adapters_with_vertex_storage_are_supported() {
+        let (_, adapter, _, _) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        assert!(CoreRenderer::supports_adapter(&adapter));
+    }
+
+    /// Renders `root` into a sampleable 64x64 target and returns the stats of the frame.
+    fn render_stats(
+        renderer: &CoreRenderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        atlas: &TextureAtlas,
+        root: &RenderNode,
+    ) -> RenderStats {
+        let stencil_atlas = TextureAtlas::new(
+            device,
+            wgpu::Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 2,
+            },
+            FORMAT,
+            0,
+        );
+        let destination = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("stats test destination"),
+            size: wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        renderer
+            .render(
+                device,
+                queue,
+                FORMAT,
+                &destination.create_view(&wgpu::TextureViewDescriptor::default()),
+                [64.0, 64.0],
+                root,
+                wgpu::Color::TRANSPARENT,
+                &atlas.texture(),
+                &stencil_atlas.texture(),
+            )
+            .unwrap();
+        renderer.last_frame_stats()
+    }
+
+    #[tokio::test]
+    async fn render_stats_count_batches_and_backdrop_blurs() {
+        let (_, adapter, _, _) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        // what the application requests
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: wgpu::Features::VERTEX_WRITABLE_STORAGE
+                    | wgpu::Features::PUSH_CONSTANTS,
+                required_limits: wgpu::Limits {
+                    max_push_constant_size: 128,
+                    ..wgpu::Limits::default()
+                },
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let renderer =
+            CoreRenderer::with_culling_mode(&device, CullingMode::Cpu).with_occlusion_culling(true);
+        let atlas = atlas(&device);
+        let region = |color| filled_region(&atlas, &device, &queue, [32, 32], color);
+        let node = |color| {
+            RenderNode::new().with_texture(
+                region(color),
+                [32.0, 32.0],
+                nalgebra::Matrix4::identity(),
+            )
+        };
+
+        // every instance in one batch
+        let root = RenderNode::new()
+            .add_child(node([255, 0, 0, 255]), translation(0.0, 0.0))
+            .add_child(node([0, 255, 0, 255]), translation(16.0, 16.0));
+        let stats = render_stats(&renderer, &device, &queue, &atlas, &root);
+        assert_eq!(stats.instances, 2);
+        assert_eq!(stats.visible_instances, Some(2));
+        assert_eq!(stats.draw_calls, 1);
+        assert_eq!(stats.backdrop_blurs, 0);
+
+        // each blur ends a batch
+        let root = RenderNode::new()
+            .add_child(node([255, 0, 0, 255]), translation(0.0, 0.0))
+            .add_child(
+                node([0, 0, 0, 0]).with_backdrop_blur([32.0, 32.0], 4.0),
+                translation(8.0, 8.0),
+            )
+            .add_child(
+                node([0, 0, 0, 0]).with_backdrop_blur([32.0, 32.0], 4.0),
+                translation(16.0, 16.0),
+            );
+        let stats = render_stats(&renderer, &device, &queue, &atlas, &root);
+        assert_eq!(stats.instances, 3);
+        assert_eq!(stats.backdrop_blurs, 2);
+        assert_eq!(stats.draw_calls, 3);
+
+        // a blur before anything is drawn leaves an empty batch, which is not drawn
+        let root = RenderNode::new()
+            .with_backdrop_blur([64.0, 64.0], 4.0)
+            .add_child(node([255, 0, 0, 255]), translation(0.0, 0.0));
+        let stats = render_stats(&renderer, &device, &queue, &atlas, &root);
+        assert_eq!(stats.backdrop_blurs, 1);
+        assert_eq!(stats.draw_calls, 1);
+
+        // an instance clear of the blur is drawn with the ones below it
+        let root = RenderNode::new()
+            .add_child(node([255, 0, 0, 255]), translation(0.0, 0.0))
+            .add_child(
+                RenderNode::new().with_backdrop_blur([16.0, 16.0], 4.0),
+                translation(0.0, 0.0),
+            )
+            .add_child(node([0, 255, 0, 255]), translation(32.0, 32.0));
+        let stats = render_stats(&renderer, &device, &queue, &atlas, &root);
+        assert_eq!(stats.visible_instances, Some(2));
+        assert_eq!(stats.backdrop_blurs, 1);
+        assert_eq!(stats.draw_calls, 1);
+
+        // hidden instances are counted but not drawn
+        let root = RenderNode::new()
+            .add_child(node([255, 0, 0, 255]), translation(8.0, 8.0))
+            .add_child(
+                RenderNode::new()
+                    .with_texture(
+                        region([0, 255, 0, 255]),
+                        [64.0, 64.0],
+                        nalgebra::Matrix4::identity(),
+                    )
+                    .with_opaque_texture(),
+                translation(0.0, 0.0),
+            );
+        let stats = render_stats(&renderer, &device, &queue, &atlas, &root);
+        assert_eq!(stats.instances, 2);
+        assert_eq!(stats.occluded_instances, 1);
+        assert_eq!(stats.visible_instances, Some(1));
+        assert_eq!(stats.draw_calls, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a GPU adapter"]
+    async fn backdrop_blur_shows_the_content_behind_it() {
+        let (_, adapter, device, queue) = gpu_utils::wgpu_utils::headless_wgpu(
+            wgpu::Limits {
+                max_push_constant_size: 128,
+                ..wgpu::Limits::default()
+            },
+            // what the application requests as well
+            wgpu::Features::VERTEX_WRITABLE_STORAGE | wgpu::Features::PUSH_CONSTANTS,
+        )
+        .await
+        .expect("no GPU adapter available");
+        let renderer = CoreRenderer::with_culling_mode(&device, CullingMode::for_adapter(&adapter));
+        let atlas = atlas(&device);
+        let stencil_atlas = TextureAtlas::new(
+            &device,
+            wgpu::Extent3d {
+                width: 16,
+                height: 16,
+                depth_or_array_layers: 2,
+            },
+            FORMAT,
+            0,
+        );
+
+        // a transparent panel blurring an opaque red background
+        let background = filled_region(&atlas, &device, &queue, [64, 64], [255, 0, 0, 255]);
+        let panel = filled_region(&atlas, &device, &queue, [32, 32], [0, 0, 0, 0]);
+        let root = RenderNode::new()
+            .with_texture(background, [64.0, 64.0], nalgebra::Matrix4::identity())
+            .add_child(
+                RenderNode::new()
+                    .with_backdrop_blur([32.0, 32.0], 4.0)
+                    .with_texture(panel, [32.0, 32.0], nalgebra::Matrix4::identity()),
+                translation(16.0, 16.0),
+            );
+
+        let destination = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("backdrop test destination"),
+            size: wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        renderer
+            .render(
+                &device,
+                &queue,
+                FORMAT,
+                &destination.create_view(&wgpu::TextureViewDescriptor::default()),
+                [64.0, 64.0],
+                &root,
+                wgpu::Color::TRANSPARENT,
+                &atlas.texture(),
+                &stencil_atlas.texture(),
+            )
+            .unwrap();
+
+        let stats = renderer.last_frame_stats();
+        assert_eq!(stats.backdrop_blurs, 1);
+        assert_eq!(stats.draw_calls, 2);
+
+        // 64 texels of 4 bytes fill the 256 byte row alignment exactly
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("backdrop test readback"),
+            size: 64 * 64 * 4,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            destination.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(64 * 4),
+                    rows_per_image: Some(64),
+                },
+            },
+            destination.size(),
+        );
+        queue.submit(Some(encoder.finish()));
+        readback.slice(..).map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::PollType::Wait).unwrap();
+        let pixels = readback.slice(..).get_mapped_range();
+
+        let center = (32 * 64 + 32) * 4;
+        let [r, _, _, a] = [0, 1, 2, 3].map(|channel| pixels[center + channel]);
+        assert!(a > 0, "the blurred backdrop is transparent");
+        assert!(
+            r > 200,
+            "the blurred backdrop lost the background color: {r}"
+        );
+    }
+}
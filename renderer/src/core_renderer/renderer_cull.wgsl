@@ -14,6 +14,11 @@
 ////   values (0.0 .. 1.0). If atlas returns pixel sizes, normalize on the host side.
 //// - `stencil_index`: index+1 of the associated stencil in the stencil data array.
 ////   0 indicates "no stencil". The shader uses `stencil_index - 1` to access the stencil.
+//// - `opacity`: multiplier of the alpha of the instance.
+//// - `clip_rect`: (min x, min y, max x, max y) in the destination coordinate space before
+////   normalization; fragments outside are discarded.
+//// - `rounded_clip_rect` / `clip_radius`: a rectangle in the same space whose corners are
+////   rounded by `clip_radius`; fragments outside fade out. A radius of 0 disables it.
 ////
 //// NOTE: Keep WGSL-side layout (field order and explicit padding) compatible with the
 //// Rust `InstanceData` declaration. When changing fields, update both Rust and WGSL.
@@ -24,7 +29,11 @@ struct InstanceData {
     in_atlas_offset: vec2<f32>,
     in_atlas_size: vec2<f32>,
     stencil_index: u32,
-    _padding2: u32,
+    opacity: f32,
+    clip_rect: vec4<f32>,
+    rounded_clip_rect: vec4<f32>,
+    clip_radius: f32,
+    _padding2: array<u32, 3>,
 };
 
 //// StencilData describes a stencil polygon used to mask instances.
@@ -168,12 +177,13 @@ fn point_in_polygon(
         polygon[0].xy - polygon[3].xy,
     );
 
-    let signs = array<bool, 4>(
-        cross_2d(points[0], lines[0]) > 0.0,
-        cross_2d(points[1], lines[1]) > 0.0,
-        cross_2d(points[2], lines[2]) > 0.0,
-        cross_2d(points[3], lines[3]) > 0.0,
+    let crosses = vec4<f32>(
+        cross_2d(points[0], lines[0]),
+        cross_2d(points[1], lines[1]),
+        cross_2d(points[2], lines[2]),
+        cross_2d(points[3], lines[3]),
     );
 
-    return signs[0] == signs[1] && signs[1] == signs[2] && signs[2] == signs[3];
+    // a point on an edge is inside, so a quad exactly covering the target is visible
+    return all(crosses >= vec4<f32>(0.0)) || all(crosses <= vec4<f32>(0.0));
 }
@@ -14,6 +14,11 @@
 //   values (0.0 .. 1.0). If atlas returns pixel sizes, normalize on the host side.
 // - `stencil_index`: index+1 of the associated stencil in the stencil data array.
 //   0 indicates "no stencil". The shader uses `stencil_index - 1` to access the stencil.
+// - `opacity`: multiplier of the alpha of the instance.
+// - `clip_rect`: (min x, min y, max x, max y) in the destination coordinate space before
+//   normalization; fragments outside are discarded.
+// - `rounded_clip_rect` / `clip_radius`: a rectangle in the same space whose corners are
+//   rounded by `clip_radius`; fragments outside fade out. A radius of 0 disables it.
 //
 // NOTE: Keep WGSL-side layout (field order and explicit padding) compatible with the
 // Rust `InstanceData` declaration. When changing fields, update both Rust and WGSL.
@@ -24,7 +29,11 @@ struct InstanceData {
     in_atlas_offset: vec2<f32>,
     in_atlas_size: vec2<f32>,
     stencil_index: u32,
-    _padding2: u32,
+    opacity: f32,
+    clip_rect: vec4<f32>,
+    rounded_clip_rect: vec4<f32>,
+    clip_radius: f32,
+    _padding2: array<u32, 3>,
 };
 
 // StencilData describes a stencil polygon used to mask instances.
@@ -66,6 +75,12 @@ struct VertexOutput {
     @location(6) stencil_atlas_page: u32,
     @location(7) stencil_atlas_bounds_x: vec2<f32>,
     @location(8) stencil_atlas_bounds_y: vec2<f32>,
+    // opacity and clipping
+    @location(9) opacity: f32,
+    @location(10) destination_position: vec2<f32>,
+    @location(11) clip_rect: vec4<f32>,
+    @location(12) rounded_clip_rect: vec4<f32>,
+    @location(13) clip_radius: f32,
 };
 
 @group(0) @binding(0) var texture_sampler: sampler;
@@ -138,9 +153,24 @@ fn vertex_main(
     output.stencil_atlas_page = stencil.atlas_page;
     output.stencil_atlas_bounds_x = vec2<f32>(stencil.in_atlas_offset.x, stencil.in_atlas_offset.x + stencil.in_atlas_size.x);
     output.stencil_atlas_bounds_y = vec2<f32>(stencil.in_atlas_offset.y, stencil.in_atlas_offset.y + stencil.in_atlas_size.y);
+    output.opacity = instance.opacity;
+    output.destination_position = pre.xy / pre.w;
+    output.clip_rect = instance.clip_rect;
+    output.rounded_clip_rect = instance.rounded_clip_rect;
+    output.clip_radius = instance.clip_radius;
     return output;
 }
 
+// Share of the pixel at `position` inside `rect` (min x, min y, max x, max y) with corners
+// rounded by `radius`, from the signed distance to its border.
+fn rounded_rect_coverage(position: vec2<f32>, rect: vec4<f32>, radius: f32) -> f32 {
+    let half_size = (rect.zw - rect.xy) * 0.5;
+    let r = min(radius, min(half_size.x, half_size.y));
+    let q = abs(position - (rect.xy + rect.zw) * 0.5) - half_size + vec2<f32>(r);
+    let distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - r;
+    return clamp(0.5 - distance, 0.0, 1.0);
+}
+
 @fragment
 fn fragment_main(
     @location(0) texture_uv: vec2<f32>,
@@ -151,7 +181,12 @@ fn fragment_main(
     @location(5) stencil_uv: vec2<f32>,
     @location(6) stencil_atlas_page: u32,
     @location(7) stencil_atlas_bounds_x: vec2<f32>,
-    @location(8) stencil_atlas_bounds_y: vec2<f32>
+    @location(8) stencil_atlas_bounds_y: vec2<f32>,
+    @location(9) opacity: f32,
+    @location(10) destination_position: vec2<f32>,
+    @location(11) clip_rect: vec4<f32>,
+    @location(12) rounded_clip_rect: vec4<f32>,
+    @location(13) clip_radius: f32
 ) -> @location(0) vec4<f32> {
     let use_stencil = use_stencil_num != 0u;
 
@@ -186,7 +221,19 @@ fn fragment_main(
         use_stencil
     );
 
-    let final_color = texture_color * stencil;
+    // after sampling, which needs uniform control flow
+    if any(destination_position < clip_rect.xy) || any(destination_position >= clip_rect.zw) {
+        discard;
+    }
+
+    let coverage = select(
+        /*false*/ 1.0,
+        /*true*/  rounded_rect_coverage(destination_position, rounded_clip_rect, clip_radius),
+        clip_radius > 0.0
+    );
+
+    let stenciled_color = texture_color * stencil;
+    let final_color = vec4<f32>(stenciled_color.rgb, stenciled_color.a * opacity * coverage);
 
     return final_color;
 }
@@ -0,0 +1,336 @@
+//! Structural dump of a [`RenderNode`] tree as JSON, for debugging and external tooling.
+//!
+//! Snapshot tests diff the dump instead of (or in addition to) the rendered pixels, so a
+//! change shows up as the node, transform or atlas region that moved. The output is
+//! deterministic for the same tree: fields are written in a fixed order, children in the
+//! order they were added, and atlases are numbered in the order the dump first meets them
+//! rather than by their process-wide id.
+//!
+//! Every node is an object with the fields below; fields without a value are `null`.
+//!
+//! - `texture`, `stencil`: the atlas region (`atlas`, `page`, `rect` as `[x, y, width,
+//!   height]` in the atlas page, `size`, `format`, `valid`) and its `transform`.
+//! - `layer`: the size of the layer cache.
+//! - `clip`, `clip_radius`, `opacity`, `opaque`, `z_index`.
+//! - `backdrop_blur`: `[width, height, radius]` of the blurred rectangle.
+//! - `children`: objects with the `transform` of the child and the child as `node`.
+//!
+//! Transforms are the 16 elements of the matrix, row by row. Non-finite numbers are written
+//! as `null`. Pixel contents are not included; use [`export_svg`](crate::export_svg) for those.
+
+use std::fmt::Write as _;
+
+use gpu_utils::texture_atlas::{AtlasRegion, TextureAtlasId};
+
+use crate::RenderNode;
+
+impl RenderNode {
+    /// Serializes this node and its descendants as an indented JSON document, see
+    /// [`debug_json`](crate::debug_json).
+    pub fn to_debug_json(&self) -> String {
+        let mut writer = JsonWriter {
+            out: String::new(),
+            atlases: Vec::new(),
+        };
+        writer.node(self, 0);
+        writer.out.push('\n');
+        writer.out
+    }
+}
+
+struct JsonWriter {
+    out: String,
+    // atlases in the order they were first referenced
+    atlases: Vec<TextureAtlasId>,
+}
+
+impl JsonWriter {
+    fn node(&mut self, node: &RenderNode, depth: usize) {
+        let inner = depth + 1;
+        self.out.push_str("{\n");
+
+        self.key(inner, "texture");
+        self.placed_region(node.texture(), inner);
+        self.out.push_str(",\n");
+        self.key(inner, "stencil");
+        self.placed_region(node.stencil(), inner);
+        self.out.push_str(",\n");
+
+        self.key(inner, "layer");
+        match node.layer() {
+            Some((_, size)) => self.numbers(size),
+            None => self.out.push_str("null"),
+        }
+        self.out.push_str(",\n");
+        self.key(inner, "clip");
+        match node.clip() {
+            Some(size) => self.numbers(&size),
+            None => self.out.push_str("null"),
+        }
+        self.out.push_str(",\n");
+        self.key(inner, "clip_radius");
+        self.number(node.clip_radius());
+        self.out.push_str(",\n");
+        self.key(inner, "opacity");
+        self.number(node.opacity());
+        self.out.push_str(",\n");
+        self.key(inner, "opaque");
+        let _ = write!(self.out, "{}", node.is_opaque());
+        self.out.push_str(",\n");
+        self.key(inner, "z_index");
+        let _ = write!(self.out, "{}", node.z_index());
+        self.out.push_str(",\n");
+        self.key(inner, "backdrop_blur");
+        match node.backdrop_blur() {
+            Some((size, radius)) => self.numbers(&[size[0], size[1], radius]),
+            None => self.out.push_str("null"),
+        }
+        self.out.push_str(",\n");
+
+        self.key(inner, "children");
+        let children = node.child_elements();
+        if children.is_empty() {
+            self.out.push_str("[]");
+        } else {
+            self.out.push_str("[\n");
+            for (i, (child, transform)) in children.iter().enumerate() {
+                indent(&mut self.out, inner + 1);
+                self.out.push_str("{\n");
+                self.key(inner + 2, "transform");
+                self.matrix(transform);
+                self.out.push_str(",\n");
+                self.key(inner + 2, "node");
+                self.node(child, inner + 2);
+                self.out.push('\n');
+                indent(&mut self.out, inner + 1);
+                self.out.push('}');
+                if i + 1 < children.len() {
+                    self.out.push(',');
+                }
+                self.out.push('\n');
+            }
+            indent(&mut self.out, inner);
+            self.out.push(']');
+        }
+        self.out.push('\n');
+
+        indent(&mut self.out, depth);
+        self.out.push('}');
+    }
+
+    fn placed_region(
+        &mut self,
+        placed: Option<&(AtlasRegion, nalgebra::Matrix4<f32>)>,
+        depth: usize,
+    ) {
+        let Some((region, transform)) = placed else {
+            self.out.push_str("null");
+            return;
+        };
+        let inner = depth + 1;
+        self.out.push_str("{\n");
+
+        let atlas = match self.atlases.iter().position(|&id| id == region.atlas_id()) {
+            Some(index) => index,
+            None => {
+                self.atlases.push(region.atlas_id());
+                self.atlases.len() - 1
+            }
+        };
+        self.key(inner, "atlas");
+        let _ = write!(self.out, "{atlas}");
+        self.out.push_str(",\n");
+
+        // a stale region has no position in its atlas anymore
+        let position = region.position_in_atlas().ok();
+        self.key(inner, "page");
+        match position {
+            Some((page, _)) => {
+                let _ = write!(self.out, "{page}");
+            }
+            None => self.out.push_str("null"),
+        }
+        self.out.push_str(",\n");
+        self.key(inner, "rect");
+        match position {
+            Some((_, rect)) => self.numbers(&[rect.min.x, rect.min.y, rect.width(), rect.height()]),
+            None => self.out.push_str("null"),
+        }
+        self.out.push_str(",\n");
+
+        let [width, height] = region.texture_size();
+        self.key(inner, "size");
+        let _ = write!(self.out, "[{width}, {height}]");
+        self.out.push_str(",\n");
+        self.key(inner, "format");
+        let _ = write!(self.out, "\"{:?}\"", region.format());
+        self.out.push_str(",\n");
+        self.key(inner, "valid");
+        let _ = write!(self.out, "{}", region.is_valid());
+        self.out.push_str(",\n");
+        self.key(inner, "transform");
+        self.matrix(transform);
+        self.out.push('\n');
+
+        indent(&mut self.out, depth);
+        self.out.push('}');
+    }
+
+    fn key(&mut self, depth: usize, key: &str) {
+        indent(&mut self.out, depth);
+        let _ = write!(self.out, "\"{key}\": ");
+    }
+
+    fn matrix(&mut self, matrix: &nalgebra::Matrix4<f32>) {
+        let rows: Vec<f32> = (0..4)
+            .flat_map(|row| (0..4).map(move |column| matrix[(row, column)]))
+            .collect();
+        self.numbers(&rows);
+    }
+
+    fn numbers(&mut self, values: &[f32]) {
+        self.out.push('[');
+        for (i, &value) in values.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.number(value);
+        }
+        self.out.push(']');
+    }
+
+    fn number(&mut self, value: f32) {
+        if value.is_finite() {
+            let _ = write!(self.out, "{value}");
+        } else {
+            self.out.push_str("null");
+        }
+    }
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::Arc;
+
+    use gpu_utils::texture_atlas::TextureAtlas;
+
+    use super::*;
+
+    fn atlas(device: &wgpu::Device) -> Arc<TextureAtlas> {
+        TextureAtlas::new(
+            device,
+            wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            0,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_to_debug_json() {
+        let (_, _, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let first_atlas = atlas(&device);
+        let second_atlas = atlas(&device);
+        let icon = first_atlas.allocate(&device, &queue, [4, 4]).unwrap();
+        let panel = second_atlas.allocate(&device, &queue, [8, 8]).unwrap();
+        let mask = second_atlas.allocate(&device, &queue, [4, 4]).unwrap();
+
+        let root = RenderNode::new()
+            .with_clip([f32::INFINITY, 10.0])
+            .add_child(
+                RenderNode::new()
+                    .with_texture(panel, [8.0, 8.0], nalgebra::Matrix4::identity())
+                    .add_child(
+                        RenderNode::new()
+                            .with_texture(icon, [4.0, 4.0], nalgebra::Matrix4::identity())
+                            .with_stencil(mask, [4.0, 4.0], nalgebra::Matrix4::identity())
+                            .with_opacity(0.5)
+                            .with_z_index(1),
+                        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(2.0, 3.0, 0.0)),
+                    ),
+                nalgebra::Matrix4::identity(),
+            );
+
+        // the second atlas is met first, so it is numbered 0; the infinite clip width is null
+        let expected = r#"{
+  "texture": null,
+  "stencil": null,
+  "layer": null,
+  "clip": [null, 10],
+  "clip_radius": 0,
+  "opacity": 1,
+  "opaque": false,
+  "z_index": 0,
+  "backdrop_blur": null,
+  "children": [
+    {
+      "transform": [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1],
+      "node": {
+        "texture": {
+          "atlas": 0,
+          "page": 0,
+          "rect": [0, 0, 0.125, 0.125],
+          "size": [8, 8],
+          "format": "Rgba8Unorm",
+          "valid": true,
+          "transform": [8, 0, 0, 0, 0, 8, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]
+        },
+        "stencil": null,
+        "layer": null,
+        "clip": null,
+        "clip_radius": 0,
+        "opacity": 1,
+        "opaque": false,
+        "z_index": 0,
+        "backdrop_blur": null,
+        "children": [
+          {
+            "transform": [1, 0, 0, 2, 0, 1, 0, 3, 0, 0, 1, 0, 0, 0, 0, 1],
+            "node": {
+              "texture": {
+                "atlas": 1,
+                "page": 0,
+                "rect": [0, 0, 0.0625, 0.0625],
+                "size": [4, 4],
+                "format": "Rgba8Unorm",
+                "valid": true,
+                "transform": [4, 0, 0, 0, 0, 4, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]
+              },
+              "stencil": {
+                "atlas": 0,
+                "page": 0,
+                "rect": [0.125, 0, 0.0625, 0.0625],
+                "size": [4, 4],
+                "format": "Rgba8Unorm",
+                "valid": true,
+                "transform": [4, 0, 0, 0, 0, 4, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1]
+              },
+              "layer": null,
+              "clip": null,
+              "clip_radius": 0,
+              "opacity": 0.5,
+              "opaque": false,
+              "z_index": 1,
+              "backdrop_blur": null,
+              "children": []
+            }
+          }
+        ]
+      }
+    }
+  ]
+}
+"#;
+        assert_eq!(root.to_debug_json(), expected);
+    }
+}
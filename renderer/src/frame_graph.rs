@@ -0,0 +1,402 @@
+//! A small frame graph for ordering GPU passes.
+//!
+//! Passes declare the resources they read and write, and [`FrameGraph::execute`] runs them
+//! in dependency order and skips passes whose output is never used.
+//!
+//! Within a frame the writers of a resource run in the order they were added, and every pass
+//! that only reads the resource runs after all of them, so a read sees the final contents of
+//! the frame. A pass that reads and writes the same resource counts as a writer.
+//!
+//! ```ignore
+//! let mut graph = FrameGraph::new();
+//! let surface = graph.surface("surface");
+//! let shadow = graph.transient("shadow");
+//!
+//! graph.pass("ui").reads(shadow).writes(surface).run(|ctx| draw_ui(ctx));
+//! graph.pass("shadow blur").writes(shadow).run(|ctx| blur(ctx));
+//!
+//! // runs "shadow blur" before "ui"
+//! let report = graph.execute(&device, &queue)?;
+//! ```
+
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+
+use gpu_utils::texture_atlas::AtlasRegion;
+use log::trace;
+use thiserror::Error;
+
+type PassFn<'a> = Box<dyn FnOnce(&mut PassContext<'_>) -> Result<(), PassError> + 'a>;
+
+/// Error returned by a pass.
+pub type PassError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A resource declared in a [`FrameGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceKind {
+    /// Presented to the screen.
+    Surface,
+    /// Atlas content that outlives the frame.
+    Region,
+    /// Only exists to pass data between passes of this frame.
+    Transient,
+}
+
+struct Resource {
+    label: String,
+    kind: ResourceKind,
+    region: Option<AtlasRegion>,
+}
+
+struct Pass<'a> {
+    label: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    run: PassFn<'a>,
+}
+
+/// What a pass receives when it runs.
+///
+/// Each pass records into its own encoder, which is submitted right after the pass returns,
+/// so buffer writes through `queue` are ordered with the pass.
+pub struct PassContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+}
+
+/// Passes and resources of one frame. See the [module documentation](self).
+#[derive(Default)]
+pub struct FrameGraph<'a> {
+    resources: Vec<Resource>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> FrameGraph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A surface target. Passes writing it are never skipped.
+    pub fn surface(&mut self, label: &str) -> ResourceId {
+        self.add_resource(label, ResourceKind::Surface, None)
+    }
+
+    /// An atlas region. Its content outlives the frame, so passes writing it are never skipped.
+    ///
+    /// Importing the same region twice returns the same id.
+    pub fn region(&mut self, label: &str, region: &AtlasRegion) -> ResourceId {
+        if let Some(index) = self
+            .resources
+            .iter()
+            .position(|r| r.region.as_ref() == Some(region))
+        {
+            return ResourceId(index);
+        }
+        self.add_resource(label, ResourceKind::Region, Some(region.clone()))
+    }
+
+    /// A resource used only within this frame. Passes writing it are skipped when no
+    /// executed pass reads it.
+    pub fn transient(&mut self, label: &str) -> ResourceId {
+        self.add_resource(label, ResourceKind::Transient, None)
+    }
+
+    /// Starts declaring a pass.
+    pub fn pass(&mut self, label: &str) -> PassBuilder<'_, 'a> {
+        PassBuilder {
+            graph: self,
+            label: label.to_string(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+        }
+    }
+
+    pub fn resource_label(&self, id: ResourceId) -> &str {
+        &self.resources[id.0].label
+    }
+
+    fn add_resource(
+        &mut self,
+        label: &str,
+        kind: ResourceKind,
+        region: Option<AtlasRegion>,
+    ) -> ResourceId {
+        self.resources.push(Resource {
+            label: label.to_string(),
+            kind,
+            region,
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+}
+
+/// Declares the reads and writes of a pass. The pass is added by [`run`](Self::run).
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut FrameGraph<'a>,
+    label: String,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+}
+
+impl<'a> PassBuilder<'_, 'a> {
+    pub fn reads(mut self, resource: ResourceId) -> Self {
+        self.reads.push(resource);
+        self
+    }
+
+    pub fn writes(mut self, resource: ResourceId) -> Self {
+        self.writes.push(resource);
+        self
+    }
+
+    /// Adds the pass with the function that records it.
+    pub fn run(self, run: impl FnOnce(&mut PassContext<'_>) -> Result<(), PassError> + 'a) {
+        self.graph.passes.push(Pass {
+            label: self.label,
+            reads: self.reads,
+            writes: self.writes,
+            run: Box::new(run),
+        });
+    }
+}
+
+impl FrameGraph<'_> {
+    /// Runs the passes in dependency order, skipping unused ones.
+    ///
+    /// Nothing runs when the graph has a dependency cycle.
+    pub fn execute(
+        mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<FrameReport, FrameGraphError> {
+        let order = self.schedule()?;
+
+        let mut runs: Vec<Option<Pass<'_>>> = self.passes.drain(..).map(Some).collect();
+        let mut report = FrameReport::default();
+
+        for index in order {
+            let Some(pass) = runs[index].take() else {
+                continue;
+            };
+            trace!("FrameGraph::execute: running pass '{}'", pass.label);
+            let _span = crate::profile_span!("render_pass", label = pass.label.as_str());
+
+            let start = Instant::now();
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&pass.label),
+            });
+            encoder.push_debug_group(&pass.label);
+            (pass.run)(&mut PassContext {
+                device,
+                queue,
+                encoder: &mut encoder,
+            })
+            .map_err(|source| FrameGraphError::Pass {
+                label: pass.label.clone(),
+                source,
+            })?;
+            encoder.pop_debug_group();
+            queue.submit(std::iter::once(encoder.finish()));
+
+            report.passes.push(PassReport {
+                label: pass.label,
+                cpu_time: start.elapsed(),
+            });
+        }
+
+        report.skipped = runs.into_iter().flatten().map(|pass| pass.label).collect();
+        if !report.skipped.is_empty() {
+            trace!(
+                "FrameGraph::execute: skipped unused passes {:?}",
+                report.skipped
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Indices of the passes to run, in order.
+    fn schedule(&self) -> Result<Vec<usize>, FrameGraphError> {
+        let pass_count = self.passes.len();
+        // dependencies[i]: passes that must run before pass i
+        let mut dependencies = vec![Vec::new(); pass_count];
+
+        for resource in 0..self.resources.len() {
+            let id = ResourceId(resource);
+            let writers = (0..pass_count)
+                .filter(|&i| self.passes[i].writes.contains(&id))
+                .collect::<Vec<_>>();
+            for pair in writers.windows(2) {
+                dependencies[pair[1]].push(pair[0]);
+            }
+            if let Some(&last_writer) = writers.last() {
+                for (i, pass) in self.passes.iter().enumerate() {
+                    if pass.reads.contains(&id) && !pass.writes.contains(&id) {
+                        dependencies[i].push(last_writer);
+                    }
+                }
+            }
+        }
+
+        // keep passes with lasting output and everything they depend on
+        let mut needed = vec![false; pass_count];
+        let mut stack = (0..pass_count)
+            .filter(|&i| {
+                let writes = &self.passes[i].writes;
+                writes.is_empty()
+                    || writes
+                        .iter()
+                        .any(|id| self.resources[id.0].kind != ResourceKind::Transient)
+            })
+            .collect::<Vec<_>>();
+        while let Some(i) = stack.pop() {
+            if !std::mem::replace(&mut needed[i], true) {
+                stack.extend(&dependencies[i]);
+            }
+        }
+
+        // topological sort, preferring the order in which passes were added
+        let mut remaining = vec![0usize; pass_count];
+        let mut dependents = vec![Vec::new(); pass_count];
+        for i in (0..pass_count).filter(|&i| needed[i]) {
+            remaining[i] = dependencies[i].len();
+            for &dependency in &dependencies[i] {
+                dependents[dependency].push(i);
+            }
+        }
+        let mut ready = (0..pass_count)
+            .filter(|&i| needed[i] && remaining[i] == 0)
+            .map(std::cmp::Reverse)
+            .collect::<BinaryHeap<_>>();
+
+        let mut order = Vec::new();
+        while let Some(std::cmp::Reverse(i)) = ready.pop() {
+            order.push(i);
+            for &dependent in &dependents[i] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(std::cmp::Reverse(dependent));
+                }
+            }
+        }
+
+        if order.len() < needed.iter().filter(|&&n| n).count() {
+            let stuck = (0..pass_count)
+                .find(|&i| needed[i] && !order.contains(&i))
+                .map(|i| self.passes[i].label.clone())
+                .unwrap_or_default();
+            return Err(FrameGraphError::Cycle(stuck));
+        }
+
+        Ok(order)
+    }
+}
+
+/// Passes run by [`FrameGraph::execute`], for profiling.
+#[derive(Debug, Clone, Default)]
+pub struct FrameReport {
+    /// Executed passes, in order.
+    pub passes: Vec<PassReport>,
+    /// Labels of passes skipped because their output was unused.
+    pub skipped: Vec<String>,
+}
+
+impl FrameReport {
+    /// CPU time spent recording all passes.
+    pub fn total_cpu_time(&self) -> Duration {
+        self.passes.iter().map(|pass| pass.cpu_time).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PassReport {
+    pub label: String,
+    /// Time spent recording and submitting the pass. GPU execution is not included.
+    pub cpu_time: Duration,
+}
+
+#[derive(Error, Debug)]
+pub enum FrameGraphError {
+    #[error("frame graph has a dependency cycle involving pass '{0}'")]
+    Cycle(String),
+    #[error("pass '{label}' failed: {source}")]
+    Pass {
+        label: String,
+        #[source]
+        source: PassError,
+    },
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn noop(_: &mut PassContext<'_>) -> Result<(), PassError> {
+        Ok(())
+    }
+
+    #[test]
+    fn test_schedule_runs_writers_before_readers_added_earlier() {
+        let mut graph = FrameGraph::new();
+        let surface = graph.surface("surface");
+        let shadow = graph.transient("shadow");
+
+        graph.pass("ui").reads(shadow).writes(surface).run(noop);
+        graph.pass("shadow blur").writes(shadow).run(noop);
+
+        assert_eq!(graph.schedule().unwrap(), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_schedule_prunes_unread_transient_writer() {
+        let mut graph = FrameGraph::new();
+        let surface = graph.surface("surface");
+        let unused = graph.transient("unused");
+        let used = graph.transient("used");
+
+        graph.pass("unused").writes(unused).run(noop);
+        graph.pass("used").writes(used).run(noop);
+        graph.pass("present").reads(used).writes(surface).run(noop);
+
+        assert_eq!(graph.schedule().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_schedule_treats_read_write_pass_as_writer() {
+        let mut graph = FrameGraph::new();
+        let surface = graph.surface("surface");
+        let buffer = graph.transient("buffer");
+
+        graph
+            .pass("present")
+            .reads(buffer)
+            .writes(surface)
+            .run(noop);
+        graph.pass("fill").writes(buffer).run(noop);
+        graph
+            .pass("accumulate")
+            .reads(buffer)
+            .writes(buffer)
+            .run(noop);
+
+        // "accumulate" runs after "fill" and "present" sees its result
+        assert_eq!(graph.schedule().unwrap(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_schedule_reports_two_pass_cycle() {
+        let mut graph = FrameGraph::new();
+        let surface = graph.surface("surface");
+        let buffer = graph.transient("buffer");
+
+        graph.pass("a").reads(buffer).writes(surface).run(noop);
+        graph.pass("b").reads(surface).writes(buffer).run(noop);
+
+        assert!(matches!(graph.schedule(), Err(FrameGraphError::Cycle(_))));
+    }
+}
@@ -1,12 +1,47 @@
+/// Enters a `tracing` span until the end of the scope when the `tracing` feature is enabled.
+#[cfg(feature = "tracing")]
+macro_rules! profile_span {
+    ($name:literal $(, $($fields:tt)*)?) => {
+        tracing::trace_span!($name $(, $($fields)*)?).entered()
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! profile_span {
+    ($name:literal $(, $($fields:tt)*)?) => {
+        $crate::NoSpan
+    };
+}
+
+pub(crate) use profile_span;
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
 pub mod core_renderer;
-pub use core_renderer::CoreRenderer;
+pub use core_renderer::{CoreRenderer, CullingMode, RenderStats};
 pub mod render_node;
-pub use render_node::RenderNode;
+pub use render_node::{LayerCache, RenderNode};
+
+pub mod frame_graph;
+pub use frame_graph::{FrameGraph, FrameGraphError, FrameReport};
 
 pub mod debug_renderer;
 pub use debug_renderer::DebugRenderer;
 
+pub mod debug_json;
+
+pub mod svg_export;
+pub use svg_export::{SvgExportError, export_svg};
+
+pub mod software_renderer;
+pub use software_renderer::SoftwareRenderer;
+pub use tiny_skia;
+
 pub mod vertex;
 
 pub mod widgets_renderer;
-pub use widgets_renderer::{bezier_2d, line_strip, texture_color, texture_copy, vertex_color};
+pub use widgets_renderer::{
+    backdrop_blur, bezier_2d, glyph_mask, line_strip, series, texture_color, texture_copy,
+    vertex_color,
+};
@@ -1,4 +1,5 @@
 use gpu_utils::texture_atlas;
+use parking_lot::Mutex;
 use smallvec::SmallVec;
 use std::sync::Arc;
 
@@ -20,6 +21,21 @@ pub struct RenderNode {
     stencil_and_position: Option<(texture_atlas::AtlasRegion, nalgebra::Matrix4<f32>)>,
 
     child_elements: SmallVec<[(Arc<RenderNode>, nalgebra::Matrix4<f32>); SMALLVEC_INLINE_CAPACITY]>,
+
+    // (cache, layer size in pixels)
+    layer: Option<(LayerCache, [f32; 2])>,
+
+    // size of the rectangle from the local origin the node and its descendants are clipped to
+    clip: Option<[f32; 2]>,
+    // radius of the corners of the clip rectangle
+    clip_radius: f32,
+    opacity: f32,
+    // every texel of the texture is fully opaque
+    opaque: bool,
+    // siblings with a higher z-index are drawn over this node
+    z_index: i32,
+    // (size of the rectangle from the local origin, radius) blurred before this node is drawn
+    backdrop_blur: Option<([f32; 2], f32)>,
 }
 
 impl Default for RenderNode {
@@ -34,6 +50,13 @@ impl RenderNode {
             texture_and_position: None,
             stencil_and_position: None,
             child_elements: SmallVec::new(),
+            layer: None,
+            clip: None,
+            clip_radius: 0.0,
+            opacity: 1.0,
+            opaque: false,
+            z_index: 0,
+            backdrop_blur: None,
         }
     }
 
@@ -49,6 +72,74 @@ impl RenderNode {
         &self.child_elements
     }
 
+    /// The children in the order they are drawn: by z-index, then in the order they were
+    /// added.
+    pub(crate) fn children_in_paint_order(
+        &self,
+    ) -> SmallVec<[&(Arc<RenderNode>, nalgebra::Matrix4<f32>); SMALLVEC_INLINE_CAPACITY]> {
+        let mut children: SmallVec<[_; SMALLVEC_INLINE_CAPACITY]> =
+            self.child_elements.iter().collect();
+        // stable, so equal z-indices keep their order
+        children.sort_by_key(|(child, _)| child.z_index);
+        children
+    }
+
+    pub fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    pub(crate) fn layer(&self) -> Option<&(LayerCache, [f32; 2])> {
+        self.layer.as_ref()
+    }
+
+    pub(crate) fn clip(&self) -> Option<[f32; 2]> {
+        self.clip
+    }
+
+    pub(crate) fn clip_radius(&self) -> f32 {
+        self.clip_radius
+    }
+
+    pub(crate) fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    pub(crate) fn is_opaque(&self) -> bool {
+        self.opaque
+    }
+
+    pub(crate) fn backdrop_blur(&self) -> Option<([f32; 2], f32)> {
+        self.backdrop_blur
+    }
+
+    /// This node without its layer cache, i.e. the content the layer rasterizes.
+    pub(crate) fn layer_content(&self) -> RenderNode {
+        RenderNode {
+            layer: None,
+            ..self.clone()
+        }
+    }
+
+    /// Compares the content of two nodes without descending into children.
+    ///
+    /// Children are immutable once shared, so the same `Arc` means the same subtree.
+    fn shallow_eq(&self, other: &RenderNode) -> bool {
+        self.texture_and_position == other.texture_and_position
+            && self.stencil_and_position == other.stencil_and_position
+            && self.clip == other.clip
+            && self.clip_radius == other.clip_radius
+            && self.opacity == other.opacity
+            && self.opaque == other.opaque
+            && self.z_index == other.z_index
+            && self.backdrop_blur == other.backdrop_blur
+            && self.child_elements.len() == other.child_elements.len()
+            && self.child_elements.iter().zip(&other.child_elements).all(
+                |((a, a_transform), (b, b_transform))| {
+                    Arc::ptr_eq(a, b) && a_transform == b_transform
+                },
+            )
+    }
+
     pub fn with_texture(
         mut self,
         texture: texture_atlas::AtlasRegion,
@@ -95,6 +186,83 @@ impl RenderNode {
         self.child_elements.push((child.into(), transform));
         self
     }
+
+    /// Clips this node and its descendants to the rectangle from its local origin to `size`.
+    ///
+    /// The clip is an axis-aligned rectangle in the render target: under a rotation the
+    /// bounding box of the transformed rectangle is used. Nested clips intersect.
+    pub fn with_clip(mut self, size: [f32; 2]) -> Self {
+        self.clip = Some(size);
+        self.clip_radius = 0.0;
+        self
+    }
+
+    /// Like [`with_clip`](Self::with_clip), with the corners of the rectangle rounded by
+    /// `radius`. The edge of the rounded rectangle is antialiased.
+    ///
+    /// Only the innermost rounded clip rounds the corners of descendants; rounded clips
+    /// further out clip them to their rectangle.
+    pub fn with_rounded_clip(mut self, size: [f32; 2], radius: f32) -> Self {
+        self.clip = Some(size);
+        self.clip_radius = radius.max(0.0);
+        self
+    }
+
+    /// Multiplies the alpha of this node and its descendants by `opacity`, clamped to
+    /// `0.0..=1.0`. Nested opacities multiply.
+    ///
+    /// Descendants are faded one by one, so overlapping ones show through each other. For a
+    /// subtree faded as a whole, set the opacity on the parent of a layer-cached node.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Draws this node over its siblings with a lower `z_index` and under those with a higher
+    /// one. Siblings with the same z-index are drawn in the order they were added; the default
+    /// is 0.
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    /// Declares every texel of this node's texture fully opaque, so the renderer may skip
+    /// whatever it covers (see [`CoreRenderer::with_occlusion_culling`](crate::CoreRenderer::with_occlusion_culling)).
+    ///
+    /// Only a texture drawn at full opacity, without a stencil and without rotation or skew
+    /// occludes anything. Marking a texture with transparent texels hides what shows
+    /// through them.
+    pub fn with_opaque_texture(mut self) -> Self {
+        self.opaque = true;
+        self
+    }
+
+    /// Blurs what is already drawn in the rectangle from the local origin to `size` with a
+    /// gaussian of `radius` pixels before this node is drawn, for acrylic-style translucent
+    /// panels.
+    ///
+    /// The renderer splits its draw at this node and blurs the render target in place, so
+    /// the blurred rectangle replaces the content below it: the node's opacity and rounded
+    /// clips do not apply to the blur, only the rectangle clips of its ancestors. Inside a
+    /// layer cache only the content of the layer is blurred. The render target must be
+    /// sampleable, see [`CoreRenderer::render`](crate::CoreRenderer::render).
+    pub fn with_backdrop_blur(mut self, size: [f32; 2], radius: f32) -> Self {
+        self.backdrop_blur = Some((size, radius.max(0.0)));
+        self
+    }
+
+    /// Draws this node and its descendants through `cache`: the subtree is rasterized once
+    /// into a texture atlas region of `size` pixels (in node-local coordinates) and then
+    /// drawn as a single quad until it changes.
+    ///
+    /// The layer is re-rendered when `size` changes, when the node's texture, stencil or
+    /// children are replaced, or after [`LayerCache::invalidate`]. Content outside
+    /// `[0, size]` is clipped. The layer is rasterized over transparent black, so it suits
+    /// mostly opaque content. See [`CoreRenderer::render_layers`](crate::CoreRenderer::render_layers).
+    pub fn with_layer_cache(mut self, cache: &LayerCache, size: [f32; 2]) -> Self {
+        self.layer = Some((cache.clone(), size));
+        self
+    }
 }
 
 impl RenderNode {
@@ -105,4 +273,119 @@ impl RenderNode {
         }
         count
     }
+
+    /// Returns `false` if any atlas region referenced by this node or its descendants
+    /// has become stale (see [`AtlasRegion::is_valid`](texture_atlas::AtlasRegion::is_valid)).
+    ///
+    /// Render caches holding a stale node must re-render it.
+    pub fn is_valid(&self) -> bool {
+        let regions_valid = [&self.texture_and_position, &self.stencil_and_position]
+            .into_iter()
+            .flatten()
+            .all(|(region, _)| region.is_valid());
+        regions_valid
+            && self
+                .child_elements
+                .iter()
+                .all(|(child, _)| child.is_valid())
+    }
+
+    /// Estimated bytes of memory held only through this node: the nodes themselves and the
+    /// atlas regions they reference.
+    ///
+    /// Children that are shared with another owner, e.g. the render cache of a child widget,
+    /// are not included, so summing over the caches of a widget tree counts each node once.
+    pub fn owned_memory_size(&self) -> usize {
+        let regions: usize = [&self.texture_and_position, &self.stencil_and_position]
+            .into_iter()
+            .flatten()
+            .map(|(region, _)| region_memory_size(region))
+            .sum();
+        let children: usize = self
+            .child_elements
+            .iter()
+            .filter(|(child, _)| Arc::strong_count(child) == 1)
+            .map(|(child, _)| child.owned_memory_size())
+            .sum();
+        std::mem::size_of::<Self>() + regions + children
+    }
+}
+
+fn region_memory_size(region: &texture_atlas::AtlasRegion) -> usize {
+    let format = region.format();
+    let [width, height] = region.texture_size();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(4);
+    width.div_ceil(block_width) as usize
+        * height.div_ceil(block_height) as usize
+        * block_size as usize
+}
+
+/// Handle to the rasterized content of a layer-cached [`RenderNode`].
+///
+/// Keep the same handle across frames (e.g. in the widget that produces the node) so the
+/// renderer can reuse the rasterized content. Clones share the same cache.
+#[derive(Debug, Clone, Default)]
+pub struct LayerCache {
+    inner: Arc<Mutex<LayerState>>,
+}
+
+#[derive(Debug, Default)]
+struct LayerState {
+    // region holding the rasterized content, and the content it was rendered from
+    rendered: Option<(texture_atlas::AtlasRegion, RenderNode)>,
+    invalidated: bool,
+}
+
+impl LayerCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forces the layer to be re-rendered on the next frame.
+    ///
+    /// Needed when content changes without the node tree changing, e.g. when a descendant
+    /// rewrites the pixels of its atlas region in place.
+    pub fn invalidate(&self) {
+        self.inner.lock().invalidated = true;
+    }
+
+    /// Drops the rasterized content and frees its atlas region.
+    pub fn clear(&self) {
+        self.inner.lock().rendered = None;
+    }
+
+    /// The region to draw for `content` at `size`, or `None` when the layer is stale.
+    pub(crate) fn current(
+        &self,
+        content: &RenderNode,
+        size: [u32; 2],
+    ) -> Option<texture_atlas::AtlasRegion> {
+        let state = self.inner.lock();
+        if state.invalidated {
+            return None;
+        }
+        let (region, rendered_content) = state.rendered.as_ref()?;
+        (region.texture_size() == size && region.is_valid() && rendered_content.shallow_eq(content))
+            .then(|| region.clone())
+    }
+
+    /// A region of `size` to render into, reusing the current one when it fits.
+    pub(crate) fn reusable_region(&self, size: [u32; 2]) -> Option<texture_atlas::AtlasRegion> {
+        let state = self.inner.lock();
+        let (region, _) = state.rendered.as_ref()?;
+        (region.texture_size() == size && region.is_valid()).then(|| region.clone())
+    }
+
+    pub(crate) fn store(&self, region: texture_atlas::AtlasRegion, content: RenderNode) {
+        let mut state = self.inner.lock();
+        state.rendered = Some((region, content));
+        state.invalidated = false;
+    }
+}
+
+/// Pixel size of a layer, or `None` when it is empty.
+pub(crate) fn layer_pixel_size(size: [f32; 2]) -> Option<[u32; 2]> {
+    let size = size.map(|v| v.ceil().max(0.0) as u32);
+    (size[0] > 0 && size[1] > 0).then_some(size)
 }
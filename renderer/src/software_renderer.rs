@@ -0,0 +1,615 @@
+//! Rasterization of a [`RenderNode`] tree on the CPU with tiny-skia, for machines whose
+//! adapter cannot run [`CoreRenderer`](crate::CoreRenderer), e.g. llvmpipe in CI or a remote
+//! shell without a GPU.
+//!
+//! The renderer follows the same rules as the core renderer: children are drawn by z-index,
+//! opacities multiply, rectangle clips intersect as axis-aligned boxes in the target, only the
+//! innermost rounded clip and the innermost stencil apply, and backdrop blurs blur what is
+//! drawn so far with the same gaussian. Layer caches are ignored and their content is drawn
+//! directly.
+//!
+//! Textures are read back from their atlas every frame, since widgets may rewrite a region in
+//! place. Blending happens on the encoded sRGB values, so translucent edges come out slightly
+//! darker than on the GPU.
+
+use std::rc::Rc;
+
+use gpu_utils::texture_atlas::AtlasRegion;
+use log::{trace, warn};
+use parking_lot::Mutex;
+use tiny_skia::{
+    FillRule, FilterQuality, IntSize, Mask, MaskType, Paint, PathBuilder, Pattern, Pixmap,
+    PixmapPaint, Rect, SpreadMode, Transform,
+};
+
+use crate::core_renderer::{intersect_rect, min_axis_scale, transformed_rect};
+use crate::widgets_renderer::backdrop_blur::MAX_BLUR_RADIUS;
+use crate::{RenderNode, RenderStats};
+
+/// Draws [`RenderNode`] trees into a [`Pixmap`] on the CPU, see the
+/// [module documentation](self).
+#[derive(Default)]
+pub struct SoftwareRenderer {
+    stats: Mutex<RenderStats>,
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fills `target` with `clear_color` and draws `node` over it. Pixels of the pixmap are
+    /// premultiplied.
+    ///
+    /// Reads every texture the tree references back from the GPU and blocks until done, so
+    /// call it off the render loop. Textures that cannot be read are skipped.
+    pub fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        target: &mut Pixmap,
+        node: &RenderNode,
+        clear_color: wgpu::Color,
+    ) -> RenderStats {
+        let _span = crate::profile_span!("software_render");
+        let mut read = Vec::<(AtlasRegion, Option<Rc<Pixmap>>)>::new();
+        let mut textures = |region: &AtlasRegion| {
+            if let Some((_, pixmap)) = read.iter().find(|(known, _)| known == region) {
+                return pixmap.clone();
+            }
+            let pixmap = read_pixmap(device, queue, region).map(Rc::new);
+            read.push((region.clone(), pixmap.clone()));
+            pixmap
+        };
+
+        let stats = rasterize(target, node, clear_color, &mut textures);
+        trace!(
+            "SoftwareRenderer::render: drew {} of {} instances",
+            stats.draw_calls, stats.instances
+        );
+        *self.stats.lock() = stats;
+        stats
+    }
+
+    /// Statistics of the last frame drawn by [`render`](Self::render). Every texture is one
+    /// draw call.
+    pub fn last_frame_stats(&self) -> RenderStats {
+        *self.stats.lock()
+    }
+}
+
+/// Reads `region` into a premultiplied pixmap, or `None` when its format is not supported.
+fn read_pixmap(device: &wgpu::Device, queue: &wgpu::Queue, region: &AtlasRegion) -> Option<Pixmap> {
+    let texels = region
+        .read_data(device, queue)
+        .inspect_err(|e| warn!("SoftwareRenderer: failed to read a texture back: {e}"))
+        .ok()?;
+    let [width, height] = region.texture_size();
+    let pixels = match region.format() {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => texels
+            .chunks_exact(4)
+            .flat_map(|texel| premultiply([texel[0], texel[1], texel[2], texel[3]]))
+            .collect(),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => texels
+            .chunks_exact(4)
+            .flat_map(|texel| premultiply([texel[2], texel[1], texel[0], texel[3]]))
+            .collect(),
+        // stencils: the coverage becomes the alpha
+        wgpu::TextureFormat::R8Unorm => texels.iter().flat_map(|&v| [v; 4]).collect(),
+        format => {
+            warn!("SoftwareRenderer: texture format {format:?} is not supported");
+            return None;
+        }
+    };
+    Pixmap::from_vec(pixels, IntSize::from_wh(width, height)?)
+}
+
+fn premultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    let scale = |c: u8| ((u16::from(c) * u16::from(a) + 127) / 255) as u8;
+    [scale(r), scale(g), scale(b), a]
+}
+
+/// Draws `node` over `clear_color` into `target`, with the pixels of each region from
+/// `textures`.
+fn rasterize(
+    target: &mut Pixmap,
+    node: &RenderNode,
+    clear_color: wgpu::Color,
+    textures: &mut dyn FnMut(&AtlasRegion) -> Option<Rc<Pixmap>>,
+) -> RenderStats {
+    let clear = tiny_skia::Color::from_rgba(
+        clear_color.r as f32,
+        clear_color.g as f32,
+        clear_color.b as f32,
+        clear_color.a as f32,
+    )
+    .unwrap_or(tiny_skia::Color::TRANSPARENT);
+    target.fill(clear);
+
+    let mut rasterizer = Rasterizer {
+        target,
+        textures,
+        stats: RenderStats {
+            visible_instances: Some(0),
+            ..RenderStats::default()
+        },
+    };
+    rasterizer.node(
+        node,
+        &State {
+            transform: nalgebra::Matrix4::identity(),
+            opacity: 1.0,
+            clip: NO_CLIP,
+            stencil: None,
+            mask: None,
+        },
+    );
+    rasterizer.stats
+}
+
+struct Rasterizer<'a> {
+    target: &'a mut Pixmap,
+    textures: &'a mut dyn FnMut(&AtlasRegion) -> Option<Rc<Pixmap>>,
+    stats: RenderStats,
+}
+
+/// What a node inherits from its ancestors.
+#[derive(Clone)]
+struct State {
+    transform: nalgebra::Matrix4<f32>,
+    opacity: f32,
+    clip: Clip,
+    // the innermost stencil and the transform of its unit square
+    stencil: Option<(Rc<Pixmap>, nalgebra::Matrix4<f32>)>,
+    // `clip` and `stencil` as coverage of the target, `None` when they cover all of it
+    mask: Option<Rc<Mask>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Clip {
+    // [min x, min y, max x, max y] in the target
+    rect: [f32; 4],
+    rounded_rect: [f32; 4],
+    // 0 if no ancestor clips with rounded corners
+    radius: f32,
+}
+
+const NO_CLIP: Clip = Clip {
+    rect: [f32::MIN, f32::MIN, f32::MAX, f32::MAX],
+    rounded_rect: [f32::MIN, f32::MIN, f32::MAX, f32::MAX],
+    radius: 0.0,
+};
+
+impl Rasterizer<'_> {
+    fn node(&mut self, node: &RenderNode, parent: &State) {
+        let mut state = State {
+            opacity: parent.opacity * node.opacity(),
+            ..parent.clone()
+        };
+        if state.opacity <= 0.0 {
+            return;
+        }
+
+        let mut mask_changed = false;
+        if let Some(size) = node.clip() {
+            let rect = transformed_rect(&state.transform, size);
+            state.clip.rect = intersect_rect(state.clip.rect, rect);
+            if state.clip.rect[0] >= state.clip.rect[2] || state.clip.rect[1] >= state.clip.rect[3]
+            {
+                return;
+            }
+            if node.clip_radius() > 0.0 {
+                state.clip.rounded_rect = rect;
+                state.clip.radius = node.clip_radius() * min_axis_scale(&state.transform);
+            }
+            mask_changed = true;
+        }
+
+        if let Some((size, radius)) = node.backdrop_blur() {
+            let rect = intersect_rect(state.clip.rect, transformed_rect(&state.transform, size));
+            let radius = radius * min_axis_scale(&state.transform);
+            if blur_in_place(self.target, rect, radius) {
+                self.stats.backdrop_blurs += 1;
+            }
+        }
+
+        if let Some((stencil, position)) = node.stencil() {
+            let position = state.transform * position;
+            // like the core renderer, a stencil that cannot be inverted masks nothing
+            state.stencil = position
+                .try_inverse()
+                .and((self.textures)(stencil))
+                .map(|pixmap| (pixmap, position));
+            self.stats.stencils += 1;
+            mask_changed = true;
+        }
+
+        if mask_changed {
+            state.mask = coverage_mask(
+                self.target.width(),
+                self.target.height(),
+                &state.clip,
+                state.stencil.as_ref(),
+            )
+            .map(Rc::new);
+        }
+
+        if let Some((texture, position)) = node.texture() {
+            self.stats.instances += 1;
+            if let Some(pixmap) = (self.textures)(texture) {
+                let paint = PixmapPaint {
+                    opacity: state.opacity,
+                    quality: FilterQuality::Bilinear,
+                    ..PixmapPaint::default()
+                };
+                let transform = unit_square_transform(&(state.transform * position), &pixmap);
+                self.target.draw_pixmap(
+                    0,
+                    0,
+                    pixmap.as_ref(),
+                    &paint,
+                    transform,
+                    state.mask.as_deref(),
+                );
+                self.stats.draw_calls += 1;
+                if let Some(visible) = &mut self.stats.visible_instances {
+                    *visible += 1;
+                }
+            }
+        }
+
+        for (child, child_transform) in node.children_in_paint_order() {
+            let child_state = State {
+                transform: state.transform * child_transform,
+                ..state.clone()
+            };
+            self.node(child, &child_state);
+        }
+    }
+}
+
+/// Maps the pixels of `pixmap` onto the unit square placed by `position`.
+fn unit_square_transform(position: &nalgebra::Matrix4<f32>, pixmap: &Pixmap) -> Transform {
+    let scale = nalgebra::Matrix4::new_nonuniform_scaling(&nalgebra::Vector3::new(
+        1.0 / pixmap.width() as f32,
+        1.0 / pixmap.height() as f32,
+        1.0,
+    ));
+    skia_transform(&(position * scale))
+}
+
+/// The 2D part of `transform`.
+fn skia_transform(transform: &nalgebra::Matrix4<f32>) -> Transform {
+    Transform::from_row(
+        transform[(0, 0)],
+        transform[(1, 0)],
+        transform[(0, 1)],
+        transform[(1, 1)],
+        transform[(0, 3)],
+        transform[(1, 3)],
+    )
+}
+
+/// Coverage of a `width` x `height` target by `clip` and `stencil`, or `None` when they
+/// leave all of it.
+fn coverage_mask(
+    width: u32,
+    height: u32,
+    clip: &Clip,
+    stencil: Option<&(Rc<Pixmap>, nalgebra::Matrix4<f32>)>,
+) -> Option<Mask> {
+    let [w, h] = [width as f32, height as f32];
+    let rect = intersect_rect(clip.rect, [0.0, 0.0, w, h]);
+    if rect == [0.0, 0.0, w, h] && clip.radius <= 0.0 && stencil.is_none() {
+        return None;
+    }
+
+    let mut mask = Mask::new(width, height)?;
+    // an empty clip leaves the mask empty
+    let Some(rect) = Rect::from_ltrb(rect[0], rect[1], rect[2], rect[3]) else {
+        return Some(mask);
+    };
+    // the core renderer discards pixels whose center is outside, without antialiasing
+    mask.fill_path(
+        &PathBuilder::from_rect(rect),
+        FillRule::Winding,
+        false,
+        Transform::identity(),
+    );
+    if clip.radius > 0.0
+        && let Some(path) = rounded_rect_path(clip.rounded_rect, clip.radius)
+    {
+        mask.intersect_path(&path, FillRule::Winding, true, Transform::identity());
+    }
+
+    if let Some((stencil, position)) = stencil {
+        let mut coverage = Pixmap::new(width, height)?;
+        // outside its square the stencil repeats its edge, like the clamped sampler
+        let paint = Paint {
+            shader: Pattern::new(
+                stencil.as_ref(),
+                SpreadMode::Pad,
+                FilterQuality::Bilinear,
+                1.0,
+                unit_square_transform(position, stencil),
+            ),
+            ..Paint::default()
+        };
+        coverage.fill_rect(
+            Rect::from_xywh(0.0, 0.0, w, h)?,
+            &paint,
+            Transform::identity(),
+            None,
+        );
+        let stencil = Mask::from_pixmap(coverage.as_ref(), MaskType::Alpha);
+        for (coverage, stencil) in mask.data_mut().iter_mut().zip(stencil.data()) {
+            *coverage = ((u16::from(*coverage) * u16::from(*stencil) + 127) / 255) as u8;
+        }
+    }
+    Some(mask)
+}
+
+/// `rect` with its corners rounded by `radius`, at most half its shorter side.
+fn rounded_rect_path([left, top, right, bottom]: [f32; 4], radius: f32) -> Option<tiny_skia::Path> {
+    // distance of the control points of a quarter circle drawn as a cubic bezier
+    const KAPPA: f32 = 0.552_284_8;
+
+    let radius = radius.min((right - left) * 0.5).min((bottom - top) * 0.5);
+    let k = radius * (1.0 - KAPPA);
+    let mut path = PathBuilder::new();
+    path.move_to(left + radius, top);
+    path.line_to(right - radius, top);
+    path.cubic_to(right - k, top, right, top + k, right, top + radius);
+    path.line_to(right, bottom - radius);
+    path.cubic_to(right, bottom - k, right - k, bottom, right - radius, bottom);
+    path.line_to(left + radius, bottom);
+    path.cubic_to(left + k, bottom, left, bottom - k, left, bottom - radius);
+    path.line_to(left, top + radius);
+    path.cubic_to(left, top + k, left + k, top, left + radius, top);
+    path.close();
+    path.finish()
+}
+
+/// Blurs the pixels of `target` within `rect` with the gaussian of the backdrop blur shader.
+/// Returns `false` when there is nothing to blur.
+fn blur_in_place(target: &mut Pixmap, rect: [f32; 4], radius: f32) -> bool {
+    let (width, height) = (target.width() as usize, target.height() as usize);
+    let x0 = rect[0].floor().clamp(0.0, width as f32) as usize;
+    let y0 = rect[1].floor().clamp(0.0, height as f32) as usize;
+    let x1 = rect[2].ceil().clamp(0.0, width as f32) as usize;
+    let y1 = rect[3].ceil().clamp(0.0, height as f32) as usize;
+    if x0 >= x1 || y0 >= y1 {
+        return false;
+    }
+    let kernel_radius = radius.min(MAX_BLUR_RADIUS).ceil() as isize;
+    if kernel_radius <= 0 {
+        return true;
+    }
+
+    let sigma = (radius * 0.5).max(0.0001);
+    let weights: Vec<f32> = (-kernel_radius..=kernel_radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let weight_sum: f32 = weights.iter().sum();
+
+    let (rect_width, rect_height) = (x1 - x0, y1 - y0);
+    let pixels = target.data_mut();
+    let texel = |x: usize, y: usize| (y * width + x) * 4;
+
+    // horizontal pass, sampling clamped to the rectangle
+    let mut horizontal = vec![[0.0f32; 4]; rect_width * rect_height];
+    for y in 0..rect_height {
+        for x in 0..rect_width {
+            let mut color = [0.0; 4];
+            for (i, weight) in (-kernel_radius..=kernel_radius).zip(&weights) {
+                let sx = (x as isize + i).clamp(0, rect_width as isize - 1) as usize;
+                let offset = texel(x0 + sx, y0 + y);
+                for (channel, &value) in color.iter_mut().zip(&pixels[offset..offset + 4]) {
+                    *channel += f32::from(value) * weight;
+                }
+            }
+            horizontal[y * rect_width + x] = color;
+        }
+    }
+
+    // vertical pass, back into the target
+    for y in 0..rect_height {
+        for x in 0..rect_width {
+            let mut color = [0.0; 4];
+            for (i, weight) in (-kernel_radius..=kernel_radius).zip(&weights) {
+                let sy = (y as isize + i).clamp(0, rect_height as isize - 1) as usize;
+                let sample = horizontal[sy * rect_width + x];
+                for (channel, value) in color.iter_mut().zip(sample) {
+                    *channel += value * weight;
+                }
+            }
+            let offset = texel(x0 + x, y0 + y);
+            for (value, channel) in pixels[offset..offset + 4].iter_mut().zip(color) {
+                *value = (channel / (weight_sum * weight_sum)).round() as u8;
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use gpu_utils::texture_atlas::TextureAtlas;
+
+    use super::*;
+
+    fn translation(x: f32, y: f32) -> nalgebra::Matrix4<f32> {
+        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0))
+    }
+
+    fn solid(size: u32, rgba: [u8; 4]) -> Rc<Pixmap> {
+        let pixels = (0..size * size).flat_map(|_| premultiply(rgba)).collect();
+        Rc::new(Pixmap::from_vec(pixels, IntSize::from_wh(size, size).unwrap()).unwrap())
+    }
+
+    fn pixel(target: &Pixmap, x: u32, y: u32) -> [u8; 4] {
+        let color = target.pixel(x, y).unwrap();
+        [color.red(), color.green(), color.blue(), color.alpha()]
+    }
+
+    /// Regions on the noop backend, each drawn as the pixmap at the same position.
+    async fn regions(count: usize) -> Vec<AtlasRegion> {
+        let (_, _, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let atlas = TextureAtlas::new(
+            &device,
+            wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            0,
+        );
+        (0..count)
+            .map(|_| atlas.allocate(&device, &queue, [4, 4]).unwrap())
+            .collect()
+    }
+
+    fn lookup(
+        regions: &[AtlasRegion],
+        pixmaps: &[Rc<Pixmap>],
+    ) -> impl FnMut(&AtlasRegion) -> Option<Rc<Pixmap>> {
+        move |region| {
+            let index = regions.iter().position(|known| known == region)?;
+            Some(pixmaps[index].clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn draws_textures_in_z_order_with_opacity() {
+        let regions = regions(2).await;
+        let pixmaps = [solid(4, [255, 0, 0, 255]), solid(4, [0, 0, 255, 255])];
+        let root = RenderNode::new()
+            // added first, but drawn over the red square
+            .add_child(
+                RenderNode::new()
+                    .with_texture(
+                        regions[1].clone(),
+                        [8.0, 8.0],
+                        nalgebra::Matrix4::identity(),
+                    )
+                    .with_z_index(1)
+                    .with_opacity(0.5),
+                translation(4.0, 0.0),
+            )
+            .add_child(
+                RenderNode::new().with_texture(
+                    regions[0].clone(),
+                    [8.0, 8.0],
+                    nalgebra::Matrix4::identity(),
+                ),
+                translation(0.0, 0.0),
+            );
+
+        let mut target = Pixmap::new(16, 16).unwrap();
+        let stats = rasterize(
+            &mut target,
+            &root,
+            wgpu::Color::BLACK,
+            &mut lookup(&regions, &pixmaps),
+        );
+
+        assert_eq!(stats.instances, 2);
+        assert_eq!(stats.draw_calls, 2);
+        assert_eq!(pixel(&target, 1, 1), [255, 0, 0, 255]);
+        // half blue over red
+        let [r, _, b, a] = pixel(&target, 6, 1);
+        assert!((127..=128).contains(&r) && (127..=128).contains(&b) && a == 255);
+        // half blue over the black base color
+        assert_eq!(pixel(&target, 10, 1)[0], 0);
+        assert!((127..=128).contains(&pixel(&target, 10, 1)[2]));
+        assert_eq!(pixel(&target, 14, 14), [0, 0, 0, 255]);
+    }
+
+    #[tokio::test]
+    async fn clips_intersect_and_stencils_mask() {
+        let regions = regions(2).await;
+        let pixmaps = [solid(4, [255, 255, 255, 255]), {
+            // left half of the stencil covers
+            let pixels = (0..16)
+                .flat_map(|i| if i % 4 < 2 { [255; 4] } else { [0; 4] })
+                .collect();
+            Rc::new(Pixmap::from_vec(pixels, IntSize::from_wh(4, 4).unwrap()).unwrap())
+        }];
+        let texture = || {
+            RenderNode::new().with_texture(
+                regions[0].clone(),
+                [16.0, 16.0],
+                nalgebra::Matrix4::identity(),
+            )
+        };
+        let root = RenderNode::new()
+            .add_child(
+                RenderNode::new()
+                    .with_clip([12.0, 12.0])
+                    .add_child(texture().with_clip([16.0, 8.0]), translation(0.0, 0.0)),
+                translation(0.0, 0.0),
+            )
+            .add_child(
+                texture().with_stencil(
+                    regions[1].clone(),
+                    [16.0, 16.0],
+                    nalgebra::Matrix4::identity(),
+                ),
+                translation(16.0, 0.0),
+            );
+
+        let mut target = Pixmap::new(32, 16).unwrap();
+        rasterize(
+            &mut target,
+            &root,
+            wgpu::Color::TRANSPARENT,
+            &mut lookup(&regions, &pixmaps),
+        );
+
+        assert_eq!(pixel(&target, 2, 2), [255; 4]);
+        // outside the outer clip, and below the inner one
+        assert_eq!(pixel(&target, 13, 2), [0; 4]);
+        assert_eq!(pixel(&target, 2, 10), [0; 4]);
+        // the stencil covers the left half of its square only
+        assert_eq!(pixel(&target, 18, 8), [255; 4]);
+        assert_eq!(pixel(&target, 30, 8), [0; 4]);
+    }
+
+    #[tokio::test]
+    async fn backdrop_blur_spreads_what_is_below_within_its_rectangle() {
+        let regions = regions(1).await;
+        let pixmaps = [solid(4, [255, 255, 255, 255])];
+        let root = RenderNode::new()
+            .add_child(
+                RenderNode::new().with_texture(
+                    regions[0].clone(),
+                    [8.0, 16.0],
+                    nalgebra::Matrix4::identity(),
+                ),
+                translation(0.0, 0.0),
+            )
+            .add_child(
+                RenderNode::new().with_backdrop_blur([16.0, 16.0], 4.0),
+                translation(0.0, 0.0),
+            );
+
+        let mut target = Pixmap::new(32, 16).unwrap();
+        let stats = rasterize(
+            &mut target,
+            &root,
+            wgpu::Color::BLACK,
+            &mut lookup(&regions, &pixmaps),
+        );
+
+        assert_eq!(stats.backdrop_blurs, 1);
+        // the edge between white and black is smoothed
+        let left = pixel(&target, 7, 8)[0];
+        let right = pixel(&target, 8, 8)[0];
+        assert!(left < 255 && right > 0, "{left} {right}");
+        // far from the edge and outside the rectangle nothing changes
+        assert_eq!(pixel(&target, 0, 8), [255; 4]);
+        assert_eq!(pixel(&target, 20, 8), [0, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,435 @@
+//! Export of a [`RenderNode`] tree as an SVG document, e.g. for printing or reports.
+//!
+//! The tree only holds rasterized content, so every texture becomes an `<image>` with its
+//! pixels embedded as PNG, placed by the same transform the renderer uses. The structure of
+//! the tree maps to vector primitives: clip rectangles become `<clipPath>`s, stencils become
+//! luminance `<mask>`s and opacities become group opacity. Text and shapes therefore scale
+//! like the atlas textures they were rasterized into; render at a higher scale factor for
+//! print resolution. Layer caches are skipped and their content is exported instead.
+//!
+//! Unlike the renderer, opacity applies to a subtree as a whole and nested stencils
+//! intersect, which differs only where faded or masked content overlaps itself. Convert the
+//! document to PDF with any SVG tool, e.g. a browser's print dialog.
+
+use std::fmt::Write as _;
+
+use gpu_utils::texture_atlas::{AtlasRegion, RegionError};
+use log::{debug, trace};
+use thiserror::Error;
+
+use crate::RenderNode;
+
+#[derive(Error, Debug)]
+pub enum SvgExportError {
+    #[error("failed to read a texture back from the atlas: {0}")]
+    ReadBack(#[from] RegionError),
+    #[error("texture format {0:?} cannot be exported")]
+    UnsupportedFormat(wgpu::TextureFormat),
+}
+
+/// Writes `node` as an SVG document showing the rectangle `[min, max]` of its coordinates.
+///
+/// Textures outside that rectangle or the clips of their ancestors are left out, as are
+/// subtrees with zero opacity.
+///
+/// Reads every referenced atlas region back from the GPU and blocks until done, so call it
+/// off the render loop.
+pub fn export_svg(
+    node: &RenderNode,
+    [min, max]: [[f32; 2]; 2],
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> Result<String, SvgExportError> {
+    let size = [max[0] - min[0], max[1] - min[1]];
+    let mut writer = SvgWriter {
+        device,
+        queue,
+        images: Vec::new(),
+        defs: String::new(),
+        body: String::new(),
+        next_id: 0,
+    };
+    let origin = nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(-min[0], -min[1], 0.0));
+    writer.node(node, origin, [0.0, 0.0, size[0], size[1]])?;
+
+    debug!(
+        "export_svg: {} nodes with {} distinct images",
+        node.count(),
+        writer.images.len()
+    );
+    Ok(format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" "#,
+            r#"width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            "\n<defs>\n{defs}</defs>\n{body}</svg>\n"
+        ),
+        w = size[0],
+        h = size[1],
+        defs = writer.defs,
+        body = writer.body,
+    ))
+}
+
+struct SvgWriter<'a> {
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    // regions already embedded in `defs` and their ids
+    images: Vec<(AtlasRegion, String)>,
+    defs: String,
+    body: String,
+    next_id: usize,
+}
+
+impl SvgWriter<'_> {
+    /// `visible` is the part of the document left by the clips of the ancestors, as
+    /// `[min x, min y, max x, max y]`.
+    fn node(
+        &mut self,
+        node: &RenderNode,
+        transform: nalgebra::Matrix4<f32>,
+        mut visible: [f32; 4],
+    ) -> Result<(), SvgExportError> {
+        if node.opacity() <= 0.0 {
+            return Ok(());
+        }
+        if let Some([width, height]) = node.clip() {
+            visible = intersect(visible, bounds(&(transform * scaling(width, height))));
+        }
+        if visible[0] >= visible[2] || visible[1] >= visible[3] {
+            return Ok(());
+        }
+
+        let mut groups = 0;
+        if node.opacity() < 1.0 {
+            let _ = writeln!(self.body, r#"<g opacity="{}">"#, node.opacity());
+            groups += 1;
+        }
+        if let Some([width, height]) = node.clip() {
+            let id = self.id("clip");
+            let radius = node.clip_radius();
+            let _ = writeln!(
+                self.defs,
+                r#"<clipPath id="{id}"><rect width="{width}" height="{height}" rx="{radius}" transform="{}"/></clipPath>"#,
+                svg_matrix(&transform)
+            );
+            let _ = writeln!(self.body, r#"<g clip-path="url(#{id})">"#);
+            groups += 1;
+        }
+        if let Some((stencil, position)) = node.stencil() {
+            let image = self.image(stencil)?;
+            let id = self.id("mask");
+            let _ = writeln!(
+                self.defs,
+                r##"<mask id="{id}" maskUnits="userSpaceOnUse" x="-1e6" y="-1e6" width="2e6" height="2e6"><use xlink:href="#{image}" transform="{}"/></mask>"##,
+                svg_matrix(&(transform * position))
+            );
+            let _ = writeln!(self.body, r#"<g mask="url(#{id})">"#);
+            groups += 1;
+        }
+
+        if let Some((texture, position)) = node
+            .texture()
+            .filter(|(_, position)| overlaps(visible, bounds(&(transform * position))))
+        {
+            let image = self.image(texture)?;
+            let _ = writeln!(
+                self.body,
+                r##"<use xlink:href="#{image}" transform="{}"/>"##,
+                svg_matrix(&(transform * position))
+            );
+        }
+        for (child, child_transform) in node.children_in_paint_order() {
+            self.node(child, transform * child_transform, visible)?;
+        }
+
+        for _ in 0..groups {
+            self.body.push_str("</g>\n");
+        }
+        Ok(())
+    }
+
+    /// Id of the unit-square `<image>` showing `region`, embedding it on first use.
+    fn image(&mut self, region: &AtlasRegion) -> Result<String, SvgExportError> {
+        if let Some((_, id)) = self.images.iter().find(|(known, _)| known == region) {
+            return Ok(id.clone());
+        }
+
+        let [width, height] = region.texture_size();
+        let color_type = match region.format() {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => {
+                PngColorType::Rgba
+            }
+            wgpu::TextureFormat::R8Unorm => PngColorType::Gray,
+            format => return Err(SvgExportError::UnsupportedFormat(format)),
+        };
+        let pixels = region.read_data(self.device, self.queue)?;
+        let png = encode_png(width, height, color_type, &pixels);
+        trace!(
+            "SvgWriter::image: embedding {width}x{height} {color_type:?} image ({} bytes)",
+            png.len()
+        );
+
+        let id = self.id("image");
+        let _ = writeln!(
+            self.defs,
+            r#"<image id="{id}" width="1" height="1" preserveAspectRatio="none" xlink:href="data:image/png;base64,{}"/>"#,
+            base64(&png)
+        );
+        self.images.push((region.clone(), id.clone()));
+        Ok(id)
+    }
+
+    fn id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{prefix}{}", self.next_id)
+    }
+}
+
+/// The 2D part of `transform` as an SVG transform attribute.
+fn svg_matrix(transform: &nalgebra::Matrix4<f32>) -> String {
+    format!(
+        "matrix({} {} {} {} {} {})",
+        transform[(0, 0)],
+        transform[(1, 0)],
+        transform[(0, 1)],
+        transform[(1, 1)],
+        transform[(0, 3)],
+        transform[(1, 3)],
+    )
+}
+
+fn scaling(width: f32, height: f32) -> nalgebra::Matrix4<f32> {
+    nalgebra::Matrix4::new_nonuniform_scaling(&nalgebra::Vector3::new(width, height, 1.0))
+}
+
+/// Bounding box of the unit square under `transform`, as `[min x, min y, max x, max y]`.
+fn bounds(transform: &nalgebra::Matrix4<f32>) -> [f32; 4] {
+    let mut rect = [
+        f32::INFINITY,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::NEG_INFINITY,
+    ];
+    for [x, y] in [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]] {
+        let point = transform.transform_point(&nalgebra::Point3::new(x, y, 0.0));
+        rect = [
+            rect[0].min(point.x),
+            rect[1].min(point.y),
+            rect[2].max(point.x),
+            rect[3].max(point.y),
+        ];
+    }
+    rect
+}
+
+fn intersect(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        a[0].max(b[0]),
+        a[1].max(b[1]),
+        a[2].min(b[2]),
+        a[3].min(b[3]),
+    ]
+}
+
+fn overlaps(a: [f32; 4], b: [f32; 4]) -> bool {
+    a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3]
+}
+
+// MARK: PNG
+
+#[derive(Debug, Clone, Copy)]
+enum PngColorType {
+    Gray,
+    Rgba,
+}
+
+impl PngColorType {
+    fn code(self) -> u8 {
+        match self {
+            Self::Gray => 0,
+            Self::Rgba => 6,
+        }
+    }
+
+    fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Gray => 1,
+            Self::Rgba => 4,
+        }
+    }
+}
+
+/// Encodes 8-bit `pixels` as a PNG with uncompressed deflate blocks. SVG consumers recompress
+/// on conversion anyway, and this keeps the renderer free of an image codec.
+fn encode_png(width: u32, height: u32, color_type: PngColorType, pixels: &[u8]) -> Vec<u8> {
+    let row_len = width as usize * color_type.bytes_per_pixel();
+
+    // every scanline starts with filter type 0 (none)
+    let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+    for row in pixels.chunks(row_len.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, color_type.code(), 0, 0, 0]);
+    png_chunk(&mut png, b"IHDR", &header);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// A zlib stream of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xffff;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    // deflate with a 32 KiB window, no preset dictionary
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = u8::from(blocks.peek().is_none());
+        let len = block.len() as u16;
+        out.push(last);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % MOD;
+        b = (b + a) % MOD;
+    }
+    (b << 16) | a
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use gpu_utils::texture_atlas::TextureAtlas;
+
+    use super::*;
+
+    fn translation(x: f32, y: f32) -> nalgebra::Matrix4<f32> {
+        nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(x, y, 0.0))
+    }
+
+    #[tokio::test]
+    async fn test_export_svg_places_visible_textures_only() {
+        let (_, _, device, queue) = gpu_utils::wgpu_utils::noop_wgpu().await;
+        let atlas = TextureAtlas::new(
+            &device,
+            wgpu::Extent3d {
+                width: 64,
+                height: 64,
+                depth_or_array_layers: 1,
+            },
+            wgpu::TextureFormat::Rgba8Unorm,
+            0,
+        );
+        let region = || atlas.allocate(&device, &queue, [4, 4]).unwrap();
+
+        let root = RenderNode::new()
+            .with_texture(region(), [16.0, 16.0], translation(20.0, 20.0))
+            // invisible
+            .add_child(
+                RenderNode::new()
+                    .with_texture(region(), [4.0, 4.0], nalgebra::Matrix4::identity())
+                    .with_opacity(0.0),
+                translation(30.0, 30.0),
+            )
+            // clip outside the document
+            .add_child(
+                RenderNode::new()
+                    .with_texture(region(), [4.0, 4.0], nalgebra::Matrix4::identity())
+                    .with_clip([8.0, 8.0]),
+                translation(200.0, 20.0),
+            )
+            // texture outside the clip of its parent
+            .add_child(
+                RenderNode::new().with_clip([10.0, 10.0]).add_child(
+                    RenderNode::new().with_texture(
+                        region(),
+                        [4.0, 4.0],
+                        nalgebra::Matrix4::identity(),
+                    ),
+                    translation(50.0, 0.0),
+                ),
+                translation(20.0, 40.0),
+            );
+
+        let svg = export_svg(&root, [[10.0, 10.0], [110.0, 60.0]], &device, &queue).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"width="100" height="50" viewBox="0 0 100 50""#));
+        assert_eq!(svg.matches("<image ").count(), 1);
+        assert_eq!(svg.matches("<use ").count(), 1);
+        assert!(
+            svg.contains(r##"<use xlink:href="#image1" transform="matrix(16 0 0 16 10 10)"/>"##)
+        );
+        // the clip inside the document is kept, with the document origin applied
+        assert_eq!(svg.matches("<clipPath ").count(), 1);
+        assert!(svg.contains(
+            r#"<clipPath id="clip2"><rect width="10" height="10" rx="0" transform="matrix(1 0 0 1 10 30)"/></clipPath>"#
+        ));
+    }
+}
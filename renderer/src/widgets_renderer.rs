@@ -1,5 +1,9 @@
+pub mod backdrop_blur;
 pub mod bezier_2d;
+pub mod box_shadow;
+pub mod glyph_mask;
 pub mod line_strip;
+pub mod series;
 pub mod texture_color;
 pub mod texture_copy;
 pub mod vertex_color;
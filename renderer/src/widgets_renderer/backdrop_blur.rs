@@ -0,0 +1,446 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
+use utils::rwoption::RwOption;
+use wgpu::PipelineCompilationOptions;
+
+/*
+bind group 0:
+    @binding(0) texture_2d<f32>
+    @binding(1) sampler
+
+push constants (as PushConstant struct):
+    source_uv_min: vec2<f32>
+    source_uv_max: vec2<f32>
+    texel_step: vec2<f32>
+    radius: f32
+*/
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PushConstant {
+    source_uv_min: [f32; 2],
+    source_uv_max: [f32; 2],
+    texel_step: [f32; 2],
+    radius: f32,
+    _padding: f32,
+}
+
+const PUSH_CONSTANTS_SIZE: u32 = std::mem::size_of::<PushConstant>() as u32;
+
+/// Largest blur radius (in pixels) the shader evaluates. Larger radii are clamped.
+pub const MAX_BLUR_RADIUS: f32 = 64.0;
+
+const PIPELINE_CACHE_SIZE: u64 = 4;
+
+/// Gaussian blur over a region of a captured background texture.
+///
+/// Used for acrylic-style panels: the region of the background behind a widget is
+/// blurred with a separable two-pass gaussian, either into a new texture ([`blur`](Self::blur))
+/// or back into the texture it was read from ([`blur_in_place`](Self::blur_in_place)), which
+/// is how [`CoreRenderer`](crate::CoreRenderer) draws
+/// [`RenderNode::with_backdrop_blur`](crate::RenderNode::with_backdrop_blur).
+#[derive(Default)]
+pub struct BackdropBlur {
+    inner: RwOption<BackdropBlurImpl>,
+}
+
+struct BackdropBlurImpl {
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    texture_sampler: wgpu::Sampler,
+    pipeline_layout: wgpu::PipelineLayout,
+    pipeline: moka::sync::Cache<wgpu::TextureFormat, wgpu::RenderPipeline, fxhash::FxBuildHasher>,
+}
+
+impl BackdropBlurImpl {
+    fn setup(device: &wgpu::Device) -> Self {
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("backdrop_blur_bind_group_layout"),
+                entries: &[
+                    // texture
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    // sampler
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("backdrop_blur_sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("backdrop_blur_pipeline_layout"),
+            bind_group_layouts: &[&texture_bind_group_layout],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                range: 0..PUSH_CONSTANTS_SIZE,
+            }],
+        });
+
+        let pipeline = moka::sync::CacheBuilder::new(PIPELINE_CACHE_SIZE)
+            .build_with_hasher(fxhash::FxBuildHasher::default());
+
+        BackdropBlurImpl {
+            texture_bind_group_layout,
+            texture_sampler,
+            pipeline_layout,
+            pipeline,
+        }
+    }
+}
+
+pub struct BlurSource<'a> {
+    /// The captured background. Its texture must have `TEXTURE_BINDING` usage.
+    pub source_texture_view: &'a wgpu::TextureView,
+    /// Size of the whole source texture in pixels.
+    pub source_texture_size: [u32; 2],
+    /// Upper-left corner of the region to blur, in source pixels.
+    pub region_position: [f32; 2],
+    /// Size of the region to blur, in pixels. This is also the size of the output texture.
+    pub region_size: [u32; 2],
+    /// Gaussian radius in pixels. Clamped to [`MAX_BLUR_RADIUS`].
+    pub radius: f32,
+}
+
+impl WidgetRenderer for BackdropBlur {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(BackdropBlurImpl::setup(device));
+        renderer
+    }
+}
+
+impl BackdropBlur {
+    /// Records the two blur passes into `encoder` and returns the blurred region.
+    ///
+    /// The returned texture has the same format as the source and `region_size` extent.
+    /// Returns `None` when the region is empty or the source cannot be sampled.
+    pub fn blur(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        BlurSource {
+            source_texture_view,
+            source_texture_size,
+            region_position,
+            region_size,
+            radius,
+        }: BlurSource<'_>,
+        device: &wgpu::Device,
+    ) -> Option<wgpu::Texture> {
+        if region_size[0] == 0
+            || region_size[1] == 0
+            || source_texture_size[0] == 0
+            || source_texture_size[1] == 0
+        {
+            return None;
+        }
+
+        let source_texture = source_texture_view.texture();
+        if !source_texture
+            .usage()
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING)
+        {
+            log::debug!("BackdropBlur::blur: source texture is not sampleable, skipping");
+            return None;
+        }
+        let format = source_texture.format();
+
+        let inner = self
+            .inner
+            .get_or_insert_with(|| BackdropBlurImpl::setup(device));
+        let render_pipeline = inner.pipeline.get_with(format, || {
+            make_pipeline(device, format, &inner.pipeline_layout)
+        });
+
+        let make_target = |label| make_blur_target(device, label, format, region_size);
+        let horizontal = make_target("backdrop_blur_horizontal");
+        let vertical = make_target("backdrop_blur_vertical");
+        let horizontal_view = horizontal.create_view(&wgpu::TextureViewDescriptor::default());
+        let vertical_view = vertical.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let (horizontal_pass, vertical_pass) =
+            push_constants(source_texture_size, region_position, region_size, radius);
+        for (source, target, push_constants) in [
+            (source_texture_view, &horizontal_view, horizontal_pass),
+            (&horizontal_view, &vertical_view, vertical_pass),
+        ] {
+            inner.draw_pass(
+                encoder,
+                &render_pipeline,
+                source,
+                target,
+                None,
+                push_constants,
+                device,
+            );
+        }
+
+        Some(vertical)
+    }
+
+    /// Records the two blur passes into `encoder`, writing the blurred region back over the
+    /// region of the source it was read from.
+    ///
+    /// The source texture is also the render target, so it needs `TEXTURE_BINDING` and
+    /// `RENDER_ATTACHMENT` usage, and the region must lie inside it. Returns `false` without
+    /// recording anything when the region is empty or the texture lacks either usage.
+    pub fn blur_in_place(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        BlurSource {
+            source_texture_view,
+            source_texture_size,
+            region_position,
+            region_size,
+            radius,
+        }: BlurSource<'_>,
+        device: &wgpu::Device,
+    ) -> bool {
+        if region_size[0] == 0
+            || region_size[1] == 0
+            || source_texture_size[0] == 0
+            || source_texture_size[1] == 0
+        {
+            return false;
+        }
+
+        let source_texture = source_texture_view.texture();
+        if !source_texture
+            .usage()
+            .contains(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT)
+        {
+            log::debug!(
+                "BackdropBlur::blur_in_place: texture is not sampleable and renderable, skipping"
+            );
+            return false;
+        }
+        let format = source_texture.format();
+
+        let inner = self
+            .inner
+            .get_or_insert_with(|| BackdropBlurImpl::setup(device));
+        let render_pipeline = inner.pipeline.get_with(format, || {
+            make_pipeline(device, format, &inner.pipeline_layout)
+        });
+
+        let horizontal = make_blur_target(device, "backdrop_blur_horizontal", format, region_size);
+        let horizontal_view = horizontal.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let (horizontal_pass, vertical_pass) =
+            push_constants(source_texture_size, region_position, region_size, radius);
+        inner.draw_pass(
+            encoder,
+            &render_pipeline,
+            source_texture_view,
+            &horizontal_view,
+            None,
+            horizontal_pass,
+            device,
+        );
+        inner.draw_pass(
+            encoder,
+            &render_pipeline,
+            &horizontal_view,
+            source_texture_view,
+            Some((region_position, region_size)),
+            vertical_pass,
+            device,
+        );
+
+        true
+    }
+}
+
+impl BackdropBlurImpl {
+    /// Draws one blur direction from `source` over the whole `target`, or over `region`
+    /// ((position, size) in pixels) of it, keeping the rest of the target.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+        region: Option<([f32; 2], [u32; 2])>,
+        push_constants: PushConstant,
+        device: &wgpu::Device,
+    ) {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("backdrop_blur_bind_group"),
+            layout: &self.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.texture_sampler),
+                },
+            ],
+        });
+
+        let load = match region {
+            Some(_) => wgpu::LoadOp::Load,
+            None => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("backdrop_blur_pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(render_pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        if let Some((position, size)) = region {
+            render_pass.set_viewport(
+                position[0],
+                position[1],
+                size[0] as f32,
+                size[1] as f32,
+                0.0,
+                1.0,
+            );
+        }
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            0,
+            bytemuck::cast_slice(&[push_constants]),
+        );
+        render_pass.draw(0..4, 0..1);
+    }
+}
+
+/// Push constants of the horizontal pass over the source region and of the vertical pass
+/// over the intermediate texture.
+fn push_constants(
+    source_texture_size: [u32; 2],
+    region_position: [f32; 2],
+    region_size: [u32; 2],
+    radius: f32,
+) -> (PushConstant, PushConstant) {
+    let source_size = [source_texture_size[0] as f32, source_texture_size[1] as f32];
+    let radius = radius.clamp(0.0, MAX_BLUR_RADIUS);
+
+    // pass 1: horizontal blur of the source region
+    let horizontal_pass = PushConstant {
+        source_uv_min: [
+            region_position[0] / source_size[0],
+            region_position[1] / source_size[1],
+        ],
+        source_uv_max: [
+            (region_position[0] + region_size[0] as f32) / source_size[0],
+            (region_position[1] + region_size[1] as f32) / source_size[1],
+        ],
+        texel_step: [1.0 / source_size[0], 0.0],
+        radius,
+        _padding: 0.0,
+    };
+    // pass 2: vertical blur of the intermediate texture
+    let vertical_pass = PushConstant {
+        source_uv_min: [0.0, 0.0],
+        source_uv_max: [1.0, 1.0],
+        texel_step: [0.0, 1.0 / region_size[1] as f32],
+        radius,
+        _padding: 0.0,
+    };
+    (horizontal_pass, vertical_pass)
+}
+
+fn make_blur_target(
+    device: &wgpu::Device,
+    label: &str,
+    format: wgpu::TextureFormat,
+    region_size: [u32; 2],
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: region_size[0],
+            height: region_size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+fn make_pipeline(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("backdrop_blur_shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("backdrop_blur.wgsl").into()),
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("backdrop_blur_pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
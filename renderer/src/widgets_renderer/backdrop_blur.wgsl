@@ -0,0 +1,66 @@
+// Declared before the resources: the GL backend compacts the module per entry point but
+// looks up the push constant types in the whole module, so their types must come first.
+struct PushConstants {
+    // uv rectangle of the source that is mapped onto the whole target
+    source_uv_min: vec2<f32>,
+    source_uv_max: vec2<f32>,
+    // one texel step along the blur direction, in uv units
+    texel_step: vec2<f32>,
+    radius: f32,
+    _padding: f32,
+};
+var<push_constant> pc: PushConstants;
+
+// resource
+@group(0) @binding(0)
+var blur_source: texture_2d<f32>;
+@group(0) @binding(1)
+var texture_sampler: sampler;
+
+const MAX_RADIUS: i32 = 64;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+// full-target quad drawn as a triangle strip
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32
+) -> VertexOutput {
+    let corner = vec2<f32>(f32(vertex_index / 2u), f32(vertex_index % 2u));
+
+    let clip = vec2<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0);
+    let tex_coords = mix(pc.source_uv_min, pc.source_uv_max, corner);
+
+    return VertexOutput(vec4<f32>(clip, 0.0, 1.0), tex_coords);
+}
+
+// one direction of a separable gaussian blur
+@fragment
+fn fs_main(
+    @location(0) tex_coords: vec2<f32>
+) -> @location(0) vec4<f32> {
+    let radius = min(i32(ceil(pc.radius)), MAX_RADIUS);
+    if radius <= 0 {
+        return textureSample(blur_source, texture_sampler, tex_coords);
+    }
+
+    let sigma = max(pc.radius * 0.5, 0.0001);
+
+    var color = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var i = -MAX_RADIUS; i <= MAX_RADIUS; i = i + 1) {
+        if abs(i) > radius {
+            continue;
+        }
+        let x = f32(i);
+        let weight = exp(-(x * x) / (2.0 * sigma * sigma));
+        let uv = clamp(tex_coords + pc.texel_step * x, pc.source_uv_min, pc.source_uv_max);
+        color = color + textureSampleLevel(blur_source, texture_sampler, uv, 0.0) * weight;
+        weight_sum = weight_sum + weight;
+    }
+
+    return color / weight_sum;
+}
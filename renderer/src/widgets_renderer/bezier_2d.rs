@@ -1,3 +1,4 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
 use gpu_utils::texture_atlas;
 use std::sync::Arc;
 use utils::rwoption::RwOption;
@@ -169,6 +170,14 @@ impl Bezier2dImpl {
     }
 }
 
+impl WidgetRenderer for Bezier2d {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(Bezier2dImpl::setup(device));
+        renderer
+    }
+}
+
 impl Bezier2d {
     pub fn render(
         &self,
@@ -0,0 +1,198 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
+use utils::rwoption::RwOption;
+use wgpu::util::DeviceExt;
+
+// Draws the soft shadows of rounded rectangles, e.g. the key and ambient shadow of an
+// elevated card. Shadows are alpha blended over the target, so layers of one call add up.
+
+const PIPELINE_CACHE_SIZE: u64 = 4;
+
+/// The shadow of one rounded rectangle.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Shadow {
+    /// [min x, min y, max x, max y] of the casting rectangle in target pixels.
+    pub rect: [f32; 4],
+    /// Straight-alpha color of the shadow under the rectangle.
+    pub color: [f32; 4],
+    /// Radius of the corners of the rectangle.
+    pub corner_radius: f32,
+    /// Standard deviation of the gaussian blur; the shadow fades out within three of them.
+    pub blur: f32,
+}
+
+impl Shadow {
+    /// How far the shadow reaches beyond its rectangle.
+    pub fn reach(&self) -> f32 {
+        3.0 * self.blur.max(0.0)
+    }
+
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x4, 1 => Float32x4, 2 => Float32x2];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Shadow>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+pub struct BoxShadow {
+    inner: RwOption<BoxShadowImpl>,
+}
+
+struct BoxShadowImpl {
+    pipeline_layout: wgpu::PipelineLayout,
+    pipeline: moka::sync::Cache<wgpu::TextureFormat, wgpu::RenderPipeline, fxhash::FxBuildHasher>,
+}
+
+impl BoxShadowImpl {
+    fn setup(device: &wgpu::Device) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("BoxShadow: Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX,
+                range: 0..(std::mem::size_of::<nalgebra::Matrix4<f32>>() as u32),
+            }],
+        });
+
+        let pipeline = moka::sync::CacheBuilder::new(PIPELINE_CACHE_SIZE)
+            .build_with_hasher(fxhash::FxBuildHasher::default());
+
+        Self {
+            pipeline_layout,
+            pipeline,
+        }
+    }
+}
+
+pub struct TargetData {
+    pub target_size: [u32; 2],
+    pub target_format: wgpu::TextureFormat,
+}
+
+impl Default for BoxShadow {
+    fn default() -> Self {
+        Self {
+            inner: RwOption::new(),
+        }
+    }
+}
+
+impl WidgetRenderer for BoxShadow {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(BoxShadowImpl::setup(device));
+        renderer
+    }
+}
+
+impl BoxShadow {
+    /// Draws `shadows` in order, later ones over earlier ones.
+    pub fn render(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        TargetData {
+            target_size,
+            target_format,
+        }: TargetData,
+        shadows: &[Shadow],
+        device: &wgpu::Device,
+    ) {
+        if shadows.is_empty() {
+            return;
+        }
+
+        let inner = self
+            .inner
+            .get_or_insert_with(|| BoxShadowImpl::setup(device));
+
+        let render_pipeline = inner.pipeline.get_with(target_format, || {
+            make_pipeline(device, target_format, &inner.pipeline_layout)
+        });
+
+        let normalize = normalize_transform([target_size[0] as f32, target_size[1] as f32]);
+
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("box_shadow_instance_buffer"),
+            contents: bytemuck::cast_slice(shadows),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        render_pass.set_pipeline(&render_pipeline);
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX,
+            0,
+            bytemuck::cast_slice(normalize.as_slice()),
+        );
+        render_pass.set_vertex_buffer(0, instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..shadows.len() as u32);
+    }
+}
+
+fn make_pipeline(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("box_shadow_shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("box_shadow.wgsl").into()),
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("box_shadow_pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Shadow::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleStrip,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Maps target pixels (origin top-left, Y down) to normalized device coordinates.
+#[rustfmt::skip]
+fn normalize_transform(viewport_size: [f32; 2]) -> nalgebra::Matrix4<f32> {
+    let transform = nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+        -1.0,
+        1.0,
+        0.0,
+    ));
+
+    let scale = nalgebra::Matrix4::new_nonuniform_scaling(
+        &nalgebra::Vector3::new(
+            2.0 / viewport_size[0],
+            -2.0 / viewport_size[1],
+            1.0,
+        ),
+    );
+
+    transform * scale
+}
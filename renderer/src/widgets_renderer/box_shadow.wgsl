@@ -0,0 +1,78 @@
+var<push_constant> normalize_affine: mat4x4<f32>;
+
+struct ShadowInput {
+    // min x, min y, max x, max y in target pixels
+    @location(0) rect: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    // corner radius, blur standard deviation
+    @location(2) radius_blur: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) position: vec2<f32>,
+    @location(1) rect: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) radius_blur: vec2<f32>,
+};
+
+// quad corners as a triangle strip:
+// 0 - 2
+// | / |
+// 1 - 3
+const CORNERS = array<vec2<f32>, 4>(
+    vec2<f32>(0.0, 0.0),
+    vec2<f32>(0.0, 1.0),
+    vec2<f32>(1.0, 0.0),
+    vec2<f32>(1.0, 1.0),
+);
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    shadow: ShadowInput,
+) -> VertexOutput {
+    // the quad covers the rectangle and the blur around it
+    let reach = 3.0 * max(shadow.radius_blur.y, 0.0);
+    let position = mix(
+        shadow.rect.xy - vec2<f32>(reach),
+        shadow.rect.zw + vec2<f32>(reach),
+        CORNERS[vertex_index]
+    );
+
+    var out: VertexOutput;
+    out.clip_position = normalize_affine * vec4<f32>(position, 0.0, 1.0);
+    out.position = position;
+    out.rect = shadow.rect;
+    out.color = shadow.color;
+    out.radius_blur = shadow.radius_blur;
+    return out;
+}
+
+// Abramowitz and Stegun approximation of the error function, max error 5e-4.
+fn erf(x: f32) -> f32 {
+    let a = abs(x);
+    let t = 1.0 + a * (0.278393 + a * (0.230389 + a * (0.000972 + a * 0.078108)));
+    let t2 = t * t;
+    return sign(x) * (1.0 - 1.0 / (t2 * t2));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let half_size = (in.rect.zw - in.rect.xy) * 0.5;
+    let radius = min(in.radius_blur.x, min(half_size.x, half_size.y));
+    let blur = max(in.radius_blur.y, 0.0);
+
+    // signed distance to the rounded rectangle
+    let q = abs(in.position - (in.rect.xy + in.rect.zw) * 0.5) - half_size + vec2<f32>(radius);
+    let distance = length(max(q, vec2<f32>(0.0))) + min(max(q.x, q.y), 0.0) - radius;
+
+    // the blurred edge of a half plane; a hard edge antialiased over one pixel without blur
+    let coverage = select(
+        clamp(0.5 - distance, 0.0, 1.0),
+        0.5 - 0.5 * erf(distance / (blur * 1.41421356)),
+        blur > 0.0
+    );
+
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
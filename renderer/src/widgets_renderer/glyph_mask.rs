@@ -0,0 +1,333 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
+use utils::rwoption::RwOption;
+use wgpu::util::DeviceExt;
+
+use crate::vertex::uv_vertex::UvVertex;
+
+// Draws glyph coverage masks tinted with a color, blended over the target.
+// - Grayscale masks hold coverage in the red channel (e.g. `R8Unorm`).
+// - Subpixel (LCD) masks hold coverage per color channel and the mean in alpha (`Rgba8Unorm`),
+//   blended per channel with dual-source blending. Devices without
+//   `Features::DUAL_SOURCE_BLENDING` draw them with the mean coverage instead.
+// - Same UV convention as texture_color.rs: v = 0 at the top.
+
+const PIPELINE_CACHE_SIZE: u64 = 4;
+
+/// How the coverage of a glyph mask is stored and blended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GlyphMaskKind {
+    /// One coverage value in the red channel.
+    #[default]
+    Grayscale,
+    /// Coverage per color channel, with the mean coverage in alpha.
+    Subpixel,
+}
+
+impl GlyphMaskKind {
+    /// The kind `device` can draw: subpixel masks need dual-source blending.
+    pub fn supported_by(self, device: &wgpu::Device) -> Self {
+        match self {
+            Self::Subpixel
+                if !device
+                    .features()
+                    .contains(wgpu::Features::DUAL_SOURCE_BLENDING) =>
+            {
+                Self::Grayscale
+            }
+            kind => kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Pipeline {
+    Coverage,
+    MeanCoverage,
+    Subpixel,
+}
+
+pub struct GlyphMask {
+    inner: RwOption<GlyphMaskImpl>,
+}
+
+struct GlyphMaskImpl {
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    pipeline: moka::sync::Cache<
+        (wgpu::TextureFormat, Pipeline),
+        wgpu::RenderPipeline,
+        fxhash::FxBuildHasher,
+    >,
+    texture_sampler: wgpu::Sampler,
+}
+
+impl GlyphMaskImpl {
+    fn setup(device: &wgpu::Device) -> Self {
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("GlyphMask: Texture Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("GlyphMask: Pipeline Layout"),
+            bind_group_layouts: &[&texture_bind_group_layout],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                range: 0..PUSH_CONSTANT_SIZE,
+            }],
+        });
+
+        let pipeline = moka::sync::CacheBuilder::new(PIPELINE_CACHE_SIZE)
+            .build_with_hasher(fxhash::FxBuildHasher::default());
+
+        // glyphs are drawn at their rasterized size, so sample texels exactly
+        let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("GlyphMask: Texture Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture_bind_group_layout,
+            pipeline_layout,
+            pipeline,
+            texture_sampler,
+        }
+    }
+}
+
+// affine matrix followed by the color
+const PUSH_CONSTANT_SIZE: u32 = (std::mem::size_of::<nalgebra::Matrix4<f32>>() + 16) as u32;
+
+pub struct TargetData {
+    pub target_size: [u32; 2],
+    pub target_format: wgpu::TextureFormat,
+}
+
+pub struct RenderData<'a> {
+    pub position: [f32; 2],
+    pub vertices: &'a [UvVertex],
+    pub indices: &'a [u16],
+    pub mask_view: &'a wgpu::TextureView,
+    pub mask_kind: GlyphMaskKind,
+    /// Straight (not premultiplied) text color.
+    pub color: [f32; 4],
+}
+
+impl Default for GlyphMask {
+    fn default() -> Self {
+        Self {
+            inner: RwOption::new(),
+        }
+    }
+}
+
+impl WidgetRenderer for GlyphMask {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(GlyphMaskImpl::setup(device));
+        renderer
+    }
+}
+
+impl GlyphMask {
+    pub fn render(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        TargetData {
+            target_size,
+            target_format,
+        }: TargetData,
+        RenderData {
+            position,
+            vertices,
+            indices,
+            mask_view,
+            mask_kind,
+            color,
+        }: RenderData,
+        device: &wgpu::Device,
+    ) {
+        let inner = self
+            .inner
+            .get_or_insert_with(|| GlyphMaskImpl::setup(device));
+
+        let pipeline = match (mask_kind, mask_kind.supported_by(device)) {
+            (GlyphMaskKind::Grayscale, _) => Pipeline::Coverage,
+            (GlyphMaskKind::Subpixel, GlyphMaskKind::Subpixel) => Pipeline::Subpixel,
+            (GlyphMaskKind::Subpixel, GlyphMaskKind::Grayscale) => Pipeline::MeanCoverage,
+        };
+        let render_pipeline = inner.pipeline.get_with((target_format, pipeline), || {
+            make_pipeline(device, target_format, pipeline, &inner.pipeline_layout)
+        });
+
+        let view_port_affine_transform =
+            affine_transform([target_size[0] as f32, target_size[1] as f32], position);
+        let mut push_constants = view_port_affine_transform.as_slice().to_vec();
+        push_constants.extend_from_slice(&color);
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("glyph_mask_vertex_buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("glyph_mask_index_buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("GlyphMask: Texture Bind Group"),
+            layout: &inner.texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(mask_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&inner.texture_sampler),
+                },
+            ],
+        });
+
+        render_pass.set_pipeline(&render_pipeline);
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX_FRAGMENT,
+            0,
+            bytemuck::cast_slice(&push_constants),
+        );
+        render_pass.set_bind_group(0, &texture_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..indices.len() as u32, 0, 0..1);
+    }
+}
+
+fn make_pipeline(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    pipeline: Pipeline,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    let (source, entry_point, blend) = match pipeline {
+        Pipeline::Coverage => (
+            include_str!("glyph_mask.wgsl"),
+            "fs_coverage",
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        ),
+        Pipeline::MeanCoverage => (
+            include_str!("glyph_mask.wgsl"),
+            "fs_mean_coverage",
+            wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+        ),
+        // each channel of the target keeps the share its subpixel leaves uncovered
+        Pipeline::Subpixel => (
+            include_str!("glyph_mask_subpixel.wgsl"),
+            "fs_subpixel",
+            wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrc1,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrc1Alpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+            },
+        ),
+    };
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("glyph_mask_shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("glyph_mask_pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[UvVertex::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some(entry_point),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+#[rustfmt::skip]
+fn affine_transform(
+    viewport_size: [f32; 2],
+    position: [f32; 2],
+) -> nalgebra::Matrix4<f32> {
+    let position = nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+        position[0],
+        position[1],
+        0.0,
+    ));
+
+    let transform = nalgebra::Matrix4::new_translation(&nalgebra::Vector3::new(
+        -1.0,
+        1.0,
+        0.0,
+    ));
+
+    let scale = nalgebra::Matrix4::new_nonuniform_scaling(
+        &nalgebra::Vector3::new(
+            2.0 / viewport_size[0],
+            -2.0 / viewport_size[1],
+            1.0,
+        ),
+    );
+
+    transform * scale * position
+}
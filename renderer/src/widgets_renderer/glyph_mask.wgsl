@@ -0,0 +1,41 @@
+struct Constants {
+    normalize_affine: mat4x4<f32>,
+    color: vec4<f32>,
+};
+
+var<push_constant> constants: Constants;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var t_mask: texture_2d<f32>;
+@group(0) @binding(1)
+var s_mask: sampler;
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    let position = vec4<f32>(model.position, 1.0);
+    return VertexOutput(constants.normalize_affine * position, model.tex_coords);
+}
+
+// coverage in the red channel, e.g. an `R8Unorm` glyph atlas
+@fragment
+fn fs_coverage(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(t_mask, s_mask, in.tex_coords).r * constants.color.a;
+    return vec4<f32>(constants.color.rgb * coverage, coverage);
+}
+
+// grayscale fallback for subpixel masks, which keep the mean coverage in alpha
+@fragment
+fn fs_mean_coverage(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(t_mask, s_mask, in.tex_coords).a * constants.color.a;
+    return vec4<f32>(constants.color.rgb * coverage, coverage);
+}
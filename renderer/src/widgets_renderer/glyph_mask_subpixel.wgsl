@@ -0,0 +1,45 @@
+enable dual_source_blending;
+
+struct Constants {
+    normalize_affine: mat4x4<f32>,
+    color: vec4<f32>,
+};
+
+var<push_constant> constants: Constants;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+// the second source blends each color channel of the target by its own coverage
+struct FragmentOutput {
+    @location(0) @blend_src(0) color: vec4<f32>,
+    @location(0) @blend_src(1) coverage: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var t_mask: texture_2d<f32>;
+@group(0) @binding(1)
+var s_mask: sampler;
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    let position = vec4<f32>(model.position, 1.0);
+    return VertexOutput(constants.normalize_affine * position, model.tex_coords);
+}
+
+// per-channel coverage in rgb and the mean coverage in alpha
+@fragment
+fn fs_subpixel(in: VertexOutput) -> FragmentOutput {
+    let coverage = textureSample(t_mask, s_mask, in.tex_coords) * constants.color.a;
+    return FragmentOutput(
+        vec4<f32>(constants.color.rgb * coverage.rgb, coverage.a),
+        coverage,
+    );
+}
@@ -1,3 +1,4 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
 /*
 push constants:
     [[f32; 4]; 4] // composed affine matrix
@@ -50,6 +51,14 @@ pub struct RenderData<'a> {
     pub vertices: &'a [ColorVertex],
 }
 
+impl WidgetRenderer for LineStripColor {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(LineStripColorImpl::setup(device));
+        renderer
+    }
+}
+
 impl LineStripColor {
     pub fn render(
         &self,
@@ -0,0 +1,353 @@
+//! Chart series drawn from points kept on the GPU.
+//!
+//! A series is a list of points in data coordinates. [`SeriesRenderer::store`] copies them
+//! into a [`SeriesBuffer`], whose chunks of [`CHUNK_POINTS`] points live in a shared
+//! [`BufferAtlas`]. Chunks whose points did not change are not uploaded again by
+//! [`SeriesRenderer::flash`], so redrawing a chart with thousands of points transfers little
+//! or nothing. The points are drawn as one of the [`SeriesKind`]s, with one instance per
+//! segment or bar.
+//!
+//! Data coordinates are mapped to pixels of the target by a scale and an offset per axis, so
+//! panning or zooming a chart only changes push constants.
+
+use std::ops::Range;
+
+use gpu_utils::buffer_atlas::buffer_atlas::{Buffer, BufferAtlas};
+use gpu_utils::gpu_type_map::WidgetRenderer;
+use parking_lot::Mutex;
+use utils::rwoption::RwOption;
+use wgpu::PipelineCompilationOptions;
+
+/*
+vertex buffers (instance step):
+    0: [f32; 2] point, the start of a segment for lines and areas
+    1: [f32; 2] the end of a segment, for lines and areas
+
+push constants (as PushConstant struct):
+    color: vec4<f32>
+    scale: vec2<f32>
+    offset: vec2<f32>
+    target_size: vec2<f32>
+    width: f32
+    baseline: f32
+*/
+
+/// Points in one chunk of a [`SeriesBuffer`].
+pub const CHUNK_POINTS: usize = 256;
+
+const POINT_SIZE: usize = std::mem::size_of::<[f32; 2]>();
+const CHUNK_BYTES: usize = CHUNK_POINTS * POINT_SIZE;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PushConstant {
+    color: [f32; 4],
+    scale: [f32; 2],
+    offset: [f32; 2],
+    target_size: [f32; 2],
+    width: f32,
+    baseline: f32,
+}
+
+const _: () = {
+    assert!(
+        wgpu::PUSH_CONSTANT_ALIGNMENT == 4,
+        "PushConstant alignment changed. check memory layout"
+    );
+};
+
+const PUSH_CONSTANTS_SIZE: u32 = std::mem::size_of::<PushConstant>() as u32;
+
+/// How the points of a series are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SeriesKind {
+    /// Straight segments between consecutive points, `width` pixels wide.
+    Line,
+    /// The region between the line through the points and the baseline.
+    Area,
+    /// A bar from the baseline to each point, `width` wide in data coordinates.
+    Bars,
+}
+
+/// The points of one series, stored by [`SeriesRenderer::store`]. Dropping it frees its
+/// space in the atlas.
+#[derive(Default)]
+pub struct SeriesBuffer {
+    chunks: Vec<Chunk>,
+}
+
+struct Chunk {
+    buffer: Buffer<CHUNK_BYTES>,
+    /// points stored in the chunk.
+    len: usize,
+}
+
+impl SeriesBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Ranges of the points stored in each chunk.
+///
+/// Consecutive chunks share a point, so the segment between them is drawn as well.
+fn chunk_ranges(len: usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::with_capacity(len.div_ceil(CHUNK_POINTS - 1));
+    let mut start = 0;
+    while start < len {
+        let end = (start + CHUNK_POINTS).min(len);
+        ranges.push(start..end);
+        if end == len {
+            break;
+        }
+        start = end - 1;
+    }
+    ranges
+}
+
+pub struct TargetData {
+    pub target_size: [u32; 2],
+    pub target_format: wgpu::TextureFormat,
+}
+
+pub struct RenderData<'a> {
+    pub series: &'a SeriesBuffer,
+    pub kind: SeriesKind,
+    pub color: [f32; 4],
+    /// Maps data coordinates to pixels of the target: `pixel = point * scale + offset`.
+    pub scale: [f32; 2],
+    pub offset: [f32; 2],
+    /// Line width in pixels, or bar width in data coordinates. Unused for areas.
+    pub width: f32,
+    /// The y in data coordinates areas and bars extend to.
+    pub baseline: f32,
+}
+
+#[derive(Default)]
+pub struct SeriesRenderer {
+    inner: RwOption<SeriesRendererImpl>,
+    atlas: Mutex<BufferAtlas<CHUNK_BYTES>>,
+}
+
+const PIPELINE_CACHE_SIZE: u64 = 12;
+
+struct SeriesRendererImpl {
+    pipeline_layout: wgpu::PipelineLayout,
+    pipeline: moka::sync::Cache<
+        (wgpu::TextureFormat, SeriesKind),
+        wgpu::RenderPipeline,
+        fxhash::FxBuildHasher,
+    >,
+}
+
+impl SeriesRendererImpl {
+    fn setup(device: &wgpu::Device) -> Self {
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("series_pipeline_layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                range: 0..PUSH_CONSTANTS_SIZE,
+            }],
+        });
+
+        let pipeline = moka::sync::CacheBuilder::new(PIPELINE_CACHE_SIZE)
+            .build_with_hasher(fxhash::FxBuildHasher::default());
+
+        Self {
+            pipeline_layout,
+            pipeline,
+        }
+    }
+}
+
+impl WidgetRenderer for SeriesRenderer {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(SeriesRendererImpl::setup(device));
+        renderer
+    }
+}
+
+impl SeriesRenderer {
+    /// Stores `points` in `series`. Chunks whose points changed are uploaded by the next
+    /// [`flash`](Self::flash).
+    pub fn store(&self, series: &mut SeriesBuffer, points: &[[f32; 2]]) {
+        let ranges = chunk_ranges(points.len());
+
+        {
+            let mut atlas = self.atlas.lock();
+            // chunks of a previous renderer, e.g. before the device was lost, are not drawn
+            let atlas_id = atlas.id();
+            series
+                .chunks
+                .retain(|chunk| chunk.buffer.atlas_id() == atlas_id);
+            series.chunks.truncate(ranges.len());
+            while series.chunks.len() < ranges.len() {
+                series.chunks.push(Chunk {
+                    buffer: atlas.allocate(),
+                    len: 0,
+                });
+            }
+        }
+
+        for (chunk, range) in series.chunks.iter_mut().zip(ranges) {
+            let mut data = [0u8; CHUNK_BYTES];
+            let bytes: &[u8] = bytemuck::cast_slice(&points[range.clone()]);
+            data[..bytes.len()].copy_from_slice(bytes);
+            chunk.buffer.store(data);
+            chunk.len = range.len();
+        }
+    }
+
+    /// Uploads the stored points that changed. Call before rendering.
+    pub fn flash(&self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.atlas.lock().flash(device, queue);
+    }
+
+    pub fn render(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        TargetData {
+            target_size,
+            target_format,
+        }: TargetData,
+        RenderData {
+            series,
+            kind,
+            color,
+            scale,
+            offset,
+            width,
+            baseline,
+        }: RenderData,
+        device: &wgpu::Device,
+    ) {
+        let SeriesRendererImpl {
+            pipeline_layout,
+            pipeline,
+        } = &*self
+            .inner
+            .get_or_insert_with(|| SeriesRendererImpl::setup(device));
+
+        let render_pipeline = pipeline.get_with((target_format, kind), || {
+            make_pipeline(device, target_format, kind, pipeline_layout)
+        });
+
+        let push_constant = PushConstant {
+            color,
+            scale,
+            offset,
+            target_size: [target_size[0] as f32, target_size[1] as f32],
+            width,
+            baseline,
+        };
+
+        render_pass.set_pipeline(&render_pipeline);
+        render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            0,
+            bytemuck::bytes_of(&push_constant),
+        );
+
+        let atlas = self.atlas.lock();
+        let Some(buffer) = atlas.buffer() else {
+            return;
+        };
+        let point = POINT_SIZE as wgpu::BufferAddress;
+        for (index, chunk) in series.chunks.iter().enumerate() {
+            // not placed until the next flash
+            let Some(start) = atlas.offset_of(&chunk.buffer) else {
+                continue;
+            };
+            let end = start + chunk.len as wgpu::BufferAddress * point;
+
+            match kind {
+                SeriesKind::Line | SeriesKind::Area => {
+                    if chunk.len < 2 {
+                        continue;
+                    }
+                    render_pass.set_vertex_buffer(0, buffer.slice(start..end - point));
+                    render_pass.set_vertex_buffer(1, buffer.slice(start + point..end));
+                    render_pass.draw(0..6, 0..(chunk.len - 1) as u32);
+                }
+                SeriesKind::Bars => {
+                    // the first point of a later chunk is the last of the one before
+                    let skip = usize::from(index > 0);
+                    if chunk.len <= skip {
+                        continue;
+                    }
+                    let start = start + skip as wgpu::BufferAddress * point;
+                    render_pass.set_vertex_buffer(0, buffer.slice(start..end));
+                    render_pass.draw(0..6, 0..(chunk.len - skip) as u32);
+                }
+            }
+        }
+    }
+}
+
+const START_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+const END_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![1 => Float32x2];
+
+const fn point_layout(attributes: &[wgpu::VertexAttribute]) -> wgpu::VertexBufferLayout<'_> {
+    wgpu::VertexBufferLayout {
+        array_stride: POINT_SIZE as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes,
+    }
+}
+
+fn make_pipeline(
+    device: &wgpu::Device,
+    target_format: wgpu::TextureFormat,
+    kind: SeriesKind,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("series_shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("series.wgsl").into()),
+    });
+
+    let segment_layouts = [
+        point_layout(&START_ATTRIBUTES),
+        point_layout(&END_ATTRIBUTES),
+    ];
+    let (entry_point, buffers) = match kind {
+        SeriesKind::Line => ("vs_line", &segment_layouts[..]),
+        SeriesKind::Area => ("vs_area", &segment_layouts[..]),
+        SeriesKind::Bars => ("vs_bar", &segment_layouts[..1]),
+    };
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("series_pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some(entry_point),
+            buffers,
+            compilation_options: PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: target_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
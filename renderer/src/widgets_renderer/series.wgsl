@@ -0,0 +1,109 @@
+struct PushConstant {
+    color: vec4<f32>,
+    scale: vec2<f32>,
+    offset: vec2<f32>,
+    target_size: vec2<f32>,
+    width: f32,
+    baseline: f32,
+};
+
+var<push_constant> pc: PushConstant;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // distance from the center line across a line in pixels, and half its width including the
+    // antialiased edge; areas and bars are fully covered
+    @location(0) distance: f32,
+    @location(1) half_width: f32,
+};
+
+fn to_pixel(point: vec2<f32>) -> vec2<f32> {
+    return point * pc.scale + pc.offset;
+}
+
+fn to_clip(pixel: vec2<f32>) -> vec4<f32> {
+    let normalized = pixel / pc.target_size * 2.0 - vec2<f32>(1.0, 1.0);
+    return vec4<f32>(normalized.x, -normalized.y, 0.0, 1.0);
+}
+
+// corners of the two triangles of a quad: x from the start to the end of a segment (or the
+// left to the right of a bar), y from one side to the other (or the top to the base)
+fn corner(index: u32) -> vec2<f32> {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+    );
+    return corners[index];
+}
+
+fn filled(clip_position: vec4<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = clip_position;
+    out.distance = 0.0;
+    out.half_width = 1.0;
+    return out;
+}
+
+@vertex
+fn vs_line(
+    @builtin(vertex_index) index: u32,
+    @location(0) start: vec2<f32>,
+    @location(1) end: vec2<f32>,
+) -> VertexOutput {
+    let a = to_pixel(start);
+    let b = to_pixel(end);
+    let delta = b - a;
+    let len = length(delta);
+    var direction = vec2<f32>(1.0, 0.0);
+    if len > 0.0 {
+        direction = delta / len;
+    }
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // half a pixel more on each side for the antialiased edge
+    let half_width = pc.width * 0.5 + 0.5;
+    let c = corner(index);
+    let across = (c.y * 2.0 - 1.0) * half_width;
+    // segments overlap by half the width at both ends so joints have no gaps
+    let along = (c.x * 2.0 - 1.0) * pc.width * 0.5;
+    let pixel = mix(a, b, c.x) + direction * along + normal * across;
+
+    var out: VertexOutput;
+    out.clip_position = to_clip(pixel);
+    out.distance = across;
+    out.half_width = half_width;
+    return out;
+}
+
+@vertex
+fn vs_area(
+    @builtin(vertex_index) index: u32,
+    @location(0) start: vec2<f32>,
+    @location(1) end: vec2<f32>,
+) -> VertexOutput {
+    let c = corner(index);
+    let top = mix(to_pixel(start), to_pixel(end), c.x);
+    let base = to_pixel(vec2<f32>(0.0, pc.baseline)).y;
+    return filled(to_clip(vec2<f32>(top.x, mix(top.y, base, c.y))));
+}
+
+@vertex
+fn vs_bar(
+    @builtin(vertex_index) index: u32,
+    @location(0) point: vec2<f32>,
+) -> VertexOutput {
+    let c = corner(index);
+    let x = point.x + (c.x - 0.5) * pc.width;
+    let y = mix(point.y, pc.baseline, c.y);
+    return filled(to_clip(to_pixel(vec2<f32>(x, y))));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = clamp(in.half_width - abs(in.distance), 0.0, 1.0);
+    return vec4<f32>(pc.color.rgb, pc.color.a * coverage);
+}
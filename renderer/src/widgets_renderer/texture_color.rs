@@ -1,3 +1,4 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
 use utils::rwoption::RwOption;
 use wgpu::util::DeviceExt;
 
@@ -102,6 +103,14 @@ impl Default for TextureColor {
     }
 }
 
+impl WidgetRenderer for TextureColor {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(TextureColorImpl::setup(device));
+        renderer
+    }
+}
+
 impl TextureColor {
     pub fn render(
         &self,
@@ -1,3 +1,4 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
 use nalgebra::Matrix4;
 use utils::rwoption::RwOption;
 use wgpu::PipelineCompilationOptions;
@@ -11,6 +12,8 @@ push constants (as PushConstant struct):
     target_texture_size: vec2<f32>
     source_texture_position_min: vec2<f32>
     source_texture_position_max: vec2<f32>
+    source_uv_min: vec2<f32>
+    source_uv_max: vec2<f32>
     color_transformation: mat4x4<f32>
     color_offset: vec4<f32>
 */
@@ -26,6 +29,8 @@ struct PushConstant {
     target_texture_size: [f32; 2],
     source_texture_position_min: [f32; 2],
     source_texture_position_max: [f32; 2],
+    source_uv_min: [f32; 2],
+    source_uv_max: [f32; 2],
 }
 
 const _: () = {
@@ -121,10 +126,21 @@ pub struct RenderData<'a> {
     pub source_texture_view: &'a wgpu::TextureView,
     pub source_texture_position_min: [f32; 2],
     pub source_texture_position_max: [f32; 2],
+    /// Minimum and maximum UV of the source to copy, e.g. a region of an atlas page.
+    /// The whole source when `None`.
+    pub source_uv: Option<[[f32; 2]; 2]>,
     pub color_transformation: Option<Matrix4<f32>>,
     pub color_offset: Option<[f32; 4]>,
 }
 
+impl WidgetRenderer for TextureCopy {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(TextureCopyImpl::setup(device));
+        renderer
+    }
+}
+
 impl TextureCopy {
     pub fn render(
         &self,
@@ -137,6 +153,7 @@ impl TextureCopy {
             source_texture_view: source_texture,
             source_texture_position_min,
             source_texture_position_max,
+            source_uv,
             color_transformation,
             color_offset,
         }: RenderData<'_>,
@@ -174,10 +191,13 @@ impl TextureCopy {
             }),
             &[],
         );
+        let [source_uv_min, source_uv_max] = source_uv.unwrap_or([[0.0, 0.0], [1.0, 1.0]]);
         let push_constants = PushConstant {
             target_texture_size: [target_size[0] as f32, target_size[1] as f32],
             source_texture_position_min,
             source_texture_position_max,
+            source_uv_min,
+            source_uv_max,
             color_transformation: color_transformation.unwrap_or_else(Matrix4::identity),
             color_offset: color_offset.unwrap_or([0.0; 4]),
         };
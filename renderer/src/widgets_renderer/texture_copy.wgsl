@@ -10,6 +10,8 @@ struct PushConstants {
     target_texture_size: vec2<f32>,
     source_texture_position_min: vec2<f32>,
     source_texture_position_max: vec2<f32>,
+    source_uv_min: vec2<f32>,
+    source_uv_max: vec2<f32>,
 };
 var<push_constant> pc: PushConstants;
 
@@ -41,6 +43,8 @@ fn vs_main(
         tex_coords = vec2<f32>(1.0, 1.0);
     }
 
+    tex_coords = mix(pc.source_uv_min, pc.source_uv_max, tex_coords);
+
     let position_y_down = (pixel_positions * 2.0) / pc.target_texture_size - vec2<f32>(1.0, 1.0);
 
     // transform the y-axis
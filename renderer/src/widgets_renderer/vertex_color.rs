@@ -1,3 +1,4 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
 /*
 push constants:
     [[f32; 4]; 4] // composed affine matrix
@@ -58,6 +59,14 @@ impl Default for VertexColor {
     }
 }
 
+impl WidgetRenderer for VertexColor {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(VertexColorImpl::setup(device));
+        renderer
+    }
+}
+
 impl VertexColor {
     pub fn render(
         &self,
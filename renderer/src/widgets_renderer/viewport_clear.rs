@@ -1,3 +1,4 @@
+use gpu_utils::gpu_type_map::WidgetRenderer;
 use utils::rwoption::RwOption;
 use wgpu::PipelineCompilationOptions;
 
@@ -51,6 +52,14 @@ impl ViewportClearImpl {
     }
 }
 
+impl WidgetRenderer for ViewportClear {
+    fn new(device: &wgpu::Device, _queue: &wgpu::Queue) -> Self {
+        let renderer = Self::default();
+        renderer.inner.set(ViewportClearImpl::setup(device));
+        renderer
+    }
+}
+
 impl ViewportClear {
     pub fn render(
         &self,
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::keys::GlyphKey;
+use crate::{keys::GlyphKey, text::Antialiasing};
 
 // MARK: CacheAtlas
 
@@ -9,11 +9,12 @@ pub struct CacheAtlas<const N: u32 = 256> {
     panel_size: u32,
     index_width: u32,
     index_height: u32,
+    antialiasing: Antialiasing,
 
     // wgpu
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
-    texture: wgpu::Texture, // R8Unorm or R8Uint
+    texture: wgpu::Texture, // R8Unorm, or Rgba8Unorm for subpixel coverage
 
     // cache management
     // map CacheKey<N> to GlyphCache
@@ -36,29 +37,19 @@ impl<const N: u32> CacheAtlas<N> {
         panel_size: u32,
         index_width: u32,
         index_height: u32,
+        antialiasing: Antialiasing,
     ) -> Self {
-        let texture_width = panel_size * index_width;
-        let texture_height = panel_size * index_height;
-
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("CacheAtlas Texture"),
-            size: wgpu::Extent3d {
-                width: texture_width,
-                height: texture_height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+        let texture = Self::create_texture(
+            &device,
+            [panel_size * index_width, panel_size * index_height],
+            antialiasing,
+        );
 
         CacheAtlas {
             panel_size,
             index_width,
             index_height,
+            antialiasing,
             device,
             queue,
             texture,
@@ -78,6 +69,56 @@ impl<const N: u32> CacheAtlas<N> {
     pub fn get_texture(&self) -> &wgpu::Texture {
         &self.texture
     }
+
+    pub fn get_device(&self) -> &Arc<wgpu::Device> {
+        &self.device
+    }
+
+    pub fn get_antialiasing(&self) -> Antialiasing {
+        self.antialiasing
+    }
+
+    /// Switches the stored coverage to `antialiasing`, dropping every cached glyph.
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        if self.antialiasing == antialiasing {
+            return;
+        }
+        self.texture = Self::create_texture(
+            &self.device,
+            [self.texture.width(), self.texture.height()],
+            antialiasing,
+        );
+        self.antialiasing = antialiasing;
+        self.cache.clear();
+        self.life_queue.clear();
+    }
+
+    /// Drops every cached glyph, e.g. after the fonts they were rasterized from changed.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.life_queue.clear();
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        [width, height]: [u32; 2],
+        antialiasing: Antialiasing,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("CacheAtlas Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: antialiasing.atlas_format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        })
+    }
 }
 
 impl<const N: u32> CacheAtlas<N> {
@@ -166,8 +207,9 @@ impl<const N: u32> CacheAtlas<N> {
             data,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(self.panel_size),
-                rows_per_image: Some(self.panel_size),
+                // `data` holds the rows of the glyph bitmap only
+                bytes_per_row: Some(data_size[0] as u32 * self.antialiasing.bytes_per_texel()),
+                rows_per_image: Some(data_size[1] as u32),
             },
             wgpu::Extent3d {
                 width: data_size[0] as u32,
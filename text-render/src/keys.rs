@@ -1,9 +1,23 @@
+/// Number of horizontal subpixel positions a glyph is cached at.
+pub const SUBPIXEL_VARIANTS: u8 = 4;
+
+/// Splits a horizontal pen position into a whole pixel and the nearest subpixel variant, so
+/// that `pixel + variant / SUBPIXEL_VARIANTS` is closest to `x`.
+pub fn quantize_subpixel(x: f32) -> (f32, u8) {
+    let variants = f32::from(SUBPIXEL_VARIANTS);
+    let steps = (x * variants).round();
+    let pixel = (steps / variants).floor();
+    (pixel, (steps - pixel * variants) as u8)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GlyphKey<const N: u32> {
     char: char,
     // store size multiplied by N to make it able to derive Eq and Hash
     multiplied_font_size: u32,
     font_hash: usize,
+    // rasterized shifted right by `subpixel_variant / SUBPIXEL_VARIANTS` pixels
+    subpixel_variant: u8,
 }
 
 impl<const N: u32> GlyphKey<N> {
@@ -14,6 +28,15 @@ impl<const N: u32> GlyphKey<N> {
             char,
             multiplied_font_size,
             font_hash,
+            subpixel_variant: 0,
+        }
+    }
+
+    /// The same glyph rasterized `variant / SUBPIXEL_VARIANTS` pixels to the right.
+    pub const fn with_subpixel_variant(self, variant: u8) -> Self {
+        GlyphKey {
+            subpixel_variant: variant % SUBPIXEL_VARIANTS,
+            ..self
         }
     }
 
@@ -28,6 +51,15 @@ impl<const N: u32> GlyphKey<N> {
     pub const fn get_font_hash(&self) -> usize {
         self.font_hash
     }
+
+    pub const fn get_subpixel_variant(&self) -> u8 {
+        self.subpixel_variant
+    }
+
+    /// Horizontal shift of the rasterized glyph in pixels.
+    pub const fn get_subpixel_offset(&self) -> f32 {
+        self.subpixel_variant as f32 / SUBPIXEL_VARIANTS as f32
+    }
 }
 
 impl<const N: u32> std::fmt::Debug for GlyphKey<N> {
@@ -36,6 +68,32 @@ impl<const N: u32> std::fmt::Debug for GlyphKey<N> {
             .field("char", &self.char)
             .field("size", &self.get_font_size())
             .field("font_hash", &self.font_hash)
+            .field("subpixel_variant", &self.subpixel_variant)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pen_positions_snap_to_the_nearest_variant() {
+        assert_eq!(quantize_subpixel(3.0), (3.0, 0));
+        assert_eq!(quantize_subpixel(3.3), (3.0, 1));
+        assert_eq!(quantize_subpixel(3.5), (3.0, 2));
+        assert_eq!(quantize_subpixel(3.7), (3.0, 3));
+        // rounds up into the next pixel
+        assert_eq!(quantize_subpixel(3.9), (4.0, 0));
+        assert_eq!(quantize_subpixel(-0.3), (-1.0, 3));
+    }
+
+    #[test]
+    fn variants_are_distinct_keys() {
+        let key = GlyphKey::<256>::new('a', 12.0, 7);
+        assert_eq!(key.get_subpixel_variant(), 0);
+        assert_ne!(key, key.with_subpixel_variant(1));
+        assert_eq!(key.with_subpixel_variant(2).get_subpixel_offset(), 0.5);
+        assert_eq!(key.with_subpixel_variant(SUBPIXEL_VARIANTS), key);
+    }
+}
@@ -4,6 +4,7 @@ pub mod recursive_atlas;
 pub mod text;
 
 mod keys;
+pub use keys::{GlyphKey, SUBPIXEL_VARIANTS, quantize_subpixel};
 
 pub use fontdb;
 pub use fontdue;
@@ -6,6 +6,18 @@ pub struct TextRenderConfig<'a> {
     pub line_height: LineHeight,
     pub line_length: f32,
     pub horizontal_layout: TextLayout,
+    /// Extra space after every glyph.
+    pub letter_spacing: f32,
+    /// Extra space after every space character, on top of `letter_spacing`.
+    pub word_spacing: f32,
+    /// Extra space after every line that ends with a line feed.
+    pub paragraph_spacing: f32,
+    pub tab_stops: TabStops<'a>,
+    /// Draws glyphs at whole pixels from rasterizations shifted by a fraction of a pixel,
+    /// picking the nearest of [`SUBPIXEL_VARIANTS`](crate::SUBPIXEL_VARIANTS) for each pen
+    /// position. Keeps glyph spacing even at fractional offsets without blurring. One layout
+    /// unit is taken as one pixel.
+    pub subpixel_positioning: bool,
 }
 
 pub struct TextRasterizeConfig<'a> {
@@ -22,6 +34,54 @@ pub struct TextRasterizeConfig<'a> {
     pub transform: [f32; 2],
 }
 
+/// How glyph edges are smoothed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Antialiasing {
+    /// One coverage value per pixel, stored in an `R8Unorm` atlas.
+    #[default]
+    Grayscale,
+    /// Coverage per color channel for LCD panels with the given subpixel order, stored in an
+    /// `Rgba8Unorm` atlas with the mean coverage in alpha. Drawing it needs
+    /// `wgpu::Features::DUAL_SOURCE_BLENDING` to blend each channel on its own.
+    Subpixel(SubpixelOrder),
+}
+
+/// Left-to-right order of the color subpixels of a display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SubpixelOrder {
+    #[default]
+    Rgb,
+    Bgr,
+}
+
+impl Antialiasing {
+    /// The mode a device with `features` can draw, falling back to grayscale.
+    pub fn resolve(self, features: wgpu::Features) -> Self {
+        match self {
+            Antialiasing::Subpixel(_)
+                if !features.contains(wgpu::Features::DUAL_SOURCE_BLENDING) =>
+            {
+                Antialiasing::Grayscale
+            }
+            mode => mode,
+        }
+    }
+
+    pub fn atlas_format(self) -> wgpu::TextureFormat {
+        match self {
+            Antialiasing::Grayscale => wgpu::TextureFormat::R8Unorm,
+            Antialiasing::Subpixel(_) => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    pub fn bytes_per_texel(self) -> u32 {
+        match self {
+            Antialiasing::Grayscale => 1,
+            Antialiasing::Subpixel(_) => 4,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Kerning {
     Kern(f32),
@@ -34,10 +94,15 @@ impl Default for Kerning {
     }
 }
 
+/// Distance between the baselines of consecutive lines.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LineHeight {
+    /// Exactly this distance.
     Fixed(f32),
+    /// The font's line spacing plus this distance.
     Relative(f32),
+    /// The font's line spacing times this factor.
+    Multiple(f32),
 }
 
 impl Default for LineHeight {
@@ -58,3 +123,72 @@ impl Default for TextLayout {
         TextLayout::Start(0.0)
     }
 }
+
+/// Where a tab character moves the pen to.
+///
+/// A tab advances to the first of `stops` to the right of the pen, and past the last stop to
+/// the next multiple of `interval`. A non-positive `interval` falls back to four space widths.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TabStops<'a> {
+    pub stops: &'a [f32],
+    pub interval: f32,
+}
+
+impl TabStops<'_> {
+    /// The position a tab at `x` advances to, with `default_interval` in place of a
+    /// non-positive `interval`.
+    pub fn next(&self, x: f32, default_interval: f32) -> f32 {
+        if let Some(&stop) = self.stops.iter().find(|&&stop| stop > x) {
+            return stop;
+        }
+        let interval = if self.interval > 0.0 {
+            self.interval
+        } else {
+            default_interval
+        };
+        if interval <= 0.0 {
+            return x;
+        }
+        ((x / interval).floor() + 1.0) * interval
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tabs_advance_to_stops_then_intervals() {
+        let tabs = TabStops {
+            stops: &[30.0, 100.0],
+            interval: 40.0,
+        };
+        assert_eq!(tabs.next(0.0, 16.0), 30.0);
+        assert_eq!(tabs.next(30.0, 16.0), 100.0);
+        assert_eq!(tabs.next(100.0, 16.0), 120.0);
+        assert_eq!(tabs.next(130.0, 16.0), 160.0);
+
+        let default = TabStops::default();
+        assert_eq!(default.next(10.0, 16.0), 16.0);
+        assert_eq!(default.next(10.0, 0.0), 10.0);
+    }
+
+    #[test]
+    fn subpixel_antialiasing_needs_dual_source_blending() {
+        let subpixel = Antialiasing::Subpixel(SubpixelOrder::Bgr);
+        assert_eq!(
+            subpixel.resolve(wgpu::Features::empty()),
+            Antialiasing::Grayscale
+        );
+        assert_eq!(
+            subpixel.resolve(wgpu::Features::DUAL_SOURCE_BLENDING),
+            subpixel
+        );
+        assert_eq!(
+            Antialiasing::Grayscale.resolve(wgpu::Features::DUAL_SOURCE_BLENDING),
+            Antialiasing::Grayscale
+        );
+        assert_eq!(subpixel.atlas_format(), wgpu::TextureFormat::Rgba8Unorm);
+    }
+}
@@ -7,8 +7,8 @@ use std::{
 use crate::{
     cache_atlas::CacheAtlas,
     error::TextError,
-    keys::GlyphKey,
-    text::{Kerning, LineHeight},
+    keys::{GlyphKey, quantize_subpixel},
+    text::{Antialiasing, Kerning, LineHeight, SubpixelOrder},
 };
 
 use super::TextRenderConfig;
@@ -20,6 +20,7 @@ pub struct TextContext<const N: u32 = 256> {
     cache_panel_size: u32,
     index_width: u32,
     index_height: u32,
+    antialiasing: Antialiasing,
 
     // font management
     font_database: fontdb::Database,
@@ -42,6 +43,7 @@ impl TextContext {
             cache_panel_size: panel_size,
             index_width,
             index_height,
+            antialiasing: Antialiasing::Grayscale,
             font_database,
             fonts: HashMap::new(),
             cache: None,
@@ -51,15 +53,35 @@ impl TextContext {
 
 impl<const N: u32> TextContext<N> {
     pub fn set_cache(&mut self, device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>) {
+        let antialiasing = self.antialiasing.resolve(device.features());
         self.cache = Some(CacheAtlas::<N>::new(
             device,
             queue,
             self.cache_panel_size,
             self.index_width,
             self.index_height,
+            antialiasing,
         ));
     }
 
+    /// Selects how glyphs of this context are antialiased, e.g. subpixel antialiasing for a
+    /// window on an LCD. Falls back to grayscale where the device cannot blend subpixel
+    /// coverage; [`Self::antialiasing`] tells the mode in use.
+    pub fn set_antialiasing(&mut self, antialiasing: Antialiasing) {
+        self.antialiasing = antialiasing;
+        if let Some(cache) = self.cache.as_mut() {
+            let features = cache.get_device().features();
+            cache.set_antialiasing(antialiasing.resolve(features));
+        }
+    }
+
+    /// The antialiasing glyphs are rasterized with.
+    pub fn antialiasing(&self) -> Antialiasing {
+        self.cache
+            .as_ref()
+            .map_or(self.antialiasing, |cache| cache.get_antialiasing())
+    }
+
     pub fn use_font(&mut self, query: fontdb::Query) {
         // load font if not loaded
         let font_hash = utils::query_hash(&query);
@@ -68,6 +90,40 @@ impl<const N: u32> TextContext<N> {
             .entry(font_hash)
             .or_insert_with(|| utils::load_font(&mut self.font_database, &query));
     }
+
+    /// Adds the faces in `bytes`, a font file or collection, e.g. one bundled with the app.
+    /// Returns the ids of the added faces; none if the data is not a font.
+    pub fn register_font_bytes(&mut self, bytes: impl Into<Vec<u8>>) -> Vec<fontdb::ID> {
+        let ids = self
+            .font_database
+            .load_font_source(fontdb::Source::Binary(Arc::new(bytes.into())));
+        if !ids.is_empty() {
+            self.fonts_changed();
+        }
+        ids.to_vec()
+    }
+
+    /// Adds every font file in `path` and its subdirectories. Returns the number of faces
+    /// added; files that are not fonts are skipped.
+    pub fn register_font_dir(&mut self, path: impl AsRef<std::path::Path>) -> usize {
+        let before = self.font_database.len();
+        self.font_database.load_fonts_dir(path);
+        let added = self.font_database.len() - before;
+        if added > 0 {
+            self.fonts_changed();
+        }
+        added
+    }
+
+    /// A new face may match queries that resolved to a fallback before, so every resolved
+    /// font and the glyphs rasterized from it are dropped. Layouts made before have to be
+    /// made again.
+    fn fonts_changed(&mut self) {
+        self.fonts.clear();
+        if let Some(cache) = self.cache.as_mut() {
+            cache.clear();
+        }
+    }
 }
 
 // MARK: layout
@@ -98,14 +154,18 @@ impl<const N: u32> TextContext<N> {
         let new_line_size = match config.line_height {
             LineHeight::Fixed(height) => height,
             LineHeight::Relative(ratio) => line_metrics.new_line_size + ratio,
+            LineHeight::Multiple(factor) => line_metrics.new_line_size * factor,
         };
 
+        // tabs default to four spaces
+        let default_tab = font.metrics(' ', config.font_size).advance_width * 4.0;
+
         // render
 
         // step 1
-        let mut line_buffer = Vec::new(); // glyph position base on baseline
+        let mut line_buffer = Vec::new(); // glyph position base on baseline, and pen position
         // step 2
-        let mut text_buffer = Vec::new(); // (line_width, line_buffer)
+        let mut text_buffer = Vec::new(); // (line_width, line_buffer, ends_paragraph)
         // step 3
         let mut glyph_layouts = HashMap::new(); // position of each glyph
 
@@ -121,9 +181,24 @@ impl<const N: u32> TextContext<N> {
             // make text buffer
 
             for c in text.chars() {
-                // skip control characters
+                if c == '\n' {
+                    // paragraph break
+                    text_buffer.push((line_width, std::mem::take(&mut line_buffer), true));
+                    max_width = max_width.max(line_width);
+                    line_width = 0.0f32;
+                    accumulated_width = 0.0f32;
+                    previous_char = None;
+                    continue;
+                }
+
+                if c == '\t' {
+                    accumulated_width = config.tab_stops.next(accumulated_width, default_tab);
+                    previous_char = None;
+                    continue;
+                }
+
+                // skip other control characters
                 if c.is_control() {
-                    // todo
                     continue;
                 }
 
@@ -140,21 +215,19 @@ impl<const N: u32> TextContext<N> {
                 // check overflow and line break
                 if accumulated_width + metrics.bounds.xmin + metrics.bounds.width
                     > config.line_length
-                    || c == '\n'
+                    && !line_buffer.is_empty()
                 {
                     // line break
 
                     // store line buffer
-                    let mut swap_line_buffer = Vec::new();
-                    std::mem::swap(&mut line_buffer, &mut swap_line_buffer);
-
-                    text_buffer.push((line_width, swap_line_buffer));
+                    text_buffer.push((line_width, std::mem::take(&mut line_buffer), false));
+                    max_width = max_width.max(line_width);
                     accumulated_width = 0.0f32;
                     // previous_char = None;
                 }
 
                 let glyph_position = GlyphPosition::from_metrics(metrics, [accumulated_width, 0.0]);
-                line_buffer.push((c, glyph_position));
+                line_buffer.push((c, glyph_position, accumulated_width));
                 line_width = accumulated_width + metrics.bounds.xmin + metrics.bounds.width;
 
                 // update accumulated width
@@ -162,6 +235,10 @@ impl<const N: u32> TextContext<N> {
                     Kerning::Kern(kern_fix) => metrics.advance_width + kern_fix,
                     Kerning::Monospace(space) => space,
                 };
+                accumulated_width += config.letter_spacing;
+                if c == ' ' {
+                    accumulated_width += config.word_spacing;
+                }
 
                 // update previous char
                 previous_char = Some(c);
@@ -169,16 +246,29 @@ impl<const N: u32> TextContext<N> {
 
             // store last line buffer
             if !line_buffer.is_empty() {
-                text_buffer.push((line_width, line_buffer));
+                text_buffer.push((line_width, line_buffer, false));
                 max_width = max_width.max(line_width);
             }
         }
 
         // data
 
-        let lines = text_buffer.len() as f32;
-        let all_lines_height =
-            new_line_size * (lines - 1.0f32) + line_metrics.ascent + line_metrics.descent;
+        // distance from a line's baseline to the next one's
+        let line_advance = |ends_paragraph: bool| {
+            if ends_paragraph {
+                new_line_size + config.paragraph_spacing
+            } else {
+                new_line_size
+            }
+        };
+
+        let lines_advance: f32 = text_buffer
+            .iter()
+            .rev()
+            .skip(1)
+            .map(|(_, _, ends_paragraph)| line_advance(*ends_paragraph))
+            .sum();
+        let all_lines_height = lines_advance + line_metrics.ascent + line_metrics.descent;
         let max_line_width = max_width;
 
         let text_bounds = [max_line_width, all_lines_height];
@@ -187,16 +277,26 @@ impl<const N: u32> TextContext<N> {
 
         {
             let mut vertical_offset = line_metrics.ascent;
-            for (line_width, line_buffer) in text_buffer {
+            for (line_width, line_buffer, ends_paragraph) in text_buffer {
                 let horizontal_offset = match config.horizontal_layout {
                     crate::text::TextLayout::Start(_) => 0.0f32,
                     crate::text::TextLayout::Center(_) => (max_line_width - line_width) / 2.0f32,
                     crate::text::TextLayout::End(_) => max_line_width - line_width,
                 };
 
-                for (c, position) in line_buffer.iter() {
-                    let glyph_position = position.transform([horizontal_offset, vertical_offset]);
-                    let key = GlyphKey::<N>::new(*c, config.font_size, font_hash);
+                for (c, position, pen) in line_buffer.iter() {
+                    let mut glyph_position =
+                        position.transform([horizontal_offset, vertical_offset]);
+                    let mut key = GlyphKey::<N>::new(*c, config.font_size, font_hash);
+
+                    if config.subpixel_positioning {
+                        // draw from a whole pixel with the variant making up the fraction
+                        let pen = pen + horizontal_offset;
+                        let (pixel, variant) = quantize_subpixel(pen);
+                        key = key.with_subpixel_variant(variant);
+                        glyph_position = glyph_position.transform([pixel - pen, 0.0]);
+                        glyph_position.size[0] += key.get_subpixel_offset();
+                    }
 
                     // store glyph position
                     let glyph_positions = glyph_layouts.entry(key).or_insert_with(Vec::new);
@@ -204,7 +304,7 @@ impl<const N: u32> TextContext<N> {
                 }
 
                 // update vertical offset
-                vertical_offset += new_line_size;
+                vertical_offset += line_advance(ends_paragraph);
             }
         }
 
@@ -247,8 +347,32 @@ impl<const N: u32> TextContext<N> {
                         .get(&key.get_font_hash())
                         .expect("Font not found in the font database.");
 
-                    let (metrics, bitmap) =
-                        font.rasterize(key.get_char(), self.cache_panel_size as f32);
+                    let antialiasing = cache.get_antialiasing();
+                    let (metrics, bitmap, channels) = match antialiasing {
+                        Antialiasing::Grayscale => {
+                            let (metrics, bitmap) =
+                                font.rasterize(key.get_char(), self.cache_panel_size as f32);
+                            (metrics, bitmap, 1)
+                        }
+                        Antialiasing::Subpixel(_) => {
+                            let (metrics, bitmap) = font
+                                .rasterize_subpixel(key.get_char(), self.cache_panel_size as f32);
+                            (metrics, bitmap, 3)
+                        }
+                    };
+                    // the panel is `panel_size / font_size` times the drawn size
+                    let shift = key.get_subpixel_offset() * self.cache_panel_size as f32
+                        / key.get_font_size();
+                    let (metrics, mut bitmap) = utils::shift_coverage(
+                        metrics,
+                        &bitmap,
+                        shift,
+                        self.cache_panel_size,
+                        channels,
+                    );
+                    if let Antialiasing::Subpixel(order) = antialiasing {
+                        bitmap = utils::subpixel_rgba(&bitmap, order);
+                    }
 
                     cache.store_data(key, metrics, &bitmap);
 
@@ -348,6 +472,60 @@ mod utils {
         fontdue::Font::from_bytes(binary_slice, font_settings).unwrap()
     }
 
+    /// Moves a coverage bitmap of `channels` values per texel `shift` texels to the right,
+    /// blending neighboring columns for the fractional part. The bitmap widens to keep the
+    /// shifted edge, up to `max_width`.
+    pub fn shift_coverage(
+        mut metrics: fontdue::Metrics,
+        bitmap: &[u8],
+        shift: f32,
+        max_width: u32,
+        channels: usize,
+    ) -> (fontdue::Metrics, Vec<u8>) {
+        if shift <= 0.0 || metrics.width == 0 {
+            return (metrics, bitmap.to_vec());
+        }
+        let whole = shift.floor() as usize;
+        let fraction = shift - shift.floor();
+
+        let width = metrics.width;
+        let shifted_width = (width + shift.ceil() as usize).min((max_width as usize).max(width));
+        let mut shifted = Vec::with_capacity(shifted_width * channels * metrics.height);
+        for row in bitmap.chunks(width * channels) {
+            let texel = |x: usize, c: usize| -> f32 {
+                x.checked_sub(whole)
+                    .and_then(|x| row.get(x * channels + c))
+                    .map_or(0.0, |&v| f32::from(v))
+            };
+            for x in 0..shifted_width {
+                shifted.extend((0..channels).map(|c| {
+                    let left = x.checked_sub(1).map_or(0.0, |x| texel(x, c));
+                    (texel(x, c) * (1.0 - fraction) + left * fraction).round() as u8
+                }));
+            }
+        }
+
+        metrics.width = shifted_width;
+        metrics.bounds.width += shift;
+        (metrics, shifted)
+    }
+
+    /// Packs per-subpixel coverage, three values per texel from left to right, into RGBA
+    /// texels with the mean coverage in alpha.
+    pub fn subpixel_rgba(bitmap: &[u8], order: SubpixelOrder) -> Vec<u8> {
+        bitmap
+            .chunks_exact(3)
+            .flat_map(|texel| {
+                let [left, middle, right] = [texel[0], texel[1], texel[2]];
+                let mean = ((u16::from(left) + u16::from(middle) + u16::from(right)) / 3) as u8;
+                match order {
+                    SubpixelOrder::Rgb => [left, middle, right, mean],
+                    SubpixelOrder::Bgr => [right, middle, left, mean],
+                }
+            })
+            .collect()
+    }
+
     pub fn query_hash(query: &fontdb::Query) -> usize {
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
         query.hash(&mut hasher);
@@ -436,6 +614,58 @@ mod utils {
         (vertices, indices)
     }
 
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn metrics(width: usize, height: usize) -> fontdue::Metrics {
+            fontdue::Metrics {
+                width,
+                height,
+                bounds: fontdue::OutlineBounds {
+                    width: width as f32,
+                    height: height as f32,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn coverage_shifts_by_whole_and_fractional_texels() {
+            let (shifted_metrics, shifted) = shift_coverage(metrics(2, 1), &[200, 100], 1.5, 64, 1);
+            assert_eq!(shifted_metrics.width, 4);
+            assert_eq!(shifted_metrics.bounds.width, 3.5);
+            assert_eq!(shifted, vec![0, 100, 150, 50]);
+
+            // no shift keeps the bitmap
+            let (_, same) = shift_coverage(metrics(2, 1), &[200, 100], 0.0, 64, 1);
+            assert_eq!(same, vec![200, 100]);
+        }
+
+        #[test]
+        fn coverage_stays_within_the_panel() {
+            let (shifted_metrics, shifted) = shift_coverage(metrics(3, 2), &[255; 6], 0.5, 3, 1);
+            assert_eq!(shifted_metrics.width, 3);
+            assert_eq!(shifted.len(), 6);
+        }
+
+        #[test]
+        fn subpixel_coverage_shifts_per_channel_and_packs_into_rgba() {
+            let (_, shifted) = shift_coverage(metrics(1, 1), &[30, 60, 90], 1.0, 64, 3);
+            assert_eq!(shifted, vec![0, 0, 0, 30, 60, 90]);
+
+            assert_eq!(
+                subpixel_rgba(&[30, 60, 90], SubpixelOrder::Rgb),
+                vec![30, 60, 90, 60]
+            );
+            assert_eq!(
+                subpixel_rgba(&[30, 60, 90], SubpixelOrder::Bgr),
+                vec![90, 60, 30, 60]
+            );
+        }
+    }
+
     // MARK: wgpu structs
 
     #[repr(C)]
@@ -445,3 +675,22 @@ mod utils {
         pub uv: [f32; 2],
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_that_is_not_a_font_adds_no_faces() {
+        let mut context = TextContext::new(64, 4, 4);
+        let faces = context.font_database.len();
+
+        assert!(
+            context
+                .register_font_bytes(b"not a font".to_vec())
+                .is_empty()
+        );
+        assert_eq!(context.register_font_dir("/nonexistent/matcha/fonts"), 0);
+        assert_eq!(context.font_database.len(), faces);
+    }
+}
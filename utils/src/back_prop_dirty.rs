@@ -3,6 +3,8 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
 };
 
+/// Clones refer to the same node.
+#[derive(Clone)]
 pub struct BackPropDirty {
     inner: Arc<BackPropDirtyInner>,
 }